@@ -0,0 +1,18 @@
+use ironrdp_pdu::gcc;
+use ironrdp_pdu::rdp::capability_sets::CapabilitySet;
+
+/// Inspects and overrides the capability sets exchanged with the client.
+///
+/// The server sends its capabilities in the Demand Active PDU before receiving the ones of the client,
+/// so that only the core data of the client (requested desktop size, color depth...) is known at this point.
+/// The capabilities confirmed by the client may then be trimmed, e.g. to disable a codec it advertises.
+pub trait CapabilitiesHook: Send + Sync {
+    /// Overrides the capability sets sent to the client in the Demand Active PDU.
+    ///
+    /// The desktop size of the acceptor follows the size given in the bitmap capability set.
+    fn server_capabilities(&self, _client: Option<&gcc::ClientCoreData>, _capabilities: &mut Vec<CapabilitySet>) {}
+
+    /// Overrides the capability sets confirmed by the client, as returned in the
+    /// [`AcceptorResult`](crate::AcceptorResult).
+    fn client_capabilities(&self, _capabilities: &mut Vec<CapabilitySet>) {}
+}
@@ -1,10 +1,11 @@
 use core::mem;
+use std::sync::{Arc, Mutex};
 
 use ironrdp_connector::{
-    encode_x224_packet, reason_err, ConnectorError, ConnectorErrorExt, ConnectorResult, DesktopSize, Sequence, State,
-    Written,
+    encode_x224_packet, reason_err, ConnectorError, ConnectorErrorExt, ConnectorErrorKind, ConnectorResult,
+    DesktopSize, Sequence, State, Written,
 };
-use ironrdp_core::{decode, WriteBuf};
+use ironrdp_core::{decode, encode_vec, WriteBuf};
 use ironrdp_pdu as pdu;
 use ironrdp_pdu::nego::SecurityProtocol;
 use ironrdp_pdu::x224::X224;
@@ -14,11 +15,17 @@ use pdu::rdp::client_info::Credentials;
 use pdu::rdp::headers::ShareControlPdu;
 use pdu::rdp::server_error_info::{ErrorInfo, ProtocolIndependentCode, ServerSetErrorInfoPdu};
 use pdu::rdp::server_license::{LicensePdu, LicensingErrorMessage};
+use pdu::rdp::session_info::ClientAutoReconnect;
+use pdu::rdp::standard_security::{
+    SecurityExchangePdu, ServerSecurityKey, StandardSecurity, CLIENT_RANDOM_LEN, SERVER_RANDOM_LEN,
+};
 use pdu::{gcc, mcs, nego, rdp};
+use rand_core::{OsRng, RngCore as _};
 
 use super::channel_connection::ChannelConnectionSequence;
 use super::finalization::FinalizationSequence;
 use crate::util::{self, wrap_share_data};
+use crate::{CapabilitiesHook, CredentialsValidator, SecurityPolicy, TlsVersion};
 
 const IO_CHANNEL_ID: u16 = 1003;
 const USER_CHANNEL_ID: u16 = 1002;
@@ -26,14 +33,36 @@ const USER_CHANNEL_ID: u16 = 1002;
 pub struct Acceptor {
     pub(crate) state: AcceptorState,
     security: SecurityProtocol,
+    security_policy: SecurityPolicy,
     io_channel_id: u16,
     user_channel_id: u16,
     desktop_size: DesktopSize,
+    monitors: Vec<gcc::Monitor>,
     server_capabilities: Vec<CapabilitySet>,
+    capabilities_hook: Option<Arc<dyn CapabilitiesHook>>,
     static_channels: StaticChannelSet,
     saved_for_reactivation: AcceptorState,
-    pub(crate) creds: Option<Credentials>,
+    pub(crate) creds: Option<Arc<dyn CredentialsValidator>>,
+    pub(crate) nla_credentials: Option<Credentials>,
     reactivation: bool,
+    client_core_data: Option<gcc::ClientCoreData>,
+    client_monitors: Option<gcc::ClientMonitorData>,
+    client_monitors_extended: Option<gcc::ClientMonitorExtendedData>,
+    client_timezone: Option<rdp::client_info::TimezoneInfo>,
+    client_compression: Option<rdp::client_info::CompressionType>,
+    username: Option<String>,
+    client_auto_reconnect: Option<ClientAutoReconnect>,
+    client_random: [u8; CLIENT_RANDOM_LEN],
+    rdp_security_key: Option<ServerSecurityKey>,
+    rdp_security_offer: Option<RdpSecurityOffer>,
+    rdp_security: Option<Arc<Mutex<StandardSecurity>>>,
+}
+
+/// Standard RDP security offered to the client in the basic settings, until it sends its client random.
+#[derive(Debug, Clone, Copy)]
+struct RdpSecurityOffer {
+    encryption_method: gcc::EncryptionMethod,
+    server_random: [u8; SERVER_RANDOM_LEN],
 }
 
 #[derive(Debug)]
@@ -44,6 +73,9 @@ pub struct AcceptorResult {
     pub user_channel_id: u16,
     pub io_channel_id: u16,
     pub reactivation: bool,
+    /// Session keys encrypting the PDUs when Standard RDP Security is used, shared with the acceptor of
+    /// the next reactivation.
+    pub rdp_security: Option<Arc<Mutex<StandardSecurity>>>,
 }
 
 impl Acceptor {
@@ -55,15 +87,30 @@ impl Acceptor {
     ) -> Self {
         Self {
             security,
+            security_policy: SecurityPolicy::default(),
             state: AcceptorState::InitiationWaitRequest,
             user_channel_id: USER_CHANNEL_ID,
             io_channel_id: IO_CHANNEL_ID,
             desktop_size,
+            monitors: Vec::new(),
             server_capabilities: capabilities,
+            capabilities_hook: None,
             static_channels: StaticChannelSet::new(),
             saved_for_reactivation: Default::default(),
-            creds,
+            creds: creds.map(|creds| Arc::new(creds) as Arc<dyn CredentialsValidator>),
+            nla_credentials: None,
             reactivation: false,
+            client_core_data: None,
+            client_monitors: None,
+            client_monitors_extended: None,
+            client_timezone: None,
+            client_compression: None,
+            username: None,
+            client_auto_reconnect: None,
+            client_random: [0; CLIENT_RANDOM_LEN],
+            rdp_security_key: None,
+            rdp_security_offer: None,
+            rdp_security: None,
         }
     }
 
@@ -96,18 +143,145 @@ impl Acceptor {
         };
         Self {
             security: consumed.security,
+            security_policy: consumed.security_policy,
             state,
             user_channel_id: consumed.user_channel_id,
             io_channel_id: consumed.io_channel_id,
             desktop_size,
+            // The layout of the previous desktop size, set again if needed.
+            monitors: Vec::new(),
             server_capabilities: consumed.server_capabilities,
+            capabilities_hook: consumed.capabilities_hook,
             static_channels,
             saved_for_reactivation,
             creds: consumed.creds,
+            nla_credentials: consumed.nla_credentials,
             reactivation: true,
+            client_core_data: consumed.client_core_data,
+            client_monitors: consumed.client_monitors,
+            client_monitors_extended: consumed.client_monitors_extended,
+            client_timezone: consumed.client_timezone,
+            client_compression: consumed.client_compression,
+            username: consumed.username,
+            client_auto_reconnect: consumed.client_auto_reconnect,
+            client_random: consumed.client_random,
+            rdp_security_key: consumed.rdp_security_key,
+            rdp_security_offer: consumed.rdp_security_offer,
+            rdp_security: consumed.rdp_security,
         }
     }
 
+    /// Validates the users credentials using the given validator, instead of the credentials given
+    /// at construction.
+    pub fn set_credentials_validator(&mut self, validator: Arc<dyn CredentialsValidator>) {
+        self.creds = Some(validator);
+    }
+
+    /// Refuses the clients not meeting the security requirements of the given policy.
+    ///
+    /// The security protocol is checked during the negotiation; the TLS requirements must be checked by the caller
+    /// once the TLS handshake is done, with [`Acceptor::check_tls`].
+    pub fn set_security_policy(&mut self, policy: SecurityPolicy) {
+        self.security_policy = policy;
+    }
+
+    /// Checks the TLS version and cipher suite (IANA number) negotiated with the client against the security policy.
+    ///
+    /// Returns an error if the client must be disconnected.
+    pub fn check_tls(&self, version: TlsVersion, cipher_suite: u16) -> ConnectorResult<()> {
+        self.security_policy.check_tls(version, cipher_suite)
+    }
+
+    /// Inspects and overrides the capability sets exchanged with the client using the given hook.
+    pub fn set_capabilities_hook(&mut self, hook: Arc<dyn CapabilitiesHook>) {
+        self.capabilities_hook = Some(hook);
+    }
+
+    /// Sets the monitors sent to the client in the Monitor Layout PDU, if it supports it.
+    ///
+    /// By default, a single primary monitor covering the desktop is sent.
+    pub fn set_monitor_layout(&mut self, monitors: Vec<gcc::Monitor>) {
+        self.monitors = monitors;
+    }
+
+    /// Enables Standard RDP Security with the RSA key of the server, when no security protocol (TLS or
+    /// CredSSP) is negotiated with the client.
+    ///
+    /// Once the client random is exchanged, the PDUs are encrypted with RC4.
+    pub fn set_rdp_security_key(&mut self, key: ServerSecurityKey) {
+        self.rdp_security_key = Some(key);
+    }
+
+    /// Returns the name of the client computer, once the basic settings are exchanged.
+    pub fn client_name(&self) -> Option<&str> {
+        self.client_core_data.as_ref().map(|core| core.client_name.as_str())
+    }
+
+    /// Returns the core data of the client (version, keyboard, color depth, requested desktop size...),
+    /// once the basic settings are exchanged.
+    pub fn client_core_data(&self) -> Option<&gcc::ClientCoreData> {
+        self.client_core_data.as_ref()
+    }
+
+    /// Returns the monitors of the client, if it sent the Client Monitor Data GCC block in the basic settings.
+    pub fn client_monitors(&self) -> Option<&gcc::ClientMonitorData> {
+        self.client_monitors.as_ref()
+    }
+
+    /// Returns the physical size, orientation and scale factors of the monitors of the client, if it sent
+    /// the Client Monitor Extended Data GCC block in the basic settings.
+    pub fn client_monitors_extended(&self) -> Option<&gcc::ClientMonitorExtendedData> {
+        self.client_monitors_extended.as_ref()
+    }
+
+    /// Returns the time zone of the client, if given in the client info.
+    pub fn client_timezone(&self) -> Option<&rdp::client_info::TimezoneInfo> {
+        self.client_timezone.as_ref()
+    }
+
+    /// Returns the highest bulk compression type supported by the client, if it advertised the compression
+    /// support in the client info.
+    pub fn client_compression(&self) -> Option<rdp::client_info::CompressionType> {
+        self.client_compression
+    }
+
+    /// Returns the name of the user given by the client, once the client info is received.
+    pub fn username(&self) -> Option<&str> {
+        self.username.as_deref()
+    }
+
+    /// Returns the credentials delegated by the user authenticated with NLA, once CredSSP is done.
+    ///
+    /// The password is empty when the client delegates none (Restricted Admin mode).
+    pub fn nla_credentials(&self) -> Option<&Credentials> {
+        self.nla_credentials.as_ref()
+    }
+
+    /// Returns the security protocols requested by the client, from its Connection Request until the basic settings
+    /// exchange.
+    pub fn requested_protocol(&self) -> Option<SecurityProtocol> {
+        match &self.state {
+            AcceptorState::InitiationSendConfirm { requested_protocol }
+            | AcceptorState::SecurityUpgrade { requested_protocol, .. }
+            | AcceptorState::Credssp { requested_protocol, .. }
+            | AcceptorState::BasicSettingsWaitInitial { requested_protocol, .. }
+            | AcceptorState::BasicSettingsSendResponse { requested_protocol, .. } => Some(*requested_protocol),
+            _ => None,
+        }
+    }
+
+    /// Returns the auto-reconnect cookie given by a reconnecting client, once the client info is received.
+    pub fn client_auto_reconnect(&self) -> Option<&ClientAutoReconnect> {
+        self.client_auto_reconnect.as_ref()
+    }
+
+    /// Returns the client random of the connection, with which the auto-reconnect cookie is verified.
+    ///
+    /// The client random is zeroed when Standard RDP Security is not used.
+    pub fn client_random(&self) -> &[u8; CLIENT_RANDOM_LEN] {
+        &self.client_random
+    }
+
     pub fn attach_static_channel<T>(&mut self, channel: T)
     where
         T: SvcServerProcessor + 'static,
@@ -152,6 +326,7 @@ impl Acceptor {
                 user_channel_id: self.user_channel_id,
                 io_channel_id: self.io_channel_id,
                 reactivation: self.reactivation,
+                rdp_security: self.rdp_security.clone(),
             }),
             previous_state => {
                 self.state = previous_state;
@@ -170,6 +345,10 @@ pub enum AcceptorState {
     InitiationSendConfirm {
         requested_protocol: SecurityProtocol,
     },
+    /// The negotiation failure was sent to the client, which is about to disconnect.
+    NegotiationFailed {
+        code: nego::FailureCode,
+    },
     SecurityUpgrade {
         requested_protocol: SecurityProtocol,
         protocol: SecurityProtocol,
@@ -236,6 +415,7 @@ impl State for AcceptorState {
             Self::Consumed => "Consumed",
             Self::InitiationWaitRequest => "InitiationWaitRequest",
             Self::InitiationSendConfirm { .. } => "InitiationSendConfirm",
+            Self::NegotiationFailed { .. } => "NegotiationFailed",
             Self::SecurityUpgrade { .. } => "SecurityUpgrade",
             Self::Credssp { .. } => "Credssp",
             Self::BasicSettingsWaitInitial { .. } => "BasicSettingsWaitInitial",
@@ -267,11 +447,15 @@ impl Sequence for Acceptor {
             AcceptorState::Consumed => None,
             AcceptorState::InitiationWaitRequest => Some(Box::new(pdu::X224_HINT)),
             AcceptorState::InitiationSendConfirm { .. } => None,
+            AcceptorState::NegotiationFailed { .. } => None,
             AcceptorState::SecurityUpgrade { .. } => None,
             AcceptorState::Credssp { .. } => None,
             AcceptorState::BasicSettingsWaitInitial { .. } => Some(Box::new(pdu::X224_HINT)),
             AcceptorState::BasicSettingsSendResponse { .. } => None,
             AcceptorState::ChannelConnection { connection, .. } => connection.next_pdu_hint(),
+            AcceptorState::RdpSecurityCommencement { .. } if self.rdp_security_offer.is_some() => {
+                Some(Box::new(pdu::X224_HINT))
+            }
             AcceptorState::RdpSecurityCommencement { .. } => None,
             AcceptorState::SecureSettingsExchange { .. } => Some(Box::new(pdu::X224_HINT)),
             AcceptorState::LicensingExchange { .. } => None,
@@ -288,6 +472,44 @@ impl Sequence for Acceptor {
     }
 
     fn step(&mut self, input: &[u8], output: &mut WriteBuf) -> ConnectorResult<Written> {
+        let Some(security) = self.rdp_security.as_ref().map(Arc::clone) else {
+            return self.step_plain(input, output);
+        };
+
+        // The licensing PDU is sent without encryption, with its own security header.
+        let encrypt_output = !matches!(self.state, AcceptorState::LicensingExchange { .. });
+
+        let input = if input.is_empty() {
+            Vec::new()
+        } else {
+            security
+                .lock()
+                .expect("poisoned")
+                .decrypt_frame(input)
+                .map_err(ConnectorError::decode)?
+        };
+
+        let mut buf = WriteBuf::new();
+        let written = self.step_plain(&input, &mut buf)?;
+
+        if written.is_nothing() || !encrypt_output {
+            output.write_slice(buf.filled());
+            return Ok(written);
+        }
+
+        let frame = security
+            .lock()
+            .expect("poisoned")
+            .encrypt_frame(buf.filled())
+            .map_err(ConnectorError::encode)?;
+        output.write_slice(&frame);
+
+        Written::from_size(frame.len())
+    }
+}
+
+impl Acceptor {
+    fn step_plain(&mut self, input: &[u8], output: &mut WriteBuf) -> ConnectorResult<Written> {
         let prev_state = mem::take(&mut self.state);
 
         let (written, next_state) = match prev_state {
@@ -308,16 +530,38 @@ impl Sequence for Acceptor {
 
             AcceptorState::InitiationSendConfirm { requested_protocol } => {
                 let protocols = requested_protocol & self.security;
-                let protocol = if protocols.intersects(SecurityProtocol::HYBRID_EX) {
-                    SecurityProtocol::HYBRID_EX
+                let negotiated = if protocols.intersects(SecurityProtocol::HYBRID_EX) {
+                    Ok(SecurityProtocol::HYBRID_EX)
                 } else if protocols.intersects(SecurityProtocol::HYBRID) {
-                    SecurityProtocol::HYBRID
+                    Ok(SecurityProtocol::HYBRID)
                 } else if protocols.intersects(SecurityProtocol::SSL) {
-                    SecurityProtocol::SSL
+                    Ok(SecurityProtocol::SSL)
                 } else if self.security.is_empty() {
-                    SecurityProtocol::empty()
+                    Ok(SecurityProtocol::empty())
+                } else if self.security.intersects(SecurityProtocol::SSL) {
+                    Err(nego::FailureCode::SSL_REQUIRED_BY_SERVER)
                 } else {
-                    return Err(ConnectorError::general("failed to negotiate security protocol"));
+                    Err(nego::FailureCode::HYBRID_REQUIRED_BY_SERVER)
+                };
+                // The clients offering a weaker protocol than the one required by the policy are refused as well.
+                let negotiated =
+                    negotiated.and_then(|protocol| self.security_policy.check_protocol(protocol).map(|()| protocol));
+
+                let protocol = match negotiated {
+                    Ok(protocol) => protocol,
+                    Err(code) => {
+                        // Let the client know which security is required, instead of just closing the connection.
+                        let connection_confirm = nego::ConnectionConfirm::Failure { code };
+
+                        debug!(message = ?connection_confirm, "Send");
+
+                        let written = ironrdp_core::encode_buf(&X224(connection_confirm), output)
+                            .map_err(ConnectorError::encode)?;
+
+                        self.state = AcceptorState::NegotiationFailed { code };
+
+                        return Written::from_size(written);
+                    }
                 };
                 let connection_confirm = nego::ConnectionConfirm::Response {
                     flags: nego::ResponseFlags::empty(),
@@ -338,6 +582,10 @@ impl Sequence for Acceptor {
                 )
             }
 
+            AcceptorState::NegotiationFailed { code } => {
+                return Err(reason_err!("Initiation", "{code}").with_class(ironrdp_connector::ErrorClass::Negotiation));
+            }
+
             AcceptorState::SecurityUpgrade {
                 requested_protocol,
                 protocol,
@@ -380,6 +628,30 @@ impl Sequence for Acceptor {
 
                 debug!(message = ?settings_initial, "Received");
 
+                if self.rdp_security_key.is_some() && protocol.is_empty() {
+                    let client_security = &settings_initial.conference_create_request.gcc_blocks.security;
+                    let client_methods = client_security.encryption_methods
+                        | gcc::EncryptionMethod::from_bits_truncate(client_security.ext_encryption_methods);
+                    let encryption_method = StandardSecurity::negotiate(client_methods)
+                        .ok_or_else(|| ConnectorError::general("no encryption method supported by the client"))?;
+
+                    let mut server_random = [0; SERVER_RANDOM_LEN];
+                    OsRng.fill_bytes(&mut server_random);
+
+                    self.rdp_security_offer = Some(RdpSecurityOffer {
+                        encryption_method,
+                        server_random,
+                    });
+                }
+
+                self.client_core_data = Some(settings_initial.conference_create_request.gcc_blocks.core.clone());
+                self.client_monitors = settings_initial.conference_create_request.gcc_blocks.monitor.clone();
+                self.client_monitors_extended = settings_initial
+                    .conference_create_request
+                    .gcc_blocks
+                    .monitor_extended
+                    .clone();
+
                 let early_capability = settings_initial
                     .conference_create_request
                     .gcc_blocks
@@ -441,11 +713,22 @@ impl Sequence for Acceptor {
                 let skip_channel_join = early_capability
                     .is_some_and(|client| client.contains(gcc::ClientEarlyCapabilityFlags::SUPPORT_SKIP_CHANNELJOIN));
 
+                let security = match (&self.rdp_security_key, self.rdp_security_offer) {
+                    (Some(key), Some(offer)) => gcc::ServerSecurityData {
+                        encryption_method: offer.encryption_method,
+                        encryption_level: gcc::EncryptionLevel::ClientCompatible,
+                        server_random: Some(offer.server_random),
+                        server_cert: encode_vec(&key.certificate()).map_err(ConnectorError::encode)?,
+                    },
+                    _ => gcc::ServerSecurityData::no_security(),
+                };
+
                 let server_blocks = create_gcc_blocks(
                     self.io_channel_id,
                     channel_ids.clone(),
                     requested_protocol,
                     skip_channel_join,
+                    security,
                 );
 
                 let settings_response = mcs::ConnectResponse {
@@ -507,14 +790,36 @@ impl Sequence for Acceptor {
                 early_capability,
                 channels,
                 ..
-            } => (
-                Written::Nothing,
-                AcceptorState::SecureSettingsExchange {
-                    protocol,
-                    early_capability,
-                    channels,
-                },
-            ),
+            } => {
+                if let (Some(key), Some(offer)) = (&self.rdp_security_key, self.rdp_security_offer) {
+                    let data: X224<mcs::SendDataRequest<'_>> = decode(input).map_err(ConnectorError::decode)?;
+                    let security_exchange: SecurityExchangePdu =
+                        decode(data.0.user_data.as_ref()).map_err(ConnectorError::decode)?;
+
+                    debug!(message = ?security_exchange, "Received");
+
+                    let client_random = key
+                        .decrypt_client_random(&security_exchange.encrypted_client_random)
+                        .map_err(ConnectorError::decode)?;
+                    let security =
+                        StandardSecurity::server(offer.encryption_method, &client_random, &offer.server_random)
+                            .ok_or_else(|| ConnectorError::general("unsupported encryption method"))?;
+
+                    debug!(encryption_method = ?offer.encryption_method, "Standard RDP security established");
+
+                    self.rdp_security = Some(Arc::new(Mutex::new(security)));
+                    self.client_random = client_random;
+                }
+
+                (
+                    Written::Nothing,
+                    AcceptorState::SecureSettingsExchange {
+                        protocol,
+                        early_capability,
+                        channels,
+                    },
+                )
+            }
 
             AcceptorState::SecureSettingsExchange {
                 protocol,
@@ -528,10 +833,30 @@ impl Sequence for Acceptor {
 
                 debug!(message = ?client_info, "Received");
 
+                self.username = Some(client_info.client_info.credentials.username.clone());
+                self.client_timezone = client_info.client_info.extra_info.optional_data.timezone().cloned();
+                self.client_compression = client_info
+                    .client_info
+                    .flags
+                    .contains(rdp::client_info::ClientInfoFlags::COMPRESSION)
+                    .then_some(client_info.client_info.compression_type);
+                self.client_auto_reconnect = client_info
+                    .client_info
+                    .extra_info
+                    .optional_data
+                    .reconnect_cookie()
+                    .and_then(|cookie| match decode::<ClientAutoReconnect>(cookie) {
+                        Ok(cookie) => Some(cookie),
+                        Err(error) => {
+                            warn!(%error, "Invalid auto-reconnect cookie");
+                            None
+                        }
+                    });
+
                 if !protocol.intersects(SecurityProtocol::HYBRID | SecurityProtocol::HYBRID_EX) {
                     let creds = client_info.client_info.credentials;
 
-                    if self.creds.as_ref() != Some(&creds) {
+                    if !self.creds.as_ref().is_some_and(|validator| validator.validate(&creds)) {
                         // FIXME: How authorization should be denied with standard RDP security?
                         // Since standard RDP security is not a priority, we just send a ServerDeniedConnection ServerSetErrorInfo PDU.
                         let info = ServerSetErrorInfoPdu(ErrorInfo::ProtocolIndependentCode(
@@ -542,7 +867,10 @@ impl Sequence for Acceptor {
 
                         util::encode_send_data_indication(self.user_channel_id, self.io_channel_id, &info, output)?;
 
-                        return Err(ConnectorError::general("invalid credentials"));
+                        return Err(ConnectorError::new(
+                            "invalid credentials",
+                            ConnectorErrorKind::AccessDenied,
+                        ));
                     }
                 }
 
@@ -586,13 +914,30 @@ impl Sequence for Acceptor {
                 early_capability,
                 channels,
             } => {
+                let mut capability_sets = self.server_capabilities.clone();
+
+                if let Some(hook) = &self.capabilities_hook {
+                    hook.server_capabilities(self.client_core_data.as_ref(), &mut capability_sets);
+
+                    // The monitor layout must cover the desktop size announced to the client.
+                    if let Some(bitmap) = capability_sets.iter().find_map(|cap| match cap {
+                        CapabilitySet::Bitmap(bitmap) => Some(bitmap),
+                        _ => None,
+                    }) {
+                        self.desktop_size = DesktopSize {
+                            width: bitmap.desktop_width,
+                            height: bitmap.desktop_height,
+                        };
+                    }
+                }
+
                 let demand_active = rdp::headers::ShareControlHeader {
                     share_id: 0,
                     pdu_source: self.io_channel_id,
                     share_control_pdu: ShareControlPdu::ServerDemandActive(rdp::capability_sets::ServerDemandActive {
                         pdu: rdp::capability_sets::DemandActive {
                             source_descriptor: "".into(),
-                            capability_sets: self.server_capabilities.clone(),
+                            capability_sets,
                         },
                     }),
                 };
@@ -617,15 +962,22 @@ impl Sequence for Acceptor {
             }
 
             AcceptorState::MonitorLayoutSend { channels } => {
+                let monitors = if self.monitors.is_empty() {
+                    // The bounds of the monitors are inclusive.
+                    vec![gcc::Monitor {
+                        left: 0,
+                        top: 0,
+                        right: i32::from(self.desktop_size.width.saturating_sub(1)),
+                        bottom: i32::from(self.desktop_size.height.saturating_sub(1)),
+                        flags: gcc::MonitorFlags::PRIMARY,
+                    }]
+                } else {
+                    self.monitors.clone()
+                };
+
                 let monitor_layout =
                     rdp::headers::ShareDataPdu::MonitorLayout(rdp::finalization_messages::MonitorLayoutPdu {
-                        monitors: vec![gcc::Monitor {
-                            left: 0,
-                            top: 0,
-                            right: i32::from(self.desktop_size.width),
-                            bottom: i32::from(self.desktop_size.height),
-                            flags: gcc::MonitorFlags::PRIMARY,
-                        }],
+                        monitors,
                     });
 
                 debug!(message = ?monitor_layout, "Send");
@@ -681,12 +1033,18 @@ impl Sequence for Acceptor {
                             return Err(ConnectorError::general("expected client confirm active"));
                         };
 
+                        let mut client_capabilities = confirm.pdu.capability_sets;
+
+                        if let Some(hook) = &self.capabilities_hook {
+                            hook.client_capabilities(&mut client_capabilities);
+                        }
+
                         (
                             Written::Nothing,
                             AcceptorState::ConnectionFinalization {
                                 channels: channels.clone(),
                                 finalization: FinalizationSequence::new(self.user_channel_id, self.io_channel_id),
-                                client_capabilities: confirm.pdu.capability_sets,
+                                client_capabilities,
                             },
                         )
                     }
@@ -740,6 +1098,7 @@ fn create_gcc_blocks(
     channel_ids: Vec<u16>,
     requested: SecurityProtocol,
     skip_channel_join: bool,
+    security: gcc::ServerSecurityData,
 ) -> gcc::ServerGccBlocks {
     gcc::ServerGccBlocks {
         core: gcc::ServerCoreData {
@@ -750,7 +1109,7 @@ fn create_gcc_blocks(
                     .then_some(gcc::ServerEarlyCapabilityFlags::SKIP_CHANNELJOIN_SUPPORTED),
             },
         },
-        security: gcc::ServerSecurityData::no_security(),
+        security,
         network: gcc::ServerNetworkData {
             channel_ids,
             io_channel,
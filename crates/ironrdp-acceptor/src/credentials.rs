@@ -0,0 +1,40 @@
+use ironrdp_pdu::rdp::client_info::Credentials;
+
+/// Validates the credentials of the users connecting to the server.
+///
+/// With NLA (CredSSP), the client never sends its password: the server proves it knows the same
+/// password instead, which is therefore looked up using [`CredentialsValidator::password`].
+/// Without NLA, the credentials sent by the client in the Client Info PDU are checked using
+/// [`CredentialsValidator::validate`].
+///
+/// Once NLA succeeded, the identity of the user is submitted to [`CredentialsValidator::authorize`].
+pub trait CredentialsValidator: Send + Sync {
+    /// Returns the password of the user, or `None` if the user is not allowed to connect.
+    fn password(&self, username: &str, domain: Option<&str>) -> Option<String>;
+
+    /// Returns whether the credentials sent by the client are valid.
+    fn validate(&self, credentials: &Credentials) -> bool {
+        self.password(&credentials.username, credentials.domain.as_deref())
+            .is_some_and(|password| password == credentials.password)
+    }
+
+    /// Returns whether the user authenticated with NLA is allowed to connect.
+    ///
+    /// The credentials are the ones delegated by the client, whose password is empty when the client
+    /// delegates none (Restricted Admin mode). All the users knowing their password are allowed by default.
+    fn authorize(&self, _credentials: &Credentials) -> bool {
+        true
+    }
+}
+
+/// A single user is allowed to connect.
+impl CredentialsValidator for Credentials {
+    fn password(&self, username: &str, _domain: Option<&str>) -> Option<String> {
+        // The domain is not checked with NLA, the client may or may not send one.
+        (username == self.username).then(|| self.password.clone())
+    }
+
+    fn validate(&self, credentials: &Credentials) -> bool {
+        self == credentials
+    }
+}
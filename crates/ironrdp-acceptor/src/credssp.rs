@@ -8,8 +8,11 @@ use ironrdp_connector::{
     custom_err, general_err, ConnectorError, ConnectorErrorKind, ConnectorResult, ServerName, Written,
 };
 use ironrdp_core::{other_err, WriteBuf};
+use ironrdp_pdu::rdp::client_info::Credentials;
 use ironrdp_pdu::PduHint;
 
+use crate::CredentialsValidator;
+
 #[derive(Debug)]
 pub(crate) enum CredsspState {
     Ongoing,
@@ -36,17 +39,24 @@ impl PduHint for CredsspTsRequestHint {
 pub(crate) struct CredsspSequence<'a> {
     server: CredSspServer<CredentialsProxyImpl<'a>>,
     state: CredsspState,
+    /// Credentials delegated by the client, once authenticated.
+    identity: Option<AuthIdentity>,
     // selected_protocol: nego::SecurityProtocol,
 }
 
-#[derive(Debug)]
 struct CredentialsProxyImpl<'a> {
-    credentials: &'a AuthIdentity,
+    validator: &'a dyn CredentialsValidator,
+}
+
+impl core::fmt::Debug for CredentialsProxyImpl<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CredentialsProxyImpl").finish_non_exhaustive()
+    }
 }
 
 impl<'a> CredentialsProxyImpl<'a> {
-    fn new(credentials: &'a AuthIdentity) -> Self {
-        Self { credentials }
+    fn new(validator: &'a dyn CredentialsValidator) -> Self {
+        Self { validator }
     }
 }
 
@@ -54,14 +64,15 @@ impl CredentialsProxy for CredentialsProxyImpl<'_> {
     type AuthenticationData = AuthIdentity;
 
     fn auth_data_by_user(&mut self, username: &Username) -> std::io::Result<Self::AuthenticationData> {
-        if username.account_name() != self.credentials.username.account_name() {
+        let Some(password) = self.validator.password(username.account_name(), username.domain_name()) else {
             return Err(std::io::Error::other("invalid username"));
-        }
+        };
 
-        let mut data = self.credentials.clone();
         // keep the original user/domain
-        data.username = username.clone();
-        Ok(data)
+        Ok(AuthIdentity {
+            username: username.clone(),
+            password: password.into(),
+        })
     }
 }
 
@@ -75,7 +86,7 @@ impl<'a> CredsspSequence<'a> {
     }
 
     pub(crate) fn init(
-        creds: &'a AuthIdentity,
+        creds: &'a dyn CredentialsValidator,
         client_computer_name: ServerName,
         public_key: Vec<u8>,
         kerberos_config: Option<KerberosConfig>,
@@ -104,6 +115,7 @@ impl<'a> CredsspSequence<'a> {
         let sequence = Self {
             server,
             state: CredsspState::Ongoing,
+            identity: None,
         };
 
         Ok(sequence)
@@ -127,6 +139,15 @@ impl<'a> CredsspSequence<'a> {
         Ok(self.server.process(request)?)
     }
 
+    /// Returns the credentials delegated by the client, once the sequence is finished.
+    pub(crate) fn take_identity(&mut self) -> Option<Credentials> {
+        self.identity.take().map(|identity| Credentials {
+            username: identity.username.account_name().to_owned(),
+            password: identity.password.as_ref().clone(),
+            domain: identity.username.domain_name().map(str::to_owned),
+        })
+    }
+
     pub(crate) fn handle_process_result(
         &mut self,
         result: Result<ServerState, Box<ServerError>>,
@@ -134,7 +155,10 @@ impl<'a> CredsspSequence<'a> {
     ) -> ConnectorResult<Written> {
         let (ts_request, next_state) = match result {
             Ok(ServerState::ReplyNeeded(ts_request)) => (Some(ts_request), CredsspState::Ongoing),
-            Ok(ServerState::Finished(_id)) => (None, CredsspState::Finished),
+            Ok(ServerState::Finished(identity)) => {
+                self.identity = Some(identity);
+                (None, CredsspState::Finished)
+            }
             Err(err) => (Some(err.ts_request), CredsspState::ServerError(err.error)),
         };
 
@@ -7,22 +7,27 @@ extern crate tracing;
 use ironrdp_async::{single_sequence_step, Framed, FramedRead, FramedWrite, StreamWrapper};
 use ironrdp_connector::credssp::KerberosConfig;
 use ironrdp_connector::sspi::credssp::EarlyUserAuthResult;
-use ironrdp_connector::sspi::{AuthIdentity, Username};
-use ironrdp_connector::{custom_err, general_err, ConnectorResult, ServerName};
+use ironrdp_connector::{general_err, ConnectorResult, ServerName};
 use ironrdp_core::WriteBuf;
 
+mod capabilities;
 mod channel_connection;
 mod connection;
+mod credentials;
 mod credssp;
 mod finalization;
+mod policy;
 mod util;
 
-pub use ironrdp_connector::DesktopSize;
+pub use ironrdp_connector::{ConnectorError, ConnectorErrorKind, DesktopSize, ErrorClass};
 use ironrdp_pdu::nego;
 
+pub use self::capabilities::CapabilitiesHook;
 pub use self::channel_connection::{ChannelConnectionSequence, ChannelConnectionState};
 pub use self::connection::{Acceptor, AcceptorResult, AcceptorState};
+pub use self::credentials::CredentialsValidator;
 pub use self::finalization::{FinalizationSequence, FinalizationState};
+pub use self::policy::{SecurityPolicy, TlsVersion};
 
 pub enum BeginResult<S>
 where
@@ -127,16 +132,11 @@ where
     {
         let creds = acceptor
             .creds
-            .as_ref()
+            .clone()
             .ok_or_else(|| general_err!("no credentials while doing credssp"))?;
-        let username = Username::new(&creds.username, None).map_err(|e| custom_err!("invalid username", e))?;
-        let identity = AuthIdentity {
-            username,
-            password: creds.password.clone().into(),
-        };
 
         let mut sequence =
-            credssp::CredsspSequence::init(&identity, client_computer_name, public_key, kerberos_config)?;
+            credssp::CredsspSequence::init(creds.as_ref(), client_computer_name, public_key, kerberos_config)?;
 
         loop {
             let Some(next_pdu_hint) = sequence.next_pdu_hint()? else {
@@ -173,6 +173,17 @@ where
                     .map_err(|e| ironrdp_connector::custom_err!("write all", e))?;
             }
         }
+
+        if let Some(identity) = sequence.take_identity() {
+            if !creds.authorize(&identity) {
+                warn!(username = %identity.username, "User authenticated with NLA is not authorized");
+                return Err(ConnectorError::new("NLA", ConnectorErrorKind::AccessDenied));
+            }
+
+            info!(username = %identity.username, "User authenticated with NLA");
+            acceptor.nla_credentials = Some(identity);
+        }
+
         Ok(())
     }
 
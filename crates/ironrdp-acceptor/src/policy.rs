@@ -0,0 +1,150 @@
+use core::fmt;
+
+use ironrdp_connector::{reason_err, ConnectorResult, ErrorClass};
+use ironrdp_pdu::nego::{FailureCode, SecurityProtocol};
+
+/// Version of the TLS protocol negotiated with the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TlsVersion {
+    Tls1_0,
+    Tls1_1,
+    Tls1_2,
+    Tls1_3,
+}
+
+impl fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = match self {
+            Self::Tls1_0 => "TLS 1.0",
+            Self::Tls1_1 => "TLS 1.1",
+            Self::Tls1_2 => "TLS 1.2",
+            Self::Tls1_3 => "TLS 1.3",
+        };
+        f.write_str(version)
+    }
+}
+
+/// Security requirements of the server, enforced by the [`Acceptor`](crate::Acceptor).
+///
+/// The clients not supporting the required security protocol are refused during the negotiation, with the failure
+/// code telling them which protocol is required (e.g. [`FailureCode::HYBRID_REQUIRED_BY_SERVER`]). The TLS
+/// requirements are checked once the TLS handshake is done, see [`Acceptor::check_tls`](crate::Acceptor::check_tls).
+///
+/// The default policy accepts any of the security protocols enabled on the acceptor.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SecurityPolicy {
+    min_protocol: Option<SecurityProtocol>,
+    min_tls_version: Option<TlsVersion>,
+    cipher_suites: Vec<u16>,
+}
+
+impl SecurityPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refuses the clients negotiating a security protocol weaker than `protocol`.
+    ///
+    /// The protocols are ordered from the weakest to the strongest: Standard RDP Security (empty), TLS
+    /// ([`SecurityProtocol::SSL`]), CredSSP ([`SecurityProtocol::HYBRID`]) and CredSSP with the Early User
+    /// Authorization Result PDU ([`SecurityProtocol::HYBRID_EX`]).
+    #[must_use]
+    pub fn with_min_protocol(mut self, protocol: SecurityProtocol) -> Self {
+        self.min_protocol = Some(self.min_protocol.map_or(protocol, |min| strongest(min, protocol)));
+        self
+    }
+
+    /// Refuses the clients not authenticating the user with CredSSP (Network Level Authentication).
+    #[must_use]
+    pub fn require_nla(self) -> Self {
+        self.with_min_protocol(SecurityProtocol::HYBRID)
+    }
+
+    /// Refuses the clients not supporting TLS, which would otherwise fall back to Standard RDP Security.
+    #[must_use]
+    pub fn deny_rdp_security(self) -> Self {
+        self.with_min_protocol(SecurityProtocol::SSL)
+    }
+
+    /// Refuses the TLS connections using a version older than `version`.
+    #[must_use]
+    pub fn with_min_tls_version(mut self, version: TlsVersion) -> Self {
+        self.min_tls_version = Some(version);
+        self
+    }
+
+    /// Refuses the TLS connections using a cipher suite not in the list, identified by its IANA number (e.g.
+    /// `0x1302` for `TLS_AES_256_GCM_SHA384`). All the cipher suites are accepted if the list is empty.
+    #[must_use]
+    pub fn with_cipher_suites(mut self, cipher_suites: impl IntoIterator<Item = u16>) -> Self {
+        self.cipher_suites = cipher_suites.into_iter().collect();
+        self
+    }
+
+    /// Returns the weakest security protocol accepted, if any.
+    pub fn min_protocol(&self) -> Option<SecurityProtocol> {
+        self.min_protocol
+    }
+
+    pub fn min_tls_version(&self) -> Option<TlsVersion> {
+        self.min_tls_version
+    }
+
+    pub fn cipher_suites(&self) -> &[u16] {
+        &self.cipher_suites
+    }
+
+    /// Returns the failure code sent to the client if the negotiated `protocol` is weaker than required.
+    pub(crate) fn check_protocol(&self, protocol: SecurityProtocol) -> Result<(), FailureCode> {
+        let Some(min_protocol) = self.min_protocol else {
+            return Ok(());
+        };
+
+        if strength(protocol) >= strength(min_protocol) {
+            Ok(())
+        } else if min_protocol.intersects(SecurityProtocol::HYBRID | SecurityProtocol::HYBRID_EX) {
+            Err(FailureCode::HYBRID_REQUIRED_BY_SERVER)
+        } else {
+            Err(FailureCode::SSL_REQUIRED_BY_SERVER)
+        }
+    }
+
+    pub(crate) fn check_tls(&self, version: TlsVersion, cipher_suite: u16) -> ConnectorResult<()> {
+        if self.min_tls_version.is_some_and(|min| version < min) {
+            return Err(
+                reason_err!("SecurityUpgrade", "{version} is not allowed by the security policy")
+                    .with_class(ErrorClass::Negotiation),
+            );
+        }
+
+        if !self.cipher_suites.is_empty() && !self.cipher_suites.contains(&cipher_suite) {
+            return Err(reason_err!(
+                "SecurityUpgrade",
+                "cipher suite {cipher_suite:#06x} is not allowed by the security policy"
+            )
+            .with_class(ErrorClass::Negotiation));
+        }
+
+        Ok(())
+    }
+}
+
+fn strength(protocol: SecurityProtocol) -> u8 {
+    if protocol.intersects(SecurityProtocol::HYBRID_EX) {
+        3
+    } else if protocol.intersects(SecurityProtocol::HYBRID) {
+        2
+    } else if protocol.intersects(SecurityProtocol::SSL) {
+        1
+    } else {
+        0
+    }
+}
+
+fn strongest(a: SecurityProtocol, b: SecurityProtocol) -> SecurityProtocol {
+    if strength(a) >= strength(b) {
+        a
+    } else {
+        b
+    }
+}
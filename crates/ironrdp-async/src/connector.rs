@@ -85,6 +85,40 @@ where
     Ok(result)
 }
 
+/// Performs the CredSSP authentication only, the rest of the connection sequence being left to the caller.
+///
+/// This is typically used to relay the rest of the connection sequence, as done by a proxy.
+#[instrument(skip_all)]
+pub async fn connect_credssp<S>(
+    _: Upgraded,
+    framed: &mut Framed<S>,
+    connector: &mut ClientConnector,
+    server_name: ServerName,
+    server_public_key: Vec<u8>,
+    network_client: Option<&mut dyn AsyncNetworkClient>,
+    kerberos_config: Option<KerberosConfig>,
+) -> ConnectorResult<()>
+where
+    S: FramedRead + FramedWrite,
+{
+    if connector.should_perform_credssp() {
+        let mut buf = WriteBuf::new();
+
+        perform_credssp_step(
+            framed,
+            connector,
+            &mut buf,
+            server_name,
+            server_public_key,
+            network_client,
+            kerberos_config,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
 #[instrument(skip_all)]
 pub async fn wasm_connect_finalize<S>(
     _: Upgraded,
@@ -189,10 +223,12 @@ where
     let (mut sequence, mut ts_request) = CredsspSequence::init(
         connector.config.credentials.clone(),
         connector.config.domain.as_deref(),
+        connector.config.remote_credential_guard,
         selected_protocol,
         server_name,
         server_public_key,
         kerberos_config,
+        connector.channel_bindings().cloned(),
     )?;
 
     loop {
@@ -273,10 +309,12 @@ where
     let (mut sequence, mut ts_request) = CredsspSequence::init(
         connector.config.credentials.clone(),
         connector.config.domain.as_deref(),
+        connector.config.remote_credential_guard,
         selected_protocol,
         server_name,
         server_public_key,
         kerberos_config,
+        connector.channel_bindings().cloned(),
     )?;
 
     loop {
@@ -38,6 +38,61 @@ pub trait FramedWrite {
     /// partially written, but future calls to `write_all` will start over
     /// from the beginning of the buffer.
     fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> Self::WriteAllFut<'a>;
+
+    /// Writes the entire buffers into this stream, one after the other, without copying them into
+    /// a contiguous buffer when the stream supports vectored writes.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is not cancellation safe, like [`FramedWrite::write_all`].
+    fn write_all_vectored<'a>(&'a mut self, bufs: &'a [&'a [u8]]) -> Self::WriteAllFut<'a>;
+}
+
+/// Buffers written with vectored writes, advanced as they are partially written.
+pub struct VectoredBufs<'a> {
+    bufs: &'a [&'a [u8]],
+    /// Number of bytes of the first buffer already written.
+    offset: usize,
+}
+
+impl<'a> VectoredBufs<'a> {
+    pub fn new(bufs: &'a [&'a [u8]]) -> Self {
+        let mut this = Self { bufs, offset: 0 };
+        // Skips the empty buffers.
+        this.advance(0);
+        this
+    }
+
+    /// Returns whether all the buffers were written.
+    pub fn is_empty(&self) -> bool {
+        self.bufs.is_empty()
+    }
+
+    /// Returns the bytes remaining to be written, as given to `write_vectored`.
+    pub fn io_slices(&self) -> Vec<io::IoSlice<'a>> {
+        let mut slices = Vec::with_capacity(self.bufs.len());
+
+        if let Some((first, rest)) = self.bufs.split_first() {
+            slices.push(io::IoSlice::new(first.get(self.offset..).unwrap_or_default()));
+            slices.extend(rest.iter().map(|buf| io::IoSlice::new(buf)));
+        }
+
+        slices
+    }
+
+    /// Marks `written` bytes as written.
+    pub fn advance(&mut self, written: usize) {
+        self.offset += written;
+
+        while let Some((first, rest)) = self.bufs.split_first() {
+            if self.offset < first.len() {
+                break;
+            }
+
+            self.offset -= first.len();
+            self.bufs = rest;
+        }
+    }
 }
 
 pub trait StreamWrapper: Sized {
@@ -142,6 +197,8 @@ where
                 Ok(Some(pdu_info)) => {
                     let frame = self.read_exact(pdu_info.length).await?;
 
+                    trace!(pdu.action = ?pdu_info.action, pdu.size = pdu_info.length, "PDU frame read");
+
                     return Ok((pdu_info.action, frame));
                 }
                 Ok(None) => {
@@ -224,6 +281,10 @@ where
     fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> Self::WriteAllFut<'a> {
         self.stream.write_all(buf)
     }
+
+    fn write_all_vectored<'a>(&'a mut self, bufs: &'a [&'a [u8]]) -> Self::WriteAllFut<'a> {
+        self.stream.write_all_vectored(bufs)
+    }
 }
 
 pub async fn single_sequence_step<S>(
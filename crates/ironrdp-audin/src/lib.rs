@@ -0,0 +1,7 @@
+#![doc = include_str!("../README.md")]
+#![doc(html_logo_url = "https://cdnweb.devolutions.net/images/projects/devolutions/logos/devolutions-icon-shadow.svg")]
+
+pub const CHANNEL_NAME: &str = "AUDIO_INPUT";
+
+pub mod pdu;
+pub mod server;
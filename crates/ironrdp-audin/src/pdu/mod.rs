@@ -0,0 +1,422 @@
+//! Audio Input Redirection Virtual Channel Extension PDUs \[MS-RDPEAI\] implementation.
+
+use ironrdp_core::{
+    cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult,
+    ReadCursor, WriteCursor,
+};
+use ironrdp_dvc::DvcEncode;
+pub use ironrdp_rdpsnd::pdu::{AudioFormat, WaveFormat};
+
+const MSG_SNDIN_VERSION: u8 = 0x01;
+const MSG_SNDIN_FORMATS: u8 = 0x02;
+const MSG_SNDIN_OPEN: u8 = 0x03;
+const MSG_SNDIN_OPEN_REPLY: u8 = 0x04;
+const MSG_SNDIN_DATA_INCOMING: u8 = 0x05;
+const MSG_SNDIN_DATA: u8 = 0x06;
+const MSG_SNDIN_FORMATCHANGE: u8 = 0x07;
+
+/// Audio input message, prefixed with `SNDIN_PDU` header
+///
+/// The message identifiers are shared by both directions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AudioInputPdu {
+    Version(VersionPdu),
+    Formats(FormatsPdu),
+    Open(OpenPdu),
+    OpenReply(OpenReplyPdu),
+    /// Sent by the client before each data PDU.
+    IncomingData,
+    Data(DataPdu),
+    FormatChange(FormatChangePdu),
+}
+
+impl AudioInputPdu {
+    const NAME: &'static str = "SNDIN_PDU";
+    const FIXED_PART_SIZE: usize = 1 /* MessageId */;
+
+    fn message_id(&self) -> u8 {
+        match self {
+            AudioInputPdu::Version(_) => MSG_SNDIN_VERSION,
+            AudioInputPdu::Formats(_) => MSG_SNDIN_FORMATS,
+            AudioInputPdu::Open(_) => MSG_SNDIN_OPEN,
+            AudioInputPdu::OpenReply(_) => MSG_SNDIN_OPEN_REPLY,
+            AudioInputPdu::IncomingData => MSG_SNDIN_DATA_INCOMING,
+            AudioInputPdu::Data(_) => MSG_SNDIN_DATA,
+            AudioInputPdu::FormatChange(_) => MSG_SNDIN_FORMATCHANGE,
+        }
+    }
+}
+
+impl Encode for AudioInputPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u8(self.message_id());
+
+        match self {
+            AudioInputPdu::Version(pdu) => pdu.encode(dst),
+            AudioInputPdu::Formats(pdu) => pdu.encode(dst),
+            AudioInputPdu::Open(pdu) => pdu.encode(dst),
+            AudioInputPdu::OpenReply(pdu) => pdu.encode(dst),
+            AudioInputPdu::IncomingData => Ok(()),
+            AudioInputPdu::Data(pdu) => pdu.encode(dst),
+            AudioInputPdu::FormatChange(pdu) => pdu.encode(dst),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+            + match self {
+                AudioInputPdu::Version(pdu) => pdu.size(),
+                AudioInputPdu::Formats(pdu) => pdu.size(),
+                AudioInputPdu::Open(pdu) => pdu.size(),
+                AudioInputPdu::OpenReply(pdu) => pdu.size(),
+                AudioInputPdu::IncomingData => 0,
+                AudioInputPdu::Data(pdu) => pdu.size(),
+                AudioInputPdu::FormatChange(pdu) => pdu.size(),
+            }
+    }
+}
+
+impl DvcEncode for AudioInputPdu {}
+
+impl<'de> Decode<'de> for AudioInputPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let pdu = match src.read_u8() {
+            MSG_SNDIN_VERSION => AudioInputPdu::Version(VersionPdu::decode(src)?),
+            MSG_SNDIN_FORMATS => AudioInputPdu::Formats(FormatsPdu::decode(src)?),
+            MSG_SNDIN_OPEN => AudioInputPdu::Open(OpenPdu::decode(src)?),
+            MSG_SNDIN_OPEN_REPLY => AudioInputPdu::OpenReply(OpenReplyPdu::decode(src)?),
+            MSG_SNDIN_DATA_INCOMING => AudioInputPdu::IncomingData,
+            MSG_SNDIN_DATA => AudioInputPdu::Data(DataPdu::decode(src)?),
+            MSG_SNDIN_FORMATCHANGE => AudioInputPdu::FormatChange(FormatChangePdu::decode(src)?),
+            _ => return Err(invalid_field_err!("MessageId", "unknown audio input message")),
+        };
+
+        Ok(pdu)
+    }
+}
+
+impl From<VersionPdu> for AudioInputPdu {
+    fn from(pdu: VersionPdu) -> Self {
+        Self::Version(pdu)
+    }
+}
+
+impl From<FormatsPdu> for AudioInputPdu {
+    fn from(pdu: FormatsPdu) -> Self {
+        Self::Formats(pdu)
+    }
+}
+
+impl From<OpenPdu> for AudioInputPdu {
+    fn from(pdu: OpenPdu) -> Self {
+        Self::Open(pdu)
+    }
+}
+
+impl From<OpenReplyPdu> for AudioInputPdu {
+    fn from(pdu: OpenReplyPdu) -> Self {
+        Self::OpenReply(pdu)
+    }
+}
+
+impl From<DataPdu> for AudioInputPdu {
+    fn from(pdu: DataPdu) -> Self {
+        Self::Data(pdu)
+    }
+}
+
+impl From<FormatChangePdu> for AudioInputPdu {
+    fn from(pdu: FormatChangePdu) -> Self {
+        Self::FormatChange(pdu)
+    }
+}
+
+/// Version of the audio input protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version(pub u32);
+
+impl Version {
+    pub const V1: Self = Self(0x0000_0001);
+    /// The format can be changed while the device is open.
+    pub const V2: Self = Self(0x0000_0002);
+}
+
+/// 2.2.2.1 Version PDU (MSG_SNDIN_VERSION)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionPdu {
+    pub version: Version,
+}
+
+impl VersionPdu {
+    const NAME: &'static str = "MSG_SNDIN_VERSION";
+    const FIXED_PART_SIZE: usize = 4 /* Version */;
+}
+
+impl Encode for VersionPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.version.0);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for VersionPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let version = Version(src.read_u32());
+
+        Ok(Self { version })
+    }
+}
+
+/// 2.2.2.2 Sound Formats PDU (MSG_SNDIN_FORMATS)
+///
+/// Sent by the server with the formats it supports, then by the client with the subset it supports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatsPdu {
+    pub formats: Vec<AudioFormat>,
+}
+
+impl FormatsPdu {
+    const NAME: &'static str = "MSG_SNDIN_FORMATS";
+    const FIXED_PART_SIZE: usize = 4 /* NumFormats */ + 4 /* cbSizeFormatsPacket */;
+}
+
+impl Encode for FormatsPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u32(cast_length!("NumFormats", self.formats.len())?);
+        // The size of the whole PDU, header included.
+        dst.write_u32(cast_length!(
+            "cbSizeFormatsPacket",
+            self.size() + AudioInputPdu::FIXED_PART_SIZE
+        )?);
+        for format in &self.formats {
+            format.encode(dst)?;
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.formats.iter().map(Encode::size).sum::<usize>()
+    }
+}
+
+impl<'de> Decode<'de> for FormatsPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let num_formats: usize = cast_length!("NumFormats", src.read_u32())?;
+        let _size = src.read_u32();
+
+        // Each format is at least 18 bytes, don't trust the count for the allocation.
+        let mut formats = Vec::with_capacity(num_formats.min(src.len() / 18));
+        for _ in 0..num_formats {
+            formats.push(AudioFormat::decode(src)?);
+        }
+
+        // The optional extra data following the formats is ignored.
+
+        Ok(Self { formats })
+    }
+}
+
+/// 2.2.2.3 Open PDU (MSG_SNDIN_OPEN)
+///
+/// Sent by the server to start the recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenPdu {
+    /// Number of audio frames the client sends in each data PDU.
+    pub frames_per_packet: u32,
+    /// Index of the initial format, in the formats sent by the client.
+    pub initial_format: u32,
+    /// Format of the recording device.
+    pub format: AudioFormat,
+}
+
+impl OpenPdu {
+    const NAME: &'static str = "MSG_SNDIN_OPEN";
+    const FIXED_PART_SIZE: usize = 4 /* FramesPerPacket */ + 4 /* initialFormat */;
+}
+
+impl Encode for OpenPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u32(self.frames_per_packet);
+        dst.write_u32(self.initial_format);
+        self.format.encode(dst)
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.format.size()
+    }
+}
+
+impl<'de> Decode<'de> for OpenPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let frames_per_packet = src.read_u32();
+        let initial_format = src.read_u32();
+        let format = AudioFormat::decode(src)?;
+
+        Ok(Self {
+            frames_per_packet,
+            initial_format,
+            format,
+        })
+    }
+}
+
+/// 2.2.2.4 Open Reply PDU (MSG_SNDIN_OPEN_REPLY)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenReplyPdu {
+    /// HRESULT of the opening of the recording device.
+    pub result: u32,
+}
+
+impl OpenReplyPdu {
+    const NAME: &'static str = "MSG_SNDIN_OPEN_REPLY";
+    const FIXED_PART_SIZE: usize = 4 /* Result */;
+
+    pub fn is_success(&self) -> bool {
+        // Failure HRESULTs have the severity bit set.
+        self.result & 0x8000_0000 == 0
+    }
+}
+
+impl Encode for OpenReplyPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.result);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for OpenReplyPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let result = src.read_u32();
+
+        Ok(Self { result })
+    }
+}
+
+/// 2.2.3.2 Data PDU (MSG_SNDIN_DATA)
+///
+/// Audio data recorded by the client, encoded with the current format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataPdu {
+    pub data: Vec<u8>,
+}
+
+impl DataPdu {
+    const NAME: &'static str = "MSG_SNDIN_DATA";
+}
+
+impl Encode for DataPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_slice(&self.data);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl<'de> Decode<'de> for DataPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        let data = src.read_remaining().to_vec();
+
+        Ok(Self { data })
+    }
+}
+
+/// 2.2.4 Format Change PDU (MSG_SNDIN_FORMATCHANGE)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatChangePdu {
+    /// Index of the new format, in the formats sent by the client.
+    pub new_format: u32,
+}
+
+impl FormatChangePdu {
+    const NAME: &'static str = "MSG_SNDIN_FORMATCHANGE";
+    const FIXED_PART_SIZE: usize = 4 /* NewFormat */;
+}
+
+impl Encode for FormatChangePdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.new_format);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for FormatChangePdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let new_format = src.read_u32();
+
+        Ok(Self { new_format })
+    }
+}
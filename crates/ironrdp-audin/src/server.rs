@@ -0,0 +1,187 @@
+use core::fmt;
+
+use ironrdp_core::{decode, impl_as_any};
+use ironrdp_dvc::{DvcMessage, DvcProcessor, DvcServerProcessor};
+use ironrdp_pdu::{decode_err, PduResult};
+use tracing::{debug, error, trace, warn};
+
+use crate::pdu::{self, AudioFormat, AudioInputPdu};
+use crate::CHANNEL_NAME;
+
+pub trait AudioInputServerHandler: Send + fmt::Debug {
+    /// Returns the formats offered to the client, in order of preference.
+    fn get_formats(&self) -> &[AudioFormat];
+
+    /// Called with the formats supported by the client.
+    ///
+    /// Returns the index of the format to record with, or `None` to not record.
+    fn start(&mut self, client_formats: &[AudioFormat]) -> Option<u32>;
+
+    /// Called when the client records using a new format, before any data in this format.
+    fn format_changed(&mut self, format: &AudioFormat);
+
+    /// Called with the audio data recorded by the client, encoded with the current format.
+    fn data(&mut self, data: &[u8]);
+
+    fn stop(&mut self);
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum AudioInputState {
+    Start,
+    WaitingForVersion,
+    WaitingForFormats,
+    Opening,
+    Recording,
+    Stop,
+}
+
+/// A server for the Audio Input Redirection Virtual Channel.
+#[derive(Debug)]
+pub struct AudioInputServer {
+    handler: Box<dyn AudioInputServerHandler>,
+    state: AudioInputState,
+    client_formats: Vec<AudioFormat>,
+    initial_format: u32,
+    format_no: Option<u32>,
+}
+
+impl AudioInputServer {
+    pub fn new(handler: Box<dyn AudioInputServerHandler>) -> Self {
+        Self {
+            handler,
+            state: AudioInputState::Start,
+            client_formats: Vec::new(),
+            initial_format: 0,
+            format_no: None,
+        }
+    }
+
+    /// Returns the format currently used by the client to record.
+    pub fn format(&self) -> Option<&AudioFormat> {
+        self.format_no
+            .and_then(|format_no| self.client_formats.get(usize::try_from(format_no).ok()?))
+    }
+
+    fn open(&mut self) -> Vec<DvcMessage> {
+        let Some(initial_format) = self.handler.start(&self.client_formats) else {
+            debug!("Audio input not requested");
+            self.state = AudioInputState::Stop;
+            return Vec::new();
+        };
+
+        let Some(format) = usize::try_from(initial_format)
+            .ok()
+            .and_then(|index| self.client_formats.get(index))
+        else {
+            error!(initial_format, "Invalid audio input format");
+            self.state = AudioInputState::Stop;
+            return Vec::new();
+        };
+
+        // Packets of 20ms.
+        let frames_per_packet = (format.n_samples_per_sec / 50).max(1);
+
+        let pdu = AudioInputPdu::from(pdu::OpenPdu {
+            frames_per_packet,
+            initial_format,
+            format: format.clone(),
+        });
+
+        self.initial_format = initial_format;
+        self.state = AudioInputState::Opening;
+        vec![Box::new(pdu)]
+    }
+
+    fn change_format(&mut self, new_format: u32) {
+        let Some(format) = usize::try_from(new_format)
+            .ok()
+            .and_then(|index| self.client_formats.get(index))
+        else {
+            error!(new_format, "Invalid audio input format");
+            self.state = AudioInputState::Stop;
+            return;
+        };
+
+        debug!(?format, "Audio input format changed");
+        self.handler.format_changed(format);
+        self.format_no = Some(new_format);
+    }
+}
+
+impl_as_any!(AudioInputServer);
+
+impl DvcProcessor for AudioInputServer {
+    fn channel_name(&self) -> &str {
+        CHANNEL_NAME
+    }
+
+    fn start(&mut self, _channel_id: u32) -> PduResult<Vec<DvcMessage>> {
+        if self.state != AudioInputState::Start {
+            error!("Attempted to start audio input channel in invalid state");
+        }
+
+        let pdu = AudioInputPdu::from(pdu::VersionPdu {
+            version: pdu::Version::V2,
+        });
+
+        self.state = AudioInputState::WaitingForVersion;
+        Ok(vec![Box::new(pdu)])
+    }
+
+    fn process(&mut self, _channel_id: u32, payload: &[u8]) -> PduResult<Vec<DvcMessage>> {
+        let pdu: AudioInputPdu = decode(payload).map_err(|e| decode_err!(e))?;
+        trace!(?pdu);
+
+        let msg = match (self.state, pdu) {
+            (AudioInputState::WaitingForVersion, AudioInputPdu::Version(version)) => {
+                debug!(?version, "Client audio input version");
+                self.state = AudioInputState::WaitingForFormats;
+                let pdu = AudioInputPdu::from(pdu::FormatsPdu {
+                    formats: self.handler.get_formats().to_vec(),
+                });
+                vec![Box::new(pdu) as DvcMessage]
+            }
+            (AudioInputState::WaitingForFormats, AudioInputPdu::Formats(formats)) => {
+                self.client_formats = formats.formats;
+                self.open()
+            }
+            (AudioInputState::Opening | AudioInputState::Recording, AudioInputPdu::FormatChange(change)) => {
+                self.change_format(change.new_format);
+                vec![]
+            }
+            (AudioInputState::Opening, AudioInputPdu::OpenReply(reply)) => {
+                if reply.is_success() {
+                    self.state = AudioInputState::Recording;
+                    // The client may not send a format change before recording with the initial format.
+                    if self.format_no.is_none() {
+                        self.change_format(self.initial_format);
+                    }
+                } else {
+                    warn!(result = reply.result, "Client failed to open its audio input");
+                    self.state = AudioInputState::Stop;
+                }
+                vec![]
+            }
+            (AudioInputState::Opening | AudioInputState::Recording, AudioInputPdu::IncomingData) => vec![],
+            (AudioInputState::Recording, AudioInputPdu::Data(data)) => {
+                self.handler.data(&data.data);
+                vec![]
+            }
+            (state, pdu) => {
+                error!(?state, ?pdu, "Invalid audio input PDU");
+                vec![]
+            }
+        };
+
+        Ok(msg)
+    }
+}
+
+impl Drop for AudioInputServer {
+    fn drop(&mut self) {
+        self.handler.stop();
+    }
+}
+
+impl DvcServerProcessor for AudioInputServer {}
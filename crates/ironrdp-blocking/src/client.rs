@@ -0,0 +1,174 @@
+use std::io::{self, Write as _};
+use std::net::{TcpStream, ToSocketAddrs as _};
+use std::sync::Arc;
+
+use ironrdp_connector::sspi::network_client::NetworkClient;
+use ironrdp_connector::{custom_err, general_err, ClientConnector, Config, ConnectionResult, ConnectorResult};
+use rustls::pki_types::ServerName;
+
+use crate::{connect_begin, connect_finalize, mark_as_upgraded, Framed};
+
+/// TCP stream secured with rustls.
+pub type TlsStream = rustls::StreamOwned<rustls::ClientConnection, TcpStream>;
+
+/// Connects to a RDP server over TCP, performing the TLS upgrade with rustls.
+///
+/// The certificate of the server is not verified: check it afterwards if needed, e.g. by pinning it. CredSSP
+/// binds the authentication to its public key.
+pub fn connect(
+    config: Config,
+    server_name: &str,
+    port: u16,
+    network_client: &mut impl NetworkClient,
+) -> ConnectorResult<(ConnectionResult, Framed<TlsStream>)> {
+    let server_addr = (server_name, port)
+        .to_socket_addrs()
+        .map_err(|e| custom_err!("lookup address", e))?
+        .next()
+        .ok_or_else(|| general_err!("no address for the server name"))?;
+
+    info!(%server_addr, "Looked up server address");
+
+    let stream = TcpStream::connect(server_addr).map_err(|e| custom_err!("TCP connect", e))?;
+
+    let mut framed = Framed::new(stream);
+
+    let mut connector = ClientConnector::new(config).with_client_addr(server_addr);
+
+    let should_upgrade = connect_begin(&mut framed, &mut connector)?;
+
+    debug!("TLS upgrade");
+
+    // Ensure there is no leftover
+    let initial_stream = framed.into_inner_no_leftover();
+
+    let (upgraded_stream, server_public_key) =
+        tls_upgrade(initial_stream, server_name).map_err(|e| custom_err!("TLS upgrade", e))?;
+
+    let upgraded = mark_as_upgraded(should_upgrade, &mut connector);
+
+    let mut upgraded_framed = Framed::new(upgraded_stream);
+
+    let connection_result = connect_finalize(
+        upgraded,
+        &mut upgraded_framed,
+        connector,
+        server_name.to_owned().into(),
+        server_public_key,
+        network_client,
+        None,
+    )?;
+
+    Ok((connection_result, upgraded_framed))
+}
+
+/// Upgrades the stream to TLS, returning the secured stream and the public key of the server.
+pub fn tls_upgrade(stream: TcpStream, server_name: &str) -> io::Result<(TlsStream, Vec<u8>)> {
+    let mut config = rustls::client::ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(danger::NoCertificateVerification))
+        .with_no_client_auth();
+
+    // This adds support for the SSLKEYLOGFILE env variable (https://wiki.wireshark.org/TLS#using-the-pre-master-secret)
+    config.key_log = Arc::new(rustls::KeyLogFile::new());
+
+    // Disable TLS resumption because it’s not supported by some services such as CredSSP.
+    //
+    // > The CredSSP Protocol does not extend the TLS wire protocol. TLS session resumption is not supported.
+    //
+    // source: https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-cssp/385a7489-d46b-464c-b224-f7340e308a5c
+    config.resumption = rustls::client::Resumption::disabled();
+
+    let server_name = ServerName::try_from(server_name.to_owned()).map_err(io::Error::other)?;
+
+    let client = rustls::ClientConnection::new(Arc::new(config), server_name).map_err(io::Error::other)?;
+
+    let mut tls_stream = rustls::StreamOwned::new(client, stream);
+
+    // We need to flush in order to ensure the TLS handshake is moving forward. Without flushing,
+    // it’s likely the peer certificate is not yet received a this point.
+    tls_stream.flush()?;
+
+    let cert = tls_stream
+        .conn
+        .peer_certificates()
+        .and_then(|certificates| certificates.first())
+        .ok_or_else(|| io::Error::other("peer certificate is missing"))?;
+
+    let server_public_key = extract_tls_server_public_key(cert)?;
+
+    Ok((tls_stream, server_public_key))
+}
+
+fn extract_tls_server_public_key(cert: &[u8]) -> io::Result<Vec<u8>> {
+    use x509_cert::der::Decode as _;
+
+    let cert = x509_cert::Certificate::from_der(cert).map_err(io::Error::other)?;
+
+    let server_public_key = cert
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key
+        .as_bytes()
+        .ok_or_else(|| io::Error::other("subject public key BIT STRING is not aligned"))?
+        .to_owned();
+
+    Ok(server_public_key)
+}
+
+mod danger {
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::{pki_types, DigitallySignedStruct, Error, SignatureScheme};
+
+    #[derive(Debug)]
+    pub(super) struct NoCertificateVerification;
+
+    impl ServerCertVerifier for NoCertificateVerification {
+        fn verify_server_cert(
+            &self,
+            _: &pki_types::CertificateDer<'_>,
+            _: &[pki_types::CertificateDer<'_>],
+            _: &pki_types::ServerName<'_>,
+            _: &[u8],
+            _: pki_types::UnixTime,
+        ) -> Result<ServerCertVerified, Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            _: &[u8],
+            _: &pki_types::CertificateDer<'_>,
+            _: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            _: &[u8],
+            _: &pki_types::CertificateDer<'_>,
+            _: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            vec![
+                SignatureScheme::RSA_PKCS1_SHA1,
+                SignatureScheme::ECDSA_SHA1_Legacy,
+                SignatureScheme::RSA_PKCS1_SHA256,
+                SignatureScheme::ECDSA_NISTP256_SHA256,
+                SignatureScheme::RSA_PKCS1_SHA384,
+                SignatureScheme::ECDSA_NISTP384_SHA384,
+                SignatureScheme::RSA_PKCS1_SHA512,
+                SignatureScheme::ECDSA_NISTP521_SHA512,
+                SignatureScheme::RSA_PSS_SHA256,
+                SignatureScheme::RSA_PSS_SHA384,
+                SignatureScheme::RSA_PSS_SHA512,
+                SignatureScheme::ED25519,
+                SignatureScheme::ED448,
+            ]
+        }
+    }
+}
@@ -133,10 +133,12 @@ where
     let (mut sequence, mut ts_request) = CredsspSequence::init(
         connector.config.credentials.clone(),
         connector.config.domain.as_deref(),
+        connector.config.remote_credential_guard,
         selected_protocol,
         server_name,
         server_public_key,
         kerberos_config,
+        connector.channel_bindings().cloned(),
     )?;
 
     loop {
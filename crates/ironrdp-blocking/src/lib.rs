@@ -4,9 +4,16 @@
 #[macro_use]
 extern crate tracing;
 
+#[cfg(feature = "rustls")]
+mod client;
 mod connector;
 mod framed;
+#[cfg(feature = "session")]
 mod session;
 
+#[cfg(feature = "rustls")]
+pub use self::client::*;
 pub use self::connector::*;
 pub use self::framed::*;
+#[cfg(feature = "session")]
+pub use self::session::*;
@@ -1 +1,179 @@
-// TODO: active session I/O helpers? I’m not yet sure we need that
+use std::io::{Read, Write};
+
+use ironrdp_connector::connection_activation::{ConnectionActivationSequence, ConnectionActivationState};
+use ironrdp_connector::{ConnectionResult, Sequence as _};
+use ironrdp_core::WriteBuf;
+use ironrdp_graphics::image_processing::PixelFormat;
+use ironrdp_pdu::input::fast_path::FastPathInputEvent;
+use ironrdp_session::image::DecodedImage;
+use ironrdp_session::{fast_path, ActiveStage, ActiveStageOutput, GracefulDisconnectReason, SessionResult};
+
+use crate::framed::Framed;
+
+/// Active session over blocking I/O, once the connection sequence is completed.
+///
+/// The graphics updates are decoded into an image, and the responses to the server are sent by the session,
+/// including the Deactivation-Reactivation Sequence.
+pub struct Session<S> {
+    framed: Framed<S>,
+    active_stage: ActiveStage,
+    image: DecodedImage,
+}
+
+impl<S> Session<S>
+where
+    S: Read + Write,
+{
+    pub fn new(framed: Framed<S>, connection_result: ConnectionResult) -> Self {
+        let image = DecodedImage::new(
+            PixelFormat::RgbA32,
+            connection_result.desktop_size.width,
+            connection_result.desktop_size.height,
+        );
+
+        Self {
+            framed,
+            active_stage: ActiveStage::new(connection_result),
+            image,
+        }
+    }
+
+    /// Returns the image of the remote desktop.
+    pub fn image(&self) -> &DecodedImage {
+        &self.image
+    }
+
+    pub fn active_stage(&self) -> &ActiveStage {
+        &self.active_stage
+    }
+
+    pub fn active_stage_mut(&mut self) -> &mut ActiveStage {
+        &mut self.active_stage
+    }
+
+    pub fn framed_mut(&mut self) -> &mut Framed<S> {
+        &mut self.framed
+    }
+
+    pub fn into_framed(self) -> Framed<S> {
+        self.framed
+    }
+
+    /// Reads and processes the next PDU of the server.
+    ///
+    /// Blocks until a PDU is received, or the read timeout of the stream, if any, elapses. Returns the outputs
+    /// which are not handled by the session, e.g. the graphics updates or the termination of the session.
+    pub fn step(&mut self) -> SessionResult<Vec<ActiveStageOutput>> {
+        let (action, payload) = self
+            .framed
+            .read_pdu()
+            .map_err(|e| ironrdp_session::custom_err!("read frame", e))?;
+
+        trace!(?action, frame_length = payload.len(), "Frame received");
+
+        let outputs = self.active_stage.process(&mut self.image, action, &payload)?;
+
+        self.handle_outputs(outputs)
+    }
+
+    /// Sends input events to the server.
+    pub fn send_input(&mut self, events: &[FastPathInputEvent]) -> SessionResult<Vec<ActiveStageOutput>> {
+        let outputs = self.active_stage.process_fastpath_input(&mut self.image, events)?;
+
+        self.handle_outputs(outputs)
+    }
+
+    /// Requests the server to end the session, which is terminated once the server disconnects.
+    pub fn shutdown(&mut self) -> SessionResult<Vec<ActiveStageOutput>> {
+        let outputs = self.active_stage.graceful_shutdown()?;
+
+        self.handle_outputs(outputs)
+    }
+
+    /// Runs the session until it is terminated, calling `on_output` with the outputs not handled by the session.
+    pub fn run(
+        &mut self,
+        mut on_output: impl FnMut(&DecodedImage, &ActiveStageOutput),
+    ) -> SessionResult<GracefulDisconnectReason> {
+        loop {
+            for output in self.step()? {
+                if let ActiveStageOutput::Terminate(reason) = output {
+                    return Ok(reason);
+                }
+
+                on_output(&self.image, &output);
+            }
+        }
+    }
+
+    fn handle_outputs(&mut self, outputs: Vec<ActiveStageOutput>) -> SessionResult<Vec<ActiveStageOutput>> {
+        let mut unhandled = Vec::new();
+
+        for output in outputs {
+            match output {
+                ActiveStageOutput::ResponseFrame(frame) => self
+                    .framed
+                    .write_all(&frame)
+                    .map_err(|e| ironrdp_session::custom_err!("write response", e))?,
+                ActiveStageOutput::DeactivateAll(connection_activation) => self.reactivate(*connection_activation)?,
+                output => unhandled.push(output),
+            }
+        }
+
+        Ok(unhandled)
+    }
+
+    /// Executes the Deactivation-Reactivation Sequence.
+    fn reactivate(&mut self, mut connection_activation: ConnectionActivationSequence) -> SessionResult<()> {
+        debug!("Received Server Deactivate All PDU, executing Deactivation-Reactivation Sequence");
+
+        let mut buf = WriteBuf::new();
+
+        loop {
+            buf.clear();
+
+            let written = if let Some(next_pdu_hint) = connection_activation.next_pdu_hint() {
+                let pdu = self
+                    .framed
+                    .read_by_hint(next_pdu_hint)
+                    .map_err(|e| ironrdp_session::custom_err!("read deactivation-reactivation sequence step", e))?;
+
+                connection_activation.step(&pdu, &mut buf)
+            } else {
+                connection_activation.step_no_input(&mut buf)
+            }
+            .map_err(|e| ironrdp_session::custom_err!("deactivation-reactivation sequence step", e))?;
+
+            if let Some(response_len) = written.size() {
+                self.framed
+                    .write_all(&buf[..response_len])
+                    .map_err(|e| ironrdp_session::custom_err!("write deactivation-reactivation sequence step", e))?;
+            }
+
+            if let ConnectionActivationState::Finalized {
+                io_channel_id,
+                user_channel_id,
+                desktop_size,
+                no_server_pointer,
+                pointer_software_rendering,
+            } = connection_activation.state
+            {
+                debug!(?desktop_size, "Deactivation-Reactivation Sequence completed");
+
+                self.image = DecodedImage::new(PixelFormat::RgbA32, desktop_size.width, desktop_size.height);
+                self.active_stage.set_fastpath_processor(
+                    fast_path::ProcessorBuilder {
+                        io_channel_id,
+                        user_channel_id,
+                        no_server_pointer,
+                        pointer_software_rendering,
+                    }
+                    .build(),
+                );
+                self.active_stage.set_no_server_pointer(no_server_pointer);
+
+                return Ok(());
+            }
+        }
+    }
+}
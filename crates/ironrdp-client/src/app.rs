@@ -2,6 +2,7 @@
 
 use core::num::NonZeroU32;
 use core::time::Duration;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -11,30 +12,40 @@ use winit::application::ApplicationHandler;
 use winit::dpi::{LogicalPosition, PhysicalSize};
 use winit::event::{self, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
-use winit::keyboard::ModifiersKeyState;
+use winit::keyboard::{KeyCode, ModifiersKeyState, ModifiersState, PhysicalKey};
 use winit::platform::scancode::PhysicalKeyExtScancode;
-use winit::window::{Window, WindowAttributes};
+use winit::window::{Window, WindowAttributes, WindowId};
 
-use crate::rdp::{RdpInputEvent, RdpOutputEvent};
+use crate::rdp::{Experience, RdpInputEvent, RdpOutputEvent, RdpSessionEvent, SessionId};
 
 type WindowSurface = (Arc<Window>, softbuffer::Surface<DisplayHandle<'static>, Arc<Window>>);
 
+/// Hosts the sessions of the client, each one in its own window.
 pub struct App {
-    input_event_sender: mpsc::UnboundedSender<RdpInputEvent>,
     context: softbuffer::Context<DisplayHandle<'static>>,
+    sessions: HashMap<SessionId, SessionWindow>,
+    windows: HashMap<WindowId, SessionId>,
+}
+
+/// The window of a session, with its own input state and RDP event channel.
+struct SessionWindow {
+    title: String,
+    input_event_sender: mpsc::UnboundedSender<RdpInputEvent>,
     window: Option<WindowSurface>,
     buffer: Vec<u32>,
     buffer_size: (u16, u16),
     input_database: ironrdp::input::Database,
     last_size: Option<PhysicalSize<u32>>,
     resize_timeout: Option<Instant>,
+    modifiers: ModifiersState,
+    /// The experience settings the session was configured with.
+    experience: Experience,
+    low_bandwidth: bool,
+    output_suppressed: bool,
 }
 
 impl App {
-    pub fn new(
-        event_loop: &EventLoop<RdpOutputEvent>,
-        input_event_sender: &mpsc::UnboundedSender<RdpInputEvent>,
-    ) -> anyhow::Result<Self> {
+    pub fn new(event_loop: &EventLoop<RdpSessionEvent>) -> anyhow::Result<Self> {
         // SAFETY: We drop the softbuffer context right before the event loop is stopped, thus making this safe.
         // FIXME: This is not a sufficient proof and the API is actually unsound as-is.
         let display_handle = unsafe {
@@ -43,19 +54,58 @@ impl App {
         let context = softbuffer::Context::new(display_handle)
             .map_err(|e| anyhow::anyhow!("unable to initialize softbuffer context: {e}"))?;
 
-        let input_database = ironrdp::input::Database::new();
         Ok(Self {
-            input_event_sender: input_event_sender.clone(),
             context,
-            window: None,
-            buffer: Vec::new(),
-            buffer_size: (0, 0),
-            input_database,
-            last_size: None,
-            resize_timeout: None,
+            sessions: HashMap::new(),
+            windows: HashMap::new(),
         })
     }
 
+    /// Hosts a session, whose window is opened once the application is resumed.
+    ///
+    /// The input events of the window are sent to the session using `input_event_sender`. The hotkeys switch between
+    /// `experience` and the low bandwidth experience.
+    pub fn add_session(
+        &mut self,
+        session: SessionId,
+        title: impl Into<String>,
+        input_event_sender: &mpsc::UnboundedSender<RdpInputEvent>,
+        experience: Experience,
+    ) {
+        self.sessions.insert(
+            session,
+            SessionWindow {
+                title: title.into(),
+                input_event_sender: input_event_sender.clone(),
+                window: None,
+                buffer: Vec::new(),
+                buffer_size: (0, 0),
+                input_database: ironrdp::input::Database::new(),
+                last_size: None,
+                resize_timeout: None,
+                modifiers: ModifiersState::empty(),
+                experience,
+                low_bandwidth: false,
+                output_suppressed: false,
+            },
+        );
+    }
+
+    /// Closes the window of a session, and stops the event loop once all the sessions are closed.
+    fn remove_session(&mut self, event_loop: &ActiveEventLoop, session: SessionId) {
+        if let Some(removed) = self.sessions.remove(&session) {
+            if let Some((window, _)) = removed.window {
+                self.windows.remove(&window.id());
+            }
+        }
+
+        if self.sessions.is_empty() {
+            event_loop.exit();
+        }
+    }
+}
+
+impl SessionWindow {
     fn send_resize_event(&mut self) {
         let Some(size) = self.last_size.take() else {
             return;
@@ -88,53 +138,77 @@ impl App {
         sb_buffer.copy_from_slice(self.buffer.as_slice());
         sb_buffer.present().expect("buffer present");
     }
-}
 
-impl ApplicationHandler<RdpOutputEvent> for App {
-    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
-        if let Some(timeout) = self.resize_timeout {
-            if let Some(timeout) = timeout.checked_duration_since(Instant::now()) {
-                event_loop.set_control_flow(ControlFlow::wait_duration(timeout));
-            } else {
-                self.send_resize_event();
-                self.resize_timeout = None;
-                event_loop.set_control_flow(ControlFlow::Wait);
-            }
+    /// Handles the hotkeys of the client, returning `true` if the key must not be sent to the server.
+    ///
+    /// - Ctrl+Alt+F9 switches between the configured and the low bandwidth experience, reconnecting the session.
+    /// - Ctrl+Alt+F10 pauses or resumes the display updates.
+    fn hotkey(&mut self, key: PhysicalKey, repeat: bool) -> bool {
+        if !(self.modifiers.control_key() && self.modifiers.alt_key()) {
+            return false;
         }
-    }
 
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window_attributes = WindowAttributes::default().with_title("IronRDP");
-        match event_loop.create_window(window_attributes) {
-            Ok(window) => {
-                let window = Arc::new(window);
-                let surface = softbuffer::Surface::new(&self.context, Arc::clone(&window)).expect("surface");
-                self.window = Some((window, surface));
+        let event = match key {
+            PhysicalKey::Code(KeyCode::F9) => {
+                if repeat {
+                    return true;
+                }
+
+                self.low_bandwidth = !self.low_bandwidth;
+                // The display updates are resumed by the reconnection.
+                self.output_suppressed = false;
+
+                info!(session = %self.title, low_bandwidth = self.low_bandwidth, "Switch experience");
+
+                if self.low_bandwidth {
+                    RdpInputEvent::SetExperience(Experience::low_bandwidth())
+                } else {
+                    RdpInputEvent::SetExperience(self.experience)
+                }
             }
-            Err(error) => {
-                error!(%error, "Failed to create window");
-                event_loop.exit();
+            PhysicalKey::Code(KeyCode::F10) => {
+                if repeat {
+                    return true;
+                }
+
+                self.output_suppressed = !self.output_suppressed;
+
+                info!(session = %self.title, suppressed = self.output_suppressed, "Toggle display updates");
+
+                RdpInputEvent::SuppressOutput(self.output_suppressed)
             }
+            _ => return false,
+        };
+
+        if self.input_event_sender.send(event).is_err() {
+            error!("Failed to send the hotkey event");
         }
+
+        true
     }
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, window_id: winit::window::WindowId, event: WindowEvent) {
+    /// Handles an event of the window, returning `false` if the window must be closed.
+    fn window_event(&mut self, event: WindowEvent) -> bool {
         let Some((window, _)) = self.window.as_mut() else {
-            return;
+            return true;
         };
-        if window_id != window.id() {
-            return;
-        }
 
         match event {
             WindowEvent::Resized(size) => {
                 self.last_size = Some(size);
                 self.resize_timeout = Some(Instant::now() + Duration::from_secs(1));
             }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                debug!(session = %self.title, scale_factor, "Scale factor changed");
+                // The display layout is updated with the new scale factor, even if the physical size is unchanged,
+                // in which case no `Resized` event follows.
+                self.last_size = Some(self.last_size.unwrap_or_else(|| window.inner_size()));
+                self.resize_timeout = Some(Instant::now() + Duration::from_secs(1));
+            }
             WindowEvent::CloseRequested => {
                 if self.input_event_sender.send(RdpInputEvent::Close).is_err() {
                     error!("Failed to send graceful shutdown event, closing the window");
-                    event_loop.exit();
+                    return false;
                 }
             }
             WindowEvent::DroppedFile(_) => {
@@ -159,6 +233,10 @@ impl ApplicationHandler<RdpOutputEvent> for App {
             // TODO(#376): Implement unicode input in native client
             // }
             WindowEvent::KeyboardInput { event, .. } => {
+                if event.state == event::ElementState::Pressed && self.hotkey(event.physical_key, event.repeat) {
+                    return true;
+                }
+
                 if let Some(scancode) = event.physical_key.to_scancode() {
                     let scancode = ironrdp::input::Scancode::from_u16(u16::try_from(scancode).unwrap());
 
@@ -173,6 +251,8 @@ impl ApplicationHandler<RdpOutputEvent> for App {
                 }
             }
             WindowEvent::ModifiersChanged(state) => {
+                self.modifiers = state.state();
+
                 const SHIFT_LEFT: ironrdp::input::Scancode = ironrdp::input::Scancode::from_u8(false, 0x2A);
                 const CONTROL_LEFT: ironrdp::input::Scancode = ironrdp::input::Scancode::from_u8(false, 0x1D);
                 const ALT_LEFT: ironrdp::input::Scancode = ironrdp::input::Scancode::from_u8(false, 0x38);
@@ -267,7 +347,7 @@ impl ApplicationHandler<RdpOutputEvent> for App {
                         if let Some(button) = ironrdp::input::MouseButton::from_native_button(native_button) {
                             button
                         } else {
-                            return;
+                            return true;
                         }
                     }
                 };
@@ -300,15 +380,17 @@ impl ApplicationHandler<RdpOutputEvent> for App {
             | WindowEvent::TouchpadPressure { .. }
             | WindowEvent::AxisMotion { .. }
             | WindowEvent::Touch(_)
-            | WindowEvent::ScaleFactorChanged { .. }
             | WindowEvent::ThemeChanged(_)
             | WindowEvent::Occluded(_) => {
                 // ignore
             }
         }
+
+        true
     }
 
-    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: RdpOutputEvent) {
+    /// Handles an output event of the session, except its termination.
+    fn output_event(&mut self, event: RdpOutputEvent) {
         let Some((window, surface)) = self.window.as_mut() else {
             return;
         };
@@ -327,38 +409,123 @@ impl ApplicationHandler<RdpOutputEvent> for App {
 
                 window.request_redraw();
             }
+            RdpOutputEvent::Connected(negotiation) => {
+                info!(
+                    session = %self.title,
+                    protocol = ?negotiation.selected_protocol,
+                    desktop_size = ?negotiation.desktop_size,
+                    channels = ?negotiation.channels,
+                    codecs = negotiation.server_bitmap_codecs().len(),
+                    "Connected"
+                );
+            }
+            RdpOutputEvent::PointerHidden => {
+                window.set_cursor_visible(false);
+            }
+            RdpOutputEvent::PointerDefault => {
+                window.set_cursor_visible(true);
+            }
+            RdpOutputEvent::PointerPosition { x, y } => {
+                if let Err(error) = window.set_cursor_position(LogicalPosition::new(x, y)) {
+                    error!(?error, "Failed to set cursor position");
+                }
+            }
+            RdpOutputEvent::ConnectionFailure(_) | RdpOutputEvent::Terminated(_) => {}
+        }
+    }
+}
+
+impl ApplicationHandler<RdpSessionEvent> for App {
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let now = Instant::now();
+        let mut next_timeout: Option<Duration> = None;
+
+        for session in self.sessions.values_mut() {
+            let Some(timeout) = session.resize_timeout else {
+                continue;
+            };
+
+            if let Some(timeout) = timeout.checked_duration_since(now) {
+                next_timeout = Some(next_timeout.map_or(timeout, |next| next.min(timeout)));
+            } else {
+                session.send_resize_event();
+                session.resize_timeout = None;
+            }
+        }
+
+        if let Some(timeout) = next_timeout {
+            event_loop.set_control_flow(ControlFlow::wait_duration(timeout));
+        } else {
+            event_loop.set_control_flow(ControlFlow::Wait);
+        }
+    }
+
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        for (id, session) in &mut self.sessions {
+            if session.window.is_some() {
+                continue;
+            }
+
+            let window_attributes = WindowAttributes::default().with_title(session.title.as_str());
+            match event_loop.create_window(window_attributes) {
+                Ok(window) => {
+                    let window = Arc::new(window);
+                    let surface = softbuffer::Surface::new(&self.context, Arc::clone(&window)).expect("surface");
+                    self.windows.insert(window.id(), *id);
+                    session.window = Some((window, surface));
+                }
+                Err(error) => {
+                    error!(%error, "Failed to create window");
+                    event_loop.exit();
+                    return;
+                }
+            }
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, window_id: WindowId, event: WindowEvent) {
+        let Some(&id) = self.windows.get(&window_id) else {
+            return;
+        };
+        let Some(session) = self.sessions.get_mut(&id) else {
+            return;
+        };
+
+        if !session.window_event(event) {
+            self.remove_session(event_loop, id);
+        }
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: RdpSessionEvent) {
+        let RdpSessionEvent { session: id, event } = event;
+
+        let Some(session) = self.sessions.get_mut(&id) else {
+            return;
+        };
+
+        match event {
             RdpOutputEvent::ConnectionFailure(error) => {
-                error!(?error);
-                eprintln!("Connection error: {}", error.report());
+                error!(session = %session.title, ?error);
+                eprintln!("{}: connection error: {}", session.title, error.report());
                 // TODO set proc_exit::sysexits::PROTOCOL_ERR.as_raw());
-                event_loop.exit();
+                self.remove_session(event_loop, id);
             }
             RdpOutputEvent::Terminated(result) => {
                 let _exit_code = match result {
                     Ok(reason) => {
-                        println!("Terminated gracefully: {reason}");
+                        println!("{}: terminated gracefully: {reason}", session.title);
                         proc_exit::sysexits::OK
                     }
                     Err(error) => {
-                        error!(?error);
-                        eprintln!("Active session error: {}", error.report());
+                        error!(session = %session.title, ?error);
+                        eprintln!("{}: active session error: {}", session.title, error.report());
                         proc_exit::sysexits::PROTOCOL_ERR
                     }
                 };
                 // TODO set exit_code.as_raw());
-                event_loop.exit();
-            }
-            RdpOutputEvent::PointerHidden => {
-                window.set_cursor_visible(false);
-            }
-            RdpOutputEvent::PointerDefault => {
-                window.set_cursor_visible(true);
-            }
-            RdpOutputEvent::PointerPosition { x, y } => {
-                if let Err(error) = window.set_cursor_position(LogicalPosition::new(x, y)) {
-                    error!(?error, "Failed to set cursor position");
-                }
+                self.remove_session(event_loop, id);
             }
+            event => session.output_event(event),
         }
     }
 }
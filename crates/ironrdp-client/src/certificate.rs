@@ -0,0 +1,203 @@
+#![allow(clippy::print_stderr)] // the certificate is described on the terminal before prompting the user
+
+use core::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use std::{fs, io};
+
+use ironrdp::connector::{self, ConnectorResult};
+use sha2::{Digest as _, Sha256};
+use x509_cert::der::Decode as _;
+
+/// Only one prompt at a time, the sessions sharing the terminal.
+static PROMPT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Verifies the certificates of the servers, pinned in a known hosts file once accepted by the user.
+///
+/// The certificates are not verified against a trust store: the user is prompted when the certificate of a server is
+/// unknown or changed since it was pinned, showing its fingerprint and subject.
+#[derive(Debug, Clone)]
+pub struct CertificateVerifier {
+    known_hosts: Option<PathBuf>,
+}
+
+impl CertificateVerifier {
+    /// Creates a verifier pinning the accepted certificates into the given known hosts file, if any.
+    pub fn new(known_hosts: Option<PathBuf>) -> Self {
+        Self { known_hosts }
+    }
+
+    /// Verifies the DER-encoded certificate of a server, prompting the user unless it is pinned for this host.
+    pub async fn verify(&self, host: &str, certificate: &[u8]) -> ConnectorResult<()> {
+        let fingerprint = fingerprint(certificate);
+
+        let pinned = match &self.known_hosts {
+            Some(path) => read_pin(path, host).map_err(|e| connector::custom_err!("read known hosts", e))?,
+            None => None,
+        };
+
+        let status = match pinned {
+            Some(pinned) if pinned.eq_ignore_ascii_case(&fingerprint) => return Ok(()),
+            Some(_) => CertificateStatus::Changed,
+            None => CertificateStatus::Unknown,
+        };
+
+        let details = CertificateDetails::parse(certificate, fingerprint)
+            .map_err(|e| connector::custom_err!("invalid server certificate", e))?;
+
+        let prompt_host = host.to_owned();
+        let prompt_details = details.clone();
+        let decision = tokio::task::spawn_blocking(move || prompt(&prompt_host, status, &prompt_details))
+            .await
+            .map_err(|e| connector::custom_err!("certificate prompt", e))?;
+
+        match decision {
+            Decision::AcceptOnce => Ok(()),
+            Decision::AcceptAndPin => {
+                if let Some(path) = &self.known_hosts {
+                    write_pin(path, host, &details.fingerprint)
+                        .map_err(|e| connector::custom_err!("write known hosts", e))?;
+                }
+
+                Ok(())
+            }
+            Decision::Abort => Err(connector::general_err!("server certificate rejected")),
+        }
+    }
+}
+
+/// Returns the SHA-256 fingerprint of a certificate, e.g. `AB:CD:…`.
+fn fingerprint(certificate: &[u8]) -> String {
+    Sha256::digest(certificate)
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CertificateStatus {
+    /// No certificate is pinned for this host.
+    Unknown,
+    /// Another certificate is pinned for this host.
+    Changed,
+}
+
+#[derive(Debug, Clone)]
+struct CertificateDetails {
+    subject: String,
+    issuer: String,
+    not_after: String,
+    expired: bool,
+    fingerprint: String,
+}
+
+impl CertificateDetails {
+    fn parse(certificate: &[u8], fingerprint: String) -> Result<Self, x509_cert::der::Error> {
+        let certificate = x509_cert::Certificate::from_der(certificate)?;
+        let tbs = &certificate.tbs_certificate;
+
+        Ok(Self {
+            subject: tbs.subject.to_string(),
+            issuer: tbs.issuer.to_string(),
+            not_after: tbs.validity.not_after.to_string(),
+            expired: tbs.validity.not_after.to_system_time() < SystemTime::now(),
+            fingerprint,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    AcceptOnce,
+    AcceptAndPin,
+    Abort,
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AcceptOnce => write!(f, "Accept once"),
+            Self::AcceptAndPin => write!(f, "Accept and remember"),
+            Self::Abort => write!(f, "Abort"),
+        }
+    }
+}
+
+fn prompt(host: &str, status: CertificateStatus, details: &CertificateDetails) -> Decision {
+    let _guard = PROMPT_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    match status {
+        CertificateStatus::Unknown => eprintln!("The certificate of {host} is not trusted yet."),
+        CertificateStatus::Changed => eprintln!(
+            "WARNING: the certificate of {host} changed since it was accepted, the connection may be intercepted."
+        ),
+    }
+
+    eprintln!("  Subject:     {}", details.subject);
+    eprintln!("  Issuer:      {}", details.issuer);
+    eprintln!(
+        "  Valid until: {}{}",
+        details.not_after,
+        if details.expired { " (expired)" } else { "" }
+    );
+    eprintln!("  SHA-256:     {}", details.fingerprint);
+
+    let options = vec![Decision::AcceptOnce, Decision::AcceptAndPin, Decision::Abort];
+
+    match inquire::Select::new("Trust this certificate?", options)
+        .with_starting_cursor(2)
+        .prompt()
+    {
+        Ok(decision) => decision,
+        Err(error) => {
+            warn!(%error, "Certificate prompt failed");
+            Decision::Abort
+        }
+    }
+}
+
+/// Returns the fingerprint pinned for a host in the known hosts file, made of `<host> <fingerprint>` lines.
+fn read_pin(path: &Path, host: &str) -> io::Result<Option<String>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let pin = content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once(' '))
+        .find(|(pinned_host, _)| *pinned_host == host)
+        .map(|(_, fingerprint)| fingerprint.trim().to_owned());
+
+    Ok(pin)
+}
+
+/// Pins the fingerprint of a host in the known hosts file, replacing the previous one.
+fn write_pin(path: &Path, host: &str, fingerprint: &str) -> io::Result<()> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    let mut lines: Vec<&str> = content
+        .lines()
+        .filter(|line| {
+            line.split_once(' ')
+                .map_or(true, |(pinned_host, _)| pinned_host != host)
+        })
+        .collect();
+
+    let pin = format!("{host} {fingerprint}");
+    lines.push(&pin);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, lines.join("\n") + "\n")
+}
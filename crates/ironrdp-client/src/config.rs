@@ -1,5 +1,6 @@
-use core::num::ParseIntError;
+use core::num::{NonZeroUsize, ParseIntError};
 use core::str::FromStr;
+use std::path::PathBuf;
 
 use anyhow::Context as _;
 use clap::clap_derive::ValueEnum;
@@ -7,6 +8,7 @@ use clap::Parser;
 use ironrdp::connector::{self, Credentials};
 use ironrdp::pdu::rdp::capability_sets::MajorPlatformType;
 use ironrdp::pdu::rdp::client_info::PerformanceFlags;
+use ironrdp_tls::ClientCertificate;
 use tap::prelude::*;
 
 const DEFAULT_WIDTH: u16 = 1920;
@@ -15,10 +17,26 @@ const DEFAULT_HEIGHT: u16 = 1080;
 #[derive(Clone, Debug)]
 pub struct Config {
     pub log_file: Option<String>,
+    /// File where the session events are written as JSON lines
+    pub event_log: Option<PathBuf>,
     pub destination: Destination,
+    /// Destinations of the other sessions, each one opened in its own window with the same settings
+    pub additional_destinations: Vec<Destination>,
     pub connector: connector::Config,
     pub clipboard_type: ClipboardType,
     pub rdcleanpath: Option<RDCleanPathConfig>,
+    /// WebSocket URL of a gateway forwarding the RDP stream to the destination, used instead of a TCP connection
+    pub websocket_url: Option<String>,
+    /// Directory where the print jobs of the redirected PDF printer are written
+    pub printer_output_dir: Option<PathBuf>,
+    /// File where the certificates of the servers accepted by the user are pinned
+    pub known_hosts: Option<PathBuf>,
+    /// Certificate presented to the servers requesting one during the TLS handshake
+    pub client_certificate: Option<ClientCertificate>,
+    /// Number of threads decoding the graphics updates, defaulting to one per core
+    pub decode_threads: Option<NonZeroUsize>,
+    /// Remote application launched over RAIL instead of the desktop
+    pub remote_app: Option<String>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -27,6 +45,8 @@ pub enum ClipboardType {
     Stub,
     #[cfg(windows)]
     Windows,
+    #[cfg(unix)]
+    Native,
     None,
 }
 
@@ -144,9 +164,20 @@ struct Args {
     #[clap(short, long, value_parser)]
     log_file: Option<String>,
 
+    /// A file where the session events (connection, negotiated security, resolution changes, channels and
+    /// disconnection) are appended as JSON lines, to be ingested by log management systems
+    #[clap(long, value_parser)]
+    event_log: Option<PathBuf>,
+
     /// An address on which the client will connect.
     destination: Option<Destination>,
 
+    /// The address of another server to connect to, in another window and with the same settings
+    ///
+    /// This option may be repeated to open several sessions at once.
+    #[clap(long = "session", value_name = "DESTINATION")]
+    additional_destinations: Vec<Destination>,
+
     /// A target RDP server user name
     #[clap(short, long, value_parser)]
     username: Option<String>,
@@ -167,6 +198,11 @@ struct Args {
     #[clap(long, requires("rdcleanpath_url"))]
     rdcleanpath_token: Option<String>,
 
+    /// WebSocket URL of a gateway forwarding the RDP stream to the destination (e.g. a Devolutions Gateway
+    /// forwarding endpoint), the connection being secured with TLS inside the WebSocket as over TCP
+    #[clap(long, conflicts_with("rdcleanpath_url"))]
+    ws_url: Option<String>,
+
     /// The keyboard type
     #[clap(long, value_enum, value_parser, default_value_t = KeyboardType::IbmEnhanced)]
     keyboard_type: KeyboardType,
@@ -229,9 +265,43 @@ struct Args {
     #[clap(long, alias = "no-nla")]
     no_credssp: bool,
 
+    /// A file where the certificates of the servers accepted by the user are pinned
+    ///
+    /// Defaults to `.ironrdp/known_hosts` in the home directory. The user is prompted when the certificate of a
+    /// server is not pinned yet or changed.
+    #[clap(long, value_parser)]
+    known_hosts: Option<PathBuf>,
+
+    /// A PEM file with the certificate chain presented to the servers requesting a client certificate
+    #[clap(long, value_parser, requires = "client_key")]
+    client_cert: Option<PathBuf>,
+
+    /// A PEM file with the PKCS#8 private key of the client certificate
+    #[clap(long, value_parser, requires = "client_cert")]
+    client_key: Option<PathBuf>,
+
+    /// Number of threads decoding the graphics updates
+    ///
+    /// Defaults to one per core, up to 8. With 1, the graphics updates are decoded on the session thread only.
+    #[clap(long)]
+    decode_threads: Option<NonZeroUsize>,
+
+    /// Run a remote application instead of the desktop, e.g. `||notepad` for a published application alias
+    ///
+    /// The input method state of the application is synchronized with the local one over the RAIL channel.
+    #[clap(long, value_parser)]
+    remote_app: Option<String>,
+
     /// The clipboard type
     #[clap(long, value_enum, value_parser, default_value_t = ClipboardType::Default)]
     clipboard_type: ClipboardType,
+
+    /// Redirect a printer saving print jobs as PDF files into the given directory
+    ///
+    /// The "Microsoft Print To PDF" driver must be installed on the server.
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    #[clap(long, value_parser)]
+    printer_output_dir: Option<PathBuf>,
 }
 
 impl Config {
@@ -280,7 +350,11 @@ impl Config {
             {
                 ClipboardType::Windows
             }
-            #[cfg(not(windows))]
+            #[cfg(unix)]
+            {
+                ClipboardType::Native
+            }
+            #[cfg(not(any(windows, unix)))]
             {
                 ClipboardType::None
             }
@@ -291,6 +365,7 @@ impl Config {
         let connector = connector::Config {
             credentials: Credentials::UsernamePassword { username, password },
             domain: args.domain,
+            remote_credential_guard: false,
             enable_tls: !args.no_tls,
             enable_credssp: !args.no_credssp,
             keyboard_type: KeyboardType::parse(args.keyboard_type),
@@ -304,6 +379,7 @@ impl Config {
                 height: DEFAULT_HEIGHT,
             },
             desktop_scale_factor: 0, // Default to 0 per FreeRDP
+            monitors: Vec::new(),
             bitmap,
             client_build: semver::Version::parse(env!("CARGO_PKG_VERSION"))
                 .map(|version| version.major * 100 + version.minor * 10 + version.patch)
@@ -327,11 +403,26 @@ impl Config {
             no_server_pointer: args.no_server_pointer,
             autologon: args.autologon,
             no_audio_playback: false,
+            message_channel: false,
+            multitransport_flags: None,
+            rail: args.remote_app.is_some(),
             request_data: None,
             pointer_software_rendering: true,
             performance_flags: PerformanceFlags::default(),
         };
 
+        let client_certificate = match args.client_cert.zip(args.client_key) {
+            Some((cert_path, key_path)) => {
+                let certificate_chain = std::fs::read(&cert_path)
+                    .with_context(|| format!("failed to read the client certificate `{}`", cert_path.display()))?;
+                let private_key = std::fs::read(&key_path)
+                    .with_context(|| format!("failed to read the client key `{}`", key_path.display()))?;
+
+                Some(ClientCertificate::from_pem(certificate_chain, private_key))
+            }
+            None => None,
+        };
+
         let rdcleanpath = args
             .rdcleanpath_url
             .zip(args.rdcleanpath_token)
@@ -339,10 +430,26 @@ impl Config {
 
         Ok(Self {
             log_file: args.log_file,
+            event_log: args.event_log,
             destination,
+            additional_destinations: args.additional_destinations,
             connector,
             clipboard_type,
             rdcleanpath,
+            websocket_url: args.ws_url,
+            #[cfg(any(target_os = "macos", target_os = "linux"))]
+            printer_output_dir: args.printer_output_dir,
+            #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+            printer_output_dir: None,
+            known_hosts: args.known_hosts.or_else(default_known_hosts),
+            client_certificate,
+            decode_threads: args.decode_threads,
+            remote_app: args.remote_app,
         })
     }
 }
+
+fn default_known_hosts() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".ironrdp").join("known_hosts"))
+}
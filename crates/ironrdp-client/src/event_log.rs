@@ -0,0 +1,208 @@
+//! Machine-readable log of the session events, e.g. to be ingested by a SIEM or an analytics system.
+//!
+//! Each event is written as a JSON object on its own line (JSON Lines), with the time of the event in milliseconds
+//! since the Unix epoch, the session it belongs to and its type, e.g.:
+//!
+//! ```text
+//! {"timestamp":1700000000000,"session":0,"event":"connected","security_protocol":"HYBRID_EX","width":1024,"height":768,"elapsed_ms":412}
+//! ```
+
+use core::fmt::{self, Write as _};
+use core::time::Duration;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ironrdp::connector::NegotiationInfo;
+use ironrdp::pdu::nego::SecurityProtocol;
+
+use crate::rdp::SessionId;
+
+/// Event of a session, written to the [`EventLog`].
+#[derive(Debug)]
+pub enum SessionLogEvent<'a> {
+    /// The connection to the server is started.
+    Connecting { destination: &'a str },
+    /// The connection is established, `elapsed` after being started.
+    Connected {
+        negotiation: &'a NegotiationInfo,
+        elapsed: Duration,
+    },
+    /// The connection failed, `elapsed` after being started.
+    ConnectionFailed { error: &'a str, elapsed: Duration },
+    /// A static virtual channel is joined.
+    ChannelOpened { name: &'a str, channel_id: u16 },
+    /// The size of the remote desktop changed during the session.
+    ResolutionChanged { width: u16, height: u16 },
+    /// The session is closed to be opened again, e.g. with a new desktop size.
+    Reconnecting { reason: &'a str },
+    /// The session ended, `duration` after being established.
+    Disconnected {
+        reason: &'a str,
+        error: bool,
+        duration: Duration,
+    },
+}
+
+/// Log of the events of the sessions, shared by all the sessions of the client.
+#[derive(Clone)]
+pub struct EventLog {
+    output: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl EventLog {
+    pub fn new(output: impl Write + Send + 'static) -> Self {
+        Self {
+            output: Arc::new(Mutex::new(Box::new(output))),
+        }
+    }
+
+    /// Opens the file at `path`, the events being appended to the previous ones.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self::new(file))
+    }
+
+    /// Writes an event of `session`, timestamped with the current time.
+    pub fn log(&self, session: SessionId, event: &SessionLogEvent<'_>) {
+        let line = format_event(SystemTime::now(), session, event);
+
+        let mut output = self.output.lock().expect("poisoned");
+        if let Err(error) = output.write_all(line.as_bytes()).and_then(|()| output.flush()) {
+            warn!(%error, "Failed to write the event log");
+        }
+    }
+}
+
+fn format_event(time: SystemTime, session: SessionId, event: &SessionLogEvent<'_>) -> String {
+    let timestamp = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+
+    let mut object = JsonObject::new();
+    object.number("timestamp", timestamp);
+    object.number("session", session.0);
+
+    match event {
+        SessionLogEvent::Connecting { destination } => {
+            object.string("event", "connecting");
+            object.string("destination", destination);
+        }
+        SessionLogEvent::Connected { negotiation, elapsed } => {
+            object.string("event", "connected");
+            object.string(
+                "security_protocol",
+                &security_protocol_name(negotiation.selected_protocol),
+            );
+            object.number("width", negotiation.desktop_size.width);
+            object.number("height", negotiation.desktop_size.height);
+            object.number("elapsed_ms", elapsed.as_millis());
+        }
+        SessionLogEvent::ConnectionFailed { error, elapsed } => {
+            object.string("event", "connection_failed");
+            object.string("error", error);
+            object.number("elapsed_ms", elapsed.as_millis());
+        }
+        SessionLogEvent::ChannelOpened { name, channel_id } => {
+            object.string("event", "channel_opened");
+            object.string("channel", name);
+            object.number("channel_id", channel_id);
+        }
+        SessionLogEvent::ResolutionChanged { width, height } => {
+            object.string("event", "resolution_changed");
+            object.number("width", width);
+            object.number("height", height);
+        }
+        SessionLogEvent::Reconnecting { reason } => {
+            object.string("event", "reconnecting");
+            object.string("reason", reason);
+        }
+        SessionLogEvent::Disconnected {
+            reason,
+            error,
+            duration,
+        } => {
+            object.string("event", "disconnected");
+            object.string("reason", reason);
+            object.boolean("error", *error);
+            object.number("duration_ms", duration.as_millis());
+        }
+    }
+
+    object.finish()
+}
+
+/// Returns the names of the protocol flags, e.g. `HYBRID_EX`, or `RDP` for the standard RDP security.
+fn security_protocol_name(protocol: SecurityProtocol) -> String {
+    if protocol.is_empty() {
+        return "RDP".to_owned();
+    }
+
+    protocol
+        .iter_names()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Writes the fields of a JSON object, on a single line.
+struct JsonObject {
+    output: String,
+}
+
+impl JsonObject {
+    fn new() -> Self {
+        Self {
+            output: String::from("{"),
+        }
+    }
+
+    fn key(&mut self, key: &str) {
+        if self.output.len() > 1 {
+            self.output.push(',');
+        }
+        write_string(&mut self.output, key);
+        self.output.push(':');
+    }
+
+    fn string(&mut self, key: &str, value: &str) {
+        self.key(key);
+        write_string(&mut self.output, value);
+    }
+
+    fn number(&mut self, key: &str, value: impl fmt::Display) {
+        self.key(key);
+        let _ = write!(self.output, "{value}");
+    }
+
+    fn boolean(&mut self, key: &str, value: bool) {
+        self.key(key);
+        self.output.push_str(if value { "true" } else { "false" });
+    }
+
+    fn finish(mut self) -> String {
+        self.output.push_str("}\n");
+        self.output
+    }
+}
+
+fn write_string(output: &mut String, value: &str) {
+    output.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(output, "\\u{:04x}", u32::from(c));
+            }
+            c => output.push(c),
+        }
+    }
+
+    output.push('"');
+}
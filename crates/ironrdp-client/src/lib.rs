@@ -13,8 +13,11 @@
 extern crate tracing;
 
 pub mod app;
+pub mod certificate;
 pub mod clipboard;
 pub mod config;
+pub mod event_log;
 pub mod rdp;
+pub mod runtime;
 
 mod ws;
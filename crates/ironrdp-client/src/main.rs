@@ -3,10 +3,14 @@
 #[macro_use]
 extern crate tracing;
 
+use std::sync::Arc;
+
 use anyhow::Context as _;
 use ironrdp_client::app::App;
 use ironrdp_client::config::{ClipboardType, Config};
-use ironrdp_client::rdp::{RdpClient, RdpInputEvent, RdpOutputEvent};
+use ironrdp_client::event_log::EventLog;
+use ironrdp_client::rdp::{Experience, RdpClient, RdpInputEvent, RdpOutputSender, RdpSessionEvent, SessionId};
+use ironrdp_client::runtime::TokioRuntime;
 use tokio::runtime;
 use winit::event_loop::EventLoop;
 
@@ -16,10 +20,9 @@ fn main() -> anyhow::Result<()> {
     setup_logging(config.log_file.as_deref()).context("unable to initialize logging")?;
 
     debug!("Initialize App");
-    let event_loop = EventLoop::<RdpOutputEvent>::with_user_event().build()?;
-    let event_loop_proxy = event_loop.create_proxy();
+    let event_loop = EventLoop::<RdpSessionEvent>::with_user_event().build()?;
     let (input_event_sender, input_event_receiver) = RdpInputEvent::create_channel();
-    let mut app = App::new(&event_loop, &input_event_sender).context("unable to initialize App")?;
+    let mut app = App::new(&event_loop).context("unable to initialize App")?;
 
     // TODO: get window size & scale factor from GUI/App
     let window_size = (1024, 768);
@@ -30,6 +33,7 @@ fn main() -> anyhow::Result<()> {
     let rt = runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
+        .map(Arc::new)
         .context("unable to create tokio runtime")?;
 
     // NOTE: we need to keep `win_clipboard` alive, otherwise it will be dropped before IronRDP
@@ -37,6 +41,10 @@ fn main() -> anyhow::Result<()> {
     #[cfg(windows)]
     let _win_clipboard;
 
+    // NOTE: same applies to `native_clipboard`, as the clipboard worker thread is stopped on drop.
+    #[cfg(unix)]
+    let _native_clipboard;
+
     let cliprdr_factory = match config.clipboard_type {
         ClipboardType::Stub => {
             use ironrdp_cliprdr_native::StubClipboard;
@@ -50,26 +58,72 @@ fn main() -> anyhow::Result<()> {
             use ironrdp_client::clipboard::ClientClipboardMessageProxy;
             use ironrdp_cliprdr_native::WinClipboard;
 
-            let cliprdr = WinClipboard::new(ClientClipboardMessageProxy::new(input_event_sender))?;
+            let cliprdr = WinClipboard::new(ClientClipboardMessageProxy::new(input_event_sender.clone()))?;
 
             let factory = cliprdr.backend_factory();
             _win_clipboard = cliprdr;
             Some(factory)
         }
-        _ => None,
-    };
+        #[cfg(unix)]
+        ClipboardType::Native => {
+            use ironrdp_client::clipboard::ClientClipboardMessageProxy;
+            use ironrdp_cliprdr_native::ArboardClipboard;
 
-    let client = RdpClient {
-        config,
-        event_loop_proxy,
-        input_event_receiver,
-        cliprdr_factory,
+            let cliprdr = ArboardClipboard::new(ClientClipboardMessageProxy::new(input_event_sender.clone()))?;
+
+            let factory = cliprdr.backend_factory();
+            _native_clipboard = cliprdr;
+            Some(factory)
+        }
+        _ => None,
     };
 
-    debug!("Start RDP thread");
-    std::thread::spawn(move || {
-        rt.block_on(client.run());
-    });
+    let event_log = config
+        .event_log
+        .as_deref()
+        .map(EventLog::open)
+        .transpose()
+        .context("unable to open the event log")?;
+
+    let additional_destinations = core::mem::take(&mut config.additional_destinations);
+    let mut first_channel = Some((input_event_sender, input_event_receiver));
+    let mut cliprdr_factory = cliprdr_factory;
+
+    // Each session runs in its own thread, with its own window and channels.
+    for (idx, destination) in core::iter::once(config.destination.clone())
+        .chain(additional_destinations)
+        .enumerate()
+    {
+        let session = SessionId(u32::try_from(idx).context("too many sessions")?);
+        let (input_event_sender, input_event_receiver) =
+            first_channel.take().unwrap_or_else(RdpInputEvent::create_channel);
+
+        app.add_session(
+            session,
+            format!("IronRDP - {}", destination.name()),
+            &input_event_sender,
+            Experience::from_config(&config.connector),
+        );
+
+        let client = RdpClient {
+            config: Config {
+                destination,
+                ..config.clone()
+            },
+            runtime: Arc::new(TokioRuntime),
+            output_sender: RdpOutputSender::new(session, event_loop.create_proxy()),
+            input_event_receiver,
+            // The clipboard of the system is shared with the first session only.
+            cliprdr_factory: cliprdr_factory.take(),
+            event_log: event_log.clone(),
+        };
+
+        debug!(?session, "Start RDP thread");
+        let rt = Arc::clone(&rt);
+        std::thread::spawn(move || {
+            rt.block_on(client.run());
+        });
+    }
 
     debug!("Run App");
     event_loop.run_app(&mut app)?;
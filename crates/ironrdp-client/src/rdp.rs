@@ -1,29 +1,47 @@
+use core::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Instant;
+
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt as _;
 use ironrdp::cliprdr::backend::{ClipboardMessage, CliprdrBackendFactory};
 use ironrdp::connector::connection_activation::ConnectionActivationState;
+use ironrdp::connector::credssp::ChannelBindings;
 use ironrdp::connector::{ConnectionResult, ConnectorResult};
 use ironrdp::displaycontrol::client::DisplayControlClient;
 use ironrdp::displaycontrol::pdu::MonitorLayoutEntry;
 use ironrdp::graphics::image_processing::PixelFormat;
+use ironrdp::pdu::geometry::InclusiveRectangle;
 use ironrdp::pdu::input::fast_path::FastPathInputEvent;
+use ironrdp::pdu::rdp::client_info::PerformanceFlags;
+use ironrdp::pdu::rdp::headers::ShareDataPdu;
+use ironrdp::pdu::rdp::suppress_output::SuppressOutputPdu;
+use ironrdp::session::heartbeat::Liveness;
 use ironrdp::session::image::DecodedImage;
-use ironrdp::session::{fast_path, ActiveStage, ActiveStageOutput, GracefulDisconnectReason, SessionResult};
-use ironrdp::{cliprdr, connector, rdpdr, rdpsnd, session};
+use ironrdp::session::{
+    fast_path, ActiveStage, ActiveStageOutput, GracefulDisconnectReason, SessionEvent, SessionResult,
+};
+use ironrdp::{cliprdr, connector, rail, rdpdr, rdpsnd, session};
 use ironrdp_core::WriteBuf;
 use ironrdp_rdpsnd_native::cpal;
+use ironrdp_tls::{DefaultTlsConnector, TlsConnector as _};
 use ironrdp_tokio::reqwest::ReqwestNetworkClient;
 use ironrdp_tokio::{single_sequence_step_read, split_tokio_framed, FramedWrite};
 use rdpdr::NoopRdpdrBackend;
 use smallvec::SmallVec;
 use tokio::io::{AsyncRead, AsyncWrite};
-use tokio::net::TcpStream;
 use tokio::sync::mpsc;
-use winit::event_loop::EventLoopProxy;
+use winit::event_loop::{EventLoopClosed, EventLoopProxy};
 
+use crate::certificate::CertificateVerifier;
 use crate::config::{Config, RDCleanPathConfig};
+use crate::event_log::{EventLog, SessionLogEvent};
+use crate::runtime::Runtime;
 
 #[derive(Debug)]
 pub enum RdpOutputEvent {
     Image { buffer: Vec<u32>, width: u16, height: u16 },
+    Connected(Box<connector::NegotiationInfo>),
     ConnectionFailure(connector::ConnectorError),
     PointerDefault,
     PointerHidden,
@@ -31,6 +49,44 @@ pub enum RdpOutputEvent {
     Terminated(SessionResult<GracefulDisconnectReason>),
 }
 
+/// Identifies one of the sessions hosted by the client, each one in its own window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SessionId(pub u32);
+
+/// Output event of one of the sessions, sent to the GUI event loop.
+#[derive(Debug)]
+pub struct RdpSessionEvent {
+    pub session: SessionId,
+    pub event: RdpOutputEvent,
+}
+
+/// Sends the output events of a session to the GUI event loop.
+#[derive(Debug, Clone)]
+pub struct RdpOutputSender {
+    session: SessionId,
+    event_loop_proxy: EventLoopProxy<RdpSessionEvent>,
+}
+
+impl RdpOutputSender {
+    pub fn new(session: SessionId, event_loop_proxy: EventLoopProxy<RdpSessionEvent>) -> Self {
+        Self {
+            session,
+            event_loop_proxy,
+        }
+    }
+
+    pub fn session(&self) -> SessionId {
+        self.session
+    }
+
+    pub fn send(&self, event: RdpOutputEvent) -> Result<(), EventLoopClosed<RdpSessionEvent>> {
+        self.event_loop_proxy.send_event(RdpSessionEvent {
+            session: self.session,
+            event,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub enum RdpInputEvent {
     Resize {
@@ -43,6 +99,10 @@ pub enum RdpInputEvent {
     FastPath(SmallVec<[FastPathInputEvent; 2]>),
     Close,
     Clipboard(ClipboardMessage),
+    /// Switches the experience settings, reconnecting because they are negotiated at connection time.
+    SetExperience(Experience),
+    /// Pauses or resumes the display updates of the server, e.g. while the network is congested.
+    SuppressOutput(bool),
 }
 
 impl RdpInputEvent {
@@ -51,61 +111,157 @@ impl RdpInputEvent {
     }
 }
 
+/// Experience settings of a session, traded against the bandwidth.
+#[derive(Debug, Clone, Copy)]
+pub struct Experience {
+    /// The visual effects of the remote desktop, e.g. the wallpaper or the font smoothing.
+    pub performance_flags: PerformanceFlags,
+    /// The color depth and compression of the bitmaps, `None` for the defaults.
+    pub bitmap: Option<connector::BitmapConfig>,
+}
+
+impl Experience {
+    /// Returns the experience settings of a connector configuration.
+    pub fn from_config(config: &connector::Config) -> Self {
+        Self {
+            performance_flags: config.performance_flags,
+            bitmap: config.bitmap,
+        }
+    }
+
+    /// Returns the experience suited to degraded networks: no visual effects, and lossy 16-bit color bitmaps.
+    pub fn low_bandwidth() -> Self {
+        Self {
+            performance_flags: PerformanceFlags::DISABLE_WALLPAPER
+                | PerformanceFlags::DISABLE_FULLWINDOWDRAG
+                | PerformanceFlags::DISABLE_MENUANIMATIONS
+                | PerformanceFlags::DISABLE_THEMING
+                | PerformanceFlags::DISABLE_CURSOR_SHADOW
+                | PerformanceFlags::DISABLE_CURSORSETTINGS,
+            bitmap: Some(connector::BitmapConfig {
+                lossy_compression: true,
+                color_depth: 16,
+            }),
+        }
+    }
+}
+
 pub struct RdpClient {
     pub config: Config,
-    pub event_loop_proxy: EventLoopProxy<RdpOutputEvent>,
+    /// Runtime the session is running on, e.g. [`TokioRuntime`](crate::runtime::TokioRuntime).
+    pub runtime: Arc<dyn Runtime>,
+    pub output_sender: RdpOutputSender,
     pub input_event_receiver: mpsc::UnboundedReceiver<RdpInputEvent>,
     pub cliprdr_factory: Option<Box<dyn CliprdrBackendFactory + Send>>,
+    /// Log where the events of the session are written, if any.
+    pub event_log: Option<EventLog>,
 }
 
 impl RdpClient {
     pub async fn run(mut self) {
         loop {
-            let (connection_result, framed) = if let Some(rdcleanpath) = self.config.rdcleanpath.as_ref() {
-                match connect_ws(&self.config, rdcleanpath, self.cliprdr_factory.as_deref()).await {
-                    Ok(result) => result,
-                    Err(e) => {
-                        let _ = self.event_loop_proxy.send_event(RdpOutputEvent::ConnectionFailure(e));
-                        break;
-                    }
-                }
+            let destination = format!("{}:{}", self.config.destination.name(), self.config.destination.port());
+            self.log_event(&SessionLogEvent::Connecting {
+                destination: &destination,
+            });
+            let connecting_since = Instant::now();
+
+            let result = if let Some(rdcleanpath) = self.config.rdcleanpath.as_ref() {
+                connect_ws(&self.config, rdcleanpath, self.cliprdr_factory.as_deref()).await
             } else {
-                match connect(&self.config, self.cliprdr_factory.as_deref()).await {
-                    Ok(result) => result,
-                    Err(e) => {
-                        let _ = self.event_loop_proxy.send_event(RdpOutputEvent::ConnectionFailure(e));
-                        break;
-                    }
+                connect(&self.config, self.runtime.as_ref(), self.cliprdr_factory.as_deref()).await
+            };
+
+            let (connection_result, framed) = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    self.log_event(&SessionLogEvent::ConnectionFailed {
+                        error: &e.report().to_string(),
+                        elapsed: connecting_since.elapsed(),
+                    });
+                    let _ = self.output_sender.send(RdpOutputEvent::ConnectionFailure(e));
+                    break;
                 }
             };
 
+            self.log_event(&SessionLogEvent::Connected {
+                negotiation: &connection_result.negotiation,
+                elapsed: connecting_since.elapsed(),
+            });
+            for (name, channel_id) in &connection_result.negotiation.channels {
+                self.log_event(&SessionLogEvent::ChannelOpened {
+                    name: name.as_str().unwrap_or_default(),
+                    channel_id: *channel_id,
+                });
+            }
+            let connected_since = Instant::now();
+
+            let _ = self.output_sender.send(RdpOutputEvent::Connected(Box::new(
+                connection_result.negotiation.clone(),
+            )));
+
             match active_session(
                 framed,
                 connection_result,
-                &self.event_loop_proxy,
+                self.config.decode_threads,
+                self.runtime.as_ref(),
+                &self.output_sender,
                 &mut self.input_event_receiver,
+                self.event_log.as_ref(),
             )
             .await
             {
-                Ok(RdpControlFlow::ReconnectWithNewSize { width, height }) => {
+                Ok(RdpControlFlow::ReconnectWithNewSize {
+                    width,
+                    height,
+                    scale_factor,
+                }) => {
+                    self.log_event(&SessionLogEvent::Reconnecting {
+                        reason: "new desktop size",
+                    });
                     self.config.connector.desktop_size.width = width;
                     self.config.connector.desktop_size.height = height;
+                    self.config.connector.desktop_scale_factor = scale_factor;
+                }
+                Ok(RdpControlFlow::ReconnectWithNewExperience(experience)) => {
+                    self.log_event(&SessionLogEvent::Reconnecting {
+                        reason: "new experience settings",
+                    });
+                    self.config.connector.performance_flags = experience.performance_flags;
+                    self.config.connector.bitmap = experience.bitmap;
                 }
                 Ok(RdpControlFlow::TerminatedGracefully(reason)) => {
-                    let _ = self.event_loop_proxy.send_event(RdpOutputEvent::Terminated(Ok(reason)));
+                    self.log_event(&SessionLogEvent::Disconnected {
+                        reason: &reason.description(),
+                        error: false,
+                        duration: connected_since.elapsed(),
+                    });
+                    let _ = self.output_sender.send(RdpOutputEvent::Terminated(Ok(reason)));
                     break;
                 }
                 Err(e) => {
-                    let _ = self.event_loop_proxy.send_event(RdpOutputEvent::Terminated(Err(e)));
+                    self.log_event(&SessionLogEvent::Disconnected {
+                        reason: &e.report().to_string(),
+                        error: true,
+                        duration: connected_since.elapsed(),
+                    });
+                    let _ = self.output_sender.send(RdpOutputEvent::Terminated(Err(e)));
                     break;
                 }
             }
         }
     }
+
+    fn log_event(&self, event: &SessionLogEvent<'_>) {
+        if let Some(event_log) = &self.event_log {
+            event_log.log(self.output_sender.session(), event);
+        }
+    }
 }
 
 enum RdpControlFlow {
-    ReconnectWithNewSize { width: u16, height: u16 },
+    ReconnectWithNewSize { width: u16, height: u16, scale_factor: u32 },
+    ReconnectWithNewExperience(Experience),
     TerminatedGracefully(GracefulDisconnectReason),
 }
 
@@ -117,32 +273,56 @@ type UpgradedFramed = ironrdp_tokio::TokioFramed<Box<dyn AsyncReadWrite + Unpin
 
 async fn connect(
     config: &Config,
+    runtime: &dyn Runtime,
     cliprdr_factory: Option<&(dyn CliprdrBackendFactory + Send)>,
 ) -> ConnectorResult<(ConnectionResult, UpgradedFramed)> {
-    let dest = format!("{}:{}", config.destination.name(), config.destination.port());
+    let (stream, server_addr) = if let Some(url) = config.websocket_url.as_deref() {
+        // The gateway forwards the stream to the destination, which is otherwise used as over TCP.
+        let stream = ironrdp_tokio::websocket::connect(url)
+            .await
+            .map_err(|e| connector::custom_err!("WS connect", e))?;
 
-    let stream = TcpStream::connect(dest)
-        .await
-        .map_err(|e| connector::custom_err!("TCP connect", e))?;
+        (Box::new(stream) as Box<dyn AsyncReadWrite + Unpin + Send + Sync>, None)
+    } else {
+        let dest = format!("{}:{}", config.destination.name(), config.destination.port());
+
+        let (stream, server_addr) = runtime
+            .connect(&dest)
+            .await
+            .map_err(|e| connector::custom_err!("TCP connect", e))?;
 
-    let server_addr = stream
-        .peer_addr()
-        .map_err(|e| connector::custom_err!("Peer address", e))?;
+        (
+            Box::new(stream) as Box<dyn AsyncReadWrite + Unpin + Send + Sync>,
+            Some(server_addr),
+        )
+    };
 
     let mut framed = ironrdp_tokio::TokioFramed::new(stream);
 
-    let mut connector = connector::ClientConnector::new(config.connector.clone())
-        .with_client_addr(server_addr)
+    let mut connector = connector::ClientConnector::new(config.connector.clone());
+
+    if let Some(server_addr) = server_addr {
+        connector = connector.with_client_addr(server_addr);
+    }
+
+    let mut connector = connector
         .with_static_channel(
             ironrdp::dvc::DrdynvcClient::new().with_dynamic_channel(DisplayControlClient::new(|_| Ok(Vec::new()))),
         )
         .with_static_channel(rdpsnd::client::Rdpsnd::new(Box::new(cpal::RdpsndBackend::new())))
-        .with_static_channel(rdpdr::Rdpdr::new(Box::new(NoopRdpdrBackend {}), "IronRDP".to_owned()).with_smartcard(0));
+        .with_static_channel(rdpdr_channel(config)?);
+
+    if let Some(remote_app) = &config.remote_app {
+        connector.attach_static_channel(rail_channel(remote_app));
+    }
 
     if let Some(builder) = cliprdr_factory {
         let backend = builder.build_cliprdr_backend();
 
-        let cliprdr = cliprdr::Cliprdr::new(backend);
+        let mut cliprdr = cliprdr::Cliprdr::new(backend);
+        if let Some(policy) = builder.build_cliprdr_policy() {
+            cliprdr = cliprdr.with_policy(policy);
+        }
 
         connector.attach_static_channel(cliprdr);
     }
@@ -154,13 +334,31 @@ async fn connect(
     // Ensure there is no leftover
     let (initial_stream, leftover_bytes) = framed.into_inner();
 
-    let (upgraded_stream, server_public_key) = ironrdp_tls::upgrade(initial_stream, config.destination.name())
+    let mut tls_connector = DefaultTlsConnector::new();
+    if let Some(certificate) = &config.client_certificate {
+        tls_connector = tls_connector.with_client_certificate(certificate.clone());
+    }
+
+    let tls_connection = tls_connector
+        .connect(Box::new(initial_stream), config.destination.name())
         .await
         .map_err(|e| connector::custom_err!("TLS upgrade", e))?;
 
+    CertificateVerifier::new(config.known_hosts.clone())
+        .verify(
+            &format!("{}:{}", config.destination.name(), config.destination.port()),
+            &tls_connection.server_certificate,
+        )
+        .await?;
+
+    connector.attach_channel_bindings(ChannelBindings::tls_server_end_point(
+        &tls_connection.server_certificate,
+    )?);
+    connector.attach_server_certificates(vec![tls_connection.server_certificate.clone()]);
+
     let upgraded = ironrdp_tokio::mark_as_upgraded(should_upgrade, &mut connector);
 
-    let erased_stream = Box::new(upgraded_stream) as Box<dyn AsyncReadWrite + Unpin + Send + Sync>;
+    let erased_stream = Box::new(tls_connection.stream) as Box<dyn AsyncReadWrite + Unpin + Send + Sync>;
     let mut upgraded_framed = ironrdp_tokio::TokioFramed::new_with_leftover(erased_stream, leftover_bytes);
 
     let connection_result = ironrdp_tokio::connect_finalize(
@@ -168,7 +366,7 @@ async fn connect(
         &mut upgraded_framed,
         connector,
         (&config.destination).into(),
-        server_public_key,
+        tls_connection.server_public_key,
         Some(&mut ReqwestNetworkClient::new()),
         None,
     )
@@ -197,12 +395,19 @@ async fn connect_ws(
             ironrdp::dvc::DrdynvcClient::new().with_dynamic_channel(DisplayControlClient::new(|_| Ok(Vec::new()))),
         )
         .with_static_channel(rdpsnd::client::Rdpsnd::new(Box::new(cpal::RdpsndBackend::new())))
-        .with_static_channel(rdpdr::Rdpdr::new(Box::new(NoopRdpdrBackend {}), "IronRDP".to_owned()).with_smartcard(0));
+        .with_static_channel(rdpdr_channel(config)?);
+
+    if let Some(remote_app) = &config.remote_app {
+        connector.attach_static_channel(rail_channel(remote_app));
+    }
 
     if let Some(builder) = cliprdr_factory {
         let backend = builder.build_cliprdr_backend();
 
-        let cliprdr = cliprdr::Cliprdr::new(backend);
+        let mut cliprdr = cliprdr::Cliprdr::new(backend);
+        if let Some(policy) = builder.build_cliprdr_policy() {
+            cliprdr = cliprdr.with_policy(policy);
+        }
 
         connector.attach_static_channel(cliprdr);
     }
@@ -215,6 +420,7 @@ async fn connect_ws(
         destination,
         rdcleanpath.auth_token.clone(),
         None,
+        &CertificateVerifier::new(config.known_hosts.clone()),
     )
     .await?;
 
@@ -242,6 +448,7 @@ async fn connect_rdcleanpath<S>(
     destination: String,
     proxy_auth_token: String,
     pcb: Option<String>,
+    certificate_verifier: &CertificateVerifier,
 ) -> ConnectorResult<(ironrdp_tokio::Upgraded, Vec<u8>)>
 where
     S: ironrdp_tokio::FramedRead + FramedWrite,
@@ -286,7 +493,7 @@ where
         let x224_pdu = buf.filled().to_vec();
 
         let rdcleanpath_req =
-            ironrdp_rdcleanpath::RDCleanPathPdu::new_request(x224_pdu, destination, proxy_auth_token, pcb)
+            ironrdp_rdcleanpath::RDCleanPathPdu::new_request(x224_pdu, destination.clone(), proxy_auth_token, pcb)
                 .map_err(|e| connector::custom_err!("new RDCleanPath request", e))?;
         debug!(message = ?rdcleanpath_req, "Send RDCleanPath request");
         let rdcleanpath_req = rdcleanpath_req
@@ -348,11 +555,17 @@ where
 
         debug_assert!(written.is_nothing());
 
+        connector.attach_server_certificates(server_cert_chain.iter().map(|cert| cert.as_bytes().to_vec()).collect());
+
         let server_cert = server_cert_chain
             .into_iter()
             .next()
             .ok_or_else(|| connector::general_err!("server cert chain missing from rdcleanpath response"))?;
 
+        certificate_verifier
+            .verify(&destination, server_cert.as_bytes())
+            .await?;
+
         let cert = x509_cert::Certificate::from_der(server_cert.as_bytes())
             .map_err(|e| connector::custom_err!("server cert chain missing from rdcleanpath response", e))?;
 
@@ -364,6 +577,8 @@ where
             .ok_or_else(|| connector::general_err!("subject public key BIT STRING is not aligned"))?
             .to_owned();
 
+        connector.attach_channel_bindings(ChannelBindings::tls_server_end_point(server_cert.as_bytes())?);
+
         let should_upgrade = ironrdp_tokio::skip_connect_begin(connector);
 
         // At this point, proxy established the TLS session.
@@ -374,11 +589,42 @@ where
     }
 }
 
+fn rail_channel(remote_app: &str) -> rail::client::RailClient {
+    rail::client::RailClient::new(Box::new(rail::client::NoopRailClientHandler)).with_exec(rail::pdu::ExecPdu {
+        flags: rail::pdu::ExecFlags::empty(),
+        exe_or_file: remote_app.to_owned(),
+        working_dir: String::new(),
+        arguments: String::new(),
+    })
+}
+
+fn rdpdr_channel(config: &Config) -> ConnectorResult<rdpdr::Rdpdr> {
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    if let Some(output_dir) = &config.printer_output_dir {
+        use ironrdp_rdpdr_native::printer::PdfPrinter;
+
+        let printer = PdfPrinter::new(output_dir);
+
+        return rdpdr::Rdpdr::new(Box::new(printer), "IronRDP".to_owned())
+            .with_smartcard(0)
+            .with_printer(1, PdfPrinter::device_data("IronRDP PDF".to_owned()))
+            .map_err(|e| connector::custom_err!("rdpdr printer", e));
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    let _ = config;
+
+    Ok(rdpdr::Rdpdr::new(Box::new(NoopRdpdrBackend {}), "IronRDP".to_owned()).with_smartcard(0))
+}
+
 async fn active_session(
     framed: UpgradedFramed,
     connection_result: ConnectionResult,
-    event_loop_proxy: &EventLoopProxy<RdpOutputEvent>,
+    decode_threads: Option<NonZeroUsize>,
+    runtime: &dyn Runtime,
+    output_sender: &RdpOutputSender,
     input_event_receiver: &mut mpsc::UnboundedReceiver<RdpInputEvent>,
+    event_log: Option<&EventLog>,
 ) -> SessionResult<RdpControlFlow> {
     let (mut reader, mut writer) = split_tokio_framed(framed);
     let mut image = DecodedImage::new(
@@ -388,21 +634,46 @@ async fn active_session(
     );
 
     let mut active_stage = ActiveStage::new(connection_result);
+    if let Some(threads) = decode_threads {
+        active_stage.set_decode_threads(threads);
+    }
+
+    // Started once the server sent its first heartbeat.
+    let mut heartbeat_interval: Option<BoxStream<'static, ()>> = None;
 
     let disconnect_reason = 'outer: loop {
         let outputs = tokio::select! {
             frame = reader.read_pdu() => {
                 let (action, payload) = frame.map_err(|e| session::custom_err!("read frame", e))?;
-                trace!(?action, frame_length = payload.len(), "Frame received");
 
-                active_stage.process(&mut image, action, &payload)?
+                let outputs = active_stage.process(&mut image, action, &payload)?;
+
+                if heartbeat_interval.is_none() {
+                    if let Some(period) = active_stage.heartbeat_period() {
+                        debug!(?period, "Monitoring the heartbeats");
+                        heartbeat_interval = Some(runtime.interval(period));
+                    }
+                }
+
+                outputs
+            }
+            _ = async { heartbeat_interval.as_mut().expect("checked by the precondition").next().await }, if heartbeat_interval.is_some() => {
+                match active_stage.check_heartbeat() {
+                    Liveness::Lost { missed } => {
+                        return Err(session::reason_err!("heartbeat", "connection lost, {missed} heartbeats missed"));
+                    }
+                    Liveness::Degraded { missed } => warn!(missed, "Heartbeats missed, the connection may be broken"),
+                    Liveness::Alive | Liveness::Unmonitored => {}
+                }
+
+                Vec::new()
             }
             input_event = input_event_receiver.recv() => {
                 let input_event = input_event.ok_or_else(|| session::general_err!("GUI is stopped"))?;
 
                 match input_event {
                     RdpInputEvent::Resize { width, height, scale_factor, physical_size } => {
-                        trace!(width, height, "Resize event");
+                        trace!(width, height, scale_factor, "Resize event");
                         let (width, height) = MonitorLayoutEntry::adjust_display_size(width.into(), height.into());
                         debug!(width, height, "Adjusted display size");
                         if let Some(response_frame) = active_stage.encode_resize(width, height, Some(scale_factor), physical_size) {
@@ -410,7 +681,7 @@ async fn active_session(
                         } else {
                             // TODO(#271): use the "auto-reconnect cookie": https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-rdpbcgr/15b0d1c9-2891-4adb-a45e-deb4aeeeab7c
                             debug!("Reconnecting with new size");
-                            return Ok(RdpControlFlow::ReconnectWithNewSize { width: width.try_into().unwrap(), height: height.try_into().unwrap() })
+                            return Ok(RdpControlFlow::ReconnectWithNewSize { width: width.try_into().unwrap(), height: height.try_into().unwrap(), scale_factor })
                         }
                     },
                     RdpInputEvent::FastPath(events) => {
@@ -420,6 +691,23 @@ async fn active_session(
                     RdpInputEvent::Close => {
                         active_stage.graceful_shutdown()?
                     }
+                    RdpInputEvent::SetExperience(experience) => {
+                        // The performance flags are only sent in the Client Info PDU.
+                        debug!(?experience, "Reconnecting with new experience settings");
+                        return Ok(RdpControlFlow::ReconnectWithNewExperience(experience));
+                    }
+                    RdpInputEvent::SuppressOutput(suppress) => {
+                        debug!(suppress, "Suppress output");
+                        let desktop_rect = (!suppress).then(|| InclusiveRectangle {
+                            left: 0,
+                            top: 0,
+                            right: image.width().saturating_sub(1),
+                            bottom: image.height().saturating_sub(1),
+                        });
+                        let mut frame = WriteBuf::new();
+                        active_stage.encode_static(&mut frame, ShareDataPdu::SuppressOutput(SuppressOutputPdu { desktop_rect }))?;
+                        vec![ActiveStageOutput::ResponseFrame(frame.into_inner())]
+                    }
                     RdpInputEvent::Clipboard(event) => {
                         if let Some(cliprdr) = active_stage.get_svc_processor::<cliprdr::CliprdrClient>() {
                             if let Some(svc_messages) = match event {
@@ -435,6 +723,22 @@ async fn active_session(
                                     Some(cliprdr.initiate_paste(format)
                                         .map_err(|e| session::custom_err!("CLIPRDR", e))?)
                                 }
+                                ClipboardMessage::SendFileContents(response) => {
+                                    Some(cliprdr.submit_file_contents(response)
+                                        .map_err(|e| session::custom_err!("CLIPRDR", e))?)
+                                }
+                                ClipboardMessage::SendFileContentsRequest(request) => {
+                                    Some(cliprdr.request_file_contents(request)
+                                        .map_err(|e| session::custom_err!("CLIPRDR", e))?)
+                                }
+                                ClipboardMessage::SendLockClipboard(data_id) => {
+                                    Some(cliprdr.lock_clipboard(data_id)
+                                        .map_err(|e| session::custom_err!("CLIPRDR", e))?)
+                                }
+                                ClipboardMessage::SendUnlockClipboard(data_id) => {
+                                    Some(cliprdr.unlock_clipboard(data_id)
+                                        .map_err(|e| session::custom_err!("CLIPRDR", e))?)
+                                }
                                 ClipboardMessage::Error(e) => {
                                     error!("Clipboard backend error: {}", e);
                                     None
@@ -458,10 +762,13 @@ async fn active_session(
 
         for out in outputs {
             match out {
-                ActiveStageOutput::ResponseFrame(frame) => writer
-                    .write_all(&frame)
-                    .await
-                    .map_err(|e| session::custom_err!("write response", e))?,
+                ActiveStageOutput::ResponseFrame(frame) => {
+                    trace!(pdu.size = frame.len(), "Response frame sent");
+                    writer
+                        .write_all(&frame)
+                        .await
+                        .map_err(|e| session::custom_err!("write response", e))?
+                }
                 ActiveStageOutput::GraphicsUpdate(_region) => {
                     let buffer: Vec<u32> = image
                         .data()
@@ -474,28 +781,28 @@ async fn active_session(
                         })
                         .collect();
 
-                    event_loop_proxy
-                        .send_event(RdpOutputEvent::Image {
+                    output_sender
+                        .send(RdpOutputEvent::Image {
                             buffer,
                             width: image.width(),
                             height: image.height(),
                         })
-                        .map_err(|e| session::custom_err!("event_loop_proxy", e))?;
+                        .map_err(|e| session::custom_err!("output_sender", e))?;
                 }
                 ActiveStageOutput::PointerDefault => {
-                    event_loop_proxy
-                        .send_event(RdpOutputEvent::PointerDefault)
-                        .map_err(|e| session::custom_err!("event_loop_proxy", e))?;
+                    output_sender
+                        .send(RdpOutputEvent::PointerDefault)
+                        .map_err(|e| session::custom_err!("output_sender", e))?;
                 }
                 ActiveStageOutput::PointerHidden => {
-                    event_loop_proxy
-                        .send_event(RdpOutputEvent::PointerHidden)
-                        .map_err(|e| session::custom_err!("event_loop_proxy", e))?;
+                    output_sender
+                        .send(RdpOutputEvent::PointerHidden)
+                        .map_err(|e| session::custom_err!("output_sender", e))?;
                 }
                 ActiveStageOutput::PointerPosition { x, y } => {
-                    event_loop_proxy
-                        .send_event(RdpOutputEvent::PointerPosition { x, y })
-                        .map_err(|e| session::custom_err!("event_loop_proxy", e))?;
+                    output_sender
+                        .send(RdpOutputEvent::PointerPosition { x, y })
+                        .map_err(|e| session::custom_err!("output_sender", e))?;
                 }
                 ActiveStageOutput::PointerBitmap(_) => {
                     // Not applicable, because we use the software cursor rendering.
@@ -525,6 +832,15 @@ async fn active_session(
                         } = connection_activation.state
                         {
                             debug!(?desktop_size, "Deactivation-Reactivation Sequence completed");
+                            if let Some(event_log) = event_log {
+                                event_log.log(
+                                    output_sender.session(),
+                                    &SessionLogEvent::ResolutionChanged {
+                                        width: desktop_size.width,
+                                        height: desktop_size.height,
+                                    },
+                                );
+                            }
                             // Update image size with the new desktop size.
                             image = DecodedImage::new(PixelFormat::RgbA32, desktop_size.width, desktop_size.height);
                             // Update the active stage with the new channel IDs and pointer settings.
@@ -542,6 +858,12 @@ async fn active_session(
                         }
                     }
                 }
+                ActiveStageOutput::SessionEvent(SessionEvent::LogonError(error)) => {
+                    warn!(?error, "Logon error notified by the server");
+                }
+                ActiveStageOutput::SessionEvent(event) => {
+                    debug!(?event, "Session event");
+                }
                 ActiveStageOutput::Terminate(reason) => break 'outer reason,
             }
         }
@@ -0,0 +1,118 @@
+//! Async runtime running the sessions.
+//!
+//! The session loop only relies on runtime-agnostic primitives (framed IO, channels, `select!`), and the few
+//! services bound to a reactor, opening the TCP connections and the timers, are provided by a [`Runtime`]. This
+//! allows embedding the client in applications which are not using tokio: [`AsyncStdRuntime`] and [`SmolRuntime`]
+//! are available behind the `async-std` and `smol` features, and another executor can be used by implementing the
+//! trait.
+//!
+//! The WebSocket transports and the requests to the Kerberos KDC still require a tokio reactor: with async-std,
+//! enable its `tokio1` feature.
+
+use core::future::Future;
+use core::pin::Pin;
+use core::time::Duration;
+use std::io;
+use std::net::SocketAddr;
+
+use futures_util::stream::BoxStream;
+use ironrdp_tls::TlsTransport;
+
+/// Connection opened by a [`Runtime`], along with the address of the peer.
+pub type RuntimeConnection = (Box<dyn TlsTransport>, SocketAddr);
+
+/// Services of the async runtime the sessions are running on.
+pub trait Runtime: Send + Sync {
+    /// Opens a TCP connection to the given `host:port` address.
+    fn connect<'a>(&'a self, addr: &'a str)
+        -> Pin<Box<dyn Future<Output = io::Result<RuntimeConnection>> + Send + 'a>>;
+
+    /// Returns a stream ticking at the given period, the first tick being after one period.
+    fn interval(&self, period: Duration) -> BoxStream<'static, ()>;
+}
+
+/// The tokio runtime, which must be running the sessions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioRuntime;
+
+impl Runtime for TokioRuntime {
+    fn connect<'a>(
+        &'a self,
+        addr: &'a str,
+    ) -> Pin<Box<dyn Future<Output = io::Result<RuntimeConnection>> + Send + 'a>> {
+        Box::pin(async move {
+            let stream = tokio::net::TcpStream::connect(addr).await?;
+            let peer_addr = stream.peer_addr()?;
+
+            Ok((Box::new(stream) as Box<dyn TlsTransport>, peer_addr))
+        })
+    }
+
+    fn interval(&self, period: Duration) -> BoxStream<'static, ()> {
+        let interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+
+        Box::pin(futures_util::stream::unfold(interval, |mut interval| async move {
+            interval.tick().await;
+            Some(((), interval))
+        }))
+    }
+}
+
+/// The async-std runtime.
+#[cfg(feature = "async-std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsyncStdRuntime;
+
+#[cfg(feature = "async-std")]
+impl Runtime for AsyncStdRuntime {
+    fn connect<'a>(
+        &'a self,
+        addr: &'a str,
+    ) -> Pin<Box<dyn Future<Output = io::Result<RuntimeConnection>> + Send + 'a>> {
+        use tokio_util::compat::FuturesAsyncReadCompatExt as _;
+
+        Box::pin(async move {
+            let stream = async_std::net::TcpStream::connect(addr).await?;
+            let peer_addr = stream.peer_addr()?;
+
+            // The TLS upgrade is performed on the tokio IO traits.
+            Ok((Box::new(stream.compat()) as Box<dyn TlsTransport>, peer_addr))
+        })
+    }
+
+    fn interval(&self, period: Duration) -> BoxStream<'static, ()> {
+        Box::pin(futures_util::stream::unfold((), move |()| async move {
+            async_std::task::sleep(period).await;
+            Some(((), ()))
+        }))
+    }
+}
+
+/// The smol runtime.
+#[cfg(feature = "smol")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SmolRuntime;
+
+#[cfg(feature = "smol")]
+impl Runtime for SmolRuntime {
+    fn connect<'a>(
+        &'a self,
+        addr: &'a str,
+    ) -> Pin<Box<dyn Future<Output = io::Result<RuntimeConnection>> + Send + 'a>> {
+        use tokio_util::compat::FuturesAsyncReadCompatExt as _;
+
+        Box::pin(async move {
+            let stream = smol::net::TcpStream::connect(addr).await?;
+            let peer_addr = stream.peer_addr()?;
+
+            // The TLS upgrade is performed on the tokio IO traits.
+            Ok((Box::new(stream.compat()) as Box<dyn TlsTransport>, peer_addr))
+        })
+    }
+
+    fn interval(&self, period: Duration) -> BoxStream<'static, ()> {
+        use futures_util::StreamExt as _;
+
+        Box::pin(smol::Timer::interval(period).map(|_| ()))
+    }
+}
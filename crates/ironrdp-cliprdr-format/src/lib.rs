@@ -3,3 +3,4 @@
 
 pub mod bitmap;
 pub mod html;
+pub mod text;
@@ -0,0 +1,91 @@
+use std::borrow::Cow;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TextError {
+    #[error("invalid UTF-16")]
+    InvalidUtf16(#[from] std::string::FromUtf16Error),
+}
+
+/// Converts all line endings (`\n`, `\r\n` or a lone `\r`) to CR-LF.
+///
+/// Windows applications expect each line of `CF_TEXT` and `CF_UNICODETEXT` data to end with
+/// a carriage return/linefeed (CR-LF) combination.
+pub fn to_crlf_line_endings(input: &str) -> Cow<'_, str> {
+    let needs_conversion = input.split("\r\n").any(|part| part.contains(['\r', '\n']));
+
+    if !needs_conversion {
+        return Cow::Borrowed(input);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                output.push_str("\r\n");
+            }
+            '\n' => output.push_str("\r\n"),
+            c => output.push(c),
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+/// Converts all line endings (`\r\n` or a lone `\r`) to LF.
+///
+/// This is the line ending convention expected by most applications on Unix-like systems.
+pub fn to_lf_line_endings(input: &str) -> Cow<'_, str> {
+    if !input.contains('\r') {
+        return Cow::Borrowed(input);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                output.push('\n');
+            }
+            c => output.push(c),
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+/// Converts `CF_UNICODETEXT` format to a string.
+///
+/// The payload is decoded as UTF-16LE until the first null character, or until the end of the
+/// input if there is none. A trailing odd byte, sent as padding by some clipboard owners, is ignored.
+/// Line endings are preserved, see [`to_lf_line_endings`].
+pub fn cf_unicodetext_to_string(input: &[u8]) -> Result<String, TextError> {
+    let code_units = input
+        .chunks_exact(2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .take_while(|code_unit| *code_unit != 0)
+        .collect::<Vec<u16>>();
+
+    Ok(String::from_utf16(&code_units)?)
+}
+
+/// Converts a string to `CF_UNICODETEXT` format.
+///
+/// Line endings are converted to CR-LF and a null terminator is appended.
+pub fn string_to_cf_unicodetext(text: &str) -> Vec<u8> {
+    to_crlf_line_endings(text)
+        .encode_utf16()
+        .chain(core::iter::once(0))
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
@@ -0,0 +1,170 @@
+//! Clipboard backend for X11, Wayland and macOS, built on top of the [`arboard`] crate.
+//!
+//! # Implementation notes
+//!
+//! Neither X11 nor macOS provide a portable way to be notified when the clipboard is changed by
+//! another application, so the OS clipboard is polled from a dedicated worker thread.
+//!
+//! Delayed rendering is not available either: when the remote announces new clipboard content,
+//! the data is fetched right away and written to the OS clipboard.
+//!
+//! On X11, large transfers using the `INCR` mechanism are handled by `arboard` itself. When the
+//! `wayland` feature is enabled, the `wlr-data-control` protocol is used when supported by the
+//! compositor, and X11 (XWayland) is used as a fallback.
+
+mod clipboard_impl;
+mod cliprdr_backend;
+
+use core::fmt;
+use core::time::Duration;
+use std::sync::mpsc as mpsc_sync;
+
+use ironrdp_cliprdr::backend::{ClipboardMessageProxy, CliprdrBackend, CliprdrBackendFactory};
+use ironrdp_cliprdr::pdu::{ClipboardFormat, FormatDataRequest, FormatDataResponse};
+use ironrdp_cliprdr_format::bitmap::BitmapError;
+
+use self::clipboard_impl::ArboardClipboardImpl;
+use self::cliprdr_backend::ArboardCliprdrBackend;
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub type ArboardCliprdrResult<T> = Result<T, ArboardCliprdrError>;
+
+#[derive(Debug)]
+pub enum ArboardCliprdrError {
+    Clipboard(arboard::Error),
+    Bitmap(BitmapError),
+    PngEncoding(png::EncodingError),
+    PngDecoding(png::DecodingError),
+    InvalidFormatData,
+    UnsupportedImageFormat,
+    WorkerThread(std::io::Error),
+    WorkerThreadTerminated,
+}
+
+impl fmt::Display for ArboardCliprdrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clipboard(_) => write!(f, "OS clipboard access failed"),
+            Self::Bitmap(_) => write!(f, "bitmap conversion failed"),
+            Self::PngEncoding(_) => write!(f, "failed to encode PNG image"),
+            Self::PngDecoding(_) => write!(f, "failed to decode PNG image"),
+            Self::InvalidFormatData => write!(f, "received invalid format data from the remote"),
+            Self::UnsupportedImageFormat => write!(f, "unsupported image format"),
+            Self::WorkerThread(_) => write!(f, "failed to spawn clipboard worker thread"),
+            Self::WorkerThreadTerminated => write!(f, "clipboard worker thread terminated unexpectedly"),
+        }
+    }
+}
+
+impl std::error::Error for ArboardCliprdrError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Clipboard(e) => Some(e),
+            Self::Bitmap(e) => Some(e),
+            Self::PngEncoding(e) => Some(e),
+            Self::PngDecoding(e) => Some(e),
+            Self::WorkerThread(e) => Some(e),
+            Self::InvalidFormatData | Self::UnsupportedImageFormat | Self::WorkerThreadTerminated => None,
+        }
+    }
+}
+
+impl From<arboard::Error> for ArboardCliprdrError {
+    fn from(e: arboard::Error) -> Self {
+        Self::Clipboard(e)
+    }
+}
+
+impl From<BitmapError> for ArboardCliprdrError {
+    fn from(e: BitmapError) -> Self {
+        Self::Bitmap(e)
+    }
+}
+
+impl From<png::EncodingError> for ArboardCliprdrError {
+    fn from(e: png::EncodingError) -> Self {
+        Self::PngEncoding(e)
+    }
+}
+
+impl From<png::DecodingError> for ArboardCliprdrError {
+    fn from(e: png::DecodingError) -> Self {
+        Self::PngDecoding(e)
+    }
+}
+
+/// Sent from the clipboard backend shim to the worker thread
+#[derive(Debug)]
+pub(crate) enum BackendEvent {
+    RemoteFormatList(Vec<ClipboardFormat>),
+    FormatDataRequest(FormatDataRequest),
+    FormatDataResponse(FormatDataResponse<'static>),
+    RemoteRequestsFormatList,
+}
+
+/// X11, Wayland and macOS RDP client clipboard implementation.
+///
+/// IronRDP client implementation should provide a message proxy to send messages from the
+/// backend to `CLIPRDR` SVC.
+///
+/// [`ArboardClipboard`] instance holds a worker thread processing the OS clipboard and should be
+/// kept alive during the whole lifetime of the application. The worker thread is stopped once
+/// this instance and all backends built by its factory are dropped.
+pub struct ArboardClipboard {
+    backend_tx: mpsc_sync::Sender<BackendEvent>,
+}
+
+impl ArboardClipboard {
+    /// Creates new clipboard instance with the default polling interval.
+    pub fn new(message_proxy: impl ClipboardMessageProxy + 'static) -> ArboardCliprdrResult<Self> {
+        Self::with_poll_interval(message_proxy, DEFAULT_POLL_INTERVAL)
+    }
+
+    /// Creates new clipboard instance, checking the OS clipboard for changes at the given interval.
+    pub fn with_poll_interval(
+        message_proxy: impl ClipboardMessageProxy + 'static,
+        poll_interval: Duration,
+    ) -> ArboardCliprdrResult<Self> {
+        let (backend_tx, backend_rx) = mpsc_sync::channel();
+        let (init_tx, init_rx) = mpsc_sync::sync_channel(1);
+
+        // The OS clipboard handle is created on the worker thread, because some platforms
+        // require it to be used on the thread it was created on.
+        std::thread::Builder::new()
+            .name("ironrdp-cliprdr".to_owned())
+            .spawn(move || match arboard::Clipboard::new() {
+                Ok(clipboard) => {
+                    let _ = init_tx.send(Ok(()));
+                    ArboardClipboardImpl::new(clipboard, message_proxy, backend_rx, poll_interval).run();
+                }
+                Err(e) => {
+                    let _ = init_tx.send(Err(ArboardCliprdrError::from(e)));
+                }
+            })
+            .map_err(ArboardCliprdrError::WorkerThread)?;
+
+        init_rx
+            .recv()
+            .map_err(|_| ArboardCliprdrError::WorkerThreadTerminated)??;
+
+        Ok(Self { backend_tx })
+    }
+
+    /// Returns clipboard backend factory suitable for making backend instances for `CLIPRDR` SVC.
+    pub fn backend_factory(&self) -> Box<dyn CliprdrBackendFactory + Send> {
+        Box::new(ArboardCliprdrBackendFactory {
+            tx: self.backend_tx.clone(),
+        })
+    }
+}
+
+struct ArboardCliprdrBackendFactory {
+    tx: mpsc_sync::Sender<BackendEvent>,
+}
+
+impl CliprdrBackendFactory for ArboardCliprdrBackendFactory {
+    fn build_cliprdr_backend(&self) -> Box<dyn CliprdrBackend> {
+        Box::new(ArboardCliprdrBackend::new(self.tx.clone()))
+    }
+}
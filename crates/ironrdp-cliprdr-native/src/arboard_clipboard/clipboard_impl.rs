@@ -0,0 +1,321 @@
+use core::time::Duration;
+use std::borrow::Cow;
+use std::sync::mpsc;
+use std::time::Instant;
+
+use ironrdp_cliprdr::backend::{ClipboardMessage, ClipboardMessageProxy};
+use ironrdp_cliprdr::pdu::{ClipboardFormat, ClipboardFormatId, ClipboardFormatName, FormatDataResponse};
+use ironrdp_cliprdr_format::bitmap::{dib_to_png, dibv5_to_png, png_to_cf_dib, png_to_cf_dibv5};
+use ironrdp_cliprdr_format::text::{cf_unicodetext_to_string, string_to_cf_unicodetext, to_lf_line_endings};
+use tracing::{debug, warn};
+
+use crate::arboard_clipboard::{ArboardCliprdrError, ArboardCliprdrResult, BackendEvent};
+
+const FORMAT_PNG_ID: ClipboardFormatId = ClipboardFormatId(0xC001);
+const FORMAT_PNG_NAME: &str = "PNG";
+
+/// Content of the OS clipboard, as seen by the worker thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ClipboardContent {
+    Text(String),
+    Image { width: usize, height: usize, rgba: Vec<u8> },
+}
+
+impl ClipboardContent {
+    fn formats(&self) -> Vec<ClipboardFormat> {
+        match self {
+            // We don't provide CF_TEXT, because it could be synthesized from CF_UNICODETEXT on the
+            // remote side.
+            ClipboardContent::Text(_) => vec![ClipboardFormat::new(ClipboardFormatId::CF_UNICODETEXT)],
+            ClipboardContent::Image { .. } => vec![
+                ClipboardFormat::new(ClipboardFormatId::CF_DIB),
+                ClipboardFormat::new(ClipboardFormatId::CF_DIBV5),
+                ClipboardFormat::new(FORMAT_PNG_ID).with_name(ClipboardFormatName::new_static(FORMAT_PNG_NAME)),
+            ],
+        }
+    }
+}
+
+/// Remote format selected for reading, in order of preference.
+#[derive(Debug, Clone, Copy)]
+enum RemoteFormat {
+    UnicodeText(ClipboardFormatId),
+    Png(ClipboardFormatId),
+    DibV5(ClipboardFormatId),
+    Dib(ClipboardFormatId),
+}
+
+impl RemoteFormat {
+    fn id(self) -> ClipboardFormatId {
+        match self {
+            RemoteFormat::UnicodeText(id) | RemoteFormat::Png(id) | RemoteFormat::DibV5(id) | RemoteFormat::Dib(id) => {
+                id
+            }
+        }
+    }
+}
+
+/// Internal implementation of the clipboard processing logic, running on the worker thread.
+pub(crate) struct ArboardClipboardImpl {
+    clipboard: arboard::Clipboard,
+    message_proxy: Box<dyn ClipboardMessageProxy>,
+    backend_rx: mpsc::Receiver<BackendEvent>,
+    poll_interval: Duration,
+    // Local clipboard is not advertised until the remote requested the initial format list
+    is_ready: bool,
+    // Last known content of the OS clipboard, including content written on behalf of the remote
+    last_content: Option<ClipboardContent>,
+    // Remote format which was requested and for which a response is pending
+    pending_remote_format: Option<RemoteFormat>,
+}
+
+impl ArboardClipboardImpl {
+    pub(crate) fn new(
+        clipboard: arboard::Clipboard,
+        message_proxy: impl ClipboardMessageProxy + 'static,
+        backend_rx: mpsc::Receiver<BackendEvent>,
+        poll_interval: Duration,
+    ) -> Self {
+        Self {
+            clipboard,
+            message_proxy: Box::new(message_proxy),
+            backend_rx,
+            poll_interval,
+            is_ready: false,
+            last_content: None,
+            pending_remote_format: None,
+        }
+    }
+
+    /// Runs the worker event loop until all backend event senders are dropped.
+    pub(crate) fn run(mut self) {
+        let mut next_poll = Instant::now() + self.poll_interval;
+
+        loop {
+            let timeout = next_poll.saturating_duration_since(Instant::now());
+
+            match self.backend_rx.recv_timeout(timeout) {
+                Ok(event) => {
+                    if let Err(err) = self.process_event(event) {
+                        self.send_error(err);
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    next_poll = Instant::now() + self.poll_interval;
+
+                    self.on_poll();
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    debug!("Clipboard backend event channel is closed, stopping clipboard worker");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn send_message(&self, message: ClipboardMessage) {
+        self.message_proxy.send_clipboard_message(message);
+    }
+
+    fn send_error(&self, err: ArboardCliprdrError) {
+        self.send_message(ClipboardMessage::Error(Box::new(err)));
+    }
+
+    fn process_event(&mut self, event: BackendEvent) -> ArboardCliprdrResult<()> {
+        match event {
+            BackendEvent::RemoteRequestsFormatList => {
+                self.is_ready = true;
+
+                let content = self.read_os_clipboard();
+                let formats = content.as_ref().map(ClipboardContent::formats).unwrap_or_default();
+                self.last_content = content;
+
+                self.send_message(ClipboardMessage::SendInitiateCopy(formats));
+            }
+            BackendEvent::RemoteFormatList(formats) => {
+                // Delayed rendering is not available, so we start querying the data right away.
+                self.pending_remote_format = select_remote_format(&formats);
+
+                if let Some(format) = self.pending_remote_format {
+                    self.send_message(ClipboardMessage::SendInitiatePaste(format.id()));
+                }
+            }
+            BackendEvent::FormatDataRequest(request) => {
+                let response = match self.render_local_format(request.format) {
+                    Ok(response) => response,
+                    Err(err) => {
+                        // Not a critical error, but we should notify remote about error.
+                        self.send_message(ClipboardMessage::SendFormatData(FormatDataResponse::new_error()));
+                        return Err(err);
+                    }
+                };
+
+                self.send_message(ClipboardMessage::SendFormatData(response));
+            }
+            BackendEvent::FormatDataResponse(response) => {
+                let Some(format) = self.pending_remote_format.take() else {
+                    warn!("Remote returned format data, but no formats were requested");
+                    return Ok(());
+                };
+
+                if response.is_error() {
+                    // Format is not available anymore.
+                    return Ok(());
+                }
+
+                let content = match format {
+                    RemoteFormat::UnicodeText(_) => {
+                        let text = cf_unicodetext_to_string(response.data())
+                            .map_err(|_| ArboardCliprdrError::InvalidFormatData)?;
+                        ClipboardContent::Text(to_lf_line_endings(&text).into_owned())
+                    }
+                    RemoteFormat::Png(_) => decode_png(response.data())?,
+                    RemoteFormat::DibV5(_) => decode_png(&dibv5_to_png(response.data())?)?,
+                    RemoteFormat::Dib(_) => decode_png(&dib_to_png(response.data())?)?,
+                };
+
+                self.write_os_clipboard(&content)?;
+
+                // Remember the content we wrote ourselves, so it is not advertised back to the remote.
+                self.last_content = Some(content);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn on_poll(&mut self) {
+        if !self.is_ready {
+            return;
+        }
+
+        let content = self.read_os_clipboard();
+
+        if content.is_some() && content != self.last_content {
+            let formats = content.as_ref().map(ClipboardContent::formats).unwrap_or_default();
+            self.last_content = content;
+
+            self.send_message(ClipboardMessage::SendInitiateCopy(formats));
+        }
+    }
+
+    fn render_local_format(&mut self, format: ClipboardFormatId) -> ArboardCliprdrResult<FormatDataResponse<'static>> {
+        let response = match (format, self.read_os_clipboard()) {
+            (ClipboardFormatId::CF_UNICODETEXT, Some(ClipboardContent::Text(text))) => {
+                FormatDataResponse::new_data(string_to_cf_unicodetext(&text))
+            }
+            (ClipboardFormatId::CF_DIB, Some(ClipboardContent::Image { width, height, rgba })) => {
+                FormatDataResponse::new_data(png_to_cf_dib(&encode_png(width, height, &rgba)?)?)
+            }
+            (ClipboardFormatId::CF_DIBV5, Some(ClipboardContent::Image { width, height, rgba })) => {
+                FormatDataResponse::new_data(png_to_cf_dibv5(&encode_png(width, height, &rgba)?)?)
+            }
+            (FORMAT_PNG_ID, Some(ClipboardContent::Image { width, height, rgba })) => {
+                FormatDataResponse::new_data(encode_png(width, height, &rgba)?)
+            }
+            _ => {
+                // No data available for this format.
+                FormatDataResponse::new_error()
+            }
+        };
+
+        Ok(response)
+    }
+
+    fn read_os_clipboard(&mut self) -> Option<ClipboardContent> {
+        match self.clipboard.get_text() {
+            Ok(text) => return Some(ClipboardContent::Text(text)),
+            Err(arboard::Error::ContentNotAvailable) => {}
+            Err(error) => debug!(%error, "Failed to read text from the OS clipboard"),
+        }
+
+        match self.clipboard.get_image() {
+            Ok(image) => Some(ClipboardContent::Image {
+                width: image.width,
+                height: image.height,
+                rgba: image.bytes.into_owned(),
+            }),
+            Err(arboard::Error::ContentNotAvailable) => None,
+            Err(error) => {
+                debug!(%error, "Failed to read image from the OS clipboard");
+                None
+            }
+        }
+    }
+
+    fn write_os_clipboard(&mut self, content: &ClipboardContent) -> ArboardCliprdrResult<()> {
+        match content {
+            ClipboardContent::Text(text) => self.clipboard.set_text(text.as_str())?,
+            ClipboardContent::Image { width, height, rgba } => self.clipboard.set_image(arboard::ImageData {
+                width: *width,
+                height: *height,
+                bytes: Cow::Borrowed(rgba),
+            })?,
+        }
+
+        Ok(())
+    }
+}
+
+fn select_remote_format(formats: &[ClipboardFormat]) -> Option<RemoteFormat> {
+    let find_standard = |id: ClipboardFormatId| formats.iter().any(|format| format.id() == id).then_some(id);
+
+    let find_registered = |name: &str| {
+        formats
+            .iter()
+            .find(|format| format.id().is_registered() && format.name().is_some_and(|n| n.value() == name))
+            .map(ClipboardFormat::id)
+    };
+
+    find_standard(ClipboardFormatId::CF_UNICODETEXT)
+        .map(RemoteFormat::UnicodeText)
+        .or_else(|| find_registered(FORMAT_PNG_NAME).map(RemoteFormat::Png))
+        .or_else(|| find_standard(ClipboardFormatId::CF_DIBV5).map(RemoteFormat::DibV5))
+        .or_else(|| find_standard(ClipboardFormatId::CF_DIB).map(RemoteFormat::Dib))
+}
+
+fn encode_png(width: usize, height: usize, rgba: &[u8]) -> ArboardCliprdrResult<Vec<u8>> {
+    let width = u32::try_from(width).map_err(|_| ArboardCliprdrError::UnsupportedImageFormat)?;
+    let height = u32::try_from(height).map_err(|_| ArboardCliprdrError::UnsupportedImageFormat)?;
+
+    let mut output = Vec::new();
+
+    let mut encoder = png::Encoder::new(&mut output, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+
+    Ok(output)
+}
+
+fn decode_png(input: &[u8]) -> ArboardCliprdrResult<ClipboardContent> {
+    let mut decoder = png::Decoder::new(input);
+
+    // The OS clipboard expects 8-bit RGBA, expand everything else.
+    decoder.set_transformations(
+        png::Transformations::ALPHA | png::Transformations::EXPAND | png::Transformations::STRIP_16,
+    );
+
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+            .collect(),
+        _ => return Err(ArboardCliprdrError::UnsupportedImageFormat),
+    };
+
+    Ok(ClipboardContent::Image {
+        width: usize::try_from(info.width).map_err(|_| ArboardCliprdrError::UnsupportedImageFormat)?,
+        height: usize::try_from(info.height).map_err(|_| ArboardCliprdrError::UnsupportedImageFormat)?,
+        rgba,
+    })
+}
@@ -0,0 +1,78 @@
+use std::sync::mpsc as mpsc_sync;
+
+use ironrdp_cliprdr::backend::CliprdrBackend;
+use ironrdp_cliprdr::pdu::{
+    ClipboardFormat, ClipboardGeneralCapabilityFlags, FileContentsRequest, FileContentsResponse, FormatDataRequest,
+    FormatDataResponse, LockDataId,
+};
+use ironrdp_core::{impl_as_any, IntoOwned};
+
+use crate::arboard_clipboard::BackendEvent;
+
+#[derive(Debug)]
+pub(crate) struct ArboardCliprdrBackend {
+    backend_event_tx: mpsc_sync::Sender<BackendEvent>,
+}
+
+impl_as_any!(ArboardCliprdrBackend);
+
+impl ArboardCliprdrBackend {
+    pub(crate) fn new(backend_event_tx: mpsc_sync::Sender<BackendEvent>) -> Self {
+        Self { backend_event_tx }
+    }
+
+    fn send_event(&self, event: BackendEvent) {
+        if self.backend_event_tx.send(event).is_err() {
+            tracing::error!("Failed to send clipboard backend event, worker thread is dead");
+        }
+    }
+}
+
+impl CliprdrBackend for ArboardCliprdrBackend {
+    fn temporary_directory(&self) -> &str {
+        ".cliprdr"
+    }
+
+    fn client_capabilities(&self) -> ClipboardGeneralCapabilityFlags {
+        // No additional capabilities yet
+        ClipboardGeneralCapabilityFlags::empty()
+    }
+
+    fn on_ready(&mut self) {}
+
+    fn on_process_negotiated_capabilities(&mut self, _capabilities: ClipboardGeneralCapabilityFlags) {
+        // No additional capabilities yet
+    }
+
+    fn on_remote_copy(&mut self, available_formats: &[ClipboardFormat]) {
+        self.send_event(BackendEvent::RemoteFormatList(available_formats.to_vec()));
+    }
+
+    fn on_format_data_request(&mut self, request: FormatDataRequest) {
+        self.send_event(BackendEvent::FormatDataRequest(request));
+    }
+
+    fn on_format_data_response(&mut self, response: FormatDataResponse<'_>) {
+        self.send_event(BackendEvent::FormatDataResponse(response.into_owned()));
+    }
+
+    fn on_file_contents_request(&mut self, _request: FileContentsRequest) {
+        // File transfer not implemented yet
+    }
+
+    fn on_file_contents_response(&mut self, _response: FileContentsResponse<'_>) {
+        // File transfer not implemented yet
+    }
+
+    fn on_lock(&mut self, _data_id: LockDataId) {
+        // File transfer not implemented yet
+    }
+
+    fn on_unlock(&mut self, _data_id: LockDataId) {
+        // File transfer not implemented yet
+    }
+
+    fn on_request_format_list(&mut self) {
+        self.send_event(BackendEvent::RemoteRequestsFormatList);
+    }
+}
@@ -15,5 +15,10 @@ mod windows;
 #[cfg(windows)]
 pub use crate::windows::{WinClipboard, WinCliprdrError, WinCliprdrResult, HWND};
 
+#[cfg(any(feature = "x11", feature = "macos"))]
+mod arboard_clipboard;
+#[cfg(any(feature = "x11", feature = "macos"))]
+pub use crate::arboard_clipboard::{ArboardClipboard, ArboardCliprdrError, ArboardCliprdrResult};
+
 mod stub;
 pub use crate::stub::{StubClipboard, StubCliprdrBackend};
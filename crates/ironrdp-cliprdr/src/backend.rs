@@ -6,6 +6,7 @@ use crate::pdu::{
     ClipboardFormat, ClipboardFormatId, ClipboardGeneralCapabilityFlags, FileContentsRequest, FileContentsResponse,
     FormatDataRequest, FormatDataResponse, LockDataId, OwnedFormatDataResponse,
 };
+use crate::policy::ClipboardPolicy;
 
 pub trait ClipboardError: std::error::Error + Send + Sync + 'static {}
 
@@ -33,6 +34,33 @@ pub enum ClipboardMessage {
     /// received.
     SendInitiatePaste(ClipboardFormatId),
 
+    /// Sent by clipboard backend when file contents are ready to be sent to the remote.
+    ///
+    /// Client implementation should send file contents to `CLIPRDR` SVC when this message is
+    /// received. This should happen after [`CliprdrBackend::on_file_contents_request`] is called.
+    SendFileContents(FileContentsResponse<'static>),
+
+    /// Sent by clipboard backend when the size or a range of the contents of a file copied on the
+    /// remote is needed, the files being listed in the file list format data.
+    ///
+    /// Client implementation should send file contents request on `CLIPRDR` SVC when this message
+    /// is received.
+    SendFileContentsRequest(FileContentsRequest),
+
+    /// Sent by clipboard backend before reading the files copied on the remote, so their contents
+    /// are kept available by the remote even if its clipboard content changes.
+    ///
+    /// Client implementation should send lock clipboard data on `CLIPRDR` SVC when this message is
+    /// received.
+    SendLockClipboard(LockDataId),
+
+    /// Sent by clipboard backend once the files copied on the remote are read, releasing the
+    /// clipboard data locked with [`ClipboardMessage::SendLockClipboard`].
+    ///
+    /// Client implementation should send unlock clipboard data on `CLIPRDR` SVC when this message
+    /// is received.
+    SendUnlockClipboard(LockDataId),
+
     /// Failure received from the OS clipboard event loop.
     ///
     /// Client implementation should log/display this error.
@@ -85,6 +113,24 @@ pub trait CliprdrBackend: AsAny + core::fmt::Debug + Send {
     /// most of the backends.
     fn on_format_list_received(&mut self) {}
 
+    /// Returns `true` if [crate::Cliprdr] should suppress local format lists echoing the remote
+    /// clipboard content.
+    ///
+    /// Backends which can't tell apart OS clipboard changes caused by themselves (e.g. when
+    /// writing remote data into the OS clipboard) from actual copy operations performed by the
+    /// user should return `true`. Otherwise, the remote content would be advertised back to the
+    /// remote, potentially causing an endless clipboard "ping-pong" between both endpoints.
+    ///
+    /// When enabled, the first call to [`crate::Cliprdr::initiate_copy`] following the reception of
+    /// a remote format list is ignored if it only advertises formats present in that format
+    /// list.
+    ///
+    /// This method has default implementation which returns `false`, because most of the backends
+    /// are able to track the OS clipboard ownership by themselves.
+    fn suppress_loopback(&self) -> bool {
+        false
+    }
+
     /// Adjusts [crate::Cliprdr] backend capabilities based on capabilities negotiated with a server.
     ///
     /// Called by [crate::Cliprdr] when capability negotiation is finished and server capabilities are
@@ -155,4 +201,9 @@ pub trait CliprdrBackend: AsAny + core::fmt::Debug + Send {
 pub trait CliprdrBackendFactory {
     /// Builds new backend instance.
     fn build_cliprdr_backend(&self) -> Box<dyn CliprdrBackend>;
+
+    /// Builds the policy applied to the clipboard content of the new channel, if any.
+    fn build_cliprdr_policy(&self) -> Option<Box<dyn ClipboardPolicy>> {
+        None
+    }
 }
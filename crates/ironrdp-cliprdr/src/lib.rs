@@ -8,6 +8,9 @@
 
 pub mod backend;
 pub mod pdu;
+pub mod policy;
+
+use core::cell::Cell;
 
 use backend::CliprdrBackend;
 use ironrdp_core::{decode, AsAny, EncodeResult};
@@ -19,11 +22,12 @@ use ironrdp_svc::{
 };
 use pdu::{
     Capabilities, ClientTemporaryDirectory, ClipboardFormat, ClipboardFormatId, ClipboardGeneralCapabilityFlags,
-    ClipboardPdu, ClipboardProtocolVersion, FileContentsResponse, FormatDataRequest, FormatListResponse,
-    OwnedFormatDataResponse,
+    ClipboardPdu, ClipboardProtocolVersion, FileContentsRequest, FileContentsResponse, FormatDataRequest,
+    FormatDataResponse, FormatListResponse, LockDataId, OwnedFormatDataResponse,
 };
+use policy::{ClipboardDirection, ClipboardPolicy, PolicyDecision};
 use thiserror::Error;
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 #[rustfmt::skip] // do not reorder
 use crate::pdu::FormatList;
@@ -47,6 +51,17 @@ enum CliprdrState {
     Failed,
 }
 
+/// Side currently owning the clipboard content, as tracked by [`Cliprdr`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClipboardOwner {
+    /// No format list was exchanged yet.
+    Unknown,
+    /// The latest format list was sent by this endpoint.
+    Local,
+    /// The latest format list was received from the remote.
+    Remote,
+}
+
 pub trait Role: core::fmt::Debug + Send + 'static {
     fn is_server() -> bool;
 }
@@ -57,6 +72,19 @@ pub struct Cliprdr<R: Role> {
     backend: Box<dyn CliprdrBackend>,
     capabilities: Capabilities,
     state: CliprdrState,
+    // The ownership is updated by `initiate_copy`, which only borrows the processor.
+    owner: Cell<ClipboardOwner>,
+    /// Incremented (wrapping) each time a format list is sent or received.
+    sequence: Cell<u32>,
+    /// Formats of the latest format list received from the remote.
+    remote_formats: Vec<ClipboardFormat>,
+    /// Set when a remote format list was received and the next local copy may be its echo.
+    loopback_expected: Cell<bool>,
+    policy: Option<Box<dyn ClipboardPolicy>>,
+    /// Format of the latest format data request sent to the remote.
+    local_request: Cell<Option<ClipboardFormatId>>,
+    /// Format of the latest format data request received from the remote.
+    remote_request: Option<ClipboardFormatId>,
     _marker: core::marker::PhantomData<R>,
 }
 
@@ -100,10 +128,82 @@ impl<R: Role> Cliprdr<R> {
             backend,
             state: CliprdrState::Initialization,
             capabilities: Capabilities::new(ClipboardProtocolVersion::V2, flags),
+            owner: Cell::new(ClipboardOwner::Unknown),
+            sequence: Cell::new(0),
+            remote_formats: Vec::new(),
+            loopback_expected: Cell::new(false),
+            policy: None,
+            local_request: Cell::new(None),
+            remote_request: None,
             _marker: core::marker::PhantomData,
         }
     }
 
+    /// Sets the policy applied to the clipboard content sent to and received from the remote.
+    #[must_use]
+    pub fn with_policy(mut self, policy: Box<dyn ClipboardPolicy>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Returns the side currently owning the clipboard content.
+    pub fn owner(&self) -> ClipboardOwner {
+        self.owner.get()
+    }
+
+    /// Returns the clipboard sequence number.
+    ///
+    /// The sequence number is incremented each time the clipboard ownership changes, i.e. each
+    /// time a format list is sent or received. Backends may use it to discard stale data.
+    pub fn sequence(&self) -> u32 {
+        self.sequence.get()
+    }
+
+    fn take_ownership(&self, owner: ClipboardOwner) {
+        self.owner.set(owner);
+        self.sequence.set(self.sequence.get().wrapping_add(1));
+    }
+
+    /// Returns `true` if the advertised local formats are likely to be an echo of the latest
+    /// remote format list, caused by the backend writing remote data into the OS clipboard.
+    fn is_loopback(&self, available_formats: &[ClipboardFormat]) -> bool {
+        if !self.loopback_expected.get() || self.owner.get() != ClipboardOwner::Remote || available_formats.is_empty() {
+            return false;
+        }
+
+        return available_formats
+            .iter()
+            .all(|local| self.remote_formats.iter().any(|remote| is_same_format(local, remote)));
+
+        fn is_same_format(local: &ClipboardFormat, remote: &ClipboardFormat) -> bool {
+            // These formats are synthesized by the OS from each other, so the echo may contain
+            // formats which were not present in the original format list.
+            const TEXT_FORMATS: &[ClipboardFormatId] = &[
+                ClipboardFormatId::CF_TEXT,
+                ClipboardFormatId::CF_OEMTEXT,
+                ClipboardFormatId::CF_UNICODETEXT,
+                ClipboardFormatId::CF_LOCALE,
+            ];
+            const BITMAP_FORMATS: &[ClipboardFormatId] = &[
+                ClipboardFormatId::CF_BITMAP,
+                ClipboardFormatId::CF_DIB,
+                ClipboardFormatId::CF_DIBV5,
+            ];
+
+            if local.id().is_registered() || remote.id().is_registered() {
+                // Registered format ids are local to each endpoint, only names can be compared.
+                return local.id().is_registered()
+                    && remote.id().is_registered()
+                    && local.name().is_some()
+                    && local.name() == remote.name();
+            }
+
+            local.id() == remote.id()
+                || (TEXT_FORMATS.contains(&local.id()) && TEXT_FORMATS.contains(&remote.id()))
+                || (BITMAP_FORMATS.contains(&local.id()) && BITMAP_FORMATS.contains(&remote.id()))
+        }
+    }
+
     pub fn downcast_backend<T: CliprdrBackend>(&self) -> Option<&T> {
         self.backend.as_any().downcast_ref::<T>()
     }
@@ -118,8 +218,66 @@ impl<R: Role> Cliprdr<R> {
             .contains(ClipboardGeneralCapabilityFlags::USE_LONG_FORMAT_NAMES)
     }
 
+    /// Returns `true` if the capability is supported by both sides.
+    fn is_negotiated(&self, flag: ClipboardGeneralCapabilityFlags) -> bool {
+        self.capabilities.flags().contains(flag)
+    }
+
     fn build_format_list(&self, formats: &[ClipboardFormat]) -> EncodeResult<FormatList<'static>> {
-        FormatList::new_unicode(formats, self.are_long_format_names_enabled())
+        let Some(policy) = self.policy.as_deref() else {
+            return FormatList::new_unicode(formats, self.are_long_format_names_enabled());
+        };
+
+        let mut formats = formats.to_vec();
+        policy.filter_formats(ClipboardDirection::Outgoing, &mut formats);
+
+        FormatList::new_unicode(&formats, self.are_long_format_names_enabled())
+    }
+
+    fn apply_format_data_policy<'a>(
+        &self,
+        direction: ClipboardDirection,
+        format: Option<ClipboardFormatId>,
+        response: FormatDataResponse<'a>,
+    ) -> FormatDataResponse<'a> {
+        let Some(policy) = self.policy.as_deref() else {
+            return response;
+        };
+
+        if response.is_error() {
+            return response;
+        }
+
+        match policy.check_format_data(direction, format, response.data()) {
+            PolicyDecision::Allow => response,
+            PolicyDecision::Replace(data) => {
+                debug!(?direction, ?format, "Clipboard format data replaced by the policy");
+                FormatDataResponse::new_data(data)
+            }
+            PolicyDecision::Reject => {
+                info!(?direction, ?format, "Clipboard format data rejected by the policy");
+                FormatDataResponse::new_error()
+            }
+        }
+    }
+
+    fn apply_file_contents_policy<'a>(
+        &self,
+        direction: ClipboardDirection,
+        response: FileContentsResponse<'a>,
+    ) -> FileContentsResponse<'a> {
+        let Some(policy) = self.policy.as_deref() else {
+            return response;
+        };
+
+        match policy.check_file_contents(direction, response.data()) {
+            PolicyDecision::Allow => response,
+            PolicyDecision::Replace(data) => FileContentsResponse::new_data_response(response.stream_id(), data),
+            PolicyDecision::Reject => {
+                info!(?direction, "Clipboard file contents rejected by the policy");
+                FileContentsResponse::new_error(response.stream_id())
+            }
+        }
     }
 
     fn handle_error_transition(&mut self, err: ClipboardError) -> PduResult<Vec<SvcMessage>> {
@@ -177,9 +335,16 @@ impl<R: Role> Cliprdr<R> {
             self.backend.on_ready();
         }
 
-        let formats = format_list.get_formats(self.are_long_format_names_enabled())?;
+        let mut formats = format_list.get_formats(self.are_long_format_names_enabled())?;
+        if let Some(policy) = self.policy.as_deref() {
+            policy.filter_formats(ClipboardDirection::Incoming, &mut formats);
+        }
         self.backend.on_remote_copy(&formats);
 
+        self.take_ownership(ClipboardOwner::Remote);
+        self.loopback_expected.set(self.backend.suppress_loopback());
+        self.remote_formats = formats;
+
         let pdu = ClipboardPdu::FormatListResponse(FormatListResponse::Ok);
 
         Ok(vec![into_cliprdr_message(pdu)])
@@ -195,6 +360,7 @@ impl<R: Role> Cliprdr<R> {
     pub fn submit_format_data(&self, response: OwnedFormatDataResponse) -> PduResult<CliprdrSvcMessages<R>> {
         ready_guard!(self, submit_format_data);
 
+        let response = self.apply_format_data_policy(ClipboardDirection::Outgoing, self.remote_request, response);
         let pdu = ClipboardPdu::FormatDataResponse(response);
 
         Ok(vec![into_cliprdr_message(pdu)].into())
@@ -210,11 +376,65 @@ impl<R: Role> Cliprdr<R> {
     pub fn submit_file_contents(&self, response: FileContentsResponse<'static>) -> PduResult<CliprdrSvcMessages<R>> {
         ready_guard!(self, submit_file_contents);
 
+        let response = self.apply_file_contents_policy(ClipboardDirection::Outgoing, response);
         let pdu = ClipboardPdu::FileContentsResponse(response);
 
         Ok(vec![into_cliprdr_message(pdu)].into())
     }
 
+    /// Requests the size or a range of the contents of a file copied on the remote, returning a
+    /// [`CliprdrSvcMessages`] to send on the channel.
+    ///
+    /// The files are listed in the file list format data of the remote, and the response is given to
+    /// [`CliprdrBackend::on_file_contents_response`]. File transfer requires both sides to support
+    /// [`ClipboardGeneralCapabilityFlags::STREAM_FILECLIP_ENABLED`].
+    pub fn request_file_contents(&self, request: FileContentsRequest) -> PduResult<CliprdrSvcMessages<R>> {
+        ready_guard!(self, request_file_contents);
+
+        if !self.is_negotiated(ClipboardGeneralCapabilityFlags::STREAM_FILECLIP_ENABLED) {
+            error!("Attempted to request file contents, but file transfer is not supported by the remote");
+            return Ok(Vec::new().into());
+        }
+
+        let pdu = ClipboardPdu::FileContentsRequest(request);
+
+        Ok(vec![into_cliprdr_message(pdu)].into())
+    }
+
+    /// Locks the clipboard data of the remote, returning a [`CliprdrSvcMessages`] to send on the
+    /// channel.
+    ///
+    /// The files copied on the remote stay available for the file contents requests using this lock
+    /// ID, even if the remote clipboard content changes, until [`Cliprdr::unlock_clipboard`]. Locking
+    /// requires both sides to support [`ClipboardGeneralCapabilityFlags::CAN_LOCK_CLIPDATA`].
+    pub fn lock_clipboard(&self, data_id: LockDataId) -> PduResult<CliprdrSvcMessages<R>> {
+        ready_guard!(self, lock_clipboard);
+
+        if !self.is_negotiated(ClipboardGeneralCapabilityFlags::CAN_LOCK_CLIPDATA) {
+            error!("Attempted to lock the clipboard data, but locking is not supported by the remote");
+            return Ok(Vec::new().into());
+        }
+
+        let pdu = ClipboardPdu::LockData(data_id);
+
+        Ok(vec![into_cliprdr_message(pdu)].into())
+    }
+
+    /// Unlocks the clipboard data of the remote locked with [`Cliprdr::lock_clipboard`], returning a
+    /// [`CliprdrSvcMessages`] to send on the channel.
+    pub fn unlock_clipboard(&self, data_id: LockDataId) -> PduResult<CliprdrSvcMessages<R>> {
+        ready_guard!(self, unlock_clipboard);
+
+        if !self.is_negotiated(ClipboardGeneralCapabilityFlags::CAN_LOCK_CLIPDATA) {
+            error!("Attempted to unlock the clipboard data, but locking is not supported by the remote");
+            return Ok(Vec::new().into());
+        }
+
+        let pdu = ClipboardPdu::UnlockData(data_id);
+
+        Ok(vec![into_cliprdr_message(pdu)].into())
+    }
+
     pub fn capabilities(&self) -> PduResult<SvcMessage> {
         let pdu = ClipboardPdu::Capabilities(self.capabilities.clone());
 
@@ -230,15 +450,28 @@ impl<R: Role> Cliprdr<R> {
     /// Starts processing of `CLIPRDR` copy command. Should be called by the clipboard
     /// implementation when user performs OS-specific copy command (e.g. `Ctrl+C` shortcut on
     /// keyboard)
+    ///
+    /// When [`CliprdrBackend::suppress_loopback`] returns `true`, a format list echoing the
+    /// latest remote format list is not sent back to the remote.
     pub fn initiate_copy(&self, available_formats: &[ClipboardFormat]) -> PduResult<CliprdrSvcMessages<R>> {
         let mut pdus = Vec::new();
 
+        let is_loopback = self.is_loopback(available_formats);
+        self.loopback_expected.set(false);
+
         match (self.state, R::is_server()) {
+            (CliprdrState::Ready, _) if is_loopback => {
+                debug!(
+                    ?available_formats,
+                    "Suppressed format list echoing the remote clipboard content"
+                );
+            }
             // When user initiates copy, we should send format list to server.
             (CliprdrState::Ready, _) => {
                 pdus.push(ClipboardPdu::FormatList(
                     self.build_format_list(available_formats).map_err(|e| encode_err!(e))?,
                 ));
+                self.take_ownership(ClipboardOwner::Local);
             }
             (CliprdrState::Initialization, false) => {
                 // During initialization state, first copy action is synthetic and should be sent along with
@@ -250,6 +483,7 @@ impl<R: Role> Cliprdr<R> {
                 pdus.push(ClipboardPdu::FormatList(
                     self.build_format_list(available_formats).map_err(|e| encode_err!(e))?,
                 ));
+                self.take_ownership(ClipboardOwner::Local);
             }
             _ => {
                 error!(?self.state, "Attempted to initiate copy in incorrect state");
@@ -265,6 +499,8 @@ impl<R: Role> Cliprdr<R> {
     pub fn initiate_paste(&self, requested_format: ClipboardFormatId) -> PduResult<CliprdrSvcMessages<R>> {
         ready_guard!(self, initiate_paste);
 
+        self.local_request.set(Some(requested_format));
+
         // When user initiates paste, we should send format data request to server, and expect to
         // receive response with contents via `FormatDataResponse` PDU.
         let pdu = ClipboardPdu::FormatDataRequest(FormatDataRequest {
@@ -314,6 +550,7 @@ impl<R: Role> SvcProcessor for Cliprdr<R> {
                 Ok(Vec::new())
             }
             ClipboardPdu::FormatDataRequest(request) => {
+                self.remote_request = Some(request.format);
                 self.backend.on_format_data_request(request);
 
                 // NOTE: An actual data should be sent later via `submit_format_data` method,
@@ -321,6 +558,8 @@ impl<R: Role> SvcProcessor for Cliprdr<R> {
                 Ok(Vec::new())
             }
             ClipboardPdu::FormatDataResponse(response) => {
+                let response =
+                    self.apply_format_data_policy(ClipboardDirection::Incoming, self.local_request.get(), response);
                 self.backend.on_format_data_response(response);
                 Ok(Vec::new())
             }
@@ -329,6 +568,7 @@ impl<R: Role> SvcProcessor for Cliprdr<R> {
                 Ok(Vec::new())
             }
             ClipboardPdu::FileContentsResponse(response) => {
+                let response = self.apply_file_contents_policy(ClipboardDirection::Incoming, response);
                 self.backend.on_file_contents_response(response);
                 Ok(Vec::new())
             }
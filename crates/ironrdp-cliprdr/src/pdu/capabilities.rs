@@ -46,7 +46,7 @@ impl Capabilities {
 
     pub fn downgrade(&mut self, server_caps: &Self) {
         let client_flags = self.flags();
-        let server_flags = self.flags();
+        let server_flags = server_caps.flags();
 
         let flags = client_flags & server_flags;
         let version = self.version().downgrade(server_caps.version());
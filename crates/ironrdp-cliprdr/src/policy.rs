@@ -0,0 +1,244 @@
+//! Policy hooks transforming or rejecting the clipboard content crossing the channel.
+//!
+//! A [`ClipboardPolicy`] set with [`crate::Cliprdr::with_policy`] is applied to the format lists and the data
+//! sent to the remote, before they are encoded, and to the format lists and the data received from the remote,
+//! before they are given to the [`crate::backend::CliprdrBackend`]. [`ClipboardFilter`] implements the common
+//! data loss prevention rules: a maximum size, blocked formats and, with the `regex` feature, text redaction.
+
+use crate::pdu::{ClipboardFormat, ClipboardFormatId, ClipboardFormatName};
+
+/// Direction of the clipboard content the policy is applied to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClipboardDirection {
+    /// Content copied on the remote, received by this endpoint.
+    Incoming,
+    /// Content copied locally, sent to the remote.
+    Outgoing,
+}
+
+/// Decision of a [`ClipboardPolicy`] about clipboard data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyDecision {
+    /// The data is transferred unchanged.
+    Allow,
+    /// The data is replaced by the given bytes, e.g. redacted text.
+    Replace(Vec<u8>),
+    /// The data is not transferred, an error response is given instead.
+    Reject,
+}
+
+/// Policy transforming or rejecting the clipboard content before it crosses the channel.
+///
+/// All methods have a default implementation allowing the content.
+pub trait ClipboardPolicy: core::fmt::Debug + Send + 'static {
+    /// Removes the formats which must not be advertised from a format list.
+    ///
+    /// The outgoing format lists are filtered before they are sent, so the remote never requests the removed
+    /// formats, and the incoming ones before [`crate::backend::CliprdrBackend::on_remote_copy`] is called.
+    fn filter_formats(&self, direction: ClipboardDirection, formats: &mut Vec<ClipboardFormat>) {
+        let _ = (direction, formats);
+    }
+
+    /// Decides whether the data of a format data response is transferred.
+    ///
+    /// `format` is the format requested by the matching format data request, if any.
+    fn check_format_data(
+        &self,
+        direction: ClipboardDirection,
+        format: Option<ClipboardFormatId>,
+        data: &[u8],
+    ) -> PolicyDecision {
+        let _ = (direction, format, data);
+        PolicyDecision::Allow
+    }
+
+    /// Decides whether a chunk of the contents of a copied file is transferred.
+    fn check_file_contents(&self, direction: ClipboardDirection, data: &[u8]) -> PolicyDecision {
+        let _ = (direction, data);
+        PolicyDecision::Allow
+    }
+}
+
+/// Name of the registered format listing the copied files.
+const FILE_LIST_FORMAT_NAME: &str = "FileGroupDescriptorW";
+
+/// Clipboard policy with a maximum data size, blocked formats and text redaction.
+#[derive(Debug, Clone, Default)]
+pub struct ClipboardFilter {
+    direction: Option<ClipboardDirection>,
+    max_size: Option<usize>,
+    blocked_formats: Vec<ClipboardFormatId>,
+    blocked_format_names: Vec<ClipboardFormatName>,
+    block_files: bool,
+    #[cfg(feature = "regex")]
+    redactions: Vec<(regex::Regex, String)>,
+}
+
+impl ClipboardFilter {
+    /// Creates a filter allowing everything, in both directions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies the filter to the content going in the given direction only.
+    #[must_use]
+    pub fn only(mut self, direction: ClipboardDirection) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Rejects the format data larger than `max_size` bytes.
+    #[must_use]
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Strips a standard format (e.g. [`ClipboardFormatId::CF_DIB`]) from the format lists.
+    #[must_use]
+    pub fn block_format(mut self, format: ClipboardFormatId) -> Self {
+        self.blocked_formats.push(format);
+        self
+    }
+
+    /// Strips a registered format (e.g. `HTML Format`) from the format lists.
+    ///
+    /// Registered format ids are local to each endpoint, so the registered formats are matched by name.
+    #[must_use]
+    pub fn block_format_name(mut self, name: impl Into<String>) -> Self {
+        self.blocked_format_names.push(ClipboardFormatName::new(name.into()));
+        self
+    }
+
+    /// Strips the file lists from the format lists and rejects the file contents.
+    #[must_use]
+    pub fn block_files(mut self) -> Self {
+        self.block_files = true;
+        self
+    }
+
+    /// Replaces the matches of `pattern` in the text formats by `replacement`.
+    ///
+    /// The replacement may refer to the capture groups, as in [`regex::Regex::replace_all`].
+    #[cfg(feature = "regex")]
+    #[must_use]
+    pub fn redact(mut self, pattern: regex::Regex, replacement: impl Into<String>) -> Self {
+        self.redactions.push((pattern, replacement.into()));
+        self
+    }
+
+    fn applies_to(&self, direction: ClipboardDirection) -> bool {
+        self.direction.map_or(true, |only| only == direction)
+    }
+
+    fn is_blocked(&self, format: &ClipboardFormat) -> bool {
+        if format.id().is_registered() {
+            let Some(name) = format.name() else {
+                return false;
+            };
+
+            (self.block_files && name.value() == FILE_LIST_FORMAT_NAME) || self.blocked_format_names.contains(name)
+        } else {
+            (self.block_files && format.id() == ClipboardFormatId::CF_HDROP)
+                || self.blocked_formats.contains(&format.id())
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    fn redacted(&self, format: ClipboardFormatId, data: &[u8]) -> Option<Vec<u8>> {
+        use std::borrow::Cow;
+
+        use crate::pdu::FormatDataResponse;
+
+        let unicode = match format {
+            ClipboardFormatId::CF_UNICODETEXT => true,
+            ClipboardFormatId::CF_TEXT | ClipboardFormatId::CF_OEMTEXT => false,
+            _ => return None,
+        };
+
+        if self.redactions.is_empty() {
+            return None;
+        }
+
+        let response = FormatDataResponse::new_data(data);
+        let text = if unicode {
+            response.to_unicode_string()
+        } else {
+            response.to_string()
+        }
+        .ok()?;
+
+        let mut redacted = text;
+        let mut is_redacted = false;
+        for (pattern, replacement) in &self.redactions {
+            let replaced = match pattern.replace_all(&redacted, replacement.as_str()) {
+                Cow::Owned(replaced) => Some(replaced),
+                Cow::Borrowed(_) => None,
+            };
+
+            if let Some(replaced) = replaced {
+                redacted = replaced;
+                is_redacted = true;
+            }
+        }
+
+        if !is_redacted {
+            return None;
+        }
+
+        let response = if unicode {
+            FormatDataResponse::new_unicode_string(&redacted)
+        } else {
+            FormatDataResponse::new_string(&redacted)
+        };
+
+        Some(response.into_data().into_owned())
+    }
+}
+
+impl ClipboardPolicy for ClipboardFilter {
+    fn filter_formats(&self, direction: ClipboardDirection, formats: &mut Vec<ClipboardFormat>) {
+        if self.applies_to(direction) {
+            formats.retain(|format| !self.is_blocked(format));
+        }
+    }
+
+    fn check_format_data(
+        &self,
+        direction: ClipboardDirection,
+        format: Option<ClipboardFormatId>,
+        data: &[u8],
+    ) -> PolicyDecision {
+        if !self.applies_to(direction) {
+            return PolicyDecision::Allow;
+        }
+
+        if self.max_size.is_some_and(|max_size| data.len() > max_size) {
+            return PolicyDecision::Reject;
+        }
+
+        let Some(format) = format else {
+            return PolicyDecision::Allow;
+        };
+
+        // The remote may request a format which was not advertised.
+        if !format.is_registered() && self.is_blocked(&ClipboardFormat::new(format)) {
+            return PolicyDecision::Reject;
+        }
+
+        #[cfg(feature = "regex")]
+        if let Some(redacted) = self.redacted(format, data) {
+            return PolicyDecision::Replace(redacted);
+        }
+
+        PolicyDecision::Allow
+    }
+
+    fn check_file_contents(&self, direction: ClipboardDirection, _: &[u8]) -> PolicyDecision {
+        if self.block_files && self.applies_to(direction) {
+            PolicyDecision::Reject
+        } else {
+            PolicyDecision::Allow
+        }
+    }
+}
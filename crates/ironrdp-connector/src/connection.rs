@@ -4,6 +4,8 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use ironrdp_core::{decode, encode_vec, Encode, WriteBuf};
+use ironrdp_dvc::{DrdynvcClient, DvcProcessor};
+use ironrdp_pdu::rdp::capability_sets::{self, CapabilitySet};
 use ironrdp_pdu::rdp::client_info::{OptionalSystemTime, TimezoneInfo};
 use ironrdp_pdu::x224::X224;
 use ironrdp_pdu::{gcc, mcs, nego, rdp, PduHint};
@@ -11,10 +13,11 @@ use ironrdp_svc::{StaticChannelSet, StaticVirtualChannel, SvcClientProcessor};
 
 use crate::channel_connection::{ChannelConnectionSequence, ChannelConnectionState};
 use crate::connection_activation::{ConnectionActivationSequence, ConnectionActivationState};
+use crate::credssp::ChannelBindings;
 use crate::license_exchange::{LicenseExchangeSequence, NoopLicenseCache};
 use crate::{
-    encode_x224_packet, Config, ConnectorError, ConnectorErrorExt as _, ConnectorResult, DesktopSize, Sequence, State,
-    Written,
+    encode_x224_packet, Config, ConnectorError, ConnectorErrorExt as _, ConnectorResult, DesktopSize, ErrorClass,
+    Sequence, State, Written,
 };
 
 #[derive(Debug)]
@@ -26,9 +29,46 @@ pub struct ConnectionResult {
     pub desktop_size: DesktopSize,
     pub no_server_pointer: bool,
     pub pointer_software_rendering: bool,
+    /// The message channel joined by the client, if requested and offered by the server.
+    pub message_channel_id: Option<u16>,
+    /// The multitransport channels supported by both the client and the server, if any.
+    pub multitransport_flags: Option<gcc::MultiTransportFlags>,
+    /// The static channels declared without a processor, with their channel ID.
+    pub named_static_channels: Vec<(gcc::ChannelName, u16)>,
+    pub negotiation: NegotiationInfo,
     pub connection_activation: ConnectionActivationSequence,
 }
 
+/// Details of the connection negotiated with the server, e.g. to display them or to make policy decisions.
+#[derive(Debug, Clone)]
+pub struct NegotiationInfo {
+    /// The security protocol selected by the server.
+    pub selected_protocol: nego::SecurityProtocol,
+    /// The DER-encoded certificate chain of the server, starting with the certificate of the server itself.
+    ///
+    /// The TLS upgrade is not performed by the connector, so the chain is empty unless attached with
+    /// [`ClientConnector::attach_server_certificates`].
+    pub server_certificates: Vec<Vec<u8>>,
+    /// The static channels joined, with their channel ID.
+    pub channels: Vec<(gcc::ChannelName, u16)>,
+    /// The capability sets advertised by the server in the Demand Active PDU.
+    pub server_capability_sets: Vec<CapabilitySet>,
+    pub desktop_size: DesktopSize,
+}
+
+impl NegotiationInfo {
+    /// Returns the bitmap codecs supported by the server.
+    pub fn server_bitmap_codecs(&self) -> &[capability_sets::Codec] {
+        self.server_capability_sets
+            .iter()
+            .find_map(|capability_set| match capability_set {
+                CapabilitySet::BitmapCodecs(codecs) => Some(codecs.0.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Default, Debug)]
 #[non_exhaustive]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
@@ -125,6 +165,20 @@ pub struct ClientConnector {
     pub state: ClientConnectorState,
     pub client_addr: Option<SocketAddr>,
     pub static_channels: StaticChannelSet,
+    /// The static channels declared without a processor, joined along with the other static channels.
+    named_static_channels: Vec<gcc::ChannelName>,
+    /// The IDs given by the server to the static channels declared without a processor.
+    named_static_channel_ids: Vec<u16>,
+    /// The security protocol selected by the server.
+    selected_protocol: nego::SecurityProtocol,
+    /// The DER-encoded certificate chain of the server, as attached after the TLS upgrade.
+    server_certificates: Vec<Vec<u8>>,
+    /// The message channel offered by the server in the basic settings exchange.
+    message_channel_id: Option<u16>,
+    /// The multitransport channels accepted by the server in the basic settings exchange.
+    multitransport_flags: Option<gcc::MultiTransportFlags>,
+    /// The channel bindings of the TLS connection, used by CredSSP.
+    channel_bindings: Option<ChannelBindings>,
 }
 
 impl ClientConnector {
@@ -134,6 +188,13 @@ impl ClientConnector {
             state: ClientConnectorState::ConnectionInitiationSendRequest,
             client_addr: None,
             static_channels: StaticChannelSet::new(),
+            named_static_channels: Vec::new(),
+            named_static_channel_ids: Vec::new(),
+            selected_protocol: nego::SecurityProtocol::empty(),
+            server_certificates: Vec::new(),
+            message_channel_id: None,
+            multitransport_flags: None,
+            channel_bindings: None,
         }
     }
 
@@ -149,6 +210,20 @@ impl ClientConnector {
         self.client_addr = Some(addr);
     }
 
+    /// Sets the channel bindings of the TLS connection, once upgraded.
+    pub fn attach_channel_bindings(&mut self, channel_bindings: ChannelBindings) {
+        self.channel_bindings = Some(channel_bindings);
+    }
+
+    pub fn channel_bindings(&self) -> Option<&ChannelBindings> {
+        self.channel_bindings.as_ref()
+    }
+
+    /// Sets the DER-encoded certificate chain of the server, once upgraded, reported in the [`NegotiationInfo`].
+    pub fn attach_server_certificates(&mut self, server_certificates: Vec<Vec<u8>>) {
+        self.server_certificates = server_certificates;
+    }
+
     #[must_use]
     pub fn with_static_channel<T>(mut self, channel: T) -> Self
     where
@@ -165,6 +240,52 @@ impl ClientConnector {
         self.static_channels.insert(channel);
     }
 
+    /// Declares a static channel without a processor, so that it is joined along with the other static channels.
+    ///
+    /// This is useful when the server only checks the presence of the channel. The data received on the channel is
+    /// ignored, a processor must be attached with [`Self::with_static_channel`] to handle it.
+    #[must_use]
+    pub fn with_static_channel_name(mut self, name: gcc::ChannelName) -> Self {
+        self.attach_static_channel_name(name);
+        self
+    }
+
+    /// Declares a static channel without a processor, so that it is joined along with the other static channels.
+    pub fn attach_static_channel_name(&mut self, name: gcc::ChannelName) {
+        if !self.named_static_channels.contains(&name) {
+            self.named_static_channels.push(name);
+        }
+    }
+
+    /// Registers a dynamic virtual channel listener.
+    ///
+    /// The DRDYNVC static channel carrying the dynamic channels is added if not already attached. Attaching a
+    /// [`DrdynvcClient`] afterwards replaces the listeners registered so far.
+    #[must_use]
+    pub fn with_dynamic_channel<T>(mut self, channel: T) -> Self
+    where
+        T: DvcProcessor + 'static,
+    {
+        self.attach_dynamic_channel(channel);
+        self
+    }
+
+    /// Registers a dynamic virtual channel listener.
+    pub fn attach_dynamic_channel<T>(&mut self, channel: T)
+    where
+        T: DvcProcessor + 'static,
+    {
+        if self.static_channels.get_by_type::<DrdynvcClient>().is_none() {
+            self.static_channels.insert(DrdynvcClient::new());
+        }
+
+        self.static_channels
+            .get_by_type_mut::<DrdynvcClient>()
+            .and_then(|svc| svc.channel_processor_downcast_mut::<DrdynvcClient>())
+            .expect("DRDYNVC channel is attached")
+            .attach_dynamic_channel(channel);
+    }
+
     pub fn should_perform_security_upgrade(&self) -> bool {
         matches!(self.state, ClientConnectorState::EnhancedSecurityUpgrade { .. })
     }
@@ -248,7 +369,13 @@ impl Sequence for ClientConnector {
                 }
 
                 if security_protocol.is_standard_rdp_security() {
-                    return Err(reason_err!("Initiation", "standard RDP security is not supported",));
+                    return Err(reason_err!("Initiation", "standard RDP security is not supported",)
+                        .with_class(ErrorClass::Negotiation));
+                }
+
+                let mut flags = nego::RequestFlags::empty();
+                if self.config.remote_credential_guard {
+                    flags.insert(nego::RequestFlags::REDIRECTED_AUTHENTICATION_MODE_REQUIRED);
                 }
 
                 let connection_request = nego::ConnectionRequest {
@@ -258,7 +385,7 @@ impl Sequence for ClientConnector {
                             .username()
                             .map(|username| nego::NegoRequestData::cookie(username.to_owned()))
                     }),
-                    flags: nego::RequestFlags::empty(),
+                    flags,
                     protocol: security_protocol,
                 };
 
@@ -285,17 +412,32 @@ impl Sequence for ClientConnector {
                     nego::ConnectionConfirm::Response { flags, protocol } => (flags, protocol),
                     nego::ConnectionConfirm::Failure { code } => {
                         error!(?code, "Received connection failure code");
-                        return Err(reason_err!("Initiation", "{code}"));
+                        return Err(reason_err!("Initiation", "{code}").with_class(ErrorClass::Negotiation));
                     }
                 };
 
                 info!(?selected_protocol, ?flags, "Server confirmed connection");
 
+                self.selected_protocol = selected_protocol;
+
                 if !selected_protocol.intersects(requested_protocol) {
                     return Err(reason_err!(
                         "Initiation",
                         "client advertised {requested_protocol}, but server selected {selected_protocol}",
-                    ));
+                    )
+                    .with_class(ErrorClass::Negotiation));
+                }
+
+                // The credentials are delegated by CredSSP, the password would be sent in the client info otherwise.
+                if self.config.remote_credential_guard
+                    && (!flags.contains(nego::ResponseFlags::REDIRECTED_AUTHENTICATION_MODE_SUPPORTED)
+                        || !selected_protocol
+                            .intersects(nego::SecurityProtocol::HYBRID | nego::SecurityProtocol::HYBRID_EX))
+                {
+                    return Err(
+                        reason_err!("Initiation", "server doesn't support Remote Credential Guard with NLA")
+                            .with_class(ErrorClass::Negotiation),
+                    );
                 }
 
                 (
@@ -332,8 +474,12 @@ impl Sequence for ClientConnector {
             ClientConnectorState::BasicSettingsExchangeSendInitial { selected_protocol } => {
                 debug!("Basic Settings Exchange");
 
-                let client_gcc_blocks =
-                    create_gcc_blocks(&self.config, selected_protocol, self.static_channels.values());
+                let client_gcc_blocks = create_gcc_blocks(
+                    &self.config,
+                    selected_protocol,
+                    self.static_channels.values(),
+                    &self.named_static_channels,
+                )?;
 
                 let connect_initial = mcs::ConnectInitial::with_gcc_blocks(client_gcc_blocks);
 
@@ -362,21 +508,36 @@ impl Sequence for ClientConnector {
                 if client_gcc_blocks.security == gcc::ClientSecurityData::no_security()
                     && server_gcc_blocks.security != gcc::ServerSecurityData::no_security()
                 {
-                    return Err(general_err!("can’t satisfy server security settings"));
+                    return Err(
+                        general_err!("can’t satisfy server security settings").with_class(ErrorClass::Negotiation)
+                    );
                 }
 
-                if server_gcc_blocks.message_channel.is_some() {
-                    warn!("Unexpected ServerMessageChannelData GCC block (not supported)");
+                match (&client_gcc_blocks.message_channel, server_gcc_blocks.message_channel) {
+                    (Some(_), Some(message_channel)) => {
+                        self.message_channel_id = Some(message_channel.mcs_message_channel_id);
+                    }
+                    (None, Some(_)) => warn!("Unexpected ServerMessageChannelData GCC block"),
+                    (Some(_), None) => debug!("Message channel not offered by the server"),
+                    (None, None) => {}
                 }
 
-                if server_gcc_blocks.multi_transport_channel.is_some() {
-                    warn!("Unexpected MultiTransportChannelData GCC block (not supported)");
+                match (
+                    &client_gcc_blocks.multi_transport_channel,
+                    server_gcc_blocks.multi_transport_channel,
+                ) {
+                    (Some(_), Some(multi_transport_channel)) => {
+                        self.multitransport_flags = Some(multi_transport_channel.flags);
+                    }
+                    (None, Some(_)) => warn!("Unexpected MultiTransportChannelData GCC block"),
+                    (Some(_), None) => debug!("Multitransport not supported by the server"),
+                    (None, None) => {}
                 }
 
                 let static_channel_ids = server_gcc_blocks.network.channel_ids;
                 let io_channel_id = server_gcc_blocks.network.io_channel;
 
-                debug!(?static_channel_ids, io_channel_id);
+                debug!(?static_channel_ids, io_channel_id, message_channel_id = ?self.message_channel_id);
 
                 let zipped: Vec<_> = self
                     .static_channels
@@ -388,6 +549,13 @@ impl Sequence for ClientConnector {
                     self.static_channels.attach_channel_id(channel, channel_id);
                 });
 
+                // The channels declared by name follow the ones with a processor in the Client Network Data.
+                self.named_static_channel_ids = static_channel_ids
+                    .iter()
+                    .skip(self.static_channels.type_ids().count())
+                    .copied()
+                    .collect();
+
                 let skip_channel_join = server_gcc_blocks
                     .core
                     .optional_data
@@ -401,7 +569,10 @@ impl Sequence for ClientConnector {
                         channel_connection: if skip_channel_join {
                             ChannelConnectionSequence::skip_channel_join()
                         } else {
-                            ChannelConnectionSequence::new(io_channel_id, static_channel_ids)
+                            let mut channel_ids = static_channel_ids;
+                            // The message channel must be joined as well.
+                            channel_ids.extend(self.message_channel_id);
+                            ChannelConnectionSequence::new(io_channel_id, channel_ids)
                         },
                     },
                 )
@@ -568,17 +739,47 @@ impl Sequence for ClientConnector {
                             desktop_size,
                             no_server_pointer,
                             pointer_software_rendering,
-                        } => ClientConnectorState::Connected {
-                            result: ConnectionResult {
-                                io_channel_id,
-                                user_channel_id,
-                                static_channels: mem::take(&mut self.static_channels),
-                                desktop_size,
-                                no_server_pointer,
-                                pointer_software_rendering,
-                                connection_activation,
-                            },
-                        },
+                        } => {
+                            let static_channels = mem::take(&mut self.static_channels);
+
+                            let named_static_channels: Vec<_> = self
+                                .named_static_channels
+                                .iter()
+                                .cloned()
+                                .zip(self.named_static_channel_ids.iter().copied())
+                                .collect();
+
+                            let channels = static_channels
+                                .iter()
+                                .filter_map(|(type_id, svc)| {
+                                    let channel_id = static_channels.get_channel_id_by_type_id(type_id)?;
+                                    Some((svc.channel_name(), channel_id))
+                                })
+                                .chain(named_static_channels.iter().cloned())
+                                .collect();
+
+                            ClientConnectorState::Connected {
+                                result: ConnectionResult {
+                                    io_channel_id,
+                                    user_channel_id,
+                                    static_channels,
+                                    desktop_size,
+                                    no_server_pointer,
+                                    pointer_software_rendering,
+                                    message_channel_id: self.message_channel_id,
+                                    multitransport_flags: self.multitransport_flags,
+                                    named_static_channels,
+                                    negotiation: NegotiationInfo {
+                                        selected_protocol: self.selected_protocol,
+                                        server_certificates: mem::take(&mut self.server_certificates),
+                                        channels,
+                                        server_capability_sets: connection_activation.server_capability_sets().to_vec(),
+                                        desktop_size,
+                                    },
+                                    connection_activation,
+                                },
+                            }
+                        }
                         _ => return Err(general_err!("invalid state (this is a bug)")),
                     }
                 };
@@ -621,7 +822,8 @@ fn create_gcc_blocks<'a>(
     config: &Config,
     selected_protocol: nego::SecurityProtocol,
     static_channels: impl Iterator<Item = &'a StaticVirtualChannel>,
-) -> gcc::ClientGccBlocks {
+    named_static_channels: &[gcc::ChannelName],
+) -> ConnectorResult<gcc::ClientGccBlocks> {
     use ironrdp_pdu::gcc::*;
 
     let max_color_depth = config.bitmap.as_ref().map(|bitmap| bitmap.color_depth).unwrap_or(32);
@@ -636,9 +838,15 @@ fn create_gcc_blocks<'a>(
 
     let channels = static_channels
         .map(ironrdp_svc::make_channel_definition)
+        .chain(named_static_channels.iter().map(|name| ChannelDef {
+            name: name.clone(),
+            options: ChannelOptions::empty(),
+        }))
         .collect::<Vec<_>>();
 
-    ClientGccBlocks {
+    let (monitor, monitor_extended) = create_monitor_blocks(&config.monitors)?;
+
+    Ok(ClientGccBlocks {
         core: ClientCoreData {
             version: RdpVersion::V5_PLUS,
             desktop_width: config.desktop_size.width,
@@ -662,7 +870,8 @@ fn create_gcc_blocks<'a>(
                     let mut early_capability_flags = ClientEarlyCapabilityFlags::VALID_CONNECTION_TYPE
                         | ClientEarlyCapabilityFlags::SUPPORT_ERR_INFO_PDU
                         | ClientEarlyCapabilityFlags::STRONG_ASYMMETRIC_KEYS
-                        | ClientEarlyCapabilityFlags::SUPPORT_SKIP_CHANNELJOIN;
+                        | ClientEarlyCapabilityFlags::SUPPORT_SKIP_CHANNELJOIN
+                        | ClientEarlyCapabilityFlags::SUPPORT_HEART_BEAT_PDU;
 
                     // TODO(#136): support for ClientEarlyCapabilityFlags::SUPPORT_STATUS_INFO_PDU
 
@@ -701,13 +910,85 @@ fn create_gcc_blocks<'a>(
         },
         // TODO(#139): support for Some(ClientClusterData { flags: RedirectionFlags::REDIRECTION_SUPPORTED, redirection_version: RedirectionVersion::V4, redirected_session_id: 0, }),
         cluster: None,
-        monitor: None,
-        // TODO(#140): support for Client Message Channel Data (https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-rdpbcgr/f50e791c-de03-4b25-b17e-e914c9020bc3)
-        message_channel: None,
-        // TODO(#140): support for Some(MultiTransportChannelData { flags: MultiTransportFlags::empty(), })
-        multi_transport_channel: None,
-        monitor_extended: None,
+        monitor,
+        message_channel: config.message_channel.then_some(ClientMessageChannelData),
+        multi_transport_channel: config
+            .multitransport_flags
+            .map(|flags| MultiTransportChannelData { flags }),
+        monitor_extended,
+    })
+}
+
+/// Creates the Client Monitor Data and Client Monitor Extended Data GCC blocks, if there are monitors.
+fn create_monitor_blocks(
+    monitors: &[crate::MonitorConfig],
+) -> ConnectorResult<(Option<gcc::ClientMonitorData>, Option<gcc::ClientMonitorExtendedData>)> {
+    const MONITOR_COUNT_MAX: usize = 16;
+
+    if monitors.is_empty() {
+        return Ok((None, None));
+    }
+
+    if monitors.len() > MONITOR_COUNT_MAX {
+        return Err(reason_err!(
+            "monitors",
+            "at most {MONITOR_COUNT_MAX} monitors are supported"
+        ));
     }
+
+    if monitors.iter().filter(|monitor| monitor.is_primary).count() != 1 {
+        return Err(reason_err!("monitors", "exactly one monitor must be primary"));
+    }
+
+    let monitor_data = monitors
+        .iter()
+        .map(|monitor| {
+            let width = i32::try_from(monitor.width)
+                .ok()
+                .filter(|&width| width > 0)
+                .ok_or_else(|| reason_err!("monitors", "invalid monitor width: {}", monitor.width))?;
+            let height = i32::try_from(monitor.height)
+                .ok()
+                .filter(|&height| height > 0)
+                .ok_or_else(|| reason_err!("monitors", "invalid monitor height: {}", monitor.height))?;
+
+            // The edges are inclusive.
+            let right = monitor.left.checked_add(width - 1);
+            let bottom = monitor.top.checked_add(height - 1);
+
+            let (Some(right), Some(bottom)) = (right, bottom) else {
+                return Err(reason_err!("monitors", "monitor out of the virtual desktop"));
+            };
+
+            Ok(gcc::Monitor {
+                left: monitor.left,
+                top: monitor.top,
+                right,
+                bottom,
+                flags: if monitor.is_primary {
+                    gcc::MonitorFlags::PRIMARY
+                } else {
+                    gcc::MonitorFlags::empty()
+                },
+            })
+        })
+        .collect::<ConnectorResult<Vec<_>>>()?;
+
+    let extended_monitors_info = monitors
+        .iter()
+        .map(|monitor| gcc::ExtendedMonitorInfo {
+            physical_width: monitor.physical_width,
+            physical_height: monitor.physical_height,
+            orientation: monitor.orientation,
+            desktop_scale_factor: monitor.desktop_scale_factor,
+            device_scale_factor: monitor.device_scale_factor,
+        })
+        .collect();
+
+    Ok((
+        Some(gcc::ClientMonitorData { monitors: monitor_data }),
+        Some(gcc::ClientMonitorExtendedData { extended_monitors_info }),
+    ))
 }
 
 fn create_client_info_pdu(config: &Config, client_addr: &SocketAddr) -> rdp::ClientInfoPdu {
@@ -745,6 +1026,10 @@ fn create_client_info_pdu(config: &Config, client_addr: &SocketAddr) -> rdp::Cli
         flags |= ClientInfoFlags::NO_AUDIO_PLAYBACK;
     }
 
+    if config.rail {
+        flags |= ClientInfoFlags::RAIL;
+    }
+
     let client_info = ClientInfo {
         credentials: Credentials {
             username: config.credentials.username().unwrap_or("").to_owned(),
@@ -21,6 +21,8 @@ use crate::{legacy, Config, ConnectionFinalizationSequence, ConnectorResult, Des
 pub struct ConnectionActivationSequence {
     pub state: ConnectionActivationState,
     config: Config,
+    /// The capability sets advertised by the server in the last Demand Active PDU.
+    server_capability_sets: Vec<CapabilitySet>,
 }
 
 impl ConnectionActivationSequence {
@@ -31,9 +33,15 @@ impl ConnectionActivationSequence {
                 user_channel_id,
             },
             config,
+            server_capability_sets: Vec::new(),
         }
     }
 
+    /// Returns the capability sets advertised by the server in the last Demand Active PDU.
+    pub fn server_capability_sets(&self) -> &[CapabilitySet] {
+        &self.server_capability_sets
+    }
+
     #[must_use]
     pub fn reset_clone(&self) -> Self {
         self.clone().reset()
@@ -151,6 +159,8 @@ impl Sequence for ConnectionActivationSequence {
                         height: self.config.desktop_size.height,
                     });
 
+                self.server_capability_sets.clone_from(&capability_sets);
+
                 let client_confirm_active = rdp::headers::ShareControlPdu::ClientConfirmActive(
                     create_client_confirm_active(&self.config, capability_sets, desktop_size),
                 );
@@ -373,6 +383,11 @@ fn create_client_confirm_active(
         }),
     ]);
 
+    if config.rail {
+        server_capability_sets.push(CapabilitySet::Rail(rail_capabilities()));
+        server_capability_sets.push(CapabilitySet::WindowList(window_list_capabilities()));
+    }
+
     if !server_capability_sets
         .iter()
         .any(|c| matches!(&c, CapabilitySet::MultiFragmentUpdate(_)))
@@ -390,3 +405,18 @@ fn create_client_confirm_active(
         },
     }
 }
+
+/// Remote Programs Capability Set (TS_RAIL_CAPABILITYSET), with `TS_RAIL_LEVEL_SUPPORTED` and
+/// `TS_RAIL_LEVEL_LANGUAGE_IME_SYNC_SUPPORTED`.
+fn rail_capabilities() -> Vec<u8> {
+    0x0000_0009u32.to_le_bytes().to_vec()
+}
+
+/// Window List Capability Set (TS_WINDOW_CAPABILITYSET), with `TS_WINDOW_LEVEL_SUPPORTED`.
+fn window_list_capabilities() -> Vec<u8> {
+    let mut capset = 0x0000_0001u32.to_le_bytes().to_vec();
+    // NumIconCaches and NumIconCacheEntries, the same as FreeRDP.
+    capset.push(3);
+    capset.extend_from_slice(&12u16.to_le_bytes());
+    capset
+}
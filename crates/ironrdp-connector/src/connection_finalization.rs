@@ -6,7 +6,7 @@ use ironrdp_pdu::rdp::headers::ShareDataPdu;
 use ironrdp_pdu::rdp::{finalization_messages, server_error_info};
 use ironrdp_pdu::PduHint;
 
-use crate::{legacy, ConnectorResult, Sequence, State, Written};
+use crate::{legacy, ConnectorError, ConnectorErrorKind, ConnectorResult, Sequence, State, Written};
 
 #[derive(Default, Debug, Clone)]
 #[non_exhaustive]
@@ -202,10 +202,9 @@ impl Sequence for ConnectionFinalizationSequence {
                                 server_error_info::ProtocolIndependentCode::None,
                             ) => ConnectionFinalizationState::WaitForResponse,
                             _ => {
-                                return Err(reason_err!(
+                                return Err(ConnectorError::new(
                                     "ServerSetErrorInfo",
-                                    "server returned error info: {}",
-                                    error_info.description()
+                                    ConnectorErrorKind::ServerErrorInfo(error_info),
                                 ));
                             }
                         }
@@ -1,18 +1,49 @@
+#[cfg(all(unix, feature = "gssapi"))]
+mod gssapi;
+#[cfg(all(windows, feature = "native-sspi"))]
+mod native_sspi;
+mod system;
+mod ts_credentials;
+
+use std::sync::Arc;
+
 use ironrdp_core::{other_err, WriteBuf};
 use ironrdp_pdu::{nego, PduHint};
 use picky::key::PrivateKey;
 use picky_asn1_x509::{oids, Certificate, ExtensionView, GeneralName};
+use sha2::{Digest as _, Sha256, Sha384, Sha512};
 use sspi::credssp::{self, ClientState, CredSspClient};
 use sspi::generator::{Generator, NetworkRequest};
 use sspi::negotiate::ProtocolConfig;
 use sspi::Username;
 
+#[cfg(all(unix, feature = "gssapi"))]
+pub use self::gssapi::Gssapi;
+#[cfg(all(windows, feature = "native-sspi"))]
+pub use self::native_sspi::NativeSspi;
+use self::system::SystemCredsspClient;
+pub use self::system::{default_system_kerberos, ContextStep, SecurityContext, SystemKerberos};
+pub use self::ts_credentials::{RemoteGuardCreds, RemoteGuardPackageCred};
 use crate::{ConnectorError, ConnectorErrorKind, ConnectorResult, Credentials, ServerName, Written};
 
 #[derive(Debug, Clone, Default)]
 pub struct KerberosConfig {
     pub kdc_proxy_url: Option<url::Url>,
     pub hostname: Option<String>,
+    pub backend: KerberosBackend,
+}
+
+/// Kerberos implementation used by the client.
+#[derive(Debug, Clone, Default)]
+pub enum KerberosBackend {
+    /// The system implementation for single sign-on (empty username), the built-in one otherwise.
+    #[default]
+    Auto,
+    /// The implementation of sspi-rs, talking to the KDC (or to the KDC proxy).
+    Builtin,
+    /// The implementation of the system (see [`default_system_kerberos`]).
+    System,
+    Custom(Arc<dyn SystemKerberos>),
 }
 
 impl KerberosConfig {
@@ -24,6 +55,7 @@ impl KerberosConfig {
         Ok(Self {
             kdc_proxy_url,
             hostname,
+            backend: KerberosBackend::default(),
         })
     }
 }
@@ -37,6 +69,45 @@ impl From<KerberosConfig> for sspi::KerberosConfig {
     }
 }
 
+/// Channel bindings binding the authentication to the TLS connection (RFC 5929), as required by the servers
+/// enforcing the Extended Protection for Authentication.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelBindings {
+    application_data: Vec<u8>,
+}
+
+impl ChannelBindings {
+    /// `tls-server-end-point` channel bindings, from the DER-encoded certificate of the server.
+    pub fn tls_server_end_point(server_certificate: &[u8]) -> ConnectorResult<Self> {
+        let cert: Certificate =
+            picky_asn1_der::from_bytes(server_certificate).map_err(|e| custom_err!("server certificate", e))?;
+
+        let signature_algorithm = cert.signature_algorithm.oid();
+
+        // The hash function of the signature is used, except MD5 and SHA-1 which are replaced by SHA-256.
+        let hash = if *signature_algorithm == oids::sha384_with_rsa_encryption()
+            || *signature_algorithm == oids::ecdsa_with_sha384()
+        {
+            Sha384::digest(server_certificate).to_vec()
+        } else if *signature_algorithm == oids::sha512_with_rsa_encryption()
+            || *signature_algorithm == oids::ecdsa_with_sha512()
+        {
+            Sha512::digest(server_certificate).to_vec()
+        } else {
+            Sha256::digest(server_certificate).to_vec()
+        };
+
+        let mut application_data = b"tls-server-end-point:".to_vec();
+        application_data.extend_from_slice(&hash);
+
+        Ok(Self { application_data })
+    }
+
+    pub fn application_data(&self) -> &[u8] {
+        &self.application_data
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 struct CredsspTsRequestHint;
 
@@ -67,11 +138,17 @@ pub type CredsspProcessGenerator<'a> = Generator<'a, NetworkRequest, sspi::Resul
 
 #[derive(Debug)]
 pub struct CredsspSequence {
-    client: CredSspClient,
+    client: Client,
     state: CredsspState,
     selected_protocol: nego::SecurityProtocol,
 }
 
+#[derive(Debug)]
+enum Client {
+    Sspi(CredSspClient),
+    System(SystemCredsspClient),
+}
+
 #[derive(Debug, PartialEq)]
 pub(crate) enum CredsspState {
     Ongoing,
@@ -88,15 +165,51 @@ impl CredsspSequence {
         }
     }
 
-    /// `server_name` must be the actual target server hostname (as opposed to the proxy)
+    /// `server_name` must be the actual target server hostname (as opposed to the proxy), and `channel_bindings`
+    /// are computed from the certificate of the TLS connection with the actual target server, if any.
+    ///
+    /// With `remote_credential_guard`, the password is not delegated to the server: Kerberos credentials are
+    /// delegated instead (TSRemoteGuardCreds), which requires a system Kerberos implementation supporting it.
     pub fn init(
         credentials: Credentials,
         domain: Option<&str>,
+        remote_credential_guard: bool,
         protocol: nego::SecurityProtocol,
         server_name: ServerName,
         server_public_key: Vec<u8>,
         kerberos_config: Option<KerberosConfig>,
+        channel_bindings: Option<ChannelBindings>,
     ) -> ConnectorResult<(Self, credssp::TsRequest)> {
+        if let Some(client) = init_system_client(
+            &credentials,
+            domain,
+            remote_credential_guard,
+            &server_name,
+            &server_public_key,
+            kerberos_config.as_ref(),
+            channel_bindings.as_ref(),
+        )? {
+            let sequence = Self {
+                client: Client::System(client),
+                state: CredsspState::Ongoing,
+                selected_protocol: protocol,
+            };
+
+            return Ok((sequence, credssp::TsRequest::default()));
+        }
+
+        if remote_credential_guard {
+            return Err(general_err!(
+                "Remote Credential Guard requires the system Kerberos, the built-in CredSSP client delegates the password"
+            ));
+        }
+
+        if channel_bindings.is_some() {
+            // The built-in CredSSP client doesn't take channel bindings: servers enforcing the Extended Protection
+            // for Authentication need a system Kerberos implementation.
+            warn!("Channel bindings are not supported by the built-in CredSSP client");
+        }
+
         let credentials: sspi::Credentials = match &credentials {
             Credentials::UsernamePassword { username, password } => {
                 let username = Username::new(username, domain).map_err(|e| custom_err!("invalid username", e))?;
@@ -160,7 +273,7 @@ impl CredsspSequence {
         .map_err(|e| ConnectorError::new("CredSSP", ConnectorErrorKind::Credssp(e)))?;
 
         let sequence = Self {
-            client,
+            client: Client::Sspi(client),
             state: CredsspState::Ongoing,
             selected_protocol: protocol,
         };
@@ -202,7 +315,17 @@ impl CredsspSequence {
     }
 
     pub fn process_ts_request(&mut self, request: credssp::TsRequest) -> CredsspProcessGenerator<'_> {
-        self.client.process(request)
+        match &mut self.client {
+            Client::Sspi(client) => client.process(request),
+            Client::System(client) => {
+                // The system implementation talks to the KDC by itself.
+                let result = client
+                    .process(request)
+                    .map_err(|e| sspi::Error::new(sspi::ErrorKind::InternalError, e.to_string()));
+
+                Generator::new(move |_| async move { result })
+            }
+        }
     }
 
     pub fn handle_process_result(&mut self, result: ClientState, output: &mut WriteBuf) -> ConnectorResult<Written> {
@@ -236,6 +359,52 @@ impl CredsspSequence {
     }
 }
 
+/// Returns the CredSSP client of the system Kerberos, when selected and usable.
+fn init_system_client(
+    credentials: &Credentials,
+    domain: Option<&str>,
+    remote_credential_guard: bool,
+    server_name: &ServerName,
+    server_public_key: &[u8],
+    kerberos_config: Option<&KerberosConfig>,
+    channel_bindings: Option<&ChannelBindings>,
+) -> ConnectorResult<Option<SystemCredsspClient>> {
+    let single_sign_on = matches!(credentials, Credentials::UsernamePassword { username, .. } if username.is_empty());
+
+    let backend: Arc<dyn SystemKerberos> = match kerberos_config.map(|config| &config.backend) {
+        Some(KerberosBackend::Builtin) => return Ok(None),
+        Some(KerberosBackend::System) => default_system_kerberos()
+            .ok_or_else(|| general_err!("no Kerberos implementation on this system"))?
+            .into(),
+        Some(KerberosBackend::Custom(backend)) => Arc::clone(backend),
+        Some(KerberosBackend::Auto) | None => match default_system_kerberos() {
+            // Only the system Kerberos provides the credentials of Remote Credential Guard.
+            Some(backend) if single_sign_on || remote_credential_guard => backend.into(),
+            _ => return Ok(None),
+        },
+    };
+
+    let Some((context, first_step)) = backend.initiate(server_name.as_str(), channel_bindings)? else {
+        info!(
+            backend = backend.name(),
+            "No system credentials, falling back to the built-in Kerberos"
+        );
+        return Ok(None);
+    };
+
+    debug!(backend = backend.name(), "Using the system Kerberos");
+
+    SystemCredsspClient::new(
+        context,
+        first_step,
+        server_public_key.to_vec(),
+        credentials,
+        domain,
+        remote_credential_guard,
+    )
+    .map(Some)
+}
+
 fn extract_user_name(cert: &Certificate) -> Option<String> {
     cert.tbs_certificate.subject.find_common_name().map(ToString::to_string)
 }
@@ -0,0 +1,327 @@
+//! Kerberos through the GSSAPI of the system (MIT or Heimdal krb5).
+
+use core::ffi::c_void;
+use core::{fmt, ptr};
+
+use super::system::{ContextStep, SecurityContext, SystemKerberos};
+use super::ChannelBindings;
+use crate::ConnectorResult;
+
+#[expect(non_camel_case_types, reason = "GSSAPI naming")]
+type OM_uint32 = u32;
+
+#[expect(non_camel_case_types, reason = "GSSAPI naming")]
+type gss_name_t = *mut c_void;
+
+#[expect(non_camel_case_types, reason = "GSSAPI naming")]
+type gss_ctx_id_t = *mut c_void;
+
+#[repr(C)]
+struct gss_buffer_desc {
+    length: usize,
+    value: *mut c_void,
+}
+
+impl gss_buffer_desc {
+    const EMPTY: Self = Self {
+        length: 0,
+        value: ptr::null_mut(),
+    };
+
+    fn from_slice(data: &[u8]) -> Self {
+        Self {
+            length: data.len(),
+            value: data.as_ptr().cast_mut().cast(),
+        }
+    }
+}
+
+#[repr(C)]
+struct gss_channel_bindings_struct {
+    initiator_addrtype: OM_uint32,
+    initiator_address: gss_buffer_desc,
+    acceptor_addrtype: OM_uint32,
+    acceptor_address: gss_buffer_desc,
+    application_data: gss_buffer_desc,
+}
+
+#[repr(C)]
+struct gss_OID_desc {
+    length: OM_uint32,
+    elements: *mut c_void,
+}
+
+const GSS_S_COMPLETE: OM_uint32 = 0;
+const GSS_S_CONTINUE_NEEDED: OM_uint32 = 1;
+const GSS_S_CALLING_ERROR_MASK: OM_uint32 = 0xFF00_0000;
+const GSS_S_ROUTINE_ERROR_MASK: OM_uint32 = 0x00FF_0000;
+
+const GSS_C_AF_UNSPEC: OM_uint32 = 0;
+
+const GSS_C_MUTUAL_FLAG: OM_uint32 = 2;
+const GSS_C_SEQUENCE_FLAG: OM_uint32 = 8;
+const GSS_C_CONF_FLAG: OM_uint32 = 16;
+const GSS_C_INTEG_FLAG: OM_uint32 = 32;
+
+/// 1.3.6.1.5.5.2
+const SPNEGO_OID: [u8; 6] = [0x2B, 0x06, 0x01, 0x05, 0x05, 0x02];
+
+#[link(name = "gssapi_krb5")]
+extern "C" {
+    static GSS_C_NT_HOSTBASED_SERVICE: *mut gss_OID_desc;
+
+    fn gss_import_name(
+        minor_status: *mut OM_uint32,
+        input_name_buffer: *mut gss_buffer_desc,
+        input_name_type: *mut gss_OID_desc,
+        output_name: *mut gss_name_t,
+    ) -> OM_uint32;
+
+    fn gss_init_sec_context(
+        minor_status: *mut OM_uint32,
+        claimant_cred_handle: *mut c_void,
+        context_handle: *mut gss_ctx_id_t,
+        target_name: gss_name_t,
+        mech_type: *mut gss_OID_desc,
+        req_flags: OM_uint32,
+        time_req: OM_uint32,
+        input_chan_bindings: *mut gss_channel_bindings_struct,
+        input_token: *mut gss_buffer_desc,
+        actual_mech_type: *mut *mut gss_OID_desc,
+        output_token: *mut gss_buffer_desc,
+        ret_flags: *mut OM_uint32,
+        time_rec: *mut OM_uint32,
+    ) -> OM_uint32;
+
+    fn gss_wrap(
+        minor_status: *mut OM_uint32,
+        context_handle: gss_ctx_id_t,
+        conf_req_flag: i32,
+        qop_req: OM_uint32,
+        input_message_buffer: *mut gss_buffer_desc,
+        conf_state: *mut i32,
+        output_message_buffer: *mut gss_buffer_desc,
+    ) -> OM_uint32;
+
+    fn gss_unwrap(
+        minor_status: *mut OM_uint32,
+        context_handle: gss_ctx_id_t,
+        input_message_buffer: *mut gss_buffer_desc,
+        output_message_buffer: *mut gss_buffer_desc,
+        conf_state: *mut i32,
+        qop_state: *mut OM_uint32,
+    ) -> OM_uint32;
+
+    fn gss_release_buffer(minor_status: *mut OM_uint32, buffer: *mut gss_buffer_desc) -> OM_uint32;
+
+    fn gss_release_name(minor_status: *mut OM_uint32, name: *mut gss_name_t) -> OM_uint32;
+
+    fn gss_delete_sec_context(
+        minor_status: *mut OM_uint32,
+        context_handle: *mut gss_ctx_id_t,
+        output_token: *mut gss_buffer_desc,
+    ) -> OM_uint32;
+}
+
+/// The SPNEGO mechanism of the GSSAPI, using the default credentials cache.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gssapi;
+
+impl SystemKerberos for Gssapi {
+    fn name(&self) -> &'static str {
+        "GSSAPI"
+    }
+
+    fn initiate(
+        &self,
+        server_name: &str,
+        channel_bindings: Option<&ChannelBindings>,
+    ) -> ConnectorResult<Option<(Box<dyn SecurityContext>, ContextStep)>> {
+        let service = format!("TERMSRV@{server_name}");
+        let mut service_buffer = gss_buffer_desc::from_slice(service.as_bytes());
+
+        let mut minor = 0;
+        let mut target_name: gss_name_t = ptr::null_mut();
+
+        // SAFETY: the name type is an immutable static of the library.
+        let name_type = unsafe { GSS_C_NT_HOSTBASED_SERVICE };
+
+        // SAFETY: the name buffer outlives the call.
+        let major = unsafe { gss_import_name(&mut minor, &mut service_buffer, name_type, &mut target_name) };
+        check("gss_import_name", major, minor)?;
+
+        let mut context = GssapiContext {
+            context: ptr::null_mut(),
+            target_name,
+            channel_bindings: channel_bindings.map(|channel_bindings| channel_bindings.application_data().to_vec()),
+        };
+
+        match context.initialize(None) {
+            Ok(step) => Ok(Some((Box::new(context), step))),
+            Err((major, minor)) => {
+                // Typically, there is no ticket in the credentials cache (GSS_S_NO_CRED or GSS_S_FAILURE).
+                debug!(major, minor, "GSSAPI credentials not available");
+                Ok(None)
+            }
+        }
+    }
+}
+
+struct GssapiContext {
+    context: gss_ctx_id_t,
+    target_name: gss_name_t,
+    /// Application data of the channel bindings, passed along every token.
+    channel_bindings: Option<Vec<u8>>,
+}
+
+// SAFETY: the GSSAPI handles are not bound to the thread that created them.
+unsafe impl Send for GssapiContext {}
+
+impl fmt::Debug for GssapiContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GssapiContext").finish_non_exhaustive()
+    }
+}
+
+impl GssapiContext {
+    fn initialize(&mut self, input: Option<&[u8]>) -> Result<ContextStep, (OM_uint32, OM_uint32)> {
+        let mut input_token = input.map_or(gss_buffer_desc::EMPTY, gss_buffer_desc::from_slice);
+        let mut output_token = gss_buffer_desc::EMPTY;
+
+        let mut mech = gss_OID_desc {
+            length: OM_uint32::try_from(SPNEGO_OID.len()).expect("short OID"),
+            elements: SPNEGO_OID.as_ptr().cast_mut().cast(),
+        };
+
+        let mut channel_bindings =
+            self.channel_bindings
+                .as_deref()
+                .map(|application_data| gss_channel_bindings_struct {
+                    initiator_addrtype: GSS_C_AF_UNSPEC,
+                    initiator_address: gss_buffer_desc::EMPTY,
+                    acceptor_addrtype: GSS_C_AF_UNSPEC,
+                    acceptor_address: gss_buffer_desc::EMPTY,
+                    application_data: gss_buffer_desc::from_slice(application_data),
+                });
+
+        let mut minor = 0;
+
+        // SAFETY: the buffers outlive the call, and the output token is allocated by the library.
+        let major = unsafe {
+            gss_init_sec_context(
+                &mut minor,
+                ptr::null_mut(),
+                &mut self.context,
+                self.target_name,
+                &mut mech,
+                GSS_C_MUTUAL_FLAG | GSS_C_SEQUENCE_FLAG | GSS_C_CONF_FLAG | GSS_C_INTEG_FLAG,
+                0,
+                channel_bindings
+                    .as_mut()
+                    .map_or(ptr::null_mut(), |channel_bindings| channel_bindings as *mut _),
+                &mut input_token,
+                ptr::null_mut(),
+                &mut output_token,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+
+        let token = take_buffer(&mut output_token);
+
+        match major {
+            GSS_S_COMPLETE | GSS_S_CONTINUE_NEEDED => Ok(ContextStep {
+                token: Some(token).filter(|token| !token.is_empty()),
+                complete: major == GSS_S_COMPLETE,
+            }),
+            _ => Err((major, minor)),
+        }
+    }
+}
+
+impl SecurityContext for GssapiContext {
+    fn step(&mut self, token: &[u8]) -> ConnectorResult<ContextStep> {
+        self.initialize(Some(token))
+            .map_err(|(major, minor)| status_err("gss_init_sec_context", major, minor))
+    }
+
+    fn wrap(&mut self, data: &[u8]) -> ConnectorResult<Vec<u8>> {
+        let mut input = gss_buffer_desc::from_slice(data);
+        let mut output = gss_buffer_desc::EMPTY;
+        let mut minor = 0;
+
+        // SAFETY: the input buffer outlives the call, and the output one is allocated by the library.
+        let major = unsafe { gss_wrap(&mut minor, self.context, 1, 0, &mut input, ptr::null_mut(), &mut output) };
+
+        let wrapped = take_buffer(&mut output);
+        check("gss_wrap", major, minor)?;
+
+        Ok(wrapped)
+    }
+
+    fn unwrap(&mut self, data: &[u8]) -> ConnectorResult<Vec<u8>> {
+        let mut input = gss_buffer_desc::from_slice(data);
+        let mut output = gss_buffer_desc::EMPTY;
+        let mut minor = 0;
+
+        // SAFETY: the input buffer outlives the call, and the output one is allocated by the library.
+        let major = unsafe {
+            gss_unwrap(
+                &mut minor,
+                self.context,
+                &mut input,
+                &mut output,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+
+        let unwrapped = take_buffer(&mut output);
+        check("gss_unwrap", major, minor)?;
+
+        Ok(unwrapped)
+    }
+}
+
+impl Drop for GssapiContext {
+    fn drop(&mut self) {
+        let mut minor = 0;
+
+        if !self.context.is_null() {
+            // SAFETY: the context was created by the library and is released once.
+            unsafe { gss_delete_sec_context(&mut minor, &mut self.context, ptr::null_mut()) };
+        }
+
+        // SAFETY: the name was imported by the library and is released once.
+        unsafe { gss_release_name(&mut minor, &mut self.target_name) };
+    }
+}
+
+/// Copies and releases a buffer allocated by the library.
+fn take_buffer(buffer: &mut gss_buffer_desc) -> Vec<u8> {
+    if buffer.value.is_null() {
+        return Vec::new();
+    }
+
+    // SAFETY: the library allocated `length` bytes for the buffer.
+    let data = unsafe { core::slice::from_raw_parts(buffer.value.cast::<u8>(), buffer.length) }.to_vec();
+
+    let mut minor = 0;
+
+    // SAFETY: the buffer was allocated by the library and is not used after this point.
+    unsafe { gss_release_buffer(&mut minor, buffer) };
+
+    data
+}
+
+fn check(function: &'static str, major: OM_uint32, minor: OM_uint32) -> ConnectorResult<()> {
+    if major & (GSS_S_CALLING_ERROR_MASK | GSS_S_ROUTINE_ERROR_MASK) == 0 {
+        Ok(())
+    } else {
+        Err(status_err(function, major, minor))
+    }
+}
+
+fn status_err(function: &'static str, major: OM_uint32, minor: OM_uint32) -> crate::ConnectorError {
+    reason_err!(function, "GSSAPI error (major: {major:#010X}, minor: {minor})").with_class(crate::ErrorClass::Security)
+}
@@ -0,0 +1,421 @@
+//! Kerberos through the native SSPI of Windows (secur32.dll).
+
+use core::ffi::c_void;
+use core::{fmt, ptr};
+
+use super::system::{ContextStep, SecurityContext, SystemKerberos};
+use super::ChannelBindings;
+use crate::ConnectorResult;
+
+const SECPKG_CRED_OUTBOUND: u32 = 0x2;
+const SECPKG_ATTR_SIZES: u32 = 0;
+
+const ISC_REQ_MUTUAL_AUTH: u32 = 0x2;
+const ISC_REQ_CONFIDENTIALITY: u32 = 0x10;
+const ISC_REQ_ALLOCATE_MEMORY: u32 = 0x100;
+const ISC_REQ_INTEGRITY: u32 = 0x10000;
+
+const SECURITY_NATIVE_DREP: u32 = 0x10;
+
+const SECBUFFER_VERSION: u32 = 0;
+const SECBUFFER_DATA: u32 = 1;
+const SECBUFFER_TOKEN: u32 = 2;
+const SECBUFFER_STREAM: u32 = 10;
+const SECBUFFER_CHANNEL_BINDINGS: u32 = 14;
+
+/// Size of the SEC_CHANNEL_BINDINGS structure, followed by the application data.
+const SEC_CHANNEL_BINDINGS_SIZE: u32 = 32;
+
+const SEC_E_OK: i32 = 0;
+const SEC_I_CONTINUE_NEEDED: i32 = 0x0009_0312;
+#[expect(clippy::cast_possible_wrap, reason = "HRESULT")]
+const SEC_E_NO_CREDENTIALS: i32 = 0x8009_030Eu32 as i32;
+
+#[repr(C)]
+#[derive(Default)]
+struct SecHandle {
+    lower: usize,
+    upper: usize,
+}
+
+#[repr(C)]
+struct SecBuffer {
+    cb_buffer: u32,
+    buffer_type: u32,
+    pv_buffer: *mut c_void,
+}
+
+#[repr(C)]
+struct SecBufferDesc {
+    ul_version: u32,
+    c_buffers: u32,
+    p_buffers: *mut SecBuffer,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct SecPkgContextSizes {
+    cb_max_token: u32,
+    cb_max_signature: u32,
+    cb_block_size: u32,
+    cb_security_trailer: u32,
+}
+
+#[link(name = "secur32")]
+extern "system" {
+    fn AcquireCredentialsHandleW(
+        principal: *const u16,
+        package: *const u16,
+        credential_use: u32,
+        logon_id: *const c_void,
+        auth_data: *const c_void,
+        get_key_fn: *const c_void,
+        get_key_argument: *const c_void,
+        credential: *mut SecHandle,
+        expiry: *mut i64,
+    ) -> i32;
+
+    fn InitializeSecurityContextW(
+        credential: *mut SecHandle,
+        context: *mut SecHandle,
+        target_name: *const u16,
+        context_req: u32,
+        reserved1: u32,
+        target_data_rep: u32,
+        input: *mut SecBufferDesc,
+        reserved2: u32,
+        new_context: *mut SecHandle,
+        output: *mut SecBufferDesc,
+        context_attr: *mut u32,
+        expiry: *mut i64,
+    ) -> i32;
+
+    fn QueryContextAttributesW(context: *mut SecHandle, attribute: u32, buffer: *mut c_void) -> i32;
+
+    fn EncryptMessage(context: *mut SecHandle, qop: u32, message: *mut SecBufferDesc, sequence_number: u32) -> i32;
+
+    fn DecryptMessage(context: *mut SecHandle, message: *mut SecBufferDesc, sequence_number: u32, qop: *mut u32)
+        -> i32;
+
+    fn FreeContextBuffer(buffer: *mut c_void) -> i32;
+
+    fn DeleteSecurityContext(context: *mut SecHandle) -> i32;
+
+    fn FreeCredentialsHandle(credential: *mut SecHandle) -> i32;
+}
+
+/// The Negotiate package of SSPI, which picks Kerberos when the server is in the domain.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NativeSspi;
+
+impl SystemKerberos for NativeSspi {
+    fn name(&self) -> &'static str {
+        "SSPI"
+    }
+
+    fn initiate(
+        &self,
+        server_name: &str,
+        channel_bindings: Option<&ChannelBindings>,
+    ) -> ConnectorResult<Option<(Box<dyn SecurityContext>, ContextStep)>> {
+        let package = wide("Negotiate");
+        let mut credential = SecHandle::default();
+
+        // SAFETY: the strings are NUL-terminated and the handle is written on success only.
+        let status = unsafe {
+            AcquireCredentialsHandleW(
+                ptr::null(),
+                package.as_ptr(),
+                SECPKG_CRED_OUTBOUND,
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                &mut credential,
+                ptr::null_mut(),
+            )
+        };
+
+        match status {
+            SEC_E_OK => {}
+            SEC_E_NO_CREDENTIALS => return Ok(None),
+            _ => return Err(status_err("AcquireCredentialsHandle", status)),
+        }
+
+        let mut context = NativeSspiContext {
+            credential,
+            context: None,
+            target_name: wide(&format!("TERMSRV/{server_name}")),
+            channel_bindings: channel_bindings.map(sec_channel_bindings).transpose()?,
+        };
+
+        match context.initialize(None) {
+            Ok(step) => Ok(Some((Box::new(context), step))),
+            Err(SEC_E_NO_CREDENTIALS) => Ok(None),
+            Err(status) => Err(status_err("InitializeSecurityContext", status)),
+        }
+    }
+}
+
+struct NativeSspiContext {
+    credential: SecHandle,
+    context: Option<SecHandle>,
+    target_name: Vec<u16>,
+    /// SEC_CHANNEL_BINDINGS structure, passed along every token.
+    channel_bindings: Option<Vec<u8>>,
+}
+
+// SAFETY: the SSPI handles are not bound to the thread that created them.
+unsafe impl Send for NativeSspiContext {}
+
+impl fmt::Debug for NativeSspiContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NativeSspiContext").finish_non_exhaustive()
+    }
+}
+
+impl NativeSspiContext {
+    fn initialize(&mut self, input: Option<&[u8]>) -> Result<ContextStep, i32> {
+        let mut input_buffers = Vec::with_capacity(2);
+
+        if let Some(input) = input {
+            input_buffers.push(SecBuffer {
+                cb_buffer: u32::try_from(input.len()).unwrap_or(u32::MAX),
+                buffer_type: SECBUFFER_TOKEN,
+                pv_buffer: input.as_ptr().cast_mut().cast(),
+            });
+        }
+
+        if let Some(channel_bindings) = self.channel_bindings.as_mut() {
+            input_buffers.push(SecBuffer {
+                cb_buffer: u32::try_from(channel_bindings.len()).unwrap_or(u32::MAX),
+                buffer_type: SECBUFFER_CHANNEL_BINDINGS,
+                pv_buffer: channel_bindings.as_mut_ptr().cast(),
+            });
+        }
+
+        let mut input_desc = SecBufferDesc {
+            ul_version: SECBUFFER_VERSION,
+            c_buffers: u32::try_from(input_buffers.len()).expect("at most 2 buffers"),
+            p_buffers: input_buffers.as_mut_ptr(),
+        };
+
+        let mut output_buffer = SecBuffer {
+            cb_buffer: 0,
+            buffer_type: SECBUFFER_TOKEN,
+            pv_buffer: ptr::null_mut(),
+        };
+        let mut output_desc = SecBufferDesc {
+            ul_version: SECBUFFER_VERSION,
+            c_buffers: 1,
+            p_buffers: &mut output_buffer,
+        };
+
+        let mut new_context = SecHandle::default();
+        let mut context_attr = 0;
+
+        let context = self
+            .context
+            .as_mut()
+            .map_or(ptr::null_mut(), |context| context as *mut _);
+
+        // SAFETY: the buffers outlive the call, and the output token is allocated by SSPI.
+        let status = unsafe {
+            InitializeSecurityContextW(
+                &mut self.credential,
+                context,
+                self.target_name.as_ptr(),
+                ISC_REQ_MUTUAL_AUTH | ISC_REQ_CONFIDENTIALITY | ISC_REQ_INTEGRITY | ISC_REQ_ALLOCATE_MEMORY,
+                0,
+                SECURITY_NATIVE_DREP,
+                if input.is_some() {
+                    &mut input_desc
+                } else {
+                    ptr::null_mut()
+                },
+                0,
+                &mut new_context,
+                &mut output_desc,
+                &mut context_attr,
+                ptr::null_mut(),
+            )
+        };
+
+        if status != SEC_E_OK && status != SEC_I_CONTINUE_NEEDED {
+            return Err(status);
+        }
+
+        if self.context.is_none() {
+            self.context = Some(new_context);
+        }
+
+        let token = if output_buffer.pv_buffer.is_null() {
+            None
+        } else {
+            // SAFETY: SSPI allocated `cb_buffer` bytes for the output token.
+            let token = unsafe {
+                core::slice::from_raw_parts(output_buffer.pv_buffer.cast::<u8>(), to_usize(output_buffer.cb_buffer))
+            }
+            .to_vec();
+
+            // SAFETY: the output token was allocated by SSPI and is not used after this point.
+            unsafe { FreeContextBuffer(output_buffer.pv_buffer) };
+
+            Some(token).filter(|token| !token.is_empty())
+        };
+
+        Ok(ContextStep {
+            token,
+            complete: status == SEC_E_OK,
+        })
+    }
+
+    fn context(&mut self) -> ConnectorResult<&mut SecHandle> {
+        self.context
+            .as_mut()
+            .ok_or_else(|| general_err!("security context is not initialized"))
+    }
+}
+
+impl SecurityContext for NativeSspiContext {
+    fn step(&mut self, token: &[u8]) -> ConnectorResult<ContextStep> {
+        self.initialize(Some(token))
+            .map_err(|status| status_err("InitializeSecurityContext", status))
+    }
+
+    fn wrap(&mut self, data: &[u8]) -> ConnectorResult<Vec<u8>> {
+        let context = self.context()?;
+
+        let mut sizes = SecPkgContextSizes::default();
+
+        // SAFETY: `sizes` is the structure of the SECPKG_ATTR_SIZES attribute.
+        let status = unsafe {
+            QueryContextAttributesW(
+                context,
+                SECPKG_ATTR_SIZES,
+                (&mut sizes as *mut SecPkgContextSizes).cast(),
+            )
+        };
+        if status != SEC_E_OK {
+            return Err(status_err("QueryContextAttributes", status));
+        }
+
+        let mut trailer = vec![0u8; to_usize(sizes.cb_security_trailer)];
+        let mut data = data.to_vec();
+
+        let mut buffers = [
+            SecBuffer {
+                cb_buffer: sizes.cb_security_trailer,
+                buffer_type: SECBUFFER_TOKEN,
+                pv_buffer: trailer.as_mut_ptr().cast(),
+            },
+            SecBuffer {
+                cb_buffer: u32::try_from(data.len()).map_err(|e| custom_err!("message too big", e))?,
+                buffer_type: SECBUFFER_DATA,
+                pv_buffer: data.as_mut_ptr().cast(),
+            },
+        ];
+        let mut desc = SecBufferDesc {
+            ul_version: SECBUFFER_VERSION,
+            c_buffers: 2,
+            p_buffers: buffers.as_mut_ptr(),
+        };
+
+        // SAFETY: the buffers are sized as required by the package and outlive the call.
+        let status = unsafe { EncryptMessage(context, 0, &mut desc, 0) };
+        if status != SEC_E_OK {
+            return Err(status_err("EncryptMessage", status));
+        }
+
+        // The trailer may be shorter than the maximum size.
+        trailer.truncate(to_usize(buffers[0].cb_buffer));
+        trailer.extend_from_slice(&data);
+
+        Ok(trailer)
+    }
+
+    fn unwrap(&mut self, data: &[u8]) -> ConnectorResult<Vec<u8>> {
+        let context = self.context()?;
+
+        let mut stream = data.to_vec();
+
+        let mut buffers = [
+            SecBuffer {
+                cb_buffer: u32::try_from(stream.len()).map_err(|e| custom_err!("message too big", e))?,
+                buffer_type: SECBUFFER_STREAM,
+                pv_buffer: stream.as_mut_ptr().cast(),
+            },
+            SecBuffer {
+                cb_buffer: 0,
+                buffer_type: SECBUFFER_DATA,
+                pv_buffer: ptr::null_mut(),
+            },
+        ];
+        let mut desc = SecBufferDesc {
+            ul_version: SECBUFFER_VERSION,
+            c_buffers: 2,
+            p_buffers: buffers.as_mut_ptr(),
+        };
+
+        let mut qop = 0;
+
+        // SAFETY: the message is decrypted in place, the data buffer pointing into `stream`.
+        let status = unsafe { DecryptMessage(context, &mut desc, 0, &mut qop) };
+        if status != SEC_E_OK {
+            return Err(status_err("DecryptMessage", status));
+        }
+
+        // SAFETY: the data buffer points into `stream`, which is still alive.
+        let plaintext =
+            unsafe { core::slice::from_raw_parts(buffers[1].pv_buffer.cast::<u8>(), to_usize(buffers[1].cb_buffer)) };
+
+        Ok(plaintext.to_vec())
+    }
+}
+
+impl Drop for NativeSspiContext {
+    fn drop(&mut self) {
+        if let Some(context) = self.context.as_mut() {
+            // SAFETY: the context was created by SSPI and is released once.
+            unsafe { DeleteSecurityContext(context) };
+        }
+
+        // SAFETY: the credentials were acquired from SSPI and are released once.
+        unsafe { FreeCredentialsHandle(&mut self.credential) };
+    }
+}
+
+/// SEC_CHANNEL_BINDINGS, with the application data only
+fn sec_channel_bindings(channel_bindings: &ChannelBindings) -> ConnectorResult<Vec<u8>> {
+    let application_data = channel_bindings.application_data();
+    let application_data_length =
+        u32::try_from(application_data.len()).map_err(|e| custom_err!("channel bindings too big", e))?;
+
+    let header = [
+        0,                         // dwInitiatorAddrType
+        0,                         // cbInitiatorLength
+        0,                         // dwInitiatorOffset
+        0,                         // dwAcceptorAddrType
+        0,                         // cbAcceptorLength
+        0,                         // dwAcceptorOffset
+        application_data_length,   // cbApplicationDataLength
+        SEC_CHANNEL_BINDINGS_SIZE, // dwApplicationDataOffset
+    ];
+
+    let mut buffer = header.iter().flat_map(|field| field.to_le_bytes()).collect::<Vec<u8>>();
+    buffer.extend_from_slice(application_data);
+
+    Ok(buffer)
+}
+
+fn to_usize(size: u32) -> usize {
+    usize::try_from(size).expect("u32 fits in usize on Windows")
+}
+
+fn wide(value: &str) -> Vec<u16> {
+    value.encode_utf16().chain(core::iter::once(0)).collect()
+}
+
+fn status_err(function: &'static str, status: i32) -> crate::ConnectorError {
+    reason_err!(function, "SSPI error {status:#010X}").with_class(crate::ErrorClass::Security)
+}
@@ -0,0 +1,246 @@
+//! CredSSP driven by a Kerberos implementation of the system (\[MS-CSSP\] 3.1.5).
+
+use core::fmt;
+
+use rand_core::{OsRng, RngCore as _};
+use sha2::{Digest as _, Sha256};
+use sspi::credssp::{ClientState, TsRequest};
+
+use super::ts_credentials::{encode_password_creds, encode_ts_credentials, CRED_TYPE_PASSWORD, CRED_TYPE_REMOTE_GUARD};
+use super::{ChannelBindings, RemoteGuardCreds};
+use crate::{ConnectorResult, Credentials};
+
+/// Highest version of the TSRequest structure supported.
+const TS_REQUEST_VERSION: u32 = 6;
+
+/// First version of the TSRequest structure using the nonce to bind the public key.
+const NONCE_VERSION: u32 = 5;
+
+const CLIENT_SERVER_HASH_MAGIC: &[u8] = b"CredSSP Client-To-Server Binding Hash\0";
+const SERVER_CLIENT_HASH_MAGIC: &[u8] = b"CredSSP Server-To-Client Binding Hash\0";
+
+/// Kerberos implementation of the system, authenticating with the credentials of the logged-on user.
+///
+/// The tokens are SPNEGO tokens, as expected by CredSSP.
+pub trait SystemKerberos: fmt::Debug + Send + Sync {
+    /// Name of the implementation, for the logs.
+    fn name(&self) -> &'static str;
+
+    /// Starts a security context with the `TERMSRV` service of `server_name`, bound to the TLS connection when
+    /// `channel_bindings` are given.
+    ///
+    /// Returns the context along with its first step, or `None` when the user has no credentials.
+    fn initiate(
+        &self,
+        server_name: &str,
+        channel_bindings: Option<&ChannelBindings>,
+    ) -> ConnectorResult<Option<(Box<dyn SecurityContext>, ContextStep)>>;
+}
+
+/// Security context established with the server by a [`SystemKerberos`].
+pub trait SecurityContext: Send {
+    /// Processes a token of the server.
+    fn step(&mut self, token: &[u8]) -> ConnectorResult<ContextStep>;
+
+    /// Encrypts a message with the session key of the context.
+    fn wrap(&mut self, data: &[u8]) -> ConnectorResult<Vec<u8>>;
+
+    /// Decrypts a message of the server with the session key of the context.
+    fn unwrap(&mut self, data: &[u8]) -> ConnectorResult<Vec<u8>>;
+
+    /// Returns the credentials delegated to the server with Remote Credential Guard, once the context is
+    /// established.
+    ///
+    /// `None` is returned by the implementations not supporting Remote Credential Guard.
+    fn remote_guard_creds(&mut self) -> ConnectorResult<Option<RemoteGuardCreds>> {
+        Ok(None)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ContextStep {
+    /// Token to send to the server, if any.
+    pub token: Option<Vec<u8>>,
+    /// Whether the context is established.
+    pub complete: bool,
+}
+
+/// Returns the Kerberos implementation of the system, if any.
+///
+/// This is the native SSPI on Windows (the `native-sspi` feature), and the GSSAPI of the system (the
+/// `gssapi` feature) elsewhere.
+pub fn default_system_kerberos() -> Option<Box<dyn SystemKerberos>> {
+    #[cfg(all(windows, feature = "native-sspi"))]
+    {
+        Some(Box::new(super::native_sspi::NativeSspi))
+    }
+
+    #[cfg(all(unix, feature = "gssapi"))]
+    {
+        Some(Box::new(super::gssapi::Gssapi))
+    }
+
+    #[cfg(not(any(all(windows, feature = "native-sspi"), all(unix, feature = "gssapi"))))]
+    {
+        None
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum State {
+    Negotiating,
+    PublicKeySent,
+    Finished,
+}
+
+pub(super) struct SystemCredsspClient {
+    context: Box<dyn SecurityContext>,
+    /// Step of the context not sent to the server yet.
+    pending_step: Option<ContextStep>,
+    public_key: Vec<u8>,
+    nonce: [u8; 32],
+    /// Lowest version of the TSRequest structure supported by both sides.
+    version: u32,
+    /// TSPasswordCreds delegated to the server, or `None` to delegate TSRemoteGuardCreds instead.
+    password_creds: Option<Vec<u8>>,
+    state: State,
+}
+
+impl fmt::Debug for SystemCredsspClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SystemCredsspClient")
+            .field("version", &self.version)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SystemCredsspClient {
+    pub(super) fn new(
+        context: Box<dyn SecurityContext>,
+        first_step: ContextStep,
+        public_key: Vec<u8>,
+        credentials: &Credentials,
+        domain: Option<&str>,
+        remote_credential_guard: bool,
+    ) -> ConnectorResult<Self> {
+        let Credentials::UsernamePassword { username, password } = credentials else {
+            return Err(general_err!(
+                "smart card credentials can't be used with the system Kerberos"
+            ));
+        };
+
+        let mut nonce = [0; 32];
+        OsRng.fill_bytes(&mut nonce);
+
+        Ok(Self {
+            context,
+            pending_step: Some(first_step),
+            public_key,
+            nonce,
+            version: TS_REQUEST_VERSION,
+            // The password is never sent with Remote Credential Guard.
+            password_creds: (!remote_credential_guard)
+                .then(|| encode_password_creds(domain.unwrap_or_default(), username, password)),
+            state: State::Negotiating,
+        })
+    }
+
+    pub(super) fn process(&mut self, request: TsRequest) -> ConnectorResult<ClientState> {
+        if let Some(error_code) = request.error_code {
+            return Err(reason_err!("CredSSP", "server error: {error_code:?}").with_class(crate::ErrorClass::Security));
+        }
+
+        match self.state {
+            State::Negotiating => {
+                let step = match self.pending_step.take() {
+                    Some(step) => step,
+                    None => {
+                        self.version = self.version.min(request.version);
+
+                        let token = request
+                            .nego_tokens
+                            .ok_or_else(|| general_err!("negoTokens missing from the server request"))?;
+
+                        self.context.step(&token)?
+                    }
+                };
+
+                let pub_key_auth = if step.complete {
+                    self.state = State::PublicKeySent;
+                    Some(self.context.wrap(&self.client_public_key_hash())?)
+                } else {
+                    None
+                };
+
+                Ok(ClientState::ReplyNeeded(TsRequest {
+                    version: TS_REQUEST_VERSION,
+                    nego_tokens: step.token,
+                    pub_key_auth,
+                    client_nonce: Some(self.nonce),
+                    ..TsRequest::default()
+                }))
+            }
+            State::PublicKeySent => {
+                let pub_key_auth = request
+                    .pub_key_auth
+                    .ok_or_else(|| general_err!("pubKeyAuth missing from the server request"))?;
+
+                if self.context.unwrap(&pub_key_auth)? != self.server_public_key_hash() {
+                    return Err(general_err!("public key of the server doesn't match the TLS one")
+                        .with_class(crate::ErrorClass::Security));
+                }
+
+                let ts_credentials = match &self.password_creds {
+                    Some(password_creds) => encode_ts_credentials(CRED_TYPE_PASSWORD, password_creds),
+                    None => {
+                        let remote_guard_creds = self.context.remote_guard_creds()?.ok_or_else(|| {
+                            general_err!("Remote Credential Guard is not supported by the Kerberos implementation")
+                        })?;
+
+                        encode_ts_credentials(CRED_TYPE_REMOTE_GUARD, &remote_guard_creds.encode())
+                    }
+                };
+                let auth_info = self.context.wrap(&ts_credentials)?;
+                self.state = State::Finished;
+
+                Ok(ClientState::FinalMessage(TsRequest {
+                    version: TS_REQUEST_VERSION,
+                    auth_info: Some(auth_info),
+                    ..TsRequest::default()
+                }))
+            }
+            State::Finished => Err(general_err!("CredSSP sequence is already done")),
+        }
+    }
+
+    fn client_public_key_hash(&self) -> Vec<u8> {
+        if self.version >= NONCE_VERSION {
+            binding_hash(CLIENT_SERVER_HASH_MAGIC, &self.nonce, &self.public_key)
+        } else {
+            self.public_key.clone()
+        }
+    }
+
+    fn server_public_key_hash(&self) -> Vec<u8> {
+        if self.version >= NONCE_VERSION {
+            binding_hash(SERVER_CLIENT_HASH_MAGIC, &self.nonce, &self.public_key)
+        } else {
+            // Before version 5, the server increments the first byte of the public key.
+            let mut public_key = self.public_key.clone();
+            if let Some(first) = public_key.first_mut() {
+                *first = first.wrapping_add(1);
+            }
+            public_key
+        }
+    }
+}
+
+fn binding_hash(magic: &[u8], nonce: &[u8], public_key: &[u8]) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(magic)
+        .chain_update(nonce)
+        .chain_update(public_key)
+        .finalize()
+        .to_vec()
+}
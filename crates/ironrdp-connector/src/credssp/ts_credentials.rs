@@ -0,0 +1,212 @@
+//! Credentials delegated to the server at the end of the CredSSP exchange (\[MS-CSSP\] 2.2.1.2).
+
+use crate::ConnectorResult;
+
+/// The credentials are a TSPasswordCreds structure.
+pub(super) const CRED_TYPE_PASSWORD: u8 = 1;
+
+/// The credentials are a TSRemoteGuardCreds structure.
+pub(super) const CRED_TYPE_REMOTE_GUARD: u8 = 6;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_CONTEXT: u8 = 0xA0;
+
+/// Credentials of a security package, delegated with Remote Credential Guard (TSRemoteGuardPackageCred).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteGuardPackageCred {
+    /// Name of the security package, e.g. `Kerberos`.
+    pub package_name: String,
+    /// Credentials in the format of the package, e.g. a KERB_TICKET_LOGON structure for Kerberos.
+    pub cred_buffer: Vec<u8>,
+}
+
+/// Credentials delegated with Remote Credential Guard instead of the password of the user (TSRemoteGuardCreds).
+///
+/// The server never receives the password: the Kerberos requests of the session are redirected to the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteGuardCreds {
+    /// Credentials used to log the user on.
+    pub logon_cred: RemoteGuardPackageCred,
+    /// Credentials of the other security packages, e.g. `NTLM`.
+    pub supplemental_creds: Vec<RemoteGuardPackageCred>,
+}
+
+impl RemoteGuardCreds {
+    /// Returns the DER encoding of the structure.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut fields = der_explicit(0, &self.logon_cred.encode());
+
+        if !self.supplemental_creds.is_empty() {
+            let creds = self
+                .supplemental_creds
+                .iter()
+                .flat_map(RemoteGuardPackageCred::encode)
+                .collect::<Vec<u8>>();
+            fields.extend(der_explicit(1, &der_tlv(TAG_SEQUENCE, &creds)));
+        }
+
+        der_tlv(TAG_SEQUENCE, &fields)
+    }
+
+    pub fn decode(input: &[u8]) -> ConnectorResult<Self> {
+        let mut fields = DerReader::new(DerReader::new(input).read(TAG_SEQUENCE)?);
+
+        let logon_cred = RemoteGuardPackageCred::decode(fields.read_explicit(0)?)?;
+
+        let mut supplemental_creds = Vec::new();
+        if !fields.is_empty() {
+            let mut creds = DerReader::new(DerReader::new(fields.read_explicit(1)?).read(TAG_SEQUENCE)?);
+            while !creds.is_empty() {
+                supplemental_creds.push(RemoteGuardPackageCred::decode(creds.read_raw(TAG_SEQUENCE)?)?);
+            }
+        }
+
+        Ok(Self {
+            logon_cred,
+            supplemental_creds,
+        })
+    }
+}
+
+impl RemoteGuardPackageCred {
+    fn encode(&self) -> Vec<u8> {
+        let mut fields = der_explicit(0, &der_octet_string(&utf16(&self.package_name)));
+        fields.extend(der_explicit(1, &der_octet_string(&self.cred_buffer)));
+
+        der_tlv(TAG_SEQUENCE, &fields)
+    }
+
+    fn decode(input: &[u8]) -> ConnectorResult<Self> {
+        let mut fields = DerReader::new(DerReader::new(input).read(TAG_SEQUENCE)?);
+
+        let package_name = DerReader::new(fields.read_explicit(0)?).read(TAG_OCTET_STRING)?;
+        let cred_buffer = DerReader::new(fields.read_explicit(1)?).read(TAG_OCTET_STRING)?;
+
+        let package_name = package_name
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect::<Vec<u16>>();
+
+        Ok(Self {
+            package_name: String::from_utf16(&package_name)
+                .map_err(|_| general_err!("invalid TSRemoteGuardPackageCred package name"))?,
+            cred_buffer: cred_buffer.to_vec(),
+        })
+    }
+}
+
+/// TSPasswordCreds
+///
+/// Empty credentials are accepted by the servers allowing the Restricted Admin mode.
+pub(super) fn encode_password_creds(domain: &str, username: &str, password: &str) -> Vec<u8> {
+    let mut fields = der_explicit(0, &der_octet_string(&utf16(domain)));
+    fields.extend(der_explicit(1, &der_octet_string(&utf16(username))));
+    fields.extend(der_explicit(2, &der_octet_string(&utf16(password))));
+
+    der_tlv(TAG_SEQUENCE, &fields)
+}
+
+/// TSCredentials carrying the credentials of the given type, e.g. [`CRED_TYPE_PASSWORD`].
+pub(super) fn encode_ts_credentials(cred_type: u8, credentials: &[u8]) -> Vec<u8> {
+    let mut fields = der_explicit(0, &der_tlv(TAG_INTEGER, &[cred_type]));
+    fields.extend(der_explicit(1, &der_octet_string(credentials)));
+
+    der_tlv(TAG_SEQUENCE, &fields)
+}
+
+fn utf16(value: &str) -> Vec<u8> {
+    value.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+fn der_explicit(tag_number: u8, content: &[u8]) -> Vec<u8> {
+    der_tlv(TAG_CONTEXT | tag_number, content)
+}
+
+fn der_octet_string(content: &[u8]) -> Vec<u8> {
+    der_tlv(TAG_OCTET_STRING, content)
+}
+
+fn der_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut tlv = vec![tag];
+
+    match u8::try_from(content.len()) {
+        Ok(len) if len < 0x80 => tlv.push(len),
+        _ => {
+            let len = content.len().to_be_bytes();
+            let len = &len[len.iter().position(|&byte| byte != 0).unwrap_or(len.len() - 1)..];
+            tlv.push(0x80 | u8::try_from(len.len()).expect("at most 8 bytes"));
+            tlv.extend_from_slice(len);
+        }
+    }
+
+    tlv.extend_from_slice(content);
+    tlv
+}
+
+/// Reads the DER elements of a structure, in order.
+struct DerReader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Self { input }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    /// Reads the content of an element with the given tag.
+    fn read(&mut self, tag: u8) -> ConnectorResult<&'a [u8]> {
+        let (header_len, content_len) = self.header(tag)?;
+        let content = &self.input[header_len..header_len + content_len];
+        self.input = &self.input[header_len + content_len..];
+
+        Ok(content)
+    }
+
+    /// Reads an element with the given tag, including its tag and length.
+    fn read_raw(&mut self, tag: u8) -> ConnectorResult<&'a [u8]> {
+        let (header_len, content_len) = self.header(tag)?;
+        let element = &self.input[..header_len + content_len];
+        self.input = &self.input[header_len + content_len..];
+
+        Ok(element)
+    }
+
+    fn read_explicit(&mut self, tag_number: u8) -> ConnectorResult<&'a [u8]> {
+        self.read(TAG_CONTEXT | tag_number)
+    }
+
+    /// Returns the length of the tag and length, and the length of the content of the next element.
+    fn header(&self, tag: u8) -> ConnectorResult<(usize, usize)> {
+        match self.input {
+            [actual, ..] if *actual != tag => Err(general_err!("unexpected DER tag")),
+            [_, len, rest @ ..] if *len < 0x80 => Self::checked(2, usize::from(*len), rest.len()),
+            [_, len, rest @ ..] => {
+                let len_size = usize::from(len & 0x7F);
+                if len_size == 0 || len_size > size_of::<usize>() || rest.len() < len_size {
+                    return Err(general_err!("invalid DER length"));
+                }
+
+                let content_len = rest[..len_size]
+                    .iter()
+                    .fold(0usize, |len, &byte| (len << 8) | usize::from(byte));
+
+                Self::checked(2 + len_size, content_len, rest.len() - len_size)
+            }
+            _ => Err(general_err!("truncated DER element")),
+        }
+    }
+
+    fn checked(header_len: usize, content_len: usize, available: usize) -> ConnectorResult<(usize, usize)> {
+        if content_len > available {
+            return Err(general_err!("truncated DER element"));
+        }
+
+        Ok((header_len, content_len))
+    }
+}
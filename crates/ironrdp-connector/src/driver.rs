@@ -0,0 +1,290 @@
+//! Sans-IO entry point for the whole connection sequence.
+//!
+//! [`ConnectionDriver`] owns the [`ClientConnector`] and the CredSSP sequence, and is driven by feeding it the bytes
+//! received from the server. It never touches the transport, which can be TCP, QUIC, an in-process pipe or anything
+//! carrying a byte stream:
+//!
+//! ```text
+//! loop {
+//!     match driver.poll()? {
+//!         DriverEvent::Send(bytes) => transport.write_all(&bytes),
+//!         DriverEvent::NeedData => driver.feed(&transport.read()),
+//!         DriverEvent::UpgradeSecurity => {
+//!             let server_public_key = transport.upgrade_to_tls();
+//!             driver.mark_security_upgraded(server_public_key);
+//!         }
+//!         DriverEvent::Connected(result) => break result,
+//!     }
+//! }
+//! ```
+
+use core::{fmt, mem};
+
+use ironrdp_core::WriteBuf;
+use ironrdp_pdu::PduHint;
+use sspi::credssp::ClientState;
+use sspi::generator::GeneratorState;
+use sspi::network_client::NetworkClient;
+
+use crate::credssp::{CredsspProcessGenerator, CredsspSequence, KerberosConfig};
+use crate::{
+    ClientConnector, ClientConnectorState, ConnectionResult, ConnectorError, ConnectorErrorKind, ConnectorResult,
+    Sequence as _, ServerName, State as _, Written,
+};
+
+/// What the [`ConnectionDriver`] expects from the caller.
+#[derive(Debug)]
+pub enum DriverEvent {
+    /// Bytes to write to the transport.
+    Send(Vec<u8>),
+    /// More bytes from the server are needed, see [`ConnectionDriver::feed`].
+    NeedData,
+    /// The transport must be secured with TLS, see [`ConnectionDriver::mark_security_upgraded`].
+    ///
+    /// All the bytes fed after this event must be the decrypted ones.
+    UpgradeSecurity,
+    /// The connection sequence is done, and the session can be started.
+    Connected(ConnectionResult),
+}
+
+/// Sans-IO state machine for the connection sequence, from the X.224 connection request to the connection
+/// finalization (including CredSSP).
+pub struct ConnectionDriver {
+    connector: ClientConnector,
+    server_name: ServerName,
+    kerberos_config: Option<KerberosConfig>,
+    /// Only used by the Kerberos implementation of sspi-rs, to talk to the KDC.
+    network_client: Option<Box<dyn NetworkClient + Send>>,
+    server_public_key: Option<Vec<u8>>,
+    credssp: Option<CredsspSequence>,
+    /// Bytes received from the server, not consumed yet.
+    input: Vec<u8>,
+    /// Bytes to send to the server.
+    output: Vec<u8>,
+    buf: WriteBuf,
+}
+
+impl fmt::Debug for ConnectionDriver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionDriver")
+            .field("connector", &self.connector)
+            .field("server_name", &self.server_name)
+            .field("credssp", &self.credssp)
+            .field("input_len", &self.input.len())
+            .field("output_len", &self.output.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ConnectionDriver {
+    pub fn new(connector: ClientConnector, server_name: ServerName) -> Self {
+        Self {
+            connector,
+            server_name,
+            kerberos_config: None,
+            network_client: None,
+            server_public_key: None,
+            credssp: None,
+            input: Vec::new(),
+            output: Vec::new(),
+            buf: WriteBuf::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_kerberos_config(mut self, kerberos_config: KerberosConfig) -> Self {
+        self.kerberos_config = Some(kerberos_config);
+        self
+    }
+
+    /// Sets the client used to reach the KDC, when authenticating with the built-in Kerberos implementation.
+    ///
+    /// This is the only IO performed by the driver, and it is never needed by NTLM nor by the Kerberos of the system.
+    #[must_use]
+    pub fn with_network_client(mut self, network_client: Box<dyn NetworkClient + Send>) -> Self {
+        self.network_client = Some(network_client);
+        self
+    }
+
+    pub fn connector(&self) -> &ClientConnector {
+        &self.connector
+    }
+
+    /// Gives access to the connector, e.g. to attach the channel bindings once the TLS connection is established.
+    pub fn connector_mut(&mut self) -> &mut ClientConnector {
+        &mut self.connector
+    }
+
+    /// Appends bytes received from the server.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.input.extend_from_slice(bytes);
+    }
+
+    /// Returns the bytes received from the server and not consumed by the connection sequence.
+    ///
+    /// Once connected, they belong to the active session.
+    pub fn take_leftover(&mut self) -> Vec<u8> {
+        mem::take(&mut self.input)
+    }
+
+    /// Resumes the connection sequence after the TLS handshake, given the public key of the server certificate.
+    pub fn mark_security_upgraded(&mut self, server_public_key: Vec<u8>) {
+        self.connector.mark_security_upgrade_as_done();
+        self.server_public_key = Some(server_public_key);
+    }
+
+    /// Advances the connection sequence as far as possible with the bytes fed so far.
+    pub fn poll(&mut self) -> ConnectorResult<DriverEvent> {
+        loop {
+            if !self.output.is_empty() {
+                return Ok(DriverEvent::Send(mem::take(&mut self.output)));
+            }
+
+            if let Some(credssp) = &mut self.credssp {
+                let Some(hint) = credssp.next_pdu_hint() else {
+                    self.finish_credssp();
+                    continue;
+                };
+
+                let Some(pdu) = take_pdu(&mut self.input, hint.as_ref())? else {
+                    return Ok(DriverEvent::NeedData);
+                };
+
+                trace!(length = pdu.len(), "PDU received");
+
+                match credssp.decode_server_message(&pdu)? {
+                    Some(ts_request) => self.process_ts_request(ts_request)?,
+                    None => self.finish_credssp(),
+                }
+
+                continue;
+            }
+
+            match self.connector.state {
+                ClientConnectorState::EnhancedSecurityUpgrade { .. } => return Ok(DriverEvent::UpgradeSecurity),
+                ClientConnectorState::Credssp { selected_protocol } => {
+                    let server_public_key = self
+                        .server_public_key
+                        .take()
+                        .ok_or_else(|| general_err!("public key of the server is missing for CredSSP"))?;
+
+                    let (sequence, ts_request) = CredsspSequence::init(
+                        self.connector.config.credentials.clone(),
+                        self.connector.config.domain.as_deref(),
+                        self.connector.config.remote_credential_guard,
+                        selected_protocol,
+                        self.server_name.clone(),
+                        server_public_key,
+                        self.kerberos_config.take(),
+                        self.connector.channel_bindings().cloned(),
+                    )?;
+
+                    self.credssp = Some(sequence);
+                    self.process_ts_request(ts_request)?;
+                }
+                ClientConnectorState::Connected { .. } => {
+                    let ClientConnectorState::Connected { result } =
+                        mem::replace(&mut self.connector.state, ClientConnectorState::Consumed)
+                    else {
+                        unreachable!()
+                    };
+
+                    info!("Connected with success");
+
+                    return Ok(DriverEvent::Connected(result));
+                }
+                _ => {
+                    self.buf.clear();
+
+                    let written = if let Some(hint) = self.connector.next_pdu_hint() {
+                        let Some(pdu) = take_pdu(&mut self.input, hint.as_ref())? else {
+                            return Ok(DriverEvent::NeedData);
+                        };
+
+                        trace!(
+                            connector.state = self.connector.state.name(),
+                            length = pdu.len(),
+                            "PDU received"
+                        );
+
+                        self.connector.step(&pdu, &mut self.buf)?
+                    } else {
+                        self.connector.step_no_input(&mut self.buf)?
+                    };
+
+                    self.push_output(written);
+                }
+            }
+        }
+    }
+
+    fn process_ts_request(&mut self, ts_request: sspi::credssp::TsRequest) -> ConnectorResult<()> {
+        let credssp = self.credssp.as_mut().expect("CredSSP sequence in progress");
+
+        let client_state = {
+            let mut generator = credssp.process_ts_request(ts_request);
+            resolve_generator(&mut generator, self.network_client.as_deref_mut())?
+        }; // drop generator
+
+        self.buf.clear();
+        let written = credssp.handle_process_result(client_state, &mut self.buf)?;
+        self.push_output(written);
+
+        Ok(())
+    }
+
+    fn finish_credssp(&mut self) {
+        self.credssp = None;
+        self.connector.mark_credssp_as_done();
+    }
+
+    fn push_output(&mut self, written: Written) {
+        if let Some(len) = written.size() {
+            trace!(len, "Send response");
+            self.output.extend_from_slice(&self.buf[..len]);
+        }
+    }
+}
+
+/// Removes the next PDU expected by the hint from the input, if fully received.
+fn take_pdu(input: &mut Vec<u8>, hint: &dyn PduHint) -> ConnectorResult<Option<Vec<u8>>> {
+    loop {
+        let Some((matched, length)) = hint.find_size(input).map_err(ConnectorError::decode)? else {
+            return Ok(None);
+        };
+
+        if input.len() < length {
+            return Ok(None);
+        }
+
+        let pdu = input.drain(..length).collect();
+
+        if matched {
+            return Ok(Some(pdu));
+        }
+
+        debug!("Received and lost an unexpected PDU");
+    }
+}
+
+fn resolve_generator(
+    generator: &mut CredsspProcessGenerator<'_>,
+    mut network_client: Option<&mut (dyn NetworkClient + Send)>,
+) -> ConnectorResult<ClientState> {
+    let mut state = generator.start();
+
+    loop {
+        match state {
+            GeneratorState::Suspended(request) => {
+                let network_client = network_client
+                    .as_deref_mut()
+                    .ok_or_else(|| general_err!("a network client is required to reach the KDC"))?;
+
+                state = generator.resume(network_client.send(&request));
+            }
+            GeneratorState::Completed(client_state) => {
+                break client_state.map_err(|e| ConnectorError::new("CredSSP", ConnectorErrorKind::Credssp(e)));
+            }
+        }
+    }
+}
@@ -14,6 +14,7 @@ mod connection;
 pub mod connection_activation;
 mod connection_finalization;
 pub mod credssp;
+mod driver;
 mod license_exchange;
 mod server_name;
 
@@ -22,6 +23,8 @@ use core::fmt;
 use std::sync::Arc;
 
 use ironrdp_core::{encode_buf, encode_vec, Encode, WriteBuf};
+use ironrdp_error::Classify;
+pub use ironrdp_error::ErrorClass;
 use ironrdp_pdu::nego::NegoRequestData;
 use ironrdp_pdu::rdp::capability_sets;
 use ironrdp_pdu::rdp::client_info::PerformanceFlags;
@@ -30,8 +33,11 @@ use ironrdp_pdu::{gcc, x224, PduHint};
 pub use sspi;
 
 pub use self::channel_connection::{ChannelConnectionSequence, ChannelConnectionState};
-pub use self::connection::{encode_send_data_request, ClientConnector, ClientConnectorState, ConnectionResult};
+pub use self::connection::{
+    encode_send_data_request, ClientConnector, ClientConnectorState, ConnectionResult, NegotiationInfo,
+};
 pub use self::connection_finalization::{ConnectionFinalizationSequence, ConnectionFinalizationState};
+pub use self::driver::{ConnectionDriver, DriverEvent};
 pub use self::license_exchange::{LicenseExchangeSequence, LicenseExchangeState};
 pub use self::server_name::ServerName;
 pub use crate::license_exchange::LicenseCache;
@@ -50,6 +56,27 @@ pub struct BitmapConfig {
     pub color_depth: u32,
 }
 
+/// A monitor of the client, sent in the Client Monitor Data and Client Monitor Extended Data GCC blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonitorConfig {
+    /// Position of the left edge in the virtual desktop, relative to the top-left corner of the primary monitor.
+    pub left: i32,
+    /// Position of the top edge in the virtual desktop, relative to the top-left corner of the primary monitor.
+    pub top: i32,
+    pub width: u32,
+    pub height: u32,
+    pub is_primary: bool,
+    /// Physical width of the monitor in millimeters, or zero when unknown.
+    pub physical_width: u32,
+    /// Physical height of the monitor in millimeters, or zero when unknown.
+    pub physical_height: u32,
+    pub orientation: gcc::MonitorOrientation,
+    /// Scale factor of the monitor, in percent (100 to 500), or zero when unknown.
+    pub desktop_scale_factor: u32,
+    /// Scale factor applied to the desktop scale factor, in percent (100, 140 or 180), or zero when unknown.
+    pub device_scale_factor: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct SmartCardIdentity {
     /// DER-encoded X509 certificate
@@ -101,6 +128,12 @@ pub struct Config {
     ///
     /// This becomes the `desktop_scale_factor` in the [`TS_UD_CS_CORE`](gcc::ClientCoreOptionalData) structure.
     pub desktop_scale_factor: u32,
+    /// The monitors of the client, for multi-monitor sessions.
+    ///
+    /// When empty, the session has a single monitor of the size of the desktop. Otherwise, the monitors are
+    /// sent in the Client Monitor Data and Client Monitor Extended Data GCC blocks: at most 16 monitors are
+    /// allowed, and exactly one of them must be primary.
+    pub monitors: Vec<MonitorConfig>,
     /// TLS + Graphical login (legacy)
     ///
     /// Also called SSL or TLS security protocol.
@@ -149,6 +182,12 @@ pub struct Config {
     pub enable_credssp: bool,
     pub credentials: Credentials,
     pub domain: Option<String>,
+    /// Delegates Kerberos credentials to the server instead of the password (Remote Credential Guard).
+    ///
+    /// The REDIRECTED_AUTHENTICATION_MODE_REQUIRED flag is set in the connection request, and the connection
+    /// fails if the server doesn't support it. Requires NLA with a system Kerberos implementation, see
+    /// [`credssp::KerberosBackend`].
+    pub remote_credential_guard: bool,
     /// The build number of the client.
     pub client_build: u32,
     /// Name of the client computer
@@ -179,6 +218,19 @@ pub struct Config {
     pub autologon: bool,
     /// If true, the INFO_NOAUDIOPLAYBACK flag is set in the [`ClientInfoPdu`](ironrdp_pdu::rdp::ClientInfoPdu)
     pub no_audio_playback: bool,
+    /// If true, the Client Message Channel Data GCC block is sent, and the message channel offered by the server is joined.
+    ///
+    /// The message channel carries the auto-detect and multitransport PDUs once the connection is established.
+    pub message_channel: bool,
+    /// The multitransport channels (UDP) supported by the client, sent in the Client Multitransport Channel Data GCC block.
+    ///
+    /// The block is not sent when `None`.
+    pub multitransport_flags: Option<gcc::MultiTransportFlags>,
+    /// If true, the INFO_RAIL flag is set in the [`ClientInfoPdu`](ironrdp_pdu::rdp::ClientInfoPdu), and the
+    /// Remote Programs and Window List capability sets are advertised.
+    ///
+    /// The server then runs remote applications instead of a desktop, controlled through the RAIL static channel.
+    pub rail: bool,
 
     pub license_cache: Option<Arc<dyn LicenseCache>>,
 
@@ -273,6 +325,8 @@ pub enum ConnectorErrorKind {
     Credssp(sspi::Error),
     Reason(String),
     AccessDenied,
+    /// The server aborted the connection with a Set Error Info PDU.
+    ServerErrorInfo(ironrdp_pdu::rdp::server_error_info::ErrorInfo),
     General,
     Custom,
 }
@@ -285,6 +339,7 @@ impl fmt::Display for ConnectorErrorKind {
             ConnectorErrorKind::Credssp(_) => write!(f, "CredSSP"),
             ConnectorErrorKind::Reason(description) => write!(f, "reason: {description}"),
             ConnectorErrorKind::AccessDenied => write!(f, "access denied"),
+            ConnectorErrorKind::ServerErrorInfo(info) => write!(f, "{info}"),
             ConnectorErrorKind::General => write!(f, "general error"),
             ConnectorErrorKind::Custom => write!(f, "custom error"),
         }
@@ -299,12 +354,26 @@ impl std::error::Error for ConnectorErrorKind {
             ConnectorErrorKind::Credssp(e) => Some(e),
             ConnectorErrorKind::Reason(_) => None,
             ConnectorErrorKind::AccessDenied => None,
+            ConnectorErrorKind::ServerErrorInfo(_) => None,
             ConnectorErrorKind::Custom => None,
             ConnectorErrorKind::General => None,
         }
     }
 }
 
+impl Classify for ConnectorErrorKind {
+    fn class(&self) -> ErrorClass {
+        match &self {
+            ConnectorErrorKind::Encode(_) | ConnectorErrorKind::Decode(_) => ErrorClass::Codec,
+            ConnectorErrorKind::Credssp(_) | ConnectorErrorKind::AccessDenied => ErrorClass::Security,
+            ConnectorErrorKind::Reason(_) => ErrorClass::ProtocolViolation,
+            ConnectorErrorKind::ServerErrorInfo(_) | ConnectorErrorKind::General | ConnectorErrorKind::Custom => {
+                ErrorClass::Other
+            }
+        }
+    }
+}
+
 pub type ConnectorError = ironrdp_error::Error<ConnectorErrorKind>;
 
 pub trait ConnectorErrorExt {
@@ -0,0 +1,64 @@
+use alloc::vec::Vec;
+
+use crate::WriteBuf;
+
+/// Default maximum number of buffers kept by a [`BufferPool`].
+const DEFAULT_MAX_BUFFERS: usize = 8;
+
+/// Default maximum capacity of the buffers kept by a [`BufferPool`].
+const DEFAULT_MAX_CAPACITY: usize = 4 * 1024 * 1024; // 4 MiB
+
+/// Pool of reusable [`WriteBuf`]s.
+///
+/// Encoding each PDU in a new buffer allocates for every PDU sent. Instead, the buffers are acquired from the pool
+/// and released once written, so that a steady stream of PDUs is encoded in the same few allocations.
+///
+/// The pool is not synchronized, it is meant to be owned by the task encoding the PDUs of a connection.
+pub struct BufferPool {
+    buffers: Vec<Vec<u8>>,
+    max_buffers: usize,
+    max_capacity: usize,
+}
+
+impl BufferPool {
+    /// Constructs a pool keeping at most `max_buffers` buffers, of at most `max_capacity` bytes each.
+    ///
+    /// The bigger buffers are dropped when released, so that an exceptionally large PDU doesn't hold its memory.
+    pub const fn new(max_buffers: usize, max_capacity: usize) -> Self {
+        Self {
+            buffers: Vec::new(),
+            max_buffers,
+            max_capacity,
+        }
+    }
+
+    /// Returns an empty buffer, reusing the memory of a released buffer when available.
+    pub fn acquire(&mut self) -> WriteBuf {
+        self.buffers.pop().map(WriteBuf::from_vec).unwrap_or_default()
+    }
+
+    /// Gives a buffer back to the pool.
+    pub fn release(&mut self, buf: WriteBuf) {
+        let buf = buf.into_inner();
+
+        if self.buffers.len() < self.max_buffers && buf.capacity() <= self.max_capacity {
+            self.buffers.push(buf);
+        }
+    }
+
+    /// Returns the number of buffers ready to be reused.
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Returns `true` if no buffer is ready to be reused.
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BUFFERS, DEFAULT_MAX_CAPACITY)
+    }
+}
@@ -66,6 +66,12 @@ pub enum DecodeErrorKind {
 #[cfg(feature = "std")]
 impl std::error::Error for DecodeErrorKind {}
 
+impl ironrdp_error::Classify for DecodeErrorKind {
+    fn class(&self) -> ironrdp_error::ErrorClass {
+        ironrdp_error::ErrorClass::Codec
+    }
+}
+
 impl fmt::Display for DecodeErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -70,6 +70,12 @@ pub enum EncodeErrorKind {
 #[cfg(feature = "std")]
 impl std::error::Error for EncodeErrorKind {}
 
+impl ironrdp_error::Classify for EncodeErrorKind {
+    fn class(&self) -> ironrdp_error::ErrorClass {
+        ironrdp_error::ErrorClass::Codec
+    }
+}
+
 impl fmt::Display for EncodeErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -12,6 +12,8 @@ extern crate alloc;
 mod macros;
 
 mod as_any;
+#[cfg(feature = "alloc")]
+mod buffer_pool;
 mod cursor;
 mod decode;
 mod encode;
@@ -24,6 +26,8 @@ mod write_buf;
 // Flat API hierarchy of common traits and types
 
 pub use self::as_any::*;
+#[cfg(feature = "alloc")]
+pub use self::buffer_pool::*;
 pub use self::cursor::*;
 pub use self::decode::*;
 pub use self::encode::*;
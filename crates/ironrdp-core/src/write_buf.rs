@@ -48,6 +48,16 @@ impl WriteBuf {
         self.inner
     }
 
+    /// Consumes the `WriteBuf`, returning the filled region as a `Vec<u8>`.
+    ///
+    /// The capacity of the buffer is kept, so that it can be reused with [`WriteBuf::from_vec`].
+    #[inline]
+    pub fn into_filled(self) -> Vec<u8> {
+        let mut inner = self.inner;
+        inner.truncate(self.filled);
+        inner
+    }
+
     /// Returns length of the filled region.
     ///
     /// This is always equal to the starting index for the unfilled initialized portion of the buffer.
@@ -20,16 +20,89 @@ pub trait Source: fmt::Display + fmt::Debug + Send + Sync + 'static {}
 #[cfg(not(feature = "std"))]
 impl<T> Source for T where T: fmt::Display + fmt::Debug + Send + Sync + 'static {}
 
-#[derive(Debug)]
+/// Broad class of a failure, shared by the errors of all the IronRDP crates.
+///
+/// It allows embedders to branch on the kind of failure without matching the error messages. The discriminants are
+/// stable and may be used as error codes, e.g. across a FFI boundary.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ErrorClass {
+    /// Any other failure, including the internal errors.
+    Other = 0,
+    /// The peers could not agree on the security protocol or on the connection parameters.
+    Negotiation = 1,
+    /// Authentication, credentials or cryptography failure.
+    Security = 2,
+    /// The peer sent an unexpected or out of sequence message.
+    ProtocolViolation = 3,
+    /// The underlying transport failed.
+    Io = 4,
+    /// A PDU, an image or a sound could not be encoded or decoded.
+    Codec = 5,
+    /// A static or dynamic virtual channel failed.
+    Channel = 6,
+}
+
+impl ErrorClass {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Other => "other",
+            Self::Negotiation => "negotiation",
+            Self::Security => "security",
+            Self::ProtocolViolation => "protocol violation",
+            Self::Io => "I/O",
+            Self::Codec => "codec",
+            Self::Channel => "channel",
+        }
+    }
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<ErrorClass> for u8 {
+    fn from(class: ErrorClass) -> Self {
+        class as u8
+    }
+}
+
+/// Implemented by the error kinds to tell the [`ErrorClass`] of their errors.
+pub trait Classify {
+    fn class(&self) -> ErrorClass;
+}
+
 pub struct Error<Kind> {
     pub context: &'static str,
     pub kind: Kind,
+    /// Class overriding the one of the kind.
+    class: Option<ErrorClass>,
     #[cfg(feature = "std")]
     source: Option<Box<dyn std::error::Error + Sync + Send>>,
     #[cfg(all(not(feature = "std"), feature = "alloc"))]
     source: Option<Box<dyn Source>>,
 }
 
+impl<Kind: fmt::Debug> fmt::Debug for Error<Kind> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Error");
+        debug.field("context", &self.context).field("kind", &self.kind);
+
+        // The class is only printed when overridden, as it's otherwise given by the kind.
+        if let Some(class) = self.class {
+            debug.field("class", &class);
+        }
+
+        #[cfg(feature = "alloc")]
+        debug.field("source", &self.source);
+
+        debug.finish()
+    }
+}
+
 impl<Kind> Error<Kind> {
     #[cold]
     #[must_use]
@@ -37,11 +110,19 @@ impl<Kind> Error<Kind> {
         Self {
             context,
             kind,
+            class: None,
             #[cfg(feature = "alloc")]
             source: None,
         }
     }
 
+    /// Overrides the class given by the kind of this error.
+    #[must_use]
+    pub fn with_class(mut self, class: ErrorClass) -> Self {
+        self.class = Some(class);
+        self
+    }
+
     #[cold]
     #[must_use]
     pub fn with_source<E>(self, source: E) -> Self
@@ -70,6 +151,7 @@ impl<Kind> Error<Kind> {
         Error {
             context: self.context,
             kind: self.kind.into(),
+            class: self.class,
             #[cfg(any(feature = "std", feature = "alloc"))]
             source: self.source,
         }
@@ -84,6 +166,38 @@ impl<Kind> Error<Kind> {
     }
 }
 
+impl<Kind> Error<Kind>
+where
+    Kind: Classify,
+{
+    /// Returns the class of this error.
+    ///
+    /// Unless overridden with [`Error::with_class`], it is the class of the kind. An error of the
+    /// [`ErrorClass::Other`] class caused by an I/O error is of the [`ErrorClass::Io`] class.
+    pub fn class(&self) -> ErrorClass {
+        if let Some(class) = self.class {
+            return class;
+        }
+
+        let class = self.kind.class();
+
+        #[cfg(feature = "std")]
+        if class == ErrorClass::Other {
+            let mut next_source = self.source.as_deref().map(|e| e as &(dyn std::error::Error + 'static));
+
+            while let Some(e) = next_source {
+                if e.is::<std::io::Error>() {
+                    return ErrorClass::Io;
+                }
+
+                next_source = e.source();
+            }
+        }
+
+        class
+    }
+}
+
 impl<Kind> fmt::Display for Error<Kind>
 where
     Kind: fmt::Display,
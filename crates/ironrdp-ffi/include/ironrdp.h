@@ -0,0 +1,379 @@
+/*
+ * C API embedding an IronRDP client session.
+ *
+ * Unless stated otherwise, the functions return IRONRDP_STATUS_OK on success, and the error is
+ * described by ironrdp_last_error_message() otherwise.
+ *
+ * A session is not thread-safe: the calls taking it must be serialized.
+ */
+
+#ifndef IRONRDP_H
+#define IRONRDP_H
+
+/* Generated by cbindgen from the sources of the ironrdp-ffi crate, do not edit. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/*
+ Status returned by the functions of the API.
+
+ When not `Ok`, a description of the error is returned by [`ironrdp_last_error_message`].
+ */
+typedef enum {
+    IRONRDP_STATUS_OK = 0,
+    IRONRDP_STATUS_INVALID_ARGUMENT = 1,
+    IRONRDP_STATUS_CONNECTION_FAILED = 2,
+    IRONRDP_STATUS_IO = 3,
+    IRONRDP_STATUS_SESSION = 4,
+    /*
+     The session is terminated, no more events will be returned.
+     */
+    IRONRDP_STATUS_TERMINATED = 5,
+    /*
+     A bug was hit in the library.
+     */
+    IRONRDP_STATUS_PANIC = 6,
+} IronRdpStatus;
+
+typedef enum {
+    /*
+     No event occurred before the timeout elapsed.
+     */
+    IRONRDP_EVENT_KIND_NONE = 0,
+    /*
+     The `rect` region of the framebuffer was updated.
+     */
+    IRONRDP_EVENT_KIND_GRAPHICS_UPDATE = 1,
+    /*
+     The desktop was resized to `width` x `height`: the framebuffer was reallocated.
+     */
+    IRONRDP_EVENT_KIND_DESKTOP_RESIZED = 2,
+    IRONRDP_EVENT_KIND_POINTER_DEFAULT = 3,
+    IRONRDP_EVENT_KIND_POINTER_HIDDEN = 4,
+    /*
+     The server moved the pointer to `x`, `y`.
+     */
+    IRONRDP_EVENT_KIND_POINTER_POSITION = 5,
+    /*
+     The pointer bitmap changed, see `ironrdp_session_pointer`.
+     */
+    IRONRDP_EVENT_KIND_POINTER_BITMAP = 6,
+    /*
+     Text was copied on the server, see `ironrdp_session_clipboard_text`.
+     */
+    IRONRDP_EVENT_KIND_CLIPBOARD_TEXT = 7,
+    /*
+     The session is terminated.
+     */
+    IRONRDP_EVENT_KIND_TERMINATED = 8,
+} IronRdpEventKind;
+
+/*
+ Pixel format of the framebuffer.
+ */
+typedef enum {
+    IRONRDP_PIXEL_FORMAT_RGBA32 = 0,
+    IRONRDP_PIXEL_FORMAT_BGRA32 = 1,
+} IronRdpPixelFormat;
+
+typedef enum {
+    IRONRDP_MOUSE_BUTTON_LEFT = 0,
+    IRONRDP_MOUSE_BUTTON_MIDDLE = 1,
+    IRONRDP_MOUSE_BUTTON_RIGHT = 2,
+    IRONRDP_MOUSE_BUTTON_X1 = 3,
+    IRONRDP_MOUSE_BUTTON_X2 = 4,
+} IronRdpMouseButton;
+
+/*
+ Connection configuration, built with the `ironrdp_config_*` functions.
+ */
+typedef struct IronRdpConfig IronRdpConfig;
+
+/*
+ Active client session, created by `ironrdp_connect`.
+ */
+typedef struct IronRdpSession IronRdpSession;
+
+/*
+ Called with the DER-encoded certificate of the server, returns whether the connection should proceed.
+ */
+typedef bool (*IronRdpVerifyCertificateFn)(void *user_data, const uint8_t *certificate, size_t certificate_len);
+
+typedef struct {
+    uint16_t x;
+    uint16_t y;
+    uint16_t width;
+    uint16_t height;
+} IronRdpRect;
+
+/*
+ Event returned by `ironrdp_session_poll`, the fields used depending on its kind.
+ */
+typedef struct {
+    IronRdpEventKind kind;
+    IronRdpRect rect;
+    uint16_t x;
+    uint16_t y;
+    uint16_t width;
+    uint16_t height;
+} IronRdpEvent;
+
+/*
+ Framebuffer of the session, valid until the next call taking the session mutably.
+ */
+typedef struct {
+    const uint8_t *data;
+    uint16_t width;
+    uint16_t height;
+    /*
+     Number of bytes per row.
+     */
+    size_t stride;
+} IronRdpFramebuffer;
+
+/*
+ Pointer bitmap, in RGBA format, valid until the next call taking the session mutably.
+ */
+typedef struct {
+    const uint8_t *data;
+    uint16_t width;
+    uint16_t height;
+    uint16_t hotspot_x;
+    uint16_t hotspot_y;
+} IronRdpPointer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Returns the message of the last error that occurred on the calling thread, or NULL.
+
+ The string is valid until the next failing call on the same thread.
+ */
+const char *ironrdp_last_error_message(void);
+
+/*
+ Creates a configuration with the default values: port 3389, 1024x768 desktop, US keyboard layout,
+ RGBA framebuffer, clipboard disabled and no certificate verification.
+ */
+IronRdpConfig *ironrdp_config_new(void);
+
+/*
+ # Safety
+
+ `config` must be NULL or returned by `ironrdp_config_new`, and not used afterwards.
+ */
+void ironrdp_config_free(IronRdpConfig *config);
+
+/*
+ # Safety
+
+ `config` must be valid and `host` a valid NUL-terminated string.
+ */
+IronRdpStatus ironrdp_config_set_server(IronRdpConfig *config, const char *host, uint16_t port);
+
+/*
+ Sets the credentials, `domain` being optional (NULL).
+
+ # Safety
+
+ `config` must be valid and the strings NULL or valid NUL-terminated strings.
+ */
+IronRdpStatus ironrdp_config_set_credentials(IronRdpConfig *config,
+                                             const char *username,
+                                             const char *password,
+                                             const char *domain);
+
+/*
+ # Safety
+
+ `config` must be valid and `name` a valid NUL-terminated string.
+ */
+IronRdpStatus ironrdp_config_set_client_name(IronRdpConfig *config, const char *name);
+
+/*
+ # Safety
+
+ `config` must be valid.
+ */
+IronRdpStatus ironrdp_config_set_desktop_size(IronRdpConfig *config, uint16_t width, uint16_t height);
+
+/*
+ Sets the keyboard layout, as a Windows keyboard layout identifier (e.g. 0x409 for US English).
+
+ # Safety
+
+ `config` must be valid.
+ */
+IronRdpStatus ironrdp_config_set_keyboard_layout(IronRdpConfig *config, uint32_t layout);
+
+/*
+ Sets the pixel format of the framebuffer, an `IronRdpPixelFormat`.
+
+ # Safety
+
+ `config` must be valid.
+ */
+IronRdpStatus ironrdp_config_set_pixel_format(IronRdpConfig *config, uint32_t format);
+
+/*
+ Enables the redirection of the plain text clipboard.
+
+ # Safety
+
+ `config` must be valid.
+ */
+IronRdpStatus ironrdp_config_set_clipboard_enabled(IronRdpConfig *config, bool enabled);
+
+/*
+ Sets the callback verifying the certificate of the server, called during `ironrdp_connect` before the
+ credentials are sent.
+
+ Without a verifier, any certificate is accepted.
+
+ # Safety
+
+ `config` must be valid, and `verify` must be callable with `user_data` from the thread calling
+ `ironrdp_connect`.
+ */
+IronRdpStatus ironrdp_config_set_certificate_verifier(IronRdpConfig *config,
+                                                      IronRdpVerifyCertificateFn verify,
+                                                      void *user_data);
+
+/*
+ Connects to the server, blocking until the session is active.
+
+ # Safety
+
+ `config` must be valid and `session` a valid pointer, set to the new session on success.
+ */
+IronRdpStatus ironrdp_connect(const IronRdpConfig *config, IronRdpSession **session);
+
+/*
+ Closes the connection and frees the session.
+
+ # Safety
+
+ `session` must be NULL or returned by `ironrdp_connect`, and not used afterwards.
+ */
+void ironrdp_session_free(IronRdpSession *session);
+
+/*
+ Processes the frames received from the server until an event is produced, or until `timeout_ms`
+ milliseconds elapse (the event is then `None`). A negative timeout blocks until an event is produced.
+
+ Returns `Terminated` once the `Terminated` event was returned.
+
+ # Safety
+
+ `session` and `event` must be valid.
+ */
+IronRdpStatus ironrdp_session_poll(IronRdpSession *session, int32_t timeout_ms, IronRdpEvent *event);
+
+/*
+ # Safety
+
+ `session` and `framebuffer` must be valid.
+ */
+IronRdpStatus ironrdp_session_framebuffer(const IronRdpSession *session, IronRdpFramebuffer *framebuffer);
+
+/*
+ Returns the last pointer bitmap received, fails with `InvalidArgument` if none was received.
+
+ # Safety
+
+ `session` and `pointer` must be valid.
+ */
+IronRdpStatus ironrdp_session_pointer(const IronRdpSession *session, IronRdpPointer *pointer);
+
+/*
+ Returns the text last copied on the server, in UTF-8, or NULL.
+
+ The string is valid until the next call taking the session mutably.
+
+ # Safety
+
+ `session` must be valid.
+ */
+const char *ironrdp_session_clipboard_text(const IronRdpSession *session);
+
+/*
+ Sets the text copied on the client, in UTF-8, and advertises it to the server. NULL empties the clipboard.
+
+ # Safety
+
+ `session` must be valid and `text` NULL or a valid NUL-terminated string.
+ */
+IronRdpStatus ironrdp_session_set_clipboard_text(IronRdpSession *session, const char *text);
+
+/*
+ Presses or releases a key, by scancode (extended keys are prefixed with 0xE0, e.g. 0xE04B for the left arrow).
+
+ # Safety
+
+ `session` must be valid.
+ */
+IronRdpStatus ironrdp_session_key(IronRdpSession *session,
+                                  uint16_t scancode,
+                                  bool pressed);
+
+/*
+ Presses or releases the key of a Unicode character, typically for the text typed with input methods.
+
+ # Safety
+
+ `session` must be valid.
+ */
+IronRdpStatus ironrdp_session_unicode(IronRdpSession *session, uint32_t codepoint, bool pressed);
+
+/*
+ # Safety
+
+ `session` must be valid.
+ */
+IronRdpStatus ironrdp_session_mouse_move(IronRdpSession *session, uint16_t x, uint16_t y);
+
+/*
+ Presses or releases a mouse button, an `IronRdpMouseButton`.
+
+ # Safety
+
+ `session` must be valid.
+ */
+IronRdpStatus ironrdp_session_mouse_button(IronRdpSession *session, uint32_t button, bool pressed);
+
+/*
+ Rotates the mouse wheel, 120 units being one notch. Positive units scroll up, or right.
+
+ # Safety
+
+ `session` must be valid.
+ */
+IronRdpStatus ironrdp_session_mouse_wheel(IronRdpSession *session, bool vertical, int16_t units);
+
+/*
+ Releases all the keys and mouse buttons pressed, typically when the application loses the focus.
+
+ # Safety
+
+ `session` must be valid.
+ */
+IronRdpStatus ironrdp_session_release_all_inputs(IronRdpSession *session);
+
+/*
+ Requests the server to end the session. The session is terminated once the `Terminated` event is polled.
+
+ # Safety
+
+ `session` must be valid.
+ */
+IronRdpStatus ironrdp_session_shutdown(IronRdpSession *session);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* IRONRDP_H */
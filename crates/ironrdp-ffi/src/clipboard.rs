@@ -0,0 +1,213 @@
+//! Plain text clipboard redirection.
+//!
+//! The clipboard backend is driven by the CLIPRDR channel while the frames are processed, so it only forwards
+//! the requests of the channel to the session, which responds once the frame is processed.
+
+use std::ffi::CString;
+use std::sync::mpsc;
+
+use ironrdp::cliprdr::backend::CliprdrBackend;
+use ironrdp::cliprdr::pdu::{
+    ClipboardFormat, ClipboardFormatId, ClipboardGeneralCapabilityFlags, FileContentsRequest, FileContentsResponse,
+    FormatDataRequest, FormatDataResponse, LockDataId,
+};
+use ironrdp::cliprdr::{CliprdrClient, CliprdrSvcMessages};
+use ironrdp::pdu::PduError;
+use ironrdp_core::impl_as_any;
+
+use crate::error::Error;
+use crate::IronRdpStatus;
+
+#[derive(Debug)]
+pub(crate) enum ClipboardEvent {
+    Ready,
+    RequestFormatList,
+    RemoteCopy { text_available: bool },
+    DataRequest(ClipboardFormatId),
+    DataResponse(Option<String>),
+}
+
+#[derive(Debug)]
+pub(crate) struct ClipboardBackend {
+    events: mpsc::Sender<ClipboardEvent>,
+}
+
+impl ClipboardBackend {
+    pub(crate) fn new(events: mpsc::Sender<ClipboardEvent>) -> Self {
+        Self { events }
+    }
+
+    fn send(&self, event: ClipboardEvent) {
+        // The receiver is only dropped along with the session, which owns this backend.
+        let _ = self.events.send(event);
+    }
+}
+
+impl_as_any!(ClipboardBackend);
+
+impl CliprdrBackend for ClipboardBackend {
+    fn temporary_directory(&self) -> &str {
+        ".cliprdr"
+    }
+
+    fn client_capabilities(&self) -> ClipboardGeneralCapabilityFlags {
+        ClipboardGeneralCapabilityFlags::empty()
+    }
+
+    fn on_ready(&mut self) {
+        self.send(ClipboardEvent::Ready);
+    }
+
+    fn on_request_format_list(&mut self) {
+        self.send(ClipboardEvent::RequestFormatList);
+    }
+
+    fn on_process_negotiated_capabilities(&mut self, _: ClipboardGeneralCapabilityFlags) {}
+
+    fn on_remote_copy(&mut self, available_formats: &[ClipboardFormat]) {
+        let text_available = available_formats
+            .iter()
+            .any(|format| format.id() == ClipboardFormatId::CF_UNICODETEXT);
+
+        self.send(ClipboardEvent::RemoteCopy { text_available });
+    }
+
+    fn on_format_data_request(&mut self, request: FormatDataRequest) {
+        self.send(ClipboardEvent::DataRequest(request.format));
+    }
+
+    fn on_format_data_response(&mut self, response: FormatDataResponse<'_>) {
+        let text = if response.is_error() {
+            None
+        } else {
+            match response.to_unicode_string() {
+                Ok(text) => Some(text),
+                Err(error) => {
+                    warn!(%error, "Invalid clipboard text");
+                    None
+                }
+            }
+        };
+
+        self.send(ClipboardEvent::DataResponse(text));
+    }
+
+    fn on_file_contents_request(&mut self, _: FileContentsRequest) {
+        // File transfer is not supported.
+    }
+
+    fn on_file_contents_response(&mut self, _: FileContentsResponse<'_>) {
+        // File transfer is not supported.
+    }
+
+    fn on_lock(&mut self, _: LockDataId) {}
+
+    fn on_unlock(&mut self, _: LockDataId) {}
+}
+
+/// Session side of the clipboard redirection.
+pub(crate) struct Clipboard {
+    events: mpsc::Receiver<ClipboardEvent>,
+    ready: bool,
+    local_text: Option<String>,
+    remote_text: Option<CString>,
+}
+
+/// What the session should do after a clipboard event.
+pub(crate) enum ClipboardAction {
+    Send(CliprdrSvcMessages<ironrdp::cliprdr::Client>),
+    RemoteTextReceived,
+}
+
+impl Clipboard {
+    pub(crate) fn new(events: mpsc::Receiver<ClipboardEvent>) -> Self {
+        Self {
+            events,
+            ready: false,
+            local_text: None,
+            remote_text: None,
+        }
+    }
+
+    /// Returns the text last copied on the server, received with a
+    /// [`IronRdpEventKind::ClipboardText`](crate::IronRdpEventKind::ClipboardText) event.
+    pub(crate) fn remote_text(&self) -> Option<&CString> {
+        self.remote_text.as_ref()
+    }
+
+    /// Returns the next pending event of the clipboard backend.
+    pub(crate) fn next_event(&self) -> Option<ClipboardEvent> {
+        self.events.try_recv().ok()
+    }
+
+    pub(crate) fn handle_event(
+        &mut self,
+        event: ClipboardEvent,
+        cliprdr: &mut CliprdrClient,
+    ) -> Result<Option<ClipboardAction>, Error> {
+        let action = match event {
+            ClipboardEvent::Ready => {
+                self.ready = true;
+                None
+            }
+            ClipboardEvent::RequestFormatList => Some(ClipboardAction::Send(
+                cliprdr.initiate_copy(&self.local_formats()).map_err(cliprdr_error)?,
+            )),
+            ClipboardEvent::RemoteCopy { text_available: true } => Some(ClipboardAction::Send(
+                cliprdr
+                    .initiate_paste(ClipboardFormatId::CF_UNICODETEXT)
+                    .map_err(cliprdr_error)?,
+            )),
+            ClipboardEvent::RemoteCopy { text_available: false } => None,
+            ClipboardEvent::DataRequest(format) => {
+                let response = match &self.local_text {
+                    Some(text) if format == ClipboardFormatId::CF_UNICODETEXT => {
+                        FormatDataResponse::new_unicode_string(text)
+                    }
+                    _ => FormatDataResponse::new_error(),
+                };
+
+                Some(ClipboardAction::Send(
+                    cliprdr.submit_format_data(response).map_err(cliprdr_error)?,
+                ))
+            }
+            ClipboardEvent::DataResponse(Some(text)) => {
+                self.remote_text = Some(CString::new(text.replace('\0', "")).unwrap_or_default());
+                Some(ClipboardAction::RemoteTextReceived)
+            }
+            ClipboardEvent::DataResponse(None) => None,
+        };
+
+        Ok(action)
+    }
+
+    /// Replaces the text copied on the client, advertising it to the server.
+    pub(crate) fn set_local_text(
+        &mut self,
+        text: Option<String>,
+        cliprdr: &mut CliprdrClient,
+    ) -> Result<Option<CliprdrSvcMessages<ironrdp::cliprdr::Client>>, Error> {
+        self.local_text = text;
+
+        if !self.ready {
+            // Advertised once the channel is initialized.
+            return Ok(None);
+        }
+
+        let messages = cliprdr.initiate_copy(&self.local_formats()).map_err(cliprdr_error)?;
+
+        Ok(Some(messages))
+    }
+
+    fn local_formats(&self) -> Vec<ClipboardFormat> {
+        if self.local_text.is_some() {
+            vec![ClipboardFormat::new(ClipboardFormatId::CF_UNICODETEXT)]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+fn cliprdr_error(error: PduError) -> Error {
+    Error::new(IronRdpStatus::Session, format!("CLIPRDR: {}", error.report()))
+}
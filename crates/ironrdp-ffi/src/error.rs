@@ -0,0 +1,71 @@
+use core::cell::RefCell;
+use core::fmt;
+use std::ffi::CString;
+use std::io;
+
+use ironrdp::connector::ConnectorError;
+use ironrdp::session::SessionError;
+
+use crate::IronRdpStatus;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug)]
+pub(crate) struct Error {
+    status: IronRdpStatus,
+    message: String,
+}
+
+impl Error {
+    pub(crate) fn new(status: IronRdpStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(IronRdpStatus::InvalidArgument, message)
+    }
+
+    pub(crate) fn io(context: &str, error: io::Error) -> Self {
+        Self::new(IronRdpStatus::Io, format!("{context}: {error}"))
+    }
+
+    pub(crate) fn terminated() -> Self {
+        Self::new(IronRdpStatus::Terminated, "session terminated")
+    }
+
+    /// Records the message of the error as the last error of the calling thread.
+    pub(crate) fn set_last(self) -> IronRdpStatus {
+        // Interior NUL bytes are unlikely, but would make the message unrepresentable.
+        let message = CString::new(self.message.replace('\0', " ")).unwrap_or_default();
+        LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+        self.status
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<ConnectorError> for Error {
+    fn from(error: ConnectorError) -> Self {
+        Self::new(IronRdpStatus::ConnectionFailed, error.report().to_string())
+    }
+}
+
+impl From<SessionError> for Error {
+    fn from(error: SessionError) -> Self {
+        Self::new(IronRdpStatus::Session, error.report().to_string())
+    }
+}
+
+/// Calls `f` with the last error message of the calling thread, if any.
+pub(crate) fn with_last<R>(f: impl FnOnce(Option<&CString>) -> R) -> R {
+    LAST_ERROR.with(|last| f(last.borrow().as_ref()))
+}
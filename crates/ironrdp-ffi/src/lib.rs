@@ -0,0 +1,744 @@
+#![doc = include_str!("../README.md")]
+#![doc(html_logo_url = "https://cdnweb.devolutions.net/images/projects/devolutions/logos/devolutions-icon-shadow.svg")]
+
+#[macro_use]
+extern crate tracing;
+
+mod clipboard;
+mod error;
+mod session;
+
+use core::ffi::{c_char, c_void, CStr};
+use core::panic::AssertUnwindSafe;
+use core::ptr;
+use core::time::Duration;
+use std::panic;
+
+use ironrdp::connector::DesktopSize;
+use ironrdp::graphics::image_processing::PixelFormat;
+use ironrdp::input::{MouseButton, MousePosition, Operation, Scancode, WheelRotations};
+
+use crate::error::Error;
+use crate::session::Session;
+
+/// Status returned by the functions of the API.
+///
+/// When not `Ok`, a description of the error is returned by [`ironrdp_last_error_message`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IronRdpStatus {
+    Ok = 0,
+    InvalidArgument = 1,
+    ConnectionFailed = 2,
+    Io = 3,
+    Session = 4,
+    /// The session is terminated, no more events will be returned.
+    Terminated = 5,
+    /// A bug was hit in the library.
+    Panic = 6,
+}
+
+/// Pixel format of the framebuffer.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IronRdpPixelFormat {
+    Rgba32 = 0,
+    Bgra32 = 1,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IronRdpMouseButton {
+    Left = 0,
+    Middle = 1,
+    Right = 2,
+    X1 = 3,
+    X2 = 4,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IronRdpEventKind {
+    /// No event occurred before the timeout elapsed.
+    None = 0,
+    /// The `rect` region of the framebuffer was updated.
+    GraphicsUpdate = 1,
+    /// The desktop was resized to `width` x `height`: the framebuffer was reallocated.
+    DesktopResized = 2,
+    PointerDefault = 3,
+    PointerHidden = 4,
+    /// The server moved the pointer to `x`, `y`.
+    PointerPosition = 5,
+    /// The pointer bitmap changed, see `ironrdp_session_pointer`.
+    PointerBitmap = 6,
+    /// Text was copied on the server, see `ironrdp_session_clipboard_text`.
+    ClipboardText = 7,
+    /// The session is terminated.
+    Terminated = 8,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IronRdpRect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Event returned by `ironrdp_session_poll`, the fields used depending on its kind.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IronRdpEvent {
+    pub kind: IronRdpEventKind,
+    pub rect: IronRdpRect,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl IronRdpEvent {
+    pub(crate) fn new(kind: IronRdpEventKind) -> Self {
+        Self {
+            kind,
+            rect: IronRdpRect::default(),
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+        }
+    }
+}
+
+/// Framebuffer of the session, valid until the next call taking the session mutably.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IronRdpFramebuffer {
+    pub data: *const u8,
+    pub width: u16,
+    pub height: u16,
+    /// Number of bytes per row.
+    pub stride: usize,
+}
+
+/// Pointer bitmap, in RGBA format, valid until the next call taking the session mutably.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IronRdpPointer {
+    pub data: *const u8,
+    pub width: u16,
+    pub height: u16,
+    pub hotspot_x: u16,
+    pub hotspot_y: u16,
+}
+
+/// Called with the DER-encoded certificate of the server, returns whether the connection should proceed.
+pub type IronRdpVerifyCertificateFn =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, certificate: *const u8, certificate_len: usize) -> bool>;
+
+/// Connection configuration, built with the `ironrdp_config_*` functions.
+pub struct IronRdpConfig {
+    host: Option<String>,
+    port: u16,
+    username: Option<String>,
+    password: Option<String>,
+    domain: Option<String>,
+    client_name: String,
+    desktop_size: DesktopSize,
+    keyboard_layout: u32,
+    pixel_format: PixelFormat,
+    clipboard: bool,
+    verify_certificate: IronRdpVerifyCertificateFn,
+    verify_certificate_user_data: *mut c_void,
+}
+
+impl IronRdpConfig {
+    /// Returns whether the connection should proceed with the given server certificate.
+    fn verify_certificate(&self, certificate: &[u8]) -> bool {
+        match self.verify_certificate {
+            // SAFETY: the caller of `ironrdp_config_set_certificate_verifier` guarantees the callback can be
+            // called with its user data, and the certificate is valid for the duration of the call.
+            Some(verify) => unsafe {
+                verify(
+                    self.verify_certificate_user_data,
+                    certificate.as_ptr(),
+                    certificate.len(),
+                )
+            },
+            None => {
+                warn!("No certificate verifier set, the server certificate is not verified");
+                true
+            }
+        }
+    }
+}
+
+/// Active client session, created by `ironrdp_connect`.
+pub struct IronRdpSession(Session);
+
+/// Returns the message of the last error that occurred on the calling thread, or NULL.
+///
+/// The string is valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn ironrdp_last_error_message() -> *const c_char {
+    error::with_last(|message| message.map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Creates a configuration with the default values: port 3389, 1024x768 desktop, US keyboard layout,
+/// RGBA framebuffer, clipboard disabled and no certificate verification.
+#[no_mangle]
+pub extern "C" fn ironrdp_config_new() -> *mut IronRdpConfig {
+    Box::into_raw(Box::new(IronRdpConfig {
+        host: None,
+        port: 3389,
+        username: None,
+        password: None,
+        domain: None,
+        client_name: "ironrdp-ffi".to_owned(),
+        desktop_size: DesktopSize {
+            width: 1024,
+            height: 768,
+        },
+        keyboard_layout: 0,
+        pixel_format: PixelFormat::RgbA32,
+        clipboard: false,
+        verify_certificate: None,
+        verify_certificate_user_data: ptr::null_mut(),
+    }))
+}
+
+/// # Safety
+///
+/// `config` must be NULL or returned by `ironrdp_config_new`, and not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_config_free(config: *mut IronRdpConfig) {
+    if !config.is_null() {
+        // SAFETY: guaranteed by the caller.
+        drop(unsafe { Box::from_raw(config) });
+    }
+}
+
+/// # Safety
+///
+/// `config` must be valid and `host` a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_config_set_server(
+    config: *mut IronRdpConfig,
+    host: *const c_char,
+    port: u16,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let config = unsafe { config_mut(config) }?;
+        // SAFETY: guaranteed by the caller.
+        config.host = Some(unsafe { string(host) }?.ok_or_else(|| Error::invalid_argument("host is NULL"))?);
+        config.port = port;
+        Ok(())
+    })
+}
+
+/// Sets the credentials, `domain` being optional (NULL).
+///
+/// # Safety
+///
+/// `config` must be valid and the strings NULL or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_config_set_credentials(
+    config: *mut IronRdpConfig,
+    username: *const c_char,
+    password: *const c_char,
+    domain: *const c_char,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let config = unsafe { config_mut(config) }?;
+        // SAFETY: guaranteed by the caller.
+        config.username =
+            Some(unsafe { string(username) }?.ok_or_else(|| Error::invalid_argument("username is NULL"))?);
+        // SAFETY: guaranteed by the caller.
+        config.password = unsafe { string(password) }?;
+        // SAFETY: guaranteed by the caller.
+        config.domain = unsafe { string(domain) }?;
+        Ok(())
+    })
+}
+
+/// # Safety
+///
+/// `config` must be valid and `name` a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_config_set_client_name(
+    config: *mut IronRdpConfig,
+    name: *const c_char,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let config = unsafe { config_mut(config) }?;
+        // SAFETY: guaranteed by the caller.
+        config.client_name = unsafe { string(name) }?.ok_or_else(|| Error::invalid_argument("name is NULL"))?;
+        Ok(())
+    })
+}
+
+/// # Safety
+///
+/// `config` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_config_set_desktop_size(
+    config: *mut IronRdpConfig,
+    width: u16,
+    height: u16,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        if width == 0 || height == 0 {
+            return Err(Error::invalid_argument("empty desktop size"));
+        }
+
+        // SAFETY: guaranteed by the caller.
+        let config = unsafe { config_mut(config) }?;
+        config.desktop_size = DesktopSize { width, height };
+        Ok(())
+    })
+}
+
+/// Sets the keyboard layout, as a Windows keyboard layout identifier (e.g. 0x409 for US English).
+///
+/// # Safety
+///
+/// `config` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_config_set_keyboard_layout(config: *mut IronRdpConfig, layout: u32) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let config = unsafe { config_mut(config) }?;
+        config.keyboard_layout = layout;
+        Ok(())
+    })
+}
+
+/// Sets the pixel format of the framebuffer, an `IronRdpPixelFormat`.
+///
+/// # Safety
+///
+/// `config` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_config_set_pixel_format(config: *mut IronRdpConfig, format: u32) -> IronRdpStatus {
+    ffi_call(|| {
+        let pixel_format = match format {
+            0 => PixelFormat::RgbA32,
+            1 => PixelFormat::BgrA32,
+            _ => return Err(Error::invalid_argument(format!("unknown pixel format {format}"))),
+        };
+
+        // SAFETY: guaranteed by the caller.
+        let config = unsafe { config_mut(config) }?;
+        config.pixel_format = pixel_format;
+        Ok(())
+    })
+}
+
+/// Enables the redirection of the plain text clipboard.
+///
+/// # Safety
+///
+/// `config` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_config_set_clipboard_enabled(
+    config: *mut IronRdpConfig,
+    enabled: bool,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let config = unsafe { config_mut(config) }?;
+        config.clipboard = enabled;
+        Ok(())
+    })
+}
+
+/// Sets the callback verifying the certificate of the server, called during `ironrdp_connect` before the
+/// credentials are sent.
+///
+/// Without a verifier, any certificate is accepted.
+///
+/// # Safety
+///
+/// `config` must be valid, and `verify` must be callable with `user_data` from the thread calling
+/// `ironrdp_connect`.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_config_set_certificate_verifier(
+    config: *mut IronRdpConfig,
+    verify: IronRdpVerifyCertificateFn,
+    user_data: *mut c_void,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let config = unsafe { config_mut(config) }?;
+        config.verify_certificate = verify;
+        config.verify_certificate_user_data = user_data;
+        Ok(())
+    })
+}
+
+/// Connects to the server, blocking until the session is active.
+///
+/// # Safety
+///
+/// `config` must be valid and `session` a valid pointer, set to the new session on success.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_connect(
+    config: *const IronRdpConfig,
+    session: *mut *mut IronRdpSession,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let config = unsafe { config.as_ref() }.ok_or_else(|| Error::invalid_argument("config is NULL"))?;
+
+        if session.is_null() {
+            return Err(Error::invalid_argument("session is NULL"));
+        }
+
+        let new_session = Box::new(IronRdpSession(Session::connect(config)?));
+
+        // SAFETY: `session` is not NULL, and valid as guaranteed by the caller.
+        unsafe { session.write(Box::into_raw(new_session)) };
+
+        Ok(())
+    })
+}
+
+/// Closes the connection and frees the session.
+///
+/// # Safety
+///
+/// `session` must be NULL or returned by `ironrdp_connect`, and not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_free(session: *mut IronRdpSession) {
+    if !session.is_null() {
+        // SAFETY: guaranteed by the caller.
+        drop(unsafe { Box::from_raw(session) });
+    }
+}
+
+/// Processes the frames received from the server until an event is produced, or until `timeout_ms`
+/// milliseconds elapse (the event is then `None`). A negative timeout blocks until an event is produced.
+///
+/// Returns `Terminated` once the `Terminated` event was returned.
+///
+/// # Safety
+///
+/// `session` and `event` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_poll(
+    session: *mut IronRdpSession,
+    timeout_ms: i32,
+    event: *mut IronRdpEvent,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let session = unsafe { session_mut(session) }?;
+
+        if event.is_null() {
+            return Err(Error::invalid_argument("event is NULL"));
+        }
+
+        let timeout = u64::try_from(timeout_ms).ok().map(Duration::from_millis);
+        let next_event = session
+            .poll(timeout)?
+            .unwrap_or_else(|| IronRdpEvent::new(IronRdpEventKind::None));
+
+        // SAFETY: `event` is not NULL, and valid as guaranteed by the caller.
+        unsafe { event.write(next_event) };
+
+        Ok(())
+    })
+}
+
+/// # Safety
+///
+/// `session` and `framebuffer` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_framebuffer(
+    session: *const IronRdpSession,
+    framebuffer: *mut IronRdpFramebuffer,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let session = unsafe { session.as_ref() }.ok_or_else(|| Error::invalid_argument("session is NULL"))?;
+
+        if framebuffer.is_null() {
+            return Err(Error::invalid_argument("framebuffer is NULL"));
+        }
+
+        let image = session.0.image();
+
+        // SAFETY: `framebuffer` is not NULL, and valid as guaranteed by the caller.
+        unsafe {
+            framebuffer.write(IronRdpFramebuffer {
+                data: image.data().as_ptr(),
+                width: image.width(),
+                height: image.height(),
+                stride: image.stride(),
+            })
+        };
+
+        Ok(())
+    })
+}
+
+/// Returns the last pointer bitmap received, fails with `InvalidArgument` if none was received.
+///
+/// # Safety
+///
+/// `session` and `pointer` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_pointer(
+    session: *const IronRdpSession,
+    pointer: *mut IronRdpPointer,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let session = unsafe { session.as_ref() }.ok_or_else(|| Error::invalid_argument("session is NULL"))?;
+
+        if pointer.is_null() {
+            return Err(Error::invalid_argument("pointer is NULL"));
+        }
+
+        let decoded = session
+            .0
+            .pointer()
+            .ok_or_else(|| Error::invalid_argument("no pointer bitmap received"))?;
+
+        // SAFETY: `pointer` is not NULL, and valid as guaranteed by the caller.
+        unsafe {
+            pointer.write(IronRdpPointer {
+                data: decoded.bitmap_data.as_ptr(),
+                width: decoded.width,
+                height: decoded.height,
+                hotspot_x: decoded.hotspot_x,
+                hotspot_y: decoded.hotspot_y,
+            })
+        };
+
+        Ok(())
+    })
+}
+
+/// Returns the text last copied on the server, in UTF-8, or NULL.
+///
+/// The string is valid until the next call taking the session mutably.
+///
+/// # Safety
+///
+/// `session` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_clipboard_text(session: *const IronRdpSession) -> *const c_char {
+    // SAFETY: guaranteed by the caller.
+    let Some(session) = (unsafe { session.as_ref() }) else {
+        return ptr::null();
+    };
+
+    session
+        .0
+        .remote_clipboard_text()
+        .map_or(ptr::null(), |text| text.as_ptr())
+}
+
+/// Sets the text copied on the client, in UTF-8, and advertises it to the server. NULL empties the clipboard.
+///
+/// # Safety
+///
+/// `session` must be valid and `text` NULL or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_set_clipboard_text(
+    session: *mut IronRdpSession,
+    text: *const c_char,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let session = unsafe { session_mut(session) }?;
+        // SAFETY: guaranteed by the caller.
+        let text = unsafe { string(text) }?;
+        session.set_clipboard_text(text)
+    })
+}
+
+/// Presses or releases a key, by scancode (extended keys are prefixed with 0xE0, e.g. 0xE04B for the left arrow).
+///
+/// # Safety
+///
+/// `session` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_key(
+    session: *mut IronRdpSession,
+    scancode: u16,
+    pressed: bool,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let session = unsafe { session_mut(session) }?;
+        let scancode = Scancode::from_u16(scancode);
+        let operation = if pressed {
+            Operation::KeyPressed(scancode)
+        } else {
+            Operation::KeyReleased(scancode)
+        };
+        session.apply_input([operation])
+    })
+}
+
+/// Presses or releases the key of a Unicode character, typically for the text typed with input methods.
+///
+/// # Safety
+///
+/// `session` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_unicode(
+    session: *mut IronRdpSession,
+    codepoint: u32,
+    pressed: bool,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let session = unsafe { session_mut(session) }?;
+        let character = char::from_u32(codepoint)
+            .ok_or_else(|| Error::invalid_argument(format!("invalid code point {codepoint:#x}")))?;
+        let operation = if pressed {
+            Operation::UnicodeKeyPressed(character)
+        } else {
+            Operation::UnicodeKeyReleased(character)
+        };
+        session.apply_input([operation])
+    })
+}
+
+/// # Safety
+///
+/// `session` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_mouse_move(session: *mut IronRdpSession, x: u16, y: u16) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let session = unsafe { session_mut(session) }?;
+        session.apply_input([Operation::MouseMove(MousePosition { x, y })])
+    })
+}
+
+/// Presses or releases a mouse button, an `IronRdpMouseButton`.
+///
+/// # Safety
+///
+/// `session` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_mouse_button(
+    session: *mut IronRdpSession,
+    button: u32,
+    pressed: bool,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let session = unsafe { session_mut(session) }?;
+        let button = usize::try_from(button)
+            .ok()
+            .and_then(MouseButton::from_idx)
+            .ok_or_else(|| Error::invalid_argument(format!("unknown mouse button {button}")))?;
+        let operation = if pressed {
+            Operation::MouseButtonPressed(button)
+        } else {
+            Operation::MouseButtonReleased(button)
+        };
+        session.apply_input([operation])
+    })
+}
+
+/// Rotates the mouse wheel, 120 units being one notch. Positive units scroll up, or right.
+///
+/// # Safety
+///
+/// `session` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_mouse_wheel(
+    session: *mut IronRdpSession,
+    vertical: bool,
+    units: i16,
+) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let session = unsafe { session_mut(session) }?;
+        session.apply_input([Operation::WheelRotations(WheelRotations {
+            is_vertical: vertical,
+            rotation_units: units,
+        })])
+    })
+}
+
+/// Releases all the keys and mouse buttons pressed, typically when the application loses the focus.
+///
+/// # Safety
+///
+/// `session` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_release_all_inputs(session: *mut IronRdpSession) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let session = unsafe { session_mut(session) }?;
+        session.release_all_inputs()
+    })
+}
+
+/// Requests the server to end the session. The session is terminated once the `Terminated` event is polled.
+///
+/// # Safety
+///
+/// `session` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ironrdp_session_shutdown(session: *mut IronRdpSession) -> IronRdpStatus {
+    ffi_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let session = unsafe { session_mut(session) }?;
+        session.shutdown()
+    })
+}
+
+/// Runs `f`, recording its error or panic as the last error of the thread.
+fn ffi_call(f: impl FnOnce() -> Result<(), Error>) -> IronRdpStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => IronRdpStatus::Ok,
+        Ok(Err(error)) => error.set_last(),
+        Err(_) => Error::new(IronRdpStatus::Panic, "panicked").set_last(),
+    }
+}
+
+/// # Safety
+///
+/// `config` must be NULL or valid.
+unsafe fn config_mut<'a>(config: *mut IronRdpConfig) -> Result<&'a mut IronRdpConfig, Error> {
+    // SAFETY: guaranteed by the caller.
+    unsafe { config.as_mut() }.ok_or_else(|| Error::invalid_argument("config is NULL"))
+}
+
+/// # Safety
+///
+/// `session` must be NULL or valid.
+unsafe fn session_mut<'a>(session: *mut IronRdpSession) -> Result<&'a mut Session, Error> {
+    // SAFETY: guaranteed by the caller.
+    let session = unsafe { session.as_mut() }.ok_or_else(|| Error::invalid_argument("session is NULL"))?;
+    Ok(&mut session.0)
+}
+
+/// # Safety
+///
+/// `value` must be NULL or a valid NUL-terminated string.
+unsafe fn string(value: *const c_char) -> Result<Option<String>, Error> {
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    // SAFETY: `value` is not NULL, and valid as guaranteed by the caller.
+    let value = unsafe { CStr::from_ptr(value) };
+
+    value
+        .to_str()
+        .map(|value| Some(value.to_owned()))
+        .map_err(|_| Error::invalid_argument("string is not valid UTF-8"))
+}
@@ -0,0 +1,542 @@
+use core::time::Duration;
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::io::{self, Write as _};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs as _};
+use std::sync::{mpsc, Arc};
+use std::time::Instant;
+
+use ironrdp::cliprdr::{Cliprdr, CliprdrClient};
+use ironrdp::connector::connection_activation::{ConnectionActivationSequence, ConnectionActivationState};
+use ironrdp::connector::{self, ClientConnector, Credentials, Sequence as _};
+use ironrdp::graphics::image_processing::PixelFormat;
+use ironrdp::graphics::pointer::DecodedPointer;
+use ironrdp::input::{Database, Operation};
+use ironrdp::pdu::gcc::KeyboardType;
+use ironrdp::pdu::rdp::capability_sets::MajorPlatformType;
+use ironrdp::pdu::rdp::client_info::PerformanceFlags;
+use ironrdp::session::image::DecodedImage;
+use ironrdp::session::{fast_path, ActiveStage, ActiveStageOutput};
+use ironrdp_blocking::Framed;
+use ironrdp_core::WriteBuf;
+use rustls::pki_types::ServerName;
+use sspi::network_client::reqwest_network_client::ReqwestNetworkClient;
+
+use crate::clipboard::{Clipboard, ClipboardAction, ClipboardBackend};
+use crate::error::Error;
+use crate::{IronRdpConfig, IronRdpEvent, IronRdpEventKind, IronRdpRect, IronRdpStatus};
+
+type TlsStream = rustls::StreamOwned<rustls::ClientConnection, TcpStream>;
+
+pub(crate) struct Session {
+    framed: Framed<TlsStream>,
+    active_stage: ActiveStage,
+    image: DecodedImage,
+    pixel_format: PixelFormat,
+    input: Database,
+    clipboard: Option<Clipboard>,
+    events: VecDeque<IronRdpEvent>,
+    pointer: Option<Arc<DecodedPointer>>,
+    terminated: bool,
+}
+
+impl Session {
+    /// Runs the whole connection sequence, blocking until the session is active.
+    pub(crate) fn connect(config: &IronRdpConfig) -> Result<Self, Error> {
+        let host = config
+            .host
+            .as_deref()
+            .ok_or_else(|| Error::invalid_argument("server not set"))?;
+
+        let server_addr = lookup_addr(host, config.port)?;
+
+        info!(%server_addr, "Looked up server address");
+
+        let tcp_stream = TcpStream::connect(server_addr).map_err(|e| Error::io("TCP connect", e))?;
+        tcp_stream
+            .set_nodelay(true)
+            .map_err(|e| Error::io("set TCP_NODELAY", e))?;
+
+        let mut framed = Framed::new(tcp_stream);
+
+        let mut connector = ClientConnector::new(connector_config(config)?).with_client_addr(server_addr);
+
+        let clipboard = if config.clipboard {
+            let (tx, rx) = mpsc::channel();
+            connector.attach_static_channel(Cliprdr::new(Box::new(ClipboardBackend::new(tx))));
+            Some(Clipboard::new(rx))
+        } else {
+            None
+        };
+
+        let should_upgrade = ironrdp_blocking::connect_begin(&mut framed, &mut connector)?;
+
+        debug!("TLS upgrade");
+
+        let tcp_stream = framed.into_inner_no_leftover();
+        let (tls_stream, server_public_key) = tls_upgrade(tcp_stream, host, config)?;
+
+        let upgraded = ironrdp_blocking::mark_as_upgraded(should_upgrade, &mut connector);
+        let mut framed = Framed::new(tls_stream);
+
+        let mut network_client = ReqwestNetworkClient;
+        let connection_result = ironrdp_blocking::connect_finalize(
+            upgraded,
+            &mut framed,
+            connector,
+            host.to_owned().into(),
+            server_public_key,
+            &mut network_client,
+            None,
+        )?;
+
+        info!(desktop_size = ?connection_result.desktop_size, "Connected");
+
+        let image = DecodedImage::new(
+            config.pixel_format,
+            connection_result.desktop_size.width,
+            connection_result.desktop_size.height,
+        );
+
+        Ok(Self {
+            framed,
+            active_stage: ActiveStage::new(connection_result),
+            image,
+            pixel_format: config.pixel_format,
+            input: Database::new(),
+            clipboard,
+            events: VecDeque::new(),
+            pointer: None,
+            terminated: false,
+        })
+    }
+
+    pub(crate) fn image(&self) -> &DecodedImage {
+        &self.image
+    }
+
+    pub(crate) fn pointer(&self) -> Option<&DecodedPointer> {
+        self.pointer.as_deref()
+    }
+
+    pub(crate) fn remote_clipboard_text(&self) -> Option<&CString> {
+        self.clipboard.as_ref().and_then(Clipboard::remote_text)
+    }
+
+    /// Returns the next event, reading and processing the frames received from the server until one is
+    /// produced, or until `timeout` elapses (`None` is then returned).
+    pub(crate) fn poll(&mut self, timeout: Option<Duration>) -> Result<Option<IronRdpEvent>, Error> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(Some(event));
+            }
+
+            if self.terminated {
+                return Err(Error::terminated());
+            }
+
+            // A zero timeout is rejected by the socket, the smallest one is used instead.
+            let read_timeout = deadline.map(|deadline| {
+                deadline
+                    .saturating_duration_since(Instant::now())
+                    .max(Duration::from_millis(1))
+            });
+
+            self.framed
+                .get_inner_mut()
+                .0
+                .sock
+                .set_read_timeout(read_timeout)
+                .map_err(|e| Error::io("set read timeout", e))?;
+
+            let (action, payload) = match self.framed.read_pdu() {
+                Ok(frame) => frame,
+                Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => return Ok(None),
+                Err(e) => return Err(Error::io("read frame", e)),
+            };
+
+            trace!(?action, frame_length = payload.len(), "Frame received");
+
+            let outputs = self.active_stage.process(&mut self.image, action, &payload)?;
+            self.handle_outputs(outputs)?;
+            self.process_clipboard_events()?;
+
+            if self.events.is_empty() && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(None);
+            }
+        }
+    }
+
+    pub(crate) fn apply_input(&mut self, operations: impl IntoIterator<Item = Operation>) -> Result<(), Error> {
+        let events = self.input.apply(operations);
+        let outputs = self.active_stage.process_fastpath_input(&mut self.image, &events)?;
+        self.handle_outputs(outputs)
+    }
+
+    pub(crate) fn release_all_inputs(&mut self) -> Result<(), Error> {
+        let events = self.input.release_all();
+        let outputs = self.active_stage.process_fastpath_input(&mut self.image, &events)?;
+        self.handle_outputs(outputs)
+    }
+
+    pub(crate) fn set_clipboard_text(&mut self, text: Option<String>) -> Result<(), Error> {
+        let (Some(clipboard), Some(cliprdr)) = (
+            self.clipboard.as_mut(),
+            self.active_stage.get_svc_processor_mut::<CliprdrClient>(),
+        ) else {
+            return Err(Error::new(IronRdpStatus::InvalidArgument, "clipboard not enabled"));
+        };
+
+        if let Some(messages) = clipboard.set_local_text(text, cliprdr)? {
+            let frame = self.active_stage.process_svc_processor_messages(messages)?;
+            self.write_frame(&frame)?;
+        }
+
+        Ok(())
+    }
+
+    /// Requests the server to end the session, which is terminated once the server disconnects.
+    pub(crate) fn shutdown(&mut self) -> Result<(), Error> {
+        let outputs = self.active_stage.graceful_shutdown()?;
+        self.handle_outputs(outputs)
+    }
+
+    fn handle_outputs(&mut self, outputs: Vec<ActiveStageOutput>) -> Result<(), Error> {
+        for out in outputs {
+            match out {
+                ActiveStageOutput::ResponseFrame(frame) => self.write_frame(&frame)?,
+                ActiveStageOutput::GraphicsUpdate(region) => self.events.push_back(IronRdpEvent {
+                    rect: IronRdpRect {
+                        x: region.left,
+                        y: region.top,
+                        width: region.right - region.left + 1,
+                        height: region.bottom - region.top + 1,
+                    },
+                    ..IronRdpEvent::new(IronRdpEventKind::GraphicsUpdate)
+                }),
+                ActiveStageOutput::PointerDefault => self
+                    .events
+                    .push_back(IronRdpEvent::new(IronRdpEventKind::PointerDefault)),
+                ActiveStageOutput::PointerHidden => self
+                    .events
+                    .push_back(IronRdpEvent::new(IronRdpEventKind::PointerHidden)),
+                ActiveStageOutput::PointerPosition { x, y } => self.events.push_back(IronRdpEvent {
+                    x,
+                    y,
+                    ..IronRdpEvent::new(IronRdpEventKind::PointerPosition)
+                }),
+                ActiveStageOutput::PointerBitmap(pointer) => {
+                    self.pointer = Some(pointer);
+                    self.events
+                        .push_back(IronRdpEvent::new(IronRdpEventKind::PointerBitmap));
+                }
+                ActiveStageOutput::DeactivateAll(sequence) => self.reactivate(sequence)?,
+                ActiveStageOutput::SessionEvent(event) => {
+                    debug!(?event, "Session event");
+                }
+                ActiveStageOutput::Terminate(reason) => {
+                    info!(%reason, "Session terminated");
+                    self.terminated = true;
+                    self.events.push_back(IronRdpEvent::new(IronRdpEventKind::Terminated));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Executes the Deactivation-Reactivation Sequence, typically after a resize of the desktop.
+    fn reactivate(&mut self, mut sequence: Box<ConnectionActivationSequence>) -> Result<(), Error> {
+        debug!("Received Server Deactivate All PDU, executing Deactivation-Reactivation Sequence");
+
+        // The sequence is completed at once, without the timeout of the caller.
+        self.framed
+            .get_inner_mut()
+            .0
+            .sock
+            .set_read_timeout(None)
+            .map_err(|e| Error::io("set read timeout", e))?;
+
+        let mut buf = WriteBuf::new();
+
+        loop {
+            buf.clear();
+
+            let written = if let Some(next_pdu_hint) = sequence.next_pdu_hint() {
+                let pdu = self
+                    .framed
+                    .read_by_hint(next_pdu_hint)
+                    .map_err(|e| Error::io("read frame by hint", e))?;
+                sequence.step(&pdu, &mut buf)?
+            } else {
+                sequence.step_no_input(&mut buf)?
+            };
+
+            if let Some(response_len) = written.size() {
+                self.write_frame(&buf[..response_len])?;
+            }
+
+            if let ConnectionActivationState::Finalized {
+                io_channel_id,
+                user_channel_id,
+                desktop_size,
+                no_server_pointer,
+                pointer_software_rendering,
+            } = sequence.state
+            {
+                debug!(?desktop_size, "Deactivation-Reactivation Sequence completed");
+
+                self.image = DecodedImage::new(self.pixel_format, desktop_size.width, desktop_size.height);
+                self.active_stage.set_fastpath_processor(
+                    fast_path::ProcessorBuilder {
+                        io_channel_id,
+                        user_channel_id,
+                        no_server_pointer,
+                        pointer_software_rendering,
+                    }
+                    .build(),
+                );
+                self.active_stage.set_no_server_pointer(no_server_pointer);
+
+                self.events.push_back(IronRdpEvent {
+                    width: desktop_size.width,
+                    height: desktop_size.height,
+                    ..IronRdpEvent::new(IronRdpEventKind::DesktopResized)
+                });
+
+                return Ok(());
+            }
+        }
+    }
+
+    fn process_clipboard_events(&mut self) -> Result<(), Error> {
+        let Some(clipboard) = self.clipboard.as_mut() else {
+            return Ok(());
+        };
+
+        while let Some(event) = clipboard.next_event() {
+            let Some(cliprdr) = self.active_stage.get_svc_processor_mut::<CliprdrClient>() else {
+                break;
+            };
+
+            match clipboard.handle_event(event, cliprdr)? {
+                Some(ClipboardAction::Send(messages)) => {
+                    let frame = self.active_stage.process_svc_processor_messages(messages)?;
+                    write_frame(&mut self.framed, &frame)?;
+                }
+                Some(ClipboardAction::RemoteTextReceived) => {
+                    self.events
+                        .push_back(IronRdpEvent::new(IronRdpEventKind::ClipboardText));
+                }
+                None => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Error> {
+        write_frame(&mut self.framed, frame)
+    }
+}
+
+fn write_frame(framed: &mut Framed<TlsStream>, frame: &[u8]) -> Result<(), Error> {
+    if frame.is_empty() {
+        return Ok(());
+    }
+
+    framed.write_all(frame).map_err(|e| Error::io("write frame", e))
+}
+
+fn lookup_addr(host: &str, port: u16) -> Result<SocketAddr, Error> {
+    (host, port)
+        .to_socket_addrs()
+        .map_err(|e| Error::io("lookup address", e))?
+        .next()
+        .ok_or_else(|| Error::new(IronRdpStatus::ConnectionFailed, format!("no address found for {host}")))
+}
+
+fn connector_config(config: &IronRdpConfig) -> Result<connector::Config, Error> {
+    let username = config
+        .username
+        .clone()
+        .ok_or_else(|| Error::invalid_argument("credentials not set"))?;
+
+    Ok(connector::Config {
+        credentials: Credentials::UsernamePassword {
+            username,
+            password: config.password.clone().unwrap_or_default(),
+        },
+        domain: config.domain.clone(),
+        remote_credential_guard: false,
+        enable_tls: true,
+        enable_credssp: true,
+        keyboard_type: KeyboardType::IbmEnhanced,
+        keyboard_subtype: 0,
+        keyboard_layout: config.keyboard_layout,
+        keyboard_functional_keys_count: 12,
+        ime_file_name: String::new(),
+        dig_product_id: String::new(),
+        desktop_size: config.desktop_size,
+        bitmap: None,
+        client_build: 0,
+        client_name: config.client_name.clone(),
+        client_dir: "C:\\Windows\\System32\\mstscax.dll".to_owned(),
+
+        #[cfg(windows)]
+        platform: MajorPlatformType::WINDOWS,
+        #[cfg(target_os = "macos")]
+        platform: MajorPlatformType::MACINTOSH,
+        #[cfg(target_os = "ios")]
+        platform: MajorPlatformType::IOS,
+        #[cfg(target_os = "linux")]
+        platform: MajorPlatformType::UNIX,
+        #[cfg(target_os = "android")]
+        platform: MajorPlatformType::ANDROID,
+        #[cfg(target_os = "freebsd")]
+        platform: MajorPlatformType::UNIX,
+        #[cfg(target_os = "dragonfly")]
+        platform: MajorPlatformType::UNIX,
+        #[cfg(target_os = "openbsd")]
+        platform: MajorPlatformType::UNIX,
+        #[cfg(target_os = "netbsd")]
+        platform: MajorPlatformType::UNIX,
+
+        // The pointer is drawn by the application, from the pointer events.
+        no_server_pointer: false,
+        pointer_software_rendering: false,
+        request_data: None,
+        autologon: false,
+        no_audio_playback: true,
+        message_channel: false,
+        multitransport_flags: None,
+        rail: false,
+        performance_flags: PerformanceFlags::default(),
+        desktop_scale_factor: 0,
+        monitors: Vec::new(),
+        hardware_id: None,
+        license_cache: None,
+    })
+}
+
+fn tls_upgrade(stream: TcpStream, host: &str, config: &IronRdpConfig) -> Result<(TlsStream, Vec<u8>), Error> {
+    let tls_config = rustls::ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(danger::NoCertificateVerification))
+        .with_no_client_auth();
+
+    let server_name = ServerName::try_from(host.to_owned())
+        .map_err(|e| Error::invalid_argument(format!("invalid server name: {e}")))?;
+
+    let connection = rustls::ClientConnection::new(Arc::new(tls_config), server_name)
+        .map_err(|e| Error::new(IronRdpStatus::ConnectionFailed, format!("TLS: {e}")))?;
+
+    let mut tls_stream = rustls::StreamOwned::new(connection, stream);
+
+    // Performs the handshake.
+    tls_stream.flush().map_err(|e| Error::io("TLS handshake", e))?;
+
+    let certificate = tls_stream
+        .conn
+        .peer_certificates()
+        .and_then(|certificates| certificates.first())
+        .ok_or_else(|| Error::new(IronRdpStatus::ConnectionFailed, "peer certificate is missing"))?;
+
+    // The certificate is verified by the application, before the credentials are sent.
+    if !config.verify_certificate(certificate) {
+        return Err(Error::new(
+            IronRdpStatus::ConnectionFailed,
+            "server certificate rejected",
+        ));
+    }
+
+    let server_public_key = extract_tls_server_public_key(certificate)?;
+
+    Ok((tls_stream, server_public_key))
+}
+
+fn extract_tls_server_public_key(certificate: &[u8]) -> Result<Vec<u8>, Error> {
+    use x509_cert::der::Decode as _;
+
+    let certificate = x509_cert::Certificate::from_der(certificate).map_err(|e| {
+        Error::new(
+            IronRdpStatus::ConnectionFailed,
+            format!("invalid server certificate: {e}"),
+        )
+    })?;
+
+    let server_public_key = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key
+        .as_bytes()
+        .ok_or_else(|| {
+            Error::new(
+                IronRdpStatus::ConnectionFailed,
+                "subject public key BIT STRING is not aligned",
+            )
+        })?
+        .to_owned();
+
+    Ok(server_public_key)
+}
+
+mod danger {
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::{pki_types, DigitallySignedStruct, Error, SignatureScheme};
+
+    /// The certificate is verified by the application once the handshake is done.
+    #[derive(Debug)]
+    pub(super) struct NoCertificateVerification;
+
+    impl ServerCertVerifier for NoCertificateVerification {
+        fn verify_server_cert(
+            &self,
+            _: &pki_types::CertificateDer<'_>,
+            _: &[pki_types::CertificateDer<'_>],
+            _: &pki_types::ServerName<'_>,
+            _: &[u8],
+            _: pki_types::UnixTime,
+        ) -> Result<ServerCertVerified, Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            _: &[u8],
+            _: &pki_types::CertificateDer<'_>,
+            _: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            _: &[u8],
+            _: &pki_types::CertificateDer<'_>,
+            _: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            vec![
+                SignatureScheme::RSA_PKCS1_SHA1,
+                SignatureScheme::ECDSA_SHA1_Legacy,
+                SignatureScheme::RSA_PKCS1_SHA256,
+                SignatureScheme::ECDSA_NISTP256_SHA256,
+                SignatureScheme::RSA_PKCS1_SHA384,
+                SignatureScheme::ECDSA_NISTP384_SHA384,
+                SignatureScheme::RSA_PKCS1_SHA512,
+                SignatureScheme::ECDSA_NISTP521_SHA512,
+                SignatureScheme::RSA_PSS_SHA256,
+                SignatureScheme::RSA_PSS_SHA384,
+                SignatureScheme::RSA_PSS_SHA512,
+                SignatureScheme::ED25519,
+                SignatureScheme::ED448,
+            ]
+        }
+    }
+}
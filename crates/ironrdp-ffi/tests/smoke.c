@@ -0,0 +1,91 @@
+/*
+ * Smoke test of the C API, built and run against the static library by `cargo xtask ffi smoke-test`.
+ *
+ * No server is needed: the test exercises the configuration and the failure paths of ironrdp_connect().
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "ironrdp.h"
+
+#define CHECK(condition)                                                                                       \
+    do {                                                                                                       \
+        if (!(condition)) {                                                                                    \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition);                     \
+            exit(EXIT_FAILURE);                                                                                \
+        }                                                                                                      \
+    } while (0)
+
+#define CHECK_STATUS(call, expected)                                                                           \
+    do {                                                                                                       \
+        IronRdpStatus status_ = (call);                                                                        \
+        if (status_ != (expected)) {                                                                           \
+            const char *message_ = ironrdp_last_error_message();                                               \
+            fprintf(stderr, "%s:%d: %s returned %d (%s)\n", __FILE__, __LINE__, #call, (int)status_,           \
+                    message_ != NULL ? message_ : "no error message");                                         \
+            exit(EXIT_FAILURE);                                                                                \
+        }                                                                                                      \
+    } while (0)
+
+static void test_config(void) {
+    IronRdpConfig *config = ironrdp_config_new();
+    CHECK(config != NULL);
+
+    CHECK_STATUS(ironrdp_config_set_server(config, "localhost", 3389), IRONRDP_STATUS_OK);
+    CHECK_STATUS(ironrdp_config_set_credentials(config, "user", "password", NULL), IRONRDP_STATUS_OK);
+    CHECK_STATUS(ironrdp_config_set_client_name(config, "smoke-test"), IRONRDP_STATUS_OK);
+    CHECK_STATUS(ironrdp_config_set_desktop_size(config, 1280, 720), IRONRDP_STATUS_OK);
+    CHECK_STATUS(ironrdp_config_set_keyboard_layout(config, 0x409), IRONRDP_STATUS_OK);
+    CHECK_STATUS(ironrdp_config_set_pixel_format(config, IRONRDP_PIXEL_FORMAT_BGRA32), IRONRDP_STATUS_OK);
+    CHECK_STATUS(ironrdp_config_set_clipboard_enabled(config, true), IRONRDP_STATUS_OK);
+
+    ironrdp_config_free(config);
+}
+
+static void test_invalid_arguments(void) {
+    IronRdpConfig *config = ironrdp_config_new();
+    CHECK(config != NULL);
+
+    CHECK_STATUS(ironrdp_config_set_server(config, NULL, 3389), IRONRDP_STATUS_INVALID_ARGUMENT);
+    CHECK(ironrdp_last_error_message() != NULL);
+
+    CHECK_STATUS(ironrdp_config_set_server(NULL, "localhost", 3389), IRONRDP_STATUS_INVALID_ARGUMENT);
+    CHECK_STATUS(ironrdp_config_set_pixel_format(config, 42), IRONRDP_STATUS_INVALID_ARGUMENT);
+
+    IronRdpSession *session = NULL;
+    CHECK_STATUS(ironrdp_connect(config, &session), IRONRDP_STATUS_INVALID_ARGUMENT);
+    CHECK(session == NULL);
+
+    ironrdp_config_free(config);
+
+    /* Freeing NULL is a no-op. */
+    ironrdp_config_free(NULL);
+    ironrdp_session_free(NULL);
+}
+
+static void test_connection_refused(void) {
+    IronRdpConfig *config = ironrdp_config_new();
+    CHECK(config != NULL);
+
+    /* Nothing listens on the port 1 of the loopback interface. */
+    CHECK_STATUS(ironrdp_config_set_server(config, "127.0.0.1", 1), IRONRDP_STATUS_OK);
+    CHECK_STATUS(ironrdp_config_set_credentials(config, "user", "password", NULL), IRONRDP_STATUS_OK);
+
+    IronRdpSession *session = NULL;
+    CHECK_STATUS(ironrdp_connect(config, &session), IRONRDP_STATUS_IO);
+    CHECK(session == NULL);
+    CHECK(ironrdp_last_error_message() != NULL);
+
+    ironrdp_config_free(config);
+}
+
+int main(void) {
+    test_config();
+    test_invalid_arguments();
+    test_connection_refused();
+
+    printf("ironrdp-ffi smoke test passed\n");
+
+    return EXIT_SUCCESS;
+}
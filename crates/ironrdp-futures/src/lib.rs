@@ -78,6 +78,10 @@ where
             Ok(())
         })
     }
+
+    fn write_all_vectored<'a>(&'a mut self, bufs: &'a [&'a [u8]]) -> Self::WriteAllFut<'a> {
+        Box::pin(write_all_vectored(&mut self.inner, bufs))
+    }
 }
 
 pub type LocalFuturesFramed<S> = Framed<LocalFuturesStream<S>>;
@@ -148,4 +152,29 @@ where
             Ok(())
         })
     }
+
+    fn write_all_vectored<'a>(&'a mut self, bufs: &'a [&'a [u8]]) -> Self::WriteAllFut<'a> {
+        Box::pin(write_all_vectored(&mut self.inner, bufs))
+    }
+}
+
+async fn write_all_vectored<S>(stream: &mut S, bufs: &[&[u8]]) -> io::Result<()>
+where
+    S: Unpin + AsyncWrite,
+{
+    use futures_util::io::AsyncWriteExt as _;
+
+    let mut bufs = VectoredBufs::new(bufs);
+
+    while !bufs.is_empty() {
+        let written = stream.write_vectored(&bufs.io_slices()).await?;
+
+        if written == 0 {
+            return Err(io::Error::from(io::ErrorKind::WriteZero));
+        }
+
+        bufs.advance(written);
+    }
+
+    stream.flush().await
 }
@@ -77,6 +77,10 @@ pub fn pdu_decode(data: &[u8]) {
 
     let _ = decode::<ironrdp_rdpsnd::pdu::ServerAudioOutputPdu<'_>>(data);
     let _ = decode::<ironrdp_rdpsnd::pdu::ClientAudioOutputPdu>(data);
+
+    let _ = decode::<ironrdp_rail::pdu::RailPdu>(data);
+
+    let _ = decode::<ironrdp_audin::pdu::AudioInputPdu>(data);
 }
 
 pub fn rle_decompress_bitmap(input: BitmapInput<'_>) {
@@ -122,6 +126,9 @@ pub fn rdp6_decode_bitmap_stream_to_rgb24(input: &BitmapInput<'_>) {
 pub fn cliprdr_format(input: &[u8]) {
     use ironrdp_cliprdr_format::bitmap::{dib_to_png, dibv5_to_png, png_to_cf_dib, png_to_cf_dibv5};
     use ironrdp_cliprdr_format::html::{cf_html_to_plain_html, plain_html_to_cf_html};
+    use ironrdp_cliprdr_format::text::{
+        cf_unicodetext_to_string, string_to_cf_unicodetext, to_crlf_line_endings, to_lf_line_endings,
+    };
 
     let _ = png_to_cf_dib(input);
     let _ = png_to_cf_dibv5(input);
@@ -131,8 +138,13 @@ pub fn cliprdr_format(input: &[u8]) {
 
     let _ = cf_html_to_plain_html(input);
 
+    let _ = cf_unicodetext_to_string(input);
+
     if let Ok(input) = core::str::from_utf8(input) {
         let _ = plain_html_to_cf_html(input);
+        let _ = to_crlf_line_endings(input);
+        let _ = to_lf_line_endings(input);
+        let _ = string_to_cf_unicodetext(input);
     }
 }
 
@@ -141,7 +153,12 @@ pub fn channel_process(input: &[u8]) {
 
     let mut rdpdr = ironrdp_rdpdr::Rdpdr::new(Box::new(ironrdp_rdpdr::NoopRdpdrBackend), "Backend".to_owned())
         .with_smartcard(1)
-        .with_drives(None);
+        .with_drives(None)
+        .with_printer(
+            2,
+            ironrdp_rdpdr::pdu::efs::PrinterDeviceData::new("Printer".to_owned(), "Driver".to_owned()),
+        )
+        .expect("printer device data is valid");
 
     let _ = rdpdr.process(input);
 }
@@ -0,0 +1,99 @@
+//! Bulk compression of the slow-path PDUs and of the virtual channel data
+//!
+//! The compression types are the ones advertised by the client in the Client Info PDU: MPPC with an 8 KB history
+//! (RDP 4.0) or a 64 KB history (RDP 5.0), and RDP 6.1. RDP 6.0 (NCRUSH) is not supported, the 64 KB MPPC being
+//! used instead with the clients advertising it.
+
+mod mppc;
+mod rdp61;
+
+use ironrdp_pdu::rdp::client_info::CompressionType;
+use ironrdp_pdu::rdp::headers::CompressionFlags;
+use thiserror::Error;
+
+pub use self::mppc::{MppcCompressor, MppcDecompressor, MppcLevel};
+pub use self::rdp61::{Rdp61Compressor, Rdp61Decompressor};
+
+/// Bulk compressor of one of the compression types.
+///
+/// Each compressor keeps a history shared by all the data it compresses, so a separate compressor is needed for
+/// each stream decompressed separately by the client (e.g. the slow-path PDUs and the virtual channels).
+pub enum BulkCompressor {
+    Mppc(MppcCompressor),
+    Rdp61(Rdp61Compressor),
+}
+
+impl BulkCompressor {
+    /// Creates a compressor of the highest supported compression type not above `compression_type`.
+    pub fn new(compression_type: CompressionType) -> Self {
+        match compression_type {
+            CompressionType::K8 => Self::Mppc(MppcCompressor::new(MppcLevel::K8)),
+            CompressionType::K64 | CompressionType::Rdp6 => Self::Mppc(MppcCompressor::new(MppcLevel::K64)),
+            CompressionType::Rdp61 => Self::Rdp61(Rdp61Compressor::new()),
+        }
+    }
+
+    /// Returns the compression type to be sent along the compression flags.
+    pub fn compression_type(&self) -> CompressionType {
+        match self {
+            Self::Mppc(mppc) => match mppc.level() {
+                MppcLevel::K8 => CompressionType::K8,
+                MppcLevel::K64 => CompressionType::K64,
+            },
+            Self::Rdp61(_) => CompressionType::Rdp61,
+        }
+    }
+
+    /// Compresses `input` into `output`.
+    ///
+    /// When the returned flags do not contain [`CompressionFlags::COMPRESSED`], `output` is left untouched and
+    /// `input` must be sent as is.
+    pub fn compress(&mut self, input: &[u8], output: &mut Vec<u8>) -> CompressionFlags {
+        match self {
+            Self::Mppc(mppc) => mppc.compress(input, output),
+            Self::Rdp61(rdp61) => rdp61.compress(input, output),
+        }
+    }
+}
+
+/// Bulk decompressor of one of the compression types.
+pub enum BulkDecompressor {
+    Mppc(MppcDecompressor),
+    Rdp61(Rdp61Decompressor),
+}
+
+impl BulkDecompressor {
+    /// Creates a decompressor of the `compression_type` data, RDP 6.0 being unsupported.
+    pub fn new(compression_type: CompressionType) -> Result<Self, BulkError> {
+        match compression_type {
+            CompressionType::K8 => Ok(Self::Mppc(MppcDecompressor::new(MppcLevel::K8))),
+            CompressionType::K64 => Ok(Self::Mppc(MppcDecompressor::new(MppcLevel::K64))),
+            CompressionType::Rdp6 => Err(BulkError::UnsupportedCompressionType(compression_type)),
+            CompressionType::Rdp61 => Ok(Self::Rdp61(Rdp61Decompressor::new())),
+        }
+    }
+
+    /// Decompresses `input` according to its compression `flags`, and returns the decompressed data.
+    pub fn decompress<'a>(&'a mut self, input: &'a [u8], flags: CompressionFlags) -> Result<&'a [u8], BulkError> {
+        match self {
+            Self::Mppc(mppc) => mppc.decompress(input, flags),
+            Self::Rdp61(rdp61) => rdp61.decompress(input, flags),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum BulkError {
+    #[error("unsupported compression type {0:?}")]
+    UnsupportedCompressionType(CompressionType),
+    #[error("compressed data ends unexpectedly")]
+    UnexpectedEnd,
+    #[error("invalid copy-offset")]
+    InvalidCopyOffset,
+    #[error("invalid length-of-match")]
+    InvalidLengthOfMatch,
+    #[error("invalid compression flags")]
+    InvalidFlags,
+    #[error("decompressed data does not fit into the history")]
+    HistoryOverflow,
+}
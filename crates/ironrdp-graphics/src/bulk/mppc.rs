@@ -0,0 +1,378 @@
+use ironrdp_pdu::rdp::headers::CompressionFlags;
+
+use super::BulkError;
+use crate::utils::{matching_length, BitWriter};
+
+/// Size of the table of the last positions of the hashed 3-byte sequences.
+const HASH_TABLE_SIZE: usize = 1 << 12;
+
+/// Marks an empty entry of the hash table.
+const NO_POSITION: usize = usize::MAX;
+
+/// History size of MPPC, selecting the encoding of the copy-offsets.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MppcLevel {
+    /// RDP 4.0 bulk compression, with an 8 KB history.
+    K8,
+    /// RDP 5.0 bulk compression, with a 64 KB history.
+    K64,
+}
+
+impl MppcLevel {
+    fn history_size(self) -> usize {
+        match self {
+            Self::K8 => 8 * 1024,
+            Self::K64 => 64 * 1024,
+        }
+    }
+}
+
+/// MPPC compressor (RDP 4.0 and 5.0 bulk compression), as specified in MS-RDPBCGR and RFC 2118.
+pub struct MppcCompressor {
+    level: MppcLevel,
+    history: Vec<u8>,
+    offset: usize,
+    hash_table: Vec<usize>,
+}
+
+impl MppcCompressor {
+    pub fn new(level: MppcLevel) -> Self {
+        Self {
+            level,
+            history: vec![0; level.history_size()],
+            offset: 0,
+            hash_table: vec![NO_POSITION; HASH_TABLE_SIZE],
+        }
+    }
+
+    pub fn level(&self) -> MppcLevel {
+        self.level
+    }
+
+    /// Compresses `input` into `output`.
+    ///
+    /// When the returned flags do not contain [`CompressionFlags::COMPRESSED`], `output` is left untouched and
+    /// `input` is sent as is: the compression did not reduce its size, and the history was flushed.
+    pub fn compress(&mut self, input: &[u8], output: &mut Vec<u8>) -> CompressionFlags {
+        if input.len() >= self.history.len() {
+            self.flush();
+            return CompressionFlags::FLUSHED;
+        }
+
+        let mut flags = CompressionFlags::COMPRESSED;
+
+        if self.offset + input.len() > self.history.len() {
+            self.offset = 0;
+            self.hash_table.fill(NO_POSITION);
+            flags |= CompressionFlags::AT_FRONT;
+        }
+
+        let start = self.offset;
+        let end = start + input.len();
+        self.history[start..end].copy_from_slice(input);
+
+        let initial_len = output.len();
+        let mut writer = BitWriter::new(output);
+        let mut position = start;
+
+        while position < end {
+            if let Some((match_position, length)) = self.find_match(position, end) {
+                write_copy_offset(&mut writer, self.level, position - match_position);
+                write_length_of_match(&mut writer, length);
+
+                for skipped in position + 1..position + length {
+                    self.insert(skipped, end);
+                }
+                position += length;
+            } else {
+                write_literal(&mut writer, self.history[position]);
+                position += 1;
+            }
+        }
+
+        writer.finish();
+
+        if output.len() - initial_len >= input.len() {
+            output.truncate(initial_len);
+            self.flush();
+            return CompressionFlags::FLUSHED;
+        }
+
+        self.offset = end;
+
+        flags
+    }
+
+    /// Resets the history, the next compressed data being sent with [`CompressionFlags::AT_FRONT`].
+    fn flush(&mut self) {
+        self.history.fill(0);
+        self.offset = self.history.len();
+    }
+
+    /// Returns the position and length of the longest sequence of the history matching the data at
+    /// `position`, when at least 3 bytes long.
+    fn find_match(&mut self, position: usize, end: usize) -> Option<(usize, usize)> {
+        let candidate = self.insert(position, end)?;
+
+        let max_length = (end - position).min(self.level.history_size() - 1);
+        let length = matching_length(
+            &self.history[position..position + max_length],
+            &self.history[candidate..],
+        );
+
+        (length >= 3).then_some((candidate, length))
+    }
+
+    /// Records the 3-byte sequence at `position`, returning the previous position of the same hash.
+    fn insert(&mut self, position: usize, end: usize) -> Option<usize> {
+        if position + 3 > end {
+            return None;
+        }
+
+        let key = hash(&self.history[position..position + 3]);
+        let previous = core::mem::replace(&mut self.hash_table[key], position);
+
+        (previous != NO_POSITION).then_some(previous)
+    }
+}
+
+/// MPPC decompressor, keeping the history shared by the successive packets.
+pub struct MppcDecompressor {
+    level: MppcLevel,
+    history: Vec<u8>,
+    offset: usize,
+}
+
+impl MppcDecompressor {
+    pub fn new(level: MppcLevel) -> Self {
+        Self {
+            level,
+            history: vec![0; level.history_size()],
+            offset: 0,
+        }
+    }
+
+    /// Decompresses `input` according to its compression `flags`, and returns the decompressed data.
+    pub fn decompress<'a>(&'a mut self, input: &'a [u8], flags: CompressionFlags) -> Result<&'a [u8], BulkError> {
+        if flags.contains(CompressionFlags::FLUSHED) {
+            self.history.fill(0);
+            self.offset = 0;
+        }
+
+        if !flags.contains(CompressionFlags::COMPRESSED) {
+            return Ok(input);
+        }
+
+        if flags.contains(CompressionFlags::AT_FRONT) {
+            self.offset = 0;
+        }
+
+        let start = self.offset;
+        let mut reader = BitReader::new(input);
+
+        // The last byte is padded with less than 8 bits, and all the encodings are at least 8 bits long.
+        while reader.remaining() >= 8 {
+            if reader.peek(1) == 0 {
+                reader.skip(1);
+                let value = reader.read(7)? as u8;
+                self.push(value)?;
+                continue;
+            }
+
+            if reader.peek(2) == 0b10 {
+                reader.skip(2);
+                let value = reader.read(7)? as u8 | 0x80;
+                self.push(value)?;
+                continue;
+            }
+
+            let copy_offset = read_copy_offset(&mut reader, self.level)?;
+            let length = read_length_of_match(&mut reader)?;
+
+            if copy_offset == 0 || copy_offset > self.offset {
+                return Err(BulkError::InvalidCopyOffset);
+            }
+
+            for _ in 0..length {
+                let value = self.history[self.offset - copy_offset];
+                self.push(value)?;
+            }
+        }
+
+        Ok(&self.history[start..self.offset])
+    }
+
+    fn push(&mut self, value: u8) -> Result<(), BulkError> {
+        let slot = self.history.get_mut(self.offset).ok_or(BulkError::HistoryOverflow)?;
+        *slot = value;
+        self.offset += 1;
+
+        Ok(())
+    }
+}
+
+fn hash(bytes: &[u8]) -> usize {
+    let value = u32::from(bytes[0]) | (u32::from(bytes[1]) << 8) | (u32::from(bytes[2]) << 16);
+
+    (value.wrapping_mul(0x9E37_79B1) >> (32 - HASH_TABLE_SIZE.trailing_zeros())) as usize
+}
+
+fn write_literal(writer: &mut BitWriter<'_>, value: u8) {
+    if value < 0x80 {
+        writer.write(u32::from(value), 8);
+    } else {
+        writer.write(0b10, 2);
+        writer.write(u32::from(value & 0x7F), 7);
+    }
+}
+
+fn write_copy_offset(writer: &mut BitWriter<'_>, level: MppcLevel, copy_offset: usize) {
+    let copy_offset = copy_offset as u32;
+
+    match level {
+        MppcLevel::K8 => match copy_offset {
+            0..=63 => {
+                writer.write(0b1111, 4);
+                writer.write(copy_offset, 6);
+            }
+            64..=319 => {
+                writer.write(0b1110, 4);
+                writer.write(copy_offset - 64, 8);
+            }
+            _ => {
+                writer.write(0b110, 3);
+                writer.write(copy_offset - 320, 13);
+            }
+        },
+        MppcLevel::K64 => match copy_offset {
+            0..=63 => {
+                writer.write(0b11111, 5);
+                writer.write(copy_offset, 6);
+            }
+            64..=319 => {
+                writer.write(0b11110, 5);
+                writer.write(copy_offset - 64, 8);
+            }
+            320..=2367 => {
+                writer.write(0b1110, 4);
+                writer.write(copy_offset - 320, 11);
+            }
+            _ => {
+                writer.write(0b110, 3);
+                writer.write(copy_offset - 2368, 16);
+            }
+        },
+    }
+}
+
+fn read_copy_offset(reader: &mut BitReader<'_>, level: MppcLevel) -> Result<usize, BulkError> {
+    let copy_offset = match level {
+        MppcLevel::K8 => {
+            if reader.peek(4) == 0b1111 {
+                reader.skip(4);
+                reader.read(6)?
+            } else if reader.peek(4) == 0b1110 {
+                reader.skip(4);
+                reader.read(8)? + 64
+            } else {
+                reader.skip(3);
+                reader.read(13)? + 320
+            }
+        }
+        MppcLevel::K64 => {
+            if reader.peek(5) == 0b11111 {
+                reader.skip(5);
+                reader.read(6)?
+            } else if reader.peek(5) == 0b11110 {
+                reader.skip(5);
+                reader.read(8)? + 64
+            } else if reader.peek(4) == 0b1110 {
+                reader.skip(4);
+                reader.read(11)? + 320
+            } else {
+                reader.skip(3);
+                reader.read(16)? + 2368
+            }
+        }
+    };
+
+    Ok(copy_offset as usize)
+}
+
+/// Writes a length of at least 3: 3 is encoded as `0`, and a length in `2^k..2^(k+1)` as `k - 1` ones, a zero
+/// and the `k` low bits of the length.
+fn write_length_of_match(writer: &mut BitWriter<'_>, length: usize) {
+    let length = length as u32;
+
+    if length == 3 {
+        writer.write(0, 1);
+        return;
+    }
+
+    let k = 31 - length.leading_zeros();
+    writer.write((1 << k) - 2, k);
+    writer.write(length - (1 << k), k);
+}
+
+fn read_length_of_match(reader: &mut BitReader<'_>) -> Result<usize, BulkError> {
+    let mut ones = 0;
+    while reader.read(1)? == 1 {
+        ones += 1;
+
+        if ones > 15 {
+            return Err(BulkError::InvalidLengthOfMatch);
+        }
+    }
+
+    if ones == 0 {
+        return Ok(3);
+    }
+
+    let k = ones + 1;
+
+    Ok(((1 << k) + reader.read(k)?) as usize)
+}
+
+/// Reads bits, most significant bit first.
+struct BitReader<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Self { input, position: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.input.len() * 8 - self.position
+    }
+
+    /// Returns the next `count` bits without consuming them, the missing bits being zeros.
+    fn peek(&self, count: u32) -> u32 {
+        (0..count as usize).fold(0, |value, i| {
+            let position = self.position + i;
+            let bit = self
+                .input
+                .get(position / 8)
+                .map_or(0, |byte| (byte >> (7 - position % 8)) & 1);
+
+            (value << 1) | u32::from(bit)
+        })
+    }
+
+    fn skip(&mut self, count: u32) {
+        self.position += count as usize;
+    }
+
+    fn read(&mut self, count: u32) -> Result<u32, BulkError> {
+        if self.remaining() < count as usize {
+            return Err(BulkError::UnexpectedEnd);
+        }
+
+        let value = self.peek(count);
+        self.skip(count);
+
+        Ok(value)
+    }
+}
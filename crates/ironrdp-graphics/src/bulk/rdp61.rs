@@ -0,0 +1,182 @@
+use ironrdp_pdu::rdp::headers::CompressionFlags;
+
+use super::mppc::{MppcCompressor, MppcDecompressor, MppcLevel};
+use super::BulkError;
+
+/// Size of the history of the level-1 compression.
+const HISTORY_SIZE: usize = 2_000_000;
+
+/// Size of the Level1ComprFlags and Level2ComprFlags header.
+const HEADER_SIZE: usize = 2;
+
+/// Size of a RDP61_MATCH_DETAILS structure.
+const MATCH_DETAILS_SIZE: usize = 8;
+
+/// Compression type of the level-2 compressor, in the Level2ComprFlags.
+const MPPC_64K_TYPE: u8 = 0x01;
+
+/// L1_COMPRESSED
+const L1_COMPRESSED: u8 = 0x01;
+/// L1_NO_COMPRESSION
+const L1_NO_COMPRESSION: u8 = 0x02;
+/// L1_PACKET_AT_FRONT
+const L1_PACKET_AT_FRONT: u8 = 0x04;
+/// L1_INNER_COMPRESSION
+const L1_INNER_COMPRESSION: u8 = 0x10;
+
+/// RDP 6.1 bulk compressor, as specified in MS-RDPEGDI.
+///
+/// The level-1 compression, matching long sequences over a 2 MB history, is not performed: the data is only
+/// compressed by the level-2 compressor, which is MPPC with a 64 KB history.
+pub struct Rdp61Compressor {
+    level2: MppcCompressor,
+    level2_output: Vec<u8>,
+}
+
+impl Rdp61Compressor {
+    pub fn new() -> Self {
+        Self {
+            level2: MppcCompressor::new(MppcLevel::K64),
+            level2_output: Vec::new(),
+        }
+    }
+
+    /// Compresses `input` into `output`, as [`MppcCompressor::compress`].
+    pub fn compress(&mut self, input: &[u8], output: &mut Vec<u8>) -> CompressionFlags {
+        // Not referring to the level-1 history, the data is always written at its front.
+        let mut level1_flags = L1_NO_COMPRESSION | L1_PACKET_AT_FRONT;
+
+        self.level2_output.clear();
+        let level2_flags = self.level2.compress(input, &mut self.level2_output);
+
+        let level1_data = if level2_flags.contains(CompressionFlags::COMPRESSED) {
+            level1_flags |= L1_INNER_COMPRESSION;
+            self.level2_output.as_slice()
+        } else {
+            input
+        };
+
+        output.reserve(HEADER_SIZE + level1_data.len());
+        output.push(level1_flags);
+        output.push(level2_flags.bits() | MPPC_64K_TYPE);
+        output.extend_from_slice(level1_data);
+
+        CompressionFlags::COMPRESSED
+    }
+}
+
+impl Default for Rdp61Compressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// RDP 6.1 bulk decompressor.
+pub struct Rdp61Decompressor {
+    level2: MppcDecompressor,
+    history: Vec<u8>,
+    offset: usize,
+}
+
+impl Rdp61Decompressor {
+    pub fn new() -> Self {
+        Self {
+            level2: MppcDecompressor::new(MppcLevel::K64),
+            history: vec![0; HISTORY_SIZE],
+            offset: 0,
+        }
+    }
+
+    /// Decompresses `input` according to its compression `flags`, and returns the decompressed data.
+    pub fn decompress<'a>(&'a mut self, input: &'a [u8], flags: CompressionFlags) -> Result<&'a [u8], BulkError> {
+        if !flags.contains(CompressionFlags::COMPRESSED) {
+            return Ok(input);
+        }
+
+        if input.len() < HEADER_SIZE {
+            return Err(BulkError::UnexpectedEnd);
+        }
+
+        let level1_flags = input[0];
+        let level2_flags = CompressionFlags::from_bits_truncate(input[1]);
+
+        // The flushes of the level-2 history are given even when the level-2 compression is not used.
+        let level1_input =
+            if level1_flags & L1_INNER_COMPRESSION != 0 || level2_flags.contains(CompressionFlags::FLUSHED) {
+                self.level2.decompress(&input[HEADER_SIZE..], level2_flags)?
+            } else {
+                &input[HEADER_SIZE..]
+            };
+
+        if level1_flags & L1_PACKET_AT_FRONT != 0 {
+            self.offset = 0;
+        }
+
+        let start = self.offset;
+        let end = if level1_flags & L1_COMPRESSED != 0 {
+            decompress_level1(level1_input, &mut self.history, start)?
+        } else if level1_flags & L1_NO_COMPRESSION != 0 {
+            let end = start + level1_input.len();
+            let destination = self.history.get_mut(start..end).ok_or(BulkError::HistoryOverflow)?;
+            destination.copy_from_slice(level1_input);
+            end
+        } else {
+            return Err(BulkError::InvalidFlags);
+        };
+        self.offset = end;
+
+        Ok(&self.history[start..end])
+    }
+}
+
+impl Default for Rdp61Decompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Copies the matches and the literals between them of level-1 compressed data into the history, returning
+/// the end of the decompressed data.
+fn decompress_level1(input: &[u8], history: &mut [u8], start: usize) -> Result<usize, BulkError> {
+    let count = input.get(..2).ok_or(BulkError::UnexpectedEnd)?;
+    let count = usize::from(u16::from_le_bytes([count[0], count[1]]));
+
+    let details = input
+        .get(2..2 + count * MATCH_DETAILS_SIZE)
+        .ok_or(BulkError::UnexpectedEnd)?;
+    let mut literals = &input[2 + count * MATCH_DETAILS_SIZE..];
+    let mut offset = start;
+
+    for details in details.chunks_exact(MATCH_DETAILS_SIZE) {
+        let length = usize::from(u16::from_le_bytes([details[0], details[1]]));
+        let output_offset = usize::from(u16::from_le_bytes([details[2], details[3]]));
+        let history_offset = u32::from_le_bytes([details[4], details[5], details[6], details[7]]) as usize;
+
+        let destination = start + output_offset;
+        let gap = destination.checked_sub(offset).ok_or(BulkError::InvalidCopyOffset)?;
+        if gap > literals.len() {
+            return Err(BulkError::UnexpectedEnd);
+        }
+        if destination + length > history.len() || history_offset + length > history.len() {
+            return Err(BulkError::HistoryOverflow);
+        }
+
+        let (gap_literals, remaining) = literals.split_at(gap);
+        history[offset..destination].copy_from_slice(gap_literals);
+        literals = remaining;
+
+        // The matched sequence may overlap the copied data.
+        for i in 0..length {
+            history[destination + i] = history[history_offset + i];
+        }
+        offset = destination + length;
+    }
+
+    let end = offset + literals.len();
+    history
+        .get_mut(offset..end)
+        .ok_or(BulkError::HistoryOverflow)?
+        .copy_from_slice(literals);
+
+    Ok(end)
+}
@@ -0,0 +1,183 @@
+//! Framebuffer formats with more than 8 bits per color channel.
+//!
+//! None of the RDP codecs carries more than 8 bits per channel yet, but the presenters able to display 10-bit
+//! color (HDR swap chains, `GL_RGB10_A2` textures...) can work with a single [`FramebufferFormat`], and the
+//! frames are converted for the presenters which cannot.
+
+use std::io;
+
+use crate::image_processing::{PixelFormat, Rgba};
+
+/// Maximum value of a 10-bit channel.
+const MAX_10: u16 = 0x3FF;
+
+/// Layout of the pixels of a framebuffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FramebufferFormat {
+    /// 8 bits per channel.
+    Standard(PixelFormat),
+    /// 10 bits per color channel and 2 bits of alpha, packed in a little-endian 32-bit integer with red in the
+    /// least significant bits (`DXGI_FORMAT_R10G10B10A2_UNORM`, `VK_FORMAT_A2B10G10R10_UNORM_PACK32`).
+    Rgb10A2,
+}
+
+impl FramebufferFormat {
+    pub const fn bytes_per_pixel(self) -> u8 {
+        match self {
+            Self::Standard(format) => format.bytes_per_pixel(),
+            Self::Rgb10A2 => 4,
+        }
+    }
+
+    /// Returns the number of bits of each color channel.
+    pub const fn bits_per_channel(self) -> u8 {
+        match self {
+            Self::Standard(_) => 8,
+            Self::Rgb10A2 => 10,
+        }
+    }
+
+    fn read_color(self, buffer: &[u8]) -> io::Result<Rgba10> {
+        match self {
+            Self::Standard(format) => format.read_color(buffer).map(Rgba10::from),
+            Self::Rgb10A2 => {
+                let bytes = buffer
+                    .get(..4)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "input buffer is not large enough"))?;
+                let value = u32::from_le_bytes(bytes.try_into().expect("four bytes"));
+
+                Ok(Rgba10::unpack(value))
+            }
+        }
+    }
+
+    fn write_color(self, color: Rgba10, buffer: &mut [u8]) -> io::Result<()> {
+        match self {
+            Self::Standard(format) => format.write_color(color.into(), buffer),
+            Self::Rgb10A2 => {
+                let bytes = buffer
+                    .get_mut(..4)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "output buffer is not large enough"))?;
+                bytes.copy_from_slice(&color.pack().to_le_bytes());
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<PixelFormat> for FramebufferFormat {
+    fn from(format: PixelFormat) -> Self {
+        Self::Standard(format)
+    }
+}
+
+/// Color with 10-bit channels, including the alpha.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rgba10 {
+    pub r: u16,
+    pub g: u16,
+    pub b: u16,
+    pub a: u16,
+}
+
+impl Rgba10 {
+    /// Packs the color in the [`FramebufferFormat::Rgb10A2`] layout, the alpha being rounded to 2 bits.
+    pub fn pack(self) -> u32 {
+        let alpha = (u32::from(self.a.min(MAX_10)) * 3 + u32::from(MAX_10 / 2)) / u32::from(MAX_10);
+
+        u32::from(self.r.min(MAX_10))
+            | (u32::from(self.g.min(MAX_10)) << 10)
+            | (u32::from(self.b.min(MAX_10)) << 20)
+            | (alpha << 30)
+    }
+
+    /// Unpacks a color in the [`FramebufferFormat::Rgb10A2`] layout.
+    pub fn unpack(value: u32) -> Self {
+        let channel = |shift: u32| (value >> shift) as u16 & MAX_10;
+
+        Self {
+            r: channel(0),
+            g: channel(10),
+            b: channel(20),
+            a: (value >> 30) as u16 * (MAX_10 / 3),
+        }
+    }
+}
+
+impl From<Rgba> for Rgba10 {
+    fn from(color: Rgba) -> Self {
+        Self {
+            r: widen(color.r),
+            g: widen(color.g),
+            b: widen(color.b),
+            a: widen(color.a),
+        }
+    }
+}
+
+impl From<Rgba10> for Rgba {
+    fn from(color: Rgba10) -> Self {
+        Self {
+            r: narrow(color.r),
+            g: narrow(color.g),
+            b: narrow(color.b),
+            a: narrow(color.a),
+        }
+    }
+}
+
+/// Expands an 8-bit channel to 10 bits, replicating the high bits so that 0xFF becomes 0x3FF.
+pub fn widen(value: u8) -> u16 {
+    (u16::from(value) << 2) | (u16::from(value) >> 6)
+}
+
+/// Reduces a 10-bit channel to 8 bits, rounding to the nearest value.
+pub fn narrow(value: u16) -> u8 {
+    ((u32::from(value.min(MAX_10)) * 255 + u32::from(MAX_10 / 2)) / u32::from(MAX_10)) as u8
+}
+
+/// Converts a `width` by `height` image between two framebuffer formats.
+///
+/// The strides are the number of bytes between the starts of two consecutive rows.
+#[allow(clippy::too_many_arguments)]
+pub fn convert(
+    src: &[u8],
+    src_format: FramebufferFormat,
+    src_stride: usize,
+    dst: &mut [u8],
+    dst_format: FramebufferFormat,
+    dst_stride: usize,
+    width: usize,
+    height: usize,
+) -> io::Result<()> {
+    let src_bpp = usize::from(src_format.bytes_per_pixel());
+    let dst_bpp = usize::from(dst_format.bytes_per_pixel());
+
+    if height > 0
+        && (src.len() < (height - 1) * src_stride + width * src_bpp
+            || dst.len() < (height - 1) * dst_stride + width * dst_bpp)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "buffer is not large enough for the image",
+        ));
+    }
+
+    for y in 0..height {
+        let src_row = &src[y * src_stride..];
+        let dst_row = &mut dst[y * dst_stride..];
+
+        if src_format == dst_format {
+            dst_row[..width * dst_bpp].copy_from_slice(&src_row[..width * src_bpp]);
+            continue;
+        }
+
+        for x in 0..width {
+            let color = src_format.read_color(&src_row[x * src_bpp..])?;
+            dst_format.write_color(color, &mut dst_row[x * dst_bpp..])?;
+        }
+    }
+
+    Ok(())
+}
@@ -6,7 +6,9 @@
 #![allow(clippy::cast_possible_wrap)] // FIXME: remove
 #![allow(clippy::cast_sign_loss)] // FIXME: remove
 
+pub mod bulk;
 pub mod color_conversion;
+pub mod deep_color;
 pub mod dwt;
 pub mod image_processing;
 pub mod pointer;
@@ -2,7 +2,10 @@ use ironrdp_core::{not_enough_bytes_err, EncodeError, WriteCursor};
 use ironrdp_pdu::bitmap::rdp6::{BitmapStreamHeader, ColorPlaneDefinition};
 use thiserror::Error;
 
-use crate::rdp6::rle::{compress_8bpp_plane, RleEncodeError};
+use crate::rdp6::rle::{compress_8bpp_plane, compress_8bpp_plane_slice, RleEncodeError};
+
+/// Number of pixels split at once, allowing the compiler to vectorize the loops.
+const SPLIT_LANES: usize = 16;
 
 #[derive(Debug, Error)]
 pub enum BitmapEncodeError {
@@ -130,6 +133,86 @@ impl BitmapEncodeError {
     }
 }
 
+/// Color planes of a bitmap, split from its pixels for [`BitmapStreamEncoder::encode_planes`].
+///
+/// The buffers are kept from one bitmap to the next, to avoid allocations.
+#[derive(Debug, Clone, Default)]
+pub struct ColorPlanes {
+    width: usize,
+    height: usize,
+    r: Vec<u8>,
+    g: Vec<u8>,
+    b: Vec<u8>,
+    deltas: Vec<u8>,
+}
+
+impl ColorPlanes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Splits the pixels of the scanlines into color planes, keeping the first `width` pixels of each scanline.
+    pub fn split<'a, F, I>(&mut self, scanlines: I, width: usize)
+    where
+        F: PixelFormat,
+        I: Iterator<Item = &'a [u8]>,
+    {
+        self.width = width;
+        self.height = 0;
+        self.r.clear();
+        self.g.clear();
+        self.b.clear();
+
+        for scanline in scanlines {
+            let start = self.r.len();
+            self.r.resize(start + width, 0);
+            self.g.resize(start + width, 0);
+            self.b.resize(start + width, 0);
+
+            split_scanline::<F>(
+                &scanline[..width * F::STRIDE],
+                &mut self.r[start..],
+                &mut self.g[start..],
+                &mut self.b[start..],
+            );
+
+            self.height += 1;
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+fn split_scanline<F: PixelFormat>(pixels: &[u8], r: &mut [u8], g: &mut [u8], b: &mut [u8]) {
+    let lanes = pixels
+        .chunks_exact(F::STRIDE * SPLIT_LANES)
+        .zip(r.chunks_exact_mut(SPLIT_LANES))
+        .zip(g.chunks_exact_mut(SPLIT_LANES))
+        .zip(b.chunks_exact_mut(SPLIT_LANES));
+
+    let mut split = 0;
+    for (((pixels, r), g), b) in lanes {
+        for (i, pixel) in pixels.chunks_exact(F::STRIDE).enumerate() {
+            r[i] = F::r(pixel);
+            g[i] = F::g(pixel);
+            b[i] = F::b(pixel);
+        }
+        split += SPLIT_LANES;
+    }
+
+    for (i, pixel) in pixels[split * F::STRIDE..].chunks_exact(F::STRIDE).enumerate() {
+        r[split + i] = F::r(pixel);
+        g[split + i] = F::g(pixel);
+        b[split + i] = F::b(pixel);
+    }
+}
+
 pub struct BitmapStreamEncoder {
     width: usize,
     height: usize,
@@ -202,6 +285,55 @@ impl BitmapStreamEncoder {
         self.encode_channels_stream((r, g, b), dst, rle)
     }
 
+    /// Encodes color planes split beforehand, which is faster than encoding streams of pixels.
+    ///
+    /// The planes must hold at least the pixels of the bitmap.
+    pub fn encode_planes(
+        &mut self,
+        planes: &mut ColorPlanes,
+        dst: &mut [u8],
+        rle: bool,
+    ) -> Result<usize, BitmapEncodeError> {
+        let len = self.width * self.height;
+        if planes.width != self.width || planes.height < self.height {
+            return Err(BitmapEncodeError::Rle(RleEncodeError::NotEnoughBytes));
+        }
+
+        let mut cursor = WriteCursor::new(dst);
+
+        let header = BitmapStreamHeader {
+            enable_rle_compression: rle,
+            use_alpha: false,
+            color_plane_definition: ColorPlaneDefinition::Argb,
+        };
+
+        ironrdp_core::encode_cursor(&header, &mut cursor).map_err(BitmapEncodeError::Encode)?;
+
+        if rle {
+            for plane in [&planes.r, &planes.g, &planes.b] {
+                compress_8bpp_plane_slice(&plane[..len], &mut cursor, self.width, self.height, &mut planes.deltas)
+                    .map_err(BitmapEncodeError::Rle)?;
+            }
+        } else {
+            let remaining = cursor.len();
+            let needed = len * 3 + 1;
+            if needed > remaining {
+                return Err(BitmapEncodeError::Encode(not_enough_bytes_err(
+                    "BitmapStreamData",
+                    remaining,
+                    needed,
+                )));
+            }
+
+            cursor.write_slice(&planes.r[..len]);
+            cursor.write_slice(&planes.g[..len]);
+            cursor.write_slice(&planes.b[..len]);
+            cursor.write_u8(0u8);
+        }
+
+        Ok(cursor.pos())
+    }
+
     pub fn encode_bitmap<F>(&mut self, src: &[u8], dst: &mut [u8], rle: bool) -> Result<usize, BitmapEncodeError>
     where
         F: PixelFormat,
@@ -129,6 +129,25 @@ mod tests {
         assert_eq!(&image.as_slice(), &actual.as_slice());
     }
 
+    fn assert_planes_match_pixels(bmp: &[u8], width: usize, height: usize, rle: bool) {
+        let image = buffer_from_bmp(bmp, width, height);
+
+        let mut expected = vec![0; width * height * 4 + 2];
+        let expected_len = BitmapStreamEncoder::new(width, height)
+            .encode_bitmap::<RgbChannels>(&image, &mut expected, rle)
+            .unwrap();
+
+        let mut planes = ColorPlanes::new();
+        planes.split::<RgbChannels, _>(image.chunks_exact(width * 3), width);
+
+        let mut actual = vec![0; width * height * 4 + 2];
+        let actual_len = BitmapStreamEncoder::new(width, height)
+            .encode_planes(&mut planes, &mut actual, rle)
+            .unwrap();
+
+        assert_eq!(&actual[..actual_len], &expected[..expected_len]);
+    }
+
     #[test]
     fn encode_32x64_rgb_raw() {
         // RGB (No alpha), no RLE
@@ -176,4 +195,14 @@ mod tests {
         // RGB (No alpha), with RLE
         encode_decode_test(include_bytes!("../test_assets/64x64_aycocg_rle.bmp"), 64, 64, true);
     }
+
+    #[test]
+    fn encode_planes_64x64_rgb_raw() {
+        assert_planes_match_pixels(include_bytes!("../test_assets/64x64_aycocg_rle.bmp"), 64, 64, false);
+    }
+
+    #[test]
+    fn encode_planes_64x64_rgb_rle() {
+        assert_planes_match_pixels(include_bytes!("../test_assets/64x64_aycocg_rle.bmp"), 64, 64, true);
+    }
 }
@@ -5,6 +5,8 @@ use byteorder::ReadBytesExt;
 use ironrdp_core::WriteCursor;
 use thiserror::Error;
 
+use crate::utils::run_length;
+
 /// Maximum possible segment size is 47 (run_length = 2, raw_bytes_count = 15), which is treated as
 /// special mode segment, which repeats last decoded byte in scanline 32 + raw_bytes_count times
 const MAX_DECODED_SEGMENT_SIZE: usize = 47;
@@ -294,6 +296,39 @@ impl RlePlaneEncoder {
         Ok(written)
     }
 
+    /// Encodes a scanline given as a slice, producing the same segments as [`Self::encode_scanline`].
+    fn encode_scanline_slice(&self, src: &[u8], dst: &mut WriteCursor<'_>) -> Result<usize, RleEncodeError> {
+        if src.is_empty() {
+            return Err(RleEncodeError::NotEnoughBytes);
+        }
+
+        let mut written = 0;
+        let mut raw_start = 0;
+        let mut pos = 0;
+
+        while pos < src.len() {
+            let run = run_length(&src[pos..], src[pos]);
+
+            // The first byte of the run is a raw byte, repeated by the run.
+            if run > 3 {
+                written += self
+                    .encode_segment(&src[raw_start..=pos], run - 1, dst)
+                    .ok_or(RleEncodeError::BufferTooSmall)?;
+                raw_start = pos + run;
+            }
+
+            pos += run;
+        }
+
+        if raw_start < src.len() {
+            written += self
+                .encode_segment(&src[raw_start..], 0, dst)
+                .ok_or(RleEncodeError::BufferTooSmall)?;
+        }
+
+        Ok(written)
+    }
+
     fn encode_segment(&self, mut raw: &[u8], run: usize, dst: &mut WriteCursor<'_>) -> Option<usize> {
         let mut extra_bytes = 0;
 
@@ -365,6 +400,49 @@ pub(crate) fn compress_8bpp_plane(
     RlePlaneEncoder::new(width, height).encode(iter, dst)
 }
 
+/// Performs compression of a 8bpp color plane into a buffer, like [`compress_8bpp_plane`].
+///
+/// The deltas of the scanlines are computed in `deltas`, without branches so that the compiler vectorizes them.
+/// Plane must have at least width * height bytes.
+///
+/// Returns number of bytes written to the dst buffer.
+pub(crate) fn compress_8bpp_plane_slice(
+    src: &[u8],
+    dst: &mut WriteCursor<'_>,
+    width: usize,
+    height: usize,
+    deltas: &mut Vec<u8>,
+) -> Result<usize, RleEncodeError> {
+    if width == 0 || src.len() < width * height {
+        return Err(RleEncodeError::NotEnoughBytes);
+    }
+
+    let encoder = RlePlaneEncoder::new(width, height);
+    let mut written = 0;
+    let mut prev_scanline: Option<&[u8]> = None;
+
+    for scanline in src.chunks_exact(width).take(height) {
+        written += match prev_scanline {
+            Some(prev) => {
+                deltas.clear();
+                deltas.extend(prev.iter().zip(scanline).map(|(&prev, &next)| delta_value(prev, next)));
+                encoder.encode_scanline_slice(deltas, dst)?
+            }
+            None => encoder.encode_scanline_slice(scanline, dst)?,
+        };
+
+        prev_scanline = Some(scanline);
+    }
+
+    Ok(written)
+}
+
+/// Branchless equivalent of [`RleEncoderScanlineIterator::delta_value`].
+fn delta_value(prev: u8, next: u8) -> u8 {
+    let delta = next.wrapping_sub(prev);
+    (delta << 1) ^ 0u8.wrapping_sub(delta >> 7)
+}
+
 #[cfg(test)]
 mod tests {
     use expect_test::expect;
@@ -679,4 +757,25 @@ mod tests {
 
         // Check same failure mode, but on non-first line
     }
+
+    #[test]
+    fn slice_encode_matches_stream_encode() {
+        let mut src: Vec<u8> = (0..=255u8).collect();
+        src.extend([0x41; 100]);
+        src.extend([19, 19, 19, 19, 18, 18, 18, 19, 19, 18, 18, 18, 18, 18, 18, 18, 18, 7, 7]);
+        src.extend((0..=255u8).rev().step_by(3));
+        src.resize(64 * 8, 0xAA);
+
+        for (width, height) in [(64, 8), (128, 4), (512, 1), (8, 64)] {
+            let mut expected = vec![0; 2048];
+            let expected_len = compress(&src, &mut expected, width, height).unwrap();
+
+            let mut actual = vec![0; 2048];
+            let actual_len =
+                compress_8bpp_plane_slice(&src, &mut WriteCursor::new(&mut actual), width, height, &mut Vec::new())
+                    .unwrap();
+
+            assert_eq!(&actual[..actual_len], &expected[..expected_len]);
+        }
+    }
 }
@@ -12,6 +12,8 @@
 use core::fmt;
 use core::ops::BitXor;
 
+use crate::utils::{matching_length, run_length};
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RlePixelFormat {
     Rgb24,
@@ -103,6 +105,65 @@ pub fn decompress_8_bpp(
     decompress_helper::<Mode8Bpp>(src, dst, width, height)
 }
 
+/// Compresses a bitmap with RLE.
+///
+/// `src`: source buffer containing the bitmap, with the same layout as a decompressed bitmap
+/// `dst`: destination buffer, cleared before receiving the compressed bitmap
+/// `width`: bitmap width
+/// `height`: bitmap height
+/// `bpp`: bits per pixel
+///
+/// Returns the size of the compressed bitmap.
+pub fn compress(src: &[u8], dst: &mut Vec<u8>, width: usize, height: usize, bpp: usize) -> Result<usize, RleError> {
+    match bpp {
+        Mode24Bpp::BPP => compress_24_bpp(src, dst, width, height),
+        Mode16Bpp::BPP => compress_16_bpp(src, dst, width, height),
+        Mode15Bpp::BPP => compress_15_bpp(src, dst, width, height),
+        Mode8Bpp::BPP => compress_8_bpp(src, dst, width, height),
+        invalid => Err(RleError::InvalidBpp { bpp: invalid }),
+    }
+}
+
+/// Compresses a 24-bpp bitmap with RLE.
+///
+/// `src`: source buffer containing the bitmap
+/// `dst`: destination buffer
+/// `width`: bitmap width
+/// `height`: bitmap height
+pub fn compress_24_bpp(src: &[u8], dst: &mut Vec<u8>, width: usize, height: usize) -> Result<usize, RleError> {
+    compress_helper::<Mode24Bpp>(src, dst, width, height)
+}
+
+/// Compresses a 16-bpp bitmap with RLE.
+///
+/// `src`: source buffer containing the bitmap
+/// `dst`: destination buffer
+/// `width`: bitmap width
+/// `height`: bitmap height
+pub fn compress_16_bpp(src: &[u8], dst: &mut Vec<u8>, width: usize, height: usize) -> Result<usize, RleError> {
+    compress_helper::<Mode16Bpp>(src, dst, width, height)
+}
+
+/// Compresses a 15-bpp bitmap with RLE.
+///
+/// `src`: source buffer containing the bitmap
+/// `dst`: destination buffer
+/// `width`: bitmap width
+/// `height`: bitmap height
+pub fn compress_15_bpp(src: &[u8], dst: &mut Vec<u8>, width: usize, height: usize) -> Result<usize, RleError> {
+    compress_helper::<Mode15Bpp>(src, dst, width, height)
+}
+
+/// Compresses a 8-bpp bitmap with RLE.
+///
+/// `src`: source buffer containing the bitmap
+/// `dst`: destination buffer
+/// `width`: bitmap width
+/// `height`: bitmap height
+pub fn compress_8_bpp(src: &[u8], dst: &mut Vec<u8>, width: usize, height: usize) -> Result<usize, RleError> {
+    compress_helper::<Mode8Bpp>(src, dst, width, height)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RleError {
     InvalidBpp {
@@ -383,6 +444,151 @@ fn decompress_impl<Mode: DepthMode>(src: &[u8], dst: &mut [u8], row_delta: usize
     Ok(())
 }
 
+fn compress_helper<Mode: DepthMode>(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    width: usize,
+    height: usize,
+) -> Result<usize, RleError> {
+    if width == 0 || height == 0 {
+        return Err(RleError::EmptyImage);
+    }
+
+    let expected = Mode::COLOR_DEPTH * width * height;
+    if src.len() < expected {
+        return Err(RleError::NotEnoughBytes {
+            expected,
+            actual: src.len(),
+        });
+    }
+
+    let mut buf = Buf::new(&src[..expected]);
+    let pixels: Vec<Mode::Pixel> = (0..width * height).map(|_| Mode::read_pixel(&mut buf)).collect();
+
+    dst.clear();
+    compress_impl::<Mode>(src, &pixels, dst, width);
+
+    Ok(dst.len())
+}
+
+/// RLE compression implementation
+///
+/// Only background runs, color runs and color images are produced. The orders of the first scanline don't
+/// span over the next scanlines, as the background of the first scanline is black instead of the pixels above.
+///
+/// `src`: source buffer containing the bitmap
+/// `pixels`: pixels of the bitmap, read from `src`
+/// `dst`: destination buffer
+/// `width`: bitmap width, in pixels
+fn compress_impl<Mode: DepthMode>(src: &[u8], pixels: &[Mode::Pixel], dst: &mut Vec<u8>, width: usize) {
+    let first_line_len = core::cmp::min(width, pixels.len());
+    let mut encoder = RleOrderEncoder::<Mode> {
+        src,
+        pixels,
+        dst,
+        _mode: core::marker::PhantomData,
+    };
+
+    encoder.encode_segment(0, first_line_len, None);
+    encoder.encode_segment(first_line_len, pixels.len(), Some(width));
+}
+
+/// Minimum number of pixels of a background run, shorter runs are left in color images.
+const MIN_BG_RUN_LENGTH: usize = 2;
+
+/// Minimum number of pixels of a color run, shorter runs are left in color images.
+const MIN_COLOR_RUN_LENGTH: usize = 3;
+
+struct RleOrderEncoder<'a, Mode: DepthMode> {
+    src: &'a [u8],
+    pixels: &'a [Mode::Pixel],
+    dst: &'a mut Vec<u8>,
+    _mode: core::marker::PhantomData<Mode>,
+}
+
+impl<'a, Mode: DepthMode> RleOrderEncoder<'a, Mode> {
+    /// Encodes the pixels from `start` to `end`, with the pixels above as background when `row_delta` is set, or
+    /// with black pixels otherwise.
+    fn encode_segment(&mut self, start: usize, end: usize, row_delta: Option<usize>) {
+        let mut image_start = start;
+        let mut pos = start;
+        let mut last_is_bg_run = false;
+
+        while pos < end {
+            let remaining = &self.pixels[pos..end];
+
+            let bg_run = match row_delta {
+                Some(row_delta) => matching_length(remaining, &self.pixels[pos - row_delta..end - row_delta]),
+                None => run_length(remaining, Mode::BLACK_PIXEL),
+            };
+            let bg_run = core::cmp::min(bg_run, usize::from(u16::MAX));
+
+            // A background run following another one inserts a foreground pixel.
+            if bg_run >= MIN_BG_RUN_LENGTH && !(last_is_bg_run && image_start == pos) {
+                self.color_image(image_start, pos);
+                self.order(Code::REGULAR_BG_RUN, Code::MEGA_MEGA_BG_RUN, bg_run);
+                pos += bg_run;
+                image_start = pos;
+                last_is_bg_run = true;
+                continue;
+            }
+
+            let color_run = core::cmp::min(run_length(remaining, remaining[0]), usize::from(u16::MAX));
+
+            if color_run >= MIN_COLOR_RUN_LENGTH {
+                self.color_image(image_start, pos);
+                self.order(Code::REGULAR_COLOR_RUN, Code::MEGA_MEGA_COLOR_RUN, color_run);
+                self.dst.extend_from_slice(self.pixel_bytes(pos, pos + 1));
+                pos += color_run;
+                image_start = pos;
+                last_is_bg_run = false;
+                continue;
+            }
+
+            last_is_bg_run = false;
+            pos += 1;
+        }
+
+        self.color_image(image_start, end);
+    }
+
+    fn color_image(&mut self, start: usize, end: usize) {
+        let mut start = start;
+
+        while start < end {
+            let len = core::cmp::min(end - start, usize::from(u16::MAX));
+            self.order(Code::REGULAR_COLOR_IMAGE, Code::MEGA_MEGA_COLOR_IMAGE, len);
+            self.dst.extend_from_slice(self.pixel_bytes(start, start + len));
+            start += len;
+        }
+    }
+
+    fn order(&mut self, regular: Code, mega_mega: Code, run_length: usize) {
+        const MAX_REGULAR_RUN_LENGTH: usize = 31;
+        const MAX_EXTENDED_RUN_LENGTH: usize = MAX_REGULAR_RUN_LENGTH + 256;
+
+        match run_length {
+            1..=MAX_REGULAR_RUN_LENGTH => {
+                let run_length = u8::try_from(run_length).expect("regular run length");
+                self.dst.push((regular.0 << 5) | run_length);
+            }
+            ..=MAX_EXTENDED_RUN_LENGTH => {
+                let extended = u8::try_from(run_length - MAX_REGULAR_RUN_LENGTH - 1).expect("extended run length");
+                self.dst.extend_from_slice(&[regular.0 << 5, extended]);
+            }
+            _ => {
+                let run_length = u16::try_from(run_length).expect("MEGA_MEGA run length");
+                self.dst.push(mega_mega.0);
+                self.dst.extend_from_slice(&run_length.to_le_bytes());
+            }
+        }
+    }
+
+    fn pixel_bytes(&self, start: usize, end: usize) -> &'a [u8] {
+        &self.src[start * Mode::COLOR_DEPTH..end * Mode::COLOR_DEPTH]
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 struct Code(u8);
 
@@ -627,7 +833,7 @@ impl<'a> BufMut<'a> {
 }
 
 trait DepthMode {
-    type Pixel: Copy + BitXor<Output = Self::Pixel>;
+    type Pixel: Copy + PartialEq + BitXor<Output = Self::Pixel>;
 
     /// The color depth (in bytes per pixel) for this mode
     const COLOR_DEPTH: usize;
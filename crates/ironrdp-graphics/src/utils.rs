@@ -36,3 +36,80 @@ impl ops::Deref for Bits<'_> {
         self.bits_slice
     }
 }
+
+/// Number of values compared at once when looking for runs, allowing the compiler to vectorize the comparisons.
+const RUN_LANES: usize = 16;
+
+/// Returns the number of values at the start of `values` equal to `value`.
+pub(crate) fn run_length<T: Copy + PartialEq>(values: &[T], value: T) -> usize {
+    let mut len = 0;
+
+    for lanes in values.chunks_exact(RUN_LANES) {
+        if lanes.iter().filter(|&&v| v == value).count() != RUN_LANES {
+            break;
+        }
+        len += RUN_LANES;
+    }
+
+    len + values[len..].iter().take_while(|&&v| v == value).count()
+}
+
+/// Returns the number of values at the start of `values` equal to the values of `other`.
+pub(crate) fn matching_length<T: Copy + PartialEq>(values: &[T], other: &[T]) -> usize {
+    let mut len = 0;
+
+    for (lanes, other_lanes) in values.chunks_exact(RUN_LANES).zip(other.chunks_exact(RUN_LANES)) {
+        if lanes.iter().zip(other_lanes).filter(|(a, b)| a == b).count() != RUN_LANES {
+            break;
+        }
+        len += RUN_LANES;
+    }
+
+    len + values[len..]
+        .iter()
+        .zip(&other[len..])
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// Writes values bit by bit, most significant bit first.
+pub(crate) struct BitWriter<'a> {
+    output: &'a mut Vec<u8>,
+    accumulator: u64,
+    len: u32,
+}
+
+impl<'a> BitWriter<'a> {
+    pub(crate) fn new(output: &'a mut Vec<u8>) -> Self {
+        Self {
+            output,
+            accumulator: 0,
+            len: 0,
+        }
+    }
+
+    /// Writes the `count` least significant bits of `value`, `count` being at most 32.
+    pub(crate) fn write(&mut self, value: u32, count: u32) {
+        debug_assert!(count <= 32);
+
+        self.accumulator = (self.accumulator << count) | (u64::from(value) & ((1 << count) - 1));
+        self.len += count;
+
+        while self.len >= 8 {
+            self.len -= 8;
+            self.output.push((self.accumulator >> self.len) as u8);
+        }
+    }
+
+    /// Pads the last byte with zeros, and returns the number of bits of padding.
+    pub(crate) fn finish(self) -> u32 {
+        if self.len == 0 {
+            return 0;
+        }
+
+        let padding = 8 - self.len;
+        self.output.push((self.accumulator << padding) as u8);
+
+        padding
+    }
+}
@@ -14,10 +14,17 @@ use thiserror::Error;
 
 use self::circular_buffer::FixedCircularBuffer;
 use self::control_messages::{BulkEncodedData, CompressionFlags, SegmentedDataPdu};
-use crate::utils::Bits;
+use crate::utils::{matching_length, BitWriter, Bits};
 
 const HISTORY_SIZE: usize = 2_500_000;
 
+/// Maximum size of the data carried by a single segment.
+const MAX_SEGMENT_SIZE: usize = 65535;
+
+const SEGMENTED_SINGLE: u8 = 0xe0;
+const SEGMENTED_MULTIPART: u8 = 0xe1;
+const COMPRESSION_TYPE_RDP8: u8 = 0x4;
+
 pub struct Decompressor {
     history: FixedCircularBuffer,
 }
@@ -124,6 +131,254 @@ impl Default for Decompressor {
     }
 }
 
+/// Size of the table of the last positions of the hashed 3-byte sequences.
+const HASH_TABLE_SIZE: usize = 1 << 16;
+
+/// Marks an empty entry of the hash table.
+const NO_POSITION: usize = usize::MAX;
+
+/// Compression type and flags of the compressed segments.
+const COMPRESSED_RDP8: u8 = COMPRESSION_TYPE_RDP8 | (CompressionFlags::COMPRESSED.bits() << 4);
+
+/// ZGFX compressor, keeping the history shared by the successive compressed structures.
+///
+/// The sequences repeated within the history are encoded as matches, and the bytes with a short literal token
+/// are encoded with it. Each segment is sent uncompressed when the compression does not reduce its size.
+pub struct Compressor {
+    /// Data given to the decompressor, the first byte being at the absolute position `history_start`.
+    history: Vec<u8>,
+    history_start: usize,
+    /// Last absolute positions of the hashed 3-byte sequences.
+    hash_table: Vec<usize>,
+    /// Prefix and prefix size of each byte, when encoded as a literal.
+    literal_codes: Vec<(u32, usize)>,
+    /// Prefix, prefix size, base and value size of the match distances.
+    distance_codes: Vec<(u32, usize, usize, usize)>,
+    segment: Vec<u8>,
+}
+
+impl Compressor {
+    pub fn new() -> Self {
+        let prefix_value = |token: &Token| token.prefix.iter().fold(0, |value, bit| (value << 1) | u32::from(*bit));
+
+        // The null literal prefix is a single zero bit, followed by the byte.
+        let mut literal_codes = (0..=u8::MAX).map(|value| (u32::from(value), 9)).collect::<Vec<_>>();
+        let mut distance_codes = Vec::new();
+
+        for token in TOKEN_TABLE.iter() {
+            match token.ty {
+                TokenType::NullLiteral => {}
+                TokenType::Literal { literal_value } => {
+                    literal_codes[usize::from(literal_value)] = (prefix_value(token), token.prefix.len());
+                }
+                TokenType::Match {
+                    distance_value_size,
+                    distance_base,
+                } => distance_codes.push((
+                    prefix_value(token),
+                    token.prefix.len(),
+                    distance_base as usize,
+                    distance_value_size,
+                )),
+            }
+        }
+
+        Self {
+            history: Vec::new(),
+            history_start: 0,
+            hash_table: vec![NO_POSITION; HASH_TABLE_SIZE],
+            literal_codes,
+            distance_codes,
+            segment: Vec::new(),
+        }
+    }
+
+    /// Compresses data into an RDP_SEGMENTED_DATA structure, split into a multipart structure when larger than a
+    /// single segment.
+    ///
+    /// Returns the number of bytes written into `output`.
+    pub fn compress(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<usize, ZgfxError> {
+        let initial_len = output.len();
+
+        if input.len() <= MAX_SEGMENT_SIZE {
+            output.push(SEGMENTED_SINGLE);
+            self.compress_segment(input, output);
+        } else {
+            let segment_count = u16::try_from(input.len().div_ceil(MAX_SEGMENT_SIZE))
+                .map_err(|_| ZgfxError::InputTooLarge { size: input.len() })?;
+            let uncompressed_size =
+                u32::try_from(input.len()).map_err(|_| ZgfxError::InputTooLarge { size: input.len() })?;
+
+            output.push(SEGMENTED_MULTIPART);
+            output.extend_from_slice(&segment_count.to_le_bytes());
+            output.extend_from_slice(&uncompressed_size.to_le_bytes());
+
+            for segment in input.chunks(MAX_SEGMENT_SIZE) {
+                let size_position = output.len();
+                output.extend_from_slice(&[0; 4]);
+                self.compress_segment(segment, output);
+
+                // The segment size includes the compression type and flags byte.
+                let size = u32::try_from(output.len() - size_position - 4).expect("segment size fits into u32");
+                output[size_position..size_position + 4].copy_from_slice(&size.to_le_bytes());
+            }
+        }
+
+        Ok(output.len() - initial_len)
+    }
+
+    /// Writes the compression type and flags byte followed by the data of a segment, compressed if smaller.
+    fn compress_segment(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        let mut segment = core::mem::take(&mut self.segment);
+        segment.clear();
+        self.encode(input, &mut segment);
+
+        if !input.is_empty() && segment.len() < input.len() {
+            output.push(COMPRESSED_RDP8);
+            output.extend_from_slice(&segment);
+        } else {
+            // The decompressor adds the uncompressed data to the history as well.
+            output.push(COMPRESSION_TYPE_RDP8);
+            output.extend_from_slice(input);
+        }
+
+        self.segment = segment;
+    }
+
+    /// Appends `input` to the history, and encodes it as tokens followed by the number of unused bits of the last
+    /// byte.
+    fn encode(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        let start = self.history.len();
+        self.history.extend_from_slice(input);
+        let end = self.history.len();
+
+        let mut writer = BitWriter::new(output);
+        let mut position = start;
+
+        while position < end {
+            if let Some((distance, length)) = self.find_match(position, end) {
+                self.write_match(&mut writer, distance, length);
+
+                for skipped in position + 1..position + length {
+                    self.insert(skipped, end);
+                }
+                position += length;
+            } else {
+                let (prefix, size) = self.literal_codes[usize::from(self.history[position])];
+                writer.write(prefix, size as u32);
+                position += 1;
+            }
+        }
+
+        let padding = writer.finish();
+        output.push(padding as u8);
+
+        // Keeps at least the part of the history the decompressor can refer to.
+        if self.history.len() > 2 * HISTORY_SIZE {
+            let excess = self.history.len() - HISTORY_SIZE;
+            self.history.drain(..excess);
+            self.history_start += excess;
+        }
+    }
+
+    /// Returns the distance and length of a sequence of the history matching the data at `position`, when at least
+    /// 3 bytes long.
+    fn find_match(&mut self, position: usize, end: usize) -> Option<(usize, usize)> {
+        let candidate = self.insert(position, end)?;
+        let candidate = candidate.checked_sub(self.history_start)?;
+
+        let distance = position - candidate;
+        if distance >= HISTORY_SIZE {
+            return None;
+        }
+
+        let length = matching_length(&self.history[position..end], &self.history[candidate..]);
+
+        (length >= 3).then_some((distance, length))
+    }
+
+    /// Records the 3-byte sequence at `position`, returning the previous absolute position of the same hash.
+    fn insert(&mut self, position: usize, end: usize) -> Option<usize> {
+        if position + 3 > end {
+            return None;
+        }
+
+        let bytes = &self.history[position..position + 3];
+        let value = u32::from(bytes[0]) | (u32::from(bytes[1]) << 8) | (u32::from(bytes[2]) << 16);
+        let key = (value.wrapping_mul(0x9E37_79B1) >> (32 - HASH_TABLE_SIZE.trailing_zeros())) as usize;
+        let previous = core::mem::replace(&mut self.hash_table[key], self.history_start + position);
+
+        (previous != NO_POSITION).then_some(previous)
+    }
+
+    fn write_match(&self, writer: &mut BitWriter<'_>, distance: usize, length: usize) {
+        let &(prefix, prefix_size, base, value_size) = self
+            .distance_codes
+            .iter()
+            .find(|(_, _, base, value_size)| distance >= *base && distance - base < 1 << value_size)
+            .expect("distance is below the history size");
+
+        writer.write(prefix, prefix_size as u32);
+        writer.write((distance - base) as u32, value_size as u32);
+
+        // A length of 3 is encoded as a single zero bit, and a length in 2^(n + 1)..2^(n + 2) as n ones, a zero
+        // and the n + 1 low bits of the length.
+        let length = length as u32;
+        if length == 3 {
+            writer.write(0, 1);
+        } else {
+            let n = 30 - length.leading_zeros();
+            writer.write((1 << (n + 1)) - 2, n + 1);
+            writer.write(length - (1 << (n + 1)), n + 1);
+        }
+    }
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Wraps data into an RDP_SEGMENTED_DATA structure, without compressing it.
+///
+/// The result is a valid ZGFX stream which can be sent to any peer expecting ZGFX compressed data,
+/// such as the graphics pipeline client. Data larger than a single segment is split into a
+/// multipart structure.
+///
+/// Returns the number of bytes written into `output`.
+pub fn wrap_uncompressed(input: &[u8], output: &mut Vec<u8>) -> Result<usize, ZgfxError> {
+    let initial_len = output.len();
+
+    if input.len() <= MAX_SEGMENT_SIZE {
+        output.reserve(input.len() + 2);
+        output.push(SEGMENTED_SINGLE);
+        output.push(COMPRESSION_TYPE_RDP8);
+        output.extend_from_slice(input);
+    } else {
+        let segment_count = u16::try_from(input.len().div_ceil(MAX_SEGMENT_SIZE))
+            .map_err(|_| ZgfxError::InputTooLarge { size: input.len() })?;
+        let uncompressed_size =
+            u32::try_from(input.len()).map_err(|_| ZgfxError::InputTooLarge { size: input.len() })?;
+
+        output.reserve(input.len() + 7 + usize::from(segment_count) * 5);
+        output.push(SEGMENTED_MULTIPART);
+        output.extend_from_slice(&segment_count.to_le_bytes());
+        output.extend_from_slice(&uncompressed_size.to_le_bytes());
+
+        for segment in input.chunks(MAX_SEGMENT_SIZE) {
+            // The segment size includes the compression type and flags byte.
+            let size = u32::try_from(segment.len() + 1).expect("segment size fits into u32");
+
+            output.extend_from_slice(&size.to_le_bytes());
+            output.push(COMPRESSION_TYPE_RDP8);
+            output.extend_from_slice(segment);
+        }
+    }
+
+    Ok(output.len() - initial_len)
+}
+
 fn handle_match(
     bits: &mut Bits<'_>,
     distance_value_size: usize,
@@ -445,6 +700,8 @@ pub enum ZgfxError {
     },
     #[error("token bits not found")]
     TokenBitsNotFound,
+    #[error("input of {size} bytes is too large to be segmented")]
+    InputTooLarge { size: usize },
 }
 
 #[cfg(test)]
@@ -629,4 +886,73 @@ mod tests {
         zgfx.decompress_segment(buffer.as_ref(), &mut decompressed).unwrap();
         assert_eq!(decompressed, expected);
     }
+
+    #[test]
+    fn zgfx_wraps_uncompressed_single_segment() {
+        let input = b"The quick brown fox jumps over the lazy dog";
+
+        let mut wrapped = Vec::new();
+        let written = wrap_uncompressed(input, &mut wrapped).unwrap();
+        assert_eq!(written, input.len() + 2);
+        assert_eq!(&wrapped[..2], &[0xe0, 0x04]);
+
+        let mut decompressed = Vec::new();
+        Decompressor::new().decompress(&wrapped, &mut decompressed).unwrap();
+        assert_eq!(decompressed, input);
+    }
+
+    #[test]
+    fn zgfx_wraps_uncompressed_multipart_segments() {
+        let input = (0..MAX_SEGMENT_SIZE * 2 + 10)
+            .map(|i| u8::try_from(i % 251).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut wrapped = Vec::new();
+        wrap_uncompressed(&input, &mut wrapped).unwrap();
+        assert_eq!(wrapped[0], 0xe1);
+        assert_eq!(&wrapped[1..3], &3u16.to_le_bytes());
+
+        let mut decompressed = Vec::new();
+        Decompressor::new().decompress(&wrapped, &mut decompressed).unwrap();
+        assert_eq!(decompressed, input);
+    }
+
+    #[test]
+    fn zgfx_compresses_repeated_data() {
+        let input =
+            b"The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog.".repeat(8);
+
+        let mut compressed = Vec::new();
+        Compressor::new().compress(&input, &mut compressed).unwrap();
+        assert_eq!(&compressed[..2], &[0xe0, 0x24]);
+        assert!(compressed.len() < input.len() / 4);
+
+        let mut decompressed = Vec::new();
+        Decompressor::new().decompress(&compressed, &mut decompressed).unwrap();
+        assert_eq!(decompressed, input);
+    }
+
+    #[test]
+    fn zgfx_compresses_successive_pdus_with_shared_history() {
+        let mut compressor = Compressor::new();
+        let mut decompressor = Decompressor::new();
+
+        let inputs = [
+            (0..MAX_SEGMENT_SIZE * 2 + 10)
+                .map(|i| u8::try_from(i % 251).unwrap())
+                .collect::<Vec<_>>(),
+            b"random-looking \x01\xff\x80 bytes".to_vec(),
+            (0..5000).map(|i| u8::try_from(i * 7 % 256).unwrap()).collect(),
+            Vec::new(),
+        ];
+
+        for input in inputs {
+            let mut compressed = Vec::new();
+            compressor.compress(&input, &mut compressed).unwrap();
+
+            let mut decompressed = Vec::new();
+            decompressor.decompress(&compressed, &mut decompressed).unwrap();
+            assert_eq!(decompressed, input);
+        }
+    }
 }
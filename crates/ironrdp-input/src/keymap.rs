@@ -0,0 +1,368 @@
+//! Conversion of the key codes of other keyboard protocols into the scancodes (set 1) used by RDP.
+//!
+//! The Pause key has no set 1 scancode which can be represented by a [`Scancode`] (its make code is the
+//! `E1 1D 45` sequence), and is mapped to Ctrl+Break (`E0 46`), as sent by most clients.
+
+use crate::Scancode;
+
+const fn key(code: u8) -> Option<Scancode> {
+    Some(Scancode::from_u8(false, code))
+}
+
+const fn extended_key(code: u8) -> Option<Scancode> {
+    Some(Scancode::from_u8(true, code))
+}
+
+impl Scancode {
+    /// Converts a Linux evdev key code (`KEY_*` in `linux/input-event-codes.h`) into a scancode.
+    ///
+    /// The X11 key codes of the evdev driver are the evdev key codes plus 8.
+    pub const fn from_evdev(key_code: u16) -> Option<Self> {
+        match key_code {
+            // The key codes of the main keys are the scancodes.
+            #[allow(clippy::cast_possible_truncation)] // the range fits in u8
+            1..=83 | 86..=88 => key(key_code as u8),
+            85 => key(0x76),           // KEY_ZENKAKUHANKAKU
+            89 => key(0x73),           // KEY_RO
+            90 => key(0x78),           // KEY_KATAKANA
+            91 => key(0x77),           // KEY_HIRAGANA
+            92 => key(0x79),           // KEY_HENKAN
+            93 => key(0x70),           // KEY_KATAKANAHIRAGANA
+            94 => key(0x7B),           // KEY_MUHENKAN
+            95 => key(0x5C),           // KEY_KPJPCOMMA
+            96 => extended_key(0x1C),  // KEY_KPENTER
+            97 => extended_key(0x1D),  // KEY_RIGHTCTRL
+            98 => extended_key(0x35),  // KEY_KPSLASH
+            99 => extended_key(0x37),  // KEY_SYSRQ
+            100 => extended_key(0x38), // KEY_RIGHTALT
+            102 => extended_key(0x47), // KEY_HOME
+            103 => extended_key(0x48), // KEY_UP
+            104 => extended_key(0x49), // KEY_PAGEUP
+            105 => extended_key(0x4B), // KEY_LEFT
+            106 => extended_key(0x4D), // KEY_RIGHT
+            107 => extended_key(0x4F), // KEY_END
+            108 => extended_key(0x50), // KEY_DOWN
+            109 => extended_key(0x51), // KEY_PAGEDOWN
+            110 => extended_key(0x52), // KEY_INSERT
+            111 => extended_key(0x53), // KEY_DELETE
+            113 => extended_key(0x20), // KEY_MUTE
+            114 => extended_key(0x2E), // KEY_VOLUMEDOWN
+            115 => extended_key(0x30), // KEY_VOLUMEUP
+            116 => extended_key(0x5E), // KEY_POWER
+            117 => key(0x59),          // KEY_KPEQUAL
+            119 => extended_key(0x46), // KEY_PAUSE
+            121 => key(0x7E),          // KEY_KPCOMMA
+            122 => key(0x72),          // KEY_HANGEUL
+            123 => key(0x71),          // KEY_HANJA
+            124 => key(0x7D),          // KEY_YEN
+            125 => extended_key(0x5B), // KEY_LEFTMETA
+            126 => extended_key(0x5C), // KEY_RIGHTMETA
+            127 => extended_key(0x5D), // KEY_COMPOSE
+            128 => extended_key(0x68), // KEY_STOP
+            140 => extended_key(0x21), // KEY_CALC
+            142 => extended_key(0x5F), // KEY_SLEEP
+            143 => extended_key(0x63), // KEY_WAKEUP
+            155 => extended_key(0x6C), // KEY_MAIL
+            156 => extended_key(0x66), // KEY_BOOKMARKS
+            157 => extended_key(0x6B), // KEY_COMPUTER
+            158 => extended_key(0x6A), // KEY_BACK
+            159 => extended_key(0x69), // KEY_FORWARD
+            163 => extended_key(0x19), // KEY_NEXTSONG
+            164 => extended_key(0x22), // KEY_PLAYPAUSE
+            165 => extended_key(0x10), // KEY_PREVIOUSSONG
+            166 => extended_key(0x24), // KEY_STOPCD
+            172 => extended_key(0x32), // KEY_HOMEPAGE
+            173 => extended_key(0x67), // KEY_REFRESH
+            // KEY_F13 to KEY_F23
+            #[allow(clippy::cast_possible_truncation)] // the range fits in u8
+            183..=193 => key((key_code - 183) as u8 + 0x64),
+            194 => key(0x76),          // KEY_F24
+            217 => extended_key(0x65), // KEY_SEARCH
+            226 => extended_key(0x6D), // KEY_MEDIA
+            _ => None,
+        }
+    }
+
+    /// Converts a USB HID usage of the Keyboard/Keypad page (0x07) into a scancode.
+    pub const fn from_hid_usage(usage: u16) -> Option<Self> {
+        match usage {
+            0x04 => key(0x1E), // A
+            0x05 => key(0x30), // B
+            0x06 => key(0x2E), // C
+            0x07 => key(0x20), // D
+            0x08 => key(0x12), // E
+            0x09 => key(0x21), // F
+            0x0A => key(0x22), // G
+            0x0B => key(0x23), // H
+            0x0C => key(0x17), // I
+            0x0D => key(0x24), // J
+            0x0E => key(0x25), // K
+            0x0F => key(0x26), // L
+            0x10 => key(0x32), // M
+            0x11 => key(0x31), // N
+            0x12 => key(0x18), // O
+            0x13 => key(0x19), // P
+            0x14 => key(0x10), // Q
+            0x15 => key(0x13), // R
+            0x16 => key(0x1F), // S
+            0x17 => key(0x14), // T
+            0x18 => key(0x16), // U
+            0x19 => key(0x2F), // V
+            0x1A => key(0x11), // W
+            0x1B => key(0x2D), // X
+            0x1C => key(0x15), // Y
+            0x1D => key(0x2C), // Z
+            // 1 to 0
+            #[allow(clippy::cast_possible_truncation)] // the range fits in u8
+            0x1E..=0x27 => key((usage - 0x1E) as u8 + 0x02),
+            0x28 => key(0x1C), // Enter
+            0x29 => key(0x01), // Escape
+            0x2A => key(0x0E), // Backspace
+            0x2B => key(0x0F), // Tab
+            0x2C => key(0x39), // Space
+            0x2D => key(0x0C), // - and _
+            0x2E => key(0x0D), // = and +
+            0x2F => key(0x1A), // [ and {
+            0x30 => key(0x1B), // ] and }
+            0x31 => key(0x2B), // \ and |
+            0x32 => key(0x2B), // Non-US # and ~
+            0x33 => key(0x27), // ; and :
+            0x34 => key(0x28), // ' and "
+            0x35 => key(0x29), // ` and ~
+            0x36 => key(0x33), // , and <
+            0x37 => key(0x34), // . and >
+            0x38 => key(0x35), // / and ?
+            0x39 => key(0x3A), // Caps Lock
+            // F1 to F10
+            #[allow(clippy::cast_possible_truncation)] // the range fits in u8
+            0x3A..=0x43 => key((usage - 0x3A) as u8 + 0x3B),
+            0x44 => key(0x57),          // F11
+            0x45 => key(0x58),          // F12
+            0x46 => extended_key(0x37), // Print Screen
+            0x47 => key(0x46),          // Scroll Lock
+            0x48 => extended_key(0x46), // Pause
+            0x49 => extended_key(0x52), // Insert
+            0x4A => extended_key(0x47), // Home
+            0x4B => extended_key(0x49), // Page Up
+            0x4C => extended_key(0x53), // Delete
+            0x4D => extended_key(0x4F), // End
+            0x4E => extended_key(0x51), // Page Down
+            0x4F => extended_key(0x4D), // Right Arrow
+            0x50 => extended_key(0x4B), // Left Arrow
+            0x51 => extended_key(0x50), // Down Arrow
+            0x52 => extended_key(0x48), // Up Arrow
+            0x53 => key(0x45),          // Num Lock
+            0x54 => extended_key(0x35), // Keypad /
+            0x55 => key(0x37),          // Keypad *
+            0x56 => key(0x4A),          // Keypad -
+            0x57 => key(0x4E),          // Keypad +
+            0x58 => extended_key(0x1C), // Keypad Enter
+            0x59 => key(0x4F),          // Keypad 1
+            0x5A => key(0x50),          // Keypad 2
+            0x5B => key(0x51),          // Keypad 3
+            0x5C => key(0x4B),          // Keypad 4
+            0x5D => key(0x4C),          // Keypad 5
+            0x5E => key(0x4D),          // Keypad 6
+            0x5F => key(0x47),          // Keypad 7
+            0x60 => key(0x48),          // Keypad 8
+            0x61 => key(0x49),          // Keypad 9
+            0x62 => key(0x52),          // Keypad 0
+            0x63 => key(0x53),          // Keypad .
+            0x64 => key(0x56),          // Non-US \ and |
+            0x65 => extended_key(0x5D), // Application
+            0x66 => extended_key(0x5E), // Power
+            0x67 => key(0x59),          // Keypad =
+            // F13 to F23
+            #[allow(clippy::cast_possible_truncation)] // the range fits in u8
+            0x68..=0x72 => key((usage - 0x68) as u8 + 0x64),
+            0x73 => key(0x76),          // F24
+            0x7F => extended_key(0x20), // Mute
+            0x80 => extended_key(0x30), // Volume Up
+            0x81 => extended_key(0x2E), // Volume Down
+            0x85 => key(0x7E),          // Keypad Comma
+            0x87 => key(0x73),          // International1 (Ro)
+            0x88 => key(0x70),          // International2 (Katakana/Hiragana)
+            0x89 => key(0x7D),          // International3 (Yen)
+            0x8A => key(0x79),          // International4 (Henkan)
+            0x8B => key(0x7B),          // International5 (Muhenkan)
+            0x8C => key(0x5C),          // International6 (Keypad JP Comma)
+            0x90 => key(0x72),          // LANG1 (Hangul)
+            0x91 => key(0x71),          // LANG2 (Hanja)
+            0x92 => key(0x78),          // LANG3 (Katakana)
+            0x93 => key(0x77),          // LANG4 (Hiragana)
+            0x94 => key(0x76),          // LANG5 (Zenkaku/Hankaku)
+            0xE0 => key(0x1D),          // Left Control
+            0xE1 => key(0x2A),          // Left Shift
+            0xE2 => key(0x38),          // Left Alt
+            0xE3 => extended_key(0x5B), // Left GUI
+            0xE4 => extended_key(0x1D), // Right Control
+            0xE5 => key(0x36),          // Right Shift
+            0xE6 => extended_key(0x38), // Right Alt
+            0xE7 => extended_key(0x5C), // Right GUI
+            _ => None,
+        }
+    }
+
+    /// Converts a make code of the scancode set 2, as sent by AT and PS/2 keyboards, into a scancode of the set 1.
+    ///
+    /// `extended` is `true` for the codes prefixed with `E0`. The fake shifts surrounding some extended keys, and
+    /// the Pause key sequence (`E1 14 77`) have no equivalent.
+    pub const fn from_set2(extended: bool, code: u8) -> Option<Self> {
+        if extended {
+            return match code {
+                0x10 => extended_key(0x65), // WWW Search
+                0x11 => extended_key(0x38), // Right Alt
+                0x14 => extended_key(0x1D), // Right Control
+                0x15 => extended_key(0x10), // Previous Track
+                0x18 => extended_key(0x66), // WWW Favorites
+                0x1F => extended_key(0x5B), // Left GUI
+                0x20 => extended_key(0x67), // WWW Refresh
+                0x21 => extended_key(0x2E), // Volume Down
+                0x23 => extended_key(0x20), // Mute
+                0x27 => extended_key(0x5C), // Right GUI
+                0x28 => extended_key(0x68), // WWW Stop
+                0x2B => extended_key(0x21), // Calculator
+                0x2F => extended_key(0x5D), // Application
+                0x30 => extended_key(0x69), // WWW Forward
+                0x32 => extended_key(0x30), // Volume Up
+                0x34 => extended_key(0x22), // Play/Pause
+                0x37 => extended_key(0x5E), // Power
+                0x38 => extended_key(0x6A), // WWW Back
+                0x3A => extended_key(0x32), // WWW Home
+                0x3B => extended_key(0x24), // Stop
+                0x3F => extended_key(0x5F), // Sleep
+                0x40 => extended_key(0x6B), // My Computer
+                0x48 => extended_key(0x6C), // Mail
+                0x4A => extended_key(0x35), // Keypad /
+                0x4D => extended_key(0x19), // Next Track
+                0x50 => extended_key(0x6D), // Media Select
+                0x5A => extended_key(0x1C), // Keypad Enter
+                0x5E => extended_key(0x63), // Wake
+                0x69 => extended_key(0x4F), // End
+                0x6B => extended_key(0x4B), // Left Arrow
+                0x6C => extended_key(0x47), // Home
+                0x70 => extended_key(0x52), // Insert
+                0x71 => extended_key(0x53), // Delete
+                0x72 => extended_key(0x50), // Down Arrow
+                0x74 => extended_key(0x4D), // Right Arrow
+                0x75 => extended_key(0x48), // Up Arrow
+                0x7A => extended_key(0x51), // Page Down
+                0x7C => extended_key(0x37), // Print Screen
+                0x7D => extended_key(0x49), // Page Up
+                0x7E => extended_key(0x46), // Ctrl+Break
+                _ => None,
+            };
+        }
+
+        match code {
+            0x01 => key(0x43), // F9
+            0x03 => key(0x3F), // F5
+            0x04 => key(0x3D), // F3
+            0x05 => key(0x3B), // F1
+            0x06 => key(0x3C), // F2
+            0x07 => key(0x58), // F12
+            0x08 => key(0x64), // F13
+            0x09 => key(0x44), // F10
+            0x0A => key(0x42), // F8
+            0x0B => key(0x40), // F6
+            0x0C => key(0x3E), // F4
+            0x0D => key(0x0F), // Tab
+            0x0E => key(0x29), // `
+            0x0F => key(0x59), // Keypad =
+            0x10 => key(0x65), // F14
+            0x11 => key(0x38), // Left Alt
+            0x12 => key(0x2A), // Left Shift
+            0x13 => key(0x70), // Katakana/Hiragana
+            0x14 => key(0x1D), // Left Control
+            0x15 => key(0x10), // Q
+            0x16 => key(0x02), // 1
+            0x18 => key(0x66), // F15
+            0x1A => key(0x2C), // Z
+            0x1B => key(0x1F), // S
+            0x1C => key(0x1E), // A
+            0x1D => key(0x11), // W
+            0x1E => key(0x03), // 2
+            0x20 => key(0x67), // F16
+            0x21 => key(0x2E), // C
+            0x22 => key(0x2D), // X
+            0x23 => key(0x20), // D
+            0x24 => key(0x12), // E
+            0x25 => key(0x05), // 4
+            0x26 => key(0x04), // 3
+            0x28 => key(0x68), // F17
+            0x29 => key(0x39), // Space
+            0x2A => key(0x2F), // V
+            0x2B => key(0x21), // F
+            0x2C => key(0x14), // T
+            0x2D => key(0x13), // R
+            0x2E => key(0x06), // 5
+            0x30 => key(0x69), // F18
+            0x31 => key(0x31), // N
+            0x32 => key(0x30), // B
+            0x33 => key(0x23), // H
+            0x34 => key(0x22), // G
+            0x35 => key(0x15), // Y
+            0x36 => key(0x07), // 6
+            0x38 => key(0x6A), // F19
+            0x3A => key(0x32), // M
+            0x3B => key(0x24), // J
+            0x3C => key(0x16), // U
+            0x3D => key(0x08), // 7
+            0x3E => key(0x09), // 8
+            0x40 => key(0x6B), // F20
+            0x41 => key(0x33), // ,
+            0x42 => key(0x25), // K
+            0x43 => key(0x17), // I
+            0x44 => key(0x18), // O
+            0x45 => key(0x0B), // 0
+            0x46 => key(0x0A), // 9
+            0x48 => key(0x6C), // F21
+            0x49 => key(0x34), // .
+            0x4A => key(0x35), // /
+            0x4B => key(0x26), // L
+            0x4C => key(0x27), // ;
+            0x4D => key(0x19), // P
+            0x4E => key(0x0C), // -
+            0x50 => key(0x6D), // F22
+            0x51 => key(0x73), // Ro
+            0x52 => key(0x28), // '
+            0x54 => key(0x1A), // [
+            0x55 => key(0x0D), // =
+            0x57 => key(0x6E), // F23
+            0x58 => key(0x3A), // Caps Lock
+            0x59 => key(0x36), // Right Shift
+            0x5A => key(0x1C), // Enter
+            0x5B => key(0x1B), // ]
+            0x5D => key(0x2B), // \
+            0x5F => key(0x76), // F24
+            0x61 => key(0x56), // Non-US \
+            0x64 => key(0x79), // Henkan
+            0x66 => key(0x0E), // Backspace
+            0x67 => key(0x7B), // Muhenkan
+            0x69 => key(0x4F), // Keypad 1
+            0x6A => key(0x7D), // Yen
+            0x6B => key(0x4B), // Keypad 4
+            0x6C => key(0x47), // Keypad 7
+            0x6D => key(0x7E), // Keypad Comma
+            0x70 => key(0x52), // Keypad 0
+            0x71 => key(0x53), // Keypad .
+            0x72 => key(0x50), // Keypad 2
+            0x73 => key(0x4C), // Keypad 5
+            0x74 => key(0x4D), // Keypad 6
+            0x75 => key(0x48), // Keypad 8
+            0x76 => key(0x01), // Escape
+            0x77 => key(0x45), // Num Lock
+            0x78 => key(0x57), // F11
+            0x79 => key(0x4E), // Keypad +
+            0x7A => key(0x51), // Keypad 3
+            0x7B => key(0x4A), // Keypad -
+            0x7C => key(0x37), // Keypad *
+            0x7D => key(0x49), // Keypad 9
+            0x7E => key(0x46), // Scroll Lock
+            0x83 => key(0x41), // F7
+            0xF1 => key(0x71), // Hanja
+            0xF2 => key(0x72), // Hangul
+            _ => None,
+        }
+    }
+}
@@ -1,11 +1,14 @@
 #![doc = include_str!("../README.md")]
 #![doc(html_logo_url = "https://cdnweb.devolutions.net/images/projects/devolutions/logos/devolutions-icon-shadow.svg")]
 
+mod keymap;
+
+use core::time::Duration;
 use std::collections::BTreeSet;
 
 use bitvec::array::BitArray;
 use bitvec::BitArr;
-use ironrdp_pdu::input::fast_path::{FastPathInputEvent, KeyboardFlags};
+use ironrdp_pdu::input::fast_path::{FastPathInputEvent, KeyboardFlags, SynchronizeFlags};
 use ironrdp_pdu::input::mouse::PointerFlags;
 use ironrdp_pdu::input::mouse_x::PointerXFlags;
 use ironrdp_pdu::input::{MousePdu, MouseXPdu};
@@ -131,7 +134,40 @@ pub struct WheelRotations {
     pub rotation_units: i16,
 }
 
-#[derive(Debug, Clone)]
+/// State of the lock keys, synchronized with the Synchronize event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LockKeys {
+    pub scroll_lock: bool,
+    pub num_lock: bool,
+    pub caps_lock: bool,
+    pub kana_lock: bool,
+}
+
+impl From<SynchronizeFlags> for LockKeys {
+    fn from(flags: SynchronizeFlags) -> Self {
+        Self {
+            scroll_lock: flags.contains(SynchronizeFlags::SCROLL_LOCK),
+            num_lock: flags.contains(SynchronizeFlags::NUM_LOCK),
+            caps_lock: flags.contains(SynchronizeFlags::CAPS_LOCK),
+            kana_lock: flags.contains(SynchronizeFlags::KANA_LOCK),
+        }
+    }
+}
+
+impl From<LockKeys> for SynchronizeFlags {
+    fn from(lock_keys: LockKeys) -> Self {
+        let mut flags = SynchronizeFlags::empty();
+
+        flags.set(SynchronizeFlags::SCROLL_LOCK, lock_keys.scroll_lock);
+        flags.set(SynchronizeFlags::NUM_LOCK, lock_keys.num_lock);
+        flags.set(SynchronizeFlags::CAPS_LOCK, lock_keys.caps_lock);
+        flags.set(SynchronizeFlags::KANA_LOCK, lock_keys.kana_lock);
+
+        flags
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Operation {
     MouseButtonPressed(MouseButton),
     MouseButtonReleased(MouseButton),
@@ -141,6 +177,46 @@ pub enum Operation {
     KeyReleased(Scancode),
     UnicodeKeyPressed(char),
     UnicodeKeyReleased(char),
+    /// Synchronizes the lock keys, typically when the client window gains the focus.
+    SynchronizeLockKeys(LockKeys),
+}
+
+/// Synthesized auto-repeat of the last pressed key, for the backends delivering a single press event per key stroke.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyRepeat {
+    /// Time between the press of the key and its first repetition.
+    pub delay: Duration,
+    /// Time between two repetitions.
+    pub interval: Duration,
+}
+
+impl Default for KeyRepeat {
+    /// The default settings of Windows, repeating 30 times per second after half a second.
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(500),
+            interval: Duration::from_millis(33),
+        }
+    }
+}
+
+/// Shortest time between two repetitions, whatever the settings.
+const MIN_KEY_REPEAT_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Maximum number of repetitions emitted at once, the others being dropped after a stall.
+const MAX_KEY_REPEATS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RepeatedKey {
+    Scancode(Scancode),
+    Unicode(char),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Repetition {
+    key: RepeatedKey,
+    /// Time until the next repetition.
+    remaining: Duration,
 }
 
 pub type KeyboardState = BitArr!(for 512);
@@ -152,6 +228,12 @@ pub struct Database {
     keyboard: KeyboardState,
     mouse_buttons: MouseButtonsState,
     mouse_position: MousePosition,
+    lock_keys: LockKeys,
+    /// High surrogate of a Unicode keyboard event received, waiting for the low surrogate.
+    pending_high_surrogate: Option<u16>,
+    key_repeat: Option<KeyRepeat>,
+    /// Repetition of the last pressed key, while it is held.
+    repetition: Option<Repetition>,
 }
 
 impl Default for Database {
@@ -167,6 +249,10 @@ impl Database {
             mouse_buttons: BitArray::ZERO,
             mouse_position: MousePosition { x: 0, y: 0 },
             unicode_keyboard_state: BTreeSet::new(),
+            lock_keys: LockKeys::default(),
+            pending_high_surrogate: None,
+            key_repeat: None,
+            repetition: None,
         }
     }
 
@@ -202,6 +288,90 @@ impl Database {
         &self.mouse_buttons
     }
 
+    pub fn lock_keys(&self) -> LockKeys {
+        self.lock_keys
+    }
+
+    pub fn key_repeat(&self) -> Option<KeyRepeat> {
+        self.key_repeat
+    }
+
+    /// Enables the synthesis of the key repetitions, returned by [`Database::advance_key_repeat`], for the
+    /// backends delivering a single press event per key stroke.
+    ///
+    /// Like on a physical keyboard, the last pressed key is repeated until it is released.
+    pub fn set_key_repeat(&mut self, key_repeat: Option<KeyRepeat>) {
+        self.key_repeat = key_repeat;
+
+        if key_repeat.is_none() {
+            self.repetition = None;
+        }
+    }
+
+    /// Returns the time until the next key repetition, if a key is repeated, to schedule the next call to
+    /// [`Database::advance_key_repeat`].
+    pub fn next_key_repeat(&self) -> Option<Duration> {
+        self.repetition.map(|repetition| repetition.remaining)
+    }
+
+    /// Advances the time of the key repetitions by `elapsed`, and returns the RDP input events of the repeated key
+    /// to send.
+    pub fn advance_key_repeat(&mut self, mut elapsed: Duration) -> SmallVec<[FastPathInputEvent; 2]> {
+        let mut events = SmallVec::new();
+
+        let (Some(key_repeat), Some(repetition)) = (self.key_repeat, &mut self.repetition) else {
+            return events;
+        };
+
+        let mut count = 0;
+
+        while elapsed >= repetition.remaining {
+            elapsed -= repetition.remaining;
+            repetition.remaining = key_repeat.interval.max(MIN_KEY_REPEAT_INTERVAL);
+
+            if count == MAX_KEY_REPEATS {
+                elapsed = Duration::ZERO;
+                break;
+            }
+
+            // The repetitions are press events, without the release events.
+            match repetition.key {
+                RepeatedKey::Scancode(scancode) => {
+                    events.push(FastPathInputEvent::KeyboardEvent(
+                        scancode_flags(scancode),
+                        scancode.code,
+                    ));
+                }
+                RepeatedKey::Unicode(character) => {
+                    let mut utf16_buffer = [0u16; 2];
+
+                    for code in character.encode_utf16(&mut utf16_buffer) {
+                        events.push(FastPathInputEvent::UnicodeKeyboardEvent(KeyboardFlags::empty(), *code));
+                    }
+                }
+            }
+
+            count += 1;
+        }
+
+        repetition.remaining -= elapsed;
+
+        events
+    }
+
+    fn start_key_repeat(&mut self, key: RepeatedKey) {
+        self.repetition = self.key_repeat.map(|key_repeat| Repetition {
+            key,
+            remaining: key_repeat.delay,
+        });
+    }
+
+    fn stop_key_repeat(&mut self, key: RepeatedKey) {
+        if self.repetition.is_some_and(|repetition| repetition.key == key) {
+            self.repetition = None;
+        }
+    }
+
     /// Apply a transaction (list of operations) and returns a list of RDP input events to send.
     ///
     /// Operations that would cause no state change are ignored.
@@ -276,11 +446,7 @@ impl Database {
                 Operation::KeyPressed(scancode) => {
                     let was_pressed = self.keyboard.replace(scancode.as_idx(), true);
 
-                    let mut flags = KeyboardFlags::empty();
-
-                    if scancode.extended {
-                        flags |= KeyboardFlags::EXTENDED
-                    };
+                    let flags = scancode_flags(scancode);
 
                     if was_pressed {
                         events.push(FastPathInputEvent::KeyboardEvent(
@@ -290,15 +456,15 @@ impl Database {
                     }
 
                     events.push(FastPathInputEvent::KeyboardEvent(flags, scancode.code));
+
+                    self.start_key_repeat(RepeatedKey::Scancode(scancode));
                 }
                 Operation::KeyReleased(scancode) => {
                     let was_pressed = self.keyboard.replace(scancode.as_idx(), false);
 
-                    let mut flags = KeyboardFlags::RELEASE;
+                    let flags = scancode_flags(scancode) | KeyboardFlags::RELEASE;
 
-                    if scancode.extended {
-                        flags |= KeyboardFlags::EXTENDED
-                    };
+                    self.stop_key_repeat(RepeatedKey::Scancode(scancode));
 
                     if was_pressed {
                         events.push(FastPathInputEvent::KeyboardEvent(flags, scancode.code));
@@ -319,10 +485,14 @@ impl Database {
                     for code in utf16_code_units {
                         events.push(FastPathInputEvent::UnicodeKeyboardEvent(KeyboardFlags::empty(), *code));
                     }
+
+                    self.start_key_repeat(RepeatedKey::Unicode(character));
                 }
                 Operation::UnicodeKeyReleased(character) => {
                     let was_pressed = self.unicode_keyboard_state.remove(&character);
 
+                    self.stop_key_repeat(RepeatedKey::Unicode(character));
+
                     let mut utf16_buffer = [0u16; 2];
                     let utf16_code_units = character.encode_utf16(&mut utf16_buffer);
 
@@ -332,12 +502,134 @@ impl Database {
                         }
                     }
                 }
+                Operation::SynchronizeLockKeys(lock_keys) => {
+                    // Always sent, the synchronization being requested precisely when the state may be out of date.
+                    self.lock_keys = lock_keys;
+                    events.push(FastPathInputEvent::SyncEvent(lock_keys.into()));
+                }
             }
         }
 
         events
     }
 
+    /// Updates the state with an RDP input event received from the client, and returns the corresponding operations.
+    ///
+    /// This is the counterpart of [`Database::apply`], for servers. The relative mouse movements and the QoE
+    /// timestamps have no corresponding operation.
+    pub fn process_event(&mut self, event: &FastPathInputEvent) -> SmallVec<[Operation; 2]> {
+        let mut operations = SmallVec::new();
+
+        match *event {
+            FastPathInputEvent::KeyboardEvent(flags, code) => {
+                let scancode = Scancode::from_u8(flags.contains(KeyboardFlags::EXTENDED), code);
+                let pressed = !flags.contains(KeyboardFlags::RELEASE);
+
+                self.keyboard.set(scancode.as_idx(), pressed);
+
+                operations.push(if pressed {
+                    Operation::KeyPressed(scancode)
+                } else {
+                    Operation::KeyReleased(scancode)
+                });
+            }
+            FastPathInputEvent::UnicodeKeyboardEvent(flags, code) => {
+                if let Some(character) = self.decode_unicode_key(code) {
+                    if flags.contains(KeyboardFlags::RELEASE) {
+                        self.unicode_keyboard_state.remove(&character);
+                        operations.push(Operation::UnicodeKeyReleased(character));
+                    } else {
+                        self.unicode_keyboard_state.insert(character);
+                        operations.push(Operation::UnicodeKeyPressed(character));
+                    }
+                }
+            }
+            FastPathInputEvent::SyncEvent(flags) => {
+                self.lock_keys = LockKeys::from(flags);
+                operations.push(Operation::SynchronizeLockKeys(self.lock_keys));
+            }
+            FastPathInputEvent::MouseEvent(ref pdu) => {
+                let position = MousePosition {
+                    x: pdu.x_position,
+                    y: pdu.y_position,
+                };
+
+                if pdu.flags.contains(PointerFlags::MOVE) && position != self.mouse_position {
+                    operations.push(Operation::MouseMove(position));
+                }
+                self.mouse_position = position;
+
+                if pdu
+                    .flags
+                    .intersects(PointerFlags::VERTICAL_WHEEL | PointerFlags::HORIZONTAL_WHEEL)
+                {
+                    operations.push(Operation::WheelRotations(WheelRotations {
+                        is_vertical: pdu.flags.contains(PointerFlags::VERTICAL_WHEEL),
+                        rotation_units: pdu.number_of_wheel_rotation_units,
+                    }));
+                } else {
+                    let pressed = pdu.flags.contains(PointerFlags::DOWN);
+
+                    for (flag, button) in [
+                        (PointerFlags::LEFT_BUTTON, MouseButton::Left),
+                        (PointerFlags::MIDDLE_BUTTON_OR_WHEEL, MouseButton::Middle),
+                        (PointerFlags::RIGHT_BUTTON, MouseButton::Right),
+                    ] {
+                        if pdu.flags.contains(flag) {
+                            operations.push(self.set_mouse_button(button, pressed));
+                        }
+                    }
+                }
+            }
+            FastPathInputEvent::MouseEventEx(ref pdu) => {
+                self.mouse_position = MousePosition {
+                    x: pdu.x_position,
+                    y: pdu.y_position,
+                };
+
+                let pressed = pdu.flags.contains(PointerXFlags::DOWN);
+
+                for (flag, button) in [
+                    (PointerXFlags::BUTTON1, MouseButton::X1),
+                    (PointerXFlags::BUTTON2, MouseButton::X2),
+                ] {
+                    if pdu.flags.contains(flag) {
+                        operations.push(self.set_mouse_button(button, pressed));
+                    }
+                }
+            }
+            FastPathInputEvent::MouseEventRel(_) | FastPathInputEvent::QoeEvent(_) => {}
+        }
+
+        operations
+    }
+
+    fn set_mouse_button(&mut self, button: MouseButton, pressed: bool) -> Operation {
+        self.mouse_buttons.set(button.as_idx(), pressed);
+
+        if pressed {
+            Operation::MouseButtonPressed(button)
+        } else {
+            Operation::MouseButtonReleased(button)
+        }
+    }
+
+    /// Returns the character of a Unicode keyboard event, once the surrogate pairs are complete.
+    fn decode_unicode_key(&mut self, code: u16) -> Option<char> {
+        if (0xD800..=0xDBFF).contains(&code) {
+            self.pending_high_surrogate = Some(code);
+            return None;
+        }
+
+        let high_surrogate = self
+            .pending_high_surrogate
+            .take()
+            .filter(|_| (0xDC00..=0xDFFF).contains(&code));
+        let units = high_surrogate.into_iter().chain(core::iter::once(code));
+
+        char::decode_utf16(units).next()?.ok()
+    }
+
     /// Releases all keys and buttons. Returns a list of RDP input events to send.
     pub fn release_all(&mut self) -> SmallVec<[FastPathInputEvent; 2]> {
         let mut events = SmallVec::new();
@@ -362,6 +654,18 @@ impl Database {
             events.push(event)
         }
 
+        self.mouse_buttons = BitArray::ZERO;
+
+        events.extend(self.release_keys());
+
+        events
+    }
+
+    /// Releases all keys, but not the mouse buttons, e.g. when the keyboard focus is lost. Returns a list of RDP
+    /// input events to send.
+    pub fn release_keys(&mut self) -> SmallVec<[FastPathInputEvent; 2]> {
+        let mut events = SmallVec::new();
+
         for idx in self.keyboard.iter_ones() {
             let (scancode, extended) = if idx >= 256 {
                 let extended_code = idx.checked_sub(256).expect("never underflow");
@@ -388,36 +692,34 @@ impl Database {
             }
         }
 
-        self.mouse_buttons = BitArray::ZERO;
         self.keyboard = BitArray::ZERO;
+        self.pending_high_surrogate = None;
+        self.repetition = None;
 
         events
     }
 }
 
-/// Returns the RDP input event to send in order to synchronize lock keys.
-pub fn synchronize_event(scroll_lock: bool, num_lock: bool, caps_lock: bool, kana_lock: bool) -> FastPathInputEvent {
-    use ironrdp_pdu::input::fast_path::SynchronizeFlags;
-
-    let mut flags = SynchronizeFlags::empty();
-
-    if scroll_lock {
-        flags |= SynchronizeFlags::SCROLL_LOCK;
-    }
-
-    if num_lock {
-        flags |= SynchronizeFlags::NUM_LOCK;
-    }
-
-    if caps_lock {
-        flags |= SynchronizeFlags::CAPS_LOCK;
-    }
-
-    if kana_lock {
-        flags |= SynchronizeFlags::KANA_LOCK;
+fn scancode_flags(scancode: Scancode) -> KeyboardFlags {
+    if scancode.extended {
+        KeyboardFlags::EXTENDED
+    } else {
+        KeyboardFlags::empty()
     }
+}
 
-    FastPathInputEvent::SyncEvent(flags)
+/// Returns the RDP input event to send in order to synchronize lock keys.
+///
+/// Unlike [`Operation::SynchronizeLockKeys`], the state of the [`Database`] is not updated.
+pub fn synchronize_event(scroll_lock: bool, num_lock: bool, caps_lock: bool, kana_lock: bool) -> FastPathInputEvent {
+    let lock_keys = LockKeys {
+        scroll_lock,
+        num_lock,
+        caps_lock,
+        kana_lock,
+    };
+
+    FastPathInputEvent::SyncEvent(lock_keys.into())
 }
 
 enum MouseButtonFlags {
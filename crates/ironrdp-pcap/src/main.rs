@@ -0,0 +1,215 @@
+#![doc = include_str!("../README.md")]
+#![doc(html_logo_url = "https://cdnweb.devolutions.net/images/projects/devolutions/logos/devolutions-icon-shadow.svg")]
+
+mod pcap;
+mod rdp;
+mod tcp;
+mod tls;
+
+use core::net::SocketAddr;
+use core::time::Duration;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::{fs, io};
+
+use anyhow::Context as _;
+use clap::Parser;
+
+use crate::rdp::{Decoder, Record};
+use crate::tcp::Reassembler;
+use crate::tls::{KeyLog, TlsEvent, TlsSession};
+
+/// Decodes the RDP PDUs of a packet capture
+#[derive(Parser, Debug)]
+#[clap(version, long_about = None)]
+struct Args {
+    /// The capture file, in the pcap or pcapng format
+    capture: PathBuf,
+
+    /// A TLS key log, in the format of SSLKEYLOGFILE, to decrypt the TLS records
+    #[clap(long, value_parser)]
+    key_log: Option<PathBuf>,
+
+    /// The TCP port of the RDP server
+    #[clap(long, value_parser, default_value_t = 3389)]
+    port: u16,
+
+    /// The maximum number of characters printed per PDU, 0 for no limit
+    #[clap(long, value_parser, default_value_t = 1000)]
+    width: usize,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let data = fs::read(&args.capture).with_context(|| format!("read {}", args.capture.display()))?;
+    let capture = pcap::read(&data).context("invalid capture file")?;
+
+    let mut key_log = KeyLog::default();
+
+    for embedded_key_log in &capture.tls_key_logs {
+        key_log.parse(embedded_key_log);
+    }
+
+    if let Some(path) = &args.key_log {
+        let content = fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        key_log.parse(&content);
+    }
+
+    let mut output = Output {
+        writer: io::stdout().lock(),
+        start: None,
+        width: args.width,
+    };
+
+    let mut connections: HashMap<(SocketAddr, SocketAddr), Connection> = HashMap::new();
+    let mut next_id = 1;
+
+    for packet in &capture.packets {
+        let Some(segment) = tcp::parse_segment(packet.link_type, &packet.data) else {
+            continue;
+        };
+
+        // The connections are keyed by the addresses of the client and of the server.
+        let (from_client, key) = if segment.destination.port() == args.port {
+            (true, (segment.source, segment.destination))
+        } else if segment.source.port() == args.port {
+            (false, (segment.destination, segment.source))
+        } else {
+            continue;
+        };
+
+        if from_client && segment.is_initial_syn() {
+            connections.remove(&key);
+        }
+
+        let connection = match connections.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let connection = Connection::new(next_id);
+                next_id += 1;
+
+                output.write(
+                    packet.timestamp,
+                    connection.id,
+                    None,
+                    &Record::new("TCP", format!("{} > {}", key.0, key.1)),
+                )?;
+
+                entry.insert(connection)
+            }
+        };
+
+        let data = connection.stream(from_client).push(&segment);
+
+        if !data.is_empty() {
+            for record in connection.feed(from_client, &data, &key_log) {
+                output.write(packet.timestamp, connection.id, Some(from_client), &record)?;
+            }
+        }
+
+        if segment.is_closing() {
+            output.write(
+                packet.timestamp,
+                connection.id,
+                Some(from_client),
+                &Record::new("TCP", "closed"),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+struct Connection {
+    id: usize,
+    client_stream: Reassembler,
+    server_stream: Reassembler,
+    /// The TLS session, once the client started the TLS handshake.
+    tls: Option<TlsSession>,
+    decoder: Decoder,
+}
+
+impl Connection {
+    fn new(id: usize) -> Self {
+        Self {
+            id,
+            client_stream: Reassembler::default(),
+            server_stream: Reassembler::default(),
+            tls: None,
+            decoder: Decoder::default(),
+        }
+    }
+
+    fn stream(&mut self, from_client: bool) -> &mut Reassembler {
+        if from_client {
+            &mut self.client_stream
+        } else {
+            &mut self.server_stream
+        }
+    }
+
+    fn feed(&mut self, from_client: bool, data: &[u8], key_log: &KeyLog) -> Vec<Record> {
+        // The ClientHello follows the X.224 negotiation, unless the standard RDP security is used.
+        if self.tls.is_none() && from_client && self.decoder.is_idle(from_client) && data.starts_with(&[0x16, 0x03]) {
+            self.tls = Some(TlsSession::default());
+        }
+
+        let Some(tls) = &mut self.tls else {
+            return self.decoder.feed(from_client, data);
+        };
+
+        let mut records = Vec::new();
+
+        for event in tls.feed(from_client, data, key_log) {
+            match event {
+                TlsEvent::Info(text) => records.push(Record::new("TLS", text)),
+                TlsEvent::Data(data) => records.extend(self.decoder.feed(from_client, &data)),
+            }
+        }
+
+        records
+    }
+}
+
+/// Writes one line per record, e.g. `0.021305 #1 S>C MCS ConnectResponse { .. }`.
+struct Output<W> {
+    writer: W,
+    /// Time of the first record, from which the times are printed.
+    start: Option<Duration>,
+    width: usize,
+}
+
+impl<W: io::Write> Output<W> {
+    fn write(
+        &mut self,
+        timestamp: Duration,
+        connection: usize,
+        from_client: Option<bool>,
+        record: &Record,
+    ) -> io::Result<()> {
+        let start = *self.start.get_or_insert(timestamp);
+        let time = timestamp.saturating_sub(start).as_secs_f64();
+
+        let direction = match from_client {
+            Some(true) => "C>S",
+            Some(false) => "S>C",
+            None => "---",
+        };
+
+        let text = if self.width != 0 && record.text.chars().count() > self.width {
+            let mut text: String = record.text.chars().take(self.width).collect();
+            text.push('…');
+            text
+        } else {
+            record.text.clone()
+        };
+
+        writeln!(
+            self.writer,
+            "{time:12.6} #{connection} {direction} {:<8} {text}",
+            record.layer
+        )
+    }
+}
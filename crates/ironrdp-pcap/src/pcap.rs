@@ -0,0 +1,254 @@
+//! Reading of the pcap and pcapng capture files.
+
+use core::time::Duration;
+
+use anyhow::{bail, ensure, Context as _};
+
+const PCAP_MAGIC_MICROS: u32 = 0xA1B2_C3D4;
+const PCAP_MAGIC_NANOS: u32 = 0xA1B2_3C4D;
+
+const PCAPNG_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const PCAPNG_SIMPLE_PACKET: u32 = 0x0000_0003;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x0000_0006;
+const PCAPNG_DECRYPTION_SECRETS: u32 = 0x0000_000A;
+
+const PCAPNG_OPTION_END: u16 = 0;
+const PCAPNG_OPTION_IF_TSRESOL: u16 = 9;
+
+/// Secrets type of a Decryption Secrets Block holding a TLS key log.
+const SECRETS_TLS_KEY_LOG: u32 = 0x544C_534B;
+
+pub(crate) struct Packet {
+    /// Time of the capture, since the Unix epoch.
+    pub(crate) timestamp: Duration,
+    /// `LINKTYPE_*` value of the interface the packet was captured on.
+    pub(crate) link_type: u32,
+    pub(crate) data: Vec<u8>,
+}
+
+#[derive(Default)]
+pub(crate) struct Capture {
+    pub(crate) packets: Vec<Packet>,
+    /// TLS key logs embedded in the capture, e.g. with `editcap --inject-secrets`.
+    pub(crate) tls_key_logs: Vec<String>,
+}
+
+/// Reads a capture file, either in the pcap or in the pcapng format.
+pub(crate) fn read(data: &[u8]) -> anyhow::Result<Capture> {
+    let magic = Reader::new(data, false).u32().context("capture file header")?;
+
+    match magic {
+        PCAPNG_SECTION_HEADER => read_pcapng(data),
+        PCAP_MAGIC_MICROS | PCAP_MAGIC_NANOS => read_pcap(data, false),
+        _ if magic.swap_bytes() == PCAP_MAGIC_MICROS || magic.swap_bytes() == PCAP_MAGIC_NANOS => read_pcap(data, true),
+        _ => bail!("not a pcap or pcapng file (magic {magic:#010x})"),
+    }
+}
+
+fn read_pcap(data: &[u8], big_endian: bool) -> anyhow::Result<Capture> {
+    let mut reader = Reader::new(data, big_endian);
+
+    let magic = reader.u32()?;
+    let _version = reader.slice(4)?;
+    let _time_zone = reader.u32()?;
+    let _sigfigs = reader.u32()?;
+    let _snap_length = reader.u32()?;
+    let link_type = reader.u32()?;
+
+    let fraction_unit = if magic == PCAP_MAGIC_NANOS { 1 } else { 1000 };
+
+    let mut capture = Capture::default();
+
+    while !reader.is_empty() {
+        let seconds = reader.u32().context("packet record header")?;
+        let fraction = reader.u32()?;
+        let captured_length = reader.u32()?;
+        let _original_length = reader.u32()?;
+        let data = reader
+            .slice(usize::try_from(captured_length)?)
+            .context("truncated packet record")?;
+
+        capture.packets.push(Packet {
+            timestamp: Duration::new(u64::from(seconds), 0) + Duration::from_nanos(u64::from(fraction) * fraction_unit),
+            link_type,
+            data: data.to_vec(),
+        });
+    }
+
+    Ok(capture)
+}
+
+struct Interface {
+    link_type: u32,
+    /// Number of timestamp units per second.
+    units_per_second: u64,
+}
+
+fn read_pcapng(data: &[u8]) -> anyhow::Result<Capture> {
+    let mut capture = Capture::default();
+    let mut interfaces = Vec::new();
+    let mut big_endian = false;
+    let mut remaining = data;
+
+    while !remaining.is_empty() {
+        let block_type = Reader::new(remaining, big_endian).u32().context("block header")?;
+
+        // The byte order is given by each section header, and the interfaces are scoped to their section.
+        if block_type == PCAPNG_SECTION_HEADER {
+            let byte_order_magic = Reader::new(remaining.get(8..).unwrap_or_default(), false)
+                .u32()
+                .context("section header")?;
+            big_endian = match byte_order_magic {
+                PCAPNG_BYTE_ORDER_MAGIC => false,
+                _ if byte_order_magic.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC => true,
+                _ => bail!("invalid pcapng byte-order magic {byte_order_magic:#010x}"),
+            };
+            interfaces.clear();
+        }
+
+        let mut reader = Reader::new(remaining, big_endian);
+        let block_type = reader.u32()?;
+        let total_length = usize::try_from(reader.u32()?)?;
+        ensure!(
+            total_length >= 12 && total_length % 4 == 0,
+            "invalid pcapng block length {total_length}"
+        );
+        let body = reader.slice(total_length - 12).context("truncated pcapng block")?;
+        remaining = remaining.get(total_length..).context("truncated pcapng block")?;
+
+        let mut body = Reader::new(body, big_endian);
+
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION => {
+                let link_type = u32::from(body.u16()?);
+                let _reserved = body.u16()?;
+                let _snap_length = body.u32()?;
+
+                let mut units_per_second = 1_000_000;
+
+                while let Ok(code) = body.u16() {
+                    let length = usize::from(body.u16()?);
+                    let value = body.slice(length)?;
+                    body.slice(padding(length))?;
+
+                    match code {
+                        PCAPNG_OPTION_END => break,
+                        PCAPNG_OPTION_IF_TSRESOL => {
+                            let resolution = *value.first().context("empty if_tsresol option")?;
+                            let exponent = u32::from(resolution & 0x7F);
+                            units_per_second = if resolution & 0x80 == 0 {
+                                10u64.checked_pow(exponent)
+                            } else {
+                                2u64.checked_pow(exponent)
+                            }
+                            .context("invalid if_tsresol option")?;
+                        }
+                        _ => {}
+                    }
+                }
+
+                interfaces.push(Interface {
+                    link_type,
+                    units_per_second,
+                });
+            }
+            PCAPNG_ENHANCED_PACKET => {
+                let interface_id = usize::try_from(body.u32()?)?;
+                let timestamp = (u64::from(body.u32()?) << 32) | u64::from(body.u32()?);
+                let captured_length = usize::try_from(body.u32()?)?;
+                let _original_length = body.u32()?;
+                let data = body.slice(captured_length)?;
+
+                let interface = interfaces
+                    .get(interface_id)
+                    .with_context(|| format!("unknown interface {interface_id}"))?;
+
+                let seconds = timestamp / interface.units_per_second;
+                let units = timestamp % interface.units_per_second;
+
+                capture.packets.push(Packet {
+                    timestamp: Duration::from_secs(seconds)
+                        + Duration::from_nanos(
+                            u64::try_from(u128::from(units) * 1_000_000_000 / u128::from(interface.units_per_second))
+                                .unwrap_or(u64::MAX),
+                        ),
+                    link_type: interface.link_type,
+                    data: data.to_vec(),
+                });
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                let original_length = usize::try_from(body.u32()?)?;
+                let data = body.remaining();
+                let interface = interfaces.first().context("simple packet without interface")?;
+
+                capture.packets.push(Packet {
+                    timestamp: Duration::ZERO,
+                    link_type: interface.link_type,
+                    data: data.get(..original_length).unwrap_or(data).to_vec(),
+                });
+            }
+            PCAPNG_DECRYPTION_SECRETS => {
+                let secrets_type = body.u32()?;
+                let length = usize::try_from(body.u32()?)?;
+                let secrets = body.slice(length)?;
+
+                if secrets_type == SECRETS_TLS_KEY_LOG {
+                    capture.tls_key_logs.push(String::from_utf8_lossy(secrets).into_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(capture)
+}
+
+fn padding(length: usize) -> usize {
+    (4 - length % 4) % 4
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], big_endian: bool) -> Self {
+        Self { data, big_endian }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+
+    fn slice(&mut self, length: usize) -> anyhow::Result<&'a [u8]> {
+        ensure!(self.data.len() >= length, "unexpected end of file");
+        let (slice, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> anyhow::Result<u16> {
+        let bytes = self.slice(2)?.try_into()?;
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        let bytes = self.slice(4)?.try_into()?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+}
@@ -0,0 +1,390 @@
+//! Decoding of the RDP byte streams of a connection with the parsers of `ironrdp-pdu`.
+
+use std::collections::HashMap;
+
+use ironrdp_core::{decode, decode_cursor, ReadCursor};
+use ironrdp_dvc::pdu::{DrdynvcClientPdu, DrdynvcServerPdu};
+use ironrdp_pdu::fast_path::{EncryptionFlags, FastPathHeader, FastPathUpdatePdu};
+use ironrdp_pdu::input::fast_path::FastPathInput;
+use ironrdp_pdu::mcs::{self, McsMessage};
+use ironrdp_pdu::nego::{self, SecurityProtocol};
+use ironrdp_pdu::rdp::headers::ShareControlHeader;
+use ironrdp_pdu::rdp::server_license::LicensePdu;
+use ironrdp_pdu::rdp::vc::{ChannelControlFlags, ChannelPduHeader};
+use ironrdp_pdu::rdp::ClientInfoPdu;
+use ironrdp_pdu::x224::{X224Data, X224};
+use ironrdp_pdu::{find_size, Action};
+
+/// A decoded PDU, or a note about the stream.
+pub(crate) struct Record {
+    pub(crate) layer: &'static str,
+    pub(crate) text: String,
+}
+
+impl Record {
+    pub(crate) fn new(layer: &'static str, text: impl Into<String>) -> Self {
+        Self {
+            layer,
+            text: text.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PduKind {
+    /// CredSSP message, sent with Hybrid security.
+    TsRequest,
+    /// Sent by the server after CredSSP, with HYBRID_EX security.
+    EarlyUserAuthorizationResult,
+    Rdp(Action),
+}
+
+/// Decodes the RDP PDUs of both directions of a connection, once the TLS records are decrypted.
+#[derive(Default)]
+pub(crate) struct Decoder {
+    client: Vec<u8>,
+    server: Vec<u8>,
+    selected_protocol: Option<SecurityProtocol>,
+    /// Whether the server is about to send the Early User Authorization Result PDU (HYBRID_EX).
+    early_user_authorization_pending: bool,
+    /// Names of the static channels requested by the client, once its Connect Initial is received.
+    channel_names: Option<Vec<String>>,
+    /// Names of the static channels by MCS channel ID, once the Connect Response is received.
+    channels: Option<HashMap<u16, String>>,
+    client_info_received: bool,
+}
+
+impl Decoder {
+    /// Whether no partial PDU of this direction is buffered.
+    pub(crate) fn is_idle(&self, from_client: bool) -> bool {
+        self.buffer(from_client).is_empty()
+    }
+
+    fn buffer(&self, from_client: bool) -> &Vec<u8> {
+        if from_client {
+            &self.client
+        } else {
+            &self.server
+        }
+    }
+
+    fn buffer_mut(&mut self, from_client: bool) -> &mut Vec<u8> {
+        if from_client {
+            &mut self.client
+        } else {
+            &mut self.server
+        }
+    }
+
+    /// Processes the bytes of one direction, returning the records of the complete PDUs.
+    pub(crate) fn feed(&mut self, from_client: bool, data: &[u8]) -> Vec<Record> {
+        let mut records = Vec::new();
+
+        self.buffer_mut(from_client).extend_from_slice(data);
+
+        loop {
+            let (kind, length) = match self.next_pdu(from_client) {
+                Ok(Some(next_pdu)) => next_pdu,
+                Ok(None) => break,
+                Err(error) => {
+                    let buffer = self.buffer_mut(from_client);
+                    records.push(Record::new(
+                        "ERROR",
+                        format!("unrecognized data, {} bytes skipped: {error}", buffer.len()),
+                    ));
+                    buffer.clear();
+                    break;
+                }
+            };
+
+            if self.buffer(from_client).len() < length {
+                break;
+            }
+
+            let pdu: Vec<u8> = self.buffer_mut(from_client).drain(..length).collect();
+
+            if !from_client && kind != PduKind::TsRequest {
+                self.early_user_authorization_pending = false;
+            }
+
+            let record = match kind {
+                PduKind::TsRequest => Record::new("CREDSSP", ts_request(&pdu)),
+                PduKind::EarlyUserAuthorizationResult => {
+                    let result = u32::from_le_bytes([pdu[0], pdu[1], pdu[2], pdu[3]]);
+                    Record::new("CREDSSP", format!("EarlyUserAuthorizationResult {result:#010X}"))
+                }
+                PduKind::Rdp(Action::FastPath) => fast_path(from_client, &pdu),
+                PduKind::Rdp(Action::X224) => self.x224(from_client, &pdu),
+            };
+
+            records.push(record);
+        }
+
+        records
+    }
+
+    /// Returns the kind and the size of the next PDU of a direction, if enough bytes are buffered to know it.
+    fn next_pdu(&self, from_client: bool) -> Result<Option<(PduKind, usize)>, String> {
+        let buffer = self.buffer(from_client);
+
+        let Some(&first_byte) = buffer.first() else {
+            return Ok(None);
+        };
+
+        if first_byte == DER_SEQUENCE {
+            return Ok(der_length(buffer).map(|(header_length, length)| (PduKind::TsRequest, header_length + length)));
+        }
+
+        if !from_client && self.early_user_authorization_pending {
+            match buffer.get(1..4) {
+                None => return Ok(None),
+                Some([0, 0, 0]) => return Ok(Some((PduKind::EarlyUserAuthorizationResult, 4))),
+                Some(_) => {}
+            }
+        }
+
+        match find_size(buffer) {
+            Ok(info) => Ok(info.map(|info| (PduKind::Rdp(info.action), info.length))),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    fn x224(&mut self, from_client: bool, pdu: &[u8]) -> Record {
+        if self.selected_protocol.is_none() {
+            if from_client {
+                if let Ok(X224(request)) = decode::<X224<nego::ConnectionRequest>>(pdu) {
+                    return Record::new("X224", format!("{request:?}"));
+                }
+            } else if let Ok(X224(confirm)) = decode::<X224<nego::ConnectionConfirm>>(pdu) {
+                if let nego::ConnectionConfirm::Response { protocol, .. } = confirm {
+                    self.selected_protocol = Some(protocol);
+                    self.early_user_authorization_pending = protocol.contains(SecurityProtocol::HYBRID_EX);
+                }
+                return Record::new("X224", format!("{confirm:?}"));
+            }
+        }
+
+        if from_client && self.channel_names.is_none() {
+            if let Some(connect_initial) = x224_data::<mcs::ConnectInitial>(pdu) {
+                self.channel_names = Some(
+                    connect_initial
+                        .channel_names()
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|channel| channel.name.as_str().unwrap_or_default().to_owned())
+                        .collect(),
+                );
+                return Record::new("MCS", format!("{connect_initial:?}"));
+            }
+        }
+
+        if !from_client && self.channels.is_none() {
+            if let Some(connect_response) = x224_data::<mcs::ConnectResponse>(pdu) {
+                let channel_names = self.channel_names.as_deref().unwrap_or_default();
+                self.channels = Some(
+                    connect_response
+                        .channel_ids()
+                        .into_iter()
+                        .zip(channel_names.iter().cloned())
+                        .collect(),
+                );
+                return Record::new("MCS", format!("{connect_response:?}"));
+            }
+        }
+
+        match decode::<X224<McsMessage<'_>>>(pdu) {
+            Ok(X224(McsMessage::SendDataRequest(request))) => {
+                self.send_data(from_client, request.channel_id, &request.user_data)
+            }
+            Ok(X224(McsMessage::SendDataIndication(indication))) => {
+                self.send_data(from_client, indication.channel_id, &indication.user_data)
+            }
+            Ok(X224(message)) => Record::new("MCS", format!("{message:?}")),
+            Err(error) => Record::new("X224", format!("undecoded PDU ({} bytes): {error}", pdu.len())),
+        }
+    }
+
+    fn send_data(&mut self, from_client: bool, channel_id: u16, data: &[u8]) -> Record {
+        if let Some(channel) = self.channels.as_ref().and_then(|channels| channels.get(&channel_id)) {
+            return virtual_channel(from_client, channel, data);
+        }
+
+        if from_client && !self.client_info_received {
+            if let Ok(mut client_info) = decode::<ClientInfoPdu>(data) {
+                self.client_info_received = true;
+
+                let password = &mut client_info.client_info.credentials.password;
+                if !password.is_empty() {
+                    *password = "<redacted>".to_owned();
+                }
+
+                return Record::new("RDP", format!("{client_info:?}"));
+            }
+        }
+
+        if let Ok(share_control) = decode::<ShareControlHeader>(data) {
+            return Record::new("RDP", format!("{share_control:?}"));
+        }
+
+        if let Ok(license) = decode::<LicensePdu>(data) {
+            return Record::new("LICENSE", format!("{license:?}"));
+        }
+
+        Record::new(
+            "MCS",
+            format!("undecoded data on channel {channel_id} ({} bytes)", data.len()),
+        )
+    }
+}
+
+fn fast_path(from_client: bool, pdu: &[u8]) -> Record {
+    if from_client {
+        return match decode::<FastPathInput>(pdu) {
+            Ok(input) => Record::new("FASTPATH", format!("{input:?}")),
+            Err(error) => Record::new("FASTPATH", format!("undecoded input ({} bytes): {error}", pdu.len())),
+        };
+    }
+
+    let mut cursor = ReadCursor::new(pdu);
+
+    let header = match decode_cursor::<FastPathHeader>(&mut cursor) {
+        Ok(header) => header,
+        Err(error) => return Record::new("FASTPATH", format!("undecoded output ({} bytes): {error}", pdu.len())),
+    };
+
+    if header.flags.contains(EncryptionFlags::ENCRYPTED) {
+        return Record::new("FASTPATH", format!("encrypted output ({} bytes)", pdu.len()));
+    }
+
+    // The updates are summarized, their data being too large to be logged.
+    let mut updates = Vec::new();
+    while !cursor.is_empty() {
+        match decode_cursor::<FastPathUpdatePdu<'_>>(&mut cursor) {
+            Ok(update) => updates.push(format!(
+                "{:?} {:?}{} ({} bytes)",
+                update.update_code,
+                update.fragmentation,
+                update
+                    .compression_flags
+                    .map(|flags| format!(" {flags:?}"))
+                    .unwrap_or_default(),
+                update.data.len()
+            )),
+            Err(error) => {
+                updates.push(format!("undecoded update: {error}"));
+                break;
+            }
+        }
+    }
+
+    Record::new("FASTPATH", updates.join(", "))
+}
+
+fn x224_data<T>(pdu: &[u8]) -> Option<T>
+where
+    T: for<'de> ironrdp_core::Decode<'de>,
+{
+    let X224(payload) = decode::<X224<X224Data<'_>>>(pdu).ok()?;
+    decode::<T>(&payload.data).ok()
+}
+
+fn virtual_channel(from_client: bool, channel: &str, data: &[u8]) -> Record {
+    let mut cursor = ReadCursor::new(data);
+
+    let header = match decode_cursor::<ChannelPduHeader>(&mut cursor) {
+        Ok(header) => header,
+        Err(error) => {
+            return Record::new(
+                "VC",
+                format!("{channel}: undecoded chunk ({} bytes): {error}", data.len()),
+            )
+        }
+    };
+
+    let complete = header
+        .flags
+        .contains(ChannelControlFlags::FLAG_FIRST | ChannelControlFlags::FLAG_LAST);
+
+    // The dynamic channels are multiplexed on `drdynvc`, whose PDUs are decoded when not fragmented.
+    if channel == "drdynvc" && complete {
+        let pdu = if from_client {
+            decode_cursor::<DrdynvcClientPdu>(&mut cursor).map(|pdu| format!("{pdu:?}"))
+        } else {
+            decode_cursor::<DrdynvcServerPdu>(&mut cursor).map(|pdu| format!("{pdu:?}"))
+        };
+
+        if let Ok(pdu) = pdu {
+            return Record::new("DVC", pdu);
+        }
+    }
+
+    Record::new("VC", format!("{channel}: {:?} ({} bytes)", header.flags, cursor.len()))
+}
+
+const DER_SEQUENCE: u8 = 0x30;
+
+/// Returns the size of the header and the length of the content of a DER element, if complete.
+fn der_length(element: &[u8]) -> Option<(usize, usize)> {
+    let first = *element.get(1)?;
+
+    if first < 0x80 {
+        return Some((2, usize::from(first)));
+    }
+
+    let size = usize::from(first & 0x7F);
+    if size > 4 {
+        // Not a TSRequest, consumed as an empty element.
+        return Some((2, 0));
+    }
+
+    let length = element
+        .get(2..2 + size)?
+        .iter()
+        .fold(0, |length, byte| (length << 8) | usize::from(*byte));
+
+    Some((2 + size, length))
+}
+
+/// Summarizes a TSRequest, listing its fields (MS-CSSP, section 2.2.1).
+fn ts_request(element: &[u8]) -> String {
+    let Some((header_length, length)) = der_length(element) else {
+        return "malformed TSRequest".to_owned();
+    };
+
+    let mut content = element.get(header_length..header_length + length).unwrap_or_default();
+    let mut fields = Vec::new();
+
+    while let Some((field_header_length, field_length)) = der_length(content) {
+        let tag = content[0];
+        let value = content
+            .get(field_header_length..field_header_length + field_length)
+            .unwrap_or_default();
+
+        let field = match tag {
+            0xA0 => format!("version={}", der_integer(value)),
+            0xA1 => "negoTokens".to_owned(),
+            0xA2 => "authInfo".to_owned(),
+            0xA3 => "pubKeyAuth".to_owned(),
+            0xA4 => format!("errorCode={:#010X}", der_integer(value)),
+            0xA5 => "clientNonce".to_owned(),
+            _ => format!("unknown field {tag:#04X}"),
+        };
+        fields.push(field);
+
+        content = content.get(field_header_length + field_length..).unwrap_or_default();
+    }
+
+    format!("TSRequest {} ({} bytes)", fields.join(" "), element.len())
+}
+
+/// Returns the value of an explicitly tagged INTEGER of up to 4 bytes.
+fn der_integer(tagged: &[u8]) -> u32 {
+    match der_length(tagged) {
+        Some((header_length, length)) if length <= 4 => tagged
+            .get(header_length..header_length + length)
+            .unwrap_or_default()
+            .iter()
+            .fold(0, |value, byte| (value << 8) | u32::from(*byte)),
+        _ => 0,
+    }
+}
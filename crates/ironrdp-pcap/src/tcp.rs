@@ -0,0 +1,229 @@
+//! Extraction and reassembly of the TCP segments of the captured packets.
+
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LOOP: u32 = 108;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+
+const IP_PROTOCOL_TCP: u8 = 6;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+const TCP_ACK: u8 = 0x10;
+
+pub(crate) struct Segment<'a> {
+    pub(crate) source: SocketAddr,
+    pub(crate) destination: SocketAddr,
+    pub(crate) sequence_number: u32,
+    flags: u8,
+    pub(crate) payload: &'a [u8],
+}
+
+impl Segment<'_> {
+    /// Whether this segment opens a new connection.
+    pub(crate) fn is_initial_syn(&self) -> bool {
+        self.flags & TCP_SYN != 0 && self.flags & TCP_ACK == 0
+    }
+
+    pub(crate) fn is_syn(&self) -> bool {
+        self.flags & TCP_SYN != 0
+    }
+
+    pub(crate) fn is_closing(&self) -> bool {
+        self.flags & (TCP_FIN | TCP_RST) != 0
+    }
+}
+
+/// Extracts the TCP segment of a captured packet, if it is one.
+///
+/// The fragmented IPv4 packets and the IPv6 extension headers are not supported.
+pub(crate) fn parse_segment(link_type: u32, frame: &[u8]) -> Option<Segment<'_>> {
+    let (ethertype, packet) = match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut ethertype = u16::from_be_bytes(frame.get(12..14)?.try_into().ok()?);
+            let mut packet = frame.get(14..)?;
+
+            if ethertype == ETHERTYPE_VLAN {
+                ethertype = u16::from_be_bytes(packet.get(2..4)?.try_into().ok()?);
+                packet = packet.get(4..)?;
+            }
+
+            (ethertype, packet)
+        }
+        LINKTYPE_NULL | LINKTYPE_LOOP => {
+            // The address family is in the byte order of the capturing host, only its low byte matters.
+            let family = frame.first()?.max(frame.get(3)?);
+            let ethertype = match family {
+                2 => ETHERTYPE_IPV4,
+                24 | 28 | 30 => ETHERTYPE_IPV6,
+                _ => return None,
+            };
+            (ethertype, frame.get(4..)?)
+        }
+        LINKTYPE_RAW => {
+            let ethertype = match frame.first()? >> 4 {
+                4 => ETHERTYPE_IPV4,
+                6 => ETHERTYPE_IPV6,
+                _ => return None,
+            };
+            (ethertype, frame)
+        }
+        LINKTYPE_LINUX_SLL => (
+            u16::from_be_bytes(frame.get(14..16)?.try_into().ok()?),
+            frame.get(16..)?,
+        ),
+        LINKTYPE_LINUX_SLL2 => (u16::from_be_bytes(frame.get(0..2)?.try_into().ok()?), frame.get(20..)?),
+        _ => return None,
+    };
+
+    let (source, destination, segment) = match ethertype {
+        ETHERTYPE_IPV4 => {
+            let header_length = usize::from(packet.first()? & 0x0F) * 4;
+            let total_length = usize::from(u16::from_be_bytes(packet.get(2..4)?.try_into().ok()?));
+            let fragment = u16::from_be_bytes(packet.get(6..8)?.try_into().ok()?);
+
+            // More fragments, or a fragment offset.
+            if *packet.get(9)? != IP_PROTOCOL_TCP || fragment & 0x3FFF != 0 {
+                return None;
+            }
+
+            let source = <[u8; 4]>::try_from(packet.get(12..16)?).ok()?;
+            let destination = <[u8; 4]>::try_from(packet.get(16..20)?).ok()?;
+
+            // The frame may be padded after the IP packet.
+            let segment = packet.get(header_length..total_length.min(packet.len()))?;
+
+            (
+                IpAddr::V4(Ipv4Addr::from(source)),
+                IpAddr::V4(Ipv4Addr::from(destination)),
+                segment,
+            )
+        }
+        ETHERTYPE_IPV6 => {
+            let payload_length = usize::from(u16::from_be_bytes(packet.get(4..6)?.try_into().ok()?));
+
+            if *packet.get(6)? != IP_PROTOCOL_TCP {
+                return None;
+            }
+
+            let source = <[u8; 16]>::try_from(packet.get(8..24)?).ok()?;
+            let destination = <[u8; 16]>::try_from(packet.get(24..40)?).ok()?;
+            let segment = packet.get(40..(40 + payload_length).min(packet.len()))?;
+
+            (
+                IpAddr::V6(Ipv6Addr::from(source)),
+                IpAddr::V6(Ipv6Addr::from(destination)),
+                segment,
+            )
+        }
+        _ => return None,
+    };
+
+    let source_port = u16::from_be_bytes(segment.get(0..2)?.try_into().ok()?);
+    let destination_port = u16::from_be_bytes(segment.get(2..4)?.try_into().ok()?);
+    let sequence_number = u32::from_be_bytes(segment.get(4..8)?.try_into().ok()?);
+    let data_offset = usize::from(segment.get(12)? >> 4) * 4;
+    let flags = *segment.get(13)?;
+
+    Some(Segment {
+        source: SocketAddr::new(source, source_port),
+        destination: SocketAddr::new(destination, destination_port),
+        sequence_number,
+        flags,
+        payload: segment.get(data_offset..)?,
+    })
+}
+
+/// Reassembles the byte stream of one direction of a TCP connection.
+#[derive(Default)]
+pub(crate) struct Reassembler {
+    next_sequence_number: Option<u32>,
+    /// Segments received ahead of the missing ones.
+    pending: Vec<(u32, Vec<u8>)>,
+}
+
+impl Reassembler {
+    /// Maximum number of segments kept while waiting for a missing one, beyond which the gap is skipped.
+    const MAX_PENDING: usize = 1024;
+
+    /// Returns the bytes following the ones returned so far, retransmissions being discarded.
+    pub(crate) fn push(&mut self, segment: &Segment<'_>) -> Vec<u8> {
+        if segment.is_syn() {
+            self.next_sequence_number = Some(segment.sequence_number.wrapping_add(1));
+            self.pending.clear();
+        }
+
+        if segment.payload.is_empty() {
+            return Vec::new();
+        }
+
+        // The capture may start in the middle of the connection.
+        let next = *self.next_sequence_number.get_or_insert(segment.sequence_number);
+
+        let mut stream = Vec::new();
+
+        if !self.append(&mut stream, segment.sequence_number, segment.payload) {
+            self.pending.push((segment.sequence_number, segment.payload.to_vec()));
+
+            if self.pending.len() > Self::MAX_PENDING {
+                // The missing segment was not captured, resume from the earliest segment received.
+                let earliest = self
+                    .pending
+                    .iter()
+                    .map(|(sequence_number, _)| *sequence_number)
+                    .min_by_key(|sequence_number| sequence_number.wrapping_sub(next))
+                    .unwrap_or(next);
+                self.next_sequence_number = Some(earliest);
+            }
+        }
+
+        // Drain the pending segments now contiguous with the stream.
+        loop {
+            let mut progressed = false;
+            let mut pending = core::mem::take(&mut self.pending);
+            pending.retain(|(sequence_number, payload)| {
+                let appended = self.append(&mut stream, *sequence_number, payload);
+                progressed |= appended;
+                !appended
+            });
+            self.pending = pending;
+
+            if !progressed {
+                break;
+            }
+        }
+
+        stream
+    }
+
+    /// Appends the part of the segment not received yet, returning `false` if it is ahead of the stream.
+    fn append(&mut self, stream: &mut Vec<u8>, sequence_number: u32, payload: &[u8]) -> bool {
+        let next = self.next_sequence_number.unwrap_or(sequence_number);
+
+        // Sequence numbers wrap around, a segment is ahead if it is less than half the sequence space after.
+        let ahead = sequence_number.wrapping_sub(next);
+        if ahead != 0 && ahead < 0x8000_0000 {
+            return false;
+        }
+
+        let already_received = usize::try_from(next.wrapping_sub(sequence_number)).unwrap_or(usize::MAX);
+
+        if let Some(new_bytes) = payload.get(already_received..) {
+            if !new_bytes.is_empty() {
+                stream.extend_from_slice(new_bytes);
+                self.next_sequence_number = Some(next.wrapping_add(u32::try_from(new_bytes.len()).unwrap_or(u32::MAX)));
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,555 @@
+//! Decryption of the TLS records with the secrets of a key log.
+//!
+//! Only the AES-GCM cipher suites are supported, which are the ones negotiated by the Windows RDP servers for TLS 1.2
+//! and TLS 1.3.
+
+use std::collections::HashMap;
+
+use aes_gcm::aead::{Aead as _, Payload};
+use aes_gcm::{Aes128Gcm, Aes256Gcm, KeyInit as _, Nonce};
+use hmac::digest::core_api::BlockSizeUser;
+use hmac::digest::Digest;
+use hmac::{Mac as _, SimpleHmac};
+use sha2::{Sha256, Sha384};
+
+const CONTENT_CHANGE_CIPHER_SPEC: u8 = 20;
+const CONTENT_ALERT: u8 = 21;
+const CONTENT_HANDSHAKE: u8 = 22;
+const CONTENT_APPLICATION_DATA: u8 = 23;
+
+const HANDSHAKE_CLIENT_HELLO: u8 = 1;
+const HANDSHAKE_SERVER_HELLO: u8 = 2;
+const HANDSHAKE_FINISHED: u8 = 20;
+
+const EXTENSION_SUPPORTED_VERSIONS: u16 = 43;
+
+const TLS_1_2: u16 = 0x0303;
+const TLS_1_3: u16 = 0x0304;
+
+const RECORD_HEADER_SIZE: usize = 5;
+const GCM_EXPLICIT_NONCE_SIZE: usize = 8;
+const GCM_TAG_SIZE: usize = 16;
+
+/// Secrets of the TLS sessions, in the NSS key log format written by the TLS libraries to `SSLKEYLOGFILE`.
+#[derive(Default)]
+pub(crate) struct KeyLog {
+    secrets: HashMap<(String, Vec<u8>), Vec<u8>>,
+}
+
+impl KeyLog {
+    /// Adds the secrets of a key log, ignoring the malformed lines.
+    pub(crate) fn parse(&mut self, key_log: &str) {
+        for line in key_log.lines() {
+            let mut fields = line.split_whitespace();
+
+            let (Some(label), Some(client_random), Some(secret)) = (fields.next(), fields.next(), fields.next()) else {
+                continue;
+            };
+
+            if label.starts_with('#') {
+                continue;
+            }
+
+            if let (Some(client_random), Some(secret)) = (decode_hex(client_random), decode_hex(secret)) {
+                self.secrets.insert((label.to_owned(), client_random), secret);
+            }
+        }
+    }
+
+    fn get(&self, label: &str, client_random: &[u8]) -> Option<&[u8]> {
+        self.secrets
+            .get(&(label.to_owned(), client_random.to_vec()))
+            .map(Vec::as_slice)
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+pub(crate) enum TlsEvent {
+    /// Handshake message, alert, or decryption issue.
+    Info(String),
+    /// Decrypted application data.
+    Data(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Hash {
+    Sha256,
+    Sha384,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CipherSuite {
+    key_size: usize,
+    hash: Hash,
+}
+
+impl CipherSuite {
+    fn from_id(id: u16) -> Option<Self> {
+        let (key_size, hash) = match id {
+            // TLS_AES_128_GCM_SHA256, and the TLS 1.2 *_WITH_AES_128_GCM_SHA256 suites with RSA, DHE and ECDHE.
+            0x1301 | 0x009C | 0x009E | 0xC02B | 0xC02F => (16, Hash::Sha256),
+            // TLS_AES_256_GCM_SHA384, and the TLS 1.2 *_WITH_AES_256_GCM_SHA384 suites with RSA, DHE and ECDHE.
+            0x1302 | 0x009D | 0x009F | 0xC02C | 0xC030 => (32, Hash::Sha384),
+            _ => return None,
+        };
+
+        Some(Self { key_size, hash })
+    }
+}
+
+/// TLS session of a connection, decrypting the records of both directions.
+#[derive(Default)]
+pub(crate) struct TlsSession {
+    client_random: Option<Vec<u8>>,
+    server_random: Option<Vec<u8>>,
+    version: u16,
+    cipher_suite: Option<CipherSuite>,
+    client: Half,
+    server: Half,
+}
+
+#[derive(Default)]
+struct Half {
+    buffer: Vec<u8>,
+    decrypter: Option<Decrypter>,
+    /// Whether the records are known not to be decrypted, which is only reported once.
+    undecrypted: bool,
+}
+
+impl TlsSession {
+    /// Processes the bytes of one direction, returning the events of the complete records.
+    pub(crate) fn feed(&mut self, from_client: bool, data: &[u8], key_log: &KeyLog) -> Vec<TlsEvent> {
+        let mut events = Vec::new();
+
+        self.half(from_client).buffer.extend_from_slice(data);
+
+        loop {
+            let buffer = &self.half(from_client).buffer;
+
+            let Some(length) = buffer
+                .get(3..5)
+                .map(|length| usize::from(u16::from_be_bytes([length[0], length[1]])))
+            else {
+                break;
+            };
+
+            if buffer.len() < RECORD_HEADER_SIZE + length {
+                break;
+            }
+
+            let record: Vec<u8> = self
+                .half(from_client)
+                .buffer
+                .drain(..RECORD_HEADER_SIZE + length)
+                .collect();
+            let (header, fragment) = record.split_at(RECORD_HEADER_SIZE);
+
+            self.record(from_client, header, fragment, key_log, &mut events);
+        }
+
+        events
+    }
+
+    fn half(&mut self, from_client: bool) -> &mut Half {
+        if from_client {
+            &mut self.client
+        } else {
+            &mut self.server
+        }
+    }
+
+    fn record(
+        &mut self,
+        from_client: bool,
+        header: &[u8],
+        fragment: &[u8],
+        key_log: &KeyLog,
+        events: &mut Vec<TlsEvent>,
+    ) {
+        let content_type = header[0];
+
+        // TLS 1.3 keeps a plaintext Change Cipher Spec for compatibility with the middleboxes.
+        if content_type != CONTENT_CHANGE_CIPHER_SPEC {
+            if let Some(decrypter) = self.half(from_client).decrypter.as_mut() {
+                match decrypter.decrypt(header, fragment) {
+                    Some((content_type, plaintext)) => {
+                        self.plaintext(from_client, content_type, &plaintext, key_log, events);
+                    }
+                    None => {
+                        events.push(TlsEvent::Info(
+                            "record decryption failed, the following records are not decrypted".to_owned(),
+                        ));
+                        let half = self.half(from_client);
+                        half.decrypter = None;
+                        half.undecrypted = true;
+                    }
+                }
+                return;
+            }
+        }
+
+        match content_type {
+            CONTENT_CHANGE_CIPHER_SPEC => {
+                events.push(TlsEvent::Info("ChangeCipherSpec".to_owned()));
+
+                if self.version == TLS_1_2 {
+                    let decrypter = self.tls12_decrypter(from_client, key_log, events);
+                    self.half(from_client).decrypter = decrypter;
+                }
+            }
+            CONTENT_APPLICATION_DATA => {
+                let half = self.half(from_client);
+                if !half.undecrypted {
+                    half.undecrypted = true;
+                    events.push(TlsEvent::Info(format!(
+                        "encrypted application data ({} bytes), the records of this direction are not decrypted",
+                        fragment.len()
+                    )));
+                }
+            }
+            _ => self.plaintext(from_client, content_type, fragment, key_log, events),
+        }
+    }
+
+    fn plaintext(
+        &mut self,
+        from_client: bool,
+        content_type: u8,
+        plaintext: &[u8],
+        key_log: &KeyLog,
+        events: &mut Vec<TlsEvent>,
+    ) {
+        match content_type {
+            CONTENT_APPLICATION_DATA => events.push(TlsEvent::Data(plaintext.to_vec())),
+            CONTENT_HANDSHAKE => self.handshake(from_client, plaintext, key_log, events),
+            CONTENT_ALERT => events.push(TlsEvent::Info(format!("Alert {plaintext:02X?}"))),
+            _ => events.push(TlsEvent::Info(format!(
+                "record of unknown content type {content_type} ({} bytes)",
+                plaintext.len()
+            ))),
+        }
+    }
+
+    fn handshake(&mut self, from_client: bool, mut messages: &[u8], key_log: &KeyLog, events: &mut Vec<TlsEvent>) {
+        while let [message_type, l0, l1, l2, rest @ ..] = messages {
+            let length = (usize::from(*l0) << 16) | (usize::from(*l1) << 8) | usize::from(*l2);
+            let Some(body) = rest.get(..length) else {
+                // Handshake messages spanning several records are only reported.
+                events.push(TlsEvent::Info(format!(
+                    "{} (fragmented)",
+                    handshake_name(*message_type)
+                )));
+                return;
+            };
+            messages = &rest[length..];
+
+            match *message_type {
+                HANDSHAKE_CLIENT_HELLO => {
+                    self.client_random = body.get(2..34).map(<[u8]>::to_vec);
+                    events.push(TlsEvent::Info("ClientHello".to_owned()));
+                }
+                HANDSHAKE_SERVER_HELLO => self.server_hello(body, key_log, events),
+                HANDSHAKE_FINISHED if self.version == TLS_1_3 => {
+                    events.push(TlsEvent::Info("Finished".to_owned()));
+
+                    // The application data follows the Finished message of each direction.
+                    let label = if from_client {
+                        "CLIENT_TRAFFIC_SECRET_0"
+                    } else {
+                        "SERVER_TRAFFIC_SECRET_0"
+                    };
+                    let decrypter = self.tls13_decrypter(label, key_log, events);
+                    self.half(from_client).decrypter = decrypter;
+                }
+                message_type => events.push(TlsEvent::Info(handshake_name(message_type).to_owned())),
+            }
+        }
+    }
+
+    fn server_hello(&mut self, body: &[u8], key_log: &KeyLog, events: &mut Vec<TlsEvent>) {
+        let Some(server_hello) = ServerHello::parse(body) else {
+            events.push(TlsEvent::Info("malformed ServerHello".to_owned()));
+            return;
+        };
+
+        self.server_random = Some(server_hello.random);
+        self.version = server_hello.version;
+        self.cipher_suite = CipherSuite::from_id(server_hello.cipher_suite);
+
+        let version = match self.version {
+            TLS_1_2 => "TLS 1.2",
+            TLS_1_3 => "TLS 1.3",
+            _ => "unsupported TLS version",
+        };
+
+        events.push(TlsEvent::Info(format!(
+            "ServerHello {version}, cipher suite {:#06X}",
+            server_hello.cipher_suite
+        )));
+
+        if self.cipher_suite.is_none() {
+            events.push(TlsEvent::Info(
+                "unsupported cipher suite, the records are not decrypted".to_owned(),
+            ));
+        }
+
+        // With TLS 1.3, the rest of the handshake is already encrypted.
+        if self.version == TLS_1_3 {
+            self.client.decrypter = self.tls13_decrypter("CLIENT_HANDSHAKE_TRAFFIC_SECRET", key_log, events);
+            self.server.decrypter = self.tls13_decrypter("SERVER_HANDSHAKE_TRAFFIC_SECRET", key_log, events);
+        }
+    }
+
+    fn tls12_decrypter(&self, from_client: bool, key_log: &KeyLog, events: &mut Vec<TlsEvent>) -> Option<Decrypter> {
+        let (Some(cipher_suite), Some(client_random), Some(server_random)) =
+            (self.cipher_suite, &self.client_random, &self.server_random)
+        else {
+            return None;
+        };
+
+        let Some(master_secret) = key_log.get("CLIENT_RANDOM", client_random) else {
+            events.push(TlsEvent::Info(
+                "no master secret in the key log for this session".to_owned(),
+            ));
+            return None;
+        };
+
+        // RFC 5246, section 6.3: the key block is split into the write keys, then the write IVs (the GCM salts).
+        let seed = [server_random.as_slice(), client_random].concat();
+        let key_block = prf(
+            cipher_suite.hash,
+            master_secret,
+            b"key expansion",
+            &seed,
+            2 * cipher_suite.key_size + 2 * 4,
+        );
+        let (keys, salts) = key_block.split_at(2 * cipher_suite.key_size);
+        let (client_write_key, server_write_key) = keys.split_at(cipher_suite.key_size);
+        let (client_salt, server_salt) = salts.split_at(4);
+
+        if from_client {
+            Decrypter::new(client_write_key, client_salt, false)
+        } else {
+            Decrypter::new(server_write_key, server_salt, false)
+        }
+    }
+
+    fn tls13_decrypter(&self, label: &str, key_log: &KeyLog, events: &mut Vec<TlsEvent>) -> Option<Decrypter> {
+        let (Some(cipher_suite), Some(client_random)) = (self.cipher_suite, &self.client_random) else {
+            return None;
+        };
+
+        let Some(secret) = key_log.get(label, client_random) else {
+            events.push(TlsEvent::Info(format!("no {label} in the key log for this session")));
+            return None;
+        };
+
+        // RFC 8446, section 7.3.
+        let key = hkdf_expand_label(cipher_suite.hash, secret, "key", cipher_suite.key_size);
+        let iv = hkdf_expand_label(cipher_suite.hash, secret, "iv", 12);
+
+        Decrypter::new(&key, &iv, true)
+    }
+}
+
+struct ServerHello {
+    version: u16,
+    random: Vec<u8>,
+    cipher_suite: u16,
+}
+
+impl ServerHello {
+    fn parse(body: &[u8]) -> Option<Self> {
+        let mut version = u16::from_be_bytes(body.get(0..2)?.try_into().ok()?);
+        let random = body.get(2..34)?.to_vec();
+        let session_id_length = usize::from(*body.get(34)?);
+        let rest = body.get(35 + session_id_length..)?;
+        let cipher_suite = u16::from_be_bytes(rest.get(0..2)?.try_into().ok()?);
+
+        // TLS 1.3 is negotiated with the supported_versions extension.
+        let mut extensions = rest.get(5..).unwrap_or_default();
+        while let [t0, t1, l0, l1, rest @ ..] = extensions {
+            let length = usize::from(u16::from_be_bytes([*l0, *l1]));
+            let data = rest.get(..length)?;
+
+            if u16::from_be_bytes([*t0, *t1]) == EXTENSION_SUPPORTED_VERSIONS {
+                version = u16::from_be_bytes(data.get(0..2)?.try_into().ok()?);
+            }
+
+            extensions = &rest[length..];
+        }
+
+        Some(Self {
+            version,
+            random,
+            cipher_suite,
+        })
+    }
+}
+
+fn handshake_name(message_type: u8) -> &'static str {
+    match message_type {
+        0 => "HelloRequest",
+        1 => "ClientHello",
+        2 => "ServerHello",
+        4 => "NewSessionTicket",
+        8 => "EncryptedExtensions",
+        11 => "Certificate",
+        12 => "ServerKeyExchange",
+        13 => "CertificateRequest",
+        14 => "ServerHelloDone",
+        15 => "CertificateVerify",
+        16 => "ClientKeyExchange",
+        20 => "Finished",
+        24 => "KeyUpdate",
+        _ => "unknown handshake message",
+    }
+}
+
+enum Cipher {
+    Aes128Gcm(Box<Aes128Gcm>),
+    Aes256Gcm(Box<Aes256Gcm>),
+}
+
+struct Decrypter {
+    cipher: Cipher,
+    /// The implicit part of the nonce: the 4-byte salt with TLS 1.2, the 12-byte IV with TLS 1.3.
+    iv: Vec<u8>,
+    sequence_number: u64,
+    tls13: bool,
+}
+
+impl Decrypter {
+    fn new(key: &[u8], iv: &[u8], tls13: bool) -> Option<Self> {
+        let cipher = match key.len() {
+            16 => Cipher::Aes128Gcm(Box::new(Aes128Gcm::new_from_slice(key).ok()?)),
+            32 => Cipher::Aes256Gcm(Box::new(Aes256Gcm::new_from_slice(key).ok()?)),
+            _ => return None,
+        };
+
+        Some(Self {
+            cipher,
+            iv: iv.to_vec(),
+            sequence_number: 0,
+            tls13,
+        })
+    }
+
+    /// Returns the content type and the plaintext of a record.
+    fn decrypt(&mut self, header: &[u8], fragment: &[u8]) -> Option<(u8, Vec<u8>)> {
+        let sequence_number = self.sequence_number.to_be_bytes();
+        self.sequence_number += 1;
+
+        if self.tls13 {
+            // RFC 8446, section 5.3: the IV XORed with the sequence number.
+            let mut nonce = self.iv.clone();
+            for (nonce, sequence_number) in nonce[4..].iter_mut().zip(sequence_number) {
+                *nonce ^= sequence_number;
+            }
+
+            let mut plaintext = self.open(&nonce, fragment, header)?;
+
+            // The content is followed by its real type, then by zero padding.
+            let content_type_position = plaintext.iter().rposition(|byte| *byte != 0)?;
+            let content_type = plaintext[content_type_position];
+            plaintext.truncate(content_type_position);
+
+            Some((content_type, plaintext))
+        } else {
+            // RFC 5288, section 3: the salt followed by the explicit nonce sent in the record.
+            if fragment.len() < GCM_EXPLICIT_NONCE_SIZE {
+                return None;
+            }
+            let (explicit_nonce, ciphertext) = fragment.split_at(GCM_EXPLICIT_NONCE_SIZE);
+            let nonce = [self.iv.as_slice(), explicit_nonce].concat();
+
+            let plaintext_length = u16::try_from(ciphertext.len().checked_sub(GCM_TAG_SIZE)?).ok()?;
+            let aad = [
+                sequence_number.as_slice(),
+                &header[..3],
+                plaintext_length.to_be_bytes().as_slice(),
+            ]
+            .concat();
+
+            let plaintext = self.open(&nonce, ciphertext, &aad)?;
+
+            Some((header[0], plaintext))
+        }
+    }
+
+    fn open(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        let nonce = Nonce::from_slice(nonce);
+        let payload = Payload { msg: ciphertext, aad };
+
+        match &self.cipher {
+            Cipher::Aes128Gcm(cipher) => cipher.decrypt(nonce, payload).ok(),
+            Cipher::Aes256Gcm(cipher) => cipher.decrypt(nonce, payload).ok(),
+        }
+    }
+}
+
+fn hmac(hash: Hash, key: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+    fn hmac_with<D: Digest + BlockSizeUser>(key: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+        let mut mac = <SimpleHmac<D> as hmac::digest::KeyInit>::new_from_slice(key).expect("HMAC accepts any key size");
+        for part in parts {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    match hash {
+        Hash::Sha256 => hmac_with::<Sha256>(key, parts),
+        Hash::Sha384 => hmac_with::<Sha384>(key, parts),
+    }
+}
+
+/// TLS 1.2 pseudo-random function (RFC 5246, section 5).
+fn prf(hash: Hash, secret: &[u8], label: &[u8], seed: &[u8], size: usize) -> Vec<u8> {
+    let seed = [label, seed].concat();
+
+    let mut output = Vec::with_capacity(size);
+    let mut a = hmac(hash, secret, &[&seed]);
+
+    while output.len() < size {
+        output.extend(hmac(hash, secret, &[&a, &seed]));
+        a = hmac(hash, secret, &[&a]);
+    }
+
+    output.truncate(size);
+    output
+}
+
+/// HKDF-Expand-Label of TLS 1.3 (RFC 8446, section 7.1), with an empty context.
+fn hkdf_expand_label(hash: Hash, secret: &[u8], label: &str, size: usize) -> Vec<u8> {
+    let label = format!("tls13 {label}");
+
+    let mut info = Vec::new();
+    info.extend_from_slice(&u16::try_from(size).expect("small key size").to_be_bytes());
+    info.push(u8::try_from(label.len()).expect("short label"));
+    info.extend_from_slice(label.as_bytes());
+    info.push(0);
+
+    // HKDF-Expand (RFC 5869, section 2.3).
+    let mut output = Vec::with_capacity(size);
+    let mut block = Vec::new();
+    let mut counter = 1u8;
+
+    while output.len() < size {
+        block = hmac(hash, secret, &[&block, &info, &[counter]]);
+        output.extend_from_slice(&block);
+        counter += 1;
+    }
+
+    output.truncate(size);
+    output
+}
@@ -3,6 +3,7 @@ mod tests;
 
 pub mod rdp6;
 
+use alloc::vec::Vec;
 use core::fmt::{self, Debug};
 
 use bitflags::bitflags;
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod tests;
 
+use alloc::vec::Vec;
+
 use bit_field::BitField;
 use bitflags::bitflags;
 use ironrdp_core::{
@@ -0,0 +1,121 @@
+use alloc::vec::Vec;
+
+use ironrdp_core::{
+    ensure_fixed_part_size, ensure_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult, ReadCursor,
+    WriteCursor,
+};
+
+const UPDATE_TYPE_PALETTE: u16 = 0x0002;
+const MAX_NUMBER_COLORS: usize = 256;
+
+/// TS_UPDATE_PALETTE_DATA
+///
+/// Color palette of the 8-bpp sessions, the bitmap data being indices into the palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteUpdateData {
+    pub entries: Vec<PaletteEntry>,
+}
+
+impl PaletteUpdateData {
+    const NAME: &'static str = "TS_UPDATE_PALETTE_DATA";
+    const FIXED_PART_SIZE: usize = 2 /* updateType */ + 2 /* pad2Octets */ + 4 /* numberColors */;
+}
+
+impl Encode for PaletteUpdateData {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        if self.entries.len() > MAX_NUMBER_COLORS {
+            return Err(invalid_field_err!("numberColors", "too many palette entries"));
+        }
+
+        dst.write_u16(UPDATE_TYPE_PALETTE);
+        dst.write_u16(0); // pad2Octets
+        dst.write_u32(u32::try_from(self.entries.len()).expect("at most 256 entries"));
+
+        for entry in &self.entries {
+            entry.encode(dst)?;
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.entries.len() * PaletteEntry::FIXED_PART_SIZE
+    }
+}
+
+impl Decode<'_> for PaletteUpdateData {
+    fn decode(src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let update_type = src.read_u16();
+        if update_type != UPDATE_TYPE_PALETTE {
+            return Err(invalid_field_err!("updateType", "invalid update type"));
+        }
+
+        let _pad = src.read_u16();
+
+        let number_colors = usize::try_from(src.read_u32())
+            .ok()
+            .filter(|&n| n <= MAX_NUMBER_COLORS)
+            .ok_or_else(|| invalid_field_err!("numberColors", "too many palette entries"))?;
+
+        ensure_size!(in: src, size: number_colors * PaletteEntry::FIXED_PART_SIZE);
+
+        let entries = (0..number_colors)
+            .map(|_| PaletteEntry::decode(src))
+            .collect::<DecodeResult<_>>()?;
+
+        Ok(Self { entries })
+    }
+}
+
+/// TS_PALETTE_ENTRY
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PaletteEntry {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl PaletteEntry {
+    const NAME: &'static str = "TS_PALETTE_ENTRY";
+    const FIXED_PART_SIZE: usize = 1 /* red */ + 1 /* green */ + 1 /* blue */;
+}
+
+impl Encode for PaletteEntry {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u8(self.red);
+        dst.write_u8(self.green);
+        dst.write_u8(self.blue);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl Decode<'_> for PaletteEntry {
+    fn decode(src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        Ok(Self {
+            red: src.read_u8(),
+            green: src.read_u8(),
+            blue: src.read_u8(),
+        })
+    }
+}
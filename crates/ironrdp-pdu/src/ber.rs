@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use ironrdp_core::{cast_length, ensure_size, invalid_field_err, ReadCursor, WriteCursor};
 
 use crate::{DecodeResult, EncodeResult};
@@ -1 +1,2 @@
+pub mod progressive;
 pub mod rfx;
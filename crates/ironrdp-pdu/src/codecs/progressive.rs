@@ -0,0 +1,822 @@
+//! RemoteFX Progressive codec (2.2.4.2 RFX_PROGRESSIVE_BITMAP_STREAM of MS-RDPEGFX), sent in the
+//! [`WireToSurface2Pdu`](crate::rdp::vc::dvc::gfx::WireToSurface2Pdu) of the graphics pipeline.
+//!
+//! The bitmap stream is a sequence of [`Block`]s. The tiles are first sent with a low quality ([`TileFirst`]), and then
+//! refined ([`TileUpgrade`]) until reaching the full quality.
+
+use alloc::vec::Vec;
+
+use bitflags::bitflags;
+use ironrdp_core::{
+    cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult,
+    ReadCursor, WriteCursor,
+};
+use num_derive::{FromPrimitive, ToPrimitive};
+use num_traits::{FromPrimitive as _, ToPrimitive as _};
+
+use crate::codecs::rfx::{Quant, RfxRectangle};
+
+const SYNC_MAGIC: u32 = 0xCACC_ACCA;
+const SYNC_VERSION: u16 = 0x0100;
+const CONTEXT_ID: u8 = 0;
+const TILE_SIZE: u16 = 0x0040;
+const REGION_TILE_SIZE: u8 = 0x40;
+
+/// Quality of the tiles sent at full quality, without upgrade.
+pub const FULL_QUALITY: u8 = 0xFF;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block<'a> {
+    Sync,
+    FrameBegin(FrameBeginPdu),
+    FrameEnd,
+    Context(ContextPdu),
+    Region(RegionPdu<'a>),
+}
+
+impl Block<'_> {
+    const NAME: &'static str = "RfxProgressiveBlock";
+
+    const FIXED_PART_SIZE: usize = BLOCK_HEADER_SIZE;
+
+    const SYNC_SIZE: usize = 4 /* magic */ + 2 /* version */;
+
+    pub fn block_type(&self) -> BlockType {
+        match self {
+            Block::Sync => BlockType::Sync,
+            Block::FrameBegin(_) => BlockType::FrameBegin,
+            Block::FrameEnd => BlockType::FrameEnd,
+            Block::Context(_) => BlockType::Context,
+            Block::Region(_) => BlockType::Region,
+        }
+    }
+}
+
+impl Encode for Block<'_> {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u16(self.block_type().to_u16().unwrap());
+        dst.write_u32(cast_length!("blockLen", self.size())?);
+
+        match self {
+            Block::Sync => {
+                dst.write_u32(SYNC_MAGIC);
+                dst.write_u16(SYNC_VERSION);
+
+                Ok(())
+            }
+            Block::FrameBegin(f) => f.encode(dst),
+            Block::FrameEnd => Ok(()),
+            Block::Context(c) => c.encode(dst),
+            Block::Region(r) => r.encode(dst),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+            + match self {
+                Block::Sync => Self::SYNC_SIZE,
+                Block::FrameBegin(f) => f.size(),
+                Block::FrameEnd => 0,
+                Block::Context(c) => c.size(),
+                Block::Region(r) => r.size(),
+            }
+    }
+}
+
+impl<'de> Decode<'de> for Block<'de> {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        let (ty, mut body) = decode_block_header(src)?;
+        let src = &mut body;
+
+        let ty = BlockType::from_u16(ty).ok_or_else(|| invalid_field_err!("blockType", "invalid block type"))?;
+
+        match ty {
+            BlockType::Sync => {
+                ensure_size!(ctx: Self::NAME, in: src, size: Self::SYNC_SIZE);
+
+                if src.read_u32() != SYNC_MAGIC {
+                    return Err(invalid_field_err!("magic", "invalid sync magic"));
+                }
+                if src.read_u16() != SYNC_VERSION {
+                    return Err(invalid_field_err!("version", "unsupported version"));
+                }
+
+                Ok(Self::Sync)
+            }
+            BlockType::FrameBegin => Ok(Self::FrameBegin(FrameBeginPdu::decode(src)?)),
+            BlockType::FrameEnd => Ok(Self::FrameEnd),
+            BlockType::Context => Ok(Self::Context(ContextPdu::decode(src)?)),
+            BlockType::Region => Ok(Self::Region(RegionPdu::decode(src)?)),
+            BlockType::TileSimple | BlockType::TileFirst | BlockType::TileUpgrade => {
+                Err(invalid_field_err!("blockType", "tile outside of a region"))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+#[repr(u16)]
+pub enum BlockType {
+    Sync = 0xCCC0,
+    FrameBegin = 0xCCC1,
+    FrameEnd = 0xCCC2,
+    Context = 0xCCC3,
+    Region = 0xCCC4,
+    TileSimple = 0xCCC5,
+    TileFirst = 0xCCC6,
+    TileUpgrade = 0xCCC7,
+}
+
+const BLOCK_HEADER_SIZE: usize = 2 /* blockType */ + 4 /* blockLen */;
+
+/// Reads the header of a block, and returns its type and a cursor over its body.
+fn decode_block_header<'de>(src: &mut ReadCursor<'de>) -> DecodeResult<(u16, ReadCursor<'de>)> {
+    ensure_size!(ctx: "RfxProgressiveBlockHeader", in: src, size: BLOCK_HEADER_SIZE);
+
+    let ty = src.read_u16();
+    let block_length: usize = cast_length!("blockLen", src.read_u32())?;
+    let body_length = block_length
+        .checked_sub(BLOCK_HEADER_SIZE)
+        .ok_or_else(|| invalid_field_err!("blockLen", "invalid block length"))?;
+
+    ensure_size!(ctx: "RfxProgressiveBlock", in: src, size: body_length);
+
+    Ok((ty, ReadCursor::new(src.read_slice(body_length))))
+}
+
+/// 2.2.4.2.1.2 RFX_PROGRESSIVE_FRAME_BEGIN
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameBeginPdu {
+    pub frame_index: u32,
+    pub region_count: u16,
+}
+
+impl FrameBeginPdu {
+    const NAME: &'static str = "RfxProgressiveFrameBegin";
+
+    const FIXED_PART_SIZE: usize = 4 /* frameIndex */ + 2 /* regionCount */;
+}
+
+impl Encode for FrameBeginPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.frame_index);
+        dst.write_u16(self.region_count);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for FrameBeginPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let frame_index = src.read_u32();
+        let region_count = src.read_u16();
+
+        Ok(Self {
+            frame_index,
+            region_count,
+        })
+    }
+}
+
+/// 2.2.4.2.1.4 RFX_PROGRESSIVE_CONTEXT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextPdu {
+    pub flags: ContextFlags,
+}
+
+impl ContextPdu {
+    const NAME: &'static str = "RfxProgressiveContext";
+
+    const FIXED_PART_SIZE: usize = 1 /* ctxId */ + 2 /* tileSize */ + 1 /* flags */;
+}
+
+impl Encode for ContextPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u8(CONTEXT_ID);
+        dst.write_u16(TILE_SIZE);
+        dst.write_u8(self.flags.bits());
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for ContextPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        if src.read_u8() != CONTEXT_ID {
+            return Err(invalid_field_err!("ctxId", "invalid context ID"));
+        }
+        if src.read_u16() != TILE_SIZE {
+            return Err(invalid_field_err!("tileSize", "invalid tile size"));
+        }
+        let flags = ContextFlags::from_bits_truncate(src.read_u8());
+
+        Ok(Self { flags })
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ContextFlags: u8 {
+        /// The sub-bands of the tiles are encoded as differences (RFX_SUBBAND_DIFFING).
+        const SUBBAND_DIFFING = 0x01;
+    }
+}
+
+/// 2.2.4.2.1.5 RFX_PROGRESSIVE_REGION
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionPdu<'a> {
+    pub flags: RegionFlags,
+    pub rectangles: Vec<RfxRectangle>,
+    pub quants: Vec<Quant>,
+    pub progressive_quants: Vec<ProgressiveQuant>,
+    pub tiles: Vec<Tile<'a>>,
+}
+
+impl RegionPdu<'_> {
+    const NAME: &'static str = "RfxProgressiveRegion";
+
+    const FIXED_PART_SIZE: usize = 1 /* tileSize */ + 2 /* numRects */ + 1 /* numQuant */ + 1 /* numProgQuant */ + 1 /* flags */ + 2 /* numTiles */ + 4 /* tileDataSize */;
+
+    const RECTANGLE_SIZE: usize = 4 * 2 /* x, y, width, height */;
+
+    const QUANT_SIZE: usize = 5;
+
+    fn tile_data_size(&self) -> usize {
+        self.tiles.iter().map(|t| t.size()).sum()
+    }
+}
+
+impl Encode for RegionPdu<'_> {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u8(REGION_TILE_SIZE);
+        dst.write_u16(cast_length!("numRects", self.rectangles.len())?);
+        dst.write_u8(cast_length!("numQuant", self.quants.len())?);
+        dst.write_u8(cast_length!("numProgQuant", self.progressive_quants.len())?);
+        dst.write_u8(self.flags.bits());
+        dst.write_u16(cast_length!("numTiles", self.tiles.len())?);
+        dst.write_u32(cast_length!("tileDataSize", self.tile_data_size())?);
+
+        for rectangle in &self.rectangles {
+            rectangle.encode(dst)?;
+        }
+        for quant in &self.quants {
+            quant.encode(dst)?;
+        }
+        for progressive_quant in &self.progressive_quants {
+            progressive_quant.encode(dst)?;
+        }
+        for tile in &self.tiles {
+            tile.encode(dst)?;
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+            + self.rectangles.len() * Self::RECTANGLE_SIZE
+            + self.quants.len() * Self::QUANT_SIZE
+            + self.progressive_quants.len() * ProgressiveQuant::FIXED_PART_SIZE
+            + self.tile_data_size()
+    }
+}
+
+impl<'de> Decode<'de> for RegionPdu<'de> {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        if src.read_u8() != REGION_TILE_SIZE {
+            return Err(invalid_field_err!("tileSize", "invalid tile size"));
+        }
+        let number_of_rectangles = usize::from(src.read_u16());
+        let number_of_quants = usize::from(src.read_u8());
+        let number_of_progressive_quants = usize::from(src.read_u8());
+        let flags = RegionFlags::from_bits_truncate(src.read_u8());
+        let number_of_tiles = usize::from(src.read_u16());
+        let tile_data_size = cast_length!("tileDataSize", src.read_u32())?;
+
+        ensure_size!(
+            in: src,
+            size: number_of_rectangles * Self::RECTANGLE_SIZE
+                + number_of_quants * Self::QUANT_SIZE
+                + number_of_progressive_quants * ProgressiveQuant::FIXED_PART_SIZE
+                + tile_data_size
+        );
+
+        let rectangles = (0..number_of_rectangles)
+            .map(|_| RfxRectangle::decode(src))
+            .collect::<Result<_, _>>()?;
+        let quants = (0..number_of_quants)
+            .map(|_| Quant::decode(src))
+            .collect::<Result<_, _>>()?;
+        let progressive_quants = (0..number_of_progressive_quants)
+            .map(|_| ProgressiveQuant::decode(src))
+            .collect::<Result<_, _>>()?;
+
+        let tiles_src = &mut ReadCursor::new(src.read_slice(tile_data_size));
+        let tiles = (0..number_of_tiles)
+            .map(|_| Tile::decode(tiles_src))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for tile in &tiles {
+            if tile
+                .quant_indices()
+                .into_iter()
+                .any(|index| usize::from(index) >= number_of_quants)
+            {
+                return Err(invalid_field_err!("quantIdx", "invalid quantization index"));
+            }
+        }
+
+        Ok(Self {
+            flags,
+            rectangles,
+            quants,
+            progressive_quants,
+            tiles,
+        })
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct RegionFlags: u8 {
+        /// The DWT uses the reduce-extrapolate method (RFX_DWT_REDUCE_EXTRAPOLATE).
+        const DWT_REDUCE_EXTRAPOLATE = 0x01;
+    }
+}
+
+/// 2.2.4.2.1.5.1 RFX_PROGRESSIVE_CODEC_QUANT
+///
+/// Quantization values of a progressive pass, for each of the color components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgressiveQuant {
+    pub quality: u8,
+    pub y_quant: Quant,
+    pub cb_quant: Quant,
+    pub cr_quant: Quant,
+}
+
+impl ProgressiveQuant {
+    const NAME: &'static str = "RfxProgressiveCodecQuant";
+
+    const FIXED_PART_SIZE: usize = 1 /* quality */ + 5 /* yQuantValues */ + 5 /* cbQuantValues */ + 5 /* crQuantValues */;
+}
+
+impl Encode for ProgressiveQuant {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u8(self.quality);
+        self.y_quant.encode(dst)?;
+        self.cb_quant.encode(dst)?;
+        self.cr_quant.encode(dst)?;
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for ProgressiveQuant {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        #![allow(clippy::similar_names)] // It’s hard to find better names for cr, cb, etc.
+        ensure_fixed_part_size!(in: src);
+
+        let quality = src.read_u8();
+        let y_quant = Quant::decode(src)?;
+        let cb_quant = Quant::decode(src)?;
+        let cr_quant = Quant::decode(src)?;
+
+        Ok(Self {
+            quality,
+            y_quant,
+            cb_quant,
+            cr_quant,
+        })
+    }
+}
+
+/// A tile of a [`RegionPdu`], sent at once or progressively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tile<'a> {
+    Simple(TileSimple<'a>),
+    First(TileFirst<'a>),
+    Upgrade(TileUpgrade<'a>),
+}
+
+impl Tile<'_> {
+    const NAME: &'static str = "RfxProgressiveTile";
+
+    const FIXED_PART_SIZE: usize = BLOCK_HEADER_SIZE;
+
+    pub fn block_type(&self) -> BlockType {
+        match self {
+            Tile::Simple(_) => BlockType::TileSimple,
+            Tile::First(_) => BlockType::TileFirst,
+            Tile::Upgrade(_) => BlockType::TileUpgrade,
+        }
+    }
+
+    /// Returns the position of the tile, as indices of the 64x64 grid.
+    pub fn position(&self) -> (u16, u16) {
+        match self {
+            Tile::Simple(t) => (t.x, t.y),
+            Tile::First(t) => (t.x, t.y),
+            Tile::Upgrade(t) => (t.x, t.y),
+        }
+    }
+
+    fn quant_indices(&self) -> [u8; 3] {
+        match self {
+            Tile::Simple(t) => [t.y_quant_index, t.cb_quant_index, t.cr_quant_index],
+            Tile::First(t) => [t.y_quant_index, t.cb_quant_index, t.cr_quant_index],
+            Tile::Upgrade(t) => [t.y_quant_index, t.cb_quant_index, t.cr_quant_index],
+        }
+    }
+}
+
+impl Encode for Tile<'_> {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u16(self.block_type().to_u16().unwrap());
+        dst.write_u32(cast_length!("blockLen", self.size())?);
+
+        match self {
+            Tile::Simple(t) => t.encode(dst),
+            Tile::First(t) => t.encode(dst),
+            Tile::Upgrade(t) => t.encode(dst),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+            + match self {
+                Tile::Simple(t) => t.size(),
+                Tile::First(t) => t.size(),
+                Tile::Upgrade(t) => t.size(),
+            }
+    }
+}
+
+impl<'de> Decode<'de> for Tile<'de> {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        let (ty, mut body) = decode_block_header(src)?;
+        let src = &mut body;
+
+        match BlockType::from_u16(ty) {
+            Some(BlockType::TileSimple) => Ok(Self::Simple(TileSimple::decode(src)?)),
+            Some(BlockType::TileFirst) => Ok(Self::First(TileFirst::decode(src)?)),
+            Some(BlockType::TileUpgrade) => Ok(Self::Upgrade(TileUpgrade::decode(src)?)),
+            _ => Err(invalid_field_err!("blockType", "invalid tile block type")),
+        }
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct TileFlags: u8 {
+        /// The tile is encoded as the difference with the previous one (RFX_TILE_DIFFERENCE).
+        const DIFFERENCE = 0x01;
+    }
+}
+
+/// 2.2.4.2.1.6 RFX_PROGRESSIVE_TILE_SIMPLE
+///
+/// A tile sent at full quality.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileSimple<'a> {
+    pub y_quant_index: u8,
+    pub cb_quant_index: u8,
+    pub cr_quant_index: u8,
+    pub x: u16,
+    pub y: u16,
+    pub flags: TileFlags,
+    pub y_data: &'a [u8],
+    pub cb_data: &'a [u8],
+    pub cr_data: &'a [u8],
+    pub tail_data: &'a [u8],
+}
+
+impl TileSimple<'_> {
+    const NAME: &'static str = "RfxProgressiveTileSimple";
+
+    const FIXED_PART_SIZE: usize = 1 /* quantIdxY */ + 1 /* quantIdxCb */ + 1 /* quantIdxCr */ + 2 /* xIdx */ + 2 /* yIdx */ + 1 /* flags */ + 2 /* yLen */ + 2 /* cbLen */ + 2 /* crLen */ + 2 /* tailLen */;
+}
+
+impl Encode for TileSimple<'_> {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u8(self.y_quant_index);
+        dst.write_u8(self.cb_quant_index);
+        dst.write_u8(self.cr_quant_index);
+        dst.write_u16(self.x);
+        dst.write_u16(self.y);
+        dst.write_u8(self.flags.bits());
+        dst.write_u16(cast_length!("yLen", self.y_data.len())?);
+        dst.write_u16(cast_length!("cbLen", self.cb_data.len())?);
+        dst.write_u16(cast_length!("crLen", self.cr_data.len())?);
+        dst.write_u16(cast_length!("tailLen", self.tail_data.len())?);
+
+        dst.write_slice(self.y_data);
+        dst.write_slice(self.cb_data);
+        dst.write_slice(self.cr_data);
+        dst.write_slice(self.tail_data);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.y_data.len() + self.cb_data.len() + self.cr_data.len() + self.tail_data.len()
+    }
+}
+
+impl<'de> Decode<'de> for TileSimple<'de> {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        #![allow(clippy::similar_names)] // It’s hard to find better names for cr, cb, etc.
+        ensure_fixed_part_size!(in: src);
+
+        let y_quant_index = src.read_u8();
+        let cb_quant_index = src.read_u8();
+        let cr_quant_index = src.read_u8();
+        let x = src.read_u16();
+        let y = src.read_u16();
+        let flags = TileFlags::from_bits_truncate(src.read_u8());
+        let y_length = usize::from(src.read_u16());
+        let cb_length = usize::from(src.read_u16());
+        let cr_length = usize::from(src.read_u16());
+        let tail_length = usize::from(src.read_u16());
+
+        ensure_size!(in: src, size: y_length + cb_length + cr_length + tail_length);
+
+        let y_data = src.read_slice(y_length);
+        let cb_data = src.read_slice(cb_length);
+        let cr_data = src.read_slice(cr_length);
+        let tail_data = src.read_slice(tail_length);
+
+        Ok(Self {
+            y_quant_index,
+            cb_quant_index,
+            cr_quant_index,
+            x,
+            y,
+            flags,
+            y_data,
+            cb_data,
+            cr_data,
+            tail_data,
+        })
+    }
+}
+
+/// 2.2.4.2.1.7 RFX_PROGRESSIVE_TILE_FIRST
+///
+/// The first progressive pass of a tile, with the quality of the [`ProgressiveQuant`] at the given index
+/// ([`FULL_QUALITY`] when not upgraded).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileFirst<'a> {
+    pub y_quant_index: u8,
+    pub cb_quant_index: u8,
+    pub cr_quant_index: u8,
+    pub x: u16,
+    pub y: u16,
+    pub flags: TileFlags,
+    pub quality: u8,
+    pub y_data: &'a [u8],
+    pub cb_data: &'a [u8],
+    pub cr_data: &'a [u8],
+    pub tail_data: &'a [u8],
+}
+
+impl TileFirst<'_> {
+    const NAME: &'static str = "RfxProgressiveTileFirst";
+
+    const FIXED_PART_SIZE: usize = 1 /* quantIdxY */ + 1 /* quantIdxCb */ + 1 /* quantIdxCr */ + 2 /* xIdx */ + 2 /* yIdx */ + 1 /* flags */ + 1 /* quality */ + 2 /* yLen */ + 2 /* cbLen */ + 2 /* crLen */ + 2 /* tailLen */;
+}
+
+impl Encode for TileFirst<'_> {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u8(self.y_quant_index);
+        dst.write_u8(self.cb_quant_index);
+        dst.write_u8(self.cr_quant_index);
+        dst.write_u16(self.x);
+        dst.write_u16(self.y);
+        dst.write_u8(self.flags.bits());
+        dst.write_u8(self.quality);
+        dst.write_u16(cast_length!("yLen", self.y_data.len())?);
+        dst.write_u16(cast_length!("cbLen", self.cb_data.len())?);
+        dst.write_u16(cast_length!("crLen", self.cr_data.len())?);
+        dst.write_u16(cast_length!("tailLen", self.tail_data.len())?);
+
+        dst.write_slice(self.y_data);
+        dst.write_slice(self.cb_data);
+        dst.write_slice(self.cr_data);
+        dst.write_slice(self.tail_data);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.y_data.len() + self.cb_data.len() + self.cr_data.len() + self.tail_data.len()
+    }
+}
+
+impl<'de> Decode<'de> for TileFirst<'de> {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        #![allow(clippy::similar_names)] // It’s hard to find better names for cr, cb, etc.
+        ensure_fixed_part_size!(in: src);
+
+        let y_quant_index = src.read_u8();
+        let cb_quant_index = src.read_u8();
+        let cr_quant_index = src.read_u8();
+        let x = src.read_u16();
+        let y = src.read_u16();
+        let flags = TileFlags::from_bits_truncate(src.read_u8());
+        let quality = src.read_u8();
+        let y_length = usize::from(src.read_u16());
+        let cb_length = usize::from(src.read_u16());
+        let cr_length = usize::from(src.read_u16());
+        let tail_length = usize::from(src.read_u16());
+
+        ensure_size!(in: src, size: y_length + cb_length + cr_length + tail_length);
+
+        let y_data = src.read_slice(y_length);
+        let cb_data = src.read_slice(cb_length);
+        let cr_data = src.read_slice(cr_length);
+        let tail_data = src.read_slice(tail_length);
+
+        Ok(Self {
+            y_quant_index,
+            cb_quant_index,
+            cr_quant_index,
+            x,
+            y,
+            flags,
+            quality,
+            y_data,
+            cb_data,
+            cr_data,
+            tail_data,
+        })
+    }
+}
+
+/// 2.2.4.2.1.8 RFX_PROGRESSIVE_TILE_UPGRADE
+///
+/// A subsequent progressive pass of a tile, refining it up to the quality of the [`ProgressiveQuant`] at the given index.
+/// The sign of the coefficients is sent with the SRL (Simplified Run-Length) data, and their raw bits with the raw data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileUpgrade<'a> {
+    pub y_quant_index: u8,
+    pub cb_quant_index: u8,
+    pub cr_quant_index: u8,
+    pub x: u16,
+    pub y: u16,
+    pub quality: u8,
+    pub y_srl_data: &'a [u8],
+    pub y_raw_data: &'a [u8],
+    pub cb_srl_data: &'a [u8],
+    pub cb_raw_data: &'a [u8],
+    pub cr_srl_data: &'a [u8],
+    pub cr_raw_data: &'a [u8],
+}
+
+impl TileUpgrade<'_> {
+    const NAME: &'static str = "RfxProgressiveTileUpgrade";
+
+    const FIXED_PART_SIZE: usize = 1 /* quantIdxY */ + 1 /* quantIdxCb */ + 1 /* quantIdxCr */ + 2 /* xIdx */ + 2 /* yIdx */ + 1 /* quality */ + 2 /* ySrlLen */ + 2 /* yRawLen */ + 2 /* cbSrlLen */ + 2 /* cbRawLen */ + 2 /* crSrlLen */ + 2 /* crRawLen */;
+
+    fn data(&self) -> [&[u8]; 6] {
+        [
+            self.y_srl_data,
+            self.y_raw_data,
+            self.cb_srl_data,
+            self.cb_raw_data,
+            self.cr_srl_data,
+            self.cr_raw_data,
+        ]
+    }
+}
+
+impl Encode for TileUpgrade<'_> {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u8(self.y_quant_index);
+        dst.write_u8(self.cb_quant_index);
+        dst.write_u8(self.cr_quant_index);
+        dst.write_u16(self.x);
+        dst.write_u16(self.y);
+        dst.write_u8(self.quality);
+
+        for data in self.data() {
+            dst.write_u16(cast_length!("dataLen", data.len())?);
+        }
+        for data in self.data() {
+            dst.write_slice(data);
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.data().iter().map(|data| data.len()).sum::<usize>()
+    }
+}
+
+impl<'de> Decode<'de> for TileUpgrade<'de> {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        #![allow(clippy::similar_names)] // It’s hard to find better names for cr, cb, etc.
+        ensure_fixed_part_size!(in: src);
+
+        let y_quant_index = src.read_u8();
+        let cb_quant_index = src.read_u8();
+        let cr_quant_index = src.read_u8();
+        let x = src.read_u16();
+        let y = src.read_u16();
+        let quality = src.read_u8();
+        let lengths: [usize; 6] = core::array::from_fn(|_| usize::from(src.read_u16()));
+
+        ensure_size!(in: src, size: lengths.iter().sum::<usize>());
+
+        let [y_srl_data, y_raw_data, cb_srl_data, cb_raw_data, cr_srl_data, cr_raw_data] =
+            lengths.map(|length| src.read_slice(length));
+
+        Ok(Self {
+            y_quant_index,
+            cb_quant_index,
+            cr_quant_index,
+            x,
+            y,
+            quality,
+            y_srl_data,
+            y_raw_data,
+            cb_srl_data,
+            cb_raw_data,
+            cr_srl_data,
+            cr_raw_data,
+        })
+    }
+}
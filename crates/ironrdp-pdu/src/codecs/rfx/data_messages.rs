@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use bit_field::BitField;
 use bitflags::bitflags;
 use ironrdp_core::{
@@ -1,3 +1,6 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::io;
 
 use ironrdp_core::{
@@ -330,6 +333,7 @@ impl UserDataHeader {
 
 #[derive(Debug, Error)]
 pub enum GccError {
+    #[cfg(feature = "std")]
     #[error("IO error")]
     IOError(#[from] io::Error),
     #[error("core data block error")]
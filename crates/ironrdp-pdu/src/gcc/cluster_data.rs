@@ -1,3 +1,4 @@
+#[cfg(feature = "std")]
 use std::io;
 
 use bitflags::bitflags;
@@ -89,6 +90,7 @@ pub enum RedirectionVersion {
 
 #[derive(Debug, Error)]
 pub enum ClusterDataError {
+    #[cfg(feature = "std")]
     #[error("IO error")]
     IOError(#[from] io::Error),
     #[error("invalid redirection flags field")]
@@ -1,6 +1,7 @@
 pub(crate) mod client;
 pub(crate) mod server;
 
+#[cfg(feature = "std")]
 use std::io;
 
 use thiserror::Error;
@@ -45,6 +46,7 @@ impl RdpVersion {
 
 #[derive(Debug, Error)]
 pub enum CoreDataError {
+    #[cfg(feature = "std")]
     #[error("IO error")]
     IOError(#[from] io::Error),
     #[error("invalid version field")]
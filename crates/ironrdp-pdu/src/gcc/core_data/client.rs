@@ -1,3 +1,5 @@
+use alloc::string::String;
+
 use bitflags::bitflags;
 use ironrdp_core::{
     ensure_fixed_part_size, ensure_size, invalid_field_err, write_padding, Decode, DecodeResult, Encode, EncodeResult,
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use bitflags::bitflags;
 use ironrdp_core::{
     cast_length, ensure_fixed_part_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult, ReadCursor,
@@ -1,5 +1,8 @@
-use std::borrow::Cow;
-use std::{io, str};
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::str;
+#[cfg(feature = "std")]
+use std::io;
 
 use bitflags::bitflags;
 use ironrdp_core::{
@@ -287,6 +290,7 @@ bitflags! {
 
 #[derive(Debug, Error)]
 pub enum NetworkDataError {
+    #[cfg(feature = "std")]
     #[error("IO error")]
     IOError(#[from] io::Error),
     #[error("UTF-8 error")]
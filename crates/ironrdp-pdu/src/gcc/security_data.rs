@@ -1,3 +1,6 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::io;
 
 use bitflags::bitflags;
@@ -208,6 +211,7 @@ pub enum EncryptionLevel {
 
 #[derive(Debug, Error)]
 pub enum SecurityDataError {
+    #[cfg(feature = "std")]
     #[error("IO error")]
     IOError(#[from] io::Error),
     #[error("invalid encryption methods field")]
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use bit_field::BitField;
 use bitflags::bitflags;
 use ironrdp_core::{
@@ -147,10 +149,13 @@ impl Encode for FastPathInputEvent {
             FastPathInputEvent::MouseEventEx(pdu) => {
                 pdu.encode(dst)?;
             }
+            FastPathInputEvent::MouseEventRel(pdu) => {
+                pdu.encode(dst)?;
+            }
             FastPathInputEvent::QoeEvent(stamp) => {
                 dst.write_u32(*stamp);
             }
-            _ => {}
+            FastPathInputEvent::SyncEvent(_) => {}
         };
 
         Ok(())
@@ -203,11 +208,7 @@ impl<'de> Decode<'de> for FastPathInputEvent {
                 let mouse_event = MouseRelPdu::decode(src)?;
                 FastPathInputEvent::MouseEventRel(mouse_event)
             }
-            FastpathInputEventType::Sync => {
-                let flags = SynchronizeFlags::from_bits(flags)
-                    .ok_or_else(|| invalid_field_err!("flags", "input synchronize flags unsupported"))?;
-                FastPathInputEvent::SyncEvent(flags)
-            }
+            FastpathInputEventType::Sync => FastPathInputEvent::SyncEvent(SynchronizeFlags::from_bits_retain(flags)),
             FastpathInputEventType::Unicode => {
                 ensure_size!(in: src, size: 2);
                 let code = src.read_u16();
@@ -241,6 +242,7 @@ bitflags! {
         const NUM_LOCK = 0x02;
         const CAPS_LOCK = 0x04;
         const KANA_LOCK = 0x08;
+        const _ = !0;
     }
 }
 
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::io;
 
 use ironrdp_core::{
@@ -174,6 +176,7 @@ impl From<&InputEvent> for InputEventType {
 
 #[derive(Debug, Error)]
 pub enum InputEventError {
+    #[cfg(feature = "std")]
     #[error("IO error")]
     IOError(#[from] io::Error),
     #[error("invalid Input Event type: {0}")]
@@ -5,6 +5,11 @@
 #![allow(clippy::cast_possible_truncation)] // FIXME: remove
 #![allow(clippy::cast_possible_wrap)] // FIXME: remove
 #![allow(clippy::cast_sign_loss)] // FIXME: remove
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(clippy::std_instead_of_alloc)]
+#![warn(clippy::std_instead_of_core)]
+
+extern crate alloc;
 
 use core::fmt;
 
@@ -34,7 +39,7 @@ pub(crate) mod ber;
 pub(crate) mod crypto;
 pub(crate) mod per;
 
-pub use crate::basic_output::{bitmap, fast_path, pointer, surface_commands};
+pub use crate::basic_output::{bitmap, fast_path, palette, pointer, surface_commands};
 pub use crate::rdp::vc::dvc;
 
 pub type PduResult<T> = Result<T, PduError>;
@@ -65,7 +70,13 @@ impl PduErrorExt for PduError {
     }
 }
 
-impl std::error::Error for PduErrorKind {}
+impl ironrdp_error::Classify for PduErrorKind {
+    fn class(&self) -> ironrdp_error::ErrorClass {
+        ironrdp_error::ErrorClass::Codec
+    }
+}
+
+impl core::error::Error for PduErrorKind {}
 
 impl fmt::Display for PduErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -1,4 +1,7 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 
 use ironrdp_core::{
     cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, other_err, read_padding,
@@ -937,6 +940,7 @@ impl DomainParameters {
 pub use legacy::McsError;
 
 mod legacy {
+    #[cfg(feature = "std")]
     use std::io;
 
     use ironrdp_core::{Decode, DecodeResult, Encode, EncodeResult};
@@ -1156,6 +1160,7 @@ mod legacy {
 
     #[derive(Debug, Error)]
     pub enum McsError {
+        #[cfg(feature = "std")]
         #[error("IO error")]
         IOError(#[from] io::Error),
         #[error("GCC block error")]
@@ -1178,6 +1183,7 @@ mod legacy {
         }
     }
 
+    #[cfg(feature = "std")]
     impl From<McsError> for io::Error {
         fn from(e: McsError) -> io::Error {
             io::Error::new(io::ErrorKind::Other, format!("MCS Connection Sequence error: {e}"))
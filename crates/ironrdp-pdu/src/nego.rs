@@ -1,5 +1,7 @@
 //! PDUs used during the Connection Initiation stage
 
+use alloc::borrow::ToOwned;
+use alloc::string::String;
 use core::fmt;
 
 use bitflags::bitflags;
@@ -23,7 +23,7 @@ pub(crate) enum PerError {
     NumericStringTooBig,
 }
 
-impl std::error::Error for PerError {}
+impl core::error::Error for PerError {}
 
 impl fmt::Display for PerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -313,7 +313,9 @@ pub(crate) fn write_numeric_string(dst: &mut WriteCursor<'_>, num_str: &[u8], mi
     Ok(())
 }
 
+#[cfg(feature = "std")]
 pub(crate) mod legacy {
+    #[cfg(feature = "std")]
     use std::io;
 
     use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
@@ -1,3 +1,5 @@
+use alloc::string::String;
+#[cfg(feature = "std")]
 use std::io;
 
 use ironrdp_core::{
@@ -12,14 +14,18 @@ use crate::rdp::headers::{BasicSecurityHeader, BasicSecurityHeaderFlags, ShareCo
 use crate::rdp::server_license::ServerLicenseError;
 use crate::PduError;
 
+pub mod autodetect;
 pub mod capability_sets;
 pub mod client_info;
 pub mod finalization_messages;
 pub mod headers;
+pub mod heartbeat;
 pub mod refresh_rectangle;
 pub mod server_error_info;
 pub mod server_license;
+pub mod server_redirection;
 pub mod session_info;
+pub mod standard_security;
 pub mod suppress_output;
 pub mod vc;
 
@@ -74,6 +80,7 @@ impl<'de> Decode<'de> for ClientInfoPdu {
 
 #[derive(Debug, Error)]
 pub enum RdpError {
+    #[cfg(feature = "std")]
     #[error("IO error")]
     IOError(#[from] io::Error),
     #[error("client Info PDU error")]
@@ -110,6 +117,7 @@ impl From<PduError> for RdpError {
     }
 }
 
+#[cfg(feature = "std")]
 impl From<RdpError> for io::Error {
     fn from(e: RdpError) -> io::Error {
         io::Error::new(io::ErrorKind::Other, format!("RDP Connection Sequence error: {e}"))
@@ -0,0 +1,556 @@
+use alloc::vec::Vec;
+
+use ironrdp_core::{
+    cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult,
+    ReadCursor, WriteCursor,
+};
+
+use crate::rdp::headers::{BasicSecurityHeader, BasicSecurityHeaderFlags};
+
+const TYPE_ID_AUTODETECT_REQUEST: u8 = 0x00;
+const TYPE_ID_AUTODETECT_RESPONSE: u8 = 0x01;
+
+const RTT_REQUEST_CONTINUOUS: u16 = 0x0001;
+const RTT_REQUEST_CONNECT_TIME: u16 = 0x1001;
+const BW_START_CONTINUOUS: u16 = 0x0014;
+const BW_START_TUNNEL: u16 = 0x0114;
+const BW_START_CONNECT_TIME: u16 = 0x1014;
+const BW_PAYLOAD: u16 = 0x0002;
+const BW_STOP_CONNECT_TIME: u16 = 0x002B;
+const BW_STOP_CONTINUOUS: u16 = 0x0429;
+const BW_STOP_TUNNEL: u16 = 0x0629;
+const NETCHAR_RESULT_BASE_RTT_AVERAGE_RTT: u16 = 0x0840;
+const NETCHAR_RESULT_BANDWIDTH_AVERAGE_RTT: u16 = 0x0880;
+const NETCHAR_RESULT_ALL: u16 = 0x08C0;
+
+const RTT_RESPONSE: u16 = 0x0000;
+const BW_RESULTS_CONNECT_TIME: u16 = 0x0003;
+const BW_RESULTS_CONTINUOUS: u16 = 0x000B;
+const NETCHAR_SYNC: u16 = 0x0018;
+
+/// Common fields of the auto-detect requests and responses: headerLength, headerTypeId, sequenceNumber and
+/// requestType (or responseType).
+const HEADER_SIZE: usize = 1 /* headerLength */ + 1 /* headerTypeId */ + 2 /* sequenceNumber */ + 2 /* requestType */;
+
+/// When the network characteristics are measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoDetectPhase {
+    /// During the Connect-Time Auto-Detection phase of the connection sequence.
+    ConnectTime,
+    /// After the connection sequence, over the main transport.
+    Continuous,
+    /// After the connection sequence, over the multitransport tunnel. Only for the bandwidth measures.
+    Tunnel,
+}
+
+/// \[MS-RDPBCGR\] 2.2.14.1 Auto-Detect Request PDU Structures (TS_AUTODETECT_REQ)
+///
+/// Sent by the server to measure the round-trip time and the bandwidth of the connection, or to send the
+/// results of the measures to the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutoDetectRequest {
+    /// RTT Measure Request (RDP_RTT_REQUEST), answered with [`AutoDetectResponse::RttMeasure`].
+    ///
+    /// The phase is either connect-time or continuous.
+    RttMeasure {
+        sequence_number: u16,
+        phase: AutoDetectPhase,
+    },
+    /// Bandwidth Measure Start (RDP_BW_START), starting the bandwidth measure.
+    BandwidthMeasureStart {
+        sequence_number: u16,
+        phase: AutoDetectPhase,
+    },
+    /// Bandwidth Measure Payload (RDP_BW_PAYLOAD), data sent during a connect-time bandwidth measure.
+    BandwidthMeasurePayload { sequence_number: u16, payload: Vec<u8> },
+    /// Bandwidth Measure Stop (RDP_BW_STOP), answered with [`AutoDetectResponse::BandwidthMeasureResults`].
+    ///
+    /// The payload is only sent during the connect-time measures.
+    BandwidthMeasureStop {
+        sequence_number: u16,
+        phase: AutoDetectPhase,
+        payload: Vec<u8>,
+    },
+    /// Network Characteristics Result (RDP_NETCHAR_RESULT), with the results of the measures.
+    ///
+    /// The round-trip times are in milliseconds, and the bandwidth in kilobits per second. At least one
+    /// of the base round-trip time and the bandwidth is sent.
+    NetworkCharacteristicsResult {
+        sequence_number: u16,
+        base_rtt: Option<u32>,
+        bandwidth: Option<u32>,
+        average_rtt: u32,
+    },
+}
+
+impl AutoDetectRequest {
+    const NAME: &'static str = "TS_AUTODETECT_REQ";
+
+    const FIXED_PART_SIZE: usize = HEADER_SIZE;
+
+    pub fn sequence_number(&self) -> u16 {
+        match *self {
+            Self::RttMeasure { sequence_number, .. }
+            | Self::BandwidthMeasureStart { sequence_number, .. }
+            | Self::BandwidthMeasurePayload { sequence_number, .. }
+            | Self::BandwidthMeasureStop { sequence_number, .. }
+            | Self::NetworkCharacteristicsResult { sequence_number, .. } => sequence_number,
+        }
+    }
+
+    fn request_type(&self) -> EncodeResult<u16> {
+        let request_type = match self {
+            Self::RttMeasure { phase, .. } => match phase {
+                AutoDetectPhase::ConnectTime => RTT_REQUEST_CONNECT_TIME,
+                AutoDetectPhase::Continuous => RTT_REQUEST_CONTINUOUS,
+                AutoDetectPhase::Tunnel => {
+                    return Err(invalid_field_err!("requestType", "no RTT measure over the tunnel"))
+                }
+            },
+            Self::BandwidthMeasureStart { phase, .. } => match phase {
+                AutoDetectPhase::ConnectTime => BW_START_CONNECT_TIME,
+                AutoDetectPhase::Continuous => BW_START_CONTINUOUS,
+                AutoDetectPhase::Tunnel => BW_START_TUNNEL,
+            },
+            Self::BandwidthMeasurePayload { .. } => BW_PAYLOAD,
+            Self::BandwidthMeasureStop { phase, .. } => match phase {
+                AutoDetectPhase::ConnectTime => BW_STOP_CONNECT_TIME,
+                AutoDetectPhase::Continuous => BW_STOP_CONTINUOUS,
+                AutoDetectPhase::Tunnel => BW_STOP_TUNNEL,
+            },
+            Self::NetworkCharacteristicsResult {
+                base_rtt, bandwidth, ..
+            } => match (base_rtt, bandwidth) {
+                (Some(_), None) => NETCHAR_RESULT_BASE_RTT_AVERAGE_RTT,
+                (None, Some(_)) => NETCHAR_RESULT_BANDWIDTH_AVERAGE_RTT,
+                (Some(_), Some(_)) => NETCHAR_RESULT_ALL,
+                (None, None) => {
+                    return Err(invalid_field_err!(
+                        "requestType",
+                        "neither the base RTT nor the bandwidth is given"
+                    ))
+                }
+            },
+        };
+
+        Ok(request_type)
+    }
+
+    /// Returns the size of the header (headerLength), the payloads of the bandwidth measures following it.
+    fn header_length(&self) -> usize {
+        match self {
+            Self::RttMeasure { .. } | Self::BandwidthMeasureStart { .. } => HEADER_SIZE,
+            Self::BandwidthMeasurePayload { .. } => {
+                HEADER_SIZE + 2 /* payloadLength */
+            }
+            Self::BandwidthMeasureStop { phase, .. } => {
+                if *phase == AutoDetectPhase::ConnectTime {
+                    HEADER_SIZE + 2 /* payloadLength */
+                } else {
+                    HEADER_SIZE
+                }
+            }
+            Self::NetworkCharacteristicsResult {
+                base_rtt, bandwidth, ..
+            } => {
+                HEADER_SIZE + base_rtt.map_or(0, |_| 4) + bandwidth.map_or(0, |_| 4) + 4
+                /* averageRTT */
+            }
+        }
+    }
+
+    fn payload(&self) -> &[u8] {
+        match self {
+            Self::BandwidthMeasurePayload { payload, .. } => payload,
+            Self::BandwidthMeasureStop {
+                phase: AutoDetectPhase::ConnectTime,
+                payload,
+                ..
+            } => payload,
+            _ => &[],
+        }
+    }
+}
+
+impl Encode for AutoDetectRequest {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u8(cast_length!("headerLength", self.header_length())?);
+        dst.write_u8(TYPE_ID_AUTODETECT_REQUEST);
+        dst.write_u16(self.sequence_number());
+        dst.write_u16(self.request_type()?);
+
+        match self {
+            Self::RttMeasure { .. } | Self::BandwidthMeasureStart { .. } => {}
+            Self::BandwidthMeasurePayload { payload, .. } => {
+                dst.write_u16(cast_length!("payloadLength", payload.len())?);
+                dst.write_slice(payload);
+            }
+            Self::BandwidthMeasureStop { phase, payload, .. } => {
+                if *phase == AutoDetectPhase::ConnectTime {
+                    dst.write_u16(cast_length!("payloadLength", payload.len())?);
+                    dst.write_slice(payload);
+                } else if !payload.is_empty() {
+                    return Err(invalid_field_err!(
+                        "payload",
+                        "only sent during the connect-time measures"
+                    ));
+                }
+            }
+            Self::NetworkCharacteristicsResult {
+                base_rtt,
+                bandwidth,
+                average_rtt,
+                ..
+            } => {
+                if let Some(base_rtt) = base_rtt {
+                    dst.write_u32(*base_rtt);
+                }
+                if let Some(bandwidth) = bandwidth {
+                    dst.write_u32(*bandwidth);
+                }
+                dst.write_u32(*average_rtt);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        self.header_length() + self.payload().len()
+    }
+}
+
+impl<'de> Decode<'de> for AutoDetectRequest {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let header_length = usize::from(src.read_u8());
+        let header_type_id = src.read_u8();
+        if header_type_id != TYPE_ID_AUTODETECT_REQUEST {
+            return Err(invalid_field_err!("headerTypeId", "not an auto-detect request"));
+        }
+        let sequence_number = src.read_u16();
+        let request_type = src.read_u16();
+
+        let read_payload = |src: &mut ReadCursor<'de>| -> DecodeResult<Vec<u8>> {
+            ensure_size!(in: src, size: 2);
+            let payload_length = usize::from(src.read_u16());
+            ensure_size!(in: src, size: payload_length);
+            Ok(src.read_slice(payload_length).to_vec())
+        };
+
+        let request = match request_type {
+            RTT_REQUEST_CONNECT_TIME | RTT_REQUEST_CONTINUOUS => Self::RttMeasure {
+                sequence_number,
+                phase: if request_type == RTT_REQUEST_CONNECT_TIME {
+                    AutoDetectPhase::ConnectTime
+                } else {
+                    AutoDetectPhase::Continuous
+                },
+            },
+            BW_START_CONNECT_TIME | BW_START_CONTINUOUS | BW_START_TUNNEL => Self::BandwidthMeasureStart {
+                sequence_number,
+                phase: match request_type {
+                    BW_START_CONNECT_TIME => AutoDetectPhase::ConnectTime,
+                    BW_START_CONTINUOUS => AutoDetectPhase::Continuous,
+                    _ => AutoDetectPhase::Tunnel,
+                },
+            },
+            BW_PAYLOAD => Self::BandwidthMeasurePayload {
+                sequence_number,
+                payload: read_payload(src)?,
+            },
+            BW_STOP_CONNECT_TIME => Self::BandwidthMeasureStop {
+                sequence_number,
+                phase: AutoDetectPhase::ConnectTime,
+                payload: read_payload(src)?,
+            },
+            BW_STOP_CONTINUOUS | BW_STOP_TUNNEL => Self::BandwidthMeasureStop {
+                sequence_number,
+                phase: if request_type == BW_STOP_CONTINUOUS {
+                    AutoDetectPhase::Continuous
+                } else {
+                    AutoDetectPhase::Tunnel
+                },
+                payload: Vec::new(),
+            },
+            NETCHAR_RESULT_BASE_RTT_AVERAGE_RTT | NETCHAR_RESULT_BANDWIDTH_AVERAGE_RTT | NETCHAR_RESULT_ALL => {
+                let has_base_rtt = request_type != NETCHAR_RESULT_BANDWIDTH_AVERAGE_RTT;
+                let has_bandwidth = request_type != NETCHAR_RESULT_BASE_RTT_AVERAGE_RTT;
+
+                ensure_size!(in: src, size: 4 * (usize::from(has_base_rtt) + usize::from(has_bandwidth) + 1));
+
+                let base_rtt = has_base_rtt.then(|| src.read_u32());
+                let bandwidth = has_bandwidth.then(|| src.read_u32());
+                let average_rtt = src.read_u32();
+
+                Self::NetworkCharacteristicsResult {
+                    sequence_number,
+                    base_rtt,
+                    bandwidth,
+                    average_rtt,
+                }
+            }
+            _ => {
+                return Err(invalid_field_err!(
+                    "requestType",
+                    "unsupported auto-detect request type"
+                ))
+            }
+        };
+
+        if header_length != request.header_length() {
+            return Err(invalid_field_err!("headerLength", "invalid header length"));
+        }
+
+        Ok(request)
+    }
+}
+
+/// \[MS-RDPBCGR\] 2.2.14.2 Auto-Detect Response PDU Structures (TS_AUTODETECT_RSP)
+///
+/// Sent by the client in answer to the auto-detect requests of the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoDetectResponse {
+    /// RTT Measure Response (RDP_RTT_RESPONSE), answering [`AutoDetectRequest::RttMeasure`].
+    RttMeasure { sequence_number: u16 },
+    /// Bandwidth Measure Results (RDP_BW_RESULTS), answering [`AutoDetectRequest::BandwidthMeasureStop`].
+    ///
+    /// The phase is either connect-time or continuous, the time delta is in milliseconds.
+    BandwidthMeasureResults {
+        sequence_number: u16,
+        phase: AutoDetectPhase,
+        time_delta: u32,
+        byte_count: u32,
+    },
+    /// Network Characteristics Sync (RDP_NETCHAR_SYNC), sent instead of the connect-time measures to give the
+    /// results of a previous connection to the server.
+    ///
+    /// The bandwidth is in kilobits per second, and the round-trip time in milliseconds.
+    NetworkCharacteristicsSync {
+        sequence_number: u16,
+        bandwidth: u32,
+        rtt: u32,
+    },
+}
+
+impl AutoDetectResponse {
+    const NAME: &'static str = "TS_AUTODETECT_RSP";
+
+    const FIXED_PART_SIZE: usize = HEADER_SIZE;
+
+    pub fn sequence_number(&self) -> u16 {
+        match *self {
+            Self::RttMeasure { sequence_number }
+            | Self::BandwidthMeasureResults { sequence_number, .. }
+            | Self::NetworkCharacteristicsSync { sequence_number, .. } => sequence_number,
+        }
+    }
+
+    fn header_length(&self) -> usize {
+        match self {
+            Self::RttMeasure { .. } => HEADER_SIZE,
+            Self::BandwidthMeasureResults { .. } => {
+                HEADER_SIZE + 4 /* timeDelta */ + 4 /* byteCount */
+            }
+            Self::NetworkCharacteristicsSync { .. } => {
+                HEADER_SIZE + 4 /* bandwidth */ + 4 /* rtt */
+            }
+        }
+    }
+}
+
+impl Encode for AutoDetectResponse {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        let response_type = match self {
+            Self::RttMeasure { .. } => RTT_RESPONSE,
+            Self::BandwidthMeasureResults { phase, .. } => match phase {
+                AutoDetectPhase::ConnectTime => BW_RESULTS_CONNECT_TIME,
+                AutoDetectPhase::Continuous => BW_RESULTS_CONTINUOUS,
+                AutoDetectPhase::Tunnel => {
+                    return Err(invalid_field_err!(
+                        "responseType",
+                        "no bandwidth results over the tunnel"
+                    ))
+                }
+            },
+            Self::NetworkCharacteristicsSync { .. } => NETCHAR_SYNC,
+        };
+
+        dst.write_u8(cast_length!("headerLength", self.header_length())?);
+        dst.write_u8(TYPE_ID_AUTODETECT_RESPONSE);
+        dst.write_u16(self.sequence_number());
+        dst.write_u16(response_type);
+
+        match *self {
+            Self::RttMeasure { .. } => {}
+            Self::BandwidthMeasureResults {
+                time_delta, byte_count, ..
+            } => {
+                dst.write_u32(time_delta);
+                dst.write_u32(byte_count);
+            }
+            Self::NetworkCharacteristicsSync { bandwidth, rtt, .. } => {
+                dst.write_u32(bandwidth);
+                dst.write_u32(rtt);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        self.header_length()
+    }
+}
+
+impl<'de> Decode<'de> for AutoDetectResponse {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let header_length = usize::from(src.read_u8());
+        let header_type_id = src.read_u8();
+        if header_type_id != TYPE_ID_AUTODETECT_RESPONSE {
+            return Err(invalid_field_err!("headerTypeId", "not an auto-detect response"));
+        }
+        let sequence_number = src.read_u16();
+        let response_type = src.read_u16();
+
+        let response = match response_type {
+            RTT_RESPONSE => Self::RttMeasure { sequence_number },
+            BW_RESULTS_CONNECT_TIME | BW_RESULTS_CONTINUOUS => {
+                ensure_size!(in: src, size: 8);
+
+                Self::BandwidthMeasureResults {
+                    sequence_number,
+                    phase: if response_type == BW_RESULTS_CONNECT_TIME {
+                        AutoDetectPhase::ConnectTime
+                    } else {
+                        AutoDetectPhase::Continuous
+                    },
+                    time_delta: src.read_u32(),
+                    byte_count: src.read_u32(),
+                }
+            }
+            NETCHAR_SYNC => {
+                ensure_size!(in: src, size: 8);
+
+                Self::NetworkCharacteristicsSync {
+                    sequence_number,
+                    bandwidth: src.read_u32(),
+                    rtt: src.read_u32(),
+                }
+            }
+            _ => {
+                return Err(invalid_field_err!(
+                    "responseType",
+                    "unsupported auto-detect response type"
+                ))
+            }
+        };
+
+        if header_length != response.header_length() {
+            return Err(invalid_field_err!("headerLength", "invalid header length"));
+        }
+
+        Ok(response)
+    }
+}
+
+/// \[MS-RDPBCGR\] 2.2.14.3 Server Auto-Detect Request PDU
+///
+/// An auto-detect request sent on the message channel, after a security header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoDetectRequestPdu(pub AutoDetectRequest);
+
+impl AutoDetectRequestPdu {
+    const NAME: &'static str = "AutoDetectRequestPdu";
+
+    const FIXED_PART_SIZE: usize = BasicSecurityHeader::FIXED_PART_SIZE;
+}
+
+impl Encode for AutoDetectRequestPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        BasicSecurityHeader {
+            flags: BasicSecurityHeaderFlags::AUTODETECT_REQ,
+        }
+        .encode(dst)?;
+        self.0.encode(dst)
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.0.size()
+    }
+}
+
+impl<'de> Decode<'de> for AutoDetectRequestPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        let header = BasicSecurityHeader::decode(src)?;
+        if !header.flags.contains(BasicSecurityHeaderFlags::AUTODETECT_REQ) {
+            return Err(invalid_field_err!("securityHeader", "expected an auto-detect request"));
+        }
+
+        Ok(Self(AutoDetectRequest::decode(src)?))
+    }
+}
+
+/// \[MS-RDPBCGR\] 2.2.14.4 Client Auto-Detect Response PDU
+///
+/// An auto-detect response sent on the message channel, after a security header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoDetectResponsePdu(pub AutoDetectResponse);
+
+impl AutoDetectResponsePdu {
+    const NAME: &'static str = "AutoDetectResponsePdu";
+
+    const FIXED_PART_SIZE: usize = BasicSecurityHeader::FIXED_PART_SIZE;
+}
+
+impl Encode for AutoDetectResponsePdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        BasicSecurityHeader {
+            flags: BasicSecurityHeaderFlags::AUTODETECT_RSP,
+        }
+        .encode(dst)?;
+        self.0.encode(dst)
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.0.size()
+    }
+}
+
+impl<'de> Decode<'de> for AutoDetectResponsePdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        let header = BasicSecurityHeader::decode(src)?;
+        if !header.flags.contains(BasicSecurityHeaderFlags::AUTODETECT_RSP) {
+            return Err(invalid_field_err!("securityHeader", "expected an auto-detect response"));
+        }
+
+        Ok(Self(AutoDetectResponse::decode(src)?))
+    }
+}
@@ -1,3 +1,7 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::io;
 
 use ironrdp_core::{
@@ -596,10 +600,11 @@ enum CapabilitySetType {
 
 #[derive(Debug, Error)]
 pub enum CapabilitySetsError {
+    #[cfg(feature = "std")]
     #[error("IO error")]
     IOError(#[from] io::Error),
     #[error("UTF-8 error")]
-    Utf8Error(#[from] std::string::FromUtf8Error),
+    Utf8Error(#[from] alloc::string::FromUtf8Error),
     #[error("invalid type field")]
     InvalidType,
     #[error("invalid bitmap compression field")]
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests;
 
-use std::fmt;
+use core::fmt;
 
 use bitflags::bitflags;
 use ironrdp_core::{
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod tests;
 
+use alloc::string::String;
+
 use bitflags::bitflags;
 use ironrdp_core::{
     ensure_fixed_part_size, read_padding, write_padding, Decode, DecodeResult, Encode, EncodeResult, ReadCursor,
@@ -1,4 +1,6 @@
+use alloc::string::String;
 use core::fmt;
+#[cfg(feature = "std")]
 use std::io;
 
 use bitflags::bitflags;
@@ -685,10 +687,11 @@ pub enum CompressionType {
 
 #[derive(Debug, Error)]
 pub enum ClientInfoError {
+    #[cfg(feature = "std")]
     #[error("IO error")]
     IOError(#[from] io::Error),
     #[error("UTF-8 error")]
-    Utf8Error(#[from] std::string::FromUtf8Error),
+    Utf8Error(#[from] alloc::string::FromUtf8Error),
     #[error("invalid address family field")]
     InvalidAddressFamily,
     #[error("invalid flags field")]
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use bitflags::bitflags;
 use ironrdp_core::{
     cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, not_enough_bytes_err, other_err, read_padding,
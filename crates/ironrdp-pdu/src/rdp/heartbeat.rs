@@ -0,0 +1,69 @@
+use ironrdp_core::{
+    ensure_fixed_part_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult, ReadCursor, WriteCursor,
+};
+
+use crate::rdp::headers::{BasicSecurityHeader, BasicSecurityHeaderFlags};
+
+/// \[MS-RDPBCGR\] 2.2.16.1 Server Heartbeat PDU (TS_HEARTBEAT_PDU)
+///
+/// Sent periodically by the server on the I/O channel, so the client can detect a broken connection
+/// when the heartbeats stop. Only sent to the clients advertising the `SUPPORT_HEART_BEAT_PDU` early
+/// capability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeartbeatPdu {
+    /// Interval between two heartbeats, in seconds.
+    pub period: u8,
+    /// Number of missed heartbeats after which the client warns the user.
+    pub count1: u8,
+    /// Number of missed heartbeats after which the client reconnects.
+    pub count2: u8,
+}
+
+impl HeartbeatPdu {
+    const NAME: &'static str = "HeartbeatPdu";
+
+    const FIXED_PART_SIZE: usize = BasicSecurityHeader::FIXED_PART_SIZE + 4 /* reserved, period, count1, count2 */;
+}
+
+impl Encode for HeartbeatPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        BasicSecurityHeader {
+            flags: BasicSecurityHeaderFlags::HEARTBEAT,
+        }
+        .encode(dst)?;
+        dst.write_u8(0); // reserved
+        dst.write_u8(self.period);
+        dst.write_u8(self.count1);
+        dst.write_u8(self.count2);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for HeartbeatPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let header = BasicSecurityHeader::decode(src)?;
+        if !header.flags.contains(BasicSecurityHeaderFlags::HEARTBEAT) {
+            return Err(invalid_field_err!("securityHeader", "expected a heartbeat packet"));
+        }
+
+        let _reserved = src.read_u8();
+        let period = src.read_u8();
+        let count1 = src.read_u8();
+        let count2 = src.read_u8();
+
+        Ok(Self { period, count1, count2 })
+    }
+}
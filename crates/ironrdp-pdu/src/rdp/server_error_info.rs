@@ -1,6 +1,8 @@
-use ironrdp_core::{
-    ensure_fixed_part_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult, ReadCursor, WriteCursor,
-};
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+
+use ironrdp_core::{ensure_fixed_part_size, Decode, DecodeResult, Encode, EncodeResult, ReadCursor, WriteCursor};
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::{FromPrimitive, ToPrimitive};
 
@@ -35,9 +37,7 @@ impl<'de> Decode<'de> for ServerSetErrorInfoPdu {
     fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
         ensure_fixed_part_size!(in: src);
 
-        let error_info = src.read_u32();
-        let error_info =
-            ErrorInfo::from_u32(error_info).ok_or_else(|| invalid_field_err!("errorInfo", "unexpected info code"))?;
+        let error_info = ErrorInfo::from(src.read_u32());
 
         Ok(Self(error_info))
     }
@@ -49,25 +49,93 @@ pub enum ErrorInfo {
     ProtocolIndependentLicensingCode(ProtocolIndependentLicensingCode),
     ProtocolIndependentConnectionBrokerCode(ProtocolIndependentConnectionBrokerCode),
     RdpSpecificCode(RdpSpecificCode),
+    /// A code not documented in \[MS-RDPBCGR\], e.g. sent by a newer server.
+    Unknown(u32),
 }
 
 impl ErrorInfo {
+    /// Returns a human-readable description, starting with the category of the error.
     pub fn description(self) -> String {
+        let details = match &self {
+            Self::ProtocolIndependentCode(c) => c.description(),
+            Self::ProtocolIndependentLicensingCode(c) => c.description(),
+            Self::ProtocolIndependentConnectionBrokerCode(c) => c.description(),
+            Self::RdpSpecificCode(c) => c.description(),
+            Self::Unknown(code) => return format!("{} (unknown error info code 0x{code:08X})", self.category()),
+        };
+
+        format!("{}: {details}", self.category())
+    }
+
+    pub fn category(self) -> ErrorInfoCategory {
         match self {
-            Self::ProtocolIndependentCode(c) => {
-                format!("[Protocol independent error] {}", c.description())
-            }
-            Self::ProtocolIndependentLicensingCode(c) => {
-                format!("[Protocol independent licensing error] {}", c.description())
-            }
-            Self::ProtocolIndependentConnectionBrokerCode(c) => {
-                format!("[Protocol independent connection broker error] {}", c.description())
-            }
-            Self::RdpSpecificCode(c) => format!("[RDP specific code]: {}", c.description()),
+            Self::ProtocolIndependentCode(c) => c.category(),
+            Self::ProtocolIndependentLicensingCode(_) => ErrorInfoCategory::Licensing,
+            Self::ProtocolIndependentConnectionBrokerCode(_) => ErrorInfoCategory::ConnectionBroker,
+            Self::RdpSpecificCode(_) => ErrorInfoCategory::Protocol,
+            Self::Unknown(_) => ErrorInfoCategory::Other,
+        }
+    }
+}
+
+impl fmt::Display for ErrorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.description())
+    }
+}
+
+impl From<u32> for ErrorInfo {
+    fn from(code: u32) -> Self {
+        Self::from_u32(code).unwrap_or(Self::Unknown(code))
+    }
+}
+
+/// Broad classification of the [`ErrorInfo`] codes, e.g. to pick the message shown to the user.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorInfoCategory {
+    /// The session was disconnected or logged off by an administrator, or the server is shutting down.
+    AdminDisconnect,
+    /// The user logged off.
+    Logoff,
+    /// Another connection took over the session.
+    OtherConnection,
+    /// A time limit of the session elapsed.
+    Timeout,
+    /// The server refused the user.
+    AccessDenied,
+    /// The server, or a component of the remote session, failed.
+    ServerFailure,
+    Licensing,
+    ConnectionBroker,
+    /// The server received unexpected or malformed data.
+    Protocol,
+    /// No error, or an unknown code.
+    Other,
+}
+
+impl ErrorInfoCategory {
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::AdminDisconnect => "disconnected by an administrator",
+            Self::Logoff => "logged off",
+            Self::OtherConnection => "disconnected by another connection",
+            Self::Timeout => "session time limit reached",
+            Self::AccessDenied => "access denied",
+            Self::ServerFailure => "server failure",
+            Self::Licensing => "licensing error",
+            Self::ConnectionBroker => "connection broker error",
+            Self::Protocol => "protocol error",
+            Self::Other => "disconnected",
         }
     }
 }
 
+impl fmt::Display for ErrorInfoCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
 impl FromPrimitive for ErrorInfo {
     fn from_i64(n: i64) -> Option<Self> {
         if let Some(v) = ProtocolIndependentCode::from_i64(n) {
@@ -101,6 +169,7 @@ impl ToPrimitive for ErrorInfo {
             Self::ProtocolIndependentLicensingCode(c) => c.to_i64(),
             Self::ProtocolIndependentConnectionBrokerCode(c) => c.to_i64(),
             Self::RdpSpecificCode(c) => c.to_i64(),
+            Self::Unknown(code) => Some(i64::from(*code)),
         }
     }
 
@@ -110,6 +179,7 @@ impl ToPrimitive for ErrorInfo {
             Self::ProtocolIndependentLicensingCode(c) => c.to_u64(),
             Self::ProtocolIndependentConnectionBrokerCode(c) => c.to_u64(),
             Self::RdpSpecificCode(c) => c.to_u64(),
+            Self::Unknown(code) => Some(u64::from(*code)),
         }
     }
 }
@@ -134,6 +204,8 @@ pub enum ProtocolIndependentCode {
     CloseStackOnDriverIfaceFailure = 0x0000_0012,
     ServerWinlogonCrash = 0x0000_0017,
     ServerCsrssCrash = 0x0000_0018,
+    ServerShutdown = 0x0000_0019,
+    ServerReboot = 0x0000_001A,
 }
 
 impl ProtocolIndependentCode {
@@ -157,6 +229,32 @@ impl ProtocolIndependentCode {
             Self::CloseStackOnDriverIfaceFailure => "The display driver in the remote session started up successfully, but due to internal failures was not usable by the remoting stack",
             Self::ServerWinlogonCrash => "The Winlogon process running in the remote session terminated unexpectedly",
             Self::ServerCsrssCrash => "The CSRSS process running in the remote session terminated unexpectedly",
+            Self::ServerShutdown => "The disconnection was initiated by an administrator shutting down the server",
+            Self::ServerReboot => "The disconnection was initiated by an administrator rebooting the server",
+        }
+    }
+
+    pub fn category(&self) -> ErrorInfoCategory {
+        match self {
+            Self::RpcInitiatedDisconnect
+            | Self::RpcInitiatedLogoff
+            | Self::RpcInitiatedDisconnectByuser
+            | Self::ServerShutdown
+            | Self::ServerReboot => ErrorInfoCategory::AdminDisconnect,
+            Self::LogoffByUser => ErrorInfoCategory::Logoff,
+            Self::DisconnectedByOtherconnection => ErrorInfoCategory::OtherConnection,
+            Self::IdleTimeout | Self::LogonTimeout => ErrorInfoCategory::Timeout,
+            Self::ServerDeniedConnection
+            | Self::ServerInsufficientPrivileges
+            | Self::ServerFreshCredentialsRequired => ErrorInfoCategory::AccessDenied,
+            Self::OutOfMemory
+            | Self::CloseStackOnDriverNotReady
+            | Self::ServerDwmCrash
+            | Self::CloseStackOnDriverFailure
+            | Self::CloseStackOnDriverIfaceFailure
+            | Self::ServerWinlogonCrash
+            | Self::ServerCsrssCrash => ErrorInfoCategory::ServerFailure,
+            Self::None => ErrorInfoCategory::Other,
         }
     }
 }
@@ -428,4 +526,27 @@ mod tests {
     fn buffer_length_is_correct_for_server_set_error_info() {
         assert_eq!(SERVER_SET_ERROR_INFO_BUFFER.len(), SERVER_SET_ERROR_INFO.size());
     }
+
+    #[test]
+    fn unknown_error_info_code_is_preserved() {
+        let buffer = [0xEF, 0xBE, 0xAD, 0xDE];
+
+        let pdu: ServerSetErrorInfoPdu = decode(buffer.as_ref()).unwrap();
+
+        assert_eq!(ServerSetErrorInfoPdu(ErrorInfo::Unknown(0xDEAD_BEEF)), pdu);
+        assert_eq!(ErrorInfoCategory::Other, pdu.0.category());
+        assert_eq!(buffer.as_ref(), encode_vec(&pdu).unwrap().as_slice());
+    }
+
+    #[test]
+    fn error_info_description_starts_with_category() {
+        let info = ErrorInfo::from(0x0000_0001);
+
+        assert_eq!(
+            ErrorInfo::ProtocolIndependentCode(ProtocolIndependentCode::RpcInitiatedDisconnect),
+            info
+        );
+        assert_eq!(ErrorInfoCategory::AdminDisconnect, info.category());
+        assert!(info.description().starts_with("disconnected by an administrator: "));
+    }
 }
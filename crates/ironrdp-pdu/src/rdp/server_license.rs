@@ -1,3 +1,7 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::io;
 
 use bitflags::bitflags;
@@ -180,10 +184,11 @@ impl BlobType {
 
 #[derive(Debug, Error)]
 pub enum ServerLicenseError {
+    #[cfg(feature = "std")]
     #[error("IO error: {0}")]
     IOError(#[from] io::Error),
     #[error("UTF-8 error: {0}")]
-    Utf8Error(#[from] std::string::FromUtf8Error),
+    Utf8Error(#[from] alloc::string::FromUtf8Error),
     #[error("invalid preamble field: {0}")]
     InvalidPreamble(String),
     #[error("invalid preamble message type field")]
@@ -210,11 +215,19 @@ pub enum ServerLicenseError {
     InvalidChallengeResponseDataClientType,
     #[error("invalid platform challenge response data license detail level")]
     InvalidChallengeResponseDataLicenseDetail,
+    #[cfg(feature = "std")]
     #[error("invalid x509 certificate")]
     InvalidX509Certificate {
         source: x509_cert::der::Error,
         cert_der: Vec<u8>,
     },
+    // The DER errors don't implement `Error` without std, they can't be the source.
+    #[cfg(not(feature = "std"))]
+    #[error("invalid x509 certificate: {error}")]
+    InvalidX509Certificate {
+        error: x509_cert::der::Error,
+        cert_der: Vec<u8>,
+    },
     #[error("invalid certificate version")]
     InvalidCertificateVersion,
     #[error("invalid x509 certificates amount")]
@@ -1,6 +1,5 @@
-use std::io;
+use alloc::vec::Vec;
 
-use byteorder::{LittleEndian, WriteBytesExt};
 use ironrdp_core::{
     ensure_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult, ReadCursor, WriteCursor,
 };
@@ -42,10 +41,11 @@ impl ClientLicenseInfo {
         hardware_data: [u32; 4],
         license_info: Vec<u8>,
     ) -> Result<(Self, LicenseEncryptionData), ServerLicenseError> {
-        let public_key = license_request.get_public_key()?
-            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData,
-                                          "attempted to retrieve the server public key from a server license request message that does not have a certificate"))?;
-        let encrypted_premaster_secret = encrypt_with_public_key(premaster_secret, &public_key)?;
+        let public_key = license_request
+            .get_public_key()?
+            .ok_or(ServerLicenseError::UnableToGetPublicKey)?;
+        let encrypted_premaster_secret = encrypt_with_public_key(premaster_secret, &public_key)
+            .map_err(|_| ServerLicenseError::RsaKeyEncryptionError)?;
 
         let master_secret = compute_master_secret(
             premaster_secret,
@@ -72,9 +72,9 @@ impl ClientLicenseInfo {
         let license_key = md5.finalize().to_vec();
 
         let mut hardware_id = Vec::with_capacity(CLIENT_HARDWARE_IDENTIFICATION_SIZE);
-        hardware_id.write_u32::<LittleEndian>(PLATFORM_ID)?;
+        hardware_id.extend_from_slice(&PLATFORM_ID.to_le_bytes());
         for data in hardware_data {
-            hardware_id.write_u32::<LittleEndian>(data)?;
+            hardware_id.extend_from_slice(&data.to_le_bytes());
         }
 
         let mut rc4 = Rc4::new(&license_key);
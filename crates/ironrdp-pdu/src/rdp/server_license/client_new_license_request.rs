@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod tests;
 
-use std::io;
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 use bitflags::bitflags;
 use ironrdp_core::{
@@ -61,11 +63,12 @@ impl ClientNewLicenseRequest {
         client_username: &str,
         client_machine_name: &str,
     ) -> Result<(Self, LicenseEncryptionData), ServerLicenseError> {
-        let public_key = license_request.get_public_key()?
-            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData,
-                "attempted to retrieve the server public key from a server license request message that does not have a certificate"))?;
+        let public_key = license_request
+            .get_public_key()?
+            .ok_or(ServerLicenseError::UnableToGetPublicKey)?;
 
-        let encrypted_premaster_secret = encrypt_with_public_key(premaster_secret, &public_key)?;
+        let encrypted_premaster_secret = encrypt_with_public_key(premaster_secret, &public_key)
+            .map_err(|_| ServerLicenseError::RsaKeyEncryptionError)?;
 
         let master_secret = compute_master_secret(
             premaster_secret,
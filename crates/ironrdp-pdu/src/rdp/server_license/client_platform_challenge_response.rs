@@ -1,9 +1,9 @@
 #[cfg(test)]
 mod test;
 
-use std::io::Write;
+use alloc::vec;
+use alloc::vec::Vec;
 
-use byteorder::{LittleEndian, WriteBytesExt as _};
 use ironrdp_core::{
     cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult,
     ReadCursor, WriteCursor,
@@ -53,16 +53,16 @@ impl ClientPlatformChallengeResponse {
         }
 
         let mut challenge_response_data = vec![0u8; RESPONSE_DATA_STATIC_FIELDS_SIZE];
-        challenge_response_data.write_u16::<LittleEndian>(RESPONSE_DATA_VERSION)?;
-        challenge_response_data.write_u16::<LittleEndian>(ClientType::Other.to_u16().unwrap())?;
-        challenge_response_data.write_u16::<LittleEndian>(LicenseDetailLevel::Detail.to_u16().unwrap())?;
-        challenge_response_data.write_u16::<LittleEndian>(decrypted_challenge.len() as u16)?;
-        challenge_response_data.write_all(&decrypted_challenge)?;
+        challenge_response_data.extend_from_slice(&RESPONSE_DATA_VERSION.to_le_bytes());
+        challenge_response_data.extend_from_slice(&ClientType::Other.to_u16().unwrap().to_le_bytes());
+        challenge_response_data.extend_from_slice(&LicenseDetailLevel::Detail.to_u16().unwrap().to_le_bytes());
+        challenge_response_data.extend_from_slice(&(decrypted_challenge.len() as u16).to_le_bytes());
+        challenge_response_data.extend_from_slice(&decrypted_challenge);
 
         let mut hardware_id = Vec::with_capacity(CLIENT_HARDWARE_IDENTIFICATION_SIZE);
-        hardware_id.write_u32::<LittleEndian>(PLATFORM_ID)?;
+        hardware_id.extend_from_slice(&PLATFORM_ID.to_le_bytes());
         for data in hardware_data {
-            hardware_id.write_u32::<LittleEndian>(data)?;
+            hardware_id.extend_from_slice(&data.to_le_bytes());
         }
 
         let mut rc4 = Rc4::new(&encryption_data.license_key);
@@ -186,8 +186,8 @@ fn challenge_response_creates_from_server_challenge_and_encryption_data_correctl
 
     let hardware_data = vec![0u8; 16];
     let mut hardware_id = Vec::with_capacity(CLIENT_HARDWARE_IDENTIFICATION_SIZE);
-    hardware_id.write_u32::<LittleEndian>(PLATFORM_ID).unwrap();
-    hardware_id.write_all(&hardware_data).unwrap();
+    hardware_id.extend_from_slice(&PLATFORM_ID.to_le_bytes());
+    hardware_id.extend_from_slice(&hardware_data);
 
     let mut rc4 = Rc4::new(&encryption_data.license_key);
     let encrypted_hwid = rc4.process(&hardware_id);
@@ -1,6 +1,9 @@
 #[cfg(test)]
 mod test;
 
+use alloc::vec;
+use alloc::vec::Vec;
+
 use ironrdp_core::{
     cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult,
     ReadCursor, WriteCursor,
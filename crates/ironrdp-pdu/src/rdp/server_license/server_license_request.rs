@@ -3,6 +3,10 @@ pub mod cert;
 #[cfg(test)]
 mod tests;
 
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use cert::{CertificateType, ProprietaryCertificate, X509CertificateChain};
 use ironrdp_core::{
     cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult,
@@ -235,9 +239,12 @@ impl ServerCertificate {
                     .last()
                     .ok_or_else(|| ServerLicenseError::InvalidX509CertificatesAmount)?;
 
-                let cert = x509_cert::Certificate::from_der(cert_der).map_err(|source| {
+                let cert = x509_cert::Certificate::from_der(cert_der).map_err(|error| {
                     ServerLicenseError::InvalidX509Certificate {
-                        source,
+                        #[cfg(feature = "std")]
+                        source: error,
+                        #[cfg(not(feature = "std"))]
+                        error,
                         cert_der: cert_der.clone(),
                     }
                 })?;
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use ironrdp_core::{
     cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, read_padding, write_padding, Decode,
     DecodeResult, Encode, EncodeResult, ReadCursor, WriteCursor,
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod test;
 
+use alloc::vec::Vec;
+
 use ironrdp_core::{
     ensure_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult, ReadCursor, WriteCursor,
 };
@@ -0,0 +1,324 @@
+//! Server redirection (\[MS-RDPBCGR\] 2.2.13), with which a server (e.g. a connection broker) sends the client
+//! to another server, typically the server running the session of the user.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use bitflags::bitflags;
+use ironrdp_core::{
+    cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, read_padding, write_padding, Decode,
+    DecodeResult, Encode, EncodeResult, ReadCursor, WriteCursor,
+};
+use num_traits::ToPrimitive as _;
+
+use crate::rdp::headers::{BasicSecurityHeaderFlags, ShareControlPduType};
+use crate::utils;
+
+const PROTOCOL_VERSION: u16 = 0x10;
+
+bitflags! {
+    /// Fields and options of a [`ServerRedirectionPdu`] (redirFlags).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    pub struct ServerRedirectionFlags: u32 {
+        const TARGET_NET_ADDRESS = 0x0000_0001;
+        const LOAD_BALANCE_INFO = 0x0000_0002;
+        const USERNAME = 0x0000_0004;
+        const DOMAIN = 0x0000_0008;
+        const PASSWORD = 0x0000_0010;
+        const DONT_STORE_USERNAME = 0x0000_0020;
+        const SMARTCARD_LOGON = 0x0000_0040;
+        const NO_REDIRECT = 0x0000_0080;
+        const TARGET_FQDN = 0x0000_0100;
+        const TARGET_NETBIOS_NAME = 0x0000_0200;
+        const TARGET_NET_ADDRESSES = 0x0000_0800;
+        const CLIENT_TSV_URL = 0x0000_1000;
+        const SERVER_TSV_CAPABLE = 0x0000_2000;
+        const PASSWORD_IS_PK_ENCRYPTED = 0x0000_4000;
+        const REDIRECTION_GUID = 0x0000_8000;
+        const TARGET_CERTIFICATE = 0x0001_0000;
+    }
+}
+
+impl ServerRedirectionFlags {
+    /// Flags indicating the presence of the fields, set from the fields of the PDU when it is encoded.
+    const FIELDS: Self = Self::TARGET_NET_ADDRESS
+        .union(Self::LOAD_BALANCE_INFO)
+        .union(Self::USERNAME)
+        .union(Self::DOMAIN)
+        .union(Self::PASSWORD)
+        .union(Self::TARGET_FQDN)
+        .union(Self::TARGET_NETBIOS_NAME)
+        .union(Self::CLIENT_TSV_URL)
+        .union(Self::REDIRECTION_GUID)
+        .union(Self::TARGET_CERTIFICATE)
+        .union(Self::TARGET_NET_ADDRESSES);
+}
+
+/// \[MS-RDPBCGR\] 2.2.13.1 Server Redirection Packet (RDP_SERVER_REDIRECTION_PACKET)
+///
+/// The flags of the fields are set from the fields when the PDU is encoded, [`ServerRedirectionPdu::flags`]
+/// only holds the options (e.g. [`ServerRedirectionFlags::NO_REDIRECT`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerRedirectionPdu {
+    /// ID of the session to which the client connects on the target server.
+    pub session_id: u32,
+    pub flags: ServerRedirectionFlags,
+    /// IP address of the target server.
+    pub target_net_address: Option<String>,
+    /// Routing token given to the target server in the X.224 Connection Request.
+    pub load_balance_info: Option<Vec<u8>>,
+    pub username: Option<String>,
+    pub domain: Option<String>,
+    /// Password of the user, or an encrypted cookie when the target server authenticates the client itself.
+    pub password: Option<Vec<u8>>,
+    pub target_fqdn: Option<String>,
+    pub target_netbios_name: Option<String>,
+    pub tsv_url: Option<Vec<u8>>,
+    pub redirection_guid: Option<Vec<u8>>,
+    pub target_certificate: Option<Vec<u8>>,
+    /// IP addresses of the target server, tried in order.
+    pub target_net_addresses: Option<Vec<String>>,
+}
+
+impl ServerRedirectionPdu {
+    const NAME: &'static str = "ServerRedirectionPdu";
+
+    const FIXED_PART_SIZE: usize = 2 /* flags */ + 2 /* length */ + 4 /* sessionId */ + 4 /* redirFlags */;
+
+    /// Returns the fields of the PDU in their encoding order, with their flag.
+    fn fields(&self) -> [(ServerRedirectionFlags, Option<Vec<u8>>); 11] {
+        let string = |value: &Option<String>| value.as_deref().map(encode_unicode);
+
+        [
+            (
+                ServerRedirectionFlags::TARGET_NET_ADDRESS,
+                string(&self.target_net_address),
+            ),
+            (
+                ServerRedirectionFlags::LOAD_BALANCE_INFO,
+                self.load_balance_info.clone(),
+            ),
+            (ServerRedirectionFlags::USERNAME, string(&self.username)),
+            (ServerRedirectionFlags::DOMAIN, string(&self.domain)),
+            (ServerRedirectionFlags::PASSWORD, self.password.clone()),
+            (ServerRedirectionFlags::TARGET_FQDN, string(&self.target_fqdn)),
+            (
+                ServerRedirectionFlags::TARGET_NETBIOS_NAME,
+                string(&self.target_netbios_name),
+            ),
+            (ServerRedirectionFlags::CLIENT_TSV_URL, self.tsv_url.clone()),
+            (ServerRedirectionFlags::REDIRECTION_GUID, self.redirection_guid.clone()),
+            (
+                ServerRedirectionFlags::TARGET_CERTIFICATE,
+                self.target_certificate.clone(),
+            ),
+            (
+                ServerRedirectionFlags::TARGET_NET_ADDRESSES,
+                self.target_net_addresses.as_deref().map(encode_net_addresses),
+            ),
+        ]
+    }
+}
+
+impl Encode for ServerRedirectionPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        let fields = self.fields();
+        let flags = fields
+            .iter()
+            .filter(|(_, value)| value.is_some())
+            .fold(self.flags - ServerRedirectionFlags::FIELDS, |flags, (flag, _)| {
+                flags | *flag
+            });
+
+        dst.write_u16(BasicSecurityHeaderFlags::REDIRECTION_PKT.bits());
+        dst.write_u16(cast_length!("length", self.size())?);
+        dst.write_u32(self.session_id);
+        dst.write_u32(flags.bits());
+
+        for value in fields.iter().filter_map(|(_, value)| value.as_ref()) {
+            dst.write_u32(cast_length!("fieldLength", value.len())?);
+            dst.write_slice(value);
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+            + self
+                .fields()
+                .iter()
+                .filter_map(|(_, value)| value.as_ref())
+                .map(|value| 4 + value.len())
+                .sum::<usize>()
+    }
+}
+
+impl<'de> Decode<'de> for ServerRedirectionPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let header_flags = src.read_u16();
+        if header_flags & BasicSecurityHeaderFlags::REDIRECTION_PKT.bits() == 0 {
+            return Err(invalid_field_err!("flags", "expected a redirection packet"));
+        }
+        let length: usize = cast_length!("length", src.read_u16())?;
+        let session_id = src.read_u32();
+        let flags = ServerRedirectionFlags::from_bits_truncate(src.read_u32());
+
+        let fields_length = length
+            .checked_sub(Self::FIXED_PART_SIZE)
+            .ok_or_else(|| invalid_field_err!("length", "redirection packet too short"))?;
+        ensure_size!(in: src, size: fields_length);
+        let mut fields = ReadCursor::new(src.read_slice(fields_length));
+
+        let mut field = |flag: ServerRedirectionFlags| -> DecodeResult<Option<&'de [u8]>> {
+            if !flags.contains(flag) {
+                return Ok(None);
+            }
+            ensure_size!(in: fields, size: 4);
+            let length = cast_length!("fieldLength", fields.read_u32())?;
+            ensure_size!(in: fields, size: length);
+            Ok(Some(fields.read_slice(length)))
+        };
+
+        let target_net_address = field(ServerRedirectionFlags::TARGET_NET_ADDRESS)?.map(decode_unicode);
+        let load_balance_info = field(ServerRedirectionFlags::LOAD_BALANCE_INFO)?.map(<[u8]>::to_vec);
+        let username = field(ServerRedirectionFlags::USERNAME)?.map(decode_unicode);
+        let domain = field(ServerRedirectionFlags::DOMAIN)?.map(decode_unicode);
+        let password = field(ServerRedirectionFlags::PASSWORD)?.map(<[u8]>::to_vec);
+        let target_fqdn = field(ServerRedirectionFlags::TARGET_FQDN)?.map(decode_unicode);
+        let target_netbios_name = field(ServerRedirectionFlags::TARGET_NETBIOS_NAME)?.map(decode_unicode);
+        let tsv_url = field(ServerRedirectionFlags::CLIENT_TSV_URL)?.map(<[u8]>::to_vec);
+        let redirection_guid = field(ServerRedirectionFlags::REDIRECTION_GUID)?.map(<[u8]>::to_vec);
+        let target_certificate = field(ServerRedirectionFlags::TARGET_CERTIFICATE)?.map(<[u8]>::to_vec);
+        let target_net_addresses = field(ServerRedirectionFlags::TARGET_NET_ADDRESSES)?
+            .map(decode_net_addresses)
+            .transpose()?;
+        // The optional padding which may follow the fields is ignored.
+
+        Ok(Self {
+            session_id,
+            flags: flags - ServerRedirectionFlags::FIELDS,
+            target_net_address,
+            load_balance_info,
+            username,
+            domain,
+            password,
+            target_fqdn,
+            target_netbios_name,
+            tsv_url,
+            redirection_guid,
+            target_certificate,
+            target_net_addresses,
+        })
+    }
+}
+
+/// \[MS-RDPBCGR\] 2.2.13.3.1 Enhanced Security Server Redirection PDU (TS_ENHANCED_SECURITY_SERVER_REDIRECTION)
+///
+/// Sends a [`ServerRedirectionPdu`] on the I/O channel when Enhanced RDP Security (TLS or CredSSP) is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnhancedSecurityServerRedirection {
+    /// ID of the I/O channel, source of the PDU.
+    pub pdu_source: u16,
+    pub redirection: ServerRedirectionPdu,
+}
+
+impl EnhancedSecurityServerRedirection {
+    const NAME: &'static str = "EnhancedSecurityServerRedirection";
+
+    const FIXED_PART_SIZE: usize = 2 /* totalLength */ + 2 /* pduType */ + 2 /* pduSource */ + 2 /* pad2Octets */ + 1 /* pad1Octet */;
+}
+
+impl Encode for EnhancedSecurityServerRedirection {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u16(cast_length!("totalLength", self.size())?);
+        dst.write_u16(PROTOCOL_VERSION | ShareControlPduType::ServerRedirect.to_u16().unwrap());
+        dst.write_u16(self.pdu_source);
+        write_padding!(dst, 2);
+        self.redirection.encode(dst)?;
+        write_padding!(dst, 1);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.redirection.size()
+    }
+}
+
+impl<'de> Decode<'de> for EnhancedSecurityServerRedirection {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let _total_length = src.read_u16();
+        let pdu_type = src.read_u16();
+        if pdu_type != PROTOCOL_VERSION | ShareControlPduType::ServerRedirect.to_u16().unwrap() {
+            return Err(invalid_field_err!("pduType", "expected a server redirection PDU"));
+        }
+        let pdu_source = src.read_u16();
+        read_padding!(src, 2);
+        let redirection = ServerRedirectionPdu::decode(src)?;
+        // Some servers omit the final padding.
+        if !src.is_empty() {
+            read_padding!(src, 1);
+        }
+
+        Ok(Self {
+            pdu_source,
+            redirection,
+        })
+    }
+}
+
+fn encode_unicode(value: &str) -> Vec<u8> {
+    let mut encoded = utils::to_utf16_bytes(value);
+    encoded.extend_from_slice(&[0, 0]);
+    encoded
+}
+
+fn decode_unicode(value: &[u8]) -> String {
+    utils::from_utf16_bytes(value).trim_end_matches('\0').to_owned()
+}
+
+/// Encodes a TARGET_NET_ADDRESSES structure.
+fn encode_net_addresses(addresses: &[String]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    encoded.extend_from_slice(&u32::try_from(addresses.len()).unwrap_or(u32::MAX).to_le_bytes());
+    for address in addresses {
+        let address = encode_unicode(address);
+        encoded.extend_from_slice(&u32::try_from(address.len()).unwrap_or(u32::MAX).to_le_bytes());
+        encoded.extend_from_slice(&address);
+    }
+    encoded
+}
+
+fn decode_net_addresses(value: &[u8]) -> DecodeResult<Vec<String>> {
+    let mut src = ReadCursor::new(value);
+
+    ensure_size!(in: src, size: 4);
+    let count = src.read_u32();
+    let mut addresses = Vec::new();
+    for _ in 0..count {
+        ensure_size!(in: src, size: 4);
+        let length = cast_length!("addressLength", src.read_u32())?;
+        ensure_size!(in: src, size: length);
+        addresses.push(decode_unicode(src.read_slice(length)));
+    }
+
+    Ok(addresses)
+}
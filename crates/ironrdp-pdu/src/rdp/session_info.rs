@@ -1,3 +1,4 @@
+#[cfg(feature = "std")]
 use std::io;
 
 use ironrdp_core::{
@@ -17,8 +18,8 @@ mod logon_extended;
 mod logon_info;
 
 pub use self::logon_extended::{
-    LogonErrorNotificationData, LogonErrorNotificationDataErrorCode, LogonErrorNotificationType, LogonErrorsInfo,
-    LogonExFlags, LogonInfoExtended, ServerAutoReconnect,
+    ClientAutoReconnect, LogonErrorNotificationData, LogonErrorNotificationDataErrorCode, LogonErrorNotificationType,
+    LogonErrorsInfo, LogonExFlags, LogonInfoExtended, ServerAutoReconnect,
 };
 pub use self::logon_info::{LogonInfo, LogonInfoVersion1, LogonInfoVersion2};
 
@@ -119,6 +120,7 @@ pub enum InfoData {
 
 #[derive(Debug, Error)]
 pub enum SessionError {
+    #[cfg(feature = "std")]
     #[error("IO error")]
     IOError(#[from] io::Error),
     #[error("invalid save session info type value")]
@@ -3,6 +3,7 @@ use ironrdp_core::{
     cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, read_padding, Decode, DecodeResult, Encode,
     EncodeResult, ReadCursor, WriteCursor,
 };
+use md5::Digest as _;
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::{FromPrimitive, ToPrimitive};
 
@@ -71,7 +72,7 @@ impl<'de> Decode<'de> for LogonInfoExtended {
         ensure_fixed_part_size!(in: src);
 
         let _self_length = src.read_u16();
-        let present_fields_flags = LogonExFlags::from_bits_truncate(src.read_u32());
+        let present_fields_flags = LogonExFlags::from_bits_retain(src.read_u32());
 
         let auto_reconnect = if present_fields_flags.contains(LogonExFlags::AUTO_RECONNECT_COOKIE) {
             Some(ServerAutoReconnect::decode(src)?)
@@ -152,6 +153,109 @@ impl<'de> Decode<'de> for ServerAutoReconnect {
     }
 }
 
+/// \[MS-RDPBCGR\] 2.2.4.3 Client Auto-Reconnect Packet (ARC_CS_PRIVATE_PACKET)
+///
+/// Sent by a reconnecting client in the auto-reconnect cookie of the extended client info, proving that
+/// it received the random bits of the [`ServerAutoReconnect`] cookie of the session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientAutoReconnect {
+    pub logon_id: u32,
+    pub security_verifier: [u8; AUTO_RECONNECT_RANDOM_BITS_SIZE],
+}
+
+impl ClientAutoReconnect {
+    const NAME: &'static str = "ClientAutoReconnect";
+
+    const FIXED_PART_SIZE: usize = AUTO_RECONNECT_PACKET_SIZE;
+
+    /// Answers the cookie of the server, with the client random of the connection (\[MS-RDPBCGR\] 5.5).
+    ///
+    /// The client random is zeroed when the connection doesn't use Standard RDP Security.
+    pub fn new(cookie: &ServerAutoReconnect, client_random: &[u8]) -> Self {
+        Self {
+            logon_id: cookie.logon_id,
+            security_verifier: hmac_md5(&cookie.random_bits, client_random),
+        }
+    }
+
+    /// Returns `true` if the verifier was computed from the cookie of the server and the client random.
+    pub fn verify(&self, cookie: &ServerAutoReconnect, client_random: &[u8]) -> bool {
+        let expected = hmac_md5(&cookie.random_bits, client_random);
+
+        // Compared in constant time, so the verifier can't be guessed byte after byte.
+        let difference = expected
+            .iter()
+            .zip(self.security_verifier.iter())
+            .fold(0, |difference, (a, b)| difference | (a ^ b));
+
+        self.logon_id == cookie.logon_id && difference == 0
+    }
+}
+
+impl Encode for ClientAutoReconnect {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(AUTO_RECONNECT_PACKET_SIZE as u32);
+        dst.write_u32(AUTO_RECONNECT_VERSION_1);
+        dst.write_u32(self.logon_id);
+        dst.write_slice(self.security_verifier.as_ref());
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for ClientAutoReconnect {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let packet_length = src.read_u32();
+        if packet_length != AUTO_RECONNECT_PACKET_SIZE as u32 {
+            return Err(invalid_field_err!("cbLen", "invalid auto-reconnect packet size"));
+        }
+
+        let version = src.read_u32();
+        if version != AUTO_RECONNECT_VERSION_1 {
+            return Err(invalid_field_err!("version", "invalid auto-reconnect version"));
+        }
+
+        let logon_id = src.read_u32();
+        let security_verifier = src.read_array();
+
+        Ok(Self {
+            logon_id,
+            security_verifier,
+        })
+    }
+}
+
+/// HMAC-MD5 (RFC 2104), with a key shorter than the block size.
+fn hmac_md5(key: &[u8; AUTO_RECONNECT_RANDOM_BITS_SIZE], data: &[u8]) -> [u8; AUTO_RECONNECT_RANDOM_BITS_SIZE] {
+    const BLOCK_SIZE: usize = 64;
+
+    let mut inner_key = [0x36; BLOCK_SIZE];
+    let mut outer_key = [0x5C; BLOCK_SIZE];
+    for (i, byte) in key.iter().enumerate() {
+        inner_key[i] ^= byte;
+        outer_key[i] ^= byte;
+    }
+
+    let inner = md5::Md5::new().chain_update(inner_key).chain_update(data).finalize();
+    md5::Md5::new()
+        .chain_update(outer_key)
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
 /// TS_LOGON_ERRORS_INFO
 ///
 /// [Doc](https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-rdpbcgr/845eb789-6edf-453a-8b0e-c976823d1f72)
@@ -209,6 +313,7 @@ bitflags! {
     pub struct LogonExFlags: u32 {
         const AUTO_RECONNECT_COOKIE = 0x0000_0001;
         const LOGON_ERRORS = 0x0000_0002;
+        const _ = !0;
     }
 }
 
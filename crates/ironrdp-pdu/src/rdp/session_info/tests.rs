@@ -433,3 +433,50 @@ fn from_buffer_parsing_with_invalid_logon_error_type_fails() {
         res => panic!("Expected InvalidLogonErrorType error, got: {res:?}"),
     };
 }
+
+const CLIENT_AUTO_RECONNECT_BUFFER: [u8; 28] = [
+    0x1c, 0x00, 0x00, 0x00, // cbLen
+    0x01, 0x00, 0x00, 0x00, // version
+    0x02, 0x00, 0x00, 0x00, // logonId
+    0x92, 0x94, 0x72, 0x7a, 0x36, 0x38, 0xbb, 0x1c, 0x13, 0xf4, 0x8e, 0xf8, 0x15, 0x8b, 0xfc,
+    0x9d, // securityVerifier
+];
+
+const SERVER_AUTO_RECONNECT: ServerAutoReconnect = ServerAutoReconnect {
+    logon_id: 2,
+    random_bits: [0x0b; 16],
+};
+
+// The security verifier is the HMAC-MD5 of the first test case of RFC 2202.
+const CLIENT_RANDOM: &[u8] = b"Hi There";
+
+#[test]
+fn from_buffer_correctly_parses_client_auto_reconnect() {
+    let cookie = ClientAutoReconnect::new(&SERVER_AUTO_RECONNECT, CLIENT_RANDOM);
+
+    assert_eq!(cookie, decode(CLIENT_AUTO_RECONNECT_BUFFER.as_ref()).unwrap());
+}
+
+#[test]
+fn to_buffer_correctly_serializes_client_auto_reconnect() {
+    let cookie = ClientAutoReconnect::new(&SERVER_AUTO_RECONNECT, CLIENT_RANDOM);
+
+    assert_eq!(
+        CLIENT_AUTO_RECONNECT_BUFFER.as_ref(),
+        encode_vec(&cookie).unwrap().as_slice()
+    );
+}
+
+#[test]
+fn client_auto_reconnect_is_verified_with_the_server_cookie() {
+    let cookie = ClientAutoReconnect::new(&SERVER_AUTO_RECONNECT, CLIENT_RANDOM);
+    assert!(cookie.verify(&SERVER_AUTO_RECONNECT, CLIENT_RANDOM));
+
+    assert!(!cookie.verify(&SERVER_AUTO_RECONNECT, &[0; 32]));
+
+    let other_session = ServerAutoReconnect {
+        logon_id: 3,
+        ..SERVER_AUTO_RECONNECT
+    };
+    assert!(!cookie.verify(&other_session, CLIENT_RANDOM));
+}
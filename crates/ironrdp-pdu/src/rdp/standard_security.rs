@@ -0,0 +1,636 @@
+//! Standard RDP Security, the legacy encryption of the connections with RC4 when neither TLS nor CredSSP
+//! is negotiated (\[MS-RDPBCGR\] 5.3).
+//!
+//! The server sends its public key and a server random in the server security data of the basic
+//! settings, and the client replies with its client random encrypted with this key in a
+//! [`SecurityExchangePdu`]. Both parties then derive the session keys (\[MS-RDPBCGR\] 5.3.5) which encrypt
+//! and sign all the following PDUs (\[MS-RDPBCGR\] 5.3.6).
+//!
+//! The FIPS encryption method is not supported.
+
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use ironrdp_core::{
+    cast_length, decode, encode_vec, ensure_fixed_part_size, ensure_size, invalid_field_err, other_err, Decode,
+    DecodeResult, Encode, EncodeResult, ReadCursor, WriteCursor,
+};
+use md5::Digest as _;
+use num_bigint::BigUint;
+
+use crate::crypto::rc4::Rc4;
+use crate::fast_path::EncryptionFlags;
+use crate::gcc::EncryptionMethod;
+use crate::mcs::McsMessage;
+use crate::rdp::headers::{BasicSecurityHeader, BasicSecurityHeaderFlags};
+use crate::rdp::server_license::cert::{CertificateType, ProprietaryCertificate, RsaPublicKey};
+use crate::rdp::server_license::ServerCertificate;
+use crate::x224::X224;
+use crate::{per, Action};
+
+pub const CLIENT_RANDOM_LEN: usize = 32;
+pub const SERVER_RANDOM_LEN: usize = 32;
+/// Length of the MAC signature of the encrypted PDUs.
+pub const SIGNATURE_LEN: usize = 8;
+
+const RSA_PADDING_LEN: usize = 8;
+const PROPRIETARY_SIGNATURE_LEN: usize = 64;
+const MAX_FAST_PATH_LEN: usize = 0x7FFF;
+
+/// Number of PDUs encrypted or decrypted with a key before it is updated (\[MS-RDPBCGR\] 5.3.7).
+const KEY_UPDATE_INTERVAL: u32 = 4096;
+
+const SALT: [u8; 3] = [0xD1, 0x26, 0x9E];
+const PAD1: [u8; 40] = [0x36; 40];
+const PAD2: [u8; 48] = [0x5C; 48];
+
+/// \[MS-RDPBCGR\] 2.2.1.10.1 Security Exchange PDU Data (TS_SECURITY_PACKET)
+///
+/// Sent by the client with its client random, encrypted with the public key of the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityExchangePdu {
+    /// The client random encrypted with the public key of the server, in little-endian order and
+    /// followed by 8 bytes of padding.
+    pub encrypted_client_random: Vec<u8>,
+}
+
+impl SecurityExchangePdu {
+    const NAME: &'static str = "SecurityExchangePdu";
+
+    const FIXED_PART_SIZE: usize = BasicSecurityHeader::FIXED_PART_SIZE + 4 /* length */;
+
+    /// Encrypts the client random with the public key of the server certificate.
+    pub fn new(client_random: &[u8; CLIENT_RANDOM_LEN], public_key: &RsaPublicKey) -> Self {
+        let modulus = BigUint::from_bytes_le(&public_key.modulus);
+        let exponent = BigUint::from(public_key.public_exponent);
+
+        let mut encrypted_client_random = BigUint::from_bytes_le(client_random)
+            .modpow(&exponent, &modulus)
+            .to_bytes_le();
+        encrypted_client_random.resize(public_key.modulus.len(), 0);
+
+        Self {
+            encrypted_client_random,
+        }
+    }
+}
+
+impl Encode for SecurityExchangePdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        BasicSecurityHeader {
+            flags: BasicSecurityHeaderFlags::EXCHANGE_PKT,
+        }
+        .encode(dst)?;
+        dst.write_u32(cast_length!("length", self.encrypted_client_random.len())?);
+        dst.write_slice(&self.encrypted_client_random);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.encrypted_client_random.len()
+    }
+}
+
+impl<'de> Decode<'de> for SecurityExchangePdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let header = BasicSecurityHeader::decode(src)?;
+        if !header.flags.contains(BasicSecurityHeaderFlags::EXCHANGE_PKT) {
+            return Err(invalid_field_err!(
+                "securityHeader",
+                "expected a security exchange packet"
+            ));
+        }
+
+        let length = cast_length!("length", src.read_u32())?;
+        ensure_size!(in: src, size: length);
+        let encrypted_client_random = src.read_slice(length).into();
+
+        Ok(Self {
+            encrypted_client_random,
+        })
+    }
+}
+
+/// RSA key of a server using Standard RDP Security, with which the clients encrypt their client random.
+///
+/// The public key is sent to the clients in a proprietary certificate (\[MS-RDPBCGR\] 2.2.1.4.3.1.1),
+/// whose signature is made with the Terminal Services signing key (\[MS-RDPBCGR\] 5.3.3.1). Unless it is
+/// set with [`ServerSecurityKey::with_signature`], the signature is zeroed and only the clients not
+/// verifying it accept the certificate.
+#[derive(Clone)]
+pub struct ServerSecurityKey {
+    modulus: BigUint,
+    public_exponent: u32,
+    private_exponent: BigUint,
+    signature: Vec<u8>,
+}
+
+impl fmt::Debug for ServerSecurityKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerSecurityKey")
+            .field("modulus_bits", &self.modulus.bits())
+            .field("public_exponent", &self.public_exponent)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServerSecurityKey {
+    /// Creates the key from its modulus and private exponent, in big-endian order.
+    pub fn new(modulus: &[u8], public_exponent: u32, private_exponent: &[u8]) -> Self {
+        Self {
+            modulus: BigUint::from_bytes_be(modulus),
+            public_exponent,
+            private_exponent: BigUint::from_bytes_be(private_exponent),
+            signature: Vec::new(),
+        }
+    }
+
+    /// Reads a DER-encoded PKCS #1 RSA private key.
+    pub fn from_pkcs1_der(der: &[u8]) -> DecodeResult<Self> {
+        let key = pkcs1::RsaPrivateKey::try_from(der)
+            .map_err(|_| invalid_field_err!("RSAPrivateKey", "invalid PKCS #1 private key"))?;
+
+        let public_exponent = key.public_exponent.as_bytes();
+        if public_exponent.len() > 4 {
+            return Err(invalid_field_err!("publicExponent", "public exponent is too large"));
+        }
+        let public_exponent = public_exponent
+            .iter()
+            .fold(0u32, |exponent, &byte| (exponent << 8) | u32::from(byte));
+
+        Ok(Self::new(
+            key.modulus.as_bytes(),
+            public_exponent,
+            key.private_exponent.as_bytes(),
+        ))
+    }
+
+    /// Sets the signature of the proprietary certificate, made with the Terminal Services signing key.
+    #[must_use]
+    pub fn with_signature(mut self, signature: Vec<u8>) -> Self {
+        self.signature = signature;
+        self
+    }
+
+    /// Returns the public key, as sent in the proprietary certificate.
+    pub fn public_key(&self) -> RsaPublicKey {
+        let mut modulus = self.modulus.to_bytes_le();
+        modulus.resize(modulus.len() + RSA_PADDING_LEN, 0);
+
+        RsaPublicKey {
+            public_exponent: self.public_exponent,
+            modulus,
+        }
+    }
+
+    /// Returns the certificate sent to the clients in the server security data.
+    pub fn certificate(&self) -> ServerCertificate {
+        let signature = if self.signature.is_empty() {
+            vec![0; PROPRIETARY_SIGNATURE_LEN + RSA_PADDING_LEN]
+        } else {
+            self.signature.clone()
+        };
+
+        ServerCertificate {
+            issued_permanently: false,
+            certificate: CertificateType::Proprietary(ProprietaryCertificate {
+                public_key: self.public_key(),
+                signature,
+            }),
+        }
+    }
+
+    /// Decrypts the client random of a [`SecurityExchangePdu`].
+    pub fn decrypt_client_random(&self, encrypted_client_random: &[u8]) -> DecodeResult<[u8; CLIENT_RANDOM_LEN]> {
+        let encrypted = BigUint::from_bytes_le(encrypted_client_random);
+        if encrypted >= self.modulus {
+            return Err(invalid_field_err!(
+                "encryptedClientRandom",
+                "encrypted client random is larger than the modulus"
+            ));
+        }
+
+        let decrypted = encrypted.modpow(&self.private_exponent, &self.modulus).to_bytes_le();
+        if decrypted.len() > CLIENT_RANDOM_LEN {
+            return Err(invalid_field_err!(
+                "encryptedClientRandom",
+                "invalid client random length"
+            ));
+        }
+
+        let mut client_random = [0; CLIENT_RANDOM_LEN];
+        client_random[..decrypted.len()].copy_from_slice(&decrypted);
+
+        Ok(client_random)
+    }
+}
+
+/// Session keys of a connection using Standard RDP Security, encrypting and signing the PDUs.
+///
+/// The PDUs are encrypted and decrypted in the order they are sent and received, since each direction is
+/// a single RC4 stream.
+pub struct StandardSecurity {
+    method: EncryptionMethod,
+    mac_key: Vec<u8>,
+    encrypt: Cipher,
+    decrypt: Cipher,
+}
+
+impl fmt::Debug for StandardSecurity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StandardSecurity")
+            .field("method", &self.method)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StandardSecurity {
+    /// Returns the strongest encryption method supported by the client, if any.
+    pub fn negotiate(client_methods: EncryptionMethod) -> Option<EncryptionMethod> {
+        [
+            EncryptionMethod::BIT_128,
+            EncryptionMethod::BIT_56,
+            EncryptionMethod::BIT_40,
+        ]
+        .into_iter()
+        .find(|&method| client_methods.contains(method))
+    }
+
+    /// Derives the session keys of the server.
+    ///
+    /// Returns `None` if the encryption method is not supported.
+    pub fn server(
+        method: EncryptionMethod,
+        client_random: &[u8; CLIENT_RANDOM_LEN],
+        server_random: &[u8; SERVER_RANDOM_LEN],
+    ) -> Option<Self> {
+        Self::new(method, client_random, server_random, true)
+    }
+
+    /// Derives the session keys of the client.
+    ///
+    /// Returns `None` if the encryption method is not supported.
+    pub fn client(
+        method: EncryptionMethod,
+        client_random: &[u8; CLIENT_RANDOM_LEN],
+        server_random: &[u8; SERVER_RANDOM_LEN],
+    ) -> Option<Self> {
+        Self::new(method, client_random, server_random, false)
+    }
+
+    fn new(
+        method: EncryptionMethod,
+        client_random: &[u8; CLIENT_RANDOM_LEN],
+        server_random: &[u8; SERVER_RANDOM_LEN],
+        server: bool,
+    ) -> Option<Self> {
+        let key_len = key_len(method)?;
+
+        let pre_master_secret = [&client_random[..24], &server_random[..24]].concat();
+        let master_secret = [
+            salted_hash(&pre_master_secret, b"A", client_random, server_random),
+            salted_hash(&pre_master_secret, b"BB", client_random, server_random),
+            salted_hash(&pre_master_secret, b"CCC", client_random, server_random),
+        ]
+        .concat();
+        let session_key_blob = [
+            salted_hash(&master_secret, b"X", client_random, server_random),
+            salted_hash(&master_secret, b"YY", client_random, server_random),
+            salted_hash(&master_secret, b"ZZZ", client_random, server_random),
+        ]
+        .concat();
+
+        let mac_key = salt(method, session_key_blob[..key_len].to_vec());
+        let server_encrypt_key = salt(
+            method,
+            final_hash(&session_key_blob[16..32], client_random, server_random)[..key_len].to_vec(),
+        );
+        let server_decrypt_key = salt(
+            method,
+            final_hash(&session_key_blob[32..48], client_random, server_random)[..key_len].to_vec(),
+        );
+
+        let (encrypt_key, decrypt_key) = if server {
+            (server_encrypt_key, server_decrypt_key)
+        } else {
+            (server_decrypt_key, server_encrypt_key)
+        };
+
+        Some(Self {
+            method,
+            mac_key,
+            encrypt: Cipher::new(encrypt_key),
+            decrypt: Cipher::new(decrypt_key),
+        })
+    }
+
+    pub fn encryption_method(&self) -> EncryptionMethod {
+        self.method
+    }
+
+    /// Encrypts a frame, either a fast-path PDU or a X.224 PDU whose MCS data is then preceded by a
+    /// security header.
+    ///
+    /// The other MCS PDUs, such as the Disconnect Provider Ultimatum, are not encrypted.
+    pub fn encrypt_frame(&mut self, frame: &[u8]) -> EncodeResult<Vec<u8>> {
+        match frame.first().map(|&header| Action::from_fp_output_header(header)) {
+            Some(Ok(Action::FastPath)) => {
+                let (header, data) = split_fast_path(frame)
+                    .ok_or_else(|| invalid_field_err!("length", "invalid fast-path PDU length"))?;
+                let (signature, data) = self.encrypt(data);
+                let header = header | (EncryptionFlags::ENCRYPTED.bits() << 6);
+
+                fast_path_frame(header, &[&signature, &data])
+                    .ok_or_else(|| invalid_field_err!("length", "encrypted fast-path PDU is too big"))
+            }
+            Some(Ok(Action::X224)) => {
+                let message = decode::<X224<McsMessage<'_>>>(frame).map_err(|e| other_err!("X224", source: e))?;
+                let message = match message.0 {
+                    McsMessage::SendDataIndication(mut data) => {
+                        data.user_data = Cow::Owned(self.seal(&data.user_data));
+                        McsMessage::SendDataIndication(data)
+                    }
+                    McsMessage::SendDataRequest(mut data) => {
+                        data.user_data = Cow::Owned(self.seal(&data.user_data));
+                        McsMessage::SendDataRequest(data)
+                    }
+                    _ => return Ok(frame.to_vec()),
+                };
+
+                encode_vec(&X224(message))
+            }
+            _ => Err(invalid_field_err!("action", "invalid frame action")),
+        }
+    }
+
+    /// Decrypts a frame encrypted with [`StandardSecurity::encrypt_frame`], verifying its signature.
+    ///
+    /// The security header of the MCS data is removed, unless its flags describe the data, e.g. for the
+    /// Client Info PDU or the licensing PDUs, in which case a basic security header without the
+    /// encryption flags is kept.
+    pub fn decrypt_frame(&mut self, frame: &[u8]) -> DecodeResult<Vec<u8>> {
+        match frame.first().map(|&header| Action::from_fp_output_header(header)) {
+            Some(Ok(Action::FastPath)) => {
+                let (header, data) = split_fast_path(frame)
+                    .ok_or_else(|| invalid_field_err!("length", "invalid fast-path PDU length"))?;
+                let flags = EncryptionFlags::from_bits_truncate(header >> 6);
+                if !flags.contains(EncryptionFlags::ENCRYPTED) {
+                    return Ok(frame.to_vec());
+                }
+
+                ensure_size!(ctx: "fast-path PDU", in: data, size: SIGNATURE_LEN);
+                let (signature, data) = data.split_at(SIGNATURE_LEN);
+                let data = self.decrypt(signature, data, flags.contains(EncryptionFlags::SECURE_CHECKSUM))?;
+
+                fast_path_frame(header & 0x3F, &[&data])
+                    .ok_or_else(|| invalid_field_err!("length", "invalid fast-path PDU length"))
+            }
+            Some(Ok(Action::X224)) => {
+                let message = decode::<X224<McsMessage<'_>>>(frame)?;
+                let message = match message.0 {
+                    McsMessage::SendDataIndication(mut data) => {
+                        data.user_data = Cow::Owned(self.open(&data.user_data)?);
+                        McsMessage::SendDataIndication(data)
+                    }
+                    McsMessage::SendDataRequest(mut data) => {
+                        data.user_data = Cow::Owned(self.open(&data.user_data)?);
+                        McsMessage::SendDataRequest(data)
+                    }
+                    _ => return Ok(frame.to_vec()),
+                };
+
+                encode_vec(&X224(message)).map_err(|e| other_err!("X224", source: e))
+            }
+            _ => Err(invalid_field_err!("action", "invalid frame action")),
+        }
+    }
+
+    /// Encrypts the MCS data, preceded by a non-FIPS security header.
+    fn seal(&mut self, data: &[u8]) -> Vec<u8> {
+        let (signature, data) = self.encrypt(data);
+
+        let mut sealed = Vec::with_capacity(BasicSecurityHeader::FIXED_PART_SIZE + SIGNATURE_LEN + data.len());
+        sealed.extend_from_slice(&BasicSecurityHeaderFlags::ENCRYPT.bits().to_le_bytes());
+        sealed.extend_from_slice(&[0, 0]); // flagsHi
+        sealed.extend_from_slice(&signature);
+        sealed.extend_from_slice(&data);
+
+        sealed
+    }
+
+    fn open(&mut self, data: &[u8]) -> DecodeResult<Vec<u8>> {
+        let mut src = ReadCursor::new(data);
+        let flags = BasicSecurityHeader::decode(&mut src)?.flags;
+
+        let data = if flags.contains(BasicSecurityHeaderFlags::ENCRYPT) {
+            ensure_size!(ctx: "security header", in: src, size: SIGNATURE_LEN);
+            let signature = src.read_slice(SIGNATURE_LEN);
+            self.decrypt(
+                signature,
+                src.remaining(),
+                flags.contains(BasicSecurityHeaderFlags::SECURE_CHECKSUM),
+            )?
+        } else {
+            src.remaining().to_vec()
+        };
+
+        let flags = flags
+            - (BasicSecurityHeaderFlags::ENCRYPT
+                | BasicSecurityHeaderFlags::SECURE_CHECKSUM
+                | BasicSecurityHeaderFlags::RESET_SEQNO
+                | BasicSecurityHeaderFlags::IGNORE_SEQNO
+                | BasicSecurityHeaderFlags::FLAGSHI_VALID);
+        if flags.is_empty() {
+            return Ok(data);
+        }
+
+        let mut opened = Vec::with_capacity(BasicSecurityHeader::FIXED_PART_SIZE + data.len());
+        opened.extend_from_slice(&flags.bits().to_le_bytes());
+        opened.extend_from_slice(&[0, 0]); // flagsHi
+        opened.extend_from_slice(&data);
+
+        Ok(opened)
+    }
+
+    fn encrypt(&mut self, data: &[u8]) -> ([u8; SIGNATURE_LEN], Vec<u8>) {
+        let signature = self.signature(data, None);
+        let data = self.encrypt.process(self.method, data);
+
+        (signature, data)
+    }
+
+    fn decrypt(&mut self, signature: &[u8], data: &[u8], salted: bool) -> DecodeResult<Vec<u8>> {
+        let count = self.decrypt.count;
+        let data = self.decrypt.process(self.method, data);
+
+        if self.signature(&data, salted.then_some(count)) != signature {
+            return Err(invalid_field_err!("dataSignature", "invalid MAC signature"));
+        }
+
+        Ok(data)
+    }
+
+    /// Computes the MAC signature of the data (\[MS-RDPBCGR\] 5.3.6.1), salted with the number of PDUs
+    /// previously encrypted when the secure checksum is used.
+    fn signature(&self, data: &[u8], count: Option<u32>) -> [u8; SIGNATURE_LEN] {
+        let length = u32::try_from(data.len()).expect("the length of a PDU fits in 16 bits");
+
+        let mut sha1 = sha1::Sha1::new();
+        sha1.update(&self.mac_key);
+        sha1.update(PAD1);
+        sha1.update(length.to_le_bytes());
+        sha1.update(data);
+        if let Some(count) = count {
+            sha1.update(count.to_le_bytes());
+        }
+
+        let mut md5 = md5::Md5::new();
+        md5.update(&self.mac_key);
+        md5.update(PAD2);
+        md5.update(sha1.finalize());
+
+        let mut signature = [0; SIGNATURE_LEN];
+        signature.copy_from_slice(&md5.finalize()[..SIGNATURE_LEN]);
+        signature
+    }
+}
+
+/// RC4 stream of one direction of the connection.
+struct Cipher {
+    initial_key: Vec<u8>,
+    key: Vec<u8>,
+    rc4: Rc4,
+    /// Number of PDUs processed with the current key.
+    use_count: u32,
+    /// Number of PDUs processed since the start of the connection.
+    count: u32,
+}
+
+impl Cipher {
+    fn new(key: Vec<u8>) -> Self {
+        Self {
+            rc4: Rc4::new(&key),
+            initial_key: key.clone(),
+            key,
+            use_count: 0,
+            count: 0,
+        }
+    }
+
+    fn process(&mut self, method: EncryptionMethod, data: &[u8]) -> Vec<u8> {
+        if self.use_count == KEY_UPDATE_INTERVAL {
+            self.key = update_key(method, &self.initial_key, &self.key);
+            self.rc4 = Rc4::new(&self.key);
+            self.use_count = 0;
+        }
+
+        self.use_count += 1;
+        self.count = self.count.wrapping_add(1);
+
+        self.rc4.process(data)
+    }
+}
+
+fn key_len(method: EncryptionMethod) -> Option<usize> {
+    if method == EncryptionMethod::BIT_128 {
+        Some(16)
+    } else if method == EncryptionMethod::BIT_56 || method == EncryptionMethod::BIT_40 {
+        Some(8)
+    } else {
+        None
+    }
+}
+
+/// Replaces the first bytes of the 40-bit and 56-bit keys with the salt.
+fn salt(method: EncryptionMethod, mut key: Vec<u8>) -> Vec<u8> {
+    if method == EncryptionMethod::BIT_40 {
+        key[..3].copy_from_slice(&SALT);
+    } else if method == EncryptionMethod::BIT_56 {
+        key[..1].copy_from_slice(&SALT[..1]);
+    }
+
+    key
+}
+
+fn salted_hash(salt: &[u8], input: &[u8], client_random: &[u8], server_random: &[u8]) -> Vec<u8> {
+    let mut sha1 = sha1::Sha1::new();
+    sha1.update(input);
+    sha1.update(salt);
+    sha1.update(client_random);
+    sha1.update(server_random);
+
+    let mut md5 = md5::Md5::new();
+    md5.update(salt);
+    md5.update(sha1.finalize());
+
+    md5.finalize().to_vec()
+}
+
+fn final_hash(key: &[u8], client_random: &[u8], server_random: &[u8]) -> Vec<u8> {
+    let mut md5 = md5::Md5::new();
+    md5.update(key);
+    md5.update(client_random);
+    md5.update(server_random);
+
+    md5.finalize().to_vec()
+}
+
+/// Derives the next key of a RC4 stream (\[MS-RDPBCGR\] 5.3.7.1).
+fn update_key(method: EncryptionMethod, initial_key: &[u8], current_key: &[u8]) -> Vec<u8> {
+    let mut sha1 = sha1::Sha1::new();
+    sha1.update(initial_key);
+    sha1.update(PAD1);
+    sha1.update(current_key);
+
+    let mut md5 = md5::Md5::new();
+    md5.update(initial_key);
+    md5.update(PAD2);
+    md5.update(sha1.finalize());
+
+    let temp_key = &md5.finalize()[..current_key.len()];
+
+    salt(method, Rc4::new(temp_key).process(temp_key))
+}
+
+/// Splits a fast-path PDU into its header byte and its data, following the length.
+fn split_fast_path(frame: &[u8]) -> Option<(u8, &[u8])> {
+    let mut src = ReadCursor::new(frame.get(1..)?);
+    let (length, sizeof_length) = per::read_length(&mut src).ok()?;
+
+    let data = frame.get(1 + sizeof_length..usize::from(length))?;
+
+    Some((frame[0], data))
+}
+
+/// Builds a fast-path PDU from its header byte and its data, computing the length.
+fn fast_path_frame(header: u8, data: &[&[u8]]) -> Option<Vec<u8>> {
+    let data_len: usize = data.iter().map(|data| data.len()).sum();
+    let mut length = 1 /* header */ + 1 /* length */ + data_len;
+    if length > 0x7F {
+        length += 1;
+    }
+    if length > MAX_FAST_PATH_LEN {
+        return None;
+    }
+
+    let mut frame = vec![0; length];
+    let mut dst = WriteCursor::new(&mut frame);
+    dst.write_u8(header);
+    per::write_length(&mut dst, u16::try_from(length).ok()?);
+    for data in data {
+        dst.write_slice(data);
+    }
+
+    Some(frame)
+}
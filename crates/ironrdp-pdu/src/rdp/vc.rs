@@ -3,7 +3,9 @@ pub mod dvc;
 #[cfg(test)]
 mod tests;
 
-use std::{io, str};
+use core::str;
+#[cfg(feature = "std")]
+use std::io;
 
 use bitflags::bitflags;
 use ironrdp_core::{ensure_fixed_part_size, Decode, DecodeResult, Encode, EncodeResult, ReadCursor, WriteCursor};
@@ -79,10 +81,11 @@ bitflags! {
 
 #[derive(Debug, Error)]
 pub enum ChannelError {
+    #[cfg(feature = "std")]
     #[error("IO error")]
     IOError(#[from] io::Error),
     #[error("from UTF-8 error")]
-    FromUtf8Error(#[from] std::string::FromUtf8Error),
+    FromUtf8Error(#[from] alloc::string::FromUtf8Error),
     #[error("invalid channel PDU header")]
     InvalidChannelPduHeader,
     #[error("invalid channel total data length")]
@@ -109,6 +112,7 @@ impl From<PduError> for ChannelError {
     }
 }
 
+#[cfg(feature = "std")]
 impl From<ChannelError> for io::Error {
     fn from(e: ChannelError) -> io::Error {
         io::Error::new(io::ErrorKind::Other, format!("Virtual channel error: {e}"))
@@ -1,11 +1,12 @@
 mod graphics_messages;
 
 pub use graphics_messages::{
-    Avc420BitmapStream, Avc444BitmapStream, CacheImportReplyPdu, CacheToSurfacePdu, CapabilitiesAdvertisePdu,
-    CapabilitiesConfirmPdu, CapabilitiesV103Flags, CapabilitiesV104Flags, CapabilitiesV107Flags, CapabilitiesV10Flags,
-    CapabilitiesV81Flags, CapabilitiesV8Flags, CapabilitySet, Codec1Type, Codec2Type, Color, CreateSurfacePdu,
-    DeleteEncodingContextPdu, DeleteSurfacePdu, Encoding, EndFramePdu, EvictCacheEntryPdu, FrameAcknowledgePdu,
-    MapSurfaceToOutputPdu, MapSurfaceToScaledOutputPdu, MapSurfaceToScaledWindowPdu, PixelFormat, Point, QuantQuality,
+    Avc420BitmapStream, Avc444BitmapStream, CacheEntryMetadata, CacheImportOfferPdu, CacheImportReplyPdu,
+    CacheToSurfacePdu, CapabilitiesAdvertisePdu, CapabilitiesConfirmPdu, CapabilitiesV103Flags, CapabilitiesV104Flags,
+    CapabilitiesV107Flags, CapabilitiesV10Flags, CapabilitiesV81Flags, CapabilitiesV8Flags, CapabilitySet, Codec1Type,
+    Codec2Type, Color, CreateSurfacePdu, DeleteEncodingContextPdu, DeleteSurfacePdu, Encoding, EndFramePdu,
+    EvictCacheEntryPdu, FrameAcknowledgePdu, MapSurfaceToOutputPdu, MapSurfaceToScaledOutputPdu,
+    MapSurfaceToScaledWindowPdu, MapSurfaceToWindowPdu, PixelFormat, Point, QoeFrameAcknowledgePdu, QuantQuality,
     QueueDepth, ResetGraphicsPdu, SolidFillPdu, StartFramePdu, SurfaceToCachePdu, SurfaceToSurfacePdu, Timestamp,
     WireToSurface1Pdu, WireToSurface2Pdu,
 };
@@ -36,6 +37,7 @@ pub enum ServerPdu {
     CacheImportReply(CacheImportReplyPdu),
     MapSurfaceToScaledOutput(MapSurfaceToScaledOutputPdu),
     MapSurfaceToScaledWindow(MapSurfaceToScaledWindowPdu),
+    MapSurfaceToWindow(MapSurfaceToWindowPdu),
 }
 
 const RDP_GFX_HEADER_SIZE: usize = 2 /* PduType */ + 2 /* flags */ + 4 /* bufferLen */;
@@ -70,6 +72,7 @@ impl Encode for ServerPdu {
             ServerPdu::MapSurfaceToOutput(pdu) => pdu.encode(dst),
             ServerPdu::MapSurfaceToScaledOutput(pdu) => pdu.encode(dst),
             ServerPdu::MapSurfaceToScaledWindow(pdu) => pdu.encode(dst),
+            ServerPdu::MapSurfaceToWindow(pdu) => pdu.encode(dst),
             ServerPdu::StartFrame(pdu) => pdu.encode(dst),
             ServerPdu::EndFrame(pdu) => pdu.encode(dst),
             ServerPdu::EvictCacheEntry(pdu) => pdu.encode(dst),
@@ -98,6 +101,7 @@ impl Encode for ServerPdu {
                 ServerPdu::MapSurfaceToOutput(pdu) => pdu.size(),
                 ServerPdu::MapSurfaceToScaledOutput(pdu) => pdu.size(),
                 ServerPdu::MapSurfaceToScaledWindow(pdu) => pdu.size(),
+                ServerPdu::MapSurfaceToWindow(pdu) => pdu.size(),
                 ServerPdu::StartFrame(pdu) => pdu.size(),
                 ServerPdu::EndFrame(pdu) => pdu.size(),
                 ServerPdu::EvictCacheEntry(pdu) => pdu.size(),
@@ -144,7 +148,7 @@ impl<'a> Decode<'a> for ServerPdu {
                 ServerPduType::MapSurfaceToScaledWindow => {
                     ServerPdu::MapSurfaceToScaledWindow(MapSurfaceToScaledWindowPdu::decode(src)?)
                 }
-                _ => return Err(invalid_field_err!("pduType", "invalid pdu type")),
+                ServerPduType::MapSurfaceToWindow => ServerPdu::MapSurfaceToWindow(MapSurfaceToWindowPdu::decode(src)?),
             };
             let buffer_length = pdu.size();
 
@@ -163,6 +167,8 @@ impl<'a> Decode<'a> for ServerPdu {
 pub enum ClientPdu {
     FrameAcknowledge(FrameAcknowledgePdu),
     CapabilitiesAdvertise(CapabilitiesAdvertisePdu),
+    CacheImportOffer(CacheImportOfferPdu),
+    QoeFrameAcknowledge(QoeFrameAcknowledgePdu),
 }
 
 impl ClientPdu {
@@ -182,6 +188,8 @@ impl Encode for ClientPdu {
         match self {
             ClientPdu::FrameAcknowledge(pdu) => pdu.encode(dst),
             ClientPdu::CapabilitiesAdvertise(pdu) => pdu.encode(dst),
+            ClientPdu::CacheImportOffer(pdu) => pdu.encode(dst),
+            ClientPdu::QoeFrameAcknowledge(pdu) => pdu.encode(dst),
         }
     }
 
@@ -194,12 +202,16 @@ impl Encode for ClientPdu {
             + match self {
                 ClientPdu::FrameAcknowledge(pdu) => pdu.size(),
                 ClientPdu::CapabilitiesAdvertise(pdu) => pdu.size(),
+                ClientPdu::CacheImportOffer(pdu) => pdu.size(),
+                ClientPdu::QoeFrameAcknowledge(pdu) => pdu.size(),
             }
     }
 }
 
 impl<'a> Decode<'a> for ClientPdu {
     fn decode(src: &mut ReadCursor<'a>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
         let pdu_type = ClientPduType::from_u16(src.read_u16())
             .ok_or_else(|| invalid_field_err!("clientPduType", "invalid pdu type"))?;
         let _flags = src.read_u16();
@@ -210,7 +222,8 @@ impl<'a> Decode<'a> for ClientPdu {
             ClientPduType::CapabilitiesAdvertise => {
                 ClientPdu::CapabilitiesAdvertise(CapabilitiesAdvertisePdu::decode(src)?)
             }
-            _ => return Err(invalid_field_err!("pduType", "invalid pdu type")),
+            ClientPduType::CacheImportOffer => ClientPdu::CacheImportOffer(CacheImportOfferPdu::decode(src)?),
+            ClientPduType::QoeFrameAcknowledge => ClientPdu::QoeFrameAcknowledge(QoeFrameAcknowledgePdu::decode(src)?),
         };
 
         if client_pdu.size() != pdu_length {
@@ -234,6 +247,8 @@ impl<'a> From<&'a ClientPdu> for ClientPduType {
         match c {
             ClientPdu::FrameAcknowledge(_) => Self::FrameAcknowledge,
             ClientPdu::CapabilitiesAdvertise(_) => Self::CapabilitiesAdvertise,
+            ClientPdu::CacheImportOffer(_) => Self::CacheImportOffer,
+            ClientPdu::QoeFrameAcknowledge(_) => Self::QoeFrameAcknowledge,
         }
     }
 }
@@ -280,6 +295,7 @@ impl<'a> From<&'a ServerPdu> for ServerPduType {
             ServerPdu::MapSurfaceToOutput(_) => Self::MapSurfaceToOutput,
             ServerPdu::MapSurfaceToScaledOutput(_) => Self::MapSurfaceToScaledOutput,
             ServerPdu::MapSurfaceToScaledWindow(_) => Self::MapSurfaceToScaledWindow,
+            ServerPdu::MapSurfaceToWindow(_) => Self::MapSurfaceToWindow,
             ServerPdu::CapabilitiesConfirm(_) => Self::CapabilitiesConfirm,
             ServerPdu::CacheImportReply(_) => Self::CacheImportReply,
         }
@@ -1,10 +1,15 @@
+use alloc::vec::Vec;
+
 use ironrdp_core::{
-    cast_length, ensure_fixed_part_size, ensure_size, Decode, DecodeResult, Encode, EncodeResult, ReadCursor,
-    WriteCursor,
+    cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult,
+    ReadCursor, WriteCursor,
 };
 
 use super::CapabilitySet;
 
+/// Maximum number of entries of the cache import offer and reply PDUs.
+const MAX_CACHE_ENTRIES: usize = 5462;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CapabilitiesAdvertisePdu(pub Vec<CapabilitySet>);
 
@@ -138,14 +143,179 @@ impl<'a> Decode<'a> for CacheImportReplyPdu {
     fn decode(src: &mut ReadCursor<'a>) -> DecodeResult<Self> {
         ensure_fixed_part_size!(in: src);
 
-        let entries_count = src.read_u16();
+        let entries_count = usize::from(src.read_u16());
+        if entries_count > MAX_CACHE_ENTRIES {
+            return Err(invalid_field_err!("importedEntriesCount", "too many cache entries"));
+        }
 
+        ensure_size!(in: src, size: entries_count * 2);
         let cache_slots = (0..entries_count).map(|_| src.read_u16()).collect();
 
         Ok(Self { cache_slots })
     }
 }
 
+/// Cache entries persisted by the client from previous sessions, offered to the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheImportOfferPdu {
+    pub cache_entries: Vec<CacheEntryMetadata>,
+}
+
+impl CacheImportOfferPdu {
+    const NAME: &'static str = "CacheImportOfferPdu";
+
+    const FIXED_PART_SIZE: usize = 2 /* cacheEntriesCount */;
+}
+
+impl Encode for CacheImportOfferPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        if self.cache_entries.len() > MAX_CACHE_ENTRIES {
+            return Err(invalid_field_err!("cacheEntriesCount", "too many cache entries"));
+        }
+
+        dst.write_u16(cast_length!("cacheEntriesCount", self.cache_entries.len())?);
+
+        for cache_entry in self.cache_entries.iter() {
+            cache_entry.encode(dst)?;
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.cache_entries.len() * CacheEntryMetadata::FIXED_PART_SIZE
+    }
+}
+
+impl<'a> Decode<'a> for CacheImportOfferPdu {
+    fn decode(src: &mut ReadCursor<'a>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let entries_count = usize::from(src.read_u16());
+        if entries_count > MAX_CACHE_ENTRIES {
+            return Err(invalid_field_err!("cacheEntriesCount", "too many cache entries"));
+        }
+
+        ensure_size!(in: src, size: entries_count * CacheEntryMetadata::FIXED_PART_SIZE);
+        let cache_entries = (0..entries_count)
+            .map(|_| CacheEntryMetadata::decode(src))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { cache_entries })
+    }
+}
+
+/// Identifies a bitmap of the persistent cache of the client.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CacheEntryMetadata {
+    /// 64-bit hash of the bitmap.
+    pub cache_key: u64,
+    /// Size of the bitmap, in bytes.
+    pub bitmap_length: u32,
+}
+
+impl CacheEntryMetadata {
+    const NAME: &'static str = "CacheEntryMetadata";
+
+    const FIXED_PART_SIZE: usize = 8 /* cacheKey */ + 4 /* bitmapLength */;
+}
+
+impl Encode for CacheEntryMetadata {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u64(self.cache_key);
+        dst.write_u32(self.bitmap_length);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'a> Decode<'a> for CacheEntryMetadata {
+    fn decode(src: &mut ReadCursor<'a>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let cache_key = src.read_u64();
+        let bitmap_length = src.read_u32();
+
+        Ok(Self {
+            cache_key,
+            bitmap_length,
+        })
+    }
+}
+
+/// Frame acknowledgement carrying the Quality of Experience metrics of the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QoeFrameAcknowledgePdu {
+    pub frame_id: u32,
+    /// Time at which the client started decoding the frame, in milliseconds.
+    pub timestamp: u32,
+    /// Time elapsed from the start to the end of the decoding, in milliseconds.
+    pub time_diff_se: u16,
+    /// Time elapsed from the end of the decoding to the end of the rendering, in milliseconds.
+    pub time_diff_edr: u16,
+}
+
+impl QoeFrameAcknowledgePdu {
+    const NAME: &'static str = "QoeFrameAcknowledgePdu";
+
+    const FIXED_PART_SIZE: usize = 4 /* frameId */ + 4 /* timestamp */ + 2 /* timeDiffSE */ + 2 /* timeDiffEDR */;
+}
+
+impl Encode for QoeFrameAcknowledgePdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.frame_id);
+        dst.write_u32(self.timestamp);
+        dst.write_u16(self.time_diff_se);
+        dst.write_u16(self.time_diff_edr);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'a> Decode<'a> for QoeFrameAcknowledgePdu {
+    fn decode(src: &mut ReadCursor<'a>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let frame_id = src.read_u32();
+        let timestamp = src.read_u32();
+        let time_diff_se = src.read_u16();
+        let time_diff_edr = src.read_u16();
+
+        Ok(Self {
+            frame_id,
+            timestamp,
+            time_diff_se,
+            time_diff_edr,
+        })
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum QueueDepth {
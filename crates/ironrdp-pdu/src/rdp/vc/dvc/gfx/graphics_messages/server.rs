@@ -1,4 +1,5 @@
-use std::fmt;
+use alloc::vec::Vec;
+use core::fmt;
 
 use bit_field::BitField;
 use ironrdp_core::{
@@ -722,6 +723,60 @@ impl<'a> Decode<'a> for MapSurfaceToScaledOutputPdu {
     }
 }
 
+/// Maps a surface to a RAIL window, sized to its client area.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapSurfaceToWindowPdu {
+    pub surface_id: u16,
+    pub window_id: u64,
+    pub mapped_width: u32,
+    pub mapped_height: u32,
+}
+
+impl MapSurfaceToWindowPdu {
+    const NAME: &'static str = "MapSurfaceToWindowPdu";
+
+    const FIXED_PART_SIZE: usize = 2 /* SurfaceId */ + 8 /* WindowId */ + 4 /* MappedWidth */ + 4 /* MappedHeight */;
+}
+
+impl Encode for MapSurfaceToWindowPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u16(self.surface_id);
+        dst.write_u64(self.window_id);
+        dst.write_u32(self.mapped_width);
+        dst.write_u32(self.mapped_height);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'a> Decode<'a> for MapSurfaceToWindowPdu {
+    fn decode(src: &mut ReadCursor<'a>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let surface_id = src.read_u16();
+        let window_id = src.read_u64();
+        let mapped_width = src.read_u32();
+        let mapped_height = src.read_u32();
+
+        Ok(Self {
+            surface_id,
+            window_id,
+            mapped_width,
+            mapped_height,
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MapSurfaceToScaledWindowPdu {
     pub surface_id: u16,
@@ -1,8 +1,9 @@
+use alloc::string::String;
+use alloc::vec::Vec;
 use core::fmt::Debug;
 use core::mem::size_of;
 use core::ops::Add;
 
-use byteorder::{LittleEndian, ReadBytesExt as _};
 use ironrdp_core::{ensure_size, invalid_field_err, other_err, ReadCursor, WriteCursor};
 use num_derive::{FromPrimitive, ToPrimitive};
 
@@ -31,11 +32,11 @@ pub fn to_utf16_bytes(value: &str) -> Vec<u8> {
         .collect::<Vec<u8>>()
 }
 
-pub fn from_utf16_bytes(mut value: &[u8]) -> String {
-    let mut value_u16 = vec![0x00; value.len() / 2];
-    value
-        .read_u16_into::<LittleEndian>(value_u16.as_mut())
-        .expect("read_u16_into cannot fail at this point");
+pub fn from_utf16_bytes(value: &[u8]) -> String {
+    let value_u16 = value
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect::<Vec<u16>>();
 
     String::from_utf16_lossy(value_u16.as_ref())
 }
@@ -92,14 +93,12 @@ pub fn read_string_from_cursor(
     let result = match character_set {
         CharacterSet::Unicode => {
             ensure_size!(ctx: "Decode string (UTF-16)", in: cursor, size: size);
-            let mut slice = cursor.read_slice(size);
-
-            let str_buffer = &mut slice;
-            let mut u16_buffer = vec![0u16; str_buffer.len() / 2];
+            let slice = cursor.read_slice(size);
 
-            str_buffer
-                .read_u16_into::<LittleEndian>(u16_buffer.as_mut())
-                .expect("BUG: str_buffer is always even for UTF16");
+            let u16_buffer = slice
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect::<Vec<u16>>();
 
             String::from_utf16(&u16_buffer)
                 .map_err(|_| invalid_field_err!("UTF16 decode", "buffer", "Failed to decode UTF16 string"))?
@@ -0,0 +1,119 @@
+use std::net::SocketAddr;
+
+use ironrdp_pdu::input::fast_path::{FastPathInput, FastPathInputEvent};
+
+/// Direction of a PDU relayed by the proxy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    ClientToServer,
+    ServerToClient,
+}
+
+/// Decision of a hook about a relayed PDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Forward,
+    Drop,
+}
+
+/// Connection relayed by the proxy.
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    pub client_addr: SocketAddr,
+    /// Name of the user authenticated by the proxy.
+    pub username: Option<String>,
+    /// Name of the client computer, once received.
+    pub client_name: Option<String>,
+}
+
+/// Hooks inspecting and filtering the PDUs relayed by the proxy.
+///
+/// The PDUs of the connection sequence are always forwarded, the hooks are only consulted for the input and the
+/// virtual channel data. When several hooks are installed, a PDU is dropped as soon as one of them drops it.
+pub trait ProxyHooks: Send + Sync {
+    /// Called once the client is authenticated and the connection to the server established.
+    fn on_connected(&self, _connection: &ConnectionInfo) {}
+
+    /// Called with the fast-path input sent by the client.
+    fn on_fastpath_input(&self, _connection: &ConnectionInfo, _input: &FastPathInput) -> Verdict {
+        Verdict::Forward
+    }
+
+    /// Called with the data sent on a static virtual channel (e.g. `cliprdr`, `rdpdr` or `drdynvc`).
+    ///
+    /// The data is a chunk of a virtual channel PDU, starting with its Channel PDU Header.
+    fn on_channel_data(
+        &self,
+        _connection: &ConnectionInfo,
+        _direction: Direction,
+        _channel: &str,
+        _data: &[u8],
+    ) -> Verdict {
+        Verdict::Forward
+    }
+
+    /// Called once the connection is closed.
+    fn on_disconnected(&self, _connection: &ConnectionInfo) {}
+}
+
+/// Drops all the data of the given static virtual channels, e.g. `cliprdr` to block the clipboard, or `rdpdr` to
+/// block the drive and device redirection.
+///
+/// The channels are still joined, but never initialized since none of their PDUs is relayed.
+#[derive(Debug, Clone)]
+pub struct BlockChannels {
+    channels: Vec<String>,
+}
+
+impl BlockChannels {
+    pub fn new(channels: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            channels: channels.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl ProxyHooks for BlockChannels {
+    fn on_channel_data(&self, connection: &ConnectionInfo, direction: Direction, channel: &str, _: &[u8]) -> Verdict {
+        if self
+            .channels
+            .iter()
+            .any(|blocked| blocked.eq_ignore_ascii_case(channel))
+        {
+            trace!(client_addr = %connection.client_addr, channel, ?direction, "Blocked channel data");
+            Verdict::Drop
+        } else {
+            Verdict::Forward
+        }
+    }
+}
+
+/// Logs the keyboard input of the client.
+///
+/// The mouse moves, which are numerous, are only logged at the TRACE level.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogInput;
+
+impl ProxyHooks for LogInput {
+    fn on_fastpath_input(&self, connection: &ConnectionInfo, input: &FastPathInput) -> Verdict {
+        for event in &input.0 {
+            match event {
+                FastPathInputEvent::MouseEvent(_)
+                | FastPathInputEvent::MouseEventEx(_)
+                | FastPathInputEvent::MouseEventRel(_) => {
+                    trace!(client_addr = %connection.client_addr, ?event, "Input");
+                }
+                _ => {
+                    info!(
+                        client_addr = %connection.client_addr,
+                        username = connection.username.as_deref(),
+                        ?event,
+                        "Input"
+                    );
+                }
+            }
+        }
+
+        Verdict::Forward
+    }
+}
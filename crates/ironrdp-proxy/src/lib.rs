@@ -0,0 +1,265 @@
+#![doc = include_str!("../README.md")]
+#![doc(html_logo_url = "https://cdnweb.devolutions.net/images/projects/devolutions/logos/devolutions-icon-shadow.svg")]
+
+#[macro_use]
+extern crate tracing;
+
+mod hooks;
+mod relay;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _};
+use ironrdp_acceptor::{Acceptor, BeginResult, CredentialsValidator, DesktopSize};
+use ironrdp_connector::credssp::ChannelBindings;
+use ironrdp_connector::{ClientConnector, ClientConnectorState, State as _};
+use ironrdp_pdu::gcc::KeyboardType;
+use ironrdp_pdu::nego::SecurityProtocol;
+use ironrdp_pdu::rdp::capability_sets::MajorPlatformType;
+use ironrdp_pdu::rdp::client_info::{self, PerformanceFlags};
+use ironrdp_tls::{DefaultTlsConnector, TlsConnector as _};
+use ironrdp_tokio::reqwest::ReqwestNetworkClient;
+use ironrdp_tokio::TokioFramed;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::TlsAcceptor;
+
+pub use self::hooks::{BlockChannels, ConnectionInfo, Direction, LogInput, ProxyHooks, Verdict};
+use self::relay::Inspector;
+
+/// Configuration of the proxy.
+pub struct ProxyConfig {
+    /// TLS acceptor for the connections of the clients.
+    pub tls_acceptor: TlsAcceptor,
+    /// Public key of the certificate of the TLS acceptor, verified by the clients during the CredSSP authentication.
+    pub tls_public_key: Vec<u8>,
+    /// Validates the credentials of the clients, which are authenticated by the proxy with NLA.
+    pub credentials: Arc<dyn CredentialsValidator>,
+    pub upstream: UpstreamConfig,
+    pub hooks: Vec<Arc<dyn ProxyHooks>>,
+}
+
+/// The server to which the proxy relays the connections.
+#[derive(Clone)]
+pub struct UpstreamConfig {
+    /// Address of the server, as `host:port`.
+    pub addr: String,
+    /// Name of the server, used for the TLS handshake and the CredSSP authentication.
+    pub server_name: String,
+    /// Credentials the proxy authenticates with, in place of the ones of the clients.
+    pub username: String,
+    pub password: String,
+    pub domain: Option<String>,
+    /// DER-encoded certificate expected from the server.
+    ///
+    /// If `None`, the certificate of the server is not verified, which is only acceptable if the network between the
+    /// proxy and the server is trusted.
+    pub certificate: Option<Vec<u8>>,
+}
+
+/// RDP proxy authenticating the clients, then relaying their connection to a server.
+///
+/// The clients are authenticated by the proxy, and the proxy is authenticated by the server with its own credentials,
+/// so that the clients never know the credentials of the server. Once both sides are authenticated, the PDUs are
+/// relayed as is, except for the few of the connection sequence referring to the security and the credentials, and
+/// the input and virtual channel data go through the hooks.
+#[derive(Clone)]
+pub struct RdpProxy {
+    config: Arc<ProxyConfig>,
+}
+
+impl RdpProxy {
+    pub fn new(config: ProxyConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+        }
+    }
+
+    /// Accepts the connections of the clients, each relayed in its own task.
+    pub async fn run(&self, listener: TcpListener) -> anyhow::Result<()> {
+        info!(addr = ?listener.local_addr(), "Proxy listening");
+
+        loop {
+            let (stream, client_addr) = listener.accept().await.context("accept connection")?;
+
+            let proxy = self.clone();
+            tokio::spawn(async move {
+                if let Err(error) = proxy.run_connection(stream, client_addr).await {
+                    error!(%client_addr, ?error, "Connection error");
+                }
+            });
+        }
+    }
+
+    /// Relays the connection of a client, until it is closed by either side.
+    #[instrument(skip(self, stream))]
+    pub async fn run_connection(&self, stream: TcpStream, client_addr: SocketAddr) -> anyhow::Result<()> {
+        let mut acceptor = Acceptor::new(
+            SecurityProtocol::HYBRID | SecurityProtocol::HYBRID_EX,
+            DesktopSize { width: 0, height: 0 },
+            Vec::new(),
+            None,
+        );
+        acceptor.set_credentials_validator(Arc::clone(&self.config.credentials));
+
+        let BeginResult::ShouldUpgrade(stream) =
+            ironrdp_acceptor::accept_begin(TokioFramed::new(stream), &mut acceptor)
+                .await
+                .context("accept begin")?
+        else {
+            bail!("client did not negotiate NLA");
+        };
+
+        let stream = self.config.tls_acceptor.accept(stream).await.context("TLS accept")?;
+        acceptor.mark_security_upgrade_as_done();
+
+        let mut client = TokioFramed::new(stream);
+
+        ironrdp_acceptor::accept_credssp(
+            &mut client,
+            &mut acceptor,
+            client_addr.ip().to_string().into(),
+            self.config.tls_public_key.clone(),
+            None,
+        )
+        .await
+        .context("authenticate client")?;
+
+        let requested_protocol = acceptor
+            .requested_protocol()
+            .context("no protocol requested by the client")?;
+
+        let connection = ConnectionInfo {
+            client_addr,
+            username: acceptor
+                .nla_credentials()
+                .map(|credentials| credentials.username.clone()),
+            client_name: None,
+        };
+
+        info!(username = connection.username, "Client authenticated");
+
+        let (mut server, server_selected_protocol) = self.connect_upstream().await.context("connect upstream")?;
+
+        let upstream = &self.config.upstream;
+        let mut inspector = Inspector::new(
+            self.config.hooks.clone(),
+            connection,
+            requested_protocol,
+            server_selected_protocol,
+            client_info::Credentials {
+                username: upstream.username.clone(),
+                password: upstream.password.clone(),
+                domain: upstream.domain.clone(),
+            },
+        );
+
+        for hook in &self.config.hooks {
+            hook.on_connected(inspector.connection());
+        }
+
+        let result = relay::relay(&mut client, &mut server, &mut inspector).await;
+
+        for hook in &self.config.hooks {
+            hook.on_disconnected(inspector.connection());
+        }
+
+        result
+    }
+
+    /// Connects and authenticates to the server, returning the protocol it selected.
+    async fn connect_upstream(
+        &self,
+    ) -> anyhow::Result<(TokioFramed<Box<dyn ironrdp_tls::TlsTransport>>, SecurityProtocol)> {
+        let upstream = &self.config.upstream;
+
+        let stream = TcpStream::connect(&upstream.addr).await.context("TCP connect")?;
+        let server_addr = stream.peer_addr().context("peer address")?;
+
+        let mut framed = TokioFramed::new(stream);
+        let mut connector = ClientConnector::new(connector_config(upstream)).with_client_addr(server_addr);
+
+        let should_upgrade = ironrdp_tokio::connect_begin(&mut framed, &mut connector).await?;
+
+        let (initial_stream, leftover_bytes) = framed.into_inner();
+
+        let tls_connection = DefaultTlsConnector::new()
+            .connect(Box::new(initial_stream), &upstream.server_name)
+            .await
+            .context("TLS upgrade")?;
+
+        if let Some(certificate) = &upstream.certificate {
+            if *certificate != tls_connection.server_certificate {
+                bail!("unexpected server certificate");
+            }
+        }
+
+        connector.attach_channel_bindings(ChannelBindings::tls_server_end_point(
+            &tls_connection.server_certificate,
+        )?);
+
+        let upgraded = ironrdp_tokio::mark_as_upgraded(should_upgrade, &mut connector);
+
+        let mut framed = TokioFramed::new_with_leftover(tls_connection.stream, leftover_bytes);
+
+        ironrdp_tokio::connect_credssp(
+            upgraded,
+            &mut framed,
+            &mut connector,
+            upstream.server_name.as_str().into(),
+            tls_connection.server_public_key,
+            Some(&mut ReqwestNetworkClient::new()),
+            None,
+        )
+        .await?;
+
+        let ClientConnectorState::BasicSettingsExchangeSendInitial { selected_protocol } = connector.state else {
+            bail!("unexpected connector state after CredSSP: {}", connector.state.name());
+        };
+
+        Ok((framed, selected_protocol))
+    }
+}
+
+/// The configuration of the connector only matters up to the CredSSP authentication, the rest of the connection
+/// sequence being relayed from the client.
+fn connector_config(upstream: &UpstreamConfig) -> ironrdp_connector::Config {
+    ironrdp_connector::Config {
+        credentials: ironrdp_connector::Credentials::UsernamePassword {
+            username: upstream.username.clone(),
+            password: upstream.password.clone(),
+        },
+        domain: upstream.domain.clone(),
+        remote_credential_guard: false,
+        enable_tls: true,
+        enable_credssp: true,
+        keyboard_type: KeyboardType::IbmEnhanced,
+        keyboard_subtype: 0,
+        keyboard_layout: 0,
+        keyboard_functional_keys_count: 12,
+        ime_file_name: String::new(),
+        dig_product_id: String::new(),
+        desktop_size: ironrdp_connector::DesktopSize {
+            width: 1024,
+            height: 768,
+        },
+        bitmap: None,
+        client_build: 0,
+        client_name: "ironrdp-proxy".to_owned(),
+        client_dir: String::new(),
+        platform: MajorPlatformType::UNSPECIFIED,
+        no_server_pointer: false,
+        pointer_software_rendering: false,
+        request_data: None,
+        autologon: false,
+        no_audio_playback: false,
+        message_channel: false,
+        multitransport_flags: None,
+        rail: false,
+        performance_flags: PerformanceFlags::default(),
+        desktop_scale_factor: 0,
+        monitors: Vec::new(),
+        hardware_id: None,
+        license_cache: None,
+    }
+}
@@ -0,0 +1,267 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use ironrdp_core::{decode, encode_vec, Encode};
+use ironrdp_pdu::input::fast_path::FastPathInput;
+use ironrdp_pdu::mcs::{self, McsMessage, SendDataIndication, SendDataRequest};
+use ironrdp_pdu::nego::SecurityProtocol;
+use ironrdp_pdu::rdp::client_info::{ClientInfoFlags, Credentials};
+use ironrdp_pdu::rdp::ClientInfoPdu;
+use ironrdp_pdu::x224::{X224Data, X224};
+use ironrdp_pdu::Action;
+use ironrdp_tokio::{Framed, FramedRead, FramedWrite};
+
+use crate::hooks::{ConnectionInfo, Direction, ProxyHooks, Verdict};
+
+/// Inspects the PDUs relayed once both sides are authenticated.
+///
+/// The client and the server each negotiated their own security protocol with the proxy, and the client its own
+/// credentials: the PDUs of the connection sequence referring to them are rewritten, everything else is relayed as
+/// is, unless dropped by the hooks.
+pub(crate) struct Inspector {
+    hooks: Vec<Arc<dyn ProxyHooks>>,
+    connection: ConnectionInfo,
+    /// Protocols requested by the client to the proxy.
+    client_requested_protocol: SecurityProtocol,
+    /// Protocol selected by the server for the proxy.
+    server_selected_protocol: SecurityProtocol,
+    upstream_credentials: Credentials,
+    /// Names of the static channels requested by the client, once its Connect Initial is received.
+    channel_names: Option<Vec<String>>,
+    /// Names of the static channels by MCS channel ID, once the Connect Response is received.
+    channels: HashMap<u16, String>,
+    io_channel_id: Option<u16>,
+    client_info_sent: bool,
+}
+
+impl Inspector {
+    pub(crate) fn new(
+        hooks: Vec<Arc<dyn ProxyHooks>>,
+        connection: ConnectionInfo,
+        client_requested_protocol: SecurityProtocol,
+        server_selected_protocol: SecurityProtocol,
+        upstream_credentials: Credentials,
+    ) -> Self {
+        Self {
+            hooks,
+            connection,
+            client_requested_protocol,
+            server_selected_protocol,
+            upstream_credentials,
+            channel_names: None,
+            channels: HashMap::new(),
+            io_channel_id: None,
+            client_info_sent: false,
+        }
+    }
+
+    pub(crate) fn connection(&self) -> &ConnectionInfo {
+        &self.connection
+    }
+
+    /// Returns the frame to forward to the server, if any.
+    pub(crate) fn client_frame<'a>(
+        &mut self,
+        action: Action,
+        frame: &'a [u8],
+    ) -> anyhow::Result<Option<Cow<'a, [u8]>>> {
+        match action {
+            Action::FastPath => match decode::<FastPathInput>(frame) {
+                Ok(input) => {
+                    if self.verdict(|hook| hook.on_fastpath_input(&self.connection, &input)) == Verdict::Drop {
+                        return Ok(None);
+                    }
+                }
+                Err(error) => debug!(%error, "Relaying undecoded fast-path input"),
+            },
+            Action::X224 if self.channel_names.is_none() => {
+                return self.connect_initial(frame).map(|frame| Some(Cow::Owned(frame)));
+            }
+            Action::X224 => match decode::<X224<McsMessage<'_>>>(frame) {
+                Ok(X224(McsMessage::SendDataRequest(request))) => return self.send_data_request(frame, request),
+                Ok(_) => {}
+                Err(error) => debug!(%error, "Relaying undecoded client PDU"),
+            },
+        }
+
+        Ok(Some(Cow::Borrowed(frame)))
+    }
+
+    /// Returns the frame to forward to the client, if any.
+    pub(crate) fn server_frame<'a>(
+        &mut self,
+        action: Action,
+        frame: &'a [u8],
+    ) -> anyhow::Result<Option<Cow<'a, [u8]>>> {
+        match action {
+            Action::FastPath => {}
+            Action::X224 if self.io_channel_id.is_none() => {
+                return self.connect_response(frame).map(|frame| Some(Cow::Owned(frame)));
+            }
+            Action::X224 => match decode::<X224<McsMessage<'_>>>(frame) {
+                Ok(X224(McsMessage::SendDataIndication(SendDataIndication {
+                    channel_id, user_data, ..
+                }))) => {
+                    if self.channel_verdict(Direction::ServerToClient, channel_id, &user_data) == Verdict::Drop {
+                        return Ok(None);
+                    }
+                }
+                Ok(_) => {}
+                Err(error) => debug!(%error, "Relaying undecoded server PDU"),
+            },
+        }
+
+        Ok(Some(Cow::Borrowed(frame)))
+    }
+
+    fn connect_initial(&mut self, frame: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let X224(payload) = decode::<X224<X224Data<'_>>>(frame).context("decode client X.224 data")?;
+        let mut connect_initial = decode::<mcs::ConnectInitial>(&payload.data).context("decode Connect Initial")?;
+
+        let core = &mut connect_initial.conference_create_request.gcc_blocks.core;
+
+        // The client announces the protocol selected by the proxy, the server expects the one it selected itself.
+        if let Some(protocol) = core.optional_data.server_selected_protocol.as_mut() {
+            *protocol = self.server_selected_protocol;
+        }
+
+        self.connection.client_name = Some(core.client_name.clone());
+        self.channel_names = Some(
+            connect_initial
+                .channel_names()
+                .unwrap_or_default()
+                .iter()
+                .map(|channel| channel.name.as_str().unwrap_or_default().to_owned())
+                .collect(),
+        );
+
+        debug!(channels = ?self.channel_names, "Relaying Connect Initial");
+
+        encode_x224_data(&connect_initial)
+    }
+
+    fn connect_response(&mut self, frame: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let X224(payload) = decode::<X224<X224Data<'_>>>(frame).context("decode server X.224 data")?;
+        let mut connect_response = decode::<mcs::ConnectResponse>(&payload.data).context("decode Connect Response")?;
+
+        let core = &mut connect_response.conference_create_response.gcc_blocks.core;
+
+        // Same as for the Connect Initial, the other way around.
+        if let Some(protocols) = core.optional_data.client_requested_protocols.as_mut() {
+            *protocols = self.client_requested_protocol;
+        }
+
+        let channel_names = self.channel_names.as_deref().unwrap_or_default();
+        self.channels = connect_response
+            .channel_ids()
+            .into_iter()
+            .zip(channel_names.iter().cloned())
+            .collect();
+        self.io_channel_id = Some(connect_response.global_channel_id());
+
+        debug!(channels = ?self.channels, "Relaying Connect Response");
+
+        encode_x224_data(&connect_response)
+    }
+
+    fn send_data_request<'a>(
+        &mut self,
+        frame: &'a [u8],
+        request: SendDataRequest<'_>,
+    ) -> anyhow::Result<Option<Cow<'a, [u8]>>> {
+        if !self.client_info_sent && Some(request.channel_id) == self.io_channel_id {
+            if let Ok(mut client_info) = decode::<ClientInfoPdu>(&request.user_data) {
+                // The client authenticated with its own credentials, the server is logged on with the upstream ones.
+                client_info.client_info.credentials = self.upstream_credentials.clone();
+                client_info.client_info.flags |= ClientInfoFlags::AUTOLOGON;
+                self.client_info_sent = true;
+
+                debug!("Relaying Client Info with the upstream credentials");
+
+                let request = SendDataRequest {
+                    user_data: Cow::Owned(encode_vec(&client_info).context("encode Client Info")?),
+                    ..request
+                };
+
+                return Ok(Some(Cow::Owned(
+                    encode_vec(&X224(request)).context("encode Send Data Request")?,
+                )));
+            }
+        }
+
+        if self.channel_verdict(Direction::ClientToServer, request.channel_id, &request.user_data) == Verdict::Drop {
+            return Ok(None);
+        }
+
+        Ok(Some(Cow::Borrowed(frame)))
+    }
+
+    fn channel_verdict(&self, direction: Direction, channel_id: u16, data: &[u8]) -> Verdict {
+        match self.channels.get(&channel_id) {
+            Some(channel) => self.verdict(|hook| hook.on_channel_data(&self.connection, direction, channel, data)),
+            None => Verdict::Forward,
+        }
+    }
+
+    fn verdict(&self, f: impl Fn(&dyn ProxyHooks) -> Verdict) -> Verdict {
+        if self.hooks.iter().any(|hook| f(hook.as_ref()) == Verdict::Drop) {
+            Verdict::Drop
+        } else {
+            Verdict::Forward
+        }
+    }
+}
+
+fn encode_x224_data<T: Encode>(pdu: &T) -> anyhow::Result<Vec<u8>> {
+    let data = encode_vec(pdu).context("encode X.224 data")?;
+    let frame = encode_vec(&X224(X224Data { data: Cow::Owned(data) })).context("encode X.224 frame")?;
+    Ok(frame)
+}
+
+/// Relays the frames between the client and the server until one of them closes the connection.
+pub(crate) async fn relay<C, S>(
+    client: &mut Framed<C>,
+    server: &mut Framed<S>,
+    inspector: &mut Inspector,
+) -> anyhow::Result<()>
+where
+    C: FramedRead + FramedWrite,
+    S: FramedRead + FramedWrite,
+{
+    loop {
+        // Reading a frame is cancel safe, the bytes already read are kept in the buffer of the framed stream.
+        tokio::select! {
+            frame = client.read_pdu() => {
+                let Some((action, frame)) = closed_on_eof(frame).context("read client frame")? else {
+                    debug!("Client closed the connection");
+                    return Ok(());
+                };
+
+                if let Some(frame) = inspector.client_frame(action, &frame)? {
+                    server.write_all(&frame).await.context("write server frame")?;
+                }
+            }
+            frame = server.read_pdu() => {
+                let Some((action, frame)) = closed_on_eof(frame).context("read server frame")? else {
+                    debug!("Server closed the connection");
+                    return Ok(());
+                };
+
+                if let Some(frame) = inspector.server_frame(action, &frame)? {
+                    client.write_all(&frame).await.context("write client frame")?;
+                }
+            }
+        }
+    }
+}
+
+fn closed_on_eof<T>(result: io::Result<T>) -> io::Result<Option<T>> {
+    match result {
+        Ok(frame) => Ok(Some(frame)),
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(error) => Err(error),
+    }
+}
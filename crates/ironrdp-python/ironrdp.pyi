@@ -0,0 +1,40 @@
+from typing import Callable, Optional
+
+import numpy as np
+import numpy.typing as npt
+
+class RdpError(Exception): ...
+class SessionTerminated(RdpError): ...
+
+def connect(
+    host: str,
+    username: str,
+    password: str,
+    *,
+    port: int = 3389,
+    domain: Optional[str] = None,
+    width: int = 1024,
+    height: int = 768,
+    keyboard_layout: int = 0x409,
+    client_name: str = "ironrdp-python",
+    verify_certificate: Optional[Callable[[bytes], bool]] = None,
+) -> Session: ...
+
+class Session:
+    @property
+    def desktop_size(self) -> tuple[int, int]: ...
+    @property
+    def terminated(self) -> bool: ...
+    def poll(self, timeout: Optional[float] = None) -> list[tuple[int, int, int, int]]: ...
+    def framebuffer(self) -> npt.NDArray[np.uint8]: ...
+    def key(self, scancode: int, pressed: bool) -> None: ...
+    def tap_key(self, scancode: int) -> None: ...
+    def type_text(self, text: str) -> None: ...
+    def mouse_move(self, x: int, y: int) -> None: ...
+    def mouse_button(self, button: int, pressed: bool) -> None: ...
+    def click(self, x: int, y: int, button: int = 0) -> None: ...
+    def wheel(self, units: int, vertical: bool = True) -> None: ...
+    def release_all(self) -> None: ...
+    def shutdown(self) -> None: ...
+    def __enter__(self) -> Session: ...
+    def __exit__(self, exc_type: object, exc_value: object, traceback: object) -> None: ...
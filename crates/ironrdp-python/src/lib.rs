@@ -0,0 +1,285 @@
+#![doc = include_str!("../README.md")]
+#![doc(html_logo_url = "https://cdnweb.devolutions.net/images/projects/devolutions/logos/devolutions-icon-shadow.svg")]
+
+#[macro_use]
+extern crate tracing;
+
+mod session;
+
+use core::time::Duration;
+use std::sync::{Mutex, MutexGuard};
+
+use ironrdp::connector::DesktopSize;
+use ironrdp::input::{MouseButton, MousePosition, Operation, Scancode, WheelRotations};
+use numpy::{PyArray1, PyArray3, PyArrayMethods as _};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::session::{ConnectOptions, Terminated};
+
+create_exception!(
+    ironrdp,
+    RdpError,
+    PyException,
+    "Error raised by an RDP connection or session."
+);
+create_exception!(
+    ironrdp,
+    SessionTerminated,
+    RdpError,
+    "Raised once the session is terminated by the server."
+);
+
+/// Connects to an RDP server, blocking until the session is active.
+///
+/// `verify_certificate` is called with the DER-encoded certificate of the server, before the credentials are
+/// sent, and returns whether the connection should proceed. Without it, any certificate is accepted.
+#[pyfunction]
+#[pyo3(signature = (
+    host,
+    username,
+    password,
+    *,
+    port = 3389,
+    domain = None,
+    width = 1024,
+    height = 768,
+    keyboard_layout = 0x409,
+    client_name = "ironrdp-python".to_owned(),
+    verify_certificate = None,
+))]
+#[expect(clippy::too_many_arguments, reason = "keyword arguments of the Python function")]
+fn connect(
+    py: Python<'_>,
+    host: String,
+    username: String,
+    password: String,
+    port: u16,
+    domain: Option<String>,
+    width: u16,
+    height: u16,
+    keyboard_layout: u32,
+    client_name: String,
+    verify_certificate: Option<Py<PyAny>>,
+) -> PyResult<Session> {
+    if width == 0 || height == 0 {
+        return Err(PyValueError::new_err("empty desktop size"));
+    }
+
+    let options = ConnectOptions {
+        host,
+        port,
+        username,
+        password,
+        domain,
+        desktop_size: DesktopSize { width, height },
+        keyboard_layout,
+        client_name,
+    };
+
+    let session = py
+        .detach(|| {
+            session::Session::connect(options, |certificate| match &verify_certificate {
+                Some(verify) => Python::attach(|py| {
+                    verify
+                        .call1(py, (PyBytes::new(py, certificate),))
+                        .and_then(|accepted| accepted.is_truthy(py))
+                        .unwrap_or_else(|error| {
+                            warn!(%error, "Certificate verification callback failed");
+                            false
+                        })
+                }),
+                None => true,
+            })
+        })
+        .map_err(to_py_err)?;
+
+    Ok(Session {
+        inner: Mutex::new(session),
+    })
+}
+
+/// An active RDP session.
+///
+/// The pointer is drawn in the framebuffer.
+#[pyclass(module = "ironrdp")]
+struct Session {
+    inner: Mutex<session::Session>,
+}
+
+#[pymethods]
+impl Session {
+    /// Processes the frames received from the server until the framebuffer is updated, or until `timeout`
+    /// seconds elapse, and returns the updated regions as `(x, y, width, height)` tuples.
+    ///
+    /// Raises `SessionTerminated` once the session is terminated.
+    #[pyo3(signature = (timeout = None))]
+    fn poll(&self, py: Python<'_>, timeout: Option<f64>) -> PyResult<Vec<(u16, u16, u16, u16)>> {
+        let timeout = timeout
+            .map(Duration::try_from_secs_f64)
+            .transpose()
+            .map_err(|e| PyValueError::new_err(format!("invalid timeout: {e}")))?;
+
+        let updates = py.detach(|| self.lock().poll(timeout)).map_err(to_py_err)?;
+
+        Ok(updates
+            .into_iter()
+            .map(|rect| (rect.x, rect.y, rect.width, rect.height))
+            .collect())
+    }
+
+    /// Returns a copy of the framebuffer, as a `(height, width, 4)` RGBA array of `uint8`.
+    fn framebuffer<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray3<u8>>> {
+        let session = self.lock();
+        let image = session.image();
+
+        PyArray1::from_slice(py, image.data()).reshape([
+            usize::from(image.height()),
+            usize::from(image.width()),
+            image.bytes_per_pixel(),
+        ])
+    }
+
+    /// The size of the desktop, as a `(width, height)` tuple.
+    #[getter]
+    fn desktop_size(&self) -> (u16, u16) {
+        let session = self.lock();
+        (session.image().width(), session.image().height())
+    }
+
+    #[getter]
+    fn terminated(&self) -> bool {
+        self.lock().is_terminated()
+    }
+
+    /// Presses or releases a key, by scancode (extended keys are prefixed with 0xE0, e.g. 0xE04B for the left
+    /// arrow).
+    fn key(&self, py: Python<'_>, scancode: u16, pressed: bool) -> PyResult<()> {
+        let scancode = Scancode::from_u16(scancode);
+        let operation = if pressed {
+            Operation::KeyPressed(scancode)
+        } else {
+            Operation::KeyReleased(scancode)
+        };
+        self.apply_input(py, vec![operation])
+    }
+
+    /// Presses and releases a key, by scancode.
+    fn tap_key(&self, py: Python<'_>, scancode: u16) -> PyResult<()> {
+        let scancode = Scancode::from_u16(scancode);
+        self.apply_input(
+            py,
+            vec![Operation::KeyPressed(scancode), Operation::KeyReleased(scancode)],
+        )
+    }
+
+    /// Types the given text, as Unicode key events.
+    fn type_text(&self, py: Python<'_>, text: &str) -> PyResult<()> {
+        let operations = text
+            .chars()
+            .flat_map(|c| [Operation::UnicodeKeyPressed(c), Operation::UnicodeKeyReleased(c)])
+            .collect();
+        self.apply_input(py, operations)
+    }
+
+    fn mouse_move(&self, py: Python<'_>, x: u16, y: u16) -> PyResult<()> {
+        self.apply_input(py, vec![Operation::MouseMove(MousePosition { x, y })])
+    }
+
+    /// Presses or releases a mouse button: 0 is left, 1 middle, 2 right, 3 and 4 the extra buttons.
+    fn mouse_button(&self, py: Python<'_>, button: usize, pressed: bool) -> PyResult<()> {
+        let button = mouse_button(button)?;
+        let operation = if pressed {
+            Operation::MouseButtonPressed(button)
+        } else {
+            Operation::MouseButtonReleased(button)
+        };
+        self.apply_input(py, vec![operation])
+    }
+
+    /// Moves the mouse to the given position and clicks the given button there.
+    #[pyo3(signature = (x, y, button = 0))]
+    fn click(&self, py: Python<'_>, x: u16, y: u16, button: usize) -> PyResult<()> {
+        let button = mouse_button(button)?;
+        self.apply_input(
+            py,
+            vec![
+                Operation::MouseMove(MousePosition { x, y }),
+                Operation::MouseButtonPressed(button),
+                Operation::MouseButtonReleased(button),
+            ],
+        )
+    }
+
+    /// Rotates the mouse wheel, 120 units being one notch. Positive units scroll up, or right.
+    #[pyo3(signature = (units, vertical = true))]
+    fn wheel(&self, py: Python<'_>, units: i16, vertical: bool) -> PyResult<()> {
+        self.apply_input(
+            py,
+            vec![Operation::WheelRotations(WheelRotations {
+                is_vertical: vertical,
+                rotation_units: units,
+            })],
+        )
+    }
+
+    /// Releases all the keys and mouse buttons pressed.
+    fn release_all(&self, py: Python<'_>) -> PyResult<()> {
+        py.detach(|| self.lock().release_all_inputs()).map_err(to_py_err)
+    }
+
+    /// Requests the server to end the session, which is terminated once `poll` raises `SessionTerminated`.
+    fn shutdown(&self, py: Python<'_>) -> PyResult<()> {
+        py.detach(|| self.lock().shutdown()).map_err(to_py_err)
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    /// Ends the session when leaving a `with` block, without waiting for the server.
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: Option<Py<PyAny>>,
+        _exc_value: Option<Py<PyAny>>,
+        _traceback: Option<Py<PyAny>>,
+    ) -> PyResult<()> {
+        self.shutdown(py)
+    }
+}
+
+impl Session {
+    fn lock(&self) -> MutexGuard<'_, session::Session> {
+        // A panic while the session is locked is raised as a Python exception, the session is still usable.
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn apply_input(&self, py: Python<'_>, operations: Vec<Operation>) -> PyResult<()> {
+        py.detach(|| self.lock().apply_input(operations)).map_err(to_py_err)
+    }
+}
+
+fn mouse_button(button: usize) -> PyResult<MouseButton> {
+    MouseButton::from_idx(button).ok_or_else(|| PyValueError::new_err(format!("unknown mouse button {button}")))
+}
+
+fn to_py_err(error: anyhow::Error) -> PyErr {
+    match error.downcast::<Terminated>() {
+        Ok(Terminated(reason)) => SessionTerminated::new_err(reason),
+        Err(error) => RdpError::new_err(format!("{error:#}")),
+    }
+}
+
+/// Scripting of RDP client sessions.
+#[pymodule]
+#[pyo3(name = "ironrdp")]
+fn ironrdp_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(connect, m)?)?;
+    m.add_class::<Session>()?;
+    m.add("RdpError", m.py().get_type::<RdpError>())?;
+    m.add("SessionTerminated", m.py().get_type::<SessionTerminated>())?;
+    Ok(())
+}
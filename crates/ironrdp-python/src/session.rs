@@ -0,0 +1,481 @@
+use core::fmt;
+use core::time::Duration;
+use std::io::{self, Write as _};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs as _};
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::Context as _;
+use ironrdp::connector::connection_activation::{ConnectionActivationSequence, ConnectionActivationState};
+use ironrdp::connector::{self, ClientConnector, Credentials, DesktopSize};
+use ironrdp::graphics::image_processing::PixelFormat;
+use ironrdp::input::{Database, Operation};
+use ironrdp::pdu::gcc::KeyboardType;
+use ironrdp::pdu::rdp::capability_sets::MajorPlatformType;
+use ironrdp::pdu::rdp::client_info::PerformanceFlags;
+use ironrdp::session::image::DecodedImage;
+use ironrdp::session::{fast_path, ActiveStage, ActiveStageOutput};
+use ironrdp_blocking::Framed;
+use ironrdp_core::WriteBuf;
+use rustls::pki_types::ServerName;
+use sspi::network_client::reqwest_network_client::ReqwestNetworkClient;
+
+type TlsStream = rustls::StreamOwned<rustls::ClientConnection, TcpStream>;
+
+pub(crate) struct ConnectOptions {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) username: String,
+    pub(crate) password: String,
+    pub(crate) domain: Option<String>,
+    pub(crate) desktop_size: DesktopSize,
+    pub(crate) keyboard_layout: u32,
+    pub(crate) client_name: String,
+}
+
+/// Region of the framebuffer updated by the server.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Rect {
+    pub(crate) x: u16,
+    pub(crate) y: u16,
+    pub(crate) width: u16,
+    pub(crate) height: u16,
+}
+
+/// Returned once the session is terminated.
+#[derive(Debug)]
+pub(crate) struct Terminated(pub(crate) String);
+
+impl fmt::Display for Terminated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "session terminated: {}", self.0)
+    }
+}
+
+impl core::error::Error for Terminated {}
+
+pub(crate) struct Session {
+    framed: Framed<TlsStream>,
+    active_stage: ActiveStage,
+    image: DecodedImage,
+    input: Database,
+    termination: Option<String>,
+}
+
+impl Session {
+    /// Runs the whole connection sequence, `verify_certificate` deciding whether the credentials are sent to
+    /// the server presenting the given DER-encoded certificate.
+    pub(crate) fn connect(
+        options: ConnectOptions,
+        verify_certificate: impl FnOnce(&[u8]) -> bool,
+    ) -> anyhow::Result<Self> {
+        let server_addr = lookup_addr(&options.host, options.port)?;
+
+        info!(%server_addr, "Looked up server address");
+
+        let tcp_stream = TcpStream::connect(server_addr).context("TCP connect")?;
+        tcp_stream.set_nodelay(true).context("set TCP_NODELAY")?;
+
+        let mut framed = Framed::new(tcp_stream);
+
+        let host = options.host.clone();
+        let mut connector = ClientConnector::new(connector_config(options)).with_client_addr(server_addr);
+
+        let should_upgrade =
+            ironrdp_blocking::connect_begin(&mut framed, &mut connector).context("begin connection")?;
+
+        debug!("TLS upgrade");
+
+        let tcp_stream = framed.into_inner_no_leftover();
+        let (tls_stream, server_public_key) = tls_upgrade(tcp_stream, &host, verify_certificate)?;
+
+        let upgraded = ironrdp_blocking::mark_as_upgraded(should_upgrade, &mut connector);
+        let mut framed = Framed::new(tls_stream);
+
+        let mut network_client = ReqwestNetworkClient;
+        let connection_result = ironrdp_blocking::connect_finalize(
+            upgraded,
+            &mut framed,
+            connector,
+            host.into(),
+            server_public_key,
+            &mut network_client,
+            None,
+        )
+        .context("finalize connection")?;
+
+        info!(desktop_size = ?connection_result.desktop_size, "Connected");
+
+        let image = DecodedImage::new(
+            PixelFormat::RgbA32,
+            connection_result.desktop_size.width,
+            connection_result.desktop_size.height,
+        );
+
+        Ok(Self {
+            framed,
+            active_stage: ActiveStage::new(connection_result),
+            image,
+            input: Database::new(),
+            termination: None,
+        })
+    }
+
+    /// Returns the framebuffer, in RGBA format.
+    pub(crate) fn image(&self) -> &DecodedImage {
+        &self.image
+    }
+
+    /// Processes the frames received from the server until the framebuffer is updated, or until `timeout`
+    /// elapses, returning the updated regions.
+    pub(crate) fn poll(&mut self, timeout: Option<Duration>) -> anyhow::Result<Vec<Rect>> {
+        self.check_not_terminated()?;
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut updates = Vec::new();
+
+        while updates.is_empty() && self.termination.is_none() {
+            // A zero timeout is rejected by the socket, the smallest one is used instead.
+            let read_timeout = deadline.map(|deadline| {
+                deadline
+                    .saturating_duration_since(Instant::now())
+                    .max(Duration::from_millis(1))
+            });
+
+            self.framed
+                .get_inner_mut()
+                .0
+                .sock
+                .set_read_timeout(read_timeout)
+                .context("set read timeout")?;
+
+            let (action, payload) = match self.framed.read_pdu() {
+                Ok(frame) => frame,
+                Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => break,
+                Err(e) => return Err(anyhow::Error::new(e).context("read frame")),
+            };
+
+            trace!(?action, frame_length = payload.len(), "Frame received");
+
+            let outputs = self.active_stage.process(&mut self.image, action, &payload)?;
+            self.handle_outputs(outputs, &mut updates)?;
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+        }
+
+        if updates.is_empty() {
+            self.check_not_terminated()?;
+        }
+
+        Ok(updates)
+    }
+
+    pub(crate) fn apply_input(&mut self, operations: impl IntoIterator<Item = Operation>) -> anyhow::Result<()> {
+        self.check_not_terminated()?;
+
+        let events = self.input.apply(operations);
+        let outputs = self.active_stage.process_fastpath_input(&mut self.image, &events)?;
+        self.handle_outputs(outputs, &mut Vec::new())
+    }
+
+    pub(crate) fn release_all_inputs(&mut self) -> anyhow::Result<()> {
+        self.check_not_terminated()?;
+
+        let events = self.input.release_all();
+        let outputs = self.active_stage.process_fastpath_input(&mut self.image, &events)?;
+        self.handle_outputs(outputs, &mut Vec::new())
+    }
+
+    /// Requests the server to end the session, which is terminated once the server disconnects.
+    pub(crate) fn shutdown(&mut self) -> anyhow::Result<()> {
+        if self.termination.is_some() {
+            return Ok(());
+        }
+
+        let outputs = self.active_stage.graceful_shutdown()?;
+        self.handle_outputs(outputs, &mut Vec::new())
+    }
+
+    pub(crate) fn is_terminated(&self) -> bool {
+        self.termination.is_some()
+    }
+
+    fn check_not_terminated(&self) -> Result<(), Terminated> {
+        match &self.termination {
+            Some(reason) => Err(Terminated(reason.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn handle_outputs(&mut self, outputs: Vec<ActiveStageOutput>, updates: &mut Vec<Rect>) -> anyhow::Result<()> {
+        for out in outputs {
+            match out {
+                ActiveStageOutput::ResponseFrame(frame) => self.write_frame(&frame)?,
+                ActiveStageOutput::GraphicsUpdate(region) => updates.push(Rect {
+                    x: region.left,
+                    y: region.top,
+                    width: region.right - region.left + 1,
+                    height: region.bottom - region.top + 1,
+                }),
+                ActiveStageOutput::DeactivateAll(sequence) => {
+                    self.reactivate(sequence)?;
+
+                    // The whole framebuffer was reallocated.
+                    updates.push(Rect {
+                        x: 0,
+                        y: 0,
+                        width: self.image.width(),
+                        height: self.image.height(),
+                    });
+                }
+                ActiveStageOutput::Terminate(reason) => {
+                    info!(%reason, "Session terminated");
+                    self.termination = Some(reason.to_string());
+                }
+                ActiveStageOutput::PointerDefault
+                | ActiveStageOutput::PointerHidden
+                | ActiveStageOutput::PointerPosition { .. }
+                | ActiveStageOutput::PointerBitmap(_) => {
+                    // The pointer is drawn by the server, in the framebuffer.
+                }
+                ActiveStageOutput::SessionEvent(event) => {
+                    debug!(?event, "Session event");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Executes the Deactivation-Reactivation Sequence, typically after a resize of the desktop.
+    fn reactivate(&mut self, mut sequence: Box<ConnectionActivationSequence>) -> anyhow::Result<()> {
+        debug!("Received Server Deactivate All PDU, executing Deactivation-Reactivation Sequence");
+
+        // The sequence is completed at once, without the timeout of the caller.
+        self.framed
+            .get_inner_mut()
+            .0
+            .sock
+            .set_read_timeout(None)
+            .context("set read timeout")?;
+
+        let mut buf = WriteBuf::new();
+
+        loop {
+            buf.clear();
+
+            let written = if let Some(next_pdu_hint) = sequence.next_pdu_hint() {
+                let pdu = self.framed.read_by_hint(next_pdu_hint).context("read frame by hint")?;
+                sequence.step(&pdu, &mut buf)?
+            } else {
+                sequence.step_no_input(&mut buf)?
+            };
+
+            if let Some(response_len) = written.size() {
+                self.write_frame(&buf[..response_len])?;
+            }
+
+            if let ConnectionActivationState::Finalized {
+                io_channel_id,
+                user_channel_id,
+                desktop_size,
+                no_server_pointer,
+                pointer_software_rendering,
+            } = sequence.state
+            {
+                debug!(?desktop_size, "Deactivation-Reactivation Sequence completed");
+
+                self.image = DecodedImage::new(PixelFormat::RgbA32, desktop_size.width, desktop_size.height);
+                self.active_stage.set_fastpath_processor(
+                    fast_path::ProcessorBuilder {
+                        io_channel_id,
+                        user_channel_id,
+                        no_server_pointer,
+                        pointer_software_rendering,
+                    }
+                    .build(),
+                );
+                self.active_stage.set_no_server_pointer(no_server_pointer);
+
+                return Ok(());
+            }
+        }
+    }
+
+    fn write_frame(&mut self, frame: &[u8]) -> anyhow::Result<()> {
+        if frame.is_empty() {
+            return Ok(());
+        }
+
+        self.framed.write_all(frame).context("write frame")
+    }
+}
+
+fn lookup_addr(host: &str, port: u16) -> anyhow::Result<SocketAddr> {
+    (host, port)
+        .to_socket_addrs()
+        .context("lookup address")?
+        .next()
+        .with_context(|| format!("no address found for {host}"))
+}
+
+fn connector_config(options: ConnectOptions) -> connector::Config {
+    connector::Config {
+        credentials: Credentials::UsernamePassword {
+            username: options.username,
+            password: options.password,
+        },
+        domain: options.domain,
+        remote_credential_guard: false,
+        enable_tls: true,
+        enable_credssp: true,
+        keyboard_type: KeyboardType::IbmEnhanced,
+        keyboard_subtype: 0,
+        keyboard_layout: options.keyboard_layout,
+        keyboard_functional_keys_count: 12,
+        ime_file_name: String::new(),
+        dig_product_id: String::new(),
+        desktop_size: options.desktop_size,
+        bitmap: None,
+        client_build: 0,
+        client_name: options.client_name,
+        client_dir: "C:\\Windows\\System32\\mstscax.dll".to_owned(),
+
+        #[cfg(windows)]
+        platform: MajorPlatformType::WINDOWS,
+        #[cfg(target_os = "macos")]
+        platform: MajorPlatformType::MACINTOSH,
+        #[cfg(target_os = "linux")]
+        platform: MajorPlatformType::UNIX,
+        #[cfg(target_os = "freebsd")]
+        platform: MajorPlatformType::UNIX,
+        #[cfg(target_os = "openbsd")]
+        platform: MajorPlatformType::UNIX,
+        #[cfg(target_os = "netbsd")]
+        platform: MajorPlatformType::UNIX,
+
+        // The pointer is drawn in the framebuffer, so that it is visible on the captured images.
+        no_server_pointer: true,
+        pointer_software_rendering: true,
+        request_data: None,
+        autologon: false,
+        no_audio_playback: true,
+        message_channel: false,
+        multitransport_flags: None,
+        rail: false,
+        performance_flags: PerformanceFlags::default(),
+        desktop_scale_factor: 0,
+        monitors: Vec::new(),
+        hardware_id: None,
+        license_cache: None,
+    }
+}
+
+fn tls_upgrade(
+    stream: TcpStream,
+    host: &str,
+    verify_certificate: impl FnOnce(&[u8]) -> bool,
+) -> anyhow::Result<(TlsStream, Vec<u8>)> {
+    let tls_config = rustls::ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(danger::NoCertificateVerification))
+        .with_no_client_auth();
+
+    let server_name = ServerName::try_from(host.to_owned()).context("invalid server name")?;
+
+    let connection = rustls::ClientConnection::new(Arc::new(tls_config), server_name).context("TLS connection")?;
+
+    let mut tls_stream = rustls::StreamOwned::new(connection, stream);
+
+    // Performs the handshake.
+    tls_stream.flush().context("TLS handshake")?;
+
+    let certificate = tls_stream
+        .conn
+        .peer_certificates()
+        .and_then(|certificates| certificates.first())
+        .context("peer certificate is missing")?;
+
+    // The certificate is verified by the caller, before the credentials are sent.
+    anyhow::ensure!(verify_certificate(certificate), "server certificate rejected");
+
+    let server_public_key = extract_tls_server_public_key(certificate)?;
+
+    Ok((tls_stream, server_public_key))
+}
+
+fn extract_tls_server_public_key(certificate: &[u8]) -> anyhow::Result<Vec<u8>> {
+    use x509_cert::der::Decode as _;
+
+    let certificate = x509_cert::Certificate::from_der(certificate).context("invalid server certificate")?;
+
+    let server_public_key = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key
+        .as_bytes()
+        .context("subject public key BIT STRING is not aligned")?
+        .to_owned();
+
+    Ok(server_public_key)
+}
+
+mod danger {
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::{pki_types, DigitallySignedStruct, Error, SignatureScheme};
+
+    /// The certificate is verified by the caller once the handshake is done.
+    #[derive(Debug)]
+    pub(super) struct NoCertificateVerification;
+
+    impl ServerCertVerifier for NoCertificateVerification {
+        fn verify_server_cert(
+            &self,
+            _: &pki_types::CertificateDer<'_>,
+            _: &[pki_types::CertificateDer<'_>],
+            _: &pki_types::ServerName<'_>,
+            _: &[u8],
+            _: pki_types::UnixTime,
+        ) -> Result<ServerCertVerified, Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            _: &[u8],
+            _: &pki_types::CertificateDer<'_>,
+            _: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            _: &[u8],
+            _: &pki_types::CertificateDer<'_>,
+            _: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            vec![
+                SignatureScheme::RSA_PKCS1_SHA1,
+                SignatureScheme::ECDSA_SHA1_Legacy,
+                SignatureScheme::RSA_PKCS1_SHA256,
+                SignatureScheme::ECDSA_NISTP256_SHA256,
+                SignatureScheme::RSA_PKCS1_SHA384,
+                SignatureScheme::ECDSA_NISTP384_SHA384,
+                SignatureScheme::RSA_PKCS1_SHA512,
+                SignatureScheme::ECDSA_NISTP521_SHA512,
+                SignatureScheme::RSA_PSS_SHA256,
+                SignatureScheme::RSA_PSS_SHA384,
+                SignatureScheme::RSA_PSS_SHA512,
+                SignatureScheme::ED25519,
+                SignatureScheme::ED448,
+            ]
+        }
+    }
+}
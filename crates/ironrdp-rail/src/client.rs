@@ -0,0 +1,183 @@
+use ironrdp_core::{impl_as_any, Decode, ReadCursor};
+use ironrdp_pdu::gcc::ChannelName;
+use ironrdp_pdu::{decode_err, pdu_other_err, PduResult};
+use ironrdp_svc::{CompressionCondition, SvcClientProcessor, SvcMessage, SvcProcessor, SvcProcessorMessages};
+use tracing::{debug, error};
+
+use crate::pdu::{
+    ClientStatusFlags, ClientStatusPdu, CompartmentInfoPdu, ExecPdu, HandshakePdu, LanguageBarInfoPdu,
+    LanguageImeInfoPdu, RailPdu, RailPduHeader,
+};
+
+pub type RailSvcMessages = SvcProcessorMessages<RailClient>;
+
+/// Build number advertised in the client Handshake PDU (Windows 7).
+const CLIENT_BUILD_NUMBER: u32 = 7601;
+
+pub trait RailClientHandler: Send + core::fmt::Debug {
+    /// Called when the RAIL handshake is completed, and the channel is ready to send orders.
+    fn ready(&mut self) {}
+
+    /// Called when the IME state of the focused remote application changes.
+    ///
+    /// The client should apply the same state to its local input method, so the composition
+    /// happens locally and the resulting characters are sent only once.
+    fn compartment_info(&mut self, pdu: CompartmentInfoPdu);
+
+    /// Called when the server requests a change of the local language bar status.
+    fn language_bar_info(&mut self, pdu: LanguageBarInfoPdu) {
+        let _ = pdu;
+    }
+}
+
+#[derive(Debug)]
+pub struct NoopRailClientHandler;
+
+impl RailClientHandler for NoopRailClientHandler {
+    fn compartment_info(&mut self, _pdu: CompartmentInfoPdu) {}
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum RailState {
+    WaitingForHandshake,
+    Ready,
+}
+
+/// RAIL static virtual channel client, as described in \[MS-RDPERP\].
+///
+/// The `INFO_RAIL` flag must be set in the Client Info PDU for the server to open this channel, see the
+/// `rail` option of the connector configuration.
+///
+/// Only the handshake and the input method integration orders are currently processed, other orders
+/// are ignored.
+#[derive(Debug)]
+pub struct RailClient {
+    handler: Box<dyn RailClientHandler>,
+    state: RailState,
+    status_flags: ClientStatusFlags,
+    exec: Option<ExecPdu>,
+}
+
+impl RailClient {
+    pub const NAME: ChannelName = ChannelName::from_static(b"rail\0\0\0\0");
+
+    pub fn new(handler: Box<dyn RailClientHandler>) -> Self {
+        Self {
+            handler,
+            state: RailState::WaitingForHandshake,
+            status_flags: ClientStatusFlags::ALLOWLOCALMOVESIZE,
+            exec: None,
+        }
+    }
+
+    /// Sets the flags sent in the Client Information PDU once the handshake is completed.
+    #[must_use]
+    pub fn with_status_flags(mut self, flags: ClientStatusFlags) -> Self {
+        self.status_flags = flags;
+        self
+    }
+
+    /// Sets the remote application launched once the handshake is completed.
+    #[must_use]
+    pub fn with_exec(mut self, pdu: ExecPdu) -> Self {
+        self.exec = Some(pdu);
+        self
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.state == RailState::Ready
+    }
+
+    /// Notifies the server of a change of the local IME state.
+    pub fn compartment_info(&self, pdu: CompartmentInfoPdu) -> PduResult<RailSvcMessages> {
+        self.order(RailPdu::CompartmentInfo(pdu))
+    }
+
+    /// Notifies the server of a change of the active input language or input method.
+    pub fn language_ime_info(&self, pdu: LanguageImeInfoPdu) -> PduResult<RailSvcMessages> {
+        self.order(RailPdu::LanguageImeInfo(pdu))
+    }
+
+    /// Notifies the server of a change of the local language bar status.
+    pub fn language_bar_info(&self, pdu: LanguageBarInfoPdu) -> PduResult<RailSvcMessages> {
+        self.order(RailPdu::LanguageBarInfo(pdu))
+    }
+
+    fn order(&self, pdu: RailPdu) -> PduResult<RailSvcMessages> {
+        if !self.is_ready() {
+            return Err(pdu_other_err!("invalid state - RAIL handshake not completed"));
+        }
+
+        Ok(RailSvcMessages::new(vec![pdu.into()]))
+    }
+
+    fn handshake(&mut self) -> Vec<SvcMessage> {
+        self.state = RailState::Ready;
+        self.handler.ready();
+
+        let mut msgs = vec![
+            RailPdu::Handshake(HandshakePdu {
+                build_number: CLIENT_BUILD_NUMBER,
+            })
+            .into(),
+            RailPdu::ClientStatus(ClientStatusPdu {
+                flags: self.status_flags,
+            })
+            .into(),
+        ];
+
+        if let Some(exec) = self.exec.take() {
+            msgs.push(RailPdu::Exec(exec).into());
+        }
+
+        msgs
+    }
+}
+
+impl_as_any!(RailClient);
+
+impl SvcProcessor for RailClient {
+    fn channel_name(&self) -> ChannelName {
+        Self::NAME
+    }
+
+    fn compression_condition(&self) -> CompressionCondition {
+        CompressionCondition::WhenRdpDataIsCompressed
+    }
+
+    fn process(&mut self, payload: &[u8]) -> PduResult<Vec<SvcMessage>> {
+        let header = RailPduHeader::decode(&mut ReadCursor::new(payload)).map_err(|e| decode_err!(e))?;
+
+        if !RailPdu::is_supported(header.order_type) {
+            debug!(order_type = ?header.order_type, "Ignoring unsupported RAIL order");
+            return Ok(Vec::new());
+        }
+
+        let pdu = RailPdu::decode(&mut ReadCursor::new(payload)).map_err(|e| decode_err!(e))?;
+
+        debug!(?pdu, ?self.state);
+        let msgs = match (self.state, pdu) {
+            (RailState::WaitingForHandshake, RailPdu::Handshake(_) | RailPdu::HandshakeEx(_)) => self.handshake(),
+            (RailState::WaitingForHandshake, pdu) => {
+                error!(?pdu, "Unexpected RAIL order before handshake");
+                Vec::new()
+            }
+            (RailState::Ready, RailPdu::CompartmentInfo(pdu)) => {
+                self.handler.compartment_info(pdu);
+                Vec::new()
+            }
+            (RailState::Ready, RailPdu::LanguageBarInfo(pdu)) => {
+                self.handler.language_bar_info(pdu);
+                Vec::new()
+            }
+            (RailState::Ready, pdu) => {
+                debug!(?pdu, "Ignoring unexpected RAIL order");
+                Vec::new()
+            }
+        };
+
+        Ok(msgs)
+    }
+}
+
+impl SvcClientProcessor for RailClient {}
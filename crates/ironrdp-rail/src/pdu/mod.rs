@@ -0,0 +1,1524 @@
+//! Remote Programs Virtual Channel Extension PDUs \[MS-RDPERP\] implementation.
+//!
+//! The system parameters, application ID, taskbar, snap arrange and power display orders are not
+//! covered. The window and notification icon orders sent in the update stream are in the [`window`]
+//! module.
+
+use core::fmt;
+
+use bitflags::bitflags;
+use ironrdp_core::{
+    cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, read_padding, write_padding, Decode,
+    DecodeResult, Encode, EncodeResult, ReadCursor, WriteCursor,
+};
+use ironrdp_pdu::utils::{from_utf16_bytes, to_utf16_bytes};
+use ironrdp_svc::SvcEncode;
+
+pub mod window;
+
+/// RAIL order type (`orderType` field of `TS_RAIL_PDU_HEADER`)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OrderType(pub u16);
+
+macro_rules! order_types {
+    (
+        $(
+            ($konst:ident, $num:expr);
+        )+
+    ) => {
+        impl OrderType {
+        $(
+            pub const $konst: OrderType = OrderType($num);
+        )+
+
+            fn as_str(&self) -> Option<&'static str> {
+                match self.0 {
+                    $(
+                        $num => Some(stringify!($konst)),
+                    )+
+                        _ => None
+                }
+            }
+        }
+    }
+}
+
+order_types! {
+    (EXEC, 0x0001);
+    (ACTIVATE, 0x0002);
+    (SYSPARAM, 0x0003);
+    (SYSCOMMAND, 0x0004);
+    (HANDSHAKE, 0x0005);
+    (NOTIFY_EVENT, 0x0006);
+    (WINDOWMOVE, 0x0008);
+    (LOCALMOVESIZE, 0x0009);
+    (MINMAXINFO, 0x000A);
+    (CLIENTSTATUS, 0x000B);
+    (SYSMENU, 0x000C);
+    (LANGBARINFO, 0x000D);
+    (GET_APPID_REQ, 0x000E);
+    (GET_APPID_RESP, 0x000F);
+    (TASKBARINFO, 0x0010);
+    (LANGUAGEIMEINFO, 0x0011);
+    (COMPARTMENTINFO, 0x0012);
+    (HANDSHAKE_EX, 0x0013);
+    (ZORDER_SYNC, 0x0014);
+    (CLOAK, 0x0015);
+    (POWER_DISPLAY_REQUEST, 0x0016);
+    (SNAP_ARRANGE, 0x0017);
+    (GET_APPID_RESP_EX, 0x0018);
+    (TEXTSCALEINFO, 0x0019);
+    (CARETBLINKINFO, 0x001A);
+    (EXEC_RESULT, 0x0080);
+}
+
+impl fmt::Debug for OrderType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(s) = self.as_str() {
+            write!(f, "OrderType::{s}")
+        } else {
+            write!(f, "OrderType(0x{:04X})", self.0)
+        }
+    }
+}
+
+/// \[MS-RDPERP\] 2.2.2.1 Common Header (TS_RAIL_PDU_HEADER)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RailPduHeader {
+    pub order_type: OrderType,
+    /// Length of the order, including this header.
+    pub order_length: u16,
+}
+
+impl RailPduHeader {
+    const NAME: &'static str = "TS_RAIL_PDU_HEADER";
+
+    pub const FIXED_PART_SIZE: usize = 2 /* orderType */ + 2 /* orderLength */;
+}
+
+impl Encode for RailPduHeader {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u16(self.order_type.0);
+        dst.write_u16(self.order_length);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for RailPduHeader {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let order_type = OrderType(src.read_u16());
+        let order_length = src.read_u16();
+
+        Ok(Self {
+            order_type,
+            order_length,
+        })
+    }
+}
+
+/// Handshake PDU (TS_RAIL_ORDER_HANDSHAKE), sent by both the client and the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandshakePdu {
+    pub build_number: u32,
+}
+
+impl HandshakePdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_HANDSHAKE";
+
+    const FIXED_PART_SIZE: usize = 4 /* buildNumber */;
+}
+
+impl Encode for HandshakePdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.build_number);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for HandshakePdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let build_number = src.read_u32();
+
+        Ok(Self { build_number })
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct HandshakeExFlags: u32 {
+        const HIDEF = 0x0000_0001;
+        const EXTENDED_SPI_SUPPORTED = 0x0000_0002;
+        const SNAP_ARRANGE_SUPPORTED = 0x0000_0004;
+        const TEXT_SCALE_SUPPORTED = 0x0000_0008;
+        const CARET_BLINK_SUPPORTED = 0x0000_0010;
+        const EXTENDED_SPI_2_SUPPORTED = 0x0000_0020;
+        const EXTENDED_SPI_3_SUPPORTED = 0x0000_0040;
+    }
+}
+
+/// HandshakeEx PDU (TS_RAIL_ORDER_HANDSHAKE_EX), sent by the server instead of the Handshake PDU
+/// when the client advertised `INFO_HIDEF_RAIL_SUPPORTED`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandshakeExPdu {
+    pub build_number: u32,
+    pub flags: HandshakeExFlags,
+}
+
+impl HandshakeExPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_HANDSHAKE_EX";
+
+    const FIXED_PART_SIZE: usize = 4 /* buildNumber */ + 4 /* railHandshakeFlags */;
+}
+
+impl Encode for HandshakeExPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.build_number);
+        dst.write_u32(self.flags.bits());
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for HandshakeExPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let build_number = src.read_u32();
+        let flags = HandshakeExFlags::from_bits_retain(src.read_u32());
+
+        Ok(Self { build_number, flags })
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ClientStatusFlags: u32 {
+        const ALLOWLOCALMOVESIZE = 0x0000_0001;
+        const AUTORECONNECT = 0x0000_0002;
+        const ZORDER_SYNC = 0x0000_0004;
+        const WINDOW_RESIZE_MARGIN_SUPPORTED = 0x0000_0010;
+        const HIGH_DPI_ICONS_SUPPORTED = 0x0000_0020;
+        const APPBAR_REMOTING_SUPPORTED = 0x0000_0040;
+        const POWER_DISPLAY_REQUEST_SUPPORTED = 0x0000_0080;
+        const BIDIRECTIONAL_CLOAK_SUPPORTED = 0x0000_0200;
+        const SUPPRESS_ICON_ORDERS = 0x0000_0400;
+    }
+}
+
+/// Client Information PDU (TS_RAIL_ORDER_CLIENTSTATUS)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientStatusPdu {
+    pub flags: ClientStatusFlags,
+}
+
+impl ClientStatusPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_CLIENTSTATUS";
+
+    const FIXED_PART_SIZE: usize = 4 /* Flags */;
+}
+
+impl Encode for ClientStatusPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.flags.bits());
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for ClientStatusPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let flags = ClientStatusFlags::from_bits_retain(src.read_u32());
+
+        Ok(Self { flags })
+    }
+}
+
+bitflags! {
+    /// Language bar status (TF_SFT_* flags)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct LanguageBarStatus: u32 {
+        const SHOWNORMAL = 0x0000_0001;
+        const DOCK = 0x0000_0002;
+        const MINIMIZED = 0x0000_0004;
+        const HIDDEN = 0x0000_0008;
+        const NOTRANSPARENCY = 0x0000_0010;
+        const LOWTRANSPARENCY = 0x0000_0020;
+        const HIGHTRANSPARENCY = 0x0000_0040;
+        const LABELS = 0x0000_0080;
+        const NOLABELS = 0x0000_0100;
+        const EXTRAICONSONMINIMIZED = 0x0000_0200;
+        const NOEXTRAICONSONMINIMIZED = 0x0000_0400;
+        const DESKBAND = 0x0000_0800;
+    }
+}
+
+/// Language Bar Information PDU (TS_RAIL_ORDER_LANGBARINFO), sent by both the client and the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageBarInfoPdu {
+    pub status: LanguageBarStatus,
+}
+
+impl LanguageBarInfoPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_LANGBARINFO";
+
+    const FIXED_PART_SIZE: usize = 4 /* LanguageBarStatus */;
+}
+
+impl Encode for LanguageBarInfoPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.status.bits());
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for LanguageBarInfoPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let status = LanguageBarStatus::from_bits_retain(src.read_u32());
+
+        Ok(Self { status })
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProfileType {
+    /// The active input method is a text service (TF_PROFILETYPE_INPUTPROCESSOR).
+    InputProcessor = 0x0000_0001,
+    /// The active input method is a keyboard layout (TF_PROFILETYPE_KEYBOARDLAYOUT).
+    KeyboardLayout = 0x0000_0002,
+}
+
+impl TryFrom<u32> for ProfileType {
+    type Error = ironrdp_core::DecodeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0x0000_0001 => Ok(Self::InputProcessor),
+            0x0000_0002 => Ok(Self::KeyboardLayout),
+            _ => Err(invalid_field_err!("ProfileType", "unknown language profile type")),
+        }
+    }
+}
+
+impl From<ProfileType> for u32 {
+    fn from(profile_type: ProfileType) -> Self {
+        profile_type as u32
+    }
+}
+
+/// Language Profile Information PDU (TS_RAIL_ORDER_LANGUAGEIMEINFO), sent by the client when the
+/// active input language or input method changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageImeInfoPdu {
+    pub profile_type: ProfileType,
+    /// Language identifier (LANGID) of the input profile.
+    pub language_id: u16,
+    /// CLSID of the text service, zeroed for keyboard layouts.
+    pub language_profile_clsid: [u8; 16],
+    /// GUID of the text service profile, zeroed for keyboard layouts.
+    pub profile_guid: [u8; 16],
+    /// Keyboard layout identifier (HKL), zero for text services.
+    pub keyboard_layout: u32,
+}
+
+impl LanguageImeInfoPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_LANGUAGEIMEINFO";
+
+    const FIXED_PART_SIZE: usize = 4 /* ProfileType */
+        + 2 /* LanguageID */
+        + 16 /* LanguageProfileCLSID */
+        + 16 /* ProfileGUID */
+        + 4 /* KeyboardLayout */;
+}
+
+impl Encode for LanguageImeInfoPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.profile_type.into());
+        dst.write_u16(self.language_id);
+        dst.write_array(self.language_profile_clsid);
+        dst.write_array(self.profile_guid);
+        dst.write_u32(self.keyboard_layout);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for LanguageImeInfoPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let profile_type = ProfileType::try_from(src.read_u32())?;
+        let language_id = src.read_u16();
+        let language_profile_clsid = src.read_array();
+        let profile_guid = src.read_array();
+        let keyboard_layout = src.read_u32();
+
+        Ok(Self {
+            profile_type,
+            language_id,
+            language_profile_clsid,
+            profile_guid,
+            keyboard_layout,
+        })
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImeState {
+    Closed = 0x0000_0000,
+    Open = 0x0000_0001,
+}
+
+impl TryFrom<u32> for ImeState {
+    type Error = ironrdp_core::DecodeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0x0000_0000 => Ok(Self::Closed),
+            0x0000_0001 => Ok(Self::Open),
+            _ => Err(invalid_field_err!("ImeState", "unknown IME state")),
+        }
+    }
+}
+
+impl From<ImeState> for u32 {
+    fn from(state: ImeState) -> Self {
+        state as u32
+    }
+}
+
+bitflags! {
+    /// IME conversion mode (IME_CMODE_* flags)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ImeConversionMode: u32 {
+        const NATIVE = 0x0000_0001;
+        const KATAKANA = 0x0000_0002;
+        const FULLSHAPE = 0x0000_0008;
+        const ROMAN = 0x0000_0010;
+        const CHARCODE = 0x0000_0020;
+        const HANJACONVERT = 0x0000_0040;
+        const SOFTKBD = 0x0000_0080;
+        const NOCONVERSION = 0x0000_0100;
+        const EUDC = 0x0000_0200;
+        const SYMBOL = 0x0000_0400;
+        const FIXED = 0x0000_0800;
+    }
+}
+
+bitflags! {
+    /// IME sentence mode (IME_SMODE_* flags)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ImeSentenceMode: u32 {
+        const PLURALCLAUSE = 0x0000_0001;
+        const SINGLECONVERT = 0x0000_0002;
+        const AUTOMATIC = 0x0000_0004;
+        const PHRASEPREDICT = 0x0000_0008;
+        const CONVERSATION = 0x0000_0010;
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KanaMode {
+    Off = 0x0000_0000,
+    On = 0x0000_0001,
+}
+
+impl TryFrom<u32> for KanaMode {
+    type Error = ironrdp_core::DecodeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0x0000_0000 => Ok(Self::Off),
+            0x0000_0001 => Ok(Self::On),
+            _ => Err(invalid_field_err!("KanaMode", "unknown kana mode")),
+        }
+    }
+}
+
+impl From<KanaMode> for u32 {
+    fn from(mode: KanaMode) -> Self {
+        mode as u32
+    }
+}
+
+/// Compartment Status Information PDU (TS_RAIL_ORDER_COMPARTMENTINFO), sent by both the client and the
+/// server when the IME state of the focused application changes.
+///
+/// Keeping both sides in sync ensures the composition happens on a single side, which avoids
+/// duplicated characters and misplaced candidate windows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompartmentInfoPdu {
+    pub ime_state: ImeState,
+    pub ime_conversion_mode: ImeConversionMode,
+    pub ime_sentence_mode: ImeSentenceMode,
+    pub kana_mode: KanaMode,
+}
+
+impl CompartmentInfoPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_COMPARTMENTINFO";
+
+    const FIXED_PART_SIZE: usize = 4 /* ImeState */
+        + 4 /* ImeConvMode */
+        + 4 /* ImeSentenceModeFlags */
+        + 4 /* KANAMode */;
+}
+
+impl Encode for CompartmentInfoPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.ime_state.into());
+        dst.write_u32(self.ime_conversion_mode.bits());
+        dst.write_u32(self.ime_sentence_mode.bits());
+        dst.write_u32(self.kana_mode.into());
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for CompartmentInfoPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let ime_state = ImeState::try_from(src.read_u32())?;
+        let ime_conversion_mode = ImeConversionMode::from_bits_retain(src.read_u32());
+        let ime_sentence_mode = ImeSentenceMode::from_bits_retain(src.read_u32());
+        let kana_mode = KanaMode::try_from(src.read_u32())?;
+
+        Ok(Self {
+            ime_state,
+            ime_conversion_mode,
+            ime_sentence_mode,
+            kana_mode,
+        })
+    }
+}
+
+bitflags! {
+    /// Flags of the Client Execute PDU (TS_RAIL_EXEC_FLAG_* flags)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ExecFlags: u16 {
+        const EXPAND_WORKINGDIRECTORY = 0x0001;
+        const TRANSLATE_FILES = 0x0002;
+        const FILE = 0x0004;
+        const EXPAND_ARGUMENTS = 0x0008;
+        const APP_USER_MODEL_ID = 0x0010;
+    }
+}
+
+/// Client Execute PDU (TS_RAIL_ORDER_EXEC), sent by the client to launch a remote application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecPdu {
+    pub flags: ExecFlags,
+    /// Executable or file to open, e.g. `||notepad` for a published application alias.
+    pub exe_or_file: String,
+    pub working_dir: String,
+    pub arguments: String,
+}
+
+impl ExecPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_EXEC";
+
+    const FIXED_PART_SIZE: usize = 2 /* Flags */
+        + 2 /* ExeOrFileLength */
+        + 2 /* WorkingDirLength */
+        + 2 /* ArgumentsLen */;
+}
+
+impl Encode for ExecPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        let exe_or_file = to_utf16_bytes(&self.exe_or_file);
+        let working_dir = to_utf16_bytes(&self.working_dir);
+        let arguments = to_utf16_bytes(&self.arguments);
+
+        dst.write_u16(self.flags.bits());
+        dst.write_u16(cast_length!("ExeOrFileLength", exe_or_file.len())?);
+        dst.write_u16(cast_length!("WorkingDirLength", working_dir.len())?);
+        dst.write_u16(cast_length!("ArgumentsLen", arguments.len())?);
+        dst.write_slice(&exe_or_file);
+        dst.write_slice(&working_dir);
+        dst.write_slice(&arguments);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + utf16_len(&self.exe_or_file) + utf16_len(&self.working_dir) + utf16_len(&self.arguments)
+    }
+}
+
+impl<'de> Decode<'de> for ExecPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let flags = ExecFlags::from_bits_retain(src.read_u16());
+        let exe_or_file_length = usize::from(src.read_u16());
+        let working_dir_length = usize::from(src.read_u16());
+        let arguments_length = usize::from(src.read_u16());
+
+        ensure_size!(in: src, size: exe_or_file_length + working_dir_length + arguments_length);
+        let exe_or_file = from_utf16_bytes(src.read_slice(exe_or_file_length));
+        let working_dir = from_utf16_bytes(src.read_slice(working_dir_length));
+        let arguments = from_utf16_bytes(src.read_slice(arguments_length));
+
+        Ok(Self {
+            flags,
+            exe_or_file,
+            working_dir,
+            arguments,
+        })
+    }
+}
+
+/// Result of the launch of a remote application (RAIL_EXEC_* codes)
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExecResult {
+    Ok = 0x0000,
+    HookNotLoaded = 0x0001,
+    DecodeFailed = 0x0002,
+    NotInAllowList = 0x0003,
+    FileNotFound = 0x0005,
+    Fail = 0x0006,
+    SessionLocked = 0x0007,
+}
+
+impl TryFrom<u16> for ExecResult {
+    type Error = ironrdp_core::DecodeError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0x0000 => Ok(Self::Ok),
+            0x0001 => Ok(Self::HookNotLoaded),
+            0x0002 => Ok(Self::DecodeFailed),
+            0x0003 => Ok(Self::NotInAllowList),
+            0x0005 => Ok(Self::FileNotFound),
+            0x0006 => Ok(Self::Fail),
+            0x0007 => Ok(Self::SessionLocked),
+            _ => Err(invalid_field_err!("ExecResult", "unknown execute result")),
+        }
+    }
+}
+
+impl From<ExecResult> for u16 {
+    fn from(result: ExecResult) -> Self {
+        result as u16
+    }
+}
+
+/// Server Execute Result PDU (TS_RAIL_ORDER_EXEC_RESULT), sent by the server in response to a
+/// Client Execute PDU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecResultPdu {
+    /// Flags of the Client Execute PDU.
+    pub flags: ExecFlags,
+    pub exec_result: ExecResult,
+    /// Operating system specific error code, e.g. the result of `ShellExecuteEx`.
+    pub raw_result: u32,
+    /// Executable or file of the Client Execute PDU.
+    pub exe_or_file: String,
+}
+
+impl ExecResultPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_EXEC_RESULT";
+
+    const FIXED_PART_SIZE: usize = 2 /* Flags */
+        + 2 /* ExecResult */
+        + 4 /* RawResult */
+        + 2 /* Padding */
+        + 2 /* ExeOrFileLength */;
+}
+
+impl Encode for ExecResultPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        let exe_or_file = to_utf16_bytes(&self.exe_or_file);
+
+        dst.write_u16(self.flags.bits());
+        dst.write_u16(self.exec_result.into());
+        dst.write_u32(self.raw_result);
+        write_padding!(dst, 2);
+        dst.write_u16(cast_length!("ExeOrFileLength", exe_or_file.len())?);
+        dst.write_slice(&exe_or_file);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + utf16_len(&self.exe_or_file)
+    }
+}
+
+impl<'de> Decode<'de> for ExecResultPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let flags = ExecFlags::from_bits_retain(src.read_u16());
+        let exec_result = ExecResult::try_from(src.read_u16())?;
+        let raw_result = src.read_u32();
+        read_padding!(src, 2);
+        let exe_or_file_length = usize::from(src.read_u16());
+
+        ensure_size!(in: src, size: exe_or_file_length);
+        let exe_or_file = from_utf16_bytes(src.read_slice(exe_or_file_length));
+
+        Ok(Self {
+            flags,
+            exec_result,
+            raw_result,
+            exe_or_file,
+        })
+    }
+}
+
+/// Client Activate PDU (TS_RAIL_ORDER_ACTIVATE), sent by the client when a window is activated or
+/// deactivated locally.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivatePdu {
+    pub window_id: u32,
+    pub enabled: bool,
+}
+
+impl ActivatePdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_ACTIVATE";
+
+    const FIXED_PART_SIZE: usize = 4 /* WindowId */ + 1 /* Enabled */;
+}
+
+impl Encode for ActivatePdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.window_id);
+        dst.write_u8(u8::from(self.enabled));
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for ActivatePdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let window_id = src.read_u32();
+        let enabled = src.read_u8() != 0;
+
+        Ok(Self { window_id, enabled })
+    }
+}
+
+/// System command (SC_* values)
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SystemCommand {
+    Size = 0xF000,
+    Move = 0xF010,
+    Minimize = 0xF020,
+    Maximize = 0xF030,
+    Close = 0xF060,
+    KeyMenu = 0xF100,
+    Restore = 0xF120,
+    Default = 0xF160,
+}
+
+impl TryFrom<u16> for SystemCommand {
+    type Error = ironrdp_core::DecodeError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0xF000 => Ok(Self::Size),
+            0xF010 => Ok(Self::Move),
+            0xF020 => Ok(Self::Minimize),
+            0xF030 => Ok(Self::Maximize),
+            0xF060 => Ok(Self::Close),
+            0xF100 => Ok(Self::KeyMenu),
+            0xF120 => Ok(Self::Restore),
+            0xF160 => Ok(Self::Default),
+            _ => Err(invalid_field_err!("Command", "unknown system command")),
+        }
+    }
+}
+
+impl From<SystemCommand> for u16 {
+    fn from(command: SystemCommand) -> Self {
+        command as u16
+    }
+}
+
+/// Client System Command PDU (TS_RAIL_ORDER_SYSCOMMAND), sent by the client when a system menu
+/// command is invoked on a window, e.g. to minimize or close it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SysCommandPdu {
+    pub window_id: u32,
+    pub command: SystemCommand,
+}
+
+impl SysCommandPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_SYSCOMMAND";
+
+    const FIXED_PART_SIZE: usize = 4 /* WindowId */ + 2 /* Command */;
+}
+
+impl Encode for SysCommandPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.window_id);
+        dst.write_u16(self.command.into());
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for SysCommandPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let window_id = src.read_u32();
+        let command = SystemCommand::try_from(src.read_u16())?;
+
+        Ok(Self { window_id, command })
+    }
+}
+
+/// Client System Menu PDU (TS_RAIL_ORDER_SYSMENU), sent by the client to show the system menu of a
+/// window, e.g. on a right click on its title bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SysMenuPdu {
+    pub window_id: u32,
+    /// Position of the menu, in screen coordinates.
+    pub left: i16,
+    pub top: i16,
+}
+
+impl SysMenuPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_SYSMENU";
+
+    const FIXED_PART_SIZE: usize = 4 /* WindowId */ + 2 /* Left */ + 2 /* Top */;
+}
+
+impl Encode for SysMenuPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.window_id);
+        dst.write_i16(self.left);
+        dst.write_i16(self.top);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for SysMenuPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let window_id = src.read_u32();
+        let left = src.read_i16();
+        let top = src.read_i16();
+
+        Ok(Self { window_id, left, top })
+    }
+}
+
+/// Notification icon message, as posted to the icon by the shell (WM_* and NIN_* values)
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NotifyIconMessage {
+    ContextMenu = 0x0000_007B,
+    LeftButtonDown = 0x0000_0201,
+    LeftButtonUp = 0x0000_0202,
+    LeftButtonDoubleClick = 0x0000_0203,
+    RightButtonDown = 0x0000_0204,
+    RightButtonUp = 0x0000_0205,
+    RightButtonDoubleClick = 0x0000_0206,
+    Select = 0x0000_0400,
+    KeySelect = 0x0000_0401,
+    BalloonShow = 0x0000_0402,
+    BalloonHide = 0x0000_0403,
+    BalloonTimeout = 0x0000_0404,
+    BalloonUserClick = 0x0000_0405,
+}
+
+impl TryFrom<u32> for NotifyIconMessage {
+    type Error = ironrdp_core::DecodeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0x0000_007B => Ok(Self::ContextMenu),
+            0x0000_0201 => Ok(Self::LeftButtonDown),
+            0x0000_0202 => Ok(Self::LeftButtonUp),
+            0x0000_0203 => Ok(Self::LeftButtonDoubleClick),
+            0x0000_0204 => Ok(Self::RightButtonDown),
+            0x0000_0205 => Ok(Self::RightButtonUp),
+            0x0000_0206 => Ok(Self::RightButtonDoubleClick),
+            0x0000_0400 => Ok(Self::Select),
+            0x0000_0401 => Ok(Self::KeySelect),
+            0x0000_0402 => Ok(Self::BalloonShow),
+            0x0000_0403 => Ok(Self::BalloonHide),
+            0x0000_0404 => Ok(Self::BalloonTimeout),
+            0x0000_0405 => Ok(Self::BalloonUserClick),
+            _ => Err(invalid_field_err!("Message", "unknown notification icon message")),
+        }
+    }
+}
+
+impl From<NotifyIconMessage> for u32 {
+    fn from(message: NotifyIconMessage) -> Self {
+        message as u32
+    }
+}
+
+/// Client Notify Event PDU (TS_RAIL_ORDER_NOTIFY_EVENT), sent by the client when the user interacts
+/// with the local representation of a notification icon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotifyEventPdu {
+    pub window_id: u32,
+    pub notify_icon_id: u32,
+    pub message: NotifyIconMessage,
+}
+
+impl NotifyEventPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_NOTIFY_EVENT";
+
+    const FIXED_PART_SIZE: usize = 4 /* WindowId */ + 4 /* NotifyIconId */ + 4 /* Message */;
+}
+
+impl Encode for NotifyEventPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.window_id);
+        dst.write_u32(self.notify_icon_id);
+        dst.write_u32(self.message.into());
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for NotifyEventPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let window_id = src.read_u32();
+        let notify_icon_id = src.read_u32();
+        let message = NotifyIconMessage::try_from(src.read_u32())?;
+
+        Ok(Self {
+            window_id,
+            notify_icon_id,
+            message,
+        })
+    }
+}
+
+/// Client Window Move PDU (TS_RAIL_ORDER_WINDOWMOVE), sent by the client at the end of a local move
+/// or resize of a window, with its new position in screen coordinates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowMovePdu {
+    pub window_id: u32,
+    pub left: i16,
+    pub top: i16,
+    pub right: i16,
+    pub bottom: i16,
+}
+
+impl WindowMovePdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_WINDOWMOVE";
+
+    const FIXED_PART_SIZE: usize = 4 /* WindowId */ + 2 /* Left */ + 2 /* Top */ + 2 /* Right */ + 2 /* Bottom */;
+}
+
+impl Encode for WindowMovePdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.window_id);
+        dst.write_i16(self.left);
+        dst.write_i16(self.top);
+        dst.write_i16(self.right);
+        dst.write_i16(self.bottom);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for WindowMovePdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let window_id = src.read_u32();
+        let left = src.read_i16();
+        let top = src.read_i16();
+        let right = src.read_i16();
+        let bottom = src.read_i16();
+
+        Ok(Self {
+            window_id,
+            left,
+            top,
+            right,
+            bottom,
+        })
+    }
+}
+
+/// Kind of local move or resize (RAIL_WMSZ_* values)
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MoveSizeType {
+    Left = 0x0001,
+    Right = 0x0002,
+    Top = 0x0003,
+    TopLeft = 0x0004,
+    TopRight = 0x0005,
+    Bottom = 0x0006,
+    BottomLeft = 0x0007,
+    BottomRight = 0x0008,
+    Move = 0x0009,
+    KeyMove = 0x000A,
+    KeySize = 0x000B,
+}
+
+impl TryFrom<u16> for MoveSizeType {
+    type Error = ironrdp_core::DecodeError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0x0001 => Ok(Self::Left),
+            0x0002 => Ok(Self::Right),
+            0x0003 => Ok(Self::Top),
+            0x0004 => Ok(Self::TopLeft),
+            0x0005 => Ok(Self::TopRight),
+            0x0006 => Ok(Self::Bottom),
+            0x0007 => Ok(Self::BottomLeft),
+            0x0008 => Ok(Self::BottomRight),
+            0x0009 => Ok(Self::Move),
+            0x000A => Ok(Self::KeyMove),
+            0x000B => Ok(Self::KeySize),
+            _ => Err(invalid_field_err!("MoveSizeType", "unknown move or size type")),
+        }
+    }
+}
+
+impl From<MoveSizeType> for u16 {
+    fn from(move_size_type: MoveSizeType) -> Self {
+        move_size_type as u16
+    }
+}
+
+/// Server Move/Size Start or End PDU (TS_RAIL_ORDER_LOCALMOVESIZE), sent by the server when a move or
+/// resize of a window starts or ends, for the client to perform it locally.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalMoveSizePdu {
+    pub window_id: u32,
+    pub is_move_size_start: bool,
+    pub move_size_type: MoveSizeType,
+    /// Position of the pointer relative to the window when starting, or the new window position in
+    /// screen coordinates when ending.
+    pub pos_x: i16,
+    pub pos_y: i16,
+}
+
+impl LocalMoveSizePdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_LOCALMOVESIZE";
+
+    const FIXED_PART_SIZE: usize =
+        4 /* WindowId */ + 2 /* IsMoveSizeStart */ + 2 /* MoveSizeType */ + 2 /* PosX */ + 2 /* PosY */;
+}
+
+impl Encode for LocalMoveSizePdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.window_id);
+        dst.write_u16(u16::from(self.is_move_size_start));
+        dst.write_u16(self.move_size_type.into());
+        dst.write_i16(self.pos_x);
+        dst.write_i16(self.pos_y);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for LocalMoveSizePdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let window_id = src.read_u32();
+        let is_move_size_start = src.read_u16() != 0;
+        let move_size_type = MoveSizeType::try_from(src.read_u16())?;
+        let pos_x = src.read_i16();
+        let pos_y = src.read_i16();
+
+        Ok(Self {
+            window_id,
+            is_move_size_start,
+            move_size_type,
+            pos_x,
+            pos_y,
+        })
+    }
+}
+
+/// Server Min Max Info PDU (TS_RAIL_ORDER_MINMAXINFO), sent by the server before a local move or
+/// resize, with the size constraints of the window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinMaxInfoPdu {
+    pub window_id: u32,
+    /// Size of the window when maximized.
+    pub max_width: i16,
+    pub max_height: i16,
+    /// Position of the window when maximized.
+    pub max_pos_x: i16,
+    pub max_pos_y: i16,
+    pub min_track_width: i16,
+    pub min_track_height: i16,
+    pub max_track_width: i16,
+    pub max_track_height: i16,
+}
+
+impl MinMaxInfoPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_MINMAXINFO";
+
+    const FIXED_PART_SIZE: usize = 4 /* WindowId */ + 8 * 2 /* sizes and positions */;
+}
+
+impl Encode for MinMaxInfoPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.window_id);
+        dst.write_i16(self.max_width);
+        dst.write_i16(self.max_height);
+        dst.write_i16(self.max_pos_x);
+        dst.write_i16(self.max_pos_y);
+        dst.write_i16(self.min_track_width);
+        dst.write_i16(self.min_track_height);
+        dst.write_i16(self.max_track_width);
+        dst.write_i16(self.max_track_height);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for MinMaxInfoPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        Ok(Self {
+            window_id: src.read_u32(),
+            max_width: src.read_i16(),
+            max_height: src.read_i16(),
+            max_pos_x: src.read_i16(),
+            max_pos_y: src.read_i16(),
+            min_track_width: src.read_i16(),
+            min_track_height: src.read_i16(),
+            max_track_width: src.read_i16(),
+            max_track_height: src.read_i16(),
+        })
+    }
+}
+
+/// Server Z-Order Sync Information PDU (TS_RAIL_ORDER_ZORDER_SYNC), sent by the server with the
+/// topmost window, once the windows orders reflect the Z-order of the remote windows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZOrderSyncPdu {
+    pub window_id_marker: u32,
+}
+
+impl ZOrderSyncPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_ZORDER_SYNC";
+
+    const FIXED_PART_SIZE: usize = 4 /* WindowIdMarker */;
+}
+
+impl Encode for ZOrderSyncPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.window_id_marker);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for ZOrderSyncPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let window_id_marker = src.read_u32();
+
+        Ok(Self { window_id_marker })
+    }
+}
+
+/// Window Cloak State Change PDU (TS_RAIL_ORDER_CLOAK), sent by the client when a window is cloaked
+/// or uncloaked locally, e.g. when moved to another virtual desktop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloakPdu {
+    pub window_id: u32,
+    pub cloaked: bool,
+}
+
+impl CloakPdu {
+    const NAME: &'static str = "TS_RAIL_ORDER_CLOAK";
+
+    const FIXED_PART_SIZE: usize = 4 /* WindowId */ + 1 /* Cloak */;
+}
+
+impl Encode for CloakPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_fixed_part_size!(in: dst);
+
+        dst.write_u32(self.window_id);
+        dst.write_u8(u8::from(self.cloaked));
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE
+    }
+}
+
+impl<'de> Decode<'de> for CloakPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        let window_id = src.read_u32();
+        let cloaked = src.read_u8() != 0;
+
+        Ok(Self { window_id, cloaked })
+    }
+}
+
+/// Returns the size of the string encoded in UTF-16, without null terminator.
+fn utf16_len(value: &str) -> usize {
+    value.encode_utf16().count() * 2
+}
+
+/// RAIL order (PDU prefixed with `TS_RAIL_PDU_HEADER`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RailPdu {
+    Handshake(HandshakePdu),
+    HandshakeEx(HandshakeExPdu),
+    ClientStatus(ClientStatusPdu),
+    LanguageBarInfo(LanguageBarInfoPdu),
+    LanguageImeInfo(LanguageImeInfoPdu),
+    CompartmentInfo(CompartmentInfoPdu),
+    Exec(ExecPdu),
+    ExecResult(ExecResultPdu),
+    Activate(ActivatePdu),
+    SysCommand(SysCommandPdu),
+    SysMenu(SysMenuPdu),
+    NotifyEvent(NotifyEventPdu),
+    WindowMove(WindowMovePdu),
+    LocalMoveSize(LocalMoveSizePdu),
+    MinMaxInfo(MinMaxInfoPdu),
+    ZOrderSync(ZOrderSyncPdu),
+    Cloak(CloakPdu),
+}
+
+impl RailPdu {
+    const NAME: &'static str = "RailPdu";
+
+    pub fn order_type(&self) -> OrderType {
+        match self {
+            Self::Handshake(_) => OrderType::HANDSHAKE,
+            Self::HandshakeEx(_) => OrderType::HANDSHAKE_EX,
+            Self::ClientStatus(_) => OrderType::CLIENTSTATUS,
+            Self::LanguageBarInfo(_) => OrderType::LANGBARINFO,
+            Self::LanguageImeInfo(_) => OrderType::LANGUAGEIMEINFO,
+            Self::CompartmentInfo(_) => OrderType::COMPARTMENTINFO,
+            Self::Exec(_) => OrderType::EXEC,
+            Self::ExecResult(_) => OrderType::EXEC_RESULT,
+            Self::Activate(_) => OrderType::ACTIVATE,
+            Self::SysCommand(_) => OrderType::SYSCOMMAND,
+            Self::SysMenu(_) => OrderType::SYSMENU,
+            Self::NotifyEvent(_) => OrderType::NOTIFY_EVENT,
+            Self::WindowMove(_) => OrderType::WINDOWMOVE,
+            Self::LocalMoveSize(_) => OrderType::LOCALMOVESIZE,
+            Self::MinMaxInfo(_) => OrderType::MINMAXINFO,
+            Self::ZOrderSync(_) => OrderType::ZORDER_SYNC,
+            Self::Cloak(_) => OrderType::CLOAK,
+        }
+    }
+
+    /// Returns true if orders of this type can be decoded into a [`RailPdu`].
+    pub fn is_supported(order_type: OrderType) -> bool {
+        matches!(
+            order_type,
+            OrderType::HANDSHAKE
+                | OrderType::HANDSHAKE_EX
+                | OrderType::CLIENTSTATUS
+                | OrderType::LANGBARINFO
+                | OrderType::LANGUAGEIMEINFO
+                | OrderType::COMPARTMENTINFO
+                | OrderType::EXEC
+                | OrderType::EXEC_RESULT
+                | OrderType::ACTIVATE
+                | OrderType::SYSCOMMAND
+                | OrderType::SYSMENU
+                | OrderType::NOTIFY_EVENT
+                | OrderType::WINDOWMOVE
+                | OrderType::LOCALMOVESIZE
+                | OrderType::MINMAXINFO
+                | OrderType::ZORDER_SYNC
+                | OrderType::CLOAK
+        )
+    }
+
+    fn body_size(&self) -> usize {
+        match self {
+            Self::Handshake(pdu) => pdu.size(),
+            Self::HandshakeEx(pdu) => pdu.size(),
+            Self::ClientStatus(pdu) => pdu.size(),
+            Self::LanguageBarInfo(pdu) => pdu.size(),
+            Self::LanguageImeInfo(pdu) => pdu.size(),
+            Self::CompartmentInfo(pdu) => pdu.size(),
+            Self::Exec(pdu) => pdu.size(),
+            Self::ExecResult(pdu) => pdu.size(),
+            Self::Activate(pdu) => pdu.size(),
+            Self::SysCommand(pdu) => pdu.size(),
+            Self::SysMenu(pdu) => pdu.size(),
+            Self::NotifyEvent(pdu) => pdu.size(),
+            Self::WindowMove(pdu) => pdu.size(),
+            Self::LocalMoveSize(pdu) => pdu.size(),
+            Self::MinMaxInfo(pdu) => pdu.size(),
+            Self::ZOrderSync(pdu) => pdu.size(),
+            Self::Cloak(pdu) => pdu.size(),
+        }
+    }
+}
+
+impl Encode for RailPdu {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        RailPduHeader {
+            order_type: self.order_type(),
+            order_length: cast_length!("RailPdu::orderLength", self.size())?,
+        }
+        .encode(dst)?;
+
+        match self {
+            Self::Handshake(pdu) => pdu.encode(dst),
+            Self::HandshakeEx(pdu) => pdu.encode(dst),
+            Self::ClientStatus(pdu) => pdu.encode(dst),
+            Self::LanguageBarInfo(pdu) => pdu.encode(dst),
+            Self::LanguageImeInfo(pdu) => pdu.encode(dst),
+            Self::CompartmentInfo(pdu) => pdu.encode(dst),
+            Self::Exec(pdu) => pdu.encode(dst),
+            Self::ExecResult(pdu) => pdu.encode(dst),
+            Self::Activate(pdu) => pdu.encode(dst),
+            Self::SysCommand(pdu) => pdu.encode(dst),
+            Self::SysMenu(pdu) => pdu.encode(dst),
+            Self::NotifyEvent(pdu) => pdu.encode(dst),
+            Self::WindowMove(pdu) => pdu.encode(dst),
+            Self::LocalMoveSize(pdu) => pdu.encode(dst),
+            Self::MinMaxInfo(pdu) => pdu.encode(dst),
+            Self::ZOrderSync(pdu) => pdu.encode(dst),
+            Self::Cloak(pdu) => pdu.encode(dst),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        RailPduHeader::FIXED_PART_SIZE
+            .checked_add(self.body_size())
+            .expect("never overflow")
+    }
+}
+
+impl<'de> Decode<'de> for RailPdu {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        let header = RailPduHeader::decode(src)?;
+
+        match header.order_type {
+            OrderType::HANDSHAKE => Ok(Self::Handshake(HandshakePdu::decode(src)?)),
+            OrderType::HANDSHAKE_EX => Ok(Self::HandshakeEx(HandshakeExPdu::decode(src)?)),
+            OrderType::CLIENTSTATUS => Ok(Self::ClientStatus(ClientStatusPdu::decode(src)?)),
+            OrderType::LANGBARINFO => Ok(Self::LanguageBarInfo(LanguageBarInfoPdu::decode(src)?)),
+            OrderType::LANGUAGEIMEINFO => Ok(Self::LanguageImeInfo(LanguageImeInfoPdu::decode(src)?)),
+            OrderType::COMPARTMENTINFO => Ok(Self::CompartmentInfo(CompartmentInfoPdu::decode(src)?)),
+            OrderType::EXEC => Ok(Self::Exec(ExecPdu::decode(src)?)),
+            OrderType::EXEC_RESULT => Ok(Self::ExecResult(ExecResultPdu::decode(src)?)),
+            OrderType::ACTIVATE => Ok(Self::Activate(ActivatePdu::decode(src)?)),
+            OrderType::SYSCOMMAND => Ok(Self::SysCommand(SysCommandPdu::decode(src)?)),
+            OrderType::SYSMENU => Ok(Self::SysMenu(SysMenuPdu::decode(src)?)),
+            OrderType::NOTIFY_EVENT => Ok(Self::NotifyEvent(NotifyEventPdu::decode(src)?)),
+            OrderType::WINDOWMOVE => Ok(Self::WindowMove(WindowMovePdu::decode(src)?)),
+            OrderType::LOCALMOVESIZE => Ok(Self::LocalMoveSize(LocalMoveSizePdu::decode(src)?)),
+            OrderType::MINMAXINFO => Ok(Self::MinMaxInfo(MinMaxInfoPdu::decode(src)?)),
+            OrderType::ZORDER_SYNC => Ok(Self::ZOrderSync(ZOrderSyncPdu::decode(src)?)),
+            OrderType::CLOAK => Ok(Self::Cloak(CloakPdu::decode(src)?)),
+            _ => Err(invalid_field_err!("RailPdu::orderType", "unsupported RAIL order type")),
+        }
+    }
+}
+
+impl SvcEncode for RailPdu {}
@@ -0,0 +1,616 @@
+//! Windowing Alternate Secondary Drawing Orders \[MS-RDPERP\] 2.2.1.3, sent by the server in the
+//! fast-path orders updates to describe the windows of the remote applications.
+//!
+//! Only the window and notification icon orders are covered, without the icons, the resize margins
+//! and the taskbar related fields.
+
+use bitflags::bitflags;
+use ironrdp_core::{
+    cast_length, ensure_fixed_part_size, ensure_size, invalid_field_err, Decode, DecodeResult, Encode, EncodeResult,
+    ReadCursor, WriteCursor,
+};
+use ironrdp_pdu::geometry::ExclusiveRectangle;
+use ironrdp_pdu::utils::{from_utf16_bytes, to_utf16_bytes};
+
+bitflags! {
+    /// Fields present in a window order (`FieldsPresentFlags` field of `TS_WINDOW_ORDER_HEADER`)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct WindowOrderFlags: u32 {
+        const FIELD_NOTIFY_TIP = 0x0000_0001;
+        const FIELD_NOTIFY_INFO_TIP = 0x0000_0002;
+        const FIELD_NOTIFY_STATE = 0x0000_0004;
+        const FIELD_NOTIFY_VERSION = 0x0000_0008;
+        const FIELD_OWNER = 0x0000_0002;
+        const FIELD_TITLE = 0x0000_0004;
+        const FIELD_STYLE = 0x0000_0008;
+        const FIELD_SHOW = 0x0000_0010;
+        const FIELD_RESIZE_MARGIN_X = 0x0000_0080;
+        const FIELD_WNDRECTS = 0x0000_0100;
+        const FIELD_VISIBILITY = 0x0000_0200;
+        const FIELD_WNDSIZE = 0x0000_0400;
+        const FIELD_WNDOFFSET = 0x0000_0800;
+        const FIELD_VISOFFSET = 0x0000_1000;
+        const FIELD_CLIENTAREAOFFSET = 0x0000_4000;
+        const FIELD_WNDCLIENTDELTA = 0x0000_8000;
+        const FIELD_CLIENTAREASIZE = 0x0001_0000;
+        const FIELD_RP_CONTENT = 0x0002_0000;
+        const FIELD_ROOTPARENT = 0x0004_0000;
+        const TYPE_WINDOW = 0x0100_0000;
+        const TYPE_NOTIFY = 0x0200_0000;
+        const FIELD_RESIZE_MARGIN_Y = 0x0800_0000;
+        const STATE_NEW = 0x1000_0000;
+        const STATE_DELETED = 0x2000_0000;
+        const ICON = 0x4000_0000;
+        const CACHED_ICON = 0x8000_0000;
+    }
+}
+
+impl WindowOrderFlags {
+    /// Fields of the window information order supported by [`WindowInfo`].
+    const SUPPORTED_FIELDS: Self = Self::FIELD_OWNER
+        .union(Self::FIELD_TITLE)
+        .union(Self::FIELD_STYLE)
+        .union(Self::FIELD_SHOW)
+        .union(Self::FIELD_WNDRECTS)
+        .union(Self::FIELD_VISIBILITY)
+        .union(Self::FIELD_WNDSIZE)
+        .union(Self::FIELD_WNDOFFSET)
+        .union(Self::FIELD_VISOFFSET)
+        .union(Self::FIELD_CLIENTAREAOFFSET)
+        .union(Self::FIELD_WNDCLIENTDELTA)
+        .union(Self::FIELD_CLIENTAREASIZE)
+        .union(Self::FIELD_ROOTPARENT);
+
+    /// Fields of the notification icon order supported by [`NotifyIconInfo`].
+    const SUPPORTED_NOTIFY_FIELDS: Self = Self::FIELD_NOTIFY_TIP
+        .union(Self::FIELD_NOTIFY_INFO_TIP)
+        .union(Self::FIELD_NOTIFY_STATE)
+        .union(Self::FIELD_NOTIFY_VERSION);
+}
+
+/// Window styles (WS_* and WS_EX_* values)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WindowStyle {
+    pub style: u32,
+    pub extended_style: u32,
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShowState {
+    Hide = 0x00,
+    Minimized = 0x02,
+    Maximized = 0x03,
+    Show = 0x05,
+}
+
+impl TryFrom<u8> for ShowState {
+    type Error = ironrdp_core::DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Self::Hide),
+            0x02 => Ok(Self::Minimized),
+            0x03 => Ok(Self::Maximized),
+            0x05 => Ok(Self::Show),
+            _ => Err(invalid_field_err!("ShowState", "unknown show state")),
+        }
+    }
+}
+
+impl From<ShowState> for u8 {
+    fn from(state: ShowState) -> Self {
+        state as u8
+    }
+}
+
+/// Properties of a window, only the fields which are set are sent to the client.
+///
+/// Coordinates are in the coordinate space of the remote desktop.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WindowInfo {
+    pub owner_window_id: Option<u32>,
+    pub style: Option<WindowStyle>,
+    pub show_state: Option<ShowState>,
+    pub title: Option<String>,
+    /// Position of the client area, in screen coordinates.
+    pub client_offset: Option<(i32, i32)>,
+    /// Size of the client area, as (width, height).
+    pub client_area_size: Option<(u32, u32)>,
+    pub root_parent_id: Option<u32>,
+    /// Position of the window, in screen coordinates.
+    pub window_offset: Option<(i32, i32)>,
+    /// Offset of the client area from the window position.
+    pub window_client_delta: Option<(i32, i32)>,
+    /// Size of the window, as (width, height).
+    pub window_size: Option<(u32, u32)>,
+    /// Shape of the window, relative to the window position.
+    pub window_rects: Option<Vec<ExclusiveRectangle>>,
+    /// Position of the visible region, in screen coordinates.
+    pub visible_offset: Option<(i32, i32)>,
+    /// Visible region of the window, relative to the visible offset.
+    pub visibility_rects: Option<Vec<ExclusiveRectangle>>,
+}
+
+impl WindowInfo {
+    fn fields(&self) -> WindowOrderFlags {
+        let mut flags = WindowOrderFlags::empty();
+        flags.set(WindowOrderFlags::FIELD_OWNER, self.owner_window_id.is_some());
+        flags.set(WindowOrderFlags::FIELD_STYLE, self.style.is_some());
+        flags.set(WindowOrderFlags::FIELD_SHOW, self.show_state.is_some());
+        flags.set(WindowOrderFlags::FIELD_TITLE, self.title.is_some());
+        flags.set(WindowOrderFlags::FIELD_CLIENTAREAOFFSET, self.client_offset.is_some());
+        flags.set(WindowOrderFlags::FIELD_CLIENTAREASIZE, self.client_area_size.is_some());
+        flags.set(WindowOrderFlags::FIELD_ROOTPARENT, self.root_parent_id.is_some());
+        flags.set(WindowOrderFlags::FIELD_WNDOFFSET, self.window_offset.is_some());
+        flags.set(
+            WindowOrderFlags::FIELD_WNDCLIENTDELTA,
+            self.window_client_delta.is_some(),
+        );
+        flags.set(WindowOrderFlags::FIELD_WNDSIZE, self.window_size.is_some());
+        flags.set(WindowOrderFlags::FIELD_WNDRECTS, self.window_rects.is_some());
+        flags.set(WindowOrderFlags::FIELD_VISOFFSET, self.visible_offset.is_some());
+        flags.set(WindowOrderFlags::FIELD_VISIBILITY, self.visibility_rects.is_some());
+        flags
+    }
+
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        // The fields are ordered as defined by the Window Information Order.
+        if let Some(owner_window_id) = self.owner_window_id {
+            dst.write_u32(owner_window_id);
+        }
+        if let Some(style) = self.style {
+            dst.write_u32(style.style);
+            dst.write_u32(style.extended_style);
+        }
+        if let Some(show_state) = self.show_state {
+            dst.write_u8(show_state.into());
+        }
+        if let Some(title) = &self.title {
+            let title = to_utf16_bytes(title);
+            dst.write_u16(cast_length!("CbString", title.len())?);
+            dst.write_slice(&title);
+        }
+        if let Some((x, y)) = self.client_offset {
+            dst.write_i32(x);
+            dst.write_i32(y);
+        }
+        if let Some((width, height)) = self.client_area_size {
+            dst.write_u32(width);
+            dst.write_u32(height);
+        }
+        if let Some(root_parent_id) = self.root_parent_id {
+            dst.write_u32(root_parent_id);
+        }
+        if let Some((x, y)) = self.window_offset {
+            dst.write_i32(x);
+            dst.write_i32(y);
+        }
+        if let Some((x, y)) = self.window_client_delta {
+            dst.write_i32(x);
+            dst.write_i32(y);
+        }
+        if let Some((width, height)) = self.window_size {
+            dst.write_u32(width);
+            dst.write_u32(height);
+        }
+        if let Some(rects) = &self.window_rects {
+            encode_rects("NumWindowRects", rects, dst)?;
+        }
+        if let Some((x, y)) = self.visible_offset {
+            dst.write_i32(x);
+            dst.write_i32(y);
+        }
+        if let Some(rects) = &self.visibility_rects {
+            encode_rects("NumVisibilityRects", rects, dst)?;
+        }
+
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        let point_size = 4 /* X */ + 4 /* Y */;
+
+        self.owner_window_id.map_or(0, |_| 4)
+            + self.style.map_or(0, |_| 4 /* Style */ + 4 /* ExtendedStyle */)
+            + self.show_state.map_or(0, |_| 1)
+            + self
+                .title
+                .as_ref()
+                .map_or(0, |title| 2 /* CbString */ + title.encode_utf16().count() * 2)
+            + self.client_offset.map_or(0, |_| point_size)
+            + self.client_area_size.map_or(0, |_| point_size)
+            + self.root_parent_id.map_or(0, |_| 4)
+            + self.window_offset.map_or(0, |_| point_size)
+            + self.window_client_delta.map_or(0, |_| point_size)
+            + self.window_size.map_or(0, |_| point_size)
+            + self.window_rects.as_ref().map_or(0, |rects| rects_size(rects))
+            + self.visible_offset.map_or(0, |_| point_size)
+            + self.visibility_rects.as_ref().map_or(0, |rects| rects_size(rects))
+    }
+
+    fn decode(fields: WindowOrderFlags, src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        let mut info = Self::default();
+
+        if fields.contains(WindowOrderFlags::FIELD_OWNER) {
+            ensure_size!(in: src, size: 4);
+            info.owner_window_id = Some(src.read_u32());
+        }
+        if fields.contains(WindowOrderFlags::FIELD_STYLE) {
+            ensure_size!(in: src, size: 8);
+            info.style = Some(WindowStyle {
+                style: src.read_u32(),
+                extended_style: src.read_u32(),
+            });
+        }
+        if fields.contains(WindowOrderFlags::FIELD_SHOW) {
+            ensure_size!(in: src, size: 1);
+            info.show_state = Some(ShowState::try_from(src.read_u8())?);
+        }
+        if fields.contains(WindowOrderFlags::FIELD_TITLE) {
+            ensure_size!(in: src, size: 2);
+            let length = usize::from(src.read_u16());
+            ensure_size!(in: src, size: length);
+            info.title = Some(from_utf16_bytes(src.read_slice(length)));
+        }
+        if fields.contains(WindowOrderFlags::FIELD_CLIENTAREAOFFSET) {
+            ensure_size!(in: src, size: 8);
+            info.client_offset = Some((src.read_i32(), src.read_i32()));
+        }
+        if fields.contains(WindowOrderFlags::FIELD_CLIENTAREASIZE) {
+            ensure_size!(in: src, size: 8);
+            info.client_area_size = Some((src.read_u32(), src.read_u32()));
+        }
+        if fields.contains(WindowOrderFlags::FIELD_ROOTPARENT) {
+            ensure_size!(in: src, size: 4);
+            info.root_parent_id = Some(src.read_u32());
+        }
+        if fields.contains(WindowOrderFlags::FIELD_WNDOFFSET) {
+            ensure_size!(in: src, size: 8);
+            info.window_offset = Some((src.read_i32(), src.read_i32()));
+        }
+        if fields.contains(WindowOrderFlags::FIELD_WNDCLIENTDELTA) {
+            ensure_size!(in: src, size: 8);
+            info.window_client_delta = Some((src.read_i32(), src.read_i32()));
+        }
+        if fields.contains(WindowOrderFlags::FIELD_WNDSIZE) {
+            ensure_size!(in: src, size: 8);
+            info.window_size = Some((src.read_u32(), src.read_u32()));
+        }
+        if fields.contains(WindowOrderFlags::FIELD_WNDRECTS) {
+            info.window_rects = Some(decode_rects(src)?);
+        }
+        if fields.contains(WindowOrderFlags::FIELD_VISOFFSET) {
+            ensure_size!(in: src, size: 8);
+            info.visible_offset = Some((src.read_i32(), src.read_i32()));
+        }
+        if fields.contains(WindowOrderFlags::FIELD_VISIBILITY) {
+            info.visibility_rects = Some(decode_rects(src)?);
+        }
+
+        Ok(info)
+    }
+}
+
+/// Balloon tooltip of a notification icon (TS_NOTIFY_ICON_INFOTIP)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NotifyIconInfoTip {
+    /// Timeout of the balloon, in milliseconds.
+    pub timeout: u32,
+    /// Icon and sound of the balloon (NIIF_* values).
+    pub info_flags: u32,
+    pub text: String,
+    pub title: String,
+}
+
+/// Properties of a notification icon, only the fields which are set are sent to the client.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NotifyIconInfo {
+    /// Behavior of the icon (NOTIFYICON_VERSION* values).
+    pub version: Option<u32>,
+    pub tooltip: Option<String>,
+    pub info_tip: Option<NotifyIconInfoTip>,
+    /// State of the icon (1 when hidden).
+    pub state: Option<u32>,
+}
+
+impl NotifyIconInfo {
+    fn fields(&self) -> WindowOrderFlags {
+        let mut flags = WindowOrderFlags::empty();
+        flags.set(WindowOrderFlags::FIELD_NOTIFY_VERSION, self.version.is_some());
+        flags.set(WindowOrderFlags::FIELD_NOTIFY_TIP, self.tooltip.is_some());
+        flags.set(WindowOrderFlags::FIELD_NOTIFY_INFO_TIP, self.info_tip.is_some());
+        flags.set(WindowOrderFlags::FIELD_NOTIFY_STATE, self.state.is_some());
+        flags
+    }
+
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        // The fields are ordered as defined by the Notification Icon Information Order.
+        if let Some(version) = self.version {
+            dst.write_u32(version);
+        }
+        if let Some(tooltip) = &self.tooltip {
+            encode_string("ToolTip", tooltip, dst)?;
+        }
+        if let Some(info_tip) = &self.info_tip {
+            dst.write_u32(info_tip.timeout);
+            dst.write_u32(info_tip.info_flags);
+            encode_string("InfoTipText", &info_tip.text, dst)?;
+            encode_string("Title", &info_tip.title, dst)?;
+        }
+        if let Some(state) = self.state {
+            dst.write_u32(state);
+        }
+
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.version.map_or(0, |_| 4)
+            + self.tooltip.as_deref().map_or(0, string_size)
+            + self.info_tip.as_ref().map_or(0, |info_tip| {
+                4 /* Timeout */ + 4 /* InfoFlags */ + string_size(&info_tip.text) + string_size(&info_tip.title)
+            })
+            + self.state.map_or(0, |_| 4)
+    }
+
+    fn decode(fields: WindowOrderFlags, src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        let mut info = Self::default();
+
+        if fields.contains(WindowOrderFlags::FIELD_NOTIFY_VERSION) {
+            ensure_size!(in: src, size: 4);
+            info.version = Some(src.read_u32());
+        }
+        if fields.contains(WindowOrderFlags::FIELD_NOTIFY_TIP) {
+            info.tooltip = Some(decode_string(src)?);
+        }
+        if fields.contains(WindowOrderFlags::FIELD_NOTIFY_INFO_TIP) {
+            ensure_size!(in: src, size: 8);
+            let timeout = src.read_u32();
+            let info_flags = src.read_u32();
+            info.info_tip = Some(NotifyIconInfoTip {
+                timeout,
+                info_flags,
+                text: decode_string(src)?,
+                title: decode_string(src)?,
+            });
+        }
+        if fields.contains(WindowOrderFlags::FIELD_NOTIFY_STATE) {
+            ensure_size!(in: src, size: 4);
+            info.state = Some(src.read_u32());
+        }
+
+        Ok(info)
+    }
+}
+
+/// UNICODE_STRING
+fn encode_string(name: &'static str, value: &str, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+    let value = to_utf16_bytes(value);
+    dst.write_u16(cast_length!(name, value.len())?);
+    dst.write_slice(&value);
+
+    Ok(())
+}
+
+fn string_size(value: &str) -> usize {
+    2 /* CbString */ + value.encode_utf16().count() * 2
+}
+
+fn decode_string(src: &mut ReadCursor<'_>) -> DecodeResult<String> {
+    ensure_size!(in: src, size: 2);
+    let length = usize::from(src.read_u16());
+    ensure_size!(in: src, size: length);
+
+    Ok(from_utf16_bytes(src.read_slice(length)))
+}
+
+fn encode_rects(name: &'static str, rects: &[ExclusiveRectangle], dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+    dst.write_u16(cast_length!(name, rects.len())?);
+    for rect in rects {
+        rect.encode(dst)?;
+    }
+
+    Ok(())
+}
+
+fn rects_size(rects: &[ExclusiveRectangle]) -> usize {
+    2 /* NumRects */ + rects.len() * ExclusiveRectangle::ENCODED_SIZE
+}
+
+fn decode_rects(src: &mut ReadCursor<'_>) -> DecodeResult<Vec<ExclusiveRectangle>> {
+    ensure_size!(in: src, size: 2);
+    let count = usize::from(src.read_u16());
+
+    ensure_size!(in: src, size: count * ExclusiveRectangle::ENCODED_SIZE);
+    (0..count).map(|_| ExclusiveRectangle::decode(src)).collect()
+}
+
+/// Window order (Window Information, Deleted Window, Notification Icon Information or Deleted
+/// Notification Icon order)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowOrder {
+    /// Creates a window when `new` is set, or updates the properties of an existing window.
+    Info {
+        window_id: u32,
+        new: bool,
+        info: WindowInfo,
+    },
+    Deleted {
+        window_id: u32,
+    },
+    /// Creates a notification icon of a window when `new` is set, or updates the properties of an
+    /// existing one.
+    NotifyIcon {
+        window_id: u32,
+        notify_icon_id: u32,
+        new: bool,
+        info: NotifyIconInfo,
+    },
+    NotifyIconDeleted {
+        window_id: u32,
+        notify_icon_id: u32,
+    },
+}
+
+impl WindowOrder {
+    const NAME: &'static str = "TS_WINDOW_ORDER";
+
+    /// `TS_SECONDARY` with the `TS_ALTSEC_WINDOW` order type.
+    const CONTROL_FLAGS: u8 = 0x02 | (0x0B << 2);
+
+    const FIXED_PART_SIZE: usize = 1 /* ControlFlags */
+        + 2 /* OrderSize */
+        + 4 /* FieldsPresentFlags */
+        + 4 /* WindowId */;
+
+    const NOTIFY_FIXED_PART_SIZE: usize = Self::FIXED_PART_SIZE + 4 /* NotifyIconId */;
+
+    pub fn window_id(&self) -> u32 {
+        match self {
+            Self::Info { window_id, .. }
+            | Self::Deleted { window_id }
+            | Self::NotifyIcon { window_id, .. }
+            | Self::NotifyIconDeleted { window_id, .. } => *window_id,
+        }
+    }
+
+    /// Returns the ID of the notification icon, for the notification icon orders.
+    pub fn notify_icon_id(&self) -> Option<u32> {
+        match self {
+            Self::Info { .. } | Self::Deleted { .. } => None,
+            Self::NotifyIcon { notify_icon_id, .. } | Self::NotifyIconDeleted { notify_icon_id, .. } => {
+                Some(*notify_icon_id)
+            }
+        }
+    }
+
+    fn fields(&self) -> WindowOrderFlags {
+        match self {
+            Self::Info { new, info, .. } => {
+                let mut flags = WindowOrderFlags::TYPE_WINDOW | info.fields();
+                flags.set(WindowOrderFlags::STATE_NEW, *new);
+                flags
+            }
+            Self::Deleted { .. } => WindowOrderFlags::TYPE_WINDOW | WindowOrderFlags::STATE_DELETED,
+            Self::NotifyIcon { new, info, .. } => {
+                let mut flags = WindowOrderFlags::TYPE_NOTIFY | info.fields();
+                flags.set(WindowOrderFlags::STATE_NEW, *new);
+                flags
+            }
+            Self::NotifyIconDeleted { .. } => WindowOrderFlags::TYPE_NOTIFY | WindowOrderFlags::STATE_DELETED,
+        }
+    }
+}
+
+impl Encode for WindowOrder {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+
+        dst.write_u8(Self::CONTROL_FLAGS);
+        dst.write_u16(cast_length!("OrderSize", self.size())?);
+        dst.write_u32(self.fields().bits());
+        dst.write_u32(self.window_id());
+
+        if let Some(notify_icon_id) = self.notify_icon_id() {
+            dst.write_u32(notify_icon_id);
+        }
+
+        match self {
+            Self::Info { info, .. } => info.encode(dst)?,
+            Self::NotifyIcon { info, .. } => info.encode(dst)?,
+            Self::Deleted { .. } | Self::NotifyIconDeleted { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Self::Info { info, .. } => Self::FIXED_PART_SIZE + info.size(),
+            Self::Deleted { .. } => Self::FIXED_PART_SIZE,
+            Self::NotifyIcon { info, .. } => Self::NOTIFY_FIXED_PART_SIZE + info.size(),
+            Self::NotifyIconDeleted { .. } => Self::NOTIFY_FIXED_PART_SIZE,
+        }
+    }
+}
+
+impl<'de> Decode<'de> for WindowOrder {
+    fn decode(src: &mut ReadCursor<'de>) -> DecodeResult<Self> {
+        ensure_fixed_part_size!(in: src);
+
+        if src.read_u8() != Self::CONTROL_FLAGS {
+            return Err(invalid_field_err!("ControlFlags", "not a window order"));
+        }
+        let _order_size = src.read_u16();
+        let fields = WindowOrderFlags::from_bits_retain(src.read_u32());
+        let window_id = src.read_u32();
+
+        if fields.intersects(WindowOrderFlags::ICON | WindowOrderFlags::CACHED_ICON) {
+            return Err(invalid_field_err!("FieldsPresentFlags", "unsupported window order"));
+        }
+
+        if fields.contains(WindowOrderFlags::TYPE_NOTIFY) {
+            return Self::decode_notify_icon(fields, window_id, src);
+        }
+
+        if !fields.contains(WindowOrderFlags::TYPE_WINDOW) {
+            return Err(invalid_field_err!("FieldsPresentFlags", "unsupported window order"));
+        }
+
+        if fields.contains(WindowOrderFlags::STATE_DELETED) {
+            return Ok(Self::Deleted { window_id });
+        }
+
+        let unsupported =
+            fields - WindowOrderFlags::SUPPORTED_FIELDS - WindowOrderFlags::TYPE_WINDOW - WindowOrderFlags::STATE_NEW;
+        if !unsupported.is_empty() {
+            return Err(invalid_field_err!(
+                "FieldsPresentFlags",
+                "unsupported window order field"
+            ));
+        }
+
+        Ok(Self::Info {
+            window_id,
+            new: fields.contains(WindowOrderFlags::STATE_NEW),
+            info: WindowInfo::decode(fields, src)?,
+        })
+    }
+}
+
+impl WindowOrder {
+    fn decode_notify_icon(fields: WindowOrderFlags, window_id: u32, src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_size!(in: src, size: 4);
+        let notify_icon_id = src.read_u32();
+
+        if fields.contains(WindowOrderFlags::STATE_DELETED) {
+            return Ok(Self::NotifyIconDeleted {
+                window_id,
+                notify_icon_id,
+            });
+        }
+
+        let unsupported = fields
+            - WindowOrderFlags::SUPPORTED_NOTIFY_FIELDS
+            - WindowOrderFlags::TYPE_NOTIFY
+            - WindowOrderFlags::STATE_NEW;
+        if !unsupported.is_empty() {
+            return Err(invalid_field_err!(
+                "FieldsPresentFlags",
+                "unsupported notification icon order field"
+            ));
+        }
+
+        Ok(Self::NotifyIcon {
+            window_id,
+            notify_icon_id,
+            new: fields.contains(WindowOrderFlags::STATE_NEW),
+            info: NotifyIconInfo::decode(fields, src)?,
+        })
+    }
+}
@@ -0,0 +1,142 @@
+use ironrdp_core::{impl_as_any, Decode, ReadCursor};
+use ironrdp_pdu::gcc::ChannelName;
+use ironrdp_pdu::{decode_err, PduResult};
+use ironrdp_svc::{CompressionCondition, SvcMessage, SvcProcessor, SvcServerProcessor};
+use tracing::{debug, error};
+
+use crate::pdu::{
+    ActivatePdu, ClientStatusFlags, ExecPdu, ExecResult, ExecResultPdu, HandshakePdu, RailPdu, RailPduHeader,
+    SysCommandPdu,
+};
+
+/// Build number advertised in the server Handshake PDU (Windows 7).
+const SERVER_BUILD_NUMBER: u32 = 7601;
+
+pub trait RailServerHandler: Send + core::fmt::Debug {
+    /// Called when the RAIL handshake is completed, with the flags of the Client Information PDU.
+    fn ready(&mut self, client_status: ClientStatusFlags) {
+        let _ = client_status;
+    }
+
+    /// Called when the client requests the launch of a remote application.
+    ///
+    /// The windows of the application are described to the client using the window orders.
+    fn exec(&mut self, pdu: &ExecPdu) -> ExecResult;
+
+    /// Called when a window is activated or deactivated by the client.
+    fn activate(&mut self, pdu: ActivatePdu) {
+        let _ = pdu;
+    }
+
+    /// Called when a system command (e.g. minimize or close) is invoked on a window by the client.
+    fn system_command(&mut self, pdu: SysCommandPdu) {
+        let _ = pdu;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum RailState {
+    WaitingForHandshake,
+    Ready,
+}
+
+/// RAIL static virtual channel server, as described in \[MS-RDPERP\].
+///
+/// Only the handshake, the launch of remote applications, and the activation and system commands
+/// of the windows are currently processed, other orders are ignored.
+#[derive(Debug)]
+pub struct RailServer {
+    handler: Box<dyn RailServerHandler>,
+    state: RailState,
+}
+
+impl RailServer {
+    pub const NAME: ChannelName = ChannelName::from_static(b"rail\0\0\0\0");
+
+    pub fn new(handler: Box<dyn RailServerHandler>) -> Self {
+        Self {
+            handler,
+            state: RailState::WaitingForHandshake,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.state == RailState::Ready
+    }
+
+    fn exec(&mut self, pdu: ExecPdu) -> Vec<SvcMessage> {
+        let exec_result = self.handler.exec(&pdu);
+        debug!(?pdu, ?exec_result, "Remote application launch");
+
+        vec![RailPdu::ExecResult(ExecResultPdu {
+            flags: pdu.flags,
+            exec_result,
+            raw_result: 0,
+            exe_or_file: pdu.exe_or_file,
+        })
+        .into()]
+    }
+}
+
+impl_as_any!(RailServer);
+
+impl SvcProcessor for RailServer {
+    fn channel_name(&self) -> ChannelName {
+        Self::NAME
+    }
+
+    fn compression_condition(&self) -> CompressionCondition {
+        CompressionCondition::WhenRdpDataIsCompressed
+    }
+
+    fn start(&mut self) -> PduResult<Vec<SvcMessage>> {
+        Ok(vec![RailPdu::Handshake(HandshakePdu {
+            build_number: SERVER_BUILD_NUMBER,
+        })
+        .into()])
+    }
+
+    fn process(&mut self, payload: &[u8]) -> PduResult<Vec<SvcMessage>> {
+        let header = RailPduHeader::decode(&mut ReadCursor::new(payload)).map_err(|e| decode_err!(e))?;
+
+        if !RailPdu::is_supported(header.order_type) {
+            debug!(order_type = ?header.order_type, "Ignoring unsupported RAIL order");
+            return Ok(Vec::new());
+        }
+
+        let pdu = RailPdu::decode(&mut ReadCursor::new(payload)).map_err(|e| decode_err!(e))?;
+
+        debug!(?pdu, ?self.state);
+        let msgs = match (self.state, pdu) {
+            (RailState::WaitingForHandshake, RailPdu::Handshake(_) | RailPdu::HandshakeEx(_)) => {
+                self.state = RailState::Ready;
+                Vec::new()
+            }
+            (RailState::WaitingForHandshake, pdu) => {
+                error!(?pdu, "Unexpected RAIL order before handshake");
+                Vec::new()
+            }
+            (RailState::Ready, RailPdu::ClientStatus(pdu)) => {
+                self.handler.ready(pdu.flags);
+                Vec::new()
+            }
+            (RailState::Ready, RailPdu::Exec(pdu)) => self.exec(pdu),
+            (RailState::Ready, RailPdu::Activate(pdu)) => {
+                self.handler.activate(pdu);
+                Vec::new()
+            }
+            (RailState::Ready, RailPdu::SysCommand(pdu)) => {
+                self.handler.system_command(pdu);
+                Vec::new()
+            }
+            (RailState::Ready, pdu) => {
+                debug!(?pdu, "Ignoring unexpected RAIL order");
+                Vec::new()
+            }
+        };
+
+        Ok(msgs)
+    }
+}
+
+impl SvcServerProcessor for RailServer {}
@@ -16,4 +16,4 @@ extern crate tracing;
 #[cfg(any(target_os = "macos", target_os = "linux"))]
 mod nix;
 #[cfg(any(target_os = "macos", target_os = "linux"))]
-pub use nix::backend;
+pub use nix::{backend, printer};
@@ -5,6 +5,7 @@ use std::os::unix::fs::MetadataExt;
 
 use ironrdp_core::impl_as_any;
 use ironrdp_pdu::{encode_err, PduResult};
+use ironrdp_rdpdr::backend::printer_io_error;
 use ironrdp_rdpdr::pdu::efs::*;
 use ironrdp_rdpdr::pdu::esc::{ScardCall, ScardIoCtlCode};
 use ironrdp_rdpdr::pdu::RdpdrPdu;
@@ -12,6 +13,8 @@ use ironrdp_rdpdr::RdpdrBackend;
 use ironrdp_svc::SvcMessage;
 use nix::dir::{Dir, OwningIter};
 
+use crate::printer::PdfPrinter;
+
 #[derive(Debug, Default)]
 pub struct NixRdpdrBackend {
     file_id: u32,
@@ -19,6 +22,7 @@ pub struct NixRdpdrBackend {
     file_map: std::collections::HashMap<u32, std::fs::File>,
     file_path_map: std::collections::HashMap<u32, String>,
     file_dir_map: std::collections::HashMap<u32, OwningIter>,
+    printer: Option<PdfPrinter>,
 }
 
 impl NixRdpdrBackend {
@@ -28,6 +32,13 @@ impl NixRdpdrBackend {
             ..Default::default()
         }
     }
+
+    /// Handles the print jobs of redirected printers using the given [`PdfPrinter`].
+    #[must_use]
+    pub fn with_printer(mut self, printer: PdfPrinter) -> Self {
+        self.printer = Some(printer);
+        self
+    }
 }
 
 impl_as_any!(NixRdpdrBackend);
@@ -68,6 +79,15 @@ impl RdpdrBackend for NixRdpdrBackend {
             }
         }
     }
+    fn handle_printer_io_request(&mut self, req: ServerPrinterIoRequest) -> PduResult<Vec<SvcMessage>> {
+        match self.printer.as_mut() {
+            Some(printer) => printer.handle_io_request(req),
+            None => {
+                warn!(?req, "No printer backend configured");
+                Ok(vec![printer_io_error(req, NtStatus::NOT_SUPPORTED)])
+            }
+        }
+    }
 }
 
 pub(crate) fn write_device(backend: &mut NixRdpdrBackend, req_inner: DeviceWriteRequest) -> PduResult<Vec<SvcMessage>> {
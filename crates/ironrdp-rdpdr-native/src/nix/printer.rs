@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ironrdp_core::impl_as_any;
+use ironrdp_pdu::PduResult;
+use ironrdp_rdpdr::pdu::efs::*;
+use ironrdp_rdpdr::pdu::esc::{ScardCall, ScardIoCtlCode};
+use ironrdp_rdpdr::pdu::RdpdrPdu;
+use ironrdp_rdpdr::RdpdrBackend;
+use ironrdp_svc::SvcMessage;
+
+/// Printer driver producing PDF documents, shipped with Windows 10 and Windows Server 2016 onwards.
+pub const PDF_DRIVER_NAME: &str = "Microsoft Print To PDF";
+
+/// Magic bytes starting a PDF document.
+const PDF_MAGIC: &[u8] = b"%PDF-";
+
+/// Print job backend writing each job as a PDF file into a directory.
+///
+/// The printer must be announced using the [`PDF_DRIVER_NAME`] driver (see [`PdfPrinter::device_data`]),
+/// so the server renders the jobs as PDF documents before sending them. No spooler is involved on the
+/// client side: a job is written to a `.part` file while it is received, and renamed to `.pdf` once
+/// the server closes it.
+///
+/// This type can be used as a standalone [`RdpdrBackend`] when only printer redirection is needed, or
+/// combined with drive redirection using [`NixRdpdrBackend::with_printer`](crate::backend::NixRdpdrBackend::with_printer).
+#[derive(Debug)]
+pub struct PdfPrinter {
+    output_dir: PathBuf,
+    next_file_id: u32,
+    jobs: HashMap<u32, PrintJob>,
+}
+
+#[derive(Debug)]
+struct PrintJob {
+    file: File,
+    part_path: PathBuf,
+    path: PathBuf,
+    length: u64,
+}
+
+impl PdfPrinter {
+    /// Creates a printer writing the jobs into `output_dir`.
+    ///
+    /// The directory is created on the first job if it doesn't exist.
+    pub fn new(output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            next_file_id: 0,
+            jobs: HashMap::new(),
+        }
+    }
+
+    /// Returns the device data to announce this printer with, see [`ironrdp_rdpdr::Rdpdr::with_printer`].
+    pub fn device_data(print_name: String) -> PrinterDeviceData {
+        PrinterDeviceData::new(print_name, PDF_DRIVER_NAME.to_owned())
+    }
+
+    pub fn handle_io_request(&mut self, req: ServerPrinterIoRequest) -> PduResult<Vec<SvcMessage>> {
+        let res = match req {
+            ServerPrinterIoRequest::DeviceCreateRequest(req) => {
+                let request = req.device_io_request;
+
+                match self.start_job() {
+                    Ok(file_id) => RdpdrPdu::DeviceCreateResponse(DeviceCreateResponse {
+                        device_io_reply: DeviceIoResponse::new(request, NtStatus::SUCCESS),
+                        file_id,
+                        information: Information::FILE_SUPERSEDED,
+                    }),
+                    Err(error) => {
+                        warn!(%error, "Failed to start print job");
+                        RdpdrPdu::DeviceCreateResponse(DeviceCreateResponse {
+                            device_io_reply: DeviceIoResponse::new(request, NtStatus::UNSUCCESSFUL),
+                            file_id: 0,
+                            information: Information::empty(),
+                        })
+                    }
+                }
+            }
+            ServerPrinterIoRequest::DeviceWriteRequest(req) => {
+                let (status, length) = match u32::try_from(req.write_data.len()) {
+                    Ok(length) => match self.write_job(req.device_io_request.file_id, &req.write_data) {
+                        Ok(()) => (NtStatus::SUCCESS, length),
+                        Err(error) => {
+                            warn!(%error, "Failed to write print job data");
+                            (NtStatus::UNSUCCESSFUL, 0)
+                        }
+                    },
+                    Err(_) => {
+                        warn!(length = req.write_data.len(), "Print job data is too large");
+                        (NtStatus::INVALID_PARAMETER, 0)
+                    }
+                };
+
+                RdpdrPdu::DeviceWriteResponse(DeviceWriteResponse {
+                    device_io_reply: DeviceIoResponse::new(req.device_io_request, status),
+                    length,
+                })
+            }
+            ServerPrinterIoRequest::DeviceCloseRequest(req) => {
+                let status = match self.end_job(req.device_io_request.file_id) {
+                    Ok(()) => NtStatus::SUCCESS,
+                    Err(error) => {
+                        warn!(%error, "Failed to end print job");
+                        NtStatus::UNSUCCESSFUL
+                    }
+                };
+
+                RdpdrPdu::DeviceCloseResponse(DeviceCloseResponse {
+                    device_io_response: DeviceIoResponse::new(req.device_io_request, status),
+                })
+            }
+        };
+
+        Ok(vec![SvcMessage::from(res)])
+    }
+
+    fn start_job(&mut self) -> io::Result<u32> {
+        fs::create_dir_all(&self.output_dir)?;
+
+        let file_id = self.next_file_id;
+        self.next_file_id = self.next_file_id.wrapping_add(1);
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let name = format!("print-job-{timestamp}-{file_id}");
+
+        let path = self.output_dir.join(format!("{name}.pdf"));
+        let part_path = self.output_dir.join(format!("{name}.pdf.part"));
+        let file = File::create(&part_path)?;
+
+        debug!(?path, file_id, "Print job started");
+
+        self.jobs.insert(
+            file_id,
+            PrintJob {
+                file,
+                part_path,
+                path,
+                length: 0,
+            },
+        );
+
+        Ok(file_id)
+    }
+
+    fn write_job(&mut self, file_id: u32, data: &[u8]) -> io::Result<()> {
+        let job = self
+            .jobs
+            .get_mut(&file_id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such print job"))?;
+
+        if job.length == 0 && !data.starts_with(PDF_MAGIC) {
+            warn!(
+                path = ?job.path,
+                "Print job data is not a PDF document, is the `{PDF_DRIVER_NAME}` driver installed on the server?"
+            );
+        }
+
+        let length = u64::try_from(data.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "print job data is too large"))?;
+
+        job.file.write_all(data)?;
+        job.length = job.length.saturating_add(length);
+
+        Ok(())
+    }
+
+    fn end_job(&mut self, file_id: u32) -> io::Result<()> {
+        let mut job = self
+            .jobs
+            .remove(&file_id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such print job"))?;
+
+        job.file.flush()?;
+        drop(job.file);
+
+        fs::rename(&job.part_path, &job.path)?;
+
+        info!(path = ?job.path, length = job.length, "Print job saved");
+
+        Ok(())
+    }
+}
+
+impl Drop for PdfPrinter {
+    fn drop(&mut self) {
+        // Unfinished jobs are discarded.
+        for (_, job) in self.jobs.drain() {
+            drop(job.file);
+            let _ = fs::remove_file(job.part_path);
+        }
+    }
+}
+
+impl_as_any!(PdfPrinter);
+
+impl RdpdrBackend for PdfPrinter {
+    fn handle_server_device_announce_response(&mut self, _pdu: ServerDeviceAnnounceResponse) -> PduResult<()> {
+        Ok(())
+    }
+    fn handle_scard_call(&mut self, _req: DeviceControlRequest<ScardIoCtlCode>, _call: ScardCall) -> PduResult<()> {
+        Ok(())
+    }
+    fn handle_drive_io_request(&mut self, _req: ServerDriveIoRequest) -> PduResult<Vec<SvcMessage>> {
+        Ok(Vec::new())
+    }
+    fn handle_printer_io_request(&mut self, req: ServerPrinterIoRequest) -> PduResult<Vec<SvcMessage>> {
+        self.handle_io_request(req)
+    }
+}
@@ -6,12 +6,43 @@ use ironrdp_core::AsAny;
 use ironrdp_pdu::PduResult;
 use ironrdp_svc::SvcMessage;
 
-use crate::pdu::efs::{DeviceControlRequest, ServerDeviceAnnounceResponse, ServerDriveIoRequest};
+use crate::pdu::efs::{
+    DeviceCloseResponse, DeviceControlRequest, DeviceCreateResponse, DeviceIoResponse, DeviceWriteResponse,
+    Information, NtStatus, ServerDeviceAnnounceResponse, ServerDriveIoRequest, ServerPrinterIoRequest,
+};
 use crate::pdu::esc::{ScardCall, ScardIoCtlCode};
+use crate::pdu::RdpdrPdu;
 
 /// OS-specific device redirection backend interface.
 pub trait RdpdrBackend: AsAny + fmt::Debug + Send {
     fn handle_server_device_announce_response(&mut self, pdu: ServerDeviceAnnounceResponse) -> PduResult<()>;
     fn handle_scard_call(&mut self, req: DeviceControlRequest<ScardIoCtlCode>, call: ScardCall) -> PduResult<()>;
     fn handle_drive_io_request(&mut self, req: ServerDriveIoRequest) -> PduResult<Vec<SvcMessage>>;
+
+    /// Handles the I/O requests of the printers announced with [`Rdpdr::with_printer`](crate::Rdpdr::with_printer).
+    ///
+    /// By default, the requests are failed with `STATUS_NOT_SUPPORTED`.
+    fn handle_printer_io_request(&mut self, req: ServerPrinterIoRequest) -> PduResult<Vec<SvcMessage>> {
+        Ok(vec![printer_io_error(req, NtStatus::NOT_SUPPORTED)])
+    }
+}
+
+/// Returns the response failing a printer I/O request with `status`.
+pub fn printer_io_error(req: ServerPrinterIoRequest, status: NtStatus) -> SvcMessage {
+    let pdu = match req {
+        ServerPrinterIoRequest::DeviceCreateRequest(req) => RdpdrPdu::DeviceCreateResponse(DeviceCreateResponse {
+            device_io_reply: DeviceIoResponse::new(req.device_io_request, status),
+            file_id: 0,
+            information: Information::empty(),
+        }),
+        ServerPrinterIoRequest::DeviceWriteRequest(req) => RdpdrPdu::DeviceWriteResponse(DeviceWriteResponse {
+            device_io_reply: DeviceIoResponse::new(req.device_io_request, status),
+            length: 0,
+        }),
+        ServerPrinterIoRequest::DeviceCloseRequest(req) => RdpdrPdu::DeviceCloseResponse(DeviceCloseResponse {
+            device_io_response: DeviceIoResponse::new(req.device_io_request, status),
+        }),
+    };
+
+    SvcMessage::from(pdu)
 }
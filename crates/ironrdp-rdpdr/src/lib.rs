@@ -11,18 +11,19 @@ extern crate tracing;
 
 use ironrdp_core::{decode_cursor, impl_as_any, ReadCursor};
 use ironrdp_pdu::gcc::ChannelName;
-use ironrdp_pdu::{decode_err, pdu_other_err, PduResult};
+use ironrdp_pdu::{decode_err, encode_err, pdu_other_err, PduResult};
 use ironrdp_svc::{CompressionCondition, SvcClientProcessor, SvcMessage, SvcProcessor};
 use pdu::efs::{
     Capabilities, ClientDeviceListAnnounce, ClientNameRequest, ClientNameRequestUnicodeFlag, CoreCapability,
-    CoreCapabilityKind, DeviceControlRequest, DeviceIoRequest, DeviceType, Devices, ServerDeviceAnnounceResponse,
-    VersionAndIdPdu, VersionAndIdPduKind,
+    CoreCapabilityKind, DeviceControlRequest, DeviceIoRequest, DeviceType, Devices, PrinterDeviceData,
+    ServerDeviceAnnounceResponse, ServerPrinterIoRequest, VersionAndIdPdu, VersionAndIdPduKind,
 };
 use pdu::esc::{ScardCall, ScardIoCtlCode};
 use pdu::RdpdrPdu;
 
 pub mod backend;
 pub mod pdu;
+pub mod server;
 
 pub use self::backend::noop::NoopRdpdrBackend;
 pub use self::backend::RdpdrBackend;
@@ -89,6 +90,20 @@ impl Rdpdr {
         self
     }
 
+    /// Adds printer redirection capability, and a printer to announce to the server.
+    ///
+    /// Print jobs are forwarded to [`RdpdrBackend::handle_printer_io_request`]. The format of the job data
+    /// depends on the driver selected by [`PrinterDeviceData::driver_name`], which must be installed on the server.
+    pub fn with_printer(mut self, device_id: u32, printer: PrinterDeviceData) -> PduResult<Self> {
+        if !self.device_list.has_device_type(DeviceType::Print) {
+            self.capabilities.add_printer();
+        }
+        self.device_list
+            .add_printer(device_id, &printer)
+            .map_err(|e| encode_err!(e))?;
+        Ok(self)
+    }
+
     /// Users should call this method to announce a new drive to the server. It's the caller's responsibility
     /// to take the returned [`ClientDeviceListAnnounce`] and send it to the server.
     pub fn add_drive(&mut self, device_id: u32, name: String) -> ClientDeviceListAnnounce {
@@ -172,6 +187,13 @@ impl Rdpdr {
 
                 Ok(self.backend.handle_drive_io_request(req)?)
             }
+            DeviceType::Print => {
+                let req = ServerPrinterIoRequest::decode(dev_io_req, src).map_err(|e| decode_err!(e))?;
+
+                debug!(?req);
+
+                Ok(self.backend.handle_printer_io_request(req)?)
+            }
             _ => {
                 // This should never happen, as we only announce devices that we support.
                 warn!(?dev_io_req, "received packet for unsupported device type");
@@ -212,6 +234,8 @@ impl SvcProcessor for Rdpdr {
             // to make sure we don't miss handling new RdpdrPdu variants here during active development.
             RdpdrPdu::ClientNameRequest(_)
             | RdpdrPdu::ClientDeviceListAnnounce(_)
+            | RdpdrPdu::ClientDeviceListRemove(_)
+            | RdpdrPdu::ServerDriveIoRequest(_)
             | RdpdrPdu::VersionAndIdPdu(_)
             | RdpdrPdu::CoreCapability(_)
             | RdpdrPdu::DeviceControlResponse(_)
@@ -75,6 +75,34 @@ impl VersionAndIdPdu {
         })
     }
 
+    /// Creates a new [`VersionAndIdPduKind::ServerAnnounceRequest`] with the given `client_id`.
+    pub fn new_server_announce_request(client_id: u32) -> Self {
+        Self {
+            version_major: VERSION_MAJOR,
+            version_minor: VERSION_MINOR_12,
+            client_id,
+            kind: VersionAndIdPduKind::ServerAnnounceRequest,
+        }
+    }
+
+    /// Creates a new [`VersionAndIdPduKind::ServerClientIdConfirm`] confirming the client ID of the given reply.
+    pub fn new_server_client_id_confirm(reply: &VersionAndIdPdu) -> DecodeResult<Self> {
+        if reply.kind != VersionAndIdPduKind::ClientAnnounceReply {
+            return Err(invalid_field_err!(
+                "VersionAndIdPdu::new_server_client_id_confirm",
+                "VersionAndIdPduKind",
+                "invalid value"
+            ));
+        }
+
+        Ok(Self {
+            version_major: VERSION_MAJOR,
+            version_minor: VERSION_MINOR_12,
+            client_id: reply.client_id,
+            kind: VersionAndIdPduKind::ServerClientIdConfirm,
+        })
+    }
+
     pub fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
         ensure_size!(ctx: self.name(), in: dst, size: Self::FIXED_PART_SIZE);
         dst.write_u16(self.version_major);
@@ -96,6 +124,18 @@ impl VersionAndIdPdu {
             }
         };
 
+        Self::decode_kind(kind, src)
+    }
+
+    /// Decodes a [`VersionAndIdPduKind::ClientAnnounceReply`].
+    ///
+    /// It shares its packet ID with [`VersionAndIdPduKind::ServerClientIdConfirm`], which is what
+    /// [`Self::decode`] returns, so the server side must decode it explicitly.
+    pub fn decode_client_announce_reply(src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        Self::decode_kind(VersionAndIdPduKind::ClientAnnounceReply, src)
+    }
+
+    fn decode_kind(kind: VersionAndIdPduKind, src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
         ensure_size!(ctx: kind.name(), in: src, size: Self::FIXED_PART_SIZE);
         let version_major = src.read_u16();
         let version_minor = src.read_u16();
@@ -145,13 +185,6 @@ impl ClientNameRequest {
         }
     }
 
-    fn computer_name(&self) -> &str {
-        match self {
-            ClientNameRequest::Ascii(name) => name,
-            ClientNameRequest::Unicode(name) => name,
-        }
-    }
-
     pub fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
         ensure_size!(in: dst, size: self.size());
         dst.write_u32(self.unicode_flag().into());
@@ -160,6 +193,36 @@ impl ClientNameRequest {
         write_string_to_cursor(dst, self.computer_name(), self.unicode_flag().into(), true)
     }
 
+    pub fn decode(src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_size!(ctx: Self::NAME, in: src, size: Self::FIXED_PART_SIZE);
+        let unicode_flag = match src.read_u32() {
+            0x0 => ClientNameRequestUnicodeFlag::Ascii,
+            0x1 => ClientNameRequestUnicodeFlag::Unicode,
+            _ => {
+                return Err(invalid_field_err!(
+                    "ClientNameRequest::decode",
+                    "UnicodeFlag",
+                    "invalid value"
+                ))
+            }
+        };
+        let _code_page = src.read_u32(); // CodePage (4 bytes): it MUST be set to 0
+        let computer_name_len: usize = cast_length!("ClientNameRequest", "ComputerNameLen", src.read_u32())?;
+
+        ensure_size!(ctx: Self::NAME, in: src, size: computer_name_len);
+        let computer_name = decode_string(src.read_slice(computer_name_len), unicode_flag.into(), true)?;
+
+        Ok(Self::new(computer_name, unicode_flag))
+    }
+
+    /// Returns the name of the computer running the client.
+    pub fn computer_name(&self) -> &str {
+        match self {
+            ClientNameRequest::Ascii(name) => name,
+            ClientNameRequest::Unicode(name) => name,
+        }
+    }
+
     pub fn name(&self) -> &'static str {
         Self::NAME
     }
@@ -215,6 +278,16 @@ impl CoreCapability {
         }
     }
 
+    /// Creates a new [`DR_CORE_CAPABILITY_REQ`] with the given `capabilities`.
+    ///
+    /// [`DR_CORE_CAPABILITY_REQ`]: https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-rdpefs/702789c3-b924-4bc2-9280-3221bc7d6797
+    pub fn new_request(capabilities: Vec<CapabilityMessage>) -> Self {
+        Self {
+            capabilities,
+            kind: CoreCapabilityKind::ServerCoreCapabilityRequest,
+        }
+    }
+
     pub fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
         ensure_size!(ctx: self.name(), in: dst, size: self.size());
         dst.write_u16(cast_length!(
@@ -307,6 +380,10 @@ impl Capabilities {
         self.push(CapabilityMessage::new_drive());
     }
 
+    pub fn add_printer(&mut self) {
+        self.push(CapabilityMessage::new_printer());
+    }
+
     fn add_general(&mut self, special_type_device_cap: u32) {
         self.push(CapabilityMessage::new_general(special_type_device_cap));
     }
@@ -394,6 +471,14 @@ impl CapabilityMessage {
         }
     }
 
+    /// Creates a new `PRINTER_CAPS_SET`, \[MS-RDPEFS\] 2.2.2.7.2 Printer Capability Set.
+    pub fn new_printer() -> Self {
+        Self {
+            header: CapabilityHeader::new_printer(),
+            capability_data: CapabilityData::Printer,
+        }
+    }
+
     fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
         ensure_size!(in: dst, size: self.size());
         self.header.encode(dst)?;
@@ -452,6 +537,14 @@ impl CapabilityHeader {
         }
     }
 
+    fn new_printer() -> Self {
+        Self {
+            cap_type: CapabilityType::Printer,
+            length: Self::SIZE as u16,
+            version: PRINT_CAPABILITY_VERSION_01,
+        }
+    }
+
     fn decode(src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
         ensure_size!(in: src, size: Self::SIZE);
         let cap_type: CapabilityType = src.read_u16().try_into()?;
@@ -501,6 +594,8 @@ pub const GENERAL_CAPABILITY_VERSION_02: u32 = 0x0000_0002;
 pub const SMARTCARD_CAPABILITY_VERSION_01: u32 = 0x0000_0001;
 /// DRIVE_CAPABILITY_VERSION_02
 pub const DRIVE_CAPABILITY_VERSION_02: u32 = 0x0000_0002;
+/// PRINT_CAPABILITY_VERSION_01
+pub const PRINT_CAPABILITY_VERSION_01: u32 = 0x0000_0001;
 
 impl TryFrom<u16> for CapabilityType {
     type Error = DecodeError;
@@ -773,6 +868,18 @@ impl ClientDeviceListAnnounce {
         Ok(())
     }
 
+    pub fn decode(src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_size!(ctx: "DR_CORE_DEVICELIST_ANNOUNCE_REQ", in: src, size: Self::FIXED_PART_SIZE);
+        let device_count = src.read_u32();
+
+        let mut device_list = Vec::new();
+        for _ in 0..device_count {
+            device_list.push(DeviceAnnounceHeader::decode(src)?);
+        }
+
+        Ok(Self { device_list })
+    }
+
     pub fn name(&self) -> &'static str {
         "DR_CORE_DEVICELIST_ANNOUNCE_REQ"
     }
@@ -782,6 +889,48 @@ impl ClientDeviceListAnnounce {
     }
 }
 
+/// 2.2.3.2 Client Drive Device List Remove (DR_DEVICELIST_REMOVE)
+#[derive(Debug, PartialEq, Clone)]
+pub struct ClientDeviceListRemove {
+    pub device_ids: Vec<u32>,
+}
+
+impl ClientDeviceListRemove {
+    const NAME: &'static str = "DR_DEVICELIST_REMOVE";
+    const FIXED_PART_SIZE: usize = size_of::<u32>(); // DeviceCount
+
+    pub fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(ctx: Self::NAME, in: dst, size: self.size());
+        dst.write_u32(cast_length!(
+            "ClientDeviceListRemove",
+            "DeviceCount",
+            self.device_ids.len()
+        )?);
+        for device_id in self.device_ids.iter() {
+            dst.write_u32(*device_id);
+        }
+        Ok(())
+    }
+
+    pub fn decode(src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_size!(ctx: Self::NAME, in: src, size: Self::FIXED_PART_SIZE);
+        let device_count: usize = cast_length!("ClientDeviceListRemove", "DeviceCount", src.read_u32())?;
+
+        ensure_size!(ctx: Self::NAME, in: src, size: device_count * size_of::<u32>());
+        let device_ids = (0..device_count).map(|_| src.read_u32()).collect();
+
+        Ok(Self { device_ids })
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    pub fn size(&self) -> usize {
+        Self::FIXED_PART_SIZE + self.device_ids.len() * size_of::<u32>()
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Devices(Vec<DeviceAnnounceHeader>);
 
@@ -798,6 +947,12 @@ impl Devices {
         self.push(DeviceAnnounceHeader::new_drive(device_id, name));
     }
 
+    pub fn add_printer(&mut self, device_id: u32, printer: &PrinterDeviceData) -> EncodeResult<()> {
+        let index = self.0.iter().filter(|d| d.device_type == DeviceType::Print).count();
+        self.push(DeviceAnnounceHeader::new_printer(device_id, index, printer)?);
+        Ok(())
+    }
+
     /// Returns the [`DeviceType`] for the given device ID.
     pub fn for_device_type(&self, device_id: u32) -> DecodeResult<DeviceType> {
         if let Some(device_type) = self.0.iter().find(|d| d.device_id == device_id).map(|d| d.device_type) {
@@ -811,6 +966,11 @@ impl Devices {
         }
     }
 
+    /// Returns true if a device of the given [`DeviceType`] was added.
+    pub fn has_device_type(&self, device_type: DeviceType) -> bool {
+        self.0.iter().any(|d| d.device_type == device_type)
+    }
+
     fn push(&mut self, device: DeviceAnnounceHeader) {
         self.0.push(device);
     }
@@ -869,6 +1029,54 @@ impl DeviceAnnounceHeader {
         }
     }
 
+    fn new_printer(device_id: u32, index: usize, printer: &PrinterDeviceData) -> EncodeResult<Self> {
+        let mut device_data = vec![0; printer.size()];
+        printer.encode(&mut WriteCursor::new(&mut device_data))?;
+
+        Ok(Self {
+            device_type: DeviceType::Print,
+            device_id,
+            // The printer name is specified in the DeviceData field, and the PreferredDosName
+            // is the name of the port the printer is attached to, numbered from the printers already
+            // announced (the device ID could be too large to fit in the field).
+            preferred_dos_name: PreferredDosName(format!("PRN{}", index + 1)),
+            device_data,
+        })
+    }
+
+    pub fn device_type(&self) -> DeviceType {
+        self.device_type
+    }
+
+    pub fn device_id(&self) -> u32 {
+        self.device_id
+    }
+
+    /// Returns the name of the device, as announced by the client.
+    ///
+    /// For drives, this is the full name from the `DeviceData` field if present, and the `PreferredDosName` otherwise.
+    pub fn name(&self) -> String {
+        if self.device_type == DeviceType::Filesystem && !self.device_data.is_empty() {
+            let data = &self.device_data;
+            // The spec says Unicode, but some clients send null terminated UTF-8 (see `Self::new_drive`).
+            let name = if data.len() % 2 == 0 && data.get(1) == Some(&0) {
+                from_utf16_bytes(data)
+            } else {
+                String::from_utf8_lossy(data).into_owned()
+            };
+            let name = name.trim_end_matches('\0');
+            if !name.is_empty() {
+                return name.to_owned();
+            }
+        }
+
+        self.preferred_dos_name.0.clone()
+    }
+
+    pub fn device_data(&self) -> &[u8] {
+        &self.device_data
+    }
+
     fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
         dst.write_u32(self.device_type.into());
         dst.write_u32(self.device_id);
@@ -882,11 +1090,185 @@ impl DeviceAnnounceHeader {
         Ok(())
     }
 
+    fn decode(src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_size!(ctx: "DEVICE_ANNOUNCE", in: src, size: Self::FIXED_PART_SIZE);
+        let device_type = DeviceType::try_from(src.read_u32())?;
+        let device_id = src.read_u32();
+        let preferred_dos_name = PreferredDosName::decode(src)?;
+        let device_data_length: usize = cast_length!("DeviceAnnounceHeader", "DeviceDataLength", src.read_u32())?;
+
+        ensure_size!(ctx: "DEVICE_ANNOUNCE", in: src, size: device_data_length);
+        let device_data = src.read_slice(device_data_length).to_vec();
+
+        Ok(Self {
+            device_type,
+            device_id,
+            preferred_dos_name,
+            device_data,
+        })
+    }
+
     fn size(&self) -> usize {
         Self::FIXED_PART_SIZE + self.device_data.len()
     }
 }
 
+/// \[MS-RDPEPC\] 2.2.2.1 Client Device List Announce Request (DR_PRN_DEVICE_ANNOUNCE)
+///
+/// Printer-specific part of the `DeviceData` field of the device announce header.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PrinterDeviceData {
+    pub flags: PrinterAnnounceFlags,
+    /// The ANSI code page used for the printer names, ignored when they are Unicode.
+    pub code_page: u32,
+    /// Plug and Play device ID of the printer.
+    pub pnp_name: String,
+    /// Name of the printer driver the server should use to render print jobs.
+    pub driver_name: String,
+    /// Name of the printer, as displayed on the server.
+    pub print_name: String,
+    pub cached_fields: Vec<u8>,
+}
+
+impl PrinterDeviceData {
+    const NAME: &'static str = "DR_PRN_DEVICE_ANNOUNCE";
+
+    const FIXED_PART_SIZE: usize = 4 // Flags
+        + 4 // CodePage
+        + 4 // PnPNameLen
+        + 4 // DriverNameLen
+        + 4 // PrintNameLen
+        + 4; // CachedFieldsLen
+
+    /// Creates the data of a printer announced with Unicode names.
+    pub fn new(print_name: String, driver_name: String) -> Self {
+        Self {
+            flags: PrinterAnnounceFlags::empty(),
+            code_page: 0,
+            pnp_name: String::new(),
+            driver_name,
+            print_name,
+            cached_fields: Vec::new(),
+        }
+    }
+
+    pub fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(ctx: Self::NAME, in: dst, size: self.size());
+
+        let character_set = self.character_set();
+
+        dst.write_u32(self.flags.bits());
+        dst.write_u32(self.code_page);
+        dst.write_u32(cast_length!(
+            Self::NAME,
+            "PnPNameLen",
+            encoded_printer_name_len(&self.pnp_name, character_set)
+        )?);
+        dst.write_u32(cast_length!(
+            Self::NAME,
+            "DriverNameLen",
+            encoded_printer_name_len(&self.driver_name, character_set)
+        )?);
+        dst.write_u32(cast_length!(
+            Self::NAME,
+            "PrintNameLen",
+            encoded_printer_name_len(&self.print_name, character_set)
+        )?);
+        dst.write_u32(cast_length!(Self::NAME, "CachedFieldsLen", self.cached_fields.len())?);
+
+        for name in [&self.pnp_name, &self.driver_name, &self.print_name] {
+            // Empty names are omitted, the length being set to zero.
+            if !name.is_empty() {
+                write_string_to_cursor(dst, name, character_set, true)?;
+            }
+        }
+        dst.write_slice(&self.cached_fields);
+
+        Ok(())
+    }
+
+    pub fn decode(src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_size!(ctx: Self::NAME, in: src, size: Self::FIXED_PART_SIZE);
+        let flags = PrinterAnnounceFlags::from_bits_retain(src.read_u32());
+        let code_page = src.read_u32();
+        let pnp_name_len: usize = cast_length!(Self::NAME, "PnPNameLen", src.read_u32())?;
+        let driver_name_len: usize = cast_length!(Self::NAME, "DriverNameLen", src.read_u32())?;
+        let print_name_len: usize = cast_length!(Self::NAME, "PrintNameLen", src.read_u32())?;
+        let cached_fields_len: usize = cast_length!(Self::NAME, "CachedFieldsLen", src.read_u32())?;
+
+        let character_set = if flags.contains(PrinterAnnounceFlags::ASCII) {
+            CharacterSet::Ansi
+        } else {
+            CharacterSet::Unicode
+        };
+
+        ensure_size!(ctx: Self::NAME, in: src, size: pnp_name_len);
+        let pnp_name = decode_string(src.read_slice(pnp_name_len), character_set, true)?;
+        ensure_size!(ctx: Self::NAME, in: src, size: driver_name_len);
+        let driver_name = decode_string(src.read_slice(driver_name_len), character_set, true)?;
+        ensure_size!(ctx: Self::NAME, in: src, size: print_name_len);
+        let print_name = decode_string(src.read_slice(print_name_len), character_set, true)?;
+        ensure_size!(ctx: Self::NAME, in: src, size: cached_fields_len);
+        let cached_fields = src.read_slice(cached_fields_len).to_vec();
+
+        Ok(Self {
+            flags,
+            code_page,
+            pnp_name,
+            driver_name,
+            print_name,
+            cached_fields,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    pub fn size(&self) -> usize {
+        let character_set = self.character_set();
+
+        Self::FIXED_PART_SIZE
+            + encoded_printer_name_len(&self.pnp_name, character_set)
+            + encoded_printer_name_len(&self.driver_name, character_set)
+            + encoded_printer_name_len(&self.print_name, character_set)
+            + self.cached_fields.len()
+    }
+
+    fn character_set(&self) -> CharacterSet {
+        if self.flags.contains(PrinterAnnounceFlags::ASCII) {
+            CharacterSet::Ansi
+        } else {
+            CharacterSet::Unicode
+        }
+    }
+}
+
+fn encoded_printer_name_len(name: &str, character_set: CharacterSet) -> usize {
+    if name.is_empty() {
+        0
+    } else {
+        encoded_str_len(name, character_set, true)
+    }
+}
+
+bitflags! {
+    /// Flags of \[MS-RDPEPC\] 2.2.2.1 Client Device List Announce Request (DR_PRN_DEVICE_ANNOUNCE)
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct PrinterAnnounceFlags: u32 {
+        /// RDPDR_PRINTER_ANNOUNCE_FLAG_ASCII
+        const ASCII = 0x0000_0001;
+        /// RDPDR_PRINTER_ANNOUNCE_FLAG_DEFAULTPRINTER
+        const DEFAULT_PRINTER = 0x0000_0002;
+        /// RDPDR_PRINTER_ANNOUNCE_FLAG_NETWORKPRINTER
+        const NETWORK_PRINTER = 0x0000_0004;
+        /// RDPDR_PRINTER_ANNOUNCE_FLAG_TSPRINTER
+        const TS_PRINTER = 0x0000_0008;
+        /// RDPDR_PRINTER_ANNOUNCE_FLAG_XPSFORMAT
+        const XPS_FORMAT = 0x0000_0010;
+    }
+}
+
 /// From ["PreferredDosName"](https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-rdpefs/32e34332-774b-4ead-8c9d-5d64720d6bf9):
 ///
 /// PreferredDosName (8 bytes): A string of ASCII characters (with a maximum length of eight characters) that represents the name of the device as it appears on the client. This field MUST be null-terminated, so the maximum device name is 7 characters long. The following characters are considered invalid for the PreferredDosName field:
@@ -906,6 +1288,10 @@ impl PreferredDosName {
         write_string_to_cursor(dst, &self.format(), CharacterSet::Ansi, false)
     }
 
+    fn decode(src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        Ok(Self(decode_string(src.read_slice(8), CharacterSet::Ansi, true)?))
+    }
+
     /// Returns the underlying String with a maximum length of 7 characters plus a null terminator.
     fn format(&self) -> String {
         let mut name: &str = &self.0;
@@ -1015,10 +1401,14 @@ impl NtStatus {
     pub const NOT_A_DIRECTORY: Self = Self(0xC000_0103);
     /// STATUS_NO_SUCH_FILE
     pub const NO_SUCH_FILE: Self = Self(0xC000_000F);
+    /// STATUS_INVALID_PARAMETER
+    pub const INVALID_PARAMETER: Self = Self(0xC000_000D);
     /// STATUS_NOT_SUPPORTED
     pub const NOT_SUPPORTED: Self = Self(0xC000_00BB);
     /// STATUS_DIRECTORY_NOT_EMPTY
     pub const DIRECTORY_NOT_EMPTY: Self = Self(0xC000_0101);
+    /// STATUS_NO_SUCH_DEVICE
+    pub const NO_SUCH_DEVICE: Self = Self(0xC000_000E);
 }
 
 impl Debug for NtStatus {
@@ -1032,8 +1422,10 @@ impl Debug for NtStatus {
             NtStatus::ACCESS_DENIED => write!(f, "STATUS_ACCESS_DENIED"),
             NtStatus::NOT_A_DIRECTORY => write!(f, "STATUS_NOT_A_DIRECTORY"),
             NtStatus::NO_SUCH_FILE => write!(f, "STATUS_NO_SUCH_FILE"),
+            NtStatus::INVALID_PARAMETER => write!(f, "STATUS_INVALID_PARAMETER"),
             NtStatus::NOT_SUPPORTED => write!(f, "STATUS_NOT_SUPPORTED"),
             NtStatus::DIRECTORY_NOT_EMPTY => write!(f, "STATUS_DIRECTORY_NOT_EMPTY"),
+            NtStatus::NO_SUCH_DEVICE => write!(f, "STATUS_NO_SUCH_DEVICE"),
             _ => write!(f, "NtStatus({:#010X})", self.0),
         }
     }
@@ -1470,6 +1862,70 @@ impl ServerDriveIoRequest {
     }
 }
 
+impl ServerDriveIoRequest {
+    pub fn device_io_request(&self) -> &DeviceIoRequest {
+        match self {
+            Self::ServerCreateDriveRequest(req) => &req.device_io_request,
+            Self::ServerDriveQueryInformationRequest(req) => &req.device_io_request,
+            Self::DeviceCloseRequest(req) => &req.device_io_request,
+            Self::ServerDriveQueryDirectoryRequest(req) => &req.device_io_request,
+            Self::ServerDriveNotifyChangeDirectoryRequest(req) => &req.device_io_request,
+            Self::ServerDriveQueryVolumeInformationRequest(req) => &req.device_io_request,
+            Self::DeviceControlRequest(req) => &req.header,
+            Self::DeviceReadRequest(req) => &req.device_io_request,
+            Self::DeviceWriteRequest(req) => &req.device_io_request,
+            Self::ServerDriveSetInformationRequest(req) => &req.device_io_request,
+            Self::ServerDriveLockControlRequest(req) => &req.device_io_request,
+        }
+    }
+
+    /// Encodes the request, including its [`DeviceIoRequest`] header.
+    ///
+    /// Only the requests needed to open, read, write, enumerate and close files are supported.
+    pub fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        match self {
+            Self::ServerCreateDriveRequest(req) => req.encode(dst),
+            Self::DeviceCloseRequest(req) => req.encode(dst),
+            Self::ServerDriveQueryDirectoryRequest(req) => req.encode(dst),
+            Self::DeviceReadRequest(req) => req.encode(dst),
+            Self::DeviceWriteRequest(req) => req.encode(dst),
+            _ => Err(unsupported_value_err!(
+                "ServerDriveIoRequest::encode",
+                "ServerDriveIoRequest",
+                self.name().to_owned()
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ServerCreateDriveRequest(_) => "DR_DRIVE_CREATE_REQ",
+            Self::ServerDriveQueryInformationRequest(_) => "DR_DRIVE_QUERY_INFORMATION_REQ",
+            Self::DeviceCloseRequest(_) => "DR_CLOSE_REQ",
+            Self::ServerDriveQueryDirectoryRequest(_) => "DR_DRIVE_QUERY_DIRECTORY_REQ",
+            Self::ServerDriveNotifyChangeDirectoryRequest(_) => "DR_DRIVE_NOTIFY_CHANGE_DIRECTORY_REQ",
+            Self::ServerDriveQueryVolumeInformationRequest(_) => "DR_DRIVE_QUERY_VOLUME_INFORMATION_REQ",
+            Self::DeviceControlRequest(_) => "DR_CONTROL_REQ",
+            Self::DeviceReadRequest(_) => "DR_READ_REQ",
+            Self::DeviceWriteRequest(_) => "DR_WRITE_REQ",
+            Self::ServerDriveSetInformationRequest(_) => "DR_DRIVE_SET_INFORMATION_REQ",
+            Self::ServerDriveLockControlRequest(_) => "DR_DRIVE_LOCK_REQ",
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Self::ServerCreateDriveRequest(req) => req.size(),
+            Self::DeviceCloseRequest(req) => req.size(),
+            Self::ServerDriveQueryDirectoryRequest(req) => req.size(),
+            Self::DeviceReadRequest(req) => req.size(),
+            Self::DeviceWriteRequest(req) => req.size(),
+            // Encoding is not supported for the other requests.
+            _ => self.device_io_request().size(),
+        }
+    }
+}
+
 impl From<DeviceCreateRequest> for ServerDriveIoRequest {
     fn from(req: DeviceCreateRequest) -> Self {
         Self::ServerCreateDriveRequest(req)
@@ -1536,6 +1992,40 @@ impl From<ServerDriveLockControlRequest> for ServerDriveIoRequest {
     }
 }
 
+/// I/O requests sent by the server to a redirected printer, as described in \[MS-RDPEPC\] 3.1.5.
+///
+/// Print jobs are opened with a create request, the job data is sent using write requests, and
+/// the job is ended with a close request.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ServerPrinterIoRequest {
+    DeviceCreateRequest(DeviceCreateRequest),
+    DeviceWriteRequest(DeviceWriteRequest),
+    DeviceCloseRequest(DeviceCloseRequest),
+}
+
+impl ServerPrinterIoRequest {
+    pub fn decode(dev_io_req: DeviceIoRequest, src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        match dev_io_req.major_function {
+            MajorFunction::Create => Ok(Self::DeviceCreateRequest(DeviceCreateRequest::decode(dev_io_req, src)?)),
+            MajorFunction::Write => Ok(Self::DeviceWriteRequest(DeviceWriteRequest::decode(dev_io_req, src)?)),
+            MajorFunction::Close => Ok(Self::DeviceCloseRequest(DeviceCloseRequest::decode(dev_io_req))),
+            _ => Err(invalid_field_err!(
+                "ServerPrinterIoRequest::decode",
+                "MajorFunction",
+                "unsupported value for a printer device"
+            )),
+        }
+    }
+
+    pub fn device_io_request(&self) -> &DeviceIoRequest {
+        match self {
+            Self::DeviceCreateRequest(req) => &req.device_io_request,
+            Self::DeviceWriteRequest(req) => &req.device_io_request,
+            Self::DeviceCloseRequest(req) => &req.device_io_request,
+        }
+    }
+}
+
 /// [2.2.3.3.1] Server Create Drive Request (DR_DRIVE_CREATE_REQ)
 /// and [2.2.1.4.1] Device Create Request (DR_CREATE_REQ)
 ///
@@ -1589,6 +2079,29 @@ impl DeviceCreateRequest {
             path,
         })
     }
+
+    pub fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(ctx: "DeviceCreateRequest", in: dst, size: self.size());
+        self.device_io_request.encode(dst)?;
+        dst.write_u32(self.desired_access.bits());
+        dst.write_u64(self.allocation_size);
+        dst.write_u32(self.file_attributes.bits());
+        dst.write_u32(self.shared_access.bits());
+        dst.write_u32(self.create_disposition.bits());
+        dst.write_u32(self.create_options.bits());
+        dst.write_u32(cast_length!(
+            "DeviceCreateRequest",
+            "path_length",
+            encoded_str_len(&self.path, CharacterSet::Unicode, true)
+        )?);
+        write_string_to_cursor(dst, &self.path, CharacterSet::Unicode, true)
+    }
+
+    pub fn size(&self) -> usize {
+        self.device_io_request.size() // DeviceIoRequest
+        + Self::FIXED_PART_SIZE
+        + encoded_str_len(&self.path, CharacterSet::Unicode, true) // Path
+    }
 }
 
 bitflags! {
@@ -1769,6 +2282,18 @@ impl DeviceCreateResponse {
         Ok(())
     }
 
+    pub fn decode(device_io_reply: DeviceIoResponse, src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_size!(ctx: Self::NAME, in: src, size: 4 /* FileId */ + 1 /* Information */);
+        let file_id = src.read_u32();
+        let information = Information::from_bits_retain(src.read_u8());
+
+        Ok(Self {
+            device_io_reply,
+            file_id,
+            information,
+        })
+    }
+
     pub fn size(&self) -> usize {
         self.device_io_reply.size() // DeviceIoReply
         + 4 // FileId
@@ -1981,6 +2506,7 @@ impl FileInformationClass {
     ) -> DecodeResult<Self> {
         match file_info_class_level {
             FileInformationClassLevel::FILE_BASIC_INFORMATION => Ok(FileBasicInformation::decode(src)?.into()),
+            FileInformationClassLevel::FILE_DIRECTORY_INFORMATION => Ok(FileDirectoryInformation::decode(src)?.into()),
             FileInformationClassLevel::FILE_END_OF_FILE_INFORMATION => {
                 Ok(FileEndOfFileInformation::decode(src)?.into())
             }
@@ -2518,6 +3044,38 @@ impl FileDirectoryInformation {
         Ok(())
     }
 
+    fn decode(src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        const FIXED_PART_SIZE: usize = 4 /* NextEntryOffset */ + 4 /* FileIndex */ + 8 * 6 /* Times and sizes */ + 4 /* FileAttributes */ + 4 /* FileNameLength */;
+
+        ensure_size!(ctx: "FileDirectoryInformation", in: src, size: FIXED_PART_SIZE);
+        let next_entry_offset = src.read_u32();
+        let file_index = src.read_u32();
+        let creation_time = src.read_i64();
+        let last_access_time = src.read_i64();
+        let last_write_time = src.read_i64();
+        let change_time = src.read_i64();
+        let end_of_file = src.read_i64();
+        let allocation_size = src.read_i64();
+        let file_attributes = FileAttributes::from_bits_retain(src.read_u32());
+        let file_name_length: usize = cast_length!("FileDirectoryInformation", "file_name_length", src.read_u32())?;
+
+        ensure_size!(ctx: "FileDirectoryInformation", in: src, size: file_name_length);
+        let file_name = decode_string(src.read_slice(file_name_length), CharacterSet::Unicode, false)?;
+
+        Ok(Self {
+            next_entry_offset,
+            file_index,
+            creation_time,
+            last_access_time,
+            last_write_time,
+            change_time,
+            end_of_file,
+            allocation_size,
+            file_attributes,
+            file_name,
+        })
+    }
+
     fn size(&self) -> usize {
         4 // NextEntryOffset
         + 4 // FileIndex
@@ -2549,6 +3107,18 @@ impl DeviceCloseRequest {
             device_io_request: dev_io_req,
         }
     }
+
+    pub fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(ctx: "DeviceCloseRequest", in: dst, size: self.size());
+        self.device_io_request.encode(dst)?;
+        write_padding!(dst, 32); // Padding
+        Ok(())
+    }
+
+    pub fn size(&self) -> usize {
+        self.device_io_request.size() // DeviceIoRequest
+        + 32 // Padding
+    }
 }
 
 /// [2.2.1.5.2] Device Close Response (DR_CLOSE_RSP)
@@ -2574,6 +3144,13 @@ impl DeviceCloseResponse {
         Ok(())
     }
 
+    pub fn decode(device_io_response: DeviceIoResponse, src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_size!(ctx: Self::NAME, in: src, size: 4);
+        read_padding!(src, 4); // Padding
+
+        Ok(Self { device_io_response })
+    }
+
     pub fn size(&self) -> usize {
         self.device_io_response.size() // DeviceIoResponse
         + 4 // Padding
@@ -2628,6 +3205,38 @@ impl ServerDriveQueryDirectoryRequest {
             path,
         })
     }
+
+    pub fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(ctx: "ServerDriveQueryDirectoryRequest", in: dst, size: self.size());
+        self.device_io_request.encode(dst)?;
+        dst.write_u32(self.file_info_class_lvl.clone().into());
+        dst.write_u8(self.initial_query);
+        dst.write_u32(cast_length!(
+            "ServerDriveQueryDirectoryRequest",
+            "path_length",
+            self.path_length()
+        )?);
+        write_padding!(dst, 23); // Padding
+        if !self.path.is_empty() {
+            write_string_to_cursor(dst, &self.path, CharacterSet::Unicode, true)?;
+        }
+        Ok(())
+    }
+
+    pub fn size(&self) -> usize {
+        self.device_io_request.size() // DeviceIoRequest
+        + Self::FIXED_PART_SIZE
+        + self.path_length() // Path
+    }
+
+    /// The path is only sent with the initial query.
+    fn path_length(&self) -> usize {
+        if self.path.is_empty() {
+            0
+        } else {
+            encoded_str_len(&self.path, CharacterSet::Unicode, true)
+        }
+    }
 }
 
 /// 2.2.3.3.11 Server Drive NotifyChange Directory Request (DR_DRIVE_NOTIFY_CHANGE_DIRECTORY_REQ)
@@ -2703,6 +3312,30 @@ impl ClientDriveQueryDirectoryResponse {
             1 // Padding: https://github.com/FreeRDP/FreeRDP/blob/511444a65e7aa2f537c5e531fa68157a50c1bd4d/channels/drive/client/drive_file.c#L937
         }
     }
+
+    /// Decodes the response to a [`ServerDriveQueryDirectoryRequest`] made with the given `file_info_class_lvl`.
+    pub fn decode(
+        device_io_reply: DeviceIoResponse,
+        file_info_class_lvl: FileInformationClassLevel,
+        src: &mut ReadCursor<'_>,
+    ) -> DecodeResult<Self> {
+        ensure_size!(ctx: Self::NAME, in: src, size: 4);
+        let length: usize = cast_length!("ClientDriveQueryDirectoryResponse", "length", src.read_u32())?;
+
+        let buffer = if length == 0 {
+            // The optional padding byte is ignored.
+            None
+        } else {
+            ensure_size!(ctx: Self::NAME, in: src, size: length);
+            let mut buffer = ReadCursor::new(src.read_slice(length));
+            Some(FileInformationClass::decode(file_info_class_lvl, length, &mut buffer)?)
+        };
+
+        Ok(Self {
+            device_io_reply,
+            buffer,
+        })
+    }
 }
 
 /// [2.2.3.3.6] Server Drive Query Volume Information Request
@@ -3147,6 +3780,20 @@ impl DeviceReadRequest {
             offset,
         })
     }
+
+    pub fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(ctx: "DeviceReadRequest", in: dst, size: self.size());
+        self.device_io_request.encode(dst)?;
+        dst.write_u32(self.length);
+        dst.write_u64(self.offset);
+        write_padding!(dst, 20); // Padding
+        Ok(())
+    }
+
+    pub fn size(&self) -> usize {
+        self.device_io_request.size() // DeviceIoRequest
+        + Self::FIXED_PART_SIZE
+    }
 }
 
 /// [2.2.1.5.3] Device Read Response (DR_READ_RSP)
@@ -3168,6 +3815,19 @@ impl DeviceReadResponse {
         Ok(())
     }
 
+    pub fn decode(device_io_reply: DeviceIoResponse, src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_size!(ctx: Self::NAME, in: src, size: 4);
+        let length: usize = cast_length!("DeviceReadResponse", "length", src.read_u32())?;
+
+        ensure_size!(ctx: Self::NAME, in: src, size: length);
+        let read_data = src.read_slice(length).to_vec();
+
+        Ok(Self {
+            device_io_reply,
+            read_data,
+        })
+    }
+
     pub fn name(&self) -> &'static str {
         Self::NAME
     }
@@ -3217,6 +3877,22 @@ impl DeviceWriteRequest {
             write_data,
         })
     }
+
+    pub fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(ctx: "DeviceWriteRequest", in: dst, size: self.size());
+        self.device_io_request.encode(dst)?;
+        dst.write_u32(cast_length!("DeviceWriteRequest", "length", self.write_data.len())?);
+        dst.write_u64(self.offset);
+        write_padding!(dst, 20); // Padding
+        dst.write_slice(&self.write_data);
+        Ok(())
+    }
+
+    pub fn size(&self) -> usize {
+        self.device_io_request.size() // DeviceIoRequest
+        + Self::FIXED_PART_SIZE
+        + self.write_data.len() // WriteData
+    }
 }
 
 impl Debug for DeviceWriteRequest {
@@ -3253,6 +3929,17 @@ impl DeviceWriteResponse {
         Ok(())
     }
 
+    pub fn decode(device_io_reply: DeviceIoResponse, src: &mut ReadCursor<'_>) -> DecodeResult<Self> {
+        ensure_size!(ctx: Self::NAME, in: src, size: 4);
+        let length = src.read_u32();
+        // The optional padding byte is ignored.
+
+        Ok(Self {
+            device_io_reply,
+            length,
+        })
+    }
+
     pub fn size(&self) -> usize {
         self.device_io_reply.size() // DeviceIoResponse
         + 4 // Length
@@ -8,10 +8,11 @@ use ironrdp_core::{
 use ironrdp_svc::SvcEncode;
 
 use self::efs::{
-    ClientDeviceListAnnounce, ClientDriveQueryDirectoryResponse, ClientDriveQueryInformationResponse,
-    ClientDriveQueryVolumeInformationResponse, ClientDriveSetInformationResponse, ClientNameRequest, CoreCapability,
-    CoreCapabilityKind, DeviceCloseResponse, DeviceControlResponse, DeviceCreateResponse, DeviceIoRequest,
-    DeviceReadResponse, DeviceWriteResponse, ServerDeviceAnnounceResponse, VersionAndIdPdu, VersionAndIdPduKind,
+    ClientDeviceListAnnounce, ClientDeviceListRemove, ClientDriveQueryDirectoryResponse,
+    ClientDriveQueryInformationResponse, ClientDriveQueryVolumeInformationResponse, ClientDriveSetInformationResponse,
+    ClientNameRequest, CoreCapability, CoreCapabilityKind, DeviceCloseResponse, DeviceControlResponse,
+    DeviceCreateResponse, DeviceIoRequest, DeviceReadResponse, DeviceWriteResponse, ServerDeviceAnnounceResponse,
+    ServerDriveIoRequest, VersionAndIdPdu, VersionAndIdPduKind,
 };
 
 pub mod efs;
@@ -23,8 +24,10 @@ pub enum RdpdrPdu {
     ClientNameRequest(ClientNameRequest),
     CoreCapability(CoreCapability),
     ClientDeviceListAnnounce(ClientDeviceListAnnounce),
+    ClientDeviceListRemove(ClientDeviceListRemove),
     ServerDeviceAnnounceResponse(ServerDeviceAnnounceResponse),
     DeviceIoRequest(DeviceIoRequest),
+    ServerDriveIoRequest(ServerDriveIoRequest),
     DeviceControlResponse(DeviceControlResponse),
     DeviceCreateResponse(DeviceCreateResponse),
     ClientDriveQueryInformationResponse(ClientDriveQueryInformationResponse),
@@ -74,11 +77,15 @@ impl RdpdrPdu {
                 component: Component::RdpdrCtypCore,
                 packet_id: PacketId::CoreDevicelistAnnounce,
             },
+            RdpdrPdu::ClientDeviceListRemove(_) => SharedHeader {
+                component: Component::RdpdrCtypCore,
+                packet_id: PacketId::CoreDevicelistRemove,
+            },
             RdpdrPdu::ServerDeviceAnnounceResponse(_) => SharedHeader {
                 component: Component::RdpdrCtypCore,
                 packet_id: PacketId::CoreDeviceReply,
             },
-            RdpdrPdu::DeviceIoRequest(_) => SharedHeader {
+            RdpdrPdu::DeviceIoRequest(_) | RdpdrPdu::ServerDriveIoRequest(_) => SharedHeader {
                 component: Component::RdpdrCtypCore,
                 packet_id: PacketId::CoreDeviceIoRequest,
             },
@@ -133,8 +140,10 @@ impl Encode for RdpdrPdu {
             RdpdrPdu::ClientNameRequest(pdu) => pdu.encode(dst),
             RdpdrPdu::CoreCapability(pdu) => pdu.encode(dst),
             RdpdrPdu::ClientDeviceListAnnounce(pdu) => pdu.encode(dst),
+            RdpdrPdu::ClientDeviceListRemove(pdu) => pdu.encode(dst),
             RdpdrPdu::ServerDeviceAnnounceResponse(pdu) => pdu.encode(dst),
             RdpdrPdu::DeviceIoRequest(pdu) => pdu.encode(dst),
+            RdpdrPdu::ServerDriveIoRequest(pdu) => pdu.encode(dst),
             RdpdrPdu::DeviceControlResponse(pdu) => pdu.encode(dst),
             RdpdrPdu::DeviceCreateResponse(pdu) => pdu.encode(dst),
             RdpdrPdu::ClientDriveQueryInformationResponse(pdu) => pdu.encode(dst),
@@ -159,8 +168,10 @@ impl Encode for RdpdrPdu {
             RdpdrPdu::ClientNameRequest(pdu) => pdu.name(),
             RdpdrPdu::CoreCapability(pdu) => pdu.name(),
             RdpdrPdu::ClientDeviceListAnnounce(pdu) => pdu.name(),
+            RdpdrPdu::ClientDeviceListRemove(pdu) => pdu.name(),
             RdpdrPdu::ServerDeviceAnnounceResponse(pdu) => pdu.name(),
             RdpdrPdu::DeviceIoRequest(pdu) => pdu.name(),
+            RdpdrPdu::ServerDriveIoRequest(pdu) => pdu.name(),
             RdpdrPdu::DeviceControlResponse(pdu) => pdu.name(),
             RdpdrPdu::DeviceCreateResponse(pdu) => pdu.name(),
             RdpdrPdu::ClientDriveQueryInformationResponse(pdu) => pdu.name(),
@@ -182,8 +193,10 @@ impl Encode for RdpdrPdu {
                 RdpdrPdu::ClientNameRequest(pdu) => pdu.size(),
                 RdpdrPdu::CoreCapability(pdu) => pdu.size(),
                 RdpdrPdu::ClientDeviceListAnnounce(pdu) => pdu.size(),
+                RdpdrPdu::ClientDeviceListRemove(pdu) => pdu.size(),
                 RdpdrPdu::ServerDeviceAnnounceResponse(pdu) => pdu.size(),
                 RdpdrPdu::DeviceIoRequest(pdu) => pdu.size(),
+                RdpdrPdu::ServerDriveIoRequest(pdu) => pdu.size(),
                 RdpdrPdu::DeviceControlResponse(pdu) => pdu.size(),
                 RdpdrPdu::DeviceCreateResponse(pdu) => pdu.size(),
                 RdpdrPdu::ClientDriveQueryInformationResponse(pdu) => pdu.size(),
@@ -216,12 +229,18 @@ impl fmt::Debug for RdpdrPdu {
             Self::ClientDeviceListAnnounce(it) => {
                 write!(f, "RdpdrPdu({:?})", it)
             }
+            Self::ClientDeviceListRemove(it) => {
+                write!(f, "RdpdrPdu({:?})", it)
+            }
             Self::ServerDeviceAnnounceResponse(it) => {
                 write!(f, "RdpdrPdu({:?})", it)
             }
             Self::DeviceIoRequest(it) => {
                 write!(f, "RdpdrPdu({:?})", it)
             }
+            Self::ServerDriveIoRequest(it) => {
+                write!(f, "RdpdrPdu({:?})", it)
+            }
             Self::DeviceControlResponse(it) => {
                 write!(f, "RdpdrPdu({:?})", it)
             }
@@ -259,6 +278,12 @@ impl fmt::Debug for RdpdrPdu {
     }
 }
 
+impl From<ServerDriveIoRequest> for RdpdrPdu {
+    fn from(value: ServerDriveIoRequest) -> Self {
+        Self::ServerDriveIoRequest(value)
+    }
+}
+
 impl From<DeviceControlResponse> for RdpdrPdu {
     fn from(value: DeviceControlResponse) -> Self {
         Self::DeviceControlResponse(value)
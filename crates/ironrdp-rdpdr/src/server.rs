@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+
+use ironrdp_core::{impl_as_any, ReadCursor};
+use ironrdp_pdu::gcc::ChannelName;
+use ironrdp_pdu::{decode_err, pdu_other_err, PduResult};
+use ironrdp_svc::{CompressionCondition, SvcMessage, SvcProcessor, SvcProcessorMessages, SvcServerProcessor};
+
+use crate::pdu::efs::{
+    Capabilities, ClientDeviceListAnnounce, ClientDeviceListRemove, ClientDriveQueryDirectoryResponse,
+    ClientNameRequest, CoreCapability, CreateDisposition, CreateOptions, DesiredAccess, DeviceCloseRequest,
+    DeviceCloseResponse, DeviceCreateRequest, DeviceCreateResponse, DeviceIoRequest, DeviceIoResponse,
+    DeviceReadRequest, DeviceReadResponse, DeviceType, DeviceWriteRequest, DeviceWriteResponse, FileAttributes,
+    FileDirectoryInformation, FileInformationClass, FileInformationClassLevel, Information, MajorFunction,
+    MinorFunction, NtStatus, ServerDeviceAnnounceResponse, ServerDriveIoRequest, ServerDriveQueryDirectoryRequest,
+    SharedAccess, VersionAndIdPdu,
+};
+use crate::pdu::{PacketId, RdpdrPdu, SharedHeader};
+use crate::Rdpdr;
+
+pub type RdpdrSvcMessages = SvcProcessorMessages<RdpdrServer>;
+
+/// Message sent by the event loop.
+#[derive(Debug)]
+pub enum RdpdrServerMessage {
+    /// A request to the drive `device_id`, answered with [`RdpdrServerHandler::drive_response`].
+    DriveRequest {
+        device_id: u32,
+        completion_id: u32,
+        request: DriveRequest,
+    },
+}
+
+/// A request to a drive redirected by the client.
+///
+/// Paths are relative to the root of the drive and use `\` as separator, e.g. `\dir\file.txt`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DriveRequest {
+    /// Opens or creates a file or a directory.
+    Create {
+        path: String,
+        desired_access: DesiredAccess,
+        create_disposition: CreateDisposition,
+        create_options: CreateOptions,
+    },
+    Read {
+        file_id: u32,
+        offset: u64,
+        length: u32,
+    },
+    Write {
+        file_id: u32,
+        offset: u64,
+        data: Vec<u8>,
+    },
+    /// Queries the next entry of an opened directory.
+    ///
+    /// The first query of a directory must include a `pattern`, e.g. `\dir\*`.
+    QueryDirectory {
+        file_id: u32,
+        pattern: Option<String>,
+    },
+    Close {
+        file_id: u32,
+    },
+}
+
+/// The successful result of a [`DriveRequest`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DriveResponse {
+    Create {
+        file_id: u32,
+        information: Information,
+    },
+    Read(Vec<u8>),
+    /// The number of bytes written.
+    Write(u32),
+    /// The next entry of the directory, or `None` if there are no more entries.
+    QueryDirectory(Option<FileDirectoryInformation>),
+    Close,
+}
+
+pub trait RdpdrServerHandler: Send + core::fmt::Debug {
+    /// Called when the client redirects a drive.
+    fn drive_announced(&mut self, device_id: u32, name: String);
+
+    /// Called when the client stops redirecting a drive.
+    fn drive_removed(&mut self, device_id: u32);
+
+    /// Called with the result of the request made with the given `completion_id`.
+    fn drive_response(&mut self, completion_id: u32, response: Result<DriveResponse, NtStatus>);
+}
+
+/// The server side of the RDPDR channel, giving access to the drives redirected by the client.
+#[derive(Debug)]
+pub struct RdpdrServer {
+    handler: Box<dyn RdpdrServerHandler>,
+    client_announce_reply: Option<VersionAndIdPdu>,
+    drives: Vec<u32>,
+    /// Device ID and major function of the requests waiting for a completion, by completion ID.
+    pending: HashMap<u32, (u32, MajorFunction)>,
+}
+
+impl RdpdrServer {
+    /// The client ID proposed to the client, which may use another one.
+    const CLIENT_ID: u32 = 0x0000_0001;
+
+    pub fn new(handler: Box<dyn RdpdrServerHandler>) -> Self {
+        Self {
+            handler,
+            client_announce_reply: None,
+            drives: Vec::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Returns the device IDs of the drives currently redirected by the client.
+    pub fn drives(&self) -> &[u32] {
+        &self.drives
+    }
+
+    /// Sends a request to a drive redirected by the client.
+    ///
+    /// The `completion_id` must not be used by another pending request. The result is passed to
+    /// [`RdpdrServerHandler::drive_response`].
+    pub fn drive_request(
+        &mut self,
+        device_id: u32,
+        completion_id: u32,
+        request: DriveRequest,
+    ) -> PduResult<RdpdrSvcMessages> {
+        if self.pending.contains_key(&completion_id) {
+            return Err(pdu_other_err!("RdpdrServer", "completion ID already in use"));
+        }
+
+        if !self.drives.contains(&device_id) {
+            warn!(device_id, "Request to an unknown drive");
+            self.handler
+                .drive_response(completion_id, Err(NtStatus::NO_SUCH_DEVICE));
+            return Ok(RdpdrSvcMessages::new(Vec::new()));
+        }
+
+        let io_request = |file_id, major_function, minor_function| DeviceIoRequest {
+            device_id,
+            file_id,
+            completion_id,
+            major_function,
+            minor_function,
+        };
+
+        let req = match request {
+            DriveRequest::Create {
+                path,
+                desired_access,
+                create_disposition,
+                create_options,
+            } => ServerDriveIoRequest::from(DeviceCreateRequest {
+                device_io_request: io_request(0, MajorFunction::Create, MinorFunction::from(0)),
+                desired_access,
+                allocation_size: 0,
+                file_attributes: FileAttributes::empty(),
+                shared_access: SharedAccess::FILE_SHARE_READ,
+                create_disposition,
+                create_options,
+                path,
+            }),
+            DriveRequest::Read {
+                file_id,
+                offset,
+                length,
+            } => ServerDriveIoRequest::from(DeviceReadRequest {
+                device_io_request: io_request(file_id, MajorFunction::Read, MinorFunction::from(0)),
+                length,
+                offset,
+            }),
+            DriveRequest::Write { file_id, offset, data } => ServerDriveIoRequest::from(DeviceWriteRequest {
+                device_io_request: io_request(file_id, MajorFunction::Write, MinorFunction::from(0)),
+                offset,
+                write_data: data,
+            }),
+            DriveRequest::QueryDirectory { file_id, pattern } => {
+                ServerDriveIoRequest::from(ServerDriveQueryDirectoryRequest {
+                    device_io_request: io_request(
+                        file_id,
+                        MajorFunction::DirectoryControl,
+                        MinorFunction::IRP_MN_QUERY_DIRECTORY,
+                    ),
+                    file_info_class_lvl: FileInformationClassLevel::FILE_DIRECTORY_INFORMATION,
+                    initial_query: u8::from(pattern.is_some()),
+                    path: pattern.unwrap_or_default(),
+                })
+            }
+            DriveRequest::Close { file_id } => ServerDriveIoRequest::from(DeviceCloseRequest {
+                device_io_request: io_request(file_id, MajorFunction::Close, MinorFunction::from(0)),
+            }),
+        };
+
+        trace!(?req);
+        self.pending
+            .insert(completion_id, (device_id, req.device_io_request().major_function));
+
+        Ok(RdpdrSvcMessages::new(vec![SvcMessage::from(RdpdrPdu::from(req))]))
+    }
+
+    fn handle_client_name(req: ClientNameRequest, reply: &VersionAndIdPdu) -> PduResult<Vec<SvcMessage>> {
+        debug!(computer_name = req.computer_name(), "Received client name");
+
+        let mut capabilities = Capabilities::new();
+        capabilities.add_drive();
+
+        let capability_request = RdpdrPdu::CoreCapability(CoreCapability::new_request(capabilities.clone_inner()));
+        let client_id_confirm = RdpdrPdu::VersionAndIdPdu(
+            VersionAndIdPdu::new_server_client_id_confirm(reply).map_err(|e| decode_err!(e))?,
+        );
+
+        Ok(vec![
+            SvcMessage::from(capability_request),
+            SvcMessage::from(client_id_confirm),
+        ])
+    }
+
+    fn handle_device_list_announce(&mut self, pdu: ClientDeviceListAnnounce) -> Vec<SvcMessage> {
+        pdu.device_list
+            .into_iter()
+            .map(|device| {
+                let device_id = device.device_id();
+                let result_code = if device.device_type() == DeviceType::Filesystem {
+                    let name = device.name();
+                    debug!(device_id, name, "Client redirected a drive");
+                    if !self.drives.contains(&device_id) {
+                        self.drives.push(device_id);
+                    }
+                    self.handler.drive_announced(device_id, name);
+                    NtStatus::SUCCESS
+                } else {
+                    debug!(device_id, device_type = ?device.device_type(), "Unsupported device");
+                    NtStatus::NOT_SUPPORTED
+                };
+
+                SvcMessage::from(RdpdrPdu::ServerDeviceAnnounceResponse(ServerDeviceAnnounceResponse {
+                    device_id,
+                    result_code,
+                }))
+            })
+            .collect()
+    }
+
+    fn handle_device_list_remove(&mut self, pdu: ClientDeviceListRemove) {
+        for device_id in pdu.device_ids {
+            if !self.drives.contains(&device_id) {
+                continue;
+            }
+
+            debug!(device_id, "Client removed a drive");
+            self.drives.retain(|id| *id != device_id);
+
+            let completion_ids: Vec<u32> = self
+                .pending
+                .iter()
+                .filter(|(_, (id, _))| *id == device_id)
+                .map(|(completion_id, _)| *completion_id)
+                .collect();
+            for completion_id in completion_ids {
+                self.pending.remove(&completion_id);
+                self.handler
+                    .drive_response(completion_id, Err(NtStatus::NO_SUCH_DEVICE));
+            }
+
+            self.handler.drive_removed(device_id);
+        }
+    }
+
+    fn handle_io_completion(&mut self, src: &mut ReadCursor<'_>) -> PduResult<Vec<SvcMessage>> {
+        let reply = DeviceIoResponse::decode(src).map_err(|e| decode_err!(e))?;
+        let completion_id = reply.completion_id;
+
+        let Some((_, major_function)) = self.pending.remove(&completion_id) else {
+            warn!(?reply, "Unexpected I/O completion");
+            return Ok(Vec::new());
+        };
+
+        let response =
+            if major_function == MajorFunction::DirectoryControl && reply.io_status == NtStatus::NO_MORE_FILES {
+                Ok(DriveResponse::QueryDirectory(None))
+            } else if reply.io_status != NtStatus::SUCCESS {
+                Err(reply.io_status)
+            } else {
+                decode_drive_response(major_function, reply, src).map_err(|error| {
+                    error!(?error, ?major_function, "Failed to decode drive I/O response");
+                    NtStatus::UNSUCCESSFUL
+                })
+            };
+
+        self.handler.drive_response(completion_id, response);
+
+        Ok(Vec::new())
+    }
+}
+
+fn decode_drive_response(
+    major_function: MajorFunction,
+    reply: DeviceIoResponse,
+    src: &mut ReadCursor<'_>,
+) -> PduResult<DriveResponse> {
+    let response = match major_function {
+        MajorFunction::Create => {
+            let rsp = DeviceCreateResponse::decode(reply, src).map_err(|e| decode_err!(e))?;
+            DriveResponse::Create {
+                file_id: rsp.file_id,
+                information: rsp.information,
+            }
+        }
+        MajorFunction::Read => {
+            let rsp = DeviceReadResponse::decode(reply, src).map_err(|e| decode_err!(e))?;
+            DriveResponse::Read(rsp.read_data)
+        }
+        MajorFunction::Write => {
+            let rsp = DeviceWriteResponse::decode(reply, src).map_err(|e| decode_err!(e))?;
+            DriveResponse::Write(rsp.length)
+        }
+        MajorFunction::DirectoryControl => {
+            let rsp = ClientDriveQueryDirectoryResponse::decode(
+                reply,
+                FileInformationClassLevel::FILE_DIRECTORY_INFORMATION,
+                src,
+            )
+            .map_err(|e| decode_err!(e))?;
+            match rsp.buffer {
+                Some(FileInformationClass::Directory(info)) => DriveResponse::QueryDirectory(Some(info)),
+                None => DriveResponse::QueryDirectory(None),
+                Some(_) => return Err(pdu_other_err!("RdpdrServer", "unexpected file information class")),
+            }
+        }
+        MajorFunction::Close => {
+            DeviceCloseResponse::decode(reply, src).map_err(|e| decode_err!(e))?;
+            DriveResponse::Close
+        }
+        _ => return Err(pdu_other_err!("RdpdrServer", "unexpected major function")),
+    };
+
+    Ok(response)
+}
+
+impl_as_any!(RdpdrServer);
+
+impl SvcProcessor for RdpdrServer {
+    fn channel_name(&self) -> ChannelName {
+        Rdpdr::NAME
+    }
+
+    fn compression_condition(&self) -> CompressionCondition {
+        CompressionCondition::WhenRdpDataIsCompressed
+    }
+
+    fn start(&mut self) -> PduResult<Vec<SvcMessage>> {
+        let pdu = RdpdrPdu::VersionAndIdPdu(VersionAndIdPdu::new_server_announce_request(Self::CLIENT_ID));
+        Ok(vec![SvcMessage::from(pdu)])
+    }
+
+    fn process(&mut self, payload: &[u8]) -> PduResult<Vec<SvcMessage>> {
+        let mut src = ReadCursor::new(payload);
+        let header = SharedHeader::decode(&mut src).map_err(|e| decode_err!(e))?;
+        trace!(?header);
+
+        match header.packet_id {
+            PacketId::CoreClientidConfirm => {
+                let reply = VersionAndIdPdu::decode_client_announce_reply(&mut src).map_err(|e| decode_err!(e))?;
+                debug!(?reply, "Received client announce reply");
+                self.client_announce_reply = Some(reply);
+                Ok(Vec::new())
+            }
+            PacketId::CoreClientName => {
+                let req = ClientNameRequest::decode(&mut src).map_err(|e| decode_err!(e))?;
+                let Some(reply) = self.client_announce_reply.as_ref() else {
+                    return Err(pdu_other_err!(
+                        "RdpdrServer",
+                        "client name received before announce reply"
+                    ));
+                };
+                Self::handle_client_name(req, reply)
+            }
+            PacketId::CoreClientCapability => {
+                let pdu = CoreCapability::decode(header, &mut src).map_err(|e| decode_err!(e))?;
+                debug!(?pdu, "Received client capabilities");
+                Ok(vec![SvcMessage::from(RdpdrPdu::UserLoggedon)])
+            }
+            PacketId::CoreDevicelistAnnounce => {
+                let pdu = ClientDeviceListAnnounce::decode(&mut src).map_err(|e| decode_err!(e))?;
+                Ok(self.handle_device_list_announce(pdu))
+            }
+            PacketId::CoreDevicelistRemove => {
+                let pdu = ClientDeviceListRemove::decode(&mut src).map_err(|e| decode_err!(e))?;
+                self.handle_device_list_remove(pdu);
+                Ok(Vec::new())
+            }
+            PacketId::CoreDeviceIoCompletion => self.handle_io_completion(&mut src),
+            packet_id => {
+                error!(%packet_id, "Unexpected RDPDR packet");
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+impl SvcServerProcessor for RdpdrServer {}
@@ -6,6 +6,7 @@ use std::thread;
 
 use anyhow::Context;
 use cpal::traits::StreamTrait;
+use ironrdp_rdpsnd::codec::{AudioCodec as _, PcmCodec};
 use ironrdp_rdpsnd::pdu::{AudioFormat, WaveFormat};
 use ironrdp_rdpsnd_native::cpal::DecodeStream;
 use tracing::debug;
@@ -43,7 +44,8 @@ fn main() -> anyhow::Result<()> {
         data: None,
     };
     let (tx, rx) = mpsc::channel();
-    let stream = DecodeStream::new(&rx_format, rx).unwrap();
+    let decoder = PcmCodec::default().new_decoder(&rx_format)?;
+    let stream = DecodeStream::new(&rx_format, decoder, rx).unwrap();
 
     let producer = thread::spawn(move || {
         let data_chunks = vec![vec![1u8, 2, 3], vec![4, 5, 6], vec![7, 8, 9]];
@@ -0,0 +1,119 @@
+//! AAC audio codec, backed by the pure Rust decoder of Symphonia.
+//!
+//! Only decoding is supported: the codec can be used by clients, but not to encode the audio sent by servers, for
+//! which [`AudioCodec::new_encoder`] always fails. Servers should offer Opus or PCM instead.
+
+use ironrdp_rdpsnd::codec::{AudioCodec, AudioCodecError, AudioCodecResult, AudioDecoder, AudioEncoder};
+use ironrdp_rdpsnd::pdu::{AudioFormat, WaveFormat};
+use symphonia_codec_aac::AacDecoder as SymphoniaAacDecoder;
+use symphonia_core::audio::{Channels, SampleBuffer};
+use symphonia_core::codecs::{CodecParameters, Decoder as _, DecoderOptions, CODEC_TYPE_AAC};
+use symphonia_core::formats::Packet;
+
+// Sampling frequencies of the MPEG-4 audio specific configuration.
+const SAMPLE_RATES: &[u32] = &[
+    8000, 11025, 12000, 16000, 22050, 24000, 32000, 44100, 48000, 64000, 88200, 96000,
+];
+
+/// AAC-LC codec (`WAVE_FORMAT_AAC_MS`).
+#[derive(Debug, Clone)]
+pub struct AacCodec {
+    formats: Vec<AudioFormat>,
+}
+
+impl Default for AacCodec {
+    fn default() -> Self {
+        Self {
+            formats: vec![AudioFormat {
+                format: WaveFormat::AAC_MS,
+                n_channels: 2,
+                n_samples_per_sec: 44100,
+                n_avg_bytes_per_sec: 176400,
+                n_block_align: 4,
+                bits_per_sample: 16,
+                data: None,
+            }],
+        }
+    }
+}
+
+impl AacCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AudioCodec for AacCodec {
+    fn formats(&self) -> &[AudioFormat] {
+        &self.formats
+    }
+
+    fn supports(&self, format: &AudioFormat) -> bool {
+        format.format == WaveFormat::AAC_MS
+            && format.bits_per_sample == 16
+            && channels(format).is_ok()
+            && SAMPLE_RATES.contains(&format.n_samples_per_sec)
+    }
+
+    fn new_encoder(&self, format: &AudioFormat) -> AudioCodecResult<Box<dyn AudioEncoder>> {
+        Err(AudioCodecError::UnsupportedFormat(format.format))
+    }
+
+    fn new_decoder(&self, format: &AudioFormat) -> AudioCodecResult<Box<dyn AudioDecoder>> {
+        let mut params = CodecParameters::new();
+        params
+            .for_codec(CODEC_TYPE_AAC)
+            .with_sample_rate(format.n_samples_per_sec)
+            .with_channels(channels(format)?);
+
+        // The extra data of the format, if any, is the audio specific configuration.
+        if let Some(config) = format.data.as_deref().filter(|data| data.len() >= 2) {
+            params.with_extra_data(config.into());
+        }
+
+        let decoder =
+            SymphoniaAacDecoder::try_new(&params, &DecoderOptions::default()).map_err(AudioCodecError::codec)?;
+
+        Ok(Box::new(AacDecoder(decoder)))
+    }
+}
+
+struct AacDecoder(SymphoniaAacDecoder);
+
+impl core::fmt::Debug for AacDecoder {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AacDecoder").finish_non_exhaustive()
+    }
+}
+
+impl AudioDecoder for AacDecoder {
+    fn decode(&mut self, data: &[u8]) -> AudioCodecResult<Vec<u8>> {
+        let packet = Packet::new_from_slice(0, 0, 0, strip_adts_header(data));
+        let decoded = self.0.decode(&packet).map_err(AudioCodecError::codec)?;
+
+        let mut samples = SampleBuffer::<i16>::new(decoded.capacity() as u64, *decoded.spec());
+        samples.copy_interleaved_ref(decoded);
+
+        Ok(samples.samples().iter().copied().flat_map(i16::to_le_bytes).collect())
+    }
+}
+
+/// Returns the raw AAC frame, without the ADTS header some encoders prepend to each frame.
+fn strip_adts_header(data: &[u8]) -> &[u8] {
+    match data {
+        [0xFF, second, ..] if second & 0xF6 == 0xF0 => {
+            // The header is followed by a CRC when the protection is not absent.
+            let header_len = if second & 0x01 == 0x01 { 7 } else { 9 };
+            data.get(header_len..).unwrap_or_default()
+        }
+        _ => data,
+    }
+}
+
+fn channels(format: &AudioFormat) -> AudioCodecResult<Channels> {
+    match format.n_channels {
+        1 => Ok(Channels::FRONT_LEFT),
+        2 => Ok(Channels::FRONT_LEFT | Channels::FRONT_RIGHT),
+        _ => Err(AudioCodecError::UnsupportedFormat(format.format)),
+    }
+}
@@ -1,4 +1,3 @@
-use core::mem::size_of;
 use core::sync::atomic::{AtomicBool, Ordering};
 use core::time::Duration;
 use std::borrow::Cow;
@@ -10,7 +9,8 @@ use anyhow::{bail, Context};
 use cpal::traits::{DeviceTrait, HostTrait};
 use cpal::{SampleFormat, Stream, StreamConfig};
 use ironrdp_rdpsnd::client::RdpsndClientHandler;
-use ironrdp_rdpsnd::pdu::{AudioFormat, PitchPdu, VolumePdu, WaveFormat};
+use ironrdp_rdpsnd::codec::{AudioCodecs, AudioDecoder, PcmCodec};
+use ironrdp_rdpsnd::pdu::{AudioFormat, PitchPdu, VolumePdu};
 
 #[derive(Debug)]
 pub struct RdpsndBackend {
@@ -19,6 +19,9 @@ pub struct RdpsndBackend {
     stream_ended: Arc<AtomicBool>,
     tx: Option<Sender<Vec<u8>>>,
     format_no: Option<usize>,
+    codecs: AudioCodecs,
+    formats: Vec<AudioFormat>,
+    negotiated_formats: Vec<AudioFormat>,
 }
 
 impl Default for RdpsndBackend {
@@ -28,12 +31,30 @@ impl Default for RdpsndBackend {
 }
 
 impl RdpsndBackend {
+    /// Creates a backend supporting Opus and AAC (when the `opus` and `aac` features are enabled) and PCM.
     pub fn new() -> Self {
+        let codecs = AudioCodecs::new();
+        #[cfg(feature = "opus")]
+        let codecs = codecs.with_codec(crate::opus::OpusCodec::new());
+        #[cfg(feature = "aac")]
+        let codecs = codecs.with_codec(crate::aac::AacCodec::new());
+        let codecs = codecs.with_codec(PcmCodec::default());
+
+        Self::with_codecs(codecs)
+    }
+
+    /// Creates a backend decoding audio using the given codecs.
+    ///
+    /// The formats of the codecs registered first are preferred.
+    pub fn with_codecs(codecs: AudioCodecs) -> Self {
         Self {
             tx: None,
             format_no: None,
             stream_handle: None,
             stream_ended: Arc::new(AtomicBool::new(false)),
+            formats: codecs.formats(),
+            codecs,
+            negotiated_formats: Vec::new(),
         }
     }
 }
@@ -46,27 +67,11 @@ impl Drop for RdpsndBackend {
 
 impl RdpsndClientHandler for RdpsndBackend {
     fn get_formats(&self) -> &[AudioFormat] {
-        &[
-            #[cfg(feature = "opus")]
-            AudioFormat {
-                format: WaveFormat::OPUS,
-                n_channels: 2,
-                n_samples_per_sec: 48000,
-                n_avg_bytes_per_sec: 192000,
-                n_block_align: 4,
-                bits_per_sample: 16,
-                data: None,
-            },
-            AudioFormat {
-                format: WaveFormat::PCM,
-                n_channels: 2,
-                n_samples_per_sec: 44100,
-                n_avg_bytes_per_sec: 176400,
-                n_block_align: 4,
-                bits_per_sample: 16,
-                data: None,
-            },
-        ]
+        &self.formats
+    }
+
+    fn set_negotiated_formats(&mut self, formats: &[AudioFormat]) {
+        self.negotiated_formats = formats.to_vec();
     }
 
     fn wave(&mut self, format_no: usize, _ts: u32, data: Cow<'_, [u8]>) {
@@ -80,15 +85,22 @@ impl RdpsndClientHandler for RdpsndBackend {
             self.tx = Some(tx);
 
             self.format_no = Some(format_no);
-            let Some(format) = self.get_formats().get(format_no) else {
+            let Some(format) = self.negotiated_formats.get(format_no) else {
                 warn!(?format_no, "Invalid format_no");
                 return;
             };
             let format = format.clone();
+            let decoder = match self.codecs.new_decoder(&format) {
+                Ok(decoder) => decoder,
+                Err(error) => {
+                    error!(?format, %error, "Failed to create audio decoder");
+                    return;
+                }
+            };
             self.stream_ended.store(false, Ordering::Relaxed);
             let stream_ended = Arc::clone(&self.stream_ended);
             self.stream_handle = Some(thread::spawn(move || {
-                let stream = match DecodeStream::new(&format, rx) {
+                let stream = match DecodeStream::new(&format, decoder, rx) {
                     Ok(stream) => stream,
                     Err(e) => {
                         error!(error = format!("{e:#}"));
@@ -131,37 +143,32 @@ impl RdpsndClientHandler for RdpsndBackend {
 
 #[doc(hidden)]
 pub struct DecodeStream {
-    _dec_thread: Option<JoinHandle<()>>,
+    _dec_thread: JoinHandle<()>,
     pub stream: Stream,
 }
 
 impl DecodeStream {
-    pub fn new(rx_format: &AudioFormat, mut rx: Receiver<Vec<u8>>) -> anyhow::Result<Self> {
-        let mut dec_thread = None;
-        match rx_format.format {
-            #[cfg(feature = "opus")]
-            WaveFormat::OPUS => {
-                let chan = match rx_format.n_channels {
-                    1 => opus::Channels::Mono,
-                    2 => opus::Channels::Stereo,
-                    _ => bail!("unsupported #channels for Opus"),
-                };
-                let (dec_tx, dec_rx) = mpsc::channel();
-                let mut dec = opus::Decoder::new(rx_format.n_samples_per_sec, chan)?;
-                dec_thread = Some(thread::spawn(move || {
-                    while let Ok(pkt) = rx.recv() {
-                        let nb_samples = dec.get_nb_samples(&pkt).unwrap();
-                        let mut pcm = vec![0u8; nb_samples * chan as usize * size_of::<i16>()];
-                        dec.decode(&pkt, bytemuck::cast_slice_mut(pcm.as_mut_slice()), false)
-                            .unwrap();
-                        dec_tx.send(pcm).unwrap();
+    pub fn new(
+        rx_format: &AudioFormat,
+        mut decoder: Box<dyn AudioDecoder>,
+        rx: Receiver<Vec<u8>>,
+    ) -> anyhow::Result<Self> {
+        let (dec_tx, dec_rx) = mpsc::channel();
+        let dec_thread = thread::spawn(move || {
+            while let Ok(pkt) = rx.recv() {
+                let pcm = match decoder.decode(&pkt) {
+                    Ok(pcm) => pcm,
+                    Err(error) => {
+                        error!(%error, "Failed to decode audio packet");
+                        continue;
                     }
-                }));
-                rx = dec_rx;
+                };
+
+                if dec_tx.send(pcm).is_err() {
+                    break;
+                }
             }
-            WaveFormat::PCM => {}
-            _ => bail!("audio format not supported"),
-        }
+        });
 
         let sample_format = match rx_format.bits_per_sample {
             8 => SampleFormat::U8,
@@ -179,7 +186,7 @@ impl DecodeStream {
         let default_config = device.default_output_config()?;
         debug!(?default_config);
 
-        let mut rx = RxBuffer::new(rx);
+        let mut rx = RxBuffer::new(dec_rx);
         let config = StreamConfig {
             channels: rx_format.n_channels,
             sample_rate: cpal::SampleRate(rx_format.n_samples_per_sec),
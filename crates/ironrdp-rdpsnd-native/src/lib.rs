@@ -7,4 +7,8 @@ use tracing_subscriber as _;
 #[macro_use]
 extern crate tracing;
 
+#[cfg(feature = "aac")]
+pub mod aac;
 pub mod cpal;
+#[cfg(feature = "opus")]
+pub mod opus;
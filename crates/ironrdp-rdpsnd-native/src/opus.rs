@@ -0,0 +1,118 @@
+//! Opus audio codec, backed by libopus.
+
+use ironrdp_rdpsnd::codec::{AudioCodec, AudioCodecError, AudioCodecResult, AudioDecoder, AudioEncoder};
+use ironrdp_rdpsnd::pdu::{AudioFormat, WaveFormat};
+
+// Recommended maximum packet size, see `opus_encode` documentation.
+const MAX_PACKET_SIZE: usize = 4000;
+
+#[derive(Debug, Clone)]
+pub struct OpusCodec {
+    formats: Vec<AudioFormat>,
+}
+
+impl Default for OpusCodec {
+    fn default() -> Self {
+        Self {
+            formats: vec![AudioFormat {
+                format: WaveFormat::OPUS,
+                n_channels: 2,
+                n_samples_per_sec: 48000,
+                n_avg_bytes_per_sec: 192000,
+                n_block_align: 4,
+                bits_per_sample: 16,
+                data: None,
+            }],
+        }
+    }
+}
+
+impl OpusCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AudioCodec for OpusCodec {
+    fn formats(&self) -> &[AudioFormat] {
+        &self.formats
+    }
+
+    fn supports(&self, format: &AudioFormat) -> bool {
+        format.format == WaveFormat::OPUS
+            && format.bits_per_sample == 16
+            && channels(format).is_ok()
+            && matches!(format.n_samples_per_sec, 8000 | 12000 | 16000 | 24000 | 48000)
+    }
+
+    fn new_encoder(&self, format: &AudioFormat) -> AudioCodecResult<Box<dyn AudioEncoder>> {
+        let encoder = opus::Encoder::new(format.n_samples_per_sec, channels(format)?, opus::Application::Audio)
+            .map_err(AudioCodecError::codec)?;
+
+        Ok(Box::new(OpusEncoder(encoder)))
+    }
+
+    fn new_decoder(&self, format: &AudioFormat) -> AudioCodecResult<Box<dyn AudioDecoder>> {
+        let channels = channels(format)?;
+        let decoder = opus::Decoder::new(format.n_samples_per_sec, channels).map_err(AudioCodecError::codec)?;
+
+        Ok(Box::new(OpusDecoder { decoder, channels }))
+    }
+}
+
+struct OpusEncoder(opus::Encoder);
+
+impl core::fmt::Debug for OpusEncoder {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OpusEncoder").finish_non_exhaustive()
+    }
+}
+
+impl AudioEncoder for OpusEncoder {
+    fn encode(&mut self, pcm: &[u8]) -> AudioCodecResult<Vec<u8>> {
+        let samples: Vec<i16> = pcm
+            .chunks_exact(2)
+            .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+            .collect();
+
+        self.0
+            .encode_vec(&samples, MAX_PACKET_SIZE)
+            .map_err(AudioCodecError::codec)
+    }
+}
+
+struct OpusDecoder {
+    decoder: opus::Decoder,
+    channels: opus::Channels,
+}
+
+impl core::fmt::Debug for OpusDecoder {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OpusDecoder")
+            .field("channels", &self.channels)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AudioDecoder for OpusDecoder {
+    fn decode(&mut self, data: &[u8]) -> AudioCodecResult<Vec<u8>> {
+        let nb_samples = self.decoder.get_nb_samples(data).map_err(AudioCodecError::codec)?;
+
+        let mut pcm = vec![0i16; nb_samples * self.channels as usize];
+        let decoded = self
+            .decoder
+            .decode(data, &mut pcm, false)
+            .map_err(AudioCodecError::codec)?;
+        pcm.truncate(decoded * self.channels as usize);
+
+        Ok(pcm.into_iter().flat_map(i16::to_le_bytes).collect())
+    }
+}
+
+fn channels(format: &AudioFormat) -> AudioCodecResult<opus::Channels> {
+    match format.n_channels {
+        1 => Ok(opus::Channels::Mono),
+        2 => Ok(opus::Channels::Stereo),
+        _ => Err(AudioCodecError::UnsupportedFormat(format.format)),
+    }
+}
@@ -1,5 +1,4 @@
 use std::borrow::Cow;
-use std::collections::HashSet;
 
 use ironrdp_core::{cast_length, impl_as_any, Decode, EncodeResult, ReadCursor};
 use ironrdp_pdu::gcc::ChannelName;
@@ -15,8 +14,16 @@ pub trait RdpsndClientHandler: Send + core::fmt::Debug {
         pdu::AudioFormatFlags::empty()
     }
 
+    /// Returns the formats supported by the client, in order of preference.
     fn get_formats(&self) -> &[AudioFormat];
 
+    /// Called once the formats in common with the server are negotiated.
+    ///
+    /// The `format_no` passed to [`RdpsndClientHandler::wave`] is an index into `formats`.
+    fn set_negotiated_formats(&mut self, formats: &[AudioFormat]) {
+        let _ = formats;
+    }
+
     fn wave(&mut self, format_no: usize, ts: u32, data: Cow<'_, [u8]>);
 
     fn set_volume(&mut self, volume: VolumePdu);
@@ -59,6 +66,7 @@ pub struct Rdpsnd {
     handler: Box<dyn RdpsndClientHandler>,
     state: RdpsndState,
     server_format: Option<ServerAudioFormatPdu>,
+    client_formats: Vec<AudioFormat>,
 }
 
 impl Rdpsnd {
@@ -69,18 +77,18 @@ impl Rdpsnd {
             handler,
             state: RdpsndState::Start,
             server_format: None,
+            client_formats: Vec::new(),
         }
     }
 
+    /// Returns the negotiated format referenced by a wave PDU.
     pub fn get_format(&self, format_no: u16) -> PduResult<&AudioFormat> {
-        let server_format = self
-            .server_format
-            .as_ref()
-            .ok_or_else(|| pdu_other_err!("invalid state - no format"))?;
+        if self.server_format.is_none() {
+            return Err(pdu_other_err!("invalid state - no format"));
+        }
 
-        server_format
-            .formats
-            .get(format_no as usize)
+        self.client_formats
+            .get(usize::from(format_no))
             .ok_or_else(|| pdu_other_err!("invalid format"))
     }
 
@@ -96,15 +104,21 @@ impl Rdpsnd {
     pub fn client_formats(&mut self) -> PduResult<RdpsndSvcMessages> {
         // Windows seems to be confused if the client replies with more formats, or unknown formats (e.g.: opus).
         // We ensure to only send supported formats in common with the server.
-        let server_format: HashSet<_> = self
+        // The client order of preference is kept, since wave PDUs are referring to formats by index.
+        let server_formats = &self
             .server_format
             .as_ref()
             .ok_or_else(|| pdu_other_err!("invalid state - no server format"))?
-            .formats
-            .iter()
-            .collect();
-        let formats: HashSet<_> = self.handler.get_formats().iter().collect();
-        let formats = formats.intersection(&server_format).map(|&x| x.clone()).collect();
+            .formats;
+        let mut formats: Vec<AudioFormat> = Vec::new();
+        for format in self.handler.get_formats() {
+            if server_formats.contains(format) && !formats.contains(format) {
+                formats.push(format.clone());
+            }
+        }
+
+        self.handler.set_negotiated_formats(&formats);
+        self.client_formats.clone_from(&formats);
 
         let pdu = pdu::ClientAudioFormatPdu {
             version: self.version()?,
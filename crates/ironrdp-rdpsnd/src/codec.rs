@@ -0,0 +1,216 @@
+//! Audio codec abstraction used to negotiate wave formats and to transcode wave data.
+//!
+//! Only PCM is handled by this crate ([`PcmCodec`]). Compressed formats such as Opus
+//! ([`WaveFormat::OPUS`]) or AAC ([`WaveFormat::AAC_MS`]) are supported by plugging an [`AudioCodec`]
+//! implementation into [`AudioCodecs`].
+
+use core::fmt;
+
+use crate::pdu::{AudioFormat, WaveFormat};
+
+#[derive(Debug)]
+pub enum AudioCodecError {
+    /// No codec is able to handle the requested format.
+    UnsupportedFormat(WaveFormat),
+    /// Codec-specific failure.
+    Codec(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl AudioCodecError {
+    pub fn codec(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self::Codec(Box::new(error))
+    }
+}
+
+impl fmt::Display for AudioCodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioCodecError::UnsupportedFormat(format) => write!(f, "unsupported audio format: {format}"),
+            AudioCodecError::Codec(_) => write!(f, "audio codec failure"),
+        }
+    }
+}
+
+impl std::error::Error for AudioCodecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AudioCodecError::UnsupportedFormat(_) => None,
+            AudioCodecError::Codec(error) => Some(error.as_ref()),
+        }
+    }
+}
+
+pub type AudioCodecResult<T> = Result<T, AudioCodecError>;
+
+/// Encodes PCM samples into wave data of a specific [`AudioFormat`].
+pub trait AudioEncoder: Send + fmt::Debug {
+    /// Encodes a block of interleaved little-endian PCM samples.
+    ///
+    /// Samples are using the channel count, sample rate and sample size of the format the encoder
+    /// was created for. Frame-based codecs may require the block to hold a valid frame duration.
+    fn encode(&mut self, pcm: &[u8]) -> AudioCodecResult<Vec<u8>>;
+}
+
+/// Decodes wave data of a specific [`AudioFormat`] into PCM samples.
+pub trait AudioDecoder: Send + fmt::Debug {
+    /// Decodes a single wave data block into interleaved little-endian PCM samples.
+    ///
+    /// Samples are using the channel count, sample rate and sample size of the format the decoder
+    /// was created for.
+    fn decode(&mut self, data: &[u8]) -> AudioCodecResult<Vec<u8>>;
+}
+
+/// Audio codec, a factory for encoders and decoders of one or more wave formats.
+pub trait AudioCodec: Send + Sync + fmt::Debug {
+    /// Returns the formats supported by this codec, in order of preference.
+    fn formats(&self) -> &[AudioFormat];
+
+    /// Returns true if this codec can handle the given format.
+    ///
+    /// By default, only the exact formats returned by [`AudioCodec::formats`] are accepted.
+    fn supports(&self, format: &AudioFormat) -> bool {
+        self.formats().contains(format)
+    }
+
+    fn new_encoder(&self, format: &AudioFormat) -> AudioCodecResult<Box<dyn AudioEncoder>>;
+
+    fn new_decoder(&self, format: &AudioFormat) -> AudioCodecResult<Box<dyn AudioDecoder>>;
+}
+
+/// Uncompressed PCM codec, passing the data through.
+#[derive(Debug, Clone)]
+pub struct PcmCodec {
+    formats: Vec<AudioFormat>,
+}
+
+impl PcmCodec {
+    /// Creates a PCM codec advertising the given formats.
+    ///
+    /// Any 8 or 16 bits PCM format is supported, regardless of the advertised formats.
+    pub fn new(formats: Vec<AudioFormat>) -> Self {
+        Self { formats }
+    }
+}
+
+impl Default for PcmCodec {
+    fn default() -> Self {
+        Self::new(vec![AudioFormat {
+            format: WaveFormat::PCM,
+            n_channels: 2,
+            n_samples_per_sec: 44100,
+            n_avg_bytes_per_sec: 176400,
+            n_block_align: 4,
+            bits_per_sample: 16,
+            data: None,
+        }])
+    }
+}
+
+impl AudioCodec for PcmCodec {
+    fn formats(&self) -> &[AudioFormat] {
+        &self.formats
+    }
+
+    fn supports(&self, format: &AudioFormat) -> bool {
+        format.format == WaveFormat::PCM && matches!(format.bits_per_sample, 8 | 16)
+    }
+
+    fn new_encoder(&self, format: &AudioFormat) -> AudioCodecResult<Box<dyn AudioEncoder>> {
+        if !self.supports(format) {
+            return Err(AudioCodecError::UnsupportedFormat(format.format));
+        }
+
+        Ok(Box::new(PcmPassthrough))
+    }
+
+    fn new_decoder(&self, format: &AudioFormat) -> AudioCodecResult<Box<dyn AudioDecoder>> {
+        if !self.supports(format) {
+            return Err(AudioCodecError::UnsupportedFormat(format.format));
+        }
+
+        Ok(Box::new(PcmPassthrough))
+    }
+}
+
+#[derive(Debug)]
+struct PcmPassthrough;
+
+impl AudioEncoder for PcmPassthrough {
+    fn encode(&mut self, pcm: &[u8]) -> AudioCodecResult<Vec<u8>> {
+        Ok(pcm.to_vec())
+    }
+}
+
+impl AudioDecoder for PcmPassthrough {
+    fn decode(&mut self, data: &[u8]) -> AudioCodecResult<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+}
+
+/// Ordered set of audio codecs.
+///
+/// Codecs registered first are preferred during format negotiation.
+#[derive(Debug, Default)]
+pub struct AudioCodecs {
+    codecs: Vec<Box<dyn AudioCodec>>,
+}
+
+impl AudioCodecs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_codec<C>(mut self, codec: C) -> Self
+    where
+        C: AudioCodec + 'static,
+    {
+        self.codecs.push(Box::new(codec));
+        self
+    }
+
+    /// Returns the formats of all registered codecs, in order of preference.
+    pub fn formats(&self) -> Vec<AudioFormat> {
+        self.codecs
+            .iter()
+            .flat_map(|codec| codec.formats().iter().cloned())
+            .collect()
+    }
+
+    /// Returns the preferred codec able to handle the given format.
+    pub fn find(&self, format: &AudioFormat) -> Option<&dyn AudioCodec> {
+        self.codecs
+            .iter()
+            .find(|codec| codec.supports(format))
+            .map(|codec| codec.as_ref())
+    }
+
+    /// Selects the best format offered by the remote peer.
+    ///
+    /// Returns the index of the selected format in `offered`. The formats advertised by the
+    /// registered codecs are considered first, in order of preference, then any other offered format
+    /// supported by a codec.
+    pub fn negotiate(&self, offered: &[AudioFormat]) -> Option<u16> {
+        let advertised = self
+            .codecs
+            .iter()
+            .flat_map(|codec| codec.formats())
+            .find_map(|format| offered.iter().position(|offered| offered == format));
+
+        let index = advertised.or_else(|| offered.iter().position(|format| self.find(format).is_some()))?;
+
+        u16::try_from(index).ok()
+    }
+
+    pub fn new_encoder(&self, format: &AudioFormat) -> AudioCodecResult<Box<dyn AudioEncoder>> {
+        self.find(format)
+            .ok_or(AudioCodecError::UnsupportedFormat(format.format))?
+            .new_encoder(format)
+    }
+
+    pub fn new_decoder(&self, format: &AudioFormat) -> AudioCodecResult<Box<dyn AudioDecoder>> {
+        self.find(format)
+            .ok_or(AudioCodecError::UnsupportedFormat(format.format))?
+            .new_decoder(format)
+    }
+}
@@ -0,0 +1,158 @@
+use core::fmt;
+use std::sync::Arc;
+
+pub use ironrdp_audin::server::AudioInputServerHandler;
+use ironrdp_rdpsnd::codec::AudioDecoder;
+
+use crate::sound::is_compatible;
+use crate::{AudioCodecs, AudioFormat};
+
+pub trait AudioInputServerFactory: Send {
+    fn build_backend(&self) -> Box<dyn AudioInputServerHandler>;
+}
+
+/// Audio input of the server, receiving the audio recorded by the clients (e.g. their microphone).
+///
+/// Used with a [`PcmAudioInputFactory`], which negotiates the wave format with each client and
+/// decodes the recorded audio using the registered codecs.
+pub trait RdpServerAudioInput: Send + Sync {
+    /// Returns the format of the PCM samples.
+    ///
+    /// Only the wave formats with the same channel count and sample rate are requested from the
+    /// clients, the samples are not resampled.
+    fn format(&self) -> AudioFormat;
+
+    /// Called with a block of interleaved little-endian PCM samples recorded by a client.
+    fn samples(&self, pcm: &[u8]);
+
+    /// Called when the audio input channel of a client is closed.
+    fn stop(&self) {}
+}
+
+/// Creates the audio input of each connection, decoding the recorded audio to PCM.
+pub struct PcmAudioInputFactory {
+    input: Arc<dyn RdpServerAudioInput>,
+    codecs: Arc<AudioCodecs>,
+}
+
+impl fmt::Debug for PcmAudioInputFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PcmAudioInputFactory")
+            .field("codecs", &self.codecs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PcmAudioInputFactory {
+    /// Creates a factory feeding the audio recorded by the clients to `input`, decoded using `codecs`.
+    pub fn new(input: Arc<dyn RdpServerAudioInput>, codecs: AudioCodecs) -> Self {
+        Self {
+            input,
+            codecs: Arc::new(codecs),
+        }
+    }
+}
+
+impl AudioInputServerFactory for PcmAudioInputFactory {
+    fn build_backend(&self) -> Box<dyn AudioInputServerHandler> {
+        let pcm = self.input.format();
+        let formats = self
+            .codecs
+            .formats()
+            .into_iter()
+            .filter(|format| is_compatible(&pcm, format))
+            .collect::<Vec<_>>();
+
+        if formats.is_empty() {
+            warn!(?pcm, "No audio codec format compatible with the PCM samples");
+        }
+
+        Box::new(PcmAudioInputHandler {
+            input: Arc::clone(&self.input),
+            codecs: Arc::clone(&self.codecs),
+            pcm,
+            formats,
+            decoder: None,
+        })
+    }
+}
+
+struct PcmAudioInputHandler {
+    input: Arc<dyn RdpServerAudioInput>,
+    codecs: Arc<AudioCodecs>,
+    pcm: AudioFormat,
+    formats: Vec<AudioFormat>,
+    decoder: Option<Box<dyn AudioDecoder>>,
+}
+
+impl fmt::Debug for PcmAudioInputHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PcmAudioInputHandler")
+            .field("pcm", &self.pcm)
+            .field("formats", &self.formats)
+            .field("decoder", &self.decoder)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AudioInputServerHandler for PcmAudioInputHandler {
+    fn get_formats(&self) -> &[AudioFormat] {
+        &self.formats
+    }
+
+    fn start(&mut self, client_formats: &[AudioFormat]) -> Option<u32> {
+        // The formats are indexed by the open and format change PDUs, the incompatible ones are
+        // skipped while keeping their position.
+        let compatible = client_formats
+            .iter()
+            .enumerate()
+            .filter(|(_, format)| is_compatible(&self.pcm, format))
+            .collect::<Vec<_>>();
+        let offered = compatible
+            .iter()
+            .map(|(_, format)| (*format).clone())
+            .collect::<Vec<_>>();
+
+        let Some((index, _)) = self
+            .codecs
+            .negotiate(&offered)
+            .and_then(|selected| compatible.get(usize::from(selected)))
+        else {
+            warn!(formats = ?client_formats, "No audio input format supported by the client");
+            return None;
+        };
+
+        u32::try_from(*index).ok()
+    }
+
+    fn format_changed(&mut self, format: &AudioFormat) {
+        if !is_compatible(&self.pcm, format) {
+            warn!(?format, "Audio input format incompatible with the PCM samples");
+            self.decoder = None;
+            return;
+        }
+
+        self.decoder = match self.codecs.new_decoder(format) {
+            Ok(decoder) => Some(decoder),
+            Err(error) => {
+                warn!(%error, ?format, "Failed to create audio decoder");
+                None
+            }
+        };
+    }
+
+    fn data(&mut self, data: &[u8]) {
+        let Some(decoder) = self.decoder.as_mut() else {
+            return;
+        };
+
+        match decoder.decode(data) {
+            Ok(pcm) => self.input.samples(&pcm),
+            Err(error) => warn!(%error, "Failed to decode audio input"),
+        }
+    }
+
+    fn stop(&mut self) {
+        self.input.stop();
+    }
+}
@@ -0,0 +1,166 @@
+use core::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use ironrdp_cliprdr::pdu::ClipboardFormat;
+pub use ironrdp_cliprdr::pdu::ClipboardFormatId;
+pub use ironrdp_cliprdr::policy::ClipboardDirection;
+use ironrdp_cliprdr::policy::{ClipboardPolicy, PolicyDecision};
+pub use ironrdp_rdpdr::pdu::efs::{CreateDisposition, DesiredAccess};
+
+use crate::ConnectionInfo;
+
+/// Security-relevant event of a connection, see [`RdpServerAuditor`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AuditEvent<'a> {
+    /// The user was authenticated, or failed to be, with the username given in the [`ConnectionInfo`].
+    Authentication { success: bool },
+    /// Clipboard data in the given format crossed the channel, or was rejected by the clipboard policy.
+    ///
+    /// `size` is the size in bytes of the transferred data, after the policy is applied; the data itself is not
+    /// reported.
+    ClipboardData {
+        direction: ClipboardDirection,
+        format: Option<ClipboardFormatId>,
+        size: usize,
+        allowed: bool,
+    },
+    /// A chunk of the contents of a copied file crossed the channel, or was rejected by the clipboard policy.
+    ClipboardFileContents {
+        direction: ClipboardDirection,
+        size: usize,
+        allowed: bool,
+    },
+    /// A file or directory of a drive redirected by the client was opened by the server.
+    ///
+    /// `path` is relative to the root of the drive, e.g. `\dir\file.txt`.
+    DriveAccess {
+        device_id: u32,
+        path: &'a str,
+        desired_access: DesiredAccess,
+        create_disposition: CreateDisposition,
+    },
+    /// The connection was closed, with the error which closed it, if any.
+    Disconnected { error: Option<&'a anyhow::Error> },
+}
+
+/// Event reported to a [`RdpServerAuditor`], with the time it occurred and the connection it belongs to.
+#[derive(Debug)]
+pub struct AuditRecord<'a> {
+    pub timestamp: SystemTime,
+    /// The peer and, once known, the identity of the user.
+    pub connection: &'a ConnectionInfo,
+    pub event: AuditEvent<'a>,
+}
+
+/// Receives the security-relevant events of the connections, e.g. to write them to an audit trail.
+///
+/// The auditor is shared by all the connections of the server, and is called from their tasks: it should
+/// return quickly. The clipboard events are reported whether or not a clipboard policy is set.
+pub trait RdpServerAuditor: Send + Sync {
+    fn audit(&self, record: &AuditRecord<'_>);
+}
+
+/// The auditor of a connection, reporting its events with the latest known information about the connection.
+#[derive(Clone)]
+pub(crate) struct ConnectionAudit {
+    auditor: Arc<dyn RdpServerAuditor>,
+    info: Arc<Mutex<ConnectionInfo>>,
+}
+
+impl fmt::Debug for ConnectionAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionAudit").finish_non_exhaustive()
+    }
+}
+
+impl ConnectionAudit {
+    pub(crate) fn new(auditor: Arc<dyn RdpServerAuditor>, info: &ConnectionInfo) -> Self {
+        Self {
+            auditor,
+            info: Arc::new(Mutex::new(info.clone())),
+        }
+    }
+
+    /// Updates the information reported with the events, e.g. once the user is known.
+    pub(crate) fn update(&self, info: &ConnectionInfo) {
+        info.clone_into(&mut self.info.lock().expect("poisoned"));
+    }
+
+    pub(crate) fn report(&self, event: AuditEvent<'_>) {
+        let info = self.info.lock().expect("poisoned");
+
+        self.auditor.audit(&AuditRecord {
+            timestamp: SystemTime::now(),
+            connection: &info,
+            event,
+        });
+    }
+}
+
+/// Clipboard policy reporting the transfers to the auditor, after applying the policy set by the application, if any.
+#[derive(Debug)]
+pub(crate) struct AuditClipboardPolicy {
+    inner: Option<Box<dyn ClipboardPolicy>>,
+    audit: ConnectionAudit,
+}
+
+impl AuditClipboardPolicy {
+    pub(crate) fn new(inner: Option<Box<dyn ClipboardPolicy>>, audit: ConnectionAudit) -> Self {
+        Self { inner, audit }
+    }
+}
+
+impl ClipboardPolicy for AuditClipboardPolicy {
+    fn filter_formats(&self, direction: ClipboardDirection, formats: &mut Vec<ClipboardFormat>) {
+        if let Some(inner) = &self.inner {
+            inner.filter_formats(direction, formats);
+        }
+    }
+
+    fn check_format_data(
+        &self,
+        direction: ClipboardDirection,
+        format: Option<ClipboardFormatId>,
+        data: &[u8],
+    ) -> PolicyDecision {
+        let decision = self.inner.as_ref().map_or(PolicyDecision::Allow, |inner| {
+            inner.check_format_data(direction, format, data)
+        });
+
+        let (size, allowed) = transferred(&decision, data);
+        self.audit.report(AuditEvent::ClipboardData {
+            direction,
+            format,
+            size,
+            allowed,
+        });
+
+        decision
+    }
+
+    fn check_file_contents(&self, direction: ClipboardDirection, data: &[u8]) -> PolicyDecision {
+        let decision = self.inner.as_ref().map_or(PolicyDecision::Allow, |inner| {
+            inner.check_file_contents(direction, data)
+        });
+
+        let (size, allowed) = transferred(&decision, data);
+        self.audit.report(AuditEvent::ClipboardFileContents {
+            direction,
+            size,
+            allowed,
+        });
+
+        decision
+    }
+}
+
+/// Returns the size of the data transferred after the decision of the policy, and whether it is transferred.
+fn transferred(decision: &PolicyDecision, data: &[u8]) -> (usize, bool) {
+    match decision {
+        PolicyDecision::Allow => (data.len(), true),
+        PolicyDecision::Replace(data) => (data.len(), true),
+        PolicyDecision::Reject => (0, false),
+    }
+}
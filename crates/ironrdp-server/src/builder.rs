@@ -1,35 +1,74 @@
+use core::time::Duration;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use anyhow::Result;
+use tokio_rustls::rustls::RootCertStore;
 use tokio_rustls::TlsAcceptor;
 
 use super::clipboard::CliprdrServerFactory;
-use super::display::{DesktopSize, RdpServerDisplay};
-use super::handler::{KeyboardEvent, MouseEvent, RdpServerInputHandler};
+use super::display::{DesktopSize, DynRdpServerDisplay, RdpServerDisplay};
+use super::handler::{DynRdpServerInputHandler, KeyboardEvent, MouseEvent, RdpServerInputHandler};
+use super::listener::RdpServerListener;
 use super::server::*;
-use crate::{DisplayUpdate, RdpServerDisplayUpdates, SoundServerFactory};
+use super::session::RdpServerSessionFactory;
+use crate::custom_channel::CustomChannels;
+use crate::flow_control::DEFAULT_MAX_UNACKNOWLEDGED_FRAMES;
+use crate::{
+    AudioInputServerFactory, CapabilitiesHook, CredentialsValidator, DisplayUpdate, DriveServerFactory,
+    DynamicChannelFactory, H264EncoderFactory, HeartbeatOptions, InputLimits, QualityPolicy, RailServerFactory,
+    RdpServerAuditor, RdpServerAuthorizer, RdpServerDisplayUpdates, RdpServerEventHandler, RdpServerMetrics,
+    RdpServerReconnectHandler, RecordingOptions, RemoteFxQuality, SecurityPolicy, SoundServerFactory,
+    StaticChannelFactory, TlsCertificates, TlsServerAcceptor,
+};
 
 pub struct WantsAddr {}
 pub struct WantsSecurity {
     addr: SocketAddr,
+    listener: Option<Box<dyn RdpServerListener>>,
 }
 pub struct WantsHandler {
     addr: SocketAddr,
+    listener: Option<Box<dyn RdpServerListener>>,
     security: RdpServerSecurity,
 }
 pub struct WantsDisplay {
     addr: SocketAddr,
+    listener: Option<Box<dyn RdpServerListener>>,
     security: RdpServerSecurity,
-    handler: Box<dyn RdpServerInputHandler>,
+    handler: Box<dyn DynRdpServerInputHandler>,
 }
 pub struct BuilderDone {
     addr: SocketAddr,
+    listener: Option<Box<dyn RdpServerListener>>,
     security: RdpServerSecurity,
     with_remote_fx: bool,
-    handler: Box<dyn RdpServerInputHandler>,
-    display: Box<dyn RdpServerDisplay>,
+    remote_fx_quality: RemoteFxQuality,
+    handler: Box<dyn DynRdpServerInputHandler>,
+    display: Box<dyn DynRdpServerDisplay>,
     cliprdr_factory: Option<Box<dyn CliprdrServerFactory>>,
     sound_factory: Option<Box<dyn SoundServerFactory>>,
+    audio_input_factory: Option<Box<dyn AudioInputServerFactory>>,
+    drive_factory: Option<Box<dyn DriveServerFactory>>,
+    rail_factory: Option<Box<dyn RailServerFactory>>,
+    h264_factory: Option<Box<dyn H264EncoderFactory>>,
+    with_avc444: bool,
+    limits: ConnectionLimits,
+    max_unacknowledged_frames: Option<u32>,
+    heartbeat: Option<HeartbeatOptions>,
+    recording: Option<RecordingOptions>,
+    input_limits: InputLimits,
+    security_policy: SecurityPolicy,
+    quality_policy: Option<Arc<dyn QualityPolicy>>,
+    reconnect_handler: Option<Arc<dyn RdpServerReconnectHandler>>,
+    credentials_validator: Option<Arc<dyn CredentialsValidator>>,
+    capabilities_hook: Option<Arc<dyn CapabilitiesHook>>,
+    authorizer: Option<Arc<dyn RdpServerAuthorizer>>,
+    event_handler: Option<Arc<dyn RdpServerEventHandler>>,
+    auditor: Option<Arc<dyn RdpServerAuditor>>,
+    metrics: Option<Arc<dyn RdpServerMetrics>>,
+    session_factory: Option<Box<dyn RdpServerSessionFactory>>,
+    custom_channels: CustomChannels,
 }
 
 pub struct RdpServerBuilder<State> {
@@ -52,7 +91,32 @@ impl RdpServerBuilder<WantsAddr> {
     #[allow(clippy::unused_self)] // ensuring state transition from WantsAddr
     pub fn with_addr(self, addr: impl Into<SocketAddr>) -> RdpServerBuilder<WantsSecurity> {
         RdpServerBuilder {
-            state: WantsSecurity { addr: addr.into() },
+            state: WantsSecurity {
+                addr: addr.into(),
+                listener: None,
+            },
+        }
+    }
+
+    /// Accepts the connections from an already bound listener, instead of binding an address.
+    ///
+    /// Besides TCP listeners, this allows listening on a Unix domain socket, or on any transport
+    /// implementing [`RdpServerListener`].
+    #[allow(clippy::unused_self)] // ensuring state transition from WantsAddr
+    pub fn with_listener<L>(self, listener: L) -> RdpServerBuilder<WantsSecurity>
+    where
+        L: RdpServerListener + 'static,
+    {
+        // The address is informative only, the connections are accepted from the listener.
+        let addr = listener
+            .local_addr()
+            .unwrap_or_else(|| SocketAddr::from(([0, 0, 0, 0], 0)));
+
+        RdpServerBuilder {
+            state: WantsSecurity {
+                addr,
+                listener: Some(Box::new(listener)),
+            },
         }
     }
 }
@@ -62,28 +126,104 @@ impl RdpServerBuilder<WantsSecurity> {
         RdpServerBuilder {
             state: WantsHandler {
                 addr: self.state.addr,
+                listener: self.state.listener,
                 security: RdpServerSecurity::None,
             },
         }
     }
 
+    /// Uses TLS, with the given acceptor or rustls `Arc<ServerConfig>`.
+    ///
+    /// The configuration may use a custom certificate resolver, see also
+    /// [`RdpServerBuilder::with_tls_certificates`].
     pub fn with_tls(self, acceptor: impl Into<TlsAcceptor>) -> RdpServerBuilder<WantsHandler> {
         RdpServerBuilder {
             state: WantsHandler {
                 addr: self.state.addr,
+                listener: self.state.listener,
                 security: RdpServerSecurity::Tls(acceptor.into()),
             },
         }
     }
 
+    /// Uses TLS, with the certificates selected using the server name requested by the client.
+    ///
+    /// The certificates can be replaced while the server is running, without restarting it.
+    pub fn with_tls_certificates(self, certificates: Arc<TlsCertificates>) -> RdpServerBuilder<WantsHandler> {
+        self.with_tls(certificates.acceptor())
+    }
+
+    /// Uses TLS, requiring the clients to present a certificate issued by one of the given authorities (mutual TLS).
+    ///
+    /// The certificate of the client is given in the [`ConnectionInfo`](crate::ConnectionInfo) of the
+    /// connection, see [`RdpServerBuilder::with_authorizer`] to authorize the devices.
+    pub fn with_tls_client_auth(
+        self,
+        certificates: Arc<TlsCertificates>,
+        client_roots: RootCertStore,
+    ) -> Result<RdpServerBuilder<WantsHandler>> {
+        certificates.require_client_certificates(client_roots)?;
+
+        Ok(self.with_tls_certificates(certificates))
+    }
+
     pub fn with_hybrid(self, acceptor: impl Into<TlsAcceptor>, pub_key: Vec<u8>) -> RdpServerBuilder<WantsHandler> {
         RdpServerBuilder {
             state: WantsHandler {
                 addr: self.state.addr,
+                listener: self.state.listener,
                 security: RdpServerSecurity::Hybrid((acceptor.into(), pub_key)),
             },
         }
     }
+
+    /// Uses TLS and CredSSP, with the certificates selected using the server name requested by the client.
+    ///
+    /// The certificates can be replaced while the server is running, without restarting it.
+    pub fn with_hybrid_certificates(self, certificates: Arc<TlsCertificates>) -> RdpServerBuilder<WantsHandler> {
+        RdpServerBuilder {
+            state: WantsHandler {
+                addr: self.state.addr,
+                listener: self.state.listener,
+                security: RdpServerSecurity::HybridCertificates(certificates),
+            },
+        }
+    }
+
+    /// Uses TLS, with another implementation than rustls.
+    pub fn with_custom_tls(self, acceptor: Arc<dyn TlsServerAcceptor>) -> RdpServerBuilder<WantsHandler> {
+        RdpServerBuilder {
+            state: WantsHandler {
+                addr: self.state.addr,
+                listener: self.state.listener,
+                security: RdpServerSecurity::CustomTls(acceptor),
+            },
+        }
+    }
+
+    /// Uses TLS and CredSSP, with another TLS implementation than rustls.
+    pub fn with_custom_hybrid(self, acceptor: Arc<dyn TlsServerAcceptor>) -> RdpServerBuilder<WantsHandler> {
+        RdpServerBuilder {
+            state: WantsHandler {
+                addr: self.state.addr,
+                listener: self.state.listener,
+                security: RdpServerSecurity::CustomHybrid(acceptor),
+            },
+        }
+    }
+
+    /// Uses Standard RDP Security, for the clients which don't support TLS.
+    ///
+    /// The PDUs are encrypted with RC4, using keys exchanged with the RSA key of the server.
+    pub fn with_rdp_security(self, key: ServerSecurityKey) -> RdpServerBuilder<WantsHandler> {
+        RdpServerBuilder {
+            state: WantsHandler {
+                addr: self.state.addr,
+                listener: self.state.listener,
+                security: RdpServerSecurity::Rdp(key),
+            },
+        }
+    }
 }
 
 impl RdpServerBuilder<WantsHandler> {
@@ -94,6 +234,7 @@ impl RdpServerBuilder<WantsHandler> {
         RdpServerBuilder {
             state: WantsDisplay {
                 addr: self.state.addr,
+                listener: self.state.listener,
                 security: self.state.security,
                 handler: Box::new(handler),
             },
@@ -104,6 +245,7 @@ impl RdpServerBuilder<WantsHandler> {
         RdpServerBuilder {
             state: WantsDisplay {
                 addr: self.state.addr,
+                listener: self.state.listener,
                 security: self.state.security,
                 handler: Box::new(NoopInputHandler),
             },
@@ -119,12 +261,35 @@ impl RdpServerBuilder<WantsDisplay> {
         RdpServerBuilder {
             state: BuilderDone {
                 addr: self.state.addr,
+                listener: self.state.listener,
                 security: self.state.security,
                 handler: self.state.handler,
                 display: Box::new(display),
                 sound_factory: None,
+                audio_input_factory: None,
+                drive_factory: None,
+                rail_factory: None,
                 cliprdr_factory: None,
                 with_remote_fx: true,
+                remote_fx_quality: RemoteFxQuality::default(),
+                h264_factory: None,
+                with_avc444: true,
+                limits: ConnectionLimits::default(),
+                max_unacknowledged_frames: Some(DEFAULT_MAX_UNACKNOWLEDGED_FRAMES),
+                heartbeat: None,
+                recording: None,
+                input_limits: InputLimits::default(),
+                security_policy: SecurityPolicy::default(),
+                quality_policy: None,
+                reconnect_handler: None,
+                credentials_validator: None,
+                capabilities_hook: None,
+                authorizer: None,
+                event_handler: None,
+                auditor: None,
+                metrics: None,
+                session_factory: None,
+                custom_channels: CustomChannels::default(),
             },
         }
     }
@@ -133,12 +298,35 @@ impl RdpServerBuilder<WantsDisplay> {
         RdpServerBuilder {
             state: BuilderDone {
                 addr: self.state.addr,
+                listener: self.state.listener,
                 security: self.state.security,
                 handler: self.state.handler,
                 display: Box::new(NoopDisplay),
                 sound_factory: None,
+                audio_input_factory: None,
+                drive_factory: None,
+                rail_factory: None,
                 cliprdr_factory: None,
                 with_remote_fx: true,
+                remote_fx_quality: RemoteFxQuality::default(),
+                h264_factory: None,
+                with_avc444: true,
+                limits: ConnectionLimits::default(),
+                max_unacknowledged_frames: Some(DEFAULT_MAX_UNACKNOWLEDGED_FRAMES),
+                heartbeat: None,
+                recording: None,
+                input_limits: InputLimits::default(),
+                security_policy: SecurityPolicy::default(),
+                quality_policy: None,
+                reconnect_handler: None,
+                credentials_validator: None,
+                capabilities_hook: None,
+                authorizer: None,
+                event_handler: None,
+                auditor: None,
+                metrics: None,
+                session_factory: None,
+                custom_channels: CustomChannels::default(),
             },
         }
     }
@@ -155,23 +343,251 @@ impl RdpServerBuilder<BuilderDone> {
         self
     }
 
+    /// Receives the audio recorded by the clients (e.g. their microphone) using the given factory.
+    pub fn with_audio_input_factory(mut self, factory: Option<Box<dyn AudioInputServerFactory>>) -> Self {
+        self.state.audio_input_factory = factory;
+        self
+    }
+
+    /// Gives access to the drives redirected by the clients using the given factory.
+    pub fn with_drive_factory(mut self, factory: Option<Box<dyn DriveServerFactory>>) -> Self {
+        self.state.drive_factory = factory;
+        self
+    }
+
+    /// Publishes remote applications (RemoteApp) using the given factory, with the windows described by
+    /// [`DisplayUpdate::Window`] updates.
+    pub fn with_rail_factory(mut self, factory: Option<Box<dyn RailServerFactory>>) -> Self {
+        self.state.rail_factory = factory;
+        self
+    }
+
     pub fn with_remote_fx(mut self, enabled: bool) -> Self {
         self.state.with_remote_fx = enabled;
         self
     }
 
+    /// Sets the quality of the RemoteFX encoding, trading image quality for bandwidth.
+    pub fn with_remote_fx_quality(mut self, quality: RemoteFxQuality) -> Self {
+        self.state.remote_fx_quality = quality;
+        self
+    }
+
+    /// Enables the graphics pipeline, encoding the display updates using H.264.
+    ///
+    /// Clients not supporting the AVC codecs of the graphics pipeline are still receiving the
+    /// display updates using the fast-path output.
+    pub fn with_h264_encoder_factory(mut self, factory: Option<Box<dyn H264EncoderFactory>>) -> Self {
+        self.state.h264_factory = factory;
+        self
+    }
+
+    /// Sets whether AVC444 is used instead of AVC420 when supported by the client.
+    ///
+    /// AVC444 preserves the full chroma resolution, which keeps text crisp, at the cost of encoding
+    /// two H.264 streams.
+    pub fn with_avc444(mut self, enabled: bool) -> Self {
+        self.state.with_avc444 = enabled;
+        self
+    }
+
+    /// Limits the number of concurrent sessions, see [`ConnectionLimits::max_connections`].
+    pub fn with_max_connections(mut self, max: usize) -> Self {
+        self.state.limits.max_connections = Some(max);
+        self
+    }
+
+    /// Limits the number of concurrent sessions from a single IP address, see
+    /// [`ConnectionLimits::max_connections_per_ip`].
+    pub fn with_max_connections_per_ip(mut self, max: usize) -> Self {
+        self.state.limits.max_connections_per_ip = Some(max);
+        self
+    }
+
+    /// Disconnects the clients which didn't send any input event for the given duration.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.state.limits.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Disconnects the clients once connected for the given duration.
+    pub fn with_session_timeout(mut self, timeout: Duration) -> Self {
+        self.state.limits.session_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum number of frames sent to a client and not acknowledged yet, see
+    /// [`RdpServerOptions::max_unacknowledged_frames`].
+    ///
+    /// Defaults to 4 frames. `None` disables the flow control.
+    pub fn with_max_unacknowledged_frames(mut self, max: Option<u32>) -> Self {
+        self.state.max_unacknowledged_frames = max;
+        self
+    }
+
+    /// Sends heartbeats to the clients supporting them, and optionally disconnects the clients from which
+    /// nothing is received for too long, see [`HeartbeatOptions`].
+    ///
+    /// Disabled by default.
+    pub fn with_heartbeat(mut self, heartbeat: Option<HeartbeatOptions>) -> Self {
+        self.state.heartbeat = heartbeat;
+        self
+    }
+
+    /// Coalesces the mouse moves and limits the rate of the input events of each connection, see
+    /// [`InputLimits`].
+    ///
+    /// Unlimited by default.
+    pub fn with_input_limits(mut self, limits: InputLimits) -> Self {
+        self.state.input_limits = limits;
+        self
+    }
+
+    /// Refuses the clients not meeting the given security requirements, e.g. not supporting NLA or negotiating
+    /// an old TLS version, see [`SecurityPolicy`].
+    ///
+    /// The security protocols enabled by default are all accepted. The TLS requirements are not checked when the
+    /// TLS handshake is done by a custom [`TlsServerAcceptor`].
+    pub fn with_security_policy(mut self, policy: SecurityPolicy) -> Self {
+        self.state.security_policy = policy;
+        self
+    }
+
+    /// Records the display updates and input events of each connection to a file, see
+    /// [`SessionRecordingReader`](crate::SessionRecordingReader) to read them.
+    ///
+    /// Disabled by default. A connection is refused if its recording can't be created.
+    pub fn with_recording(mut self, recording: Option<RecordingOptions>) -> Self {
+        self.state.recording = recording;
+        self
+    }
+
+    /// Adapts the encoding quality of each connection to its network conditions, e.g. with a
+    /// [`LatencyQualityPolicy`](crate::LatencyQualityPolicy).
+    ///
+    /// The network conditions are measured from the frame acknowledgements, the quality of clients not
+    /// acknowledging the frames is not adapted.
+    pub fn with_quality_policy(mut self, policy: Option<Arc<dyn QualityPolicy>>) -> Self {
+        self.state.quality_policy = policy;
+        self
+    }
+
+    /// Validates the users credentials with the given validator.
+    ///
+    /// Combined with [`RdpServerBuilder::with_hybrid`], this requires the clients to authenticate
+    /// using NLA (CredSSP) before the connection is established.
+    pub fn with_credentials_validator(mut self, validator: Option<Arc<dyn CredentialsValidator>>) -> Self {
+        self.state.credentials_validator = validator;
+        self
+    }
+
+    /// Inspects and overrides the capability sets exchanged with the clients using the given hook, e.g. to
+    /// disable a codec advertised by some clients.
+    pub fn with_capabilities_hook(mut self, hook: Option<Arc<dyn CapabilitiesHook>>) -> Self {
+        self.state.capabilities_hook = hook;
+        self
+    }
+
+    /// Authorizes the connections with the given authorizer, once the users are authenticated.
+    pub fn with_authorizer(mut self, authorizer: Option<Arc<dyn RdpServerAuthorizer>>) -> Self {
+        self.state.authorizer = authorizer;
+        self
+    }
+
+    /// Reports the lifecycle of the connections to the given handler.
+    pub fn with_event_handler(mut self, handler: Option<Arc<dyn RdpServerEventHandler>>) -> Self {
+        self.state.event_handler = handler;
+        self
+    }
+
+    /// Reports the security-relevant events of the connections to the given auditor: the authentications, the
+    /// clipboard transfers, the accesses to the redirected drives and the disconnections.
+    pub fn with_auditor(mut self, auditor: Option<Arc<dyn RdpServerAuditor>>) -> Self {
+        self.state.auditor = auditor;
+        self
+    }
+
+    /// Forwards the statistics of the connections to the given metrics, e.g. to export them.
+    pub fn with_metrics(mut self, metrics: Option<Arc<dyn RdpServerMetrics>>) -> Self {
+        self.state.metrics = metrics;
+        self
+    }
+
+    /// Enables the auto-reconnection of the clients, which are re-attached to their session by the given
+    /// handler.
+    ///
+    /// The clients supporting it are given an auto-reconnect cookie once their session starts, with which
+    /// they reconnect when the connection is lost.
+    pub fn with_auto_reconnect(mut self, handler: Option<Arc<dyn RdpServerReconnectHandler>>) -> Self {
+        self.state.reconnect_handler = handler;
+        self
+    }
+
+    /// Runs the sessions concurrently, with the input handler and display of each session created
+    /// by the given factory, from the address of the client.
+    ///
+    /// The factory can be a closure, and can also replace the channels and the security of the session.
+    pub fn with_session_factory(mut self, factory: Option<Box<dyn RdpServerSessionFactory>>) -> Self {
+        self.state.session_factory = factory;
+        self
+    }
+
+    /// Registers a custom static virtual channel, e.g. `|| MyChannel::new()`, created for each connection.
+    pub fn with_static_channel<F>(mut self, factory: F) -> Self
+    where
+        F: StaticChannelFactory + 'static,
+    {
+        self.state.custom_channels.add_static(factory);
+        self
+    }
+
+    /// Registers a custom dynamic virtual channel, e.g. `|| MyChannel::new()`, created for each connection.
+    pub fn with_dynamic_channel<F>(mut self, factory: F) -> Self
+    where
+        F: DynamicChannelFactory + 'static,
+    {
+        self.state.custom_channels.add_dynamic(factory);
+        self
+    }
+
     pub fn build(self) -> RdpServer {
-        RdpServer::new(
+        let mut server = RdpServer::new(
             RdpServerOptions {
                 addr: self.state.addr,
                 security: self.state.security,
                 with_remote_fx: self.state.with_remote_fx,
+                remote_fx_quality: self.state.remote_fx_quality,
+                with_avc444: self.state.with_avc444,
+                limits: self.state.limits,
+                max_unacknowledged_frames: self.state.max_unacknowledged_frames,
+                heartbeat: self.state.heartbeat,
+                recording: self.state.recording,
+                input_limits: self.state.input_limits,
+                security_policy: self.state.security_policy,
             },
             self.state.handler,
             self.state.display,
             self.state.sound_factory,
             self.state.cliprdr_factory,
-        )
+            self.state.h264_factory,
+        );
+        server.set_credentials_validator(self.state.credentials_validator);
+        server.set_capabilities_hook(self.state.capabilities_hook);
+        server.set_authorizer(self.state.authorizer);
+        server.set_event_handler(self.state.event_handler);
+        server.set_auditor(self.state.auditor);
+        server.set_metrics(self.state.metrics);
+        server.set_quality_policy(self.state.quality_policy);
+        server.set_reconnect_handler(self.state.reconnect_handler);
+        server.set_session_factory(self.state.session_factory);
+        server.set_audio_input_factory(self.state.audio_input_factory);
+        server.set_drive_factory(self.state.drive_factory);
+        server.set_rail_factory(self.state.rail_factory);
+        server.set_custom_channels(self.state.custom_channels);
+        if let Some(listener) = self.state.listener {
+            server.set_listener(listener);
+        }
+        server
     }
 }
 
@@ -184,7 +600,6 @@ impl RdpServerInputHandler for NoopInputHandler {
 
 struct NoopDisplayUpdates;
 
-#[async_trait::async_trait]
 impl RdpServerDisplayUpdates for NoopDisplayUpdates {
     async fn next_update(&mut self) -> Option<DisplayUpdate> {
         let () = core::future::pending().await;
@@ -194,13 +609,14 @@ impl RdpServerDisplayUpdates for NoopDisplayUpdates {
 
 struct NoopDisplay;
 
-#[async_trait::async_trait]
 impl RdpServerDisplay for NoopDisplay {
+    type Updates = NoopDisplayUpdates;
+
     async fn size(&mut self) -> DesktopSize {
         DesktopSize { width: 0, height: 0 }
     }
 
-    async fn updates(&mut self) -> Result<Box<dyn RdpServerDisplayUpdates>> {
-        Ok(Box::new(NoopDisplayUpdates {}))
+    async fn updates(&mut self) -> Result<NoopDisplayUpdates> {
+        Ok(NoopDisplayUpdates {})
     }
 }
@@ -2,23 +2,44 @@ use ironrdp_pdu::rdp::capability_sets::{self, GeneralExtraFlags};
 
 use crate::{DesktopSize, RdpServerOptions};
 
-pub(crate) fn capabilities(opts: &RdpServerOptions, size: DesktopSize) -> Vec<capability_sets::CapabilitySet> {
-    vec![
+pub(crate) fn capabilities(
+    opts: &RdpServerOptions,
+    size: DesktopSize,
+    rail: bool,
+) -> Vec<capability_sets::CapabilitySet> {
+    let mut capabilities = vec![
         capability_sets::CapabilitySet::General(general_capabilities()),
         capability_sets::CapabilitySet::Bitmap(bitmap_capabilities(&size)),
         capability_sets::CapabilitySet::Order(order_capabilities()),
         capability_sets::CapabilitySet::SurfaceCommands(surface_capabilities()),
         capability_sets::CapabilitySet::Pointer(pointer_capabilities()),
+        capability_sets::CapabilitySet::LargePointer(large_pointer_capabilities()),
         capability_sets::CapabilitySet::Input(input_capabilities()),
         capability_sets::CapabilitySet::VirtualChannel(virtual_channel_capabilities()),
         capability_sets::CapabilitySet::MultiFragmentUpdate(multifragment_update()),
         capability_sets::CapabilitySet::BitmapCodecs(bitmap_codecs(opts.with_remote_fx)),
-    ]
+    ];
+
+    if let Some(max) = opts.max_unacknowledged_frames {
+        capabilities.push(capability_sets::CapabilitySet::FrameAcknowledge(
+            capability_sets::FrameAcknowledge {
+                max_unacknowledged_frame_count: max,
+            },
+        ));
+    }
+
+    if rail {
+        capabilities.push(capability_sets::CapabilitySet::Rail(rail_capabilities()));
+        capabilities.push(capability_sets::CapabilitySet::WindowList(window_list_capabilities()));
+    }
+
+    capabilities
 }
 
 fn general_capabilities() -> capability_sets::General {
     capability_sets::General {
         extra_flags: GeneralExtraFlags::FASTPATH_OUTPUT_SUPPORTED,
+        suppress_output_support: true,
         ..Default::default()
     }
 }
@@ -57,6 +78,13 @@ fn pointer_capabilities() -> capability_sets::Pointer {
     }
 }
 
+fn large_pointer_capabilities() -> capability_sets::LargePointer {
+    capability_sets::LargePointer {
+        flags: capability_sets::LargePointerSupportFlags::UP_TO_96X96_PIXELS
+            | capability_sets::LargePointerSupportFlags::UP_TO_384X384_PIXELS,
+    }
+}
+
 fn input_capabilities() -> capability_sets::Input {
     capability_sets::Input {
         input_flags: capability_sets::InputFlags::SCANCODES
@@ -88,6 +116,20 @@ fn multifragment_update() -> capability_sets::MultifragmentUpdate {
     }
 }
 
+/// Remote Programs Capability Set (TS_RAIL_CAPABILITYSET), with `TS_RAIL_LEVEL_SUPPORTED`.
+fn rail_capabilities() -> Vec<u8> {
+    0x0000_0001u32.to_le_bytes().to_vec()
+}
+
+/// Window List Capability Set (TS_WINDOW_CAPABILITYSET), with `TS_WINDOW_LEVEL_SUPPORTED`.
+fn window_list_capabilities() -> Vec<u8> {
+    let mut capset = 0x0000_0001u32.to_le_bytes().to_vec();
+    // NumIconCaches and NumIconCacheEntries, the icons are not sent.
+    capset.push(3);
+    capset.extend_from_slice(&12u16.to_le_bytes());
+    capset
+}
+
 fn bitmap_codecs(with_remote_fx: bool) -> capability_sets::BitmapCodecs {
     let mut codecs = Vec::new();
     if with_remote_fx {
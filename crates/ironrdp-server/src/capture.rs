@@ -0,0 +1,260 @@
+//! Display source capturing the local desktop.
+//!
+//! The primary display is captured with the native APIs of the platform, through the `scap` crate:
+//! Windows.Graphics.Capture on Windows, PipeWire (with the ScreenCast portal) on Linux and ScreenCaptureKit
+//! on macOS.
+
+use core::num::NonZeroU16;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use anyhow::{anyhow, Context as _, Result};
+use bytes::Bytes;
+use ironrdp_graphics::image_processing::PixelFormat;
+use scap::capturer::{Capturer, Options};
+use scap::frame::{Frame, FrameType};
+
+use crate::{
+    display_channel, BitmapUpdate, DesktopSize, DisplayFrame, DisplayUpdate, DisplayUpdateReceiver,
+    DisplayUpdateSender, RdpServerDisplay,
+};
+
+/// Default frame rate of the capture.
+const DEFAULT_FPS: u32 = 30;
+
+/// Display source capturing the primary display of the local machine.
+///
+/// The capture runs on its own thread from the creation of the display until it is dropped, and the frames are
+/// pushed to the client connected, the frames not sent yet being coalesced. The display can't be resized by the
+/// clients.
+pub struct ScreenCaptureDisplay {
+    shared: Arc<Mutex<Shared>>,
+    stop: Arc<AtomicBool>,
+}
+
+struct Shared {
+    /// Latest captured frame.
+    frame: BitmapUpdate,
+    /// Sender of the connected client, if any.
+    sender: Option<DisplayUpdateSender>,
+}
+
+impl ScreenCaptureDisplay {
+    /// Starts capturing the primary display at 30 frames per second.
+    pub fn new() -> Result<Self> {
+        Self::with_fps(DEFAULT_FPS)
+    }
+
+    /// Starts capturing the primary display at the given frame rate.
+    ///
+    /// Blocks until the first frame is captured, requesting the permission to capture the screen if needed.
+    pub fn with_fps(fps: u32) -> Result<Self> {
+        if !scap::is_supported() {
+            return Err(anyhow!("screen capture is not supported on this platform"));
+        }
+
+        if !scap::has_permission() && !scap::request_permission() {
+            return Err(anyhow!("permission to capture the screen denied"));
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (first_frame_tx, first_frame_rx) = mpsc::sync_channel(1);
+
+        let capture_stop = Arc::clone(&stop);
+        let (shared_tx, shared_rx) = mpsc::sync_channel::<Arc<Mutex<Shared>>>(1);
+
+        // The capturer is not `Send` on all the platforms, so it lives on the capture thread.
+        thread::Builder::new()
+            .name("screen-capture".to_owned())
+            .spawn(move || {
+                let options = Options {
+                    fps,
+                    show_cursor: true,
+                    output_type: FrameType::BGRAFrame,
+                    ..Default::default()
+                };
+
+                let mut capturer = match Capturer::build(options) {
+                    Ok(capturer) => capturer,
+                    Err(error) => {
+                        let _ = first_frame_tx.send(Err(anyhow!(error).context("screen capturer")));
+                        return;
+                    }
+                };
+
+                capturer.start_capture();
+                capture_loop(&capturer, &capture_stop, first_frame_tx, shared_rx);
+                capturer.stop_capture();
+
+                debug!("Screen capture stopped");
+            })
+            .context("spawn the capture thread")?;
+
+        let frame = first_frame_rx
+            .recv()
+            .context("the capture stopped before the first frame")??;
+
+        let shared = Arc::new(Mutex::new(Shared { frame, sender: None }));
+        shared_tx
+            .send(Arc::clone(&shared))
+            .context("the capture stopped after the first frame")?;
+
+        Ok(Self { shared, stop })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Shared> {
+        self.shared.lock().expect("poisoned")
+    }
+}
+
+impl Drop for ScreenCaptureDisplay {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl core::fmt::Debug for ScreenCaptureDisplay {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ScreenCaptureDisplay").finish_non_exhaustive()
+    }
+}
+
+impl RdpServerDisplay for ScreenCaptureDisplay {
+    type Updates = DisplayUpdateReceiver;
+
+    async fn size(&mut self) -> DesktopSize {
+        size(&self.lock().frame)
+    }
+
+    async fn updates(&mut self) -> Result<DisplayUpdateReceiver> {
+        let (sender, receiver) = display_channel();
+
+        let mut shared = self.lock();
+        sender.submit(full_frame(&shared.frame));
+        // The updates of the previous connection end with its sender.
+        shared.sender = Some(sender);
+
+        Ok(receiver)
+    }
+
+    fn request_refresh(&mut self) {
+        let shared = self.lock();
+
+        if let Some(sender) = &shared.sender {
+            sender.submit(full_frame(&shared.frame));
+        }
+    }
+}
+
+fn capture_loop(
+    capturer: &Capturer,
+    stop: &AtomicBool,
+    first_frame_tx: mpsc::SyncSender<Result<BitmapUpdate>>,
+    shared_rx: mpsc::Receiver<Arc<Mutex<Shared>>>,
+) {
+    // Waits for the first frame, which gives the size of the display.
+    let first_frame = loop {
+        match capturer.get_next_frame() {
+            Ok(frame) => {
+                if let Some(bitmap) = to_bitmap(frame) {
+                    break bitmap;
+                }
+            }
+            Err(_) => {
+                let _ = first_frame_tx.send(Err(anyhow!("the screen capture ended")));
+                return;
+            }
+        }
+    };
+
+    if first_frame_tx.send(Ok(first_frame)).is_err() {
+        return;
+    }
+
+    let Ok(shared) = shared_rx.recv() else {
+        return;
+    };
+
+    while !stop.load(Ordering::Relaxed) {
+        let Ok(frame) = capturer.get_next_frame() else {
+            warn!("The screen capture ended");
+            break;
+        };
+
+        let Some(bitmap) = to_bitmap(frame) else {
+            continue;
+        };
+
+        let mut shared = shared.lock().expect("poisoned");
+
+        let resized = size(&shared.frame) != size(&bitmap);
+        shared.frame = bitmap;
+
+        if let Some(sender) = &shared.sender {
+            if resized {
+                debug!(size = ?size(&shared.frame), "Captured display resized");
+                sender.send(DisplayUpdate::Resize(size(&shared.frame)));
+            }
+
+            // The regions which changed are found by the damage tracking of the encoder.
+            sender.submit(full_frame(&shared.frame));
+        }
+    }
+}
+
+/// Converts a captured frame, or returns `None` if its format is not supported.
+fn to_bitmap(frame: Frame) -> Option<BitmapUpdate> {
+    let (format, width, height, data) = match frame {
+        Frame::BGRA(frame) => (PixelFormat::BgrA32, frame.width, frame.height, frame.data),
+        Frame::BGRx(frame) => (PixelFormat::BgrX32, frame.width, frame.height, frame.data),
+        Frame::BGR0(frame) => (PixelFormat::BgrX32, frame.width, frame.height, frame.data),
+        Frame::RGBx(frame) => (PixelFormat::RgbX32, frame.width, frame.height, frame.data),
+        Frame::XBGR(frame) => (PixelFormat::XBgr32, frame.width, frame.height, frame.data),
+        Frame::RGB(_) | Frame::YUVFrame(_) => {
+            warn!("Unsupported format of the captured frame");
+            return None;
+        }
+    };
+
+    let width = NonZeroU16::new(u16::try_from(width).ok()?)?;
+    let height = NonZeroU16::new(u16::try_from(height).ok()?)?;
+
+    // The rows may be padded.
+    let stride = data.len() / usize::from(height.get());
+    if stride < usize::from(width.get()) * usize::from(format.bytes_per_pixel()) {
+        warn!(
+            width = width.get(),
+            height = height.get(),
+            len = data.len(),
+            "Captured frame too small"
+        );
+        return None;
+    }
+
+    Some(BitmapUpdate {
+        x: 0,
+        y: 0,
+        width,
+        height,
+        format,
+        data: Bytes::from(data),
+        stride,
+    })
+}
+
+fn full_frame(bitmap: &BitmapUpdate) -> DisplayFrame {
+    DisplayFrame {
+        bitmap: bitmap.clone(),
+        damage: Vec::new(),
+        timestamp: Instant::now(),
+    }
+}
+
+fn size(bitmap: &BitmapUpdate) -> DesktopSize {
+    DesktopSize {
+        width: bitmap.width.get(),
+        height: bitmap.height.get(),
+    }
+}
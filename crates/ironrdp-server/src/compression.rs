@@ -0,0 +1,124 @@
+//! Bulk compression of the slow-path PDUs and of the static virtual channel data sent to the client.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use ironrdp_core::WriteBuf;
+use ironrdp_graphics::bulk::BulkCompressor;
+use ironrdp_pdu::rdp::capability_sets::{CapabilitySet, VirtualChannelFlags};
+use ironrdp_pdu::rdp::client_info::CompressionType;
+use ironrdp_pdu::rdp::headers::CompressionFlags;
+use ironrdp_svc::{
+    server_encode_svc_messages_compressed_into, server_encode_svc_messages_into, ChannelFlags, SvcCompressor,
+    SvcMessage,
+};
+
+/// Size of the Share Control Header and of the Share Data Header, preceding the data of a share data PDU.
+const SHARE_DATA_HEADERS_SIZE: usize = 18;
+
+/// Offset of the compressedType field in the Share Data Header.
+const COMPRESSED_TYPE_OFFSET: usize = 15;
+
+/// Offset of the compressedLength field in the Share Data Header.
+const COMPRESSED_LENGTH_OFFSET: usize = 16;
+
+/// Compressors of the data sent to a client supporting the bulk compression.
+///
+/// The slow-path PDUs and the virtual channel data are decompressed separately by the client, each with its own
+/// history.
+#[derive(Clone)]
+pub(crate) struct Compression {
+    share_data: Arc<Mutex<BulkCompressor>>,
+    channels: Option<Arc<Mutex<ChannelCompressor>>>,
+}
+
+impl Compression {
+    /// Creates the compressors of the highest compression type supported by the client, if it advertised the
+    /// compression support in its client info.
+    pub(crate) fn new(
+        client_compression: Option<CompressionType>,
+        client_capabilities: &[CapabilitySet],
+    ) -> Option<Self> {
+        let compression_type = client_compression?;
+
+        let channels_compression = client_capabilities.iter().any(|capability| match capability {
+            CapabilitySet::VirtualChannel(vc) => vc.flags.contains(VirtualChannelFlags::COMPRESSION_SERVER_TO_CLIENT),
+            _ => false,
+        });
+
+        // The virtual channel data is compressed with MPPC only.
+        let channels = channels_compression.then(|| {
+            let compression_type = match compression_type {
+                CompressionType::K8 => CompressionType::K8,
+                CompressionType::K64 | CompressionType::Rdp6 | CompressionType::Rdp61 => CompressionType::K64,
+            };
+            Arc::new(Mutex::new(ChannelCompressor(BulkCompressor::new(compression_type))))
+        });
+
+        let share_data = BulkCompressor::new(compression_type);
+        debug!(
+            share_data = ?share_data.compression_type(),
+            channels = channels.is_some(),
+            "Bulk compression enabled"
+        );
+
+        Some(Self {
+            share_data: Arc::new(Mutex::new(share_data)),
+            channels,
+        })
+    }
+
+    /// Compresses the data of an encoded Share Control Header carrying a share data PDU, in place.
+    pub(crate) fn compress_share_data(&self, pdu: &mut Vec<u8>) -> Result<()> {
+        let data = pdu.get(SHARE_DATA_HEADERS_SIZE..).context("share data PDU too short")?;
+
+        let mut compressed = Vec::new();
+        let mut compressor = self.share_data.lock().expect("poisoned");
+        let flags = compressor.compress(data, &mut compressed);
+        pdu[COMPRESSED_TYPE_OFFSET] = flags.bits() | compressor.compression_type() as u8;
+
+        if flags.contains(CompressionFlags::COMPRESSED) {
+            pdu.truncate(SHARE_DATA_HEADERS_SIZE);
+            pdu.extend_from_slice(&compressed);
+
+            // The total length and the compressed length both include the headers.
+            let length = u16::try_from(pdu.len()).context("compressed share data PDU too large")?;
+            pdu[..2].copy_from_slice(&length.to_le_bytes());
+            pdu[COMPRESSED_LENGTH_OFFSET..COMPRESSED_LENGTH_OFFSET + 2].copy_from_slice(&length.to_le_bytes());
+        }
+
+        Ok(())
+    }
+
+    /// Encodes the messages of a static virtual channel, compressing them if the client supports it.
+    pub(crate) fn encode_svc_messages(
+        compression: Option<&Self>,
+        messages: Vec<SvcMessage>,
+        channel_id: u16,
+        initiator_id: u16,
+        buf: &mut WriteBuf,
+    ) -> Result<usize> {
+        let written = match compression.and_then(|compression| compression.channels.as_ref()) {
+            Some(channels) => {
+                let mut compressor = channels.lock().expect("poisoned");
+                server_encode_svc_messages_compressed_into(messages, channel_id, initiator_id, &mut *compressor, buf)?
+            }
+            None => server_encode_svc_messages_into(messages, channel_id, initiator_id, buf)?,
+        };
+
+        Ok(written)
+    }
+}
+
+/// Compressor of the static virtual channel data, with its own history.
+struct ChannelCompressor(BulkCompressor);
+
+impl SvcCompressor for ChannelCompressor {
+    fn compress(&mut self, input: &[u8], output: &mut Vec<u8>) -> ChannelFlags {
+        let flags = self.0.compress(input, output);
+        let compression_type = self.0.compression_type() as u8;
+
+        // The compression flags and type are in the third byte of the channel PDU header flags.
+        ChannelFlags::from_bits_retain(u32::from(flags.bits() | compression_type) << 16)
+    }
+}
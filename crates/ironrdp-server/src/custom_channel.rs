@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use ironrdp_acceptor::Acceptor;
+use ironrdp_dvc::{DrdynvcServer, DvcServerProcessor};
+use ironrdp_svc::SvcServerProcessor;
+
+/// Creates the processor of a custom static virtual channel, for each connection.
+///
+/// A closure returning the processor is a factory.
+pub trait StaticChannelFactory: Send + Sync {
+    type Processor: SvcServerProcessor + 'static;
+
+    fn build_processor(&self) -> Self::Processor;
+}
+
+impl<F, P> StaticChannelFactory for F
+where
+    F: Fn() -> P + Send + Sync,
+    P: SvcServerProcessor + 'static,
+{
+    type Processor = P;
+
+    fn build_processor(&self) -> P {
+        self()
+    }
+}
+
+/// Creates the processor of a custom dynamic virtual channel, for each connection.
+///
+/// A closure returning the processor is a factory.
+pub trait DynamicChannelFactory: Send + Sync {
+    type Processor: DvcServerProcessor + 'static;
+
+    fn build_processor(&self) -> Self::Processor;
+}
+
+impl<F, P> DynamicChannelFactory for F
+where
+    F: Fn() -> P + Send + Sync,
+    P: DvcServerProcessor + 'static,
+{
+    type Processor = P;
+
+    fn build_processor(&self) -> P {
+        self()
+    }
+}
+
+type AttachStaticChannel = Arc<dyn Fn(&mut Acceptor) + Send + Sync>;
+type AttachDynamicChannel = Arc<dyn Fn(DrdynvcServer) -> DrdynvcServer + Send + Sync>;
+
+/// Custom channels attached to each connection, along with the channels of the server.
+///
+/// The channels are identified by the type of their processor, a processor type is registered once.
+#[derive(Clone, Default)]
+pub(crate) struct CustomChannels {
+    statics: Vec<AttachStaticChannel>,
+    dynamics: Vec<AttachDynamicChannel>,
+}
+
+impl CustomChannels {
+    pub(crate) fn add_static<F>(&mut self, factory: F)
+    where
+        F: StaticChannelFactory + 'static,
+    {
+        self.statics.push(Arc::new(move |acceptor: &mut Acceptor| {
+            acceptor.attach_static_channel(factory.build_processor());
+        }));
+    }
+
+    pub(crate) fn add_dynamic<F>(&mut self, factory: F)
+    where
+        F: DynamicChannelFactory + 'static,
+    {
+        self.dynamics.push(Arc::new(move |dvc: DrdynvcServer| {
+            dvc.with_dynamic_channel(factory.build_processor())
+        }));
+    }
+
+    pub(crate) fn attach_static(&self, acceptor: &mut Acceptor) {
+        for attach in &self.statics {
+            attach(acceptor);
+        }
+    }
+
+    pub(crate) fn attach_dynamic(&self, mut dvc: DrdynvcServer) -> DrdynvcServer {
+        for attach in &self.dynamics {
+            dvc = attach(dvc);
+        }
+
+        dvc
+    }
+}
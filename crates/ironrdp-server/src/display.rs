@@ -1,36 +1,66 @@
+use core::future::Future;
 use core::num::NonZeroU16;
 
 use anyhow::Result;
 use bytes::{Bytes, BytesMut};
+use futures_util::future::BoxFuture;
+use futures_util::stream::{self, BoxStream, StreamExt as _};
 use ironrdp_displaycontrol::pdu::DisplayControlMonitorLayout;
 use ironrdp_pdu::pointer::PointerPositionAttribute;
 
+use crate::{ClientSessionInfo, WindowUpdate};
+
 #[rustfmt::skip]
 pub use ironrdp_acceptor::DesktopSize;
 pub use ironrdp_graphics::image_processing::PixelFormat;
+pub use ironrdp_pdu::gcc::{Monitor, MonitorFlags};
 
 /// Display Update
 ///
 /// Contains all types of display updates currently supported by the server implementation
 /// and the RDP spec
 ///
+/// The cursor is a layer of its own, drawn by the client over the bitmaps: the bitmaps should not
+/// include it, its position and shape being sent with the pointer updates instead, so moving the
+/// cursor doesn't re-encode the regions of the display it covers.
 #[derive(Debug, Clone)]
 pub enum DisplayUpdate {
     Resize(DesktopSize),
     Bitmap(BitmapUpdate),
+    /// Moves the cursor, in desktop coordinates.
     PointerPosition(PointerPositionAttribute),
+    /// Changes the cursor shape, with AND and XOR masks.
     ColorPointer(ColorPointer),
+    /// Changes the cursor shape, with an alpha channel.
     RGBAPointer(RGBAPointer),
     HidePointer,
+    /// Restores the default cursor shape of the client.
     DefaultPointer,
+    Window(WindowUpdate),
+}
+
+impl DisplayUpdate {
+    /// Returns whether the update changes the cursor shape.
+    pub(crate) fn is_cursor_shape(&self) -> bool {
+        matches!(
+            self,
+            Self::ColorPointer(_) | Self::RGBAPointer(_) | Self::HidePointer | Self::DefaultPointer
+        )
+    }
 }
 
+/// Pointer shape with an alpha channel
+///
+/// Pointers up to 96x96 pixels are supported, or up to 384x384 pixels when the client supports
+/// large pointers. Shapes already sent are cached by the client.
 #[derive(Clone)]
 pub struct RGBAPointer {
     pub width: u16,
     pub height: u16,
     pub hot_x: u16,
     pub hot_y: u16,
+    /// Pixels in top-down rows, 4 bytes per pixel in RGBA order, with a straight (not
+    /// premultiplied) alpha.
     pub data: Vec<u8>,
 }
 
@@ -171,14 +201,13 @@ impl core::fmt::Debug for BitmapUpdate {
 /// display updates which will then be encoded and sent to the client
 ///
 /// See [`RdpServerDisplay`] example.
-#[async_trait::async_trait]
-pub trait RdpServerDisplayUpdates {
+pub trait RdpServerDisplayUpdates: Send {
     /// # Cancel safety
     ///
     /// This method MUST be cancellation safe because it is used in a
     /// `tokio::select!` statement. If some other branch completes first, it
     /// MUST be guaranteed that no data is lost.
-    async fn next_update(&mut self) -> Option<DisplayUpdate>;
+    fn next_update(&mut self) -> impl Future<Output = Option<DisplayUpdate>> + Send;
 }
 
 /// Display for an RDP server
@@ -193,7 +222,6 @@ pub trait RdpServerDisplayUpdates {
 ///     receiver: tokio::sync::mpsc::Receiver<DisplayUpdate>,
 /// }
 ///
-/// #[async_trait::async_trait]
 /// impl RdpServerDisplayUpdates for DisplayUpdates {
 ///     async fn next_update(&mut self) -> Option<DisplayUpdate> {
 ///         self.receiver.recv().await
@@ -205,29 +233,129 @@ pub trait RdpServerDisplayUpdates {
 ///     height: u16,
 /// }
 ///
-/// #[async_trait::async_trait]
 /// impl RdpServerDisplay for DisplayHandler {
+///     type Updates = DisplayUpdates;
+///
 ///     async fn size(&mut self) -> DesktopSize {
 ///         DesktopSize { width: self.width, height: self.height }
 ///     }
 ///
-///     async fn updates(&mut self) -> Result<Box<dyn RdpServerDisplayUpdates>> {
-///         Ok(Box::new(DisplayUpdates { receiver: todo!() }))
+///     async fn updates(&mut self) -> Result<DisplayUpdates> {
+///         Ok(DisplayUpdates { receiver: todo!() })
 ///     }
 /// }
 /// ```
-#[async_trait::async_trait]
 pub trait RdpServerDisplay: Send {
+    type Updates: RdpServerDisplayUpdates + 'static;
+
     /// This method should return the current size of the display.
-    /// Currently, there is no way for the client to negotiate resolution,
-    /// so the size returned by this method will be enforced.
-    async fn size(&mut self) -> DesktopSize;
+    ///
+    /// The size returned by this method is enforced, unless the display accepts the size requested by
+    /// the client in [`RdpServerDisplay::resize`].
+    fn size(&mut self) -> impl Future<Output = DesktopSize> + Send;
 
     /// Return a display updates receiver
-    async fn updates(&mut self) -> Result<Box<dyn RdpServerDisplayUpdates>>;
+    ///
+    /// A display source pushing its frames at its own pace can return the receiver of a
+    /// [`display_channel`](crate::display_channel).
+    fn updates(&mut self) -> impl Future<Output = Result<Self::Updates>> + Send;
+
+    /// Returns the layout of the monitors of the display, sent to the clients supporting it once the
+    /// capabilities are exchanged.
+    ///
+    /// The bounds of the monitors are inclusive, in desktop coordinates, and the primary monitor is at
+    /// the origin. An empty layout is a single primary monitor covering the desktop.
+    fn monitor_layout(&mut self) -> impl Future<Output = Vec<Monitor>> + Send {
+        async { Vec::new() }
+    }
 
     /// Request a new size for the display
     fn request_layout(&mut self, layout: DisplayControlMonitorLayout) {
         debug!(?layout, "Requesting layout")
     }
+
+    /// Request the full content of the display, e.g. when the client resumes the display updates it
+    /// suppressed while its window was minimized.
+    ///
+    /// The display should then send a bitmap update covering the whole desktop.
+    fn request_refresh(&mut self) {
+        debug!("Requesting refresh")
+    }
+
+    /// Resize the display to the size requested by the client, either at connection time or with the
+    /// Display Control channel.
+    ///
+    /// Returns the new size of the display, which is then applied to the client session, or `None` if
+    /// the display can't be resized. A display resized later should send a [`DisplayUpdate::Resize`].
+    fn resize(&mut self, size: DesktopSize) -> impl Future<Output = Option<DesktopSize>> + Send {
+        let _ = size;
+        async { None }
+    }
+
+    /// Called with the settings of the client once it is connected, before the display updates are
+    /// requested, e.g. to render at the color depth of the client.
+    fn session_info(&mut self, info: &ClientSessionInfo) {
+        let _ = info;
+    }
+}
+
+/// Object-safe version of [`RdpServerDisplay`], implemented for all the displays.
+///
+/// The server and the sessions hold their display as a `Box<dyn DynRdpServerDisplay>`. The futures of
+/// the methods are boxed, but the updates are received from a stream allocated once per connection.
+pub trait DynRdpServerDisplay: Send {
+    fn size(&mut self) -> BoxFuture<'_, DesktopSize>;
+
+    fn updates(&mut self) -> BoxFuture<'_, Result<BoxStream<'static, DisplayUpdate>>>;
+
+    fn monitor_layout(&mut self) -> BoxFuture<'_, Vec<Monitor>>;
+
+    fn request_layout(&mut self, layout: DisplayControlMonitorLayout);
+
+    fn request_refresh(&mut self);
+
+    fn resize(&mut self, size: DesktopSize) -> BoxFuture<'_, Option<DesktopSize>>;
+
+    fn session_info(&mut self, info: &ClientSessionInfo);
+}
+
+impl<D: RdpServerDisplay> DynRdpServerDisplay for D {
+    fn size(&mut self) -> BoxFuture<'_, DesktopSize> {
+        Box::pin(RdpServerDisplay::size(self))
+    }
+
+    fn updates(&mut self) -> BoxFuture<'_, Result<BoxStream<'static, DisplayUpdate>>> {
+        Box::pin(async {
+            let updates = RdpServerDisplay::updates(self).await?;
+
+            // The receiver is moved in and out of the future of each update, which is kept by the stream
+            // when the stream is not polled to completion.
+            let stream = stream::unfold(updates, |mut updates| async move {
+                let update = updates.next_update().await?;
+                Some((update, updates))
+            });
+
+            Ok(stream.boxed())
+        })
+    }
+
+    fn monitor_layout(&mut self) -> BoxFuture<'_, Vec<Monitor>> {
+        Box::pin(RdpServerDisplay::monitor_layout(self))
+    }
+
+    fn request_layout(&mut self, layout: DisplayControlMonitorLayout) {
+        RdpServerDisplay::request_layout(self, layout)
+    }
+
+    fn request_refresh(&mut self) {
+        RdpServerDisplay::request_refresh(self)
+    }
+
+    fn resize(&mut self, size: DesktopSize) -> BoxFuture<'_, Option<DesktopSize>> {
+        Box::pin(RdpServerDisplay::resize(self, size))
+    }
+
+    fn session_info(&mut self, info: &ClientSessionInfo) {
+        RdpServerDisplay::session_info(self, info)
+    }
 }
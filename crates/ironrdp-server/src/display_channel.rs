@@ -0,0 +1,289 @@
+//! Push-based display updates.
+//!
+//! Instead of awaiting the display source one update at a time, the source pushes its frames to a
+//! [`DisplayUpdateSender`] at its own pace. The frames not sent to the client yet are coalesced: only
+//! the latest frame is kept, with the regions damaged since the last update sent, so a slow connection
+//! skips the intermediate frames instead of falling behind.
+//!
+//! The cursor is coalesced the same way, only its latest shape and position are sent.
+
+use core::num::NonZeroU16;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+pub use ironrdp_pdu::geometry::ExclusiveRectangle;
+use ironrdp_pdu::geometry::Rectangle as _;
+use ironrdp_pdu::pointer::PointerPositionAttribute;
+use tokio::sync::Notify;
+
+use crate::{BitmapUpdate, DisplayUpdate, RGBAPointer, RdpServerDisplayUpdates};
+
+/// Number of pending damaged regions above which they are merged into their bounding box.
+const MAX_DAMAGED_REGIONS: usize = 32;
+
+/// Frame captured by a display source.
+#[derive(Debug, Clone)]
+pub struct DisplayFrame {
+    /// Content of the whole desktop.
+    pub bitmap: BitmapUpdate,
+    /// Regions changed since the previous frame, in desktop coordinates, or empty if the whole frame
+    /// changed.
+    pub damage: Vec<ExclusiveRectangle>,
+    /// Capture time of the frame, frames older than the latest submitted one are dropped.
+    pub timestamp: Instant,
+}
+
+#[derive(Default)]
+struct State {
+    /// Updates other than frames and cursor, sent in order before the cursor and the damaged regions.
+    updates: VecDeque<DisplayUpdate>,
+    /// Latest cursor shape not sent yet.
+    cursor_shape: Option<DisplayUpdate>,
+    /// Latest cursor position not sent yet.
+    cursor_position: Option<PointerPositionAttribute>,
+    /// Latest frame, from which the damaged regions are sent.
+    frame: Option<BitmapUpdate>,
+    timestamp: Option<Instant>,
+    /// Regions of the latest frame not sent yet.
+    damage: Vec<ExclusiveRectangle>,
+    dropped_frames: u64,
+}
+
+impl State {
+    fn submit(&mut self, frame: DisplayFrame) {
+        if self.timestamp.is_some_and(|latest| frame.timestamp < latest) {
+            trace!("Dropping frame older than the latest frame");
+            self.dropped_frames += 1;
+            return;
+        }
+
+        if !self.damage.is_empty() {
+            // The pending regions are sent from the new frame instead.
+            self.dropped_frames += 1;
+        }
+
+        let bitmap = frame.bitmap;
+        let bounds = bounds(&bitmap);
+        let resized = self
+            .frame
+            .as_ref()
+            .is_some_and(|previous| previous.width != bitmap.width || previous.height != bitmap.height);
+
+        if frame.damage.is_empty() || resized {
+            self.damage = vec![bounds];
+        } else {
+            for region in frame.damage.iter().filter_map(|region| clip(region, &bounds)) {
+                if !self.damage.iter().any(|pending| contains(pending, &region)) {
+                    self.damage.retain(|pending| !contains(&region, pending));
+                    self.damage.push(region);
+                }
+            }
+
+            if self.damage.len() > MAX_DAMAGED_REGIONS {
+                self.damage = vec![ExclusiveRectangle::union_all(&self.damage)];
+            }
+        }
+
+        self.frame = Some(bitmap);
+        self.timestamp = Some(frame.timestamp);
+    }
+
+    fn push(&mut self, update: DisplayUpdate) {
+        if let DisplayUpdate::PointerPosition(position) = update {
+            self.cursor_position = Some(position);
+            return;
+        }
+
+        if update.is_cursor_shape() {
+            self.cursor_shape = Some(update);
+            return;
+        }
+
+        if matches!(update, DisplayUpdate::Resize(_)) {
+            // The pending regions belong to the previous desktop.
+            self.frame = None;
+            self.damage.clear();
+        }
+
+        self.updates.push_back(update);
+    }
+
+    fn next_update(&mut self) -> Option<DisplayUpdate> {
+        if let Some(update) = self.updates.pop_front() {
+            return Some(update);
+        }
+
+        if let Some(shape) = self.cursor_shape.take() {
+            return Some(shape);
+        }
+
+        if let Some(position) = self.cursor_position.take() {
+            return Some(DisplayUpdate::PointerPosition(position));
+        }
+
+        let frame = self.frame.as_ref()?;
+        let bounds = bounds(frame);
+        while let Some(region) = self.damage.pop() {
+            let bitmap = clip(&region, &bounds).and_then(|region| {
+                frame.sub(
+                    region.left - frame.x,
+                    region.top - frame.y,
+                    NonZeroU16::new(region.width())?,
+                    NonZeroU16::new(region.height())?,
+                )
+            });
+            if let Some(bitmap) = bitmap {
+                if let Some(timestamp) = self.timestamp {
+                    trace!(latency = ?timestamp.elapsed(), "Sending damaged region");
+                }
+                return Some(DisplayUpdate::Bitmap(bitmap));
+            }
+        }
+
+        None
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    notify: Notify,
+    senders: AtomicUsize,
+}
+
+/// Creates a push-based display update channel.
+///
+/// The receiver is returned by [`RdpServerDisplay::updates`](crate::RdpServerDisplay::updates), and the
+/// display source pushes its updates with the sender. The updates end once all the senders are dropped.
+pub fn display_channel() -> (DisplayUpdateSender, DisplayUpdateReceiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State::default()),
+        notify: Notify::new(),
+        senders: AtomicUsize::new(1),
+    });
+
+    (
+        DisplayUpdateSender {
+            shared: Arc::clone(&shared),
+        },
+        DisplayUpdateReceiver { shared },
+    )
+}
+
+/// Pushes the frames and updates of a display source, see [`display_channel`].
+pub struct DisplayUpdateSender {
+    shared: Arc<Shared>,
+}
+
+impl DisplayUpdateSender {
+    /// Submits a frame, replacing the frame not sent yet.
+    ///
+    /// The regions damaged by the previous frames and not sent yet are sent from this frame.
+    pub fn submit(&self, frame: DisplayFrame) {
+        self.shared.state.lock().expect("poisoned").submit(frame);
+        self.shared.notify.notify_one();
+    }
+
+    /// Sends an update other than a frame, in order.
+    ///
+    /// A [`DisplayUpdate::Resize`] discards the frame not sent yet, the frames of the new size are
+    /// submitted afterwards. A [`DisplayUpdate::Bitmap`] is sent as is, without being coalesced.
+    ///
+    /// The pointer updates replace the cursor shape or position not sent yet, and are sent after the
+    /// other updates, before the frame.
+    pub fn send(&self, update: DisplayUpdate) {
+        self.shared.state.lock().expect("poisoned").push(update);
+        self.shared.notify.notify_one();
+    }
+
+    /// Moves the cursor, in desktop coordinates.
+    pub fn move_cursor(&self, x: u16, y: u16) {
+        self.send(DisplayUpdate::PointerPosition(PointerPositionAttribute { x, y }));
+    }
+
+    /// Changes the cursor shape, `None` hiding the cursor.
+    pub fn set_cursor(&self, shape: Option<RGBAPointer>) {
+        self.send(shape.map_or(DisplayUpdate::HidePointer, DisplayUpdate::RGBAPointer));
+    }
+
+    /// Returns the number of frames dropped, either replaced by a newer frame before being sent, or
+    /// submitted after a newer frame.
+    pub fn dropped_frames(&self) -> u64 {
+        self.shared.state.lock().expect("poisoned").dropped_frames
+    }
+}
+
+impl Clone for DisplayUpdateSender {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl Drop for DisplayUpdateSender {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.notify.notify_one();
+        }
+    }
+}
+
+impl core::fmt::Debug for DisplayUpdateSender {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DisplayUpdateSender").finish_non_exhaustive()
+    }
+}
+
+/// Receives the coalesced updates pushed by a [`DisplayUpdateSender`], see [`display_channel`].
+pub struct DisplayUpdateReceiver {
+    shared: Arc<Shared>,
+}
+
+impl RdpServerDisplayUpdates for DisplayUpdateReceiver {
+    async fn next_update(&mut self) -> Option<DisplayUpdate> {
+        loop {
+            if let Some(update) = self.shared.state.lock().expect("poisoned").next_update() {
+                return Some(update);
+            }
+            if self.shared.senders.load(Ordering::Acquire) == 0 {
+                return None;
+            }
+
+            // A notification sent meanwhile is kept by `notify_one`, so it is not missed.
+            self.shared.notify.notified().await;
+        }
+    }
+}
+
+impl core::fmt::Debug for DisplayUpdateReceiver {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DisplayUpdateReceiver").finish_non_exhaustive()
+    }
+}
+
+fn bounds(bitmap: &BitmapUpdate) -> ExclusiveRectangle {
+    ExclusiveRectangle {
+        left: bitmap.x,
+        top: bitmap.y,
+        right: bitmap.x.saturating_add(bitmap.width.get()),
+        bottom: bitmap.y.saturating_add(bitmap.height.get()),
+    }
+}
+
+fn clip(region: &ExclusiveRectangle, bounds: &ExclusiveRectangle) -> Option<ExclusiveRectangle> {
+    let clipped = ExclusiveRectangle {
+        left: region.left.max(bounds.left),
+        top: region.top.max(bounds.top),
+        right: region.right.min(bounds.right),
+        bottom: region.bottom.min(bounds.bottom),
+    };
+
+    (clipped.left < clipped.right && clipped.top < clipped.bottom).then_some(clipped)
+}
+
+fn contains(outer: &ExclusiveRectangle, inner: &ExclusiveRectangle) -> bool {
+    outer.left <= inner.left && outer.top <= inner.top && outer.right >= inner.right && outer.bottom >= inner.bottom
+}
@@ -0,0 +1,375 @@
+use core::fmt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+pub use ironrdp_rdpdr::pdu::efs::NtStatus;
+use ironrdp_rdpdr::pdu::efs::{CreateDisposition, CreateOptions, DesiredAccess, FileAttributes};
+pub use ironrdp_rdpdr::server::RdpdrServerHandler;
+use ironrdp_rdpdr::server::{DriveRequest, DriveResponse, RdpdrServerMessage};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{ServerEvent, ServerEventSender};
+
+pub trait DriveServerFactory: ServerEventSender + Send {
+    fn build_backend(&self) -> Box<dyn RdpdrServerHandler>;
+}
+
+/// Drives redirected by the clients (RDPDR channel).
+///
+/// Used with a [`ClientDriveFactory`], which gives access to the files of each drive through a [`ClientDrive`].
+pub trait RdpServerDrives: Send + Sync {
+    /// Called when a client redirects a drive.
+    fn drive_added(&self, drive: ClientDrive);
+
+    /// Called when a client stops redirecting the drive `device_id`.
+    fn drive_removed(&self, device_id: u32) {
+        let _ = device_id;
+    }
+}
+
+/// Creates the RDPDR channel of each connection, giving access to the drives redirected by the client.
+pub struct ClientDriveFactory {
+    drives: Arc<dyn RdpServerDrives>,
+    ev_sender: Option<mpsc::UnboundedSender<ServerEvent>>,
+}
+
+impl fmt::Debug for ClientDriveFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientDriveFactory").finish_non_exhaustive()
+    }
+}
+
+impl ClientDriveFactory {
+    pub fn new(drives: Arc<dyn RdpServerDrives>) -> Self {
+        Self {
+            drives,
+            ev_sender: None,
+        }
+    }
+}
+
+impl ServerEventSender for ClientDriveFactory {
+    fn set_sender(&mut self, sender: mpsc::UnboundedSender<ServerEvent>) {
+        self.ev_sender = Some(sender);
+    }
+}
+
+impl DriveServerFactory for ClientDriveFactory {
+    fn build_backend(&self) -> Box<dyn RdpdrServerHandler> {
+        Box::new(ClientDriveHandler {
+            drives: Arc::clone(&self.drives),
+            ev_sender: self.ev_sender.clone(),
+            pending: Arc::new(Mutex::new(PendingRequests::default())),
+        })
+    }
+}
+
+type DriveReply = oneshot::Sender<Result<DriveResponse, NtStatus>>;
+
+/// The requests of a connection waiting for a response, by completion ID.
+#[derive(Default)]
+struct PendingRequests {
+    next_id: u32,
+    replies: HashMap<u32, DriveReply>,
+}
+
+impl PendingRequests {
+    fn insert(&mut self, reply: DriveReply) -> u32 {
+        while self.replies.contains_key(&self.next_id) {
+            self.next_id = self.next_id.wrapping_add(1);
+        }
+        let completion_id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.replies.insert(completion_id, reply);
+        completion_id
+    }
+}
+
+struct ClientDriveHandler {
+    drives: Arc<dyn RdpServerDrives>,
+    ev_sender: Option<mpsc::UnboundedSender<ServerEvent>>,
+    pending: Arc<Mutex<PendingRequests>>,
+}
+
+impl fmt::Debug for ClientDriveHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientDriveHandler").finish_non_exhaustive()
+    }
+}
+
+impl RdpdrServerHandler for ClientDriveHandler {
+    fn drive_announced(&mut self, device_id: u32, name: String) {
+        let Some(ev_sender) = self.ev_sender.clone() else {
+            warn!("No server event sender, drive redirection disabled");
+            return;
+        };
+
+        self.drives.drive_added(ClientDrive {
+            device_id,
+            name,
+            ev_sender,
+            // The requests of a drive fail once the connection is closed.
+            pending: Arc::downgrade(&self.pending),
+        });
+    }
+
+    fn drive_removed(&mut self, device_id: u32) {
+        self.drives.drive_removed(device_id);
+    }
+
+    fn drive_response(&mut self, completion_id: u32, response: Result<DriveResponse, NtStatus>) {
+        let reply = self.pending.lock().expect("poisoned").replies.remove(&completion_id);
+        match reply {
+            Some(reply) => {
+                let _ = reply.send(response);
+            }
+            None => warn!(completion_id, "Unexpected drive response"),
+        }
+    }
+}
+
+/// A drive redirected by a client.
+///
+/// Paths are relative to the root of the drive, e.g. `dir/file.txt`; both `/` and `\` are accepted as separators.
+#[derive(Clone)]
+pub struct ClientDrive {
+    device_id: u32,
+    name: String,
+    ev_sender: mpsc::UnboundedSender<ServerEvent>,
+    pending: Weak<Mutex<PendingRequests>>,
+}
+
+impl fmt::Debug for ClientDrive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientDrive")
+            .field("device_id", &self.device_id)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ClientDrive {
+    pub fn device_id(&self) -> u32 {
+        self.device_id
+    }
+
+    /// Returns the name of the drive, as displayed by the client.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Opens an existing file for reading.
+    pub async fn open(&self, path: &str) -> Result<ClientFile> {
+        let file_id = self
+            .create(
+                path,
+                DesiredAccess::GENERIC_READ,
+                CreateDisposition::FILE_OPEN,
+                CreateOptions::FILE_NON_DIRECTORY_FILE,
+            )
+            .await?;
+        Ok(ClientFile::new(self.clone(), file_id))
+    }
+
+    /// Creates a file for writing, truncating it if it exists.
+    pub async fn create_file(&self, path: &str) -> Result<ClientFile> {
+        let file_id = self
+            .create(
+                path,
+                DesiredAccess::GENERIC_READ | DesiredAccess::GENERIC_WRITE,
+                CreateDisposition::FILE_OVERWRITE_IF,
+                CreateOptions::FILE_NON_DIRECTORY_FILE,
+            )
+            .await?;
+        Ok(ClientFile::new(self.clone(), file_id))
+    }
+
+    /// Returns the entries of a directory, excluding `.` and `..`.
+    pub async fn read_dir(&self, path: &str) -> Result<Vec<ClientDirEntry>> {
+        let path = drive_path(path);
+        let file_id = self
+            .create(
+                &path,
+                DesiredAccess::FILE_READ_DATA_OR_FILE_LIST_DIRECTORY | DesiredAccess::FILE_READ_ATTRIBUTES,
+                CreateDisposition::FILE_OPEN,
+                CreateOptions::FILE_DIRECTORY_FILE,
+            )
+            .await?;
+        let dir = ClientFile::new(self.clone(), file_id);
+
+        let mut pattern = Some(format!("{}\\*", path.trim_end_matches('\\')));
+        let mut entries = Vec::new();
+        loop {
+            let request = DriveRequest::QueryDirectory {
+                file_id,
+                pattern: pattern.take(),
+            };
+            let DriveResponse::QueryDirectory(info) = self.request(request).await? else {
+                bail!("unexpected drive response");
+            };
+            let Some(info) = info else {
+                break;
+            };
+
+            if info.file_name != "." && info.file_name != ".." {
+                entries.push(ClientDirEntry {
+                    name: info.file_name,
+                    is_dir: info.file_attributes.contains(FileAttributes::FILE_ATTRIBUTE_DIRECTORY),
+                    size: u64::try_from(info.end_of_file).unwrap_or(0),
+                    last_write_time: info.last_write_time,
+                });
+            }
+        }
+
+        dir.close().await?;
+        Ok(entries)
+    }
+
+    async fn create(
+        &self,
+        path: &str,
+        desired_access: DesiredAccess,
+        create_disposition: CreateDisposition,
+        create_options: CreateOptions,
+    ) -> Result<u32> {
+        let request = DriveRequest::Create {
+            path: drive_path(path),
+            desired_access,
+            create_disposition,
+            create_options,
+        };
+        match self.request(request).await? {
+            DriveResponse::Create { file_id, .. } => Ok(file_id),
+            _ => bail!("unexpected drive response"),
+        }
+    }
+
+    async fn request(&self, request: DriveRequest) -> Result<DriveResponse> {
+        let (tx, rx) = oneshot::channel();
+        self.send(request, tx)?;
+
+        match rx.await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(status)) => Err(anyhow!("drive request failed with {status:?}")),
+            Err(_) => bail!("client disconnected"),
+        }
+    }
+
+    fn send(&self, request: DriveRequest, reply: DriveReply) -> Result<()> {
+        let pending = self.pending.upgrade().context("client disconnected")?;
+        let completion_id = pending.lock().expect("poisoned").insert(reply);
+
+        let message = RdpdrServerMessage::DriveRequest {
+            device_id: self.device_id,
+            completion_id,
+            request,
+        };
+        if self.ev_sender.send(ServerEvent::Rdpdr(message)).is_err() {
+            pending.lock().expect("poisoned").replies.remove(&completion_id);
+            bail!("client disconnected");
+        }
+
+        Ok(())
+    }
+}
+
+/// An entry of a directory of a [`ClientDrive`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientDirEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /// The time of the last write, as a Windows FILETIME (100-nanosecond intervals since January 1, 1601).
+    pub last_write_time: i64,
+}
+
+/// A file opened on a [`ClientDrive`].
+///
+/// The file is closed by [`ClientFile::close`], or in the background when dropped.
+#[derive(Debug)]
+pub struct ClientFile {
+    drive: ClientDrive,
+    file_id: u32,
+    closed: bool,
+}
+
+impl ClientFile {
+    /// The size of the blocks requested by [`ClientFile::read_to_end`].
+    const READ_BLOCK_SIZE: u32 = 64 * 1024;
+
+    fn new(drive: ClientDrive, file_id: u32) -> Self {
+        Self {
+            drive,
+            file_id,
+            closed: false,
+        }
+    }
+
+    /// Reads up to `length` bytes at `offset`, returning an empty buffer at the end of the file.
+    pub async fn read(&self, offset: u64, length: u32) -> Result<Vec<u8>> {
+        let request = DriveRequest::Read {
+            file_id: self.file_id,
+            offset,
+            length,
+        };
+        match self.drive.request(request).await? {
+            DriveResponse::Read(data) => Ok(data),
+            _ => bail!("unexpected drive response"),
+        }
+    }
+
+    /// Reads the whole file.
+    pub async fn read_to_end(&self) -> Result<Vec<u8>> {
+        let mut content = Vec::new();
+        loop {
+            let offset = u64::try_from(content.len())?;
+            let data = self.read(offset, Self::READ_BLOCK_SIZE).await?;
+            if data.is_empty() {
+                return Ok(content);
+            }
+            content.extend_from_slice(&data);
+        }
+    }
+
+    /// Writes `data` at `offset`, returning the number of bytes written.
+    pub async fn write(&self, offset: u64, data: &[u8]) -> Result<u32> {
+        let request = DriveRequest::Write {
+            file_id: self.file_id,
+            offset,
+            data: data.to_vec(),
+        };
+        match self.drive.request(request).await? {
+            DriveResponse::Write(length) => Ok(length),
+            _ => bail!("unexpected drive response"),
+        }
+    }
+
+    pub async fn close(mut self) -> Result<()> {
+        self.closed = true;
+        self.drive
+            .request(DriveRequest::Close { file_id: self.file_id })
+            .await?;
+        Ok(())
+    }
+}
+
+impl Drop for ClientFile {
+    fn drop(&mut self) {
+        if !self.closed {
+            // The response is ignored.
+            let (tx, _) = oneshot::channel();
+            let _ = self.drive.send(DriveRequest::Close { file_id: self.file_id }, tx);
+        }
+    }
+}
+
+/// Converts a path relative to the root of the drive to the format expected by the client, e.g. `\dir\file.txt`.
+fn drive_path(path: &str) -> String {
+    let path = path.replace('/', "\\");
+    if path.starts_with('\\') {
+        path
+    } else {
+        format!("\\{path}")
+    }
+}
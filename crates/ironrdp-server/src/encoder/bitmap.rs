@@ -1,21 +1,65 @@
 use ironrdp_core::{invalid_field_err, Encode, EncodeResult, WriteCursor};
 use ironrdp_graphics::image_processing::PixelFormat;
-use ironrdp_graphics::rdp6::{ABgrChannels, ARgbChannels, BgrAChannels, BitmapStreamEncoder, RgbAChannels};
+use ironrdp_graphics::rdp6::{
+    self, ABgrChannels, ARgbChannels, BgrAChannels, BitmapStreamEncoder, ColorPlanes, RgbAChannels,
+};
+use ironrdp_graphics::rle;
 use ironrdp_pdu::bitmap::{self, BitmapData, BitmapUpdateData, Compression};
 use ironrdp_pdu::geometry::InclusiveRectangle;
+use ironrdp_pdu::palette::PaletteEntry;
 
 use crate::BitmapUpdate;
 
+/// Compression of the bitmap updates, chosen from the color depth of the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BitmapCompression {
+    /// RDP 6.0 bitmap compression (planar), for 32-bpp sessions.
+    Planar,
+    /// Interleaved RLE, for 8, 15, 16 and 24-bpp sessions.
+    ///
+    /// The 8-bpp pixels are indices into the [`palette`].
+    Interleaved { bpp: u16 },
+}
+
+impl BitmapCompression {
+    pub(crate) fn from_color_depth(bpp: u16) -> Self {
+        match bpp {
+            8 | 15 | 16 | 24 => Self::Interleaved { bpp },
+            _ => Self::Planar,
+        }
+    }
+
+    /// Returns whether the bitmaps use the [`palette`], to be sent to the client beforehand.
+    pub(crate) fn uses_palette(self) -> bool {
+        self == Self::Interleaved { bpp: 8 }
+    }
+
+    fn bytes_per_pixel(self, format: PixelFormat) -> usize {
+        match self {
+            Self::Planar => usize::from(format.bytes_per_pixel()),
+            Self::Interleaved { bpp } => usize::from(bpp.div_ceil(8)),
+        }
+    }
+}
+
 // PERF: we could also remove the need for this buffer
 #[derive(Clone)]
 pub(crate) struct BitmapEncoder {
+    compression: BitmapCompression,
     buffer: Vec<u8>,
+    /// Color planes split from the pixels, for the planar compression.
+    planes: ColorPlanes,
+    /// Pixels converted to the color depth of the session, for the interleaved RLE compression.
+    pixels: Vec<u8>,
 }
 
 impl BitmapEncoder {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(compression: BitmapCompression) -> Self {
         Self {
+            compression,
             buffer: vec![0; u16::MAX as usize],
+            planes: ColorPlanes::new(),
+            pixels: Vec::new(),
         }
     }
 
@@ -28,9 +72,14 @@ impl BitmapEncoder {
             return Err(invalid_field_err!("bitmap", "Width must be a multiple of 4"));
         }
 
-        let bytes_per_pixel = usize::from(bitmap.format.bytes_per_pixel());
+        let src_row_len = usize::from(bitmap.width.get()) * usize::from(bitmap.format.bytes_per_pixel());
+        let bytes_per_pixel = self.compression.bytes_per_pixel(bitmap.format);
         let row_len = usize::from(bitmap.width.get()) * bytes_per_pixel;
-        let chunk_height = usize::from(u16::MAX) / row_len;
+        let chunk_height = match self.compression {
+            BitmapCompression::Planar => usize::from(u16::MAX) / row_len,
+            // The interleaved RLE may slightly expand incompressible bitmaps, with the headers of the orders.
+            BitmapCompression::Interleaved { .. } => (usize::from(u16::MAX) - 16) / row_len,
+        };
 
         let mut cursor = WriteCursor::new(output);
         let chunks = bitmap.data.chunks(bitmap.stride * chunk_height);
@@ -42,16 +91,27 @@ impl BitmapEncoder {
             let height = chunk.len() / bitmap.stride;
             let top = usize::from(bitmap.y) + i * chunk_height;
 
-            let encoder = BitmapStreamEncoder::new(usize::from(bitmap.width.get()), height);
+            // Bitmap data is stored bottom-up.
+            let rows = chunk.chunks(bitmap.stride).map(|row| &row[..src_row_len]).rev();
 
-            let len = {
-                let pixels = chunk
-                    .chunks(bitmap.stride)
-                    .map(|row| &row[..row_len])
-                    .rev()
-                    .flat_map(|row| row.chunks(bytes_per_pixel));
+            let len = match bitmap.format {
+                PixelFormat::ARgb32 | PixelFormat::XRgb32 => {
+                    self.encode_chunk::<ARgbChannels, _>(rows, usize::from(bitmap.width.get()), height)?
+                }
+                PixelFormat::RgbA32 | PixelFormat::RgbX32 => {
+                    self.encode_chunk::<RgbAChannels, _>(rows, usize::from(bitmap.width.get()), height)?
+                }
+                PixelFormat::ABgr32 | PixelFormat::XBgr32 => {
+                    self.encode_chunk::<ABgrChannels, _>(rows, usize::from(bitmap.width.get()), height)?
+                }
+                PixelFormat::BgrA32 | PixelFormat::BgrX32 => {
+                    self.encode_chunk::<BgrAChannels, _>(rows, usize::from(bitmap.width.get()), height)?
+                }
+            };
 
-                Self::encode_iter(encoder, bitmap.format, pixels, self.buffer.as_mut_slice())
+            let bits_per_pixel = match self.compression {
+                BitmapCompression::Planar => u16::from(bitmap.format.bytes_per_pixel()) * 8,
+                BitmapCompression::Interleaved { bpp } => bpp,
             };
 
             let data = BitmapData {
@@ -63,7 +123,7 @@ impl BitmapEncoder {
                 },
                 width: u16::from(bitmap.width),
                 height: u16::try_from(height).unwrap(),
-                bits_per_pixel: u16::from(bitmap.format.bytes_per_pixel()) * 8,
+                bits_per_pixel,
                 compression_flags: Compression::BITMAP_COMPRESSION,
                 compressed_data_header: Some(bitmap::CompressedDataHeader {
                     main_body_size: u16::try_from(len).unwrap(),
@@ -79,23 +139,68 @@ impl BitmapEncoder {
         Ok(cursor.pos())
     }
 
-    fn encode_iter<'a, P>(mut encoder: BitmapStreamEncoder, format: PixelFormat, src: P, dst: &mut [u8]) -> usize
+    /// Compresses the rows of a chunk into the buffer, returning the size of the compressed data.
+    fn encode_chunk<'a, F, I>(&mut self, rows: I, width: usize, height: usize) -> EncodeResult<usize>
     where
-        P: Iterator<Item = &'a [u8]> + Clone,
+        F: rdp6::PixelFormat,
+        I: Iterator<Item = &'a [u8]>,
     {
-        match format {
-            PixelFormat::ARgb32 | PixelFormat::XRgb32 => {
-                encoder.encode_pixels_stream::<_, ARgbChannels>(src, dst, true).unwrap()
-            }
-            PixelFormat::RgbA32 | PixelFormat::RgbX32 => {
-                encoder.encode_pixels_stream::<_, RgbAChannels>(src, dst, true).unwrap()
-            }
-            PixelFormat::ABgr32 | PixelFormat::XBgr32 => {
-                encoder.encode_pixels_stream::<_, ABgrChannels>(src, dst, true).unwrap()
+        match self.compression {
+            BitmapCompression::Planar => {
+                self.planes.split::<F, _>(rows, width);
+                self.buffer.resize(usize::from(u16::MAX), 0);
+
+                let len = BitmapStreamEncoder::new(width, height)
+                    .encode_planes(&mut self.planes, &mut self.buffer, true)
+                    .unwrap();
+
+                Ok(len)
             }
-            PixelFormat::BgrA32 | PixelFormat::BgrX32 => {
-                encoder.encode_pixels_stream::<_, BgrAChannels>(src, dst, true).unwrap()
+            BitmapCompression::Interleaved { bpp } => {
+                self.pixels.clear();
+                for row in rows {
+                    convert_pixels::<F>(row, bpp, &mut self.pixels);
+                }
+
+                rle::compress(&self.pixels, &mut self.buffer, width, height, usize::from(bpp))
+                    .map_err(|_| invalid_field_err!("bitmap", "RLE compression failed"))
             }
         }
     }
 }
+
+/// Converts 32-bpp pixels to the color depth of the session.
+fn convert_pixels<F: rdp6::PixelFormat>(row: &[u8], bpp: u16, dst: &mut Vec<u8>) {
+    let pixels = row.chunks_exact(F::STRIDE);
+
+    match bpp {
+        24 => dst.extend(pixels.flat_map(|pixel| [F::b(pixel), F::g(pixel), F::r(pixel)])),
+        16 => dst.extend(pixels.flat_map(|pixel| {
+            let (r, g, b) = (u16::from(F::r(pixel)), u16::from(F::g(pixel)), u16::from(F::b(pixel)));
+            (((r >> 3) << 11) | ((g >> 2) << 5) | (b >> 3)).to_le_bytes()
+        })),
+        15 => dst.extend(pixels.flat_map(|pixel| {
+            let (r, g, b) = (u16::from(F::r(pixel)), u16::from(F::g(pixel)), u16::from(F::b(pixel)));
+            (((r >> 3) << 10) | ((g >> 3) << 5) | (b >> 3)).to_le_bytes()
+        })),
+        8 => dst.extend(pixels.map(|pixel| (F::r(pixel) & 0xE0) | ((F::g(pixel) & 0xE0) >> 3) | (F::b(pixel) >> 6))),
+        _ => unreachable!("unsupported color depth"),
+    }
+}
+
+/// Palette of the 8-bpp sessions, with 3 bits of red, 3 bits of green and 2 bits of blue in each index.
+pub(crate) fn palette() -> Vec<PaletteEntry> {
+    // Scales a component of `bits` bits to 8 bits, the maximum value being white.
+    fn scale(value: u8, bits: u32) -> u8 {
+        let max = (1u16 << bits) - 1;
+        u8::try_from(u16::from(value) * 255 / max).expect("at most 255")
+    }
+
+    (0..=u8::MAX)
+        .map(|index| PaletteEntry {
+            red: scale(index >> 5, 3),
+            green: scale((index >> 2) & 0x07, 3),
+            blue: scale(index & 0x03, 2),
+        })
+        .collect()
+}
@@ -0,0 +1,221 @@
+use core::hash::Hasher as _;
+use std::hash::DefaultHasher;
+
+use ironrdp_acceptor::DesktopSize;
+use ironrdp_graphics::image_processing::PixelFormat;
+use ironrdp_pdu::geometry::{ExclusiveRectangle, Rectangle as _};
+
+use crate::BitmapUpdate;
+
+/// Size of the square tiles the desktop is divided into.
+const TILE_SIZE: u16 = 64;
+
+/// Tracks the content of the desktop, in order to only send the regions changed by the display updates.
+///
+/// The desktop is divided into tiles, and the hash of the last content sent for each tile is kept.
+/// A tile entirely covered by an update is damaged only if its hash changed, while a tile partially
+/// covered by an update is always damaged: its full content is then unknown until the next update
+/// covering it entirely.
+pub(crate) struct DamageTracker {
+    width: u16,
+    height: u16,
+    columns: usize,
+    format: Option<PixelFormat>,
+    tiles: Vec<Option<u64>>,
+}
+
+impl core::fmt::Debug for DamageTracker {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DamageTracker")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+impl DamageTracker {
+    pub(crate) fn new(desktop_size: DesktopSize) -> Self {
+        let columns = usize::from(desktop_size.width.div_ceil(TILE_SIZE));
+        let rows = usize::from(desktop_size.height.div_ceil(TILE_SIZE));
+
+        Self {
+            width: desktop_size.width,
+            height: desktop_size.height,
+            columns,
+            format: None,
+            tiles: vec![None; columns * rows],
+        }
+    }
+
+    /// Forgets the content of the desktop, the next updates are entirely damaged.
+    pub(crate) fn reset(&mut self) {
+        self.format = None;
+        self.tiles.fill(None);
+    }
+
+    /// Returns the regions of the desktop changed by the bitmap update.
+    ///
+    /// Damaged tiles are merged into horizontal runs, which are merged with the runs of the previous
+    /// tile rows spanning the same columns. The regions are clipped to the bitmap and to the desktop.
+    pub(crate) fn damage(&mut self, bitmap: &BitmapUpdate) -> Vec<ExclusiveRectangle> {
+        let right = (bitmap.x + bitmap.width.get()).min(self.width);
+        let bottom = (bitmap.y + bitmap.height.get()).min(self.height);
+        if bitmap.x >= right || bitmap.y >= bottom {
+            return Vec::new();
+        }
+
+        if self.format != Some(bitmap.format) {
+            self.tiles.fill(None);
+            self.format = Some(bitmap.format);
+        }
+
+        let mut regions: Vec<ExclusiveRectangle> = Vec::new();
+
+        for tile_y in bitmap.y / TILE_SIZE..bottom.div_ceil(TILE_SIZE) {
+            let top = (tile_y * TILE_SIZE).max(bitmap.y);
+            let tile_bottom = (tile_y * TILE_SIZE).saturating_add(TILE_SIZE).min(bottom);
+            let mut run: Option<u16> = None;
+
+            for tile_x in bitmap.x / TILE_SIZE..right.div_ceil(TILE_SIZE) {
+                if self.update_tile(bitmap, tile_x, tile_y) {
+                    run.get_or_insert(tile_x);
+                } else if let Some(start) = run.take() {
+                    push_run(&mut regions, bitmap, start, tile_x, top, tile_bottom, right);
+                }
+            }
+
+            if let Some(start) = run {
+                push_run(
+                    &mut regions,
+                    bitmap,
+                    start,
+                    right.div_ceil(TILE_SIZE),
+                    top,
+                    tile_bottom,
+                    right,
+                );
+            }
+        }
+
+        regions
+    }
+
+    /// Updates the hash of a tile, returning whether it is damaged.
+    fn update_tile(&mut self, bitmap: &BitmapUpdate, tile_x: u16, tile_y: u16) -> bool {
+        let index = usize::from(tile_y) * self.columns + usize::from(tile_x);
+
+        let tile = ExclusiveRectangle {
+            left: tile_x * TILE_SIZE,
+            top: tile_y * TILE_SIZE,
+            right: (tile_x * TILE_SIZE).saturating_add(TILE_SIZE).min(self.width),
+            bottom: (tile_y * TILE_SIZE).saturating_add(TILE_SIZE).min(self.height),
+        };
+
+        let covered = tile.left >= bitmap.x
+            && tile.top >= bitmap.y
+            && tile.right <= bitmap.x + bitmap.width.get()
+            && tile.bottom <= bitmap.y + bitmap.height.get();
+        if !covered {
+            self.tiles[index] = None;
+            return true;
+        }
+
+        let bpp = usize::from(bitmap.format.bytes_per_pixel());
+        let left = usize::from(tile.left - bitmap.x) * bpp;
+        let len = usize::from(tile.right - tile.left) * bpp;
+
+        let mut hasher = DefaultHasher::new();
+        for row in tile.top - bitmap.y..tile.bottom - bitmap.y {
+            let start = usize::from(row) * bitmap.stride + left;
+            hasher.write(&bitmap.data[start..start + len]);
+        }
+        let hash = Some(hasher.finish());
+
+        if self.tiles[index] == hash {
+            false
+        } else {
+            self.tiles[index] = hash;
+            true
+        }
+    }
+}
+
+fn push_run(
+    regions: &mut Vec<ExclusiveRectangle>,
+    bitmap: &BitmapUpdate,
+    start: u16,
+    end: u16,
+    top: u16,
+    bottom: u16,
+    right: u16,
+) {
+    let left = (start * TILE_SIZE).max(bitmap.x);
+    let right = end.saturating_mul(TILE_SIZE).min(right);
+
+    if let Some(region) = regions
+        .iter_mut()
+        .find(|r| r.left == left && r.right == right && r.bottom == top)
+    {
+        region.bottom = bottom;
+    } else {
+        regions.push(ExclusiveRectangle {
+            left,
+            top,
+            right,
+            bottom,
+        });
+    }
+}
+
+/// Splits the bitmap update into the given regions.
+///
+/// The bitmap itself is returned when a single region covers it entirely.
+pub(crate) fn split(bitmap: BitmapUpdate, regions: &[ExclusiveRectangle]) -> Vec<BitmapUpdate> {
+    if let [region] = regions {
+        if region.left == bitmap.x
+            && region.top == bitmap.y
+            && region.width() == bitmap.width.get()
+            && region.height() == bitmap.height.get()
+        {
+            return vec![bitmap];
+        }
+    }
+
+    regions
+        .iter()
+        .filter_map(|region| {
+            bitmap.sub(
+                region.left - bitmap.x,
+                region.top - bitmap.y,
+                core::num::NonZeroU16::new(region.width())?,
+                core::num::NonZeroU16::new(region.height())?,
+            )
+        })
+        .collect()
+}
+
+#[cfg(feature = "__bench")]
+pub(crate) mod bench {
+    use super::*;
+
+    pub struct DamageTracker(super::DamageTracker);
+
+    impl DamageTracker {
+        pub fn new(desktop_size: DesktopSize) -> Self {
+            Self(super::DamageTracker::new(desktop_size))
+        }
+
+        pub fn reset(&mut self) {
+            self.0.reset();
+        }
+
+        pub fn damage(&mut self, bitmap: &BitmapUpdate) -> Vec<ExclusiveRectangle> {
+            self.0.damage(bitmap)
+        }
+    }
+
+    pub fn split(bitmap: BitmapUpdate, regions: &[ExclusiveRectangle]) -> Vec<BitmapUpdate> {
+        super::split(bitmap, regions)
+    }
+}
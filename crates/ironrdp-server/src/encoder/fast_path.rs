@@ -1,4 +1,4 @@
-use core::{cmp, fmt};
+use core::fmt;
 
 use ironrdp_pdu::fast_path::{EncryptionFlags, FastPathHeader, FastPathUpdatePdu, Fragmentation, UpdateCode};
 use ironrdp_pdu::{Encode, WriteCursor};
@@ -33,30 +33,35 @@ impl UpdateFragmenter {
         }
     }
 
-    pub(crate) fn size_hint(&self) -> usize {
-        FASTPATH_HEADER_SIZE + cmp::min(self.data.len(), MAX_FASTPATH_UPDATE_SIZE)
+    /// Returns the encoded update, to reuse its buffer once all the fragments are sent.
+    pub(crate) fn into_data(self) -> Vec<u8> {
+        self.data
     }
 
-    pub(crate) fn next(&mut self, dst: &mut [u8]) -> Option<usize> {
-        let (consumed, written) = self.encode_next(dst)?;
-        self.position += consumed;
+    /// Encodes the headers of the next fragment into `dst`, returning their size along with the data of the
+    /// fragment, to be written after them.
+    pub(crate) fn next_vectored(&mut self, dst: &mut [u8]) -> Option<(usize, &[u8])> {
+        let (fragmentation, len) = self.next_fragment()?;
+        let written = self.encode_fastpath_header(fragmentation, len, dst)?;
+        let start = self.position;
+        self.position += len;
         self.index = self.index.checked_add(1)?;
-        Some(written)
+        Some((written, &self.data[start..self.position]))
     }
 
-    fn encode_next(&mut self, dst: &mut [u8]) -> Option<(usize, usize)> {
+    /// Returns the fragmentation and the data length of the next fragment.
+    fn next_fragment(&self) -> Option<(Fragmentation, usize)> {
         match self.data.len() - self.position {
             0 => None,
 
-            1..=MAX_FASTPATH_UPDATE_SIZE => {
+            remaining @ 1..=MAX_FASTPATH_UPDATE_SIZE => {
                 let frag = if self.index > 0 {
                     Fragmentation::Last
                 } else {
                     Fragmentation::Single
                 };
 
-                self.encode_fastpath(frag, &self.data[self.position..], dst)
-                    .map(|written| (self.data.len() - self.position, written))
+                Some((frag, remaining))
             }
 
             _ => {
@@ -66,17 +71,12 @@ impl UpdateFragmenter {
                     Fragmentation::First
                 };
 
-                self.encode_fastpath(
-                    frag,
-                    &self.data[self.position..MAX_FASTPATH_UPDATE_SIZE + self.position],
-                    dst,
-                )
-                .map(|written| (MAX_FASTPATH_UPDATE_SIZE, written))
+                Some((frag, MAX_FASTPATH_UPDATE_SIZE))
             }
         }
     }
 
-    fn encode_fastpath(&self, frag: Fragmentation, data: &[u8], dst: &mut [u8]) -> Option<usize> {
+    fn encode_fastpath_header(&self, frag: Fragmentation, data_len: usize, dst: &mut [u8]) -> Option<usize> {
         let mut cursor = WriteCursor::new(dst);
 
         let update = FastPathUpdatePdu {
@@ -84,15 +84,21 @@ impl UpdateFragmenter {
             update_code: self.code,
             compression_flags: None,
             compression_type: None,
-            data,
+            data: &[],
         };
 
-        let header = FastPathHeader::new(EncryptionFlags::empty(), update.size());
+        let header = FastPathHeader::new(EncryptionFlags::empty(), update.size() + data_len);
 
         header.encode(&mut cursor).ok()?;
         update.encode(&mut cursor).ok()?;
 
-        Some(cursor.pos())
+        // The update is encoded without its data, whose length ends the headers.
+        let written = cursor.pos();
+        let data_len = u16::try_from(data_len).ok()?;
+        dst.get_mut(written - 2..written)?
+            .copy_from_slice(&data_len.to_le_bytes());
+
+        Some(written)
     }
 }
 
@@ -102,13 +108,18 @@ mod tests {
 
     use super::*;
 
+    fn next_frame(fragmenter: &mut UpdateFragmenter) -> Option<Vec<u8>> {
+        let mut header = [0u8; FASTPATH_HEADER_SIZE];
+        let (len, data) = fragmenter.next_vectored(&mut header)?;
+        Some([&header[..len], data].concat())
+    }
+
     #[test]
     fn test_single_fragment() {
         let data = vec![1, 2, 3, 4];
         let mut fragmenter = UpdateFragmenter::new(UpdateCode::Bitmap, data);
-        let mut buffer = vec![0; 100];
-        let written = fragmenter.next(&mut buffer).unwrap();
-        assert!(written > 0);
+        let buffer = next_frame(&mut fragmenter).unwrap();
+        assert!(!buffer.is_empty());
         assert_eq!(fragmenter.index, 1);
 
         let mut cursor = ReadCursor::new(&buffer);
@@ -123,16 +134,15 @@ mod tests {
             }
         ));
 
-        assert!(fragmenter.next(&mut buffer).is_none());
+        assert!(next_frame(&mut fragmenter).is_none());
     }
 
     #[test]
     fn test_multi_fragment() {
         let data = vec![0u8; MAX_FASTPATH_UPDATE_SIZE * 2 + 10];
         let mut fragmenter = UpdateFragmenter::new(UpdateCode::Bitmap, data);
-        let mut buffer = vec![0u8; fragmenter.size_hint()];
-        let written = fragmenter.next(&mut buffer).unwrap();
-        assert!(written > 0);
+        let buffer = next_frame(&mut fragmenter).unwrap();
+        assert!(!buffer.is_empty());
         assert_eq!(fragmenter.index, 1);
 
         let mut cursor = ReadCursor::new(&buffer);
@@ -147,8 +157,8 @@ mod tests {
         ));
         assert_eq!(update.data.len(), MAX_FASTPATH_UPDATE_SIZE);
 
-        let written = fragmenter.next(&mut buffer).unwrap();
-        assert!(written > 0);
+        let buffer = next_frame(&mut fragmenter).unwrap();
+        assert!(!buffer.is_empty());
         assert_eq!(fragmenter.index, 2);
         let mut cursor = ReadCursor::new(&buffer);
         let _header: FastPathHeader = decode_cursor(&mut cursor).unwrap();
@@ -162,8 +172,8 @@ mod tests {
         ));
         assert_eq!(update.data.len(), MAX_FASTPATH_UPDATE_SIZE);
 
-        let written = fragmenter.next(&mut buffer).unwrap();
-        assert!(written > 0);
+        let buffer = next_frame(&mut fragmenter).unwrap();
+        assert!(!buffer.is_empty());
         assert_eq!(fragmenter.index, 3);
         let mut cursor = ReadCursor::new(&buffer);
         let _header: FastPathHeader = decode_cursor(&mut cursor).unwrap();
@@ -177,6 +187,6 @@ mod tests {
         ));
         assert_eq!(update.data.len(), 10);
 
-        assert!(fragmenter.next(&mut buffer).is_none());
+        assert!(next_frame(&mut fragmenter).is_none());
     }
 }
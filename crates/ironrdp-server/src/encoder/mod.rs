@@ -1,21 +1,34 @@
 use core::fmt;
+use std::collections::{HashSet, VecDeque};
 
 use anyhow::{Context, Result};
 use ironrdp_acceptor::DesktopSize;
-use ironrdp_pdu::encode_vec;
+use ironrdp_core::{encode_buf, BufferPool, Encode, WriteBuf};
 use ironrdp_pdu::fast_path::UpdateCode;
 use ironrdp_pdu::geometry::ExclusiveRectangle;
-use ironrdp_pdu::pointer::{ColorPointerAttribute, Point16, PointerAttribute, PointerPositionAttribute};
+use ironrdp_pdu::palette::PaletteUpdateData;
+use ironrdp_pdu::pointer::{
+    CachedPointerAttribute, ColorPointerAttribute, LargePointerAttribute, Point16, PointerAttribute,
+    PointerPositionAttribute,
+};
 use ironrdp_pdu::rdp::capability_sets::{CmdFlags, EntropyBits};
-use ironrdp_pdu::surface_commands::{ExtendedBitmapDataPdu, SurfaceBitsPdu, SurfaceCommand};
-
-use self::bitmap::BitmapEncoder;
-use self::rfx::RfxEncoder;
+use ironrdp_pdu::surface_commands::{
+    ExtendedBitmapDataPdu, FrameAction, FrameMarkerPdu, SurfaceBitsPdu, SurfaceCommand,
+};
+use ironrdp_rail::pdu::window::WindowOrder;
+
+use self::bitmap::{BitmapCompression, BitmapEncoder};
+use self::damage::DamageTracker;
+use self::pointer::{PointerCache, MAX_LARGE_POINTER_SIZE, MAX_POINTER_SIZE};
+use self::rfx::{RemoteFxQuality, RfxEncoder};
 use super::BitmapUpdate;
-use crate::{time_warn, ColorPointer, DisplayUpdate, Framebuffer, RGBAPointer};
+use crate::flow_control::FrameTracker;
+use crate::{time_warn, ColorPointer, DisplayUpdate, FrameCodec, Framebuffer, RGBAPointer, WindowUpdate};
 
 mod bitmap;
+pub(crate) mod damage;
 mod fast_path;
+mod pointer;
 pub(crate) mod rfx;
 
 pub(crate) use fast_path::*;
@@ -31,31 +44,64 @@ pub(crate) struct UpdateEncoder {
     // FIXME: draw updates on the framebuffer
     framebuffer: Option<Framebuffer>,
     bitmap_updater: BitmapUpdater,
+    /// Whether the palette of the 8-bpp bitmaps is still to be sent.
+    palette_pending: bool,
+    damage: DamageTracker,
+    pointer_cache: PointerCache,
+    large_pointer: bool,
+    window_orders: bool,
+    /// Windows already created on the client.
+    windows: HashSet<u32>,
+    /// Tracks the frames delimited by Frame Marker commands, when the client acknowledges them.
+    frames: Option<FrameTracker>,
+    /// Buffers of the encoded updates, released once the updates are sent.
+    pool: BufferPool,
 }
 
 impl fmt::Debug for UpdateEncoder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("UpdateEncoder")
             .field("bitmap_update", &self.bitmap_updater)
+            .field("damage", &self.damage)
+            .field("pointer_cache", &self.pointer_cache)
             .finish()
     }
 }
 
 impl UpdateEncoder {
-    pub(crate) fn new(desktop_size: DesktopSize, surface_flags: CmdFlags, remotefx: Option<(EntropyBits, u8)>) -> Self {
+    pub(crate) fn new(
+        desktop_size: DesktopSize,
+        surface_flags: CmdFlags,
+        remotefx: Option<(EntropyBits, u8)>,
+        remotefx_quality: RemoteFxQuality,
+        color_depth: u16,
+        pointer_cache_size: u16,
+        large_pointer: bool,
+        window_orders: bool,
+    ) -> Self {
+        let mut palette_pending = false;
         let bitmap_updater = if !surface_flags.contains(CmdFlags::SET_SURFACE_BITS) {
-            BitmapUpdater::Bitmap(BitmapHandler::new())
-        } else if remotefx.is_some() {
-            let (algo, id) = remotefx.unwrap();
-            BitmapUpdater::RemoteFx(RemoteFxHandler::new(algo, id))
+            let compression = BitmapCompression::from_color_depth(color_depth);
+            palette_pending = compression.uses_palette();
+            BitmapUpdater::Bitmap(BitmapHandler::new(compression))
+        } else if let Some((algo, id)) = remotefx {
+            BitmapUpdater::RemoteFx(RemoteFxHandler::new(algo, id, remotefx_quality))
         } else {
-            BitmapUpdater::None(NoneHandler)
+            BitmapUpdater::None(NoneHandler::default())
         };
 
         Self {
             desktop_size,
             framebuffer: None,
             bitmap_updater,
+            palette_pending,
+            damage: DamageTracker::new(desktop_size),
+            pointer_cache: PointerCache::new(pointer_cache_size),
+            large_pointer,
+            window_orders,
+            windows: HashSet::new(),
+            frames: None,
+            pool: BufferPool::default(),
         }
     }
 
@@ -63,49 +109,119 @@ impl UpdateEncoder {
         EncoderIter {
             encoder: self,
             update: Some(update),
+            damaged: VecDeque::new(),
+            frame_end: None,
         }
     }
 
     pub(crate) fn set_desktop_size(&mut self, size: DesktopSize) {
         self.desktop_size = size;
+        self.damage = DamageTracker::new(size);
+    }
+
+    /// Returns the codec of the bitmap updates.
+    pub(crate) fn codec(&self) -> FrameCodec {
+        match self.bitmap_updater {
+            BitmapUpdater::None(_) => FrameCodec::Uncompressed,
+            BitmapUpdater::Bitmap(_) => FrameCodec::Bitmap,
+            BitmapUpdater::RemoteFx(_) => FrameCodec::RemoteFx,
+        }
     }
 
-    fn rgba_pointer(ptr: RGBAPointer) -> Result<UpdateFragmenter> {
-        let xor_mask = ptr.data;
+    /// Delimits the bitmap updates with Frame Marker commands, tracking the frames until acknowledged.
+    ///
+    /// Frame markers are surface commands, they can't be used with bitmap updates.
+    pub(crate) fn set_frame_tracker(&mut self, frames: FrameTracker) {
+        debug_assert!(!matches!(self.bitmap_updater, BitmapUpdater::Bitmap(_)));
+        self.frames = Some(frames);
+    }
+
+    /// Changes the quality of the next RemoteFX frames.
+    pub(crate) fn set_remote_fx_quality(&mut self, quality: RemoteFxQuality) {
+        if let BitmapUpdater::RemoteFx(handler) = &mut self.bitmap_updater {
+            handler.remotefx.set_quality(quality);
+        }
+    }
+
+    /// Forgets the content sent to the client, when it was updated by other means.
+    pub(crate) fn reset_damage(&mut self) {
+        self.damage.reset();
+    }
+
+    fn rgba_pointer(&mut self, ptr: RGBAPointer) -> Result<UpdateFragmenter> {
+        let max_size = if self.large_pointer {
+            MAX_LARGE_POINTER_SIZE
+        } else {
+            MAX_POINTER_SIZE
+        };
+        if ptr.width > max_size || ptr.height > max_size {
+            warn!(ptr.width, ptr.height, "Pointer too large, using the default pointer");
+            return Self::default_pointer();
+        }
+
+        let (cache_index, cached) = self.pointer_cache.lookup(pointer::rgba_hash(&ptr));
+        if cached {
+            return self.cached_pointer(cache_index);
+        }
 
+        let (xor_mask, and_mask) = pointer::rgba_to_masks(&ptr)?;
         let hot_spot = Point16 {
             x: ptr.hot_x,
             y: ptr.hot_y,
         };
+
+        if ptr.width > MAX_POINTER_SIZE || ptr.height > MAX_POINTER_SIZE {
+            let ptr = LargePointerAttribute {
+                xor_bpp: 32,
+                cache_index,
+                hot_spot,
+                width: ptr.width,
+                height: ptr.height,
+                xor_mask: &xor_mask,
+                and_mask: &and_mask,
+            };
+            return self.encode_update(UpdateCode::LargePointer, &ptr);
+        }
+
         let color_pointer = ColorPointerAttribute {
-            cache_index: 0,
+            cache_index,
             hot_spot,
             width: ptr.width,
             height: ptr.height,
             xor_mask: &xor_mask,
-            and_mask: &[],
+            and_mask: &and_mask,
         };
         let ptr = PointerAttribute {
             xor_bpp: 32,
             color_pointer,
         };
-        Ok(UpdateFragmenter::new(UpdateCode::NewPointer, encode_vec(&ptr)?))
+        self.encode_update(UpdateCode::NewPointer, &ptr)
     }
 
-    fn color_pointer(ptr: ColorPointer) -> Result<UpdateFragmenter> {
+    fn color_pointer(&mut self, ptr: ColorPointer) -> Result<UpdateFragmenter> {
+        let (cache_index, cached) = self.pointer_cache.lookup(pointer::color_hash(&ptr));
+        if cached {
+            return self.cached_pointer(cache_index);
+        }
+
         let hot_spot = Point16 {
             x: ptr.hot_x,
             y: ptr.hot_y,
         };
         let ptr = ColorPointerAttribute {
-            cache_index: 0,
+            cache_index,
             hot_spot,
             width: ptr.width,
             height: ptr.height,
             xor_mask: &ptr.xor_mask,
             and_mask: &ptr.and_mask,
         };
-        Ok(UpdateFragmenter::new(UpdateCode::ColorPointer, encode_vec(&ptr)?))
+        self.encode_update(UpdateCode::ColorPointer, &ptr)
+    }
+
+    fn cached_pointer(&mut self, cache_index: u16) -> Result<UpdateFragmenter> {
+        let ptr = CachedPointerAttribute { cache_index };
+        self.encode_update(UpdateCode::CachedPointer, &ptr)
     }
 
     fn default_pointer() -> Result<UpdateFragmenter> {
@@ -116,18 +232,74 @@ impl UpdateEncoder {
         Ok(UpdateFragmenter::new(UpdateCode::HiddenPointer, vec![]))
     }
 
-    fn pointer_position(pos: PointerPositionAttribute) -> Result<UpdateFragmenter> {
-        Ok(UpdateFragmenter::new(UpdateCode::PositionPointer, encode_vec(&pos)?))
+    /// Returns the window order of the update, or `None` if the client doesn't support the window orders.
+    fn window(&mut self, update: WindowUpdate) -> Option<Result<UpdateFragmenter>> {
+        if !self.window_orders {
+            trace!(?update, "Window orders not supported by the client");
+            return None;
+        }
+
+        let order = match update {
+            WindowUpdate::Update { window_id, info } => WindowOrder::Info {
+                window_id,
+                new: self.windows.insert(window_id),
+                info,
+            },
+            WindowUpdate::Delete { window_id } => {
+                self.windows.remove(&window_id);
+                WindowOrder::Deleted { window_id }
+            }
+        };
+
+        // TS_FP_UPDATE_ORDERS, a single order preceded by the number of orders.
+        let mut buf = self.pool.acquire();
+        buf.write_u16(1);
+        let res = encode_buf(&order, &mut buf).map(|_| UpdateFragmenter::new(UpdateCode::Orders, buf.into_filled()));
+        Some(res.map_err(Into::into))
+    }
+
+    /// Returns the palette update to send before the first bitmap update of an 8-bpp session.
+    fn palette(&mut self) -> Option<Result<UpdateFragmenter>> {
+        if !core::mem::take(&mut self.palette_pending) {
+            return None;
+        }
+
+        let palette = PaletteUpdateData {
+            entries: bitmap::palette(),
+        };
+        Some(self.encode_update(UpdateCode::Palette, &palette))
+    }
+
+    fn pointer_position(&mut self, pos: PointerPositionAttribute) -> Result<UpdateFragmenter> {
+        self.encode_update(UpdateCode::PositionPointer, &pos)
+    }
+
+    fn frame_marker(&mut self, frame_action: FrameAction, frame_id: u32) -> Result<UpdateFragmenter> {
+        let cmd = SurfaceCommand::FrameMarker(FrameMarkerPdu {
+            frame_action,
+            frame_id: Some(frame_id),
+        });
+        self.encode_update(UpdateCode::SurfaceCommands, &cmd)
+    }
+
+    /// Encodes the update in a buffer of the pool.
+    fn encode_update(&mut self, code: UpdateCode, pdu: &impl Encode) -> Result<UpdateFragmenter> {
+        let mut buf = self.pool.acquire();
+        encode_buf(pdu, &mut buf)?;
+        Ok(UpdateFragmenter::new(code, buf.into_filled()))
     }
 
     async fn bitmap(&mut self, bitmap: BitmapUpdate) -> Result<UpdateFragmenter> {
-        // Clone to satisfy spawn_blocking 'static requirement
-        // this should be cheap, even if using bitmap, since vec![] will be empty
-        let mut updater = self.bitmap_updater.clone();
-        let (res, bitmap) =
-            tokio::task::spawn_blocking(move || time_warn!("Encoding bitmap", 10, (updater.handle(&bitmap), bitmap)))
-                .await
-                .unwrap();
+        // Move the updater into the blocking task to satisfy the spawn_blocking 'static requirement,
+        // it is restored afterwards so its state is kept across updates.
+        let mut updater = core::mem::replace(&mut self.bitmap_updater, BitmapUpdater::None(NoneHandler::default()));
+        let buf = self.pool.acquire();
+        let (res, bitmap, updater) = tokio::task::spawn_blocking(move || {
+            time_warn!("Encoding bitmap", 10, (updater.handle(&bitmap, buf), bitmap, updater))
+        })
+        .await
+        .unwrap();
+        self.bitmap_updater = updater;
         if bitmap.x == 0
             && bitmap.y == 0
             && bitmap.width.get() == self.desktop_size.width
@@ -145,25 +317,62 @@ impl UpdateEncoder {
 pub(crate) struct EncoderIter<'a> {
     encoder: &'a mut UpdateEncoder,
     update: Option<DisplayUpdate>,
+    /// Damaged regions of the bitmap update, encoded one after the other.
+    damaged: VecDeque<BitmapUpdate>,
+    /// ID of the frame to end once the damaged regions are encoded.
+    frame_end: Option<u32>,
 }
 
 impl EncoderIter<'_> {
     pub(crate) async fn next(&mut self) -> Option<Result<UpdateFragmenter>> {
+        if let Some(bitmap) = self.damaged.pop_front() {
+            return Some(self.encoder.bitmap(bitmap).await);
+        }
+
+        if let Some(frame_id) = self.frame_end.take() {
+            return Some(self.encoder.frame_marker(FrameAction::End, frame_id));
+        }
+
         let update = self.update.take()?;
         let encoder = &mut self.encoder;
 
         let res = match update {
-            DisplayUpdate::Bitmap(bitmap) => encoder.bitmap(bitmap).await,
-            DisplayUpdate::PointerPosition(pos) => UpdateEncoder::pointer_position(pos),
-            DisplayUpdate::RGBAPointer(ptr) => UpdateEncoder::rgba_pointer(ptr),
-            DisplayUpdate::ColorPointer(ptr) => UpdateEncoder::color_pointer(ptr),
+            DisplayUpdate::Bitmap(bitmap) => {
+                let regions = encoder.damage.damage(&bitmap);
+                trace!(?regions, "Damaged regions");
+                self.damaged = damage::split(bitmap, &regions).into();
+
+                if let Some(frames) = encoder.frames.as_ref().filter(|_| !self.damaged.is_empty()) {
+                    let frame_id = frames.next_frame();
+                    self.frame_end = Some(frame_id);
+                    return Some(encoder.frame_marker(FrameAction::Begin, frame_id));
+                }
+
+                if !self.damaged.is_empty() {
+                    if let Some(palette) = encoder.palette() {
+                        return Some(palette);
+                    }
+                }
+
+                let bitmap = self.damaged.pop_front()?;
+                encoder.bitmap(bitmap).await
+            }
+            DisplayUpdate::PointerPosition(pos) => encoder.pointer_position(pos),
+            DisplayUpdate::RGBAPointer(ptr) => encoder.rgba_pointer(ptr),
+            DisplayUpdate::ColorPointer(ptr) => encoder.color_pointer(ptr),
             DisplayUpdate::HidePointer => UpdateEncoder::hide_pointer(),
             DisplayUpdate::DefaultPointer => UpdateEncoder::default_pointer(),
+            DisplayUpdate::Window(update) => encoder.window(update)?,
             DisplayUpdate::Resize(_) => return None,
         };
 
         Some(res)
     }
+
+    /// Gives the buffer of a sent update back to the encoder.
+    pub(crate) fn recycle(&mut self, fragmenter: UpdateFragmenter) {
+        self.encoder.pool.release(WriteBuf::from_vec(fragmenter.into_data()));
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -174,30 +383,34 @@ enum BitmapUpdater {
 }
 
 impl BitmapUpdater {
-    fn handle(&mut self, bitmap: &BitmapUpdate) -> Result<UpdateFragmenter> {
+    fn handle(&mut self, bitmap: &BitmapUpdate, buf: WriteBuf) -> Result<UpdateFragmenter> {
         match self {
-            Self::None(up) => up.handle(bitmap),
-            Self::Bitmap(up) => up.handle(bitmap),
-            Self::RemoteFx(up) => up.handle(bitmap),
+            Self::None(up) => up.handle(bitmap, buf),
+            Self::Bitmap(up) => up.handle(bitmap, buf),
+            Self::RemoteFx(up) => up.handle(bitmap, buf),
         }
     }
 }
 
 trait BitmapUpdateHandler {
-    fn handle(&mut self, bitmap: &BitmapUpdate) -> Result<UpdateFragmenter>;
+    /// Encodes the bitmap update in `buf`.
+    fn handle(&mut self, bitmap: &BitmapUpdate, buf: WriteBuf) -> Result<UpdateFragmenter>;
 }
 
-#[derive(Clone, Debug)]
-struct NoneHandler;
+#[derive(Clone, Debug, Default)]
+struct NoneHandler {
+    /// Bottom-up rows of the bitmap, reused across the updates.
+    rows: Vec<u8>,
+}
 
 impl BitmapUpdateHandler for NoneHandler {
-    fn handle(&mut self, bitmap: &BitmapUpdate) -> Result<UpdateFragmenter> {
+    fn handle(&mut self, bitmap: &BitmapUpdate, buf: WriteBuf) -> Result<UpdateFragmenter> {
         let stride = usize::from(bitmap.format.bytes_per_pixel()) * usize::from(bitmap.width.get());
-        let mut data = Vec::with_capacity(stride * usize::from(bitmap.height.get()));
+        self.rows.clear();
         for row in bitmap.data.chunks(bitmap.stride).rev() {
-            data.extend_from_slice(&row[..stride]);
+            self.rows.extend_from_slice(&row[..stride]);
         }
-        set_surface(bitmap, CodecId::None as u8, &data)
+        set_surface(bitmap, CodecId::None as u8, &self.rows, buf)
     }
 }
 
@@ -213,22 +426,22 @@ impl fmt::Debug for BitmapHandler {
 }
 
 impl BitmapHandler {
-    fn new() -> Self {
+    fn new(compression: BitmapCompression) -> Self {
         Self {
-            bitmap: BitmapEncoder::new(),
+            bitmap: BitmapEncoder::new(compression),
         }
     }
 }
 
 impl BitmapUpdateHandler for BitmapHandler {
-    fn handle(&mut self, bitmap: &BitmapUpdate) -> Result<UpdateFragmenter> {
-        let mut buffer = vec![0; bitmap.data.len() * 2]; // TODO: estimate bitmap encoded size
+    fn handle(&mut self, bitmap: &BitmapUpdate, mut buf: WriteBuf) -> Result<UpdateFragmenter> {
+        let mut size = bitmap.data.len() * 2; // TODO: estimate bitmap encoded size
         let len = loop {
-            match self.bitmap.encode(bitmap, buffer.as_mut_slice()) {
+            match self.bitmap.encode(bitmap, buf.unfilled_to(size)) {
                 Err(e) => match e.kind() {
                     ironrdp_core::EncodeErrorKind::NotEnoughBytes { .. } => {
-                        buffer.resize(buffer.len() * 2, 0);
-                        debug!("encoder buffer resized to: {}", buffer.len() * 2);
+                        size *= 2;
+                        debug!("encoder buffer resized to: {}", size);
                     }
 
                     _ => Err(e).context("bitmap encode error")?,
@@ -237,8 +450,8 @@ impl BitmapUpdateHandler for BitmapHandler {
             }
         };
 
-        buffer.truncate(len);
-        Ok(UpdateFragmenter::new(UpdateCode::Bitmap, buffer))
+        buf.advance(len);
+        Ok(UpdateFragmenter::new(UpdateCode::Bitmap, buf.into_filled()))
     }
 }
 
@@ -246,26 +459,32 @@ impl BitmapUpdateHandler for BitmapHandler {
 struct RemoteFxHandler {
     remotefx: RfxEncoder,
     codec_id: u8,
+    /// RemoteFX messages of the bitmap, reused across the updates.
+    buffer: Vec<u8>,
 }
 
 impl RemoteFxHandler {
-    fn new(algo: EntropyBits, codec_id: u8) -> Self {
+    fn new(algo: EntropyBits, codec_id: u8, quality: RemoteFxQuality) -> Self {
         Self {
-            remotefx: RfxEncoder::new(algo),
+            remotefx: RfxEncoder::new(algo, quality),
             codec_id,
+            buffer: Vec::new(),
         }
     }
 }
 
 impl BitmapUpdateHandler for RemoteFxHandler {
-    fn handle(&mut self, bitmap: &BitmapUpdate) -> Result<UpdateFragmenter> {
-        let mut buffer = vec![0; bitmap.data.len()];
+    fn handle(&mut self, bitmap: &BitmapUpdate, buf: WriteBuf) -> Result<UpdateFragmenter> {
+        if self.buffer.len() < bitmap.data.len() {
+            self.buffer.resize(bitmap.data.len(), 0);
+        }
+
         let len = loop {
-            match self.remotefx.encode(bitmap, buffer.as_mut_slice()) {
+            match self.remotefx.encode(bitmap, self.buffer.as_mut_slice()) {
                 Err(e) => match e.kind() {
                     ironrdp_core::EncodeErrorKind::NotEnoughBytes { .. } => {
-                        buffer.resize(buffer.len() * 2, 0);
-                        debug!("encoder buffer resized to: {}", buffer.len() * 2);
+                        self.buffer.resize(self.buffer.len() * 2, 0);
+                        debug!("encoder buffer resized to: {}", self.buffer.len());
                     }
 
                     _ => Err(e).context("RemoteFX encode error")?,
@@ -274,11 +493,11 @@ impl BitmapUpdateHandler for RemoteFxHandler {
             }
         };
 
-        set_surface(bitmap, self.codec_id, &buffer[..len])
+        set_surface(bitmap, self.codec_id, &self.buffer[..len], buf)
     }
 }
 
-fn set_surface(bitmap: &BitmapUpdate, codec_id: u8, data: &[u8]) -> Result<UpdateFragmenter> {
+fn set_surface(bitmap: &BitmapUpdate, codec_id: u8, data: &[u8], mut buf: WriteBuf) -> Result<UpdateFragmenter> {
     let destination = ExclusiveRectangle {
         left: bitmap.x,
         top: bitmap.y,
@@ -298,5 +517,6 @@ fn set_surface(bitmap: &BitmapUpdate, codec_id: u8, data: &[u8]) -> Result<Updat
         extended_bitmap_data,
     };
     let cmd = SurfaceCommand::SetSurfaceBits(pdu);
-    Ok(UpdateFragmenter::new(UpdateCode::SurfaceCommands, encode_vec(&cmd)?))
+    encode_buf(&cmd, &mut buf)?;
+    Ok(UpdateFragmenter::new(UpdateCode::SurfaceCommands, buf.into_filled()))
 }
@@ -0,0 +1,107 @@
+use core::hash::{Hash as _, Hasher as _};
+use std::hash::DefaultHasher;
+
+use anyhow::{ensure, Result};
+
+use crate::{ColorPointer, RGBAPointer};
+
+/// Maximum width and height of the pointers sent using the New Pointer update.
+pub(crate) const MAX_POINTER_SIZE: u16 = 96;
+
+/// Maximum width and height of the pointers sent using the Large Pointer update.
+pub(crate) const MAX_LARGE_POINTER_SIZE: u16 = 384;
+
+/// Client pointer cache, avoiding to send the same pointer shapes again.
+///
+/// The least recently used shape is evicted when the cache is full.
+#[derive(Debug)]
+pub(crate) struct PointerCache {
+    /// Hashes and cache indices of the pointer shapes, the most recently used last.
+    entries: Vec<(u64, u16)>,
+    size: u16,
+}
+
+impl PointerCache {
+    pub(crate) fn new(size: u16) -> Self {
+        Self {
+            entries: Vec::with_capacity(usize::from(size)),
+            size,
+        }
+    }
+
+    /// Returns the cache index of the pointer shape, and whether it is already cached by the client.
+    pub(crate) fn lookup(&mut self, hash: u64) -> (u16, bool) {
+        if self.size == 0 {
+            return (0, false);
+        }
+
+        if let Some(position) = self.entries.iter().position(|(h, _)| *h == hash) {
+            let entry = self.entries.remove(position);
+            self.entries.push(entry);
+            return (entry.1, true);
+        }
+
+        let index = if self.entries.len() < usize::from(self.size) {
+            u16::try_from(self.entries.len()).expect("less than the cache size")
+        } else {
+            self.entries.remove(0).1
+        };
+        self.entries.push((hash, index));
+
+        (index, false)
+    }
+}
+
+pub(crate) fn rgba_hash(ptr: &RGBAPointer) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (ptr.width, ptr.height, ptr.hot_x, ptr.hot_y).hash(&mut hasher);
+    ptr.data.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub(crate) fn color_hash(ptr: &ColorPointer) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (ptr.width, ptr.height, ptr.hot_x, ptr.hot_y).hash(&mut hasher);
+    ptr.xor_mask.hash(&mut hasher);
+    ptr.and_mask.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Converts an RGBA pointer to the 32 bpp XOR mask and the AND mask of the pointer updates.
+///
+/// The XOR mask is made of bottom-up BGRA rows. The AND mask is set on the fully transparent
+/// pixels, so clients ignoring the alpha channel still show them as transparent.
+pub(crate) fn rgba_to_masks(ptr: &RGBAPointer) -> Result<(Vec<u8>, Vec<u8>)> {
+    let width = usize::from(ptr.width);
+    let height = usize::from(ptr.height);
+    ensure!(
+        ptr.data.len() == width * height * 4,
+        "invalid RGBA pointer data length: {} for {}x{}",
+        ptr.data.len(),
+        ptr.width,
+        ptr.height
+    );
+
+    // The rows of the AND mask are padded to 2 bytes, the XOR mask rows are always aligned in 32 bpp.
+    let and_stride = width.div_ceil(16) * 2;
+
+    let mut xor_mask = Vec::with_capacity(ptr.data.len());
+    let mut and_mask = vec![0; and_stride * height];
+
+    if width == 0 {
+        return Ok((xor_mask, and_mask));
+    }
+
+    for (row, pixels) in ptr.data.chunks_exact(width * 4).rev().enumerate() {
+        for (column, pixel) in pixels.chunks_exact(4).enumerate() {
+            let [r, g, b, a] = [pixel[0], pixel[1], pixel[2], pixel[3]];
+            xor_mask.extend_from_slice(&[b, g, r, a]);
+
+            if a == 0 {
+                and_mask[row * and_stride + column / 8] |= 0x80 >> (column % 8);
+            }
+        }
+    }
+
+    Ok((xor_mask, and_mask))
+}
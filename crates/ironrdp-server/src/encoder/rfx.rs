@@ -6,23 +6,130 @@ use ironrdp_pdu::codecs::rfx::{
     self, Block, ChannelsPdu, CodecChannel, CodecVersionsPdu, FrameBeginPdu, FrameEndPdu, OperatingMode, Quant,
     RegionPdu, RfxChannel, SyncPdu, TileSetPdu,
 };
-use ironrdp_pdu::rdp::capability_sets::EntropyBits;
+use ironrdp_pdu::rdp::capability_sets::{Codec, CodecProperty, EntropyBits, RemoteFxContainer, RfxICapFlags};
 use ironrdp_pdu::WriteCursor;
 
 use crate::BitmapUpdate;
 
+/// Quality of the RemoteFX encoding.
+///
+/// The quality selects the quantization values applied to the tiles: a lower quality gives a
+/// higher compression rate, at the cost of more visible artifacts.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum RemoteFxQuality {
+    High,
+    /// Quantization values used by the Microsoft RDP server.
+    #[default]
+    Medium,
+    Low,
+}
+
+impl RemoteFxQuality {
+    /// Returns the quantization values of the luma (Y) and chroma (Cb, Cr) components.
+    fn quants(self) -> (Quant, Quant) {
+        match self {
+            RemoteFxQuality::High => {
+                let quant = Quant {
+                    ll3: 6,
+                    lh3: 6,
+                    hl3: 6,
+                    hh3: 6,
+                    lh2: 6,
+                    hl2: 6,
+                    hh2: 6,
+                    lh1: 7,
+                    hl1: 7,
+                    hh1: 7,
+                };
+                (quant.clone(), quant)
+            }
+            RemoteFxQuality::Medium => (Quant::default(), Quant::default()),
+            // The eye is less sensitive to color details, so chroma is quantized more aggressively.
+            RemoteFxQuality::Low => (
+                Quant {
+                    ll3: 7,
+                    lh3: 7,
+                    hl3: 7,
+                    hh3: 7,
+                    lh2: 8,
+                    hl2: 8,
+                    hh2: 9,
+                    lh1: 10,
+                    hl1: 10,
+                    hh1: 11,
+                },
+                Quant {
+                    ll3: 8,
+                    lh3: 8,
+                    hl3: 8,
+                    hh3: 8,
+                    lh2: 9,
+                    hl2: 9,
+                    hh2: 10,
+                    lh1: 11,
+                    hl1: 11,
+                    hh1: 12,
+                },
+            ),
+        }
+    }
+}
+
+/// Selects the RemoteFX codec to use among the codecs advertised by the client.
+///
+/// Returns the entropy algorithm and the codec ID. The encoder operates in image mode only, so the
+/// Image RemoteFX codec and the capabilities supporting image mode are preferred. RLGR3 is then
+/// preferred over RLGR1 for its better compression rate.
+// FIXME: implement the video mode, see [MS-RDPRFX] 3.1.1.1 "State Machine", which allows to skip
+// sending the header messages for each frame.
+pub(crate) fn negotiate(codecs: &[Codec]) -> Option<(EntropyBits, u8)> {
+    codecs
+        .iter()
+        .filter_map(|codec| match &codec.property {
+            CodecProperty::ImageRemoteFx(RemoteFxContainer::ClientContainer(c)) => Some((true, codec.id, c)),
+            CodecProperty::RemoteFx(RemoteFxContainer::ClientContainer(c)) => Some((false, codec.id, c)),
+            _ => None,
+        })
+        .flat_map(|(is_image_codec, id, container)| {
+            container.caps_data.0 .0.iter().map(move |icap| {
+                let image_mode = icap.flags.contains(RfxICapFlags::CODEC_MODE);
+                let rlgr3 = icap.entropy_bits == EntropyBits::Rlgr3;
+                ((image_mode, is_image_codec, rlgr3), (icap.entropy_bits, id))
+            })
+        })
+        // On equal score, the first advertised codec is kept.
+        .rev()
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, codec)| codec)
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct RfxEncoder {
     entropy_algorithm: rfx::EntropyAlgorithm,
+    luma_quant: Quant,
+    chroma_quant: Quant,
+    frame_index: u32,
 }
 
 impl RfxEncoder {
-    pub(crate) fn new(entropy_bits: EntropyBits) -> Self {
+    pub(crate) fn new(entropy_bits: EntropyBits, quality: RemoteFxQuality) -> Self {
         let entropy_algorithm = match entropy_bits {
             EntropyBits::Rlgr1 => rfx::EntropyAlgorithm::Rlgr1,
             EntropyBits::Rlgr3 => rfx::EntropyAlgorithm::Rlgr3,
         };
-        Self { entropy_algorithm }
+        let (luma_quant, chroma_quant) = quality.quants();
+
+        Self {
+            entropy_algorithm,
+            luma_quant,
+            chroma_quant,
+            frame_index: 0,
+        }
+    }
+
+    /// Changes the quantization values of the next frames.
+    pub(crate) fn set_quality(&mut self, quality: RemoteFxQuality) {
+        (self.luma_quant, self.chroma_quant) = quality.quants();
     }
 
     pub(crate) fn encode(&mut self, bitmap: &BitmapUpdate, output: &mut [u8]) -> EncodeResult<usize> {
@@ -33,7 +140,7 @@ impl RfxEncoder {
         let entropy_algorithm = self.entropy_algorithm;
 
         // header messages
-        // FIXME: skip if unnecessary?
+        // In image mode, each frame is decoded independently, and must start with the header messages.
         Block::Sync(SyncPdu).encode(&mut cursor)?;
         let context = rfx::ContextPdu {
             flags: OperatingMode::IMAGE_MODE,
@@ -51,13 +158,13 @@ impl RfxEncoder {
 
         // data messages
         let frame_begin = FrameBeginPdu {
-            index: 0,
+            index: self.frame_index,
             number_of_regions: 1,
         };
+        self.frame_index = self.frame_index.wrapping_add(1);
         Block::CodecChannel(CodecChannel::FrameBegin(frame_begin)).encode(&mut cursor)?;
 
-        let width = bitmap.width.get();
-        let height = bitmap.height.get();
+        // The tiles are clipped by the client to the region, which covers the updated area only.
         let rectangles = vec![rfx::RfxRectangle {
             x: 0,
             y: 0,
@@ -67,12 +174,19 @@ impl RfxEncoder {
         let region = RegionPdu { rectangles };
         Block::CodecChannel(CodecChannel::Region(region)).encode(&mut cursor)?;
 
-        let quant = Quant::default();
-
-        let (encoder, mut data) = UpdateEncoder::new(bitmap, quant.clone(), entropy_algorithm);
+        let (encoder, mut data) = UpdateEncoder::new(
+            bitmap,
+            self.luma_quant.clone(),
+            self.chroma_quant.clone(),
+            entropy_algorithm,
+        );
         let tiles = encoder.encode(&mut data)?;
 
-        let quants = vec![quant];
+        let quants = if self.luma_quant == self.chroma_quant {
+            vec![self.luma_quant.clone()]
+        } else {
+            vec![self.luma_quant.clone(), self.chroma_quant.clone()]
+        };
         let tile_set = TileSetPdu {
             entropy_algorithm,
             quants,
@@ -89,7 +203,8 @@ impl RfxEncoder {
 
 pub(crate) struct UpdateEncoder<'a> {
     bitmap: &'a BitmapUpdate,
-    quant: Quant,
+    luma_quant: Quant,
+    chroma_quant: Quant,
     entropy_algorithm: rfx::EntropyAlgorithm,
 }
 
@@ -104,12 +219,14 @@ struct EncodedTile<'a> {
 impl<'a> UpdateEncoder<'a> {
     fn new(
         bitmap: &'a BitmapUpdate,
-        quant: Quant,
+        luma_quant: Quant,
+        chroma_quant: Quant,
         entropy_algorithm: rfx::EntropyAlgorithm,
     ) -> (Self, UpdateEncoderData) {
         let this = Self {
             bitmap,
-            quant,
+            luma_quant,
+            chroma_quant,
             entropy_algorithm,
         };
         let data = this.alloc_data();
@@ -135,6 +252,8 @@ impl<'a> UpdateEncoder<'a> {
         use rayon::prelude::*;
 
         let (tiles_x, tiles_y) = self.tiles_xy();
+        // Chroma quantization values are stored after the luma ones when they differ.
+        let chroma_quant_index = u8::from(self.luma_quant != self.chroma_quant);
 
         #[cfg(not(feature = "rayon"))]
         let chunks = data.0.chunks_mut(64 * 64 * 3);
@@ -156,8 +275,8 @@ impl<'a> UpdateEncoder<'a> {
 
                 let tile = rfx::Tile {
                     y_quant_index: 0,
-                    cb_quant_index: 0,
-                    cr_quant_index: 0,
+                    cb_quant_index: chroma_quant_index,
+                    cr_quant_index: chroma_quant_index,
                     x: u16::try_from(tile_x).unwrap(),
                     y: u16::try_from(tile_y).unwrap(),
                     y_data,
@@ -201,11 +320,11 @@ impl<'a> UpdateEncoder<'a> {
         let (y_data, buf) = buf.split_at_mut(4096);
         let (cb_data, cr_data) = buf.split_at_mut(4096);
 
-        let len = rfx_encode_component(y, y_data, &self.quant, self.entropy_algorithm)?;
+        let len = rfx_encode_component(y, y_data, &self.luma_quant, self.entropy_algorithm)?;
         let y_data = &y_data[..len];
-        let len = rfx_encode_component(cb, cb_data, &self.quant, self.entropy_algorithm)?;
+        let len = rfx_encode_component(cb, cb_data, &self.chroma_quant, self.entropy_algorithm)?;
         let cb_data = &cb_data[..len];
-        let len = rfx_encode_component(cr, cr_data, &self.quant, self.entropy_algorithm)?;
+        let len = rfx_encode_component(cr, cr_data, &self.chroma_quant, self.entropy_algorithm)?;
         let cr_data = &cr_data[..len];
 
         Ok(EncodedTile {
@@ -227,14 +346,22 @@ pub(crate) mod bench {
         tile_x: usize,
         tile_y: usize,
     ) {
-        let (enc, mut data) = UpdateEncoder::new(bitmap, quant.clone(), algo);
+        let (enc, mut data) = UpdateEncoder::new(bitmap, quant.clone(), quant.clone(), algo);
 
         enc.encode_tile(tile_x, tile_y, &mut data.0).unwrap();
     }
 
     pub fn rfx_enc(bitmap: &BitmapUpdate, quant: &Quant, algo: rfx::EntropyAlgorithm) {
-        let (enc, mut data) = UpdateEncoder::new(bitmap, quant.clone(), algo);
+        let (enc, mut data) = UpdateEncoder::new(bitmap, quant.clone(), quant.clone(), algo);
 
         enc.encode(&mut data).unwrap();
     }
+
+    pub fn negotiate(codecs: &[Codec]) -> Option<(EntropyBits, u8)> {
+        super::negotiate(codecs)
+    }
+
+    pub fn quants(quality: RemoteFxQuality) -> (Quant, Quant) {
+        quality.quants()
+    }
 }
@@ -0,0 +1,36 @@
+use std::io;
+
+use ironrdp_acceptor::ConnectorError;
+pub use ironrdp_acceptor::ErrorClass;
+use ironrdp_core::{DecodeError, EncodeError};
+use ironrdp_pdu::PduError;
+use tokio_rustls::rustls;
+
+/// Returns the class of an error returned by the server, e.g. by [`crate::RdpServer::run_connection`].
+///
+/// The first cause of the chain with a known class gives the class of the error.
+pub fn error_class(error: &anyhow::Error) -> ErrorClass {
+    for cause in error.chain() {
+        let class = if let Some(e) = cause.downcast_ref::<ConnectorError>() {
+            e.class()
+        } else if let Some(e) = cause.downcast_ref::<PduError>() {
+            e.class()
+        } else if let Some(e) = cause.downcast_ref::<DecodeError>() {
+            e.class()
+        } else if let Some(e) = cause.downcast_ref::<EncodeError>() {
+            e.class()
+        } else if cause.is::<io::Error>() {
+            ErrorClass::Io
+        } else if cause.is::<rustls::Error>() {
+            ErrorClass::Security
+        } else {
+            continue;
+        };
+
+        if class != ErrorClass::Other {
+            return class;
+        }
+    }
+
+    ErrorClass::Other
+}
@@ -0,0 +1,171 @@
+//! Flow control of the bitmap updates, using the frame acknowledgements of the client.
+//!
+//! The bitmap updates are sent as frames, which the client acknowledges once processed: using the
+//! Frame Acknowledge PDU for the surface commands delimited by Frame Marker commands (\[MS-RDPBCGR\]
+//! 2.2.14.4), or the RDPGFX_FRAME_ACKNOWLEDGE_PDU for the graphics pipeline (\[MS-RDPEGFX\] 2.2.2.13).
+//!
+//! No frame is encoded while too many frames are in flight, so the updates of a slow client or link
+//! are not queued in the network buffers, which would delay the following updates by seconds.
+
+use core::time::Duration;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tokio::sync::Notify;
+
+use crate::NetworkFeedback;
+
+/// Default maximum number of frames sent to a client and not acknowledged yet.
+pub(crate) const DEFAULT_MAX_UNACKNOWLEDGED_FRAMES: u32 = 4;
+
+/// The frames in flight are forgotten when no acknowledgement is received for this duration, so a
+/// client not acknowledging the frames as expected doesn't freeze the display.
+const ACKNOWLEDGEMENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    /// Maximum number of frames in flight, `None` when the client is not acknowledging the frames.
+    max_in_flight: Option<u32>,
+    next_frame_id: u32,
+    /// IDs of the frames in flight, and when they were sent.
+    in_flight: VecDeque<(u32, Instant)>,
+    /// Smoothed latency of the acknowledgements.
+    latency: Option<Duration>,
+}
+
+impl TrackerState {
+    fn add_latency_sample(&mut self, sample: Duration) {
+        // Same smoothing as the TCP round-trip time, see RFC 6298.
+        self.latency = Some(match self.latency {
+            Some(latency) => latency * 7 / 8 + sample / 8,
+            None => sample,
+        });
+    }
+
+    fn is_ready(&self) -> bool {
+        self.max_in_flight
+            .is_none_or(|max| self.in_flight.len() < usize::try_from(max).unwrap_or(usize::MAX))
+    }
+}
+
+/// Tracks the frames sent to a client, until acknowledged.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FrameTracker {
+    state: Arc<Mutex<TrackerState>>,
+    acknowledged: Arc<Notify>,
+}
+
+impl FrameTracker {
+    /// Starts tracking the frames, once the client is known to acknowledge them.
+    pub(crate) fn enable(&self, max_in_flight: u32) {
+        let mut state = self.state.lock().expect("poisoned");
+        if state.max_in_flight.is_none() {
+            debug!(max_in_flight, "Frame acknowledgement enabled");
+        }
+        state.max_in_flight = Some(max_in_flight.max(1));
+    }
+
+    /// Stops tracking the frames, e.g. when the client suspends the acknowledgements.
+    pub(crate) fn disable(&self) {
+        let mut state = self.state.lock().expect("poisoned");
+        if state.max_in_flight.take().is_some() {
+            debug!("Frame acknowledgement disabled");
+        }
+        state.in_flight.clear();
+        state.latency = None;
+        drop(state);
+
+        self.acknowledged.notify_waiters();
+    }
+
+    /// Returns the ID of a new frame, which is in flight until acknowledged.
+    pub(crate) fn next_frame(&self) -> u32 {
+        let mut state = self.state.lock().expect("poisoned");
+        let frame_id = state.next_frame_id;
+        state.next_frame_id = frame_id.wrapping_add(1);
+        if state.max_in_flight.is_some() {
+            state.in_flight.push_back((frame_id, Instant::now()));
+        }
+
+        frame_id
+    }
+
+    /// Handles the acknowledgement of a frame, which also acknowledges the frames sent before it.
+    pub(crate) fn acknowledge(&self, frame_id: u32) {
+        let mut state = self.state.lock().expect("poisoned");
+        let Some(position) = state.in_flight.iter().position(|&(id, _)| id == frame_id) else {
+            trace!(frame_id, "Acknowledged frame not in flight");
+            return;
+        };
+        let sent_at = state.in_flight[position].1;
+        state.in_flight.drain(..=position);
+        state.add_latency_sample(sent_at.elapsed());
+        drop(state);
+
+        self.acknowledged.notify_waiters();
+    }
+
+    /// Waits until a new frame can be sent.
+    pub(crate) async fn ready(&self) {
+        let wait = async {
+            loop {
+                // Created before checking the state, so no acknowledgement is missed.
+                let acknowledged = self.acknowledged.notified();
+                if self.state.lock().expect("poisoned").is_ready() {
+                    break;
+                }
+                acknowledged.await;
+            }
+        };
+
+        if tokio::time::timeout(ACKNOWLEDGEMENT_TIMEOUT, wait).await.is_err() {
+            let mut state = self.state.lock().expect("poisoned");
+            warn!(in_flight = state.in_flight.len(), "Frames not acknowledged in time");
+            state.in_flight.clear();
+            state.add_latency_sample(ACKNOWLEDGEMENT_TIMEOUT);
+        }
+    }
+
+    /// Returns the network conditions measured from the acknowledgements, if any frame was acknowledged.
+    pub(crate) fn feedback(&self) -> Option<NetworkFeedback> {
+        let state = self.state.lock().expect("poisoned");
+
+        Some(NetworkFeedback {
+            frame_latency: state.latency?,
+            unacknowledged_frames: state.in_flight.len(),
+        })
+    }
+}
+
+#[cfg(feature = "__bench")]
+pub(crate) mod bench {
+    #[derive(Debug, Clone, Default)]
+    pub struct FrameTracker(super::FrameTracker);
+
+    impl FrameTracker {
+        pub fn enable(&self, max_in_flight: u32) {
+            self.0.enable(max_in_flight);
+        }
+
+        pub fn disable(&self) {
+            self.0.disable();
+        }
+
+        pub fn next_frame(&self) -> u32 {
+            self.0.next_frame()
+        }
+
+        pub fn acknowledge(&self, frame_id: u32) {
+            self.0.acknowledge(frame_id);
+        }
+
+        pub async fn ready(&self) {
+            self.0.ready().await;
+        }
+
+        pub fn feedback(&self) -> Option<crate::NetworkFeedback> {
+            self.0.feedback()
+        }
+    }
+}
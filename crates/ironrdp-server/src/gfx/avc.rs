@@ -0,0 +1,263 @@
+use core::fmt;
+
+use anyhow::{Context as _, Result};
+
+use crate::BitmapUpdate;
+
+/// Frames are aligned on H.264 macroblocks.
+const MACROBLOCK_SIZE: usize = 16;
+
+/// H.264 encoder producing the bitstreams of the graphics pipeline AVC codecs.
+///
+/// Implementations are typically wrapping a software encoder (e.g. OpenH264), or a hardware one
+/// (e.g. NVENC, VA-API).
+pub trait H264Encoder: Send {
+    /// Encodes a frame into an H.264 Annex B bitstream.
+    ///
+    /// The first frame produced by an encoder must be an IDR picture. Every frame is decoded by the
+    /// client as soon as it is received, the bitstream must therefore not contain B-frames.
+    fn encode(&mut self, frame: &Yuv420Frame) -> Result<Vec<u8>>;
+
+    /// Sets the target bitrate of the next frames, in kilobits per second.
+    ///
+    /// Called when the quality of the connection is adapted, see [`QualityPolicy`](crate::QualityPolicy).
+    /// Encoders without rate control may ignore it.
+    fn set_bitrate(&mut self, bitrate: u32) {
+        let _ = bitrate;
+    }
+}
+
+/// Creates the H.264 encoders of each connection.
+///
+/// When the AVC444 codec is used, two encoders are created: one for the luma view and one for the
+/// chroma view of the frames.
+pub trait H264EncoderFactory: Send + Sync {
+    /// Creates an encoder for frames of the given dimensions, which are multiples of 16.
+    fn build_encoder(&self, width: u16, height: u16) -> Result<Box<dyn H264Encoder>>;
+}
+
+/// Planar YUV 4:2:0 frame (I420), using the BT.709 full range color space.
+///
+/// Dimensions are multiples of 16. The planes are tightly packed, the chroma planes being half the
+/// width and half the height of the luma plane.
+pub struct Yuv420Frame {
+    width: u16,
+    height: u16,
+    y: Vec<u8>,
+    u: Vec<u8>,
+    v: Vec<u8>,
+}
+
+impl fmt::Debug for Yuv420Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Yuv420Frame")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+impl Yuv420Frame {
+    fn new(width: u16, height: u16) -> Self {
+        let luma = usize::from(width) * usize::from(height);
+
+        Self {
+            width,
+            height,
+            y: vec![0; luma],
+            u: vec![128; luma / 4],
+            v: vec![128; luma / 4],
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Luma plane, with a stride equal to the frame width.
+    pub fn y(&self) -> &[u8] {
+        &self.y
+    }
+
+    /// Blue-difference chroma plane, with a stride equal to half the frame width.
+    pub fn u(&self) -> &[u8] {
+        &self.u
+    }
+
+    /// Red-difference chroma plane, with a stride equal to half the frame width.
+    pub fn v(&self) -> &[u8] {
+        &self.v
+    }
+}
+
+/// Full resolution YUV 4:4:4 copy of the desktop, from which the AVC frames are derived.
+pub(crate) struct Yuv444Surface {
+    width: u16,
+    height: u16,
+    stride: usize,
+    rows: usize,
+    y: Vec<u8>,
+    u: Vec<u8>,
+    v: Vec<u8>,
+}
+
+impl Yuv444Surface {
+    pub(crate) fn new(width: u16, height: u16) -> Self {
+        let stride = usize::from(width).next_multiple_of(MACROBLOCK_SIZE);
+        let rows = usize::from(height).next_multiple_of(MACROBLOCK_SIZE);
+
+        Self {
+            width,
+            height,
+            stride,
+            rows,
+            y: vec![0; stride * rows],
+            u: vec![128; stride * rows],
+            v: vec![128; stride * rows],
+        }
+    }
+
+    pub(crate) fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub(crate) fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Dimensions of the frames derived from this surface.
+    ///
+    /// Fails when the dimensions aligned on the macroblocks don't fit into the frames, e.g. for a width of 65535.
+    pub(crate) fn frame_size(&self) -> Result<(u16, u16)> {
+        let width = u16::try_from(self.stride).context("aligned width too large for an H.264 frame")?;
+        let height = u16::try_from(self.rows).context("aligned height too large for an H.264 frame")?;
+
+        Ok((width, height))
+    }
+
+    /// Converts the bitmap into the surface, ignoring the parts outside of the surface.
+    pub(crate) fn update(&mut self, bitmap: &BitmapUpdate) -> Result<()> {
+        let right = (bitmap.x + bitmap.width.get()).min(self.width);
+        let bottom = (bitmap.y + bitmap.height.get()).min(self.height);
+        if bitmap.x >= right || bitmap.y >= bottom {
+            return Ok(());
+        }
+
+        let bpp = usize::from(bitmap.format.bytes_per_pixel());
+        let columns = usize::from(right - bitmap.x);
+
+        for (row, src) in bitmap
+            .data
+            .chunks(bitmap.stride)
+            .take(usize::from(bottom - bitmap.y))
+            .enumerate()
+        {
+            let offset = (usize::from(bitmap.y) + row) * self.stride + usize::from(bitmap.x);
+
+            for (column, pixel) in src.chunks_exact(bpp).take(columns).enumerate() {
+                let color = bitmap.format.read_color(pixel)?;
+                let (y, u, v) = rgb_to_yuv(color.r, color.g, color.b);
+
+                self.y[offset + column] = y;
+                self.u[offset + column] = u;
+                self.v[offset + column] = v;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the YUV 4:2:0 frame of the AVC420 codec.
+    ///
+    /// The chroma planes are subsampled by averaging each 2x2 block, this is also the main view of
+    /// the AVC444 codec.
+    pub(crate) fn main_view(&self) -> Result<Yuv420Frame> {
+        let (width, height) = self.frame_size()?;
+        let mut frame = Yuv420Frame::new(width, height);
+        let half_stride = self.stride / 2;
+
+        frame.y.copy_from_slice(&self.y);
+
+        for y in 0..self.rows / 2 {
+            let top = 2 * y * self.stride;
+            let bottom = top + self.stride;
+
+            for x in 0..half_stride {
+                let (left, right) = (2 * x, 2 * x + 1);
+
+                frame.u[y * half_stride + x] = average([
+                    self.u[top + left],
+                    self.u[top + right],
+                    self.u[bottom + left],
+                    self.u[bottom + right],
+                ]);
+                frame.v[y * half_stride + x] = average([
+                    self.v[top + left],
+                    self.v[top + right],
+                    self.v[bottom + left],
+                    self.v[bottom + right],
+                ]);
+            }
+        }
+
+        Ok(frame)
+    }
+
+    /// Returns the auxiliary view of the AVC444 codec, holding the chroma samples dropped by the main view.
+    ///
+    /// The layout is described in \[MS-RDPEGFX\] 3.3.8.3.2: the luma plane is made of the odd rows of
+    /// the chroma planes, interleaved by blocks of 8 rows, while the chroma planes are made of the odd
+    /// columns of the even rows.
+    pub(crate) fn auxiliary_view(&self) -> Result<Yuv420Frame> {
+        let (width, height) = self.frame_size()?;
+        let mut frame = Yuv420Frame::new(width, height);
+        let half_stride = self.stride / 2;
+
+        for (row, dst) in frame.y.chunks_exact_mut(self.stride).enumerate() {
+            let block = row / MACROBLOCK_SIZE * MACROBLOCK_SIZE;
+            let index = row % MACROBLOCK_SIZE;
+            let (plane, src_row) = if index < 8 {
+                (&self.u, block + 2 * index + 1)
+            } else {
+                (&self.v, block + 2 * (index - 8) + 1)
+            };
+
+            dst.copy_from_slice(&plane[src_row * self.stride..][..self.stride]);
+        }
+
+        for y in 0..self.rows / 2 {
+            let src = 2 * y * self.stride;
+
+            for x in 0..half_stride {
+                frame.u[y * half_stride + x] = self.u[src + 2 * x + 1];
+                frame.v[y * half_stride + x] = self.v[src + 2 * x + 1];
+            }
+        }
+
+        Ok(frame)
+    }
+}
+
+fn rgb_to_yuv(r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+    let (r, g, b) = (i32::from(r), i32::from(g), i32::from(b));
+
+    let y = (54 * r + 183 * g + 18 * b) >> 8;
+    let u = ((-29 * r - 99 * g + 128 * b) >> 8) + 128;
+    let v = ((128 * r - 116 * g - 12 * b) >> 8) + 128;
+
+    (clamp(y), clamp(u), clamp(v))
+}
+
+fn clamp(value: i32) -> u8 {
+    u8::try_from(value.clamp(0, 255)).expect("value is clamped")
+}
+
+fn average(values: [u8; 4]) -> u8 {
+    let sum = values.into_iter().map(u16::from).sum::<u16>();
+
+    u8::try_from((sum + 2) / 4).expect("average of u8 values")
+}
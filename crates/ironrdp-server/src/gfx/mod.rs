@@ -0,0 +1,612 @@
+//! Graphics pipeline (EGFX) dynamic channel, as described in \[MS-RDPEGFX\].
+//!
+//! Only the AVC420 and AVC444 codecs are supported: once the channel is ready, the bitmap updates
+//! are composed into a surface covering the whole desktop, which is encoded using the configured
+//! [`H264EncoderFactory`] and sent as graphics pipeline frames instead of fast-path updates.
+
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context as _, Result};
+use ironrdp_acceptor::DesktopSize;
+use ironrdp_core::{
+    decode, encode_vec, ensure_size, impl_as_any, other_err, Encode, EncodeResult, ReadCursor, WriteCursor,
+};
+use ironrdp_dvc::{encode_dvc_messages, DvcEncode, DvcMessage, DvcProcessor, DvcServerProcessor};
+use ironrdp_graphics::zgfx;
+use ironrdp_pdu::dvc::gfx::{
+    Avc420BitmapStream, Avc444BitmapStream, CacheImportReplyPdu, CapabilitiesConfirmPdu, CapabilitiesV103Flags,
+    CapabilitiesV104Flags, CapabilitiesV107Flags, CapabilitiesV10Flags, CapabilitiesV81Flags, CapabilitySet, ClientPdu,
+    ClientPduType, Codec1Type, CreateSurfacePdu, DeleteSurfacePdu, Encoding, EndFramePdu, MapSurfaceToOutputPdu,
+    PixelFormat, QuantQuality, QueueDepth, ResetGraphicsPdu, ServerPdu, StartFramePdu, Timestamp, WireToSurface1Pdu,
+};
+use ironrdp_pdu::gcc::{Monitor, MonitorFlags};
+use ironrdp_pdu::geometry::{ExclusiveRectangle, InclusiveRectangle};
+use ironrdp_pdu::{decode_err, encode_err, pdu_other_err, PduResult};
+use ironrdp_svc::{server_encode_svc_messages, ChannelFlags};
+
+use self::avc::Yuv444Surface;
+use crate::encoder::damage::{self, DamageTracker};
+use crate::flow_control::FrameTracker;
+use crate::BitmapUpdate;
+
+mod avc;
+
+pub use self::avc::{H264Encoder, H264EncoderFactory, Yuv420Frame};
+
+const CHANNEL_NAME: &str = "Microsoft::Windows::RDS::Graphics";
+
+/// The whole desktop is covered by a single surface.
+const SURFACE_ID: u16 = 0;
+
+/// Quantization and quality values sent along the AVC bitstreams.
+///
+/// Those are informative only, the client is not using them for decoding.
+const QUANT_QUALITY: QuantQuality = QuantQuality {
+    quantization_parameter: 22,
+    progressive: false,
+    quality: 100,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum AvcCodec {
+    Avc420,
+    Avc444,
+}
+
+/// Graphics pipeline state shared between the channel processor and the display updates.
+#[derive(Default)]
+pub(crate) struct GfxState {
+    channel_id: Option<u32>,
+    codec: Option<AvcCodec>,
+    /// The capabilities were (re)negotiated, all the client-side graphics state must be recreated.
+    reset: bool,
+    surface_created: bool,
+    /// Compressor of the messages sent on the channel, its history being shared with the client.
+    ///
+    /// The messages must be written in the order they are compressed.
+    compressor: zgfx::Compressor,
+}
+
+pub(crate) type SharedGfxState = Arc<Mutex<GfxState>>;
+
+/// Selects the capability set to confirm, and the AVC codec it enables.
+///
+/// The most recent version allowing AVC is preferred. AVC444 requires version 10 or later.
+fn negotiate(caps: &[CapabilitySet], avc444: bool) -> Option<(CapabilitySet, AvcCodec)> {
+    let v10_codec = if avc444 { AvcCodec::Avc444 } else { AvcCodec::Avc420 };
+
+    caps.iter()
+        .filter_map(|cap| {
+            let codec = match cap {
+                CapabilitySet::V8_1 { flags } if flags.contains(CapabilitiesV81Flags::AVC420_ENABLED) => {
+                    AvcCodec::Avc420
+                }
+                CapabilitySet::V10 { flags } | CapabilitySet::V10_2 { flags }
+                    if !flags.contains(CapabilitiesV10Flags::AVC_DISABLED) =>
+                {
+                    v10_codec
+                }
+                CapabilitySet::V10_1 => v10_codec,
+                CapabilitySet::V10_3 { flags } if !flags.contains(CapabilitiesV103Flags::AVC_DISABLED) => v10_codec,
+                CapabilitySet::V10_4 { flags }
+                | CapabilitySet::V10_5 { flags }
+                | CapabilitySet::V10_6 { flags }
+                | CapabilitySet::V10_6Err { flags }
+                    if !flags.contains(CapabilitiesV104Flags::AVC_DISABLED) =>
+                {
+                    v10_codec
+                }
+                CapabilitySet::V10_7 { flags } if !flags.contains(CapabilitiesV107Flags::AVC_DISABLED) => v10_codec,
+                _ => return None,
+            };
+
+            Some((cap.clone(), codec))
+        })
+        .max_by_key(|(cap, _)| version_rank(cap))
+}
+
+fn version_rank(cap: &CapabilitySet) -> u8 {
+    match cap {
+        CapabilitySet::V8 { .. } => 0,
+        CapabilitySet::V8_1 { .. } => 1,
+        CapabilitySet::V10 { .. } => 2,
+        CapabilitySet::V10_1 => 3,
+        CapabilitySet::V10_2 { .. } => 4,
+        CapabilitySet::V10_3 { .. } => 5,
+        CapabilitySet::V10_4 { .. } => 6,
+        CapabilitySet::V10_5 { .. } => 7,
+        CapabilitySet::V10_6 { .. } | CapabilitySet::V10_6Err { .. } => 8,
+        CapabilitySet::V10_7 { .. } => 9,
+        CapabilitySet::Unknown(_) => 0,
+    }
+}
+
+/// Graphics pipeline PDUs, compressed into a ZGFX segmented data structure.
+struct GfxMessage(Vec<u8>);
+
+impl GfxMessage {
+    const NAME: &'static str = "GfxMessage";
+
+    fn new(pdus: &[ServerPdu], compressor: &mut zgfx::Compressor) -> EncodeResult<Self> {
+        let mut data = Vec::new();
+        for pdu in pdus {
+            data.extend_from_slice(&encode_vec(pdu)?);
+        }
+
+        let mut compressed = Vec::with_capacity(data.len() + 16);
+        compressor
+            .compress(&data, &mut compressed)
+            .map_err(|e| other_err!("ZGFX", source: e))?;
+
+        Ok(Self(compressed))
+    }
+}
+
+impl Encode for GfxMessage {
+    fn encode(&self, dst: &mut WriteCursor<'_>) -> EncodeResult<()> {
+        ensure_size!(in: dst, size: self.size());
+        dst.write_slice(&self.0);
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl DvcEncode for GfxMessage {}
+
+/// Graphics pipeline dynamic channel processor.
+///
+/// It handles the capabilities negotiation, while the frames are sent by the [`GfxHandler`].
+pub(crate) struct GfxServer {
+    state: SharedGfxState,
+    avc444: bool,
+    frames: FrameTracker,
+    max_unacknowledged_frames: Option<u32>,
+}
+
+impl GfxServer {
+    pub(crate) fn new(
+        state: SharedGfxState,
+        avc444: bool,
+        frames: FrameTracker,
+        max_unacknowledged_frames: Option<u32>,
+    ) -> Self {
+        Self {
+            state,
+            avc444,
+            frames,
+            max_unacknowledged_frames,
+        }
+    }
+
+    /// Tracks the frames until acknowledged, unless the client suspended the acknowledgements.
+    fn track_frames(&self, queue_depth: QueueDepth) {
+        match (queue_depth, self.max_unacknowledged_frames) {
+            (QueueDepth::Suspend, _) | (_, None) => self.frames.disable(),
+            (_, Some(max)) => self.frames.enable(max),
+        }
+    }
+}
+
+impl_as_any!(GfxServer);
+
+impl DvcProcessor for GfxServer {
+    fn channel_name(&self) -> &str {
+        CHANNEL_NAME
+    }
+
+    fn start(&mut self, channel_id: u32) -> PduResult<Vec<DvcMessage>> {
+        let mut state = self.state.lock().expect("poisoned");
+        *state = GfxState {
+            channel_id: Some(channel_id),
+            ..GfxState::default()
+        };
+
+        // The client starts the negotiation by advertising its capabilities.
+        Ok(Vec::new())
+    }
+
+    fn close(&mut self, _channel_id: u32) {
+        *self.state.lock().expect("poisoned") = GfxState::default();
+        self.frames.disable();
+    }
+
+    fn process(&mut self, _channel_id: u32, payload: &[u8]) -> PduResult<Vec<DvcMessage>> {
+        let pdu_type = ReadCursor::new(payload)
+            .try_read_u16()
+            .map_err(|e| pdu_other_err!("graphics pipeline PDU", source: e))?;
+        let supported = [
+            ClientPduType::CapabilitiesAdvertise,
+            ClientPduType::FrameAcknowledge,
+            ClientPduType::CacheImportOffer,
+            ClientPduType::QoeFrameAcknowledge,
+        ];
+        if !supported.into_iter().any(|ty| ty as u16 == pdu_type) {
+            debug!(pdu_type, "Ignoring unsupported graphics pipeline PDU");
+            return Ok(Vec::new());
+        }
+
+        match decode(payload).map_err(|e| decode_err!(e))? {
+            ClientPdu::CapabilitiesAdvertise(pdu) => {
+                debug!(?pdu, "Received graphics pipeline capabilities");
+
+                let Some((cap, codec)) = negotiate(&pdu.0, self.avc444) else {
+                    warn!("No graphics pipeline capability set with AVC support");
+                    return Ok(Vec::new());
+                };
+                debug!(?cap, ?codec, "Graphics pipeline capabilities negotiated");
+
+                let mut state = self.state.lock().expect("poisoned");
+                state.codec = Some(codec);
+                state.reset = true;
+                state.surface_created = false;
+
+                // The client acknowledges all the frames, until it suspends the acknowledgements.
+                self.track_frames(QueueDepth::Unavailable);
+
+                let confirm = ServerPdu::CapabilitiesConfirm(CapabilitiesConfirmPdu(cap));
+                let msg = GfxMessage::new(&[confirm], &mut state.compressor).map_err(|e| encode_err!(e))?;
+
+                Ok(vec![Box::new(msg)])
+            }
+            ClientPdu::FrameAcknowledge(pdu) => {
+                trace!(?pdu, "Frame acknowledged");
+                self.track_frames(pdu.queue_depth);
+                self.frames.acknowledge(pdu.frame_id);
+                Ok(Vec::new())
+            }
+            ClientPdu::CacheImportOffer(pdu) => {
+                // The surface cache is not used, so none of the entries persisted by the client are imported.
+                debug!(entries = pdu.cache_entries.len(), "Declining the cache import offer");
+
+                let reply = ServerPdu::CacheImportReply(CacheImportReplyPdu {
+                    cache_slots: Vec::new(),
+                });
+                let mut state = self.state.lock().expect("poisoned");
+                let msg = GfxMessage::new(&[reply], &mut state.compressor).map_err(|e| encode_err!(e))?;
+
+                Ok(vec![Box::new(msg)])
+            }
+            ClientPdu::QoeFrameAcknowledge(pdu) => {
+                trace!(?pdu, "Frame quality of experience");
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+impl DvcServerProcessor for GfxServer {}
+
+struct GfxEncoders {
+    codec: AvcCodec,
+    main: Box<dyn H264Encoder>,
+    auxiliary: Option<Box<dyn H264Encoder>>,
+}
+
+impl GfxEncoders {
+    fn set_bitrate(&mut self, bitrate: u32) {
+        match self.auxiliary.as_mut() {
+            None => self.main.set_bitrate(bitrate),
+            Some(auxiliary) => {
+                self.main.set_bitrate(bitrate / 2);
+                auxiliary.set_bitrate(bitrate / 2);
+            }
+        }
+    }
+}
+
+/// Encodes the bitmap updates as graphics pipeline frames, once the channel is ready.
+///
+/// All the bitmap updates must be given to the handler, including the ones sent before the channel is
+/// ready, so the content of the desktop is known when the surface is created.
+pub(crate) struct GfxHandler {
+    state: SharedGfxState,
+    factory: Arc<dyn H264EncoderFactory>,
+    drdynvc_channel_id: u16,
+    user_channel_id: u16,
+    surface: Yuv444Surface,
+    damage: DamageTracker,
+    encoders: Option<GfxEncoders>,
+    frames: FrameTracker,
+    /// Target bitrate of the encoders, in kilobits per second.
+    bitrate: Option<u32>,
+}
+
+impl GfxHandler {
+    pub(crate) fn new(
+        state: SharedGfxState,
+        factory: Arc<dyn H264EncoderFactory>,
+        desktop_size: DesktopSize,
+        drdynvc_channel_id: u16,
+        user_channel_id: u16,
+        frames: FrameTracker,
+    ) -> Self {
+        Self {
+            state,
+            factory,
+            drdynvc_channel_id,
+            user_channel_id,
+            surface: Yuv444Surface::new(desktop_size.width, desktop_size.height),
+            damage: DamageTracker::new(desktop_size),
+            encoders: None,
+            frames,
+            bitrate: None,
+        }
+    }
+
+    /// Returns `true` if the surface is created, i.e. the bitmap updates are sent using the graphics pipeline.
+    pub(crate) fn is_active(&self) -> bool {
+        let state = self.state.lock().expect("poisoned");
+        state.channel_id.is_some() && state.codec.is_some() && state.surface_created
+    }
+
+    /// Resizes the surface, which is recreated with the next bitmap update.
+    ///
+    /// The new size is given to the client with a Reset Graphics PDU, without deactivating the session.
+    pub(crate) fn resize(&mut self, desktop_size: DesktopSize) {
+        self.surface = Yuv444Surface::new(desktop_size.width, desktop_size.height);
+        self.damage = DamageTracker::new(desktop_size);
+        self.state.lock().expect("poisoned").reset = true;
+    }
+
+    /// Sets the target bitrate of the H.264 encoders, shared between the two encoders of AVC444.
+    pub(crate) fn set_bitrate(&mut self, bitrate: u32) {
+        self.bitrate = Some(bitrate);
+        if let Some(encoders) = self.encoders.as_mut() {
+            encoders.set_bitrate(bitrate);
+        }
+    }
+
+    /// Handles a bitmap update.
+    ///
+    /// Returns the PDUs of the frame, to be given to [`Self::encode`], or `None` if the graphics pipeline is not
+    /// ready and the update must be sent using the fast-path output.
+    pub(crate) fn handle(&mut self, bitmap: &BitmapUpdate) -> Result<Option<Vec<ServerPdu>>> {
+        // Only the damaged regions are converted, and sent once the surface is created.
+        let regions = self.damage.damage(bitmap);
+        for bitmap in damage::split(bitmap.clone(), &regions) {
+            self.surface.update(&bitmap).context("failed to convert bitmap")?;
+        }
+
+        let (codec, reset, surface_created) = {
+            let mut state = self.state.lock().expect("poisoned");
+            let (Some(_), Some(codec)) = (state.channel_id, state.codec) else {
+                return Ok(None);
+            };
+            let reset = core::mem::take(&mut state.reset);
+            let surface_created = core::mem::replace(&mut state.surface_created, true);
+
+            (codec, reset, surface_created)
+        };
+
+        let mut pdus = Vec::new();
+
+        let regions = if reset || !surface_created || self.encoders.as_ref().is_none_or(|e| e.codec != codec) {
+            if surface_created {
+                pdus.push(ServerPdu::DeleteSurface(DeleteSurfacePdu { surface_id: SURFACE_ID }));
+            }
+            pdus.extend(self.create_surface());
+            self.encoders = Some(self.build_encoders(codec)?);
+
+            // The surface is new, it is entirely sent.
+            vec![self.surface_rectangle()]
+        } else if regions.is_empty() {
+            // Nothing to send, the surface is unchanged.
+            return Ok(Some(Vec::new()));
+        } else {
+            regions.into_iter().map(rect16).collect()
+        };
+
+        let frame_id = self.frames.next_frame();
+
+        pdus.push(ServerPdu::StartFrame(StartFramePdu {
+            timestamp: timestamp(),
+            frame_id,
+        }));
+        pdus.push(self.encode_frame(regions)?);
+        pdus.push(ServerPdu::EndFrame(EndFramePdu { frame_id }));
+
+        Ok(Some(pdus))
+    }
+
+    /// Compresses the PDUs of a frame, returning the data to send to the client, or `None` if the channel was
+    /// closed meanwhile.
+    ///
+    /// The data must be written before any other message is compressed, since the history of the compressor is
+    /// shared with the client.
+    pub(crate) fn encode(&self, pdus: &[ServerPdu]) -> Result<Option<Vec<u8>>> {
+        let mut state = self.state.lock().expect("poisoned");
+        let Some(channel_id) = state.channel_id else {
+            return Ok(None);
+        };
+
+        let msg = GfxMessage::new(pdus, &mut state.compressor)?;
+        let msgs = encode_dvc_messages(channel_id, vec![Box::new(msg)], ChannelFlags::SHOW_PROTOCOL)?;
+        let data = server_encode_svc_messages(msgs, self.drdynvc_channel_id, self.user_channel_id)?;
+
+        Ok(Some(data))
+    }
+
+    fn create_surface(&self) -> [ServerPdu; 3] {
+        let (width, height) = (self.surface.width(), self.surface.height());
+
+        [
+            ServerPdu::ResetGraphics(ResetGraphicsPdu {
+                width: u32::from(width),
+                height: u32::from(height),
+                monitors: vec![Monitor {
+                    left: 0,
+                    top: 0,
+                    right: i32::from(width) - 1,
+                    bottom: i32::from(height) - 1,
+                    flags: MonitorFlags::PRIMARY,
+                }],
+            }),
+            ServerPdu::CreateSurface(CreateSurfacePdu {
+                surface_id: SURFACE_ID,
+                width,
+                height,
+                pixel_format: PixelFormat::XRgb,
+            }),
+            ServerPdu::MapSurfaceToOutput(MapSurfaceToOutputPdu {
+                surface_id: SURFACE_ID,
+                output_origin_x: 0,
+                output_origin_y: 0,
+            }),
+        ]
+    }
+
+    fn build_encoders(&self, codec: AvcCodec) -> Result<GfxEncoders> {
+        let (width, height) = self.surface.frame_size()?;
+        debug!(?codec, width, height, "Creating H.264 encoders");
+
+        let main = self.factory.build_encoder(width, height)?;
+        let auxiliary = match codec {
+            AvcCodec::Avc420 => None,
+            AvcCodec::Avc444 => Some(self.factory.build_encoder(width, height)?),
+        };
+
+        let mut encoders = GfxEncoders { codec, main, auxiliary };
+        if let Some(bitrate) = self.bitrate {
+            encoders.set_bitrate(bitrate);
+        }
+
+        Ok(encoders)
+    }
+
+    fn encode_frame(&mut self, regions: Vec<InclusiveRectangle>) -> Result<ServerPdu> {
+        let encoders = self.encoders.as_mut().context("H.264 encoders not created")?;
+
+        let main = encoders.main.encode(&self.surface.main_view()?)?;
+        let main = Avc420BitmapStream {
+            quant_qual_vals: vec![QUANT_QUALITY; regions.len()],
+            rectangles: regions.clone(),
+            data: &main,
+        };
+
+        let (codec_id, bitmap_data) = match encoders.auxiliary.as_mut() {
+            None => (Codec1Type::Avc420, encode_vec(&main)?),
+            Some(auxiliary) => {
+                let auxiliary = auxiliary.encode(&self.surface.auxiliary_view()?)?;
+                let stream = Avc444BitmapStream {
+                    encoding: Encoding::LUMA_AND_CHROMA,
+                    stream1: main,
+                    stream2: Some(Avc420BitmapStream {
+                        quant_qual_vals: vec![QUANT_QUALITY; regions.len()],
+                        rectangles: regions,
+                        data: &auxiliary,
+                    }),
+                };
+
+                (Codec1Type::Avc444, encode_vec(&stream)?)
+            }
+        };
+
+        Ok(ServerPdu::WireToSurface1(WireToSurface1Pdu {
+            surface_id: SURFACE_ID,
+            codec_id,
+            pixel_format: PixelFormat::XRgb,
+            destination_rectangle: self.surface_rectangle(),
+            bitmap_data,
+        }))
+    }
+
+    fn surface_rectangle(&self) -> InclusiveRectangle {
+        rect16(ExclusiveRectangle {
+            left: 0,
+            top: 0,
+            right: self.surface.width(),
+            bottom: self.surface.height(),
+        })
+    }
+}
+
+/// Converts a rectangle to the RDPGFX_RECT16 representation.
+///
+/// The PDU definitions are using `InclusiveRectangle`, but the bounds of RDPGFX_RECT16 are exclusive.
+fn rect16(rect: ExclusiveRectangle) -> InclusiveRectangle {
+    InclusiveRectangle {
+        left: rect.left,
+        top: rect.top,
+        right: rect.right,
+        bottom: rect.bottom,
+    }
+}
+
+fn timestamp() -> Timestamp {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = now.as_secs() % 86_400;
+
+    Timestamp {
+        milliseconds: u16::try_from(now.subsec_millis()).expect("less than 1000"),
+        seconds: u8::try_from(seconds % 60).expect("less than 60"),
+        minutes: u8::try_from(seconds / 60 % 60).expect("less than 60"),
+        hours: u16::try_from(seconds / 3600).expect("less than 24"),
+    }
+}
+
+#[cfg(feature = "__bench")]
+pub(crate) mod bench {
+    use super::*;
+
+    /// Returns the confirmed capability set, and whether the AVC444 codec is used.
+    pub fn negotiate(caps: &[CapabilitySet], avc444: bool) -> Option<(CapabilitySet, bool)> {
+        super::negotiate(caps, avc444).map(|(cap, codec)| (cap, codec == AvcCodec::Avc444))
+    }
+
+    pub fn version_rank(cap: &CapabilitySet) -> u8 {
+        super::version_rank(cap)
+    }
+
+    /// Returns the main and auxiliary views of a desktop updated with the bitmap.
+    pub fn views(desktop_size: DesktopSize, bitmap: &BitmapUpdate) -> Result<(Yuv420Frame, Yuv420Frame)> {
+        let mut surface = Yuv444Surface::new(desktop_size.width, desktop_size.height);
+        surface.update(bitmap)?;
+
+        Ok((surface.main_view()?, surface.auxiliary_view()?))
+    }
+
+    /// Handles the bitmap updates with a graphics pipeline ready to use the negotiated codec, returning
+    /// the data sent to the client.
+    pub fn handle(
+        factory: Arc<dyn H264EncoderFactory>,
+        desktop_size: DesktopSize,
+        avc444: bool,
+        bitmaps: &[BitmapUpdate],
+    ) -> Result<Vec<Vec<u8>>> {
+        let state = Arc::new(Mutex::new(GfxState {
+            channel_id: Some(1),
+            codec: Some(if avc444 { AvcCodec::Avc444 } else { AvcCodec::Avc420 }),
+            reset: true,
+            ..GfxState::default()
+        }));
+        let mut handler = GfxHandler::new(state, factory, desktop_size, 1004, 1002, FrameTracker::default());
+
+        bitmaps
+            .iter()
+            .map(|bitmap| match handler.handle(bitmap)? {
+                Some(pdus) if !pdus.is_empty() => Ok(handler.encode(&pdus)?.unwrap_or_default()),
+                _ => Ok(Vec::new()),
+            })
+            .collect()
+    }
+
+    /// Compresses the messages with the compressor of a single channel, returning the ZGFX segmented data of
+    /// each of them.
+    pub fn compress(messages: &[Vec<ServerPdu>]) -> EncodeResult<Vec<Vec<u8>>> {
+        let mut compressor = zgfx::Compressor::new();
+
+        messages
+            .iter()
+            .map(|pdus| Ok(GfxMessage::new(pdus, &mut compressor)?.0))
+            .collect()
+    }
+}
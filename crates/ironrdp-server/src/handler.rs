@@ -1,3 +1,6 @@
+use core::future::Future;
+
+use futures_util::future::BoxFuture;
 use ironrdp_ainput as ainput;
 use ironrdp_pdu::input::fast_path::{self, SynchronizeFlags};
 use ironrdp_pdu::input::mouse::PointerFlags;
@@ -6,17 +9,32 @@ use ironrdp_pdu::input::mouse_x::PointerXFlags;
 use ironrdp_pdu::input::sync::SyncToggleFlags;
 use ironrdp_pdu::input::{scan_code, unicode, MousePdu, MouseRelPdu, MouseXPdu};
 
+use crate::ClientSessionInfo;
+
 /// Keyboard Event
 ///
 /// Describes a keyboard event received from the client
 ///
 #[derive(Debug)]
 pub enum KeyboardEvent {
-    Pressed { code: u8, extended: bool },
-    Released { code: u8, extended: bool },
+    Pressed {
+        code: u8,
+        extended: bool,
+    },
+    Released {
+        code: u8,
+        extended: bool,
+    },
     UnicodePressed(u16),
     UnicodeReleased(u16),
-    Synchronize(SynchronizeFlags),
+    /// State of the lock keys of the client, sent when the client gets the focus so the server can
+    /// synchronize its own toggle states (and keyboard LEDs).
+    Sync {
+        num_lock: bool,
+        caps_lock: bool,
+        scroll_lock: bool,
+        kana: bool,
+    },
 }
 
 /// Mouse Event
@@ -70,9 +88,60 @@ pub enum MouseEvent {
 ///     }
 /// }
 /// ```
+///
+/// The callbacks are called from the I/O task of the connection and should return quickly. Heavy
+/// processing can be moved to another task with [`input_channel`](crate::input_channel).
 pub trait RdpServerInputHandler: Send {
     fn keyboard(&mut self, event: KeyboardEvent);
     fn mouse(&mut self, event: MouseEvent);
+
+    /// Waits until the handler can accept the next input event.
+    ///
+    /// It is awaited before each input event, the server stops reading from the client meanwhile so
+    /// the client is throttled by the transport instead of the events piling up.
+    fn ready(&mut self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called with the settings of the client once it is connected, before its first input event.
+    ///
+    /// The scancodes of the keyboard events are to be interpreted with the keyboard layout of the client.
+    fn session_info(&mut self, info: &ClientSessionInfo) {
+        let _ = info;
+    }
+}
+
+/// Object-safe version of [`RdpServerInputHandler`], implemented for all the input handlers.
+///
+/// The server and the sessions hold their input handler as a `Box<dyn DynRdpServerInputHandler>`. The
+/// future of [`RdpServerInputHandler::ready`] is boxed, which doesn't allocate for the handlers keeping
+/// the default implementation.
+pub trait DynRdpServerInputHandler: Send {
+    fn keyboard(&mut self, event: KeyboardEvent);
+
+    fn mouse(&mut self, event: MouseEvent);
+
+    fn ready(&mut self) -> BoxFuture<'_, ()>;
+
+    fn session_info(&mut self, info: &ClientSessionInfo);
+}
+
+impl<H: RdpServerInputHandler> DynRdpServerInputHandler for H {
+    fn keyboard(&mut self, event: KeyboardEvent) {
+        RdpServerInputHandler::keyboard(self, event)
+    }
+
+    fn mouse(&mut self, event: MouseEvent) {
+        RdpServerInputHandler::mouse(self, event)
+    }
+
+    fn ready(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(RdpServerInputHandler::ready(self))
+    }
+
+    fn session_info(&mut self, info: &ClientSessionInfo) {
+        RdpServerInputHandler::session_info(self, info)
+    }
 }
 
 impl From<(u8, fast_path::KeyboardFlags)> for KeyboardEvent {
@@ -126,14 +195,23 @@ impl From<(u16, unicode::KeyboardFlags)> for KeyboardEvent {
 
 impl From<SynchronizeFlags> for KeyboardEvent {
     fn from(value: SynchronizeFlags) -> Self {
-        KeyboardEvent::Synchronize(value)
+        KeyboardEvent::Sync {
+            num_lock: value.contains(SynchronizeFlags::NUM_LOCK),
+            caps_lock: value.contains(SynchronizeFlags::CAPS_LOCK),
+            scroll_lock: value.contains(SynchronizeFlags::SCROLL_LOCK),
+            kana: value.contains(SynchronizeFlags::KANA_LOCK),
+        }
     }
 }
 
 impl From<SyncToggleFlags> for KeyboardEvent {
-    #[allow(clippy::cast_possible_truncation)] // we are actually truncating the value
     fn from(value: SyncToggleFlags) -> Self {
-        KeyboardEvent::Synchronize(SynchronizeFlags::from_bits_truncate(value.bits() as u8))
+        KeyboardEvent::Sync {
+            num_lock: value.contains(SyncToggleFlags::NUM_LOCK),
+            caps_lock: value.contains(SyncToggleFlags::CAPS_LOCK),
+            scroll_lock: value.contains(SyncToggleFlags::SCROLL_LOCK),
+            kana: value.contains(SyncToggleFlags::KANA_LOCK),
+        }
     }
 }
 
@@ -0,0 +1,92 @@
+use core::fmt;
+use core::time::Duration;
+
+use ironrdp_pdu::rdp::heartbeat::HeartbeatPdu;
+
+/// Heartbeats sent to the clients, and monitoring of the liveness of the clients.
+///
+/// The heartbeats (\[MS-RDPBCGR\] 2.2.16.1) let the clients detect a broken connection, and reconnect.
+/// They are only sent to the clients supporting them, and not when Standard RDP Security is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeartbeatOptions {
+    /// Interval between two heartbeats, from 1 to 255 seconds.
+    pub interval: Duration,
+    /// Number of missed heartbeats after which the client warns the user.
+    pub warning_count: u8,
+    /// Number of missed heartbeats after which the client reconnects.
+    pub reconnect_count: u8,
+    /// Disconnects the clients from which nothing was received for this many intervals.
+    ///
+    /// The clients don't reply to the heartbeats: an idle client whose display is not updated may send
+    /// nothing for a long time, so this should be well above the idle periods of the clients. `None`
+    /// doesn't monitor the clients.
+    pub max_silent_intervals: Option<u32>,
+}
+
+impl Default for HeartbeatOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            warning_count: 3,
+            reconnect_count: 6,
+            max_silent_intervals: None,
+        }
+    }
+}
+
+impl HeartbeatOptions {
+    /// Returns the interval between two heartbeats, as sent to the client.
+    pub(crate) fn interval(&self) -> Duration {
+        Duration::from_secs(u64::from(self.period()))
+    }
+
+    fn period(&self) -> u8 {
+        u8::try_from(self.interval.as_secs()).unwrap_or(u8::MAX).max(1)
+    }
+
+    pub(crate) fn pdu(&self) -> HeartbeatPdu {
+        HeartbeatPdu {
+            period: self.period(),
+            count1: self.warning_count,
+            count2: self.reconnect_count,
+        }
+    }
+
+    /// Returns the duration after which a silent client is disconnected, if monitored.
+    pub(crate) fn max_silence(&self) -> Option<Duration> {
+        self.max_silent_intervals
+            .map(|intervals| self.interval().saturating_mul(intervals))
+    }
+}
+
+/// Error closing the connection of a client from which nothing was received for too long, see
+/// [`HeartbeatOptions::max_silent_intervals`].
+///
+/// Given to [`RdpServerEventHandler::disconnected`](crate::RdpServerEventHandler::disconnected), from
+/// which it can be downcast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientUnresponsive {
+    /// Time elapsed since data was last received from the client.
+    pub silence: Duration,
+}
+
+impl fmt::Display for ClientUnresponsive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "nothing received from the client for {:?}", self.silence)
+    }
+}
+
+impl std::error::Error for ClientUnresponsive {}
+
+#[cfg(feature = "__bench")]
+pub(crate) mod bench {
+    use super::*;
+
+    pub fn pdu(options: &HeartbeatOptions) -> HeartbeatPdu {
+        options.pdu()
+    }
+
+    pub fn max_silence(options: &HeartbeatOptions) -> Option<Duration> {
+        options.max_silence()
+    }
+}
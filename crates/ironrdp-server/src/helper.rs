@@ -7,6 +7,7 @@ use anyhow::Context;
 use rustls_pemfile::{certs, pkcs8_private_keys};
 use tokio_rustls::rustls::pki_types::pem::PemObject;
 use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::sign::CertifiedKey;
 use tokio_rustls::rustls::{self};
 use tokio_rustls::TlsAcceptor;
 
@@ -43,18 +44,7 @@ impl TlsIdentityCtx {
                 .map(PrivateKeyDer::from)?
         };
 
-        let pub_key = {
-            use x509_cert::der::Decode as _;
-
-            let cert = certs.first().ok_or_else(|| std::io::Error::other("invalid cert"))?;
-            let cert = x509_cert::Certificate::from_der(cert).map_err(std::io::Error::other)?;
-            cert.tbs_certificate
-                .subject_public_key_info
-                .subject_public_key
-                .as_bytes()
-                .ok_or_else(|| std::io::Error::other("subject public key BIT STRING is not aligned"))?
-                .to_owned()
-        };
+        let pub_key = crate::tls::public_key(certs.first().context("no server cert")?)?;
 
         Ok(Self {
             certs,
@@ -74,4 +64,16 @@ impl TlsIdentityCtx {
 
         Ok(TlsAcceptor::from(Arc::new(server_config)))
     }
+
+    /// Returns the certificate and key, e.g. to add them to the [`TlsCertificates`](crate::TlsCertificates)
+    /// of the server.
+    pub fn make_certified_key(&self) -> anyhow::Result<Arc<CertifiedKey>> {
+        let provider = Arc::clone(rustls::ServerConfig::builder().crypto_provider());
+        let key = provider
+            .key_provider
+            .load_private_key(self.priv_key.clone_key())
+            .context("bad key")?;
+
+        Ok(Arc::new(CertifiedKey::new(self.certs.clone(), key)))
+    }
 }
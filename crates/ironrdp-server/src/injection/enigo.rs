@@ -0,0 +1,210 @@
+//! Injection with SendInput on Windows and CGEvent on macOS.
+
+use anyhow::{anyhow, Result};
+use enigo::{Axis, Coordinate, Direction, Enigo, Key, Keyboard as _, Mouse as _, Settings};
+
+use super::{Backend, Button};
+
+pub(super) struct EnigoBackend {
+    enigo: Enigo,
+}
+
+impl EnigoBackend {
+    pub(super) fn new() -> Result<Self> {
+        let enigo = Enigo::new(&Settings::default()).map_err(|e| anyhow!("input injection: {e}"))?;
+
+        Ok(Self { enigo })
+    }
+}
+
+fn direction(pressed: bool) -> Direction {
+    if pressed {
+        Direction::Press
+    } else {
+        Direction::Release
+    }
+}
+
+impl Backend for EnigoBackend {
+    fn key(&mut self, code: u8, extended: bool, pressed: bool) -> Result<()> {
+        let Some(keycode) = platform_keycode(code, extended) else {
+            debug!(code, extended, "No key code for the scancode");
+            return Ok(());
+        };
+
+        self.enigo
+            .raw(keycode, direction(pressed))
+            .map_err(|e| anyhow!("inject key: {e}"))
+    }
+
+    fn unicode(&mut self, character: char, pressed: bool) -> Result<()> {
+        self.enigo
+            .key(Key::Unicode(character), direction(pressed))
+            .map_err(|e| anyhow!("inject character: {e}"))
+    }
+
+    fn move_to(&mut self, x: u16, y: u16) -> Result<()> {
+        self.enigo
+            .move_mouse(i32::from(x), i32::from(y), Coordinate::Abs)
+            .map_err(|e| anyhow!("inject pointer position: {e}"))
+    }
+
+    fn move_by(&mut self, x: i32, y: i32) -> Result<()> {
+        self.enigo
+            .move_mouse(x, y, Coordinate::Rel)
+            .map_err(|e| anyhow!("inject mouse motion: {e}"))
+    }
+
+    fn button(&mut self, button: Button, pressed: bool) -> Result<()> {
+        let button = match button {
+            Button::Left => enigo::Button::Left,
+            Button::Right => enigo::Button::Right,
+            Button::Middle => enigo::Button::Middle,
+            Button::Back => enigo::Button::Back,
+            Button::Forward => enigo::Button::Forward,
+        };
+
+        self.enigo
+            .button(button, direction(pressed))
+            .map_err(|e| anyhow!("inject button: {e}"))
+    }
+
+    fn scroll(&mut self, _units: i32, notches: i32, horizontal: bool) -> Result<()> {
+        if notches == 0 {
+            return Ok(());
+        }
+
+        // Unlike RDP, positive lengths scroll downward, and rightward.
+        let (length, axis) = if horizontal {
+            (notches, Axis::Horizontal)
+        } else {
+            (-notches, Axis::Vertical)
+        };
+
+        self.enigo
+            .scroll(length, axis)
+            .map_err(|e| anyhow!("inject wheel: {e}"))
+    }
+}
+
+/// Returns the scancode, with the `0xE0` prefix of the extended keys, injected with `KEYEVENTF_SCANCODE`.
+#[cfg(windows)]
+fn platform_keycode(code: u8, extended: bool) -> Option<u16> {
+    Some(if extended {
+        0xE000 | u16::from(code)
+    } else {
+        u16::from(code)
+    })
+}
+
+/// Returns the virtual key code of a scancode of the set 1, for the ANSI and ISO layouts.
+#[cfg(target_os = "macos")]
+fn platform_keycode(code: u8, extended: bool) -> Option<u16> {
+    let keycode = match (extended, code) {
+        (false, 0x01) => 0x35, // Escape
+        (false, 0x02) => 0x12, // 1
+        (false, 0x03) => 0x13, // 2
+        (false, 0x04) => 0x14, // 3
+        (false, 0x05) => 0x15, // 4
+        (false, 0x06) => 0x17, // 5
+        (false, 0x07) => 0x16, // 6
+        (false, 0x08) => 0x1A, // 7
+        (false, 0x09) => 0x1C, // 8
+        (false, 0x0A) => 0x19, // 9
+        (false, 0x0B) => 0x1D, // 0
+        (false, 0x0C) => 0x1B, // Minus
+        (false, 0x0D) => 0x18, // Equal
+        (false, 0x0E) => 0x33, // Backspace
+        (false, 0x0F) => 0x30, // Tab
+        (false, 0x10) => 0x0C, // Q
+        (false, 0x11) => 0x0D, // W
+        (false, 0x12) => 0x0E, // E
+        (false, 0x13) => 0x0F, // R
+        (false, 0x14) => 0x11, // T
+        (false, 0x15) => 0x10, // Y
+        (false, 0x16) => 0x20, // U
+        (false, 0x17) => 0x22, // I
+        (false, 0x18) => 0x1F, // O
+        (false, 0x19) => 0x23, // P
+        (false, 0x1A) => 0x21, // Left bracket
+        (false, 0x1B) => 0x1E, // Right bracket
+        (false, 0x1C) => 0x24, // Return
+        (false, 0x1D) => 0x3B, // Left control
+        (false, 0x1E) => 0x00, // A
+        (false, 0x1F) => 0x01, // S
+        (false, 0x20) => 0x02, // D
+        (false, 0x21) => 0x03, // F
+        (false, 0x22) => 0x05, // G
+        (false, 0x23) => 0x04, // H
+        (false, 0x24) => 0x26, // J
+        (false, 0x25) => 0x28, // K
+        (false, 0x26) => 0x25, // L
+        (false, 0x27) => 0x29, // Semicolon
+        (false, 0x28) => 0x27, // Quote
+        (false, 0x29) => 0x32, // Grave
+        (false, 0x2A) => 0x38, // Left shift
+        (false, 0x2B) => 0x2A, // Backslash
+        (false, 0x2C) => 0x06, // Z
+        (false, 0x2D) => 0x07, // X
+        (false, 0x2E) => 0x08, // C
+        (false, 0x2F) => 0x09, // V
+        (false, 0x30) => 0x0B, // B
+        (false, 0x31) => 0x2D, // N
+        (false, 0x32) => 0x2E, // M
+        (false, 0x33) => 0x2B, // Comma
+        (false, 0x34) => 0x2F, // Period
+        (false, 0x35) => 0x2C, // Slash
+        (false, 0x36) => 0x3C, // Right shift
+        (false, 0x37) => 0x43, // Keypad multiply
+        (false, 0x38) => 0x3A, // Left option
+        (false, 0x39) => 0x31, // Space
+        (false, 0x3A) => 0x39, // Caps lock
+        (false, 0x3B) => 0x7A, // F1
+        (false, 0x3C) => 0x78, // F2
+        (false, 0x3D) => 0x63, // F3
+        (false, 0x3E) => 0x76, // F4
+        (false, 0x3F) => 0x60, // F5
+        (false, 0x40) => 0x61, // F6
+        (false, 0x41) => 0x62, // F7
+        (false, 0x42) => 0x64, // F8
+        (false, 0x43) => 0x65, // F9
+        (false, 0x44) => 0x6D, // F10
+        (false, 0x45) => 0x47, // Num lock, as keypad clear
+        (false, 0x47) => 0x59, // Keypad 7
+        (false, 0x48) => 0x5B, // Keypad 8
+        (false, 0x49) => 0x5C, // Keypad 9
+        (false, 0x4A) => 0x4E, // Keypad minus
+        (false, 0x4B) => 0x56, // Keypad 4
+        (false, 0x4C) => 0x57, // Keypad 5
+        (false, 0x4D) => 0x58, // Keypad 6
+        (false, 0x4E) => 0x45, // Keypad plus
+        (false, 0x4F) => 0x53, // Keypad 1
+        (false, 0x50) => 0x54, // Keypad 2
+        (false, 0x51) => 0x55, // Keypad 3
+        (false, 0x52) => 0x52, // Keypad 0
+        (false, 0x53) => 0x41, // Keypad decimal
+        (false, 0x56) => 0x0A, // ISO section
+        (false, 0x57) => 0x67, // F11
+        (false, 0x58) => 0x6F, // F12
+        (true, 0x1C) => 0x4C,  // Keypad enter
+        (true, 0x1D) => 0x3E,  // Right control
+        (true, 0x35) => 0x4B,  // Keypad divide
+        (true, 0x38) => 0x3D,  // Right option
+        (true, 0x47) => 0x73,  // Home
+        (true, 0x48) => 0x7E,  // Up arrow
+        (true, 0x49) => 0x74,  // Page up
+        (true, 0x4B) => 0x7B,  // Left arrow
+        (true, 0x4D) => 0x7C,  // Right arrow
+        (true, 0x4F) => 0x77,  // End
+        (true, 0x50) => 0x7D,  // Down arrow
+        (true, 0x51) => 0x79,  // Page down
+        (true, 0x52) => 0x72,  // Insert, as help
+        (true, 0x53) => 0x75,  // Forward delete
+        (true, 0x5B) => 0x37,  // Left command
+        (true, 0x5C) => 0x36,  // Right command
+        (true, 0x5D) => 0x6E,  // Context menu
+        _ => return None,
+    };
+
+    Some(keycode)
+}
@@ -0,0 +1,283 @@
+//! Input handler injecting the input events of the client into the local machine.
+//!
+//! The events are injected with the native APIs of the platform: a uinput virtual keyboard and mouse on Linux,
+//! and SendInput on Windows or CGEvent on macOS, through the `enigo` crate. Paired with the
+//! [`ScreenCaptureDisplay`](crate::ScreenCaptureDisplay), the server remotely controls the local machine.
+
+#[cfg(any(windows, target_os = "macos"))]
+mod enigo;
+#[cfg(target_os = "linux")]
+mod uinput;
+
+use std::collections::HashSet;
+use std::sync::mpsc;
+use std::thread;
+
+use anyhow::{Context as _, Result};
+
+use crate::{DesktopSize, KeyboardEvent, MouseEvent, RdpServerInputHandler};
+
+/// Wheel rotation units of a wheel notch.
+const WHEEL_DELTA: i32 = 120;
+
+/// Input handler injecting the keyboard and mouse events of the client into the local machine.
+///
+/// The events are injected on a dedicated thread, and the keys and buttons still pressed are released once the
+/// injector is dropped, e.g. when the client disconnects.
+pub struct InputInjector {
+    sender: mpsc::Sender<Input>,
+}
+
+enum Input {
+    Keyboard(KeyboardEvent),
+    Mouse(MouseEvent),
+}
+
+/// Injects the events into the local machine.
+trait Backend {
+    fn key(&mut self, code: u8, extended: bool, pressed: bool) -> Result<()>;
+
+    fn unicode(&mut self, character: char, pressed: bool) -> Result<()>;
+
+    fn move_to(&mut self, x: u16, y: u16) -> Result<()>;
+
+    fn move_by(&mut self, x: i32, y: i32) -> Result<()>;
+
+    fn button(&mut self, button: Button, pressed: bool) -> Result<()>;
+
+    /// Scrolls by the given number of rotation units, positive upward or rightward.
+    fn scroll(&mut self, units: i32, notches: i32, horizontal: bool) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Button {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+}
+
+impl InputInjector {
+    /// Creates an injector for a desktop of the given size, in which the pointer positions are.
+    pub fn new(size: DesktopSize) -> Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let (ready_tx, ready_rx) = mpsc::sync_channel(1);
+
+        thread::Builder::new()
+            .name("input-injection".to_owned())
+            .spawn(move || {
+                let backend = match create_backend(size) {
+                    Ok(backend) => {
+                        let _ = ready_tx.send(Ok(()));
+                        backend
+                    }
+                    Err(error) => {
+                        let _ = ready_tx.send(Err(error));
+                        return;
+                    }
+                };
+
+                Injection::new(backend).run(&receiver);
+
+                debug!("Input injection stopped");
+            })
+            .context("spawn the input injection thread")?;
+
+        ready_rx.recv().context("the input injection thread stopped")??;
+
+        Ok(Self { sender })
+    }
+
+    fn send(&self, input: Input) {
+        if self.sender.send(input).is_err() {
+            warn!("Input injection stopped, dropping the input event");
+        }
+    }
+}
+
+impl RdpServerInputHandler for InputInjector {
+    fn keyboard(&mut self, event: KeyboardEvent) {
+        self.send(Input::Keyboard(event));
+    }
+
+    fn mouse(&mut self, event: MouseEvent) {
+        self.send(Input::Mouse(event));
+    }
+}
+
+impl core::fmt::Debug for InputInjector {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("InputInjector").finish_non_exhaustive()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn create_backend(size: DesktopSize) -> Result<Box<dyn Backend>> {
+    Ok(Box::new(uinput::UinputBackend::new(size)?))
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn create_backend(_size: DesktopSize) -> Result<Box<dyn Backend>> {
+    Ok(Box::new(enigo::EnigoBackend::new()?))
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+fn create_backend(_size: DesktopSize) -> Result<Box<dyn Backend>> {
+    Err(anyhow::anyhow!("input injection is not supported on this platform"))
+}
+
+/// State of the injection, tracking what is pressed to release it in the end.
+struct Injection {
+    backend: Box<dyn Backend>,
+    pressed_keys: HashSet<(u8, bool)>,
+    pressed_buttons: HashSet<Button>,
+    /// Leading UTF-16 surrogate of the next unicode key.
+    high_surrogate: Option<u16>,
+    /// Rotation units not scrolled yet, less than a notch, of the vertical and horizontal wheels.
+    wheel_units: i32,
+    hwheel_units: i32,
+}
+
+impl Injection {
+    fn new(backend: Box<dyn Backend>) -> Self {
+        Self {
+            backend,
+            pressed_keys: HashSet::new(),
+            pressed_buttons: HashSet::new(),
+            high_surrogate: None,
+            wheel_units: 0,
+            hwheel_units: 0,
+        }
+    }
+
+    fn run(mut self, receiver: &mpsc::Receiver<Input>) {
+        for input in receiver {
+            let result = match input {
+                Input::Keyboard(event) => self.keyboard(event),
+                Input::Mouse(event) => self.mouse(event),
+            };
+
+            if let Err(error) = result {
+                warn!(%error, "Failed to inject an input event");
+            }
+        }
+
+        for (code, extended) in core::mem::take(&mut self.pressed_keys) {
+            if let Err(error) = self.backend.key(code, extended, false) {
+                warn!(%error, code, extended, "Failed to release a key");
+            }
+        }
+
+        for button in core::mem::take(&mut self.pressed_buttons) {
+            if let Err(error) = self.backend.button(button, false) {
+                warn!(%error, ?button, "Failed to release a mouse button");
+            }
+        }
+    }
+
+    fn keyboard(&mut self, event: KeyboardEvent) -> Result<()> {
+        match event {
+            KeyboardEvent::Pressed { code, extended } => {
+                self.pressed_keys.insert((code, extended));
+                self.backend.key(code, extended, true)
+            }
+            KeyboardEvent::Released { code, extended } => {
+                self.pressed_keys.remove(&(code, extended));
+                self.backend.key(code, extended, false)
+            }
+            KeyboardEvent::UnicodePressed(unit) => self.unicode_pressed(unit),
+            KeyboardEvent::UnicodeReleased(unit) => match char::from_u32(u32::from(unit)) {
+                Some(character) => self.backend.unicode(character, false),
+                // The characters outside of the BMP are released along with their press.
+                None => Ok(()),
+            },
+            KeyboardEvent::Sync { .. } => {
+                // The lock keys of the local machine are left as is.
+                Ok(())
+            }
+        }
+    }
+
+    /// Presses the character of a UTF-16 code unit, the characters outside of the BMP being typed once their
+    /// trailing surrogate is pressed.
+    fn unicode_pressed(&mut self, unit: u16) -> Result<()> {
+        if let Some(character) = char::from_u32(u32::from(unit)) {
+            self.high_surrogate = None;
+            return self.backend.unicode(character, true);
+        }
+
+        if (0xD800..0xDC00).contains(&unit) {
+            self.high_surrogate = Some(unit);
+            return Ok(());
+        }
+
+        let character = self
+            .high_surrogate
+            .take()
+            .and_then(|high| char::decode_utf16([high, unit]).next()?.ok());
+
+        match character {
+            Some(character) => {
+                self.backend.unicode(character, true)?;
+                self.backend.unicode(character, false)
+            }
+            None => {
+                debug!(unit, "Unpaired UTF-16 surrogate");
+                Ok(())
+            }
+        }
+    }
+
+    fn mouse(&mut self, event: MouseEvent) -> Result<()> {
+        match event {
+            MouseEvent::Move { x, y } => self.backend.move_to(x, y),
+            MouseEvent::RelMove { x, y } => self.backend.move_by(x, y),
+            MouseEvent::LeftPressed => self.button(Button::Left, true),
+            MouseEvent::LeftReleased => self.button(Button::Left, false),
+            MouseEvent::RightPressed => self.button(Button::Right, true),
+            MouseEvent::RightReleased => self.button(Button::Right, false),
+            MouseEvent::MiddlePressed => self.button(Button::Middle, true),
+            MouseEvent::MiddleReleased => self.button(Button::Middle, false),
+            MouseEvent::Button4Pressed => self.button(Button::Back, true),
+            MouseEvent::Button4Released => self.button(Button::Back, false),
+            MouseEvent::Button5Pressed => self.button(Button::Forward, true),
+            MouseEvent::Button5Released => self.button(Button::Forward, false),
+            MouseEvent::VerticalScroll { value } => self.scroll(i32::from(value), false),
+            MouseEvent::Scroll { x, y } => {
+                if x != 0 {
+                    self.scroll(x, true)?;
+                }
+                if y != 0 {
+                    self.scroll(y, false)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn button(&mut self, button: Button, pressed: bool) -> Result<()> {
+        if pressed {
+            self.pressed_buttons.insert(button);
+        } else {
+            self.pressed_buttons.remove(&button);
+        }
+
+        self.backend.button(button, pressed)
+    }
+
+    fn scroll(&mut self, units: i32, horizontal: bool) -> Result<()> {
+        // The high resolution wheels send fractions of notches, accumulated until a whole notch is scrolled.
+        let pending = if horizontal {
+            &mut self.hwheel_units
+        } else {
+            &mut self.wheel_units
+        };
+
+        let total = *pending + units;
+        let notches = total / WHEEL_DELTA;
+        *pending = total % WHEEL_DELTA;
+
+        self.backend.scroll(units, notches, horizontal)
+    }
+}
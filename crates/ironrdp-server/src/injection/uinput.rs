@@ -0,0 +1,195 @@
+//! Injection with uinput virtual devices, which requires the write access to `/dev/uinput`.
+
+use anyhow::{Context as _, Result};
+use evdev::uinput::VirtualDevice;
+use evdev::{
+    AbsInfo, AbsoluteAxisCode, AttributeSet, EventType, InputEvent, KeyCode, RelativeAxisCode, UinputAbsSetup,
+};
+
+use super::{Backend, Button};
+use crate::DesktopSize;
+
+/// Three virtual devices: a keyboard, a pointer with absolute positions, and a mouse for the relative moves.
+pub(super) struct UinputBackend {
+    keyboard: VirtualDevice,
+    pointer: VirtualDevice,
+    mouse: VirtualDevice,
+}
+
+impl UinputBackend {
+    pub(super) fn new(size: DesktopSize) -> Result<Self> {
+        let keys: AttributeSet<KeyCode> = (1..=KEYBOARD_MAX_KEY).map(KeyCode::new).collect();
+
+        let keyboard = VirtualDevice::builder()
+            .context("open uinput")?
+            .name("IronRDP keyboard")
+            .with_keys(&keys)
+            .context("keyboard keys")?
+            .build()
+            .context("create the virtual keyboard")?;
+
+        let buttons: AttributeSet<KeyCode> = [
+            KeyCode::BTN_LEFT,
+            KeyCode::BTN_RIGHT,
+            KeyCode::BTN_MIDDLE,
+            KeyCode::BTN_SIDE,
+            KeyCode::BTN_EXTRA,
+        ]
+        .into_iter()
+        .collect();
+
+        let wheels: AttributeSet<RelativeAxisCode> = [
+            RelativeAxisCode::REL_WHEEL,
+            RelativeAxisCode::REL_HWHEEL,
+            RelativeAxisCode::REL_WHEEL_HI_RES,
+            RelativeAxisCode::REL_HWHEEL_HI_RES,
+        ]
+        .into_iter()
+        .collect();
+
+        let x_axis = UinputAbsSetup::new(
+            AbsoluteAxisCode::ABS_X,
+            AbsInfo::new(0, 0, i32::from(size.width.saturating_sub(1)), 0, 0, 0),
+        );
+        let y_axis = UinputAbsSetup::new(
+            AbsoluteAxisCode::ABS_Y,
+            AbsInfo::new(0, 0, i32::from(size.height.saturating_sub(1)), 0, 0, 0),
+        );
+
+        let pointer = VirtualDevice::builder()
+            .context("open uinput")?
+            .name("IronRDP pointer")
+            .with_keys(&buttons)
+            .context("pointer buttons")?
+            .with_relative_axes(&wheels)
+            .context("pointer wheels")?
+            .with_absolute_axis(&x_axis)
+            .context("pointer X axis")?
+            .with_absolute_axis(&y_axis)
+            .context("pointer Y axis")?
+            .build()
+            .context("create the virtual pointer")?;
+
+        let axes: AttributeSet<RelativeAxisCode> =
+            [RelativeAxisCode::REL_X, RelativeAxisCode::REL_Y].into_iter().collect();
+
+        let mouse = VirtualDevice::builder()
+            .context("open uinput")?
+            .name("IronRDP mouse")
+            .with_keys(&buttons)
+            .context("mouse buttons")?
+            .with_relative_axes(&axes)
+            .context("mouse axes")?
+            .build()
+            .context("create the virtual mouse")?;
+
+        Ok(Self {
+            keyboard,
+            pointer,
+            mouse,
+        })
+    }
+}
+
+impl Backend for UinputBackend {
+    fn key(&mut self, code: u8, extended: bool, pressed: bool) -> Result<()> {
+        let Some(key) = scancode_to_evdev(code, extended) else {
+            debug!(code, extended, "No evdev key for the scancode");
+            return Ok(());
+        };
+
+        self.keyboard
+            .emit(&[InputEvent::new(EventType::KEY.0, key, i32::from(pressed))])
+            .context("emit key")
+    }
+
+    fn unicode(&mut self, character: char, _pressed: bool) -> Result<()> {
+        // A virtual keyboard only has the keys of the keyboard layout.
+        debug!(?character, "Unicode input not supported with uinput");
+        Ok(())
+    }
+
+    fn move_to(&mut self, x: u16, y: u16) -> Result<()> {
+        self.pointer
+            .emit(&[
+                InputEvent::new(EventType::ABSOLUTE.0, AbsoluteAxisCode::ABS_X.0, i32::from(x)),
+                InputEvent::new(EventType::ABSOLUTE.0, AbsoluteAxisCode::ABS_Y.0, i32::from(y)),
+            ])
+            .context("emit pointer position")
+    }
+
+    fn move_by(&mut self, x: i32, y: i32) -> Result<()> {
+        self.mouse
+            .emit(&[
+                InputEvent::new(EventType::RELATIVE.0, RelativeAxisCode::REL_X.0, x),
+                InputEvent::new(EventType::RELATIVE.0, RelativeAxisCode::REL_Y.0, y),
+            ])
+            .context("emit mouse motion")
+    }
+
+    fn button(&mut self, button: Button, pressed: bool) -> Result<()> {
+        let code = match button {
+            Button::Left => KeyCode::BTN_LEFT,
+            Button::Right => KeyCode::BTN_RIGHT,
+            Button::Middle => KeyCode::BTN_MIDDLE,
+            Button::Back => KeyCode::BTN_SIDE,
+            Button::Forward => KeyCode::BTN_EXTRA,
+        };
+
+        self.pointer
+            .emit(&[InputEvent::new(EventType::KEY.0, code.0, i32::from(pressed))])
+            .context("emit button")
+    }
+
+    fn scroll(&mut self, units: i32, notches: i32, horizontal: bool) -> Result<()> {
+        let (axis, hi_res_axis) = if horizontal {
+            (RelativeAxisCode::REL_HWHEEL, RelativeAxisCode::REL_HWHEEL_HI_RES)
+        } else {
+            (RelativeAxisCode::REL_WHEEL, RelativeAxisCode::REL_WHEEL_HI_RES)
+        };
+
+        // The high resolution axis has the same 120 units per notch as RDP, and the same direction.
+        let mut events = vec![InputEvent::new(EventType::RELATIVE.0, hi_res_axis.0, units)];
+        if notches != 0 {
+            events.push(InputEvent::new(EventType::RELATIVE.0, axis.0, notches));
+        }
+
+        self.pointer.emit(&events).context("emit wheel")
+    }
+}
+
+/// Highest evdev key code of the keyboard.
+const KEYBOARD_MAX_KEY: u16 = 0x7F;
+
+/// Returns the evdev key code of a scancode of the set 1.
+///
+/// The evdev key codes are the scancodes for the keys not extended.
+fn scancode_to_evdev(code: u8, extended: bool) -> Option<u16> {
+    if !extended {
+        return (1..=0x58).contains(&code).then_some(u16::from(code));
+    }
+
+    let key = match code {
+        0x1C => KeyCode::KEY_KPENTER,
+        0x1D => KeyCode::KEY_RIGHTCTRL,
+        0x35 => KeyCode::KEY_KPSLASH,
+        0x37 => KeyCode::KEY_SYSRQ,
+        0x38 => KeyCode::KEY_RIGHTALT,
+        0x47 => KeyCode::KEY_HOME,
+        0x48 => KeyCode::KEY_UP,
+        0x49 => KeyCode::KEY_PAGEUP,
+        0x4B => KeyCode::KEY_LEFT,
+        0x4D => KeyCode::KEY_RIGHT,
+        0x4F => KeyCode::KEY_END,
+        0x50 => KeyCode::KEY_DOWN,
+        0x51 => KeyCode::KEY_PAGEDOWN,
+        0x52 => KeyCode::KEY_INSERT,
+        0x53 => KeyCode::KEY_DELETE,
+        0x5B => KeyCode::KEY_LEFTMETA,
+        0x5C => KeyCode::KEY_RIGHTMETA,
+        0x5D => KeyCode::KEY_COMPOSE,
+        _ => return None,
+    };
+
+    Some(key.0)
+}
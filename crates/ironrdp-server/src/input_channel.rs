@@ -0,0 +1,183 @@
+//! Queued input events.
+//!
+//! The input handler is called from the I/O task of the connection, a handler with heavy processing
+//! delays the display updates and the other messages. Queueing the events to a [`InputEventReceiver`]
+//! lets them be processed by another task, while the connection stops reading from the client when the
+//! queue is full.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+
+use crate::{KeyboardEvent, MouseEvent, RdpServerInputHandler};
+
+/// Input event received from a client.
+#[derive(Debug)]
+pub enum InputEvent {
+    Keyboard(KeyboardEvent),
+    Mouse(MouseEvent),
+}
+
+#[derive(Default)]
+struct State {
+    events: VecDeque<InputEvent>,
+    closed: bool,
+}
+
+impl State {
+    fn push(&mut self, event: InputEvent) {
+        push_coalesced(&mut self.events, event);
+    }
+}
+
+/// Queues an event, merging the mouse moves with the previous move not processed yet.
+pub(crate) fn push_coalesced(events: &mut VecDeque<InputEvent>, event: InputEvent) {
+    match (events.back_mut(), event) {
+        (
+            Some(InputEvent::Mouse(MouseEvent::Move { x, y })),
+            InputEvent::Mouse(MouseEvent::Move { x: to_x, y: to_y }),
+        ) => {
+            *x = to_x;
+            *y = to_y;
+        }
+        (
+            Some(InputEvent::Mouse(MouseEvent::RelMove { x, y })),
+            InputEvent::Mouse(MouseEvent::RelMove { x: dx, y: dy }),
+        ) => {
+            *x = x.saturating_add(dx);
+            *y = y.saturating_add(dy);
+        }
+        (_, event) => events.push_back(event),
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    capacity: usize,
+    /// Notified when an event is queued, or the queue is closed.
+    queued: Notify,
+    /// Notified when an event is processed.
+    processed: Notify,
+}
+
+/// Creates a queue of input events holding up to `capacity` events.
+///
+/// The [`InputEventQueue`] is the input handler of the server, the events are processed from the
+/// [`InputEventReceiver`].
+pub fn input_channel(capacity: usize) -> (InputEventQueue, InputEventReceiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State::default()),
+        capacity: capacity.max(1),
+        queued: Notify::new(),
+        processed: Notify::new(),
+    });
+
+    (
+        InputEventQueue {
+            shared: Arc::clone(&shared),
+        },
+        InputEventReceiver { shared },
+    )
+}
+
+/// Input handler queueing the events, see [`input_channel`].
+///
+/// When the queue is full, the server waits for the events to be processed before reading the next
+/// input events of the client. The consecutive mouse moves are merged in the queue.
+pub struct InputEventQueue {
+    shared: Arc<Shared>,
+}
+
+impl InputEventQueue {
+    fn push(&self, event: InputEvent) {
+        self.shared.state.lock().expect("poisoned").push(event);
+        self.shared.queued.notify_one();
+    }
+}
+
+impl RdpServerInputHandler for InputEventQueue {
+    fn keyboard(&mut self, event: KeyboardEvent) {
+        self.push(InputEvent::Keyboard(event));
+    }
+
+    fn mouse(&mut self, event: MouseEvent) {
+        self.push(InputEvent::Mouse(event));
+    }
+
+    async fn ready(&mut self) {
+        loop {
+            {
+                let state = self.shared.state.lock().expect("poisoned");
+                if state.closed || state.events.len() < self.shared.capacity {
+                    return;
+                }
+            }
+
+            // A notification sent meanwhile is kept by `notify_one`, so it is not missed.
+            self.shared.processed.notified().await;
+        }
+    }
+}
+
+impl Drop for InputEventQueue {
+    fn drop(&mut self) {
+        self.shared.state.lock().expect("poisoned").closed = true;
+        self.shared.queued.notify_one();
+    }
+}
+
+impl core::fmt::Debug for InputEventQueue {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("InputEventQueue")
+            .field("capacity", &self.shared.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Receives the input events queued by an [`InputEventQueue`], see [`input_channel`].
+pub struct InputEventReceiver {
+    shared: Arc<Shared>,
+}
+
+impl InputEventReceiver {
+    /// Returns the next input event, or `None` once the queue is dropped and all its events are received.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe, no event is lost if it is used in a `tokio::select!` statement.
+    pub async fn recv(&mut self) -> Option<InputEvent> {
+        loop {
+            let next = {
+                let mut state = self.shared.state.lock().expect("poisoned");
+                let next = state.events.pop_front();
+                if next.is_none() && state.closed {
+                    return None;
+                }
+                next
+            };
+
+            if let Some(event) = next {
+                self.shared.processed.notify_one();
+                return Some(event);
+            }
+
+            // A notification sent meanwhile is kept by `notify_one`, so it is not missed.
+            self.shared.queued.notified().await;
+        }
+    }
+}
+
+impl Drop for InputEventReceiver {
+    fn drop(&mut self) {
+        // Nothing processes the events anymore, the server must not wait for room in the queue.
+        self.shared.state.lock().expect("poisoned").closed = true;
+        self.shared.processed.notify_one();
+    }
+}
+
+impl core::fmt::Debug for InputEventReceiver {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("InputEventReceiver").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,111 @@
+use core::num::NonZeroU32;
+use core::time::Duration;
+
+use tokio::time::Instant;
+
+/// Limits of the input events of a connection, protecting the input handler from floods of events.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputLimits {
+    /// Merges the consecutive mouse moves received together, the handler getting only the last
+    /// position (or the sum of the relative moves).
+    pub coalesce_mouse_moves: bool,
+    /// Maximum rate of the input events given to the handler, with bursts of up to a second of events.
+    ///
+    /// Over the limit, the mouse moves are dropped, and the other events are delayed, the connection
+    /// not being read meanwhile. `None` disables the limit.
+    pub max_events_per_second: Option<NonZeroU32>,
+}
+
+/// Decision for an input event, see [`InputLimiter::admit`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Admission {
+    Now,
+    After(Duration),
+    Drop,
+}
+
+/// Token bucket applying the [`InputLimits`] of a connection.
+#[derive(Debug)]
+pub(crate) struct InputLimiter {
+    limits: InputLimits,
+    tokens: f64,
+    refilled: Instant,
+    dropped: u64,
+}
+
+impl InputLimiter {
+    pub(crate) fn new(limits: InputLimits) -> Self {
+        Self {
+            limits,
+            tokens: limits.max_events_per_second.map_or(0.0, |rate| f64::from(rate.get())),
+            refilled: Instant::now(),
+            dropped: 0,
+        }
+    }
+
+    pub(crate) fn limits(&self) -> InputLimits {
+        self.limits
+    }
+
+    /// Takes a token for an event, which can be dropped over the limit if `droppable`.
+    pub(crate) fn admit(&mut self, droppable: bool) -> Admission {
+        let Some(rate) = self.limits.max_events_per_second else {
+            return Admission::Now;
+        };
+        let rate = f64::from(rate.get());
+
+        let now = Instant::now();
+        self.tokens = (self.tokens + now.duration_since(self.refilled).as_secs_f64() * rate).min(rate);
+        self.refilled = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Admission::Now;
+        }
+
+        if droppable {
+            self.dropped += 1;
+            if self.dropped.is_power_of_two() {
+                warn!(
+                    dropped = self.dropped,
+                    "Input events over the limit, dropping mouse moves"
+                );
+            }
+            return Admission::Drop;
+        }
+
+        // The token is borrowed from the next refill.
+        let wait = (1.0 - self.tokens) / rate;
+        self.tokens -= 1.0;
+        Admission::After(Duration::from_secs_f64(wait))
+    }
+}
+
+#[cfg(feature = "__bench")]
+pub(crate) mod bench {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Admission {
+        Now,
+        After(Duration),
+        Drop,
+    }
+
+    #[derive(Debug)]
+    pub struct InputLimiter(super::InputLimiter);
+
+    impl InputLimiter {
+        pub fn new(limits: InputLimits) -> Self {
+            Self(super::InputLimiter::new(limits))
+        }
+
+        pub fn admit(&mut self, droppable: bool) -> Admission {
+            match self.0.admit(droppable) {
+                super::Admission::Now => Admission::Now,
+                super::Admission::After(delay) => Admission::After(delay),
+                super::Admission::Drop => Admission::Drop,
+            }
+        }
+    }
+}
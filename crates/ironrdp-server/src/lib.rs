@@ -7,32 +7,119 @@ pub use {tokio, tokio_rustls};
 #[macro_use]
 extern crate tracing;
 
+mod audio_input;
+mod audit;
 mod builder;
 mod capabilities;
+#[cfg(feature = "capture")]
+mod capture;
 mod clipboard;
+mod compression;
+mod custom_channel;
 mod display;
+mod display_channel;
+mod drive;
 mod encoder;
+mod error;
+mod flow_control;
+mod gfx;
 mod handler;
+mod heartbeat;
 #[cfg(feature = "helper")]
 mod helper;
+#[cfg(feature = "injection")]
+mod injection;
+mod input_channel;
+mod input_limit;
+mod lifecycle;
+mod listener;
+mod quality;
+mod rail;
+mod reconnect;
+mod recording;
 mod server;
+mod session;
+mod shadow;
 mod sound;
+mod stats;
+mod tls;
+#[cfg(feature = "websocket")]
+mod ws;
 
+pub use audio_input::*;
+pub use audit::*;
+#[cfg(feature = "capture")]
+pub use capture::*;
 pub use clipboard::*;
+pub use custom_channel::{DynamicChannelFactory, StaticChannelFactory};
 pub use display::*;
+pub use display_channel::*;
+pub use drive::*;
+pub use encoder::rfx::RemoteFxQuality;
+pub use error::*;
+pub use gfx::{H264Encoder, H264EncoderFactory, Yuv420Frame};
 pub use handler::*;
+pub use heartbeat::*;
 #[cfg(feature = "helper")]
 pub use helper::*;
+#[cfg(feature = "injection")]
+pub use injection::InputInjector;
+pub use input_channel::*;
+pub use input_limit::InputLimits;
+pub use lifecycle::*;
+pub use listener::*;
+pub use quality::*;
+pub use rail::*;
+pub use reconnect::*;
+pub use recording::*;
 pub use server::*;
+pub use session::*;
+pub use shadow::*;
 pub use sound::*;
+pub use stats::*;
+pub use tls::*;
+#[cfg(feature = "websocket")]
+pub use ws::*;
 
 #[cfg(feature = "__bench")]
 pub mod bench {
     pub mod encoder {
+        pub mod damage {
+            pub use crate::encoder::damage::bench::{split, DamageTracker};
+        }
+
         pub mod rfx {
-            pub use crate::encoder::rfx::bench::{rfx_enc, rfx_enc_tile};
+            pub use crate::encoder::rfx::bench::{negotiate, quants, rfx_enc, rfx_enc_tile};
         }
     }
+
+    pub mod flow_control {
+        pub use crate::flow_control::bench::FrameTracker;
+    }
+
+    pub mod gfx {
+        pub use crate::gfx::bench::{compress, handle, negotiate, version_rank, views};
+    }
+
+    pub mod heartbeat {
+        pub use crate::heartbeat::bench::{max_silence, pdu};
+    }
+
+    pub mod input_limit {
+        pub use crate::input_limit::bench::{Admission, InputLimiter};
+    }
+
+    pub mod quality {
+        pub use crate::quality::bench::QualityController;
+    }
+
+    pub mod reconnect {
+        pub use crate::reconnect::bench::AutoReconnectCookies;
+    }
+
+    pub mod server {
+        pub use crate::server::bench::{allows, layout_desktop_size, next_timeout};
+    }
 }
 
 #[macro_export]
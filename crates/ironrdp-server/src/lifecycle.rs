@@ -0,0 +1,142 @@
+use ironrdp_pdu::gcc::{ChannelName, ClientCoreData};
+pub use ironrdp_pdu::gcc::{ClientColorDepth, KeyboardType};
+use ironrdp_pdu::rdp::capability_sets::CapabilitySet;
+pub use ironrdp_pdu::rdp::client_info::TimezoneInfo;
+pub use ironrdp_pdu::rdp::server_error_info::{ErrorInfo, ProtocolIndependentCode};
+pub use ironrdp_pdu::rdp::server_redirection::{ServerRedirectionFlags, ServerRedirectionPdu};
+
+use crate::{DesktopSize, PeerInfo};
+
+/// Information about a connection, completed as the connection sequence progresses.
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    pub peer: PeerInfo,
+    /// Name of the client computer, known once the basic settings are exchanged.
+    pub client_name: Option<String>,
+    /// Name of the user, known once the client info is received.
+    pub username: Option<String>,
+    /// Identifier of the session, given to the client in its auto-reconnect cookie.
+    ///
+    /// Known once the session starts when the auto-reconnection is enabled, see
+    /// [`RdpServerReconnectHandler`](crate::RdpServerReconnectHandler). A client re-attached to its session
+    /// keeps the logon ID of the session.
+    pub logon_id: Option<u32>,
+    /// DER-encoded certificate presented by the client during the TLS handshake, if requested by the server.
+    ///
+    /// See [`TlsCertificates::require_client_certificates`](crate::TlsCertificates::require_client_certificates).
+    pub client_certificate: Option<Vec<u8>>,
+}
+
+impl ConnectionInfo {
+    pub(crate) fn new(peer: PeerInfo) -> Self {
+        Self {
+            peer,
+            client_name: None,
+            username: None,
+            logon_id: None,
+            client_certificate: None,
+        }
+    }
+}
+
+/// Settings of the client, negotiated during the connection sequence.
+///
+/// Given to the handlers of the session, see [`RdpServerInputHandler::session_info`](crate::RdpServerInputHandler::session_info)
+/// and [`RdpServerDisplay::session_info`](crate::RdpServerDisplay::session_info), e.g. to apply the keyboard
+/// layout or the time zone of the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientSessionInfo {
+    /// Name of the client computer.
+    pub client_name: String,
+    /// Build number of the client.
+    pub client_build: u32,
+    /// Active input locale identifier of the client, e.g. 0x409 for the US English layout.
+    pub keyboard_layout: u32,
+    pub keyboard_type: KeyboardType,
+    pub keyboard_subtype: u32,
+    pub keyboard_functional_keys_count: u32,
+    /// Color depth requested by the client.
+    pub color_depth: ClientColorDepth,
+    /// Size of the desktop requested by the client.
+    pub desktop_size: DesktopSize,
+    /// Time zone of the client, if given in the client info.
+    pub timezone: Option<TimezoneInfo>,
+}
+
+impl ClientSessionInfo {
+    pub(crate) fn new(core: &ClientCoreData, timezone: Option<TimezoneInfo>) -> Self {
+        Self {
+            client_name: core.client_name.clone(),
+            client_build: core.client_build,
+            keyboard_layout: core.keyboard_layout,
+            keyboard_type: core.keyboard_type,
+            keyboard_subtype: core.keyboard_subtype,
+            keyboard_functional_keys_count: core.keyboard_functional_keys_count,
+            color_depth: core.client_color_depth(),
+            desktop_size: DesktopSize {
+                width: core.desktop_width,
+                height: core.desktop_height,
+            },
+            timezone,
+        }
+    }
+}
+
+/// Decision of a [`RdpServerAuthorizer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Authorization {
+    Allow,
+    /// Refuses the connection, the reason is given to the client before it is disconnected.
+    ///
+    /// For instance [`ProtocolIndependentCode::ServerDeniedConnection`] or
+    /// [`ProtocolIndependentCode::ServerInsufficientPrivileges`].
+    Deny(ErrorInfo),
+    /// Redirects the client to another server, e.g. the server running the session of the user when
+    /// the server is a connection broker.
+    ///
+    /// The redirection requires Enhanced RDP Security, the client is disconnected otherwise.
+    Redirect(Box<ServerRedirectionPdu>),
+}
+
+/// Authorizes the connections, after the security negotiation and the authentication of the user, and
+/// before the session starts.
+#[async_trait::async_trait]
+pub trait RdpServerAuthorizer: Send + Sync {
+    async fn authorize(&self, info: &ConnectionInfo) -> Authorization;
+}
+
+/// Callbacks reporting the lifecycle of the connections, e.g. to audit the sessions.
+///
+/// The handler is shared by all the connections of the server, and is called from their tasks: the
+/// callbacks should return quickly.
+pub trait RdpServerEventHandler: Send + Sync {
+    /// Called when a connection is accepted, before the connection sequence.
+    ///
+    /// Returns `false` to refuse the connection, which is then closed.
+    fn connected(&self, info: &ConnectionInfo) -> bool {
+        let _ = info;
+        true
+    }
+
+    /// Called with the result of the authentication of the user.
+    fn authenticated(&self, info: &ConnectionInfo, success: bool) {
+        let _ = (info, success);
+    }
+
+    /// Called when the connection sequence is completed, with the static channels joined by the client.
+    fn channels_joined(&self, info: &ConnectionInfo, channels: &[ChannelName]) {
+        let _ = (info, channels);
+    }
+
+    /// Called when the client is activated, with its capabilities.
+    ///
+    /// The client is activated again after each resize of the desktop.
+    fn activated(&self, info: &ConnectionInfo, capabilities: &[CapabilitySet]) {
+        let _ = (info, capabilities);
+    }
+
+    /// Called when the connection is closed, with the error which closed it, if any.
+    fn disconnected(&self, info: &ConnectionInfo, error: Option<&anyhow::Error>) {
+        let _ = (info, error);
+    }
+}
@@ -0,0 +1,151 @@
+use std::io;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::os::fd::{FromRawFd as _, OwnedFd, RawFd};
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+
+/// Information about the client at the other end of a connection.
+#[derive(Debug, Clone, Default)]
+pub struct PeerInfo {
+    /// Address of the client, if connected over TCP.
+    pub addr: Option<SocketAddr>,
+}
+
+impl From<SocketAddr> for PeerInfo {
+    fn from(addr: SocketAddr) -> Self {
+        Self { addr: Some(addr) }
+    }
+}
+
+impl PeerInfo {
+    /// Name of the client computer, used during the NLA authentication.
+    pub(crate) fn client_name(&self) -> String {
+        self.addr.map(|addr| addr.to_string()).unwrap_or_default()
+    }
+}
+
+/// Transport of a connection, usually a TCP stream.
+pub trait RdpServerStream: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
+
+impl<T> RdpServerStream for T where T: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
+
+/// Accepts the connections of an RDP server.
+///
+/// Implemented for TCP and Unix domain socket listeners, other transports (e.g. in-memory streams
+/// for tests, or connections handed over by a co-located proxy) can be accepted by implementing
+/// this trait.
+#[async_trait::async_trait]
+pub trait RdpServerListener: Send {
+    /// Waits for a new connection.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method MUST be cancellation safe because it is used in a
+    /// `tokio::select!` statement. If some other branch completes first, it
+    /// MUST be guaranteed that no connection is lost.
+    async fn accept(&mut self) -> io::Result<(Box<dyn RdpServerStream>, PeerInfo)>;
+
+    /// Returns the address the listener is bound to, if any.
+    fn local_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+}
+
+#[async_trait::async_trait]
+impl RdpServerListener for TcpListener {
+    async fn accept(&mut self) -> io::Result<(Box<dyn RdpServerStream>, PeerInfo)> {
+        let (stream, addr) = TcpListener::accept(self).await?;
+
+        Ok((Box::new(stream), addr.into()))
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        TcpListener::local_addr(self).ok()
+    }
+}
+
+#[cfg(unix)]
+#[async_trait::async_trait]
+impl RdpServerListener for tokio::net::UnixListener {
+    async fn accept(&mut self) -> io::Result<(Box<dyn RdpServerStream>, PeerInfo)> {
+        let (stream, addr) = tokio::net::UnixListener::accept(self).await?;
+        debug!(?addr, "Accepted Unix domain socket connection");
+
+        Ok((Box::new(stream), PeerInfo::default()))
+    }
+}
+
+/// First file descriptor passed by systemd, see `sd_listen_fds(3)`.
+#[cfg(unix)]
+const SD_LISTEN_FDS_START: RawFd = 3;
+
+/// Whether [`systemd_listeners`] took over the sockets passed by systemd.
+#[cfg(unix)]
+static SYSTEMD_LISTENERS_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// Takes over the listening sockets passed by systemd socket activation.
+///
+/// Returns no listener when the process was not activated by systemd, i.e. when `LISTEN_PID` is not the process
+/// ID. The sockets can be taken over only once per process: the next calls fail.
+///
+/// The `LISTEN_*` variables are only read, and left in the environment since modifying it is not thread-safe.
+/// The child processes ignore them, as `LISTEN_PID` is not their process ID.
+///
+/// Each listener can be given to [`RdpServerBuilder::with_listener`](crate::RdpServerBuilder::with_listener),
+/// which allows the service to listen on a privileged port without running as root.
+///
+/// Must be called from within a tokio runtime.
+#[cfg(unix)]
+pub fn systemd_listeners() -> io::Result<Vec<TcpListener>> {
+    let activated = std::env::var("LISTEN_PID")
+        .ok()
+        .and_then(|pid| pid.parse::<u32>().ok())
+        .is_some_and(|pid| pid == std::process::id());
+
+    if !activated {
+        return Ok(Vec::new());
+    }
+
+    let count = std::env::var("LISTEN_FDS")
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        .parse::<RawFd>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    if SYSTEMD_LISTENERS_TAKEN.swap(true, Ordering::AcqRel) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "the systemd sockets were already taken over",
+        ));
+    }
+
+    debug!(count, "Taking over the sockets passed by systemd");
+
+    (SD_LISTEN_FDS_START..SD_LISTEN_FDS_START.saturating_add(count))
+        .map(|fd| {
+            // SAFETY: systemd passes the sockets to this process from the first descriptor, and they are owned by
+            // no one else since they are taken over only once.
+            let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+            listener_from_fd(fd)
+        })
+        .collect()
+}
+
+/// Takes over a listening TCP socket, e.g. inherited from the parent process which bound it.
+///
+/// Must be called from within a tokio runtime.
+#[cfg(unix)]
+pub fn listener_from_fd(fd: OwnedFd) -> io::Result<TcpListener> {
+    let listener = std::net::TcpListener::from(fd);
+
+    // Fails if the socket is not a TCP socket.
+    let addr = listener.local_addr()?;
+    debug!(%addr, "Listening on an inherited socket");
+
+    listener.set_nonblocking(true)?;
+
+    TcpListener::from_std(listener)
+}
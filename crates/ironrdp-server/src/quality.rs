@@ -0,0 +1,190 @@
+use core::time::Duration;
+use std::sync::Arc;
+
+use tokio::time::Instant;
+
+use crate::RemoteFxQuality;
+
+/// The policy is consulted at most once per interval, so the effect of a change is measured before
+/// the next one.
+const ADAPTATION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Interval between two frames when the update rate is first limited, i.e. 30 frames per second.
+const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
+/// Encoding quality of the display updates of a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodingQuality {
+    /// Quantization of the RemoteFX tiles.
+    pub remote_fx: RemoteFxQuality,
+    /// Target bitrate of the H.264 encoders of the graphics pipeline, in kilobits per second.
+    ///
+    /// `None` keeps the bitrate of the encoders, see [`H264Encoder::set_bitrate`](crate::H264Encoder::set_bitrate).
+    pub h264_bitrate: Option<u32>,
+    /// Minimum interval between two frames, limiting the update rate. Zero doesn't limit it.
+    pub frame_interval: Duration,
+}
+
+/// Network conditions of a connection, measured from the frame acknowledgements of the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkFeedback {
+    /// Smoothed time between sending a frame and receiving its acknowledgement.
+    pub frame_latency: Duration,
+    /// Number of frames sent to the client and not acknowledged yet.
+    pub unacknowledged_frames: usize,
+}
+
+/// Adapts the encoding quality of the connections to their network conditions.
+///
+/// The policy is consulted periodically for each connection whose client acknowledges the frames,
+/// see [`RdpServerOptions::max_unacknowledged_frames`](crate::RdpServerOptions::max_unacknowledged_frames).
+pub trait QualityPolicy: Send + Sync {
+    /// Returns the quality of the next frames, given the current quality and the network feedback.
+    fn adapt(&self, quality: EncodingQuality, feedback: &NetworkFeedback) -> EncodingQuality;
+}
+
+/// Lowers the quality while the frame latency is above a target, and raises it back once the latency
+/// is below half the target.
+///
+/// Each step lowers the RemoteFX quality and the H.264 bitrate, and halves the update rate.
+#[derive(Debug, Clone)]
+pub struct LatencyQualityPolicy {
+    pub target_latency: Duration,
+    /// Highest RemoteFX quality the quality is raised back to.
+    pub max_remote_fx: RemoteFxQuality,
+    /// Range of the H.264 bitrate, in kilobits per second.
+    pub min_h264_bitrate: u32,
+    pub max_h264_bitrate: u32,
+    /// Longest interval between two frames.
+    pub max_frame_interval: Duration,
+}
+
+impl Default for LatencyQualityPolicy {
+    fn default() -> Self {
+        Self {
+            target_latency: Duration::from_millis(150),
+            max_remote_fx: RemoteFxQuality::High,
+            min_h264_bitrate: 1_000,
+            max_h264_bitrate: 10_000,
+            max_frame_interval: Duration::from_millis(200),
+        }
+    }
+}
+
+impl QualityPolicy for LatencyQualityPolicy {
+    fn adapt(&self, quality: EncodingQuality, feedback: &NetworkFeedback) -> EncodingQuality {
+        if feedback.frame_latency > self.target_latency {
+            let bitrate = quality.h264_bitrate.unwrap_or(self.max_h264_bitrate);
+
+            EncodingQuality {
+                remote_fx: match quality.remote_fx {
+                    RemoteFxQuality::High => RemoteFxQuality::Medium,
+                    RemoteFxQuality::Medium | RemoteFxQuality::Low => RemoteFxQuality::Low,
+                },
+                h264_bitrate: Some((bitrate / 4 * 3).max(self.min_h264_bitrate)),
+                frame_interval: quality
+                    .frame_interval
+                    .saturating_mul(2)
+                    .clamp(MIN_FRAME_INTERVAL, self.max_frame_interval.max(MIN_FRAME_INTERVAL)),
+            }
+        } else if feedback.frame_latency < self.target_latency / 2 {
+            let remote_fx = match quality.remote_fx {
+                RemoteFxQuality::Low => RemoteFxQuality::Medium,
+                RemoteFxQuality::Medium | RemoteFxQuality::High => RemoteFxQuality::High,
+            };
+            let frame_interval = quality.frame_interval / 2;
+
+            EncodingQuality {
+                remote_fx: if rank(remote_fx) <= rank(self.max_remote_fx) {
+                    remote_fx
+                } else {
+                    quality.remote_fx
+                },
+                h264_bitrate: quality
+                    .h264_bitrate
+                    .map(|bitrate| bitrate.saturating_add(bitrate / 4).min(self.max_h264_bitrate)),
+                frame_interval: if frame_interval < MIN_FRAME_INTERVAL {
+                    Duration::ZERO
+                } else {
+                    frame_interval
+                },
+            }
+        } else {
+            quality
+        }
+    }
+}
+
+fn rank(quality: RemoteFxQuality) -> u8 {
+    match quality {
+        RemoteFxQuality::Low => 0,
+        RemoteFxQuality::Medium => 1,
+        RemoteFxQuality::High => 2,
+    }
+}
+
+/// Applies a quality policy to a connection.
+pub(crate) struct QualityController {
+    policy: Arc<dyn QualityPolicy>,
+    quality: EncodingQuality,
+    adapted_at: Instant,
+    next_frame: Option<Instant>,
+}
+
+impl QualityController {
+    pub(crate) fn new(policy: Arc<dyn QualityPolicy>, quality: EncodingQuality) -> Self {
+        Self {
+            policy,
+            quality,
+            adapted_at: Instant::now(),
+            next_frame: None,
+        }
+    }
+
+    /// Consults the policy, returning the new quality when it changed.
+    pub(crate) fn adapt(&mut self, feedback: Option<NetworkFeedback>) -> Option<EncodingQuality> {
+        if self.adapted_at.elapsed() < ADAPTATION_INTERVAL {
+            return None;
+        }
+        let feedback = feedback?;
+        self.adapted_at = Instant::now();
+
+        let quality = self.policy.adapt(self.quality, &feedback);
+        if quality == self.quality {
+            return None;
+        }
+        debug!(?quality, ?feedback, "Encoding quality adapted");
+        self.quality = quality;
+
+        Some(quality)
+    }
+
+    /// Waits until the next frame can be sent, according to the update rate.
+    pub(crate) async fn throttle(&mut self) {
+        if let Some(next_frame) = self.next_frame {
+            tokio::time::sleep_until(next_frame).await;
+        }
+        self.next_frame = Some(Instant::now() + self.quality.frame_interval);
+    }
+}
+
+#[cfg(feature = "__bench")]
+pub(crate) mod bench {
+    use super::*;
+
+    pub struct QualityController(super::QualityController);
+
+    impl QualityController {
+        pub fn new(policy: Arc<dyn QualityPolicy>, quality: EncodingQuality) -> Self {
+            Self(super::QualityController::new(policy, quality))
+        }
+
+        pub fn adapt(&mut self, feedback: Option<NetworkFeedback>) -> Option<EncodingQuality> {
+            self.0.adapt(feedback)
+        }
+
+        pub async fn throttle(&mut self) {
+            self.0.throttle().await;
+        }
+    }
+}
@@ -0,0 +1,26 @@
+pub use ironrdp_rail::pdu::window::{ShowState, WindowInfo, WindowStyle};
+pub use ironrdp_rail::pdu::{
+    ActivatePdu, ClientStatusFlags, ExecFlags, ExecPdu, ExecResult, SysCommandPdu, SystemCommand,
+};
+pub use ironrdp_rail::server::RailServerHandler;
+
+/// Creates the RAIL channel of each connection, publishing remote applications (RemoteApp) instead of the
+/// whole desktop.
+///
+/// The windows of the applications are described to the clients using [`WindowUpdate`] display updates.
+pub trait RailServerFactory: Send {
+    fn build_backend(&self) -> Box<dyn RailServerHandler>;
+}
+
+/// Update of a window of the remote applications, sent to the clients supporting the window orders.
+///
+/// The content of the windows is still sent using the bitmap updates of the desktop.
+#[derive(Debug, Clone)]
+pub enum WindowUpdate {
+    /// Creates the window, or updates the properties of an existing window.
+    ///
+    /// Only the properties which are set are updated, a new window should describe all of them.
+    Update { window_id: u32, info: WindowInfo },
+    /// Closes the window.
+    Delete { window_id: u32 },
+}
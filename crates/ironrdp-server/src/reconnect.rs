@@ -0,0 +1,113 @@
+//! Auto-reconnection of the clients (\[MS-RDPBCGR\] 5.5).
+//!
+//! Once a session starts, the server sends an auto-reconnect cookie to the client in a Save Session Info
+//! PDU. When the connection is lost, the client reconnects with a verifier of this cookie, identifying the
+//! session it was connected to.
+
+use core::time::Duration;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use ironrdp_pdu::rdp::session_info::{ClientAutoReconnect, ServerAutoReconnect};
+use rand_core::{OsRng, RngCore as _};
+use tokio::time::Instant;
+
+use crate::ConnectionInfo;
+
+/// The cookies are only valid for this duration, and once.
+const COOKIE_LIFETIME: Duration = Duration::from_secs(10 * 60);
+
+/// Re-attaches the reconnecting clients to their session.
+///
+/// The sessions are identified by a logon ID, given to the clients in their auto-reconnect cookie, see
+/// [`ConnectionInfo::logon_id`].
+pub trait RdpServerReconnectHandler: Send + Sync {
+    /// Called when a client reconnects with a valid auto-reconnect cookie of the session `logon_id`.
+    ///
+    /// Returns `true` to re-attach the connection to this session, which keeps its logon ID, or `false` to
+    /// start a new session.
+    fn reconnect(&self, info: &ConnectionInfo, logon_id: u32) -> bool;
+}
+
+struct IssuedCookie {
+    random_bits: [u8; 16],
+    issued_at: Instant,
+}
+
+#[derive(Default)]
+struct Cookies {
+    next_logon_id: u32,
+    by_logon_id: HashMap<u32, IssuedCookie>,
+}
+
+/// Auto-reconnect cookies issued by a server, shared by its connections.
+#[derive(Clone, Default)]
+pub(crate) struct AutoReconnectCookies {
+    cookies: Arc<Mutex<Cookies>>,
+}
+
+impl AutoReconnectCookies {
+    /// Issues a cookie for a session, replacing its previous cookie. A new logon ID is allocated for a new
+    /// session.
+    pub(crate) fn issue(&self, logon_id: Option<u32>) -> ServerAutoReconnect {
+        let mut cookies = self.cookies.lock().expect("poisoned");
+        cookies
+            .by_logon_id
+            .retain(|_, cookie| cookie.issued_at.elapsed() < COOKIE_LIFETIME);
+
+        let logon_id = logon_id.unwrap_or_else(|| {
+            let logon_id = cookies.next_logon_id;
+            cookies.next_logon_id = logon_id.wrapping_add(1);
+            logon_id
+        });
+
+        let mut random_bits = [0; 16];
+        OsRng.fill_bytes(&mut random_bits);
+        cookies.by_logon_id.insert(
+            logon_id,
+            IssuedCookie {
+                random_bits,
+                issued_at: Instant::now(),
+            },
+        );
+
+        ServerAutoReconnect { logon_id, random_bits }
+    }
+
+    /// Verifies the cookie of a reconnecting client, returning the logon ID of its session if valid.
+    ///
+    /// The cookie is consumed, a new one is issued to the reconnected client.
+    pub(crate) fn redeem(&self, cookie: &ClientAutoReconnect, client_random: &[u8]) -> Option<u32> {
+        let mut cookies = self.cookies.lock().expect("poisoned");
+        let issued = cookies.by_logon_id.get(&cookie.logon_id)?;
+
+        let server_cookie = ServerAutoReconnect {
+            logon_id: cookie.logon_id,
+            random_bits: issued.random_bits,
+        };
+        if issued.issued_at.elapsed() >= COOKIE_LIFETIME || !cookie.verify(&server_cookie, client_random) {
+            return None;
+        }
+        cookies.by_logon_id.remove(&cookie.logon_id);
+
+        Some(cookie.logon_id)
+    }
+}
+
+#[cfg(feature = "__bench")]
+pub(crate) mod bench {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    pub struct AutoReconnectCookies(super::AutoReconnectCookies);
+
+    impl AutoReconnectCookies {
+        pub fn issue(&self, logon_id: Option<u32>) -> ServerAutoReconnect {
+            self.0.issue(logon_id)
+        }
+
+        pub fn redeem(&self, cookie: &ClientAutoReconnect, client_random: &[u8]) -> Option<u32> {
+            self.0.redeem(cookie, client_random)
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Session recording.
+//!
+//! The display updates sent to a client and the input events received from it are recorded with
+//! their time, as encoded on the wire before encryption, so they can be decoded again with the PDU
+//! types of `ironrdp-pdu`.
+//!
+//! A recording starts with a header, the magic `IRDPREC\0`, the format version (`u16`) and the start
+//! time in milliseconds since the Unix epoch (`u64`). It is followed by the records, each one made of
+//! its time in microseconds since the start (`u64`), its [`RecordKind`] (`u8`), the length of its data
+//! (`u32`) and its data. All the integers are little-endian.
+
+use core::time::Duration;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::PeerInfo;
+
+const MAGIC: &[u8; 8] = b"IRDPREC\0";
+const VERSION: u16 = 1;
+
+/// Recording of the sessions.
+#[derive(Debug, Clone)]
+pub struct RecordingOptions {
+    /// Directory where a recording file is created for each connection, named after the start time
+    /// and the address of the client.
+    pub directory: PathBuf,
+}
+
+/// Kind of data of a record.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RecordKind {
+    /// Display update sent to the client, a fast-path output PDU, or an X.224 PDU when the graphics
+    /// pipeline is used.
+    Output,
+    /// Fast-path input PDU received from the client (`FastPathInput`).
+    FastPathInput,
+    /// Input events received from the client in a slow-path Input PDU (`InputEventPdu`).
+    SlowPathInput,
+    /// Input event received from the client on the ainput dynamic channel (`ironrdp_ainput::ClientPdu`).
+    AInput,
+}
+
+impl RecordKind {
+    fn as_u8(self) -> u8 {
+        match self {
+            Self::Output => 0,
+            Self::FastPathInput => 1,
+            Self::SlowPathInput => 2,
+            Self::AInput => 3,
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Output),
+            1 => Some(Self::FastPathInput),
+            2 => Some(Self::SlowPathInput),
+            3 => Some(Self::AInput),
+            _ => None,
+        }
+    }
+}
+
+/// Record read from a session recording.
+#[derive(Debug, Clone)]
+pub struct Record {
+    /// Time of the record since the start of the recording.
+    pub elapsed: Duration,
+    pub kind: RecordKind,
+    pub data: Vec<u8>,
+}
+
+/// Records a session.
+///
+/// A failure to write stops the recording, the session going on.
+pub struct SessionRecorder {
+    writer: Mutex<Option<Box<dyn Write + Send>>>,
+    started: Instant,
+}
+
+impl SessionRecorder {
+    /// Starts a recording written to the given writer.
+    pub fn new(mut writer: Box<dyn Write + Send>) -> io::Result<Self> {
+        let started_at = SystemTime::now().duration_since(UNIX_EPOCH).map_err(io::Error::other)?;
+        let started_at = u64::try_from(started_at.as_millis()).map_err(io::Error::other)?;
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&started_at.to_le_bytes())?;
+
+        Ok(Self {
+            writer: Mutex::new(Some(writer)),
+            started: Instant::now(),
+        })
+    }
+
+    /// Starts a recording of the connection of a client in a new file of the given directory.
+    pub fn create(directory: &Path, peer: &PeerInfo) -> io::Result<(Self, PathBuf)> {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(io::Error::other)?
+            .as_millis();
+        let peer = peer
+            .addr
+            .map(|addr| addr.to_string().replace([':', '[', ']'], "_"))
+            .unwrap_or_else(|| "local".to_owned());
+        let path = directory.join(format!("session-{started_at}-{peer}.rdprec"));
+
+        let file = File::create_new(&path)?;
+        let recorder = Self::new(Box::new(BufWriter::new(file)))?;
+
+        Ok((recorder, path))
+    }
+
+    pub fn record(&self, kind: RecordKind, data: &[u8]) {
+        let mut writer = self.writer.lock().expect("poisoned");
+        let Some(inner) = writer.as_mut() else {
+            return;
+        };
+
+        let elapsed = u64::try_from(self.started.elapsed().as_micros()).unwrap_or(u64::MAX);
+        let result = u32::try_from(data.len()).map_err(io::Error::other).and_then(|len| {
+            inner.write_all(&elapsed.to_le_bytes())?;
+            inner.write_all(&[kind.as_u8()])?;
+            inner.write_all(&len.to_le_bytes())?;
+            inner.write_all(data)
+        });
+
+        if let Err(error) = result {
+            error!(?error, "Failed to write the session recording, stopping the recording");
+            *writer = None;
+        }
+    }
+
+    /// Writes the buffered records.
+    pub fn flush(&self) -> io::Result<()> {
+        match self.writer.lock().expect("poisoned").as_mut() {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for SessionRecorder {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            error!(?error, "Failed to flush the session recording");
+        }
+    }
+}
+
+impl core::fmt::Debug for SessionRecorder {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SessionRecorder")
+            .field("started", &self.started)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads the records of a session recording.
+#[derive(Debug)]
+pub struct SessionRecordingReader<R> {
+    reader: R,
+    started_at: SystemTime,
+}
+
+impl<R: Read> SessionRecordingReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a session recording"));
+        }
+
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        if u16::from_le_bytes(version) != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unsupported session recording version",
+            ));
+        }
+
+        let mut started_at = [0; 8];
+        reader.read_exact(&mut started_at)?;
+        let started_at = UNIX_EPOCH + Duration::from_millis(u64::from_le_bytes(started_at));
+
+        Ok(Self { reader, started_at })
+    }
+
+    /// Returns the time the recording started.
+    pub fn started_at(&self) -> SystemTime {
+        self.started_at
+    }
+
+    /// Reads the next record, or returns `None` at the end of the recording.
+    pub fn next_record(&mut self) -> io::Result<Option<Record>> {
+        let mut elapsed = [0; 8];
+        match self.reader.read_exact(&mut elapsed) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error),
+        }
+
+        let mut header = [0; 5];
+        self.reader.read_exact(&mut header)?;
+        let kind = RecordKind::from_u8(header[0])
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid record kind"))?;
+        let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+
+        let mut data = Vec::new();
+        (&mut self.reader).take(u64::from(len)).read_to_end(&mut data)?;
+        if data.len() != usize::try_from(len).map_err(io::Error::other)? {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(Some(Record {
+            elapsed: Duration::from_micros(u64::from_le_bytes(elapsed)),
+            kind,
+            data,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for SessionRecordingReader<R> {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
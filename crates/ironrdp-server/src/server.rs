@@ -1,44 +1,164 @@
+use core::time::Duration;
+use std::collections::VecDeque;
 use std::net::SocketAddr;
 use std::rc::Rc;
 use std::sync::Arc;
 
 use anyhow::{anyhow, bail, Context, Result};
-use ironrdp_acceptor::{self, Acceptor, AcceptorResult, BeginResult, DesktopSize};
+use futures_util::StreamExt as _;
+use ironrdp_acceptor::{self, Acceptor, AcceptorResult, BeginResult, ConnectorErrorKind, DesktopSize};
+pub use ironrdp_acceptor::{CapabilitiesHook, CredentialsValidator, SecurityPolicy, TlsVersion};
 use ironrdp_async::{bytes, Framed};
+use ironrdp_audin::server::AudioInputServer;
 use ironrdp_cliprdr::backend::ClipboardMessage;
 use ironrdp_cliprdr::CliprdrServer;
-use ironrdp_core::{decode, encode_vec, impl_as_any};
+use ironrdp_core::{decode, encode_vec, impl_as_any, Encode, WriteBuf};
 use ironrdp_displaycontrol::pdu::DisplayControlMonitorLayout;
 use ironrdp_displaycontrol::server::{DisplayControlHandler, DisplayControlServer};
+use ironrdp_dvc as dvc;
+use ironrdp_pdu::gcc::ClientEarlyCapabilityFlags;
 use ironrdp_pdu::input::fast_path::{FastPathInput, FastPathInputEvent};
 use ironrdp_pdu::input::InputEventPdu;
 use ironrdp_pdu::mcs::{SendDataIndication, SendDataRequest};
-use ironrdp_pdu::rdp::capability_sets::{BitmapCodecs, CapabilitySet, CmdFlags, GeneralExtraFlags};
+use ironrdp_pdu::rdp::capability_sets::{
+    BitmapCodecs, CapabilitySet, CmdFlags, GeneralExtraFlags, LargePointerSupportFlags,
+};
 pub use ironrdp_pdu::rdp::client_info::Credentials;
 use ironrdp_pdu::rdp::headers::{ServerDeactivateAll, ShareControlPdu};
+use ironrdp_pdu::rdp::server_error_info::{ErrorInfo, ProtocolIndependentCode, ServerSetErrorInfoPdu};
+use ironrdp_pdu::rdp::server_redirection::EnhancedSecurityServerRedirection;
+use ironrdp_pdu::rdp::session_info::{
+    InfoData, InfoType, LogonExFlags, LogonInfoExtended, SaveSessionInfoPdu, ServerAutoReconnect,
+};
+pub use ironrdp_pdu::rdp::standard_security::ServerSecurityKey;
+use ironrdp_pdu::rdp::standard_security::StandardSecurity;
 use ironrdp_pdu::x224::X224;
 use ironrdp_pdu::{self, decode_err, mcs, nego, rdp, Action, PduResult};
-use ironrdp_svc::{server_encode_svc_messages, StaticChannelId, StaticChannelSet, SvcProcessor};
+use ironrdp_rail::server::RailServer;
+use ironrdp_rdpdr::server::{DriveRequest, RdpdrServer, RdpdrServerMessage};
+use ironrdp_rdpsnd as rdpsnd;
+use ironrdp_svc::{StaticChannelId, StaticChannelSet, SvcProcessor};
 use ironrdp_tokio::{split_tokio_framed, unsplit_tokio_framed, FramedRead, FramedWrite, TokioFramed};
 use rdpsnd::server::{RdpsndServer, RdpsndServerMessage};
 use tokio::io::{AsyncRead, AsyncWrite};
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
 use tokio::task;
 use tokio_rustls::TlsAcceptor;
-use {ironrdp_dvc as dvc, ironrdp_rdpsnd as rdpsnd};
+use tracing::Instrument as _;
 
+use crate::audio_input::AudioInputServerFactory;
+use crate::audit::{AuditClipboardPolicy, AuditEvent, ConnectionAudit, RdpServerAuditor};
 use crate::clipboard::CliprdrServerFactory;
-use crate::display::{DisplayUpdate, RdpServerDisplay};
-use crate::encoder::UpdateEncoder;
-use crate::handler::RdpServerInputHandler;
-use crate::{builder, capabilities, SoundServerFactory};
+use crate::compression::Compression;
+use crate::custom_channel::{CustomChannels, DynamicChannelFactory, StaticChannelFactory};
+use crate::display::{DisplayUpdate, DynRdpServerDisplay};
+use crate::drive::DriveServerFactory;
+use crate::encoder::{rfx, UpdateEncoder};
+use crate::flow_control::FrameTracker;
+use crate::gfx::{GfxHandler, GfxServer, H264EncoderFactory, SharedGfxState};
+use crate::handler::{DynRdpServerInputHandler, MouseEvent};
+use crate::heartbeat::{ClientUnresponsive, HeartbeatOptions};
+use crate::input_channel::{push_coalesced, InputEvent};
+use crate::input_limit::{Admission, InputLimiter, InputLimits};
+use crate::lifecycle::{
+    Authorization, ClientSessionInfo, ConnectionInfo, RdpServerAuthorizer, RdpServerEventHandler, ServerRedirectionPdu,
+};
+use crate::listener::{PeerInfo, RdpServerListener};
+use crate::quality::{EncodingQuality, QualityController, QualityPolicy};
+use crate::rail::RailServerFactory;
+use crate::reconnect::{AutoReconnectCookies, RdpServerReconnectHandler};
+use crate::recording::{RecordKind, RecordingOptions, SessionRecorder};
+use crate::session::{RdpServerHandle, RdpServerSession, RdpServerSessionFactory, RdpServerSessions, SessionInfo};
+use crate::stats::{FrameCodec, InputKind, RdpServerMetrics, StatsRecorder};
+use crate::tls::tls_version;
+use crate::{
+    builder, capabilities, time_warn, RdpServerStream, RemoteFxQuality, SoundServerFactory, TlsCertificates,
+    TlsServerAcceptor,
+};
 
 #[derive(Clone)]
 pub struct RdpServerOptions {
     pub addr: SocketAddr,
     pub security: RdpServerSecurity,
     pub with_remote_fx: bool,
+    pub remote_fx_quality: RemoteFxQuality,
+    /// Use the AVC444 codec instead of AVC420 when the graphics pipeline is enabled and the client supports it.
+    pub with_avc444: bool,
+    pub limits: ConnectionLimits,
+    /// Maximum number of frames sent to a client and not acknowledged yet, when the client acknowledges
+    /// the frames.
+    ///
+    /// The display updates are not encoded while this many frames are in flight, which bounds the
+    /// latency of the display on slow clients or links. `None` disables the flow control.
+    pub max_unacknowledged_frames: Option<u32>,
+    /// Heartbeats sent to the clients, and monitoring of their liveness. `None` disables both.
+    pub heartbeat: Option<HeartbeatOptions>,
+    /// Recording of the display updates and input events of each connection. `None` disables it.
+    pub recording: Option<RecordingOptions>,
+    /// Limits of the input events of each connection.
+    pub input_limits: InputLimits,
+    /// Security requirements of the connections; the clients not meeting them are refused.
+    pub security_policy: SecurityPolicy,
+}
+
+/// Limits of the connections of a server.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionLimits {
+    /// Maximum number of concurrent sessions, when the sessions are run by a session factory.
+    ///
+    /// The connections over the limits are closed as soon as they are accepted.
+    pub max_connections: Option<usize>,
+    /// Maximum number of concurrent sessions from a single IP address, when the sessions are run by a
+    /// session factory.
+    pub max_connections_per_ip: Option<usize>,
+    /// Disconnects the clients which didn't send any input event for this duration.
+    pub idle_timeout: Option<Duration>,
+    /// Disconnects the clients once connected for this duration.
+    pub session_timeout: Option<Duration>,
+}
+
+impl ConnectionLimits {
+    /// Returns the time until the next timeout and its reason, if any timeout is set.
+    fn next_timeout(&self, stats: &StatsRecorder) -> Option<(Duration, ProtocolIndependentCode)> {
+        let idle = self.idle_timeout.map(|timeout| {
+            (
+                timeout.saturating_sub(stats.idle()),
+                ProtocolIndependentCode::IdleTimeout,
+            )
+        });
+        // ERRINFO_LOGON_TIMEOUT is the active session time limit.
+        let session = self.session_timeout.map(|timeout| {
+            (
+                timeout.saturating_sub(stats.elapsed()),
+                ProtocolIndependentCode::LogonTimeout,
+            )
+        });
+
+        match (idle, session) {
+            (Some(idle), Some(session)) => Some(if idle.0 <= session.0 { idle } else { session }),
+            (idle, session) => idle.or(session),
+        }
+    }
+
+    /// Returns `true` if a new connection from `peer` is within the limits.
+    fn allows(&self, peer: &PeerInfo, sessions: &[SessionInfo]) -> bool {
+        if self.max_connections.is_some_and(|max| sessions.len() >= max) {
+            return false;
+        }
+
+        let ip = peer.addr.map(|addr| addr.ip());
+        match (self.max_connections_per_ip, ip) {
+            (Some(max), Some(ip)) => {
+                let count = sessions
+                    .iter()
+                    .filter(|session| session.peer.addr.map(|addr| addr.ip()) == Some(ip))
+                    .count();
+                count < max
+            }
+            _ => true,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -47,20 +167,40 @@ pub enum RdpServerSecurity {
     Tls(TlsAcceptor),
     /// Used for both hybrid + hybrid-ex.
     Hybrid((TlsAcceptor, Vec<u8>)),
+    /// Used for both hybrid + hybrid-ex, with the certificates selected using the server name requested
+    /// by the client, which can be replaced while the server is running.
+    HybridCertificates(Arc<TlsCertificates>),
+    /// TLS, with another implementation than rustls.
+    CustomTls(Arc<dyn TlsServerAcceptor>),
+    /// Used for both hybrid + hybrid-ex, with another TLS implementation than rustls.
+    CustomHybrid(Arc<dyn TlsServerAcceptor>),
+    /// Standard RDP Security, encrypting the PDUs with RC4 keys exchanged with the RSA key of the server.
+    ///
+    /// This legacy security is weak, and only meant for the clients which can't negotiate TLS.
+    Rdp(ServerSecurityKey),
 }
 
 impl RdpServerSecurity {
     pub fn flag(&self) -> nego::SecurityProtocol {
         match self {
-            RdpServerSecurity::None => nego::SecurityProtocol::empty(),
-            RdpServerSecurity::Tls(_) => nego::SecurityProtocol::SSL,
-            RdpServerSecurity::Hybrid(_) => nego::SecurityProtocol::HYBRID | nego::SecurityProtocol::HYBRID_EX,
+            RdpServerSecurity::None | RdpServerSecurity::Rdp(_) => nego::SecurityProtocol::empty(),
+            RdpServerSecurity::Tls(_) | RdpServerSecurity::CustomTls(_) => nego::SecurityProtocol::SSL,
+            RdpServerSecurity::Hybrid(_)
+            | RdpServerSecurity::HybridCertificates(_)
+            | RdpServerSecurity::CustomHybrid(_) => nego::SecurityProtocol::HYBRID | nego::SecurityProtocol::HYBRID_EX,
         }
     }
 }
 
+const RPC_INITIATED_DISCONNECT: ErrorInfo =
+    ErrorInfo::ProtocolIndependentCode(ProtocolIndependentCode::RpcInitiatedDisconnect);
+
+/// Time given to the sessions to disconnect their clients when the server shuts down.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 struct AInputHandler {
-    handler: Arc<Mutex<Box<dyn RdpServerInputHandler>>>,
+    // The events are given to the input handler with the other input events, see `RdpServer::handle_ainput`.
+    sender: mpsc::UnboundedSender<ironrdp_ainput::ClientPdu>,
 }
 
 impl_as_any!(AInputHandler);
@@ -83,14 +223,8 @@ impl dvc::DvcProcessor for AInputHandler {
     fn process(&mut self, _channel_id: u32, payload: &[u8]) -> PduResult<Vec<dvc::DvcMessage>> {
         use ironrdp_ainput::ClientPdu;
 
-        match decode(payload).map_err(|e| decode_err!(e))? {
-            ClientPdu::Mouse(pdu) => {
-                let handler = Arc::clone(&self.handler);
-                task::spawn_blocking(move || {
-                    handler.blocking_lock().mouse(pdu.into());
-                });
-            }
-        }
+        let pdu: ClientPdu = decode(payload).map_err(|e| decode_err!(e))?;
+        let _ = self.sender.send(pdu);
 
         Ok(Vec::new())
     }
@@ -99,28 +233,55 @@ impl dvc::DvcProcessor for AInputHandler {
 impl dvc::DvcServerProcessor for AInputHandler {}
 
 struct DisplayControlBackend {
-    display: Arc<Mutex<Box<dyn RdpServerDisplay>>>,
+    display: Arc<Mutex<Box<dyn DynRdpServerDisplay>>>,
+    // The layouts are applied by the client loop, which resizes the session.
+    layout_sender: mpsc::UnboundedSender<DisplayControlMonitorLayout>,
 }
 
 impl DisplayControlBackend {
-    fn new(display: Arc<Mutex<Box<dyn RdpServerDisplay>>>) -> Self {
-        Self { display }
+    fn new(
+        display: Arc<Mutex<Box<dyn DynRdpServerDisplay>>>,
+        layout_sender: mpsc::UnboundedSender<DisplayControlMonitorLayout>,
+    ) -> Self {
+        Self { display, layout_sender }
     }
 }
 
 impl DisplayControlHandler for DisplayControlBackend {
     fn monitor_layout(&self, layout: DisplayControlMonitorLayout) {
         let display = Arc::clone(&self.display);
+        let _ = self.layout_sender.send(layout.clone());
         task::spawn_blocking(move || display.blocking_lock().request_layout(layout));
     }
 }
 
+/// Returns the size of the desktop containing all the monitors of the layout.
+fn layout_desktop_size(layout: &DisplayControlMonitorLayout) -> Option<DesktopSize> {
+    let mut monitors = layout.monitors().iter().filter_map(|monitor| {
+        let (left, top) = monitor.position()?;
+        let (width, height) = monitor.dimensions();
+        let right = i64::from(left) + i64::from(width);
+        let bottom = i64::from(top) + i64::from(height);
+        Some((i64::from(left), i64::from(top), right, bottom))
+    });
+
+    let first = monitors.next()?;
+    let (left, top, right, bottom) = monitors.fold(first, |(l, t, r, b), (left, top, right, bottom)| {
+        (l.min(left), t.min(top), r.max(right), b.max(bottom))
+    });
+
+    Some(DesktopSize {
+        width: u16::try_from(right - left).ok()?,
+        height: u16::try_from(bottom - top).ok()?,
+    })
+}
+
 /// RDP Server
 ///
 /// A server is created to listen for connections.
 /// After the connection sequence is finalized using the provided security mechanism, the server can:
-///  - receive display updates from a [`RdpServerDisplay`] and forward them to the client
-///  - receive input events from a client and forward them to an [`RdpServerInputHandler`]
+///  - receive display updates from a [`RdpServerDisplay`](crate::RdpServerDisplay) and forward them to the client
+///  - receive input events from a client and forward them to an [`RdpServerInputHandler`](crate::RdpServerInputHandler)
 ///
 /// # Example
 ///
@@ -135,13 +296,19 @@ impl DisplayControlHandler for DisplayControlBackend {
 ///#     fn keyboard(&mut self, _: KeyboardEvent) {}
 ///#     fn mouse(&mut self, _: MouseEvent) {}
 ///# }
+///# struct NoopDisplayUpdates;
+///# impl RdpServerDisplayUpdates for NoopDisplayUpdates {
+///#     async fn next_update(&mut self) -> Option<DisplayUpdate> {
+///#         todo!()
+///#     }
+///# }
 ///# struct NoopDisplay;
-///# #[async_trait::async_trait]
 ///# impl RdpServerDisplay for NoopDisplay {
+///#     type Updates = NoopDisplayUpdates;
 ///#     async fn size(&mut self) -> DesktopSize {
 ///#         todo!()
 ///#     }
-///#     async fn updates(&mut self) -> Result<Box<dyn RdpServerDisplayUpdates>> {
+///#     async fn updates(&mut self) -> Result<NoopDisplayUpdates> {
 ///#         todo!()
 ///#     }
 ///# }
@@ -178,23 +345,125 @@ impl DisplayControlHandler for DisplayControlBackend {
 pub struct RdpServer {
     opts: RdpServerOptions,
     // FIXME: replace with a channel and poll/process the handler?
-    handler: Arc<Mutex<Box<dyn RdpServerInputHandler>>>,
-    display: Arc<Mutex<Box<dyn RdpServerDisplay>>>,
-    static_channels: StaticChannelSet,
+    handler: Arc<Mutex<Box<dyn DynRdpServerInputHandler>>>,
+    display: Arc<Mutex<Box<dyn DynRdpServerDisplay>>>,
+    custom_channels: CustomChannels,
     sound_factory: Option<Box<dyn SoundServerFactory>>,
     cliprdr_factory: Option<Box<dyn CliprdrServerFactory>>,
+    audio_input_factory: Option<Box<dyn AudioInputServerFactory>>,
+    drive_factory: Option<Box<dyn DriveServerFactory>>,
+    rail_factory: Option<Box<dyn RailServerFactory>>,
+    h264_factory: Option<Arc<dyn H264EncoderFactory>>,
     ev_sender: mpsc::UnboundedSender<ServerEvent>,
     ev_receiver: Arc<Mutex<mpsc::UnboundedReceiver<ServerEvent>>>,
     creds: Option<Credentials>,
+    credentials_validator: Option<Arc<dyn CredentialsValidator>>,
+    capabilities_hook: Option<Arc<dyn CapabilitiesHook>>,
+    authorizer: Option<Arc<dyn RdpServerAuthorizer>>,
+    event_handler: Option<Arc<dyn RdpServerEventHandler>>,
+    auditor: Option<Arc<dyn RdpServerAuditor>>,
+    metrics: Option<Arc<dyn RdpServerMetrics>>,
+    quality_policy: Option<Arc<dyn QualityPolicy>>,
+    reconnect_handler: Option<Arc<dyn RdpServerReconnectHandler>>,
+    auto_reconnect: AutoReconnectCookies,
+    input_limiter: InputLimiter,
+    listener: Option<Box<dyn RdpServerListener>>,
+    session_factory: Option<Box<dyn RdpServerSessionFactory>>,
+    sessions: RdpServerSessions,
     local_addr: Option<SocketAddr>,
+    shutting_down: bool,
+    /// Reused to encode the messages of the static virtual channels, instead of allocating for each of them.
+    svc_buf: WriteBuf,
+}
+
+/// State of a client connection, created when the connection starts.
+struct Connection {
+    static_channels: StaticChannelSet,
+    gfx_state: Option<SharedGfxState>,
+    /// Monitor layouts requested by the client, using the display control channel.
+    layout_receiver: Arc<Mutex<mpsc::UnboundedReceiver<DisplayControlMonitorLayout>>>,
+    /// Input events received on the ainput dynamic channel.
+    ainput_receiver: mpsc::UnboundedReceiver<ironrdp_ainput::ClientPdu>,
+    stats: StatsRecorder,
+    frames: FrameTracker,
+    /// Whether the client supports the heartbeats.
+    client_heartbeat: bool,
+    /// Whether the client suppressed the display updates (Suppress Output PDU).
+    output_suppressed: watch::Sender<bool>,
+    /// Recorder of the session, when recorded.
+    recorder: Option<Arc<SessionRecorder>>,
+    /// Keys of the Standard RDP Security, when used instead of TLS.
+    rdp_security: Option<Arc<std::sync::Mutex<StandardSecurity>>>,
+    /// Bulk compressors, when the client supports the compression.
+    compression: Option<Compression>,
+    /// Auditor of the connection, when the server has an auditor.
+    audit: Option<ConnectionAudit>,
+}
+
+impl Connection {
+    fn new(stats: StatsRecorder) -> Self {
+        // Replaced by the channels of the display control and ainput, once attached.
+        let (_, layout_receiver) = mpsc::unbounded_channel();
+        let (_, ainput_receiver) = mpsc::unbounded_channel();
+
+        Self {
+            static_channels: StaticChannelSet::new(),
+            gfx_state: None,
+            layout_receiver: Arc::new(Mutex::new(layout_receiver)),
+            ainput_receiver,
+            stats,
+            frames: FrameTracker::default(),
+            client_heartbeat: false,
+            output_suppressed: watch::Sender::new(false),
+            recorder: None,
+            rdp_security: None,
+            compression: None,
+            audit: None,
+        }
+    }
+
+    /// Reports an event of the connection to the auditor, if any.
+    fn audit(&self, info: &ConnectionInfo, event: AuditEvent<'_>) {
+        if let Some(audit) = &self.audit {
+            audit.update(info);
+            audit.report(event);
+        }
+    }
+
+    fn record_input(&self, kind: RecordKind, input: &impl Encode) {
+        let Some(recorder) = &self.recorder else {
+            return;
+        };
+
+        match encode_vec(input) {
+            Ok(data) => recorder.record(kind, &data),
+            Err(error) => warn!(?error, "Failed to encode the recorded input"),
+        }
+    }
+
+    fn get_svc_processor<T: SvcProcessor + 'static>(&mut self) -> Option<&mut T> {
+        self.static_channels
+            .get_by_type_mut::<T>()
+            .and_then(|svc| svc.channel_processor_downcast_mut())
+    }
+
+    fn get_channel_id_by_type<T: SvcProcessor + 'static>(&self) -> Option<StaticChannelId> {
+        self.static_channels.get_channel_id_by_type::<T>()
+    }
 }
 
 #[derive(Debug)]
 pub enum ServerEvent {
+    /// Disconnects the client.
     Quit(String),
+    /// Disconnects the client and stops the server, see [`RdpServerHandle::shutdown`].
+    Shutdown,
     Clipboard(ClipboardMessage),
     Rdpsnd(RdpsndServerMessage),
+    Rdpdr(RdpdrServerMessage),
     SetCredentials(Credentials),
+    /// Redirects the client to another server, see [`RdpServerSessions::redirect`].
+    Redirect(Box<ServerRedirectionPdu>),
     GetLocalAddr(oneshot::Sender<Option<SocketAddr>>),
 }
 
@@ -218,10 +487,11 @@ enum RunState {
 impl RdpServer {
     pub fn new(
         opts: RdpServerOptions,
-        handler: Box<dyn RdpServerInputHandler>,
-        display: Box<dyn RdpServerDisplay>,
+        handler: Box<dyn DynRdpServerInputHandler>,
+        display: Box<dyn DynRdpServerDisplay>,
         mut sound_factory: Option<Box<dyn SoundServerFactory>>,
         mut cliprdr_factory: Option<Box<dyn CliprdrServerFactory>>,
+        h264_factory: Option<Box<dyn H264EncoderFactory>>,
     ) -> Self {
         let (ev_sender, ev_receiver) = ServerEvent::create_channel();
         if let Some(cliprdr) = cliprdr_factory.as_mut() {
@@ -234,13 +504,32 @@ impl RdpServer {
             opts,
             handler: Arc::new(Mutex::new(handler)),
             display: Arc::new(Mutex::new(display)),
-            static_channels: StaticChannelSet::new(),
+            custom_channels: CustomChannels::default(),
             sound_factory,
             cliprdr_factory,
+            audio_input_factory: None,
+            drive_factory: None,
+            rail_factory: None,
+            h264_factory: h264_factory.map(Arc::from),
             ev_sender,
             ev_receiver: Arc::new(Mutex::new(ev_receiver)),
             creds: None,
+            credentials_validator: None,
+            capabilities_hook: None,
+            authorizer: None,
+            event_handler: None,
+            auditor: None,
+            metrics: None,
+            quality_policy: None,
+            reconnect_handler: None,
+            auto_reconnect: AutoReconnectCookies::default(),
+            input_limiter: InputLimiter::new(InputLimits::default()),
+            listener: None,
+            session_factory: None,
+            sessions: RdpServerSessions::default(),
             local_addr: None,
+            shutting_down: false,
+            svc_buf: WriteBuf::new(),
         }
     }
 
@@ -252,11 +541,17 @@ impl RdpServer {
         &self.ev_sender
     }
 
-    fn attach_channels(&mut self, acceptor: &mut Acceptor) {
+    fn attach_channels(&mut self, conn: &mut Connection, acceptor: &mut Acceptor) {
         if let Some(cliprdr_factory) = self.cliprdr_factory.as_deref() {
             let backend = cliprdr_factory.build_cliprdr_backend();
 
-            let cliprdr = CliprdrServer::new(backend);
+            let mut cliprdr = CliprdrServer::new(backend);
+            let policy = cliprdr_factory.build_cliprdr_policy();
+            if let Some(audit) = &conn.audit {
+                cliprdr = cliprdr.with_policy(Box::new(AuditClipboardPolicy::new(policy, audit.clone())));
+            } else if let Some(policy) = policy {
+                cliprdr = cliprdr.with_policy(policy);
+            }
 
             acceptor.attach_static_channel(cliprdr);
         }
@@ -267,23 +562,124 @@ impl RdpServer {
             acceptor.attach_static_channel(RdpsndServer::new(backend));
         }
 
-        let dcs_backend = DisplayControlBackend::new(Arc::clone(&self.display));
-        let dvc = dvc::DrdynvcServer::new()
-            .with_dynamic_channel(AInputHandler {
-                handler: Arc::clone(&self.handler),
-            })
+        if let Some(factory) = self.drive_factory.as_deref() {
+            acceptor.attach_static_channel(RdpdrServer::new(factory.build_backend()));
+        }
+
+        if let Some(factory) = self.rail_factory.as_deref() {
+            acceptor.attach_static_channel(RailServer::new(factory.build_backend()));
+        }
+
+        self.custom_channels.attach_static(acceptor);
+
+        let (layout_sender, layout_receiver) = mpsc::unbounded_channel();
+        conn.layout_receiver = Arc::new(Mutex::new(layout_receiver));
+        let dcs_backend = DisplayControlBackend::new(Arc::clone(&self.display), layout_sender);
+        let (ainput_sender, ainput_receiver) = mpsc::unbounded_channel();
+        conn.ainput_receiver = ainput_receiver;
+        let mut dvc = dvc::DrdynvcServer::new()
+            .with_dynamic_channel(AInputHandler { sender: ainput_sender })
             .with_dynamic_channel(DisplayControlServer::new(Box::new(dcs_backend)));
+
+        if let Some(factory) = self.audio_input_factory.as_deref() {
+            dvc = dvc.with_dynamic_channel(AudioInputServer::new(factory.build_backend()));
+        }
+
+        if self.h264_factory.is_some() {
+            let state = SharedGfxState::default();
+            dvc = dvc.with_dynamic_channel(GfxServer::new(
+                Arc::clone(&state),
+                self.opts.with_avc444,
+                conn.frames.clone(),
+                self.opts.max_unacknowledged_frames,
+            ));
+            conn.gfx_state = Some(state);
+        }
+
+        let dvc = self.custom_channels.attach_dynamic(dvc);
         acceptor.attach_static_channel(dvc);
     }
 
-    pub async fn run_connection(&mut self, stream: TcpStream) -> Result<()> {
+    /// Runs a connection accepted by the caller, until the client disconnects.
+    ///
+    /// This allows accepting the connections outside of [`RdpServer::run`], for instance from a
+    /// custom accept loop. Any transport can be used, e.g. a TCP stream or a Unix domain socket.
+    pub async fn run_connection<S>(&mut self, stream: S, peer: PeerInfo) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static,
+    {
+        let stats = StatsRecorder::new(self.metrics.clone());
+        self.serve_connection(stream, peer, stats).await
+    }
+
+    /// Runs a connection, recording its statistics with the given recorder.
+    async fn serve_connection<S>(&mut self, stream: S, peer: PeerInfo, stats: StatsRecorder) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static,
+    {
+        let mut info = ConnectionInfo::new(peer);
+        if let Some(handler) = &self.event_handler {
+            if !handler.connected(&info) {
+                info!(peer = ?info.peer, "Connection refused");
+                return Ok(());
+            }
+        }
+
+        let mut conn = Connection::new(stats);
+        conn.audit = self
+            .auditor
+            .as_ref()
+            .map(|auditor| ConnectionAudit::new(Arc::clone(auditor), &info));
+        if let Some(recording) = &self.opts.recording {
+            let (recorder, path) = SessionRecorder::create(&recording.directory, &info.peer)
+                .context("failed to create the session recording")?;
+            info!(path = %path.display(), "Recording session");
+            conn.recorder = Some(Arc::new(recorder));
+        }
+
+        self.input_limiter = InputLimiter::new(self.opts.input_limits);
+        let res = self.accept_connection(&mut conn, stream, &mut info).await;
+
+        if let Some(handler) = &self.event_handler {
+            handler.disconnected(&info, res.as_ref().err());
+        }
+        conn.audit(
+            &info,
+            AuditEvent::Disconnected {
+                error: res.as_ref().err(),
+            },
+        );
+        res
+    }
+
+    async fn accept_connection<S>(&mut self, conn: &mut Connection, stream: S, info: &mut ConnectionInfo) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static,
+    {
+        let peer = info.peer.clone();
+        debug!(?peer, "Accepting connection");
+
         let framed = TokioFramed::new(stream);
 
-        let size = self.display.lock().await.size().await;
-        let capabilities = capabilities::capabilities(&self.opts, size);
+        let (size, monitors) = {
+            let mut display = self.display.lock().await;
+            (display.size().await, display.monitor_layout().await)
+        };
+        let capabilities = capabilities::capabilities(&self.opts, size, self.rail_factory.is_some());
         let mut acceptor = Acceptor::new(self.opts.security.flag(), size, capabilities, self.creds.clone());
+        acceptor.set_monitor_layout(monitors);
+        acceptor.set_security_policy(self.opts.security_policy.clone());
+        if let Some(validator) = &self.credentials_validator {
+            acceptor.set_credentials_validator(Arc::clone(validator));
+        }
+        if let Some(hook) = &self.capabilities_hook {
+            acceptor.set_capabilities_hook(Arc::clone(hook));
+        }
+        if let RdpServerSecurity::Rdp(key) = &self.opts.security {
+            acceptor.set_rdp_security_key(key.clone());
+        }
 
-        self.attach_channels(&mut acceptor);
+        self.attach_channels(conn, &mut acceptor);
 
         let res = ironrdp_acceptor::accept_begin(framed, &mut acceptor)
             .await
@@ -291,42 +687,93 @@ impl RdpServer {
 
         match res {
             BeginResult::ShouldUpgrade(stream) => {
-                let tls_acceptor = match &self.opts.security {
-                    RdpServerSecurity::Tls(acceptor) => acceptor,
-                    RdpServerSecurity::Hybrid((acceptor, _)) => acceptor,
-                    RdpServerSecurity::None => unreachable!(),
-                };
-                let accept = match tls_acceptor.accept(stream).await {
-                    Ok(accept) => accept,
-                    Err(e) => {
-                        warn!("Failed to TLS accept: {}", e);
-                        return Ok(());
+                let (accept, pub_key) = match &self.opts.security {
+                    RdpServerSecurity::CustomTls(acceptor) | RdpServerSecurity::CustomHybrid(acceptor) => {
+                        let (accept, pub_key) = match acceptor.accept(Box::new(stream)).await {
+                            Ok(accept) => accept,
+                            Err(e) => {
+                                warn!("Failed to TLS accept: {}", e);
+                                return Ok(());
+                            }
+                        };
+                        let hybrid = matches!(self.opts.security, RdpServerSecurity::CustomHybrid(_));
+                        (accept, hybrid.then_some(pub_key))
+                    }
+                    _ => {
+                        let tls_acceptor = match &self.opts.security {
+                            RdpServerSecurity::Tls(acceptor) => acceptor.clone(),
+                            RdpServerSecurity::Hybrid((acceptor, _)) => acceptor.clone(),
+                            RdpServerSecurity::HybridCertificates(certificates) => certificates.acceptor(),
+                            RdpServerSecurity::None
+                            | RdpServerSecurity::Rdp(_)
+                            | RdpServerSecurity::CustomTls(_)
+                            | RdpServerSecurity::CustomHybrid(_) => unreachable!(),
+                        };
+                        let accept = match tls_acceptor.accept(stream).await {
+                            Ok(accept) => accept,
+                            Err(e) => {
+                                warn!("Failed to TLS accept: {}", e);
+                                return Ok(());
+                            }
+                        };
+                        let connection = accept.get_ref().1;
+                        if let (Some(version), Some(cipher_suite)) =
+                            (connection.protocol_version(), connection.negotiated_cipher_suite())
+                        {
+                            acceptor
+                                .check_tls(tls_version(version), u16::from(cipher_suite.suite()))
+                                .context("TLS connection refused")?;
+                        }
+                        info.client_certificate = connection
+                            .peer_certificates()
+                            .and_then(|certificates| certificates.first())
+                            .map(|certificate| certificate.to_vec());
+
+                        let pub_key = match &self.opts.security {
+                            RdpServerSecurity::Hybrid((_, pub_key)) => Some(pub_key.clone()),
+                            RdpServerSecurity::HybridCertificates(certificates) => {
+                                // The same certificate as the one of the handshake, unless replaced in the meantime.
+                                let server_name = accept.get_ref().1.server_name();
+                                Some(
+                                    certificates
+                                        .public_key(server_name)
+                                        .context("no TLS certificate for the server name")?,
+                                )
+                            }
+                            _ => None,
+                        };
+                        (Box::new(accept) as Box<dyn RdpServerStream>, pub_key)
                     }
                 };
                 let mut framed = TokioFramed::new(accept);
 
                 acceptor.mark_security_upgrade_as_done();
 
-                if let RdpServerSecurity::Hybrid((_, pub_key)) = &self.opts.security {
+                if let Some(pub_key) = pub_key {
                     // how to get the client name?
                     // doesn't seem to matter yet
-                    let client_name = framed.get_inner().0.get_ref().0.peer_addr()?.to_string();
-
-                    ironrdp_acceptor::accept_credssp(
+                    let res = ironrdp_acceptor::accept_credssp(
                         &mut framed,
                         &mut acceptor,
-                        client_name.into(),
-                        pub_key.clone(),
+                        peer.client_name().into(),
+                        pub_key,
                         None,
                     )
-                    .await?;
+                    .await;
+                    if res.is_err() {
+                        if let Some(handler) = &self.event_handler {
+                            handler.authenticated(info, false);
+                        }
+                        conn.audit(info, AuditEvent::Authentication { success: false });
+                    }
+                    res?;
                 }
 
-                self.accept_finalize(framed, acceptor).await?;
+                self.accept_finalize(conn, framed, acceptor, info).await?;
             }
 
             BeginResult::Continue(framed) => {
-                self.accept_finalize(framed, acceptor).await?;
+                self.accept_finalize(conn, framed, acceptor, info).await?;
             }
         };
 
@@ -334,40 +781,42 @@ impl RdpServer {
     }
 
     pub async fn run(&mut self) -> Result<()> {
-        let listener = TcpListener::bind(self.opts.addr).await?;
-        let local_addr = listener.local_addr()?;
+        let mut listener = match self.listener.take() {
+            Some(listener) => listener,
+            None => Box::new(TcpListener::bind(self.opts.addr).await?),
+        };
+        let local_addr = listener.local_addr();
+
+        debug!(?local_addr, "Listening for connections");
+        self.local_addr = local_addr;
 
-        debug!("Listening for connections on {local_addr}");
-        self.local_addr = Some(local_addr);
+        if let Some(factory) = self.session_factory.take() {
+            let res = self.run_sessions(listener, factory).await;
+            self.sessions.clear();
+            return res;
+        }
 
         loop {
             let ev_receiver = Arc::clone(&self.ev_receiver);
             let mut ev_receiver = ev_receiver.lock().await;
             tokio::select! {
                 Some(event) = ev_receiver.recv() => {
-                    match event {
-                        ServerEvent::Quit(reason) => {
-                            debug!("Got quit event {reason}");
-                            break;
-                        }
-                        ServerEvent::GetLocalAddr(tx) => {
-                            let _ = tx.send(self.local_addr);
-                        }
-                        ServerEvent::SetCredentials(creds) => {
-                            self.set_credentials(Some(creds));
-                        }
-                        ev => {
-                            debug!("Unexpected event {:?}", ev);
-                        }
+                    if self.handle_listener_event(event) {
+                        break;
                     }
                 },
                 Ok((stream, peer)) = listener.accept() => {
                     debug!(?peer, "Received connection");
                     drop(ev_receiver);
-                    if let Err(error) = self.run_connection(stream).await {
+                    let stats = StatsRecorder::new(self.metrics.clone());
+                    let id = self.sessions.register(peer.clone(), self.ev_sender.clone(), stats.clone());
+                    if let Err(error) = self.serve_connection(stream, peer, stats).await {
                         error!(?error, "Connection error");
                     }
-                    self.static_channels = StaticChannelSet::new();
+                    self.sessions.unregister(id);
+                    if self.shutting_down {
+                        break;
+                    }
                 }
                 else => break,
             }
@@ -376,18 +825,138 @@ impl RdpServer {
         Ok(())
     }
 
-    pub fn get_svc_processor<T: SvcProcessor + 'static>(&mut self) -> Option<&mut T> {
-        self.static_channels
-            .get_by_type_mut::<T>()
-            .and_then(|svc| svc.channel_processor_downcast_mut())
+    /// Runs the sessions concurrently, each one with the handlers created by the session factory.
+    async fn run_sessions(
+        &mut self,
+        mut listener: Box<dyn RdpServerListener>,
+        mut factory: Box<dyn RdpServerSessionFactory>,
+    ) -> Result<()> {
+        // The client loop is not `Send`, the sessions are therefore running on the current thread.
+        let local = task::LocalSet::new();
+
+        local
+            .run_until(async {
+                let ev_receiver = Arc::clone(&self.ev_receiver);
+                let mut ev_receiver = ev_receiver.lock().await;
+
+                loop {
+                    tokio::select! {
+                        Some(event) = ev_receiver.recv() => {
+                            if self.handle_listener_event(event) {
+                                break;
+                            }
+                        },
+                        Ok((stream, peer)) = listener.accept() => {
+                            debug!(?peer, "Received connection");
+                            if !self.opts.limits.allows(&peer, &self.sessions.list()) {
+                                warn!(?peer, "Connection limit reached, closing the connection");
+                                continue;
+                            }
+
+                            let session = match factory.new_session(&peer) {
+                                Ok(session) => session,
+                                Err(error) => {
+                                    warn!(?error, ?peer, "Session refused");
+                                    continue;
+                                }
+                            };
+
+                            let mut server = self.session_server(session);
+                            let sessions = self.sessions.clone();
+                            let stats = StatsRecorder::new(self.metrics.clone());
+                            let id = sessions.register(peer.clone(), server.ev_sender.clone(), stats.clone());
+
+                            task::spawn_local(async move {
+                                info!(%id, ?peer, "Session started");
+                                if let Err(error) = server.serve_connection(stream, peer, stats).await {
+                                    error!(%id, ?error, "Connection error");
+                                }
+                                sessions.unregister(id);
+                                info!(%id, "Session ended");
+                            });
+                        }
+                        else => break,
+                    }
+                }
+            })
+            .await;
+
+        if self.shutting_down {
+            // The sessions received the shutdown event, let them disconnect their clients.
+            if tokio::time::timeout(SHUTDOWN_TIMEOUT, local).await.is_err() {
+                warn!("Sessions still running after shutdown timeout");
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn get_channel_id_by_type<T: SvcProcessor + 'static>(&self) -> Option<StaticChannelId> {
-        self.static_channels.get_channel_id_by_type::<T>()
+    /// Creates the server running a session, sharing the configuration of this server.
+    fn session_server(&self, session: RdpServerSession) -> RdpServer {
+        let mut opts = self.opts.clone();
+        if let Some(security) = session.security {
+            opts.security = security;
+        }
+        if let Some(limits) = session.input_limits {
+            opts.input_limits = limits;
+        }
+
+        let mut server = RdpServer::new(
+            opts,
+            session.handler,
+            session.display,
+            session.sound_factory,
+            session.cliprdr_factory,
+            None,
+        );
+        server.audio_input_factory = session.audio_input_factory;
+        server.set_drive_factory(session.drive_factory);
+        server.rail_factory = session.rail_factory;
+        server.h264_factory = self.h264_factory.clone();
+        server.creds = self.creds.clone();
+        server.credentials_validator = self.credentials_validator.clone();
+        server.capabilities_hook = self.capabilities_hook.clone();
+        server.authorizer = self.authorizer.clone();
+        server.event_handler = self.event_handler.clone();
+        server.auditor = self.auditor.clone();
+        server.metrics = self.metrics.clone();
+        server.quality_policy = self.quality_policy.clone();
+        server.reconnect_handler = self.reconnect_handler.clone();
+        server.auto_reconnect = self.auto_reconnect.clone();
+        server.custom_channels = self.custom_channels.clone();
+        server.local_addr = self.local_addr;
+        server
+    }
+
+    /// Handles an event received while waiting for connections, returning `true` if the server must stop.
+    fn handle_listener_event(&mut self, event: ServerEvent) -> bool {
+        match event {
+            ServerEvent::Quit(reason) => {
+                debug!("Got quit event {reason}");
+                return true;
+            }
+            ServerEvent::Shutdown => {
+                debug!("Got shutdown event");
+                self.shutting_down = true;
+                return true;
+            }
+            ServerEvent::GetLocalAddr(tx) => {
+                let _ = tx.send(self.local_addr);
+            }
+            ServerEvent::SetCredentials(creds) => {
+                self.set_credentials(Some(creds));
+            }
+            ev => {
+                debug!("Unexpected event {:?}", ev);
+            }
+        }
+
+        false
     }
 
     async fn dispatch_pdu(
         &mut self,
+        conn: &mut Connection,
         action: Action,
         bytes: bytes::BytesMut,
         writer: &mut impl FramedWrite,
@@ -397,12 +966,12 @@ impl RdpServer {
         match action {
             Action::FastPath => {
                 let input = decode(&bytes)?;
-                self.handle_fastpath(input).await;
+                self.handle_fastpath(conn, input).await;
             }
 
             Action::X224 => {
                 if self
-                    .handle_x224(writer, io_channel_id, user_channel_id, &bytes)
+                    .handle_x224(conn, writer, io_channel_id, user_channel_id, &bytes)
                     .await
                     .context("X224 input error")?
                 {
@@ -422,14 +991,63 @@ impl RdpServer {
         io_channel_id: u16,
         buffer: &mut Vec<u8>,
         mut encoder: UpdateEncoder,
+        gfx: &mut Option<GfxHandler>,
+        stats: &StatsRecorder,
     ) -> Result<(RunState, UpdateEncoder)> {
         if let DisplayUpdate::Resize(desktop_size) = update {
             debug!(?desktop_size, "Display resize");
             encoder.set_desktop_size(desktop_size);
+
+            // The graphics pipeline surface is resized without reactivating the session.
+            if let Some(handler) = gfx.as_mut().filter(|handler| handler.is_active()) {
+                handler.resize(desktop_size);
+                return Ok((RunState::Continue, encoder));
+            }
+
             deactivate_all(io_channel_id, user_channel_id, writer).await?;
             return Ok((RunState::DeactivationReactivation { desktop_size }, encoder));
         }
 
+        if let DisplayUpdate::Bitmap(bitmap) = &update {
+            if let Some(mut handler) = gfx.take() {
+                let bitmap = bitmap.clone();
+                let (res, handler) = task::spawn_blocking(move || {
+                    time_warn!(
+                        "Encoding graphics pipeline frame",
+                        10,
+                        (handler.handle(&bitmap), handler)
+                    )
+                })
+                .await
+                .context("graphics pipeline encoding task failed")?;
+                let handler = gfx.insert(handler);
+
+                if let Some(pdus) = res.context("error while encoding graphics pipeline frame")? {
+                    // The fast-path output is not used while the graphics pipeline is.
+                    encoder.reset_damage();
+                    if pdus.is_empty() {
+                        return Ok((RunState::Continue, encoder));
+                    }
+
+                    // Compressed only now, so the frames and the responses of the channel processor are written
+                    // in the order they are compressed.
+                    let Some(data) = handler
+                        .encode(&pdus)
+                        .context("failed to compress graphics pipeline frame")?
+                    else {
+                        return Ok((RunState::Continue, encoder));
+                    };
+                    writer
+                        .write_all(&data)
+                        .await
+                        .context("failed to write graphics pipeline frame")?;
+                    stats.frame_encoded(FrameCodec::H264);
+                    return Ok((RunState::Continue, encoder));
+                }
+            }
+        }
+
+        let is_bitmap = matches!(update, DisplayUpdate::Bitmap(_));
         let mut encoder_iter = encoder.update(update);
         loop {
             let Some(fragmenter) = encoder_iter.next().await else {
@@ -437,25 +1055,55 @@ impl RdpServer {
             };
 
             let mut fragmenter = fragmenter.context("error while encoding")?;
-            if fragmenter.size_hint() > buffer.len() {
-                buffer.resize(fragmenter.size_hint(), 0);
-            }
 
-            while let Some(len) = fragmenter.next(buffer) {
+            // Only the headers are encoded into the buffer, the data of the update is not copied.
+            while let Some((len, data)) = fragmenter.next_vectored(buffer) {
                 writer
-                    .write_all(&buffer[..len])
+                    .write_all_vectored(&[&buffer[..len], data])
                     .await
                     .context("failed to write display update")?;
             }
+
+            encoder_iter.recycle(fragmenter);
+        }
+
+        if is_bitmap {
+            stats.frame_encoded(encoder.codec());
         }
 
         Ok((RunState::Continue, encoder))
     }
 
+    /// Redirects the client to another server, which then disconnects.
+    async fn redirect(
+        &self,
+        conn: &Connection,
+        io_channel_id: u16,
+        user_channel_id: u16,
+        redirection: ServerRedirectionPdu,
+        writer: &mut impl FramedWrite,
+    ) -> Result<()> {
+        if conn.rdp_security.is_some() {
+            warn!("Server redirection is not supported with Standard RDP Security, disconnecting the client");
+            return disconnect(
+                io_channel_id,
+                user_channel_id,
+                RPC_INITIATED_DISCONNECT,
+                conn.compression.as_ref(),
+                writer,
+            )
+            .await;
+        }
+
+        send_server_redirection(io_channel_id, user_channel_id, redirection, writer).await
+    }
+
     async fn dispatch_server_events(
         &mut self,
+        conn: &mut Connection,
         events: &mut Vec<ServerEvent>,
         writer: &mut impl FramedWrite,
+        io_channel_id: u16,
         user_channel_id: u16,
     ) -> Result<RunState> {
         // Avoid wave message queuing up and causing extra delays.
@@ -467,6 +1115,27 @@ impl RdpServer {
             match event {
                 ServerEvent::Quit(reason) => {
                     debug!("Got quit event: {reason}");
+                    disconnect(
+                        io_channel_id,
+                        user_channel_id,
+                        RPC_INITIATED_DISCONNECT,
+                        conn.compression.as_ref(),
+                        writer,
+                    )
+                    .await?;
+                    return Ok(RunState::Disconnect);
+                }
+                ServerEvent::Shutdown => {
+                    debug!("Got shutdown event");
+                    self.shutting_down = true;
+                    disconnect(
+                        io_channel_id,
+                        user_channel_id,
+                        RPC_INITIATED_DISCONNECT,
+                        conn.compression.as_ref(),
+                        writer,
+                    )
+                    .await?;
                     return Ok(RunState::Disconnect);
                 }
                 ServerEvent::GetLocalAddr(tx) => {
@@ -475,8 +1144,14 @@ impl RdpServer {
                 ServerEvent::SetCredentials(creds) => {
                     self.set_credentials(Some(creds));
                 }
+                ServerEvent::Redirect(redirection) => {
+                    debug!(?redirection, "Got redirect event");
+                    self.redirect(conn, io_channel_id, user_channel_id, *redirection, writer)
+                        .await?;
+                    return Ok(RunState::Disconnect);
+                }
                 ServerEvent::Rdpsnd(s) => {
-                    let Some(rdpsnd) = self.get_svc_processor::<RdpsndServer>() else {
+                    let Some(rdpsnd) = conn.get_svc_processor::<RdpsndServer>() else {
                         warn!("No rdpsnd channel, dropping event");
                         continue;
                     };
@@ -497,14 +1172,63 @@ impl RdpServer {
                         }
                     }
                     .context("failed to send rdpsnd event")?;
-                    let channel_id = self
+                    let channel_id = conn
                         .get_channel_id_by_type::<RdpsndServer>()
                         .ok_or_else(|| anyhow!("SVC channel not found"))?;
-                    let data = server_encode_svc_messages(msgs.into(), channel_id, user_channel_id)?;
-                    writer.write_all(&data).await?;
+                    self.svc_buf.clear();
+                    Compression::encode_svc_messages(
+                        conn.compression.as_ref(),
+                        msgs.into(),
+                        channel_id,
+                        user_channel_id,
+                        &mut self.svc_buf,
+                    )?;
+                    writer.write_all(self.svc_buf.filled()).await?;
+                }
+                ServerEvent::Rdpdr(RdpdrServerMessage::DriveRequest {
+                    device_id,
+                    completion_id,
+                    request,
+                }) => {
+                    if let (
+                        Some(audit),
+                        DriveRequest::Create {
+                            path,
+                            desired_access,
+                            create_disposition,
+                            ..
+                        },
+                    ) = (&conn.audit, &request)
+                    {
+                        audit.report(AuditEvent::DriveAccess {
+                            device_id,
+                            path,
+                            desired_access: desired_access.clone(),
+                            create_disposition: create_disposition.clone(),
+                        });
+                    }
+                    let Some(rdpdr) = conn.get_svc_processor::<RdpdrServer>() else {
+                        warn!("No rdpdr channel, dropping event");
+                        continue;
+                    };
+                    let msgs = rdpdr
+                        .drive_request(device_id, completion_id, request)
+                        .context("failed to send drive request")?;
+                    let channel_id = conn
+                        .get_channel_id_by_type::<RdpdrServer>()
+                        .ok_or_else(|| anyhow!("SVC channel not found"))?;
+                    self.svc_buf.clear();
+                    Compression::encode_svc_messages(
+                        conn.compression.as_ref(),
+                        msgs.into(),
+                        channel_id,
+                        user_channel_id,
+                        &mut self.svc_buf,
+                    )?;
+                    writer.write_all(self.svc_buf.filled()).await?;
                 }
                 ServerEvent::Clipboard(c) => {
-                    let Some(cliprdr) = self.get_svc_processor::<CliprdrServer>() else {
+                    let Some(cliprdr) = conn.get_svc_processor::<CliprdrServer>() else {
                         warn!("No clipboard channel, dropping event");
                         continue;
                     };
@@ -512,17 +1236,28 @@ impl RdpServer {
                         ClipboardMessage::SendInitiateCopy(formats) => cliprdr.initiate_copy(&formats),
                         ClipboardMessage::SendFormatData(data) => cliprdr.submit_format_data(data),
                         ClipboardMessage::SendInitiatePaste(format) => cliprdr.initiate_paste(format),
+                        ClipboardMessage::SendFileContents(response) => cliprdr.submit_file_contents(response),
+                        ClipboardMessage::SendFileContentsRequest(request) => cliprdr.request_file_contents(request),
+                        ClipboardMessage::SendLockClipboard(data_id) => cliprdr.lock_clipboard(data_id),
+                        ClipboardMessage::SendUnlockClipboard(data_id) => cliprdr.unlock_clipboard(data_id),
                         ClipboardMessage::Error(error) => {
                             error!(?error, "Handling clipboard event");
                             continue;
                         }
                     }
                     .context("failed to send clipboard event")?;
-                    let channel_id = self
+                    let channel_id = conn
                         .get_channel_id_by_type::<CliprdrServer>()
                         .ok_or_else(|| anyhow!("SVC channel not found"))?;
-                    let data = server_encode_svc_messages(msgs.into(), channel_id, user_channel_id)?;
-                    writer.write_all(&data).await?;
+                    self.svc_buf.clear();
+                    Compression::encode_svc_messages(
+                        conn.compression.as_ref(),
+                        msgs.into(),
+                        channel_id,
+                        user_channel_id,
+                        &mut self.svc_buf,
+                    )?;
+                    writer.write_all(self.svc_buf.filled()).await?;
                 }
             }
         }
@@ -532,11 +1267,13 @@ impl RdpServer {
 
     async fn client_loop<R, W>(
         &mut self,
+        conn: &mut Connection,
         reader: &mut Framed<R>,
-        writer: &mut Framed<W>,
+        writer: &mut W,
         io_channel_id: u16,
         user_channel_id: u16,
         mut encoder: UpdateEncoder,
+        mut gfx: Option<GfxHandler>,
     ) -> Result<RunState>
     where
         R: FramedRead,
@@ -544,19 +1281,53 @@ impl RdpServer {
     {
         debug!("Starting client loop");
         let mut display_updates = self.display.lock().await.updates().await?;
-        let mut writer = SharedWriter::new(writer);
-        let mut display_writer = writer.clone();
+        let display = Arc::clone(&self.display);
+        let layout_receiver = Arc::clone(&conn.layout_receiver);
+        let stats = conn.stats.clone();
+        let frames = conn.frames.clone();
+        let mut output_suppressed = conn.output_suppressed.subscribe();
+        let mut quality = self.quality_policy.clone().map(|policy| {
+            let quality = EncodingQuality {
+                remote_fx: self.opts.remote_fx_quality,
+                h264_bitrate: None,
+                frame_interval: Duration::ZERO,
+            };
+            QualityController::new(policy, quality)
+        });
+        let limits = self.opts.limits.clone();
+        let mut writer = SharedWriter::new(writer, stats.clone());
+        let mut display_writer = RecordingWriter::new(writer.clone(), conn.recorder.clone());
         let mut event_writer = writer.clone();
+        let mut timeout_writer = writer.clone();
+        let mut heartbeat_writer = writer.clone();
+        let heartbeat = self.opts.heartbeat;
+        // The heartbeats are not encrypted, so not sent with Standard RDP Security.
+        let send_heartbeats = conn.client_heartbeat && conn.rdp_security.is_none();
         let ev_receiver = Arc::clone(&self.ev_receiver);
-        let s = Rc::new(Mutex::new(self));
+        let rdp_security = conn.rdp_security.clone();
+        let compression = conn.compression.clone();
+        let s = Rc::new(Mutex::new((self, conn)));
 
         let this = Rc::clone(&s);
+        let pdu_stats = stats.clone();
+        let timeout_stats = stats.clone();
+        let heartbeat_stats = stats.clone();
         let dispatch_pdu = async move {
+            let mut sequence: u64 = 0;
             loop {
-                let (action, bytes) = reader.read_pdu().await?;
-                let mut this = this.lock().await;
+                let (action, mut bytes) = reader.read_pdu().await?;
+                pdu_stats.bytes_received(bytes.len());
+                let span = debug_span!("pdu", pdu.sequence = sequence, pdu.action = ?action, pdu.size = bytes.len());
+                sequence += 1;
+                if let Some(security) = &rdp_security {
+                    let decrypted = security.lock().expect("poisoned").decrypt_frame(&bytes)?;
+                    bytes = bytes::BytesMut::from(decrypted.as_slice());
+                }
+                let mut guard = this.lock().await;
+                let (this, conn) = &mut *guard;
                 match this
-                    .dispatch_pdu(action, bytes, &mut writer, io_channel_id, user_channel_id)
+                    .dispatch_pdu(conn, action, bytes, &mut writer, io_channel_id, user_channel_id)
+                    .instrument(span)
                     .await?
                 {
                     RunState::Continue => continue,
@@ -567,8 +1338,54 @@ impl RdpServer {
 
         let dispatch_display = async move {
             let mut buffer = vec![0u8; 4096];
+            let mut layout_receiver = layout_receiver.lock().await;
             loop {
-                if let Some(update) = display_updates.next_update().await {
+                let update = tokio::select! {
+                    update = display_updates.next() => update,
+                    Some(layout) = layout_receiver.recv() => {
+                        let Some(size) = layout_desktop_size(&layout) else {
+                            warn!(?layout, "Invalid monitor layout");
+                            continue;
+                        };
+                        debug!(?size, "Client requested display resize");
+                        match display.lock().await.resize(size).await {
+                            Some(size) => Some(DisplayUpdate::Resize(size)),
+                            None => continue,
+                        }
+                    }
+                    Ok(()) = output_suppressed.changed() => {
+                        if *output_suppressed.borrow_and_update() {
+                            debug!("Display updates suppressed");
+                        } else {
+                            // The client discarded its content, so the whole desktop is sent again.
+                            debug!("Display updates resumed, refreshing the display");
+                            encoder.reset_damage();
+                            display.lock().await.request_refresh();
+                        }
+                        continue;
+                    }
+                };
+
+                if let Some(update) = update {
+                    if matches!(update, DisplayUpdate::Bitmap(_)) && *output_suppressed.borrow() {
+                        trace!("Display updates suppressed, dropping bitmap");
+                        continue;
+                    }
+
+                    if matches!(update, DisplayUpdate::Bitmap(_)) {
+                        frames.ready().await;
+
+                        if let Some(quality) = quality.as_mut() {
+                            if let Some(adapted) = quality.adapt(frames.feedback()) {
+                                encoder.set_remote_fx_quality(adapted.remote_fx);
+                                if let (Some(handler), Some(bitrate)) = (gfx.as_mut(), adapted.h264_bitrate) {
+                                    handler.set_bitrate(bitrate);
+                                }
+                            }
+                            quality.throttle().await;
+                        }
+                    }
+
                     match Self::dispatch_display_update(
                         update,
                         &mut display_writer,
@@ -576,6 +1393,8 @@ impl RdpServer {
                         io_channel_id,
                         &mut buffer,
                         encoder,
+                        &mut gfx,
+                        &stats,
                     )
                     .await?
                     {
@@ -606,9 +1425,10 @@ impl RdpServer {
                 while let Ok(ev) = ev_receiver.try_recv() {
                     events.push(ev);
                 }
-                let mut this = this.lock().await;
+                let mut guard = this.lock().await;
+                let (this, conn) = &mut *guard;
                 match this
-                    .dispatch_server_events(&mut events, &mut event_writer, user_channel_id)
+                    .dispatch_server_events(conn, &mut events, &mut event_writer, io_channel_id, user_channel_id)
                     .await?
                 {
                     RunState::Continue => continue,
@@ -617,10 +1437,62 @@ impl RdpServer {
             }
         };
 
+        let dispatch_timeouts = async move {
+            loop {
+                let Some((remaining, reason)) = limits.next_timeout(&timeout_stats) else {
+                    break core::future::pending().await;
+                };
+                if remaining.is_zero() {
+                    info!(?reason, "Session timeout");
+                    let reason = ErrorInfo::ProtocolIndependentCode(reason);
+                    disconnect(
+                        io_channel_id,
+                        user_channel_id,
+                        reason,
+                        compression.as_ref(),
+                        &mut timeout_writer,
+                    )
+                    .await?;
+                    break Ok(RunState::Disconnect);
+                }
+                tokio::time::sleep(remaining).await;
+            }
+        };
+
+        let dispatch_heartbeats = async move {
+            let Some(options) = heartbeat else {
+                return core::future::pending().await;
+            };
+            let pdu = SendDataIndication {
+                initiator_id: user_channel_id,
+                channel_id: io_channel_id,
+                user_data: encode_vec(&options.pdu())?.into(),
+            };
+            let pdu = encode_vec(&X224(pdu))?;
+
+            let mut interval = tokio::time::interval(options.interval());
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+
+                let silence = heartbeat_stats.silence();
+                if options.max_silence().is_some_and(|max| silence >= max) {
+                    warn!(?silence, "Client unresponsive");
+                    break Err(anyhow::Error::new(ClientUnresponsive { silence }));
+                }
+
+                if send_heartbeats {
+                    heartbeat_writer.write_all(&pdu).await?;
+                }
+            }
+        };
+
         let state = tokio::select!(
             state = dispatch_pdu => state,
             state = dispatch_display => state,
             state = dispatch_events => state,
+            state = dispatch_timeouts => state,
+            state = dispatch_heartbeats => state,
         );
 
         debug!("End of client loop: {state:?}");
@@ -629,8 +1501,9 @@ impl RdpServer {
 
     async fn client_accepted<R, W>(
         &mut self,
+        conn: &mut Connection,
         reader: &mut Framed<R>,
-        writer: &mut Framed<W>,
+        writer: &mut W,
         result: AcceptorResult,
     ) -> Result<RunState>
     where
@@ -642,6 +1515,7 @@ impl RdpServer {
         if !result.input_events.is_empty() {
             debug!("Handling input event backlog from acceptor sequence");
             self.handle_input_backlog(
+                conn,
                 writer,
                 result.io_channel_id,
                 result.user_channel_id,
@@ -650,21 +1524,33 @@ impl RdpServer {
             .await?;
         }
 
-        self.static_channels = result.static_channels;
+        conn.static_channels = result.static_channels;
         if !result.reactivation {
-            for (_type_id, channel, channel_id) in self.static_channels.iter_mut() {
+            for (_type_id, channel, channel_id) in conn.static_channels.iter_mut() {
                 debug!(?channel, ?channel_id, "Start");
                 let Some(channel_id) = channel_id else {
                     continue;
                 };
                 let svc_responses = channel.start()?;
-                let response = server_encode_svc_messages(svc_responses, channel_id, result.user_channel_id)?;
-                writer.write_all(&response).await?;
+                self.svc_buf.clear();
+                Compression::encode_svc_messages(
+                    conn.compression.as_ref(),
+                    svc_responses,
+                    channel_id,
+                    result.user_channel_id,
+                    &mut self.svc_buf,
+                )?;
+                writer.write_all(self.svc_buf.filled()).await?;
             }
         }
 
         let mut rfxcodec = None;
         let mut surface_flags = CmdFlags::empty();
+        let mut color_depth = 32;
+        let mut pointer_cache_size = 0;
+        let mut large_pointer = false;
+        let mut window_orders = false;
+        let mut frame_acknowledge = None;
         for c in result.capabilities {
             match c {
                 CapabilitySet::General(c) => {
@@ -674,6 +1560,8 @@ impl RdpServer {
                     }
                 }
                 CapabilitySet::Bitmap(b) => {
+                    color_depth = b.pref_bits_per_pix;
+
                     if !b.desktop_resize_flag {
                         debug!("Desktop resize is not supported by the client");
                         continue;
@@ -683,7 +1571,21 @@ impl RdpServer {
                         width: b.desktop_width,
                         height: b.desktop_height,
                     };
-                    let display_size = self.display.lock().await.size().await;
+                    let mut display_size = self.display.lock().await.size().await;
+
+                    // The display may follow the size requested by the client, in which case the session is
+                    // reactivated with the new size. After a reactivation, the client already uses the
+                    // size of the display.
+                    if !result.reactivation && client_size != display_size {
+                        if let Some(size) = self.display.lock().await.resize(client_size).await {
+                            if size != client_size {
+                                debug!(?client_size, ?size, "Display resized");
+                                deactivate_all(result.io_channel_id, result.user_channel_id, writer).await?;
+                                return Ok(RunState::DeactivationReactivation { desktop_size: size });
+                            }
+                            display_size = size;
+                        }
+                    }
 
                     // It's problematic when the client didn't resize, as we send bitmap updates that don't fit.
                     // The client will likely drop the connection.
@@ -698,54 +1600,87 @@ impl RdpServer {
                 CapabilitySet::SurfaceCommands(c) => {
                     surface_flags = c.flags;
                 }
-                CapabilitySet::BitmapCodecs(BitmapCodecs(codecs)) => {
-                    for codec in codecs {
-                        match codec.property {
-                            // FIXME: The encoder operates in image mode only.
-                            //
-                            // See [MS-RDPRFX] 3.1.1.1 "State Machine" for
-                            // implementation of the video mode. which allows to
-                            // skip sending Header for each image.
-                            //
-                            // We should distinguish parameters for both modes,
-                            // and somehow choose the "best", instead of picking
-                            // the last parsed here.
-                            rdp::capability_sets::CodecProperty::RemoteFx(
-                                rdp::capability_sets::RemoteFxContainer::ClientContainer(c),
-                            ) if self.opts.with_remote_fx => {
-                                for caps in c.caps_data.0 .0 {
-                                    rfxcodec = Some((caps.entropy_bits, codec.id));
-                                }
-                            }
-                            rdp::capability_sets::CodecProperty::ImageRemoteFx(
-                                rdp::capability_sets::RemoteFxContainer::ClientContainer(c),
-                            ) if self.opts.with_remote_fx => {
-                                for caps in c.caps_data.0 .0 {
-                                    rfxcodec = Some((caps.entropy_bits, codec.id));
-                                }
-                            }
-                            rdp::capability_sets::CodecProperty::NsCodec(_) => (),
-                            _ => (),
-                        }
-                    }
+                CapabilitySet::Pointer(c) => {
+                    // The color and the new pointer updates are sharing the same cache indices.
+                    pointer_cache_size = c.pointer_cache_size.min(c.color_pointer_cache_size);
+                }
+                CapabilitySet::LargePointer(c) => {
+                    large_pointer = c.flags.contains(LargePointerSupportFlags::UP_TO_384X384_PIXELS);
+                }
+                CapabilitySet::WindowList(c) if self.rail_factory.is_some() => {
+                    // WndSupportLevel, TS_WINDOW_LEVEL_NOT_SUPPORTED when zero.
+                    window_orders = c.get(..4).is_some_and(|level| level.iter().any(|&b| b != 0));
+                    debug!(window_orders, "Window list capability");
+                }
+                CapabilitySet::FrameAcknowledge(c) => {
+                    frame_acknowledge = Some(c.max_unacknowledged_frame_count);
+                }
+                CapabilitySet::BitmapCodecs(BitmapCodecs(codecs)) if self.opts.with_remote_fx => {
+                    rfxcodec = rfx::negotiate(&codecs);
+                    debug!(?rfxcodec, "RemoteFX codec negotiated");
                 }
                 _ => {}
             }
         }
 
         let desktop_size = self.display.lock().await.size().await;
-        let encoder = UpdateEncoder::new(desktop_size, surface_flags, rfxcodec);
+        let mut encoder = UpdateEncoder::new(
+            desktop_size,
+            surface_flags,
+            rfxcodec,
+            self.opts.remote_fx_quality,
+            color_depth,
+            pointer_cache_size,
+            large_pointer,
+            window_orders,
+        );
+
+        // The frames are delimited with Frame Marker commands, so surface commands are required.
+        if let (Some(max), Some(client_max)) = (self.opts.max_unacknowledged_frames, frame_acknowledge) {
+            if surface_flags.contains(CmdFlags::FRAME_MARKER) && encoder.codec() != FrameCodec::Bitmap {
+                // Zero means the client doesn't limit the number of unacknowledged frames.
+                let max = if client_max == 0 { max } else { max.min(client_max) };
+                conn.frames.enable(max);
+                encoder.set_frame_tracker(conn.frames.clone());
+            }
+        }
+
+        let gfx = self.gfx_handler(conn, desktop_size, result.user_channel_id);
 
         let state = self
-            .client_loop(reader, writer, result.io_channel_id, result.user_channel_id, encoder)
+            .client_loop(
+                conn,
+                reader,
+                writer,
+                result.io_channel_id,
+                result.user_channel_id,
+                encoder,
+                gfx,
+            )
             .await
             .context("client loop failure")?;
 
         Ok(state)
     }
 
+    fn gfx_handler(&self, conn: &Connection, desktop_size: DesktopSize, user_channel_id: u16) -> Option<GfxHandler> {
+        let factory = self.h264_factory.as_ref()?;
+        let state = conn.gfx_state.as_ref()?;
+        let drdynvc_channel_id = conn.get_channel_id_by_type::<dvc::DrdynvcServer>()?;
+
+        Some(GfxHandler::new(
+            Arc::clone(state),
+            Arc::clone(factory),
+            desktop_size,
+            drdynvc_channel_id,
+            user_channel_id,
+            conn.frames.clone(),
+        ))
+    }
+
     async fn handle_input_backlog(
         &mut self,
+        conn: &mut Connection,
         writer: &mut impl FramedWrite,
         io_channel_id: u16,
         user_channel_id: u16,
@@ -755,11 +1690,13 @@ impl RdpServer {
             match Action::from_fp_output_header(frame[0]) {
                 Ok(Action::FastPath) => {
                     let input = decode(&frame)?;
-                    self.handle_fastpath(input).await;
+                    self.handle_fastpath(conn, input).await;
                 }
 
                 Ok(Action::X224) => {
-                    let _ = self.handle_x224(writer, io_channel_id, user_channel_id, &frame).await;
+                    let _ = self
+                        .handle_x224(conn, writer, io_channel_id, user_channel_id, &frame)
+                        .await;
                 }
 
                 // the frame here is always valid, because otherwise it would
@@ -771,58 +1708,117 @@ impl RdpServer {
         Ok(())
     }
 
-    async fn handle_fastpath(&mut self, input: FastPathInput) {
-        for event in input.0 {
-            let mut handler = self.handler.lock().await;
-            match event {
-                FastPathInputEvent::KeyboardEvent(flags, key) => {
-                    handler.keyboard((key, flags).into());
-                }
+    async fn handle_fastpath(&mut self, conn: &Connection, input: FastPathInput) {
+        conn.record_input(RecordKind::FastPathInput, &input);
+
+        let events = input.0.into_iter().filter_map(|event| match event {
+            FastPathInputEvent::KeyboardEvent(flags, key) => Some(InputEvent::Keyboard((key, flags).into())),
+            FastPathInputEvent::UnicodeKeyboardEvent(flags, key) => Some(InputEvent::Keyboard((key, flags).into())),
+            FastPathInputEvent::SyncEvent(flags) => Some(InputEvent::Keyboard(flags.into())),
+            FastPathInputEvent::MouseEvent(mouse) => Some(InputEvent::Mouse(mouse.into())),
+            FastPathInputEvent::MouseEventEx(mouse) => Some(InputEvent::Mouse(mouse.into())),
+            FastPathInputEvent::MouseEventRel(mouse) => Some(InputEvent::Mouse(mouse.into())),
+            FastPathInputEvent::QoeEvent(quality) => {
+                warn!("Received QoE: {}", quality);
+                None
+            }
+        });
 
-                FastPathInputEvent::UnicodeKeyboardEvent(flags, key) => {
-                    handler.keyboard((key, flags).into());
-                }
+        self.handle_input(conn, events).await;
+    }
 
-                FastPathInputEvent::SyncEvent(flags) => {
-                    handler.keyboard(flags.into());
-                }
+    /// Gives the input events received on the ainput dynamic channel to the handler, like the other
+    /// input events.
+    async fn handle_ainput(&mut self, conn: &mut Connection) {
+        let mut pdus = Vec::new();
+        while let Ok(pdu) = conn.ainput_receiver.try_recv() {
+            pdus.push(pdu);
+        }
 
-                FastPathInputEvent::MouseEvent(mouse) => {
-                    handler.mouse(mouse.into());
-                }
+        for pdu in &pdus {
+            conn.record_input(RecordKind::AInput, pdu);
+        }
 
-                FastPathInputEvent::MouseEventEx(mouse) => {
-                    handler.mouse(mouse.into());
-                }
+        let events = pdus.into_iter().map(|pdu| match pdu {
+            ironrdp_ainput::ClientPdu::Mouse(pdu) => InputEvent::Mouse(pdu.into()),
+        });
 
-                FastPathInputEvent::MouseEventRel(mouse) => {
-                    handler.mouse(mouse.into());
-                }
+        self.handle_input(conn, events).await;
+    }
 
-                FastPathInputEvent::QoeEvent(quality) => {
-                    warn!("Received QoE: {}", quality);
-                }
+    /// Gives the input events received together to the handler, within the input limits.
+    async fn handle_input(&mut self, conn: &Connection, events: impl Iterator<Item = InputEvent>) {
+        let coalesce = self.input_limiter.limits().coalesce_mouse_moves;
+        let mut queue = VecDeque::new();
+        for event in events {
+            conn.stats.input_event(match event {
+                InputEvent::Keyboard(_) => InputKind::Keyboard,
+                InputEvent::Mouse(_) => InputKind::Mouse,
+            });
+
+            if coalesce {
+                push_coalesced(&mut queue, event);
+            } else {
+                queue.push_back(event);
+            }
+        }
+
+        for event in queue {
+            let droppable = matches!(
+                event,
+                InputEvent::Mouse(MouseEvent::Move { .. } | MouseEvent::RelMove { .. })
+            );
+            match self.input_limiter.admit(droppable) {
+                Admission::Now => {}
+                Admission::After(delay) => tokio::time::sleep(delay).await,
+                Admission::Drop => continue,
+            }
+
+            let mut handler = self.handler.lock().await;
+            handler.ready().await;
+            match event {
+                InputEvent::Keyboard(event) => handler.keyboard(event),
+                InputEvent::Mouse(event) => handler.mouse(event),
             }
         }
     }
 
-    async fn handle_io_channel_data(&mut self, data: SendDataRequest<'_>) -> Result<bool> {
+    async fn handle_io_channel_data(&mut self, conn: &Connection, data: SendDataRequest<'_>) -> Result<bool> {
         let control: rdp::headers::ShareControlHeader = decode(data.user_data.as_ref())?;
 
         match control.share_control_pdu {
-            ShareControlPdu::Data(header) => match header.share_data_pdu {
-                rdp::headers::ShareDataPdu::Input(pdu) => {
-                    self.handle_input_event(pdu).await;
-                }
+            ShareControlPdu::Data(header) => {
+                trace!(
+                    pdu.kind = header.share_data_pdu.as_short_name(),
+                    "Share Data PDU received"
+                );
+
+                match header.share_data_pdu {
+                    rdp::headers::ShareDataPdu::Input(pdu) => {
+                        self.handle_input_event(conn, pdu).await;
+                    }
 
-                rdp::headers::ShareDataPdu::ShutdownRequest => {
-                    return Ok(true);
-                }
+                    rdp::headers::ShareDataPdu::FrameAcknowledge(pdu) => {
+                        trace!(frame_id = pdu.frame_id, "Frame acknowledged");
+                        conn.frames.acknowledge(pdu.frame_id);
+                    }
+
+                    rdp::headers::ShareDataPdu::SuppressOutput(pdu) => {
+                        let suppressed = pdu.desktop_rect.is_none();
+                        debug!(suppressed, "Suppress output");
+                        conn.output_suppressed
+                            .send_if_modified(|current| core::mem::replace(current, suppressed) != suppressed);
+                    }
 
-                unexpected => {
-                    warn!(?unexpected, "Unexpected share data pdu");
+                    rdp::headers::ShareDataPdu::ShutdownRequest => {
+                        return Ok(true);
+                    }
+
+                    unexpected => {
+                        warn!(?unexpected, "Unexpected share data pdu");
+                    }
                 }
-            },
+            }
 
             unexpected => {
                 warn!(?unexpected, "Unexpected share control");
@@ -834,6 +1830,7 @@ impl RdpServer {
 
     async fn handle_x224(
         &mut self,
+        conn: &mut Connection,
         writer: &mut impl FramedWrite,
         io_channel_id: u16,
         user_channel_id: u16,
@@ -844,13 +1841,27 @@ impl RdpServer {
             mcs::McsMessage::SendDataRequest(data) => {
                 debug!(?data, "McsMessage::SendDataRequest");
                 if data.channel_id == io_channel_id {
-                    return self.handle_io_channel_data(data).await;
+                    return self.handle_io_channel_data(conn, data).await;
                 }
 
-                if let Some(svc) = self.static_channels.get_by_channel_id_mut(data.channel_id) {
+                if let Some(svc) = conn.static_channels.get_by_channel_id_mut(data.channel_id) {
+                    trace!(
+                        pdu.channel_id = data.channel_id,
+                        pdu.channel = svc.channel_name().as_str(),
+                        pdu.size = data.user_data.len(),
+                        "Static channel PDU received"
+                    );
                     let response_pdus = svc.process(&data.user_data)?;
-                    let response = server_encode_svc_messages(response_pdus, data.channel_id, user_channel_id)?;
-                    writer.write_all(&response).await?;
+                    self.svc_buf.clear();
+                    Compression::encode_svc_messages(
+                        conn.compression.as_ref(),
+                        response_pdus,
+                        data.channel_id,
+                        user_channel_id,
+                        &mut self.svc_buf,
+                    )?;
+                    writer.write_all(self.svc_buf.filled()).await?;
+                    self.handle_ainput(conn).await;
                 } else {
                     warn!(channel_id = data.channel_id, "Unexpected channel received: ID",);
                 }
@@ -870,51 +1881,150 @@ impl RdpServer {
         Ok(false)
     }
 
-    async fn handle_input_event(&mut self, input: InputEventPdu) {
-        for event in input.0 {
-            let mut handler = self.handler.lock().await;
-            match event {
-                ironrdp_pdu::input::InputEvent::ScanCode(key) => {
-                    handler.keyboard((key.key_code, key.flags).into());
-                }
+    async fn handle_input_event(&mut self, conn: &Connection, input: InputEventPdu) {
+        conn.record_input(RecordKind::SlowPathInput, &input);
+
+        let events = input.0.into_iter().filter_map(|event| match event {
+            ironrdp_pdu::input::InputEvent::ScanCode(key) => {
+                Some(InputEvent::Keyboard((key.key_code, key.flags).into()))
+            }
+            ironrdp_pdu::input::InputEvent::Unicode(key) => {
+                Some(InputEvent::Keyboard((key.unicode_code, key.flags).into()))
+            }
+            ironrdp_pdu::input::InputEvent::Sync(sync) => Some(InputEvent::Keyboard(sync.flags.into())),
+            ironrdp_pdu::input::InputEvent::Mouse(mouse) => Some(InputEvent::Mouse(mouse.into())),
+            ironrdp_pdu::input::InputEvent::MouseX(mouse) => Some(InputEvent::Mouse(mouse.into())),
+            ironrdp_pdu::input::InputEvent::MouseRel(mouse) => Some(InputEvent::Mouse(mouse.into())),
+            ironrdp_pdu::input::InputEvent::Unused(_) => None,
+        });
+
+        self.handle_input(conn, events).await;
+    }
 
-                ironrdp_pdu::input::InputEvent::Unicode(key) => {
-                    handler.keyboard((key.unicode_code, key.flags).into());
+    async fn accept_finalize<S>(
+        &mut self,
+        conn: &mut Connection,
+        mut framed: TokioFramed<S>,
+        mut acceptor: Acceptor,
+        info: &mut ConnectionInfo,
+    ) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Sync + Send + Unpin,
+    {
+        loop {
+            let res = ironrdp_acceptor::accept_finalize(framed, &mut acceptor).await;
+
+            info.client_name = acceptor.client_name().map(str::to_owned);
+            info.username = acceptor.username().map(str::to_owned);
+
+            let (new_framed, result) = match res {
+                Ok(res) => res,
+                Err(error) => {
+                    if matches!(error.kind(), ConnectorErrorKind::AccessDenied) {
+                        if let Some(handler) = &self.event_handler {
+                            handler.authenticated(info, false);
+                        }
+                        conn.audit(info, AuditEvent::Authentication { success: false });
+                    }
+                    return Err(error).context("failed to accept client during finalize");
                 }
+            };
+
+            conn.rdp_security = result.rdp_security.clone();
+            conn.client_heartbeat = acceptor
+                .client_core_data()
+                .and_then(|core| core.optional_data.early_capability_flags)
+                .is_some_and(|flags| flags.contains(ClientEarlyCapabilityFlags::SUPPORT_HEART_BEAT_PDU));
+            let (mut reader, writer) = split_tokio_framed(new_framed);
+            let mut writer = RdpSecurityWriter::new(writer, conn.rdp_security.clone());
 
-                ironrdp_pdu::input::InputEvent::Sync(sync) => {
-                    handler.keyboard(sync.flags.into());
+            if !result.reactivation {
+                conn.compression = Compression::new(acceptor.client_compression(), &result.capabilities);
+
+                if let Some(handler) = &self.event_handler {
+                    handler.authenticated(info, true);
                 }
+                conn.audit(info, AuditEvent::Authentication { success: true });
 
-                ironrdp_pdu::input::InputEvent::Mouse(mouse) => {
-                    handler.mouse(mouse.into());
+                if let (Some(handler), Some(cookie)) = (&self.reconnect_handler, acceptor.client_auto_reconnect()) {
+                    match self.auto_reconnect.redeem(cookie, acceptor.client_random()) {
+                        Some(logon_id) if handler.reconnect(info, logon_id) => {
+                            info!(logon_id, "Client re-attached to its session");
+                            info.logon_id = Some(logon_id);
+                        }
+                        Some(logon_id) => debug!(logon_id, "Client not re-attached to its session"),
+                        None => debug!("Invalid or expired auto-reconnect cookie"),
+                    }
                 }
 
-                ironrdp_pdu::input::InputEvent::MouseX(mouse) => {
-                    handler.mouse(mouse.into());
+                if let Some(authorizer) = &self.authorizer {
+                    match authorizer.authorize(info).await {
+                        Authorization::Allow => {}
+                        Authorization::Deny(reason) => {
+                            info!(peer = ?info.peer, username = ?info.username, ?reason, "Connection denied");
+                            disconnect(
+                                result.io_channel_id,
+                                result.user_channel_id,
+                                reason,
+                                conn.compression.as_ref(),
+                                &mut writer,
+                            )
+                            .await?;
+                            return Ok(());
+                        }
+                        Authorization::Redirect(redirection) => {
+                            info!(peer = ?info.peer, username = ?info.username, "Connection redirected");
+                            self.redirect(
+                                conn,
+                                result.io_channel_id,
+                                result.user_channel_id,
+                                *redirection,
+                                &mut writer,
+                            )
+                            .await?;
+                            return Ok(());
+                        }
+                    }
                 }
 
-                ironrdp_pdu::input::InputEvent::MouseRel(mouse) => {
-                    handler.mouse(mouse.into());
+                let auto_reconnect = result.capabilities.iter().any(|c| {
+                    matches!(c, CapabilitySet::General(c) if c.extra_flags.contains(GeneralExtraFlags::AUTORECONNECT_SUPPORTED))
+                });
+                if self.reconnect_handler.is_some() && auto_reconnect {
+                    let cookie = self.auto_reconnect.issue(info.logon_id);
+                    info.logon_id = Some(cookie.logon_id);
+                    send_auto_reconnect_cookie(
+                        result.io_channel_id,
+                        result.user_channel_id,
+                        cookie,
+                        conn.compression.as_ref(),
+                        &mut writer,
+                    )
+                    .await?;
                 }
 
-                ironrdp_pdu::input::InputEvent::Unused(_) => {}
+                if let Some(core) = acceptor.client_core_data() {
+                    let session_info = ClientSessionInfo::new(core, acceptor.client_timezone().cloned());
+                    debug!(?session_info, "Client session");
+                    self.handler.lock().await.session_info(&session_info);
+                    self.display.lock().await.session_info(&session_info);
+                }
             }
-        }
-    }
 
-    async fn accept_finalize<S>(&mut self, mut framed: TokioFramed<S>, mut acceptor: Acceptor) -> Result<()>
-    where
-        S: AsyncRead + AsyncWrite + Sync + Send + Unpin,
-    {
-        loop {
-            let (new_framed, result) = ironrdp_acceptor::accept_finalize(framed, &mut acceptor)
-                .await
-                .context("failed to accept client during finalize")?;
-
-            let (mut reader, mut writer) = split_tokio_framed(new_framed);
+            if let Some(handler) = &self.event_handler {
+                if !result.reactivation {
+                    let channels = result
+                        .static_channels
+                        .iter()
+                        .filter(|(type_id, _)| result.static_channels.get_channel_id_by_type_id(*type_id).is_some())
+                        .map(|(_, channel)| channel.channel_name())
+                        .collect::<Vec<_>>();
+                    handler.channels_joined(info, &channels);
+                }
+                handler.activated(info, &result.capabilities);
+            }
 
-            match self.client_accepted(&mut reader, &mut writer, result).await? {
+            match self.client_accepted(conn, &mut reader, &mut writer, result).await? {
                 RunState::Continue => {
                     unreachable!();
                 }
@@ -925,10 +2035,11 @@ impl RdpServer {
                     // various state issues during client resize.
                     acceptor = Acceptor::new_deactivation_reactivation(
                         acceptor,
-                        core::mem::take(&mut self.static_channels),
+                        core::mem::take(&mut conn.static_channels),
                         desktop_size,
                     );
-                    framed = unsplit_tokio_framed(reader, writer);
+                    acceptor.set_monitor_layout(self.display.lock().await.monitor_layout().await);
+                    framed = unsplit_tokio_framed(reader, writer.into_inner());
                     continue;
                 }
                 RunState::Disconnect => break,
@@ -942,6 +2053,223 @@ impl RdpServer {
         debug!(?creds, "Changing credentials");
         self.creds = creds
     }
+
+    /// Returns a handle to the active sessions.
+    pub fn sessions(&self) -> RdpServerSessions {
+        self.sessions.clone()
+    }
+
+    /// Returns a handle to shut the server down or disconnect its clients gracefully.
+    pub fn handle(&self) -> RdpServerHandle {
+        RdpServerHandle::new(self.ev_sender.clone(), self.sessions.clone())
+    }
+
+    /// Sets the factory creating the handlers of each session, allowing [`RdpServer::run`] to run
+    /// several sessions concurrently.
+    ///
+    /// The input handler and display given at construction are then unused.
+    pub fn set_session_factory(&mut self, factory: Option<Box<dyn RdpServerSessionFactory>>) {
+        self.session_factory = factory;
+    }
+
+    /// Registers a custom static virtual channel, with a processor created by the factory for each
+    /// connection.
+    ///
+    /// The channel is joined if the client requests it, e.g. from a plugin of the client.
+    pub fn add_static_channel<F>(&mut self, factory: F)
+    where
+        F: StaticChannelFactory + 'static,
+    {
+        self.custom_channels.add_static(factory);
+    }
+
+    /// Registers a custom dynamic virtual channel, with a processor created by the factory for each
+    /// connection.
+    ///
+    /// The channel is opened once the dynamic virtual channels are ready, if the client accepts it.
+    pub fn add_dynamic_channel<F>(&mut self, factory: F)
+    where
+        F: DynamicChannelFactory + 'static,
+    {
+        self.custom_channels.add_dynamic(factory);
+    }
+
+    pub(crate) fn set_custom_channels(&mut self, channels: CustomChannels) {
+        self.custom_channels = channels;
+    }
+
+    /// Sets the listener accepting the connections in [`RdpServer::run`], instead of binding the
+    /// configured address.
+    pub fn set_listener(&mut self, listener: Box<dyn RdpServerListener>) {
+        self.listener = Some(listener);
+    }
+
+    /// Sets the validator of the users credentials, taking precedence over the credentials set with
+    /// [`RdpServer::set_credentials`].
+    ///
+    /// The credentials are validated during the NLA (CredSSP) authentication when the server is using
+    /// hybrid security, or from the Client Info PDU otherwise.
+    pub fn set_credentials_validator(&mut self, validator: Option<Arc<dyn CredentialsValidator>>) {
+        self.credentials_validator = validator;
+    }
+
+    /// Sets the hook inspecting and overriding the capability sets exchanged with the clients.
+    pub fn set_capabilities_hook(&mut self, hook: Option<Arc<dyn CapabilitiesHook>>) {
+        self.capabilities_hook = hook;
+    }
+
+    /// Sets the authorizer of the connections, called once the user is authenticated and before the
+    /// session starts.
+    pub fn set_authorizer(&mut self, authorizer: Option<Arc<dyn RdpServerAuthorizer>>) {
+        self.authorizer = authorizer;
+    }
+
+    /// Sets the metrics receiving the statistics of the connections, also available with
+    /// [`RdpServerSessions::stats`].
+    pub fn set_metrics(&mut self, metrics: Option<Arc<dyn RdpServerMetrics>>) {
+        self.metrics = metrics;
+    }
+
+    /// Adapts the encoding quality of the connections with the given policy, see [`QualityPolicy`].
+    pub fn set_quality_policy(&mut self, policy: Option<Arc<dyn QualityPolicy>>) {
+        self.quality_policy = policy;
+    }
+
+    /// Sets the handler notified of the lifecycle of the connections.
+    pub fn set_event_handler(&mut self, handler: Option<Arc<dyn RdpServerEventHandler>>) {
+        self.event_handler = handler;
+    }
+
+    /// Sets the auditor receiving the security-relevant events of the connections, see [`RdpServerAuditor`].
+    pub fn set_auditor(&mut self, auditor: Option<Arc<dyn RdpServerAuditor>>) {
+        self.auditor = auditor;
+    }
+
+    /// Enables the auto-reconnection of the clients, which are re-attached to their session by the given
+    /// handler.
+    pub fn set_reconnect_handler(&mut self, handler: Option<Arc<dyn RdpServerReconnectHandler>>) {
+        self.reconnect_handler = handler;
+    }
+
+    /// Sets the factory of the audio input (AUDIO_INPUT channel), receiving the audio recorded by the clients.
+    pub fn set_audio_input_factory(&mut self, factory: Option<Box<dyn AudioInputServerFactory>>) {
+        self.audio_input_factory = factory;
+    }
+
+    /// Sets the factory of the drive redirection (RDPDR channel), giving access to the drives redirected by the clients.
+    pub fn set_drive_factory(&mut self, mut factory: Option<Box<dyn DriveServerFactory>>) {
+        if let Some(drive) = factory.as_mut() {
+            drive.set_sender(self.ev_sender.clone());
+        }
+        self.drive_factory = factory;
+    }
+
+    /// Sets the factory of the RAIL channel, publishing remote applications (RemoteApp) to the clients.
+    pub fn set_rail_factory(&mut self, factory: Option<Box<dyn RailServerFactory>>) {
+        self.rail_factory = factory;
+    }
+}
+
+/// Disconnects the client gracefully: the reason is given with a Set Error Info PDU, then the session is
+/// deactivated and the MCS domain is left.
+async fn disconnect(
+    io_channel_id: u16,
+    user_channel_id: u16,
+    reason: ErrorInfo,
+    compression: Option<&Compression>,
+    writer: &mut impl FramedWrite,
+) -> Result<()> {
+    let error_info = ServerSetErrorInfoPdu(reason);
+    let pdu = rdp::headers::ShareControlHeader {
+        share_id: 0,
+        pdu_source: io_channel_id,
+        share_control_pdu: ShareControlPdu::Data(rdp::headers::ShareDataHeader {
+            share_data_pdu: rdp::headers::ShareDataPdu::ServerSetErrorInfo(error_info),
+            stream_priority: rdp::headers::StreamPriority::Undefined,
+            compression_flags: rdp::headers::CompressionFlags::empty(),
+            compression_type: rdp::client_info::CompressionType::K8,
+        }),
+    };
+    let pdu = SendDataIndication {
+        initiator_id: user_channel_id,
+        channel_id: io_channel_id,
+        user_data: encode_share_data(&pdu, compression)?.into(),
+    };
+    writer.write_all(&encode_vec(&X224(pdu))?).await?;
+
+    deactivate_all(io_channel_id, user_channel_id, writer).await?;
+
+    let ultimatum = mcs::McsMessage::DisconnectProviderUltimatum(mcs::DisconnectProviderUltimatum::from_reason(
+        mcs::DisconnectReason::ProviderInitiated,
+    ));
+    writer.write_all(&encode_vec(&X224(ultimatum))?).await?;
+
+    Ok(())
+}
+
+async fn send_server_redirection(
+    io_channel_id: u16,
+    user_channel_id: u16,
+    redirection: ServerRedirectionPdu,
+    writer: &mut impl FramedWrite,
+) -> Result<()> {
+    let pdu = EnhancedSecurityServerRedirection {
+        pdu_source: io_channel_id,
+        redirection,
+    };
+    let pdu = SendDataIndication {
+        initiator_id: user_channel_id,
+        channel_id: io_channel_id,
+        user_data: encode_vec(&pdu)?.into(),
+    };
+    writer.write_all(&encode_vec(&X224(pdu))?).await?;
+
+    Ok(())
+}
+
+async fn send_auto_reconnect_cookie(
+    io_channel_id: u16,
+    user_channel_id: u16,
+    cookie: ServerAutoReconnect,
+    compression: Option<&Compression>,
+    writer: &mut impl FramedWrite,
+) -> Result<()> {
+    let save_session_info = SaveSessionInfoPdu {
+        info_type: InfoType::LogonExtended,
+        info_data: InfoData::LogonExtended(LogonInfoExtended {
+            present_fields_flags: LogonExFlags::AUTO_RECONNECT_COOKIE,
+            auto_reconnect: Some(cookie),
+            errors_info: None,
+        }),
+    };
+    let pdu = rdp::headers::ShareControlHeader {
+        share_id: 0,
+        pdu_source: io_channel_id,
+        share_control_pdu: ShareControlPdu::Data(rdp::headers::ShareDataHeader {
+            share_data_pdu: rdp::headers::ShareDataPdu::SaveSessionInfo(save_session_info),
+            stream_priority: rdp::headers::StreamPriority::Undefined,
+            compression_flags: rdp::headers::CompressionFlags::empty(),
+            compression_type: rdp::client_info::CompressionType::K8,
+        }),
+    };
+    let pdu = SendDataIndication {
+        initiator_id: user_channel_id,
+        channel_id: io_channel_id,
+        user_data: encode_share_data(&pdu, compression)?.into(),
+    };
+    writer.write_all(&encode_vec(&X224(pdu))?).await?;
+
+    Ok(())
+}
+
+/// Encodes a share data PDU, compressed when the client supports the compression.
+fn encode_share_data(pdu: &rdp::headers::ShareControlHeader, compression: Option<&Compression>) -> Result<Vec<u8>> {
+    let mut encoded = encode_vec(pdu)?;
+    if let Some(compression) = compression {
+        compression.compress_share_data(&mut encoded)?;
+    }
+
+    Ok(encoded)
 }
 
 async fn deactivate_all(
@@ -966,14 +2294,115 @@ async fn deactivate_all(
     Ok(())
 }
 
+/// Writer encrypting the frames with the session keys, when Standard RDP Security is used.
+struct RdpSecurityWriter<W> {
+    writer: W,
+    security: Option<Arc<std::sync::Mutex<StandardSecurity>>>,
+}
+
+impl<W> RdpSecurityWriter<W> {
+    fn new(writer: W, security: Option<Arc<std::sync::Mutex<StandardSecurity>>>) -> Self {
+        Self { writer, security }
+    }
+
+    fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W> FramedWrite for RdpSecurityWriter<W>
+where
+    W: FramedWrite,
+{
+    type WriteAllFut<'write>
+        = core::pin::Pin<Box<dyn core::future::Future<Output = std::io::Result<()>> + 'write>>
+    where
+        Self: 'write;
+
+    fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> Self::WriteAllFut<'a> {
+        Box::pin(async {
+            let Some(security) = &self.security else {
+                return self.writer.write_all(buf).await;
+            };
+
+            let frame = security
+                .lock()
+                .expect("poisoned")
+                .encrypt_frame(buf)
+                .map_err(std::io::Error::other)?;
+            self.writer.write_all(&frame).await
+        })
+    }
+
+    fn write_all_vectored<'a>(&'a mut self, bufs: &'a [&'a [u8]]) -> Self::WriteAllFut<'a> {
+        Box::pin(async {
+            let Some(security) = &self.security else {
+                return self.writer.write_all_vectored(bufs).await;
+            };
+
+            // The frame is encrypted as a whole.
+            let frame = security
+                .lock()
+                .expect("poisoned")
+                .encrypt_frame(&bufs.concat())
+                .map_err(std::io::Error::other)?;
+            self.writer.write_all(&frame).await
+        })
+    }
+}
+
+/// Records the PDUs written, before writing them.
+struct RecordingWriter<W> {
+    writer: W,
+    recorder: Option<Arc<SessionRecorder>>,
+}
+
+impl<W> RecordingWriter<W> {
+    fn new(writer: W, recorder: Option<Arc<SessionRecorder>>) -> Self {
+        Self { writer, recorder }
+    }
+}
+
+impl<W> FramedWrite for RecordingWriter<W>
+where
+    W: FramedWrite,
+{
+    type WriteAllFut<'write>
+        = core::pin::Pin<Box<dyn core::future::Future<Output = std::io::Result<()>> + 'write>>
+    where
+        Self: 'write;
+
+    fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> Self::WriteAllFut<'a> {
+        Box::pin(async {
+            if let Some(recorder) = &self.recorder {
+                recorder.record(RecordKind::Output, buf);
+            }
+
+            self.writer.write_all(buf).await
+        })
+    }
+
+    fn write_all_vectored<'a>(&'a mut self, bufs: &'a [&'a [u8]]) -> Self::WriteAllFut<'a> {
+        Box::pin(async {
+            if let Some(recorder) = &self.recorder {
+                recorder.record(RecordKind::Output, &bufs.concat());
+            }
+
+            self.writer.write_all_vectored(bufs).await
+        })
+    }
+}
+
 struct SharedWriter<'w, W: FramedWrite> {
     writer: Rc<Mutex<&'w mut W>>,
+    stats: StatsRecorder,
 }
 
 impl<W: FramedWrite> Clone for SharedWriter<'_, W> {
     fn clone(&self) -> Self {
         Self {
             writer: Rc::clone(&self.writer),
+            stats: self.stats.clone(),
         }
     }
 }
@@ -992,15 +2421,52 @@ where
             let mut writer = self.writer.lock().await;
 
             writer.write_all(buf).await?;
+            self.stats.bytes_sent(buf.len());
+            Ok(())
+        })
+    }
+
+    fn write_all_vectored<'a>(&'a mut self, bufs: &'a [&'a [u8]]) -> Self::WriteAllFut<'a> {
+        Box::pin(async {
+            let mut writer = self.writer.lock().await;
+
+            writer.write_all_vectored(bufs).await?;
+            self.stats.bytes_sent(bufs.iter().map(|buf| buf.len()).sum());
             Ok(())
         })
     }
 }
 
 impl<'a, W: FramedWrite> SharedWriter<'a, W> {
-    fn new(writer: &'a mut W) -> Self {
+    fn new(writer: &'a mut W, stats: StatsRecorder) -> Self {
         Self {
             writer: Rc::new(Mutex::new(writer)),
+            stats,
         }
     }
 }
+
+#[cfg(feature = "__bench")]
+pub(crate) mod bench {
+    use super::*;
+
+    pub fn layout_desktop_size(layout: &DisplayControlMonitorLayout) -> Option<DesktopSize> {
+        super::layout_desktop_size(layout)
+    }
+
+    /// Returns `true` if a new connection from `peer` is within the limits, given the peers of the active sessions.
+    pub fn allows(limits: &ConnectionLimits, peer: &PeerInfo, active: &[PeerInfo]) -> bool {
+        let sessions = RdpServerSessions::default();
+        for peer in active {
+            let (ev_sender, _) = ServerEvent::create_channel();
+            sessions.register(peer.clone(), ev_sender, StatsRecorder::new(None));
+        }
+
+        limits.allows(peer, &sessions.list())
+    }
+
+    /// Returns the time until the next timeout of a new connection, and its reason.
+    pub fn next_timeout(limits: &ConnectionLimits) -> Option<(Duration, ProtocolIndependentCode)> {
+        limits.next_timeout(&StatsRecorder::new(None))
+    }
+}
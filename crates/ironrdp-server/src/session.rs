@@ -0,0 +1,238 @@
+use core::fmt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use anyhow::Result;
+use tokio::sync::mpsc;
+
+use crate::stats::StatsRecorder;
+use crate::{
+    AudioInputServerFactory, CliprdrServerFactory, ConnectionStats, DriveServerFactory, DynRdpServerDisplay,
+    DynRdpServerInputHandler, InputLimits, PeerInfo, RailServerFactory, RdpServerSecurity, ServerEvent,
+    ServerRedirectionPdu, SoundServerFactory,
+};
+
+/// Handlers of a session, created for each connection by a [`RdpServerSessionFactory`].
+pub struct RdpServerSession {
+    pub handler: Box<dyn DynRdpServerInputHandler>,
+    pub display: Box<dyn DynRdpServerDisplay>,
+    pub sound_factory: Option<Box<dyn SoundServerFactory>>,
+    pub cliprdr_factory: Option<Box<dyn CliprdrServerFactory>>,
+    pub audio_input_factory: Option<Box<dyn AudioInputServerFactory>>,
+    pub drive_factory: Option<Box<dyn DriveServerFactory>>,
+    pub rail_factory: Option<Box<dyn RailServerFactory>>,
+    /// Security of the connection replacing the security of the server, e.g. to present the certificate
+    /// of the address the client connected to.
+    pub security: Option<RdpServerSecurity>,
+    /// Limits of the input events of the connection replacing the limits of the server.
+    pub input_limits: Option<InputLimits>,
+}
+
+impl RdpServerSession {
+    pub fn new(handler: Box<dyn DynRdpServerInputHandler>, display: Box<dyn DynRdpServerDisplay>) -> Self {
+        Self {
+            handler,
+            display,
+            sound_factory: None,
+            cliprdr_factory: None,
+            audio_input_factory: None,
+            drive_factory: None,
+            rail_factory: None,
+            security: None,
+            input_limits: None,
+        }
+    }
+}
+
+/// Creates the handlers of each session.
+///
+/// When a session factory is set, the server runs the sessions concurrently, each one with its own
+/// input handler and display, instead of accepting the connections one after the other.
+///
+/// The factory can be a closure:
+///
+/// ```
+/// use ironrdp_server::{PeerInfo, RdpServerSession, RdpServerSessionFactory};
+///
+/// # fn session(peer: &PeerInfo) -> anyhow::Result<RdpServerSession> { unimplemented!() }
+/// let factory: Box<dyn RdpServerSessionFactory> = Box::new(|peer: &PeerInfo| session(peer));
+/// ```
+pub trait RdpServerSessionFactory: Send {
+    /// Creates the handlers of a new session, or returns an error to refuse the connection.
+    fn new_session(&mut self, peer: &PeerInfo) -> Result<RdpServerSession>;
+}
+
+impl<F> RdpServerSessionFactory for F
+where
+    F: FnMut(&PeerInfo) -> Result<RdpServerSession> + Send,
+{
+    fn new_session(&mut self, peer: &PeerInfo) -> Result<RdpServerSession> {
+        self(peer)
+    }
+}
+
+/// Identifier of a session, unique for the lifetime of the server.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SessionId(u32);
+
+impl fmt::Display for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub id: SessionId,
+    pub peer: PeerInfo,
+    pub connected_at: SystemTime,
+}
+
+struct SessionEntry {
+    info: SessionInfo,
+    ev_sender: mpsc::UnboundedSender<ServerEvent>,
+    stats: StatsRecorder,
+}
+
+#[derive(Default)]
+struct Sessions {
+    next_id: u32,
+    active: HashMap<SessionId, SessionEntry>,
+}
+
+/// Handle to the active sessions of a server.
+///
+/// The handle can be cloned and used from any task, see [`RdpServer::sessions`](crate::RdpServer::sessions).
+#[derive(Clone, Default)]
+pub struct RdpServerSessions {
+    inner: Arc<Mutex<Sessions>>,
+}
+
+impl fmt::Debug for RdpServerSessions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.list()).finish()
+    }
+}
+
+impl RdpServerSessions {
+    /// Returns the active sessions, ordered by identifier.
+    pub fn list(&self) -> Vec<SessionInfo> {
+        let sessions = self.inner.lock().expect("poisoned");
+        let mut list = sessions
+            .active
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect::<Vec<_>>();
+        list.sort_by_key(|info| info.id);
+        list
+    }
+
+    pub fn get(&self, id: SessionId) -> Option<SessionInfo> {
+        let sessions = self.inner.lock().expect("poisoned");
+        sessions.active.get(&id).map(|entry| entry.info.clone())
+    }
+
+    /// Returns the statistics of a session.
+    pub fn stats(&self, id: SessionId) -> Option<ConnectionStats> {
+        let sessions = self.inner.lock().expect("poisoned");
+        sessions.active.get(&id).map(|entry| entry.stats.snapshot())
+    }
+
+    /// Disconnects a session, returning `false` if it is not active.
+    pub fn disconnect(&self, id: SessionId, reason: impl Into<String>) -> bool {
+        let sessions = self.inner.lock().expect("poisoned");
+        match sessions.active.get(&id) {
+            Some(entry) => entry.ev_sender.send(ServerEvent::Quit(reason.into())).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Redirects the client of a session to another server, returning `false` if the session is not active.
+    ///
+    /// The client disconnects, and connects to the target server of the redirection.
+    pub fn redirect(&self, id: SessionId, redirection: ServerRedirectionPdu) -> bool {
+        let sessions = self.inner.lock().expect("poisoned");
+        match sessions.active.get(&id) {
+            Some(entry) => entry
+                .ev_sender
+                .send(ServerEvent::Redirect(Box::new(redirection)))
+                .is_ok(),
+            None => false,
+        }
+    }
+
+    pub(crate) fn register(
+        &self,
+        peer: PeerInfo,
+        ev_sender: mpsc::UnboundedSender<ServerEvent>,
+        stats: StatsRecorder,
+    ) -> SessionId {
+        let mut sessions = self.inner.lock().expect("poisoned");
+        let id = SessionId(sessions.next_id);
+        sessions.next_id = sessions.next_id.wrapping_add(1);
+
+        let info = SessionInfo {
+            id,
+            peer,
+            connected_at: SystemTime::now(),
+        };
+        sessions.active.insert(id, SessionEntry { info, ev_sender, stats });
+
+        id
+    }
+
+    /// Sends the shutdown event to all the sessions.
+    pub(crate) fn shutdown(&self) {
+        let sessions = self.inner.lock().expect("poisoned");
+        for entry in sessions.active.values() {
+            let _ = entry.ev_sender.send(ServerEvent::Shutdown);
+        }
+    }
+
+    pub(crate) fn unregister(&self, id: SessionId) {
+        self.inner.lock().expect("poisoned").active.remove(&id);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.inner.lock().expect("poisoned").active.clear();
+    }
+}
+
+/// Control handle of a server, see [`RdpServer::handle`](crate::RdpServer::handle).
+///
+/// The handle can be cloned and used from any task to stop the server or disconnect its clients, which are
+/// notified of the disconnection instead of seeing the connection dropped.
+#[derive(Debug, Clone)]
+pub struct RdpServerHandle {
+    ev_sender: mpsc::UnboundedSender<ServerEvent>,
+    sessions: RdpServerSessions,
+}
+
+impl RdpServerHandle {
+    pub(crate) fn new(ev_sender: mpsc::UnboundedSender<ServerEvent>, sessions: RdpServerSessions) -> Self {
+        Self { ev_sender, sessions }
+    }
+
+    /// Stops accepting connections and disconnects all the clients.
+    ///
+    /// [`RdpServer::run`](crate::RdpServer::run) returns once the sessions are closed.
+    pub fn shutdown(&self) {
+        self.sessions.shutdown();
+        let _ = self.ev_sender.send(ServerEvent::Shutdown);
+    }
+
+    /// Disconnects a client, returning `false` if its session is not active.
+    pub fn disconnect_client(&self, id: SessionId, reason: impl Into<String>) -> bool {
+        self.sessions.disconnect(id, reason)
+    }
+
+    /// Redirects a client to another server, returning `false` if its session is not active.
+    pub fn redirect_client(&self, id: SessionId, redirection: ServerRedirectionPdu) -> bool {
+        self.sessions.redirect(id, redirection)
+    }
+
+    pub fn sessions(&self) -> &RdpServerSessions {
+        &self.sessions
+    }
+}
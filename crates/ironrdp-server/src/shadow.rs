@@ -0,0 +1,400 @@
+//! Session shadowing, a single session shared by several connections.
+
+use core::fmt;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use futures_util::StreamExt as _;
+use tokio::sync::broadcast;
+
+use crate::{
+    ClientSessionInfo, DesktopSize, DisplayUpdate, DynRdpServerDisplay, DynRdpServerInputHandler, KeyboardEvent,
+    Monitor, MouseEvent, RdpServerDisplay, RdpServerDisplayUpdates, RdpServerInputHandler, RdpServerSession,
+};
+
+/// Number of display updates buffered for each viewer, a viewer lagging behind is refreshed.
+const UPDATE_BUFFER: usize = 256;
+
+/// Identifier of a viewer of a [`ShadowSession`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ShadowViewerId(u64);
+
+impl fmt::Display for ShadowViewerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShadowRole {
+    /// Sees the display, and controls the session with its input events.
+    Controller,
+    /// Only sees the display.
+    Observer,
+}
+
+#[derive(Debug, Clone)]
+pub struct ShadowViewer {
+    pub id: ShadowViewerId,
+    pub role: ShadowRole,
+}
+
+#[derive(Default)]
+struct Viewers {
+    next_id: u64,
+    ids: Vec<ShadowViewerId>,
+    controller: Option<ShadowViewerId>,
+    /// Whether the display updates are being broadcast.
+    broadcasting: bool,
+}
+
+/// Input event of the controller, queued until processed by the input handler.
+enum QueuedInput {
+    Keyboard(KeyboardEvent),
+    Mouse(MouseEvent),
+    SessionInfo(ClientSessionInfo),
+}
+
+impl QueuedInput {
+    fn apply(self, handler: &mut dyn DynRdpServerInputHandler) {
+        match self {
+            Self::Keyboard(event) => handler.keyboard(event),
+            Self::Mouse(event) => handler.mouse(event),
+            Self::SessionInfo(info) => handler.session_info(&info),
+        }
+    }
+}
+
+struct Shared {
+    display: tokio::sync::Mutex<Box<dyn DynRdpServerDisplay>>,
+    handler: tokio::sync::Mutex<Box<dyn DynRdpServerInputHandler>>,
+    /// Input events not processed yet, the handler being busy when they were received.
+    pending_input: Mutex<VecDeque<QueuedInput>>,
+    /// Updates of the display, `None` once they ended.
+    updates: broadcast::Sender<Option<DisplayUpdate>>,
+    viewers: Mutex<Viewers>,
+}
+
+impl Shared {
+    fn is_controller(&self, id: ShadowViewerId) -> bool {
+        self.viewers.lock().expect("poisoned").controller == Some(id)
+    }
+
+    /// Requests the full content of the display, for the viewers which missed some updates.
+    fn request_refresh(self: &Arc<Self>) {
+        let shared = Arc::clone(self);
+        tokio::spawn(async move {
+            shared.display.lock().await.request_refresh();
+        });
+    }
+}
+
+/// Session shared by several connections.
+///
+/// The display of the session is broadcast to all the connections, the viewers. One of them can be the
+/// controller, whose input events are sent to the input handler of the session, while the input events
+/// of the other viewers, the observers, are ignored.
+///
+/// The connections are joined from a session factory:
+///
+/// ```no_run
+/// use ironrdp_server::{DynRdpServerDisplay, DynRdpServerInputHandler, PeerInfo, ShadowRole, ShadowSession};
+///
+/// # fn factory(handler: Box<dyn DynRdpServerInputHandler>, display: Box<dyn DynRdpServerDisplay>) {
+/// let shadow = ShadowSession::new(handler, display);
+/// let factory = move |_peer: &PeerInfo| {
+///     let (_id, session) = shadow.join(ShadowRole::Observer);
+///     Ok::<_, anyhow::Error>(session)
+/// };
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ShadowSession {
+    shared: Arc<Shared>,
+}
+
+impl ShadowSession {
+    pub fn new(handler: Box<dyn DynRdpServerInputHandler>, display: Box<dyn DynRdpServerDisplay>) -> Self {
+        let (updates, _) = broadcast::channel(UPDATE_BUFFER);
+
+        Self {
+            shared: Arc::new(Shared {
+                display: tokio::sync::Mutex::new(display),
+                handler: tokio::sync::Mutex::new(handler),
+                pending_input: Mutex::new(VecDeque::new()),
+                updates,
+                viewers: Mutex::new(Viewers::default()),
+            }),
+        }
+    }
+
+    /// Adds a viewer, returning the input handler and display of its connection.
+    ///
+    /// A new controller demotes the current one. The viewer leaves once its connection ends.
+    pub fn join(&self, role: ShadowRole) -> (ShadowViewerId, RdpServerSession) {
+        let id = {
+            let mut viewers = self.shared.viewers.lock().expect("poisoned");
+            let id = ShadowViewerId(viewers.next_id);
+            viewers.next_id += 1;
+            viewers.ids.push(id);
+            if role == ShadowRole::Controller {
+                viewers.controller = Some(id);
+            }
+            id
+        };
+
+        info!(%id, ?role, "Shadow viewer joined");
+
+        let handler = ViewerInputHandler {
+            id,
+            shared: Arc::clone(&self.shared),
+        };
+        let display = ViewerDisplay {
+            id,
+            shared: Arc::clone(&self.shared),
+        };
+
+        (id, RdpServerSession::new(Box::new(handler), Box::new(display)))
+    }
+
+    /// Returns the viewers of the session.
+    pub fn viewers(&self) -> Vec<ShadowViewer> {
+        let viewers = self.shared.viewers.lock().expect("poisoned");
+
+        viewers
+            .ids
+            .iter()
+            .map(|&id| ShadowViewer {
+                id,
+                role: if viewers.controller == Some(id) {
+                    ShadowRole::Controller
+                } else {
+                    ShadowRole::Observer
+                },
+            })
+            .collect()
+    }
+
+    /// Returns the viewer controlling the session, if any.
+    pub fn controller(&self) -> Option<ShadowViewerId> {
+        self.shared.viewers.lock().expect("poisoned").controller
+    }
+
+    /// Gives the control of the session to a viewer, the current controller becoming an observer.
+    ///
+    /// Returns `false` if the viewer left the session.
+    pub fn promote(&self, id: ShadowViewerId) -> bool {
+        let mut viewers = self.shared.viewers.lock().expect("poisoned");
+        if !viewers.ids.contains(&id) {
+            return false;
+        }
+
+        info!(%id, previous = ?viewers.controller, "Shadow viewer promoted");
+        viewers.controller = Some(id);
+        true
+    }
+
+    /// Takes the control of the session back from a viewer, which becomes an observer.
+    ///
+    /// Returns `false` if the viewer isn't the controller.
+    pub fn demote(&self, id: ShadowViewerId) -> bool {
+        let mut viewers = self.shared.viewers.lock().expect("poisoned");
+        if viewers.controller != Some(id) {
+            return false;
+        }
+
+        info!(%id, "Shadow viewer demoted");
+        viewers.controller = None;
+        true
+    }
+}
+
+impl fmt::Debug for ShadowSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShadowSession")
+            .field("viewers", &self.viewers())
+            .finish_non_exhaustive()
+    }
+}
+
+struct ViewerInputHandler {
+    id: ShadowViewerId,
+    shared: Arc<Shared>,
+}
+
+impl ViewerInputHandler {
+    fn send(&self, input: QueuedInput) {
+        if !self.shared.is_controller(self.id) {
+            return;
+        }
+
+        // The events are queued, and processed in order by whoever holds the handler: here, or in `ready`
+        // once done waiting, which checks the queue before releasing the handler.
+        let mut pending = self.shared.pending_input.lock().expect("poisoned");
+        pending.push_back(input);
+        match self.shared.handler.try_lock() {
+            Ok(mut handler) => pending.drain(..).for_each(|input| input.apply(handler.as_mut())),
+            Err(_) => trace!(id = %self.id, queued = pending.len(), "Input handler busy, queuing input event"),
+        }
+    }
+}
+
+impl RdpServerInputHandler for ViewerInputHandler {
+    fn keyboard(&mut self, event: KeyboardEvent) {
+        self.send(QueuedInput::Keyboard(event));
+    }
+
+    fn mouse(&mut self, event: MouseEvent) {
+        self.send(QueuedInput::Mouse(event));
+    }
+
+    async fn ready(&mut self) {
+        if !self.shared.is_controller(self.id) {
+            return;
+        }
+
+        let mut handler = self.shared.handler.lock().await;
+        handler.ready().await;
+
+        let mut pending = self.shared.pending_input.lock().expect("poisoned");
+        pending.drain(..).for_each(|input| input.apply(handler.as_mut()));
+        // Released with the queue locked, so no event is queued without being processed.
+        drop(handler);
+        drop(pending);
+    }
+
+    fn session_info(&mut self, info: &ClientSessionInfo) {
+        self.send(QueuedInput::SessionInfo(info.clone()));
+    }
+}
+
+impl Drop for ViewerInputHandler {
+    fn drop(&mut self) {
+        let mut viewers = self.shared.viewers.lock().expect("poisoned");
+        viewers.ids.retain(|&id| id != self.id);
+        if viewers.controller == Some(self.id) {
+            viewers.controller = None;
+        }
+
+        info!(id = %self.id, "Shadow viewer left");
+    }
+}
+
+struct ViewerDisplay {
+    id: ShadowViewerId,
+    shared: Arc<Shared>,
+}
+
+impl RdpServerDisplay for ViewerDisplay {
+    type Updates = ViewerUpdates;
+
+    async fn size(&mut self) -> DesktopSize {
+        self.shared.display.lock().await.size().await
+    }
+
+    async fn monitor_layout(&mut self) -> Vec<Monitor> {
+        self.shared.display.lock().await.monitor_layout().await
+    }
+
+    async fn updates(&mut self) -> Result<ViewerUpdates> {
+        let (receiver, start) = {
+            let mut viewers = self.shared.viewers.lock().expect("poisoned");
+            let start = !viewers.broadcasting;
+            viewers.broadcasting = true;
+            (self.shared.updates.subscribe(), start)
+        };
+
+        if start {
+            broadcast_updates(Arc::clone(&self.shared));
+        }
+
+        // The new viewer needs the whole display.
+        self.shared.request_refresh();
+
+        Ok(ViewerUpdates {
+            id: self.id,
+            receiver,
+            shared: Arc::clone(&self.shared),
+        })
+    }
+
+    fn request_refresh(&mut self) {
+        self.shared.request_refresh();
+    }
+
+    async fn resize(&mut self, size: DesktopSize) -> Option<DesktopSize> {
+        // The display is shared, only the controller resizes it.
+        if !self.shared.is_controller(self.id) {
+            return None;
+        }
+
+        self.shared.display.lock().await.resize(size).await
+    }
+
+    fn session_info(&mut self, info: &ClientSessionInfo) {
+        if !self.shared.is_controller(self.id) {
+            return;
+        }
+
+        // The display may be busy, e.g. resizing.
+        let shared = Arc::clone(&self.shared);
+        let info = info.clone();
+        tokio::spawn(async move {
+            shared.display.lock().await.session_info(&info);
+        });
+    }
+}
+
+struct ViewerUpdates {
+    id: ShadowViewerId,
+    receiver: broadcast::Receiver<Option<DisplayUpdate>>,
+    shared: Arc<Shared>,
+}
+
+impl RdpServerDisplayUpdates for ViewerUpdates {
+    async fn next_update(&mut self) -> Option<DisplayUpdate> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(update) => return update,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(id = %self.id, skipped, "Shadow viewer lagging behind, refreshing the display");
+                    self.shared.request_refresh();
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Broadcasts the updates of the display to the viewers, until none is left.
+fn broadcast_updates(shared: Arc<Shared>) {
+    tokio::spawn(async move {
+        let updates = shared.display.lock().await.updates().await;
+        let mut updates = match updates {
+            Ok(updates) => updates,
+            Err(error) => {
+                error!(?error, "Failed to get the shadow display updates");
+                shared.viewers.lock().expect("poisoned").broadcasting = false;
+                return;
+            }
+        };
+
+        loop {
+            let update = updates.next().await;
+
+            // The viewers subscribe with the lock held, none can be missed.
+            let mut viewers = shared.viewers.lock().expect("poisoned");
+            let ended = update.is_none();
+            if ended {
+                debug!("Shadow display updates ended");
+            }
+
+            if shared.updates.send(update).is_err() || ended {
+                debug!("Stopping the shadow display broadcast");
+                viewers.broadcasting = false;
+                break;
+            }
+        }
+    });
+}
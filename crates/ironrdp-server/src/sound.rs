@@ -1,7 +1,224 @@
+use core::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::{Context as _, Result};
+use ironrdp_rdpsnd::codec::AudioEncoder;
+pub use ironrdp_rdpsnd::codec::{AudioCodec, AudioCodecs, PcmCodec};
+pub use ironrdp_rdpsnd::pdu::AudioFormat;
+use ironrdp_rdpsnd::pdu::{ClientAudioFormatPdu, WaveFormat};
 pub use ironrdp_rdpsnd::server::{RdpsndServerHandler, RdpsndServerMessage};
+use tokio::sync::mpsc;
 
-use crate::ServerEventSender;
+use crate::{ServerEvent, ServerEventSender};
 
 pub trait SoundServerFactory: ServerEventSender {
     fn build_backend(&self) -> Box<dyn RdpsndServerHandler>;
 }
+
+/// Audio output of the server, streaming PCM samples to the clients.
+///
+/// Used with a [`PcmSoundFactory`], which negotiates the wave format with each client and encodes
+/// the samples using the registered codecs.
+pub trait RdpServerSound: Send + Sync {
+    /// Returns the format of the PCM samples.
+    ///
+    /// Only the wave formats with the same channel count and sample rate are advertised to the
+    /// clients, the samples are not resampled.
+    fn format(&self) -> AudioFormat;
+
+    /// Called when a client is ready to play audio.
+    ///
+    /// The samples are fed to the client using the sender, until [`SoundSender::send`] fails
+    /// because the client is disconnected.
+    fn start(&self, sender: SoundSender);
+
+    /// Called when the audio output channel of a client is closed.
+    fn stop(&self) {}
+}
+
+/// Feeds PCM samples to the audio output of a client.
+pub struct SoundSender {
+    encoder: Box<dyn AudioEncoder>,
+    format: AudioFormat,
+    ev_sender: mpsc::UnboundedSender<ServerEvent>,
+    start: Instant,
+}
+
+impl fmt::Debug for SoundSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SoundSender")
+            .field("encoder", &self.encoder)
+            .field("format", &self.format)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SoundSender {
+    /// Returns the wave format negotiated with the client.
+    pub fn format(&self) -> &AudioFormat {
+        &self.format
+    }
+
+    /// Encodes a block of interleaved little-endian PCM samples and sends it to the client.
+    ///
+    /// The samples are timestamped with the time elapsed since the client is ready to play audio.
+    pub fn send(&mut self, pcm: &[u8]) -> Result<()> {
+        let data = self.encoder.encode(pcm).context("failed to encode audio")?;
+
+        #[allow(clippy::cast_possible_truncation)] // The audio timestamp is in milliseconds and wraps around.
+        let ts = self.start.elapsed().as_millis() as u32;
+
+        self.ev_sender
+            .send(ServerEvent::Rdpsnd(RdpsndServerMessage::Wave(data, ts)))
+            .context("client disconnected")
+    }
+
+    /// Sets the volume of the client, if supported.
+    pub fn set_volume(&self, left: u16, right: u16) -> Result<()> {
+        self.ev_sender
+            .send(ServerEvent::Rdpsnd(RdpsndServerMessage::SetVolume { left, right }))
+            .context("client disconnected")
+    }
+}
+
+/// Creates the audio output of each connection from a PCM source.
+pub struct PcmSoundFactory {
+    sound: Arc<dyn RdpServerSound>,
+    codecs: Arc<AudioCodecs>,
+    ev_sender: Option<mpsc::UnboundedSender<ServerEvent>>,
+}
+
+impl fmt::Debug for PcmSoundFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PcmSoundFactory")
+            .field("codecs", &self.codecs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PcmSoundFactory {
+    /// Creates a factory streaming the samples of `sound`, encoded using `codecs`.
+    ///
+    /// Codecs registered first are preferred, [`PcmCodec`] should usually be registered last as a
+    /// fallback.
+    pub fn new(sound: Arc<dyn RdpServerSound>, codecs: AudioCodecs) -> Self {
+        Self {
+            sound,
+            codecs: Arc::new(codecs),
+            ev_sender: None,
+        }
+    }
+}
+
+impl ServerEventSender for PcmSoundFactory {
+    fn set_sender(&mut self, sender: mpsc::UnboundedSender<ServerEvent>) {
+        self.ev_sender = Some(sender);
+    }
+}
+
+impl SoundServerFactory for PcmSoundFactory {
+    fn build_backend(&self) -> Box<dyn RdpsndServerHandler> {
+        let pcm = self.sound.format();
+        let formats = self
+            .codecs
+            .formats()
+            .into_iter()
+            .filter(|format| is_compatible(&pcm, format))
+            .collect::<Vec<_>>();
+
+        if formats.is_empty() {
+            warn!(?pcm, "No audio codec format compatible with the PCM samples");
+        }
+
+        Box::new(PcmSoundHandler {
+            sound: Arc::clone(&self.sound),
+            codecs: Arc::clone(&self.codecs),
+            ev_sender: self.ev_sender.clone(),
+            pcm,
+            formats,
+        })
+    }
+}
+
+/// Returns whether the samples can be encoded into the format without resampling.
+pub(crate) fn is_compatible(pcm: &AudioFormat, format: &AudioFormat) -> bool {
+    format.n_channels == pcm.n_channels
+        && format.n_samples_per_sec == pcm.n_samples_per_sec
+        && (format.format != WaveFormat::PCM || format.bits_per_sample == pcm.bits_per_sample)
+}
+
+struct PcmSoundHandler {
+    sound: Arc<dyn RdpServerSound>,
+    codecs: Arc<AudioCodecs>,
+    ev_sender: Option<mpsc::UnboundedSender<ServerEvent>>,
+    pcm: AudioFormat,
+    formats: Vec<AudioFormat>,
+}
+
+impl fmt::Debug for PcmSoundHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PcmSoundHandler")
+            .field("pcm", &self.pcm)
+            .field("formats", &self.formats)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RdpsndServerHandler for PcmSoundHandler {
+    fn get_formats(&self) -> &[AudioFormat] {
+        &self.formats
+    }
+
+    fn start(&mut self, client_format: &ClientAudioFormatPdu) -> Option<u16> {
+        let Some(ev_sender) = self.ev_sender.clone() else {
+            warn!("No server event sender, audio output disabled");
+            return None;
+        };
+
+        // The client formats are indexed by the wave PDUs, the incompatible ones are skipped
+        // while keeping their position.
+        let compatible = client_format
+            .formats
+            .iter()
+            .enumerate()
+            .filter(|(_, format)| is_compatible(&self.pcm, format))
+            .collect::<Vec<_>>();
+        let offered = compatible
+            .iter()
+            .map(|(_, format)| (*format).clone())
+            .collect::<Vec<_>>();
+
+        let Some((index, format)) = self
+            .codecs
+            .negotiate(&offered)
+            .and_then(|selected| compatible.get(usize::from(selected)))
+        else {
+            warn!(formats = ?client_format.formats, "No audio format supported by the client");
+            return None;
+        };
+
+        let encoder = match self.codecs.new_encoder(format) {
+            Ok(encoder) => encoder,
+            Err(error) => {
+                warn!(%error, ?format, "Failed to create audio encoder");
+                return None;
+            }
+        };
+
+        debug!(?format, "Starting audio output");
+
+        self.sound.start(SoundSender {
+            encoder,
+            format: (*format).clone(),
+            ev_sender,
+            start: Instant::now(),
+        });
+
+        u16::try_from(*index).ok()
+    }
+
+    fn stop(&mut self) {
+        self.sound.stop();
+    }
+}
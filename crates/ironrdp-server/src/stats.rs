@@ -0,0 +1,193 @@
+use core::fmt;
+use core::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Codec used to encode a frame, i.e. a bitmap update of the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameCodec {
+    /// Surface bits without compression.
+    Uncompressed,
+    /// Bitmap updates with RDP 6.0 compression.
+    Bitmap,
+    RemoteFx,
+    /// Graphics pipeline (EGFX) with AVC420 or AVC444.
+    H264,
+}
+
+/// Kind of an input event received from a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputKind {
+    Keyboard,
+    Mouse,
+}
+
+/// Statistics of a connection, see [`RdpServerSessions::stats`](crate::RdpServerSessions::stats).
+///
+/// The counters are accumulated from the start of the connection. Rates, such as the frame rate or the
+/// throughput, are computed by sampling the counters periodically.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectionStats {
+    /// Bytes sent to the client once it is connected.
+    pub bytes_sent: u64,
+    /// Bytes received from the client once it is connected.
+    pub bytes_received: u64,
+    pub keyboard_events: u64,
+    pub mouse_events: u64,
+    pub uncompressed_frames: u64,
+    pub bitmap_frames: u64,
+    pub remote_fx_frames: u64,
+    pub h264_frames: u64,
+}
+
+impl ConnectionStats {
+    /// Returns the number of frames encoded, with any codec.
+    pub fn frames(&self) -> u64 {
+        self.uncompressed_frames
+            .saturating_add(self.bitmap_frames)
+            .saturating_add(self.remote_fx_frames)
+            .saturating_add(self.h264_frames)
+    }
+
+    pub fn input_events(&self) -> u64 {
+        self.keyboard_events.saturating_add(self.mouse_events)
+    }
+}
+
+/// Callbacks receiving the statistics of all the connections as they are recorded, e.g. to update the
+/// counters of a Prometheus exporter.
+///
+/// The callbacks are called from the connection tasks and should return quickly.
+pub trait RdpServerMetrics: Send + Sync {
+    fn bytes_sent(&self, bytes: usize) {
+        let _ = bytes;
+    }
+
+    fn bytes_received(&self, bytes: usize) {
+        let _ = bytes;
+    }
+
+    fn input_event(&self, kind: InputKind) {
+        let _ = kind;
+    }
+
+    fn frame_encoded(&self, codec: FrameCodec) {
+        let _ = codec;
+    }
+}
+
+struct RecorderState {
+    stats: ConnectionStats,
+    started_at: Instant,
+    last_input: Instant,
+    last_received: Instant,
+}
+
+impl Default for RecorderState {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self {
+            stats: ConnectionStats::default(),
+            started_at: now,
+            last_input: now,
+            last_received: now,
+        }
+    }
+}
+
+/// Records the statistics of a connection, and forwards them to the metrics of the server.
+#[derive(Clone)]
+pub(crate) struct StatsRecorder {
+    state: Arc<Mutex<RecorderState>>,
+    metrics: Option<Arc<dyn RdpServerMetrics>>,
+}
+
+impl fmt::Debug for StatsRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatsRecorder")
+            .field("stats", &self.snapshot())
+            .finish()
+    }
+}
+
+impl StatsRecorder {
+    /// Returns a recorder for a new connection, forwarding to the given metrics.
+    pub(crate) fn new(metrics: Option<Arc<dyn RdpServerMetrics>>) -> Self {
+        Self {
+            state: Arc::default(),
+            metrics,
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> ConnectionStats {
+        self.state.lock().expect("poisoned").stats
+    }
+
+    /// Returns the time elapsed since the start of the connection.
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.state.lock().expect("poisoned").started_at.elapsed()
+    }
+
+    /// Returns the time elapsed since the last input event, or the start of the connection.
+    pub(crate) fn idle(&self) -> Duration {
+        self.state.lock().expect("poisoned").last_input.elapsed()
+    }
+
+    /// Returns the time elapsed since data was last received from the client, or the start of the connection.
+    pub(crate) fn silence(&self) -> Duration {
+        self.state.lock().expect("poisoned").last_received.elapsed()
+    }
+
+    pub(crate) fn bytes_sent(&self, bytes: usize) {
+        add(&mut self.state.lock().expect("poisoned").stats.bytes_sent, bytes);
+        if let Some(metrics) = &self.metrics {
+            metrics.bytes_sent(bytes);
+        }
+    }
+
+    pub(crate) fn bytes_received(&self, bytes: usize) {
+        let mut state = self.state.lock().expect("poisoned");
+        state.last_received = Instant::now();
+        add(&mut state.stats.bytes_received, bytes);
+        drop(state);
+
+        if let Some(metrics) = &self.metrics {
+            metrics.bytes_received(bytes);
+        }
+    }
+
+    pub(crate) fn input_event(&self, kind: InputKind) {
+        let mut state = self.state.lock().expect("poisoned");
+        state.last_input = Instant::now();
+        let counter = match kind {
+            InputKind::Keyboard => &mut state.stats.keyboard_events,
+            InputKind::Mouse => &mut state.stats.mouse_events,
+        };
+        add(counter, 1);
+        drop(state);
+
+        if let Some(metrics) = &self.metrics {
+            metrics.input_event(kind);
+        }
+    }
+
+    pub(crate) fn frame_encoded(&self, codec: FrameCodec) {
+        let mut state = self.state.lock().expect("poisoned");
+        let counter = match codec {
+            FrameCodec::Uncompressed => &mut state.stats.uncompressed_frames,
+            FrameCodec::Bitmap => &mut state.stats.bitmap_frames,
+            FrameCodec::RemoteFx => &mut state.stats.remote_fx_frames,
+            FrameCodec::H264 => &mut state.stats.h264_frames,
+        };
+        add(counter, 1);
+        drop(state);
+
+        if let Some(metrics) = &self.metrics {
+            metrics.frame_encoded(codec);
+        }
+    }
+}
+
+fn add(counter: &mut u64, value: usize) {
+    *counter = counter.saturating_add(u64::try_from(value).unwrap_or(u64::MAX));
+}
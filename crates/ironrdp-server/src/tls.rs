@@ -0,0 +1,219 @@
+use core::fmt;
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, RwLock};
+
+use anyhow::{Context as _, Result};
+use tokio_rustls::rustls::pki_types::CertificateDer;
+use tokio_rustls::rustls::server::danger::ClientCertVerifier;
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use tokio_rustls::rustls::sign::CertifiedKey;
+use tokio_rustls::rustls::{ProtocolVersion, RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+use crate::{RdpServerStream, TlsVersion};
+
+/// TLS implementation securing the connections, in place of rustls.
+///
+/// This allows to use another TLS library (e.g. a FIPS-validated OpenSSL, or SChannel with the certificates of the
+/// platform store) without forking the TLS upgrade code.
+#[async_trait::async_trait]
+pub trait TlsServerAcceptor: Send + Sync {
+    /// Performs the TLS handshake with the client.
+    ///
+    /// Returns the secured stream, along with the subject public key of the certificate presented to the client
+    /// (see [`certificate_public_key`]), which the client verifies during the CredSSP authentication.
+    async fn accept(&self, stream: Box<dyn RdpServerStream>) -> io::Result<(Box<dyn RdpServerStream>, Vec<u8>)>;
+}
+
+#[derive(Clone)]
+struct TlsCertificate {
+    key: Arc<CertifiedKey>,
+    /// Public key of the certificate, which the client verifies during the CredSSP authentication.
+    pub_key: Vec<u8>,
+}
+
+impl TlsCertificate {
+    fn new(key: Arc<CertifiedKey>) -> Result<Self> {
+        let cert = key.end_entity_cert().context("no end-entity certificate")?;
+        let pub_key = public_key(cert)?;
+
+        Ok(Self { key, pub_key })
+    }
+}
+
+#[derive(Default)]
+struct Certificates {
+    default: Option<TlsCertificate>,
+    by_name: HashMap<String, TlsCertificate>,
+    /// Verifier of the certificates of the clients, when the clients are required to present one.
+    client_verifier: Option<Arc<dyn ClientCertVerifier>>,
+}
+
+/// Certificates of the server, selected using the server name requested by the client (SNI).
+///
+/// The certificates can be replaced while the server is running, e.g. when they are renewed: the new
+/// certificates are used by the next connections, the established connections are not affected.
+///
+/// ```no_run
+///# use std::sync::Arc;
+///# use ironrdp_server::TlsCertificates;
+///# use ironrdp_server::tokio_rustls::rustls::sign::CertifiedKey;
+///# fn load(name: &str) -> Arc<CertifiedKey> { todo!() }
+///# fn stub() -> anyhow::Result<()> {
+/// let certificates = Arc::new(TlsCertificates::new(load("default"))?);
+/// certificates.insert("tenant.example.com", load("tenant"))?;
+///
+/// // Later, once the certificate is renewed.
+/// certificates.set_default(load("default"))?;
+///# Ok(())
+///# }
+/// ```
+#[derive(Default)]
+pub struct TlsCertificates {
+    certificates: RwLock<Certificates>,
+}
+
+impl fmt::Debug for TlsCertificates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let certificates = self.certificates.read().expect("poisoned");
+        f.debug_struct("TlsCertificates")
+            .field("default", &certificates.default.is_some())
+            .field("names", &certificates.by_name.keys())
+            .field("client_auth", &certificates.client_verifier.is_some())
+            .finish()
+    }
+}
+
+impl TlsCertificates {
+    /// Creates the certificates of the server, with the certificate used when the client requests no
+    /// known server name.
+    pub fn new(default: Arc<CertifiedKey>) -> Result<Self> {
+        let certificates = Self::default();
+        certificates.set_default(default)?;
+
+        Ok(certificates)
+    }
+
+    /// Sets the certificate used when the client requests no known server name.
+    pub fn set_default(&self, key: Arc<CertifiedKey>) -> Result<()> {
+        let certificate = TlsCertificate::new(key)?;
+        self.certificates.write().expect("poisoned").default = Some(certificate);
+
+        Ok(())
+    }
+
+    /// Adds or replaces the certificate of a server name.
+    pub fn insert(&self, server_name: &str, key: Arc<CertifiedKey>) -> Result<()> {
+        let certificate = TlsCertificate::new(key)?;
+        self.certificates
+            .write()
+            .expect("poisoned")
+            .by_name
+            .insert(server_name.to_ascii_lowercase(), certificate);
+
+        Ok(())
+    }
+
+    /// Removes the certificate of a server name, returning `true` if it was present.
+    pub fn remove(&self, server_name: &str) -> bool {
+        self.certificates
+            .write()
+            .expect("poisoned")
+            .by_name
+            .remove(&server_name.to_ascii_lowercase())
+            .is_some()
+    }
+
+    /// Requires the clients to present a certificate issued by one of the given authorities (mutual TLS).
+    ///
+    /// The certificate of the client is then given in the [`ConnectionInfo`](crate::ConnectionInfo) of the
+    /// connection, e.g. to authorize the device in the [`RdpServerAuthorizer`](crate::RdpServerAuthorizer).
+    pub fn require_client_certificates(&self, roots: RootCertStore) -> Result<()> {
+        let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+            .build()
+            .context("client certificate verifier")?;
+        self.set_client_verifier(Some(verifier));
+
+        Ok(())
+    }
+
+    /// Sets the verifier of the certificates of the clients, or `None` to not request a certificate from the
+    /// clients.
+    pub fn set_client_verifier(&self, verifier: Option<Arc<dyn ClientCertVerifier>>) {
+        self.certificates.write().expect("poisoned").client_verifier = verifier;
+    }
+
+    /// Returns a TLS acceptor using these certificates.
+    pub fn acceptor(self: &Arc<Self>) -> TlsAcceptor {
+        let client_verifier = self.certificates.read().expect("poisoned").client_verifier.clone();
+
+        let builder = ServerConfig::builder();
+        let builder = match client_verifier {
+            Some(verifier) => builder.with_client_cert_verifier(verifier),
+            None => builder.with_no_client_auth(),
+        };
+        let config = builder.with_cert_resolver(Arc::clone(self) as Arc<dyn ResolvesServerCert>);
+
+        TlsAcceptor::from(Arc::new(config))
+    }
+
+    fn get(&self, server_name: Option<&str>) -> Option<TlsCertificate> {
+        let certificates = self.certificates.read().expect("poisoned");
+        server_name
+            .and_then(|name| certificates.by_name.get(&name.to_ascii_lowercase()))
+            .or(certificates.default.as_ref())
+            .cloned()
+    }
+
+    /// Returns the public key of the certificate selected for a server name.
+    /// Returns the version checked against the [`SecurityPolicy`](crate::SecurityPolicy) of the server.
+    ///
+    /// The versions older than TLS 1.0, which rustls never negotiates anyway, are reported as TLS 1.0.
+    pub(crate) fn tls_version(version: ProtocolVersion) -> TlsVersion {
+        match version {
+            ProtocolVersion::TLSv1_3 => TlsVersion::Tls1_3,
+            ProtocolVersion::TLSv1_2 => TlsVersion::Tls1_2,
+            ProtocolVersion::TLSv1_1 => TlsVersion::Tls1_1,
+            _ => TlsVersion::Tls1_0,
+        }
+    }
+
+    pub(crate) fn public_key(&self, server_name: Option<&str>) -> Option<Vec<u8>> {
+        self.get(server_name).map(|certificate| certificate.pub_key)
+    }
+}
+
+impl ResolvesServerCert for TlsCertificates {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let certificate = self.get(client_hello.server_name());
+        if certificate.is_none() {
+            warn!(
+                server_name = client_hello.server_name(),
+                "No TLS certificate for the server name"
+            );
+        }
+
+        certificate.map(|certificate| certificate.key)
+    }
+}
+
+pub(crate) fn public_key(cert: &CertificateDer<'_>) -> Result<Vec<u8>> {
+    certificate_public_key(cert)
+}
+
+/// Returns the subject public key of a DER-encoded certificate, as expected by CredSSP.
+pub fn certificate_public_key(cert: &[u8]) -> Result<Vec<u8>> {
+    use x509_cert::der::Decode as _;
+
+    let cert = x509_cert::Certificate::from_der(cert).context("invalid certificate")?;
+    let pub_key = cert
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key
+        .as_bytes()
+        .context("subject public key BIT STRING is not aligned")?
+        .to_owned();
+
+    Ok(pub_key)
+}
@@ -0,0 +1,95 @@
+//! RDP over WebSocket.
+//!
+//! The RDP stream is carried in binary WebSocket messages, each message holding any part of the
+//! stream, as done by the web clients connecting through a WebSocket gateway. The connections are then
+//! secured with TLS inside the WebSocket, as any other connection.
+
+use core::time::Duration;
+use std::io;
+use std::net::SocketAddr;
+
+pub use ironrdp_tokio::websocket::WebSocketStream;
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::task::JoinSet;
+use tokio_tungstenite::tungstenite;
+
+use crate::{PeerInfo, RdpServerListener, RdpServerStream};
+
+/// Time given to the clients to complete the WebSocket handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Handshake = (
+    SocketAddr,
+    Result<tokio_tungstenite::WebSocketStream<TcpStream>, tungstenite::Error>,
+);
+
+/// Accepts RDP connections over WebSocket, see [`WebSocketStream`].
+///
+/// The WebSocket handshakes are run concurrently, a slow client does not delay the other connections.
+pub struct WebSocketListener {
+    listener: TcpListener,
+    handshakes: JoinSet<Option<Handshake>>,
+}
+
+impl WebSocketListener {
+    pub async fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self::from(TcpListener::bind(addr).await?))
+    }
+}
+
+impl From<TcpListener> for WebSocketListener {
+    fn from(listener: TcpListener) -> Self {
+        Self {
+            listener,
+            handshakes: JoinSet::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RdpServerListener for WebSocketListener {
+    async fn accept(&mut self) -> io::Result<(Box<dyn RdpServerStream>, PeerInfo)> {
+        loop {
+            // Both branches are cancel safe, the accepted streams are kept in the join set.
+            tokio::select! {
+                accepted = self.listener.accept() => {
+                    let (stream, addr) = accepted?;
+                    self.handshakes.spawn(async move {
+                        let handshake = tokio_tungstenite::accept_async(stream);
+                        match tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake).await {
+                            Ok(result) => Some((addr, result)),
+                            Err(_) => {
+                                warn!(%addr, "WebSocket handshake timed out");
+                                None
+                            }
+                        }
+                    });
+                }
+                Some(joined) = self.handshakes.join_next() => {
+                    match joined {
+                        Ok(Some((addr, Ok(stream)))) => {
+                            debug!(%addr, "Accepted WebSocket connection");
+                            return Ok((Box::new(WebSocketStream::new(stream)), addr.into()));
+                        }
+                        Ok(Some((addr, Err(error)))) => warn!(%addr, %error, "WebSocket handshake failed"),
+                        Ok(None) => {}
+                        Err(error) => error!(?error, "WebSocket handshake task failed"),
+                    }
+                }
+            }
+        }
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.local_addr().ok()
+    }
+}
+
+impl core::fmt::Debug for WebSocketListener {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WebSocketListener")
+            .field("local_addr", &self.listener.local_addr().ok())
+            .field("handshakes", &self.handshakes.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -1,3 +1,4 @@
+use core::num::NonZeroUsize;
 use std::sync::Arc;
 
 use ironrdp_connector::connection_activation::ConnectionActivationSequence;
@@ -9,10 +10,12 @@ use ironrdp_graphics::pointer::DecodedPointer;
 use ironrdp_pdu::geometry::InclusiveRectangle;
 use ironrdp_pdu::input::fast_path::{FastPathInput, FastPathInputEvent};
 use ironrdp_pdu::rdp::headers::ShareDataPdu;
+use ironrdp_pdu::rdp::server_error_info::ErrorInfo;
 use ironrdp_pdu::{mcs, Action};
 use ironrdp_svc::{SvcProcessor, SvcProcessorMessages};
 
 use crate::fast_path::UpdateKind;
+use crate::heartbeat::{HeartbeatPolicy, Liveness};
 use crate::image::DecodedImage;
 use crate::{fast_path, x224, SessionError, SessionErrorExt, SessionResult};
 
@@ -20,16 +23,22 @@ pub struct ActiveStage {
     x224_processor: x224::Processor,
     fast_path_processor: fast_path::Processor,
     no_server_pointer: bool,
+    /// Number of threads decoding the graphics updates, if not the default.
+    decode_threads: Option<NonZeroUsize>,
+    /// Number of frames received from the server, used as the sequence number of the PDU spans.
+    received_frames: u64,
 }
 
 impl ActiveStage {
     pub fn new(connection_result: ConnectionResult) -> Self {
-        let x224_processor = x224::Processor::new(
+        let mut x224_processor = x224::Processor::new(
             connection_result.static_channels,
             connection_result.user_channel_id,
             connection_result.io_channel_id,
+            connection_result.message_channel_id,
             connection_result.connection_activation,
         );
+        x224_processor.ignore_channels(connection_result.named_static_channels.into_iter().map(|(_, id)| id));
 
         let fast_path_processor = fast_path::ProcessorBuilder {
             io_channel_id: connection_result.io_channel_id,
@@ -43,6 +52,8 @@ impl ActiveStage {
             x224_processor,
             fast_path_processor,
             no_server_pointer: connection_result.no_server_pointer,
+            decode_threads: None,
+            received_frames: 0,
         }
     }
 
@@ -98,14 +109,24 @@ impl ActiveStage {
     }
 
     /// Process a frame received from the server.
+    #[instrument(
+        level = "debug",
+        name = "pdu",
+        skip_all,
+        fields(pdu.sequence = self.received_frames, pdu.action = ?action, pdu.size = frame.len())
+    )]
     pub fn process(
         &mut self,
         image: &mut DecodedImage,
         action: Action,
         frame: &[u8],
     ) -> SessionResult<Vec<ActiveStageOutput>> {
+        self.received_frames += 1;
+
         let (mut stage_outputs, processor_updates) = match action {
             Action::FastPath => {
+                self.x224_processor.heartbeat_mut().frame_received();
+
                 let mut output = WriteBuf::new();
                 let processor_updates = self.fast_path_processor.process(image, frame, &mut output)?;
                 (
@@ -148,7 +169,10 @@ impl ActiveStage {
         Ok(stage_outputs)
     }
 
-    pub fn set_fastpath_processor(&mut self, processor: fast_path::Processor) {
+    pub fn set_fastpath_processor(&mut self, mut processor: fast_path::Processor) {
+        if let Some(threads) = self.decode_threads {
+            processor.set_decode_threads(threads);
+        }
         self.fast_path_processor = processor;
     }
 
@@ -156,6 +180,36 @@ impl ActiveStage {
         self.no_server_pointer = no_server_pointer;
     }
 
+    /// Sets the number of threads decoding the graphics updates, the calling thread included.
+    ///
+    /// Defaults to [`default_decode_threads`](crate::default_decode_threads), based on the number of cores. With a
+    /// single thread, the updates are decoded on the thread calling [`ActiveStage::process`] only, and no thread is
+    /// spawned.
+    pub fn set_decode_threads(&mut self, threads: NonZeroUsize) {
+        self.decode_threads = Some(threads);
+        self.fast_path_processor.set_decode_threads(threads);
+    }
+
+    /// Sets the thresholds applied to the missed heartbeats of the server.
+    pub fn set_heartbeat_policy(&mut self, policy: HeartbeatPolicy) {
+        self.x224_processor.heartbeat_mut().set_policy(policy);
+    }
+
+    /// Returns the interval at which [`ActiveStage::check_heartbeat`] must be called, once the server
+    /// sent its first heartbeat.
+    pub fn heartbeat_period(&self) -> Option<core::time::Duration> {
+        self.x224_processor.heartbeat().period()
+    }
+
+    /// Counts a missed heartbeat if nothing was received from the server since the last call, and returns
+    /// the state of the connection.
+    ///
+    /// Detects a broken connection faster than the TCP timeouts: the connection should be closed when
+    /// [`Liveness::Lost`] is returned.
+    pub fn check_heartbeat(&mut self) -> Liveness {
+        self.x224_processor.heartbeat_mut().tick()
+    }
+
     /// Encodes client-side graceful shutdown request. Note that upon sending this request,
     /// client should wait for server's ShutdownDenied PDU before closing the connection.
     ///
@@ -257,6 +311,7 @@ pub enum ActiveStageOutput {
     PointerBitmap(Arc<DecodedPointer>),
     Terminate(GracefulDisconnectReason),
     DeactivateAll(Box<ConnectionActivationSequence>),
+    SessionEvent(x224::SessionEvent),
 }
 
 impl TryFrom<x224::ProcessorOutput> for ActiveStageOutput {
@@ -272,12 +327,13 @@ impl TryFrom<x224::ProcessorOutput> for ActiveStageOutput {
                         mcs::DisconnectReason::UserRequested => GracefulDisconnectReason::UserInitiated,
                         other => GracefulDisconnectReason::Other(other.description().to_owned()),
                     },
-                    x224::DisconnectDescription::ErrorInfo(info) => GracefulDisconnectReason::Other(info.description()),
+                    x224::DisconnectDescription::ErrorInfo(info) => GracefulDisconnectReason::ServerErrorInfo(info),
                 };
 
                 Ok(Self::Terminate(desc))
             }
             x224::ProcessorOutput::DeactivateAll(cas) => Ok(Self::DeactivateAll(cas)),
+            x224::ProcessorOutput::SessionEvent(event) => Ok(Self::SessionEvent(event)),
         }
     }
 }
@@ -288,6 +344,8 @@ impl TryFrom<x224::ProcessorOutput> for ActiveStageOutput {
 pub enum GracefulDisconnectReason {
     UserInitiated,
     ServerInitiated,
+    /// The server sent a Set Error Info PDU, whose category tells why the session ended (e.g. logoff, timeout).
+    ServerErrorInfo(ErrorInfo),
     Other(String),
 }
 
@@ -296,6 +354,7 @@ impl GracefulDisconnectReason {
         match self {
             GracefulDisconnectReason::UserInitiated => "user initiated disconnect".to_owned(),
             GracefulDisconnectReason::ServerInitiated => "server initiated disconnect".to_owned(),
+            GracefulDisconnectReason::ServerErrorInfo(info) => info.description(),
             GracefulDisconnectReason::Other(description) => description.clone(),
         }
     }
@@ -0,0 +1,136 @@
+use core::num::NonZeroUsize;
+use core::panic::AssertUnwindSafe;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+
+/// Maximum number of decode threads used by default, a frame rarely having enough tiles to keep more busy.
+const MAX_DEFAULT_DECODE_THREADS: usize = 8;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Returns the default number of decode threads: one per core, up to 8.
+///
+/// A single thread is returned when the number of cores can't be determined (e.g. on WebAssembly).
+pub fn default_decode_threads() -> NonZeroUsize {
+    let threads = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(MAX_DEFAULT_DECODE_THREADS);
+
+    NonZeroUsize::new(threads).unwrap_or(NonZeroUsize::MIN)
+}
+
+/// Worker threads decoding the independent parts of a graphics update, e.g. the RemoteFX tiles of a frame.
+///
+/// The calling thread takes its share of the work, so a pool of `n` threads spawns `n - 1` workers, and none
+/// when single-threaded. The workers are spawned on first use, and stopped when the pool is dropped.
+pub(crate) struct DecodePool {
+    threads: NonZeroUsize,
+    workers: Vec<Worker>,
+}
+
+struct Worker {
+    jobs: mpsc::Sender<Job>,
+    handle: JoinHandle<()>,
+}
+
+impl DecodePool {
+    pub(crate) fn new(threads: NonZeroUsize) -> Self {
+        Self {
+            threads,
+            workers: Vec::new(),
+        }
+    }
+
+    pub(crate) fn threads(&self) -> NonZeroUsize {
+        self.threads
+    }
+
+    /// Runs the jobs, the first one on the calling thread and the other ones on the workers, and returns their
+    /// results in order.
+    ///
+    /// There must not be more jobs than threads. A panic of a job is propagated to the calling thread.
+    pub(crate) fn run<T, F>(&mut self, jobs: Vec<F>) -> Vec<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        debug_assert!(jobs.len() <= self.threads.get());
+
+        let count = jobs.len();
+        let (results_tx, results_rx) = mpsc::channel();
+        let mut jobs = jobs.into_iter().enumerate();
+        let first = jobs.next();
+
+        for (index, job) in jobs {
+            let results_tx = results_tx.clone();
+            let job: Job = Box::new(move || {
+                let result = std::panic::catch_unwind(AssertUnwindSafe(job));
+                let _ = results_tx.send((index, result));
+            });
+
+            // The job is run on the calling thread if its worker can't be spawned.
+            let job = match self.worker(index - 1) {
+                Some(worker) => match worker.jobs.send(job) {
+                    Ok(()) => continue,
+                    Err(mpsc::SendError(job)) => job,
+                },
+                None => job,
+            };
+            job();
+        }
+
+        if let Some((index, job)) = first {
+            let result = std::panic::catch_unwind(AssertUnwindSafe(job));
+            let _ = results_tx.send((index, result));
+        }
+        drop(results_tx);
+
+        let mut results: Vec<Option<T>> = (0..count).map(|_| None).collect();
+        for (index, result) in results_rx {
+            match result {
+                Ok(result) => results[index] = Some(result),
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.expect("each job sends its result"))
+            .collect()
+    }
+
+    /// Returns the worker of the given index, spawning the missing workers.
+    fn worker(&mut self, index: usize) -> Option<&Worker> {
+        while self.workers.len() <= index {
+            let (jobs_tx, jobs_rx) = mpsc::channel::<Job>();
+
+            let handle = thread::Builder::new()
+                .name(format!("rdp-decode-{}", self.workers.len() + 1))
+                .spawn(move || {
+                    for job in jobs_rx {
+                        job();
+                    }
+                });
+
+            match handle {
+                Ok(handle) => self.workers.push(Worker { jobs: jobs_tx, handle }),
+                Err(error) => {
+                    warn!(%error, "Failed to spawn a decode thread");
+                    return None;
+                }
+            }
+        }
+
+        self.workers.get(index)
+    }
+}
+
+impl Drop for DecodePool {
+    fn drop(&mut self) {
+        for Worker { jobs, handle } in self.workers.drain(..) {
+            // Closing the channel stops the worker once its pending jobs are done.
+            drop(jobs);
+            let _ = handle.join();
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use core::num::NonZeroUsize;
 use std::sync::Arc;
 
 use ironrdp_core::{decode_cursor, DecodeErrorKind, ReadCursor, WriteBuf};
@@ -44,6 +45,11 @@ impl Processor {
         self.mouse_pos_update = Some((x, y));
     }
 
+    /// Sets the number of threads decoding the RemoteFX tiles, the calling thread included.
+    pub fn set_decode_threads(&mut self, threads: NonZeroUsize) {
+        self.rfx_handler.set_decode_threads(threads);
+    }
+
     /// Process input fast path frame and return list of updates.
     pub fn process(
         &mut self,
@@ -65,7 +71,12 @@ impl Processor {
         trace!(fast_path_header = ?header, "Received Fast-Path packet");
 
         let update_pdu = decode_cursor::<FastPathUpdatePdu<'_>>(&mut input).map_err(SessionError::decode)?;
-        trace!(fast_path_update_fragmentation = ?update_pdu.fragmentation);
+        trace!(
+            pdu.kind = ?update_pdu.update_code,
+            pdu.size = update_pdu.data.len(),
+            fast_path_update_fragmentation = ?update_pdu.fragmentation,
+            "Received Fast-Path update"
+        );
 
         let processed_complete_data = self
             .complete_data
@@ -0,0 +1,119 @@
+use core::time::Duration;
+
+use ironrdp_pdu::rdp::heartbeat::HeartbeatPdu;
+
+/// Thresholds applied to the missed heartbeats, overriding the ones sent by the server.
+///
+/// By default, the counts of the Server Heartbeat PDU (\[MS-RDPBCGR\] 2.2.16.1) are used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeartbeatPolicy {
+    /// Number of missed heartbeats after which the connection is degraded.
+    pub warning_count: Option<u8>,
+    /// Number of missed heartbeats after which the connection is lost.
+    pub lost_count: Option<u8>,
+}
+
+/// State of the connection, as seen from the heartbeats of the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liveness {
+    /// The server doesn't send heartbeats, so the connection is not monitored.
+    Unmonitored,
+    Alive,
+    /// Some heartbeats were missed, the user should be warned.
+    Degraded {
+        missed: u32,
+    },
+    /// Too many heartbeats were missed, the connection should be closed (and possibly reconnected).
+    Lost {
+        missed: u32,
+    },
+}
+
+/// Tracks the missed heartbeats of the server.
+///
+/// The monitor doesn't read the clock: [`HeartbeatMonitor::tick`] must be called by the embedder every
+/// [`HeartbeatMonitor::period`], and a heartbeat is missed when nothing was received from the server
+/// between two ticks.
+#[derive(Debug, Clone)]
+pub struct HeartbeatMonitor {
+    policy: HeartbeatPolicy,
+    /// Last heartbeat received from the server.
+    heartbeat: Option<HeartbeatPdu>,
+    /// Whether something was received from the server since the last tick.
+    received: bool,
+    missed: u32,
+}
+
+impl HeartbeatMonitor {
+    pub fn new(policy: HeartbeatPolicy) -> Self {
+        Self {
+            policy,
+            heartbeat: None,
+            received: false,
+            missed: 0,
+        }
+    }
+
+    pub fn policy(&self) -> HeartbeatPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: HeartbeatPolicy) {
+        self.policy = policy;
+    }
+
+    /// Returns the interval between two heartbeats, once the server sent its first heartbeat.
+    pub fn period(&self) -> Option<Duration> {
+        self.heartbeat
+            .filter(|heartbeat| heartbeat.period != 0)
+            .map(|heartbeat| Duration::from_secs(u64::from(heartbeat.period)))
+    }
+
+    /// Returns the state of the connection, without counting a missed heartbeat.
+    pub fn liveness(&self) -> Liveness {
+        let Some(heartbeat) = self.heartbeat else {
+            return Liveness::Unmonitored;
+        };
+
+        // A zero count disables the threshold.
+        let exceeds = |count: u8| count != 0 && self.missed >= u32::from(count);
+
+        if exceeds(self.policy.lost_count.unwrap_or(heartbeat.count2)) {
+            Liveness::Lost { missed: self.missed }
+        } else if exceeds(self.policy.warning_count.unwrap_or(heartbeat.count1)) {
+            Liveness::Degraded { missed: self.missed }
+        } else {
+            Liveness::Alive
+        }
+    }
+
+    /// Counts a missed heartbeat if nothing was received from the server since the last tick, and
+    /// returns the state of the connection.
+    pub fn tick(&mut self) -> Liveness {
+        if self.heartbeat.is_none() {
+            return Liveness::Unmonitored;
+        }
+
+        if self.received {
+            self.received = false;
+            self.missed = 0;
+        } else {
+            self.missed = self.missed.saturating_add(1);
+        }
+
+        self.liveness()
+    }
+
+    pub(crate) fn heartbeat_received(&mut self, heartbeat: HeartbeatPdu) {
+        if self.heartbeat != Some(heartbeat) {
+            debug!(?heartbeat, "Heartbeat settings changed");
+        }
+
+        self.heartbeat = Some(heartbeat);
+        self.frame_received();
+    }
+
+    pub(crate) fn frame_received(&mut self) {
+        self.received = true;
+    }
+}
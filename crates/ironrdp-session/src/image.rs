@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use ironrdp_core::assert_impl;
 use ironrdp_graphics::color_conversion::rdp_16bit_to_rgb;
+use ironrdp_graphics::deep_color::{self, FramebufferFormat};
 use ironrdp_graphics::image_processing::{ImageRegion, ImageRegionMut, PixelFormat};
 use ironrdp_graphics::pointer::DecodedPointer;
 use ironrdp_graphics::rectangle_processing::Region;
@@ -39,6 +40,64 @@ pub struct DecodedImage {
 
 assert_impl!(DecodedImage: Send);
 
+/// A copy of the decoded framebuffer, e.g. for thumbnails, previews or automated visual checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FramebufferSnapshot {
+    pub pixel_format: PixelFormat,
+    pub width: u16,
+    pub height: u16,
+    /// Number of bytes between the starts of two consecutive rows.
+    pub stride: usize,
+    pub data: Vec<u8>,
+}
+
+impl FramebufferSnapshot {
+    /// Returns the pixels converted to the given format, the rows being tightly packed.
+    ///
+    /// This allows the presenters using a 10-bit surface to receive the frames of the 8-bit codecs, and the 8-bit
+    /// presenters to receive the frames of the codecs carrying more than 8 bits per channel.
+    pub fn to_format(&self, format: FramebufferFormat) -> std::io::Result<Vec<u8>> {
+        let width = usize::from(self.width);
+        let height = usize::from(self.height);
+        let dst_stride = width * usize::from(format.bytes_per_pixel());
+        let mut data = vec![0; dst_stride * height];
+
+        deep_color::convert(
+            &self.data,
+            self.pixel_format.into(),
+            self.stride,
+            &mut data,
+            format,
+            dst_stride,
+            width,
+            height,
+        )?;
+
+        Ok(data)
+    }
+
+    /// Converts the snapshot into an RGBA image.
+    #[cfg(feature = "image")]
+    pub fn into_rgba_image(self) -> image::RgbaImage {
+        let width = u32::from(self.width);
+        let height = u32::from(self.height);
+        let bytes_per_pixel = usize::from(self.pixel_format.bytes_per_pixel());
+
+        if self.pixel_format == PixelFormat::RgbA32 && self.stride == usize::from(self.width) * bytes_per_pixel {
+            return image::RgbaImage::from_raw(width, height, self.data).expect("buffer matches the dimensions");
+        }
+
+        image::RgbaImage::from_fn(width, height, |x, y| {
+            let offset = y as usize * self.stride + x as usize * bytes_per_pixel;
+            let color = self
+                .pixel_format
+                .read_color(&self.data[offset..])
+                .expect("buffer matches the dimensions");
+            image::Rgba([color.r, color.g, color.b, color.a])
+        })
+    }
+}
+
 impl core::fmt::Debug for DecodedImage {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("DecodedImage")
@@ -167,6 +226,13 @@ impl DecodedImage {
         self.pixel_format
     }
 
+    /// Returns the format of the framebuffer.
+    ///
+    /// The codecs currently decode 8 bits per channel, in the pixel format given on creation.
+    pub fn framebuffer_format(&self) -> FramebufferFormat {
+        FramebufferFormat::Standard(self.pixel_format)
+    }
+
     pub fn data(&self) -> &[u8] {
         &self.data
     }
@@ -194,6 +260,23 @@ impl DecodedImage {
         self.height
     }
 
+    /// Returns a copy of the framebuffer, including the pointer when it is rendered by software.
+    pub fn snapshot(&self) -> FramebufferSnapshot {
+        FramebufferSnapshot {
+            pixel_format: self.pixel_format,
+            width: self.width,
+            height: self.height,
+            stride: self.stride(),
+            data: self.data.clone(),
+        }
+    }
+
+    /// Returns a copy of the framebuffer as an RGBA image.
+    #[cfg(feature = "image")]
+    pub fn to_rgba_image(&self) -> image::RgbaImage {
+        self.snapshot().into_rgba_image()
+    }
+
     fn apply_pointer_layer(&mut self, layer: PointerLayer) -> SessionResult<Option<InclusiveRectangle>> {
         // Pointer is not hidden, but its texture is not visible on the screen, so we don't
         // need to render it
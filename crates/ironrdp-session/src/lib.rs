@@ -9,6 +9,7 @@ extern crate tracing;
 mod macros;
 
 pub mod fast_path;
+pub mod heartbeat;
 pub mod image;
 pub mod legacy;
 pub mod pointer;
@@ -17,10 +18,15 @@ pub mod utils;
 pub mod x224;
 
 mod active_stage;
+mod decode_pool;
 
 use core::fmt;
 
 pub use active_stage::{ActiveStage, ActiveStageOutput, GracefulDisconnectReason};
+pub use decode_pool::default_decode_threads;
+use ironrdp_error::Classify;
+pub use ironrdp_error::ErrorClass;
+pub use x224::SessionEvent;
 
 pub type SessionResult<T> = Result<T, SessionError>;
 
@@ -61,6 +67,16 @@ impl std::error::Error for SessionErrorKind {
     }
 }
 
+impl Classify for SessionErrorKind {
+    fn class(&self) -> ErrorClass {
+        match &self {
+            SessionErrorKind::Pdu(_) | SessionErrorKind::Encode(_) | SessionErrorKind::Decode(_) => ErrorClass::Codec,
+            SessionErrorKind::Reason(_) => ErrorClass::ProtocolViolation,
+            SessionErrorKind::General | SessionErrorKind::Custom => ErrorClass::Other,
+        }
+    }
+}
+
 pub type SessionError = ironrdp_error::Error<SessionErrorKind>;
 
 pub trait SessionErrorExt {
@@ -1,4 +1,5 @@
 use core::cmp::min;
+use core::num::NonZeroUsize;
 
 use ironrdp_graphics::color_conversion::{self, YCbCrBuffer};
 use ironrdp_graphics::image_processing::PixelFormat;
@@ -8,10 +9,12 @@ use ironrdp_pdu::codecs::rfx::{self, EntropyAlgorithm, Quant, RfxRectangle, Tile
 use ironrdp_pdu::geometry::{InclusiveRectangle, Rectangle};
 use ironrdp_pdu::{decode_cursor, Decode, ReadCursor};
 
+use crate::decode_pool::{default_decode_threads, DecodePool};
 use crate::image::DecodedImage;
 use crate::SessionResult;
 
 const TILE_SIZE: u16 = 64;
+const TILE_OUTPUT_SIZE: usize = TILE_SIZE as usize * TILE_SIZE as usize * 4;
 
 pub type FrameId = u32;
 
@@ -19,10 +22,24 @@ pub struct DecodingContext {
     context: rfx::ContextPdu,
     channels: rfx::ChannelsPdu,
     decoding_tiles: DecodingTileContext,
+    pool: DecodePool,
 }
 
 impl Default for DecodingContext {
     fn default() -> Self {
+        Self::with_decode_threads(default_decode_threads())
+    }
+}
+
+impl DecodingContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a context decoding the tiles of a frame with up to `threads` threads, the calling thread included.
+    ///
+    /// With a single thread, the tiles are decoded on the calling thread only.
+    pub fn with_decode_threads(threads: NonZeroUsize) -> Self {
         Self {
             context: rfx::ContextPdu {
                 flags: rfx::OperatingMode::empty(),
@@ -30,13 +47,15 @@ impl Default for DecodingContext {
             },
             channels: rfx::ChannelsPdu(Vec::new()),
             decoding_tiles: DecodingTileContext::new(),
+            pool: DecodePool::new(threads),
         }
     }
-}
 
-impl DecodingContext {
-    pub fn new() -> Self {
-        Self::default()
+    /// Sets the number of threads decoding the tiles of a frame, the calling thread included.
+    pub fn set_decode_threads(&mut self, threads: NonZeroUsize) {
+        if threads != self.pool.threads() {
+            self.pool = DecodePool::new(threads);
+        }
     }
 
     pub fn decode(
@@ -148,6 +167,37 @@ impl DecodingContext {
 
         let mut final_update_rectangle = clipping_rectangles.extents.clone();
 
+        let threads = self.pool.threads().get().min(tile_set.tiles.len());
+        if threads > 1 {
+            // The tiles are split between the threads, and applied to the image on the calling thread.
+            let tiles_per_thread = tile_set.tiles.len().div_ceil(threads);
+            let jobs: Vec<_> = map_tiles_data(tile_set.tiles.as_slice(), tile_set.quants.as_slice())
+                .chunks(tiles_per_thread)
+                .map(|tiles| {
+                    let tiles = tiles.iter().map(OwnedTileData::from).collect::<Vec<_>>();
+                    move || decode_tiles(&tiles, entropy_algorithm)
+                })
+                .collect();
+
+            let mut update_rectangles = tiles_to_rectangles(tile_set.tiles.as_slice(), destination);
+            for output in self.pool.run(jobs) {
+                for (tile_output, update_rectangle) in
+                    output?.chunks_exact(TILE_OUTPUT_SIZE).zip(&mut update_rectangles)
+                {
+                    let current_update_rectangle = image.apply_tile(
+                        tile_output,
+                        PixelFormat::RgbA32,
+                        &clipping_rectangles,
+                        &update_rectangle,
+                    )?;
+
+                    final_update_rectangle = final_update_rectangle.union(&current_update_rectangle);
+                }
+            }
+
+            return Ok((frame_begin.index, final_update_rectangle));
+        }
+
         for (update_rectangle, tile_data) in tiles_to_rectangles(tile_set.tiles.as_slice(), destination)
             .zip(map_tiles_data(tile_set.tiles.as_slice(), tile_set.quants.as_slice()))
         {
@@ -183,7 +233,7 @@ struct DecodingTileContext {
 impl DecodingTileContext {
     fn new() -> Self {
         Self {
-            tile_output: vec![0; TILE_SIZE as usize * TILE_SIZE as usize * 4],
+            tile_output: vec![0; TILE_OUTPUT_SIZE],
             ycbcr_buffer: vec![vec![0; TILE_SIZE as usize * TILE_SIZE as usize]; 3],
             ycbcr_temp_buffer: vec![0; TILE_SIZE as usize * TILE_SIZE as usize],
         }
@@ -212,6 +262,29 @@ fn decode_tile(
     Ok(())
 }
 
+/// Decodes the tiles one after the other, and returns their concatenated outputs.
+fn decode_tiles(tiles: &[OwnedTileData], entropy_algorithm: EntropyAlgorithm) -> SessionResult<Vec<u8>> {
+    let mut context = DecodingTileContext::new();
+    let mut output = Vec::with_capacity(tiles.len() * TILE_OUTPUT_SIZE);
+
+    for tile in tiles {
+        let tile = TileData {
+            quants: tile.quants.clone(),
+            data: tile.data.each_ref().map(Vec::as_slice),
+        };
+        decode_tile(
+            &tile,
+            entropy_algorithm,
+            context.tile_output.as_mut(),
+            context.ycbcr_buffer.as_mut(),
+            context.ycbcr_temp_buffer.as_mut(),
+        )?;
+        output.extend_from_slice(&context.tile_output);
+    }
+
+    Ok(output)
+}
+
 fn decode_component(
     quant: &Quant,
     entropy_algorithm: EntropyAlgorithm,
@@ -278,3 +351,18 @@ struct TileData<'a> {
     quants: [Quant; 3],
     data: [&'a [u8]; 3],
 }
+
+/// Copy of the data of a tile, to be decoded by another thread.
+struct OwnedTileData {
+    quants: [Quant; 3],
+    data: [Vec<u8>; 3],
+}
+
+impl From<&TileData<'_>> for OwnedTileData {
+    fn from(tile: &TileData<'_>) -> Self {
+        Self {
+            quants: tile.quants.clone(),
+            data: tile.data.map(<[u8]>::to_vec),
+        }
+    }
+}
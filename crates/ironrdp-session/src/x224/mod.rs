@@ -1,14 +1,17 @@
 use ironrdp_connector::connection_activation::ConnectionActivationSequence;
 use ironrdp_connector::legacy::SendDataIndicationCtx;
-use ironrdp_core::WriteBuf;
+use ironrdp_core::{ReadCursor, WriteBuf};
 use ironrdp_dvc::{DrdynvcClient, DvcProcessor, DynamicVirtualChannel};
 use ironrdp_pdu::mcs::{DisconnectProviderUltimatum, DisconnectReason, McsMessage};
 use ironrdp_pdu::rdp::headers::ShareDataPdu;
+use ironrdp_pdu::rdp::heartbeat::HeartbeatPdu;
 use ironrdp_pdu::rdp::server_error_info::{ErrorInfo, ProtocolIndependentCode, ServerSetErrorInfoPdu};
+use ironrdp_pdu::rdp::session_info::{InfoData, LogonErrorsInfo, LogonInfo, SaveSessionInfoPdu, ServerAutoReconnect};
 use ironrdp_pdu::x224::X224;
 use ironrdp_svc::{client_encode_svc_messages, StaticChannelSet, SvcMessage, SvcProcessor, SvcProcessorMessages};
 
-use crate::{SessionError, SessionErrorExt as _, SessionResult};
+use crate::heartbeat::{HeartbeatMonitor, HeartbeatPolicy};
+use crate::{ErrorClass, SessionError, SessionErrorExt as _, SessionResult};
 
 /// X224 Processor output
 #[derive(Debug, Clone)]
@@ -22,6 +25,8 @@ pub enum ProcessorOutput {
     ///
     /// [Deactivation-Reactivation Sequence]: https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-rdpbcgr/dfc234ce-481a-4674-9a5d-2a7bafb14432
     DeactivateAll(Box<ConnectionActivationSequence>),
+    /// A logon notification, from a [`SaveSessionInfoPdu`].
+    SessionEvent(SessionEvent),
 }
 
 #[derive(Debug, Clone)]
@@ -36,11 +41,47 @@ pub enum DisconnectDescription {
     ErrorInfo(ErrorInfo),
 }
 
+/// Logon notifications sent by the server in the Save Session Info PDUs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// The user is logged on.
+    ///
+    /// The user name and the domain are not known when the server only sent a plain notification.
+    Logon(Option<LogonInfo>),
+    /// The auto-reconnect cookie of the session, to present when reconnecting after a network failure.
+    ///
+    /// Each new cookie replaces the previous one.
+    AutoReconnectCookie(ServerAutoReconnect),
+    /// The logon failed, or the server requires the attention of the user (e.g. the session is in use).
+    LogonError(LogonErrorsInfo),
+}
+
+impl SessionEvent {
+    /// Extracts the events carried by a Save Session Info PDU.
+    pub fn from_save_session_info(pdu: SaveSessionInfoPdu) -> Vec<Self> {
+        match pdu.info_data {
+            InfoData::LogonInfoV1(info) => vec![Self::Logon(Some(info.logon_info))],
+            InfoData::LogonInfoV2(info) => vec![Self::Logon(Some(info.logon_info))],
+            InfoData::PlainNotify => vec![Self::Logon(None)],
+            InfoData::LogonExtended(extended) => extended
+                .auto_reconnect
+                .map(Self::AutoReconnectCookie)
+                .into_iter()
+                .chain(extended.errors_info.map(Self::LogonError))
+                .collect(),
+        }
+    }
+}
+
 pub struct Processor {
     static_channels: StaticChannelSet,
     user_channel_id: u16,
     io_channel_id: u16,
+    message_channel_id: Option<u16>,
+    /// The static channels joined without a processor, whose data is ignored.
+    named_channel_ids: Vec<u16>,
     connection_activation: ConnectionActivationSequence,
+    heartbeat: HeartbeatMonitor,
 }
 
 impl Processor {
@@ -48,16 +89,33 @@ impl Processor {
         static_channels: StaticChannelSet,
         user_channel_id: u16,
         io_channel_id: u16,
+        message_channel_id: Option<u16>,
         connection_activation: ConnectionActivationSequence,
     ) -> Self {
         Self {
             static_channels,
             user_channel_id,
             io_channel_id,
+            message_channel_id,
+            named_channel_ids: Vec::new(),
             connection_activation,
+            heartbeat: HeartbeatMonitor::new(HeartbeatPolicy::default()),
         }
     }
 
+    /// Ignores the data received on the static channels joined without a processor.
+    pub fn ignore_channels(&mut self, channel_ids: impl IntoIterator<Item = u16>) {
+        self.named_channel_ids.extend(channel_ids);
+    }
+
+    pub fn heartbeat(&self) -> &HeartbeatMonitor {
+        &self.heartbeat
+    }
+
+    pub fn heartbeat_mut(&mut self) -> &mut HeartbeatMonitor {
+        &mut self.heartbeat
+    }
+
     pub fn get_svc_processor<T: SvcProcessor + 'static>(&self) -> Option<&T> {
         self.static_channels
             .get_by_type::<T>()
@@ -79,7 +137,7 @@ impl Processor {
         let channel_id = self
             .static_channels
             .get_channel_id_by_type::<C>()
-            .ok_or_else(|| reason_err!("SVC", "channel not found"))?;
+            .ok_or_else(|| reason_err!("SVC", "channel not found").with_class(ErrorClass::Channel))?;
 
         process_svc_messages(messages.into(), channel_id, self.user_channel_id)
     }
@@ -95,14 +153,44 @@ impl Processor {
             ironrdp_connector::legacy::decode_send_data_indication(frame).map_err(crate::legacy::map_error)?;
         let channel_id = data_ctx.channel_id;
 
+        trace!(
+            pdu.channel_id = channel_id,
+            pdu.size = data_ctx.user_data.len(),
+            "Send Data Indication received"
+        );
+
+        self.heartbeat.frame_received();
+
+        // The heartbeats are sent on the message channel, or on the I/O channel by some servers.
+        if channel_id == self.io_channel_id || Some(channel_id) == self.message_channel_id {
+            if let Some(heartbeat) = decode_heartbeat(data_ctx.user_data) {
+                trace!(?heartbeat, "Received");
+                self.heartbeat.heartbeat_received(heartbeat);
+                return Ok(Vec::new());
+            }
+        }
+
         if channel_id == self.io_channel_id {
             self.process_io_channel(data_ctx)
+        } else if Some(channel_id) == self.message_channel_id {
+            debug!(channel_id, "Ignored message channel PDU");
+            Ok(Vec::new())
         } else if let Some(svc) = self.static_channels.get_by_channel_id_mut(channel_id) {
-            let response_pdus = svc.process(data_ctx.user_data).map_err(SessionError::pdu)?;
+            trace!(
+                pdu.channel_id = channel_id,
+                pdu.channel = svc.channel_name().as_str(),
+                "Static channel PDU received"
+            );
+            let response_pdus = svc
+                .process(data_ctx.user_data)
+                .map_err(|e| SessionError::pdu(e).with_class(ErrorClass::Channel))?;
             process_svc_messages(response_pdus, channel_id, data_ctx.initiator_id)
                 .map(|data| vec![ProcessorOutput::ResponseFrame(data)])
+        } else if self.named_channel_ids.contains(&channel_id) {
+            debug!(channel_id, "Ignored data of a channel without processor");
+            Ok(Vec::new())
         } else {
-            Err(reason_err!("X224", "unexpected channel received: ID {channel_id}"))
+            Err(reason_err!("X224", "unexpected channel received: ID {channel_id}").with_class(ErrorClass::Channel))
         }
     }
 
@@ -113,10 +201,15 @@ impl Processor {
 
         match io_channel {
             ironrdp_connector::legacy::IoChannelPdu::Data(ctx) => {
+                trace!(pdu.kind = ctx.pdu.as_short_name(), "Share Data PDU received");
+
                 match ctx.pdu {
                     ShareDataPdu::SaveSessionInfo(session_info) => {
                         debug!("Got Session Save Info PDU: {session_info:?}");
-                        Ok(Vec::new())
+                        Ok(SessionEvent::from_save_session_info(session_info)
+                            .into_iter()
+                            .map(ProcessorOutput::SessionEvent)
+                            .collect())
                     }
                     // FIXME: workaround fix to not terminate the session on "unhandled PDU: Set Keyboard Indicators PDU"
                     ShareDataPdu::SetKeyboardIndicators(data) => {
@@ -180,6 +273,16 @@ impl Processor {
     }
 }
 
+/// Decodes a Server Heartbeat PDU, if the data is one.
+///
+/// A Share Control Header, starting with its total length, can't be mistaken for the security header of a
+/// heartbeat, as such a length is at least 16 KiB.
+fn decode_heartbeat(user_data: &[u8]) -> Option<HeartbeatPdu> {
+    let mut cursor = ReadCursor::new(user_data);
+    let heartbeat = ironrdp_core::decode_cursor::<HeartbeatPdu>(&mut cursor).ok()?;
+    cursor.is_empty().then_some(heartbeat)
+}
+
 /// Processes a vector of [`SvcMessage`] in preparation for sending them to the server on the `channel_id` channel.
 ///
 /// This includes chunkifying the messages, adding MCS, x224, and tpkt headers, and encoding them into a buffer.
@@ -169,22 +169,60 @@ impl StaticVirtualChannel {
     }
 }
 
+/// Bulk compressor of the static virtual channel data, see [`server_encode_svc_messages_compressed_into`].
+pub trait SvcCompressor: Send {
+    /// Compresses the data of a chunk into `output`.
+    ///
+    /// Returns the compression flags of the chunk: [`ChannelFlags::COMPRESSED`], [`ChannelFlags::AT_FRONT`] and
+    /// [`ChannelFlags::FLUSHED`], along with the compression type in the `0x000F_0000` bits. When the flags do not
+    /// contain [`ChannelFlags::COMPRESSED`], the data is sent as is and `output` is ignored.
+    fn compress(&mut self, input: &[u8], output: &mut Vec<u8>) -> ChannelFlags;
+}
+
 fn encode_svc_messages(
     messages: Vec<SvcMessage>,
     channel_id: u16,
     initiator_id: u16,
     client: bool,
-) -> EncodeResult<Vec<u8>> {
-    let mut fully_encoded_responses = WriteBuf::new(); // TODO(perf): reuse this buffer using `clear` and `filled` as appropriate
+    compressor: Option<&mut dyn SvcCompressor>,
+    buf: &mut WriteBuf,
+) -> EncodeResult<usize> {
+    let start = buf.filled_len();
 
     // For each response PDU, chunkify it and add appropriate static channel headers.
-    let chunks = StaticVirtualChannel::chunkify(messages)?;
+    let mut chunks = StaticVirtualChannel::chunkify(messages)?;
+
+    // Each chunk is compressed separately, the length of the header remaining the uncompressed length of the PDU.
+    if let Some(compressor) = compressor {
+        let mut compressed = Vec::new();
+        for chunk in &mut chunks {
+            compressed.clear();
+            let (header, data) = chunk.filled().split_at(ChannelPduHeader::FIXED_PART_SIZE);
+            let flags = compressor.compress(data, &mut compressed);
+
+            let mut header = ReadCursor::new(header);
+            let header = ChannelPduHeader {
+                length: header.read_u32(),
+                flags: ChannelFlags::from_bits_retain(header.read_u32()) | flags,
+            };
+
+            let data = if flags.contains(ChannelFlags::COMPRESSED) {
+                compressed.as_slice()
+            } else {
+                data
+            };
+            let mut compressed_chunk = WriteBuf::new();
+            encode_buf(&header, &mut compressed_chunk)?;
+            compressed_chunk.write_slice(data);
+            *chunk = compressed_chunk;
+        }
+    }
 
     // SendData is [`McsPdu`], which is [`x224Pdu`], which is [`Encode`]. [`Encode`] for [`x224Pdu`]
     // also takes care of adding the Tpkt header, so therefore we can just call `encode_buf` on each of these and
     // we will create a buffer of fully encoded PDUs ready to send to the server.
     //
-    // For example, if we had 2 chunks, our fully_encoded_responses buffer would look like:
+    // For example, if we had 2 chunks, our buffer would look like:
     //
     // [ | tpkt | x224 | mcs::SendDataRequest | chunk 1 | tpkt | x224 | mcs::SendDataRequest | chunk 2 | ]
     //   |<------------------- PDU 1 ------------------>|<------------------- PDU 2 ------------------>|
@@ -195,7 +233,7 @@ fn encode_svc_messages(
                 channel_id,
                 user_data: Cow::Borrowed(chunk.filled()),
             };
-            encode_buf(&X224(pdu), &mut fully_encoded_responses)?;
+            encode_buf(&X224(pdu), buf)?;
         }
     } else {
         for chunk in chunks {
@@ -204,11 +242,11 @@ fn encode_svc_messages(
                 channel_id,
                 user_data: Cow::Borrowed(chunk.filled()),
             };
-            encode_buf(&X224(pdu), &mut fully_encoded_responses)?;
+            encode_buf(&X224(pdu), buf)?;
         }
     }
 
-    Ok(fully_encoded_responses.into_inner())
+    Ok(buf.filled_len() - start)
 }
 
 /// Encode a vector of [`SvcMessage`] in preparation for sending them on the `channel_id` channel.
@@ -222,7 +260,21 @@ pub fn client_encode_svc_messages(
     channel_id: u16,
     initiator_id: u16,
 ) -> EncodeResult<Vec<u8>> {
-    encode_svc_messages(messages, channel_id, initiator_id, true)
+    let mut buf = WriteBuf::new();
+    client_encode_svc_messages_into(messages, channel_id, initiator_id, &mut buf)?;
+    Ok(buf.into_inner())
+}
+
+/// Same as [`client_encode_svc_messages`], but appends the encoded messages to a reusable buffer.
+///
+/// Returns the number of bytes written.
+pub fn client_encode_svc_messages_into(
+    messages: Vec<SvcMessage>,
+    channel_id: u16,
+    initiator_id: u16,
+    buf: &mut WriteBuf,
+) -> EncodeResult<usize> {
+    encode_svc_messages(messages, channel_id, initiator_id, true, None, buf)
 }
 
 /// Encode a vector of [`SvcMessage`] in preparation for sending them on the `channel_id` channel.
@@ -236,7 +288,35 @@ pub fn server_encode_svc_messages(
     channel_id: u16,
     initiator_id: u16,
 ) -> EncodeResult<Vec<u8>> {
-    encode_svc_messages(messages, channel_id, initiator_id, false)
+    let mut buf = WriteBuf::new();
+    server_encode_svc_messages_into(messages, channel_id, initiator_id, &mut buf)?;
+    Ok(buf.into_inner())
+}
+
+/// Same as [`server_encode_svc_messages`], but appends the encoded messages to a reusable buffer.
+///
+/// Returns the number of bytes written.
+pub fn server_encode_svc_messages_into(
+    messages: Vec<SvcMessage>,
+    channel_id: u16,
+    initiator_id: u16,
+    buf: &mut WriteBuf,
+) -> EncodeResult<usize> {
+    encode_svc_messages(messages, channel_id, initiator_id, false, None, buf)
+}
+
+/// Same as [`server_encode_svc_messages_into`], but compresses the data of the chunks with `compressor`.
+///
+/// The client must have advertised the support of the compression of the virtual channel data, and the
+/// compressor must not be shared with the other PDUs.
+pub fn server_encode_svc_messages_compressed_into(
+    messages: Vec<SvcMessage>,
+    channel_id: u16,
+    initiator_id: u16,
+    compressor: &mut dyn SvcCompressor,
+    buf: &mut WriteBuf,
+) -> EncodeResult<usize> {
+    encode_svc_messages(messages, channel_id, initiator_id, false, Some(compressor), buf)
 }
 
 /// A type that is a Static Virtual Channel
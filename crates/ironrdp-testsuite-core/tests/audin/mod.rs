@@ -0,0 +1,210 @@
+use std::sync::{Arc, Mutex};
+
+use ironrdp_audin::pdu;
+use ironrdp_audin::server::{AudioInputServer, AudioInputServerHandler};
+use ironrdp_core::encode_vec;
+use ironrdp_dvc::DvcProcessor as _;
+use ironrdp_rdpsnd::pdu::{AudioFormat, WaveFormat};
+use ironrdp_testsuite_core::encode_decode_test;
+
+fn pcm_format(n_samples_per_sec: u32) -> AudioFormat {
+    AudioFormat {
+        format: WaveFormat::PCM,
+        n_channels: 1,
+        n_samples_per_sec,
+        n_avg_bytes_per_sec: n_samples_per_sec * 2,
+        n_block_align: 2,
+        bits_per_sample: 16,
+        data: None,
+    }
+}
+
+encode_decode_test! {
+    version: pdu::AudioInputPdu::Version(pdu::VersionPdu { version: pdu::Version::V2 }),
+    [
+        0x01,
+        0x02, 0x00, 0x00, 0x00,
+    ];
+
+    formats: pdu::AudioInputPdu::Formats(pdu::FormatsPdu { formats: vec![pcm_format(16000)] }),
+    [
+        0x02,
+        // NumFormats
+        0x01, 0x00, 0x00, 0x00,
+        // cbSizeFormatsPacket
+        0x1B, 0x00, 0x00, 0x00,
+        // Format
+        0x01, 0x00, 0x01, 0x00, 0x80, 0x3E, 0x00, 0x00, 0x00, 0x7D, 0x00, 0x00, 0x02, 0x00, 0x10, 0x00, 0x00, 0x00,
+    ];
+
+    open: pdu::AudioInputPdu::Open(pdu::OpenPdu {
+        frames_per_packet: 320,
+        initial_format: 0,
+        format: pcm_format(16000),
+    }),
+    [
+        0x03,
+        // FramesPerPacket
+        0x40, 0x01, 0x00, 0x00,
+        // initialFormat
+        0x00, 0x00, 0x00, 0x00,
+        // Format
+        0x01, 0x00, 0x01, 0x00, 0x80, 0x3E, 0x00, 0x00, 0x00, 0x7D, 0x00, 0x00, 0x02, 0x00, 0x10, 0x00, 0x00, 0x00,
+    ];
+
+    open_reply: pdu::AudioInputPdu::OpenReply(pdu::OpenReplyPdu { result: 0 }),
+    [
+        0x04,
+        0x00, 0x00, 0x00, 0x00,
+    ];
+
+    incoming_data: pdu::AudioInputPdu::IncomingData,
+    [
+        0x05,
+    ];
+
+    data: pdu::AudioInputPdu::Data(pdu::DataPdu { data: vec![0x01, 0x02, 0x03, 0x04] }),
+    [
+        0x06,
+        0x01, 0x02, 0x03, 0x04,
+    ];
+
+    format_change: pdu::AudioInputPdu::FormatChange(pdu::FormatChangePdu { new_format: 1 }),
+    [
+        0x07,
+        0x01, 0x00, 0x00, 0x00,
+    ];
+}
+
+#[derive(Debug, Default)]
+struct Recorded {
+    formats: Vec<AudioFormat>,
+    data: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct TestHandler {
+    formats: Vec<AudioFormat>,
+    recorded: Arc<Mutex<Recorded>>,
+}
+
+impl AudioInputServerHandler for TestHandler {
+    fn get_formats(&self) -> &[AudioFormat] {
+        &self.formats
+    }
+
+    fn start(&mut self, client_formats: &[AudioFormat]) -> Option<u32> {
+        client_formats
+            .iter()
+            .position(|format| format.n_samples_per_sec == 16000)
+            .and_then(|index| u32::try_from(index).ok())
+    }
+
+    fn format_changed(&mut self, format: &AudioFormat) {
+        self.recorded.lock().unwrap().formats.push(format.clone());
+    }
+
+    fn data(&mut self, data: &[u8]) {
+        self.recorded.lock().unwrap().data.extend_from_slice(data);
+    }
+
+    fn stop(&mut self) {}
+}
+
+fn encode(pdu: impl Into<pdu::AudioInputPdu>) -> Vec<u8> {
+    encode_vec(&pdu.into()).unwrap()
+}
+
+#[test]
+fn server_records() {
+    let recorded = Arc::new(Mutex::new(Recorded::default()));
+    let mut server = AudioInputServer::new(Box::new(TestHandler {
+        formats: vec![pcm_format(44100), pcm_format(16000)],
+        recorded: Arc::clone(&recorded),
+    }));
+
+    let messages = server.start(0).unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].name(), "SNDIN_PDU");
+
+    let version = encode(pdu::VersionPdu {
+        version: pdu::Version::V1,
+    });
+    // Server formats.
+    assert_eq!(server.process(0, &version).unwrap().len(), 1);
+
+    let client_formats = encode(pdu::FormatsPdu {
+        formats: vec![pcm_format(44100), pcm_format(16000)],
+    });
+    let messages = server.process(0, &client_formats).unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(
+        encode_vec(messages[0].as_ref()).unwrap(),
+        encode(pdu::OpenPdu {
+            frames_per_packet: 320,
+            initial_format: 1,
+            format: pcm_format(16000),
+        })
+    );
+
+    // No format change before the reply: the initial format is used.
+    assert!(server
+        .process(0, &encode(pdu::OpenReplyPdu { result: 0 }))
+        .unwrap()
+        .is_empty());
+    assert_eq!(server.format(), Some(&pcm_format(16000)));
+
+    server
+        .process(0, &encode_vec(&pdu::AudioInputPdu::IncomingData).unwrap())
+        .unwrap();
+    server
+        .process(0, &encode(pdu::DataPdu { data: vec![1, 2, 3, 4] }))
+        .unwrap();
+
+    server
+        .process(0, &encode(pdu::FormatChangePdu { new_format: 0 }))
+        .unwrap();
+    assert_eq!(server.format(), Some(&pcm_format(44100)));
+
+    let recorded = recorded.lock().unwrap();
+    assert_eq!(recorded.formats, vec![pcm_format(16000), pcm_format(44100)]);
+    assert_eq!(recorded.data, vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn server_open_failure() {
+    let recorded = Arc::new(Mutex::new(Recorded::default()));
+    let mut server = AudioInputServer::new(Box::new(TestHandler {
+        formats: vec![pcm_format(16000)],
+        recorded: Arc::clone(&recorded),
+    }));
+
+    server.start(0).unwrap();
+    server
+        .process(
+            0,
+            &encode(pdu::VersionPdu {
+                version: pdu::Version::V2,
+            }),
+        )
+        .unwrap();
+    server
+        .process(
+            0,
+            &encode(pdu::FormatsPdu {
+                formats: vec![pcm_format(16000)],
+            }),
+        )
+        .unwrap();
+
+    // E_FAIL
+    server
+        .process(0, &encode(pdu::OpenReplyPdu { result: 0x8000_4005 }))
+        .unwrap();
+    server
+        .process(0, &encode(pdu::DataPdu { data: vec![1, 2, 3, 4] }))
+        .unwrap();
+
+    assert_eq!(server.format(), None);
+    assert!(recorded.lock().unwrap().data.is_empty());
+}
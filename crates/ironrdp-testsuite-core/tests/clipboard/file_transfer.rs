@@ -0,0 +1,126 @@
+use ironrdp_cliprdr::backend::CliprdrBackend;
+use ironrdp_cliprdr::pdu::{
+    Capabilities, ClipboardFormat, ClipboardGeneralCapabilityFlags, ClipboardPdu, ClipboardProtocolVersion,
+    FileContentsFlags, FileContentsRequest, FileContentsResponse, FormatDataRequest, FormatDataResponse, FormatList,
+    LockDataId,
+};
+use ironrdp_cliprdr::CliprdrServer;
+use ironrdp_core::impl_as_any;
+use ironrdp_svc::{SvcMessage, SvcProcessor as _};
+
+#[derive(Debug)]
+struct TestBackend;
+
+impl_as_any!(TestBackend);
+
+impl CliprdrBackend for TestBackend {
+    fn temporary_directory(&self) -> &str {
+        ".cliprdr"
+    }
+
+    fn client_capabilities(&self) -> ClipboardGeneralCapabilityFlags {
+        ClipboardGeneralCapabilityFlags::STREAM_FILECLIP_ENABLED
+            | ClipboardGeneralCapabilityFlags::FILECLIP_NO_FILE_PATHS
+            | ClipboardGeneralCapabilityFlags::CAN_LOCK_CLIPDATA
+    }
+
+    fn on_ready(&mut self) {}
+
+    fn on_request_format_list(&mut self) {}
+
+    fn on_process_negotiated_capabilities(&mut self, _: ClipboardGeneralCapabilityFlags) {}
+
+    fn on_remote_copy(&mut self, _: &[ClipboardFormat]) {}
+
+    fn on_format_data_request(&mut self, _: FormatDataRequest) {}
+
+    fn on_format_data_response(&mut self, _: FormatDataResponse<'_>) {}
+
+    fn on_file_contents_request(&mut self, _: FileContentsRequest) {}
+
+    fn on_file_contents_response(&mut self, _: FileContentsResponse<'_>) {}
+
+    fn on_lock(&mut self, _: LockDataId) {}
+
+    fn on_unlock(&mut self, _: LockDataId) {}
+}
+
+/// Returns a server whose client sent its capabilities and initial format list.
+fn ready_server(client_flags: ClipboardGeneralCapabilityFlags) -> CliprdrServer {
+    let mut cliprdr = CliprdrServer::new(Box::new(TestBackend));
+    cliprdr.start().unwrap();
+
+    let capabilities = Capabilities::new(ClipboardProtocolVersion::V2, client_flags);
+    process(&mut cliprdr, ClipboardPdu::Capabilities(capabilities));
+    let format_list = FormatList::new_unicode(&[], true).unwrap();
+    process(&mut cliprdr, ClipboardPdu::FormatList(format_list));
+
+    cliprdr
+}
+
+fn process(cliprdr: &mut CliprdrServer, pdu: ClipboardPdu<'_>) -> Vec<SvcMessage> {
+    let payload = ironrdp_core::encode_vec(&pdu).unwrap();
+    cliprdr.process(&payload).unwrap()
+}
+
+fn size_request() -> FileContentsRequest {
+    FileContentsRequest {
+        stream_id: 1,
+        index: 0,
+        flags: FileContentsFlags::SIZE,
+        position: 0,
+        requested_size: 8,
+        data_id: Some(3),
+    }
+}
+
+#[test]
+fn server_requests_file_contents() {
+    let cliprdr = ready_server(
+        ClipboardGeneralCapabilityFlags::USE_LONG_FORMAT_NAMES
+            | ClipboardGeneralCapabilityFlags::STREAM_FILECLIP_ENABLED,
+    );
+
+    let messages = Vec::<SvcMessage>::from(cliprdr.request_file_contents(size_request()).unwrap());
+
+    assert_eq!(messages.len(), 1);
+}
+
+#[test]
+fn file_contents_are_not_requested_without_client_support() {
+    let cliprdr = ready_server(ClipboardGeneralCapabilityFlags::USE_LONG_FORMAT_NAMES);
+
+    let messages = Vec::<SvcMessage>::from(cliprdr.request_file_contents(size_request()).unwrap());
+
+    assert!(messages.is_empty());
+}
+
+#[test]
+fn file_contents_are_not_requested_before_ready() {
+    let cliprdr = CliprdrServer::new(Box::new(TestBackend));
+
+    let messages = Vec::<SvcMessage>::from(cliprdr.request_file_contents(size_request()).unwrap());
+
+    assert!(messages.is_empty());
+}
+
+#[test]
+fn server_locks_and_unlocks_client_clipboard() {
+    let cliprdr = ready_server(ClipboardGeneralCapabilityFlags::CAN_LOCK_CLIPDATA);
+
+    assert_eq!(
+        Vec::<SvcMessage>::from(cliprdr.lock_clipboard(LockDataId(3)).unwrap()).len(),
+        1
+    );
+    assert_eq!(
+        Vec::<SvcMessage>::from(cliprdr.unlock_clipboard(LockDataId(3)).unwrap()).len(),
+        1
+    );
+}
+
+#[test]
+fn client_clipboard_is_not_locked_without_client_support() {
+    let cliprdr = ready_server(ClipboardGeneralCapabilityFlags::STREAM_FILECLIP_ENABLED);
+
+    assert!(Vec::<SvcMessage>::from(cliprdr.lock_clipboard(LockDataId(3)).unwrap()).is_empty());
+}
@@ -1,5 +1,9 @@
 use ironrdp_cliprdr_format::bitmap::{dib_to_png, dibv5_to_png, png_to_cf_dib, png_to_cf_dibv5};
 use ironrdp_cliprdr_format::html::{cf_html_to_plain_html, plain_html_to_cf_html};
+use ironrdp_cliprdr_format::text::{
+    cf_unicodetext_to_string, string_to_cf_unicodetext, to_crlf_line_endings, to_lf_line_endings,
+};
+use rstest::rstest;
 
 #[test]
 fn dib_to_png_conversion_1() {
@@ -52,3 +56,51 @@ fn test_cf_html_to_text() {
     let roundtrip_html_text = cf_html_to_plain_html(&cf_html).unwrap();
     assert_eq!(actual, roundtrip_html_text);
 }
+
+#[rstest]
+#[case("", "")]
+#[case("hello", "hello")]
+#[case("a\r\nb", "a\r\nb")]
+#[case("a\nb\n", "a\r\nb\r\n")]
+#[case("a\rb", "a\r\nb")]
+#[case("\n\r\n\r", "\r\n\r\n\r\n")]
+fn text_to_crlf_line_endings(#[case] input: &str, #[case] expected: &str) {
+    assert_eq!(to_crlf_line_endings(input), expected);
+}
+
+#[rstest]
+#[case("", "")]
+#[case("hello", "hello")]
+#[case("a\nb", "a\nb")]
+#[case("a\r\nb\r\n", "a\nb\n")]
+#[case("a\rb", "a\nb")]
+fn text_to_lf_line_endings(#[case] input: &str, #[case] expected: &str) {
+    assert_eq!(to_lf_line_endings(input), expected);
+}
+
+#[test]
+fn cf_unicodetext_roundtrip() {
+    let encoded = string_to_cf_unicodetext("h\u{e9}llo\nw\u{f6}rld \u{1F600}");
+    assert_eq!(&encoded[..4], b"h\0\xe9\0");
+    assert_eq!(&encoded[encoded.len() - 2..], b"\0\0");
+
+    let decoded = cf_unicodetext_to_string(&encoded).unwrap();
+    assert_eq!(decoded, "h\u{e9}llo\r\nw\u{f6}rld \u{1F600}");
+}
+
+#[test]
+fn cf_unicodetext_padding() {
+    // Data following the null terminator is ignored.
+    assert_eq!(cf_unicodetext_to_string(b"h\0i\0\0\0\xff\xff").unwrap(), "hi");
+    // The null terminator is optional.
+    assert_eq!(cf_unicodetext_to_string(b"h\0i\0").unwrap(), "hi");
+    // A trailing odd byte is ignored.
+    assert_eq!(cf_unicodetext_to_string(b"h\0i\0\0\0\0").unwrap(), "hi");
+    assert_eq!(cf_unicodetext_to_string(b"h\0i").unwrap(), "h");
+}
+
+#[test]
+fn cf_unicodetext_failure() {
+    // Unpaired surrogate
+    assert!(cf_unicodetext_to_string(&[0x00, 0xD8, 0x41, 0x00]).is_err());
+}
@@ -0,0 +1,135 @@
+use ironrdp_cliprdr::backend::CliprdrBackend;
+use ironrdp_cliprdr::pdu::{
+    ClipboardFormat, ClipboardFormatId, ClipboardFormatName, ClipboardGeneralCapabilityFlags, ClipboardPdu,
+    FileContentsRequest, FileContentsResponse, FormatDataRequest, FormatDataResponse, FormatList, FormatListResponse,
+    LockDataId,
+};
+use ironrdp_cliprdr::{ClipboardOwner, CliprdrClient};
+use ironrdp_core::impl_as_any;
+use ironrdp_svc::{SvcMessage, SvcProcessor as _};
+
+#[derive(Debug)]
+struct TestBackend {
+    suppress_loopback: bool,
+}
+
+impl_as_any!(TestBackend);
+
+impl CliprdrBackend for TestBackend {
+    fn temporary_directory(&self) -> &str {
+        ".cliprdr"
+    }
+
+    fn client_capabilities(&self) -> ClipboardGeneralCapabilityFlags {
+        ClipboardGeneralCapabilityFlags::empty()
+    }
+
+    fn on_ready(&mut self) {}
+
+    fn on_request_format_list(&mut self) {}
+
+    fn on_process_negotiated_capabilities(&mut self, _: ClipboardGeneralCapabilityFlags) {}
+
+    fn on_remote_copy(&mut self, _: &[ClipboardFormat]) {}
+
+    fn on_format_data_request(&mut self, _: FormatDataRequest) {}
+
+    fn on_format_data_response(&mut self, _: FormatDataResponse<'_>) {}
+
+    fn on_file_contents_request(&mut self, _: FileContentsRequest) {}
+
+    fn on_file_contents_response(&mut self, _: FileContentsResponse<'_>) {}
+
+    fn on_lock(&mut self, _: LockDataId) {}
+
+    fn on_unlock(&mut self, _: LockDataId) {}
+
+    fn suppress_loopback(&self) -> bool {
+        self.suppress_loopback
+    }
+}
+
+fn ready_client(suppress_loopback: bool) -> CliprdrClient {
+    let mut cliprdr = CliprdrClient::new(Box::new(TestBackend { suppress_loopback }));
+
+    // Initial synthetic copy, followed by the server acknowledgement.
+    cliprdr.initiate_copy(&[]).unwrap();
+    process(&mut cliprdr, ClipboardPdu::FormatListResponse(FormatListResponse::Ok));
+
+    cliprdr
+}
+
+fn process(cliprdr: &mut CliprdrClient, pdu: ClipboardPdu<'_>) -> Vec<SvcMessage> {
+    let payload = ironrdp_core::encode_vec(&pdu).unwrap();
+    cliprdr.process(&payload).unwrap()
+}
+
+fn remote_copy(cliprdr: &mut CliprdrClient, formats: &[ClipboardFormat]) {
+    let format_list = FormatList::new_unicode(formats, true).unwrap();
+    process(cliprdr, ClipboardPdu::FormatList(format_list));
+}
+
+fn initiate_copy(cliprdr: &CliprdrClient, formats: &[ClipboardFormat]) -> usize {
+    Vec::<SvcMessage>::from(cliprdr.initiate_copy(formats).unwrap()).len()
+}
+
+fn remote_text_formats() -> Vec<ClipboardFormat> {
+    vec![
+        ClipboardFormat::new(ClipboardFormatId::CF_UNICODETEXT),
+        ClipboardFormat::new(ClipboardFormatId::new(0xC00A)).with_name(ClipboardFormatName::new("HTML Format")),
+    ]
+}
+
+#[test]
+fn ownership_is_tracked() {
+    let mut cliprdr = ready_client(false);
+    assert_eq!(cliprdr.owner(), ClipboardOwner::Local);
+    let sequence = cliprdr.sequence();
+
+    remote_copy(&mut cliprdr, &remote_text_formats());
+    assert_eq!(cliprdr.owner(), ClipboardOwner::Remote);
+    assert_eq!(cliprdr.sequence(), sequence.wrapping_add(1));
+
+    assert_eq!(initiate_copy(&cliprdr, &remote_text_formats()), 1);
+    assert_eq!(cliprdr.owner(), ClipboardOwner::Local);
+    assert_eq!(cliprdr.sequence(), sequence.wrapping_add(2));
+}
+
+#[test]
+fn loopback_is_not_suppressed_by_default() {
+    let mut cliprdr = ready_client(false);
+
+    remote_copy(&mut cliprdr, &remote_text_formats());
+
+    assert_eq!(initiate_copy(&cliprdr, &remote_text_formats()), 1);
+}
+
+#[test]
+fn loopback_is_suppressed_once() {
+    let mut cliprdr = ready_client(true);
+
+    remote_copy(&mut cliprdr, &remote_text_formats());
+
+    // Echo with a synthesized format and a different id for the registered format.
+    let echo = [
+        ClipboardFormat::new(ClipboardFormatId::CF_TEXT),
+        ClipboardFormat::new(ClipboardFormatId::CF_UNICODETEXT),
+        ClipboardFormat::new(ClipboardFormatId::new(0xC123)).with_name(ClipboardFormatName::new("HTML Format")),
+    ];
+    assert_eq!(initiate_copy(&cliprdr, &echo), 0);
+    assert_eq!(cliprdr.owner(), ClipboardOwner::Remote);
+
+    // A subsequent copy is an actual user copy.
+    assert_eq!(initiate_copy(&cliprdr, &echo), 1);
+    assert_eq!(cliprdr.owner(), ClipboardOwner::Local);
+}
+
+#[test]
+fn copy_with_new_formats_is_not_suppressed() {
+    let mut cliprdr = ready_client(true);
+
+    remote_copy(&mut cliprdr, &remote_text_formats());
+
+    let formats = [ClipboardFormat::new(ClipboardFormatId::CF_DIB)];
+    assert_eq!(initiate_copy(&cliprdr, &formats), 1);
+}
@@ -1,4 +1,7 @@
+mod file_transfer;
 mod format;
+mod loopback;
+mod policy;
 
 use expect_test::expect;
 use ironrdp_cliprdr::pdu::{
@@ -0,0 +1,199 @@
+use ironrdp_cliprdr::backend::CliprdrBackend;
+use ironrdp_cliprdr::pdu::{
+    ClipboardFormat, ClipboardFormatId, ClipboardFormatName, ClipboardGeneralCapabilityFlags, ClipboardPdu,
+    FileContentsRequest, FileContentsResponse, FormatDataRequest, FormatDataResponse, FormatList, FormatListResponse,
+    LockDataId, OwnedFileContentsResponse, OwnedFormatDataResponse,
+};
+use ironrdp_cliprdr::policy::{ClipboardDirection, ClipboardFilter};
+use ironrdp_cliprdr::{Client, CliprdrClient, CliprdrSvcMessages};
+use ironrdp_core::{impl_as_any, IntoOwned as _};
+use ironrdp_svc::{SvcMessage, SvcProcessor as _};
+
+#[derive(Debug, Default)]
+struct TestBackend {
+    remote_formats: Vec<ClipboardFormat>,
+    format_data: Option<OwnedFormatDataResponse>,
+    file_contents: Option<OwnedFileContentsResponse>,
+}
+
+impl_as_any!(TestBackend);
+
+impl CliprdrBackend for TestBackend {
+    fn temporary_directory(&self) -> &str {
+        ".cliprdr"
+    }
+
+    fn client_capabilities(&self) -> ClipboardGeneralCapabilityFlags {
+        ClipboardGeneralCapabilityFlags::STREAM_FILECLIP_ENABLED
+    }
+
+    fn on_ready(&mut self) {}
+
+    fn on_request_format_list(&mut self) {}
+
+    fn on_process_negotiated_capabilities(&mut self, _: ClipboardGeneralCapabilityFlags) {}
+
+    fn on_remote_copy(&mut self, available_formats: &[ClipboardFormat]) {
+        self.remote_formats = available_formats.to_vec();
+    }
+
+    fn on_format_data_request(&mut self, _: FormatDataRequest) {}
+
+    fn on_format_data_response(&mut self, response: FormatDataResponse<'_>) {
+        self.format_data = Some(response.into_owned());
+    }
+
+    fn on_file_contents_request(&mut self, _: FileContentsRequest) {}
+
+    fn on_file_contents_response(&mut self, response: FileContentsResponse<'_>) {
+        self.file_contents = Some(response.into_owned());
+    }
+
+    fn on_lock(&mut self, _: LockDataId) {}
+
+    fn on_unlock(&mut self, _: LockDataId) {}
+}
+
+fn ready_client(filter: ClipboardFilter) -> CliprdrClient {
+    let mut cliprdr = CliprdrClient::new(Box::<TestBackend>::default()).with_policy(Box::new(filter));
+
+    // Initial synthetic copy, followed by the server acknowledgement.
+    cliprdr.initiate_copy(&[]).unwrap();
+    process(&mut cliprdr, ClipboardPdu::FormatListResponse(FormatListResponse::Ok));
+
+    cliprdr
+}
+
+fn process(cliprdr: &mut CliprdrClient, pdu: ClipboardPdu<'_>) -> Vec<SvcMessage> {
+    let payload = ironrdp_core::encode_vec(&pdu).unwrap();
+    cliprdr.process(&payload).unwrap()
+}
+
+/// Encodes the single PDU of `messages`, and calls `f` with the decoded PDU.
+fn with_sent_pdu<T>(messages: CliprdrSvcMessages<Client>, f: impl FnOnce(ClipboardPdu<'_>) -> T) -> T {
+    let messages = Vec::<SvcMessage>::from(messages);
+    assert_eq!(messages.len(), 1);
+
+    let chunks = ironrdp_svc::StaticVirtualChannel::chunkify(messages).unwrap();
+
+    // Skip the channel PDU header.
+    f(ironrdp_core::decode(&chunks[0].filled()[8..]).unwrap())
+}
+
+fn backend(cliprdr: &CliprdrClient) -> &TestBackend {
+    cliprdr.downcast_backend::<TestBackend>().unwrap()
+}
+
+fn html_format() -> ClipboardFormat {
+    ClipboardFormat::new(ClipboardFormatId::new(0xC00A)).with_name(ClipboardFormatName::new("HTML Format"))
+}
+
+fn file_list_format() -> ClipboardFormat {
+    ClipboardFormat::new(ClipboardFormatId::new(0xC0BC)).with_name(ClipboardFormatName::new("FileGroupDescriptorW"))
+}
+
+#[test]
+fn blocked_formats_are_not_advertised() {
+    let cliprdr = ready_client(
+        ClipboardFilter::new()
+            .block_format(ClipboardFormatId::CF_DIB)
+            .block_format_name("HTML Format"),
+    );
+
+    let formats = [
+        ClipboardFormat::new(ClipboardFormatId::CF_UNICODETEXT),
+        ClipboardFormat::new(ClipboardFormatId::CF_DIB),
+        html_format(),
+    ];
+    let sent = with_sent_pdu(cliprdr.initiate_copy(&formats).unwrap(), |pdu| match pdu {
+        ClipboardPdu::FormatList(format_list) => format_list.get_formats(true).unwrap(),
+        pdu => panic!("unexpected PDU: {pdu:?}"),
+    });
+
+    assert_eq!(sent, [ClipboardFormat::new(ClipboardFormatId::CF_UNICODETEXT)]);
+}
+
+#[test]
+fn blocked_formats_are_not_given_to_the_backend() {
+    let mut cliprdr = ready_client(ClipboardFilter::new().block_files());
+
+    let formats = [
+        ClipboardFormat::new(ClipboardFormatId::CF_UNICODETEXT),
+        file_list_format(),
+    ];
+    let format_list = FormatList::new_unicode(&formats, true).unwrap();
+    process(&mut cliprdr, ClipboardPdu::FormatList(format_list));
+
+    assert_eq!(
+        backend(&cliprdr).remote_formats,
+        [ClipboardFormat::new(ClipboardFormatId::CF_UNICODETEXT)]
+    );
+}
+
+#[test]
+fn oversized_format_data_is_rejected() {
+    let mut cliprdr = ready_client(ClipboardFilter::new().with_max_size(16));
+
+    process(
+        &mut cliprdr,
+        ClipboardPdu::FormatDataRequest(FormatDataRequest {
+            format: ClipboardFormatId::CF_UNICODETEXT,
+        }),
+    );
+
+    let response = FormatDataResponse::new_unicode_string("more than sixteen bytes");
+    with_sent_pdu(cliprdr.submit_format_data(response).unwrap(), |pdu| match pdu {
+        ClipboardPdu::FormatDataResponse(response) => assert!(response.is_error()),
+        pdu => panic!("unexpected PDU: {pdu:?}"),
+    });
+
+    let response = FormatDataResponse::new_unicode_string("short");
+    with_sent_pdu(cliprdr.submit_format_data(response).unwrap(), |pdu| match pdu {
+        ClipboardPdu::FormatDataResponse(response) => assert_eq!(response.to_unicode_string().unwrap(), "short"),
+        pdu => panic!("unexpected PDU: {pdu:?}"),
+    });
+}
+
+#[test]
+fn incoming_text_is_redacted() {
+    let pattern = regex::Regex::new(r"\d{4}-\d{4}").unwrap();
+    let mut cliprdr = ready_client(ClipboardFilter::new().redact(pattern, "[redacted]"));
+
+    cliprdr.initiate_paste(ClipboardFormatId::CF_UNICODETEXT).unwrap();
+    let response = FormatDataResponse::new_unicode_string("card 1234-5678, pin 42");
+    process(&mut cliprdr, ClipboardPdu::FormatDataResponse(response));
+
+    let received = backend(&cliprdr).format_data.as_ref().unwrap();
+    assert_eq!(received.to_unicode_string().unwrap(), "card [redacted], pin 42");
+}
+
+#[test]
+fn filter_applies_to_its_direction_only() {
+    let mut cliprdr = ready_client(
+        ClipboardFilter::new()
+            .only(ClipboardDirection::Outgoing)
+            .with_max_size(4)
+            .block_files(),
+    );
+
+    cliprdr.initiate_paste(ClipboardFormatId::CF_UNICODETEXT).unwrap();
+    let response = FormatDataResponse::new_unicode_string("incoming");
+    process(&mut cliprdr, ClipboardPdu::FormatDataResponse(response));
+    process(
+        &mut cliprdr,
+        ClipboardPdu::FileContentsResponse(FileContentsResponse::new_data_response(1, b"file".as_slice())),
+    );
+
+    let backend = backend(&cliprdr);
+    assert_eq!(
+        backend.format_data.as_ref().unwrap().to_unicode_string().unwrap(),
+        "incoming"
+    );
+    assert_eq!(backend.file_contents.as_ref().unwrap().data(), b"file");
+
+    let response = FileContentsResponse::new_data_response(1, b"file".to_vec());
+    with_sent_pdu(cliprdr.submit_file_contents(response).unwrap(), |pdu| match pdu {
+        ClipboardPdu::FileContentsResponse(response) => assert_eq!(response, FileContentsResponse::new_error(1)),
+        pdu => panic!("unexpected PDU: {pdu:?}"),
+    });
+}
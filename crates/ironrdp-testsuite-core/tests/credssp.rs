@@ -0,0 +1,62 @@
+use ironrdp_connector::credssp::{RemoteGuardCreds, RemoteGuardPackageCred};
+
+fn kerberos_creds() -> RemoteGuardCreds {
+    RemoteGuardCreds {
+        logon_cred: RemoteGuardPackageCred {
+            package_name: "Kerberos".to_owned(),
+            cred_buffer: vec![0xAA, 0xBB],
+        },
+        supplemental_creds: Vec::new(),
+    }
+}
+
+#[test]
+fn remote_guard_creds_encoding() {
+    #[rustfmt::skip]
+    let expected = [
+        0x30, 0x1E, // TSRemoteGuardCreds
+        0xA0, 0x1C, // logonCred
+        0x30, 0x1A, // TSRemoteGuardPackageCred
+        0xA0, 0x12, 0x04, 0x10, // packageName
+        b'K', 0, b'e', 0, b'r', 0, b'b', 0, b'e', 0, b'r', 0, b'o', 0, b's', 0,
+        0xA1, 0x04, 0x04, 0x02, // credBuffer
+        0xAA, 0xBB,
+    ];
+
+    let encoded = kerberos_creds().encode();
+
+    assert_eq!(encoded, expected);
+    assert_eq!(RemoteGuardCreds::decode(&encoded).unwrap(), kerberos_creds());
+}
+
+#[test]
+fn remote_guard_creds_with_supplemental_creds_round_trip() {
+    let mut creds = kerberos_creds();
+    creds.logon_cred.cred_buffer = vec![0x5A; 300];
+    creds.supplemental_creds = vec![
+        RemoteGuardPackageCred {
+            package_name: "NTLM".to_owned(),
+            cred_buffer: vec![1, 2, 3],
+        },
+        RemoteGuardPackageCred {
+            package_name: "CloudAP".to_owned(),
+            cred_buffer: Vec::new(),
+        },
+    ];
+
+    let decoded = RemoteGuardCreds::decode(&creds.encode()).unwrap();
+
+    assert_eq!(decoded, creds);
+}
+
+#[test]
+fn truncated_remote_guard_creds_are_rejected() {
+    let encoded = kerberos_creds().encode();
+
+    for len in 0..encoded.len() {
+        assert!(
+            RemoteGuardCreds::decode(&encoded[..len]).is_err(),
+            "decoded {len} bytes"
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use std::io;
+
+use ironrdp_connector::{ConnectorError, ConnectorErrorExt as _, ConnectorErrorKind, ErrorClass};
+use ironrdp_core::{invalid_field_err, DecodeError};
+use ironrdp_session::{SessionError, SessionErrorExt as _};
+
+#[test]
+fn class_of_the_kind() {
+    let decode_error = || -> DecodeError { invalid_field_err!("test", "field", "reason") };
+
+    assert_eq!(decode_error().class(), ErrorClass::Codec);
+    assert_eq!(ConnectorError::decode(decode_error()).class(), ErrorClass::Codec);
+    assert_eq!(SessionError::decode(decode_error()).class(), ErrorClass::Codec);
+    assert_eq!(
+        ConnectorError::new("NLA", ConnectorErrorKind::AccessDenied).class(),
+        ErrorClass::Security
+    );
+    assert_eq!(ConnectorError::general("test").class(), ErrorClass::Other);
+}
+
+#[test]
+fn overridden_class() {
+    let error = ConnectorError::reason("Initiation", "test").with_class(ErrorClass::Negotiation);
+    assert_eq!(error.class(), ErrorClass::Negotiation);
+}
+
+#[test]
+fn io_source() {
+    let error = ConnectorError::custom("read frame", io::Error::from(io::ErrorKind::UnexpectedEof));
+    assert_eq!(error.class(), ErrorClass::Io);
+
+    let error = SessionError::custom("write response", io::Error::from(io::ErrorKind::BrokenPipe));
+    assert_eq!(error.class(), ErrorClass::Io);
+}
+
+#[test]
+fn stable_discriminants() {
+    assert_eq!(u8::from(ErrorClass::Other), 0);
+    assert_eq!(u8::from(ErrorClass::Negotiation), 1);
+    assert_eq!(u8::from(ErrorClass::Security), 2);
+    assert_eq!(u8::from(ErrorClass::ProtocolViolation), 3);
+    assert_eq!(u8::from(ErrorClass::Io), 4);
+    assert_eq!(u8::from(ErrorClass::Codec), 5);
+    assert_eq!(u8::from(ErrorClass::Channel), 6);
+}
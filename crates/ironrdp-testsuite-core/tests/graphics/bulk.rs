@@ -0,0 +1,118 @@
+use ironrdp_graphics::bulk::{BulkCompressor, BulkDecompressor, MppcCompressor, MppcLevel};
+use ironrdp_pdu::rdp::client_info::CompressionType;
+use ironrdp_pdu::rdp::headers::CompressionFlags;
+
+fn text(len: usize) -> Vec<u8> {
+    b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor. "
+        .iter()
+        .copied()
+        .cycle()
+        .take(len)
+        .collect()
+}
+
+fn noise(len: usize, seed: u32) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            state.to_le_bytes()[2]
+        })
+        .collect()
+}
+
+/// Compresses the packets one after the other, checking the decompressed data.
+fn round_trip(compression_type: CompressionType, packets: &[Vec<u8>]) -> Vec<CompressionFlags> {
+    let mut compressor = BulkCompressor::new(compression_type);
+    let mut decompressor = BulkDecompressor::new(compressor.compression_type()).unwrap();
+
+    packets
+        .iter()
+        .map(|packet| {
+            let mut compressed = Vec::new();
+            let flags = compressor.compress(packet, &mut compressed);
+
+            let data = if flags.contains(CompressionFlags::COMPRESSED) {
+                compressed.as_slice()
+            } else {
+                assert!(compressed.is_empty());
+                packet.as_slice()
+            };
+            assert_eq!(decompressor.decompress(data, flags).unwrap(), packet.as_slice());
+
+            flags
+        })
+        .collect()
+}
+
+#[test]
+fn mppc_encodes_literals_and_copy() {
+    let mut compressor = MppcCompressor::new(MppcLevel::K8);
+
+    let mut compressed = Vec::new();
+    let flags = compressor.compress(b"abcabcabc", &mut compressed);
+
+    assert_eq!(flags, CompressionFlags::COMPRESSED);
+    // Three literals, then the copy-offset 3 (1111 000011) and the length-of-match 6 (10 10).
+    assert_eq!(compressed, [0x61, 0x62, 0x63, 0xF0, 0xE8]);
+}
+
+#[test]
+fn mppc_history_is_shared_by_the_packets() {
+    for compression_type in [CompressionType::K8, CompressionType::K64] {
+        let packets = [text(1000), text(1000), text(3000), text(5000)];
+        let flags = round_trip(compression_type, &packets);
+
+        assert!(flags.iter().all(|flags| flags.contains(CompressionFlags::COMPRESSED)));
+        assert!(!flags[1].contains(CompressionFlags::AT_FRONT));
+    }
+}
+
+#[test]
+fn mppc_restarts_at_front_of_full_history() {
+    let packets = [text(5000), text(5000), text(60_000), text(60_000)];
+
+    let flags = round_trip(CompressionType::K8, &packets[..2]);
+    assert!(flags[1].contains(CompressionFlags::AT_FRONT));
+
+    // Packets larger than the history are sent uncompressed.
+    let flags = round_trip(CompressionType::K8, &packets[2..]);
+    assert_eq!(flags, [CompressionFlags::FLUSHED, CompressionFlags::FLUSHED]);
+
+    let flags = round_trip(CompressionType::K64, &packets);
+    assert!(flags[3].contains(CompressionFlags::COMPRESSED | CompressionFlags::AT_FRONT));
+}
+
+#[test]
+fn incompressible_data_flushes_the_history() {
+    let packets = [text(2000), noise(2000, 1), text(2000), noise(100, 2), text(100)];
+    let flags = round_trip(CompressionType::K64, &packets);
+
+    assert_eq!(flags[1], CompressionFlags::FLUSHED);
+    assert_eq!(flags[2], CompressionFlags::COMPRESSED | CompressionFlags::AT_FRONT);
+    assert_eq!(flags[3], CompressionFlags::FLUSHED);
+}
+
+#[test]
+fn rdp61_wraps_mppc_64k() {
+    let mut compressor = BulkCompressor::new(CompressionType::Rdp61);
+    assert_eq!(compressor.compression_type(), CompressionType::Rdp61);
+
+    let mut compressed = Vec::new();
+    let flags = compressor.compress(&text(4000), &mut compressed);
+    assert_eq!(flags, CompressionFlags::COMPRESSED);
+    // L1_INNER_COMPRESSION | L1_PACKET_AT_FRONT | L1_NO_COMPRESSION, then the compressed 64K MPPC data.
+    assert_eq!(&compressed[..2], &[0x16, 0x21]);
+
+    let packets = [text(4000), noise(3000, 3), text(70_000), text(500)];
+    let flags = round_trip(CompressionType::Rdp61, &packets);
+    assert!(flags.iter().all(|flags| *flags == CompressionFlags::COMPRESSED));
+}
+
+#[test]
+fn rdp6_falls_back_to_mppc_64k() {
+    let compressor = BulkCompressor::new(CompressionType::Rdp6);
+    assert_eq!(compressor.compression_type(), CompressionType::K64);
+
+    assert!(BulkDecompressor::new(CompressionType::Rdp6).is_err());
+}
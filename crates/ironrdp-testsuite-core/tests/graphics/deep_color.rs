@@ -0,0 +1,86 @@
+use ironrdp_graphics::deep_color::{convert, narrow, widen, FramebufferFormat, Rgba10};
+use ironrdp_graphics::image_processing::PixelFormat;
+
+#[test]
+fn channels_round_trip() {
+    for value in 0..=u8::MAX {
+        assert_eq!(narrow(widen(value)), value);
+    }
+
+    assert_eq!(widen(0xFF), 0x3FF);
+    assert_eq!(narrow(0x3FF), 0xFF);
+    assert_eq!(narrow(0x200), 0x80);
+}
+
+#[test]
+fn rgb10a2_packing() {
+    let color = Rgba10 {
+        r: 0x3FF,
+        g: 0x155,
+        b: 0x001,
+        a: 0x3FF,
+    };
+
+    let packed = color.pack();
+    assert_eq!(packed, 0xC015_57FF);
+    assert_eq!(Rgba10::unpack(packed), color);
+}
+
+#[test]
+fn rgb10a2_to_standard_and_back() {
+    // Two RGBA pixels, with one byte of padding at the end of the rows.
+    let src = [
+        0xFF, 0x80, 0x00, 0xFF, 0x10, 0x20, 0x30, 0xFF, 0x00, //
+        0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00,
+    ];
+    let standard = FramebufferFormat::Standard(PixelFormat::RgbA32);
+
+    let mut deep = [0; 16];
+    convert(&src, standard, 9, &mut deep, FramebufferFormat::Rgb10A2, 8, 2, 2).unwrap();
+
+    let first = Rgba10::unpack(u32::from_le_bytes(deep[..4].try_into().unwrap()));
+    assert_eq!(
+        first,
+        Rgba10 {
+            r: 0x3FF,
+            g: 0x202,
+            b: 0,
+            a: 0x3FF
+        }
+    );
+
+    let mut back = [0; 16];
+    convert(&deep, FramebufferFormat::Rgb10A2, 8, &mut back, standard, 8, 2, 2).unwrap();
+    assert_eq!(back[..8], src[..8]);
+    assert_eq!(back[8..], src[9..17]);
+
+    let mut bgra = [0; 16];
+    convert(
+        &deep,
+        FramebufferFormat::Rgb10A2,
+        8,
+        &mut bgra,
+        FramebufferFormat::Standard(PixelFormat::BgrA32),
+        8,
+        2,
+        2,
+    )
+    .unwrap();
+    assert_eq!(bgra[..4], [0x00, 0x80, 0xFF, 0xFF]);
+}
+
+#[test]
+fn too_small_buffer_is_rejected() {
+    let mut dst = [0; 4];
+    assert!(convert(
+        &[0; 4],
+        FramebufferFormat::Rgb10A2,
+        4,
+        &mut dst,
+        FramebufferFormat::Rgb10A2,
+        4,
+        2,
+        1
+    )
+    .is_err());
+}
@@ -1,4 +1,6 @@
+mod bulk;
 mod color_conversion;
+mod deep_color;
 mod dwt;
 mod image_processing;
 mod rle;
@@ -55,3 +55,75 @@ fn decompress_bpp_16(#[case] src: &[u8], #[case] expected: &[u8]) {
     ironrdp_graphics::rle::decompress_16_bpp(src, &mut out, 64, 64).expect("decompress 16 bpp");
     assert_eq!(out, expected);
 }
+
+#[rstest]
+#[case::x27019fd9f222cebce9dfebcddb12bfa0(include_bytes!(
+    "../../test_data/rle/tile-27019fd9f222cebce9dfebcddb12bfa0-decompressed.bin"
+))]
+#[case::x4d75aa6a18c435c6230ba739b802a861(include_bytes!(
+    "../../test_data/rle/tile-4d75aa6a18c435c6230ba739b802a861-decompressed.bin"
+))]
+#[case::x94bb5b131eb3bc110905dfcb0f60da79(include_bytes!(
+    "../../test_data/rle/tile-94bb5b131eb3bc110905dfcb0f60da79-decompressed.bin"
+))]
+#[case::xfbcefc9af4db651aefd91bcabc8ea9fc(include_bytes!(
+    "../../test_data/rle/tile-fbcefc9af4db651aefd91bcabc8ea9fc-decompressed.bin"
+))]
+fn compress_bpp_16(#[case] image: &[u8]) {
+    let mut compressed = Vec::new();
+    let len = ironrdp_graphics::rle::compress_16_bpp(image, &mut compressed, 64, 64).expect("compress 16 bpp");
+    assert!(len < image.len());
+
+    let mut out = Vec::new();
+    ironrdp_graphics::rle::decompress_16_bpp(&compressed, &mut out, 64, 64).expect("decompress 16 bpp");
+    assert_eq!(out, image);
+}
+
+/// Image with black and colored runs on the first line, long runs, and lines repeating the previous ones.
+fn synthetic_image(width: usize, height: usize, bytes_per_pixel: usize) -> Vec<u8> {
+    let mut image = Vec::with_capacity(width * height * bytes_per_pixel);
+
+    for y in 0..height {
+        for x in 0..width {
+            let value = match (y, x) {
+                (0, 0..=9) => 0,
+                (0, 10..=19) => 0x42,
+                (_, 0..=99) if y % 3 == 1 => 0x10,
+                _ if y % 3 == 2 => u8::try_from(x % 7).unwrap(),
+                _ => u8::try_from((x * 31 + y * 17) % 251).unwrap(),
+            };
+            image.extend(core::iter::repeat_n(value, bytes_per_pixel));
+        }
+    }
+
+    image
+}
+
+#[rstest]
+#[case::bpp_24(24, 3)]
+#[case::bpp_16(16, 2)]
+#[case::bpp_15(15, 2)]
+#[case::bpp_8(8, 1)]
+fn compress_round_trip(#[case] bpp: usize, #[case] bytes_per_pixel: usize) {
+    for (width, height) in [(1, 1), (7, 1), (64, 64), (400, 9), (1000, 70)] {
+        let image = synthetic_image(width, height, bytes_per_pixel);
+
+        let mut compressed = Vec::new();
+        ironrdp_graphics::rle::compress(&image, &mut compressed, width, height, bpp).expect("compress");
+
+        let mut out = Vec::new();
+        ironrdp_graphics::rle::decompress(&compressed, &mut out, width, height, bpp).expect("decompress");
+        assert_eq!(out, image, "{width}x{height}");
+    }
+}
+
+#[test]
+fn compress_black_image() {
+    let image = vec![0; 64 * 64 * 3];
+
+    let mut compressed = Vec::new();
+    ironrdp_graphics::rle::compress_24_bpp(&image, &mut compressed, 64, 64).expect("compress 24 bpp");
+
+    // A background run for the first line, black, then a MEGA_MEGA background run for the next lines.
+    assert_eq!(compressed, [0x00, 0x20, 0xF0, 0xC0, 0x0F]);
+}
@@ -1,3 +1,5 @@
+use core::time::Duration;
+
 use ironrdp_input::*;
 use ironrdp_pdu::input::fast_path::{FastPathInputEvent, KeyboardFlags, SynchronizeFlags};
 use ironrdp_pdu::input::mouse::PointerFlags;
@@ -264,6 +266,67 @@ fn release_all() {
     assert_eq!(actual_inputs.as_slice(), expected_inputs.as_slice());
 }
 
+#[test]
+fn release_keys() {
+    let mut db = Database::default();
+
+    let ops = [
+        Operation::KeyPressed(Scancode::from_u8(false, 23)),
+        Operation::KeyPressed(Scancode::from_u8(true, 19)),
+        Operation::UnicodeKeyPressed('a'),
+        Operation::MouseButtonPressed(MouseButton::Left),
+    ];
+
+    let _ = db.apply(ops);
+
+    let expected_inputs = [
+        FastPathInputEvent::KeyboardEvent(KeyboardFlags::RELEASE, 23),
+        FastPathInputEvent::KeyboardEvent(KeyboardFlags::RELEASE | KeyboardFlags::EXTENDED, 19),
+        FastPathInputEvent::UnicodeKeyboardEvent(KeyboardFlags::RELEASE, u16::from(b'a')),
+    ];
+
+    let actual_inputs = db.release_keys();
+
+    assert_eq!(actual_inputs.as_slice(), expected_inputs.as_slice());
+    assert!(db.is_mouse_button_pressed(MouseButton::Left));
+}
+
+#[test]
+fn key_repeat() {
+    let mut db = Database::default();
+    db.set_key_repeat(Some(KeyRepeat {
+        delay: Duration::from_millis(500),
+        interval: Duration::from_millis(50),
+    }));
+
+    let _ = db.apply([Operation::KeyPressed(Scancode::from_u8(true, 72))]);
+    assert_eq!(db.next_key_repeat(), Some(Duration::from_millis(500)));
+
+    assert!(db.advance_key_repeat(Duration::from_millis(400)).is_empty());
+    assert_eq!(db.next_key_repeat(), Some(Duration::from_millis(100)));
+
+    let repeat = FastPathInputEvent::KeyboardEvent(KeyboardFlags::EXTENDED, 72);
+    let actual_inputs = db.advance_key_repeat(Duration::from_millis(210));
+    assert_eq!(
+        actual_inputs.as_slice(),
+        [repeat.clone(), repeat.clone(), repeat.clone()].as_slice()
+    );
+    assert_eq!(db.next_key_repeat(), Some(Duration::from_millis(40)));
+
+    // The last pressed key is repeated.
+    let _ = db.apply([Operation::KeyPressed(Scancode::from_u8(false, 30))]);
+    let _ = db.apply([Operation::KeyReleased(Scancode::from_u8(true, 72))]);
+    let actual_inputs = db.advance_key_repeat(Duration::from_millis(500));
+    assert_eq!(
+        actual_inputs.as_slice(),
+        [FastPathInputEvent::KeyboardEvent(KeyboardFlags::empty(), 30)].as_slice()
+    );
+
+    let _ = db.apply([Operation::KeyReleased(Scancode::from_u8(false, 30))]);
+    assert_eq!(db.next_key_repeat(), None);
+    assert!(db.advance_key_repeat(Duration::from_secs(1)).is_empty());
+}
+
 #[rstest]
 #[case(true, false, true, false, SynchronizeFlags::SCROLL_LOCK | SynchronizeFlags::CAPS_LOCK)]
 #[case(true, true, true, false, SynchronizeFlags::SCROLL_LOCK | SynchronizeFlags::NUM_LOCK | SynchronizeFlags::CAPS_LOCK)]
@@ -328,3 +391,93 @@ fn wheel_rotations() {
 
     assert_eq!(actual_inputs.as_slice(), expected_inputs.as_slice());
 }
+
+#[test]
+fn synchronize_lock_keys() {
+    let mut db = Database::default();
+
+    let lock_keys = LockKeys {
+        num_lock: true,
+        caps_lock: true,
+        ..LockKeys::default()
+    };
+
+    // Sent even without change, the synchronization being requested when the state may be out of date.
+    for _ in 0..2 {
+        let actual_inputs = db.apply([Operation::SynchronizeLockKeys(lock_keys)]);
+
+        assert_eq!(
+            actual_inputs.as_slice(),
+            [FastPathInputEvent::SyncEvent(
+                SynchronizeFlags::NUM_LOCK | SynchronizeFlags::CAPS_LOCK
+            )]
+        );
+        assert_eq!(db.lock_keys(), lock_keys);
+    }
+}
+
+#[test]
+fn process_events_round_trip() {
+    let operations = [
+        Operation::SynchronizeLockKeys(LockKeys {
+            scroll_lock: true,
+            ..LockKeys::default()
+        }),
+        Operation::MouseMove(MousePosition { x: 10, y: 20 }),
+        Operation::MouseButtonPressed(MouseButton::Right),
+        Operation::MouseButtonPressed(MouseButton::X2),
+        Operation::KeyPressed(Scancode::from_u8(true, 0x1d)),
+        Operation::UnicodeKeyPressed('é'),
+        Operation::UnicodeKeyPressed('😀'),
+        Operation::UnicodeKeyReleased('😀'),
+        Operation::WheelRotations(WheelRotations {
+            is_vertical: true,
+            rotation_units: 120,
+        }),
+        Operation::KeyReleased(Scancode::from_u8(true, 0x1d)),
+        Operation::MouseButtonReleased(MouseButton::Right),
+    ];
+
+    let mut client = Database::default();
+    let events = client.apply(operations.clone());
+
+    let mut server = Database::default();
+    let processed = events
+        .iter()
+        .flat_map(|event| server.process_event(event))
+        .collect::<Vec<_>>();
+
+    assert_eq!(processed, operations);
+    assert_eq!(server.lock_keys(), client.lock_keys());
+    assert_eq!(server.mouse_position(), client.mouse_position());
+    assert_eq!(server.mouse_buttons_state(), client.mouse_buttons_state());
+    assert_eq!(server.keyboard_state(), client.keyboard_state());
+    assert!(server.is_unicode_key_pressed('é'));
+    assert!(!server.is_unicode_key_pressed('😀'));
+}
+
+#[test]
+fn process_event_ignores_unpaired_surrogates() {
+    let mut db = Database::default();
+
+    // A high surrogate not followed by a low surrogate is dropped.
+    assert!(db
+        .process_event(&FastPathInputEvent::UnicodeKeyboardEvent(
+            KeyboardFlags::empty(),
+            0xd83d
+        ))
+        .is_empty());
+    assert_eq!(
+        db.process_event(&FastPathInputEvent::UnicodeKeyboardEvent(KeyboardFlags::empty(), 0x61))
+            .as_slice(),
+        [Operation::UnicodeKeyPressed('a')]
+    );
+
+    // So is a lone low surrogate.
+    assert!(db
+        .process_event(&FastPathInputEvent::UnicodeKeyboardEvent(
+            KeyboardFlags::empty(),
+            0xde00
+        ))
+        .is_empty());
+}
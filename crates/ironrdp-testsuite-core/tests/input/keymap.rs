@@ -0,0 +1,66 @@
+use ironrdp_input::Scancode;
+
+#[test]
+fn evdev_keys() {
+    // KEY_ESC, KEY_A, KEY_F12
+    assert_eq!(Scancode::from_evdev(1), Some(Scancode::from_u8(false, 0x01)));
+    assert_eq!(Scancode::from_evdev(30), Some(Scancode::from_u8(false, 0x1E)));
+    assert_eq!(Scancode::from_evdev(88), Some(Scancode::from_u8(false, 0x58)));
+    // KEY_RIGHTCTRL, KEY_UP, KEY_LEFTMETA
+    assert_eq!(Scancode::from_evdev(97), Some(Scancode::from_u8(true, 0x1D)));
+    assert_eq!(Scancode::from_evdev(103), Some(Scancode::from_u8(true, 0x48)));
+    assert_eq!(Scancode::from_evdev(125), Some(Scancode::from_u8(true, 0x5B)));
+    // KEY_F13, KEY_F23
+    assert_eq!(Scancode::from_evdev(183), Some(Scancode::from_u8(false, 0x64)));
+    assert_eq!(Scancode::from_evdev(193), Some(Scancode::from_u8(false, 0x6E)));
+    // KEY_RESERVED, BTN_LEFT
+    assert_eq!(Scancode::from_evdev(0), None);
+    assert_eq!(Scancode::from_evdev(0x110), None);
+}
+
+#[test]
+fn hid_usages() {
+    // A, Z, 1, 0
+    assert_eq!(Scancode::from_hid_usage(0x04), Some(Scancode::from_u8(false, 0x1E)));
+    assert_eq!(Scancode::from_hid_usage(0x1D), Some(Scancode::from_u8(false, 0x2C)));
+    assert_eq!(Scancode::from_hid_usage(0x1E), Some(Scancode::from_u8(false, 0x02)));
+    assert_eq!(Scancode::from_hid_usage(0x27), Some(Scancode::from_u8(false, 0x0B)));
+    // F1, F10, Keypad Enter, Right GUI
+    assert_eq!(Scancode::from_hid_usage(0x3A), Some(Scancode::from_u8(false, 0x3B)));
+    assert_eq!(Scancode::from_hid_usage(0x43), Some(Scancode::from_u8(false, 0x44)));
+    assert_eq!(Scancode::from_hid_usage(0x58), Some(Scancode::from_u8(true, 0x1C)));
+    assert_eq!(Scancode::from_hid_usage(0xE7), Some(Scancode::from_u8(true, 0x5C)));
+    // ErrorRollOver
+    assert_eq!(Scancode::from_hid_usage(0x01), None);
+}
+
+#[test]
+fn set2_codes() {
+    // Escape, A, F7, Keypad Enter, Print Screen
+    assert_eq!(Scancode::from_set2(false, 0x76), Some(Scancode::from_u8(false, 0x01)));
+    assert_eq!(Scancode::from_set2(false, 0x1C), Some(Scancode::from_u8(false, 0x1E)));
+    assert_eq!(Scancode::from_set2(false, 0x83), Some(Scancode::from_u8(false, 0x41)));
+    assert_eq!(Scancode::from_set2(true, 0x5A), Some(Scancode::from_u8(true, 0x1C)));
+    assert_eq!(Scancode::from_set2(true, 0x7C), Some(Scancode::from_u8(true, 0x37)));
+    // Fake shift
+    assert_eq!(Scancode::from_set2(true, 0x12), None);
+}
+
+#[test]
+fn tables_agree() {
+    // The letters, digits and main keys are the same keys in every table.
+    for evdev in 1..=83 {
+        let Some(scancode) = Scancode::from_evdev(evdev) else {
+            panic!("no scancode for the evdev key code {evdev}");
+        };
+
+        assert!(
+            (0..=0xFF).any(|usage| Scancode::from_hid_usage(usage) == Some(scancode)),
+            "no HID usage for {scancode:?}"
+        );
+        assert!(
+            (0..=0xFF).any(|code| Scancode::from_set2(false, code) == Some(scancode)),
+            "no set 2 code for {scancode:?}"
+        );
+    }
+}
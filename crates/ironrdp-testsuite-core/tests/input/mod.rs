@@ -1,2 +1,3 @@
 mod fastpath_packets;
+mod keymap;
 mod smoke;
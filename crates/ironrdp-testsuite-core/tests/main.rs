@@ -11,16 +11,22 @@
 //! Cargo will run all tests from a single binary in parallel, but
 //! binaries themselves are run sequentially.
 
+mod audin;
 mod clipboard;
+mod credssp;
 mod displaycontrol;
 mod dvc;
+mod error_class;
 mod fuzz_regression;
 mod graphics;
 mod input;
 mod pcb;
 mod pdu;
+mod rail;
 mod rdcleanpath;
+mod rdpdr;
 mod rdpsnd;
 mod server;
 mod server_name;
 mod session;
+mod svc;
@@ -0,0 +1,157 @@
+use ironrdp_core::{decode, encode_vec};
+use ironrdp_pdu::rdp::autodetect::{
+    AutoDetectPhase, AutoDetectRequest, AutoDetectRequestPdu, AutoDetectResponse, AutoDetectResponsePdu,
+};
+
+const RTT_REQUEST_PDU_BUFFER: [u8; 10] = [
+    0x00, 0x10, 0x00, 0x00, // security header, SEC_AUTODETECT_REQ
+    0x06, // headerLength
+    0x00, // headerTypeId, TYPE_ID_AUTODETECT_REQUEST
+    0x01, 0x00, // sequenceNumber
+    0x01, 0x10, // requestType, connect-time RTT measure
+];
+
+const RTT_RESPONSE_PDU_BUFFER: [u8; 10] = [
+    0x00, 0x20, 0x00, 0x00, // security header, SEC_AUTODETECT_RSP
+    0x06, // headerLength
+    0x01, // headerTypeId, TYPE_ID_AUTODETECT_RESPONSE
+    0x01, 0x00, // sequenceNumber
+    0x00, 0x00, // responseType
+];
+
+const BANDWIDTH_STOP_BUFFER: [u8; 11] = [
+    0x08, // headerLength
+    0x00, // headerTypeId
+    0x03, 0x00, // sequenceNumber
+    0x2b, 0x00, // requestType, connect-time bandwidth measure stop
+    0x03, 0x00, // payloadLength
+    0xaa, 0xbb, 0xcc, // payload
+];
+
+const BANDWIDTH_RESULTS_BUFFER: [u8; 14] = [
+    0x0e, // headerLength
+    0x01, // headerTypeId
+    0x03, 0x00, // sequenceNumber
+    0x0b, 0x00, // responseType, continuous bandwidth measure results
+    0x64, 0x00, 0x00, 0x00, // timeDelta
+    0x00, 0x10, 0x00, 0x00, // byteCount
+];
+
+const NETWORK_CHARACTERISTICS_RESULT_BUFFER: [u8; 14] = [
+    0x0e, // headerLength
+    0x00, // headerTypeId
+    0x04, 0x00, // sequenceNumber
+    0x80, 0x08, // requestType, bandwidth and average RTT
+    0x00, 0x28, 0x00, 0x00, // bandwidth
+    0x14, 0x00, 0x00, 0x00, // averageRTT
+];
+
+const NETWORK_CHARACTERISTICS_SYNC_BUFFER: [u8; 14] = [
+    0x0e, // headerLength
+    0x01, // headerTypeId
+    0x05, 0x00, // sequenceNumber
+    0x18, 0x00, // responseType
+    0x00, 0x28, 0x00, 0x00, // bandwidth
+    0x14, 0x00, 0x00, 0x00, // rtt
+];
+
+#[test]
+fn rtt_measure_request_pdu_roundtrip() {
+    let pdu = AutoDetectRequestPdu(AutoDetectRequest::RttMeasure {
+        sequence_number: 1,
+        phase: AutoDetectPhase::ConnectTime,
+    });
+
+    assert_eq!(pdu, decode(RTT_REQUEST_PDU_BUFFER.as_slice()).unwrap());
+    assert_eq!(RTT_REQUEST_PDU_BUFFER.as_slice(), encode_vec(&pdu).unwrap());
+}
+
+#[test]
+fn rtt_measure_response_pdu_roundtrip() {
+    let pdu = AutoDetectResponsePdu(AutoDetectResponse::RttMeasure { sequence_number: 1 });
+
+    assert_eq!(pdu, decode(RTT_RESPONSE_PDU_BUFFER.as_slice()).unwrap());
+    assert_eq!(RTT_RESPONSE_PDU_BUFFER.as_slice(), encode_vec(&pdu).unwrap());
+}
+
+#[test]
+fn bandwidth_measure_stop_roundtrip() {
+    let request = AutoDetectRequest::BandwidthMeasureStop {
+        sequence_number: 3,
+        phase: AutoDetectPhase::ConnectTime,
+        payload: vec![0xaa, 0xbb, 0xcc],
+    };
+
+    assert_eq!(request, decode(BANDWIDTH_STOP_BUFFER.as_slice()).unwrap());
+    assert_eq!(BANDWIDTH_STOP_BUFFER.as_slice(), encode_vec(&request).unwrap());
+}
+
+#[test]
+fn bandwidth_measure_results_roundtrip() {
+    let response = AutoDetectResponse::BandwidthMeasureResults {
+        sequence_number: 3,
+        phase: AutoDetectPhase::Continuous,
+        time_delta: 100,
+        byte_count: 4096,
+    };
+
+    assert_eq!(response, decode(BANDWIDTH_RESULTS_BUFFER.as_slice()).unwrap());
+    assert_eq!(BANDWIDTH_RESULTS_BUFFER.as_slice(), encode_vec(&response).unwrap());
+}
+
+#[test]
+fn network_characteristics_result_roundtrip() {
+    let request = AutoDetectRequest::NetworkCharacteristicsResult {
+        sequence_number: 4,
+        base_rtt: None,
+        bandwidth: Some(10240),
+        average_rtt: 20,
+    };
+
+    assert_eq!(
+        request,
+        decode(NETWORK_CHARACTERISTICS_RESULT_BUFFER.as_slice()).unwrap()
+    );
+    assert_eq!(
+        NETWORK_CHARACTERISTICS_RESULT_BUFFER.as_slice(),
+        encode_vec(&request).unwrap()
+    );
+}
+
+#[test]
+fn network_characteristics_sync_roundtrip() {
+    let response = AutoDetectResponse::NetworkCharacteristicsSync {
+        sequence_number: 5,
+        bandwidth: 10240,
+        rtt: 20,
+    };
+
+    assert_eq!(
+        response,
+        decode(NETWORK_CHARACTERISTICS_SYNC_BUFFER.as_slice()).unwrap()
+    );
+    assert_eq!(
+        NETWORK_CHARACTERISTICS_SYNC_BUFFER.as_slice(),
+        encode_vec(&response).unwrap()
+    );
+}
+
+#[test]
+fn network_characteristics_result_without_measures_is_rejected() {
+    let request = AutoDetectRequest::NetworkCharacteristicsResult {
+        sequence_number: 4,
+        base_rtt: None,
+        bandwidth: None,
+        average_rtt: 20,
+    };
+
+    encode_vec(&request).unwrap_err();
+}
+
+#[test]
+fn invalid_header_length_is_rejected() {
+    let mut buffer = NETWORK_CHARACTERISTICS_RESULT_BUFFER;
+    buffer[0] = 0x12;
+
+    decode::<AutoDetectRequest>(buffer.as_slice()).unwrap_err();
+}
@@ -1,4 +1,8 @@
 use ironrdp_core::{decode, decode_cursor, encode_vec, Encode, ReadCursor};
+use ironrdp_pdu::rdp::vc::dvc::gfx::{
+    CacheEntryMetadata, CacheImportOfferPdu, ClientPdu, MapSurfaceToWindowPdu, QoeFrameAcknowledgePdu, ServerPdu,
+};
+use ironrdp_testsuite_core::encode_decode_test;
 use ironrdp_testsuite_core::gfx::*;
 use ironrdp_testsuite_core::graphics_messages::*;
 
@@ -444,3 +448,59 @@ fn to_buffer_consume_correctly_serializes_avc_444_message() {
 
     assert_eq!(expected, buffer.as_slice());
 }
+
+encode_decode_test! {
+    map_surface_to_window: ServerPdu::MapSurfaceToWindow(MapSurfaceToWindowPdu {
+        surface_id: 1,
+        window_id: 0x0001_0203,
+        mapped_width: 1024,
+        mapped_height: 768,
+    }), [
+        0x15, 0x00, // cmdId = RDPGFX_CMDID_MAPSURFACETOWINDOW
+        0x00, 0x00, // flags
+        0x1a, 0x00, 0x00, 0x00, // pduLength = 26
+        0x01, 0x00, // surfaceId = 1
+        0x03, 0x02, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, // windowId
+        0x00, 0x04, 0x00, 0x00, // mappedWidth = 1024
+        0x00, 0x03, 0x00, 0x00, // mappedHeight = 768
+    ];
+    cache_import_offer: ClientPdu::CacheImportOffer(CacheImportOfferPdu {
+        cache_entries: vec![CacheEntryMetadata {
+            cache_key: 0x0102_0304_0506_0708,
+            bitmap_length: 0x4000,
+        }],
+    }), [
+        0x10, 0x00, // cmdId = RDPGFX_CMDID_CACHEIMPORTOFFER
+        0x00, 0x00, // flags
+        0x16, 0x00, 0x00, 0x00, // pduLength = 22
+        0x01, 0x00, // cacheEntriesCount = 1
+        0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, // cacheEntries::cacheKey
+        0x00, 0x40, 0x00, 0x00, // cacheEntries::bitmapLength = 0x4000
+    ];
+    qoe_frame_acknowledge: ClientPdu::QoeFrameAcknowledge(QoeFrameAcknowledgePdu {
+        frame_id: 5,
+        timestamp: 1000,
+        time_diff_se: 10,
+        time_diff_edr: 5,
+    }), [
+        0x16, 0x00, // cmdId = RDPGFX_CMDID_QOEFRAMEACKNOWLEDGE
+        0x00, 0x00, // flags
+        0x14, 0x00, 0x00, 0x00, // pduLength = 20
+        0x05, 0x00, 0x00, 0x00, // frameId = 5
+        0xe8, 0x03, 0x00, 0x00, // timestamp = 1000
+        0x0a, 0x00, // timeDiffSE = 10
+        0x05, 0x00, // timeDiffEDR = 5
+    ];
+}
+
+#[test]
+fn decoding_cache_import_offer_with_too_many_entries_fails() {
+    let buffer = [
+        0x10, 0x00, // cmdId = RDPGFX_CMDID_CACHEIMPORTOFFER
+        0x00, 0x00, // flags
+        0x0a, 0x00, 0x00, 0x00, // pduLength = 10
+        0x57, 0x15, // cacheEntriesCount = 5463
+    ];
+
+    assert!(decode::<ClientPdu>(&buffer).is_err());
+}
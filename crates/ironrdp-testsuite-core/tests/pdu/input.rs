@@ -1,7 +1,9 @@
-use ironrdp_core::{decode_cursor, encode_vec, ReadCursor};
-use ironrdp_pdu::input::fast_path::{FastPathInput, FastPathInputEvent};
+use ironrdp_core::{decode, decode_cursor, encode_vec, ReadCursor};
+use ironrdp_pdu::input::fast_path::{FastPathInput, FastPathInputEvent, KeyboardFlags, SynchronizeFlags};
 use ironrdp_pdu::input::mouse::PointerFlags;
-use ironrdp_pdu::input::MousePdu;
+use ironrdp_pdu::input::mouse_rel::PointerRelFlags;
+use ironrdp_pdu::input::{MousePdu, MouseRelPdu};
+use ironrdp_testsuite_core::encode_decode_test;
 
 const FASTPATH_INPUT_MESSAGE: [u8; 44] = [
     0x18, 0x2c, 0x20, 0x0, 0x90, 0x1a, 0x0, 0x26, 0x4, 0x20, 0x0, 0x8, 0x1b, 0x0, 0x26, 0x4, 0x20, 0x0, 0x10, 0x1b,
@@ -65,3 +67,39 @@ fn to_buffer_correctly_serializes_fastpath_input_message() {
 
     assert_eq!(buffer, FASTPATH_INPUT_MESSAGE.as_ref());
 }
+
+encode_decode_test! {
+    fastpath_sync_event: FastPathInputEvent::SyncEvent(SynchronizeFlags::NUM_LOCK | SynchronizeFlags::CAPS_LOCK),
+        [0x66]; // eventHeader = FASTPATH_INPUT_EVENT_SYNC | NUM_LOCK | CAPS_LOCK
+    fastpath_unicode_key_pressed: FastPathInputEvent::UnicodeKeyboardEvent(KeyboardFlags::empty(), 0x00e9),
+        [
+            0x80, // eventHeader = FASTPATH_INPUT_EVENT_UNICODE
+            0xe9, 0x00, // unicodeCode
+        ];
+    fastpath_unicode_key_released: FastPathInputEvent::UnicodeKeyboardEvent(KeyboardFlags::RELEASE, 0xd83d),
+        [
+            0x81, // eventHeader = FASTPATH_INPUT_EVENT_UNICODE | FASTPATH_INPUT_KBDFLAGS_RELEASE
+            0x3d, 0xd8, // unicodeCode
+        ];
+    fastpath_relative_mouse_event: FastPathInputEvent::MouseEventRel(MouseRelPdu {
+        flags: PointerRelFlags::MOVE,
+        x_delta: 5,
+        y_delta: -2,
+    }), [
+        0xa0, // eventHeader = FASTPATH_INPUT_EVENT_MOUSEREL
+        0x00, 0x08, // pointerFlags = PTRREL_FLAGS_MOVE
+        0x05, 0x00, // xDelta = 5
+        0xfe, 0xff, // yDelta = -2
+    ];
+}
+
+#[test]
+fn fastpath_sync_event_keeps_unknown_flags() {
+    let event = decode::<FastPathInputEvent>(&[0x70]).unwrap();
+
+    assert_eq!(
+        event,
+        FastPathInputEvent::SyncEvent(SynchronizeFlags::from_bits_retain(0x10))
+    );
+    assert_eq!(encode_vec(&event).unwrap(), [0x70]);
+}
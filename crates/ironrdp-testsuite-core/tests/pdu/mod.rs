@@ -1,8 +1,12 @@
+mod autodetect;
 mod gcc;
 mod gfx;
 mod input;
 mod mcs;
+mod palette;
 mod pointer;
+mod progressive;
 mod rdp;
 mod rfx;
+mod standard_security;
 mod x224;
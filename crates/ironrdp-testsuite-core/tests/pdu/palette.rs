@@ -0,0 +1,41 @@
+use ironrdp_core::{decode, encode_vec};
+use ironrdp_pdu::palette::{PaletteEntry, PaletteUpdateData};
+
+const PALETTE_BUFFER: [u8; 14] = [
+    0x02, 0x00, // updateType
+    0x00, 0x00, // pad2Octets
+    0x02, 0x00, 0x00, 0x00, // numberColors
+    0x00, 0x00, 0x00, // black
+    0xff, 0x80, 0x00, // orange
+];
+
+fn palette() -> PaletteUpdateData {
+    PaletteUpdateData {
+        entries: vec![
+            PaletteEntry::default(),
+            PaletteEntry {
+                red: 0xff,
+                green: 0x80,
+                blue: 0x00,
+            },
+        ],
+    }
+}
+
+#[test]
+fn palette_update_roundtrip() {
+    assert_eq!(palette(), decode(&PALETTE_BUFFER).unwrap());
+    assert_eq!(PALETTE_BUFFER.as_slice(), encode_vec(&palette()).unwrap());
+}
+
+#[test]
+fn palette_update_too_many_colors() {
+    let palette = PaletteUpdateData {
+        entries: vec![PaletteEntry::default(); 257],
+    };
+    encode_vec(&palette).unwrap_err();
+
+    let mut buffer = PALETTE_BUFFER.to_vec();
+    buffer[4..8].copy_from_slice(&257u32.to_le_bytes());
+    decode::<PaletteUpdateData>(&buffer).unwrap_err();
+}
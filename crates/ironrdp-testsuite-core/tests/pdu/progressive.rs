@@ -0,0 +1,187 @@
+use ironrdp_core::decode;
+use ironrdp_pdu::codecs::progressive::*;
+use ironrdp_pdu::codecs::rfx::{Quant, RfxRectangle};
+use ironrdp_testsuite_core::encode_decode_test;
+
+const SYNC_BUFFER: [u8; 12] = [
+    0xc0, 0xcc, // blockType = PROGRESSIVE_WBT_SYNC
+    0x0c, 0x00, 0x00, 0x00, // blockLen = 12
+    0xca, 0xac, 0xcc, 0xca, // magic
+    0x00, 0x01, // version = 0x0100
+];
+
+const FRAME_BEGIN_BUFFER: [u8; 12] = [
+    0xc1, 0xcc, // blockType = PROGRESSIVE_WBT_FRAME_BEGIN
+    0x0c, 0x00, 0x00, 0x00, // blockLen = 12
+    0x05, 0x00, 0x00, 0x00, // frameIndex = 5
+    0x01, 0x00, // regionCount = 1
+];
+
+const FRAME_END_BUFFER: [u8; 6] = [
+    0xc2, 0xcc, // blockType = PROGRESSIVE_WBT_FRAME_END
+    0x06, 0x00, 0x00, 0x00, // blockLen = 6
+];
+
+const CONTEXT_BUFFER: [u8; 10] = [
+    0xc3, 0xcc, // blockType = PROGRESSIVE_WBT_CONTEXT
+    0x0a, 0x00, 0x00, 0x00, // blockLen = 10
+    0x00, // ctxId
+    0x40, 0x00, // tileSize = 64
+    0x01, // flags = RFX_SUBBAND_DIFFING
+];
+
+const REGION_BUFFER: [u8; 74] = [
+    0xc4, 0xcc, // blockType = PROGRESSIVE_WBT_REGION
+    0x4a, 0x00, 0x00, 0x00, // blockLen = 74
+    0x40, // tileSize = 64
+    0x01, 0x00, // numRects = 1
+    0x01, // numQuant = 1
+    0x01, // numProgQuant = 1
+    0x00, // flags
+    0x01, 0x00, // numTiles = 1
+    0x1b, 0x00, 0x00, 0x00, // tileDataSize = 27
+    0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x40, 0x00, // rects
+    0x66, 0x66, 0x77, 0x88, 0x98, // quantVals
+    0x00, // quantProgVals::quality
+    0x66, 0x66, 0x77, 0x88, 0x98, // quantProgVals::yQuantValues
+    0x66, 0x66, 0x77, 0x88, 0x98, // quantProgVals::cbQuantValues
+    0x66, 0x66, 0x77, 0x88, 0x98, // quantProgVals::crQuantValues
+    0xc6, 0xcc, // tiles::blockType = PROGRESSIVE_WBT_TILE_FIRST
+    0x1b, 0x00, 0x00, 0x00, // tiles::blockLen = 27
+    0x00, 0x00, 0x00, // tiles::quantIdxY, quantIdxCb, quantIdxCr
+    0x00, 0x00, // tiles::xIdx
+    0x00, 0x00, // tiles::yIdx
+    0x00, // tiles::flags
+    0x00, // tiles::quality
+    0x02, 0x00, // tiles::yLen = 2
+    0x01, 0x00, // tiles::cbLen = 1
+    0x01, 0x00, // tiles::crLen = 1
+    0x00, 0x00, // tiles::tailLen = 0
+    0x01, 0x02, // tiles::yData
+    0x03, // tiles::cbData
+    0x04, // tiles::crData
+];
+
+const TILE_SIMPLE_BUFFER: [u8; 24] = [
+    0xc5, 0xcc, // blockType = PROGRESSIVE_WBT_TILE_SIMPLE
+    0x18, 0x00, 0x00, 0x00, // blockLen = 24
+    0x00, 0x01, 0x02, // quantIdxY, quantIdxCb, quantIdxCr
+    0x03, 0x00, // xIdx = 3
+    0x04, 0x00, // yIdx = 4
+    0x01, // flags = RFX_TILE_DIFFERENCE
+    0x01, 0x00, // yLen = 1
+    0x00, 0x00, // cbLen = 0
+    0x00, 0x00, // crLen = 0
+    0x01, 0x00, // tailLen = 1
+    0x11, // yData
+    0x22, // tailData
+];
+
+const TILE_UPGRADE_BUFFER: [u8; 28] = [
+    0xc7, 0xcc, // blockType = PROGRESSIVE_WBT_TILE_UPGRADE
+    0x1c, 0x00, 0x00, 0x00, // blockLen = 28
+    0x00, 0x00, 0x00, // quantIdxY, quantIdxCb, quantIdxCr
+    0x01, 0x00, // xIdx = 1
+    0x02, 0x00, // yIdx = 2
+    0x01, // quality = 1
+    0x01, 0x00, // ySrlLen = 1
+    0x01, 0x00, // yRawLen = 1
+    0x00, 0x00, // cbSrlLen = 0
+    0x00, 0x00, // cbRawLen = 0
+    0x00, 0x00, // crSrlLen = 0
+    0x00, 0x00, // crRawLen = 0
+    0xaa, // ySrlData
+    0xbb, // yRawData
+];
+
+fn region() -> Block<'static> {
+    Block::Region(RegionPdu {
+        flags: RegionFlags::empty(),
+        rectangles: vec![RfxRectangle {
+            x: 0,
+            y: 0,
+            width: 64,
+            height: 64,
+        }],
+        quants: vec![Quant::default()],
+        progressive_quants: vec![ProgressiveQuant {
+            quality: 0,
+            y_quant: Quant::default(),
+            cb_quant: Quant::default(),
+            cr_quant: Quant::default(),
+        }],
+        tiles: vec![Tile::First(TileFirst {
+            y_quant_index: 0,
+            cb_quant_index: 0,
+            cr_quant_index: 0,
+            x: 0,
+            y: 0,
+            flags: TileFlags::empty(),
+            quality: 0,
+            y_data: &[0x01, 0x02],
+            cb_data: &[0x03],
+            cr_data: &[0x04],
+            tail_data: &[],
+        })],
+    })
+}
+
+encode_decode_test! {
+    sync: Block::Sync, SYNC_BUFFER;
+    frame_begin: Block::FrameBegin(FrameBeginPdu {
+        frame_index: 5,
+        region_count: 1,
+    }), FRAME_BEGIN_BUFFER;
+    frame_end: Block::FrameEnd, FRAME_END_BUFFER;
+    context: Block::Context(ContextPdu {
+        flags: ContextFlags::SUBBAND_DIFFING,
+    }), CONTEXT_BUFFER;
+    region: region(), REGION_BUFFER;
+    tile_simple: Tile::Simple(TileSimple {
+        y_quant_index: 0,
+        cb_quant_index: 1,
+        cr_quant_index: 2,
+        x: 3,
+        y: 4,
+        flags: TileFlags::DIFFERENCE,
+        y_data: &[0x11],
+        cb_data: &[],
+        cr_data: &[],
+        tail_data: &[0x22],
+    }), TILE_SIMPLE_BUFFER;
+    tile_upgrade: Tile::Upgrade(TileUpgrade {
+        y_quant_index: 0,
+        cb_quant_index: 0,
+        cr_quant_index: 0,
+        x: 1,
+        y: 2,
+        quality: 1,
+        y_srl_data: &[0xaa],
+        y_raw_data: &[0xbb],
+        cb_srl_data: &[],
+        cb_raw_data: &[],
+        cr_srl_data: &[],
+        cr_raw_data: &[],
+    }), TILE_UPGRADE_BUFFER;
+}
+
+#[test]
+fn decoding_sync_with_invalid_magic_fails() {
+    let mut buffer = SYNC_BUFFER;
+    buffer[6] = 0x00;
+
+    assert!(decode::<Block<'_>>(&buffer).is_err());
+}
+
+#[test]
+fn decoding_tile_outside_of_region_fails() {
+    assert!(decode::<Block<'_>>(&TILE_SIMPLE_BUFFER).is_err());
+}
+
+#[test]
+fn decoding_region_with_invalid_quant_index_fails() {
+    let mut buffer = REGION_BUFFER;
+    buffer[55] = 0x01; // tiles::quantIdxCr
+
+    assert!(decode::<Block<'_>>(&buffer).is_err());
+}
@@ -1,4 +1,8 @@
 use ironrdp_core::{decode, encode_vec, Encode};
+use ironrdp_pdu::rdp::heartbeat::HeartbeatPdu;
+use ironrdp_pdu::rdp::server_redirection::{
+    EnhancedSecurityServerRedirection, ServerRedirectionFlags, ServerRedirectionPdu,
+};
 use ironrdp_testsuite_core::capsets::*;
 use ironrdp_testsuite_core::client_info::*;
 use ironrdp_testsuite_core::rdp::*;
@@ -447,3 +451,104 @@ fn buffer_length_is_correct_for_client_demand_active() {
 
     assert_eq!(expected_buffer_len, len);
 }
+
+const HEARTBEAT_BUFFER: [u8; 8] = [
+    0x00, 0x40, 0x00, 0x00, // security header, SEC_HEARTBEAT
+    0x00, // reserved
+    0x05, // period
+    0x03, // count1
+    0x06, // count2
+];
+
+const HEARTBEAT: HeartbeatPdu = HeartbeatPdu {
+    period: 5,
+    count1: 3,
+    count2: 6,
+};
+
+#[test]
+fn from_buffer_correctly_parses_heartbeat() {
+    assert_eq!(HEARTBEAT, decode(HEARTBEAT_BUFFER.as_slice()).unwrap());
+}
+
+#[test]
+fn to_buffer_correctly_serializes_heartbeat() {
+    assert_eq!(HEARTBEAT_BUFFER.as_slice(), encode_vec(&HEARTBEAT).unwrap().as_slice());
+}
+
+#[test]
+fn heartbeat_without_heartbeat_flag_is_rejected() {
+    let mut buffer = HEARTBEAT_BUFFER;
+    buffer[1] = 0x00;
+
+    assert!(decode::<HeartbeatPdu>(buffer.as_slice()).is_err());
+}
+
+const ENHANCED_SECURITY_SERVER_REDIRECTION_BUFFER: [u8; 45] = [
+    0x2d, 0x00, // totalLength
+    0x1a, 0x00, // pduType, PDUTYPE_SERVER_REDIR_PKT
+    0xeb, 0x03, // pduSource
+    0x00, 0x00, // pad2Octets
+    0x00, 0x04, // flags, SEC_REDIRECTION_PKT
+    0x24, 0x00, // length
+    0x03, 0x00, 0x00, 0x00, // sessionId
+    0x27, 0x00, 0x00, 0x00, // redirFlags
+    0x06, 0x00, 0x00, 0x00, 0x61, 0x00, 0x62, 0x00, 0x00, 0x00, // TargetNetAddress
+    0x02, 0x00, 0x00, 0x00, 0x4c, 0x42, // LoadBalanceInfo
+    0x04, 0x00, 0x00, 0x00, 0x75, 0x00, 0x00, 0x00, // UserName
+    0x00, // pad1Octet
+];
+
+fn enhanced_security_server_redirection() -> EnhancedSecurityServerRedirection {
+    EnhancedSecurityServerRedirection {
+        pdu_source: 0x03eb,
+        redirection: ServerRedirectionPdu {
+            session_id: 3,
+            flags: ServerRedirectionFlags::DONT_STORE_USERNAME,
+            target_net_address: Some("ab".to_owned()),
+            load_balance_info: Some(b"LB".to_vec()),
+            username: Some("u".to_owned()),
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn from_buffer_correctly_parses_enhanced_security_server_redirection() {
+    assert_eq!(
+        enhanced_security_server_redirection(),
+        decode(ENHANCED_SECURITY_SERVER_REDIRECTION_BUFFER.as_slice()).unwrap()
+    );
+}
+
+#[test]
+fn to_buffer_correctly_serializes_enhanced_security_server_redirection() {
+    assert_eq!(
+        ENHANCED_SECURITY_SERVER_REDIRECTION_BUFFER.as_slice(),
+        encode_vec(&enhanced_security_server_redirection()).unwrap().as_slice()
+    );
+}
+
+#[test]
+fn server_redirection_with_all_fields_round_trips() {
+    let redirection = ServerRedirectionPdu {
+        session_id: 7,
+        flags: ServerRedirectionFlags::NO_REDIRECT | ServerRedirectionFlags::SERVER_TSV_CAPABLE,
+        target_net_address: Some("192.168.1.2".to_owned()),
+        load_balance_info: Some(b"Cookie: msts=1234\r\n".to_vec()),
+        username: Some("user".to_owned()),
+        domain: Some("DOMAIN".to_owned()),
+        password: Some(vec![1, 2, 3, 4]),
+        target_fqdn: Some("host.example.com".to_owned()),
+        target_netbios_name: Some("HOST".to_owned()),
+        tsv_url: Some(vec![5, 6]),
+        redirection_guid: Some(vec![7; 16]),
+        target_certificate: Some(vec![8; 32]),
+        target_net_addresses: Some(vec!["192.168.1.2".to_owned(), "fe80::1".to_owned()]),
+    };
+
+    let buffer = encode_vec(&redirection).unwrap();
+
+    assert_eq!(redirection.size(), buffer.len());
+    assert_eq!(redirection, decode(buffer.as_slice()).unwrap());
+}
@@ -0,0 +1,175 @@
+use std::borrow::Cow;
+
+use ironrdp_core::{decode, encode_vec};
+use ironrdp_pdu::gcc::EncryptionMethod;
+use ironrdp_pdu::mcs::{McsMessage, SendDataIndication, SendDataRequest};
+use ironrdp_pdu::rdp::standard_security::{
+    SecurityExchangePdu, ServerSecurityKey, StandardSecurity, CLIENT_RANDOM_LEN, SERVER_RANDOM_LEN,
+};
+use ironrdp_pdu::x224::X224;
+
+const MODULUS: &str = "94bbb105c1cf649fcd19e40da4155556cff120e091f5a4553c43ca855405b55eaa1917e164d7c355cb4c2bf154d5aa0d996466940649342a5a5bc0450f0b6795";
+const PRIVATE_EXPONENT: &str = "9277df9576ce211ec9a15497265e27e34cf5fdd58987a924932b8356176f63b26bedc3d0b6aec468cc86e4a2d33b0621b79687e05deaad08d0ef2d9cb92d78a1";
+
+const CLIENT_RANDOM: [u8; CLIENT_RANDOM_LEN] = [0x11; CLIENT_RANDOM_LEN];
+const SERVER_RANDOM: [u8; SERVER_RANDOM_LEN] = [0x22; SERVER_RANDOM_LEN];
+
+fn server_key() -> ServerSecurityKey {
+    ServerSecurityKey::new(
+        &hex::decode(MODULUS).unwrap(),
+        0x10001,
+        &hex::decode(PRIVATE_EXPONENT).unwrap(),
+    )
+}
+
+fn session_keys(method: EncryptionMethod) -> (StandardSecurity, StandardSecurity) {
+    (
+        StandardSecurity::client(method, &CLIENT_RANDOM, &SERVER_RANDOM).unwrap(),
+        StandardSecurity::server(method, &CLIENT_RANDOM, &SERVER_RANDOM).unwrap(),
+    )
+}
+
+fn send_data_indication(user_data: &[u8]) -> Vec<u8> {
+    encode_vec(&X224(McsMessage::SendDataIndication(SendDataIndication {
+        initiator_id: 1002,
+        channel_id: 1003,
+        user_data: Cow::Borrowed(user_data),
+    })))
+    .unwrap()
+}
+
+fn send_data_request(user_data: &[u8]) -> Vec<u8> {
+    encode_vec(&X224(McsMessage::SendDataRequest(SendDataRequest {
+        initiator_id: 1002,
+        channel_id: 1003,
+        user_data: Cow::Borrowed(user_data),
+    })))
+    .unwrap()
+}
+
+fn fast_path(data: &[u8]) -> Vec<u8> {
+    let length = 1 + 2 + data.len();
+    let [high, low] = u16::try_from(length).unwrap().to_be_bytes();
+    let mut frame = vec![0x00, 0x80 | high, low];
+    frame.extend_from_slice(data);
+    frame
+}
+
+#[test]
+fn security_exchange_pdu_round_trip() {
+    let pdu = SecurityExchangePdu {
+        encrypted_client_random: vec![0xAB; 72],
+    };
+
+    let encoded = encode_vec(&pdu).unwrap();
+    assert_eq!(&encoded[..8], &[0x01, 0x00, 0x00, 0x00, 0x48, 0x00, 0x00, 0x00]);
+    assert_eq!(pdu, decode(&encoded).unwrap());
+}
+
+#[test]
+fn client_random_is_exchanged_with_the_server_key() {
+    let key = server_key();
+
+    let pdu = SecurityExchangePdu::new(&CLIENT_RANDOM, &key.public_key());
+    assert_eq!(pdu.encrypted_client_random.len(), 64 + 8);
+
+    assert_eq!(
+        key.decrypt_client_random(&pdu.encrypted_client_random).unwrap(),
+        CLIENT_RANDOM
+    );
+}
+
+#[test]
+fn server_certificate_is_proprietary() {
+    let certificate = encode_vec(&server_key().certificate()).unwrap();
+
+    // dwVersion, dwSigAlgId, dwKeyAlgId, then the public key blob.
+    assert_eq!(
+        &certificate[..16],
+        &[0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x00, 0x5C, 0x00]
+    );
+}
+
+#[test]
+fn strongest_encryption_method_is_negotiated() {
+    assert_eq!(
+        StandardSecurity::negotiate(EncryptionMethod::BIT_40 | EncryptionMethod::BIT_128),
+        Some(EncryptionMethod::BIT_128)
+    );
+    assert_eq!(
+        StandardSecurity::negotiate(EncryptionMethod::BIT_40 | EncryptionMethod::BIT_56),
+        Some(EncryptionMethod::BIT_56)
+    );
+    assert_eq!(StandardSecurity::negotiate(EncryptionMethod::FIPS), None);
+}
+
+#[test]
+fn fips_is_not_supported() {
+    assert!(StandardSecurity::server(EncryptionMethod::FIPS, &CLIENT_RANDOM, &SERVER_RANDOM).is_none());
+}
+
+#[test]
+fn slow_path_frames_round_trip() {
+    for method in [
+        EncryptionMethod::BIT_40,
+        EncryptionMethod::BIT_56,
+        EncryptionMethod::BIT_128,
+    ] {
+        let (mut client, mut server) = session_keys(method);
+
+        let request = send_data_request(b"client to server");
+        let encrypted = client.encrypt_frame(&request).unwrap();
+        assert_ne!(encrypted, request);
+        assert_eq!(server.decrypt_frame(&encrypted).unwrap(), request);
+
+        let indication = send_data_indication(b"server to client");
+        let encrypted = server.encrypt_frame(&indication).unwrap();
+        assert_eq!(client.decrypt_frame(&encrypted).unwrap(), indication);
+    }
+}
+
+#[test]
+fn fast_path_frames_round_trip() {
+    let (mut client, mut server) = session_keys(EncryptionMethod::BIT_128);
+
+    for data in [&[0x01, 0x02, 0x03][..], &[0x42; 200][..]] {
+        let frame = fast_path(data);
+        let encrypted = server.encrypt_frame(&frame).unwrap();
+        assert_eq!(encrypted[0], 0x80);
+
+        let decrypted = client.decrypt_frame(&encrypted).unwrap();
+        assert_eq!(&decrypted[decrypted.len() - data.len()..], data);
+        assert_eq!(decrypted[0], 0x00);
+    }
+}
+
+#[test]
+fn keys_are_updated_after_4096_frames() {
+    let (mut client, mut server) = session_keys(EncryptionMethod::BIT_128);
+
+    let frame = send_data_request(&[0x55; 32]);
+    for _ in 0..5000 {
+        let encrypted = client.encrypt_frame(&frame).unwrap();
+        assert_eq!(server.decrypt_frame(&encrypted).unwrap(), frame);
+    }
+}
+
+#[test]
+fn tampered_frame_is_rejected() {
+    let (mut client, mut server) = session_keys(EncryptionMethod::BIT_128);
+
+    let mut encrypted = client.encrypt_frame(&send_data_request(b"client to server")).unwrap();
+    let last = encrypted.len() - 1;
+    encrypted[last] ^= 0xFF;
+
+    server.decrypt_frame(&encrypted).unwrap_err();
+}
+
+#[test]
+fn unencrypted_licensing_header_is_kept() {
+    let (_, mut server) = session_keys(EncryptionMethod::BIT_128);
+
+    // SEC_LICENSE_PKT, without SEC_ENCRYPT.
+    let frame = send_data_request(&[0x80, 0x00, 0x00, 0x00, 0xFF, 0x03]);
+    assert_eq!(server.decrypt_frame(&frame).unwrap(), frame);
+}
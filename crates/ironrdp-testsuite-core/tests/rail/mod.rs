@@ -0,0 +1,508 @@
+use std::sync::{Arc, Mutex};
+
+use ironrdp_core::{decode, encode_vec};
+use ironrdp_pdu::geometry::ExclusiveRectangle;
+use ironrdp_rail::client::{RailClient, RailClientHandler};
+use ironrdp_rail::pdu;
+use ironrdp_rail::pdu::window;
+use ironrdp_rail::server::{RailServer, RailServerHandler};
+use ironrdp_svc::{StaticVirtualChannel, SvcMessage, SvcProcessor as _};
+use ironrdp_testsuite_core::encode_decode_test;
+
+encode_decode_test! {
+    handshake: pdu::RailPdu::Handshake(pdu::HandshakePdu { build_number: 7601 }),
+    [
+        // Header
+        0x05, 0x00, 0x08, 0x00,
+        // Payload
+        0xB1, 0x1D, 0x00, 0x00,
+    ];
+
+    handshake_ex: pdu::RailPdu::HandshakeEx(pdu::HandshakeExPdu {
+        build_number: 7601,
+        flags: pdu::HandshakeExFlags::HIDEF | pdu::HandshakeExFlags::EXTENDED_SPI_SUPPORTED,
+    }),
+    [
+        // Header
+        0x13, 0x00, 0x0C, 0x00,
+        // Payload
+        0xB1, 0x1D, 0x00, 0x00,
+        0x03, 0x00, 0x00, 0x00,
+    ];
+
+    client_status: pdu::RailPdu::ClientStatus(pdu::ClientStatusPdu {
+        flags: pdu::ClientStatusFlags::ALLOWLOCALMOVESIZE | pdu::ClientStatusFlags::AUTORECONNECT,
+    }),
+    [
+        // Header
+        0x0B, 0x00, 0x08, 0x00,
+        // Payload
+        0x03, 0x00, 0x00, 0x00,
+    ];
+
+    language_bar_info: pdu::RailPdu::LanguageBarInfo(pdu::LanguageBarInfoPdu {
+        status: pdu::LanguageBarStatus::HIDDEN,
+    }),
+    [
+        // Header
+        0x0D, 0x00, 0x08, 0x00,
+        // Payload
+        0x08, 0x00, 0x00, 0x00,
+    ];
+
+    language_ime_info: pdu::RailPdu::LanguageImeInfo(pdu::LanguageImeInfoPdu {
+        profile_type: pdu::ProfileType::KeyboardLayout,
+        language_id: 0x0411,
+        language_profile_clsid: [0; 16],
+        profile_guid: [0; 16],
+        keyboard_layout: 0xE001_0411,
+    }),
+    [
+        // Header
+        0x11, 0x00, 0x2E, 0x00,
+        // Payload
+        0x02, 0x00, 0x00, 0x00,
+        0x11, 0x04,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x11, 0x04, 0x01, 0xE0,
+    ];
+
+    compartment_info: pdu::RailPdu::CompartmentInfo(pdu::CompartmentInfoPdu {
+        ime_state: pdu::ImeState::Open,
+        ime_conversion_mode: pdu::ImeConversionMode::NATIVE | pdu::ImeConversionMode::FULLSHAPE,
+        ime_sentence_mode: pdu::ImeSentenceMode::PHRASEPREDICT,
+        kana_mode: pdu::KanaMode::Off,
+    }),
+    [
+        // Header
+        0x12, 0x00, 0x14, 0x00,
+        // Payload
+        0x01, 0x00, 0x00, 0x00,
+        0x09, 0x00, 0x00, 0x00,
+        0x08, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+    ];
+
+    exec: pdu::RailPdu::Exec(pdu::ExecPdu {
+        flags: pdu::ExecFlags::EXPAND_ARGUMENTS,
+        exe_or_file: "||a".to_owned(),
+        working_dir: String::new(),
+        arguments: "x".to_owned(),
+    }),
+    [
+        // Header
+        0x01, 0x00, 0x14, 0x00,
+        // Payload
+        0x08, 0x00,
+        0x06, 0x00,
+        0x00, 0x00,
+        0x02, 0x00,
+        0x7C, 0x00, 0x7C, 0x00, 0x61, 0x00,
+        0x78, 0x00,
+    ];
+
+    exec_result: pdu::RailPdu::ExecResult(pdu::ExecResultPdu {
+        flags: pdu::ExecFlags::empty(),
+        exec_result: pdu::ExecResult::FileNotFound,
+        raw_result: 2,
+        exe_or_file: "||a".to_owned(),
+    }),
+    [
+        // Header
+        0x80, 0x00, 0x16, 0x00,
+        // Payload
+        0x00, 0x00,
+        0x05, 0x00,
+        0x02, 0x00, 0x00, 0x00,
+        0x00, 0x00,
+        0x06, 0x00,
+        0x7C, 0x00, 0x7C, 0x00, 0x61, 0x00,
+    ];
+
+    activate: pdu::RailPdu::Activate(pdu::ActivatePdu {
+        window_id: 0x0201,
+        enabled: true,
+    }),
+    [
+        // Header
+        0x02, 0x00, 0x09, 0x00,
+        // Payload
+        0x01, 0x02, 0x00, 0x00,
+        0x01,
+    ];
+
+    sys_command: pdu::RailPdu::SysCommand(pdu::SysCommandPdu {
+        window_id: 0x0201,
+        command: pdu::SystemCommand::Close,
+    }),
+    [
+        // Header
+        0x04, 0x00, 0x0A, 0x00,
+        // Payload
+        0x01, 0x02, 0x00, 0x00,
+        0x60, 0xF0,
+    ];
+
+    sys_menu: pdu::RailPdu::SysMenu(pdu::SysMenuPdu {
+        window_id: 0x0201,
+        left: 100,
+        top: 200,
+    }),
+    [
+        // Header
+        0x0C, 0x00, 0x0C, 0x00,
+        // Payload
+        0x01, 0x02, 0x00, 0x00,
+        0x64, 0x00,
+        0xC8, 0x00,
+    ];
+
+    notify_event: pdu::RailPdu::NotifyEvent(pdu::NotifyEventPdu {
+        window_id: 0x0201,
+        notify_icon_id: 1,
+        message: pdu::NotifyIconMessage::RightButtonUp,
+    }),
+    [
+        // Header
+        0x06, 0x00, 0x10, 0x00,
+        // Payload
+        0x01, 0x02, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00,
+        0x05, 0x02, 0x00, 0x00,
+    ];
+
+    window_move: pdu::RailPdu::WindowMove(pdu::WindowMovePdu {
+        window_id: 0x0201,
+        left: -10,
+        top: 20,
+        right: 300,
+        bottom: 400,
+    }),
+    [
+        // Header
+        0x08, 0x00, 0x10, 0x00,
+        // Payload
+        0x01, 0x02, 0x00, 0x00,
+        0xF6, 0xFF,
+        0x14, 0x00,
+        0x2C, 0x01,
+        0x90, 0x01,
+    ];
+
+    local_move_size: pdu::RailPdu::LocalMoveSize(pdu::LocalMoveSizePdu {
+        window_id: 0x0201,
+        is_move_size_start: true,
+        move_size_type: pdu::MoveSizeType::Move,
+        pos_x: 5,
+        pos_y: 6,
+    }),
+    [
+        // Header
+        0x09, 0x00, 0x10, 0x00,
+        // Payload
+        0x01, 0x02, 0x00, 0x00,
+        0x01, 0x00,
+        0x09, 0x00,
+        0x05, 0x00,
+        0x06, 0x00,
+    ];
+
+    min_max_info: pdu::RailPdu::MinMaxInfo(pdu::MinMaxInfoPdu {
+        window_id: 0x0201,
+        max_width: 1920,
+        max_height: 1080,
+        max_pos_x: -8,
+        max_pos_y: -8,
+        min_track_width: 136,
+        min_track_height: 39,
+        max_track_width: 1940,
+        max_track_height: 1100,
+    }),
+    [
+        // Header
+        0x0A, 0x00, 0x18, 0x00,
+        // Payload
+        0x01, 0x02, 0x00, 0x00,
+        0x80, 0x07, 0x38, 0x04,
+        0xF8, 0xFF, 0xF8, 0xFF,
+        0x88, 0x00, 0x27, 0x00,
+        0x94, 0x07, 0x4C, 0x04,
+    ];
+
+    zorder_sync: pdu::RailPdu::ZOrderSync(pdu::ZOrderSyncPdu { window_id_marker: 0x0201 }),
+    [
+        // Header
+        0x14, 0x00, 0x08, 0x00,
+        // Payload
+        0x01, 0x02, 0x00, 0x00,
+    ];
+
+    cloak: pdu::RailPdu::Cloak(pdu::CloakPdu {
+        window_id: 0x0201,
+        cloaked: true,
+    }),
+    [
+        // Header
+        0x15, 0x00, 0x09, 0x00,
+        // Payload
+        0x01, 0x02, 0x00, 0x00,
+        0x01,
+    ];
+
+    window_info: window::WindowOrder::Info {
+        window_id: 0x20,
+        new: true,
+        info: window::WindowInfo {
+            show_state: Some(window::ShowState::Show),
+            title: Some("a".to_owned()),
+            window_offset: Some((-10, 20)),
+            window_size: Some((100, 50)),
+            window_rects: Some(vec![ExclusiveRectangle {
+                left: 0,
+                top: 0,
+                right: 100,
+                bottom: 50,
+            }]),
+            ..Default::default()
+        },
+    },
+    [
+        // Header
+        0x2E, 0x2A, 0x00,
+        0x14, 0x0D, 0x00, 0x11,
+        0x20, 0x00, 0x00, 0x00,
+        // ShowState
+        0x05,
+        // TitleInfo
+        0x02, 0x00, 0x61, 0x00,
+        // WindowOffsetX, WindowOffsetY
+        0xF6, 0xFF, 0xFF, 0xFF, 0x14, 0x00, 0x00, 0x00,
+        // WindowWidth, WindowHeight
+        0x64, 0x00, 0x00, 0x00, 0x32, 0x00, 0x00, 0x00,
+        // NumWindowRects, WindowRects
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x64, 0x00, 0x32, 0x00,
+    ];
+
+    window_deleted: window::WindowOrder::Deleted { window_id: 0x20 },
+    [
+        0x2E, 0x0B, 0x00,
+        0x00, 0x00, 0x00, 0x21,
+        0x20, 0x00, 0x00, 0x00,
+    ];
+
+    notify_icon: window::WindowOrder::NotifyIcon {
+        window_id: 0x20,
+        notify_icon_id: 1,
+        new: true,
+        info: window::NotifyIconInfo {
+            version: Some(4),
+            tooltip: Some("a".to_owned()),
+            state: Some(0),
+            ..Default::default()
+        },
+    },
+    [
+        // Header
+        0x2E, 0x1B, 0x00,
+        0x0D, 0x00, 0x00, 0x12,
+        0x20, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00,
+        // Version
+        0x04, 0x00, 0x00, 0x00,
+        // ToolTip
+        0x02, 0x00, 0x61, 0x00,
+        // State
+        0x00, 0x00, 0x00, 0x00,
+    ];
+
+    notify_icon_deleted: window::WindowOrder::NotifyIconDeleted {
+        window_id: 0x20,
+        notify_icon_id: 1,
+    },
+    [
+        0x2E, 0x0F, 0x00,
+        0x00, 0x00, 0x00, 0x22,
+        0x20, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00,
+    ];
+}
+
+#[derive(Debug, Default)]
+struct TestHandler {
+    compartment_info: Arc<Mutex<Vec<pdu::CompartmentInfoPdu>>>,
+}
+
+impl RailClientHandler for TestHandler {
+    fn compartment_info(&mut self, pdu: pdu::CompartmentInfoPdu) {
+        self.compartment_info.lock().unwrap().push(pdu);
+    }
+}
+
+fn compartment_info() -> pdu::CompartmentInfoPdu {
+    pdu::CompartmentInfoPdu {
+        ime_state: pdu::ImeState::Open,
+        ime_conversion_mode: pdu::ImeConversionMode::NATIVE,
+        ime_sentence_mode: pdu::ImeSentenceMode::empty(),
+        kana_mode: pdu::KanaMode::Off,
+    }
+}
+
+#[test]
+fn client_handshake() {
+    let mut client = RailClient::new(Box::new(TestHandler::default()));
+    assert!(!client.is_ready());
+    assert!(client.compartment_info(compartment_info()).is_err());
+
+    let server_handshake = encode_vec(&pdu::RailPdu::Handshake(pdu::HandshakePdu { build_number: 9200 })).unwrap();
+
+    // Client Handshake PDU, followed by the Client Information PDU.
+    assert_eq!(client.process(&server_handshake).unwrap().len(), 2);
+    assert!(client.is_ready());
+    assert!(client.compartment_info(compartment_info()).is_ok());
+}
+
+#[test]
+fn client_exec_after_handshake() {
+    let exec = pdu::ExecPdu {
+        flags: pdu::ExecFlags::empty(),
+        exe_or_file: "||notepad".to_owned(),
+        working_dir: String::new(),
+        arguments: String::new(),
+    };
+    let mut client = RailClient::new(Box::new(TestHandler::default())).with_exec(exec.clone());
+
+    let server_handshake = encode_vec(&pdu::RailPdu::Handshake(pdu::HandshakePdu { build_number: 9200 })).unwrap();
+    let msgs = encode_messages(client.process(&server_handshake).unwrap());
+
+    // Client Handshake PDU, Client Information PDU, then Client Execute PDU.
+    assert_eq!(msgs.len(), 3);
+    assert_eq!(decode::<pdu::RailPdu>(&msgs[2]).unwrap(), pdu::RailPdu::Exec(exec));
+}
+
+#[test]
+fn client_compartment_info() {
+    let handler = TestHandler::default();
+    let received = Arc::clone(&handler.compartment_info);
+    let mut client = RailClient::new(Box::new(handler));
+
+    let server_handshake = encode_vec(&pdu::RailPdu::Handshake(pdu::HandshakePdu { build_number: 9200 })).unwrap();
+    client.process(&server_handshake).unwrap();
+
+    let pdu = encode_vec(&pdu::RailPdu::CompartmentInfo(compartment_info())).unwrap();
+    assert!(client.process(&pdu).unwrap().is_empty());
+
+    assert_eq!(received.lock().unwrap().as_slice(), &[compartment_info()]);
+}
+
+#[test]
+fn client_ignores_unsupported_orders() {
+    let mut client = RailClient::new(Box::new(TestHandler::default()));
+
+    // TS_RAIL_ORDER_SYSPARAM, not supported yet.
+    let sysparam = [0x03, 0x00, 0x09, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00];
+
+    assert!(client.process(&sysparam).unwrap().is_empty());
+    assert!(!client.is_ready());
+}
+
+#[derive(Debug, Default)]
+struct TestServerHandler {
+    ready: Arc<Mutex<Option<pdu::ClientStatusFlags>>>,
+    exec: Arc<Mutex<Vec<pdu::ExecPdu>>>,
+}
+
+impl RailServerHandler for TestServerHandler {
+    fn ready(&mut self, client_status: pdu::ClientStatusFlags) {
+        *self.ready.lock().unwrap() = Some(client_status);
+    }
+
+    fn exec(&mut self, pdu: &pdu::ExecPdu) -> pdu::ExecResult {
+        self.exec.lock().unwrap().push(pdu.clone());
+        pdu::ExecResult::Ok
+    }
+}
+
+/// Returns the encoded messages, without the channel PDU header.
+fn encode_messages(messages: Vec<SvcMessage>) -> Vec<Vec<u8>> {
+    StaticVirtualChannel::chunkify(messages)
+        .unwrap()
+        .into_iter()
+        .map(|buf| buf.filled()[8..].to_vec())
+        .collect()
+}
+
+fn connected_server(handler: TestServerHandler) -> RailServer {
+    let mut server = RailServer::new(Box::new(handler));
+
+    let handshake = encode_messages(server.start().unwrap());
+    assert_eq!(
+        decode::<pdu::RailPdu>(&handshake[0]).unwrap(),
+        pdu::RailPdu::Handshake(pdu::HandshakePdu { build_number: 7601 })
+    );
+
+    let client_handshake = encode_vec(&pdu::RailPdu::Handshake(pdu::HandshakePdu { build_number: 7601 })).unwrap();
+    assert!(server.process(&client_handshake).unwrap().is_empty());
+    assert!(server.is_ready());
+
+    let client_status = encode_vec(&pdu::RailPdu::ClientStatus(pdu::ClientStatusPdu {
+        flags: pdu::ClientStatusFlags::ALLOWLOCALMOVESIZE,
+    }))
+    .unwrap();
+    assert!(server.process(&client_status).unwrap().is_empty());
+
+    server
+}
+
+#[test]
+fn server_handshake() {
+    let handler = TestServerHandler::default();
+    let ready = Arc::clone(&handler.ready);
+    connected_server(handler);
+
+    assert_eq!(*ready.lock().unwrap(), Some(pdu::ClientStatusFlags::ALLOWLOCALMOVESIZE));
+}
+
+#[test]
+fn server_exec() {
+    let handler = TestServerHandler::default();
+    let received = Arc::clone(&handler.exec);
+    let mut server = connected_server(handler);
+
+    let exec = pdu::ExecPdu {
+        flags: pdu::ExecFlags::empty(),
+        exe_or_file: "||notepad".to_owned(),
+        working_dir: String::new(),
+        arguments: String::new(),
+    };
+    let msgs = server
+        .process(&encode_vec(&pdu::RailPdu::Exec(exec.clone())).unwrap())
+        .unwrap();
+
+    assert_eq!(received.lock().unwrap().as_slice(), &[exec]);
+    assert_eq!(
+        decode::<pdu::RailPdu>(&encode_messages(msgs)[0]).unwrap(),
+        pdu::RailPdu::ExecResult(pdu::ExecResultPdu {
+            flags: pdu::ExecFlags::empty(),
+            exec_result: pdu::ExecResult::Ok,
+            raw_result: 0,
+            exe_or_file: "||notepad".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn server_ignores_exec_before_handshake() {
+    let handler = TestServerHandler::default();
+    let received = Arc::clone(&handler.exec);
+    let mut server = RailServer::new(Box::new(handler));
+
+    let exec = pdu::RailPdu::Exec(pdu::ExecPdu {
+        flags: pdu::ExecFlags::empty(),
+        exe_or_file: "||notepad".to_owned(),
+        working_dir: String::new(),
+        arguments: String::new(),
+    });
+    assert!(server.process(&encode_vec(&exec).unwrap()).unwrap().is_empty());
+    assert!(received.lock().unwrap().is_empty());
+}
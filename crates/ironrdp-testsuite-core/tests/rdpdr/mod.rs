@@ -0,0 +1,412 @@
+use std::sync::{Arc, Mutex};
+
+use ironrdp_core::{decode_cursor, encode_vec, ReadCursor};
+use ironrdp_rdpdr::pdu::efs::{
+    Capabilities, ClientDeviceListAnnounce, ClientDeviceListRemove, ClientDriveQueryDirectoryResponse,
+    ClientNameRequest, ClientNameRequestUnicodeFlag, CoreCapability, CreateDisposition, CreateOptions, DesiredAccess,
+    DeviceCloseRequest, DeviceCloseResponse, DeviceCreateRequest, DeviceCreateResponse, DeviceIoRequest,
+    DeviceIoResponse, DeviceReadResponse, DeviceType, DeviceWriteRequest, DeviceWriteResponse, Devices, FileAttributes,
+    FileDirectoryInformation, FileInformationClass, Information, MajorFunction, MinorFunction, NtStatus,
+    PrinterAnnounceFlags, PrinterDeviceData, ServerDriveIoRequest, SharedAccess, VersionAndIdPdu,
+};
+use ironrdp_rdpdr::pdu::{PacketId, RdpdrPdu, SharedHeader};
+use ironrdp_rdpdr::server::{DriveRequest, DriveResponse, RdpdrServer, RdpdrServerHandler};
+use ironrdp_rdpdr::{NoopRdpdrBackend, Rdpdr};
+use ironrdp_svc::{StaticVirtualChannel, SvcMessage, SvcProcessor};
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Announced(u32, String),
+    Removed(u32),
+    Response(u32, Result<DriveResponse, NtStatus>),
+}
+
+#[derive(Debug, Default)]
+struct TestHandler {
+    events: Arc<Mutex<Vec<Event>>>,
+}
+
+impl RdpdrServerHandler for TestHandler {
+    fn drive_announced(&mut self, device_id: u32, name: String) {
+        self.events.lock().unwrap().push(Event::Announced(device_id, name));
+    }
+
+    fn drive_removed(&mut self, device_id: u32) {
+        self.events.lock().unwrap().push(Event::Removed(device_id));
+    }
+
+    fn drive_response(&mut self, completion_id: u32, response: Result<DriveResponse, NtStatus>) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(Event::Response(completion_id, response));
+    }
+}
+
+const DRIVE_ID: u32 = 1;
+
+/// Returns the encoded messages, without the channel PDU header.
+fn encode_messages(messages: Vec<SvcMessage>) -> Vec<Vec<u8>> {
+    StaticVirtualChannel::chunkify(messages)
+        .unwrap()
+        .into_iter()
+        .map(|buf| buf.filled()[8..].to_vec())
+        .collect()
+}
+
+fn process(server: &mut RdpdrServer, pdu: RdpdrPdu) -> Vec<SvcMessage> {
+    server.process(&encode_vec(&pdu).unwrap()).unwrap()
+}
+
+fn io_response(completion_id: u32, io_status: NtStatus) -> DeviceIoResponse {
+    DeviceIoResponse {
+        device_id: DRIVE_ID,
+        completion_id,
+        io_status,
+    }
+}
+
+fn connected_server() -> (RdpdrServer, Arc<Mutex<Vec<Event>>>) {
+    let handler = TestHandler::default();
+    let events = Arc::clone(&handler.events);
+    let mut server = RdpdrServer::new(Box::new(handler));
+
+    // Server Announce Request.
+    assert_eq!(server.start().unwrap().len(), 1);
+
+    let reply = VersionAndIdPdu::new_client_announce_reply(VersionAndIdPdu::new_server_announce_request(1)).unwrap();
+    assert!(process(&mut server, RdpdrPdu::VersionAndIdPdu(reply)).is_empty());
+
+    // Server Core Capability Request, followed by the Server Client ID Confirm.
+    let name = ClientNameRequest::new("client".to_owned(), ClientNameRequestUnicodeFlag::Unicode);
+    assert_eq!(process(&mut server, RdpdrPdu::ClientNameRequest(name)).len(), 2);
+
+    // Server User Logged On.
+    let mut capabilities = Capabilities::new();
+    capabilities.add_drive();
+    let capabilities = CoreCapability::new_response(capabilities.clone_inner());
+    assert_eq!(process(&mut server, RdpdrPdu::CoreCapability(capabilities)).len(), 1);
+
+    // A Server Device Announce Response for each device.
+    let mut devices = Devices::new();
+    devices.add_drive(DRIVE_ID, "home".to_owned());
+    devices.add_smartcard(2);
+    let announce = ClientDeviceListAnnounce {
+        device_list: devices.clone_inner(),
+    };
+    assert_eq!(
+        process(&mut server, RdpdrPdu::ClientDeviceListAnnounce(announce)).len(),
+        2
+    );
+
+    (server, events)
+}
+
+#[test]
+fn server_announced_drives() {
+    let (server, events) = connected_server();
+
+    assert_eq!(server.drives(), &[DRIVE_ID]);
+    assert_eq!(
+        events.lock().unwrap().as_slice(),
+        &[Event::Announced(DRIVE_ID, "home".to_owned())]
+    );
+}
+
+#[test]
+fn server_drive_create() {
+    let (mut server, events) = connected_server();
+    events.lock().unwrap().clear();
+
+    let request = DriveRequest::Create {
+        path: "\\dir\\file.txt".to_owned(),
+        desired_access: DesiredAccess::GENERIC_READ,
+        create_disposition: CreateDisposition::FILE_OPEN,
+        create_options: CreateOptions::FILE_NON_DIRECTORY_FILE,
+    };
+    let messages = server.drive_request(DRIVE_ID, 5, request).unwrap();
+
+    let messages = encode_messages(messages.into());
+    assert_eq!(messages.len(), 1);
+    let mut src = ReadCursor::new(&messages[0]);
+    let RdpdrPdu::DeviceIoRequest(header) = decode_cursor::<RdpdrPdu>(&mut src).unwrap() else {
+        panic!("expected a device I/O request");
+    };
+    assert_eq!(
+        ServerDriveIoRequest::decode(header, &mut src).unwrap(),
+        ServerDriveIoRequest::ServerCreateDriveRequest(DeviceCreateRequest {
+            device_io_request: DeviceIoRequest {
+                device_id: DRIVE_ID,
+                file_id: 0,
+                completion_id: 5,
+                major_function: MajorFunction::Create,
+                minor_function: MinorFunction::from(0),
+            },
+            desired_access: DesiredAccess::GENERIC_READ,
+            allocation_size: 0,
+            file_attributes: FileAttributes::empty(),
+            shared_access: SharedAccess::FILE_SHARE_READ,
+            create_disposition: CreateDisposition::FILE_OPEN,
+            create_options: CreateOptions::FILE_NON_DIRECTORY_FILE,
+            path: "\\dir\\file.txt".to_owned(),
+        })
+    );
+
+    let response = DeviceCreateResponse {
+        device_io_reply: io_response(5, NtStatus::SUCCESS),
+        file_id: 7,
+        information: Information::FILE_OPENED,
+    };
+    assert!(process(&mut server, RdpdrPdu::DeviceCreateResponse(response)).is_empty());
+
+    assert_eq!(
+        events.lock().unwrap().as_slice(),
+        &[Event::Response(
+            5,
+            Ok(DriveResponse::Create {
+                file_id: 7,
+                information: Information::FILE_OPENED
+            })
+        )]
+    );
+}
+
+#[test]
+fn server_drive_read() {
+    let (mut server, events) = connected_server();
+    events.lock().unwrap().clear();
+
+    let request = DriveRequest::Read {
+        file_id: 7,
+        offset: 0,
+        length: 4,
+    };
+    server.drive_request(DRIVE_ID, 6, request).unwrap();
+
+    let response = DeviceReadResponse {
+        device_io_reply: io_response(6, NtStatus::SUCCESS),
+        read_data: vec![1, 2, 3, 4],
+    };
+    process(&mut server, RdpdrPdu::DeviceReadResponse(response));
+
+    assert_eq!(
+        events.lock().unwrap().as_slice(),
+        &[Event::Response(6, Ok(DriveResponse::Read(vec![1, 2, 3, 4])))]
+    );
+}
+
+#[test]
+fn server_drive_query_directory() {
+    let (mut server, events) = connected_server();
+    events.lock().unwrap().clear();
+
+    let entry = FileDirectoryInformation::new(
+        1,
+        2,
+        3,
+        4,
+        42,
+        FileAttributes::FILE_ATTRIBUTE_NORMAL,
+        "file.txt".to_owned(),
+    );
+
+    let request = DriveRequest::QueryDirectory {
+        file_id: 7,
+        pattern: Some("\\*".to_owned()),
+    };
+    server.drive_request(DRIVE_ID, 1, request).unwrap();
+    let response = ClientDriveQueryDirectoryResponse {
+        device_io_reply: io_response(1, NtStatus::SUCCESS),
+        buffer: Some(FileInformationClass::Directory(entry.clone())),
+    };
+    process(&mut server, RdpdrPdu::ClientDriveQueryDirectoryResponse(response));
+
+    let request = DriveRequest::QueryDirectory {
+        file_id: 7,
+        pattern: None,
+    };
+    server.drive_request(DRIVE_ID, 2, request).unwrap();
+    let response = ClientDriveQueryDirectoryResponse {
+        device_io_reply: io_response(2, NtStatus::NO_MORE_FILES),
+        buffer: None,
+    };
+    process(&mut server, RdpdrPdu::ClientDriveQueryDirectoryResponse(response));
+
+    assert_eq!(
+        events.lock().unwrap().as_slice(),
+        &[
+            Event::Response(1, Ok(DriveResponse::QueryDirectory(Some(entry)))),
+            Event::Response(2, Ok(DriveResponse::QueryDirectory(None))),
+        ]
+    );
+}
+
+#[test]
+fn server_drive_error() {
+    let (mut server, events) = connected_server();
+    events.lock().unwrap().clear();
+
+    let request = DriveRequest::Create {
+        path: "\\secret".to_owned(),
+        desired_access: DesiredAccess::GENERIC_READ,
+        create_disposition: CreateDisposition::FILE_OPEN,
+        create_options: CreateOptions::empty(),
+    };
+    server.drive_request(DRIVE_ID, 3, request).unwrap();
+
+    let response = DeviceCreateResponse {
+        device_io_reply: io_response(3, NtStatus::ACCESS_DENIED),
+        file_id: 0,
+        information: Information::empty(),
+    };
+    process(&mut server, RdpdrPdu::DeviceCreateResponse(response));
+
+    // A request to a drive that is not redirected fails immediately.
+    let messages = server.drive_request(42, 4, DriveRequest::Close { file_id: 7 }).unwrap();
+    assert!(Vec::<SvcMessage>::from(messages).is_empty());
+
+    assert_eq!(
+        events.lock().unwrap().as_slice(),
+        &[
+            Event::Response(3, Err(NtStatus::ACCESS_DENIED)),
+            Event::Response(4, Err(NtStatus::NO_SUCH_DEVICE)),
+        ]
+    );
+}
+
+#[test]
+fn server_drive_removed() {
+    let (mut server, events) = connected_server();
+    events.lock().unwrap().clear();
+
+    server
+        .drive_request(DRIVE_ID, 8, DriveRequest::Close { file_id: 7 })
+        .unwrap();
+
+    let remove = ClientDeviceListRemove {
+        device_ids: vec![DRIVE_ID],
+    };
+    assert!(process(&mut server, RdpdrPdu::ClientDeviceListRemove(remove)).is_empty());
+
+    assert!(server.drives().is_empty());
+    assert_eq!(
+        events.lock().unwrap().as_slice(),
+        &[
+            Event::Response(8, Err(NtStatus::NO_SUCH_DEVICE)),
+            Event::Removed(DRIVE_ID),
+        ]
+    );
+}
+
+const PRINTER_ID: u32 = 2;
+
+#[test]
+fn printer_announce_round_trip() {
+    let unicode = PrinterDeviceData::new("Office printer".to_owned(), "MS Publisher Imagesetter".to_owned());
+    let ascii = PrinterDeviceData {
+        flags: PrinterAnnounceFlags::ASCII | PrinterAnnounceFlags::DEFAULT_PRINTER,
+        code_page: 1252,
+        pnp_name: String::new(),
+        driver_name: "Generic / Text Only".to_owned(),
+        print_name: "Label printer".to_owned(),
+        cached_fields: vec![1, 2, 3],
+    };
+
+    let mut devices = Devices::new();
+    devices.add_printer(PRINTER_ID, &unicode).unwrap();
+    devices.add_printer(PRINTER_ID + 1, &ascii).unwrap();
+
+    let announce = ClientDeviceListAnnounce {
+        device_list: devices.clone_inner(),
+    };
+    let encoded = encode_vec(&RdpdrPdu::ClientDeviceListAnnounce(announce.clone())).unwrap();
+    let mut src = ReadCursor::new(&encoded);
+    SharedHeader::decode(&mut src).unwrap();
+    let decoded = ClientDeviceListAnnounce::decode(&mut src).unwrap();
+    assert_eq!(decoded, announce);
+
+    let [first, second] = decoded.device_list.as_slice() else {
+        panic!("expected two devices");
+    };
+    for (device, device_id, name, data) in [
+        (first, PRINTER_ID, "PRN1", &unicode),
+        (second, PRINTER_ID + 1, "PRN2", &ascii),
+    ] {
+        assert_eq!(device.device_type(), DeviceType::Print);
+        assert_eq!(device.device_id(), device_id);
+        assert_eq!(device.name(), name);
+        assert_eq!(
+            &PrinterDeviceData::decode(&mut ReadCursor::new(device.device_data())).unwrap(),
+            data
+        );
+    }
+}
+
+/// Returns the reply of a client with the default backend to a printer I/O request.
+fn unsupported_printer_reply(request: ServerDriveIoRequest) -> (DeviceIoResponse, Vec<u8>) {
+    let printer = PrinterDeviceData::new("Office printer".to_owned(), "MS Publisher Imagesetter".to_owned());
+    let mut client = Rdpdr::new(Box::new(NoopRdpdrBackend), "client".to_owned())
+        .with_printer(PRINTER_ID, printer)
+        .unwrap();
+
+    let messages = client
+        .process(&encode_vec(&RdpdrPdu::ServerDriveIoRequest(request)).unwrap())
+        .unwrap();
+    let messages = encode_messages(messages);
+    assert_eq!(messages.len(), 1);
+
+    let mut src = ReadCursor::new(&messages[0]);
+    let header = SharedHeader::decode(&mut src).unwrap();
+    assert!(matches!(header.packet_id, PacketId::CoreDeviceIoCompletion));
+    let reply = DeviceIoResponse::decode(&mut src).unwrap();
+    (reply, src.remaining().to_vec())
+}
+
+fn printer_io_request(completion_id: u32, major_function: MajorFunction) -> DeviceIoRequest {
+    DeviceIoRequest {
+        device_id: PRINTER_ID,
+        file_id: 1,
+        completion_id,
+        major_function,
+        minor_function: MinorFunction::from(0),
+    }
+}
+
+#[test]
+fn printer_unsupported_requests() {
+    let expected = |completion_id| DeviceIoResponse {
+        device_id: PRINTER_ID,
+        completion_id,
+        io_status: NtStatus::NOT_SUPPORTED,
+    };
+
+    let (reply, rest) =
+        unsupported_printer_reply(ServerDriveIoRequest::ServerCreateDriveRequest(DeviceCreateRequest {
+            device_io_request: printer_io_request(1, MajorFunction::Create),
+            desired_access: DesiredAccess::GENERIC_WRITE,
+            allocation_size: 0,
+            file_attributes: FileAttributes::empty(),
+            shared_access: SharedAccess::empty(),
+            create_disposition: CreateDisposition::FILE_OVERWRITE_IF,
+            create_options: CreateOptions::empty(),
+            path: String::new(),
+        }));
+    assert_eq!(reply, expected(1));
+    let response = DeviceCreateResponse::decode(reply, &mut ReadCursor::new(&rest)).unwrap();
+    assert_eq!(response.file_id, 0);
+
+    let (reply, rest) = unsupported_printer_reply(ServerDriveIoRequest::DeviceWriteRequest(DeviceWriteRequest {
+        device_io_request: printer_io_request(2, MajorFunction::Write),
+        offset: 0,
+        write_data: b"%!PS".to_vec(),
+    }));
+    assert_eq!(reply, expected(2));
+    let response = DeviceWriteResponse::decode(reply, &mut ReadCursor::new(&rest)).unwrap();
+    assert_eq!(response.length, 0);
+
+    let (reply, rest) = unsupported_printer_reply(ServerDriveIoRequest::DeviceCloseRequest(DeviceCloseRequest {
+        device_io_request: printer_io_request(3, MajorFunction::Close),
+    }));
+    assert_eq!(reply, expected(3));
+    DeviceCloseResponse::decode(reply, &mut ReadCursor::new(&rest)).unwrap();
+}
@@ -0,0 +1,106 @@
+use std::borrow::Cow;
+
+use ironrdp_core::encode_vec;
+use ironrdp_rdpsnd::client::{Rdpsnd, RdpsndClientHandler};
+use ironrdp_rdpsnd::codec::{AudioCodec as _, AudioCodecError, AudioCodecs, PcmCodec};
+use ironrdp_rdpsnd::pdu::{self, AudioFormat, WaveFormat};
+use ironrdp_svc::SvcProcessor as _;
+
+fn format(format: WaveFormat, n_samples_per_sec: u32) -> AudioFormat {
+    AudioFormat {
+        format,
+        n_channels: 2,
+        n_samples_per_sec,
+        n_avg_bytes_per_sec: n_samples_per_sec * 4,
+        n_block_align: 4,
+        bits_per_sample: 16,
+        data: None,
+    }
+}
+
+#[test]
+fn pcm_codec_passthrough() {
+    let codec = PcmCodec::default();
+    let pcm = format(WaveFormat::PCM, 22050);
+
+    assert!(codec.supports(&pcm));
+    assert_eq!(
+        codec.new_encoder(&pcm).unwrap().encode(&[1, 2, 3, 4]).unwrap(),
+        [1, 2, 3, 4]
+    );
+    assert_eq!(
+        codec.new_decoder(&pcm).unwrap().decode(&[1, 2, 3, 4]).unwrap(),
+        [1, 2, 3, 4]
+    );
+
+    assert!(matches!(
+        codec.new_decoder(&format(WaveFormat::AAC_MS, 44100)),
+        Err(AudioCodecError::UnsupportedFormat(WaveFormat::AAC_MS))
+    ));
+}
+
+#[test]
+fn negotiate_prefers_advertised_formats() {
+    let codecs = AudioCodecs::new().with_codec(PcmCodec::new(vec![
+        format(WaveFormat::PCM, 44100),
+        format(WaveFormat::PCM, 22050),
+    ]));
+
+    let offered = [
+        format(WaveFormat::AAC_MS, 44100),
+        format(WaveFormat::PCM, 22050),
+        format(WaveFormat::PCM, 44100),
+    ];
+    assert_eq!(codecs.negotiate(&offered), Some(2));
+
+    // Not advertised, but still supported by the PCM codec.
+    let offered = [format(WaveFormat::OPUS, 48000), format(WaveFormat::PCM, 8000)];
+    assert_eq!(codecs.negotiate(&offered), Some(1));
+
+    let offered = [format(WaveFormat::OPUS, 48000)];
+    assert_eq!(codecs.negotiate(&offered), None);
+}
+
+#[derive(Debug)]
+struct TestHandler {
+    formats: Vec<AudioFormat>,
+}
+
+impl RdpsndClientHandler for TestHandler {
+    fn get_formats(&self) -> &[AudioFormat] {
+        &self.formats
+    }
+
+    fn wave(&mut self, _format_no: usize, _ts: u32, _data: Cow<'_, [u8]>) {}
+
+    fn set_volume(&mut self, _volume: pdu::VolumePdu) {}
+
+    fn set_pitch(&mut self, _pitch: pdu::PitchPdu) {}
+
+    fn close(&mut self) {}
+}
+
+#[test]
+fn client_keeps_format_preference_order() {
+    let mut rdpsnd = Rdpsnd::new(Box::new(TestHandler {
+        formats: vec![
+            format(WaveFormat::OPUS, 48000),
+            format(WaveFormat::AAC_MS, 44100),
+            format(WaveFormat::PCM, 44100),
+        ],
+    }));
+
+    let server_formats = pdu::ServerAudioOutputPdu::AudioFormat(pdu::ServerAudioFormatPdu {
+        version: pdu::Version::V5,
+        formats: vec![
+            format(WaveFormat::PCM, 44100),
+            format(WaveFormat::PCM, 22050),
+            format(WaveFormat::OPUS, 48000),
+        ],
+    });
+    rdpsnd.process(&encode_vec(&server_formats).unwrap()).unwrap();
+
+    assert_eq!(rdpsnd.get_format(0).unwrap(), &format(WaveFormat::OPUS, 48000));
+    assert_eq!(rdpsnd.get_format(1).unwrap(), &format(WaveFormat::PCM, 44100));
+    assert!(rdpsnd.get_format(2).is_err());
+}
@@ -1,3 +1,5 @@
+mod codec;
+
 use std::borrow::Cow;
 
 use ironrdp_rdpsnd::pdu;
@@ -0,0 +1,149 @@
+use core::num::NonZeroU16;
+
+use ironrdp_pdu::geometry::ExclusiveRectangle;
+use ironrdp_server::bench::encoder::damage::{split, DamageTracker};
+use ironrdp_server::{BitmapUpdate, DesktopSize, PixelFormat};
+
+const DESKTOP: DesktopSize = DesktopSize {
+    width: 128,
+    height: 128,
+};
+
+fn bitmap(x: u16, y: u16, width: u16, height: u16, format: PixelFormat, data: Vec<u8>) -> BitmapUpdate {
+    BitmapUpdate {
+        x,
+        y,
+        width: NonZeroU16::new(width).unwrap(),
+        height: NonZeroU16::new(height).unwrap(),
+        format,
+        data: data.into(),
+        stride: usize::from(width) * 4,
+    }
+}
+
+fn filled(x: u16, y: u16, width: u16, height: u16, value: u8) -> BitmapUpdate {
+    let data = vec![value; usize::from(width) * usize::from(height) * 4];
+    bitmap(x, y, width, height, PixelFormat::BgrX32, data)
+}
+
+/// Desktop-sized bitmap, with the given pixel changed.
+fn desktop_with_pixel(x: u16, y: u16) -> BitmapUpdate {
+    let mut data = vec![0; usize::from(DESKTOP.width) * usize::from(DESKTOP.height) * 4];
+    data[(usize::from(y) * usize::from(DESKTOP.width) + usize::from(x)) * 4] = 0xff;
+    bitmap(0, 0, DESKTOP.width, DESKTOP.height, PixelFormat::BgrX32, data)
+}
+
+fn rect(left: u16, top: u16, right: u16, bottom: u16) -> ExclusiveRectangle {
+    ExclusiveRectangle {
+        left,
+        top,
+        right,
+        bottom,
+    }
+}
+
+#[test]
+fn first_update_entirely_damaged() {
+    let mut tracker = DamageTracker::new(DESKTOP);
+
+    // The four tiles are merged into a single region.
+    assert_eq!(tracker.damage(&filled(0, 0, 128, 128, 0)), [rect(0, 0, 128, 128)]);
+}
+
+#[test]
+fn unchanged_update_not_damaged() {
+    let mut tracker = DamageTracker::new(DESKTOP);
+    tracker.damage(&filled(0, 0, 128, 128, 0));
+
+    assert!(tracker.damage(&filled(0, 0, 128, 128, 0)).is_empty());
+}
+
+#[test]
+fn changed_tile_damaged() {
+    let mut tracker = DamageTracker::new(DESKTOP);
+    tracker.damage(&desktop_with_pixel(0, 0));
+
+    assert_eq!(tracker.damage(&desktop_with_pixel(100, 10)), [rect(0, 0, 128, 64)]);
+    assert_eq!(tracker.damage(&desktop_with_pixel(100, 100)), [rect(64, 0, 128, 128)]);
+}
+
+#[test]
+fn runs_merged_across_tile_rows() {
+    let mut tracker = DamageTracker::new(DesktopSize {
+        width: 192,
+        height: 192,
+    });
+    tracker.damage(&filled(0, 0, 192, 192, 0));
+
+    // Same columns in consecutive rows are merged, the other runs are kept apart.
+    let mut update = filled(0, 0, 192, 192, 0);
+    let mut data = update.data.to_vec();
+    for (x, y) in [(70, 10), (130, 70), (70, 130)] {
+        data[(y * 192 + x) * 4] = 0xff;
+    }
+    update.data = data.into();
+
+    assert_eq!(
+        tracker.damage(&update),
+        [rect(64, 0, 128, 64), rect(128, 64, 192, 128), rect(64, 128, 128, 192)]
+    );
+}
+
+#[test]
+fn partially_covered_tile_always_damaged() {
+    let mut tracker = DamageTracker::new(DESKTOP);
+
+    assert_eq!(tracker.damage(&filled(5, 5, 10, 10, 0)), [rect(5, 5, 15, 15)]);
+    assert_eq!(tracker.damage(&filled(5, 5, 10, 10, 0)), [rect(5, 5, 15, 15)]);
+}
+
+#[test]
+fn damage_clipped_to_desktop() {
+    let mut tracker = DamageTracker::new(DesktopSize {
+        width: 100,
+        height: 100,
+    });
+
+    assert_eq!(tracker.damage(&filled(64, 64, 64, 64, 0)), [rect(64, 64, 100, 100)]);
+    assert!(tracker.damage(&filled(64, 64, 64, 64, 0)).is_empty());
+    assert!(tracker.damage(&filled(100, 0, 10, 10, 0)).is_empty());
+}
+
+#[test]
+fn format_change_damages_everything() {
+    let mut tracker = DamageTracker::new(DESKTOP);
+    tracker.damage(&filled(0, 0, 128, 128, 0));
+
+    let update = bitmap(0, 0, 128, 128, PixelFormat::RgbX32, vec![0; 128 * 128 * 4]);
+    assert_eq!(tracker.damage(&update), [rect(0, 0, 128, 128)]);
+}
+
+#[test]
+fn reset_damages_everything() {
+    let mut tracker = DamageTracker::new(DESKTOP);
+    tracker.damage(&filled(0, 0, 128, 128, 0));
+    tracker.reset();
+
+    assert_eq!(tracker.damage(&filled(0, 0, 128, 128, 0)), [rect(0, 0, 128, 128)]);
+}
+
+#[test]
+fn split_covering_region_keeps_bitmap() {
+    let update = filled(10, 20, 30, 40, 0);
+
+    let bitmaps = split(update.clone(), &[rect(10, 20, 40, 60)]);
+    assert_eq!(bitmaps.len(), 1);
+    assert_eq!(bitmaps[0].data, update.data);
+}
+
+#[test]
+fn split_into_regions() {
+    let bitmaps = split(filled(0, 0, 128, 128, 0), &[rect(64, 0, 128, 64), rect(0, 64, 32, 128)]);
+
+    let bounds = bitmaps
+        .iter()
+        .map(|b| (b.x, b.y, b.width.get(), b.height.get()))
+        .collect::<Vec<_>>();
+    assert_eq!(bounds, [(64, 0, 64, 64), (0, 64, 32, 64)]);
+    assert_eq!(bitmaps[0].stride, 128 * 4);
+}
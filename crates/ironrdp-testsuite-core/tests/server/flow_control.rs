@@ -0,0 +1,125 @@
+use core::time::Duration;
+
+use ironrdp_server::bench::flow_control::FrameTracker;
+use tokio::time::timeout;
+
+/// Shorter than the acknowledgement timeout, to tell a stalled tracker from a ready one.
+const STALL: Duration = Duration::from_millis(100);
+
+async fn is_ready(tracker: &FrameTracker) -> bool {
+    timeout(STALL, tracker.ready()).await.is_ok()
+}
+
+#[tokio::test(start_paused = true)]
+async fn disabled_tracker_always_ready() {
+    let tracker = FrameTracker::default();
+
+    for _ in 0..10 {
+        tracker.next_frame();
+    }
+
+    assert!(is_ready(&tracker).await);
+    assert!(tracker.feedback().is_none());
+}
+
+#[tokio::test(start_paused = true)]
+async fn frame_ids_increase() {
+    let tracker = FrameTracker::default();
+    tracker.enable(4);
+
+    assert_eq!(tracker.next_frame(), 0);
+    assert_eq!(tracker.next_frame(), 1);
+    assert_eq!(tracker.next_frame(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn stalls_until_acknowledged() {
+    let tracker = FrameTracker::default();
+    tracker.enable(2);
+
+    let first = tracker.next_frame();
+    assert!(is_ready(&tracker).await);
+    tracker.next_frame();
+    assert!(!is_ready(&tracker).await);
+
+    tracker.acknowledge(first);
+    assert!(is_ready(&tracker).await);
+
+    let feedback = tracker.feedback().unwrap();
+    assert_eq!(feedback.unacknowledged_frames, 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn acknowledgement_resumes_waiter() {
+    let tracker = FrameTracker::default();
+    tracker.enable(1);
+    let frame = tracker.next_frame();
+
+    let waiter = tokio::spawn({
+        let tracker = tracker.clone();
+        async move { tracker.ready().await }
+    });
+    tokio::task::yield_now().await;
+    assert!(!waiter.is_finished());
+
+    tracker.acknowledge(frame);
+    timeout(STALL, waiter).await.unwrap().unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn acknowledgement_covers_previous_frames() {
+    let tracker = FrameTracker::default();
+    tracker.enable(4);
+
+    tracker.next_frame();
+    let second = tracker.next_frame();
+    tracker.next_frame();
+
+    tracker.acknowledge(second);
+    assert_eq!(tracker.feedback().unwrap().unacknowledged_frames, 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn unknown_acknowledgement_ignored() {
+    let tracker = FrameTracker::default();
+    tracker.enable(1);
+    tracker.next_frame();
+
+    tracker.acknowledge(42);
+
+    assert!(tracker.feedback().is_none());
+    assert!(!is_ready(&tracker).await);
+}
+
+#[tokio::test(start_paused = true)]
+async fn stall_times_out() {
+    let tracker = FrameTracker::default();
+    tracker.enable(1);
+    tracker.next_frame();
+
+    // The frames in flight are forgotten after two seconds, counted as the latency.
+    timeout(Duration::from_secs(3), tracker.ready()).await.unwrap();
+
+    let feedback = tracker.feedback().unwrap();
+    assert_eq!(feedback.frame_latency, Duration::from_secs(2));
+    assert_eq!(feedback.unacknowledged_frames, 0);
+    assert!(is_ready(&tracker).await);
+}
+
+#[tokio::test(start_paused = true)]
+async fn disable_resumes_waiter() {
+    let tracker = FrameTracker::default();
+    tracker.enable(1);
+    tracker.next_frame();
+
+    let waiter = tokio::spawn({
+        let tracker = tracker.clone();
+        async move { tracker.ready().await }
+    });
+    tokio::task::yield_now().await;
+    assert!(!waiter.is_finished());
+
+    tracker.disable();
+    timeout(STALL, waiter).await.unwrap().unwrap();
+    assert!(tracker.feedback().is_none());
+}
@@ -0,0 +1,377 @@
+use core::num::NonZeroU16;
+use std::sync::{Arc, Mutex};
+
+use ironrdp_core::{decode_cursor, ReadCursor};
+use ironrdp_graphics::zgfx::Decompressor;
+use ironrdp_pdu::dvc::gfx::{
+    CacheImportReplyPdu, CapabilitiesConfirmPdu, CapabilitiesV103Flags, CapabilitiesV104Flags, CapabilitiesV107Flags,
+    CapabilitiesV10Flags, CapabilitiesV81Flags, CapabilitiesV8Flags, CapabilitySet, Codec1Type, EndFramePdu,
+    PixelFormat as GfxPixelFormat, ServerPdu, StartFramePdu, Timestamp, WireToSurface1Pdu,
+};
+use ironrdp_pdu::geometry::InclusiveRectangle;
+use ironrdp_server::bench::gfx::{compress, handle, negotiate, version_rank, views};
+use ironrdp_server::{BitmapUpdate, DesktopSize, H264Encoder, H264EncoderFactory, PixelFormat, Yuv420Frame};
+
+/// YUV values of the pure red color.
+const RED_Y: u8 = 53;
+const RED_U: u8 = 99;
+const RED_V: u8 = 255;
+
+/// YUV values of the mid gray color.
+const GRAY_Y: u8 = 127;
+const GRAY_CHROMA: u8 = 128;
+
+/// H.264 encoder test double, recording the dimensions of the encoded frames.
+struct RecordingEncoder {
+    frames: Arc<Mutex<Vec<(u16, u16)>>>,
+}
+
+impl H264Encoder for RecordingEncoder {
+    fn encode(&mut self, frame: &Yuv420Frame) -> anyhow::Result<Vec<u8>> {
+        self.frames.lock().unwrap().push((frame.width(), frame.height()));
+
+        // An IDR slice NAL unit, the content doesn't matter to the server.
+        Ok(vec![0, 0, 0, 1, 0x65, 0x88])
+    }
+}
+
+#[derive(Default)]
+struct RecordingEncoderFactory {
+    encoders: Arc<Mutex<Vec<(u16, u16)>>>,
+    frames: Arc<Mutex<Vec<(u16, u16)>>>,
+}
+
+impl H264EncoderFactory for RecordingEncoderFactory {
+    fn build_encoder(&self, width: u16, height: u16) -> anyhow::Result<Box<dyn H264Encoder>> {
+        self.encoders.lock().unwrap().push((width, height));
+
+        Ok(Box::new(RecordingEncoder {
+            frames: Arc::clone(&self.frames),
+        }))
+    }
+}
+
+/// BGRX bitmap covering the desktop, the even rows being gray and the odd rows red.
+fn striped_bitmap(width: u16, height: u16) -> BitmapUpdate {
+    let stride = usize::from(width) * 4;
+    let data = (0..height)
+        .flat_map(|row| {
+            let pixel = if row % 2 == 0 {
+                [0x80, 0x80, 0x80, 0xff]
+            } else {
+                [0x00, 0x00, 0xff, 0xff]
+            };
+            core::iter::repeat_n(pixel, usize::from(width)).flatten()
+        })
+        .collect::<Vec<u8>>();
+
+    BitmapUpdate {
+        x: 0,
+        y: 0,
+        width: NonZeroU16::new(width).unwrap(),
+        height: NonZeroU16::new(height).unwrap(),
+        format: PixelFormat::BgrX32,
+        data: data.into(),
+        stride,
+    }
+}
+
+#[test]
+fn negotiate_without_avc() {
+    let caps = [
+        CapabilitySet::V8 {
+            flags: CapabilitiesV8Flags::empty(),
+        },
+        CapabilitySet::V8_1 {
+            flags: CapabilitiesV81Flags::empty(),
+        },
+        CapabilitySet::V10 {
+            flags: CapabilitiesV10Flags::AVC_DISABLED,
+        },
+        CapabilitySet::V10_7 {
+            flags: CapabilitiesV107Flags::AVC_DISABLED,
+        },
+    ];
+
+    assert_eq!(negotiate(&caps, true), None);
+}
+
+#[test]
+fn negotiate_prefers_most_recent_version() {
+    let caps = [
+        CapabilitySet::V10_4 {
+            flags: CapabilitiesV104Flags::empty(),
+        },
+        CapabilitySet::V8_1 {
+            flags: CapabilitiesV81Flags::AVC420_ENABLED,
+        },
+        CapabilitySet::V10_3 {
+            flags: CapabilitiesV103Flags::empty(),
+        },
+        // Not allowing AVC, so not confirmed even if more recent.
+        CapabilitySet::V10_7 {
+            flags: CapabilitiesV107Flags::AVC_DISABLED,
+        },
+    ];
+
+    let (cap, avc444) = negotiate(&caps, true).unwrap();
+    assert_eq!(
+        cap,
+        CapabilitySet::V10_4 {
+            flags: CapabilitiesV104Flags::empty(),
+        }
+    );
+    assert!(avc444);
+
+    let (_, avc444) = negotiate(&caps, false).unwrap();
+    assert!(!avc444);
+}
+
+#[test]
+fn negotiate_avc420_only_before_v10() {
+    let caps = [CapabilitySet::V8_1 {
+        flags: CapabilitiesV81Flags::AVC420_ENABLED,
+    }];
+
+    let (cap, avc444) = negotiate(&caps, true).unwrap();
+    assert_eq!(cap, caps[0]);
+    assert!(!avc444);
+}
+
+#[test]
+fn version_rank_follows_protocol_versions() {
+    let caps = [
+        CapabilitySet::V8 {
+            flags: CapabilitiesV8Flags::empty(),
+        },
+        CapabilitySet::V8_1 {
+            flags: CapabilitiesV81Flags::empty(),
+        },
+        CapabilitySet::V10 {
+            flags: CapabilitiesV10Flags::empty(),
+        },
+        CapabilitySet::V10_1,
+        CapabilitySet::V10_2 {
+            flags: CapabilitiesV10Flags::empty(),
+        },
+        CapabilitySet::V10_3 {
+            flags: CapabilitiesV103Flags::empty(),
+        },
+        CapabilitySet::V10_4 {
+            flags: CapabilitiesV104Flags::empty(),
+        },
+        CapabilitySet::V10_5 {
+            flags: CapabilitiesV104Flags::empty(),
+        },
+        CapabilitySet::V10_6 {
+            flags: CapabilitiesV104Flags::empty(),
+        },
+        CapabilitySet::V10_7 {
+            flags: CapabilitiesV107Flags::empty(),
+        },
+    ];
+
+    let ranks = caps.iter().map(version_rank).collect::<Vec<_>>();
+    assert!(ranks.windows(2).all(|pair| pair[0] < pair[1]), "{ranks:?}");
+
+    assert_eq!(
+        version_rank(&CapabilitySet::V10_6Err {
+            flags: CapabilitiesV104Flags::empty(),
+        }),
+        version_rank(&CapabilitySet::V10_6 {
+            flags: CapabilitiesV104Flags::empty(),
+        })
+    );
+    assert_eq!(version_rank(&CapabilitySet::Unknown(Vec::new())), 0);
+}
+
+#[test]
+fn main_view_subsamples_chroma() {
+    let (main, _) = views(DesktopSize { width: 32, height: 32 }, &striped_bitmap(32, 32)).unwrap();
+
+    assert_eq!((main.width(), main.height()), (32, 32));
+    for (row, luma) in main.y().chunks_exact(32).enumerate() {
+        let expected = if row % 2 == 0 { GRAY_Y } else { RED_Y };
+        assert!(luma.iter().all(|&y| y == expected), "row {row}");
+    }
+
+    // Each 2x2 block has two gray and two red pixels.
+    assert_eq!(main.u().len(), 16 * 16);
+    assert!(main.u().iter().all(|&u| u == 114));
+    assert!(main.v().iter().all(|&v| v == 192));
+}
+
+#[test]
+fn auxiliary_view_holds_dropped_chroma() {
+    let (_, auxiliary) = views(DesktopSize { width: 32, height: 32 }, &striped_bitmap(32, 32)).unwrap();
+
+    // The luma plane is made of the odd (red) rows of the chroma planes: U in the first 8 rows of each
+    // block of 16 rows, V in the last 8 rows.
+    for (row, luma) in auxiliary.y().chunks_exact(32).enumerate() {
+        let expected = if row % 16 < 8 { RED_U } else { RED_V };
+        assert!(luma.iter().all(|&y| y == expected), "row {row}");
+    }
+
+    // The chroma planes are made of the odd columns of the even (gray) rows.
+    assert!(auxiliary.u().iter().all(|&u| u == GRAY_CHROMA));
+    assert!(auxiliary.v().iter().all(|&v| v == GRAY_CHROMA));
+}
+
+#[test]
+fn views_are_aligned_on_macroblocks() {
+    let (main, auxiliary) = views(DesktopSize { width: 20, height: 10 }, &striped_bitmap(20, 10)).unwrap();
+
+    assert_eq!((main.width(), main.height()), (32, 16));
+    assert_eq!((auxiliary.width(), auxiliary.height()), (32, 16));
+    assert_eq!(main.y().len(), 32 * 16);
+    assert_eq!(main.u().len(), 16 * 8);
+}
+
+#[test]
+fn views_too_large_for_frames() {
+    let bitmap = striped_bitmap(1, 1);
+
+    assert!(views(
+        DesktopSize {
+            width: 65535,
+            height: 1
+        },
+        &bitmap
+    )
+    .is_err());
+}
+
+#[test]
+fn avc420_uses_single_encoder() {
+    let factory = Arc::new(RecordingEncoderFactory::default());
+    let bitmap = striped_bitmap(20, 10);
+
+    let data = handle(
+        Arc::<RecordingEncoderFactory>::clone(&factory),
+        DesktopSize { width: 20, height: 10 },
+        false,
+        &[bitmap.clone(), bitmap],
+    )
+    .unwrap();
+
+    assert_eq!(*factory.encoders.lock().unwrap(), [(32, 16)]);
+    assert_eq!(*factory.frames.lock().unwrap(), [(32, 16)]);
+    assert!(!data[0].is_empty());
+    // Unchanged desktop, nothing is encoded.
+    assert!(data[1].is_empty());
+}
+
+#[test]
+fn avc444_uses_luma_and_chroma_encoders() {
+    let factory = Arc::new(RecordingEncoderFactory::default());
+
+    let data = handle(
+        Arc::<RecordingEncoderFactory>::clone(&factory),
+        DesktopSize { width: 32, height: 32 },
+        true,
+        &[striped_bitmap(32, 32)],
+    )
+    .unwrap();
+
+    assert_eq!(*factory.encoders.lock().unwrap(), [(32, 32), (32, 32)]);
+    assert_eq!(*factory.frames.lock().unwrap(), [(32, 32), (32, 32)]);
+    assert!(!data[0].is_empty());
+}
+
+/// Frame PDUs carrying a bitstream of pseudo-random, so incompressible, bytes, the same for all the frames.
+fn frame(frame_id: u32) -> Vec<ServerPdu> {
+    let mut seed: u32 = 0x2545_f491;
+    let bitmap_data = (0..4096)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed.to_le_bytes()[0]
+        })
+        .collect();
+
+    vec![
+        ServerPdu::StartFrame(StartFramePdu {
+            timestamp: Timestamp {
+                milliseconds: 0,
+                seconds: 0,
+                minutes: 0,
+                hours: 0,
+            },
+            frame_id,
+        }),
+        ServerPdu::WireToSurface1(WireToSurface1Pdu {
+            surface_id: 0,
+            codec_id: Codec1Type::Avc420,
+            pixel_format: GfxPixelFormat::XRgb,
+            destination_rectangle: InclusiveRectangle {
+                left: 0,
+                top: 0,
+                right: 64,
+                bottom: 64,
+            },
+            bitmap_data,
+        }),
+        ServerPdu::EndFrame(EndFramePdu { frame_id }),
+    ]
+}
+
+/// Decompresses the messages in order with a single decompressor, like the client does, and decodes their PDUs.
+fn decompress(messages: &[Vec<u8>]) -> Vec<Vec<ServerPdu>> {
+    let mut decompressor = Decompressor::new();
+
+    messages
+        .iter()
+        .map(|message| {
+            let mut data = Vec::new();
+            decompressor.decompress(message, &mut data).unwrap();
+
+            let mut cursor = ReadCursor::new(&data);
+            let mut pdus = Vec::new();
+            while !cursor.is_empty() {
+                pdus.push(decode_cursor::<ServerPdu>(&mut cursor).unwrap());
+            }
+            pdus
+        })
+        .collect()
+}
+
+#[test]
+fn compressed_messages_round_trip() {
+    let messages = vec![
+        vec![ServerPdu::CapabilitiesConfirm(CapabilitiesConfirmPdu(
+            CapabilitySet::V10_7 {
+                flags: CapabilitiesV107Flags::empty(),
+            },
+        ))],
+        vec![ServerPdu::CacheImportReply(CacheImportReplyPdu {
+            cache_slots: Vec::new(),
+        })],
+        frame(0),
+        frame(1),
+    ];
+
+    let compressed = compress(&messages).unwrap();
+
+    assert_eq!(decompress(&compressed), messages);
+}
+
+#[test]
+fn compression_history_is_kept_between_messages() {
+    let compressed = compress(&[frame(0), frame(1)]).unwrap();
+
+    // The second bitstream is found in the history, so only referenced.
+    assert!(compressed[0].len() > 4096);
+    assert!(compressed[1].len() < 100, "{}", compressed[1].len());
+}
+
+#[test]
+fn large_messages_round_trip() {
+    // Larger than a single ZGFX segment, so split into a multipart structure.
+    let messages = (0..32).map(frame).collect::<Vec<_>>().concat();
+    let messages = vec![messages];
+
+    let compressed = compress(&messages).unwrap();
+
+    assert_eq!(decompress(&compressed), messages);
+}
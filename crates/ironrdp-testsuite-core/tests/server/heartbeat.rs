@@ -0,0 +1,56 @@
+use core::time::Duration;
+
+use ironrdp_pdu::rdp::heartbeat::HeartbeatPdu;
+use ironrdp_server::bench::heartbeat::{max_silence, pdu};
+use ironrdp_server::HeartbeatOptions;
+
+#[test]
+fn pdu_from_options() {
+    let options = HeartbeatOptions {
+        interval: Duration::from_secs(10),
+        warning_count: 2,
+        reconnect_count: 4,
+        max_silent_intervals: None,
+    };
+
+    assert_eq!(
+        pdu(&options),
+        HeartbeatPdu {
+            period: 10,
+            count1: 2,
+            count2: 4,
+        }
+    );
+}
+
+#[test]
+fn period_clamped() {
+    let short = HeartbeatOptions {
+        interval: Duration::from_millis(200),
+        ..Default::default()
+    };
+    assert_eq!(pdu(&short).period, 1);
+
+    let long = HeartbeatOptions {
+        interval: Duration::from_secs(1000),
+        ..Default::default()
+    };
+    assert_eq!(pdu(&long).period, 255);
+}
+
+#[test]
+fn silence_not_monitored_by_default() {
+    assert_eq!(max_silence(&HeartbeatOptions::default()), None);
+}
+
+#[test]
+fn max_silence_counted_in_sent_intervals() {
+    let options = HeartbeatOptions {
+        interval: Duration::from_millis(2500),
+        max_silent_intervals: Some(3),
+        ..Default::default()
+    };
+
+    // The interval is sent in whole seconds.
+    assert_eq!(max_silence(&options), Some(Duration::from_secs(6)));
+}
@@ -0,0 +1,71 @@
+use core::num::NonZeroU32;
+use core::time::Duration;
+
+use ironrdp_server::bench::input_limit::{Admission, InputLimiter};
+use ironrdp_server::InputLimits;
+
+fn limiter(max_events_per_second: u32) -> InputLimiter {
+    InputLimiter::new(InputLimits {
+        coalesce_mouse_moves: false,
+        max_events_per_second: NonZeroU32::new(max_events_per_second),
+    })
+}
+
+#[tokio::test(start_paused = true)]
+async fn unlimited_by_default() {
+    let mut limiter = InputLimiter::new(InputLimits::default());
+
+    for _ in 0..1000 {
+        assert_eq!(limiter.admit(true), Admission::Now);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn burst_of_a_second_admitted() {
+    let mut limiter = limiter(4);
+
+    for _ in 0..4 {
+        assert_eq!(limiter.admit(false), Admission::Now);
+    }
+    assert_eq!(limiter.admit(true), Admission::Drop);
+}
+
+#[tokio::test(start_paused = true)]
+async fn events_over_the_limit_delayed() {
+    let mut limiter = limiter(2);
+    limiter.admit(false);
+    limiter.admit(false);
+
+    // Each delayed event borrows a token from the next refill.
+    assert_eq!(limiter.admit(false), Admission::After(Duration::from_millis(500)));
+    assert_eq!(limiter.admit(false), Admission::After(Duration::from_secs(1)));
+
+    // Nothing left for the mouse moves until the borrowed tokens are refilled.
+    tokio::time::advance(Duration::from_millis(500)).await;
+    assert_eq!(limiter.admit(true), Admission::Drop);
+    tokio::time::advance(Duration::from_millis(1000)).await;
+    assert_eq!(limiter.admit(true), Admission::Now);
+}
+
+#[tokio::test(start_paused = true)]
+async fn tokens_refilled_over_time() {
+    let mut limiter = limiter(10);
+    for _ in 0..10 {
+        limiter.admit(false);
+    }
+    assert_eq!(limiter.admit(true), Admission::Drop);
+
+    tokio::time::advance(Duration::from_millis(100)).await;
+    assert_eq!(limiter.admit(true), Admission::Now);
+    assert_eq!(limiter.admit(true), Admission::Drop);
+}
+
+#[tokio::test(start_paused = true)]
+async fn refill_capped_at_a_second() {
+    let mut limiter = limiter(2);
+
+    tokio::time::advance(Duration::from_secs(10)).await;
+    assert_eq!(limiter.admit(false), Admission::Now);
+    assert_eq!(limiter.admit(false), Admission::Now);
+    assert_eq!(limiter.admit(true), Admission::Drop);
+}
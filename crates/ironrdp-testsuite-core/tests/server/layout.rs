@@ -0,0 +1,68 @@
+use ironrdp_displaycontrol::pdu::{DisplayControlMonitorLayout, MonitorLayoutEntry};
+use ironrdp_server::bench::server::layout_desktop_size;
+use ironrdp_server::DesktopSize;
+
+fn secondary(width: u32, height: u32, left: i32, top: i32) -> MonitorLayoutEntry {
+    MonitorLayoutEntry::new_secondary(width, height)
+        .unwrap()
+        .with_position(left, top)
+        .unwrap()
+}
+
+#[test]
+fn single_monitor() {
+    let layout = DisplayControlMonitorLayout::new_single_primary_monitor(1920, 1080, None, None).unwrap();
+
+    assert_eq!(
+        layout_desktop_size(&layout),
+        Some(DesktopSize {
+            width: 1920,
+            height: 1080
+        })
+    );
+}
+
+#[test]
+fn monitors_side_by_side() {
+    let layout = DisplayControlMonitorLayout::new(&[
+        MonitorLayoutEntry::new_primary(1920, 1080).unwrap(),
+        secondary(1280, 1024, 1920, 0),
+    ])
+    .unwrap();
+
+    assert_eq!(
+        layout_desktop_size(&layout),
+        Some(DesktopSize {
+            width: 3200,
+            height: 1080
+        })
+    );
+}
+
+#[test]
+fn monitors_at_negative_positions() {
+    let layout = DisplayControlMonitorLayout::new(&[
+        MonitorLayoutEntry::new_primary(1920, 1080).unwrap(),
+        secondary(1280, 1024, -1280, -200),
+        secondary(800, 600, 0, 1080),
+    ])
+    .unwrap();
+
+    // Bounding box of the monitors, from (-1280, -200) to (1920, 1680).
+    assert_eq!(
+        layout_desktop_size(&layout),
+        Some(DesktopSize {
+            width: 3200,
+            height: 1880
+        })
+    );
+}
+
+#[test]
+fn desktop_too_large() {
+    let mut monitors = vec![MonitorLayoutEntry::new_primary(8192, 1080).unwrap()];
+    monitors.extend((1..9).map(|i| secondary(8192, 1080, i * 8192, 0)));
+    let layout = DisplayControlMonitorLayout::new(&monitors).unwrap();
+
+    assert_eq!(layout_desktop_size(&layout), None);
+}
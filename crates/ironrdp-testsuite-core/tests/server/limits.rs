@@ -0,0 +1,83 @@
+use core::time::Duration;
+
+use ironrdp_pdu::rdp::server_error_info::ProtocolIndependentCode;
+use ironrdp_server::bench::server::{allows, next_timeout};
+use ironrdp_server::{ConnectionLimits, PeerInfo};
+
+fn peer(addr: &str) -> PeerInfo {
+    PeerInfo::from(addr.parse::<core::net::SocketAddr>().unwrap())
+}
+
+#[test]
+fn no_limits() {
+    let active = vec![peer("10.0.0.1:1000"); 100];
+
+    assert!(allows(&ConnectionLimits::default(), &peer("10.0.0.1:2000"), &active));
+}
+
+#[test]
+fn max_connections() {
+    let limits = ConnectionLimits {
+        max_connections: Some(2),
+        ..ConnectionLimits::default()
+    };
+    let new_peer = peer("10.0.0.3:1000");
+
+    assert!(allows(&limits, &new_peer, &[]));
+    assert!(allows(&limits, &new_peer, &[peer("10.0.0.1:1000")]));
+    assert!(!allows(
+        &limits,
+        &new_peer,
+        &[peer("10.0.0.1:1000"), peer("10.0.0.2:1000")]
+    ));
+}
+
+#[test]
+fn max_connections_per_ip() {
+    let limits = ConnectionLimits {
+        max_connections_per_ip: Some(2),
+        ..ConnectionLimits::default()
+    };
+    let active = [peer("10.0.0.1:1000"), peer("10.0.0.1:1001"), peer("10.0.0.2:1000")];
+
+    // The port is ignored, only the IP address is limited.
+    assert!(!allows(&limits, &peer("10.0.0.1:2000"), &active));
+    assert!(allows(&limits, &peer("10.0.0.2:2000"), &active));
+    assert!(allows(&limits, &peer("10.0.0.3:2000"), &active));
+}
+
+#[test]
+fn max_connections_per_ip_without_address() {
+    let limits = ConnectionLimits {
+        max_connections_per_ip: Some(1),
+        ..ConnectionLimits::default()
+    };
+    let active = [PeerInfo::default(), PeerInfo::default()];
+
+    // Connections not over TCP, e.g. from a Unix domain socket, aren't limited per IP address.
+    assert!(allows(&limits, &PeerInfo::default(), &active));
+}
+
+#[test]
+fn no_timeout() {
+    assert!(next_timeout(&ConnectionLimits::default()).is_none());
+}
+
+#[test]
+fn earliest_timeout() {
+    let idle = ConnectionLimits {
+        idle_timeout: Some(Duration::from_secs(60)),
+        ..ConnectionLimits::default()
+    };
+    let (remaining, reason) = next_timeout(&idle).unwrap();
+    assert!(remaining <= Duration::from_secs(60) && remaining > Duration::from_secs(50));
+    assert_eq!(reason, ProtocolIndependentCode::IdleTimeout);
+
+    let both = ConnectionLimits {
+        session_timeout: Some(Duration::from_secs(30)),
+        ..idle
+    };
+    let (remaining, reason) = next_timeout(&both).unwrap();
+    assert!(remaining <= Duration::from_secs(30));
+    assert_eq!(reason, ProtocolIndependentCode::LogonTimeout);
+}
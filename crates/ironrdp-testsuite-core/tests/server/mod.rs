@@ -1 +1,12 @@
+mod damage;
 mod fast_path;
+mod flow_control;
+mod gfx;
+mod heartbeat;
+mod input_limit;
+mod layout;
+mod limits;
+mod quality;
+mod reconnect;
+mod rfx;
+mod shadow;
@@ -0,0 +1,157 @@
+use core::time::Duration;
+use std::sync::Arc;
+
+use ironrdp_server::bench::quality::QualityController;
+use ironrdp_server::{EncodingQuality, LatencyQualityPolicy, NetworkFeedback, QualityPolicy, RemoteFxQuality};
+
+const BEST: EncodingQuality = EncodingQuality {
+    remote_fx: RemoteFxQuality::High,
+    h264_bitrate: None,
+    frame_interval: Duration::ZERO,
+};
+
+fn feedback(latency_ms: u64) -> NetworkFeedback {
+    NetworkFeedback {
+        frame_latency: Duration::from_millis(latency_ms),
+        unacknowledged_frames: 0,
+    }
+}
+
+#[test]
+fn high_latency_lowers_quality() {
+    let policy = LatencyQualityPolicy::default();
+
+    let quality = policy.adapt(BEST, &feedback(300));
+    assert_eq!(
+        quality,
+        EncodingQuality {
+            remote_fx: RemoteFxQuality::Medium,
+            h264_bitrate: Some(7_500),
+            frame_interval: Duration::from_millis(33),
+        }
+    );
+
+    let quality = policy.adapt(quality, &feedback(300));
+    assert_eq!(
+        quality,
+        EncodingQuality {
+            remote_fx: RemoteFxQuality::Low,
+            h264_bitrate: Some(5_625),
+            frame_interval: Duration::from_millis(66),
+        }
+    );
+}
+
+#[test]
+fn lowered_quality_bounded() {
+    let policy = LatencyQualityPolicy::default();
+
+    let quality = (0..20).fold(BEST, |quality, _| policy.adapt(quality, &feedback(300)));
+    assert_eq!(
+        quality,
+        EncodingQuality {
+            remote_fx: RemoteFxQuality::Low,
+            h264_bitrate: Some(1_000),
+            frame_interval: Duration::from_millis(200),
+        }
+    );
+}
+
+#[test]
+fn moderate_latency_keeps_quality() {
+    let policy = LatencyQualityPolicy::default();
+    let quality = EncodingQuality {
+        remote_fx: RemoteFxQuality::Medium,
+        h264_bitrate: Some(5_000),
+        frame_interval: Duration::from_millis(66),
+    };
+
+    assert_eq!(policy.adapt(quality, &feedback(100)), quality);
+}
+
+#[test]
+fn low_latency_raises_quality() {
+    let policy = LatencyQualityPolicy::default();
+    let quality = EncodingQuality {
+        remote_fx: RemoteFxQuality::Low,
+        h264_bitrate: Some(8_000),
+        frame_interval: Duration::from_millis(66),
+    };
+
+    let quality = policy.adapt(quality, &feedback(10));
+    assert_eq!(
+        quality,
+        EncodingQuality {
+            remote_fx: RemoteFxQuality::Medium,
+            h264_bitrate: Some(10_000),
+            frame_interval: Duration::from_millis(33),
+        }
+    );
+
+    // The update rate isn't limited anymore below 30 frames per second.
+    let quality = policy.adapt(quality, &feedback(10));
+    assert_eq!(quality.remote_fx, RemoteFxQuality::High);
+    assert_eq!(quality.h264_bitrate, Some(10_000));
+    assert_eq!(quality.frame_interval, Duration::ZERO);
+}
+
+#[test]
+fn raised_quality_bounded_by_max_remote_fx() {
+    let policy = LatencyQualityPolicy {
+        max_remote_fx: RemoteFxQuality::Medium,
+        ..Default::default()
+    };
+    let quality = EncodingQuality {
+        remote_fx: RemoteFxQuality::Medium,
+        ..BEST
+    };
+
+    assert_eq!(policy.adapt(quality, &feedback(10)).remote_fx, RemoteFxQuality::Medium);
+}
+
+#[tokio::test(start_paused = true)]
+async fn controller_adapts_once_per_interval() {
+    let mut controller = QualityController::new(Arc::new(LatencyQualityPolicy::default()), BEST);
+
+    // Not consulted right after the start of the connection.
+    assert_eq!(controller.adapt(Some(feedback(300))), None);
+
+    tokio::time::advance(Duration::from_secs(1)).await;
+    assert_eq!(
+        controller.adapt(Some(feedback(300))).map(|quality| quality.remote_fx),
+        Some(RemoteFxQuality::Medium)
+    );
+    assert_eq!(controller.adapt(Some(feedback(300))), None);
+
+    tokio::time::advance(Duration::from_secs(1)).await;
+    assert_eq!(
+        controller.adapt(Some(feedback(300))).map(|quality| quality.remote_fx),
+        Some(RemoteFxQuality::Low)
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn controller_reports_changes_only() {
+    let mut controller = QualityController::new(Arc::new(LatencyQualityPolicy::default()), BEST);
+    tokio::time::advance(Duration::from_secs(1)).await;
+
+    assert_eq!(controller.adapt(None), None);
+    assert_eq!(controller.adapt(Some(feedback(10))), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn controller_throttles_frames() {
+    let quality = EncodingQuality {
+        frame_interval: Duration::from_millis(100),
+        ..BEST
+    };
+    let mut controller = QualityController::new(Arc::new(LatencyQualityPolicy::default()), quality);
+
+    let start = tokio::time::Instant::now();
+    controller.throttle().await;
+    assert_eq!(start.elapsed(), Duration::ZERO);
+
+    controller.throttle().await;
+    controller.throttle().await;
+    assert_eq!(start.elapsed(), Duration::from_millis(200));
+}
@@ -0,0 +1,72 @@
+use core::time::Duration;
+
+use ironrdp_pdu::rdp::session_info::ClientAutoReconnect;
+use ironrdp_server::bench::reconnect::AutoReconnectCookies;
+
+const CLIENT_RANDOM: [u8; 32] = [0x42; 32];
+
+#[tokio::test(start_paused = true)]
+async fn new_sessions_get_new_logon_ids() {
+    let cookies = AutoReconnectCookies::default();
+
+    let first = cookies.issue(None);
+    let second = cookies.issue(None);
+    assert_ne!(first.logon_id, second.logon_id);
+    assert_ne!(first.random_bits, second.random_bits);
+
+    assert_eq!(cookies.issue(Some(first.logon_id)).logon_id, first.logon_id);
+}
+
+#[tokio::test(start_paused = true)]
+async fn valid_cookie_redeemed_once() {
+    let cookies = AutoReconnectCookies::default();
+    let cookie = cookies.issue(None);
+    let verifier = ClientAutoReconnect::new(&cookie, &CLIENT_RANDOM);
+
+    assert_eq!(cookies.redeem(&verifier, &CLIENT_RANDOM), Some(cookie.logon_id));
+    assert_eq!(cookies.redeem(&verifier, &CLIENT_RANDOM), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn verifier_bound_to_client_random() {
+    let cookies = AutoReconnectCookies::default();
+    let cookie = cookies.issue(None);
+    let verifier = ClientAutoReconnect::new(&cookie, &CLIENT_RANDOM);
+
+    assert_eq!(cookies.redeem(&verifier, &[0x24; 32]), None);
+    // A failed attempt doesn't consume the cookie.
+    assert_eq!(cookies.redeem(&verifier, &CLIENT_RANDOM), Some(cookie.logon_id));
+}
+
+#[tokio::test(start_paused = true)]
+async fn reissued_cookie_replaces_previous() {
+    let cookies = AutoReconnectCookies::default();
+    let previous = cookies.issue(None);
+    let current = cookies.issue(Some(previous.logon_id));
+
+    let verifier = ClientAutoReconnect::new(&previous, &CLIENT_RANDOM);
+    assert_eq!(cookies.redeem(&verifier, &CLIENT_RANDOM), None);
+
+    let verifier = ClientAutoReconnect::new(&current, &CLIENT_RANDOM);
+    assert_eq!(cookies.redeem(&verifier, &CLIENT_RANDOM), Some(current.logon_id));
+}
+
+#[tokio::test(start_paused = true)]
+async fn unknown_logon_id_rejected() {
+    let cookies = AutoReconnectCookies::default();
+    let mut cookie = cookies.issue(None);
+    cookie.logon_id += 1;
+
+    let verifier = ClientAutoReconnect::new(&cookie, &CLIENT_RANDOM);
+    assert_eq!(cookies.redeem(&verifier, &CLIENT_RANDOM), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn cookie_expires() {
+    let cookies = AutoReconnectCookies::default();
+    let cookie = cookies.issue(None);
+    let verifier = ClientAutoReconnect::new(&cookie, &CLIENT_RANDOM);
+
+    tokio::time::advance(Duration::from_secs(10 * 60)).await;
+    assert_eq!(cookies.redeem(&verifier, &CLIENT_RANDOM), None);
+}
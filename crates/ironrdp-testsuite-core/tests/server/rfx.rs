@@ -0,0 +1,133 @@
+use ironrdp_pdu::codecs::rfx::Quant;
+use ironrdp_pdu::rdp::capability_sets::{
+    CaptureFlags, Codec, CodecProperty, EntropyBits, NsCodec, RemoteFxContainer, RfxCaps, RfxCapset,
+    RfxClientCapsContainer, RfxICap, RfxICapFlags,
+};
+use ironrdp_server::bench::encoder::rfx::{negotiate, quants};
+use ironrdp_server::RemoteFxQuality;
+
+fn rfx_container(icaps: &[(RfxICapFlags, EntropyBits)]) -> RemoteFxContainer {
+    RemoteFxContainer::ClientContainer(RfxClientCapsContainer {
+        capture_flags: CaptureFlags::empty(),
+        caps_data: RfxCaps(RfxCapset(
+            icaps
+                .iter()
+                .map(|&(flags, entropy_bits)| RfxICap { flags, entropy_bits })
+                .collect(),
+        )),
+    })
+}
+
+fn remote_fx(id: u8, icaps: &[(RfxICapFlags, EntropyBits)]) -> Codec {
+    Codec {
+        id,
+        property: CodecProperty::RemoteFx(rfx_container(icaps)),
+    }
+}
+
+fn image_remote_fx(id: u8, icaps: &[(RfxICapFlags, EntropyBits)]) -> Codec {
+    Codec {
+        id,
+        property: CodecProperty::ImageRemoteFx(rfx_container(icaps)),
+    }
+}
+
+const IMAGE_MODE: RfxICapFlags = RfxICapFlags::CODEC_MODE;
+const VIDEO_MODE: RfxICapFlags = RfxICapFlags::empty();
+
+#[test]
+fn negotiate_without_remote_fx() {
+    assert_eq!(negotiate(&[]), None);
+
+    let nscodec = Codec {
+        id: 1,
+        property: CodecProperty::NsCodec(NsCodec {
+            is_dynamic_fidelity_allowed: true,
+            is_subsampling_allowed: true,
+            color_loss_level: 3,
+        }),
+    };
+    assert_eq!(negotiate(&[nscodec]), None);
+
+    // Empty capability sets.
+    assert_eq!(negotiate(&[remote_fx(3, &[])]), None);
+}
+
+#[test]
+fn negotiate_prefers_image_mode() {
+    let codecs = [remote_fx(
+        3,
+        &[(VIDEO_MODE, EntropyBits::Rlgr3), (IMAGE_MODE, EntropyBits::Rlgr1)],
+    )];
+    assert_eq!(negotiate(&codecs), Some((EntropyBits::Rlgr1, 3)));
+
+    // Only video mode is advertised, the codec is still used.
+    let codecs = [remote_fx(3, &[(VIDEO_MODE, EntropyBits::Rlgr1)])];
+    assert_eq!(negotiate(&codecs), Some((EntropyBits::Rlgr1, 3)));
+}
+
+#[test]
+fn negotiate_prefers_image_codec() {
+    let codecs = [
+        remote_fx(3, &[(IMAGE_MODE, EntropyBits::Rlgr3)]),
+        image_remote_fx(5, &[(IMAGE_MODE, EntropyBits::Rlgr1)]),
+    ];
+    assert_eq!(negotiate(&codecs), Some((EntropyBits::Rlgr1, 5)));
+}
+
+#[test]
+fn negotiate_prefers_rlgr3() {
+    let codecs = [remote_fx(
+        3,
+        &[(IMAGE_MODE, EntropyBits::Rlgr1), (IMAGE_MODE, EntropyBits::Rlgr3)],
+    )];
+    assert_eq!(negotiate(&codecs), Some((EntropyBits::Rlgr3, 3)));
+}
+
+#[test]
+fn negotiate_keeps_first_codec_on_tie() {
+    let codecs = [
+        remote_fx(3, &[(IMAGE_MODE, EntropyBits::Rlgr3)]),
+        remote_fx(4, &[(IMAGE_MODE, EntropyBits::Rlgr3)]),
+    ];
+    assert_eq!(negotiate(&codecs), Some((EntropyBits::Rlgr3, 3)));
+}
+
+fn quant_values(quant: &Quant) -> [u8; 10] {
+    [
+        quant.ll3, quant.lh3, quant.hl3, quant.hh3, quant.lh2, quant.hl2, quant.hh2, quant.lh1, quant.hl1, quant.hh1,
+    ]
+}
+
+#[test]
+fn quants_at_quality_bounds() {
+    let (luma, chroma) = quants(RemoteFxQuality::High);
+    assert_eq!(quant_values(&luma), [6, 6, 6, 6, 6, 6, 6, 7, 7, 7]);
+    assert_eq!(luma, chroma);
+
+    let (luma, chroma) = quants(RemoteFxQuality::Medium);
+    assert_eq!(luma, Quant::default());
+    assert_eq!(chroma, Quant::default());
+
+    let (luma, chroma) = quants(RemoteFxQuality::Low);
+    assert_eq!(quant_values(&luma), [7, 7, 7, 7, 8, 8, 9, 10, 10, 11]);
+    assert_eq!(quant_values(&chroma), [8, 8, 8, 8, 9, 9, 10, 11, 11, 12]);
+}
+
+#[test]
+fn quants_increase_as_quality_decreases() {
+    let qualities = [RemoteFxQuality::High, RemoteFxQuality::Medium, RemoteFxQuality::Low];
+
+    for pair in qualities.windows(2) {
+        let (better_luma, better_chroma) = quants(pair[0]);
+        let (worse_luma, worse_chroma) = quants(pair[1]);
+
+        for (better, worse) in [(&better_luma, &worse_luma), (&better_chroma, &worse_chroma)] {
+            for (better, worse) in quant_values(better).into_iter().zip(quant_values(worse)) {
+                // [MS-RDPRFX] 2.2.2.1.5: the quantization values range from 6 to 15.
+                assert!((6..=15).contains(&better) && (6..=15).contains(&worse));
+                assert!(better <= worse);
+            }
+        }
+    }
+}
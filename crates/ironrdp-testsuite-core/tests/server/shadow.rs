@@ -0,0 +1,123 @@
+use std::sync::{Arc, Mutex};
+
+use ironrdp_server::{
+    DesktopSize, DisplayUpdate, KeyboardEvent, MouseEvent, RdpServerDisplay, RdpServerDisplayUpdates,
+    RdpServerInputHandler, ShadowRole, ShadowSession,
+};
+use tokio::sync::Semaphore;
+
+/// Input handler test double, recording the scancodes of the pressed keys.
+///
+/// It is ready once given a permit, so the tests control how long it is busy.
+struct RecordingHandler {
+    keys: Arc<Mutex<Vec<u8>>>,
+    ready: Arc<Semaphore>,
+}
+
+impl RdpServerInputHandler for RecordingHandler {
+    fn keyboard(&mut self, event: KeyboardEvent) {
+        if let KeyboardEvent::Pressed { code, .. } = event {
+            self.keys.lock().unwrap().push(code);
+        }
+    }
+
+    fn mouse(&mut self, _: MouseEvent) {}
+
+    async fn ready(&mut self) {
+        self.ready.acquire().await.unwrap().forget();
+    }
+}
+
+struct NoUpdates;
+
+impl RdpServerDisplayUpdates for NoUpdates {
+    async fn next_update(&mut self) -> Option<DisplayUpdate> {
+        None
+    }
+}
+
+struct StaticDisplay;
+
+impl RdpServerDisplay for StaticDisplay {
+    type Updates = NoUpdates;
+
+    async fn size(&mut self) -> DesktopSize {
+        DesktopSize {
+            width: 1024,
+            height: 768,
+        }
+    }
+
+    async fn updates(&mut self) -> anyhow::Result<NoUpdates> {
+        Ok(NoUpdates)
+    }
+}
+
+fn shadow_session() -> (ShadowSession, Arc<Mutex<Vec<u8>>>, Arc<Semaphore>) {
+    let keys = Arc::new(Mutex::new(Vec::new()));
+    let ready = Arc::new(Semaphore::new(0));
+    let handler = RecordingHandler {
+        keys: Arc::clone(&keys),
+        ready: Arc::clone(&ready),
+    };
+
+    (
+        ShadowSession::new(Box::new(handler), Box::new(StaticDisplay)),
+        keys,
+        ready,
+    )
+}
+
+fn pressed(code: u8) -> KeyboardEvent {
+    KeyboardEvent::Pressed { code, extended: false }
+}
+
+#[tokio::test]
+async fn observer_input_ignored() {
+    let (shadow, keys, _) = shadow_session();
+    let (_, mut controller) = shadow.join(ShadowRole::Controller);
+    let (_, mut observer) = shadow.join(ShadowRole::Observer);
+
+    observer.handler.keyboard(pressed(1));
+    controller.handler.keyboard(pressed(2));
+
+    assert_eq!(*keys.lock().unwrap(), [2]);
+}
+
+#[tokio::test]
+async fn input_queued_while_handler_busy() {
+    let (shadow, keys, ready) = shadow_session();
+    let (_, mut previous) = shadow.join(ShadowRole::Controller);
+
+    // The previous controller waits for the handler, holding it.
+    let waiting = tokio::spawn(async move {
+        previous.handler.ready().await;
+        previous
+    });
+    tokio::task::yield_now().await;
+
+    let (_, mut controller) = shadow.join(ShadowRole::Controller);
+    controller.handler.keyboard(pressed(1));
+    controller.handler.keyboard(pressed(2));
+    assert!(keys.lock().unwrap().is_empty());
+
+    // Processed in order once the handler is ready.
+    ready.add_permits(1);
+    let _previous = waiting.await.unwrap();
+    assert_eq!(*keys.lock().unwrap(), [1, 2]);
+
+    controller.handler.keyboard(pressed(3));
+    assert_eq!(*keys.lock().unwrap(), [1, 2, 3]);
+}
+
+#[tokio::test]
+async fn input_processed_after_ready() {
+    let (shadow, keys, ready) = shadow_session();
+    let (_, mut controller) = shadow.join(ShadowRole::Controller);
+
+    ready.add_permits(1);
+    controller.handler.ready().await;
+    controller.handler.keyboard(pressed(1));
+
+    assert_eq!(*keys.lock().unwrap(), [1]);
+}
@@ -1 +1,2 @@
 mod rfx;
+mod session_info;
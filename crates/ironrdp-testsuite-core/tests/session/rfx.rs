@@ -1,4 +1,8 @@
+use core::num::NonZeroUsize;
+
+use ironrdp_core::{decode_cursor, encode_vec};
 use ironrdp_graphics::image_processing::PixelFormat;
+use ironrdp_pdu::codecs::rfx;
 use ironrdp_pdu::geometry::InclusiveRectangle;
 use ironrdp_pdu::ReadCursor;
 use ironrdp_session::image::DecodedImage;
@@ -32,6 +36,81 @@ fn decode_decodes_valid_sequence_of_messages() {
     assert_eq!(expected, image.data());
 }
 
+/// Same messages as [`ENCODED_MESSAGES`], the tile being repeated over a 128x128 image.
+fn repeated_tile_messages() -> Vec<u8> {
+    let mut input = ReadCursor::new(ENCODED_MESSAGES.as_ref());
+    let mut encoded = Vec::new();
+
+    while !input.is_empty() {
+        let block = match decode_cursor::<rfx::Block<'_>>(&mut input).unwrap() {
+            rfx::Block::Channels(_) => rfx::Block::Channels(rfx::ChannelsPdu(vec![rfx::RfxChannel {
+                width: 128,
+                height: 128,
+            }])),
+            rfx::Block::CodecChannel(rfx::CodecChannel::Region(_)) => {
+                rfx::Block::CodecChannel(rfx::CodecChannel::Region(rfx::RegionPdu {
+                    rectangles: vec![rfx::RfxRectangle {
+                        x: 0,
+                        y: 0,
+                        width: 128,
+                        height: 128,
+                    }],
+                }))
+            }
+            rfx::Block::CodecChannel(rfx::CodecChannel::TileSet(mut tile_set)) => {
+                let tile = tile_set.tiles[0].clone();
+                tile_set.tiles = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                    .map(|(x, y)| rfx::Tile { x, y, ..tile.clone() })
+                    .to_vec();
+                rfx::Block::CodecChannel(rfx::CodecChannel::TileSet(tile_set))
+            }
+            block => block,
+        };
+
+        encoded.extend(encode_vec(&block).unwrap());
+    }
+
+    encoded
+}
+
+#[test]
+fn tiles_decoded_by_several_threads_match_single_threaded_decoding() {
+    let destination = InclusiveRectangle {
+        left: 0,
+        top: 0,
+        right: 127,
+        bottom: 127,
+    };
+    let encoded = repeated_tile_messages();
+
+    let decode = |threads| {
+        let mut image = DecodedImage::new(PixelFormat::BgrX32, 128, 128);
+        let mut handler = DecodingContext::with_decode_threads(NonZeroUsize::new(threads).unwrap());
+        let (_, rectangle) = handler
+            .decode(&mut image, &destination, &mut ReadCursor::new(&encoded))
+            .unwrap();
+        assert_eq!(rectangle, destination);
+
+        image.data().to_vec()
+    };
+
+    let single_threaded = decode(1);
+    assert_eq!(decode(3), single_threaded);
+    assert_eq!(decode(4), single_threaded);
+
+    // Each 64x64 quadrant is the decoded tile.
+    let row_size = IMAGE_WIDTH * FORMAT_SIZE;
+    for (row, expected) in DECODED_IMAGE.chunks_exact(row_size).enumerate() {
+        let offset = row * 2 * row_size;
+        assert_eq!(&single_threaded[offset..offset + row_size], expected);
+        assert_eq!(&single_threaded[offset + row_size..offset + 2 * row_size], expected);
+
+        let offset = offset + IMAGE_HEIGHT * 2 * row_size;
+        assert_eq!(&single_threaded[offset..offset + row_size], expected);
+        assert_eq!(&single_threaded[offset + row_size..offset + 2 * row_size], expected);
+    }
+}
+
 const ENCODED_MESSAGES: [u8; 2970] = [
     /* HEADERS as in 4.2.2 */
     0xc0, 0xcc, 0x0c, 0x00, 0x00, 0x00, 0xca, 0xac, 0xcc, 0xca, 0x00, 0x01, 0xc3, 0xcc, 0x0d, 0x00, 0x00, 0x00, 0x01,
@@ -0,0 +1,63 @@
+use ironrdp_pdu::rdp::session_info::{
+    InfoData, InfoType, LogonErrorNotificationData, LogonErrorNotificationDataErrorCode, LogonErrorNotificationType,
+    LogonErrorsInfo, LogonExFlags, LogonInfo, LogonInfoExtended, LogonInfoVersion2, SaveSessionInfoPdu,
+    ServerAutoReconnect,
+};
+use ironrdp_session::SessionEvent;
+
+#[test]
+fn logon_info_is_surfaced_as_logon_event() {
+    let logon_info = LogonInfo {
+        session_id: 42,
+        user_name: "Administrator".to_owned(),
+        domain_name: "NTDEV".to_owned(),
+    };
+
+    let events = SessionEvent::from_save_session_info(SaveSessionInfoPdu {
+        info_type: InfoType::LogonLong,
+        info_data: InfoData::LogonInfoV2(LogonInfoVersion2 {
+            logon_info: logon_info.clone(),
+        }),
+    });
+
+    assert_eq!(events, [SessionEvent::Logon(Some(logon_info))]);
+}
+
+#[test]
+fn plain_notify_is_surfaced_as_logon_event_without_info() {
+    let events = SessionEvent::from_save_session_info(SaveSessionInfoPdu {
+        info_type: InfoType::PlainNotify,
+        info_data: InfoData::PlainNotify,
+    });
+
+    assert_eq!(events, [SessionEvent::Logon(None)]);
+}
+
+#[test]
+fn logon_extended_is_surfaced_as_cookie_and_error_events() {
+    let cookie = ServerAutoReconnect {
+        logon_id: 42,
+        random_bits: [0xA5; 16],
+    };
+    let error = LogonErrorsInfo {
+        error_type: LogonErrorNotificationType::NoPermission,
+        error_data: LogonErrorNotificationData::ErrorCode(LogonErrorNotificationDataErrorCode::FailedBadPassword),
+    };
+
+    let events = SessionEvent::from_save_session_info(SaveSessionInfoPdu {
+        info_type: InfoType::LogonExtended,
+        info_data: InfoData::LogonExtended(LogonInfoExtended {
+            present_fields_flags: LogonExFlags::AUTO_RECONNECT_COOKIE | LogonExFlags::LOGON_ERRORS,
+            auto_reconnect: Some(cookie.clone()),
+            errors_info: Some(error.clone()),
+        }),
+    });
+
+    assert_eq!(
+        events,
+        [
+            SessionEvent::AutoReconnectCookie(cookie),
+            SessionEvent::LogonError(error)
+        ]
+    );
+}
@@ -0,0 +1,87 @@
+use ironrdp_core::{BufferPool, WriteBuf};
+use ironrdp_svc::{
+    server_encode_svc_messages, server_encode_svc_messages_compressed_into, server_encode_svc_messages_into,
+    ChannelFlags, SvcCompressor, SvcMessage,
+};
+
+const CHANNEL_ID: u16 = 1004;
+const INITIATOR_ID: u16 = 1002;
+
+fn messages() -> Vec<SvcMessage> {
+    vec![SvcMessage::from(vec![0xA5u8; 32]), SvcMessage::from(vec![0x5Au8; 2000])]
+}
+
+#[test]
+fn svc_messages_are_appended_to_the_buffer() {
+    let expected = server_encode_svc_messages(messages(), CHANNEL_ID, INITIATOR_ID).unwrap();
+
+    let mut buf = WriteBuf::new();
+    buf.write_slice(&[0xFF; 3]);
+    let written = server_encode_svc_messages_into(messages(), CHANNEL_ID, INITIATOR_ID, &mut buf).unwrap();
+
+    assert_eq!(written, expected.len());
+    assert_eq!(&buf.filled()[..3], &[0xFF; 3]);
+    assert_eq!(&buf.filled()[3..], expected.as_slice());
+}
+
+#[test]
+fn buffer_pool_reuses_released_buffers() {
+    let mut pool = BufferPool::new(1, 4096);
+
+    let mut buf = pool.acquire();
+    server_encode_svc_messages_into(messages(), CHANNEL_ID, INITIATOR_ID, &mut buf).unwrap();
+    let data = buf.into_filled();
+    let ptr = data.as_ptr();
+    pool.release(WriteBuf::from_vec(data));
+    assert_eq!(pool.len(), 1);
+
+    let mut buf = pool.acquire();
+    assert!(pool.is_empty());
+    assert_eq!(buf.filled_len(), 0);
+    buf.write_slice(&[1, 2, 3]);
+    assert_eq!(buf.filled().as_ptr(), ptr);
+    pool.release(buf);
+
+    // Too big to be kept.
+    pool.acquire();
+    pool.release(WriteBuf::from_vec(vec![0; 8192]));
+    assert!(pool.is_empty());
+}
+
+/// Keeps the first byte of the data, enough for the repeated bytes of the test messages.
+struct FirstByteCompressor;
+
+impl SvcCompressor for FirstByteCompressor {
+    fn compress(&mut self, input: &[u8], output: &mut Vec<u8>) -> ChannelFlags {
+        output.push(input[0]);
+        ChannelFlags::COMPRESSED | ChannelFlags::from_bits_retain(0x0001_0000)
+    }
+}
+
+#[test]
+fn compressed_svc_chunks_keep_the_uncompressed_length() {
+    let uncompressed = server_encode_svc_messages(messages(), CHANNEL_ID, INITIATOR_ID).unwrap();
+
+    let mut buf = WriteBuf::new();
+    let written = server_encode_svc_messages_compressed_into(
+        messages(),
+        CHANNEL_ID,
+        INITIATOR_ID,
+        &mut FirstByteCompressor,
+        &mut buf,
+    )
+    .unwrap();
+    assert_eq!(written, buf.filled_len());
+
+    // The chunks of 32, 1600 and 400 bytes are each reduced to a single byte, the MCS length of the last two
+    // becoming one byte shorter.
+    assert_eq!(uncompressed.len() - written, 31 + 1599 + 399 + 2);
+
+    // Channel PDU header and data of the last chunk.
+    let last = &buf.filled()[written - 9..];
+    assert_eq!(u32::from_le_bytes(last[..4].try_into().unwrap()), 2000);
+    let flags = ChannelFlags::from_bits_retain(u32::from_le_bytes(last[4..8].try_into().unwrap()));
+    assert!(flags.contains(ChannelFlags::COMPRESSED | ChannelFlags::LAST));
+    assert_eq!(flags.bits() & 0x000F_0000, 0x0001_0000);
+    assert_eq!(last[8], 0x5A);
+}
@@ -120,7 +120,6 @@ struct TestDisplayUpdates {
     rx: DisplayUpdatesRx,
 }
 
-#[async_trait::async_trait]
 impl RdpServerDisplayUpdates for TestDisplayUpdates {
     async fn next_update(&mut self) -> Option<DisplayUpdate> {
         let mut rx = self.rx.lock().await;
@@ -133,8 +132,9 @@ struct TestDisplay {
     rx: DisplayUpdatesRx,
 }
 
-#[async_trait::async_trait]
 impl RdpServerDisplay for TestDisplay {
+    type Updates = TestDisplayUpdates;
+
     async fn size(&mut self) -> DesktopSize {
         DesktopSize {
             width: DESKTOP_WIDTH,
@@ -142,10 +142,10 @@ impl RdpServerDisplay for TestDisplay {
         }
     }
 
-    async fn updates(&mut self) -> Result<Box<dyn RdpServerDisplayUpdates>> {
-        Ok(Box::new(TestDisplayUpdates {
+    async fn updates(&mut self) -> Result<TestDisplayUpdates> {
+        Ok(TestDisplayUpdates {
             rx: Arc::clone(&self.rx),
-        }))
+        })
     }
 }
 
@@ -252,6 +252,7 @@ fn default_client_config() -> connector::Config {
             height: DESKTOP_HEIGHT,
         },
         desktop_scale_factor: 0, // Default to 0 per FreeRDP
+        monitors: Vec::new(),
         enable_tls: true,
         enable_credssp: true,
         credentials: connector::Credentials::UsernamePassword {
@@ -259,6 +260,7 @@ fn default_client_config() -> connector::Config {
             password: PASSWORD.into(),
         },
         domain: None,
+        remote_credential_guard: false,
         client_build: semver::Version::parse(env!("CARGO_PKG_VERSION"))
             .map(|version| version.major * 100 + version.minor * 10 + version.patch)
             .unwrap_or(0)
@@ -297,6 +299,9 @@ fn default_client_config() -> connector::Config {
         request_data: None,
         autologon: false,
         no_audio_playback: false,
+        message_channel: false,
+        multitransport_flags: None,
+        rail: false,
         license_cache: None,
         no_server_pointer: true,
         pointer_software_rendering: true,
@@ -9,26 +9,144 @@ mod impl_;
 #[path = "native_tls.rs"]
 mod impl_;
 
+#[cfg(feature = "openssl")]
+#[path = "openssl.rs"]
+mod impl_;
+
 #[cfg(feature = "stub")]
 #[path = "stub.rs"]
 mod impl_;
 
 #[cfg(any(
-    not(any(feature = "stub", feature = "native-tls", feature = "rustls")),
+    not(any(feature = "stub", feature = "native-tls", feature = "rustls", feature = "openssl")),
     all(feature = "stub", feature = "native-tls"),
     all(feature = "stub", feature = "rustls"),
+    all(feature = "stub", feature = "openssl"),
     all(feature = "rustls", feature = "native-tls"),
+    all(feature = "rustls", feature = "openssl"),
+    all(feature = "native-tls", feature = "openssl"),
 ))]
-compile_error!("a TLS backend must be selected by enabling a single feature out of: `rustls`, `native-tls`, `stub`");
+compile_error!(
+    "a TLS backend must be selected by enabling a single feature out of: `rustls`, `native-tls`, `openssl`, `stub`"
+);
+
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use std::io;
 
 // The whole public API of this crate.
-#[cfg(any(feature = "stub", feature = "native-tls", feature = "rustls"))]
-pub use impl_::{upgrade, TlsStream};
+#[cfg(any(feature = "stub", feature = "native-tls", feature = "rustls", feature = "openssl"))]
+pub use impl_::{server_certificate, upgrade, upgrade_with_client_certificate, TlsStream};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Transport secured by a [`TlsConnector`], usually a TCP stream.
+pub trait TlsTransport: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
+
+impl<T> TlsTransport for T where T: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
+
+/// Connection secured by a [`TlsConnector`].
+pub struct TlsConnection {
+    pub stream: Box<dyn TlsTransport>,
+    /// DER-encoded certificate of the server.
+    pub server_certificate: Vec<u8>,
+    /// Public key of the certificate of the server, verified during the CredSSP authentication.
+    pub server_public_key: Vec<u8>,
+}
 
-#[cfg(any(feature = "native-tls", feature = "rustls"))]
-pub(crate) fn extract_tls_server_public_key(cert: &[u8]) -> std::io::Result<Vec<u8>> {
-    use std::io;
+impl TlsConnection {
+    pub fn new(stream: Box<dyn TlsTransport>, server_certificate: Vec<u8>) -> io::Result<Self> {
+        let server_public_key = extract_tls_server_public_key(&server_certificate)?;
+
+        Ok(Self {
+            stream,
+            server_certificate,
+            server_public_key,
+        })
+    }
+}
+
+/// TLS implementation securing the connection with the server.
+///
+/// The backend selected with the features is [`DefaultTlsConnector`]. This trait allows to use another
+/// implementation without forking the TLS upgrade code, e.g. a FIPS-validated library or a configuration using the
+/// trust store of the platform.
+///
+/// As with the default backend, the certificate of the server is typically not verified by the implementation:
+/// the client verifies it afterwards, and CredSSP binds the authentication to its public key.
+pub trait TlsConnector: Send + Sync {
+    /// Performs the TLS handshake with the server.
+    fn connect<'a>(
+        &'a self,
+        stream: Box<dyn TlsTransport>,
+        server_name: &'a str,
+    ) -> Pin<Box<dyn Future<Output = io::Result<TlsConnection>> + Send + 'a>>;
+}
+
+/// Certificate presented by the client during the TLS handshake, for the servers authenticating the clients
+/// with their certificates (mutual TLS).
+#[derive(Clone)]
+pub struct ClientCertificate {
+    /// PEM-encoded certificate chain, starting with the certificate of the client.
+    pub certificate_chain: Vec<u8>,
+    /// PEM-encoded PKCS#8 private key of the certificate.
+    pub private_key: Vec<u8>,
+}
+
+impl ClientCertificate {
+    pub fn from_pem(certificate_chain: impl Into<Vec<u8>>, private_key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            certificate_chain: certificate_chain.into(),
+            private_key: private_key.into(),
+        }
+    }
+}
+
+impl fmt::Debug for ClientCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The private key is not printed.
+        f.debug_struct("ClientCertificate").finish_non_exhaustive()
+    }
+}
+
+/// The TLS backend selected with the features.
+#[derive(Debug, Clone, Default)]
+pub struct DefaultTlsConnector {
+    client_certificate: Option<ClientCertificate>,
+}
+
+impl DefaultTlsConnector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Presents a certificate to the servers requesting one.
+    #[must_use]
+    pub fn with_client_certificate(mut self, certificate: ClientCertificate) -> Self {
+        self.client_certificate = Some(certificate);
+        self
+    }
+}
+
+#[cfg(any(feature = "stub", feature = "native-tls", feature = "rustls", feature = "openssl"))]
+impl TlsConnector for DefaultTlsConnector {
+    fn connect<'a>(
+        &'a self,
+        stream: Box<dyn TlsTransport>,
+        server_name: &'a str,
+    ) -> Pin<Box<dyn Future<Output = io::Result<TlsConnection>> + Send + 'a>> {
+        Box::pin(async move {
+            let (tls_stream, _) =
+                upgrade_with_client_certificate(stream, server_name, self.client_certificate.as_ref()).await?;
+            let server_certificate = server_certificate(&tls_stream)?;
+
+            TlsConnection::new(Box::new(tls_stream), server_certificate)
+        })
+    }
+}
 
+/// Returns the public key of a DER-encoded certificate.
+pub fn extract_tls_server_public_key(cert: &[u8]) -> io::Result<Vec<u8>> {
     use x509_cert::der::Decode as _;
 
     let cert = x509_cert::Certificate::from_der(cert).map_err(io::Error::other)?;
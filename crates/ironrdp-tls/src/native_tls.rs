@@ -2,16 +2,39 @@ use std::io;
 
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt as _};
 
+use crate::ClientCertificate;
+
 pub type TlsStream<S> = tokio_native_tls::TlsStream<S>;
 
 pub async fn upgrade<S>(stream: S, server_name: &str) -> io::Result<(TlsStream<S>, Vec<u8>)>
+where
+    S: Unpin + AsyncRead + AsyncWrite,
+{
+    upgrade_with_client_certificate(stream, server_name, None).await
+}
+
+pub async fn upgrade_with_client_certificate<S>(
+    stream: S,
+    server_name: &str,
+    client_certificate: Option<&ClientCertificate>,
+) -> io::Result<(TlsStream<S>, Vec<u8>)>
 where
     S: Unpin + AsyncRead + AsyncWrite,
 {
     let mut tls_stream = {
-        let connector = tokio_native_tls::native_tls::TlsConnector::builder()
-            .danger_accept_invalid_certs(true)
-            .use_sni(false)
+        let mut builder = tokio_native_tls::native_tls::TlsConnector::builder();
+        builder.danger_accept_invalid_certs(true).use_sni(false);
+
+        if let Some(certificate) = client_certificate {
+            let identity = tokio_native_tls::native_tls::Identity::from_pkcs8(
+                &certificate.certificate_chain,
+                &certificate.private_key,
+            )
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            builder.identity(identity);
+        }
+
+        let connector = builder
             .build()
             .map(tokio_native_tls::TlsConnector::from)
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
@@ -24,15 +47,18 @@ where
 
     tls_stream.flush().await?;
 
-    let server_public_key = {
-        let cert = tls_stream
-            .get_ref()
-            .peer_certificate()
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
-            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "peer certificate is missing"))?;
-        let cert = cert.to_der().map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-        crate::extract_tls_server_public_key(&cert)?
-    };
+    let server_public_key = crate::extract_tls_server_public_key(&server_certificate(&tls_stream)?)?;
 
     Ok((tls_stream, server_public_key))
 }
+
+/// Returns the DER-encoded certificate of the server.
+pub fn server_certificate<S>(tls_stream: &TlsStream<S>) -> io::Result<Vec<u8>> {
+    let cert = tls_stream
+        .get_ref()
+        .peer_certificate()
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "peer certificate is missing"))?;
+
+    cert.to_der().map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+}
@@ -0,0 +1,80 @@
+use core::pin::Pin;
+use std::io;
+
+use openssl::pkey::PKey;
+use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+use openssl::x509::X509;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt as _};
+
+use crate::ClientCertificate;
+
+pub type TlsStream<S> = tokio_openssl::SslStream<S>;
+
+pub async fn upgrade<S>(stream: S, server_name: &str) -> io::Result<(TlsStream<S>, Vec<u8>)>
+where
+    S: Unpin + AsyncRead + AsyncWrite,
+{
+    upgrade_with_client_certificate(stream, server_name, None).await
+}
+
+pub async fn upgrade_with_client_certificate<S>(
+    stream: S,
+    server_name: &str,
+    client_certificate: Option<&ClientCertificate>,
+) -> io::Result<(TlsStream<S>, Vec<u8>)>
+where
+    S: Unpin + AsyncRead + AsyncWrite,
+{
+    let mut tls_stream = {
+        let mut builder = SslConnector::builder(SslMethod::tls_client()).map_err(io::Error::other)?;
+
+        // The certificate is verified by the client afterwards, and bound to the CredSSP authentication.
+        builder.set_verify(SslVerifyMode::NONE);
+
+        if let Some(certificate) = client_certificate {
+            let mut chain = X509::stack_from_pem(&certificate.certificate_chain).map_err(io::Error::other)?;
+            if chain.is_empty() {
+                return Err(io::Error::other("no client certificate"));
+            }
+            let private_key = PKey::private_key_from_pem(&certificate.private_key).map_err(io::Error::other)?;
+
+            builder.set_certificate(&chain.remove(0)).map_err(io::Error::other)?;
+            for intermediate in chain {
+                builder.add_extra_chain_cert(intermediate).map_err(io::Error::other)?;
+            }
+            builder.set_private_key(&private_key).map_err(io::Error::other)?;
+            builder.check_private_key().map_err(io::Error::other)?;
+        }
+
+        let ssl = builder
+            .build()
+            .configure()
+            .map_err(io::Error::other)?
+            .verify_hostname(false)
+            .use_server_name_indication(false)
+            .into_ssl(server_name)
+            .map_err(io::Error::other)?;
+
+        let mut tls_stream = tokio_openssl::SslStream::new(ssl, stream).map_err(io::Error::other)?;
+
+        Pin::new(&mut tls_stream).connect().await.map_err(io::Error::other)?;
+
+        tls_stream
+    };
+
+    tls_stream.flush().await?;
+
+    let server_public_key = crate::extract_tls_server_public_key(&server_certificate(&tls_stream)?)?;
+
+    Ok((tls_stream, server_public_key))
+}
+
+/// Returns the DER-encoded certificate of the server.
+pub fn server_certificate<S>(tls_stream: &TlsStream<S>) -> io::Result<Vec<u8>> {
+    let cert = tls_stream
+        .ssl()
+        .peer_certificate()
+        .ok_or_else(|| io::Error::other("peer certificate is missing"))?;
+
+    cert.to_der().map_err(io::Error::other)
+}
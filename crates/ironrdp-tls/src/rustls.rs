@@ -1,20 +1,47 @@
 use std::io;
 
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt as _};
-use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::pki_types::pem::PemObject as _;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
 use tokio_rustls::rustls::{self};
 
+use crate::ClientCertificate;
+
 pub type TlsStream<S> = tokio_rustls::client::TlsStream<S>;
 
 pub async fn upgrade<S>(stream: S, server_name: &str) -> io::Result<(TlsStream<S>, Vec<u8>)>
+where
+    S: Unpin + AsyncRead + AsyncWrite,
+{
+    upgrade_with_client_certificate(stream, server_name, None).await
+}
+
+pub async fn upgrade_with_client_certificate<S>(
+    stream: S,
+    server_name: &str,
+    client_certificate: Option<&ClientCertificate>,
+) -> io::Result<(TlsStream<S>, Vec<u8>)>
 where
     S: Unpin + AsyncRead + AsyncWrite,
 {
     let mut tls_stream = {
-        let mut config = rustls::client::ClientConfig::builder()
+        let builder = rustls::client::ClientConfig::builder()
             .dangerous()
-            .with_custom_certificate_verifier(std::sync::Arc::new(danger::NoCertificateVerification))
-            .with_no_client_auth();
+            .with_custom_certificate_verifier(std::sync::Arc::new(danger::NoCertificateVerification));
+
+        let mut config = match client_certificate {
+            Some(certificate) => {
+                let certificate_chain = CertificateDer::pem_slice_iter(&certificate.certificate_chain)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(io::Error::other)?;
+                let private_key = PrivateKeyDer::from_pem_slice(&certificate.private_key).map_err(io::Error::other)?;
+
+                builder
+                    .with_client_auth_cert(certificate_chain, private_key)
+                    .map_err(io::Error::other)?
+            }
+            None => builder.with_no_client_auth(),
+        };
 
         // This adds support for the SSLKEYLOGFILE env variable (https://wiki.wireshark.org/TLS#using-the-pre-master-secret)
         config.key_log = std::sync::Arc::new(rustls::KeyLogFile::new());
@@ -35,19 +62,23 @@ where
 
     tls_stream.flush().await?;
 
-    let server_public_key = {
-        let cert = tls_stream
-            .get_ref()
-            .1
-            .peer_certificates()
-            .and_then(|certificates| certificates.first())
-            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "peer certificate is missing"))?;
-        crate::extract_tls_server_public_key(cert)?
-    };
+    let server_public_key = crate::extract_tls_server_public_key(&server_certificate(&tls_stream)?)?;
 
     Ok((tls_stream, server_public_key))
 }
 
+/// Returns the DER-encoded certificate of the server.
+pub fn server_certificate<S>(tls_stream: &TlsStream<S>) -> io::Result<Vec<u8>> {
+    let cert = tls_stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|certificates| certificates.first())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "peer certificate is missing"))?;
+
+    Ok(cert.to_vec())
+}
+
 mod danger {
     use tokio_rustls::rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
     use tokio_rustls::rustls::{pki_types, DigitallySignedStruct, Error, SignatureScheme};
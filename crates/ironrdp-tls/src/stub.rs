@@ -4,6 +4,8 @@ use std::task::{Context, Poll};
 
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
+use crate::ClientCertificate;
+
 #[derive(Debug)]
 pub struct TlsStream<S> {
     _marker: PhantomData<S>,
@@ -37,3 +39,21 @@ where
     let _ = (stream, server_name);
     Err(io::Error::other("no TLS backend enabled for this build"))
 }
+
+pub async fn upgrade_with_client_certificate<S>(
+    stream: S,
+    server_name: &str,
+    client_certificate: Option<&ClientCertificate>,
+) -> io::Result<(TlsStream<S>, Vec<u8>)>
+where
+    S: Unpin + AsyncRead + AsyncWrite,
+{
+    // Do nothing and fail
+    let _ = (stream, server_name, client_certificate);
+    Err(io::Error::other("no TLS backend enabled for this build"))
+}
+
+pub fn server_certificate<S>(tls_stream: &TlsStream<S>) -> io::Result<Vec<u8>> {
+    let _ = tls_stream;
+    Err(io::Error::other("no TLS backend enabled for this build"))
+}
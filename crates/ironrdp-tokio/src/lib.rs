@@ -7,6 +7,12 @@ pub use ironrdp_async::*;
 #[cfg(feature = "reqwest")]
 pub mod reqwest;
 
+#[cfg(feature = "quic")]
+pub mod quic;
+
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
 use core::pin::Pin;
 use std::io;
 
@@ -94,6 +100,10 @@ where
             Ok(())
         })
     }
+
+    fn write_all_vectored<'a>(&'a mut self, bufs: &'a [&'a [u8]]) -> Self::WriteAllFut<'a> {
+        Box::pin(write_all_vectored(&mut self.inner, bufs))
+    }
 }
 
 pub type LocalTokioFramed<S> = Framed<LocalTokioStream<S>>;
@@ -157,4 +167,29 @@ where
             Ok(())
         })
     }
+
+    fn write_all_vectored<'a>(&'a mut self, bufs: &'a [&'a [u8]]) -> Self::WriteAllFut<'a> {
+        Box::pin(write_all_vectored(&mut self.inner, bufs))
+    }
+}
+
+async fn write_all_vectored<S>(stream: &mut S, bufs: &[&[u8]]) -> io::Result<()>
+where
+    S: Unpin + AsyncWrite,
+{
+    use tokio::io::AsyncWriteExt as _;
+
+    let mut bufs = VectoredBufs::new(bufs);
+
+    while !bufs.is_empty() {
+        let written = stream.write_vectored(&bufs.io_slices()).await?;
+
+        if written == 0 {
+            return Err(io::Error::from(io::ErrorKind::WriteZero));
+        }
+
+        bufs.advance(written);
+    }
+
+    stream.flush().await
 }
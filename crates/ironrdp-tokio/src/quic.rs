@@ -0,0 +1,138 @@
+//! RDP over QUIC streams.
+//!
+//! The RDP stream is carried by a bidirectional QUIC stream. The QUIC handshake already secures the
+//! connection with TLS 1.3, so the security upgrade of the connection sequence is skipped and CredSSP is
+//! bound to the certificate presented by the server during the QUIC handshake.
+//!
+//! This is an experimental transport: there is no standard RDP-over-QUIC protocol, both ends (or a tunnel)
+//! have to agree on it.
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::io;
+
+use ironrdp_async::ironrdp_connector::credssp::KerberosConfig;
+use ironrdp_async::ironrdp_connector::{custom_err, ClientConnector, ConnectionResult, ConnectorResult, ServerName};
+use ironrdp_async::{connect_begin, connect_finalize, mark_as_upgraded, AsyncNetworkClient};
+use quinn::rustls::pki_types::CertificateDer;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::TokioFramed;
+
+pub type QuicFramed = TokioFramed<QuicStream>;
+
+/// Bidirectional QUIC stream, read and written as a byte stream.
+pub struct QuicStream {
+    send: quinn::SendStream,
+    recv: quinn::RecvStream,
+}
+
+impl QuicStream {
+    pub fn new(send: quinn::SendStream, recv: quinn::RecvStream) -> Self {
+        Self { send, recv }
+    }
+
+    /// Opens a new stream, on the client side.
+    pub async fn open(connection: &quinn::Connection) -> io::Result<Self> {
+        let (send, recv) = connection.open_bi().await?;
+        Ok(Self::new(send, recv))
+    }
+
+    /// Accepts the next stream opened by the peer, on the server side.
+    pub async fn accept(connection: &quinn::Connection) -> io::Result<Self> {
+        let (send, recv) = connection.accept_bi().await?;
+        Ok(Self::new(send, recv))
+    }
+
+    pub fn into_inner(self) -> (quinn::SendStream, quinn::RecvStream) {
+        (self.send, self.recv)
+    }
+}
+
+impl AsyncRead for QuicStream {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        AsyncRead::poll_read(Pin::new(&mut self.recv), cx, buf)
+    }
+}
+
+impl AsyncWrite for QuicStream {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(Pin::new(&mut self.send), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(Pin::new(&mut self.send), cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_shutdown(Pin::new(&mut self.send), cx)
+    }
+}
+
+/// Returns the DER-encoded certificate presented by the peer during the QUIC handshake.
+pub fn peer_certificate(connection: &quinn::Connection) -> io::Result<Vec<u8>> {
+    let certificates = connection
+        .peer_identity()
+        .ok_or_else(|| io::Error::other("no certificate presented by the peer"))?
+        .downcast::<Vec<CertificateDer<'static>>>()
+        .map_err(|_| io::Error::other("peer identity is not a certificate chain"))?;
+
+    let certificate = certificates
+        .first()
+        .ok_or_else(|| io::Error::other("empty certificate chain"))?;
+
+    Ok(certificate.to_vec())
+}
+
+/// Returns the public key of the certificate presented by the peer during the QUIC handshake.
+pub fn peer_public_key(connection: &quinn::Connection) -> io::Result<Vec<u8>> {
+    use x509_cert::der::Decode as _;
+
+    let certificate = peer_certificate(connection)?;
+    let certificate = x509_cert::Certificate::from_der(&certificate).map_err(io::Error::other)?;
+
+    let public_key = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key
+        .as_bytes()
+        .ok_or_else(|| io::Error::other("subject public key BIT STRING is not aligned"))?
+        .to_owned();
+
+    Ok(public_key)
+}
+
+/// Runs the whole connection sequence over a new stream of an established QUIC connection.
+///
+/// The security upgrade is skipped, the QUIC connection being already secured, and the public key verified
+/// by CredSSP is the one of the certificate presented during the QUIC handshake.
+pub async fn connect(
+    connection: &quinn::Connection,
+    mut connector: ClientConnector,
+    server_name: ServerName,
+    network_client: Option<&mut dyn AsyncNetworkClient>,
+    kerberos_config: Option<KerberosConfig>,
+) -> ConnectorResult<(ConnectionResult, QuicFramed)> {
+    let stream = QuicStream::open(connection)
+        .await
+        .map_err(|e| custom_err!("QUIC stream", e))?;
+    let mut framed = QuicFramed::new(stream);
+
+    let should_upgrade = connect_begin(&mut framed, &mut connector).await?;
+
+    let server_public_key = peer_public_key(connection).map_err(|e| custom_err!("Server public key", e))?;
+    let upgraded = mark_as_upgraded(should_upgrade, &mut connector);
+
+    let connection_result = connect_finalize(
+        upgraded,
+        &mut framed,
+        connector,
+        server_name,
+        server_public_key,
+        network_client,
+        kerberos_config,
+    )
+    .await?;
+
+    Ok((connection_result, framed))
+}
@@ -0,0 +1,126 @@
+//! RDP over WebSocket.
+//!
+//! The RDP stream is carried in binary WebSocket messages, each message holding any part of the stream, as done
+//! by the WebSocket gateways forwarding the stream to an RDP server. The connection is then secured with TLS inside
+//! the WebSocket, as any TCP connection.
+
+use core::pin::Pin;
+use core::task::{ready, Context, Poll};
+use std::io;
+
+use bytes::{Buf as _, Bytes};
+use futures_util::{Sink, Stream};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::MaybeTlsStream;
+
+use crate::TokioFramed;
+
+pub type WebSocketFramed<S> = TokioFramed<WebSocketStream<S>>;
+
+/// WebSocket opened by [`connect`].
+pub type ClientWebSocket = tokio_tungstenite::WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Opens a WebSocket to a gateway forwarding the RDP stream, e.g. `wss://gateway.example.com/jet/fwd/tcp/<token>`.
+///
+/// The returned stream is then used as a TCP stream to the RDP server.
+pub async fn connect(request: impl IntoClientRequest + Unpin) -> io::Result<WebSocketStream<ClientWebSocket>> {
+    let (stream, _) = tokio_tungstenite::connect_async(request).await.map_err(to_io_error)?;
+    Ok(WebSocketStream::new(stream))
+}
+
+/// Byte stream over the binary messages of a WebSocket.
+///
+/// The text messages are read as binary ones, the ping and pong messages are ignored and a close
+/// message ends the stream.
+pub struct WebSocketStream<S> {
+    inner: S,
+    /// Remaining data of the last message received.
+    read_buf: Bytes,
+}
+
+impl<S> WebSocketStream<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            read_buf: Bytes::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> AsyncRead for WebSocketStream<S>
+where
+    S: Stream<Item = Result<Message, tungstenite::Error>> + Unpin,
+{
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+
+        while this.read_buf.is_empty() {
+            let message = match ready!(Pin::new(&mut this.inner).poll_next(cx)) {
+                Some(message) => message.map_err(to_io_error)?,
+                None => return Poll::Ready(Ok(())),
+            };
+
+            match message {
+                Message::Binary(data) => this.read_buf = data,
+                Message::Text(text) => this.read_buf = Bytes::from(text),
+                Message::Close(_) => return Poll::Ready(Ok(())),
+                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {}
+            }
+        }
+
+        let len = this.read_buf.len().min(buf.remaining());
+        buf.put_slice(&this.read_buf[..len]);
+        this.read_buf.advance(len);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> AsyncWrite for WebSocketStream<S>
+where
+    S: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let inner = Pin::new(&mut self.inner);
+        ready!(inner.poll_ready(cx)).map_err(to_io_error)?;
+
+        Pin::new(&mut self.inner)
+            .start_send(Message::Binary(Bytes::copy_from_slice(buf)))
+            .map_err(to_io_error)?;
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx).map_err(to_io_error)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx).map_err(to_io_error)
+    }
+}
+
+impl<S> core::fmt::Debug for WebSocketStream<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WebSocketStream")
+            .field("buffered", &self.read_buf.len())
+            .finish_non_exhaustive()
+    }
+}
+
+fn to_io_error(error: tungstenite::Error) -> io::Error {
+    match error {
+        tungstenite::Error::Io(error) => error,
+        tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {
+            io::Error::new(io::ErrorKind::BrokenPipe, error)
+        }
+        error => io::Error::other(error),
+    }
+}
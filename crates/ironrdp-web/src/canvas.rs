@@ -4,9 +4,12 @@ use ironrdp::pdu::geometry::{InclusiveRectangle, Rectangle as _};
 use softbuffer::{NoDisplayHandle, NoWindowHandle};
 use web_sys::HtmlCanvasElement;
 
-pub(crate) struct Canvas {
-    width: u32,
-    surface: softbuffer::Surface<NoDisplayHandle, NoWindowHandle>,
+use crate::webgl::WebGlCanvas;
+
+/// Renders the desktop on the canvas, with WebGL 2 when supported by the browser, or with a 2D context.
+pub(crate) enum Canvas {
+    WebGl(WebGlCanvas),
+    Software(SoftwareCanvas),
 }
 
 impl Canvas {
@@ -14,6 +17,52 @@ impl Canvas {
         render_canvas.set_width(width);
         render_canvas.set_height(height);
 
+        if let Some(canvas) = WebGlCanvas::new(&render_canvas, width, height)? {
+            debug!("Rendering with WebGL 2");
+            return Ok(Self::WebGl(canvas));
+        }
+
+        debug!("WebGL 2 not supported, rendering with a 2D context");
+        Ok(Self::Software(SoftwareCanvas::new(render_canvas, width, height)))
+    }
+
+    pub(crate) fn resize(&mut self, width: NonZeroU32, height: NonZeroU32) -> anyhow::Result<()> {
+        match self {
+            Self::WebGl(canvas) => canvas.resize(width.get(), height.get()),
+            Self::Software(canvas) => {
+                canvas.resize(width, height);
+                Ok(())
+            }
+        }
+    }
+
+    /// Updates a region of the desktop, `buffer` holding its RGBA pixels.
+    ///
+    /// The updates may only be visible once [`Canvas::present`] is called.
+    fn draw(&mut self, buffer: &[u8], region: InclusiveRectangle) -> anyhow::Result<()> {
+        match self {
+            Self::WebGl(canvas) => canvas.draw(buffer, region),
+            Self::Software(canvas) => canvas.draw(buffer, region),
+        }
+    }
+
+    /// Shows the regions drawn since the last call.
+    pub(crate) fn present(&mut self) {
+        match self {
+            Self::WebGl(canvas) => canvas.present(),
+            // The regions are presented as they are drawn.
+            Self::Software(_) => {}
+        }
+    }
+}
+
+pub(crate) struct SoftwareCanvas {
+    width: u32,
+    surface: softbuffer::Surface<NoDisplayHandle, NoWindowHandle>,
+}
+
+impl SoftwareCanvas {
+    fn new(render_canvas: HtmlCanvasElement, width: u32, height: u32) -> Self {
         #[cfg(target_arch = "wasm32")]
         let mut surface = {
             use softbuffer::SurfaceExtWeb as _;
@@ -33,15 +82,15 @@ impl Canvas {
             .resize(NonZeroU32::new(width).unwrap(), NonZeroU32::new(height).unwrap())
             .expect("surface resize");
 
-        Ok(Self { width, surface })
+        Self { width, surface }
     }
 
-    pub(crate) fn resize(&mut self, width: NonZeroU32, height: NonZeroU32) {
+    fn resize(&mut self, width: NonZeroU32, height: NonZeroU32) {
         self.surface.resize(width, height).expect("surface resize");
         self.width = width.get();
     }
 
-    pub(crate) fn draw(&mut self, buffer: &[u8], region: InclusiveRectangle) -> anyhow::Result<()> {
+    fn draw(&mut self, buffer: &[u8], region: InclusiveRectangle) -> anyhow::Result<()> {
         let region_width = region.width();
         let region_height = region.height();
 
@@ -0,0 +1,116 @@
+//! Access to the system clipboard with the asynchronous Clipboard API of the browser.
+//!
+//! The browsers only grant the access to a focused page, and reading usually requires the permission of the
+//! user, so the errors are expected and are not fatal: the clipboard is then left unsynchronized.
+
+use anyhow::Context as _;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+use super::transaction::{ClipboardContent, ClipboardContentValue, ClipboardTransaction};
+use super::{MIME_HTML, MIME_PNG, MIME_TEXT};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["navigator", "clipboard"], js_name = read, catch)]
+    async fn clipboard_read() -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(js_namespace = ["navigator", "clipboard"], js_name = write, catch)]
+    async fn clipboard_write(items: js_sys::Array) -> Result<JsValue, JsValue>;
+
+    type ClipboardItem;
+
+    #[wasm_bindgen(constructor, catch)]
+    fn new(items: &js_sys::Object) -> Result<ClipboardItem, JsValue>;
+
+    #[wasm_bindgen(method, getter)]
+    fn types(this: &ClipboardItem) -> js_sys::Array;
+
+    #[wasm_bindgen(method, js_name = getType, catch)]
+    async fn get_type(this: &ClipboardItem, mime_type: &str) -> Result<JsValue, JsValue>;
+}
+
+/// Reads the text, HTML and PNG contents of the system clipboard.
+pub(crate) async fn read() -> anyhow::Result<ClipboardTransaction> {
+    let items = clipboard_read().await.map_err(js_error).context("read clipboard")?;
+    let items = js_sys::Array::from(&items);
+
+    let mut transaction = ClipboardTransaction::init();
+
+    for item in items.iter() {
+        let item = item.unchecked_into::<ClipboardItem>();
+
+        for mime_type in item.types().iter().filter_map(|mime_type| mime_type.as_string()) {
+            if ![MIME_TEXT, MIME_HTML, MIME_PNG].contains(&mime_type.as_str())
+                || transaction
+                    .contents()
+                    .iter()
+                    .any(|content| content.mime_type() == mime_type)
+            {
+                continue;
+            }
+
+            let blob = item
+                .get_type(&mime_type)
+                .await
+                .map_err(js_error)
+                .with_context(|| format!("get `{mime_type}` clipboard data"))?
+                .unchecked_into::<web_sys::Blob>();
+
+            let content = if mime_type == MIME_PNG {
+                let buffer = JsFuture::from(blob.array_buffer())
+                    .await
+                    .map_err(js_error)
+                    .context("read clipboard image")?;
+                ClipboardContent::new_binary(&mime_type, &js_sys::Uint8Array::new(&buffer).to_vec())
+            } else {
+                let text = JsFuture::from(blob.text())
+                    .await
+                    .map_err(js_error)
+                    .context("read clipboard text")?;
+                ClipboardContent::new_text(&mime_type, &text.as_string().unwrap_or_default())
+            };
+
+            transaction.add_content(content);
+        }
+    }
+
+    Ok(transaction)
+}
+
+/// Replaces the content of the system clipboard.
+pub(crate) async fn write(transaction: &ClipboardTransaction) -> anyhow::Result<()> {
+    let record = js_sys::Object::new();
+
+    for content in transaction.contents() {
+        let parts = js_sys::Array::of1(&content.value().js_value());
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type(content.mime_type());
+
+        let blob = match content.value() {
+            ClipboardContentValue::Text(_) => web_sys::Blob::new_with_str_sequence_and_options(&parts, &options),
+            ClipboardContentValue::Binary(_) => web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options),
+        }
+        .map_err(js_error)
+        .context("create clipboard blob")?;
+
+        js_sys::Reflect::set(&record, &JsValue::from_str(content.mime_type()), &blob)
+            .map_err(js_error)
+            .context("set clipboard item")?;
+    }
+
+    let item = ClipboardItem::new(&record)
+        .map_err(js_error)
+        .context("create clipboard item")?;
+
+    clipboard_write(js_sys::Array::of1(&item))
+        .await
+        .map_err(js_error)
+        .context("write clipboard")?;
+
+    Ok(())
+}
+
+fn js_error(error: JsValue) -> anyhow::Error {
+    anyhow::Error::msg(format!("{error:?}"))
+}
@@ -10,7 +10,11 @@
 //! target application in which the user performs the paste operation, either one could be
 //! requested: when pasting into notepad, which does not support "text/html", "text/plain"
 //! will be requested, and when pasting into WordPad, "text/html" will be requested.
+//!
+//! The system clipboard is either handled by the JS code with callbacks, or directly accessed with
+//! the asynchronous Clipboard API of the browser (see the `BrowserClipboard` extension).
 
+mod browser;
 mod transaction;
 
 use std::collections::HashMap;
@@ -26,6 +30,7 @@ use ironrdp_cliprdr_format::html::{cf_html_to_plain_html, plain_html_to_cf_html}
 use ironrdp_core::{impl_as_any, IntoOwned};
 use transaction::{ClipboardContent, ClipboardContentValue};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
 
 use crate::session::RdpInputEvent;
 
@@ -124,22 +129,30 @@ pub(crate) struct WasmClipboard {
 
     proxy: WasmClipboardMessageProxy,
     js_callbacks: JsClipboardCallbacks,
+    /// Whether the system clipboard is accessed with the Clipboard API of the browser, when not handled by the
+    /// callbacks.
+    browser_clipboard: bool,
 }
 
 /// Callbacks, required to interact with JS code from within the backend.
 pub(crate) struct JsClipboardCallbacks {
-    pub(crate) on_remote_clipboard_changed: js_sys::Function,
+    pub(crate) on_remote_clipboard_changed: Option<js_sys::Function>,
     pub(crate) on_remote_received_format_list: Option<js_sys::Function>,
     pub(crate) on_force_clipboard_update: Option<js_sys::Function>,
 }
 
 impl WasmClipboard {
-    pub(crate) fn new(message_proxy: WasmClipboardMessageProxy, js_callbacks: JsClipboardCallbacks) -> Self {
+    pub(crate) fn new(
+        message_proxy: WasmClipboardMessageProxy,
+        js_callbacks: JsClipboardCallbacks,
+        browser_clipboard: bool,
+    ) -> Self {
         Self {
             local_clipboard: None,
             remote_clipboard: ClipboardTransaction::init(),
             proxy: message_proxy,
             js_callbacks,
+            browser_clipboard,
 
             remote_mapping: HashMap::new(),
             remote_formats_to_read: Vec::new(),
@@ -434,10 +447,17 @@ impl WasmClipboard {
                 return Ok(());
             }
             // Set clipboard when all formats were read
-            self.js_callbacks
-                .on_remote_clipboard_changed
-                .call1(&JsValue::NULL, &JsValue::from(transaction))
-                .expect("Failed to call JS callback");
+            if let Some(callback) = &self.js_callbacks.on_remote_clipboard_changed {
+                callback
+                    .call1(&JsValue::NULL, &JsValue::from(transaction))
+                    .expect("Failed to call JS callback");
+            } else if self.browser_clipboard {
+                spawn_local(async move {
+                    if let Err(err) = browser::write(&transaction).await {
+                        warn!("Failed to write browser clipboard: {:#}", err);
+                    }
+                });
+            }
         }
 
         Ok(())
@@ -502,6 +522,8 @@ impl WasmClipboard {
             WasmClipboardBackendMessage::ForceClipboardUpdate => {
                 if let Some(callback) = self.js_callbacks.on_force_clipboard_update.as_mut() {
                     callback.call0(&JsValue::NULL).expect("Failed to call JS callback");
+                } else if self.browser_clipboard {
+                    spawn_local(read_browser_clipboard(self.proxy.clone()));
                 } else {
                     // If no initial clipboard callback was set, send empty format list instead
                     return self.process_event(WasmClipboardBackendMessage::LocalClipboardChanged(
@@ -515,6 +537,20 @@ impl WasmClipboard {
     }
 }
 
+/// Sends the content of the system clipboard to the remote, read with the Clipboard API of the browser.
+pub(crate) async fn read_browser_clipboard(proxy: WasmClipboardMessageProxy) {
+    let transaction = match browser::read().await {
+        Ok(transaction) => transaction,
+        Err(err) => {
+            // Not a critical error, the page may not be focused or the permission denied.
+            warn!("Failed to read browser clipboard: {:#}", err);
+            ClipboardTransaction::init()
+        }
+    };
+
+    proxy.send_backend_message(WasmClipboardBackendMessage::LocalClipboardChanged(transaction));
+}
+
 /// CLIPRDR backend implementation for web. This object could be instantiated via [`WasmClipboard`]
 /// to pass it to CLIPRDR SVC constructor.
 #[derive(Debug)]
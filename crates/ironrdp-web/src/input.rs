@@ -68,6 +68,17 @@ impl InputTransaction {
     pub fn add_event(&mut self, event: DeviceEvent) {
         self.0.push(event.0);
     }
+
+    /// Types `text`, each character being pressed and released as a Unicode key.
+    ///
+    /// Typically used for the text inserted by on-screen keyboards and input methods, whose key events don't
+    /// identify the characters typed.
+    pub fn add_text(&mut self, text: &str) {
+        for character in text.chars() {
+            self.0.push(Operation::UnicodeKeyPressed(character));
+            self.0.push(Operation::UnicodeKeyReleased(character));
+        }
+    }
 }
 
 impl IntoIterator for InputTransaction {
@@ -19,6 +19,7 @@ mod image;
 mod input;
 mod network_client;
 mod session;
+mod webgl;
 
 use wasm_bindgen::prelude::*;
 
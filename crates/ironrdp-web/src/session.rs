@@ -17,7 +17,7 @@ use gloo_net::websocket::futures::WebSocket;
 use ironrdp::cliprdr::backend::ClipboardMessage;
 use ironrdp::cliprdr::CliprdrClient;
 use ironrdp::connector::connection_activation::ConnectionActivationState;
-use ironrdp::connector::credssp::KerberosConfig;
+use ironrdp::connector::credssp::{ChannelBindings, KerberosBackend, KerberosConfig};
 use ironrdp::connector::{self, ClientConnector, Credentials};
 use ironrdp::displaycontrol::client::DisplayControlClient;
 use ironrdp::dvc::DrdynvcClient;
@@ -25,7 +25,7 @@ use ironrdp::graphics::image_processing::PixelFormat;
 use ironrdp::pdu::input::fast_path::FastPathInputEvent;
 use ironrdp::pdu::rdp::client_info::PerformanceFlags;
 use ironrdp::session::image::DecodedImage;
-use ironrdp::session::{fast_path, ActiveStage, ActiveStageOutput, GracefulDisconnectReason};
+use ironrdp::session::{fast_path, ActiveStage, ActiveStageOutput, GracefulDisconnectReason, SessionEvent};
 use ironrdp_core::WriteBuf;
 use ironrdp_futures::{single_sequence_step_read, FramedWrite};
 use rgb::AsPixels as _;
@@ -70,6 +70,7 @@ struct SessionBuilderInner {
     force_clipboard_update_callback: Option<js_sys::Function>,
 
     use_display_control: bool,
+    browser_clipboard: bool,
 }
 
 impl Default for SessionBuilderInner {
@@ -97,6 +98,7 @@ impl Default for SessionBuilderInner {
             force_clipboard_update_callback: None,
 
             use_display_control: false,
+            browser_clipboard: false,
         }
     }
 }
@@ -213,6 +215,9 @@ impl SessionBuilder {
                 Extension::DisplayControl(use_display_control) => {
                     self.0.borrow_mut().use_display_control = use_display_control
                 }
+                Extension::BrowserClipboard(browser_clipboard) => {
+                    self.0.borrow_mut().browser_clipboard = browser_clipboard
+                }
             },
             Err(error) => error!(%error, "Unsupported extension value"),
         }
@@ -238,6 +243,7 @@ impl SessionBuilder {
             remote_clipboard_changed_callback,
             remote_received_format_list_callback,
             force_clipboard_update_callback,
+            browser_clipboard,
         );
 
         {
@@ -267,6 +273,7 @@ impl SessionBuilder {
             remote_clipboard_changed_callback = inner.remote_clipboard_changed_callback.clone();
             remote_received_format_list_callback = inner.remote_received_format_list_callback.clone();
             force_clipboard_update_callback = inner.force_clipboard_update_callback.clone();
+            browser_clipboard = inner.browser_clipboard;
         }
 
         info!("Connect to RDP host");
@@ -275,14 +282,15 @@ impl SessionBuilder {
 
         let (input_events_tx, input_events_rx) = mpsc::unbounded();
 
-        let clipboard = remote_clipboard_changed_callback.clone().map(|callback| {
+        let clipboard = (remote_clipboard_changed_callback.is_some() || browser_clipboard).then(|| {
             WasmClipboard::new(
                 clipboard::WasmClipboardMessageProxy::new(input_events_tx.clone()),
                 clipboard::JsClipboardCallbacks {
-                    on_remote_clipboard_changed: callback,
+                    on_remote_clipboard_changed: remote_clipboard_changed_callback,
                     on_remote_received_format_list: remote_received_format_list_callback,
                     on_force_clipboard_update: force_clipboard_update_callback,
                 },
+                browser_clipboard,
             )
         });
 
@@ -357,6 +365,9 @@ enum Extension {
     KdcProxyUrl(String),
     Pcb(String),
     DisplayControl(bool),
+    /// Accesses the system clipboard with the asynchronous Clipboard API of the browser, in place of the
+    /// clipboard callbacks not set.
+    BrowserClipboard(bool),
 }
 
 pub(crate) type FastPathInputEvents = smallvec::SmallVec<[FastPathInputEvent; 2]>;
@@ -488,6 +499,22 @@ impl Session {
                                         cliprdr.initiate_paste(format)
                                             .context("CLIPRDR initiate paste")?
                                     ),
+                                    ClipboardMessage::SendFileContents(response) => Some(
+                                        cliprdr.submit_file_contents(response)
+                                            .context("CLIPRDR submit file contents")?
+                                    ),
+                                    ClipboardMessage::SendFileContentsRequest(request) => Some(
+                                        cliprdr.request_file_contents(request)
+                                            .context("CLIPRDR request file contents")?
+                                    ),
+                                    ClipboardMessage::SendLockClipboard(data_id) => Some(
+                                        cliprdr.lock_clipboard(data_id)
+                                            .context("CLIPRDR lock clipboard")?
+                                    ),
+                                    ClipboardMessage::SendUnlockClipboard(data_id) => Some(
+                                        cliprdr.unlock_clipboard(data_id)
+                                            .context("CLIPRDR unlock clipboard")?
+                                    ),
                                     ClipboardMessage::Error(e) => {
                                         error!("Clipboard backend error: {}", e);
                                         None
@@ -524,7 +551,8 @@ impl Session {
                             } else if let Some(response_frame) = active_stage.encode_resize(width, height, scale_factor, physical_size) {
                                 self.render_canvas.set_width(width);
                                 self.render_canvas.set_height(height);
-                                gui.resize(NonZeroU32::new(width).unwrap(), NonZeroU32::new(height).unwrap());
+                                gui.resize(NonZeroU32::new(width).unwrap(), NonZeroU32::new(height).unwrap())
+                                    .context("canvas resize")?;
                                 vec![ActiveStageOutput::ResponseFrame(response_frame?)]
                             } else {
                                 debug!("Resize event ignored");
@@ -693,9 +721,17 @@ impl Session {
                             }
                         }
                     }
+                    ActiveStageOutput::SessionEvent(SessionEvent::LogonError(error)) => {
+                        warn!(?error, "Logon error notified by the server");
+                    }
+                    ActiveStageOutput::SessionEvent(event) => {
+                        debug!(?event, "Session event");
+                    }
                     ActiveStageOutput::Terminate(reason) => break 'outer reason,
                 }
             }
+
+            gui.present();
         };
 
         info!(%disconnect_reason, "RPD session terminated");
@@ -773,6 +809,17 @@ impl Session {
         Ok(())
     }
 
+    /// Sends the content of the system clipboard to the remote, read with the Clipboard API of the browser.
+    ///
+    /// The browsers only grant the access to the clipboard to a focused page, e.g. this is to be called when the
+    /// page gains the focus.
+    pub async fn synchronize_clipboard(&self) -> Result<(), IronError> {
+        let proxy = clipboard::WasmClipboardMessageProxy::new(self.input_events_tx.clone());
+        clipboard::read_browser_clipboard(proxy).await;
+
+        Ok(())
+    }
+
     fn set_cursor_style(&self, style: CursorStyle) -> Result<(), IronError> {
         let (kind, data, hotspot_x, hotspot_y) = match style {
             CursorStyle::Default => ("default", None, None, None),
@@ -839,6 +886,7 @@ fn build_config(
     connector::Config {
         credentials: Credentials::UsernamePassword { username, password },
         domain,
+        remote_credential_guard: false,
         // TODO(#327): expose these options from the WASM module.
         enable_tls: true,
         enable_credssp: true,
@@ -870,10 +918,14 @@ fn build_config(
         no_server_pointer: false,
         autologon: false,
         no_audio_playback: true,
+        message_channel: false,
+        multitransport_flags: None,
+        rail: false,
         request_data: None,
         pointer_software_rendering: false,
         performance_flags: PerformanceFlags::default(),
         desktop_scale_factor: 0,
+        monitors: Vec::new(),
         hardware_id: None,
         license_cache: None,
     }
@@ -909,6 +961,7 @@ struct ConnectParams {
     kdc_proxy_url: Option<String>,
     clipboard_backend: Option<WasmClipboardBackend>,
     use_display_control: bool,
+    browser_clipboard: bool,
 }
 
 async fn connect(
@@ -954,6 +1007,7 @@ async fn connect(
                 // HACK: It’s supposed to be the computer name of the client, but since it’s not easy to retrieve this information in the browser,
                 // we set the destination hostname instead because it happens to work.
                 hostname: Some(destination),
+                backend: KerberosBackend::Builtin,
             }),
     )
     .await?;
@@ -1072,6 +1126,8 @@ where
 
         debug_assert!(written.is_nothing());
 
+        connector.attach_server_certificates(server_cert_chain.iter().map(|cert| cert.as_bytes().to_vec()).collect());
+
         let server_cert = server_cert_chain
             .into_iter()
             .next()
@@ -1088,6 +1144,8 @@ where
             .context("subject public key BIT STRING is not aligned")?
             .to_owned();
 
+        connector.attach_channel_bindings(ChannelBindings::tls_server_end_point(server_cert.as_bytes())?);
+
         let should_upgrade = ironrdp_futures::skip_connect_begin(connector);
 
         // At this point, proxy established the TLS session.
@@ -0,0 +1,190 @@
+//! WebGL 2 renderer.
+//!
+//! The desktop is kept in a texture on the GPU: the updated regions are uploaded as is (the decoded image
+//! is already RGBA), and the texture is drawn on the whole canvas once per batch of updates. Contrary to the
+//! 2D canvas, the pixels are neither converted nor copied into an intermediate buffer on the CPU.
+
+use anyhow::Context as _;
+use ironrdp::pdu::geometry::{InclusiveRectangle, Rectangle as _};
+use wasm_bindgen::JsCast as _;
+use web_sys::{HtmlCanvasElement, WebGl2RenderingContext as Gl, WebGlProgram, WebGlShader, WebGlTexture};
+
+const VERTEX_SHADER: &str = r#"#version 300 es
+out vec2 uv;
+
+void main() {
+    // Full-screen triangle strip, generated from the vertex index.
+    vec2 position = vec2(float(gl_VertexID & 1), float(gl_VertexID >> 1));
+    uv = vec2(position.x, 1.0 - position.y);
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+
+uniform sampler2D desktop;
+in vec2 uv;
+out vec4 color;
+
+void main() {
+    color = vec4(texture(desktop, uv).rgb, 1.0);
+}
+"#;
+
+pub(crate) struct WebGlCanvas {
+    gl: Gl,
+    program: WebGlProgram,
+    texture: WebGlTexture,
+    width: u32,
+    height: u32,
+    /// Whether regions were uploaded since the last time the texture was drawn.
+    dirty: bool,
+}
+
+impl WebGlCanvas {
+    /// Returns `None` if WebGL 2 is not supported by the browser.
+    pub(crate) fn new(render_canvas: &HtmlCanvasElement, width: u32, height: u32) -> anyhow::Result<Option<Self>> {
+        let Some(context) = render_canvas
+            .get_context("webgl2")
+            .map_err(|e| anyhow::Error::msg(format!("get WebGL 2 context: {e:?}")))?
+        else {
+            return Ok(None);
+        };
+
+        let gl = context
+            .dyn_into::<Gl>()
+            .map_err(|_| anyhow::Error::msg("not a WebGL 2 context"))?;
+
+        let program = link_program(&gl)?;
+        let texture = gl.create_texture().context("create texture")?;
+
+        let mut canvas = Self {
+            gl,
+            program,
+            texture,
+            width: 0,
+            height: 0,
+            dirty: false,
+        };
+
+        canvas.resize(width, height)?;
+
+        Ok(Some(canvas))
+    }
+
+    /// Reallocates the texture for the new size of the desktop, its content being sent again by the server.
+    pub(crate) fn resize(&mut self, width: u32, height: u32) -> anyhow::Result<()> {
+        let gl = &self.gl;
+
+        gl.bind_texture(Gl::TEXTURE_2D, Some(&self.texture));
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            Gl::TEXTURE_2D,
+            0,
+            i32::try_from(Gl::RGBA8)?,
+            i32::try_from(width)?,
+            i32::try_from(height)?,
+            0,
+            Gl::RGBA,
+            Gl::UNSIGNED_BYTE,
+            None,
+        )
+        .map_err(|e| anyhow::Error::msg(format!("allocate texture: {e:?}")))?;
+        gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_MIN_FILTER, i32::try_from(Gl::NEAREST)?);
+        gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_MAG_FILTER, i32::try_from(Gl::NEAREST)?);
+        gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_WRAP_S, i32::try_from(Gl::CLAMP_TO_EDGE)?);
+        gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_WRAP_T, i32::try_from(Gl::CLAMP_TO_EDGE)?);
+        gl.viewport(0, 0, i32::try_from(width)?, i32::try_from(height)?);
+
+        self.width = width;
+        self.height = height;
+
+        Ok(())
+    }
+
+    /// Uploads an updated region, `buffer` holding its RGBA pixels.
+    pub(crate) fn draw(&mut self, buffer: &[u8], region: InclusiveRectangle) -> anyhow::Result<()> {
+        if u32::from(region.right) >= self.width || u32::from(region.bottom) >= self.height {
+            anyhow::bail!(
+                "region {region:?} outside of the {}x{} desktop",
+                self.width,
+                self.height
+            );
+        }
+
+        let gl = &self.gl;
+
+        gl.bind_texture(Gl::TEXTURE_2D, Some(&self.texture));
+        gl.pixel_storei(Gl::UNPACK_ALIGNMENT, 1);
+        gl.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_u8_array(
+            Gl::TEXTURE_2D,
+            0,
+            i32::from(region.left),
+            i32::from(region.top),
+            i32::from(region.width()),
+            i32::from(region.height()),
+            Gl::RGBA,
+            Gl::UNSIGNED_BYTE,
+            Some(buffer),
+        )
+        .map_err(|e| anyhow::Error::msg(format!("upload region: {e:?}")))?;
+
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    /// Draws the desktop on the canvas, if updated.
+    pub(crate) fn present(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        let gl = &self.gl;
+
+        gl.use_program(Some(&self.program));
+        gl.active_texture(Gl::TEXTURE0);
+        gl.bind_texture(Gl::TEXTURE_2D, Some(&self.texture));
+        gl.draw_arrays(Gl::TRIANGLE_STRIP, 0, 4);
+
+        self.dirty = false;
+    }
+}
+
+fn link_program(gl: &Gl) -> anyhow::Result<WebGlProgram> {
+    let vertex_shader = compile_shader(gl, Gl::VERTEX_SHADER, VERTEX_SHADER)?;
+    let fragment_shader = compile_shader(gl, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER)?;
+
+    let program = gl.create_program().context("create program")?;
+    gl.attach_shader(&program, &vertex_shader);
+    gl.attach_shader(&program, &fragment_shader);
+    gl.link_program(&program);
+
+    if !gl
+        .get_program_parameter(&program, Gl::LINK_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        let log = gl.get_program_info_log(&program).unwrap_or_default();
+        anyhow::bail!("link program: {log}");
+    }
+
+    Ok(program)
+}
+
+fn compile_shader(gl: &Gl, kind: u32, source: &str) -> anyhow::Result<WebGlShader> {
+    let shader = gl.create_shader(kind).context("create shader")?;
+    gl.shader_source(&shader, source);
+    gl.compile_shader(&shader);
+
+    if !gl
+        .get_shader_parameter(&shader, Gl::COMPILE_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        let log = gl.get_shader_info_log(&shader).unwrap_or_default();
+        anyhow::bail!("compile shader: {log}");
+    }
+
+    Ok(shader)
+}
@@ -169,6 +169,7 @@ fn build_config(username: String, password: String, domain: Option<String>) -> c
     connector::Config {
         credentials: Credentials::UsernamePassword { username, password },
         domain,
+        remote_credential_guard: false,
         enable_tls: false, // This example does not expose any frontend.
         enable_credssp: true,
         keyboard_type: KeyboardType::IbmEnhanced,
@@ -210,9 +211,13 @@ fn build_config(username: String, password: String, domain: Option<String>) -> c
         request_data: None,
         autologon: false,
         no_audio_playback: true,
+        message_channel: false,
+        multitransport_flags: None,
+        rail: false,
         pointer_software_rendering: true,
         performance_flags: PerformanceFlags::default(),
         desktop_scale_factor: 0,
+        monitors: Vec::new(),
         hardware_id: None,
         license_cache: None,
     }
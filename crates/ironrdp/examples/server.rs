@@ -153,7 +153,6 @@ const HEIGHT: u16 = 1080;
 
 struct DisplayUpdates;
 
-#[async_trait::async_trait]
 impl RdpServerDisplayUpdates for DisplayUpdates {
     async fn next_update(&mut self) -> Option<DisplayUpdate> {
         sleep(Duration::from_millis(100)).await;
@@ -190,8 +189,9 @@ impl RdpServerDisplayUpdates for DisplayUpdates {
     }
 }
 
-#[async_trait::async_trait]
 impl RdpServerDisplay for Handler {
+    type Updates = DisplayUpdates;
+
     async fn size(&mut self) -> DesktopSize {
         DesktopSize {
             width: WIDTH,
@@ -199,8 +199,8 @@ impl RdpServerDisplay for Handler {
         }
     }
 
-    async fn updates(&mut self) -> anyhow::Result<Box<dyn RdpServerDisplayUpdates>> {
-        Ok(Box::new(DisplayUpdates {}))
+    async fn updates(&mut self) -> anyhow::Result<DisplayUpdates> {
+        Ok(DisplayUpdates {})
     }
 }
 
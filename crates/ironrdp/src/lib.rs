@@ -4,7 +4,7 @@
 
 #[cfg(test)]
 use {
-    anyhow as _, async_trait as _, image as _, ironrdp_blocking as _, ironrdp_cliprdr_native as _, opus as _,
+    anyhow as _, image as _, ironrdp_blocking as _, ironrdp_cliprdr_native as _, opus as _,
     pico_args as _, rand as _, sspi as _, tokio_rustls as _, tracing as _, tracing_subscriber as _, x509_cert as _,
 };
 
@@ -12,6 +12,10 @@ use {
 #[doc(inline)]
 pub use ironrdp_acceptor as acceptor;
 
+#[cfg(feature = "audin")]
+#[doc(inline)]
+pub use ironrdp_audin as audin;
+
 #[cfg(feature = "cliprdr")]
 #[doc(inline)]
 pub use ironrdp_cliprdr as cliprdr;
@@ -44,6 +48,10 @@ pub use ironrdp_input as input;
 #[doc(inline)]
 pub use ironrdp_pdu as pdu;
 
+#[cfg(feature = "rail")]
+#[doc(inline)]
+pub use ironrdp_rail as rail;
+
 #[cfg(feature = "rdpdr")]
 #[doc(inline)]
 pub use ironrdp_rdpdr as rdpdr;
@@ -17,4 +17,8 @@ public enum ClipboardMessageType
     SendFormatData = 1,
     SendInitiatePaste = 2,
     Error = 3,
+    SendFileContents = 4,
+    SendFileContentsRequest = 5,
+    SendLockClipboard = 6,
+    SendUnlockClipboard = 7,
 }
@@ -0,0 +1,427 @@
+using System.Net.Security;
+
+namespace Devolutions.IronRdp;
+
+public sealed class GraphicsUpdatedEventArgs : EventArgs
+{
+    public ushort Left { get; }
+    public ushort Top { get; }
+    public ushort Width { get; }
+    public ushort Height { get; }
+
+    public GraphicsUpdatedEventArgs(ushort left, ushort top, ushort width, ushort height)
+    {
+        Left = left;
+        Top = top;
+        Width = width;
+        Height = height;
+    }
+}
+
+public sealed class DesktopResizedEventArgs : EventArgs
+{
+    public ushort Width { get; }
+    public ushort Height { get; }
+
+    public DesktopResizedEventArgs(ushort width, ushort height)
+    {
+        Width = width;
+        Height = height;
+    }
+}
+
+public sealed class PointerPositionEventArgs : EventArgs
+{
+    public ushort X { get; }
+    public ushort Y { get; }
+
+    public PointerPositionEventArgs(ushort x, ushort y)
+    {
+        X = x;
+        Y = y;
+    }
+}
+
+public sealed class PointerBitmapEventArgs : EventArgs
+{
+    public ushort Width { get; }
+    public ushort Height { get; }
+    public ushort HotspotX { get; }
+    public ushort HotspotY { get; }
+
+    /// <summary>
+    /// The pointer bitmap, in RGBA format.
+    /// </summary>
+    public byte[] Data { get; }
+
+    public PointerBitmapEventArgs(ushort width, ushort height, ushort hotspotX, ushort hotspotY, byte[] data)
+    {
+        Width = width;
+        Height = height;
+        HotspotX = hotspotX;
+        HotspotY = hotspotY;
+        Data = data;
+    }
+}
+
+/// <summary>
+/// An active client session, driving the active stage of the connection.
+/// </summary>
+/// <remarks>
+/// The frames are processed by <see cref="RunAsync"/>, which raises the events of the session. The events are
+/// raised on the synchronization context captured by <see cref="ConnectAsync"/> (e.g. the UI thread of a WPF or
+/// WinUI application), or on the thread pool if there is none.
+/// The other methods may be called from any thread, the accesses to the session being serialized.
+/// </remarks>
+public sealed class RdpSession : IAsyncDisposable
+{
+    private readonly Framed<SslStream> _framed;
+    private readonly ActiveStage _activeStage;
+    private readonly InputDatabase _inputDatabase = InputDatabase.New();
+    private readonly SemaphoreSlim _lock = new(1, 1);
+    private readonly SynchronizationContext? _synchronizationContext;
+    private DecodedImage _image;
+    private bool _terminated;
+
+    public event EventHandler<GraphicsUpdatedEventArgs>? GraphicsUpdated;
+
+    /// <summary>
+    /// Raised when the desktop was resized, the framebuffer being reallocated.
+    /// </summary>
+    public event EventHandler<DesktopResizedEventArgs>? DesktopResized;
+
+    public event EventHandler? PointerDefault;
+    public event EventHandler? PointerHidden;
+    public event EventHandler<PointerPositionEventArgs>? PointerPositionChanged;
+    public event EventHandler<PointerBitmapEventArgs>? PointerBitmapChanged;
+
+    /// <summary>
+    /// Raised when the server ends the session, before <see cref="RunAsync"/> completes.
+    /// </summary>
+    public event EventHandler? Terminated;
+
+    private RdpSession(ConnectionResult connectionResult, Framed<SslStream> framed,
+        SynchronizationContext? synchronizationContext)
+    {
+        var desktopSize = connectionResult.GetDesktopSize();
+        _image = DecodedImage.New(PixelFormat.RgbA32, desktopSize.GetWidth(), desktopSize.GetHeight());
+        _activeStage = ActiveStage.New(connectionResult);
+        _framed = framed;
+        _synchronizationContext = synchronizationContext;
+    }
+
+    /// <summary>
+    /// Connects to the server, completing once the session is active.
+    /// </summary>
+    /// <remarks>Cancelling stops waiting for the connection, which is abandoned.</remarks>
+    public static async Task<RdpSession> ConnectAsync(Config config, string serverName,
+        CliprdrBackendFactory? factory = null, int port = 3389, CancellationToken cancellationToken = default)
+    {
+        var synchronizationContext = SynchronizationContext.Current;
+        var (result, framed) = await Connection.Connect(config, serverName, factory, port)
+            .WaitAsync(cancellationToken)
+            .ConfigureAwait(false);
+
+        return new RdpSession(result, framed, synchronizationContext);
+    }
+
+    public ushort DesktopWidth => WithLock(() => _image.GetWidth());
+    public ushort DesktopHeight => WithLock(() => _image.GetHeight());
+
+    /// <summary>
+    /// Reads and processes the frames received from the server, until the session is terminated.
+    /// </summary>
+    /// <remarks>Cancelling ends the session: it should be disposed.</remarks>
+    public async Task RunAsync(CancellationToken cancellationToken = default)
+    {
+        while (!_terminated)
+        {
+            var (action, payload) = await _framed.ReadPdu().WaitAsync(cancellationToken).ConfigureAwait(false);
+
+            await _lock.WaitAsync(cancellationToken).ConfigureAwait(false);
+            List<System.Action> events;
+            try
+            {
+                var outputs = _activeStage.Process(_image, action, payload);
+                events = await HandleOutputs(outputs).ConfigureAwait(false);
+            }
+            finally
+            {
+                _lock.Release();
+            }
+
+            foreach (var raise in events)
+            {
+                Raise(raise);
+            }
+        }
+    }
+
+    /// <summary>
+    /// Copies the framebuffer, in RGBA format, into <paramref name="buffer"/>.
+    /// </summary>
+    /// <returns>The size of the framebuffer, the buffer being too small if its length is lower than
+    /// <c>width * height * 4</c>.</returns>
+    public (ushort Width, ushort Height) CopyFramebuffer(byte[] buffer)
+    {
+        return WithLock(() =>
+        {
+            var data = _image.GetData();
+            if ((nuint)buffer.Length >= data.GetSize())
+            {
+                data.Fill(buffer);
+            }
+
+            return (_image.GetWidth(), _image.GetHeight());
+        });
+    }
+
+    /// <summary>
+    /// Presses or releases a key, by scancode (extended keys are prefixed with 0xE0, e.g. 0xE04B for the left arrow).
+    /// </summary>
+    public Task SendKeyAsync(ushort scancode, bool pressed)
+    {
+        var key = Scancode.FromU16(scancode);
+        return ApplyInputAsync(pressed ? key.AsOperationKeyPressed() : key.AsOperationKeyReleased());
+    }
+
+    /// <summary>
+    /// Presses or releases the key of a Unicode character, typically for the text typed with input methods.
+    /// </summary>
+    public Task SendUnicodeAsync(uint codePoint, bool pressed)
+    {
+        var character = Char.New(codePoint);
+        return ApplyInputAsync(pressed
+            ? character.AsOperationUnicodeKeyPressed()
+            : character.AsOperationUnicodeKeyReleased());
+    }
+
+    public Task SendMouseMoveAsync(ushort x, ushort y)
+    {
+        return ApplyInputAsync(MousePosition.New(x, y).AsMoveOperation());
+    }
+
+    public Task SendMouseButtonAsync(MouseButtonType button, bool pressed)
+    {
+        var mouseButton = MouseButton.New(button);
+        return ApplyInputAsync(pressed
+            ? mouseButton.AsOperationMouseButtonPressed()
+            : mouseButton.AsOperationMouseButtonReleased());
+    }
+
+    /// <summary>
+    /// Rotates the mouse wheel, 120 units being one notch. Positive units scroll up, or right.
+    /// </summary>
+    public Task SendWheelAsync(bool vertical, short units)
+    {
+        return ApplyInputAsync(WheelRotations.New(vertical, units).AsOperation());
+    }
+
+    /// <summary>
+    /// Requests the server to resize the desktop, <see cref="DesktopResized"/> being raised once it is.
+    /// </summary>
+    public Task ResizeAsync(uint width, uint height)
+    {
+        return ProcessAsync(() => _activeStage.EncodedResize(width, height));
+    }
+
+    /// <summary>
+    /// Sends the message of the clipboard backend to the server.
+    /// </summary>
+    public async Task SendClipboardMessageAsync(ClipboardMessage message)
+    {
+        await _lock.WaitAsync().ConfigureAwait(false);
+        try
+        {
+            VecU8 frame = message.GetMessageType() switch
+            {
+                ClipboardMessageType.SendFormatData =>
+                    _activeStage.SubmitClipboardFormatData(message.GetSendFormatData()!),
+                ClipboardMessageType.SendInitiateCopy =>
+                    _activeStage.InitiateClipboardCopy(message.GetSendInitiateCopy()!),
+                ClipboardMessageType.SendInitiatePaste =>
+                    _activeStage.InitiateClipboardPaste(message.GetSendInitiatePaste()!),
+                var messageType => throw new ArgumentException("Unexpected clipboard message: " + messageType,
+                    nameof(message)),
+            };
+
+            await _framed.Write(Utils.VecU8ToByte(frame)).ConfigureAwait(false);
+        }
+        finally
+        {
+            _lock.Release();
+        }
+    }
+
+    /// <summary>
+    /// Requests the server to end the session, <see cref="RunAsync"/> completing once it is.
+    /// </summary>
+    public Task ShutdownAsync()
+    {
+        return ProcessAsync(() => _activeStage.GracefulShutdown());
+    }
+
+    public async ValueTask DisposeAsync()
+    {
+        var (stream, _) = _framed.GetInner();
+        await stream.DisposeAsync().ConfigureAwait(false);
+        _lock.Dispose();
+    }
+
+    private Task ApplyInputAsync(Operation operation)
+    {
+        return ProcessAsync(() => _activeStage.ProcessFastpathInput(_image, _inputDatabase.Apply(operation)));
+    }
+
+    private async Task ProcessAsync(Func<ActiveStageOutputIterator?> process)
+    {
+        await _lock.WaitAsync().ConfigureAwait(false);
+        List<System.Action> events;
+        try
+        {
+            var outputs = process();
+            if (outputs == null)
+            {
+                return;
+            }
+
+            events = await HandleOutputs(outputs).ConfigureAwait(false);
+        }
+        finally
+        {
+            _lock.Release();
+        }
+
+        foreach (var raise in events)
+        {
+            Raise(raise);
+        }
+    }
+
+    /// <summary>
+    /// Handles the outputs of the active stage, the lock being held. Returns the events to raise once released.
+    /// </summary>
+    private async Task<List<System.Action>> HandleOutputs(ActiveStageOutputIterator outputs)
+    {
+        var events = new List<System.Action>();
+
+        while (!outputs.IsEmpty())
+        {
+            // Not null since the iterator is not empty.
+            var output = outputs.Next()!;
+
+            switch (output.GetEnumType())
+            {
+                case ActiveStageOutputType.ResponseFrame:
+                {
+                    var frame = output.GetResponseFrame();
+                    var bytes = new byte[frame.GetSize()];
+                    frame.Fill(bytes);
+                    await _framed.Write(bytes).ConfigureAwait(false);
+                    break;
+                }
+                case ActiveStageOutputType.GraphicsUpdate:
+                {
+                    var region = output.GetGraphicsUpdate();
+                    var args = new GraphicsUpdatedEventArgs(region.GetLeft(), region.GetTop(), region.GetWidth(),
+                        region.GetHeight());
+                    events.Add(() => GraphicsUpdated?.Invoke(this, args));
+                    break;
+                }
+                case ActiveStageOutputType.PointerDefault:
+                    events.Add(() => PointerDefault?.Invoke(this, EventArgs.Empty));
+                    break;
+                case ActiveStageOutputType.PointerHidden:
+                    events.Add(() => PointerHidden?.Invoke(this, EventArgs.Empty));
+                    break;
+                case ActiveStageOutputType.PointerPosition:
+                {
+                    var position = output.GetPointerPosition();
+                    var args = new PointerPositionEventArgs(position.X, position.Y);
+                    events.Add(() => PointerPositionChanged?.Invoke(this, args));
+                    break;
+                }
+                case ActiveStageOutputType.PointerBitmap:
+                {
+                    var pointer = output.GetPointerBitmap();
+                    var data = pointer.GetData();
+                    var bytes = new byte[data.GetSize()];
+                    data.Fill(bytes);
+                    var args = new PointerBitmapEventArgs(pointer.GetWidth(), pointer.GetHeight(),
+                        pointer.GetHotspotX(), pointer.GetHotspotY(), bytes);
+                    events.Add(() => PointerBitmapChanged?.Invoke(this, args));
+                    break;
+                }
+                case ActiveStageOutputType.DeactivateAll:
+                {
+                    var (width, height) = await Reactivate(output.GetDeactivateAll()).ConfigureAwait(false);
+                    var args = new DesktopResizedEventArgs(width, height);
+                    events.Add(() => DesktopResized?.Invoke(this, args));
+                    break;
+                }
+                case ActiveStageOutputType.Terminate:
+                    _terminated = true;
+                    events.Add(() => Terminated?.Invoke(this, EventArgs.Empty));
+                    break;
+                case ActiveStageOutputType.SessionEvent:
+                    break;
+            }
+        }
+
+        return events;
+    }
+
+    /// <summary>
+    /// Executes the Deactivation-Reactivation Sequence, typically after a resize of the desktop.
+    /// </summary>
+    private async Task<(ushort, ushort)> Reactivate(ConnectionActivationSequence activationSequence)
+    {
+        var writeBuf = WriteBuf.New();
+
+        while (true)
+        {
+            await Connection.SingleSequenceStep(activationSequence, writeBuf, _framed).ConfigureAwait(false);
+
+            if (activationSequence.GetState().GetType() != ConnectionActivationStateType.Finalized)
+            {
+                continue;
+            }
+
+            var finalized = activationSequence.GetState().GetFinalized();
+            var desktopSize = finalized.GetDesktopSize();
+            var noServerPointer = finalized.GetNoServerPointer();
+
+            _image = DecodedImage.New(PixelFormat.RgbA32, desktopSize.GetWidth(), desktopSize.GetHeight());
+            _activeStage.SetFastpathProcessor(finalized.GetIoChannelId(), finalized.GetUserChannelId(),
+                noServerPointer, finalized.GetPointerSoftwareRendering());
+            _activeStage.SetNoServerPointer(noServerPointer);
+
+            return (desktopSize.GetWidth(), desktopSize.GetHeight());
+        }
+    }
+
+    private void Raise(System.Action raise)
+    {
+        if (_synchronizationContext == null)
+        {
+            raise();
+        }
+        else
+        {
+            _synchronizationContext.Post(_ => raise(), null);
+        }
+    }
+
+    private T WithLock<T>(Func<T> f)
+    {
+        _lock.Wait();
+        try
+        {
+            return f();
+        }
+        finally
+        {
+            _lock.Release();
+        }
+    }
+}
@@ -14,6 +14,18 @@ pub mod ffi {
                 ironrdp::cliprdr::backend::ClipboardMessage::SendInitiatePaste(_) => {
                     ClipboardMessageType::SendInitiatePaste
                 }
+                ironrdp::cliprdr::backend::ClipboardMessage::SendFileContents(_) => {
+                    ClipboardMessageType::SendFileContents
+                }
+                ironrdp::cliprdr::backend::ClipboardMessage::SendFileContentsRequest(_) => {
+                    ClipboardMessageType::SendFileContentsRequest
+                }
+                ironrdp::cliprdr::backend::ClipboardMessage::SendLockClipboard(_) => {
+                    ClipboardMessageType::SendLockClipboard
+                }
+                ironrdp::cliprdr::backend::ClipboardMessage::SendUnlockClipboard(_) => {
+                    ClipboardMessageType::SendUnlockClipboard
+                }
                 ironrdp::cliprdr::backend::ClipboardMessage::Error(_) => ClipboardMessageType::Error,
             }
         }
@@ -52,6 +64,10 @@ pub mod ffi {
         SendFormatData,
         SendInitiatePaste,
         Error,
+        SendFileContents,
+        SendFileContentsRequest,
+        SendLockClipboard,
+        SendUnlockClipboard,
     }
 
     #[diplomat::opaque]
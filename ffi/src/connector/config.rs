@@ -156,6 +156,7 @@ pub mod ffi {
             let inner_config = ironrdp::connector::Config {
                 credentials: self.credentials.clone().ok_or("credentials not set")?,
                 domain: self.domain.clone(),
+                remote_credential_guard: false,
                 enable_tls: self.enable_tls.unwrap_or(false),
                 enable_credssp: self.enable_credssp.unwrap_or(true),
                 keyboard_layout: self.keyboard_layout.unwrap_or(0),
@@ -194,10 +195,14 @@ pub mod ffi {
                 no_server_pointer: self.no_server_pointer.unwrap_or(false),
                 autologon: self.autologon.unwrap_or(false),
                 no_audio_playback: self.no_audio_playback.unwrap_or(false),
+                message_channel: false,
+                multitransport_flags: None,
+                rail: false,
                 request_data: None,
                 pointer_software_rendering: self.pointer_software_rendering.unwrap_or(false),
                 performance_flags: self.performance_flags.ok_or("performance flag is missing")?,
                 desktop_scale_factor: 0,
+                monitors: Vec::new(),
                 hardware_id: None,
                 license_cache: None,
             };
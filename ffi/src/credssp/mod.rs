@@ -68,6 +68,7 @@ pub mod ffi {
                         server_name.into(),
                         server_public_key.to_owned(),
                         kerbero_configs.map(|config| config.0.clone()),
+                        connector.channel_bindings().cloned(),
                     )?;
 
                     Ok(Box::new(CredsspSequenceInitResult {
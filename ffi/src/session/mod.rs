@@ -177,6 +177,7 @@ pub mod ffi {
         PointerBitmap,
         Terminate,
         DeactivateAll,
+        SessionEvent,
     }
 
     impl ActiveStageOutput {
@@ -190,6 +191,7 @@ pub mod ffi {
                 ironrdp::session::ActiveStageOutput::PointerBitmap { .. } => ActiveStageOutputType::PointerBitmap,
                 ironrdp::session::ActiveStageOutput::Terminate { .. } => ActiveStageOutputType::Terminate,
                 ironrdp::session::ActiveStageOutput::DeactivateAll { .. } => ActiveStageOutputType::DeactivateAll,
+                ironrdp::session::ActiveStageOutput::SessionEvent { .. } => ActiveStageOutputType::SessionEvent,
             }
         }
 
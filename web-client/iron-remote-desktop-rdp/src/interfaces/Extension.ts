@@ -1,4 +1,8 @@
-type ExtensionValue = { Pcb: string } | { KdcProxyUrl: string } | { DisplayControl: boolean };
+type ExtensionValue =
+    | { Pcb: string }
+    | { KdcProxyUrl: string }
+    | { DisplayControl: boolean }
+    | { BrowserClipboard: boolean };
 
 export class Extension {
     static init(ident: string, value: unknown): ExtensionValue {
@@ -21,6 +25,12 @@ export class Extension {
                 } else {
                     throw new Error('DisplayControl must be a boolean');
                 }
+            case 'BrowserClipboard':
+                if (typeof value === 'boolean') {
+                    return { BrowserClipboard: value };
+                } else {
+                    throw new Error('BrowserClipboard must be a boolean');
+                }
             default:
                 throw new Error(`Invalid extension type: ${ident}`);
         }
@@ -3,4 +3,5 @@ import type { DeviceEvent } from './DeviceEvent';
 export interface InputTransaction {
     init(): InputTransaction;
     add_event(event: DeviceEvent): void;
+    add_text(text: string): void;
 }
@@ -28,4 +28,6 @@ export interface UserInteraction {
     resize(width: number, height: number, scale?: number): void;
 
     setEnableClipboard(enable: boolean): void;
+
+    setVirtualKeyboardVisible(visible: boolean): void;
 }
@@ -0,0 +1,198 @@
+import type { MousePosition } from '../interfaces/MousePosition';
+
+// A touch held still for this long is a right click.
+const LONG_PRESS_DELAY = 500; // ms
+// A touch moving further than this is a pan, not a tap nor a long press.
+const TAP_MAX_DISTANCE = 10; // px
+const MAX_ZOOM = 5;
+
+export interface TouchGestureCallbacks {
+    /// Clicks the given mouse button (0: left, 2: right) at the given position of the remote desktop.
+    click(position: MousePosition, button: number): void;
+    /// Scrolls by the given number of wheel rotation units.
+    scroll(vertical: boolean, rotationUnits: number): void;
+}
+
+interface Point {
+    x: number;
+    y: number;
+}
+
+/// Translates the touch events of the canvas into mouse inputs:
+///
+/// - a tap is a left click,
+/// - a long press is a right click,
+/// - a one-finger pan scrolls,
+/// - a two-finger pinch zooms the canvas, and a two-finger pan moves the zoomed canvas.
+///
+/// The zoom is local: the canvas is scaled with a CSS transform, the remote desktop size is left unchanged.
+export class TouchGestures {
+    private canvas: HTMLCanvasElement;
+    private callbacks: TouchGestureCallbacks;
+
+    private mode: 'none' | 'tap' | 'pan' | 'pinch' = 'none';
+    private start: Point = { x: 0, y: 0 };
+    private last: Point = { x: 0, y: 0 };
+    private longPressTimer?: ReturnType<typeof setTimeout>;
+
+    private zoom = 1;
+    private translation: Point = { x: 0, y: 0 };
+    private pinchStartDistance = 0;
+    private pinchStartZoom = 1;
+
+    constructor(canvas: HTMLCanvasElement, callbacks: TouchGestureCallbacks) {
+        this.canvas = canvas;
+        this.callbacks = callbacks;
+    }
+
+    /// Resets the zoom, e.g. when the canvas is resized.
+    resetZoom() {
+        this.zoom = 1;
+        this.translation = { x: 0, y: 0 };
+        this.applyTransform();
+    }
+
+    touchStart(evt: TouchEvent) {
+        // Prevent the emulated mouse events and the browser gestures.
+        evt.preventDefault();
+
+        if (evt.touches.length === 1) {
+            const point = clientPoint(evt.touches[0]);
+            this.mode = 'tap';
+            this.start = point;
+            this.last = point;
+
+            this.cancelLongPress();
+            this.longPressTimer = setTimeout(() => {
+                this.longPressTimer = undefined;
+                if (this.mode === 'tap') {
+                    this.mode = 'none';
+                    this.callbacks.click(this.remotePosition(this.start), 2);
+                }
+            }, LONG_PRESS_DELAY);
+        } else if (evt.touches.length === 2) {
+            this.cancelLongPress();
+            this.mode = 'pinch';
+            this.pinchStartDistance = distance(evt.touches[0], evt.touches[1]);
+            this.pinchStartZoom = this.zoom;
+            this.last = midpoint(evt.touches[0], evt.touches[1]);
+        } else {
+            this.cancelLongPress();
+            this.mode = 'none';
+        }
+    }
+
+    touchMove(evt: TouchEvent) {
+        evt.preventDefault();
+
+        if ((this.mode === 'tap' || this.mode === 'pan') && evt.touches.length === 1) {
+            const point = clientPoint(evt.touches[0]);
+
+            if (this.mode === 'tap') {
+                if (Math.hypot(point.x - this.start.x, point.y - this.start.y) <= TAP_MAX_DISTANCE) {
+                    return;
+                }
+
+                this.cancelLongPress();
+                this.mode = 'pan';
+            }
+
+            // Moving the finger up scrolls down, as on touch screens.
+            const deltaX = Math.round(point.x - this.last.x);
+            const deltaY = Math.round(point.y - this.last.y);
+
+            if (deltaY !== 0) {
+                this.callbacks.scroll(true, deltaY);
+            }
+            if (deltaX !== 0) {
+                this.callbacks.scroll(false, deltaX);
+            }
+
+            this.last = point;
+        } else if (this.mode === 'pinch' && evt.touches.length === 2) {
+            const center = midpoint(evt.touches[0], evt.touches[1]);
+            const zoom = this.pinchStartZoom * (distance(evt.touches[0], evt.touches[1]) / this.pinchStartDistance);
+            this.zoomAt(clamp(zoom, 1, MAX_ZOOM), center);
+            this.last = center;
+        }
+    }
+
+    touchEnd(evt: TouchEvent) {
+        evt.preventDefault();
+
+        if (this.mode === 'tap' && evt.touches.length === 0) {
+            this.callbacks.click(this.remotePosition(this.start), 0);
+        }
+
+        this.cancelLongPress();
+
+        // The remaining fingers of a pinch must be lifted before the next gesture.
+        this.mode = 'none';
+    }
+
+    private cancelLongPress() {
+        if (this.longPressTimer !== undefined) {
+            clearTimeout(this.longPressTimer);
+            this.longPressTimer = undefined;
+        }
+    }
+
+    /// Zooms the canvas to `zoom`, keeping the point under the previous center of the pinch under `center`.
+    private zoomAt(zoom: number, center: Point) {
+        // The untransformed position of the canvas.
+        const rect = this.canvas.getBoundingClientRect();
+        const left = rect.left - this.translation.x;
+        const top = rect.top - this.translation.y;
+        const width = rect.width / this.zoom;
+        const height = rect.height / this.zoom;
+
+        const ratio = zoom / this.zoom;
+        const x = center.x - left - (this.last.x - left - this.translation.x) * ratio;
+        const y = center.y - top - (this.last.y - top - this.translation.y) * ratio;
+
+        this.zoom = zoom;
+        // Keep the canvas covering its box.
+        this.translation = {
+            x: clamp(x, width * (1 - zoom), 0),
+            y: clamp(y, height * (1 - zoom), 0),
+        };
+        this.applyTransform();
+    }
+
+    private applyTransform() {
+        if (this.zoom === 1) {
+            this.canvas.style.transform = '';
+            this.canvas.style.transformOrigin = '';
+        } else {
+            const { x, y } = this.translation;
+            this.canvas.style.transformOrigin = '0 0';
+            this.canvas.style.transform = `translate(${x}px, ${y}px) scale(${this.zoom})`;
+        }
+    }
+
+    private remotePosition(point: Point): MousePosition {
+        // The bounding box includes the zoom transform.
+        const rect = this.canvas.getBoundingClientRect();
+
+        return {
+            x: Math.round((point.x - rect.left) * (this.canvas.width / rect.width)),
+            y: Math.round((point.y - rect.top) * (this.canvas.height / rect.height)),
+        };
+    }
+}
+
+function clientPoint(touch: Touch): Point {
+    return { x: touch.clientX, y: touch.clientY };
+}
+
+function midpoint(a: Touch, b: Touch): Point {
+    return { x: (a.clientX + b.clientX) / 2, y: (a.clientY + b.clientY) / 2 };
+}
+
+function distance(a: Touch, b: Touch): number {
+    return Math.hypot(a.clientX - b.clientX, a.clientY - b.clientY);
+}
+
+function clamp(value: number, min: number, max: number): number {
+    return Math.min(Math.max(value, min), max);
+}
@@ -62,6 +62,10 @@ export class PublicAPI {
         this.remoteDesktopService.setEnableClipboard(enable);
     }
 
+    private setVirtualKeyboardVisible(visible: boolean) {
+        this.remoteDesktopService.setVirtualKeyboardVisible(visible);
+    }
+
     getExposedFunctions(): UserInteraction {
         return {
             setVisibility: this.setVisibility.bind(this),
@@ -78,6 +82,7 @@ export class PublicAPI {
             setCursorStyleOverride: this.setCursorStyleOverride.bind(this),
             resize: this.resize.bind(this),
             setEnableClipboard: this.setEnableClipboard.bind(this),
+            setVirtualKeyboardVisible: this.setVirtualKeyboardVisible.bind(this),
         };
     }
 }
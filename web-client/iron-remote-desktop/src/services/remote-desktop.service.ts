@@ -37,6 +37,7 @@ export class RemoteDesktopService {
     private sessionEvent: Subject<SessionEvent> = new Subject();
     private scale: BehaviorSubject<ScreenScale> = new BehaviorSubject(ScreenScale.Fit as ScreenScale);
     private canvas?: HTMLCanvasElement;
+    private virtualKeyboardInput?: HTMLTextAreaElement;
     private keyboardUnicodeMode: boolean = false;
     private backendSupportsUnicodeKeyboardShortcuts: boolean | undefined = undefined;
     private onRemoteClipboardChanged?: OnRemoteClipboardChanged;
@@ -254,6 +255,41 @@ export class RemoteDesktopService {
         this.doTransactionFromDeviceEvents([this.module.DeviceEvent.wheel_rotations(vertical, -rotation)]);
     }
 
+    /// Clicks at the given position, for the touch gestures.
+    click(position: MousePosition, button: number) {
+        this.doTransactionFromDeviceEvents([
+            this.module.DeviceEvent.mouse_move(position.x, position.y),
+            this.module.DeviceEvent.mouse_button_pressed(button),
+            this.module.DeviceEvent.mouse_button_released(button),
+        ]);
+        this.mousePosition.next(position);
+    }
+
+    scroll(vertical: boolean, rotationUnits: number) {
+        this.doTransactionFromDeviceEvents([this.module.DeviceEvent.wheel_rotations(vertical, rotationUnits)]);
+    }
+
+    /// Types the text inserted by an on-screen keyboard or an input method.
+    sendText(text: string) {
+        const transaction = this.module.InputTransaction.init();
+        transaction.add_text(text);
+        this.session?.apply_inputs(transaction);
+    }
+
+    /// Presses and releases the key with the given `KeyboardEvent.code`.
+    sendKey(code: string) {
+        const keyScanCode = scanCode(code, OS.WINDOWS);
+
+        if (Number.isNaN(keyScanCode)) {
+            return;
+        }
+
+        this.doTransactionFromDeviceEvents([
+            this.module.DeviceEvent.key_pressed(keyScanCode),
+            this.module.DeviceEvent.key_released(keyScanCode),
+        ]);
+    }
+
     setVisibility(state: boolean) {
         this.changeVisibility.next(state);
     }
@@ -266,6 +302,22 @@ export class RemoteDesktopService {
         this.canvas = canvas;
     }
 
+    setVirtualKeyboardInput(input: HTMLTextAreaElement) {
+        this.virtualKeyboardInput = input;
+    }
+
+    /// Shows or hides the on-screen keyboard of mobile devices, by focusing the hidden virtual keyboard input.
+    ///
+    /// Browsers only show the on-screen keyboard when this is called while handling a user gesture (e.g. a tap).
+    setVirtualKeyboardVisible(visible: boolean) {
+        if (visible) {
+            this.virtualKeyboardInput?.focus();
+        } else {
+            this.virtualKeyboardInput?.blur();
+            this.canvas?.focus();
+        }
+    }
+
     resizeDynamic(width: number, height: number, scale?: number) {
         this.dynamicResize.next({ width, height });
         this.session?.resize(width, height, scale);
@@ -64,6 +64,21 @@ pub fn tests_run(sh: &Shell) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub fn no_std(sh: &Shell) -> anyhow::Result<()> {
+    let _s = Section::new("NO-STD");
+
+    // Building without the default features ensures the dependencies don't enable `std` on their own.
+    const NO_STD_CRATES: &[&str] = &["ironrdp-core", "ironrdp-error", "ironrdp-pdu"];
+
+    for krate in NO_STD_CRATES {
+        cmd!(sh, "{CARGO} build -p {krate} --no-default-features --locked").run()?;
+    }
+
+    println!("All good!");
+
+    Ok(())
+}
+
 pub fn lock_files(sh: &Shell) -> anyhow::Result<()> {
     let _s = Section::new("CHECK-LOCKS");
 
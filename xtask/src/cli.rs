@@ -13,6 +13,7 @@ TASKS:
   check fmt               Check formatting
   check lints             Check lints
   check locks             Check for dirty or staged lock files not yet committed
+  check no-std            Check the no_std crates build without their default features
   check tests [--no-run]  Compile tests and, unless specified otherwise, run them
   check typos             Check for typos in the codebase
   check install           Install all requirements for check tasks
@@ -41,6 +42,8 @@ TASKS:
   ffi build [--release]   Build DLL for FFI (default is debug)
   ffi bindings [--skip-dotnet-build]            
                           Generate C# bindings for FFI, optionally skipping the .NET build
+  ffi header              Generate the C header of ironrdp-ffi with cbindgen
+  ffi smoke-test          Build ironrdp-ffi and run the C smoke test against it
 ";
 
 pub fn print_help() {
@@ -58,6 +61,7 @@ pub enum Action {
     CheckFmt,
     CheckLints,
     CheckLocks,
+    CheckNoStd,
     CheckTests {
         no_run: bool,
     },
@@ -98,6 +102,8 @@ pub enum Action {
     FfiBuildBindings {
         skip_dotnet_build: bool,
     },
+    FfiHeader,
+    FfiSmokeTest,
 }
 
 pub fn parse_args() -> anyhow::Result<Args> {
@@ -112,6 +118,7 @@ pub fn parse_args() -> anyhow::Result<Args> {
                 Some("fmt") => Action::CheckFmt,
                 Some("lints") => Action::CheckLints,
                 Some("locks") => Action::CheckLocks,
+                Some("no-std") => Action::CheckNoStd,
                 Some("tests") => Action::CheckTests {
                     no_run: args.contains("--no-run"),
                 },
@@ -174,6 +181,8 @@ pub fn parse_args() -> anyhow::Result<Args> {
                 Some("bindings") => Action::FfiBuildBindings {
                     skip_dotnet_build: args.contains("--skip-dotnet-build"),
                 },
+                Some("header") => Action::FfiHeader,
+                Some("smoke-test") => Action::FfiSmokeTest,
                 Some(unknown) => anyhow::bail!("unknown ffi action: {unknown}"),
                 None => Action::ShowHelp,
             },
@@ -12,6 +12,10 @@ const OUTPUT_LIB_NAME: &str = "libironrdp.so";
 #[cfg(target_os = "macos")]
 const OUTPUT_LIB_NAME: &str = "libironrdp.dylib";
 
+const C_HEADER_PATH: &str = "include/ironrdp.h";
+
+const STATIC_LIB_NAME: &str = "libironrdp_ffi.a";
+
 #[cfg(target_os = "windows")]
 const DOTNET_NATIVE_LIB_NAME: &str = "DevolutionsIronRdp.dll";
 #[cfg(target_os = "linux")]
@@ -30,6 +34,51 @@ pub(crate) fn install(sh: &Shell) -> anyhow::Result<()> {
     let _s = Section::new("FFI-INSTALL");
 
     cargo_install(sh, &DIPLOMAT_TOOL)?;
+    cargo_install(sh, &CBINDGEN)?;
+
+    Ok(())
+}
+
+pub(crate) fn header(sh: &Shell) -> anyhow::Result<()> {
+    let _s = Section::new("FFI-HEADER");
+
+    if !is_installed(sh, &CBINDGEN) {
+        anyhow::bail!("`cbindgen` binary is missing. Please run `cargo xtask ffi install`.");
+    }
+
+    let _guard = sh.push_dir("./crates/ironrdp-ffi");
+
+    let header = cmd!(sh, "cbindgen --quiet --config cbindgen.toml src/lib.rs").read()?;
+
+    // cbindgen splits the IronRdp prefix of the enum constants into IRON_RDP.
+    let header = header.replace("IRON_RDP_", "IRONRDP_");
+
+    sh.write_file(C_HEADER_PATH, header + "\n")?;
+
+    println!("Generated {C_HEADER_PATH}");
+
+    Ok(())
+}
+
+/// Builds the C smoke test with the system C compiler and runs it, on Unix only.
+pub(crate) fn smoke_test(sh: &Shell) -> anyhow::Result<()> {
+    let _s = Section::new("FFI-SMOKE-TEST");
+
+    cmd!(sh, "{CARGO} build --package ironrdp-ffi --locked").run()?;
+
+    let root_dir = sh.current_dir();
+    let static_lib_path = root_dir.join("target").join("debug").join(STATIC_LIB_NAME);
+    let test_path = root_dir.join("target").join("ironrdp-ffi-smoke");
+
+    cmd!(
+        sh,
+        "cc -std=c99 -Wall -Wextra -Werror -I crates/ironrdp-ffi/include crates/ironrdp-ffi/tests/smoke.c {static_lib_path} -lpthread -ldl -lm -o {test_path}"
+    )
+    .run()?;
+
+    cmd!(sh, "{test_path}").run()?;
+
+    println!("All good!");
 
     Ok(())
 }
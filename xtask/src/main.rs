@@ -71,6 +71,7 @@ fn main() -> anyhow::Result<()> {
         Action::CheckFmt => check::fmt(&sh)?,
         Action::CheckLints => check::lints(&sh)?,
         Action::CheckLocks => check::lock_files(&sh)?,
+        Action::CheckNoStd => check::no_std(&sh)?,
         Action::CheckTests { no_run } => {
             if no_run {
                 check::tests_compile(&sh)?;
@@ -90,6 +91,7 @@ fn main() -> anyhow::Result<()> {
             check::tests_compile(&sh)?;
             check::tests_run(&sh)?;
             check::lints(&sh)?;
+            check::no_std(&sh)?;
             wasm::check(&sh)?;
             fuzz::run(&sh, None, None)?;
             web::install(&sh)?;
@@ -116,6 +118,8 @@ fn main() -> anyhow::Result<()> {
         Action::FfiInstall => ffi::install(&sh)?,
         Action::FfiBuildDll { release } => ffi::build_dynamic_lib(&sh, release)?,
         Action::FfiBuildBindings { skip_dotnet_build } => ffi::build_bindings(&sh, skip_dotnet_build)?,
+        Action::FfiHeader => ffi::header(&sh)?,
+        Action::FfiSmokeTest => ffi::smoke_test(&sh)?,
     }
 
     Ok(())